@@ -0,0 +1,171 @@
+//! Benchmarks of the storage layer with the RocksDB backend, run with
+//! `cargo bench --bench storage`. The synthetic state is generated from fixed
+//! seeds, so that the results are comparable across runs and versions. The
+//! storage is hashed with the same hasher as the ledger's shell.
+
+use anoma::ledger::storage::{MerkleTree, Sha256Hasher, Storage};
+use anoma::types::address::{self, Address, EstablishedAddressGen};
+use anoma::types::chain::ChainId;
+use anoma::types::storage::{BlockHash, BlockHeight, Key};
+use anoma::types::token;
+use anoma_apps::node::ledger::storage::PersistentDB;
+use borsh::BorshSerialize;
+use criterion::{
+    criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion,
+    Throughput,
+};
+use tempfile::TempDir;
+
+/// The number of the balances in the state of the read benchmark
+const READ_STATE_KEYS: usize = 10_000;
+
+/// The numbers of the keys of the prefix scans
+const PREFIX_SIZES: [usize; 3] = [10, 100, 1_000];
+
+/// The numbers of the writes of the committed blocks
+const COMMIT_SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+/// The numbers of the keys of the Merkle trees
+const MERKLE_SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+/// The storage of the ledger's shell
+type ShellStorage = Storage<PersistentDB, Sha256Hasher>;
+
+/// Generate the balances of `n` owners of a token. The owners are generated
+/// from the `seed`, so that the same keys and values are generated in every
+/// run.
+fn balances(token: &Address, seed: &str, n: usize) -> Vec<(Key, Vec<u8>)> {
+    let mut address_gen = EstablishedAddressGen::new(seed);
+    (0..n)
+        .map(|i| {
+            let owner = address_gen.generate_address(i.to_le_bytes());
+            let amount = token::Amount::from(i as u64 + 1);
+            (
+                token::balance_key(token, &owner),
+                amount.try_to_vec().unwrap(),
+            )
+        })
+        .collect()
+}
+
+/// Open an empty storage in a temporary directory, which is removed once
+/// the returned directory is dropped
+fn open_storage() -> (TempDir, ShellStorage) {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = ShellStorage::open(dir.path(), ChainId::default(), None);
+    (dir, storage)
+}
+
+/// Write the key-vals in the block at the given height and commit it
+fn commit_block(
+    storage: &mut ShellStorage,
+    height: u64,
+    key_vals: &[(Key, Vec<u8>)],
+) {
+    storage
+        .begin_block(BlockHash::default(), BlockHeight(height))
+        .unwrap();
+    for (key, value) in key_vals {
+        storage.write(key, value).unwrap();
+    }
+    storage.commit().unwrap();
+}
+
+/// Benchmark the reads of single committed keys
+fn read(c: &mut Criterion) {
+    let (_dir, mut storage) = open_storage();
+    let key_vals = balances(&address::xan(), "read", READ_STATE_KEYS);
+    commit_block(&mut storage, 1, &key_vals);
+
+    let mut i = 0;
+    c.bench_function("read", |b| {
+        b.iter(|| {
+            // Step over the keys in an order other than the keys' order
+            i = (i + 7919) % key_vals.len();
+            storage.read(&key_vals[i].0).unwrap()
+        })
+    });
+}
+
+/// Benchmark the scans of the prefixes with different numbers of keys
+fn iter_prefix(c: &mut Criterion) {
+    let (_dir, mut storage) = open_storage();
+    // The balances of a token for each of the sizes
+    let mut token_gen = EstablishedAddressGen::new("iter_prefix");
+    let mut key_vals = vec![];
+    let mut prefixes = vec![];
+    for size in PREFIX_SIZES {
+        let token = token_gen.generate_address(size.to_le_bytes());
+        key_vals.extend(balances(&token, "iter_prefix", size));
+        prefixes.push((size, token::balance_prefix(&token)));
+    }
+    commit_block(&mut storage, 1, &key_vals);
+
+    let mut group = c.benchmark_group("iter_prefix");
+    for (size, prefix) in prefixes {
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size),
+            &prefix,
+            |b, prefix| {
+                b.iter(|| {
+                    let (iter, _gas) = storage.iter_prefix(prefix);
+                    assert_eq!(iter.count(), size);
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Benchmark the commits of the blocks with different numbers of writes
+fn commit(c: &mut Criterion) {
+    let mut group = c.benchmark_group("commit");
+    for size in COMMIT_SIZES {
+        let key_vals = balances(&address::xan(), "commit", size);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size),
+            &key_vals,
+            |b, key_vals| {
+                b.iter_batched(
+                    open_storage,
+                    |(dir, mut storage)| {
+                        commit_block(&mut storage, 1, key_vals);
+                        // Dropped outside of the measurement
+                        (dir, storage)
+                    },
+                    BatchSize::PerIteration,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Benchmark the computation of the Merkle roots of the trees with different
+/// numbers of keys
+fn merkle_root(c: &mut Criterion) {
+    let mut group = c.benchmark_group("merkle_root");
+    for size in MERKLE_SIZES {
+        let key_vals = balances(&address::xan(), "merkle_root", size);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size),
+            &key_vals,
+            |b, key_vals| {
+                b.iter(|| {
+                    let mut tree = MerkleTree::<Sha256Hasher>::default();
+                    for (key, value) in key_vals {
+                        tree.update(key, value).unwrap();
+                    }
+                    tree.root()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, read, iter_prefix, commit, merkle_root);
+criterion_main!(benches);
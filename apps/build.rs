@@ -96,6 +96,12 @@ fn main() {
         }
     }
 
+    #[allow(unused_mut)]
+    let mut protos = vec![format!("{}/services.proto", PROTO_SRC)];
+    // The ledger queries are only served with the `grpc` feature
+    #[cfg(feature = "grpc")]
+    protos.push(format!("{}/ledger.proto", PROTO_SRC));
+
     tonic_build::configure()
         .out_dir("src/lib/proto/generated")
         .format(true)
@@ -105,6 +111,6 @@ fn main() {
         // TODO try to add json encoding to simplify use for user
         // .type_attribute("types.Intent", "#[derive(serde::Serialize,
         // serde::Deserialize)]")
-        .compile(&[format!("{}/services.proto", PROTO_SRC)], &[PROTO_SRC])
+        .compile(&protos, &[PROTO_SRC])
         .unwrap();
 }
@@ -18,12 +18,30 @@ pub async fn main() -> Result<()> {
                 Sub::TxTransfer(TxTransfer(args)) => {
                     tx::submit_transfer(ctx, args).await;
                 }
+                Sub::TxApprove(TxApprove(args)) => {
+                    tx::submit_approve(ctx, args).await;
+                }
+                Sub::TxTransferFrom(TxTransferFrom(args)) => {
+                    tx::submit_transfer_from(ctx, args).await;
+                }
                 Sub::TxUpdateVp(TxUpdateVp(args)) => {
                     tx::submit_update_vp(ctx, args).await;
                 }
+                Sub::TxUpdateAccount(TxUpdateAccount(args)) => {
+                    tx::submit_update_account(ctx, args).await;
+                }
                 Sub::TxInitAccount(TxInitAccount(args)) => {
                     tx::submit_init_account(ctx, args).await;
                 }
+                Sub::TxPromoteAccount(TxPromoteAccount(args)) => {
+                    tx::submit_promote_account(ctx, args).await;
+                }
+                Sub::TxInitToken(TxInitToken(args)) => {
+                    tx::submit_init_token(ctx, args).await;
+                }
+                Sub::TxRegisterAlias(TxRegisterAlias(args)) => {
+                    tx::submit_register_alias(ctx, args).await;
+                }
                 Sub::TxInitValidator(TxInitValidator(args)) => {
                     tx::submit_init_validator(ctx, args).await;
                 }
@@ -33,40 +51,116 @@ pub async fn main() -> Result<()> {
                 Sub::TxMintNft(TxMintNft(args)) => {
                     tx::submit_mint_nft(ctx, args).await;
                 }
+                Sub::TxTransferNft(TxTransferNft(args)) => {
+                    tx::submit_transfer_nft(ctx, args).await;
+                }
                 Sub::TxInitProposal(TxInitProposal(args)) => {
                     tx::submit_init_proposal(ctx, args).await;
                 }
                 Sub::TxVoteProposal(TxVoteProposal(args)) => {
                     tx::submit_vote_proposal(ctx, args).await;
                 }
+                Sub::Faucet(Faucet::FaucetWithdraw(FaucetWithdraw(args))) => {
+                    tx::submit_faucet_withdraw(ctx, args).await;
+                }
                 Sub::Bond(Bond(args)) => {
                     tx::submit_bond(ctx, args).await;
                 }
+                Sub::BatchBond(BatchBond(args)) => {
+                    tx::submit_batch_bond(ctx, args).await;
+                }
                 Sub::Unbond(Unbond(args)) => {
                     tx::submit_unbond(ctx, args).await;
                 }
                 Sub::Withdraw(Withdraw(args)) => {
                     tx::submit_withdraw(ctx, args).await;
                 }
+                Sub::Redelegate(Redelegate(args)) => {
+                    tx::submit_redelegate(ctx, args).await;
+                }
+                Sub::ChangeConsensusKey(ChangeConsensusKey(args)) => {
+                    tx::submit_change_consensus_key(ctx, args).await;
+                }
+                Sub::ClaimRewards(ClaimRewards(args)) => {
+                    tx::submit_claim_rewards(ctx, args).await;
+                }
+                Sub::ChangeCommissionRate(ChangeCommissionRate(args)) => {
+                    tx::submit_change_commission_rate(ctx, args).await;
+                }
+                Sub::ChangeValidatorMetadata(ChangeValidatorMetadata(args)) => {
+                    tx::submit_change_validator_metadata(ctx, args).await;
+                }
+                Sub::UnjailValidator(UnjailValidator(args)) => {
+                    tx::submit_unjail_validator(ctx, args).await;
+                }
                 // Ledger queries
                 Sub::QueryEpoch(QueryEpoch(args)) => {
-                    rpc::query_epoch(args).await;
+                    rpc::query_epoch_with_output(args, ctx.global_args.output)
+                        .await;
+                }
+                Sub::QueryBlockGas(QueryBlockGas(args)) => {
+                    rpc::query_block_gas(args).await;
+                }
+                Sub::QueryNodeStatus(QueryNodeStatus(args)) => {
+                    rpc::query_node_status(args).await;
+                }
+                Sub::QueryProofSpecs(QueryProofSpecs(args)) => {
+                    rpc::query_proof_specs(args).await;
                 }
                 Sub::QueryBalance(QueryBalance(args)) => {
                     rpc::query_balance(ctx, args).await;
                 }
+                Sub::QueryAccount(QueryAccount(args)) => {
+                    rpc::query_account(ctx, args).await;
+                }
+                Sub::QueryAllowances(QueryAllowances(args)) => {
+                    rpc::query_allowances(ctx, args).await;
+                }
+                Sub::QueryTokenMetadata(QueryTokenMetadata(args)) => {
+                    rpc::query_token_metadata(ctx, args).await;
+                }
+                Sub::QueryAlias(QueryAlias(args)) => {
+                    rpc::query_alias(ctx, args).await;
+                }
+                Sub::QueryNft(QueryNft(args)) => {
+                    rpc::query_nft(args).await;
+                }
                 Sub::QueryBonds(QueryBonds(args)) => {
                     rpc::query_bonds(ctx, args).await;
                 }
+                Sub::QueryRewards(QueryRewards(args)) => {
+                    rpc::query_rewards(ctx, args).await;
+                }
+                Sub::QueryTxs(QueryTxs(args)) => {
+                    rpc::query_txs(ctx, args).await;
+                }
+                Sub::QueryTransfers(QueryTransfers(args)) => {
+                    rpc::query_transfers(ctx, args).await;
+                }
+                Sub::QueryBlockResults(QueryBlockResults(args)) => {
+                    rpc::query_block_results(args).await;
+                }
                 Sub::QueryVotingPower(QueryVotingPower(args)) => {
                     rpc::query_voting_power(ctx, args).await;
                 }
                 Sub::QuerySlashes(QuerySlashes(args)) => {
                     rpc::query_slashes(ctx, args).await;
                 }
+                Sub::QueryInflation(QueryInflation(args)) => {
+                    rpc::query_inflation(args).await;
+                }
+                Sub::QueryValidatorKeys(QueryValidatorKeys(args)) => {
+                    rpc::query_validator_keys(ctx, args).await;
+                }
                 Sub::QueryResult(QueryResult(args)) => {
                     rpc::query_result(ctx, args).await;
                 }
+                Sub::TxStatus(TxStatus(args)) => {
+                    tx::tx_status(ctx, args).await;
+                }
+                Sub::TxResubmit(TxResubmit(args)) => {
+                    tx::tx_resubmit(ctx, args).await;
+                }
                 Sub::QueryRawBytes(QueryRawBytes(args)) => {
                     rpc::query_raw_bytes(ctx, args).await;
                 }
@@ -80,6 +174,12 @@ pub async fn main() -> Result<()> {
                 Sub::QueryProtocolParameters(QueryProtocolParameters(args)) => {
                     rpc::query_protocol_parameters(ctx, args).await;
                 }
+                Sub::QueryUpgrade(QueryUpgrade(args)) => {
+                    rpc::query_upgrade(args).await;
+                }
+                Sub::VerifyHeader(VerifyHeader(args)) => {
+                    rpc::verify_header(args).await;
+                }
                 // Gossip cmds
                 Sub::Intent(Intent(args)) => {
                     gossip::gossip_intent(ctx, args).await;
@@ -100,6 +200,9 @@ pub async fn main() -> Result<()> {
             Utils::InitGenesisValidator(InitGenesisValidator(args)) => {
                 utils::init_genesis_validator(global_args, args)
             }
+            Utils::ValidateGenesis(ValidateGenesis(args)) => {
+                utils::validate_genesis(global_args, args)
+            }
         },
     }
     Ok(())
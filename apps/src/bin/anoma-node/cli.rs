@@ -1,6 +1,7 @@
 //! Anoma node CLI.
 
 use anoma_apps::cli::{self, args, cmds};
+use anoma_apps::config;
 use anoma_apps::node::{gossip, ledger, matchmaker};
 use eyre::{Context, Result};
 
@@ -11,7 +12,19 @@ pub fn main() -> Result<()> {
     }
     match cmd {
         cmds::AnomaNode::Ledger(sub) => match sub {
-            cmds::Ledger::Run(_) => {
+            cmds::Ledger::Run(cmds::LedgerRun(args::LedgerRun { dev })) => {
+                if dev {
+                    #[cfg(feature = "dev")]
+                    ledger::dev::init(&mut ctx);
+                    #[cfg(not(feature = "dev"))]
+                    {
+                        eprintln!(
+                            "The `--dev` mode requires a development build, \
+                             with `ANOMA_DEV=true`"
+                        );
+                        cli::safe_exit(1)
+                    }
+                }
                 let wasm_dir = ctx.wasm_dir();
                 ledger::run(ctx.config.ledger, wasm_dir);
             }
@@ -19,6 +32,129 @@ pub fn main() -> Result<()> {
                 ledger::reset(ctx.config.ledger)
                     .wrap_err("Failed to reset Anoma node")?;
             }
+            cmds::Ledger::Rollback(cmds::LedgerRollback(
+                args::LedgerRollback { height },
+            )) => {
+                let height = ledger::rollback(ctx.config.ledger, height)
+                    .wrap_err("Failed to rollback the blocks")?;
+                println!("Rolled back to the block at height {}", height);
+            }
+            cmds::Ledger::Migrate(cmds::LedgerMigrate(
+                args::LedgerMigrate { dry_run },
+            )) => {
+                let report = ledger::migrate(ctx.config.ledger, dry_run)
+                    .wrap_err("Failed to migrate the DB")?;
+                if report.migrations.is_empty() {
+                    println!(
+                        "The DB is at the schema version {} of the node",
+                        report.to_version
+                    );
+                } else {
+                    let action = if dry_run { "Pending" } else { "Applied" };
+                    println!(
+                        "{} schema migrations from version {} to {}:",
+                        action, report.from_version, report.to_version
+                    );
+                    for (version, description) in report.migrations {
+                        println!("  {}: {}", version, description);
+                    }
+                }
+            }
+            cmds::Ledger::VerifyStorage(_) => {
+                let report = ledger::verify_storage(ctx.config.ledger)
+                    .wrap_err("Failed to verify the storage")?;
+                println!(
+                    "Verified {} keys of the block at height {}. Committed \
+                     app hash: {}, recomputed: {}",
+                    report.keys,
+                    report.height,
+                    report.committed_root,
+                    report.computed_root
+                );
+                if !report.is_valid() {
+                    if report.divergent_keys.is_empty() {
+                        eprintln!(
+                            "The storage is corrupted, some keys of the \
+                             committed Merkle tree are missing"
+                        );
+                    } else {
+                        eprintln!(
+                            "The storage is corrupted, {} keys have values \
+                             that don't match the committed Merkle tree:",
+                            report.divergent_keys.len()
+                        );
+                        for key in &report.divergent_keys {
+                            eprintln!("  {}", key);
+                        }
+                    }
+                    cli::safe_exit(1)
+                }
+            }
+            cmds::Ledger::CheckPos(_) => {
+                let report = ledger::check_pos(ctx.config.ledger)
+                    .wrap_err("Failed to check the PoS invariants")?;
+                println!(
+                    "Checked the PoS invariants of the block at height {} in \
+                     epoch {}",
+                    report.height, report.epoch
+                );
+                if !report.violations.is_empty() {
+                    eprintln!(
+                        "{} PoS invariants are violated:",
+                        report.violations.len()
+                    );
+                    for violation in &report.violations {
+                        eprintln!("  {}", violation);
+                    }
+                    cli::safe_exit(1)
+                }
+            }
+            cmds::Ledger::Replay(cmds::LedgerReplay(args::LedgerReplay {
+                from_height,
+                to_height,
+            })) => {
+                let wasm_dir = ctx.wasm_dir();
+                ledger::replay(
+                    ctx.config.ledger,
+                    wasm_dir,
+                    from_height,
+                    to_height,
+                )
+                .wrap_err("Failed to replay the blocks")?;
+            }
+            cmds::Ledger::ExportState(cmds::LedgerExportState(
+                args::LedgerExportState {
+                    height,
+                    state_path,
+                    format,
+                },
+            )) => {
+                let dump = ledger::export_state(
+                    ctx.config.ledger,
+                    height,
+                    &state_path,
+                    format,
+                )
+                .wrap_err("Failed to export the state")?;
+                println!(
+                    "Exported the state at height {} with {} keys to {}",
+                    dump.height,
+                    dump.entries.len(),
+                    state_path.display()
+                );
+            }
+            cmds::Ledger::ImportState(cmds::LedgerImportState(
+                args::LedgerImportState { state_path },
+            )) => {
+                let dump = ledger::import_state(ctx.config.ledger, &state_path)
+                    .wrap_err("Failed to import the state")?;
+                println!(
+                    "Imported the state of the chain {} at height {}. The \
+                     chain will be initialized from it when the ledger is \
+                     started.",
+                    dump.chain_id, dump.height
+                );
+            }
         },
         cmds::AnomaNode::Gossip(sub) => match sub {
             cmds::Gossip::Run(cmds::GossipRun(args::GossipRun {
@@ -64,6 +200,7 @@ pub fn main() -> Result<()> {
                 mm_config,
                 intent_gossiper_addr,
                 ledger_addr,
+                config.ledger.chain_id,
                 tx_signing_key,
                 tx_source_address,
                 wasm_dir,
@@ -88,6 +225,22 @@ pub fn main() -> Result<()> {
                     &ctx.global_config.default_chain_id
                 );
             }
+            cmds::Config::Validate(cmds::ConfigValidate) => {
+                let wasm_dir = ctx.wasm_dir();
+                let errors =
+                    config::validation::validate(&ctx.config, wasm_dir);
+                if !errors.is_empty() {
+                    for err in &errors {
+                        eprintln!("{}", err);
+                    }
+                    eprintln!(
+                        "Found {} error(s) in the configuration",
+                        errors.len()
+                    );
+                    cli::safe_exit(1)
+                }
+                println!("The configuration is valid");
+            }
         },
     }
     Ok(())
@@ -49,8 +49,10 @@ fn handle_command(cmd: cli::cmds::Anoma, raw_sub_cmd: String) -> Result<()> {
         | cli::cmds::Anoma::TxCustom(_)
         | cli::cmds::Anoma::TxTransfer(_)
         | cli::cmds::Anoma::TxUpdateVp(_)
+        | cli::cmds::Anoma::TxUpdateAccount(_)
         | cli::cmds::Anoma::TxInitNft(_)
         | cli::cmds::Anoma::TxMintNft(_)
+        | cli::cmds::Anoma::TxTransferNft(_)
         | cli::cmds::Anoma::TxInitProposal(_)
         | cli::cmds::Anoma::TxVoteProposal(_)
         | cli::cmds::Anoma::Intent(_) => handle_subcommand("anomac", sub_args),
@@ -14,8 +14,7 @@ pub use utils::safe_exit;
 use utils::*;
 
 pub use self::context::Context;
-
-include!("../../version.rs");
+use crate::anoma_version;
 
 const APP_NAME: &str = "Anoma";
 
@@ -48,8 +47,10 @@ pub mod cmds {
         TxCustom(TxCustom),
         TxTransfer(TxTransfer),
         TxUpdateVp(TxUpdateVp),
+        TxUpdateAccount(TxUpdateAccount),
         TxInitNft(TxInitNft),
         TxMintNft(TxMintNft),
+        TxTransferNft(TxTransferNft),
         TxInitProposal(TxInitProposal),
         TxVoteProposal(TxVoteProposal),
         Intent(Intent),
@@ -66,8 +67,10 @@ pub mod cmds {
                 .subcommand(TxCustom::def())
                 .subcommand(TxTransfer::def())
                 .subcommand(TxUpdateVp::def())
+                .subcommand(TxUpdateAccount::def())
                 .subcommand(TxInitNft::def())
                 .subcommand(TxMintNft::def())
+                .subcommand(TxTransferNft::def())
                 .subcommand(TxInitProposal::def())
                 .subcommand(TxVoteProposal::def())
                 .subcommand(Intent::def())
@@ -83,8 +86,12 @@ pub mod cmds {
             let tx_custom = SubCmd::parse(matches).map(Self::TxCustom);
             let tx_transfer = SubCmd::parse(matches).map(Self::TxTransfer);
             let tx_update_vp = SubCmd::parse(matches).map(Self::TxUpdateVp);
+            let tx_update_account =
+                SubCmd::parse(matches).map(Self::TxUpdateAccount);
             let tx_nft_create = SubCmd::parse(matches).map(Self::TxInitNft);
             let tx_nft_mint = SubCmd::parse(matches).map(Self::TxMintNft);
+            let tx_nft_transfer =
+                SubCmd::parse(matches).map(Self::TxTransferNft);
             let tx_init_proposal =
                 SubCmd::parse(matches).map(Self::TxInitProposal);
             let tx_vote_proposal =
@@ -98,8 +105,10 @@ pub mod cmds {
                 .or(tx_custom)
                 .or(tx_transfer)
                 .or(tx_update_vp)
+                .or(tx_update_account)
                 .or(tx_nft_create)
                 .or(tx_nft_mint)
+                .or(tx_nft_transfer)
                 .or(tx_init_proposal)
                 .or(tx_vote_proposal)
                 .or(intent)
@@ -170,30 +179,64 @@ pub mod cmds {
                 // Simple transactions
                 .subcommand(TxCustom::def().display_order(1))
                 .subcommand(TxTransfer::def().display_order(1))
+                .subcommand(TxApprove::def().display_order(1))
+                .subcommand(TxTransferFrom::def().display_order(1))
                 .subcommand(TxUpdateVp::def().display_order(1))
+                .subcommand(TxUpdateAccount::def().display_order(1))
                 .subcommand(TxInitAccount::def().display_order(1))
+                .subcommand(TxPromoteAccount::def().display_order(1))
+                .subcommand(TxInitToken::def().display_order(1))
+                .subcommand(TxRegisterAlias::def().display_order(1))
                 .subcommand(TxInitValidator::def().display_order(1))
                 // Nft transactions
                 .subcommand(TxInitNft::def().display_order(1))
                 .subcommand(TxMintNft::def().display_order(1))
+                .subcommand(TxTransferNft::def().display_order(1))
                 // Proposal transactions
                 .subcommand(TxInitProposal::def().display_order(1))
                 .subcommand(TxVoteProposal::def().display_order(1))
+                // Faucet transactions
+                .subcommand(Faucet::def().display_order(1))
                 // PoS transactions
                 .subcommand(Bond::def().display_order(2))
+                .subcommand(BatchBond::def().display_order(2))
                 .subcommand(Unbond::def().display_order(2))
                 .subcommand(Withdraw::def().display_order(2))
+                .subcommand(Redelegate::def().display_order(2))
+                .subcommand(ChangeConsensusKey::def().display_order(2))
+                .subcommand(ClaimRewards::def().display_order(2))
+                .subcommand(ChangeCommissionRate::def().display_order(2))
+                .subcommand(ChangeValidatorMetadata::def().display_order(2))
+                .subcommand(UnjailValidator::def().display_order(2))
                 // Queries
                 .subcommand(QueryEpoch::def().display_order(3))
+                .subcommand(QueryBlockGas::def().display_order(3))
+                .subcommand(QueryNodeStatus::def().display_order(3))
+                .subcommand(QueryProofSpecs::def().display_order(3))
                 .subcommand(QueryBalance::def().display_order(3))
+                .subcommand(QueryAccount::def().display_order(3))
+                .subcommand(QueryAllowances::def().display_order(3))
+                .subcommand(QueryTokenMetadata::def().display_order(3))
+                .subcommand(QueryAlias::def().display_order(3))
+                .subcommand(QueryNft::def().display_order(3))
                 .subcommand(QueryBonds::def().display_order(3))
+                .subcommand(QueryRewards::def().display_order(3))
+                .subcommand(QueryTxs::def().display_order(3))
+                .subcommand(QueryTransfers::def().display_order(3))
+                .subcommand(QueryBlockResults::def().display_order(3))
                 .subcommand(QueryVotingPower::def().display_order(3))
                 .subcommand(QuerySlashes::def().display_order(3))
+                .subcommand(QueryInflation::def().display_order(3))
+                .subcommand(QueryValidatorKeys::def().display_order(3))
                 .subcommand(QueryResult::def().display_order(3))
+                .subcommand(TxStatus::def().display_order(3))
+                .subcommand(TxResubmit::def().display_order(3))
                 .subcommand(QueryRawBytes::def().display_order(3))
                 .subcommand(QueryProposal::def().display_order(3))
                 .subcommand(QueryProposalResult::def().display_order(3))
                 .subcommand(QueryProtocolParameters::def().display_order(3))
+                .subcommand(QueryUpgrade::def().display_order(3))
+                .subcommand(VerifyHeader::def().display_order(3))
                 // Intents
                 .subcommand(Intent::def().display_order(4))
                 .subcommand(SubscribeTopic::def().display_order(4))
@@ -205,57 +248,137 @@ pub mod cmds {
             use AnomaClientWithContext::*;
             let tx_custom = Self::parse_with_ctx(matches, TxCustom);
             let tx_transfer = Self::parse_with_ctx(matches, TxTransfer);
+            let tx_approve = Self::parse_with_ctx(matches, TxApprove);
+            let tx_transfer_from =
+                Self::parse_with_ctx(matches, TxTransferFrom);
             let tx_update_vp = Self::parse_with_ctx(matches, TxUpdateVp);
+            let tx_update_account =
+                Self::parse_with_ctx(matches, TxUpdateAccount);
             let tx_init_account = Self::parse_with_ctx(matches, TxInitAccount);
+            let tx_promote_account =
+                Self::parse_with_ctx(matches, TxPromoteAccount);
+            let tx_init_token = Self::parse_with_ctx(matches, TxInitToken);
+            let tx_register_alias =
+                Self::parse_with_ctx(matches, TxRegisterAlias);
             let tx_init_validator =
                 Self::parse_with_ctx(matches, TxInitValidator);
             let tx_nft_create = Self::parse_with_ctx(matches, TxInitNft);
             let tx_nft_mint = Self::parse_with_ctx(matches, TxMintNft);
+            let tx_nft_transfer = Self::parse_with_ctx(matches, TxTransferNft);
             let tx_init_proposal =
                 Self::parse_with_ctx(matches, TxInitProposal);
             let tx_vote_proposal =
                 Self::parse_with_ctx(matches, TxVoteProposal);
+            let faucet = Self::parse_with_ctx(matches, Faucet);
             let bond = Self::parse_with_ctx(matches, Bond);
+            let batch_bond = Self::parse_with_ctx(matches, BatchBond);
             let unbond = Self::parse_with_ctx(matches, Unbond);
             let withdraw = Self::parse_with_ctx(matches, Withdraw);
+            let redelegate = Self::parse_with_ctx(matches, Redelegate);
+            let change_consensus_key =
+                Self::parse_with_ctx(matches, ChangeConsensusKey);
+            let claim_rewards = Self::parse_with_ctx(matches, ClaimRewards);
+            let change_commission_rate =
+                Self::parse_with_ctx(matches, ChangeCommissionRate);
+            let change_validator_metadata =
+                Self::parse_with_ctx(matches, ChangeValidatorMetadata);
+            let unjail_validator =
+                Self::parse_with_ctx(matches, UnjailValidator);
             let query_epoch = Self::parse_with_ctx(matches, QueryEpoch);
+            let query_block_gas = Self::parse_with_ctx(matches, QueryBlockGas);
+            let query_node_status =
+                Self::parse_with_ctx(matches, QueryNodeStatus);
+            let query_proof_specs =
+                Self::parse_with_ctx(matches, QueryProofSpecs);
             let query_balance = Self::parse_with_ctx(matches, QueryBalance);
+            let query_account = Self::parse_with_ctx(matches, QueryAccount);
+            let query_allowances =
+                Self::parse_with_ctx(matches, QueryAllowances);
+            let query_token_metadata =
+                Self::parse_with_ctx(matches, QueryTokenMetadata);
+            let query_alias = Self::parse_with_ctx(matches, QueryAlias);
+            let query_nft = Self::parse_with_ctx(matches, QueryNft);
             let query_bonds = Self::parse_with_ctx(matches, QueryBonds);
+            let query_rewards = Self::parse_with_ctx(matches, QueryRewards);
+            let query_txs = Self::parse_with_ctx(matches, QueryTxs);
+            let query_transfers = Self::parse_with_ctx(matches, QueryTransfers);
+            let query_block_results =
+                Self::parse_with_ctx(matches, QueryBlockResults);
             let query_voting_power =
                 Self::parse_with_ctx(matches, QueryVotingPower);
             let query_slashes = Self::parse_with_ctx(matches, QuerySlashes);
+            let query_inflation = Self::parse_with_ctx(matches, QueryInflation);
+            let query_validator_keys =
+                Self::parse_with_ctx(matches, QueryValidatorKeys);
             let query_result = Self::parse_with_ctx(matches, QueryResult);
+            let tx_status = Self::parse_with_ctx(matches, TxStatus);
+            let tx_resubmit = Self::parse_with_ctx(matches, TxResubmit);
             let query_raw_bytes = Self::parse_with_ctx(matches, QueryRawBytes);
             let query_proposal = Self::parse_with_ctx(matches, QueryProposal);
             let query_proposal_result =
                 Self::parse_with_ctx(matches, QueryProposalResult);
             let query_protocol_parameters =
                 Self::parse_with_ctx(matches, QueryProtocolParameters);
+            let query_upgrade = Self::parse_with_ctx(matches, QueryUpgrade);
+            let verify_header = Self::parse_with_ctx(matches, VerifyHeader);
             let intent = Self::parse_with_ctx(matches, Intent);
             let subscribe_topic = Self::parse_with_ctx(matches, SubscribeTopic);
             let utils = SubCmd::parse(matches).map(Self::WithoutContext);
             tx_custom
                 .or(tx_transfer)
+                .or(tx_approve)
+                .or(tx_transfer_from)
                 .or(tx_update_vp)
+                .or(tx_update_account)
                 .or(tx_init_account)
+                .or(tx_promote_account)
+                .or(tx_init_token)
+                .or(tx_register_alias)
                 .or(tx_init_validator)
                 .or(tx_nft_create)
                 .or(tx_nft_mint)
+                .or(tx_nft_transfer)
                 .or(tx_init_proposal)
                 .or(tx_vote_proposal)
+                .or(faucet)
                 .or(bond)
+                .or(batch_bond)
                 .or(unbond)
                 .or(withdraw)
+                .or(redelegate)
+                .or(change_consensus_key)
+                .or(claim_rewards)
+                .or(change_commission_rate)
+                .or(change_validator_metadata)
+                .or(unjail_validator)
                 .or(query_epoch)
+                .or(query_block_gas)
+                .or(query_node_status)
+                .or(query_proof_specs)
                 .or(query_balance)
+                .or(query_account)
+                .or(query_allowances)
+                .or(query_token_metadata)
+                .or(query_alias)
+                .or(query_nft)
                 .or(query_bonds)
+                .or(query_rewards)
+                .or(query_txs)
+                .or(query_transfers)
+                .or(query_block_results)
                 .or(query_voting_power)
                 .or(query_slashes)
+                .or(query_inflation)
+                .or(query_validator_keys)
                 .or(query_result)
+                .or(tx_status)
+                .or(tx_resubmit)
                 .or(query_raw_bytes)
                 .or(query_proposal)
                 .or(query_proposal_result)
                 .or(query_protocol_parameters)
+                .or(query_upgrade)
+                .or(verify_header)
                 .or(intent)
                 .or(subscribe_topic)
                 .or(utils)
@@ -296,26 +419,59 @@ pub mod cmds {
         // Ledger cmds
         TxCustom(TxCustom),
         TxTransfer(TxTransfer),
+        TxApprove(TxApprove),
+        TxTransferFrom(TxTransferFrom),
         QueryResult(QueryResult),
+        TxStatus(TxStatus),
+        TxResubmit(TxResubmit),
         TxUpdateVp(TxUpdateVp),
+        TxUpdateAccount(TxUpdateAccount),
         TxInitAccount(TxInitAccount),
+        TxPromoteAccount(TxPromoteAccount),
+        TxInitToken(TxInitToken),
+        TxRegisterAlias(TxRegisterAlias),
         TxInitValidator(TxInitValidator),
         TxInitNft(TxInitNft),
         TxMintNft(TxMintNft),
+        TxTransferNft(TxTransferNft),
         TxInitProposal(TxInitProposal),
         TxVoteProposal(TxVoteProposal),
+        Faucet(Faucet),
         Bond(Bond),
+        BatchBond(BatchBond),
         Unbond(Unbond),
         Withdraw(Withdraw),
+        Redelegate(Redelegate),
+        ChangeConsensusKey(ChangeConsensusKey),
+        ClaimRewards(ClaimRewards),
+        ChangeCommissionRate(ChangeCommissionRate),
+        ChangeValidatorMetadata(ChangeValidatorMetadata),
+        UnjailValidator(UnjailValidator),
         QueryEpoch(QueryEpoch),
+        QueryBlockGas(QueryBlockGas),
+        QueryNodeStatus(QueryNodeStatus),
+        QueryProofSpecs(QueryProofSpecs),
         QueryBalance(QueryBalance),
+        QueryAccount(QueryAccount),
+        QueryAllowances(QueryAllowances),
+        QueryTokenMetadata(QueryTokenMetadata),
+        QueryAlias(QueryAlias),
+        QueryNft(QueryNft),
         QueryBonds(QueryBonds),
+        QueryRewards(QueryRewards),
+        QueryTxs(QueryTxs),
+        QueryTransfers(QueryTransfers),
+        QueryBlockResults(QueryBlockResults),
         QueryVotingPower(QueryVotingPower),
         QuerySlashes(QuerySlashes),
+        QueryInflation(QueryInflation),
+        QueryValidatorKeys(QueryValidatorKeys),
         QueryRawBytes(QueryRawBytes),
         QueryProposal(QueryProposal),
         QueryProposalResult(QueryProposalResult),
         QueryProtocolParameters(QueryProtocolParameters),
+        QueryUpgrade(QueryUpgrade),
+        VerifyHeader(VerifyHeader),
         // Gossip cmds
         Intent(Intent),
         SubscribeTopic(SubscribeTopic),
@@ -598,6 +754,13 @@ pub mod cmds {
     pub enum Ledger {
         Run(LedgerRun),
         Reset(LedgerReset),
+        Rollback(LedgerRollback),
+        Migrate(LedgerMigrate),
+        VerifyStorage(LedgerVerifyStorage),
+        CheckPos(LedgerCheckPos),
+        Replay(LedgerReplay),
+        ExportState(LedgerExportState),
+        ImportState(LedgerImportState),
     }
 
     impl SubCmd for Ledger {
@@ -607,9 +770,28 @@ pub mod cmds {
             matches.subcommand_matches(Self::CMD).and_then(|matches| {
                 let run = SubCmd::parse(matches).map(Self::Run);
                 let reset = SubCmd::parse(matches).map(Self::Reset);
+                let rollback = SubCmd::parse(matches).map(Self::Rollback);
+                let migrate = SubCmd::parse(matches).map(Self::Migrate);
+                let verify_storage =
+                    SubCmd::parse(matches).map(Self::VerifyStorage);
+                let check_pos = SubCmd::parse(matches).map(Self::CheckPos);
+                let replay = SubCmd::parse(matches).map(Self::Replay);
+                let export_state =
+                    SubCmd::parse(matches).map(Self::ExportState);
+                let import_state =
+                    SubCmd::parse(matches).map(Self::ImportState);
                 run.or(reset)
+                    .or(rollback)
+                    .or(migrate)
+                    .or(verify_storage)
+                    .or(check_pos)
+                    .or(replay)
+                    .or(export_state)
+                    .or(import_state)
                     // The `run` command is the default if no sub-command given
-                    .or(Some(Self::Run(LedgerRun)))
+                    .or(Some(Self::Run(LedgerRun(args::LedgerRun {
+                        dev: false,
+                    }))))
             })
         }
 
@@ -621,21 +803,32 @@ pub mod cmds {
                 )
                 .subcommand(LedgerRun::def())
                 .subcommand(LedgerReset::def())
+                .subcommand(LedgerRollback::def())
+                .subcommand(LedgerMigrate::def())
+                .subcommand(LedgerVerifyStorage::def())
+                .subcommand(LedgerCheckPos::def())
+                .subcommand(LedgerReplay::def())
+                .subcommand(LedgerExportState::def())
+                .subcommand(LedgerImportState::def())
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct LedgerRun;
+    pub struct LedgerRun(pub args::LedgerRun);
 
     impl SubCmd for LedgerRun {
         const CMD: &'static str = "run";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches.subcommand_matches(Self::CMD).map(|_matches| Self)
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::LedgerRun::parse(matches)))
         }
 
         fn def() -> App {
-            App::new(Self::CMD).about("Run Anoma ledger node.")
+            App::new(Self::CMD)
+                .about("Run Anoma ledger node.")
+                .add_args::<args::LedgerRun>()
         }
     }
 
@@ -657,6 +850,163 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct LedgerRollback(pub args::LedgerRollback);
+
+    impl SubCmd for LedgerRollback {
+        const CMD: &'static str = "rollback";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::LedgerRollback::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Revert the last committed block, or all the blocks \
+                     above the given height, from Anoma ledger node's \
+                     storage, so that the node can re-apply them from its \
+                     peers. The ledger node must not be running.",
+                )
+                .add_args::<args::LedgerRollback>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerMigrate(pub args::LedgerMigrate);
+
+    impl SubCmd for LedgerMigrate {
+        const CMD: &'static str = "migrate";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::LedgerMigrate::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Migrate Anoma ledger node's storage to the DB schema \
+                     version of the node. The migrations are otherwise \
+                     applied when the node is started. The ledger node must \
+                     not be running.",
+                )
+                .add_args::<args::LedgerMigrate>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerVerifyStorage;
+
+    impl SubCmd for LedgerVerifyStorage {
+        const CMD: &'static str = "verify-storage";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|_matches| Self)
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD).about(
+                "Recompute the Merkle tree of the last committed block from \
+                 Anoma ledger node's storage and compare its root with the \
+                 committed app hash, reporting the keys whose values don't \
+                 match. The ledger node must not be running.",
+            )
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerCheckPos;
+
+    impl SubCmd for LedgerCheckPos {
+        const CMD: &'static str = "check-pos";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|_matches| Self)
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD).about(
+                "Check the invariants of the PoS state of the last committed \
+                 block in Anoma ledger node's storage, reporting the violated \
+                 ones. The ledger node must not be running.",
+            )
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerReplay(pub args::LedgerReplay);
+
+    impl SubCmd for LedgerReplay {
+        const CMD: &'static str = "replay";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::LedgerReplay::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Re-execute the committed blocks in a range of heights \
+                     in a copy of the DB and compare the resulting app hashes \
+                     with the committed ones. The ledger node must not be \
+                     running.",
+                )
+                .add_args::<args::LedgerReplay>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerExportState(pub args::LedgerExportState);
+
+    impl SubCmd for LedgerExportState {
+        const CMD: &'static str = "export-state";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::LedgerExportState::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Export the state of the ledger at a block height into a \
+                     versioned state dump file, from which a new chain can be \
+                     initialized. The ledger node must not be running.",
+                )
+                .add_args::<args::LedgerExportState>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerImportState(pub args::LedgerImportState);
+
+    impl SubCmd for LedgerImportState {
+        const CMD: &'static str = "import-state";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::LedgerImportState::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Verify a state dump file and import it into a new chain, \
+                     which is initialized from the imported state instead of \
+                     its genesis accounts when the ledger node is started.",
+                )
+                .add_args::<args::LedgerImportState>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub enum Gossip {
         Run(GossipRun),
@@ -730,15 +1080,18 @@ pub mod cmds {
     #[derive(Clone, Debug)]
     pub enum Config {
         Gen(ConfigGen),
+        Validate(ConfigValidate),
     }
 
     impl SubCmd for Config {
         const CMD: &'static str = "config";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches
-                .subcommand_matches(Self::CMD)
-                .and_then(|matches| SubCmd::parse(matches).map(Self::Gen))
+            matches.subcommand_matches(Self::CMD).and_then(|matches| {
+                let gen = SubCmd::parse(matches).map(Self::Gen);
+                let validate = SubCmd::parse(matches).map(Self::Validate);
+                gen.or(validate)
+            })
         }
 
         fn def() -> App {
@@ -746,6 +1099,7 @@ pub mod cmds {
                 .setting(AppSettings::SubcommandRequiredElseHelp)
                 .about("Configuration sub-commands.")
                 .subcommand(ConfigGen::def())
+                .subcommand(ConfigValidate::def())
         }
     }
 
@@ -765,6 +1119,24 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct ConfigValidate;
+
+    impl SubCmd for ConfigValidate {
+        const CMD: &'static str = "validate";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|_matches| Self)
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD).about(
+                "Validate the configuration file of the chain and report the \
+                 settings that need to be fixed.",
+            )
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct QueryResult(pub args::QueryResult);
 
@@ -784,6 +1156,50 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct TxStatus(pub args::TxStatus);
+
+    impl SubCmd for TxStatus {
+        const CMD: &'static str = "tx-status";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| TxStatus(args::TxStatus::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Check the status of the submitted transactions that are \
+                     tracked by the client.",
+                )
+                .add_args::<args::TxStatus>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxResubmit(pub args::TxResubmit);
+
+    impl SubCmd for TxResubmit {
+        const CMD: &'static str = "tx-resubmit";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| TxResubmit(args::TxResubmit::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Resubmit a tracked transaction that was evicted from the \
+                     mempool in a new wrapper with a bumped fee.",
+                )
+                .add_args::<args::TxResubmit>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct QueryProposal(pub args::QueryProposal);
 
@@ -852,6 +1268,50 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct QueryUpgrade(pub args::Query);
+
+    impl SubCmd for QueryUpgrade {
+        const CMD: &'static str = "query-upgrade";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryUpgrade(args::Query::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query the protocol version of the chain and the pending \
+                     protocol upgrade.",
+                )
+                .add_args::<args::Query>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct VerifyHeader(pub args::VerifyHeader);
+
+    impl SubCmd for VerifyHeader {
+        const CMD: &'static str = "verify-header";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| VerifyHeader(args::VerifyHeader::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Verify that the header of a block is signed by more than \
+                     2/3 of the voting power of the PoS validator set.",
+                )
+                .add_args::<args::VerifyHeader>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct TxCustom(pub args::TxCustom);
 
@@ -890,6 +1350,50 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct TxApprove(pub args::TxApprove);
+
+    impl SubCmd for TxApprove {
+        const CMD: &'static str = "approve";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| TxApprove(args::TxApprove::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Send a signed transaction to approve a spender to \
+                     transfer the source's tokens.",
+                )
+                .add_args::<args::TxApprove>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxTransferFrom(pub args::TxTransferFrom);
+
+    impl SubCmd for TxTransferFrom {
+        const CMD: &'static str = "transfer-from";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxTransferFrom(args::TxTransferFrom::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Send a signed transfer transaction from the spender's \
+                     allowance of the source's tokens.",
+                )
+                .add_args::<args::TxTransferFrom>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct TxUpdateVp(pub args::TxUpdateVp);
 
@@ -912,6 +1416,29 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct TxUpdateAccount(pub args::TxUpdateAccount);
+
+    impl SubCmd for TxUpdateAccount {
+        const CMD: &'static str = "update-account";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxUpdateAccount(args::TxUpdateAccount::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Send a signed transaction to rotate an established \
+                     account's public key and/or update its validity \
+                     predicate.",
+                )
+                .add_args::<args::TxUpdateAccount>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct TxInitAccount(pub args::TxInitAccount);
 
@@ -935,1279 +1462,2847 @@ pub mod cmds {
     }
 
     #[derive(Clone, Debug)]
-    pub struct TxInitValidator(pub args::TxInitValidator);
+    pub struct TxPromoteAccount(pub args::TxPromoteAccount);
 
-    impl SubCmd for TxInitValidator {
-        const CMD: &'static str = "init-validator";
+    impl SubCmd for TxPromoteAccount {
+        const CMD: &'static str = "promote-account";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches.subcommand_matches(Self::CMD).map(|matches| {
-                TxInitValidator(args::TxInitValidator::parse(matches))
+                TxPromoteAccount(args::TxPromoteAccount::parse(matches))
             })
         }
 
         fn def() -> App {
             App::new(Self::CMD)
                 .about(
-                    "Send a signed transaction to create a new validator and \
-                     its staking reward account.",
+                    "Send a signed transaction to create a new established \
+                     account controlled by the key of an implicit account and \
+                     move the implicit account's balances to it.",
                 )
-                .add_args::<args::TxInitValidator>()
+                .add_args::<args::TxPromoteAccount>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct Bond(pub args::Bond);
+    pub struct TxInitToken(pub args::TxInitToken);
 
-    impl SubCmd for Bond {
-        const CMD: &'static str = "bond";
+    impl SubCmd for TxInitToken {
+        const CMD: &'static str = "init-token";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| Bond(args::Bond::parse(matches)))
+                .map(|matches| TxInitToken(args::TxInitToken::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Bond tokens in PoS system.")
-                .add_args::<args::Bond>()
+                .about(
+                    "Send a signed transaction to create a new token with the \
+                     given metadata and register its symbol.",
+                )
+                .add_args::<args::TxInitToken>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct Unbond(pub args::Unbond);
+    pub struct TxRegisterAlias(pub args::TxRegisterAlias);
 
-    impl SubCmd for Unbond {
-        const CMD: &'static str = "unbond";
+    impl SubCmd for TxRegisterAlias {
+        const CMD: &'static str = "register-alias";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches
-                .subcommand_matches(Self::CMD)
-                .map(|matches| Unbond(args::Unbond::parse(matches)))
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxRegisterAlias(args::TxRegisterAlias::parse(matches))
+            })
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Unbond tokens from a PoS bond.")
-                .add_args::<args::Unbond>()
+                .about(
+                    "Send a signed transaction to register an alias of an \
+                     address in the name service.",
+                )
+                .add_args::<args::TxRegisterAlias>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct Withdraw(pub args::Withdraw);
+    pub struct TxInitValidator(pub args::TxInitValidator);
 
-    impl SubCmd for Withdraw {
-        const CMD: &'static str = "withdraw";
+    impl SubCmd for TxInitValidator {
+        const CMD: &'static str = "init-validator";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches
-                .subcommand_matches(Self::CMD)
-                .map(|matches| Withdraw(args::Withdraw::parse(matches)))
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxInitValidator(args::TxInitValidator::parse(matches))
+            })
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Withdraw tokens from previously unbonded PoS bond.")
-                .add_args::<args::Withdraw>()
+                .about(
+                    "Send a signed transaction to create a new validator and \
+                     its staking reward account.",
+                )
+                .add_args::<args::TxInitValidator>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct QueryEpoch(pub args::Query);
+    pub struct Bond(pub args::Bond);
 
-    impl SubCmd for QueryEpoch {
-        const CMD: &'static str = "epoch";
+    impl SubCmd for Bond {
+        const CMD: &'static str = "bond";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| QueryEpoch(args::Query::parse(matches)))
+                .map(|matches| Bond(args::Bond::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Query the epoch of the last committed block.")
-                .add_args::<args::Query>()
+                .about("Bond tokens in PoS system.")
+                .add_args::<args::Bond>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct QueryBalance(pub args::QueryBalance);
+    pub struct BatchBond(pub args::BatchBond);
 
-    impl SubCmd for QueryBalance {
-        const CMD: &'static str = "balance";
+    impl SubCmd for BatchBond {
+        const CMD: &'static str = "batch-bond";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| QueryBalance(args::QueryBalance::parse(matches)))
+                .map(|matches| BatchBond(args::BatchBond::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Query balance(s) of tokens.")
-                .add_args::<args::QueryBalance>()
+                .about(
+                    "Bond tokens to several validators in PoS system, either \
+                     all of them or none of them.",
+                )
+                .add_args::<args::BatchBond>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct QueryBonds(pub args::QueryBonds);
+    pub struct Unbond(pub args::Unbond);
 
-    impl SubCmd for QueryBonds {
-        const CMD: &'static str = "bonds";
+    impl SubCmd for Unbond {
+        const CMD: &'static str = "unbond";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| QueryBonds(args::QueryBonds::parse(matches)))
+                .map(|matches| Unbond(args::Unbond::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Query PoS bond(s).")
-                .add_args::<args::QueryBonds>()
+                .about("Unbond tokens from a PoS bond.")
+                .add_args::<args::Unbond>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct QueryVotingPower(pub args::QueryVotingPower);
+    pub struct Withdraw(pub args::Withdraw);
 
-    impl SubCmd for QueryVotingPower {
-        const CMD: &'static str = "voting-power";
+    impl SubCmd for Withdraw {
+        const CMD: &'static str = "withdraw";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches.subcommand_matches(Self::CMD).map(|matches| {
-                QueryVotingPower(args::QueryVotingPower::parse(matches))
-            })
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Withdraw(args::Withdraw::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Query PoS voting power.")
-                .add_args::<args::QueryVotingPower>()
+                .about("Withdraw tokens from previously unbonded PoS bond.")
+                .add_args::<args::Withdraw>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct QuerySlashes(pub args::QuerySlashes);
+    pub struct Redelegate(pub args::Redelegate);
 
-    impl SubCmd for QuerySlashes {
-        const CMD: &'static str = "slashes";
+    impl SubCmd for Redelegate {
+        const CMD: &'static str = "redelegate";
 
-        fn parse(matches: &ArgMatches) -> Option<Self>
-        where
-            Self: Sized,
-        {
+        fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| QuerySlashes(args::QuerySlashes::parse(matches)))
+                .map(|matches| Redelegate(args::Redelegate::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Query PoS applied slashes.")
-                .add_args::<args::QuerySlashes>()
+                .about(
+                    "Redelegate tokens from a PoS delegation to another \
+                     validator without unbonding them.",
+                )
+                .add_args::<args::Redelegate>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct QueryRawBytes(pub args::QueryRawBytes);
+    pub struct ChangeConsensusKey(pub args::ChangeConsensusKey);
 
-    impl SubCmd for QueryRawBytes {
-        const CMD: &'static str = "query-bytes";
+    impl SubCmd for ChangeConsensusKey {
+        const CMD: &'static str = "change-consensus-key";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches.subcommand_matches(Self::CMD).map(|matches| {
-                QueryRawBytes(args::QueryRawBytes::parse(matches))
+                ChangeConsensusKey(args::ChangeConsensusKey::parse(matches))
             })
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Query the raw bytes of a given storage key")
-                .add_args::<args::QueryRawBytes>()
+                .about(
+                    "Change the consensus key of a validator. The new key is \
+                     used from the pipeline epoch.",
+                )
+                .add_args::<args::ChangeConsensusKey>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct TxInitNft(pub args::NftCreate);
+    pub struct ClaimRewards(pub args::Withdraw);
 
-    impl SubCmd for TxInitNft {
-        const CMD: &'static str = "init-nft";
+    impl SubCmd for ClaimRewards {
+        const CMD: &'static str = "claim-rewards";
 
-        fn parse(matches: &ArgMatches) -> Option<Self>
-        where
-            Self: Sized,
-        {
+        fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| TxInitNft(args::NftCreate::parse(matches)))
+                .map(|matches| ClaimRewards(args::Withdraw::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Create a new NFT.")
-                .add_args::<args::NftCreate>()
+                .about("Claim the rewards accrued to a PoS bond.")
+                .add_args::<args::Withdraw>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct TxMintNft(pub args::NftMint);
+    pub struct ChangeCommissionRate(pub args::ChangeCommissionRate);
 
-    impl SubCmd for TxMintNft {
-        const CMD: &'static str = "mint-nft";
+    impl SubCmd for ChangeCommissionRate {
+        const CMD: &'static str = "change-commission-rate";
 
-        fn parse(matches: &ArgMatches) -> Option<Self>
-        where
-            Self: Sized,
-        {
-            matches
-                .subcommand_matches(Self::CMD)
-                .map(|matches| TxMintNft(args::NftMint::parse(matches)))
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                ChangeCommissionRate(args::ChangeCommissionRate::parse(matches))
+            })
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Mint new NFT tokens.")
-                .add_args::<args::NftMint>()
+                .about(
+                    "Change the commission rate of a validator. The new rate \
+                     is used from the pipeline epoch.",
+                )
+                .add_args::<args::ChangeCommissionRate>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct TxInitProposal(pub args::InitProposal);
+    pub struct ChangeValidatorMetadata(pub args::ChangeValidatorMetadata);
 
-    impl SubCmd for TxInitProposal {
-        const CMD: &'static str = "init-proposal";
+    impl SubCmd for ChangeValidatorMetadata {
+        const CMD: &'static str = "change-validator-metadata";
 
-        fn parse(matches: &ArgMatches) -> Option<Self>
-        where
-            Self: Sized,
-        {
+        fn parse(matches: &ArgMatches) -> Option<Self> {
             matches.subcommand_matches(Self::CMD).map(|matches| {
-                TxInitProposal(args::InitProposal::parse(matches))
+                ChangeValidatorMetadata(args::ChangeValidatorMetadata::parse(
+                    matches,
+                ))
             })
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Create a new proposal.")
-                .add_args::<args::InitProposal>()
+                .about(
+                    "Change the display metadata of a validator, which \
+                     replaces its previous metadata.",
+                )
+                .add_args::<args::ChangeValidatorMetadata>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct TxVoteProposal(pub args::VoteProposal);
+    pub struct UnjailValidator(pub args::UnjailValidator);
 
-    impl SubCmd for TxVoteProposal {
-        const CMD: &'static str = "vote-proposal";
+    impl SubCmd for UnjailValidator {
+        const CMD: &'static str = "unjail-validator";
 
-        fn parse(matches: &ArgMatches) -> Option<Self>
-        where
-            Self: Sized,
-        {
+        fn parse(matches: &ArgMatches) -> Option<Self> {
             matches.subcommand_matches(Self::CMD).map(|matches| {
-                TxVoteProposal(args::VoteProposal::parse(matches))
+                UnjailValidator(args::UnjailValidator::parse(matches))
             })
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Vote a proposal.")
-                .add_args::<args::VoteProposal>()
+                .about(
+                    "Unjail a jailed validator once its cooldown has passed. \
+                     The validator is back in the validator set from the \
+                     pipeline epoch.",
+                )
+                .add_args::<args::UnjailValidator>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct Intent(pub args::Intent);
+    pub struct QueryEpoch(pub args::Query);
 
-    impl SubCmd for Intent {
-        const CMD: &'static str = "intent";
+    impl SubCmd for QueryEpoch {
+        const CMD: &'static str = "epoch";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| Intent(args::Intent::parse(matches)))
+                .map(|matches| QueryEpoch(args::Query::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Send an intent.")
-                .add_args::<args::Intent>()
+                .about("Query the epoch of the last committed block.")
+                .add_args::<args::Query>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct SubscribeTopic(pub args::SubscribeTopic);
+    pub struct QueryInflation(pub args::Query);
 
-    impl SubCmd for SubscribeTopic {
-        const CMD: &'static str = "subscribe-topic";
+    impl SubCmd for QueryInflation {
+        const CMD: &'static str = "inflation";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches.subcommand_matches(Self::CMD).map(|matches| {
-                SubscribeTopic(args::SubscribeTopic::parse(matches))
-            })
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryInflation(args::Query::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
                 .about(
-                    "Subscribe intent gossip node with a matchmaker to a \
-                     topic.",
+                    "Query the inflation of the native token in the last \
+                     committed epoch and the estimated APR of the staking \
+                     rewards.",
                 )
-                .add_args::<args::SubscribeTopic>()
+                .add_args::<args::Query>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub enum Utils {
-        JoinNetwork(JoinNetwork),
-        InitNetwork(InitNetwork),
-        InitGenesisValidator(InitGenesisValidator),
+    pub struct QueryBlockGas(pub args::Query);
+
+    impl SubCmd for QueryBlockGas {
+        const CMD: &'static str = "block-gas";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryBlockGas(args::Query::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query the gas used by the last committed block and the \
+                     current block and transaction gas limits.",
+                )
+                .add_args::<args::Query>()
+        }
     }
 
-    impl SubCmd for Utils {
-        const CMD: &'static str = "utils";
+    #[derive(Clone, Debug)]
+    pub struct QueryNodeStatus(pub args::Query);
+
+    impl SubCmd for QueryNodeStatus {
+        const CMD: &'static str = "node-status";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches.subcommand_matches(Self::CMD).and_then(|matches| {
-                let join_network =
-                    SubCmd::parse(matches).map(Self::JoinNetwork);
-                let init_network =
-                    SubCmd::parse(matches).map(Self::InitNetwork);
-                let init_genesis =
-                    SubCmd::parse(matches).map(Self::InitGenesisValidator);
-                join_network.or(init_network).or(init_genesis)
-            })
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryNodeStatus(args::Query::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Utilities.")
-                .subcommand(JoinNetwork::def())
-                .subcommand(InitNetwork::def())
-                .subcommand(InitGenesisValidator::def())
-                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .about(
+                    "Query the status of the node and of its chain, the size \
+                     of the node's mempool and the gas and the wrapper and \
+                     decrypted transactions of the recent blocks.",
+                )
+                .add_args::<args::Query>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct JoinNetwork(pub args::JoinNetwork);
+    pub struct QueryProofSpecs(pub args::Query);
 
-    impl SubCmd for JoinNetwork {
-        const CMD: &'static str = "join-network";
+    impl SubCmd for QueryProofSpecs {
+        const CMD: &'static str = "proof-specs";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| Self(args::JoinNetwork::parse(matches)))
+                .map(|matches| QueryProofSpecs(args::Query::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Configure Anoma to join an existing network.")
-                .add_args::<args::JoinNetwork>()
+                .about(
+                    "Query the ICS-23 proof specs of the Merkle proofs of the \
+                     storage values, encoded with protobuf in hex.",
+                )
+                .add_args::<args::Query>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct InitNetwork(pub args::InitNetwork);
+    pub struct QueryBalance(pub args::QueryBalance);
 
-    impl SubCmd for InitNetwork {
-        const CMD: &'static str = "init-network";
+    impl SubCmd for QueryBalance {
+        const CMD: &'static str = "balance";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| Self(args::InitNetwork::parse(matches)))
+                .map(|matches| QueryBalance(args::QueryBalance::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Initialize a new test network.")
-                .add_args::<args::InitNetwork>()
+                .about("Query balance(s) of tokens.")
+                .add_args::<args::QueryBalance>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct InitGenesisValidator(pub args::InitGenesisValidator);
+    pub struct QueryAccount(pub args::QueryAccount);
 
-    impl SubCmd for InitGenesisValidator {
-        const CMD: &'static str = "init-genesis-validator";
+    impl SubCmd for QueryAccount {
+        const CMD: &'static str = "account";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| Self(args::InitGenesisValidator::parse(matches)))
+                .map(|matches| QueryAccount(args::QueryAccount::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
                 .about(
-                    "Initialize genesis validator's address, staking reward \
-                     address, consensus key, validator account key and \
-                     staking rewards key and use it in the ledger's node.",
+                    "Query an account's current public key and validity \
+                     predicate code hash.",
                 )
-                .add_args::<args::InitGenesisValidator>()
+                .add_args::<args::QueryAccount>()
         }
     }
-}
 
-pub mod args {
+    #[derive(Clone, Debug)]
+    pub struct QueryAllowances(pub args::QueryAllowances);
 
-    use std::convert::TryFrom;
-    use std::env;
-    use std::fs::File;
-    use std::net::SocketAddr;
-    use std::path::PathBuf;
-    use std::str::FromStr;
+    impl SubCmd for QueryAllowances {
+        const CMD: &'static str = "allowances";
 
-    use anoma::types::address::Address;
-    use anoma::types::chain::{ChainId, ChainIdPrefix};
-    use anoma::types::governance::ProposalVote;
-    use anoma::types::intent::{DecimalWrapper, Exchange};
-    use anoma::types::key::*;
-    use anoma::types::storage::{self, Epoch};
-    use anoma::types::token;
-    use anoma::types::transaction::GasLimit;
-    use libp2p::Multiaddr;
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryAllowances(args::QueryAllowances::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query allowance(s) of tokens given to spenders.")
+                .add_args::<args::QueryAllowances>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryTokenMetadata(pub args::QueryTokenMetadata);
+
+    impl SubCmd for QueryTokenMetadata {
+        const CMD: &'static str = "token-metadata";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryTokenMetadata(args::QueryTokenMetadata::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query the metadata and total supply of the registered \
+                     token(s).",
+                )
+                .add_args::<args::QueryTokenMetadata>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryAlias(pub args::QueryAlias);
+
+    impl SubCmd for QueryAlias {
+        const CMD: &'static str = "query-alias";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryAlias(args::QueryAlias::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query the address of an alias registered in the name \
+                     service.",
+                )
+                .add_args::<args::QueryAlias>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryNft(pub args::QueryNft);
+
+    impl SubCmd for QueryNft {
+        const CMD: &'static str = "query-nft";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryNft(args::QueryNft::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query the tokens of a NFT and their owners.")
+                .add_args::<args::QueryNft>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryBonds(pub args::QueryBonds);
+
+    impl SubCmd for QueryBonds {
+        const CMD: &'static str = "bonds";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryBonds(args::QueryBonds::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query PoS bond(s).")
+                .add_args::<args::QueryBonds>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryRewards(pub args::QueryBonds);
+
+    impl SubCmd for QueryRewards {
+        const CMD: &'static str = "rewards";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryRewards(args::QueryBonds::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query the pending rewards of PoS bond(s).")
+                .add_args::<args::QueryBonds>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryTxs(pub args::QueryTxs);
+
+    impl SubCmd for QueryTxs {
+        const CMD: &'static str = "txs";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryTxs(args::QueryTxs::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query the txs indexed by a node with the indexer \
+                     enabled.",
+                )
+                .add_args::<args::QueryTxs>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryTransfers(pub args::QueryTransfers);
+
+    impl SubCmd for QueryTransfers {
+        const CMD: &'static str = "transfers";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryTransfers(args::QueryTransfers::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query the history of token transfers of an address \
+                     indexed by a node with the indexer enabled.",
+                )
+                .add_args::<args::QueryTransfers>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryBlockResults(pub args::QueryBlockResults);
+
+    impl SubCmd for QueryBlockResults {
+        const CMD: &'static str = "block-results";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryBlockResults(args::QueryBlockResults::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query the results and the events of the txs of a block \
+                     indexed by a node with the indexer enabled.",
+                )
+                .add_args::<args::QueryBlockResults>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryVotingPower(pub args::QueryVotingPower);
+
+    impl SubCmd for QueryVotingPower {
+        const CMD: &'static str = "voting-power";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryVotingPower(args::QueryVotingPower::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query PoS voting power.")
+                .add_args::<args::QueryVotingPower>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QuerySlashes(pub args::QuerySlashes);
+
+    impl SubCmd for QuerySlashes {
+        const CMD: &'static str = "slashes";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QuerySlashes(args::QuerySlashes::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query PoS applied slashes.")
+                .add_args::<args::QuerySlashes>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryValidatorKeys(pub args::QueryValidatorKeys);
+
+    impl SubCmd for QueryValidatorKeys {
+        const CMD: &'static str = "validator-keys";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryValidatorKeys(args::QueryValidatorKeys::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query the consensus, protocol and staking reward keys of \
+                     a PoS validator.",
+                )
+                .add_args::<args::QueryValidatorKeys>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryRawBytes(pub args::QueryRawBytes);
+
+    impl SubCmd for QueryRawBytes {
+        const CMD: &'static str = "query-bytes";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryRawBytes(args::QueryRawBytes::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query the raw bytes of a given storage key")
+                .add_args::<args::QueryRawBytes>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxInitNft(pub args::NftCreate);
+
+    impl SubCmd for TxInitNft {
+        const CMD: &'static str = "init-nft";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| TxInitNft(args::NftCreate::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Create a new NFT.")
+                .add_args::<args::NftCreate>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxMintNft(pub args::NftMint);
+
+    impl SubCmd for TxMintNft {
+        const CMD: &'static str = "mint-nft";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| TxMintNft(args::NftMint::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Mint new NFT tokens.")
+                .add_args::<args::NftMint>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxTransferNft(pub args::NftTransfer);
+
+    impl SubCmd for TxTransferNft {
+        const CMD: &'static str = "transfer-nft";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| TxTransferNft(args::NftTransfer::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Transfer a NFT token to a new owner.")
+                .add_args::<args::NftTransfer>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxInitProposal(pub args::InitProposal);
+
+    impl SubCmd for TxInitProposal {
+        const CMD: &'static str = "init-proposal";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxInitProposal(args::InitProposal::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Create a new proposal.")
+                .add_args::<args::InitProposal>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxVoteProposal(pub args::VoteProposal);
+
+    impl SubCmd for TxVoteProposal {
+        const CMD: &'static str = "vote-proposal";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxVoteProposal(args::VoteProposal::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Vote a proposal.")
+                .add_args::<args::VoteProposal>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub enum Faucet {
+        FaucetWithdraw(FaucetWithdraw),
+    }
+
+    impl SubCmd for Faucet {
+        const CMD: &'static str = "faucet";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).and_then(|matches| {
+                SubCmd::parse(matches).map(Self::FaucetWithdraw)
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Testnet faucet transactions.")
+                .subcommand(FaucetWithdraw::def())
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct FaucetWithdraw(pub args::FaucetWithdraw);
+
+    impl SubCmd for FaucetWithdraw {
+        const CMD: &'static str = "withdraw";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::FaucetWithdraw::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Withdraw tokens from a testnet faucet, without the \
+                     faucet's signature.",
+                )
+                .add_args::<args::FaucetWithdraw>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Intent(pub args::Intent);
+
+    impl SubCmd for Intent {
+        const CMD: &'static str = "intent";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Intent(args::Intent::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Send an intent.")
+                .add_args::<args::Intent>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct SubscribeTopic(pub args::SubscribeTopic);
+
+    impl SubCmd for SubscribeTopic {
+        const CMD: &'static str = "subscribe-topic";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                SubscribeTopic(args::SubscribeTopic::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Subscribe intent gossip node with a matchmaker to a \
+                     topic.",
+                )
+                .add_args::<args::SubscribeTopic>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub enum Utils {
+        JoinNetwork(JoinNetwork),
+        InitNetwork(InitNetwork),
+        InitGenesisValidator(InitGenesisValidator),
+        ValidateGenesis(ValidateGenesis),
+    }
+
+    impl SubCmd for Utils {
+        const CMD: &'static str = "utils";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).and_then(|matches| {
+                let join_network =
+                    SubCmd::parse(matches).map(Self::JoinNetwork);
+                let init_network =
+                    SubCmd::parse(matches).map(Self::InitNetwork);
+                let init_genesis =
+                    SubCmd::parse(matches).map(Self::InitGenesisValidator);
+                let validate_genesis =
+                    SubCmd::parse(matches).map(Self::ValidateGenesis);
+                join_network
+                    .or(init_network)
+                    .or(init_genesis)
+                    .or(validate_genesis)
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Utilities.")
+                .subcommand(JoinNetwork::def())
+                .subcommand(InitNetwork::def())
+                .subcommand(InitGenesisValidator::def())
+                .subcommand(ValidateGenesis::def())
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct JoinNetwork(pub args::JoinNetwork);
+
+    impl SubCmd for JoinNetwork {
+        const CMD: &'static str = "join-network";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::JoinNetwork::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Configure Anoma to join an existing network.")
+                .add_args::<args::JoinNetwork>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct InitNetwork(pub args::InitNetwork);
+
+    impl SubCmd for InitNetwork {
+        const CMD: &'static str = "init-network";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::InitNetwork::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Initialize a new test network from a preliminary \
+                     genesis configuration, generating the keys and configs \
+                     of the validators and packaging a release archive and \
+                     a bundle for each validator.",
+                )
+                .add_args::<args::InitNetwork>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct ValidateGenesis(pub args::ValidateGenesis);
+
+    impl SubCmd for ValidateGenesis {
+        const CMD: &'static str = "validate-genesis";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::ValidateGenesis::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Validate that a genesis file matches a published chain \
+                     ID.",
+                )
+                .add_args::<args::ValidateGenesis>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct InitGenesisValidator(pub args::InitGenesisValidator);
+
+    impl SubCmd for InitGenesisValidator {
+        const CMD: &'static str = "init-genesis-validator";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::InitGenesisValidator::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Initialize genesis validator's address, staking reward \
+                     address, consensus key, validator account key and \
+                     staking rewards key and use it in the ledger's node.",
+                )
+                .add_args::<args::InitGenesisValidator>()
+        }
+    }
+}
+
+pub mod args {
+
+    use std::convert::TryFrom;
+    use std::env;
+    use std::fs::File;
+    use std::net::SocketAddr;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    use anoma::ledger::pos::{self, BasisPoints};
+    use anoma::types::address::Address;
+    use anoma::types::chain::{ChainId, ChainIdPrefix};
+    use anoma::types::governance::ProposalVote;
+    use anoma::types::intent::{DecimalWrapper, Exchange};
+    use anoma::types::key::*;
+    use anoma::types::storage::{self, BlockHeight, Epoch};
+    use anoma::types::token;
+    use anoma::types::transaction::GasLimit;
+    use libp2p::Multiaddr;
     use serde::Deserialize;
-    #[cfg(not(feature = "ABCI"))]
-    use tendermint::Timeout;
-    #[cfg(not(feature = "ABCI"))]
-    use tendermint_config::net::Address as TendermintAddress;
-    #[cfg(feature = "ABCI")]
-    use tendermint_config_abci::net::Address as TendermintAddress;
-    #[cfg(feature = "ABCI")]
-    use tendermint_stable::Timeout;
+    use tendermint_compat::tendermint::Timeout;
+    use tendermint_compat::tendermint_config::net::Address as TendermintAddress;
+
+    use super::context::{WalletAddress, WalletKeypair, WalletPublicKey};
+    use super::utils::*;
+    use super::ArgMatches;
+    use crate::client::output::OutputMode;
+    use crate::config;
+    use crate::config::TendermintMode;
+    use crate::node::ledger::StateDumpFormat;
+
+    const ADDRESS: Arg<WalletAddress> = arg("address");
+    const ALIAS_OPT: ArgOpt<String> = ALIAS.opt();
+    const ALIAS: Arg<String> = arg("alias");
+    const ALLOW_DUPLICATE_IP: ArgFlag = flag("allow-duplicate-ip");
+    const AMOUNT: Arg<token::Amount> = arg("amount");
+    const ARCHIVE_DIR: ArgOpt<PathBuf> = arg_opt("archive-dir");
+    const BASE_DIR: ArgDefault<PathBuf> = arg_default(
+        "base-dir",
+        DefaultFn(|| match env::var("ANOMA_BASE_DIR") {
+            Ok(dir) => dir.into(),
+            Err(_) => config::DEFAULT_BASE_DIR.into(),
+        }),
+    );
+    const BATCH_BONDS: ArgMulti<BatchBondEntry> = arg_multi("bond");
+    const BROADCAST_ONLY: ArgFlag = flag("broadcast-only");
+    const CHAIN_ID: Arg<ChainId> = arg("chain-id");
+    const CHAIN_ID_OPT: ArgOpt<ChainId> = CHAIN_ID.opt();
+    const CHAIN_ID_PREFIX: Arg<ChainIdPrefix> = arg("chain-prefix");
+    const CODE_PATH: Arg<PathBuf> = arg("code-path");
+    const CODE_PATH_OPT: ArgOpt<PathBuf> = CODE_PATH.opt();
+    const COMMISSION_RATE: ArgDefault<BasisPoints> = arg_default(
+        "commission-rate",
+        DefaultFn(|| pos::DEFAULT_COMMISSION_RATE),
+    );
+    const CONSENSUS_KEY: Arg<WalletPublicKey> = arg("consensus-key");
+    const CONSENSUS_TIMEOUT_COMMIT: ArgDefault<Timeout> = arg_default(
+        "consensus-timeout-commit",
+        DefaultFn(|| Timeout::from_str("1s").unwrap()),
+    );
+    const CONTACT: ArgOpt<String> = arg_opt("contact");
+    const DATA_PATH_OPT: ArgOpt<PathBuf> = arg_opt("data-path");
+    const DATA_PATH: Arg<PathBuf> = arg("data-path");
+    const DECIMALS: ArgDefault<u8> =
+        arg_default("decimals", DefaultFn(|| token::MAX_DECIMAL_PLACES as u8));
+    const DECRYPT: ArgFlag = flag("decrypt");
+    const DEST_VALIDATOR: Arg<WalletAddress> = arg("dest-validator");
+    const DEV: ArgFlag = flag("dev");
+    const DONT_ARCHIVE: ArgFlag = flag("dont-archive");
+    const DRY_RUN_MIGRATE: ArgFlag = flag("dry-run");
+    const DRY_RUN_TX: ArgFlag = flag("dry-run");
+    const EPOCH: ArgOpt<Epoch> = arg_opt("epoch");
+    const FEE_AMOUNT: ArgDefault<token::Amount> =
+        arg_default("fee-amount", DefaultFn(|| token::Amount::from(0)));
+    const FEE_AMOUNT_OPT: ArgOpt<token::Amount> = arg_opt("fee-amount");
+    const FAUCET: ArgDefaultFromCtx<WalletAddress> =
+        arg_default_from_ctx("faucet", DefaultFn(|| "faucet".into()));
+    const FEE_TOKEN: ArgDefaultFromCtx<WalletAddress> =
+        arg_default_from_ctx("fee-token", DefaultFn(|| "XAN".into()));
+    const FORCE: ArgFlag = flag("force");
+    const FROM_EPOCH: ArgOpt<Epoch> = arg_opt("from-epoch");
+    const FROM_HEIGHT: Arg<BlockHeight> = arg("from-height");
+    const FROM_HEIGHT_OPT: ArgOpt<BlockHeight> = FROM_HEIGHT.opt();
+    const GAS_LIMIT: ArgDefault<token::Amount> =
+        arg_default("gas-limit", DefaultFn(|| token::Amount::from(0)));
+    const GENESIS_PATH: Arg<PathBuf> = arg("genesis-path");
+    const GENESIS_VALIDATOR: ArgOpt<String> = arg("genesis-validator").opt();
+    const INITIAL_SUPPLY: ArgDefault<token::Amount> =
+        arg_default("initial-supply", DefaultFn(|| token::Amount::from(0)));
+    const INTENT_GOSSIPER_ADDR: ArgDefault<SocketAddr> = arg_default(
+        "intent-gossiper",
+        DefaultFn(|| {
+            let raw = "127.0.0.1:26661";
+            SocketAddr::from_str(raw).unwrap()
+        }),
+    );
+    const HEIGHT: Arg<BlockHeight> = arg("height");
+    const HEIGHT_OPT: ArgOpt<BlockHeight> = HEIGHT.opt();
+    const LEDGER_ADDRESS_ABOUT: &str =
+        "Address of a ledger node as \"{scheme}://{host}:{port}\". If the \
+         scheme is not supplied, it is assumed to be TCP.";
+    const LEDGER_ADDRESS_DEFAULT: ArgDefault<TendermintAddress> =
+        LEDGER_ADDRESS.default(DefaultFn(|| {
+            let raw = "127.0.0.1:26657";
+            TendermintAddress::from_str(raw).unwrap()
+        }));
+
+    const LEDGER_ADDRESS: Arg<TendermintAddress> = arg("ledger-address");
+    const LOCALHOST: ArgFlag = flag("localhost");
+    const MATCHMAKER_PATH: ArgOpt<PathBuf> = arg_opt("matchmaker-path");
+    const MAX_COMMISSION_RATE_CHANGE: ArgDefault<BasisPoints> = arg_default(
+        "max-commission-rate-change",
+        DefaultFn(|| pos::DEFAULT_MAX_COMMISSION_RATE_CHANGE),
+    );
+    const MAX_SUPPLY: ArgOpt<token::Amount> = arg_opt("max-supply");
+    const MINTER: ArgOpt<WalletAddress> = arg_opt("minter");
+    const MODE: ArgOpt<String> = arg_opt("mode");
+    const MONIKER: Arg<String> = arg("moniker");
+    const MULTIADDR_OPT: ArgOpt<Multiaddr> = arg_opt("address");
+    const NET_ADDRESS: Arg<SocketAddr> = arg("net-address");
+    const NODE_OPT: ArgOpt<String> = arg_opt("node");
+    const NODE: Arg<String> = arg("node");
+    const NFT_ADDRESS: Arg<Address> = arg("nft-address");
+    const NFT_TOKEN_ID: Arg<u64> = arg("token-id");
+    const NFT_TOKEN_ID_OPT: ArgOpt<u64> = NFT_TOKEN_ID.opt();
+    const OUTPUT: ArgDefault<OutputMode> =
+        arg_default("output", DefaultFn(|| OutputMode::Text));
+    const OWNER: ArgOpt<WalletAddress> = arg_opt("owner");
+    const PROPOSAL_OFFLINE: ArgFlag = flag("offline");
+    const PROTOCOL_KEY: ArgOpt<WalletPublicKey> = arg_opt("protocol-key");
+    const PRE_GENESIS_PATH: ArgOpt<PathBuf> = arg_opt("pre-genesis-path");
+    const PUBLIC_KEY: Arg<WalletPublicKey> = arg("public-key");
+    const PUBLIC_KEY_OPT: ArgOpt<WalletPublicKey> = PUBLIC_KEY.opt();
+    const PROPOSAL_ID: Arg<u64> = arg("proposal-id");
+    const PROPOSAL_ID_OPT: ArgOpt<u64> = arg_opt("proposal-id");
+    const PROPOSAL_VOTE: Arg<ProposalVote> = arg("vote");
+    const RATE: Arg<BasisPoints> = arg("rate");
+    const RAW_ADDRESS: Arg<Address> = arg("address");
+    const RAW_PUBLIC_KEY_OPT: ArgOpt<common::PublicKey> = arg_opt("public-key");
+    const REWARDS_CODE_PATH: ArgOpt<PathBuf> = arg_opt("rewards-code-path");
+    const REWARDS_KEY: ArgOpt<WalletPublicKey> = arg_opt("rewards-key");
+    const RPC_SOCKET_ADDR: ArgOpt<SocketAddr> = arg_opt("rpc");
+    const SENDER: ArgOpt<WalletAddress> = arg_opt("sender");
+    const SIGNER: ArgOpt<WalletAddress> = arg_opt("signer");
+    const SIGNING_KEY_OPT: ArgOpt<WalletKeypair> = SIGNING_KEY.opt();
+    const SIGNING_KEY: Arg<WalletKeypair> = arg("signing-key");
+    const SOURCE: Arg<WalletAddress> = arg("source");
+    const SOURCE_OPT: ArgOpt<WalletAddress> = SOURCE.opt();
+    const SPENDER: Arg<WalletAddress> = arg("spender");
+    const SRC_VALIDATOR: Arg<WalletAddress> = arg("src-validator");
+    const SPENDER_OPT: ArgOpt<WalletAddress> = SPENDER.opt();
+    const STATE_DUMP_FORMAT: ArgDefault<StateDumpFormat> =
+        arg_default("format", DefaultFn(|| StateDumpFormat::Borsh));
+    const STATE_PATH: Arg<PathBuf> = arg("state-path");
+    const STORAGE_KEY: Arg<storage::Key> = arg("storage-key");
+    const SYMBOL: Arg<String> = arg("symbol");
+    const TARGET: Arg<WalletAddress> = arg("target");
+    const TO_EPOCH: ArgOpt<Epoch> = arg_opt("to-epoch");
+    const TO_HEIGHT: Arg<BlockHeight> = arg("to-height");
+    const TO_HEIGHT_OPT: ArgOpt<BlockHeight> = TO_HEIGHT.opt();
+    const TO_STDOUT: ArgFlag = flag("stdout");
+    const TOKEN_OPT: ArgOpt<WalletAddress> = TOKEN.opt();
+    const TOKEN: Arg<WalletAddress> = arg("token");
+    const TOPIC_OPT: ArgOpt<String> = arg_opt("topic");
+    const TOPIC: Arg<String> = arg("topic");
+    const TX_CODE_PATH: ArgOpt<PathBuf> = arg_opt("tx-code-path");
+    const TX_HASH: Arg<String> = arg("tx-hash");
+    const TX_HASH_OPT: ArgOpt<String> = TX_HASH.opt();
+    const TX_TIMEOUT: ArgDefault<Timeout> = arg_default(
+        "tx-timeout",
+        DefaultFn(|| Timeout::from_str("60s").unwrap()),
+    );
+    const UNSAFE_DONT_ENCRYPT: ArgFlag = flag("unsafe-dont-encrypt");
+    const UNSAFE_SHOW_SECRET: ArgFlag = flag("unsafe-show-secret");
+    const VALIDATOR: Arg<WalletAddress> = arg("validator");
+    const VALIDATOR_OPT: ArgOpt<WalletAddress> = VALIDATOR.opt();
+    const VALIDATOR_ACCOUNT_KEY: ArgOpt<WalletPublicKey> =
+        arg_opt("account-key");
+    const VALIDATOR_CONSENSUS_KEY: ArgOpt<WalletKeypair> =
+        arg_opt("consensus-key");
+    const VALIDATOR_CODE_PATH: ArgOpt<PathBuf> = arg_opt("validator-code-path");
+    const VALUE: ArgOpt<String> = arg_opt("value");
+    const WASM_CHECKSUMS_PATH: Arg<PathBuf> = arg("wasm-checksums-path");
+    const WASM_DIR: ArgOpt<PathBuf> = arg_opt("wasm-dir");
+    const WEBSITE: ArgOpt<String> = arg_opt("website");
+    const YES: ArgFlag = flag("yes");
+
+    /// Global command arguments
+    #[derive(Clone, Debug)]
+    pub struct Global {
+        pub chain_id: Option<ChainId>,
+        pub base_dir: PathBuf,
+        pub wasm_dir: Option<PathBuf>,
+        pub mode: Option<TendermintMode>,
+        pub output: OutputMode,
+    }
+
+    impl Global {
+        /// Parse global arguments
+        pub fn parse(matches: &ArgMatches) -> Self {
+            let chain_id = CHAIN_ID_OPT.parse(matches);
+            let base_dir = BASE_DIR.parse(matches);
+            let wasm_dir = WASM_DIR.parse(matches);
+            let mode = MODE.parse(matches).map(TendermintMode::from);
+            let output = OUTPUT.parse(matches);
+            Global {
+                chain_id,
+                base_dir,
+                wasm_dir,
+                mode,
+                output,
+            }
+        }
+
+        /// Add global args definition. Should be added to every top-level
+        /// command.
+        pub fn def(app: App) -> App {
+            app.arg(CHAIN_ID_OPT.def().about("The chain ID."))
+                .arg(BASE_DIR.def().about(
+                    "The base directory is where the nodes, client and wallet \
+                     configuration and state is stored. This value can also \
+                     be set via `ANOMA_BASE_DIR` environment variable, but \
+                     the argument takes precedence, if specified. Defaults to \
+                     `.anoma`.",
+                ))
+                .arg(WASM_DIR.def().about(
+                    "Directory with built WASM validity predicates, \
+                     transactions and matchmaker files. This must not be an \
+                     absolute path as the directory is nested inside the \
+                     chain directory. This value can also be set via \
+                     `ANOMA_WASM_DIR` environment variable, but the argument \
+                     takes precedence, if specified.",
+                ))
+                .arg(MODE.def().about(
+                    "The mode in which to run Anoma. Options are \n\t * \
+                     Validator (default)\n\t * Full\n\t * Seed",
+                ))
+                .arg(OUTPUT.def().about(
+                    "The format in which the results of the queries are \
+                     printed. Options are \n\t * text (default)\n\t * json",
+                ))
+        }
+    }
+
+    /// Transaction associated results arguments
+    #[derive(Clone, Debug)]
+    pub struct QueryResult {
+        /// Common query args
+        pub query: Query,
+        /// Hash of transaction to lookup
+        pub tx_hash: String,
+    }
+
+    impl Args for QueryResult {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let tx_hash = TX_HASH.parse(matches);
+            Self { query, tx_hash }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>().arg(
+                TX_HASH
+                    .def()
+                    .about("The hash of the transaction being looked up."),
+            )
+        }
+    }
+
+    /// Tracked transactions status arguments
+    #[derive(Clone, Debug)]
+    pub struct TxStatus {
+        /// Common query args
+        pub query: Query,
+        /// Hash of the tracked transaction, or all of them if not given
+        pub tx_hash: Option<String>,
+    }
+
+    impl Args for TxStatus {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let tx_hash = TX_HASH_OPT.parse(matches);
+            Self { query, tx_hash }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>().arg(TX_HASH_OPT.def().about(
+                "The hash of the tracked transaction or of its last wrapper. \
+                 If not given, all the tracked transactions are checked.",
+            ))
+        }
+    }
+
+    /// Tracked transaction resubmission arguments
+    #[derive(Clone, Debug)]
+    pub struct TxResubmit {
+        /// Common query args
+        pub query: Query,
+        /// Hash of the tracked transaction
+        pub tx_hash: String,
+        /// The fee of the new wrapper, instead of the bumped fee
+        pub fee_amount: Option<token::Amount>,
+        /// How long to wait for each of the events of the transaction
+        pub tx_timeout: Timeout,
+    }
+
+    impl Args for TxResubmit {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let tx_hash = TX_HASH.parse(matches);
+            let fee_amount = FEE_AMOUNT_OPT.parse(matches);
+            let tx_timeout = TX_TIMEOUT.parse(matches);
+            Self {
+                query,
+                tx_hash,
+                fee_amount,
+                tx_timeout,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>()
+                .arg(TX_HASH.def().about(
+                    "The hash of the tracked transaction or of its last \
+                     wrapper.",
+                ))
+                .arg(FEE_AMOUNT_OPT.def().about(
+                    "The fee of the new wrapper. Defaults to the fee of the \
+                     last wrapper bumped by 20%.",
+                ))
+                .arg(TX_TIMEOUT.def().about(
+                    "How long to wait for the transaction to be accepted and \
+                     applied, e.g. \"60s\".",
+                ))
+        }
+    }
+
+    /// Custom transaction arguments
+    #[derive(Clone, Debug)]
+    pub struct TxCustom {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Path to the tx WASM code file
+        pub code_path: PathBuf,
+        /// Path to the data file
+        pub data_path: Option<PathBuf>,
+    }
+
+    impl Args for TxCustom {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let code_path = CODE_PATH.parse(matches);
+            let data_path = DATA_PATH_OPT.parse(matches);
+            Self {
+                tx,
+                code_path,
+                data_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(
+                    CODE_PATH
+                        .def()
+                        .about("The path to the transaction's WASM code."),
+                )
+                .arg(DATA_PATH_OPT.def().about(
+                    "The data file at this path containing arbitrary bytes \
+                     will be passed to the transaction code when it's \
+                     executed.",
+                ))
+        }
+    }
+
+    /// Transfer transaction arguments
+    #[derive(Clone, Debug)]
+    pub struct TxTransfer {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Transfer source address
+        pub source: WalletAddress,
+        /// Transfer target address
+        pub target: WalletAddress,
+        /// Transferred token address
+        pub token: WalletAddress,
+        /// Transferred token amount
+        pub amount: token::Amount,
+    }
+
+    impl Args for TxTransfer {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let source = SOURCE.parse(matches);
+            let target = TARGET.parse(matches);
+            let token = TOKEN.parse(matches);
+            let amount = AMOUNT.parse(matches);
+            Self {
+                tx,
+                source,
+                target,
+                token,
+                amount,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(SOURCE.def().about(
+                    "The source account address. The source's key is used to \
+                     produce the signature.",
+                ))
+                .arg(TARGET.def().about("The target account address."))
+                .arg(TOKEN.def().about("The transfer token."))
+                .arg(AMOUNT.def().about(
+                    "The amount to transfer in decimal, in the units of the \
+                     token. It must not have more decimal places than the \
+                     token.",
+                ))
+        }
+    }
+
+    /// Approval transaction arguments
+    #[derive(Clone, Debug)]
+    pub struct TxApprove {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Address of the owner of the tokens
+        pub source: WalletAddress,
+        /// Address of the approved spender
+        pub spender: WalletAddress,
+        /// Approved token address
+        pub token: WalletAddress,
+        /// Approved token amount
+        pub amount: token::Amount,
+    }
+
+    impl Args for TxApprove {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let source = SOURCE.parse(matches);
+            let spender = SPENDER.parse(matches);
+            let token = TOKEN.parse(matches);
+            let amount = AMOUNT.parse(matches);
+            Self {
+                tx,
+                source,
+                spender,
+                token,
+                amount,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(SOURCE.def().about(
+                    "The account address of the owner of the tokens. The \
+                     source's key is used to produce the signature.",
+                ))
+                .arg(SPENDER.def().about("The spender account address."))
+                .arg(TOKEN.def().about("The approved token."))
+                .arg(AMOUNT.def().about(
+                    "The amount that the spender may transfer in decimal. It \
+                     replaces any previous allowance.",
+                ))
+        }
+    }
+
+    /// Transfer from an allowance transaction arguments
+    #[derive(Clone, Debug)]
+    pub struct TxTransferFrom {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Address of the approved spender
+        pub spender: WalletAddress,
+        /// Transfer source address
+        pub source: WalletAddress,
+        /// Transfer target address
+        pub target: WalletAddress,
+        /// Transferred token address
+        pub token: WalletAddress,
+        /// Transferred token amount
+        pub amount: token::Amount,
+    }
+
+    impl Args for TxTransferFrom {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let spender = SPENDER.parse(matches);
+            let source = SOURCE.parse(matches);
+            let target = TARGET.parse(matches);
+            let token = TOKEN.parse(matches);
+            let amount = AMOUNT.parse(matches);
+            Self {
+                tx,
+                spender,
+                source,
+                target,
+                token,
+                amount,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(SPENDER.def().about(
+                    "The spender account address. The spender's key is used \
+                     to produce the signature.",
+                ))
+                .arg(SOURCE.def().about(
+                    "The source account address that approved the spender.",
+                ))
+                .arg(TARGET.def().about("The target account address."))
+                .arg(TOKEN.def().about("The transfer token."))
+                .arg(AMOUNT.def().about("The amount to transfer in decimal."))
+        }
+    }
+
+    /// Transaction to initialize a new account
+    #[derive(Clone, Debug)]
+    pub struct TxInitAccount {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Address of the source account
+        pub source: WalletAddress,
+        /// Path to the VP WASM code file for the new account
+        pub vp_code_path: Option<PathBuf>,
+        /// Public key for the new account
+        pub public_key: WalletPublicKey,
+    }
+
+    impl Args for TxInitAccount {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let source = SOURCE.parse(matches);
+            let vp_code_path = CODE_PATH_OPT.parse(matches);
+            let public_key = PUBLIC_KEY.parse(matches);
+            Self {
+                tx,
+                source,
+                vp_code_path,
+                public_key,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(SOURCE.def().about(
+                    "The source account's address that signs the transaction.",
+                ))
+                .arg(CODE_PATH_OPT.def().about(
+                    "The path to the validity predicate WASM code to be used \
+                     for the new account. Uses the default user VP if none \
+                     specified.",
+                ))
+                .arg(PUBLIC_KEY.def().about(
+                    "A public key to be used for the new account in \
+                     hexadecimal encoding.",
+                ))
+        }
+    }
+
+    /// Transaction to promote an implicit account to an established account
+    #[derive(Clone, Debug)]
+    pub struct TxPromoteAccount {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Address of the implicit account
+        pub source: WalletAddress,
+        /// Path to the VP WASM code file for the new account
+        pub vp_code_path: Option<PathBuf>,
+    }
+
+    impl Args for TxPromoteAccount {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let source = SOURCE.parse(matches);
+            let vp_code_path = CODE_PATH_OPT.parse(matches);
+            Self {
+                tx,
+                source,
+                vp_code_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(SOURCE.def().about(
+                    "The implicit account's address. Its key signs the \
+                     transaction and controls the new account.",
+                ))
+                .arg(CODE_PATH_OPT.def().about(
+                    "The path to the validity predicate WASM code to be used \
+                     for the new account. Uses the default user VP if none \
+                     specified.",
+                ))
+        }
+    }
+
+    /// Transaction to initialize a new token
+    #[derive(Clone, Debug)]
+    pub struct TxInitToken {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Address of the source account that receives the initial supply
+        pub source: WalletAddress,
+        /// Path to the VP WASM code file for the new token
+        pub vp_code_path: Option<PathBuf>,
+        /// The token's symbol
+        pub symbol: String,
+        /// The token's number of decimal places
+        pub decimals: u8,
+        /// The token's maximum supply
+        pub max_supply: Option<token::Amount>,
+        /// The token's minter
+        pub minter: Option<WalletAddress>,
+        /// The amount minted to the source
+        pub initial_supply: token::Amount,
+    }
+
+    impl Args for TxInitToken {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let source = SOURCE.parse(matches);
+            let vp_code_path = CODE_PATH_OPT.parse(matches);
+            let symbol = SYMBOL.parse(matches);
+            let decimals = DECIMALS.parse(matches);
+            let max_supply = MAX_SUPPLY.parse(matches);
+            let minter = MINTER.parse(matches);
+            let initial_supply = INITIAL_SUPPLY.parse(matches);
+            Self {
+                tx,
+                source,
+                vp_code_path,
+                symbol,
+                decimals,
+                max_supply,
+                minter,
+                initial_supply,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(SOURCE.def().about(
+                    "The source account's address that signs the transaction \
+                     and receives the initial supply.",
+                ))
+                .arg(CODE_PATH_OPT.def().about(
+                    "The path to the validity predicate WASM code to be used \
+                     for the new token. Uses the default token VP if none \
+                     specified.",
+                ))
+                .arg(SYMBOL.def().about(
+                    "The token's symbol, unique among the registered tokens. \
+                     It must be made of up to 12 uppercase letters or digits.",
+                ))
+                .arg(DECIMALS.def().about(
+                    "The number of decimal places used to display the token's \
+                     amounts, at most 6.",
+                ))
+                .arg(MAX_SUPPLY.def().about(
+                    "The maximum total supply of the token. Unlimited if none \
+                     specified.",
+                ))
+                .arg(MINTER.def().about(
+                    "The address allowed to mint the token. The total supply \
+                     is fixed if none specified.",
+                ))
+                .arg(
+                    INITIAL_SUPPLY
+                        .def()
+                        .about("The amount minted to the source in decimal."),
+                )
+        }
+    }
+
+    /// Transaction to register an alias in the name service
+    #[derive(Clone, Debug)]
+    pub struct TxRegisterAlias {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// The alias to register
+        pub alias: String,
+        /// Address that the alias resolves to
+        pub address: WalletAddress,
+    }
+
+    impl Args for TxRegisterAlias {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let alias = ALIAS.parse(matches);
+            let address = ADDRESS.parse(matches);
+            Self { tx, alias, address }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(ALIAS.def().about(
+                    "The alias to register. It must be made of 3 to 64 \
+                     lowercase letters, digits or dashes.",
+                ))
+                .arg(ADDRESS.def().about(
+                    "The address that the alias resolves to. It signs the \
+                     transaction and pays the registration fee.",
+                ))
+        }
+    }
+
+    /// Transaction to initialize a new account
+    #[derive(Clone, Debug)]
+    pub struct TxInitValidator {
+        pub tx: Tx,
+        pub source: WalletAddress,
+        pub account_key: Option<WalletPublicKey>,
+        pub consensus_key: Option<WalletKeypair>,
+        pub rewards_account_key: Option<WalletPublicKey>,
+        pub protocol_key: Option<WalletPublicKey>,
+        pub commission_rate: BasisPoints,
+        pub max_commission_rate_change: BasisPoints,
+        pub validator_vp_code_path: Option<PathBuf>,
+        pub rewards_vp_code_path: Option<PathBuf>,
+        pub unsafe_dont_encrypt: bool,
+    }
+
+    impl Args for TxInitValidator {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let source = SOURCE.parse(matches);
+            let account_key = VALIDATOR_ACCOUNT_KEY.parse(matches);
+            let consensus_key = VALIDATOR_CONSENSUS_KEY.parse(matches);
+            let rewards_account_key = REWARDS_KEY.parse(matches);
+            let protocol_key = PROTOCOL_KEY.parse(matches);
+            let commission_rate = COMMISSION_RATE.parse(matches);
+            let max_commission_rate_change =
+                MAX_COMMISSION_RATE_CHANGE.parse(matches);
+            let validator_vp_code_path = VALIDATOR_CODE_PATH.parse(matches);
+            let rewards_vp_code_path = REWARDS_CODE_PATH.parse(matches);
+            let unsafe_dont_encrypt = UNSAFE_DONT_ENCRYPT.parse(matches);
+            Self {
+                tx,
+                source,
+                account_key,
+                consensus_key,
+                rewards_account_key,
+                protocol_key,
+                commission_rate,
+                max_commission_rate_change,
+                validator_vp_code_path,
+                rewards_vp_code_path,
+                unsafe_dont_encrypt,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(SOURCE.def().about(
+                    "The source account's address that signs the transaction.",
+                ))
+                .arg(VALIDATOR_ACCOUNT_KEY.def().about(
+                    "A public key for the validator account. A new one will \
+                     be generated if none given.",
+                ))
+                .arg(VALIDATOR_CONSENSUS_KEY.def().about(
+                    "A consensus key for the validator account. A new one \
+                     will be generated if none given.",
+                ))
+                .arg(REWARDS_KEY.def().about(
+                    "A public key for the staking reward account. A new one \
+                     will be generated if none given.",
+                ))
+                .arg(PROTOCOL_KEY.def().about(
+                    "A public key for signing protocol transactions. A new \
+                     one will be generated if none given.",
+                ))
+                .arg(COMMISSION_RATE.def().about(
+                    "The portion of the validator's rewards credited to its \
+                     staking reward account, in basis points.",
+                ))
+                .arg(MAX_COMMISSION_RATE_CHANGE.def().about(
+                    "The maximum change of the validator's commission rate \
+                     per epoch, in basis points. It cannot be changed later.",
+                ))
+                .arg(VALIDATOR_CODE_PATH.def().about(
+                    "The path to the validity predicate WASM code to be used \
+                     for the validator account. Uses the default validator VP \
+                     if none specified.",
+                ))
+                .arg(REWARDS_CODE_PATH.def().about(
+                    "The path to the validity predicate WASM code to be used \
+                     for the staking reward account. Uses the default staking \
+                     reward VP if none specified.",
+                ))
+                .arg(UNSAFE_DONT_ENCRYPT.def().about(
+                    "UNSAFE: Do not encrypt the generated keypairs. Do not \
+                     use this for keys used in a live network.",
+                ))
+        }
+    }
+
+    /// Transaction to update a VP arguments
+    #[derive(Clone, Debug)]
+    pub struct TxUpdateVp {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Path to the VP WASM code file
+        pub vp_code_path: PathBuf,
+        /// Address of the account whose VP is to be updated
+        pub addr: WalletAddress,
+    }
+
+    impl Args for TxUpdateVp {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let vp_code_path = CODE_PATH.parse(matches);
+            let addr = ADDRESS.parse(matches);
+            Self {
+                tx,
+                vp_code_path,
+                addr,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(
+                    CODE_PATH.def().about(
+                        "The path to the new validity predicate WASM code.",
+                    ),
+                )
+                .arg(ADDRESS.def().about(
+                    "The account's address. It's key is used to produce the \
+                     signature.",
+                ))
+        }
+    }
+
+    /// Transaction to update an established account's public key and/or VP
+    #[derive(Clone, Debug)]
+    pub struct TxUpdateAccount {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Address of the account to update
+        pub addr: WalletAddress,
+        /// The new public key of the account
+        pub public_key: Option<WalletPublicKey>,
+        /// Path to the new VP WASM code file
+        pub vp_code_path: Option<PathBuf>,
+    }
+
+    impl Args for TxUpdateAccount {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let addr = ADDRESS.parse(matches);
+            let public_key = PUBLIC_KEY_OPT.parse(matches);
+            let vp_code_path = CODE_PATH_OPT.parse(matches);
+            Self {
+                tx,
+                addr,
+                public_key,
+                vp_code_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(ADDRESS.def().about(
+                    "The account's address. Its current key is used to \
+                     produce the signature.",
+                ))
+                .arg(PUBLIC_KEY_OPT.def().about(
+                    "The new public key of the account in hexadecimal \
+                     encoding. The current key is no longer valid for the \
+                     account once the transaction is applied.",
+                ))
+                .arg(
+                    CODE_PATH_OPT.def().about(
+                        "The path to the new validity predicate WASM code.",
+                    ),
+                )
+        }
+    }
+
+    /// Bond arguments
+    #[derive(Clone, Debug)]
+    pub struct Bond {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Validator address
+        pub validator: WalletAddress,
+        /// Amount of tokens to stake in a bond
+        pub amount: token::Amount,
+        /// Source address for delegations. For self-bonds, the validator is
+        /// also the source.
+        pub source: Option<WalletAddress>,
+    }
+
+    impl Args for Bond {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            let amount = AMOUNT.parse(matches);
+            let source = SOURCE_OPT.parse(matches);
+            Self {
+                tx,
+                validator,
+                amount,
+                source,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(VALIDATOR.def().about("Validator address."))
+                .arg(AMOUNT.def().about("Amount of tokens to stake in a bond."))
+                .arg(SOURCE_OPT.def().about(
+                    "Source address for delegations. For self-bonds, the \
+                     validator is also the source.",
+                ))
+        }
+    }
+
+    /// A bond of a batch of bonds, parsed from the validator's address or
+    /// alias and the amount separated by a colon, e.g. `validator:100`
+    #[derive(Clone, Debug)]
+    pub struct BatchBondEntry {
+        /// Validator address
+        pub validator: WalletAddress,
+        /// Amount of tokens to stake in the bond
+        pub amount: token::Amount,
+    }
+
+    impl FromStr for BatchBondEntry {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let (validator, amount) = s.rsplit_once(':').ok_or_else(|| {
+                format!("Expected a validator and an amount in {}", s)
+            })?;
+            let amount = token::Amount::from_str(amount)
+                .map_err(|err| format!("Invalid amount {}: {}", amount, err))?;
+            Ok(Self {
+                validator: WalletAddress::new(validator.to_owned()),
+                amount,
+            })
+        }
+    }
+
+    /// Batch bond arguments
+    #[derive(Clone, Debug)]
+    pub struct BatchBond {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// The validators with the amounts of tokens to stake in the bonds
+        pub bonds: Vec<BatchBondEntry>,
+        /// Source address of the delegations
+        pub source: WalletAddress,
+    }
+
+    impl Args for BatchBond {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let bonds = BATCH_BONDS.parse(matches);
+            let source = SOURCE.parse(matches);
+            Self { tx, bonds, source }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(BATCH_BONDS.def().takes_value(true).required(true).about(
+                    "A validator address and an amount of tokens to stake \
+                     in a bond to it, separated by a colon, e.g. \
+                     `validator:100`. Can be repeated for each validator.",
+                ))
+                .arg(SOURCE.def().about(
+                    "Source address of the delegations, which must not be a \
+                     validator.",
+                ))
+        }
+    }
+
+    /// Unbond arguments
+    #[derive(Clone, Debug)]
+    pub struct Unbond {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Validator address
+        pub validator: WalletAddress,
+        /// Amount of tokens to unbond from a bond
+        pub amount: token::Amount,
+        /// Source address for unbonding from delegations. For unbonding from
+        /// self-bonds, the validator is also the source
+        pub source: Option<WalletAddress>,
+    }
+
+    impl Args for Unbond {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            let amount = AMOUNT.parse(matches);
+            let source = SOURCE_OPT.parse(matches);
+            Self {
+                tx,
+                validator,
+                amount,
+                source,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(VALIDATOR.def().about("Validator address."))
+                .arg(
+                    AMOUNT
+                        .def()
+                        .about("Amount of tokens to unbond from a bond."),
+                )
+                .arg(SOURCE_OPT.def().about(
+                    "Source address for unbonding from delegations. For \
+                     unbonding from self-bonds, the validator is also the \
+                     source.",
+                ))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct InitProposal {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// The proposal file path
+        pub proposal_data: PathBuf,
+        /// Flag if proposal should be run offline
+        pub offline: bool,
+    }
+
+    impl Args for InitProposal {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let proposal_data = DATA_PATH.parse(matches);
+            let offline = PROPOSAL_OFFLINE.parse(matches);
+
+            Self {
+                tx,
+                proposal_data,
+                offline,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(DATA_PATH.def().about(
+                    "The data path file (json) that describes the proposal.",
+                ))
+                .arg(
+                    PROPOSAL_OFFLINE
+                        .def()
+                        .about("Flag if the proposal vote should run offline."),
+                )
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct VoteProposal {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Proposal id
+        pub proposal_id: Option<u64>,
+        /// The vote
+        pub vote: ProposalVote,
+        /// Flag if proposal vote should be run offline
+        pub offline: bool,
+        /// The proposal file path
+        pub proposal_data: Option<PathBuf>,
+    }
+
+    impl Args for VoteProposal {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let proposal_id = PROPOSAL_ID_OPT.parse(matches);
+            let vote = PROPOSAL_VOTE.parse(matches);
+            let offline = PROPOSAL_OFFLINE.parse(matches);
+            let proposal_data = DATA_PATH_OPT.parse(matches);
+
+            Self {
+                tx,
+                proposal_id,
+                vote,
+                offline,
+                proposal_data,
+            }
+        }
 
-    use super::context::{WalletAddress, WalletKeypair, WalletPublicKey};
-    use super::utils::*;
-    use super::ArgMatches;
-    use crate::config;
-    use crate::config::TendermintMode;
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(
+                    PROPOSAL_ID_OPT
+                        .def()
+                        .about("The proposal identifier.")
+                        .conflicts_with_all(&[
+                            PROPOSAL_OFFLINE.name,
+                            DATA_PATH_OPT.name,
+                        ]),
+                )
+                .arg(
+                    PROPOSAL_VOTE
+                        .def()
+                        .about("The vote for the proposal. Either yay or nay."),
+                )
+                .arg(
+                    PROPOSAL_OFFLINE
+                        .def()
+                        .about("Flag if the proposal vote should run offline.")
+                        .conflicts_with(PROPOSAL_ID.name),
+                )
+                .arg(
+                    DATA_PATH_OPT
+                        .def()
+                        .about(
+                            "The data path file (json) that describes the \
+                             proposal.",
+                        )
+                        .conflicts_with(PROPOSAL_ID.name),
+                )
+        }
+    }
 
-    const ADDRESS: Arg<WalletAddress> = arg("address");
-    const ALIAS_OPT: ArgOpt<String> = ALIAS.opt();
-    const ALIAS: Arg<String> = arg("alias");
-    const ALLOW_DUPLICATE_IP: ArgFlag = flag("allow-duplicate-ip");
-    const AMOUNT: Arg<token::Amount> = arg("amount");
-    const ARCHIVE_DIR: ArgOpt<PathBuf> = arg_opt("archive-dir");
-    const BASE_DIR: ArgDefault<PathBuf> = arg_default(
-        "base-dir",
-        DefaultFn(|| match env::var("ANOMA_BASE_DIR") {
-            Ok(dir) => dir.into(),
-            Err(_) => config::DEFAULT_BASE_DIR.into(),
-        }),
-    );
-    const BROADCAST_ONLY: ArgFlag = flag("broadcast-only");
-    const CHAIN_ID: Arg<ChainId> = arg("chain-id");
-    const CHAIN_ID_OPT: ArgOpt<ChainId> = CHAIN_ID.opt();
-    const CHAIN_ID_PREFIX: Arg<ChainIdPrefix> = arg("chain-prefix");
-    const CODE_PATH: Arg<PathBuf> = arg("code-path");
-    const CODE_PATH_OPT: ArgOpt<PathBuf> = CODE_PATH.opt();
-    const CONSENSUS_TIMEOUT_COMMIT: ArgDefault<Timeout> = arg_default(
-        "consensus-timeout-commit",
-        DefaultFn(|| Timeout::from_str("1s").unwrap()),
-    );
-    const DATA_PATH_OPT: ArgOpt<PathBuf> = arg_opt("data-path");
-    const DATA_PATH: Arg<PathBuf> = arg("data-path");
-    const DECRYPT: ArgFlag = flag("decrypt");
-    const DONT_ARCHIVE: ArgFlag = flag("dont-archive");
-    const DRY_RUN_TX: ArgFlag = flag("dry-run");
-    const EPOCH: ArgOpt<Epoch> = arg_opt("epoch");
-    const FEE_AMOUNT: ArgDefault<token::Amount> =
-        arg_default("fee-amount", DefaultFn(|| token::Amount::from(0)));
-    const FEE_TOKEN: ArgDefaultFromCtx<WalletAddress> =
-        arg_default_from_ctx("fee-token", DefaultFn(|| "XAN".into()));
-    const FORCE: ArgFlag = flag("force");
-    const GAS_LIMIT: ArgDefault<token::Amount> =
-        arg_default("gas-limit", DefaultFn(|| token::Amount::from(0)));
-    const GENESIS_PATH: Arg<PathBuf> = arg("genesis-path");
-    const GENESIS_VALIDATOR: ArgOpt<String> = arg("genesis-validator").opt();
-    const INTENT_GOSSIPER_ADDR: ArgDefault<SocketAddr> = arg_default(
-        "intent-gossiper",
-        DefaultFn(|| {
-            let raw = "127.0.0.1:26661";
-            SocketAddr::from_str(raw).unwrap()
-        }),
-    );
-    const LEDGER_ADDRESS_ABOUT: &str =
-        "Address of a ledger node as \"{scheme}://{host}:{port}\". If the \
-         scheme is not supplied, it is assumed to be TCP.";
-    const LEDGER_ADDRESS_DEFAULT: ArgDefault<TendermintAddress> =
-        LEDGER_ADDRESS.default(DefaultFn(|| {
-            let raw = "127.0.0.1:26657";
-            TendermintAddress::from_str(raw).unwrap()
-        }));
+    #[derive(Clone, Debug)]
+    pub struct QueryProposal {
+        /// Common query args
+        pub query: Query,
+        /// Proposal id
+        pub proposal_id: Option<u64>,
+    }
 
-    const LEDGER_ADDRESS: Arg<TendermintAddress> = arg("ledger-address");
-    const LOCALHOST: ArgFlag = flag("localhost");
-    const MATCHMAKER_PATH: ArgOpt<PathBuf> = arg_opt("matchmaker-path");
-    const MODE: ArgOpt<String> = arg_opt("mode");
-    const MULTIADDR_OPT: ArgOpt<Multiaddr> = arg_opt("address");
-    const NET_ADDRESS: Arg<SocketAddr> = arg("net-address");
-    const NODE_OPT: ArgOpt<String> = arg_opt("node");
-    const NODE: Arg<String> = arg("node");
-    const NFT_ADDRESS: Arg<Address> = arg("nft-address");
-    const OWNER: ArgOpt<WalletAddress> = arg_opt("owner");
-    const PROPOSAL_OFFLINE: ArgFlag = flag("offline");
-    const PROTOCOL_KEY: ArgOpt<WalletPublicKey> = arg_opt("protocol-key");
-    const PRE_GENESIS_PATH: ArgOpt<PathBuf> = arg_opt("pre-genesis-path");
-    const PUBLIC_KEY: Arg<WalletPublicKey> = arg("public-key");
-    const PROPOSAL_ID: Arg<u64> = arg("proposal-id");
-    const PROPOSAL_ID_OPT: ArgOpt<u64> = arg_opt("proposal-id");
-    const PROPOSAL_VOTE: Arg<ProposalVote> = arg("vote");
-    const RAW_ADDRESS: Arg<Address> = arg("address");
-    const RAW_PUBLIC_KEY_OPT: ArgOpt<common::PublicKey> = arg_opt("public-key");
-    const REWARDS_CODE_PATH: ArgOpt<PathBuf> = arg_opt("rewards-code-path");
-    const REWARDS_KEY: ArgOpt<WalletPublicKey> = arg_opt("rewards-key");
-    const RPC_SOCKET_ADDR: ArgOpt<SocketAddr> = arg_opt("rpc");
-    const SIGNER: ArgOpt<WalletAddress> = arg_opt("signer");
-    const SIGNING_KEY_OPT: ArgOpt<WalletKeypair> = SIGNING_KEY.opt();
-    const SIGNING_KEY: Arg<WalletKeypair> = arg("signing-key");
-    const SOURCE: Arg<WalletAddress> = arg("source");
-    const SOURCE_OPT: ArgOpt<WalletAddress> = SOURCE.opt();
-    const STORAGE_KEY: Arg<storage::Key> = arg("storage-key");
-    const TARGET: Arg<WalletAddress> = arg("target");
-    const TO_STDOUT: ArgFlag = flag("stdout");
-    const TOKEN_OPT: ArgOpt<WalletAddress> = TOKEN.opt();
-    const TOKEN: Arg<WalletAddress> = arg("token");
-    const TOPIC_OPT: ArgOpt<String> = arg_opt("topic");
-    const TOPIC: Arg<String> = arg("topic");
-    const TX_CODE_PATH: ArgOpt<PathBuf> = arg_opt("tx-code-path");
-    const TX_HASH: Arg<String> = arg("tx-hash");
-    const UNSAFE_DONT_ENCRYPT: ArgFlag = flag("unsafe-dont-encrypt");
-    const UNSAFE_SHOW_SECRET: ArgFlag = flag("unsafe-show-secret");
-    const VALIDATOR: Arg<WalletAddress> = arg("validator");
-    const VALIDATOR_OPT: ArgOpt<WalletAddress> = VALIDATOR.opt();
-    const VALIDATOR_ACCOUNT_KEY: ArgOpt<WalletPublicKey> =
-        arg_opt("account-key");
-    const VALIDATOR_CONSENSUS_KEY: ArgOpt<WalletKeypair> =
-        arg_opt("consensus-key");
-    const VALIDATOR_CODE_PATH: ArgOpt<PathBuf> = arg_opt("validator-code-path");
-    const VALUE: ArgOpt<String> = arg_opt("value");
-    const WASM_CHECKSUMS_PATH: Arg<PathBuf> = arg("wasm-checksums-path");
-    const WASM_DIR: ArgOpt<PathBuf> = arg_opt("wasm-dir");
+    impl Args for QueryProposal {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let proposal_id = PROPOSAL_ID_OPT.parse(matches);
+
+            Self { query, proposal_id }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(PROPOSAL_ID_OPT.def().about("The proposal identifier."))
+        }
+    }
 
-    /// Global command arguments
     #[derive(Clone, Debug)]
-    pub struct Global {
-        pub chain_id: Option<ChainId>,
-        pub base_dir: PathBuf,
-        pub wasm_dir: Option<PathBuf>,
-        pub mode: Option<TendermintMode>,
+    pub struct QueryProposalResult {
+        /// Common query args
+        pub query: Query,
+        /// Proposal id
+        pub proposal_id: Option<u64>,
+        /// Flag if proposal result should be run on offline data
+        pub offline: bool,
+        /// The folder containing the proposal and votes
+        pub proposal_folder: Option<PathBuf>,
     }
 
-    impl Global {
-        /// Parse global arguments
-        pub fn parse(matches: &ArgMatches) -> Self {
-            let chain_id = CHAIN_ID_OPT.parse(matches);
-            let base_dir = BASE_DIR.parse(matches);
-            let wasm_dir = WASM_DIR.parse(matches);
-            let mode = MODE.parse(matches).map(TendermintMode::from);
-            Global {
-                chain_id,
-                base_dir,
-                wasm_dir,
-                mode,
+    impl Args for QueryProposalResult {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let proposal_id = PROPOSAL_ID_OPT.parse(matches);
+            let offline = PROPOSAL_OFFLINE.parse(matches);
+            let proposal_folder = DATA_PATH_OPT.parse(matches);
+
+            Self {
+                query,
+                proposal_id,
+                offline,
+                proposal_folder,
             }
         }
 
-        /// Add global args definition. Should be added to every top-level
-        /// command.
-        pub fn def(app: App) -> App {
-            app.arg(CHAIN_ID_OPT.def().about("The chain ID."))
-                .arg(BASE_DIR.def().about(
-                    "The base directory is where the nodes, client and wallet \
-                     configuration and state is stored. This value can also \
-                     be set via `ANOMA_BASE_DIR` environment variable, but \
-                     the argument takes precedence, if specified. Defaults to \
-                     `.anoma`.",
-                ))
-                .arg(WASM_DIR.def().about(
-                    "Directory with built WASM validity predicates, \
-                     transactions and matchmaker files. This must not be an \
-                     absolute path as the directory is nested inside the \
-                     chain directory. This value can also be set via \
-                     `ANOMA_WASM_DIR` environment variable, but the argument \
-                     takes precedence, if specified.",
-                ))
-                .arg(MODE.def().about(
-                    "The mode in which to run Anoma. Options are \n\t * \
-                     Validator (default)\n\t * Full\n\t * Seed",
-                ))
+        fn def(app: App) -> App {
+            app.add_args::<Query>()
+                .arg(PROPOSAL_ID_OPT.def().about("The proposal identifier."))
+                .arg(
+                    PROPOSAL_OFFLINE
+                        .def()
+                        .about(
+                            "Flag if the proposal result should run on \
+                             offline data.",
+                        )
+                        .conflicts_with(PROPOSAL_ID.name),
+                )
+                .arg(
+                    DATA_PATH_OPT
+                        .def()
+                        .about(
+                            "The path to the folder containing the proposal \
+                             json and votes",
+                        )
+                        .conflicts_with(PROPOSAL_ID.name),
+                )
         }
     }
 
-    /// Transaction associated results arguments
     #[derive(Clone, Debug)]
-    pub struct QueryResult {
+    pub struct QueryProtocolParameters {
         /// Common query args
         pub query: Query,
-        /// Hash of transaction to lookup
-        pub tx_hash: String,
     }
 
-    impl Args for QueryResult {
+    impl Args for QueryProtocolParameters {
         fn parse(matches: &ArgMatches) -> Self {
             let query = Query::parse(matches);
-            let tx_hash = TX_HASH.parse(matches);
-            Self { query, tx_hash }
+
+            Self { query }
         }
 
         fn def(app: App) -> App {
-            app.add_args::<Query>().arg(
-                TX_HASH
-                    .def()
-                    .about("The hash of the transaction being looked up."),
-            )
+            app.add_args::<Query>()
         }
     }
 
-    /// Custom transaction arguments
+    /// Verify header arguments
     #[derive(Clone, Debug)]
-    pub struct TxCustom {
-        /// Common tx arguments
-        pub tx: Tx,
-        /// Path to the tx WASM code file
-        pub code_path: PathBuf,
-        /// Path to the data file
-        pub data_path: Option<PathBuf>,
+    pub struct VerifyHeader {
+        /// Common query args
+        pub query: Query,
+        /// The height of the block whose header to verify
+        pub height: BlockHeight,
+        /// The epoch of the trusted validator set, or the last committed
+        /// epoch if `None`
+        pub epoch: Option<Epoch>,
     }
 
-    impl Args for TxCustom {
+    impl Args for VerifyHeader {
         fn parse(matches: &ArgMatches) -> Self {
-            let tx = Tx::parse(matches);
-            let code_path = CODE_PATH.parse(matches);
-            let data_path = DATA_PATH_OPT.parse(matches);
+            let query = Query::parse(matches);
+            let height = HEIGHT.parse(matches);
+            let epoch = EPOCH.parse(matches);
             Self {
-                tx,
-                code_path,
-                data_path,
+                query,
+                height,
+                epoch,
             }
         }
 
         fn def(app: App) -> App {
-            app.add_args::<Tx>()
-                .arg(
-                    CODE_PATH
-                        .def()
-                        .about("The path to the transaction's WASM code."),
-                )
-                .arg(DATA_PATH_OPT.def().about(
-                    "The data file at this path containing arbitrary bytes \
-                     will be passed to the transaction code when it's \
-                     executed.",
+            app.add_args::<Query>()
+                .arg(HEIGHT.def().about("The height of the block to verify."))
+                .arg(EPOCH.def().about(
+                    "The epoch of the validator set that is trusted to sign \
+                     the header (last committed, if not specified).",
                 ))
         }
     }
 
-    /// Transfer transaction arguments
+    /// Withdraw arguments
     #[derive(Clone, Debug)]
-    pub struct TxTransfer {
+    pub struct Withdraw {
         /// Common tx arguments
         pub tx: Tx,
-        /// Transfer source address
-        pub source: WalletAddress,
-        /// Transfer target address
-        pub target: WalletAddress,
-        /// Transferred token address
-        pub token: WalletAddress,
-        /// Transferred token amount
-        pub amount: token::Amount,
+        /// Validator address
+        pub validator: WalletAddress,
+        /// Source address for withdrawing from delegations. For withdrawing
+        /// from self-bonds, the validator is also the source
+        pub source: Option<WalletAddress>,
     }
 
-    impl Args for TxTransfer {
+    impl Args for Withdraw {
         fn parse(matches: &ArgMatches) -> Self {
             let tx = Tx::parse(matches);
-            let source = SOURCE.parse(matches);
-            let target = TARGET.parse(matches);
-            let token = TOKEN.parse(matches);
-            let amount = AMOUNT.parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            let source = SOURCE_OPT.parse(matches);
             Self {
                 tx,
+                validator,
                 source,
-                target,
-                token,
-                amount,
             }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Tx>()
-                .arg(SOURCE.def().about(
-                    "The source account address. The source's key is used to \
-                     produce the signature.",
+                .arg(VALIDATOR.def().about("Validator address."))
+                .arg(SOURCE_OPT.def().about(
+                    "Source address for withdrawing from delegations. For \
+                     withdrawing from self-bonds, the validator is also the \
+                     source.",
                 ))
-                .arg(TARGET.def().about("The target account address."))
-                .arg(TOKEN.def().about("The transfer token."))
-                .arg(AMOUNT.def().about("The amount to transfer in decimal."))
         }
     }
 
-    /// Transaction to initialize a new account
+    /// Redelegation arguments
     #[derive(Clone, Debug)]
-    pub struct TxInitAccount {
+    pub struct Redelegate {
         /// Common tx arguments
         pub tx: Tx,
-        /// Address of the source account
+        /// The validator from which the tokens are redelegated
+        pub src_validator: WalletAddress,
+        /// The validator to which the tokens are redelegated
+        pub dest_validator: WalletAddress,
+        /// Amount of tokens to redelegate
+        pub amount: token::Amount,
+        /// Source address of the delegation
         pub source: WalletAddress,
-        /// Path to the VP WASM code file for the new account
-        pub vp_code_path: Option<PathBuf>,
-        /// Public key for the new account
-        pub public_key: WalletPublicKey,
     }
 
-    impl Args for TxInitAccount {
+    impl Args for Redelegate {
         fn parse(matches: &ArgMatches) -> Self {
             let tx = Tx::parse(matches);
+            let src_validator = SRC_VALIDATOR.parse(matches);
+            let dest_validator = DEST_VALIDATOR.parse(matches);
+            let amount = AMOUNT.parse(matches);
             let source = SOURCE.parse(matches);
-            let vp_code_path = CODE_PATH_OPT.parse(matches);
-            let public_key = PUBLIC_KEY.parse(matches);
             Self {
                 tx,
+                src_validator,
+                dest_validator,
+                amount,
                 source,
-                vp_code_path,
-                public_key,
             }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Tx>()
-                .arg(SOURCE.def().about(
-                    "The source account's address that signs the transaction.",
+                .arg(SRC_VALIDATOR.def().about(
+                    "The address of the validator from which the tokens are \
+                     redelegated.",
                 ))
-                .arg(CODE_PATH_OPT.def().about(
-                    "The path to the validity predicate WASM code to be used \
-                     for the new account. Uses the default user VP if none \
-                     specified.",
+                .arg(DEST_VALIDATOR.def().about(
+                    "The address of the validator to which the tokens are \
+                     redelegated.",
                 ))
-                .arg(PUBLIC_KEY.def().about(
-                    "A public key to be used for the new account in \
-                     hexadecimal encoding.",
+                .arg(AMOUNT.def().about("Amount of tokens to redelegate."))
+                .arg(SOURCE.def().about(
+                    "Source address of the delegation. A self-bond cannot be \
+                     redelegated.",
                 ))
         }
     }
 
-    /// Transaction to initialize a new account
+    /// Consensus key change arguments
     #[derive(Clone, Debug)]
-    pub struct TxInitValidator {
+    pub struct ChangeConsensusKey {
+        /// Common tx arguments
         pub tx: Tx,
-        pub source: WalletAddress,
-        pub account_key: Option<WalletPublicKey>,
-        pub consensus_key: Option<WalletKeypair>,
-        pub rewards_account_key: Option<WalletPublicKey>,
-        pub protocol_key: Option<WalletPublicKey>,
-        pub validator_vp_code_path: Option<PathBuf>,
-        pub rewards_vp_code_path: Option<PathBuf>,
-        pub unsafe_dont_encrypt: bool,
+        /// Validator address
+        pub validator: WalletAddress,
+        /// The new consensus key
+        pub consensus_key: WalletPublicKey,
     }
 
-    impl Args for TxInitValidator {
+    impl Args for ChangeConsensusKey {
         fn parse(matches: &ArgMatches) -> Self {
             let tx = Tx::parse(matches);
-            let source = SOURCE.parse(matches);
-            let account_key = VALIDATOR_ACCOUNT_KEY.parse(matches);
-            let consensus_key = VALIDATOR_CONSENSUS_KEY.parse(matches);
-            let rewards_account_key = REWARDS_KEY.parse(matches);
-            let protocol_key = PROTOCOL_KEY.parse(matches);
-            let validator_vp_code_path = VALIDATOR_CODE_PATH.parse(matches);
-            let rewards_vp_code_path = REWARDS_CODE_PATH.parse(matches);
-            let unsafe_dont_encrypt = UNSAFE_DONT_ENCRYPT.parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            let consensus_key = CONSENSUS_KEY.parse(matches);
             Self {
                 tx,
-                source,
-                account_key,
+                validator,
                 consensus_key,
-                rewards_account_key,
-                protocol_key,
-                validator_vp_code_path,
-                rewards_vp_code_path,
-                unsafe_dont_encrypt,
             }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Tx>()
-                .arg(SOURCE.def().about(
-                    "The source account's address that signs the transaction.",
-                ))
-                .arg(VALIDATOR_ACCOUNT_KEY.def().about(
-                    "A public key for the validator account. A new one will \
-                     be generated if none given.",
-                ))
-                .arg(VALIDATOR_CONSENSUS_KEY.def().about(
-                    "A consensus key for the validator account. A new one \
-                     will be generated if none given.",
-                ))
-                .arg(REWARDS_KEY.def().about(
-                    "A public key for the staking reward account. A new one \
-                     will be generated if none given.",
-                ))
-                .arg(PROTOCOL_KEY.def().about(
-                    "A public key for signing protocol transactions. A new \
-                     one will be generated if none given.",
-                ))
-                .arg(VALIDATOR_CODE_PATH.def().about(
-                    "The path to the validity predicate WASM code to be used \
-                     for the validator account. Uses the default validator VP \
-                     if none specified.",
-                ))
-                .arg(REWARDS_CODE_PATH.def().about(
-                    "The path to the validity predicate WASM code to be used \
-                     for the staking reward account. Uses the default staking \
-                     reward VP if none specified.",
-                ))
-                .arg(UNSAFE_DONT_ENCRYPT.def().about(
-                    "UNSAFE: Do not encrypt the generated keypairs. Do not \
-                     use this for keys used in a live network.",
+                .arg(VALIDATOR.def().about("Validator address."))
+                .arg(CONSENSUS_KEY.def().about(
+                    "The new Ed25519 consensus key of the validator. It must \
+                     not be used by any validator.",
                 ))
         }
     }
 
-    /// Transaction to update a VP arguments
+    /// Commission rate change arguments
     #[derive(Clone, Debug)]
-    pub struct TxUpdateVp {
+    pub struct ChangeCommissionRate {
         /// Common tx arguments
         pub tx: Tx,
-        /// Path to the VP WASM code file
-        pub vp_code_path: PathBuf,
-        /// Address of the account whose VP is to be updated
-        pub addr: WalletAddress,
+        /// Validator address
+        pub validator: WalletAddress,
+        /// The new commission rate
+        pub rate: BasisPoints,
     }
 
-    impl Args for TxUpdateVp {
+    impl Args for ChangeCommissionRate {
         fn parse(matches: &ArgMatches) -> Self {
             let tx = Tx::parse(matches);
-            let vp_code_path = CODE_PATH.parse(matches);
-            let addr = ADDRESS.parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            let rate = RATE.parse(matches);
             Self {
                 tx,
-                vp_code_path,
-                addr,
+                validator,
+                rate,
             }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Tx>()
-                .arg(
-                    CODE_PATH.def().about(
-                        "The path to the new validity predicate WASM code.",
-                    ),
-                )
-                .arg(ADDRESS.def().about(
-                    "The account's address. It's key is used to produce the \
-                     signature.",
+                .arg(VALIDATOR.def().about("Validator address."))
+                .arg(RATE.def().about(
+                    "The new commission rate of the validator, in basis \
+                     points. It may only differ from the current rate by the \
+                     validator's maximum change.",
                 ))
         }
     }
 
-    /// Bond arguments
+    /// Validator metadata change arguments
     #[derive(Clone, Debug)]
-    pub struct Bond {
+    pub struct ChangeValidatorMetadata {
         /// Common tx arguments
         pub tx: Tx,
         /// Validator address
         pub validator: WalletAddress,
-        /// Amount of tokens to stake in a bond
-        pub amount: token::Amount,
-        /// Source address for delegations. For self-bonds, the validator is
-        /// also the source.
-        pub source: Option<WalletAddress>,
+        /// The display name of the validator
+        pub moniker: String,
+        /// The website of the validator
+        pub website: Option<String>,
+        /// The contact of the validator
+        pub contact: Option<String>,
     }
 
-    impl Args for Bond {
+    impl Args for ChangeValidatorMetadata {
         fn parse(matches: &ArgMatches) -> Self {
             let tx = Tx::parse(matches);
             let validator = VALIDATOR.parse(matches);
-            let amount = AMOUNT.parse(matches);
-            let source = SOURCE_OPT.parse(matches);
+            let moniker = MONIKER.parse(matches);
+            let website = WEBSITE.parse(matches);
+            let contact = CONTACT.parse(matches);
             Self {
                 tx,
                 validator,
-                amount,
-                source,
+                moniker,
+                website,
+                contact,
             }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Tx>()
                 .arg(VALIDATOR.def().about("Validator address."))
-                .arg(AMOUNT.def().about("Amount of tokens to stake in a bond."))
-                .arg(SOURCE_OPT.def().about(
-                    "Source address for delegations. For self-bonds, the \
-                     validator is also the source.",
+                .arg(MONIKER.def().about(
+                    "The display name of the validator, at most 64 bytes.",
+                ))
+                .arg(
+                    WEBSITE.def().about(
+                        "The website of the validator, at most 256 bytes.",
+                    ),
+                )
+                .arg(CONTACT.def().about(
+                    "The contact of the validator, e.g. an e-mail address, at \
+                     most 256 bytes.",
                 ))
         }
     }
 
-    /// Unbond arguments
+    /// Validator unjail arguments
     #[derive(Clone, Debug)]
-    pub struct Unbond {
+    pub struct UnjailValidator {
         /// Common tx arguments
         pub tx: Tx,
         /// Validator address
         pub validator: WalletAddress,
-        /// Amount of tokens to unbond from a bond
-        pub amount: token::Amount,
-        /// Source address for unbonding from delegations. For unbonding from
-        /// self-bonds, the validator is also the source
-        pub source: Option<WalletAddress>,
     }
 
-    impl Args for Unbond {
+    impl Args for UnjailValidator {
         fn parse(matches: &ArgMatches) -> Self {
             let tx = Tx::parse(matches);
             let validator = VALIDATOR.parse(matches);
-            let amount = AMOUNT.parse(matches);
-            let source = SOURCE_OPT.parse(matches);
-            Self {
-                tx,
-                validator,
-                amount,
-                source,
-            }
+            Self { tx, validator }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Tx>()
                 .arg(VALIDATOR.def().about("Validator address."))
-                .arg(
-                    AMOUNT
-                        .def()
-                        .about("Amount of tokens to unbond from a bond."),
-                )
-                .arg(SOURCE_OPT.def().about(
-                    "Source address for unbonding from delegations. For \
-                     unbonding from self-bonds, the validator is also the \
-                     source.",
-                ))
+        }
+    }
+
+    // Transaction to create a new nft
+    #[derive(Clone, Debug)]
+    pub struct NftCreate {
+        /// Common tx argumentsips
+        pub tx: Tx,
+        /// Path to the nft file description
+        pub nft_data: PathBuf,
+    }
+
+    impl Args for NftCreate {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let data_path = DATA_PATH.parse(matches);
+
+            Self {
+                tx,
+                nft_data: data_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(DATA_PATH.def().about("The path nft description file."))
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct InitProposal {
+    pub struct NftMint {
         /// Common tx arguments
         pub tx: Tx,
-        /// The proposal file path
-        pub proposal_data: PathBuf,
-        /// Flag if proposal should be run offline
-        pub offline: bool,
+        /// The nft address
+        pub nft_address: Address,
+        /// The nft token description
+        pub nft_data: PathBuf,
     }
 
-    impl Args for InitProposal {
+    impl Args for NftMint {
         fn parse(matches: &ArgMatches) -> Self {
             let tx = Tx::parse(matches);
-            let proposal_data = DATA_PATH.parse(matches);
-            let offline = PROPOSAL_OFFLINE.parse(matches);
+            let nft_address = NFT_ADDRESS.parse(matches);
+            let data_path = DATA_PATH.parse(matches);
 
             Self {
                 tx,
-                proposal_data,
-                offline,
+                nft_address,
+                nft_data: data_path,
             }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Tx>()
-                .arg(DATA_PATH.def().about(
-                    "The data path file (json) that describes the proposal.",
-                ))
+                .arg(NFT_ADDRESS.def().about("The nft address."))
                 .arg(
-                    PROPOSAL_OFFLINE
-                        .def()
-                        .about("Flag if the proposal vote should run offline."),
+                    DATA_PATH.def().about(
+                        "The data path file that describes the nft tokens.",
+                    ),
                 )
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct VoteProposal {
+    pub struct NftTransfer {
         /// Common tx arguments
         pub tx: Tx,
-        /// Proposal id
-        pub proposal_id: Option<u64>,
-        /// The vote
-        pub vote: ProposalVote,
-        /// Flag if proposal vote should be run offline
-        pub offline: bool,
-        /// The proposal file path
-        pub proposal_data: Option<PathBuf>,
+        /// The nft address
+        pub nft_address: Address,
+        /// The id of the token to transfer
+        pub token_id: u64,
+        /// The current owner of the token or one of its approved addresses
+        pub source: WalletAddress,
+        /// The new owner of the token
+        pub target: WalletAddress,
     }
 
-    impl Args for VoteProposal {
+    impl Args for NftTransfer {
         fn parse(matches: &ArgMatches) -> Self {
             let tx = Tx::parse(matches);
-            let proposal_id = PROPOSAL_ID_OPT.parse(matches);
-            let vote = PROPOSAL_VOTE.parse(matches);
-            let offline = PROPOSAL_OFFLINE.parse(matches);
-            let proposal_data = DATA_PATH_OPT.parse(matches);
+            let nft_address = NFT_ADDRESS.parse(matches);
+            let token_id = NFT_TOKEN_ID.parse(matches);
+            let source = SOURCE.parse(matches);
+            let target = TARGET.parse(matches);
 
             Self {
                 tx,
-                proposal_id,
-                vote,
-                offline,
-                proposal_data,
+                nft_address,
+                token_id,
+                source,
+                target,
             }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Tx>()
-                .arg(
-                    PROPOSAL_ID_OPT
-                        .def()
-                        .about("The proposal identifier.")
-                        .conflicts_with_all(&[
-                            PROPOSAL_OFFLINE.name,
-                            DATA_PATH_OPT.name,
-                        ]),
-                )
-                .arg(
-                    PROPOSAL_VOTE
-                        .def()
-                        .about("The vote for the proposal. Either yay or nay."),
-                )
-                .arg(
-                    PROPOSAL_OFFLINE
-                        .def()
-                        .about("Flag if the proposal vote should run offline.")
-                        .conflicts_with(PROPOSAL_ID.name),
-                )
-                .arg(
-                    DATA_PATH_OPT
-                        .def()
-                        .about(
-                            "The data path file (json) that describes the \
-                             proposal.",
-                        )
-                        .conflicts_with(PROPOSAL_ID.name),
-                )
+                .arg(NFT_ADDRESS.def().about("The nft address."))
+                .arg(NFT_TOKEN_ID.def().about("The id of the token."))
+                .arg(SOURCE.def().about(
+                    "The current owner of the token or one of its approved \
+                     addresses. This address will be used to sign the \
+                     transaction.",
+                ))
+                .arg(TARGET.def().about("The new owner of the token."))
         }
     }
 
+    /// Faucet withdrawal transaction arguments
     #[derive(Clone, Debug)]
-    pub struct QueryProposal {
-        /// Common query args
-        pub query: Query,
-        /// Proposal id
-        pub proposal_id: Option<u64>,
+    pub struct FaucetWithdraw {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// The faucet address
+        pub faucet: WalletAddress,
+        /// Withdrawal target address
+        pub target: WalletAddress,
+        /// Withdrawn token address
+        pub token: WalletAddress,
+        /// Withdrawn token amount
+        pub amount: token::Amount,
     }
 
-    impl Args for QueryProposal {
+    impl Args for FaucetWithdraw {
         fn parse(matches: &ArgMatches) -> Self {
-            let query = Query::parse(matches);
-            let proposal_id = PROPOSAL_ID_OPT.parse(matches);
-
-            Self { query, proposal_id }
+            let tx = Tx::parse(matches);
+            let faucet = FAUCET.parse(matches);
+            let target = TARGET.parse(matches);
+            let token = TOKEN.parse(matches);
+            let amount = AMOUNT.parse(matches);
+            Self {
+                tx,
+                faucet,
+                target,
+                token,
+                amount,
+            }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Tx>()
-                .arg(PROPOSAL_ID_OPT.def().about("The proposal identifier."))
+                .arg(FAUCET.def().about("The faucet account address."))
+                .arg(TARGET.def().about(
+                    "The target account address. The target's key is used to \
+                     produce the signature.",
+                ))
+                .arg(TOKEN.def().about("The withdrawn token."))
+                .arg(AMOUNT.def().about("The amount to withdraw in decimal."))
         }
     }
 
+    /// Query token balance(s)
     #[derive(Clone, Debug)]
-    pub struct QueryProposalResult {
+    pub struct QueryBalance {
         /// Common query args
         pub query: Query,
-        /// Proposal id
-        pub proposal_id: Option<u64>,
-        /// Flag if proposal result should be run on offline data
-        pub offline: bool,
-        /// The folder containing the proposal and votes
-        pub proposal_folder: Option<PathBuf>,
+        /// Address of an owner
+        pub owner: Option<WalletAddress>,
+        /// Address of a token
+        pub token: Option<WalletAddress>,
     }
 
-    impl Args for QueryProposalResult {
+    impl Args for QueryBalance {
         fn parse(matches: &ArgMatches) -> Self {
             let query = Query::parse(matches);
-            let proposal_id = PROPOSAL_ID_OPT.parse(matches);
-            let offline = PROPOSAL_OFFLINE.parse(matches);
-            let proposal_folder = DATA_PATH_OPT.parse(matches);
-
+            let owner = OWNER.parse(matches);
+            let token = TOKEN_OPT.parse(matches);
             Self {
                 query,
-                proposal_id,
-                offline,
-                proposal_folder,
+                owner,
+                token,
             }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Query>()
-                .arg(PROPOSAL_ID_OPT.def().about("The proposal identifier."))
                 .arg(
-                    PROPOSAL_OFFLINE
+                    OWNER
                         .def()
-                        .about(
-                            "Flag if the proposal result should run on \
-                             offline data.",
-                        )
-                        .conflicts_with(PROPOSAL_ID.name),
+                        .about("The account address whose balance to query."),
                 )
                 .arg(
-                    DATA_PATH_OPT
+                    TOKEN_OPT
                         .def()
-                        .about(
-                            "The path to the folder containing the proposal \
-                             json and votes",
-                        )
-                        .conflicts_with(PROPOSAL_ID.name),
+                        .about("The token's address whose balance to query."),
                 )
         }
     }
 
+    /// Query token allowance(s)
     #[derive(Clone, Debug)]
-    pub struct QueryProtocolParameters {
+    pub struct QueryAllowances {
         /// Common query args
         pub query: Query,
+        /// Address of an owner
+        pub owner: Option<WalletAddress>,
+        /// Address of a spender
+        pub spender: Option<WalletAddress>,
+        /// Address of a token
+        pub token: Option<WalletAddress>,
     }
 
-    impl Args for QueryProtocolParameters {
+    impl Args for QueryAllowances {
         fn parse(matches: &ArgMatches) -> Self {
             let query = Query::parse(matches);
-
-            Self { query }
+            let owner = OWNER.parse(matches);
+            let spender = SPENDER_OPT.parse(matches);
+            let token = TOKEN_OPT.parse(matches);
+            Self {
+                query,
+                owner,
+                spender,
+                token,
+            }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Query>()
+                .arg(OWNER.def().about(
+                    "The account address whose given allowances to query.",
+                ))
+                .arg(SPENDER_OPT.def().about(
+                    "The account address whose received allowances to query.",
+                ))
+                .arg(
+                    TOKEN_OPT.def().about(
+                        "The token's address whose allowances to query.",
+                    ),
+                )
         }
     }
 
-    /// Withdraw arguments
+    /// Query an account's keys and VP
     #[derive(Clone, Debug)]
-    pub struct Withdraw {
-        /// Common tx arguments
-        pub tx: Tx,
-        /// Validator address
-        pub validator: WalletAddress,
-        /// Source address for withdrawing from delegations. For withdrawing
-        /// from self-bonds, the validator is also the source
-        pub source: Option<WalletAddress>,
+    pub struct QueryAccount {
+        /// Common query args
+        pub query: Query,
+        /// Address of the account
+        pub addr: WalletAddress,
     }
 
-    impl Args for Withdraw {
+    impl Args for QueryAccount {
         fn parse(matches: &ArgMatches) -> Self {
-            let tx = Tx::parse(matches);
-            let validator = VALIDATOR.parse(matches);
-            let source = SOURCE_OPT.parse(matches);
-            Self {
-                tx,
-                validator,
-                source,
-            }
+            let query = Query::parse(matches);
+            let addr = ADDRESS.parse(matches);
+            Self { query, addr }
         }
 
         fn def(app: App) -> App {
-            app.add_args::<Tx>()
-                .arg(VALIDATOR.def().about("Validator address."))
-                .arg(SOURCE_OPT.def().about(
-                    "Source address for withdrawing from delegations. For \
-                     withdrawing from self-bonds, the validator is also the \
-                     source.",
-                ))
+            app.add_args::<Query>()
+                .arg(ADDRESS.def().about("The account's address."))
         }
     }
 
-    // Transaction to create a new nft
+    /// Query token metadata
     #[derive(Clone, Debug)]
-    pub struct NftCreate {
-        /// Common tx argumentsips
-        pub tx: Tx,
-        /// Path to the nft file description
-        pub nft_data: PathBuf,
+    pub struct QueryTokenMetadata {
+        /// Common query args
+        pub query: Query,
+        /// Address of a token
+        pub token: Option<WalletAddress>,
     }
 
-    impl Args for NftCreate {
+    impl Args for QueryTokenMetadata {
         fn parse(matches: &ArgMatches) -> Self {
-            let tx = Tx::parse(matches);
-            let data_path = DATA_PATH.parse(matches);
-
-            Self {
-                tx,
-                nft_data: data_path,
-            }
+            let query = Query::parse(matches);
+            let token = TOKEN_OPT.parse(matches);
+            Self { query, token }
         }
 
         fn def(app: App) -> App {
-            app.add_args::<Tx>()
-                .arg(DATA_PATH.def().about("The path nft description file."))
-        }
-    }
-
-    #[derive(Clone, Debug)]
-    pub struct NftMint {
-        /// Common tx arguments
-        pub tx: Tx,
-        /// The nft address
-        pub nft_address: Address,
-        /// The nft token description
-        pub nft_data: PathBuf,
+            app.add_args::<Query>().arg(TOKEN_OPT.def().about(
+                "The token's address whose metadata to query. Queries all \
+                 the registered tokens if none specified.",
+            ))
+        }
     }
 
-    impl Args for NftMint {
-        fn parse(matches: &ArgMatches) -> Self {
-            let tx = Tx::parse(matches);
-            let nft_address = NFT_ADDRESS.parse(matches);
-            let data_path = DATA_PATH.parse(matches);
+    /// Query an alias of the name service
+    #[derive(Clone, Debug)]
+    pub struct QueryAlias {
+        /// Common query args
+        pub query: Query,
+        /// The alias to resolve
+        pub alias: Option<String>,
+    }
 
-            Self {
-                tx,
-                nft_address,
-                nft_data: data_path,
-            }
+    impl Args for QueryAlias {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let alias = ALIAS_OPT.parse(matches);
+            Self { query, alias }
         }
 
         fn def(app: App) -> App {
-            app.add_args::<Tx>()
-                .arg(NFT_ADDRESS.def().about("The nft address."))
-                .arg(
-                    DATA_PATH.def().about(
-                        "The data path file that describes the nft tokens.",
-                    ),
-                )
+            app.add_args::<Query>().arg(ALIAS_OPT.def().about(
+                "The alias whose address to query. Queries all the \
+                 registered aliases if none specified.",
+            ))
         }
     }
 
-    /// Query token balance(s)
+    /// Query the tokens of a nft
     #[derive(Clone, Debug)]
-    pub struct QueryBalance {
+    pub struct QueryNft {
         /// Common query args
         pub query: Query,
-        /// Address of an owner
-        pub owner: Option<WalletAddress>,
-        /// Address of a token
-        pub token: Option<WalletAddress>,
+        /// The nft address
+        pub nft_address: Address,
+        /// The id of a token of the nft
+        pub token_id: Option<u64>,
     }
 
-    impl Args for QueryBalance {
+    impl Args for QueryNft {
         fn parse(matches: &ArgMatches) -> Self {
             let query = Query::parse(matches);
-            let owner = OWNER.parse(matches);
-            let token = TOKEN_OPT.parse(matches);
+            let nft_address = NFT_ADDRESS.parse(matches);
+            let token_id = NFT_TOKEN_ID_OPT.parse(matches);
             Self {
                 query,
-                owner,
-                token,
+                nft_address,
+                token_id,
             }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Query>()
-                .arg(
-                    OWNER
-                        .def()
-                        .about("The account address whose balance to query."),
-                )
-                .arg(
-                    TOKEN_OPT
-                        .def()
-                        .about("The token's address whose balance to query."),
-                )
+                .arg(NFT_ADDRESS.def().about("The nft address."))
+                .arg(NFT_TOKEN_ID_OPT.def().about(
+                    "The id of the token to query. Queries all the tokens of \
+                     the nft if none specified.",
+                ))
         }
     }
 
@@ -2272,6 +4367,125 @@ pub mod args {
         }
     }
 
+    /// Query indexed txs
+    #[derive(Clone, Debug)]
+    pub struct QueryTxs {
+        /// Common query args
+        pub query: Query,
+        /// Address involved in the txs
+        pub owner: Option<WalletAddress>,
+        /// Address of a token moved by the txs
+        pub token: Option<WalletAddress>,
+        /// Address of the signer of the txs
+        pub sender: Option<WalletAddress>,
+    }
+
+    impl Args for QueryTxs {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let owner = OWNER.parse(matches);
+            let token = TOKEN_OPT.parse(matches);
+            let sender = SENDER.parse(matches);
+            Self {
+                query,
+                owner,
+                token,
+                sender,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>()
+                .arg(
+                    OWNER
+                        .def()
+                        .about("The account address involved in the txs."),
+                )
+                .arg(TOKEN_OPT.def().about(
+                    "The token's address moved by the txs. When given \
+                     together with an owner, only the txs involving the \
+                     owner that moved this token are shown.",
+                ))
+                .arg(
+                    SENDER
+                        .def()
+                        .about(
+                            "The address that signed the txs and paid their \
+                             fees. When given, the results of the txs signed \
+                             by this address are shown.",
+                        )
+                        .conflicts_with_all(&[OWNER.name, TOKEN_OPT.name]),
+                )
+        }
+    }
+
+    /// Query the transfer history of an address
+    #[derive(Clone, Debug)]
+    pub struct QueryTransfers {
+        /// Common query args
+        pub query: Query,
+        /// Address whose transfers to query
+        pub address: WalletAddress,
+        /// The lowest height of the transfers (inclusive)
+        pub from_height: Option<BlockHeight>,
+        /// The highest height of the transfers (inclusive)
+        pub to_height: Option<BlockHeight>,
+    }
+
+    impl Args for QueryTransfers {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let address = ADDRESS.parse(matches);
+            let from_height = FROM_HEIGHT_OPT.parse(matches);
+            let to_height = TO_HEIGHT_OPT.parse(matches);
+            Self {
+                query,
+                address,
+                from_height,
+                to_height,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>()
+                .arg(
+                    ADDRESS
+                        .def()
+                        .about("The address whose transfers to query."),
+                )
+                .arg(FROM_HEIGHT_OPT.def().about(
+                    "Only show the transfers included at this block height \
+                     or above.",
+                ))
+                .arg(TO_HEIGHT_OPT.def().about(
+                    "Only show the transfers included at this block height \
+                     or below.",
+                ))
+        }
+    }
+
+    /// Query the results of the txs of a block
+    #[derive(Clone, Debug)]
+    pub struct QueryBlockResults {
+        /// Common query args
+        pub query: Query,
+        /// The height of the block
+        pub height: BlockHeight,
+    }
+
+    impl Args for QueryBlockResults {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let height = HEIGHT.parse(matches);
+            Self { query, height }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>()
+                .arg(HEIGHT.def().about("The height of the queried block."))
+        }
+    }
+
     /// Query PoS bond(s)
     #[derive(Clone, Debug)]
     pub struct QueryBonds {
@@ -2352,20 +4566,65 @@ pub mod args {
         pub query: Query,
         /// Address of a validator
         pub validator: Option<WalletAddress>,
+        /// The first epoch of the slashes
+        pub from_epoch: Option<Epoch>,
+        /// The last epoch of the slashes
+        pub to_epoch: Option<Epoch>,
     }
 
     impl Args for QuerySlashes {
         fn parse(matches: &ArgMatches) -> Self {
             let query = Query::parse(matches);
             let validator = VALIDATOR_OPT.parse(matches);
+            let from_epoch = FROM_EPOCH.parse(matches);
+            let to_epoch = TO_EPOCH.parse(matches);
+            Self {
+                query,
+                validator,
+                from_epoch,
+                to_epoch,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>()
+                .arg(
+                    VALIDATOR_OPT.def().about(
+                        "The validator's address whose slashes to query.",
+                    ),
+                )
+                .arg(FROM_EPOCH.def().about(
+                    "The first epoch of the slashable events of the slashes \
+                     to query.",
+                ))
+                .arg(TO_EPOCH.def().about(
+                    "The last epoch of the slashable events of the slashes \
+                     to query.",
+                ))
+        }
+    }
+
+    /// Query the keys of a PoS validator
+    #[derive(Clone, Debug)]
+    pub struct QueryValidatorKeys {
+        /// Common query args
+        pub query: Query,
+        /// Address of a validator
+        pub validator: WalletAddress,
+    }
+
+    impl Args for QueryValidatorKeys {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let validator = VALIDATOR.parse(matches);
             Self { query, validator }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Query>().arg(
-                VALIDATOR_OPT
+                VALIDATOR
                     .def()
-                    .about("The validator's address whose slashes to query."),
+                    .about("The validator's address whose keys to query."),
             )
         }
     }
@@ -2519,6 +4778,154 @@ pub mod args {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct LedgerRun {
+        /// Run the single-validator dev chain
+        pub dev: bool,
+    }
+
+    impl Args for LedgerRun {
+        fn parse(matches: &ArgMatches) -> Self {
+            let dev = DEV.parse(matches);
+            Self { dev }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(DEV.def().about(
+                "Run a local single-validator chain with sub-second blocks \
+                 and the \"dev\" genesis, in which the well-known accounts \
+                 are prefunded. The wallet of the dev chain is initialized \
+                 with the aliases and keys of the accounts. The node is the \
+                 validator of the chain, unless another `--mode` is given. \
+                 Requires a development build, with `ANOMA_DEV=true`.",
+            ))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerExportState {
+        pub height: Option<BlockHeight>,
+        pub state_path: PathBuf,
+        pub format: StateDumpFormat,
+    }
+
+    impl Args for LedgerExportState {
+        fn parse(matches: &ArgMatches) -> Self {
+            let height = HEIGHT_OPT.parse(matches);
+            let state_path = STATE_PATH.parse(matches);
+            let format = STATE_DUMP_FORMAT.parse(matches);
+            Self {
+                height,
+                state_path,
+                format,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(HEIGHT_OPT.def().about(
+                "The height of the exported block. Defaults to the last \
+                 committed block.",
+            ))
+            .arg(
+                STATE_PATH
+                    .def()
+                    .about("The path of the state dump file to write."),
+            )
+            .arg(STATE_DUMP_FORMAT.def().about(
+                "The encoding of the state dump file. Options are \n\t * \
+                 borsh (default)\n\t * json",
+            ))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerImportState {
+        pub state_path: PathBuf,
+    }
+
+    impl Args for LedgerImportState {
+        fn parse(matches: &ArgMatches) -> Self {
+            let state_path = STATE_PATH.parse(matches);
+            Self { state_path }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(STATE_PATH.def().about(
+                "The path of the state dump file to import, in either the \
+                 Borsh or the JSON format.",
+            ))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerMigrate {
+        pub dry_run: bool,
+    }
+
+    impl Args for LedgerMigrate {
+        fn parse(matches: &ArgMatches) -> Self {
+            let dry_run = DRY_RUN_MIGRATE.parse(matches);
+            Self { dry_run }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(DRY_RUN_MIGRATE.def().about(
+                "Only print the pending schema migrations, without applying \
+                 them.",
+            ))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerRollback {
+        pub height: Option<BlockHeight>,
+    }
+
+    impl Args for LedgerRollback {
+        fn parse(matches: &ArgMatches) -> Self {
+            let height = HEIGHT_OPT.parse(matches);
+            Self { height }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(HEIGHT_OPT.def().about(
+                "The height of the block to rollback to, whose data must not \
+                 have been pruned. Defaults to the block before the last \
+                 committed one.",
+            ))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerReplay {
+        pub from_height: BlockHeight,
+        pub to_height: BlockHeight,
+    }
+
+    impl Args for LedgerReplay {
+        fn parse(matches: &ArgMatches) -> Self {
+            let from_height = FROM_HEIGHT.parse(matches);
+            let to_height = TO_HEIGHT.parse(matches);
+            Self {
+                from_height,
+                to_height,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                FROM_HEIGHT
+                    .def()
+                    .about("The height of the first block to replay."),
+            )
+            .arg(
+                TO_HEIGHT
+                    .def()
+                    .about("The height of the last block to replay."),
+            )
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct GossipRun {
         pub addr: Option<Multiaddr>,
@@ -2626,6 +5033,11 @@ pub mod args {
         pub signing_key: Option<WalletKeypair>,
         /// Sign the tx with the keypair of the public key of the given address
         pub signer: Option<WalletAddress>,
+        /// Sign the tx without asking for a confirmation of its preview
+        pub yes: bool,
+        /// How long to wait for each of the events of the tx before it's
+        /// resubmitted
+        pub tx_timeout: Timeout,
     }
 
     impl Args for Tx {
@@ -2677,6 +5089,16 @@ pub mod args {
                     )
                     .conflicts_with(SIGNING_KEY_OPT.name),
             )
+            .arg(YES.def().about(
+                "Sign the transaction without asking for a confirmation of \
+                 its preview. This can also be set via `ANOMA_ASSUME_YES` \
+                 environment variable.",
+            ))
+            .arg(TX_TIMEOUT.def().about(
+                "How long to wait for the transaction to be accepted and \
+                 applied, e.g. \"60s\". A transaction that isn't accepted \
+                 in time is resubmitted with a bumped fee.",
+            ))
         }
 
         fn parse(matches: &ArgMatches) -> Self {
@@ -2691,6 +5113,8 @@ pub mod args {
 
             let signing_key = SIGNING_KEY_OPT.parse(matches);
             let signer = SIGNER.parse(matches);
+            let yes = YES.parse(matches);
+            let tx_timeout = TX_TIMEOUT.parse(matches);
             Self {
                 dry_run,
                 force,
@@ -2702,6 +5126,8 @@ pub mod args {
                 gas_limit,
                 signing_key,
                 signer,
+                yes,
+                tx_timeout,
             }
         }
     }
@@ -2998,11 +5424,10 @@ pub mod args {
                 "Toggle to disable guard against peers connecting from the \
                  same IP. This option shouldn't be used in mainnet.",
             ))
-            .arg(
-                DONT_ARCHIVE
-                    .def()
-                    .about("Do NOT create the release archive."),
-            )
+            .arg(DONT_ARCHIVE.def().about(
+                "Do NOT create the release archive and the validators' \
+                 bundles.",
+            ))
             .arg(ARCHIVE_DIR.def().about(
                 "Specify a directory into which to store the archive. Default \
                  is the current working directory.",
@@ -3010,6 +5435,32 @@ pub mod args {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct ValidateGenesis {
+        pub genesis_path: PathBuf,
+        pub chain_id: ChainId,
+    }
+
+    impl Args for ValidateGenesis {
+        fn parse(matches: &ArgMatches) -> Self {
+            let genesis_path = GENESIS_PATH.parse(matches);
+            let chain_id = CHAIN_ID.parse(matches);
+            Self {
+                genesis_path,
+                chain_id,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                GENESIS_PATH
+                    .def()
+                    .about("Path to the genesis configuration file."),
+            )
+            .arg(CHAIN_ID.def().about("The published chain ID."))
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct InitGenesisValidator {
         pub alias: String,
@@ -168,6 +168,18 @@ impl Context {
     pub fn read_wasm(&self, file_name: impl AsRef<Path>) -> Vec<u8> {
         wasm_loader::read_wasm(self.wasm_dir(), file_name)
     }
+
+    /// Try to parse a raw address or to look-up an alias of an address in the
+    /// wallet. Unlike [`Context::get`], this doesn't exit when the address is
+    /// not found, so that it can be looked-up elsewhere.
+    pub fn try_get_address(&self, raw: impl AsRef<str>) -> Option<Address> {
+        let raw = raw.as_ref();
+        // An address can be either raw (bech32m encoding)
+        Address::from_str(raw)
+            .ok()
+            // Or it can be an alias that may be found in the wallet
+            .or_else(|| self.wallet.find_address(raw).cloned())
+    }
 }
 
 /// Load global config from expected path in the `base_dir` or try to generate a
@@ -216,6 +228,11 @@ impl<T> FromContext<T> {
             phantom: PhantomData,
         }
     }
+
+    /// The raw value of the argument, before it's parsed or looked-up
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
 }
 
 impl<T> FromContext<T>
@@ -252,18 +269,10 @@ pub trait ArgFromMutContext: Sized {
 impl ArgFromContext for Address {
     fn arg_from_ctx(ctx: &Context, raw: impl AsRef<str>) -> Self {
         let raw = raw.as_ref();
-        // An address can be either raw (bech32m encoding)
-        FromStr::from_str(raw)
-            // Or it can be an alias that may be found in the wallet
-            .unwrap_or_else(|_| {
-                ctx.wallet
-                    .find_address(raw)
-                    .unwrap_or_else(|| {
-                        eprintln!("Unknown address {}", raw);
-                        safe_exit(1)
-                    })
-                    .clone()
-            })
+        ctx.try_get_address(raw).unwrap_or_else(|| {
+            eprintln!("Unknown address {}", raw);
+            safe_exit(1)
+        })
     }
 }
 
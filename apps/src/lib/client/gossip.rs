@@ -4,10 +4,7 @@ use std::io::Write;
 use anoma::proto::Signed;
 use anoma::types::intent::{Exchange, FungibleTokenIntent};
 use borsh::BorshSerialize;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_config::net::Address as TendermintAddress;
-#[cfg(feature = "ABCI")]
-use tendermint_config_abci::net::Address as TendermintAddress;
+use tendermint_compat::tendermint_config::net::Address as TendermintAddress;
 
 use super::signing;
 use crate::cli::{self, args, Context};
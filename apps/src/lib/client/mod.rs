@@ -1,8 +1,11 @@
 pub mod gossip;
+pub mod output;
+pub mod preview;
 pub mod rpc;
 pub mod signing;
 pub mod tendermint_rpc_types;
-mod tendermint_websocket_client;
+pub mod tendermint_websocket_client;
 mod tm_jsonrpc_client;
 pub mod tx;
+pub mod tx_manager;
 pub mod utils;
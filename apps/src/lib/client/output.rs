@@ -0,0 +1,877 @@
+//! The results of the client queries, which are printed either as text or as
+//! JSON for scripts, depending on the global `--output` argument.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use anoma::ledger::pos::types::{BasisPoints, SlashType};
+use anoma::ledger::pos::Slash;
+use anoma::types::address::Address;
+use anoma::types::key::common;
+use anoma::types::query::Balance;
+use anoma::types::storage::Epoch;
+use anoma::types::token;
+use anoma::types::transaction::TxResponse;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Unknown output mode {0}, expected \"text\" or \"json\"")]
+    UnknownMode(String),
+}
+
+/// The format in which the results of the client commands are printed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Human-readable text
+    Text,
+    /// Machine-readable JSON
+    Json,
+}
+
+impl FromStr for OutputMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(Error::UnknownMode(s.to_string())),
+        }
+    }
+}
+
+impl Display for OutputMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text => write!(f, "text"),
+            Self::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// Print the result of a command in the given output mode
+pub fn print<T: Serialize + Display>(mode: OutputMode, result: &T) {
+    match mode {
+        OutputMode::Text => print!("{}", result),
+        OutputMode::Json => print_json(result),
+    }
+}
+
+/// Print the result of a command as JSON
+pub fn print_json<T: Serialize>(result: &T) {
+    println!("{}", to_json(result));
+}
+
+/// Encode the result of a command as pretty-printed JSON, like it's printed
+/// with the `json` output mode. The results of the queries can be decoded
+/// back with [`serde_json`].
+pub fn to_json<T: Serialize>(result: &T) -> String {
+    serde_json::to_string_pretty(result).unwrap()
+}
+
+/// Serialize a value with its [`Display`] implementation. The token amounts
+/// are serialized as their decimal strings so they don't lose precision.
+fn serialize_display<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Display,
+    S: Serializer,
+{
+    serializer.collect_str(value)
+}
+
+/// Deserialize a value serialized with [`serialize_display`] with its
+/// [`FromStr`] implementation
+fn deserialize_from_str<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    T::from_str(&value).map_err(serde::de::Error::custom)
+}
+
+/// Serialize an optional value with its [`Display`] implementation
+fn serialize_opt_display<T, S>(
+    value: &Option<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    T: Display,
+    S: Serializer,
+{
+    match value {
+        Some(value) => serializer.collect_str(value),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// The result of the balance query
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BalanceQueryResult {
+    /// The queried token, if any
+    pub token: Option<Address>,
+    /// The queried owner, if any
+    pub owner: Option<Address>,
+    /// The balances found, ordered by token
+    pub balances: Vec<Balance>,
+}
+
+impl Display for BalanceQueryResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.token, &self.owner) {
+            (Some(token), Some(owner)) if self.balances.is_empty() => {
+                writeln!(
+                    f,
+                    "No balance of token {} found for {}",
+                    token.encode(),
+                    owner
+                )
+            }
+            (None, Some(owner)) if self.balances.is_empty() => {
+                writeln!(f, "No balance found for {}", owner)
+            }
+            (Some(token), None) if self.balances.is_empty() => {
+                writeln!(f, "No balances for token {}", token.encode())
+            }
+            (None, None) if self.balances.is_empty() => {
+                writeln!(f, "No balances found")
+            }
+            (_, Some(_)) => {
+                for balance in &self.balances {
+                    writeln!(f, "{} {}", balance.amount, balance.symbol)?;
+                }
+                Ok(())
+            }
+            (_, None) => {
+                let mut current_token = None;
+                for balance in &self.balances {
+                    if current_token != Some(&balance.token) {
+                        writeln!(f, "Token {}:", balance.symbol)?;
+                        current_token = Some(&balance.token);
+                    }
+                    writeln!(
+                        f,
+                        "  {} {}, owned by {}",
+                        balance.amount, balance.symbol, balance.owner
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// An alias registered in the name service
+#[derive(Debug, Serialize)]
+pub struct AliasEntry {
+    /// The registered alias
+    pub alias: String,
+    /// The address that the alias resolves to
+    pub address: Address,
+}
+
+/// The result of the alias query
+#[derive(Debug, Serialize)]
+pub struct AliasQueryResult {
+    /// The queried alias, if any
+    pub alias: Option<String>,
+    /// The aliases found, ordered by alias
+    pub aliases: Vec<AliasEntry>,
+}
+
+impl Display for AliasQueryResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.alias {
+            Some(alias) if self.aliases.is_empty() => {
+                writeln!(f, "The alias {} is not registered", alias)
+            }
+            None if self.aliases.is_empty() => {
+                writeln!(f, "No registered aliases found")
+            }
+            _ => {
+                for entry in &self.aliases {
+                    writeln!(f, "{}: {}", entry.alias, entry.address)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A bond of tokens to a validator, after the slashes of the validator
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BondEntry {
+    /// The owner of the bonded tokens
+    pub source: Address,
+    /// The epoch from which the bond is active
+    pub start_epoch: Epoch,
+    /// The bonded amount, after the slashes
+    #[serde(
+        serialize_with = "serialize_display",
+        deserialize_with = "deserialize_from_str"
+    )]
+    pub amount: token::Amount,
+    /// The amount slashed from the bond
+    #[serde(
+        serialize_with = "serialize_display",
+        deserialize_with = "deserialize_from_str"
+    )]
+    pub slashed: token::Amount,
+    /// Whether the bond is active in the last committed epoch
+    pub active: bool,
+}
+
+/// An unbond of tokens from a validator, after the slashes of the validator
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnbondEntry {
+    /// The owner of the unbonded tokens
+    pub source: Address,
+    /// The epoch from which the unbonded tokens were active
+    pub start_epoch: Epoch,
+    /// The epoch from which the unbonded tokens can be withdrawn
+    pub withdraw_epoch: Epoch,
+    /// The unbonded amount, after the slashes
+    #[serde(
+        serialize_with = "serialize_display",
+        deserialize_with = "deserialize_from_str"
+    )]
+    pub amount: token::Amount,
+    /// The amount slashed from the unbond
+    #[serde(
+        serialize_with = "serialize_display",
+        deserialize_with = "deserialize_from_str"
+    )]
+    pub slashed: token::Amount,
+    /// Whether the tokens can be withdrawn in the last committed epoch
+    pub withdrawable: bool,
+}
+
+/// The bonds and the unbonds found for a validator
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ValidatorBonds {
+    /// The bonds to the validator
+    pub bonds: Vec<BondEntry>,
+    /// The unbonds from the validator
+    pub unbonds: Vec<UnbondEntry>,
+    /// The total amount of the bonds
+    #[serde(
+        serialize_with = "serialize_display",
+        deserialize_with = "deserialize_from_str"
+    )]
+    pub bonds_total: token::Amount,
+    /// The total amount of the bonds active in the last committed epoch
+    #[serde(
+        serialize_with = "serialize_display",
+        deserialize_with = "deserialize_from_str"
+    )]
+    pub active_total: token::Amount,
+    /// The total amount of the unbonds
+    #[serde(
+        serialize_with = "serialize_display",
+        deserialize_with = "deserialize_from_str"
+    )]
+    pub unbonds_total: token::Amount,
+    /// The total amount of the unbonds withdrawable in the last committed
+    /// epoch
+    #[serde(
+        serialize_with = "serialize_display",
+        deserialize_with = "deserialize_from_str"
+    )]
+    pub withdrawable_total: token::Amount,
+    /// The earliest epoch from which an unbond that isn't withdrawable yet
+    /// can be withdrawn, if any
+    pub next_withdraw_epoch: Option<Epoch>,
+}
+
+impl ValidatorBonds {
+    /// Add a bond to the bonds and their totals
+    pub fn add_bond(&mut self, bond: BondEntry) {
+        self.bonds_total += bond.amount;
+        if bond.active {
+            self.active_total += bond.amount;
+        }
+        self.bonds.push(bond);
+    }
+
+    /// Add an unbond to the unbonds and their totals
+    pub fn add_unbond(&mut self, unbond: UnbondEntry) {
+        self.unbonds_total += unbond.amount;
+        if unbond.withdrawable {
+            self.withdrawable_total += unbond.amount;
+        } else {
+            self.next_withdraw_epoch = Some(
+                self.next_withdraw_epoch
+                    .map_or(unbond.withdraw_epoch, |epoch| {
+                        epoch.min(unbond.withdraw_epoch)
+                    }),
+            );
+        }
+        self.unbonds.push(unbond);
+    }
+}
+
+/// The result of the bonds query
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BondQueryResult {
+    /// The last committed epoch, in which the bonds are active or
+    /// withdrawable
+    pub epoch: Epoch,
+    /// The bonds and the unbonds found, by their validator
+    pub validators: BTreeMap<Address, ValidatorBonds>,
+}
+
+impl BondQueryResult {
+    /// The total amount of the bonds to all the validators
+    pub fn bonds_total(&self) -> token::Amount {
+        self.sum(|bonds| bonds.bonds_total)
+    }
+
+    /// The total amount of the bonds to all the validators that are active
+    /// in the last committed epoch
+    pub fn active_total(&self) -> token::Amount {
+        self.sum(|bonds| bonds.active_total)
+    }
+
+    /// The total amount of the unbonds from all the validators
+    pub fn unbonds_total(&self) -> token::Amount {
+        self.sum(|bonds| bonds.unbonds_total)
+    }
+
+    /// The total amount of the unbonds from all the validators that are
+    /// withdrawable in the last committed epoch
+    pub fn withdrawable_total(&self) -> token::Amount {
+        self.sum(|bonds| bonds.withdrawable_total)
+    }
+
+    fn sum(
+        &self,
+        amount: impl Fn(&ValidatorBonds) -> token::Amount,
+    ) -> token::Amount {
+        self.validators
+            .values()
+            .fold(token::Amount::default(), |sum, bonds| sum + amount(bonds))
+    }
+}
+
+impl Display for BondQueryResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.validators.is_empty() {
+            return writeln!(f, "No bonds found");
+        }
+        for (validator, bonds) in &self.validators {
+            writeln!(f, "Validator {}:", validator.encode())?;
+            for bond in &bonds.bonds {
+                let bond_type = if &bond.source == validator {
+                    "Self-bond".to_string()
+                } else {
+                    format!("Delegation from {}", bond.source.encode())
+                };
+                writeln!(
+                    f,
+                    "  {} active from epoch {}: {}{}",
+                    bond_type,
+                    bond.start_epoch,
+                    bond.amount,
+                    slashed_note(bond.slashed)
+                )?;
+            }
+            for unbond in &bonds.unbonds {
+                let bond_type = if &unbond.source == validator {
+                    "Unbonded self-bond".to_string()
+                } else {
+                    format!(
+                        "Unbonded delegation from {}",
+                        unbond.source.encode()
+                    )
+                };
+                writeln!(
+                    f,
+                    "  {} withdrawable from epoch {}: {}{}",
+                    bond_type,
+                    unbond.withdraw_epoch,
+                    unbond.amount,
+                    slashed_note(unbond.slashed)
+                )?;
+            }
+            writeln!(
+                f,
+                "  Bonds total: {} ({} active)",
+                bonds.bonds_total, bonds.active_total
+            )?;
+            if !bonds.unbonds.is_empty() {
+                writeln!(
+                    f,
+                    "  Unbonds total: {} ({} withdrawable)",
+                    bonds.unbonds_total, bonds.withdrawable_total
+                )?;
+            }
+            if let Some(epoch) = bonds.next_withdraw_epoch {
+                writeln!(f, "  Next unbond withdrawable from epoch {}", epoch)?;
+            }
+        }
+        writeln!(
+            f,
+            "Bonds total in epoch {}: {} ({} active)",
+            self.epoch,
+            self.bonds_total(),
+            self.active_total()
+        )?;
+        writeln!(
+            f,
+            "Unbonds total: {} ({} withdrawable)",
+            self.unbonds_total(),
+            self.withdrawable_total()
+        )
+    }
+}
+
+/// The note of the amount slashed from a bond or an unbond, if any
+fn slashed_note(slashed: token::Amount) -> String {
+    if slashed == token::Amount::default() {
+        String::new()
+    } else {
+        format!(" (slashed {})", slashed)
+    }
+}
+
+/// The pending rewards of a bond to a validator
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RewardsEntry {
+    /// The owner of the bond
+    pub source: Address,
+    /// The validator the tokens are bonded to
+    pub validator: Address,
+    /// The epoch before which the rewards have been claimed, if any
+    pub claimed_epoch: Option<Epoch>,
+    /// The rewards that can be claimed
+    #[serde(
+        serialize_with = "serialize_display",
+        deserialize_with = "deserialize_from_str"
+    )]
+    pub pending: token::Amount,
+}
+
+/// The result of the rewards query
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RewardsQueryResult {
+    /// The last committed epoch, before which the rewards are pending
+    pub epoch: Epoch,
+    /// The rewards of the bonds found
+    pub rewards: Vec<RewardsEntry>,
+    /// The total amount of the pending rewards
+    #[serde(
+        serialize_with = "serialize_display",
+        deserialize_with = "deserialize_from_str"
+    )]
+    pub pending_total: token::Amount,
+}
+
+impl Display for RewardsQueryResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.rewards.is_empty() {
+            return writeln!(f, "No bonds found");
+        }
+        for entry in &self.rewards {
+            let claimed = entry
+                .claimed_epoch
+                .map(|epoch| format!(", claimed before epoch {}", epoch))
+                .unwrap_or_default();
+            if entry.source == entry.validator {
+                writeln!(
+                    f,
+                    "Self-bond of {}: {} pending{}",
+                    entry.validator.encode(),
+                    entry.pending,
+                    claimed
+                )?;
+            } else {
+                writeln!(
+                    f,
+                    "Delegation from {} to {}: {} pending{}",
+                    entry.source.encode(),
+                    entry.validator.encode(),
+                    entry.pending,
+                    claimed
+                )?;
+            }
+        }
+        writeln!(
+            f,
+            "Pending rewards total in epoch {}: {}",
+            self.epoch, self.pending_total
+        )
+    }
+}
+
+/// A slash of a validator
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SlashEntry {
+    /// The slashed validator
+    pub validator: Address,
+    /// The epoch at which the slashable event occurred
+    pub epoch: Epoch,
+    /// The block height at which the slashable event occurred
+    pub block_height: u64,
+    /// The portion of the staked tokens that is slashed
+    #[serde(
+        serialize_with = "serialize_display",
+        deserialize_with = "deserialize_from_str"
+    )]
+    pub rate: BasisPoints,
+    /// The type of the slashable event
+    #[serde(
+        serialize_with = "serialize_display",
+        deserialize_with = "deserialize_from_str"
+    )]
+    pub r#type: SlashType,
+}
+
+impl SlashEntry {
+    /// Create an entry of a slash of the given validator
+    pub fn new(validator: Address, slash: Slash) -> Self {
+        Self {
+            validator,
+            epoch: slash.epoch.into(),
+            block_height: slash.block_height,
+            rate: slash.rate,
+            r#type: slash.r#type,
+        }
+    }
+}
+
+/// The result of the slashes query
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SlashQueryResult {
+    /// The queried validator, if any
+    pub validator: Option<Address>,
+    /// The slashes found
+    pub slashes: Vec<SlashEntry>,
+}
+
+impl Display for SlashQueryResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.validator {
+            Some(validator) if self.slashes.is_empty() => {
+                writeln!(f, "No slashes found for {}", validator.encode())
+            }
+            None if self.slashes.is_empty() => writeln!(f, "No slashes found"),
+            Some(_) => {
+                for slash in &self.slashes {
+                    writeln!(
+                        f,
+                        "Slash epoch {}, rate {}, type {}",
+                        slash.epoch, slash.rate, slash.r#type
+                    )?;
+                }
+                Ok(())
+            }
+            None => {
+                for slash in &self.slashes {
+                    writeln!(
+                        f,
+                        "Slash epoch {}, block height {}, rate {}, type {}, \
+                         validator {}",
+                        slash.epoch,
+                        slash.block_height,
+                        slash.rate,
+                        slash.r#type,
+                        slash.validator,
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The consensus key of a validator in an epoch
+#[derive(Debug, Serialize)]
+pub struct ConsensusKeyEntry {
+    /// The epoch from which the key is used
+    pub epoch: Epoch,
+    /// The consensus key
+    #[serde(serialize_with = "serialize_display")]
+    pub consensus_key: common::PublicKey,
+    /// The Tendermint address of the consensus key
+    pub tm_address: String,
+}
+
+/// The result of the validator keys query
+#[derive(Debug, Serialize)]
+pub struct ValidatorKeysQueryResult {
+    /// The queried validator
+    pub validator: Address,
+    /// The consensus keys from the current epoch until the pipeline epoch,
+    /// when they change in between
+    pub consensus_keys: Vec<ConsensusKeyEntry>,
+    /// The key with which the validator signs the protocol txs
+    #[serde(serialize_with = "serialize_opt_display")]
+    pub protocol_key: Option<common::PublicKey>,
+    /// The key of the validator account
+    #[serde(serialize_with = "serialize_opt_display")]
+    pub account_key: Option<common::PublicKey>,
+    /// The address of the staking reward account
+    pub staking_reward_address: Option<Address>,
+    /// The key of the staking reward account
+    #[serde(serialize_with = "serialize_opt_display")]
+    pub staking_reward_key: Option<common::PublicKey>,
+}
+
+impl Display for ValidatorKeysQueryResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let or_none = |key: &Option<common::PublicKey>| {
+            key.as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_else(|| "none".to_string())
+        };
+        writeln!(f, "Keys of the validator {}:", self.validator.encode())?;
+        for entry in &self.consensus_keys {
+            writeln!(
+                f,
+                "  Consensus key from epoch {}: {} (Tendermint address {})",
+                entry.epoch, entry.consensus_key, entry.tm_address
+            )?;
+        }
+        writeln!(f, "  Protocol key: {}", or_none(&self.protocol_key))?;
+        writeln!(f, "  Account key: {}", or_none(&self.account_key))?;
+        match &self.staking_reward_address {
+            Some(address) => writeln!(
+                f,
+                "  Staking reward address: {}, key: {}",
+                address.encode(),
+                or_none(&self.staking_reward_key)
+            ),
+            None => writeln!(f, "  Staking reward address: none"),
+        }
+    }
+}
+
+/// The status of a tx found by the tx result query
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxStatus {
+    /// The tx was applied
+    Applied,
+    /// The wrapper of the tx was accepted, but its payload is not applied yet
+    Accepted,
+}
+
+/// The result of the tx result query
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TxQueryResult {
+    /// Whether the tx was applied or only accepted
+    pub status: TxStatus,
+    /// The event of the tx
+    pub result: TxResponse,
+}
+
+impl Display for TxQueryResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self.status {
+            TxStatus::Applied => "applied",
+            TxStatus::Accepted => "accepted",
+        };
+        writeln!(
+            f,
+            "Transaction {} with result: {}",
+            status,
+            serde_json::to_string_pretty(&self.result).unwrap()
+        )?;
+        match self.result.error_message() {
+            Some(message) => writeln!(f, "{}", message),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::address::testing::established_address_1;
+    use anoma::types::key::{testing, RefTo};
+    use anoma::types::transaction::ResultCode;
+
+    use super::*;
+
+    #[test]
+    fn test_output_mode_from_str() {
+        assert_eq!("text".parse::<OutputMode>().unwrap(), OutputMode::Text);
+        assert_eq!("json".parse::<OutputMode>().unwrap(), OutputMode::Json);
+        assert!("yaml".parse::<OutputMode>().is_err());
+    }
+
+    #[test]
+    fn test_slash_query_result_json() {
+        let validator = established_address_1();
+        let result = SlashQueryResult {
+            validator: Some(validator.clone()),
+            slashes: vec![SlashEntry {
+                validator: validator.clone(),
+                epoch: Epoch(2),
+                block_height: 10,
+                rate: BasisPoints::new(500),
+                r#type: SlashType::DuplicateVote,
+            }],
+        };
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["validator"], validator.encode());
+        assert_eq!(json["slashes"][0]["epoch"], 2);
+        assert_eq!(json["slashes"][0]["block_height"], 10);
+        assert_eq!(json["slashes"][0]["type"], "Duplicate vote");
+    }
+
+    /// Test that the totals of the bonds and the unbonds are summed per
+    /// validator and over all the validators, with the earliest epoch of the
+    /// unbonds that aren't withdrawable yet.
+    #[test]
+    fn test_bond_query_result_totals() {
+        let source = established_address_1();
+        let unbond = |withdraw_epoch: u64, withdrawable: bool| UnbondEntry {
+            source: source.clone(),
+            start_epoch: Epoch(1),
+            withdraw_epoch: Epoch(withdraw_epoch),
+            amount: token::Amount::from(10),
+            slashed: token::Amount::default(),
+            withdrawable,
+        };
+        let mut bonds = ValidatorBonds::default();
+        bonds.add_bond(BondEntry {
+            source: source.clone(),
+            start_epoch: Epoch(6),
+            amount: token::Amount::from(100),
+            slashed: token::Amount::default(),
+            active: false,
+        });
+        bonds.add_unbond(unbond(3, true));
+        bonds.add_unbond(unbond(9, false));
+        bonds.add_unbond(unbond(7, false));
+        assert_eq!(bonds.bonds_total, token::Amount::from(100));
+        assert_eq!(bonds.active_total, token::Amount::default());
+        assert_eq!(bonds.unbonds_total, token::Amount::from(30));
+        assert_eq!(bonds.withdrawable_total, token::Amount::from(10));
+        assert_eq!(bonds.next_withdraw_epoch, Some(Epoch(7)));
+
+        let mut other_bonds = ValidatorBonds::default();
+        other_bonds.add_unbond(unbond(4, true));
+        let validator = anoma::types::address::testing::established_address_2();
+        let result = BondQueryResult {
+            epoch: Epoch(5),
+            validators: [(source.clone(), other_bonds), (validator, bonds)]
+                .into_iter()
+                .collect(),
+        };
+        assert_eq!(result.bonds_total(), token::Amount::from(100));
+        assert_eq!(result.unbonds_total(), token::Amount::from(40));
+        assert_eq!(result.withdrawable_total(), token::Amount::from(20));
+    }
+
+    /// Test that the results of the queries are decoded from their JSON with
+    /// the same values.
+    #[test]
+    fn test_query_results_json_round_trip() {
+        let source = established_address_1();
+        let validator = anoma::types::address::testing::established_address_2();
+        let mut bonds = ValidatorBonds::default();
+        bonds.add_bond(BondEntry {
+            source: source.clone(),
+            start_epoch: Epoch(2),
+            amount: token::Amount::from(95_000_000),
+            slashed: token::Amount::from(5_000_000),
+            active: true,
+        });
+        let result = BondQueryResult {
+            epoch: Epoch(5),
+            validators: [(validator.clone(), bonds)].into_iter().collect(),
+        };
+        let decoded: BondQueryResult =
+            serde_json::from_str(&to_json(&result)).unwrap();
+        assert_eq!(decoded.epoch, result.epoch);
+        let decoded_bonds = &decoded.validators[&validator];
+        let bonds = &result.validators[&validator];
+        assert_eq!(decoded_bonds.bonds[0].source, source);
+        assert_eq!(decoded_bonds.bonds[0].amount, bonds.bonds[0].amount);
+        assert_eq!(decoded_bonds.bonds[0].slashed, bonds.bonds[0].slashed);
+        assert_eq!(decoded_bonds.bonds_total, bonds.bonds_total);
+        assert_eq!(decoded.bonds_total(), result.bonds_total());
+
+        let result = SlashQueryResult {
+            validator: None,
+            slashes: vec![SlashEntry {
+                validator: validator.clone(),
+                epoch: Epoch(2),
+                block_height: 10,
+                rate: BasisPoints::new(500),
+                r#type: SlashType::LightClientAttack,
+            }],
+        };
+        let decoded: SlashQueryResult =
+            serde_json::from_str(&to_json(&result)).unwrap();
+        assert_eq!(decoded.slashes[0].rate, BasisPoints::new(500));
+        assert!(matches!(
+            decoded.slashes[0].r#type,
+            SlashType::LightClientAttack
+        ));
+
+        let result = TxQueryResult {
+            status: TxStatus::Applied,
+            result: TxResponse {
+                info: String::new(),
+                log: String::new(),
+                height: "10".to_string(),
+                hash: "ABCD".to_string(),
+                code: ResultCode::VpRejected,
+                gas_used: "100".to_string(),
+                initialized_accounts: vec![],
+                rejected_vps: vec![validator.clone()],
+                rejected_vp_keys: [(
+                    validator.clone(),
+                    vec!["a/b".to_string()],
+                )]
+                .into_iter()
+                .collect(),
+            },
+        };
+        let decoded: TxQueryResult =
+            serde_json::from_str(&to_json(&result)).unwrap();
+        assert!(matches!(decoded.status, TxStatus::Applied));
+        assert_eq!(decoded.result.code, ResultCode::VpRejected);
+        assert_eq!(
+            decoded.result.rejected_vp_keys,
+            result.result.rejected_vp_keys
+        );
+    }
+
+    #[test]
+    fn test_validator_keys_query_result_json() {
+        let validator = established_address_1();
+        let consensus_key = testing::keypair_1().ref_to();
+        let result = ValidatorKeysQueryResult {
+            validator: validator.clone(),
+            consensus_keys: vec![ConsensusKeyEntry {
+                epoch: Epoch(3),
+                consensus_key: consensus_key.clone(),
+                tm_address: "ABCD".to_string(),
+            }],
+            protocol_key: Some(testing::keypair_2().ref_to()),
+            account_key: None,
+            staking_reward_address: None,
+            staking_reward_key: None,
+        };
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["validator"], validator.encode());
+        assert_eq!(json["consensus_keys"][0]["epoch"], 3);
+        assert_eq!(
+            json["consensus_keys"][0]["consensus_key"],
+            consensus_key.to_string()
+        );
+        assert_eq!(
+            json["protocol_key"],
+            testing::keypair_2().ref_to().to_string()
+        );
+        assert!(json["account_key"].is_null());
+    }
+}
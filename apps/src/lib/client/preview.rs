@@ -0,0 +1,324 @@
+//! A human-readable preview of a tx, which is shown before the tx is signed
+//! so that it can be reviewed and confirmed.
+
+use std::env;
+use std::fmt::{self, Display};
+use std::io::{self, Write};
+
+use anoma::ledger::name_service;
+use anoma::proto::Tx;
+use anoma::types::address::{self, Address};
+use anoma::types::token;
+use anoma::types::transaction::{pos, RegisterAlias};
+use borsh::BorshDeserialize;
+
+use crate::cli::{args, Context};
+use crate::wasm_loader::{self, Checksums};
+
+/// Env. var to sign the txs without asking for a confirmation, which is the
+/// same as passing `--yes`
+pub const ENV_VAR_ASSUME_YES: &str = "ANOMA_ASSUME_YES";
+
+const TX_TRANSFER_WASM: &str = "tx_transfer.wasm";
+const TX_BOND_WASM: &str = "tx_bond.wasm";
+const TX_BATCH_BOND_WASM: &str = "tx_batch_bond.wasm";
+const TX_UNBOND_WASM: &str = "tx_unbond.wasm";
+const TX_WITHDRAW_WASM: &str = "tx_withdraw.wasm";
+const TX_REDELEGATE_WASM: &str = "tx_redelegate.wasm";
+const TX_CHANGE_CONSENSUS_KEY_WASM: &str = "tx_change_consensus_key.wasm";
+const TX_CLAIM_REWARDS_WASM: &str = "tx_claim_rewards.wasm";
+const TX_CHANGE_COMMISSION_RATE_WASM: &str = "tx_change_commission_rate.wasm";
+const TX_CHANGE_VALIDATOR_METADATA_WASM: &str =
+    "tx_change_validator_metadata.wasm";
+const TX_UNJAIL_VALIDATOR_WASM: &str = "tx_unjail_validator.wasm";
+const TX_REGISTER_ALIAS_WASM: &str = "tx_register_alias.wasm";
+
+/// A preview of a tx with the fields decoded from its data, if the tx code is
+/// one of the known pre-built txs
+#[derive(Debug)]
+pub struct TxPreview {
+    /// The file name of the known WASM code of the tx, if any
+    pub tx_name: Option<String>,
+    /// The hash of the code of the tx
+    pub code_hash: String,
+    /// The decoded fields of the tx data as pairs of a label and a value
+    pub fields: Vec<(&'static str, String)>,
+    /// The fee paid for the tx
+    pub fee: String,
+    /// The maximum amount of gas the tx may use
+    pub gas_limit: u64,
+}
+
+impl TxPreview {
+    /// Decode the preview of the given tx with the fee and gas of the tx args
+    pub fn new(ctx: &Context, tx: &Tx, args: &args::Tx) -> Self {
+        let code_hash = wasm_loader::code_hash(&tx.code);
+        let tx_name = Checksums::try_read_checksums(ctx.wasm_dir())
+            .ok()
+            .and_then(|checksums| {
+                checksums.find_name(&tx.code).map(ToString::to_string)
+            });
+        let fields = match (tx_name.as_deref(), tx.data.as_ref()) {
+            (Some(name), Some(data)) => decode_fields(name, data),
+            _ => vec![],
+        };
+        let fee_token = ctx.get(&args.fee_token);
+        Self {
+            tx_name,
+            code_hash,
+            fields,
+            fee: format!("{} {}", args.fee_amount, token_symbol(&fee_token)),
+            gas_limit: args.gas_limit.clone().into(),
+        }
+    }
+}
+
+impl Display for TxPreview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Transaction preview:")?;
+        match &self.tx_name {
+            Some(name) => writeln!(f, "  Code: {} ({})", name, self.code_hash)?,
+            None => {
+                writeln!(f, "  Code: {}", self.code_hash)?;
+                writeln!(
+                    f,
+                    "  ⚠ WARNING: The code hash doesn't match any known \
+                     transaction. Only sign it if you trust its source."
+                )?;
+            }
+        }
+        for (label, value) in &self.fields {
+            writeln!(f, "  {}: {}", label, value)?;
+        }
+        writeln!(f, "  Fee: {}", self.fee)?;
+        writeln!(f, "  Gas limit: {}", self.gas_limit)?;
+        writeln!(f, "  Expiration: none")
+    }
+}
+
+/// Print the preview of the tx and, unless `--yes` is given or the tx is only
+/// a dry run, ask for a confirmation. Returns `true` if the tx may be signed.
+pub fn confirm_tx(ctx: &Context, tx: &Tx, args: &args::Tx) -> bool {
+    print!("{}", TxPreview::new(ctx, tx, args));
+    let assume_yes = match env::var(ENV_VAR_ASSUME_YES) {
+        Ok(val) => parse_assume_yes(&val),
+        _ => false,
+    };
+    if args.yes || assume_yes || args.dry_run {
+        return true;
+    }
+    print!("Sign and submit this transaction? [y/N]: ");
+    io::stdout().flush().unwrap();
+    let mut answer = String::new();
+    match io::stdin().read_line(&mut answer) {
+        Ok(_) => {
+            matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+        }
+        Err(_) => false,
+    }
+}
+
+/// Decode the data of a known tx into the fields of its preview
+fn decode_fields(tx_name: &str, data: &[u8]) -> Vec<(&'static str, String)> {
+    match tx_name {
+        TX_TRANSFER_WASM => match token::Transfer::try_from_slice(data) {
+            Ok(transfer) => vec![
+                ("Source", transfer.source.encode()),
+                ("Target", transfer.target.encode()),
+                ("Token", token_symbol(&transfer.token)),
+                (
+                    "Amount",
+                    match token::Denomination::native(&transfer.token) {
+                        Some(denomination) => {
+                            denomination.format(transfer.amount)
+                        }
+                        None => transfer.amount.to_string(),
+                    },
+                ),
+            ],
+            Err(_) => vec![],
+        },
+        TX_BOND_WASM | TX_UNBOND_WASM => {
+            match pos::Bond::try_from_slice(data) {
+                Ok(bond) => vec![
+                    (
+                        "Source",
+                        bond.source
+                            .as_ref()
+                            .unwrap_or(&bond.validator)
+                            .encode(),
+                    ),
+                    ("Validator", bond.validator.encode()),
+                    ("Amount", format!("{} XAN", bond.amount)),
+                ],
+                Err(_) => vec![],
+            }
+        }
+        TX_BATCH_BOND_WASM => match pos::BatchBond::try_from_slice(data) {
+            Ok(batch) => {
+                let mut fields = vec![("Source", batch.source.encode())];
+                for (validator, amount) in &batch.bonds {
+                    fields.push(("Validator", validator.encode()));
+                    fields.push(("Amount", format!("{} XAN", amount)));
+                }
+                fields
+            }
+            Err(_) => vec![],
+        },
+        TX_WITHDRAW_WASM => match pos::Withdraw::try_from_slice(data) {
+            Ok(withdraw) => vec![
+                (
+                    "Source",
+                    withdraw
+                        .source
+                        .as_ref()
+                        .unwrap_or(&withdraw.validator)
+                        .encode(),
+                ),
+                ("Validator", withdraw.validator.encode()),
+            ],
+            Err(_) => vec![],
+        },
+        TX_REDELEGATE_WASM => match pos::Redelegation::try_from_slice(data) {
+            Ok(redelegation) => vec![
+                ("Source", redelegation.source.encode()),
+                ("From validator", redelegation.src_validator.encode()),
+                ("To validator", redelegation.dest_validator.encode()),
+                ("Amount", redelegation.amount.to_string()),
+            ],
+            Err(_) => vec![],
+        },
+        TX_CHANGE_CONSENSUS_KEY_WASM => {
+            match pos::ConsensusKeyChange::try_from_slice(data) {
+                Ok(change) => vec![
+                    ("Validator", change.validator.encode()),
+                    ("Consensus key", change.consensus_key.to_string()),
+                ],
+                Err(_) => vec![],
+            }
+        }
+        TX_CLAIM_REWARDS_WASM => {
+            match pos::ClaimRewards::try_from_slice(data) {
+                Ok(claim) => vec![
+                    (
+                        "Source",
+                        claim
+                            .source
+                            .as_ref()
+                            .unwrap_or(&claim.validator)
+                            .encode(),
+                    ),
+                    ("Validator", claim.validator.encode()),
+                ],
+                Err(_) => vec![],
+            }
+        }
+        TX_CHANGE_COMMISSION_RATE_WASM => {
+            match pos::CommissionRateChange::try_from_slice(data) {
+                Ok(change) => vec![
+                    ("Validator", change.validator.encode()),
+                    ("Commission rate", format!("{} bps", change.rate)),
+                ],
+                Err(_) => vec![],
+            }
+        }
+        TX_CHANGE_VALIDATOR_METADATA_WASM => {
+            match pos::ValidatorMetadataChange::try_from_slice(data) {
+                Ok(change) => {
+                    let mut fields = vec![
+                        ("Validator", change.validator.encode()),
+                        ("Moniker", change.moniker),
+                    ];
+                    if let Some(website) = change.website {
+                        fields.push(("Website", website));
+                    }
+                    if let Some(contact) = change.contact {
+                        fields.push(("Contact", contact));
+                    }
+                    fields
+                }
+                Err(_) => vec![],
+            }
+        }
+        TX_UNJAIL_VALIDATOR_WASM => match pos::Unjail::try_from_slice(data) {
+            Ok(unjail) => vec![("Validator", unjail.validator.encode())],
+            Err(_) => vec![],
+        },
+        TX_REGISTER_ALIAS_WASM => match RegisterAlias::try_from_slice(data) {
+            Ok(register) => vec![
+                ("Alias", register.alias),
+                ("Address", register.address.encode()),
+                (
+                    "Registration fee",
+                    format!("{} XAN", name_service::REGISTRATION_FEE),
+                ),
+            ],
+            Err(_) => vec![],
+        },
+        _ => vec![],
+    }
+}
+
+/// The currency code of a known token, or the address of the token
+fn token_symbol(token: &Address) -> String {
+    address::tokens()
+        .get(token)
+        .map(|code| code.to_string())
+        .unwrap_or_else(|| token.encode())
+}
+
+/// Parse the value of [`ENV_VAR_ASSUME_YES`] as a boolean. Any value other
+/// than `true`, `yes`, `y` or `1` in any case is `false`, so that the txs are
+/// never signed without a confirmation by mistake.
+fn parse_assume_yes(val: &str) -> bool {
+    matches!(
+        val.trim().to_ascii_lowercase().as_str(),
+        "true" | "yes" | "y" | "1"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::address::testing::{
+        established_address_1, established_address_2,
+    };
+    use borsh::BorshSerialize;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_assume_yes() {
+        for val in ["true", "TRUE", "yes", "Y", "1", " 1 "] {
+            assert!(parse_assume_yes(val), "{} should be yes", val);
+        }
+        for val in ["false", "0", "no", "n", "", "maybe"] {
+            assert!(!parse_assume_yes(val), "{} should be no", val);
+        }
+    }
+
+    #[test]
+    fn test_decode_transfer_fields() {
+        let transfer = token::Transfer {
+            source: established_address_1(),
+            target: established_address_2(),
+            token: address::xan(),
+            amount: token::Amount::from(10_000_000),
+        };
+        let data = transfer.try_to_vec().unwrap();
+        let fields = decode_fields(TX_TRANSFER_WASM, &data);
+        assert_eq!(
+            fields,
+            vec![
+                ("Source", established_address_1().encode()),
+                ("Target", established_address_2().encode()),
+                ("Token", "XAN".to_string()),
+                ("Amount", "10 XAN".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_unknown_fields() {
+        assert!(decode_fields("tx_custom.wasm", &[1, 2, 3]).is_empty());
+    }
+}
@@ -1,82 +1,358 @@
 //! Client RPC queries
 
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::convert::TryInto;
+use std::env;
 use std::fs::File;
 use std::io::{self, Write};
 use std::iter::Iterator;
+use std::time::Duration;
 
+use anoma::client::{
+    self as query_client, CachingClient, QueryClient, RetryPolicy,
+    StorageValueError,
+};
+use anoma::ledger::gas::BlockGasUsage;
 use anoma::ledger::governance::storage as gov_storage;
 use anoma::ledger::governance::utils::Votes;
-use anoma::ledger::parameters::{storage as param_storage, EpochDuration};
-use anoma::ledger::pos::types::{
-    Epoch as PosEpoch, VotingPower, WeightedValidator,
+use anoma::ledger::light_client::{
+    self, TrustedValidator, TrustedValidatorSet,
 };
-use anoma::ledger::pos::{
-    self, is_validator_slashes_key, BondId, Bonds, PosParams, Slash, Unbonds,
+use anoma::ledger::name_service::storage as name_storage;
+use anoma::ledger::parameters::{
+    storage as param_storage, EpochDuration, EpochInfo, UpgradeInfo,
+};
+use anoma::ledger::pos::types::{
+    Epoch as PosEpoch, ValidatorState, VotingPower, WeightedValidator,
 };
+use anoma::ledger::pos::{self, BondId, Bonds, PosParams, Slash};
+use anoma::ledger::storage::ProofSpec;
+use anoma::ledger::token_registry::storage as registry_storage;
 use anoma::ledger::treasury::storage as treasury_storage;
+use anoma::types::account::{AccountInfo, StorageUsage, StorageUsagePage};
 use anoma::types::address::Address;
+use anoma::types::chain::PROTOCOL_VERSION;
 use anoma::types::governance::{
     OfflineProposal, OfflineVote, ProposalVote, TallyResult,
 };
+use anoma::types::hash::Hash;
+use anoma::types::indexer::{
+    BlockResults, IndexedTx, TransferDirection, TransferRecord,
+};
 use anoma::types::key::*;
-use anoma::types::storage::{Epoch, PrefixValue};
+use anoma::types::nft;
+use anoma::types::query::{Balance, EpochQueryResult};
+use anoma::types::storage::{Epoch, MultiValueResult, PrefixPage, PrefixValue};
+use anoma::types::time::DateTimeUtc;
 use anoma::types::token::{balance_key, Amount};
+use anoma::types::transaction::TxResponse;
 use anoma::types::{address, storage, token};
 use async_std::fs::{self};
 use async_std::path::PathBuf;
 use async_std::prelude::*;
 use borsh::BorshDeserialize;
 use itertools::Itertools;
-#[cfg(not(feature = "ABCI"))]
-use tendermint::abci::Code;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_config::net::Address as TendermintAddress;
-#[cfg(feature = "ABCI")]
-use tendermint_config_abci::net::Address as TendermintAddress;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_rpc::error::Error as TError;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_rpc::query::Query;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_rpc::{Client, HttpClient};
-#[cfg(not(feature = "ABCI"))]
-use tendermint_rpc::{Order, SubscriptionClient, WebSocketClient};
-#[cfg(feature = "ABCI")]
-use tendermint_rpc_abci::error::Error as TError;
-#[cfg(feature = "ABCI")]
-use tendermint_rpc_abci::query::Query;
-#[cfg(feature = "ABCI")]
-use tendermint_rpc_abci::{Client, HttpClient};
-#[cfg(feature = "ABCI")]
-use tendermint_rpc_abci::{Order, SubscriptionClient, WebSocketClient};
-#[cfg(feature = "ABCI")]
-use tendermint_stable::abci::Code;
+use prost::Message;
+use tendermint_compat::tendermint::abci::Code;
+use tendermint_compat::tendermint::block::Height;
+use tendermint_compat::tendermint_config::net::Address as TendermintAddress;
+use tendermint_compat::tendermint_proto::types::SignedHeader;
+use tendermint_compat::tendermint_rpc::endpoint::abci_query::AbciQuery;
+use tendermint_compat::tendermint_rpc::error::Error as TError;
+use tendermint_compat::tendermint_rpc::query::Query;
+use tendermint_compat::tendermint_rpc::{
+    Client, HttpClient, Order, SubscriptionClient, WebSocketClient,
+};
 
+use crate::cli::context::WalletAddress;
 use crate::cli::{self, args, Context};
-use crate::client::tendermint_rpc_types::TxResponse;
-use crate::node::ledger::rpc::Path;
+use crate::client::output::{
+    self, AliasEntry, AliasQueryResult, BalanceQueryResult, BondEntry,
+    BondQueryResult, ConsensusKeyEntry, OutputMode, RewardsEntry,
+    RewardsQueryResult, SlashEntry, SlashQueryResult, TxQueryResult, TxStatus,
+    UnbondEntry, ValidatorKeysQueryResult,
+};
+use crate::client::tendermint_rpc_types::ResultCodeFilter;
+use crate::node::ledger::node_status::{BlockStats, MempoolStatus, NodeStatus};
+use crate::node::ledger::rpc::{
+    Path, QueryRejection, MAX_CHANGED_KEYS_BLOCKS, MAX_PREFIX_PAGE_LIMIT,
+    MAX_SLASHES_PAGE_LIMIT, MAX_VALUES_KEYS,
+};
+
+/// Env. var to set the maximum number of retries of a query that failed with
+/// a transient error
+const ENV_VAR_QUERY_RETRIES: &str = "ANOMA_QUERY_RETRIES";
+
+/// Env. var to set the deadline of a query with its retries, in seconds. The
+/// deadline is disabled with `0`.
+const ENV_VAR_QUERY_DEADLINE: &str = "ANOMA_QUERY_DEADLINE";
+
+/// The maximum number of responses cached by the client of the commands that
+/// read the same storage values repeatedly, e.g. the tallies of the proposals
+const QUERY_CACHE_ENTRIES: usize = 1024;
+
+/// The retry policy of the queries, with the values of the env vars
+fn retry_policy() -> RetryPolicy {
+    let mut policy = RetryPolicy::default();
+    if let Some(retries) = parse_env_var(ENV_VAR_QUERY_RETRIES) {
+        policy.max_retries = retries;
+    }
+    if let Some(secs) = parse_env_var(ENV_VAR_QUERY_DEADLINE) {
+        policy.deadline = (secs > 0).then(|| Duration::from_secs(secs));
+    }
+    policy
+}
+
+fn parse_env_var<T: std::str::FromStr>(name: &str) -> Option<T> {
+    let value = env::var(name).ok()?;
+    match value.parse() {
+        Ok(value) => Some(value),
+        Err(_) => {
+            eprintln!("Ignoring the invalid value of {}: {}", name, value);
+            None
+        }
+    }
+}
+
+/// A client of the ledger that caches the responses of the queries at the
+/// last committed block, for the commands that read the same storage values
+/// repeatedly
+fn caching_client(
+    ledger_address: TendermintAddress,
+) -> CachingClient<HttpClient> {
+    CachingClient::new(
+        HttpClient::new(ledger_address).unwrap(),
+        QUERY_CACHE_ENTRIES,
+    )
+}
+
+/// Send an ABCI query with the [`retry_policy`]. The queries that fail with
+/// an RPC error, e.g. a transient failure of the node, and the queries
+/// rejected by the rate limiter of the node are retried.
+async fn abci_query(
+    client: &HttpClient,
+    path: Path,
+    data: Vec<u8>,
+    height: Option<Height>,
+    prove: bool,
+) -> query_client::Result<AbciQuery> {
+    retry_policy()
+        .retry(
+            || {
+                let path = path.clone();
+                let data = data.clone();
+                async move {
+                    client
+                        .abci_query(Some(path.into()), data, height, prove)
+                        .await
+                        .map_err(query_client::Error::Rpc)
+                }
+            },
+            |result| match result {
+                Ok(response) => match response.code {
+                    Code::Err(code) => {
+                        QueryRejection::from_code(code)
+                            == Some(QueryRejection::RateLimited)
+                    }
+                    Code::Ok => false,
+                },
+                Err(err) => err.is_retryable(),
+            },
+        )
+        .await
+}
 
 /// Query the epoch of the last committed block
 pub async fn query_epoch(args: args::Query) -> Epoch {
+    query_epoch_with_output(args, OutputMode::Text).await
+}
+
+/// Query the epoch of the last committed block and print it in the given
+/// output mode
+pub async fn query_epoch_with_output(
+    args: args::Query,
+    output: OutputMode,
+) -> Epoch {
+    let epoch = fetch_epoch(args).await;
+    output::print(output, &EpochQueryResult { epoch });
+    epoch
+}
+
+/// Query the epoch of the last committed block without printing it
+async fn fetch_epoch(args: args::Query) -> Epoch {
     let client = HttpClient::new(args.ledger_address).unwrap();
     let path = Path::Epoch;
     let data = vec![];
-    let response = client
-        .abci_query(Some(path.into()), data, None, false)
-        .await
-        .unwrap();
+    let response = abci_query(&client, path, data, None, false).await.unwrap();
     match response.code {
         Code::Ok => match Epoch::try_from_slice(&response.value[..]) {
-            Ok(epoch) => {
-                println!("Last committed epoch: {}", epoch);
-                return epoch;
+            Ok(epoch) => return epoch,
+            Err(err) => {
+                eprintln!("Error decoding the epoch value: {}", err)
             }
+        },
+        Code::Err(err) => eprintln!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        ),
+    }
+    cli::safe_exit(1)
+}
 
+/// Query the gas used by the last committed block and the current gas limits
+pub async fn query_block_gas(args: args::Query) -> BlockGasUsage {
+    let client = HttpClient::new(args.ledger_address).unwrap();
+    let path = Path::BlockGas;
+    let data = vec![];
+    let response = abci_query(&client, path, data, None, false).await.unwrap();
+    match response.code {
+        Code::Ok => match BlockGasUsage::try_from_slice(&response.value[..]) {
+            Ok(usage) => {
+                println!("Max block gas: {}", usage.max_block_gas);
+                println!("Max tx gas: {}", usage.max_tx_gas);
+                println!("Gas used by the last block: {}", usage.used_gas);
+                println!("Remaining block gas: {}", usage.remaining_gas());
+                return usage;
+            }
             Err(err) => {
-                eprintln!("Error decoding the epoch value: {}", err)
+                eprintln!("Error decoding the block gas value: {}", err)
+            }
+        },
+        Code::Err(err) => eprintln!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        ),
+    }
+    cli::safe_exit(1)
+}
+
+/// Query the protocol version of the chain and the pending protocol upgrade
+pub async fn query_upgrade(args: args::Query) -> UpgradeInfo {
+    let client = HttpClient::new(args.ledger_address).unwrap();
+    let path = Path::Upgrade;
+    let data = vec![];
+    let response = abci_query(&client, path, data, None, false).await.unwrap();
+    match response.code {
+        Code::Ok => match UpgradeInfo::try_from_slice(&response.value[..]) {
+            Ok(info) => {
+                println!("Chain's protocol version: {}", info.protocol_version);
+                println!("Client's protocol version: {}", PROTOCOL_VERSION);
+                match &info.pending_upgrade {
+                    Some(upgrade) => println!(
+                        "Pending upgrade to protocol version {} at height {}",
+                        upgrade.version, upgrade.height
+                    ),
+                    None => println!("No pending protocol upgrade"),
+                }
+                return info;
+            }
+            Err(err) => {
+                eprintln!("Error decoding the protocol upgrade value: {}", err)
+            }
+        },
+        Code::Err(err) => eprintln!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        ),
+    }
+    cli::safe_exit(1)
+}
+
+/// Query the status of the node and of its chain, the size of the node's
+/// mempool and the gas and the txs of the recent blocks, to show how
+/// congested the node is
+pub async fn query_node_status(
+    args: args::Query,
+) -> (NodeStatus, MempoolStatus, Vec<BlockStats>) {
+    let client = HttpClient::new(args.ledger_address).unwrap();
+    let mut status: NodeStatus =
+        fetch_node_status_value(&client, Path::Status).await;
+    status.syncing = match client.status().await {
+        Ok(tm_status) => Some(tm_status.sync_info.catching_up),
+        Err(err) => {
+            eprintln!("Error in the Tendermint status query: {}", err);
+            None
+        }
+    };
+    println!("Chain ID: {}", status.chain_id);
+    println!("Node version: {}", status.version);
+    println!(
+        "Last block: height {}, app hash {}",
+        status.last_height,
+        hex::encode_upper(&status.app_hash)
+    );
+    if let Some(time) = status.last_block_time {
+        println!("Last block time: {}", time.to_rfc3339());
+    }
+    match status.epoch_start_height {
+        Some(height) => {
+            println!("Epoch: {} (started at height {})", status.epoch, height)
+        }
+        None => println!("Epoch: {}", status.epoch),
+    }
+    let syncing = match status.syncing {
+        Some(true) => "yes",
+        Some(false) => "no",
+        None => "unknown",
+    };
+    println!("Syncing: {}", syncing);
+
+    let mempool: MempoolStatus =
+        fetch_node_status_value(&client, Path::Mempool).await;
+    let blocks: Vec<BlockStats> =
+        fetch_node_status_value(&client, Path::RecentBlocks).await;
+
+    println!("Mempool: {} txs, {} bytes", mempool.txs, mempool.bytes);
+    match (blocks.first(), blocks.last()) {
+        (Some(first), Some(last)) => {
+            println!(
+                "Recent blocks: {} (heights {} to {})",
+                blocks.len(),
+                first.height,
+                last.height
+            );
+            println!(
+                "Last block: {} of {} gas used ({:.2}%)",
+                last.used_gas,
+                last.max_block_gas,
+                last.fullness()
+            );
+            let average_fullness =
+                blocks.iter().map(BlockStats::fullness).sum::<f64>()
+                    / blocks.len() as f64;
+            println!("Average block fullness: {:.2}%", average_fullness);
+            let wrapper_txs: u64 =
+                blocks.iter().map(|block| block.wrapper_txs).sum();
+            let decrypted_txs: u64 =
+                blocks.iter().map(|block| block.decrypted_txs).sum();
+            println!(
+                "Wrapper txs: {}, decrypted txs: {}",
+                wrapper_txs, decrypted_txs
+            );
+            if decrypted_txs > 0 {
+                println!(
+                    "Wrapper to decrypted txs ratio: {:.2}",
+                    wrapper_txs as f64 / decrypted_txs as f64
+                );
+            }
+        }
+        _ => println!("No blocks have been finalized since the node started"),
+    }
+    (status, mempool, blocks)
+}
+
+/// Query and decode a value of the node's status
+async fn fetch_node_status_value<T: BorshDeserialize>(
+    client: &HttpClient,
+    path: Path,
+) -> T {
+    let response = abci_query(client, path, vec![], None, false).await.unwrap();
+    match response.code {
+        Code::Ok => match T::try_from_slice(&response.value[..]) {
+            Ok(value) => return value,
+            Err(err) => {
+                eprintln!("Error decoding the node status value: {}", err)
             }
         },
         Code::Err(err) => eprintln!(
@@ -87,15 +363,90 @@ pub async fn query_epoch(args: args::Query) -> Epoch {
     cli::safe_exit(1)
 }
 
+/// Verify the header of a block against the active validator set of an
+/// epoch, with their consensus keys and voting powers from PoS
+pub async fn verify_header(args: args::VerifyHeader) {
+    // The validator set of the last committed epoch is trusted by default
+    let epoch = match args.epoch {
+        Some(epoch) => epoch,
+        None => fetch_epoch(args.query.clone()).await,
+    };
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let height = Height::try_from(args.height.0).unwrap_or_else(|err| {
+        eprintln!("Invalid block height {}: {}", args.height, err);
+        cli::safe_exit(1)
+    });
+    let response = client.commit(height).await.unwrap_or_else(|err| {
+        eprintln!(
+            "Error fetching the commit of the block at height {}: {}",
+            args.height, err
+        );
+        cli::safe_exit(1)
+    });
+    let validators = get_trusted_validator_set(&client, epoch).await;
+    match light_client::verify_signed_header(
+        SignedHeader::from(response.signed_header),
+        &validators,
+    ) {
+        Ok(verified) => {
+            println!(
+                "The header of the block at height {} is valid",
+                verified.height()
+            );
+            println!("Block hash: {}", verified.header.hash());
+            println!(
+                "App hash: {}",
+                hex::encode_upper(verified.header.app_hash.value())
+            );
+            println!(
+                "Signed by a voting power of {} out of {} in epoch {}",
+                verified.signed_voting_power,
+                verified.total_voting_power,
+                epoch
+            );
+        }
+        Err(err) => {
+            eprintln!(
+                "The header of the block at height {} is not valid: {}",
+                args.height, err
+            );
+            cli::safe_exit(1)
+        }
+    }
+}
+
+/// Get the active validators of the epoch with the consensus keys that they
+/// had in the epoch, which may be a past epoch within the unbonding length
+async fn get_trusted_validator_set(
+    client: &HttpClient,
+    epoch: Epoch,
+) -> TrustedValidatorSet {
+    let validator_set = query_validator_set_at(client, epoch)
+        .await
+        .unwrap_or_else(|| {
+            eprintln!("No validator set is kept for the epoch {}", epoch);
+            cli::safe_exit(1)
+        });
+    let validators = validator_set
+        .validators
+        .into_iter()
+        .filter(|validator| validator.active)
+        .map(|validator| TrustedValidator {
+            consensus_key: validator
+                .consensus_key
+                .expect("An active validator should have a consensus key"),
+            voting_power: VotingPower::from(validator.voting_power),
+        })
+        .collect();
+    TrustedValidatorSet::new(validators)
+}
+
 /// Query the raw bytes of given storage key
 pub async fn query_raw_bytes(_ctx: Context, args: args::QueryRawBytes) {
     let client = HttpClient::new(args.query.ledger_address).unwrap();
     let path = Path::Value(args.storage_key);
     let data = vec![];
-    let response = client
-        .abci_query(Some(path.into()), data, None, false)
-        .await
-        .unwrap();
+    let response = abci_query(&client, path, data, None, false).await.unwrap();
     match response.code {
         Code::Ok => {
             println!("{}", hex::encode(&response.value));
@@ -114,167 +465,511 @@ pub async fn query_raw_bytes(_ctx: Context, args: args::QueryRawBytes) {
 pub async fn query_balance(ctx: Context, args: args::QueryBalance) {
     let client = HttpClient::new(args.query.ledger_address).unwrap();
     let tokens = address::tokens();
-    match (args.token, args.owner) {
+    let token = ctx.get_opt(&args.token);
+    let owner = ctx.get_opt(&args.owner);
+    let mut balances = vec![];
+    match (&token, &owner) {
         (Some(token), Some(owner)) => {
-            let token = ctx.get(&token);
-            let owner = ctx.get(&owner);
-            let key = token::balance_key(&token, &owner);
-            let currency_code = tokens
-                .get(&token)
-                .map(|c| Cow::Borrowed(*c))
-                .unwrap_or_else(|| Cow::Owned(token.to_string()));
-            match query_storage_value::<token::Amount>(&client, &key).await {
-                Some(balance) => {
-                    println!("{}: {}", currency_code, balance);
-                }
-                None => {
-                    println!("No {} balance found for {}", currency_code, owner)
-                }
+            let key = token::balance_key(token, owner);
+            if let Some(amount) =
+                query_storage_value::<token::Amount>(&client, &key).await
+            {
+                let denomination = query_denomination(&client, token).await;
+                balances.push(Balance {
+                    token: token.clone(),
+                    symbol: denomination.symbol,
+                    owner: owner.clone(),
+                    amount,
+                });
             }
         }
         (None, Some(owner)) => {
-            let owner = ctx.get(&owner);
-            let mut found_any = false;
-            for (token, currency_code) in tokens {
-                let key = token::balance_key(&token, &owner);
-                if let Some(balance) =
-                    query_storage_value::<token::Amount>(&client, &key).await
-                {
-                    println!("{}: {}", currency_code, balance);
-                    found_any = true;
+            // The node's txs indexer also finds the tokens that the client
+            // doesn't know. It only indexes the balances written since it's
+            // been enabled, so the known tokens that it hasn't found are
+            // queried too.
+            let mut amounts: Vec<(Address, Option<token::Amount>)> =
+                query_owner_balances(&client, owner)
+                    .await
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(token, amount)| (token, Some(amount)))
+                    .collect();
+            let tokens: Vec<Address> = tokens
+                .keys()
+                .filter(|token| {
+                    !amounts.iter().any(|(indexed, _)| indexed == *token)
+                })
+                .cloned()
+                .collect();
+            let keys: Vec<storage::Key> = tokens
+                .iter()
+                .map(|token| token::balance_key(token, owner))
+                .collect();
+            let known_amounts =
+                query_storage_values::<token::Amount>(&client, &keys).await;
+            amounts.extend(tokens.into_iter().zip(known_amounts));
+            for (token, amount) in amounts {
+                if let Some(amount) = amount {
+                    let denomination =
+                        query_denomination(&client, &token).await;
+                    balances.push(Balance {
+                        token,
+                        symbol: denomination.symbol,
+                        owner: owner.clone(),
+                        amount,
+                    });
                 }
             }
-            if !found_any {
-                println!("No balance found for {}", owner);
-            }
         }
         (Some(token), None) => {
-            let token = ctx.get(&token);
-            let key = token::balance_prefix(&token);
-            let balances =
-                query_storage_prefix::<token::Amount>(client, key).await;
-            match balances {
-                Some(balances) => {
-                    let currency_code = tokens
-                        .get(&token)
-                        .map(|c| Cow::Borrowed(*c))
-                        .unwrap_or_else(|| Cow::Owned(token.to_string()));
-                    let stdout = io::stdout();
-                    let mut w = stdout.lock();
-                    writeln!(w, "Token {}:", currency_code).unwrap();
-                    for (key, balance) in balances {
-                        let owner =
-                            token::is_any_token_balance_key(&key).unwrap();
-                        writeln!(w, "  {}, owned by {}", balance, owner)
-                            .unwrap();
-                    }
-                }
-                None => {
-                    println!("No balances for token {}", token.encode())
-                }
-            }
+            balances = query_token_balances(&client, token).await;
         }
         (None, None) => {
-            let stdout = io::stdout();
-            let mut w = stdout.lock();
-            for (token, currency_code) in tokens {
-                let key = token::balance_prefix(&token);
-                let balances =
-                    query_storage_prefix::<token::Amount>(client.clone(), key)
-                        .await;
-                match balances {
-                    Some(balances) => {
-                        writeln!(w, "Token {}:", currency_code).unwrap();
-                        for (key, balance) in balances {
-                            let owner =
-                                token::is_any_token_balance_key(&key).unwrap();
-                            writeln!(w, "  {}, owned by {}", balance, owner)
-                                .unwrap();
-                        }
-                    }
-                    None => {
-                        println!("No balances for token {}", token.encode())
-                    }
-                }
+            for token in tokens.keys() {
+                balances.extend(query_token_balances(&client, token).await);
             }
         }
     }
+    let result = BalanceQueryResult {
+        token,
+        owner,
+        balances,
+    };
+    output::print(ctx.global_args.output, &result);
 }
 
-/// Query Proposals
-pub async fn query_proposal(_ctx: Context, args: args::QueryProposal) {
-    async fn print_proposal(
-        client: &HttpClient,
-        id: u64,
-        current_epoch: Epoch,
-        details: bool,
-    ) -> Option<()> {
-        let author_key = gov_storage::get_author_key(id);
-        let start_epoch_key = gov_storage::get_voting_start_epoch_key(id);
-        let end_epoch_key = gov_storage::get_voting_end_epoch_key(id);
-
-        let author =
-            query_storage_value::<Address>(client, &author_key).await?;
-        let start_epoch =
-            query_storage_value::<Epoch>(client, &start_epoch_key).await?;
-        let end_epoch =
-            query_storage_value::<Epoch>(client, &end_epoch_key).await?;
+/// Query the balances of all the owners of the given token
+async fn query_token_balances(
+    client: &HttpClient,
+    token: &Address,
+) -> Vec<Balance> {
+    let key = token::balance_prefix(token);
+    let balances = query_storage_prefix::<token::Amount>(client.clone(), key)
+        .await
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    if balances.is_empty() {
+        return vec![];
+    }
+    let denomination = query_denomination(client, token).await;
+    balances
+        .into_iter()
+        .map(|(key, amount)| Balance {
+            token: token.clone(),
+            symbol: denomination.symbol.clone(),
+            owner: token::is_any_token_balance_key(&key).unwrap().clone(),
+            amount,
+        })
+        .collect()
+}
 
-        if details {
-            let content_key = gov_storage::get_content_key(id);
-            let grace_epoch_key = gov_storage::get_grace_epoch_key(id);
-            let content = query_storage_value::<HashMap<String, String>>(
-                client,
-                &content_key,
-            )
-            .await?;
-            let grace_epoch =
-                query_storage_value::<Epoch>(client, &grace_epoch_key).await?;
+/// Find the denomination of the token, which is hard-coded for the native
+/// tokens and read from the metadata of the other tokens. A token without
+/// metadata is denominated by its address with all the decimal places.
+pub async fn query_denomination(
+    client: &HttpClient,
+    token: &Address,
+) -> token::Denomination {
+    if let Some(denomination) = token::Denomination::native(token) {
+        return denomination;
+    }
+    let metadata_key = token::metadata_key(token);
+    match query_storage_value::<token::Metadata>(client, &metadata_key).await {
+        Some(metadata) => token::Denomination::from(&metadata),
+        None => token::Denomination {
+            symbol: token.encode(),
+            decimals: token::MAX_DECIMAL_PLACES as u8,
+        },
+    }
+}
 
-            println!("Proposal: {}", id);
-            println!("{:4}Author: {}", "", author);
-            println!("{:4}Content:", "");
-            for (key, value) in &content {
-                println!("{:8}{}: {}", "", key, value);
-            }
-            println!("{:4}Start Epoch: {}", "", start_epoch);
-            println!("{:4}End Epoch: {}", "", end_epoch);
-            println!("{:4}Grace Epoch: {}", "", grace_epoch);
-            if start_epoch > current_epoch {
-                println!("{:4}Status: pending", "");
-            } else if start_epoch <= current_epoch && current_epoch <= end_epoch
-            {
-                println!("{:4}Status: on-going", "");
-            } else {
-                let votes = get_proposal_votes(client, start_epoch, id).await;
-                let proposal_result =
-                    compute_tally(client, start_epoch, votes).await;
-                println!("{:4}Status: done", "");
-                println!("{:4}Result: {}", "", proposal_result);
+/// Query token allowance(s)
+pub async fn query_allowances(ctx: Context, args: args::QueryAllowances) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let owner = args.owner.map(|owner| ctx.get(&owner));
+    let spender = args.spender.map(|spender| ctx.get(&spender));
+    let tokens: Vec<(Address, Cow<str>)> = match args.token {
+        Some(token) => {
+            let token = ctx.get(&token);
+            let currency_code = address::tokens()
+                .get(&token)
+                .map(|c| Cow::Borrowed(*c))
+                .unwrap_or_else(|| Cow::Owned(token.to_string()));
+            vec![(token, currency_code)]
+        }
+        None => address::tokens()
+            .into_iter()
+            .map(|(token, currency_code)| (token, Cow::Borrowed(currency_code)))
+            .collect(),
+    };
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    let mut found_any = false;
+    for (token, currency_code) in tokens {
+        let prefix = match &owner {
+            Some(owner) => token::allowance_prefix(&token, owner),
+            None => token::allowances_prefix(&token),
+        };
+        let allowances =
+            query_storage_prefix::<token::Amount>(client.clone(), prefix).await;
+        let allowances = allowances.into_iter().flatten().filter(|(key, _)| {
+            match token::is_allowance_key(&token, key) {
+                Some((_owner, key_spender)) => spender
+                    .as_ref()
+                    .map(|spender| spender == key_spender)
+                    .unwrap_or(true),
+                None => false,
             }
-        } else {
-            println!("Proposal: {}", id);
-            println!("{:4}Author: {}", "", author);
-            println!("{:4}Start Epoch: {}", "", start_epoch);
-            println!("{:4}End Epoch: {}", "", end_epoch);
-            if start_epoch > current_epoch {
-                println!("{:4}Status: pending", "");
-            } else if start_epoch <= current_epoch && current_epoch <= end_epoch
-            {
-                println!("{:4}Status: on-going", "");
-            } else {
-                println!("{:4}Status: done", "");
+        });
+        let mut printed_token = false;
+        for (key, allowance) in allowances {
+            if !printed_token {
+                writeln!(w, "Token {}:", currency_code).unwrap();
+                printed_token = true;
             }
+            let (key_owner, key_spender) =
+                token::is_allowance_key(&token, &key).unwrap();
+            writeln!(
+                w,
+                "  {}, owned by {}, spendable by {}",
+                allowance, key_owner, key_spender
+            )
+            .unwrap();
+            found_any = true;
         }
-
-        Some(())
     }
+    if !found_any {
+        println!("No allowances found");
+    }
+}
 
-    let client = HttpClient::new(args.query.ledger_address.clone()).unwrap();
-    let current_epoch = query_epoch(args.query.clone()).await;
-    match args.proposal_id {
-        Some(id) => {
-            if print_proposal(&client, id, current_epoch, true)
+/// Query the address of an alias or all the aliases registered in the name
+/// service
+pub async fn query_alias(ctx: Context, args: args::QueryAlias) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let aliases = match &args.alias {
+        Some(alias) => query_alias_address(&client, alias)
+            .await
+            .map(|address| AliasEntry {
+                alias: alias.clone(),
+                address,
+            })
+            .into_iter()
+            .collect(),
+        None => {
+            let prefix = name_storage::alias_prefix();
+            query_storage_prefix::<Address>(client.clone(), prefix)
+                .await
+                .into_iter()
+                .flatten()
+                .filter_map(|(key, address)| {
+                    name_storage::is_alias_key(&key).map(|alias| AliasEntry {
+                        alias: alias.clone(),
+                        address,
+                    })
+                })
+                .collect()
+        }
+    };
+    let result = AliasQueryResult {
+        alias: args.alias,
+        aliases,
+    };
+    output::print(ctx.global_args.output, &result);
+}
+
+/// Query the address that the alias resolves to in the name service, if it's
+/// registered
+pub async fn query_alias_address(
+    client: &HttpClient,
+    alias: &str,
+) -> Option<Address> {
+    query_storage_value::<Address>(client, &name_storage::alias_key(alias))
+        .await
+}
+
+/// Resolve an address argument, which can be a raw address, an alias of an
+/// address in the wallet or an alias registered in the name service. Exits
+/// when the address is not found.
+pub async fn resolve_address(
+    ctx: &Context,
+    address: &WalletAddress,
+    ledger_address: TendermintAddress,
+) -> Address {
+    let raw = address.raw();
+    if let Some(address) = ctx.try_get_address(raw) {
+        return address;
+    }
+    let client = HttpClient::new(ledger_address).unwrap();
+    match query_alias_address(&client, raw).await {
+        Some(address) => address,
+        None => {
+            eprintln!("Unknown address {}", raw);
+            cli::safe_exit(1)
+        }
+    }
+}
+
+/// Query the metadata of a token or of all the registered tokens
+pub async fn query_token_metadata(
+    ctx: Context,
+    args: args::QueryTokenMetadata,
+) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let tokens: Vec<Address> = match args.token {
+        Some(token) => vec![ctx.get(&token)],
+        None => {
+            let prefix = registry_storage::symbol_prefix();
+            query_storage_prefix::<Address>(client.clone(), prefix)
+                .await
+                .into_iter()
+                .flatten()
+                .map(|(_key, token)| token)
+                .collect()
+        }
+    };
+    if tokens.is_empty() {
+        println!("No registered tokens found");
+    }
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    for token in tokens {
+        let metadata_key = token::metadata_key(&token);
+        let metadata =
+            query_storage_value::<token::Metadata>(&client, &metadata_key)
+                .await;
+        match metadata {
+            Some(metadata) => {
+                writeln!(w, "Token {} ({}):", metadata.symbol, token).unwrap();
+                writeln!(w, "  Decimals: {}", metadata.decimals).unwrap();
+                match query_total_supply(&client, &token).await {
+                    Some(supply) => {
+                        writeln!(w, "  Total supply: {}", supply).unwrap()
+                    }
+                    None => writeln!(w, "  Total supply: not tracked").unwrap(),
+                }
+                match metadata.max_supply {
+                    Some(max_supply) => {
+                        writeln!(w, "  Max supply: {}", max_supply).unwrap()
+                    }
+                    None => writeln!(w, "  Max supply: unlimited").unwrap(),
+                }
+                match metadata.minter {
+                    Some(minter) => {
+                        writeln!(w, "  Minter: {}", minter).unwrap()
+                    }
+                    None => {
+                        writeln!(w, "  Minter: none, fixed supply").unwrap()
+                    }
+                }
+            }
+            None => {
+                writeln!(w, "No metadata found for token {}", token).unwrap()
+            }
+        }
+    }
+}
+
+/// Query the tokens of a nft with their owners and metadata URIs
+pub async fn query_nft(args: args::QueryNft) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let nft_address = args.nft_address;
+    let creator_key = nft::get_creator_key(&nft_address);
+    let creator =
+        match query_storage_value::<Address>(&client, &creator_key).await {
+            Some(creator) => creator,
+            None => {
+                eprintln!("No nft found at address {}", nft_address);
+                cli::safe_exit(1)
+            }
+        };
+    let tag_key = nft::get_tag_key(&nft_address);
+    let tag = query_storage_value::<String>(&client, &tag_key)
+        .await
+        .unwrap_or_default();
+    let keys_key = nft::get_keys_key(&nft_address);
+    let keys = query_storage_value::<Vec<String>>(&client, &keys_key)
+        .await
+        .unwrap_or_default();
+
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    writeln!(w, "Nft {} (tag: {}):", nft_address, tag).unwrap();
+    writeln!(w, "  Creator: {}", creator).unwrap();
+    writeln!(w, "  Keys: {:?}", keys).unwrap();
+
+    let token_ids = match args.token_id {
+        Some(token_id) => vec![token_id],
+        None => query_nft_token_ids(&client, &nft_address).await,
+    };
+    if token_ids.is_empty() {
+        writeln!(w, "No tokens found").unwrap();
+    }
+    for token_id in token_ids {
+        let token_id = token_id.to_string();
+        let current_owner_key =
+            nft::get_token_current_owner_key(&nft_address, &token_id);
+        let current_owner =
+            match query_storage_value::<Address>(&client, &current_owner_key)
+                .await
+            {
+                Some(owner) => owner,
+                None => {
+                    writeln!(w, "No token {} found", token_id).unwrap();
+                    continue;
+                }
+            };
+        let metadata_key = nft::get_token_metadata_key(&nft_address, &token_id);
+        let metadata = query_storage_value::<String>(&client, &metadata_key)
+            .await
+            .unwrap_or_default();
+        let past_owners_key =
+            nft::get_token_past_owners_key(&nft_address, &token_id);
+        let past_owners =
+            query_storage_value::<Vec<Address>>(&client, &past_owners_key)
+                .await
+                .unwrap_or_default();
+        let approval_key = nft::get_token_approval_key(&nft_address, &token_id);
+        let approvals =
+            query_storage_value::<Vec<Address>>(&client, &approval_key)
+                .await
+                .unwrap_or_default();
+        let burnt_key = nft::get_token_burnt_key(&nft_address, &token_id);
+        let burnt = query_storage_value::<bool>(&client, &burnt_key)
+            .await
+            .unwrap_or_default();
+        writeln!(w, "  Token {}:", token_id).unwrap();
+        writeln!(w, "    Owner: {}", current_owner).unwrap();
+        writeln!(w, "    Metadata URI: {}", metadata).unwrap();
+        if !past_owners.is_empty() {
+            writeln!(w, "    Past owners: {:?}", past_owners).unwrap();
+        }
+        if !approvals.is_empty() {
+            writeln!(w, "    Approvals: {:?}", approvals).unwrap();
+        }
+        if burnt {
+            writeln!(w, "    Burnt").unwrap();
+        }
+    }
+}
+
+/// Query the ids of all the tokens of a nft. The values stored under a
+/// token's prefix have different types, so only the keys of the prefix
+/// query's response are decoded.
+async fn query_nft_token_ids(
+    client: &HttpClient,
+    nft_address: &Address,
+) -> Vec<u64> {
+    let path = Path::Prefix(nft::get_tokens_prefix(nft_address));
+    let data = vec![];
+    let response = abci_query(client, path, data, None, false).await.unwrap();
+    match response.code {
+        Code::Ok => {
+            match Vec::<PrefixValue>::try_from_slice(&response.value[..]) {
+                Ok(values) => values
+                    .into_iter()
+                    .filter_map(|PrefixValue { key, value: _ }| {
+                        nft::is_nft_current_owner_key(&key, nft_address)
+                            .and_then(|(_, token_id)| token_id.parse().ok())
+                    })
+                    .collect(),
+                Err(err) => {
+                    eprintln!("Error decoding the values: {}", err);
+                    cli::safe_exit(1)
+                }
+            }
+        }
+        // No tokens found
+        Code::Err(1) => vec![],
+        Code::Err(err) => {
+            eprintln!(
+                "Error in the query {} (error code {})",
+                response.info, err
+            );
+            cli::safe_exit(1)
+        }
+    }
+}
+
+/// Query Proposals
+pub async fn query_proposal(_ctx: Context, args: args::QueryProposal) {
+    async fn print_proposal(
+        client: &CachingClient<HttpClient>,
+        id: u64,
+        current_epoch: Epoch,
+        details: bool,
+    ) -> Option<()> {
+        let author_key = gov_storage::get_author_key(id);
+        let start_epoch_key = gov_storage::get_voting_start_epoch_key(id);
+        let end_epoch_key = gov_storage::get_voting_end_epoch_key(id);
+
+        let author =
+            query_cached_storage_value::<Address>(client, &author_key).await?;
+        let start_epoch =
+            query_cached_storage_value::<Epoch>(client, &start_epoch_key)
+                .await?;
+        let end_epoch =
+            query_cached_storage_value::<Epoch>(client, &end_epoch_key).await?;
+
+        if details {
+            let content_key = gov_storage::get_content_key(id);
+            let grace_epoch_key = gov_storage::get_grace_epoch_key(id);
+            let content =
+                query_cached_storage_value::<HashMap<String, String>>(
+                    client,
+                    &content_key,
+                )
+                .await?;
+            let grace_epoch =
+                query_cached_storage_value::<Epoch>(client, &grace_epoch_key)
+                    .await?;
+
+            println!("Proposal: {}", id);
+            println!("{:4}Author: {}", "", author);
+            println!("{:4}Content:", "");
+            for (key, value) in &content {
+                println!("{:8}{}: {}", "", key, value);
+            }
+            println!("{:4}Start Epoch: {}", "", start_epoch);
+            println!("{:4}End Epoch: {}", "", end_epoch);
+            println!("{:4}Grace Epoch: {}", "", grace_epoch);
+            if start_epoch > current_epoch {
+                println!("{:4}Status: pending", "");
+            } else if start_epoch <= current_epoch && current_epoch <= end_epoch
+            {
+                println!("{:4}Status: on-going", "");
+            } else {
+                let votes = get_proposal_votes(client, start_epoch, id).await;
+                let proposal_result =
+                    compute_tally(client, start_epoch, votes).await;
+                println!("{:4}Status: done", "");
+                println!("{:4}Result: {}", "", proposal_result);
+            }
+        } else {
+            println!("Proposal: {}", id);
+            println!("{:4}Author: {}", "", author);
+            println!("{:4}Start Epoch: {}", "", start_epoch);
+            println!("{:4}End Epoch: {}", "", end_epoch);
+            if start_epoch > current_epoch {
+                println!("{:4}Status: pending", "");
+            } else if start_epoch <= current_epoch && current_epoch <= end_epoch
+            {
+                println!("{:4}Status: on-going", "");
+            } else {
+                println!("{:4}Status: done", "");
+            }
+        }
+
+        Some(())
+    }
+
+    let client = caching_client(args.query.ledger_address.clone());
+    let current_epoch = query_epoch(args.query.clone()).await;
+    match args.proposal_id {
+        Some(id) => {
+            if print_proposal(&client, id, current_epoch, true)
                 .await
                 .is_none()
             {
@@ -283,10 +978,12 @@ pub async fn query_proposal(_ctx: Context, args: args::QueryProposal) {
         }
         None => {
             let last_proposal_id_key = gov_storage::get_counter_key();
-            let last_proposal_id =
-                query_storage_value::<u64>(&client, &last_proposal_id_key)
-                    .await
-                    .unwrap();
+            let last_proposal_id = query_cached_storage_value::<u64>(
+                &client,
+                &last_proposal_id_key,
+            )
+            .await
+            .unwrap();
 
             for id in 0..last_proposal_id {
                 if print_proposal(&client, id, current_epoch, false)
@@ -314,7 +1011,7 @@ pub async fn query_proposal_result(
     _ctx: Context,
     args: args::QueryProposalResult,
 ) {
-    let client = HttpClient::new(args.query.ledger_address.clone()).unwrap();
+    let client = caching_client(args.query.ledger_address.clone());
     let current_epoch = query_epoch(args.query.clone()).await;
 
     match args.proposal_id {
@@ -322,9 +1019,11 @@ pub async fn query_proposal_result(
             let start_epoch_key = gov_storage::get_voting_start_epoch_key(id);
             let end_epoch_key = gov_storage::get_voting_end_epoch_key(id);
             let start_epoch =
-                query_storage_value::<Epoch>(&client, &start_epoch_key).await;
+                query_cached_storage_value::<Epoch>(&client, &start_epoch_key)
+                    .await;
             let end_epoch =
-                query_storage_value::<Epoch>(&client, &end_epoch_key).await;
+                query_cached_storage_value::<Epoch>(&client, &end_epoch_key)
+                    .await;
 
             match (start_epoch, end_epoch) {
                 (Some(start_epoch), Some(end_epoch)) => {
@@ -504,6 +1203,21 @@ pub async fn query_protocol_parameters(
         .expect("Parameter should be definied.");
     println!("{:4}Max. block duration: {}", "", max_block_duration);
 
+    let epoch_info = query_epoch_info(&client).await;
+    match epoch_info.start_height {
+        Some(height) => println!(
+            "{:4}Current epoch: {}, started at height {}",
+            "", epoch_info.epoch, height
+        ),
+        None => println!("{:4}Current epoch: {}", "", epoch_info.epoch),
+    }
+    let next_epoch = epoch_info.epoch.next();
+    if let Some(time) =
+        epoch_info.estimated_start_time(next_epoch, DateTimeUtc::now())
+    {
+        println!("{:4}Next epoch expected by: {}", "", time.to_rfc3339());
+    }
+
     let key = param_storage::get_tx_whitelist_storage_key();
     let vp_whitelist = query_storage_value::<Vec<String>>(&client, &key)
         .await
@@ -527,10 +1241,7 @@ pub async fn query_protocol_parameters(
     );
 
     println!("PoS parameters");
-    let key = pos::params_key();
-    let pos_params = query_storage_value::<PosParams>(&client, &key)
-        .await
-        .expect("Parameter should be definied.");
+    let pos_params = query_pos_params(&client).await;
     println!(
         "{:4}Block proposer reward: {}",
         "", pos_params.block_proposer_reward
@@ -547,6 +1258,45 @@ pub async fn query_protocol_parameters(
         "{:4}Light client attack slash rate: {}",
         "", pos_params.light_client_attack_slash_rate
     );
+    println!(
+        "{:4}Liveness window length: {}",
+        "", pos_params.liveness_window_len
+    );
+    println!(
+        "{:4}Liveness max. missed blocks: {}",
+        "", pos_params.liveness_max_missed_blocks
+    );
+    println!(
+        "{:4}Downtime slash rate: {}",
+        "", pos_params.downtime_slash_rate
+    );
+    println!(
+        "{:4}Unjail cooldown length: {}",
+        "", pos_params.unjail_cooldown_len
+    );
+    println!(
+        "{:4}Min. self-bond: {}",
+        "",
+        Amount::from(pos_params.min_self_bond)
+    );
+    println!(
+        "{:4}Min. delegation: {}",
+        "",
+        Amount::from(pos_params.min_delegation)
+    );
+    println!(
+        "{:4}Max. inflation rate: {}",
+        "", pos_params.max_inflation_rate
+    );
+    println!(
+        "{:4}Min. inflation rate: {}",
+        "", pos_params.min_inflation_rate
+    );
+    println!(
+        "{:4}Target staked ratio: {}",
+        "", pos_params.target_staked_ratio
+    );
+    println!("{:4}Epochs per year: {}", "", pos_params.epochs_per_year);
     println!(
         "{:4}Max. validator slots: {}",
         "", pos_params.max_validator_slots
@@ -556,359 +1306,343 @@ pub async fn query_protocol_parameters(
     println!("{:4}Votes per token: {}", "", pos_params.votes_per_token);
 }
 
-/// Query PoS bond(s)
+/// Query PoS bond(s) and unbond(s), by their validator
 pub async fn query_bonds(ctx: Context, args: args::QueryBonds) {
-    let epoch = query_epoch(args.query.clone()).await;
-    let client = HttpClient::new(args.query.ledger_address).unwrap();
-    match (args.owner, args.validator) {
-        (Some(owner), Some(validator)) => {
-            let source = ctx.get(&owner);
-            let validator = ctx.get(&validator);
-            // Find owner's delegations to the given validator
-            let bond_id = pos::BondId { source, validator };
-            let bond_key = pos::bond_key(&bond_id);
-            let bonds =
-                query_storage_value::<pos::Bonds>(&client, &bond_key).await;
-            // Find owner's unbonded delegations from the given
-            // validator
-            let unbond_key = pos::unbond_key(&bond_id);
-            let unbonds =
-                query_storage_value::<pos::Unbonds>(&client, &unbond_key).await;
-            // Find validator's slashes, if any
-            let slashes_key = pos::validator_slashes_key(&bond_id.validator);
-            let slashes =
-                query_storage_value::<pos::Slashes>(&client, &slashes_key)
-                    .await
-                    .unwrap_or_default();
-
-            let stdout = io::stdout();
-            let mut w = stdout.lock();
+    let owner = ctx.get_opt(&args.owner);
+    let validator = ctx.get_opt(&args.validator);
+    let result = query_bonds_result(args.query, owner, validator).await;
+    output::print(ctx.global_args.output, &result);
+}
 
-            if let Some(bonds) = &bonds {
-                let bond_type = if bond_id.source == bond_id.validator {
-                    "Self-bonds"
-                } else {
-                    "Delegations"
-                };
-                writeln!(w, "{}:", bond_type).unwrap();
-                process_bonds_query(
-                    bonds, &slashes, &epoch, None, None, None, &mut w,
-                );
+/// Query PoS bond(s) and unbond(s) of the given owner and/or validator, with
+/// the slashes of the validators applied
+async fn query_bonds_result(
+    args: args::Query,
+    owner: Option<Address>,
+    validator: Option<Address>,
+) -> BondQueryResult {
+    let client = HttpClient::new(args.ledger_address.clone()).unwrap();
+    let path = Path::Bonds { owner, validator };
+    let response = abci_query(&client, path, vec![], None, false)
+        .await
+        .unwrap();
+    let validators = match response.code {
+        Code::Ok => {
+            match Vec::<pos::BondQueryResult>::try_from_slice(
+                &response.value[..],
+            ) {
+                Ok(validators) => validators,
+                Err(err) => {
+                    eprintln!("Error decoding the bonds: {}", err);
+                    cli::safe_exit(1)
+                }
             }
+        }
+        // No bonds found
+        Code::Err(1) => vec![],
+        Code::Err(err) => {
+            eprintln!(
+                "Error in the query {} (error code {})",
+                response.info, err
+            );
+            cli::safe_exit(1)
+        }
+    };
+    let epoch = match validators.first() {
+        Some(result) => result.epoch,
+        None => fetch_epoch(args).await,
+    };
 
-            if let Some(unbonds) = &unbonds {
-                let bond_type = if bond_id.source == bond_id.validator {
-                    "Unbonded self-bonds"
-                } else {
-                    "Unbonded delegations"
-                };
-                writeln!(w, "{}:", bond_type).unwrap();
-                process_unbonds_query(
-                    unbonds, &slashes, &epoch, None, None, None, &mut w,
-                );
-            }
+    let mut result = BondQueryResult {
+        epoch,
+        ..Default::default()
+    };
+    for pos::BondQueryResult {
+        validator,
+        bonds,
+        unbonds,
+        ..
+    } in validators
+    {
+        let validator_bonds = result.validators.entry(validator).or_default();
+        for bond in bonds {
+            validator_bonds.add_bond(BondEntry {
+                source: bond.source,
+                start_epoch: bond.start_epoch,
+                amount: bond.amount,
+                slashed: bond.slashed,
+                active: bond.active,
+            });
+        }
+        for unbond in unbonds {
+            validator_bonds.add_unbond(UnbondEntry {
+                source: unbond.source,
+                start_epoch: unbond.start_epoch,
+                withdraw_epoch: unbond.withdraw_epoch,
+                amount: unbond.amount,
+                slashed: unbond.slashed,
+                withdrawable: unbond.withdrawable,
+            });
+        }
+    }
+    result
+}
 
-            if bonds.is_none() && unbonds.is_none() {
-                writeln!(
-                    w,
-                    "No delegations found for {} to validator {}",
-                    bond_id.source,
-                    bond_id.validator.encode()
-                )
-                .unwrap();
+/// Query the pending rewards of the bonds of the given owner and/or validator
+pub async fn query_rewards(ctx: Context, args: args::QueryBonds) {
+    let owner = ctx.get_opt(&args.owner);
+    let validator = ctx.get_opt(&args.validator);
+    let client = HttpClient::new(args.query.ledger_address.clone()).unwrap();
+    let path = Path::Rewards { owner, validator };
+    let response = abci_query(&client, path, vec![], None, false)
+        .await
+        .unwrap();
+    let results = match response.code {
+        Code::Ok => {
+            match Vec::<pos::RewardsQueryResult>::try_from_slice(
+                &response.value[..],
+            ) {
+                Ok(results) => results,
+                Err(err) => {
+                    eprintln!("Error decoding the rewards: {}", err);
+                    cli::safe_exit(1)
+                }
             }
         }
-        (None, Some(validator)) => {
-            let validator = ctx.get(&validator);
-            // Find validator's self-bonds
-            let bond_id = pos::BondId {
-                source: validator.clone(),
-                validator,
-            };
-            let bond_key = pos::bond_key(&bond_id);
-            let bonds =
-                query_storage_value::<pos::Bonds>(&client, &bond_key).await;
-            // Find validator's unbonded self-bonds
-            let unbond_key = pos::unbond_key(&bond_id);
-            let unbonds =
-                query_storage_value::<pos::Unbonds>(&client, &unbond_key).await;
-            // Find validator's slashes, if any
-            let slashes_key = pos::validator_slashes_key(&bond_id.validator);
-            let slashes =
-                query_storage_value::<pos::Slashes>(&client, &slashes_key)
-                    .await
-                    .unwrap_or_default();
-
-            let stdout = io::stdout();
-            let mut w = stdout.lock();
-
-            if let Some(bonds) = &bonds {
-                writeln!(w, "Self-bonds:").unwrap();
-                process_bonds_query(
-                    bonds, &slashes, &epoch, None, None, None, &mut w,
-                );
+        // No bonds found
+        Code::Err(1) => vec![],
+        Code::Err(err) => {
+            eprintln!(
+                "Error in the query {} (error code {})",
+                response.info, err
+            );
+            cli::safe_exit(1)
+        }
+    };
+    let epoch = match results.first() {
+        Some(result) => result.epoch,
+        None => fetch_epoch(args.query).await,
+    };
+    let mut pending_total = token::Amount::default();
+    let rewards = results
+        .into_iter()
+        .map(|result| {
+            pending_total += result.pending;
+            RewardsEntry {
+                source: result.source,
+                validator: result.validator,
+                claimed_epoch: result.claimed_epoch,
+                pending: result.pending,
             }
+        })
+        .collect();
+    let result = RewardsQueryResult {
+        epoch,
+        rewards,
+        pending_total,
+    };
+    output::print(ctx.global_args.output, &result);
+}
 
-            if let Some(unbonds) = &unbonds {
-                writeln!(w, "Unbonded self-bonds:").unwrap();
-                process_unbonds_query(
-                    unbonds, &slashes, &epoch, None, None, None, &mut w,
-                );
-            }
-
-            if bonds.is_none() && unbonds.is_none() {
-                writeln!(
-                    w,
-                    "No self-bonds found for validator {}",
-                    bond_id.validator.encode()
-                )
-                .unwrap();
+/// Query the validator set of the given epoch, or of the last committed
+/// epoch, with the consensus keys and the voting powers of the validators.
+/// Returns `None` if the validator set of the epoch is not found.
+pub async fn query_validator_set(
+    client: &HttpClient,
+    epoch: Option<Epoch>,
+) -> Option<pos::ValidatorSetQueryResult> {
+    let path = Path::ValidatorSet(epoch);
+    let response = abci_query(client, path, vec![], None, false).await.unwrap();
+    match response.code {
+        Code::Ok => {
+            match pos::ValidatorSetQueryResult::try_from_slice(
+                &response.value[..],
+            ) {
+                Ok(result) => return Some(result),
+                Err(err) => {
+                    eprintln!("Error decoding the validator set: {}", err)
+                }
             }
         }
-        (Some(owner), None) => {
-            let owner = ctx.get(&owner);
-            // Find owner's bonds to any validator
-            let bonds_prefix = pos::bonds_for_source_prefix(&owner);
-            let bonds = query_storage_prefix::<pos::Bonds>(
-                client.clone(),
-                bonds_prefix,
-            )
-            .await;
-            // Find owner's unbonds to any validator
-            let unbonds_prefix = pos::unbonds_for_source_prefix(&owner);
-            let unbonds = query_storage_prefix::<pos::Unbonds>(
-                client.clone(),
-                unbonds_prefix,
-            )
-            .await;
+        Code::Err(1) => return None,
+        Code::Err(err) => eprintln!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        ),
+    }
+    cli::safe_exit(1)
+}
 
-            let mut total: token::Amount = 0.into();
-            let mut total_active: token::Amount = 0.into();
-            let mut any_bonds = false;
-            if let Some(bonds) = bonds {
-                for (key, bonds) in bonds {
-                    match pos::is_bond_key(&key) {
-                        Some(pos::BondId { source, validator }) => {
-                            // Find validator's slashes, if any
-                            let slashes_key =
-                                pos::validator_slashes_key(&validator);
-                            let slashes = query_storage_value::<pos::Slashes>(
-                                &client,
-                                &slashes_key,
-                            )
-                            .await
-                            .unwrap_or_default();
-
-                            let stdout = io::stdout();
-                            let mut w = stdout.lock();
-                            any_bonds = true;
-                            let bond_type: Cow<str> = if source == validator {
-                                "Self-bonds".into()
-                            } else {
-                                format!(
-                                    "Delegations from {} to {}",
-                                    source, validator
-                                )
-                                .into()
-                            };
-                            writeln!(w, "{}:", bond_type).unwrap();
-                            let (tot, tot_active) = process_bonds_query(
-                                &bonds,
-                                &slashes,
-                                &epoch,
-                                Some(&source),
-                                Some(total),
-                                Some(total_active),
-                                &mut w,
-                            );
-                            total = tot;
-                            total_active = tot_active;
-                        }
-                        None => {
-                            panic!("Unexpected storage key {}", key)
-                        }
-                    }
+/// Query the validator set of the given epoch, with the consensus keys and the
+/// voting powers that the validators had in the epoch, which may be a past
+/// epoch within the unbonding length. Returns `None` if the validator set of
+/// the epoch is not kept.
+pub async fn query_validator_set_at(
+    client: &HttpClient,
+    epoch: Epoch,
+) -> Option<pos::ValidatorSetQueryResult> {
+    let path = Path::ValidatorSetAt(epoch);
+    let response = abci_query(client, path, vec![], None, false).await.unwrap();
+    match response.code {
+        Code::Ok => {
+            match pos::ValidatorSetQueryResult::try_from_slice(
+                &response.value[..],
+            ) {
+                Ok(result) => return Some(result),
+                Err(err) => {
+                    eprintln!("Error decoding the validator set: {}", err)
                 }
             }
-            if total_active != 0.into() && total_active != total {
-                println!("Active bonds total: {}", total_active);
-            }
+        }
+        Code::Err(1) => return None,
+        Code::Err(err) => eprintln!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        ),
+    }
+    cli::safe_exit(1)
+}
 
-            let mut total: token::Amount = 0.into();
-            let mut total_withdrawable: token::Amount = 0.into();
-            if let Some(unbonds) = unbonds {
-                for (key, unbonds) in unbonds {
-                    match pos::is_unbond_key(&key) {
-                        Some(pos::BondId { source, validator }) => {
-                            // Find validator's slashes, if any
-                            let slashes_key =
-                                pos::validator_slashes_key(&validator);
-                            let slashes = query_storage_value::<pos::Slashes>(
-                                &client,
-                                &slashes_key,
-                            )
-                            .await
-                            .unwrap_or_default();
-
-                            let stdout = io::stdout();
-                            let mut w = stdout.lock();
-                            any_bonds = true;
-                            let bond_type: Cow<str> = if source == validator {
-                                "Unbonded self-bonds".into()
-                            } else {
-                                format!("Unbonded delegations from {}", source)
-                                    .into()
-                            };
-                            writeln!(w, "{}:", bond_type).unwrap();
-                            let (tot, tot_withdrawable) = process_unbonds_query(
-                                &unbonds,
-                                &slashes,
-                                &epoch,
-                                Some(&source),
-                                Some(total),
-                                Some(total_withdrawable),
-                                &mut w,
-                            );
-                            total = tot;
-                            total_withdrawable = tot_withdrawable;
-                        }
-                        None => {
-                            panic!("Unexpected storage key {}", key)
-                        }
-                    }
+/// Query the total supply of a token, which is tracked by the token or else
+/// summed from its balances by the node. Returns `None` if the token has no
+/// balances, or too many balances to sum without a tracked supply.
+pub async fn query_total_supply(
+    client: &HttpClient,
+    token: &Address,
+) -> Option<token::Amount> {
+    let path = Path::TotalSupply(token.clone());
+    let response = abci_query(client, path, vec![], None, false).await.unwrap();
+    match response.code {
+        Code::Ok => match token::Amount::try_from_slice(&response.value[..]) {
+            Ok(supply) => return Some(supply),
+            Err(err) => eprintln!("Error decoding the total supply: {}", err),
+        },
+        Code::Err(1) => return None,
+        Code::Err(err) => eprintln!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        ),
+    }
+    cli::safe_exit(1)
+}
+
+/// Query the start of the current epoch and the parameters to estimate when
+/// the next epochs start
+pub async fn query_epoch_info(client: &HttpClient) -> EpochInfo {
+    let response = abci_query(client, Path::EpochInfo, vec![], None, false)
+        .await
+        .unwrap();
+    match response.code {
+        Code::Ok => match EpochInfo::try_from_slice(&response.value[..]) {
+            Ok(info) => return info,
+            Err(err) => eprintln!("Error decoding the epoch info: {}", err),
+        },
+        Code::Err(err) => eprintln!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        ),
+    }
+    cli::safe_exit(1)
+}
+
+/// Query the ICS-23 proof specs of the Merkle proofs of the storage values.
+/// The specs are printed encoded with protobuf, as they're given to the IBC
+/// clients of the chain: first the spec of the proof of a value in its
+/// subtree, then the one of the proofs of the roots of the subtrees.
+pub async fn query_proof_specs(args: args::Query) -> Vec<ProofSpec> {
+    let client = HttpClient::new(args.ledger_address).unwrap();
+    let response = abci_query(&client, Path::ProofSpecs, vec![], None, false)
+        .await
+        .unwrap();
+    match response.code {
+        Code::Ok => match Vec::<Vec<u8>>::try_from_slice(&response.value[..])
+            .map_err(|err| err.to_string())
+            .and_then(|encoded| {
+                let specs = encoded
+                    .iter()
+                    .map(|spec| ProofSpec::decode(&spec[..]))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|err| err.to_string())?;
+                Ok((encoded, specs))
+            }) {
+            Ok((encoded, specs)) => {
+                for spec in encoded {
+                    println!("{}", hex::encode_upper(spec));
                 }
+                return specs;
             }
-            if total_withdrawable != 0.into() {
-                println!("Withdrawable total: {}", total_withdrawable);
-            }
+            Err(err) => eprintln!("Error decoding the proof specs: {}", err),
+        },
+        Code::Err(err) => eprintln!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        ),
+    }
+    cli::safe_exit(1)
+}
 
-            if !any_bonds {
-                println!("No self-bonds or delegations found for {}", owner);
-            }
-        }
-        (None, None) => {
-            // Find all the bonds
-            let bonds_prefix = pos::bonds_prefix();
-            let bonds = query_storage_prefix::<pos::Bonds>(
-                client.clone(),
-                bonds_prefix,
-            )
-            .await;
-            // Find all the unbonds
-            let unbonds_prefix = pos::unbonds_prefix();
-            let unbonds = query_storage_prefix::<pos::Unbonds>(
-                client.clone(),
-                unbonds_prefix,
-            )
-            .await;
+/// Query the PoS parameters
+pub async fn query_pos_params(client: &HttpClient) -> PosParams {
+    let path = Path::PosParams;
+    let response = abci_query(client, path, vec![], None, false).await.unwrap();
+    match response.code {
+        Code::Ok => match PosParams::try_from_slice(&response.value[..]) {
+            Ok(params) => return params,
+            Err(err) => eprintln!("Error decoding the PoS parameters: {}", err),
+        },
+        Code::Err(err) => eprintln!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        ),
+    }
+    cli::safe_exit(1)
+}
 
-            let mut total: token::Amount = 0.into();
-            let mut total_active: token::Amount = 0.into();
-            if let Some(bonds) = bonds {
-                for (key, bonds) in bonds {
-                    match pos::is_bond_key(&key) {
-                        Some(pos::BondId { source, validator }) => {
-                            // Find validator's slashes, if any
-                            let slashes_key =
-                                pos::validator_slashes_key(&validator);
-                            let slashes = query_storage_value::<pos::Slashes>(
-                                &client,
-                                &slashes_key,
-                            )
-                            .await
-                            .unwrap_or_default();
-
-                            let stdout = io::stdout();
-                            let mut w = stdout.lock();
-                            let bond_type = if source == validator {
-                                format!("Self-bonds for {}", validator.encode())
-                            } else {
-                                format!(
-                                    "Delegations from {} to validator {}",
-                                    source,
-                                    validator.encode()
-                                )
-                            };
-                            writeln!(w, "{}:", bond_type).unwrap();
-                            let (tot, tot_active) = process_bonds_query(
-                                &bonds,
-                                &slashes,
-                                &epoch,
-                                Some(&source),
-                                Some(total),
-                                Some(total_active),
-                                &mut w,
-                            );
-                            total = tot;
-                            total_active = tot_active;
-                        }
-                        None => {
-                            panic!("Unexpected storage key {}", key)
-                        }
-                    }
+/// Query the estimate of the inflation of the staking token in the last
+/// committed epoch
+pub async fn query_inflation(args: args::Query) -> pos::InflationQueryResult {
+    let client = HttpClient::new(args.ledger_address).unwrap();
+    let path = Path::Inflation;
+    let response = abci_query(&client, path, vec![], None, false)
+        .await
+        .unwrap();
+    match response.code {
+        Code::Ok => {
+            match pos::InflationQueryResult::try_from_slice(&response.value[..])
+            {
+                Ok(inflation) => {
+                    println!("Epoch: {}", inflation.epoch);
+                    println!("Total supply: {}", inflation.total_supply);
+                    println!(
+                        "Staked: {} ({} of the total supply)",
+                        inflation.staked, inflation.staked_ratio
+                    );
+                    println!(
+                        "Annual inflation rate: {}",
+                        inflation.inflation_rate
+                    );
+                    println!(
+                        "Minted at the end of the epoch: {}",
+                        inflation.epoch_inflation
+                    );
+                    println!(
+                        "Estimated APR of the staking rewards, before the \
+                         commissions: {}",
+                        inflation.apr
+                    );
+                    return inflation;
                 }
-            }
-            if total_active != 0.into() && total_active != total {
-                println!("Bond total active: {}", total_active);
-            }
-            println!("Bond total: {}", total);
-
-            let mut total: token::Amount = 0.into();
-            let mut total_withdrawable: token::Amount = 0.into();
-            if let Some(unbonds) = unbonds {
-                for (key, unbonds) in unbonds {
-                    match pos::is_unbond_key(&key) {
-                        Some(pos::BondId { source, validator }) => {
-                            // Find validator's slashes, if any
-                            let slashes_key =
-                                pos::validator_slashes_key(&validator);
-                            let slashes = query_storage_value::<pos::Slashes>(
-                                &client,
-                                &slashes_key,
-                            )
-                            .await
-                            .unwrap_or_default();
-
-                            let stdout = io::stdout();
-                            let mut w = stdout.lock();
-                            let bond_type = if source == validator {
-                                format!(
-                                    "Unbonded self-bonds for {}",
-                                    validator.encode()
-                                )
-                            } else {
-                                format!(
-                                    "Unbonded delegations from {} to \
-                                     validator {}",
-                                    source,
-                                    validator.encode()
-                                )
-                            };
-                            writeln!(w, "{}:", bond_type).unwrap();
-                            let (tot, tot_withdrawable) = process_unbonds_query(
-                                &unbonds,
-                                &slashes,
-                                &epoch,
-                                Some(&source),
-                                Some(total),
-                                Some(total_withdrawable),
-                                &mut w,
-                            );
-                            total = tot;
-                            total_withdrawable = tot_withdrawable;
-                        }
-                        None => {
-                            panic!("Unexpected storage key {}", key)
-                        }
-                    }
+                Err(err) => {
+                    eprintln!("Error decoding the inflation: {}", err)
                 }
             }
-            if total_withdrawable != 0.into() {
-                println!("Withdrawable total: {}", total_withdrawable);
-            }
-            println!("Unbonded total: {}", total);
         }
+        Code::Err(err) => eprintln!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        ),
     }
+    cli::safe_exit(1)
 }
 
 /// Query PoS voting power
@@ -950,15 +1684,31 @@ pub async fn query_voting_power(ctx: Context, args: args::QueryVotingPower) {
                         voting_power,
                     };
                     let is_active = validator_set.active.contains(&weighted);
-                    if !is_active {
-                        debug_assert!(
-                            validator_set.inactive.contains(&weighted)
-                        );
-                    }
+                    let state = query_storage_value::<pos::ValidatorStates>(
+                        &client,
+                        &pos::validator_state_key(&validator),
+                    )
+                    .await;
+                    // A jailed validator is not in the validator set
+                    let status = match state
+                        .as_ref()
+                        .and_then(|state| state.get(epoch))
+                    {
+                        Some(ValidatorState::Jailed {
+                            epoch: jailed_epoch,
+                        }) => format!("jailed since epoch {}", jailed_epoch),
+                        _ if is_active => "active".to_string(),
+                        _ => {
+                            debug_assert!(validator_set
+                                .inactive
+                                .contains(&weighted));
+                            "inactive".to_string()
+                        }
+                    };
                     println!(
                         "Validator {} is {}, voting power: {}",
                         validator.encode(),
-                        if is_active { "active" } else { "inactive" },
+                        status,
                         voting_power
                     )
                 }
@@ -972,121 +1722,500 @@ pub async fn query_voting_power(ctx: Context, args: args::QueryVotingPower) {
             let stdout = io::stdout();
             let mut w = stdout.lock();
 
-            writeln!(w, "Active validators:").unwrap();
-            for active in &validator_set.active {
-                writeln!(
-                    w,
-                    "  {}: {}",
-                    active.address.encode(),
-                    active.voting_power
-                )
-                .unwrap();
-            }
-            if !validator_set.inactive.is_empty() {
-                writeln!(w, "Inactive validators:").unwrap();
-                for inactive in &validator_set.inactive {
-                    writeln!(
-                        w,
-                        "  {}: {}",
-                        inactive.address.encode(),
-                        inactive.voting_power
-                    )
-                    .unwrap();
-                }
+            writeln!(w, "Active validators:").unwrap();
+            for active in &validator_set.active {
+                writeln!(
+                    w,
+                    "  {}: {}",
+                    active.address.encode(),
+                    active.voting_power
+                )
+                .unwrap();
+            }
+            if !validator_set.inactive.is_empty() {
+                writeln!(w, "Inactive validators:").unwrap();
+                for inactive in &validator_set.inactive {
+                    writeln!(
+                        w,
+                        "  {}: {}",
+                        inactive.address.encode(),
+                        inactive.voting_power
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+    let total_voting_power_key = pos::total_voting_power_key();
+    let total_voting_powers = query_storage_value::<pos::TotalVotingPowers>(
+        &client,
+        &total_voting_power_key,
+    )
+    .await
+    .expect("Total voting power should always be set");
+    let total_voting_power = total_voting_powers
+        .get(epoch)
+        .expect("Total voting power should be always set in the current epoch");
+    println!("Total voting power: {}", total_voting_power);
+}
+
+/// Query PoS slashes of the given validator, or of all the validators, in
+/// the given range of epochs. The slashes are fetched in pages of at most
+/// [`MAX_SLASHES_PAGE_LIMIT`] slashes.
+pub async fn query_slashes(ctx: Context, args: args::QuerySlashes) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let validator = ctx.get_opt(&args.validator);
+    let mut slashes = vec![];
+    let mut start = None;
+    loop {
+        let path = Path::Slashes {
+            validator: validator.clone(),
+            from_epoch: args.from_epoch,
+            to_epoch: args.to_epoch,
+            start,
+            limit: MAX_SLASHES_PAGE_LIMIT,
+        };
+        let response = abci_query(&client, path, vec![], None, false)
+            .await
+            .unwrap();
+        let page = match response.code {
+            Code::Ok => {
+                match pos::SlashesPage::try_from_slice(&response.value[..]) {
+                    Ok(page) => page,
+                    Err(err) => {
+                        eprintln!("Error decoding the slashes: {}", err);
+                        cli::safe_exit(1)
+                    }
+                }
+            }
+            Code::Err(err) => {
+                eprintln!(
+                    "Error in the query {} (error code {})",
+                    response.info, err
+                );
+                cli::safe_exit(1)
+            }
+        };
+        slashes.extend(page.slashes.into_iter().map(
+            |pos::ValidatorSlash { validator, slash }| {
+                SlashEntry::new(validator, slash)
+            },
+        ));
+        start = page.next;
+        if start.is_none() {
+            break;
+        }
+    }
+    let result = SlashQueryResult { validator, slashes };
+    output::print(ctx.global_args.output, &result);
+}
+
+/// Query the keys of a validator, with its consensus keys from the current
+/// epoch until the pipeline epoch
+pub async fn query_validator_keys(
+    ctx: Context,
+    args: args::QueryValidatorKeys,
+) {
+    let epoch = query_epoch(args.query.clone()).await;
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let validator = ctx.get(&args.validator);
+
+    let consensus_key_key = pos::validator_consensus_key_key(&validator);
+    let consensus_keys = query_storage_value::<pos::ValidatorConsensusKeys>(
+        &client,
+        &consensus_key_key,
+    )
+    .await
+    .unwrap_or_else(|| {
+        eprintln!(
+            "The address {} doesn't belong to any known validator account.",
+            validator
+        );
+        cli::safe_exit(1)
+    });
+    let params = query_pos_params(&client).await;
+    // Only the epochs in which the key changes are listed
+    let mut entries: Vec<ConsensusKeyEntry> = vec![];
+    for epoch in (epoch.0..=epoch.0 + params.pipeline_len).map(Epoch) {
+        if let Some(consensus_key) = consensus_keys.get(epoch) {
+            let is_changed = entries
+                .last()
+                .map(|entry| entry.consensus_key != *consensus_key)
+                .unwrap_or(true);
+            if is_changed {
+                entries.push(ConsensusKeyEntry {
+                    epoch,
+                    consensus_key: consensus_key.clone(),
+                    tm_address: light_client::tm_address_of(consensus_key),
+                });
+            }
+        }
+    }
+
+    let protocol_key = query_storage_value::<common::PublicKey>(
+        &client,
+        &protocol_pk_key(&validator),
+    )
+    .await;
+    let account_key =
+        query_storage_value::<common::PublicKey>(&client, &pk_key(&validator))
+            .await;
+    let staking_reward_address = query_storage_value::<Address>(
+        &client,
+        &pos::validator_staking_reward_address_key(&validator),
+    )
+    .await;
+    let staking_reward_key = match &staking_reward_address {
+        Some(address) => {
+            query_storage_value::<common::PublicKey>(&client, &pk_key(address))
+                .await
+        }
+        None => None,
+    };
+    let result = ValidatorKeysQueryResult {
+        validator,
+        consensus_keys: entries,
+        protocol_key,
+        account_key,
+        staking_reward_address,
+        staking_reward_key,
+    };
+    output::print(ctx.global_args.output, &result);
+}
+
+/// Query the txs indexed by the node
+pub async fn query_txs(ctx: Context, args: args::QueryTxs) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    if let Some(sender) = args.sender {
+        let sender = ctx.get(&sender);
+        return query_txs_by_sender(&client, sender).await;
+    }
+    let owner = args.owner.map(|owner| ctx.get(&owner));
+    let token = args.token.map(|token| ctx.get(&token));
+    let path = match (&owner, &token) {
+        (Some(owner), _) => Path::TxsByAddress(owner.clone()),
+        (None, Some(token)) => Path::TxsByToken(token.clone()),
+        (None, None) => {
+            eprintln!("An owner, a token or a sender address is required.");
+            cli::safe_exit(1)
+        }
+    };
+    let txs = match query_indexer::<Vec<IndexedTx>>(&client, path).await {
+        Some(txs) => txs,
+        None => cli::safe_exit(1),
+    };
+    let txs = txs.into_iter().filter_map(|mut tx| match &token {
+        Some(token) => {
+            tx.movements.retain(|movement| &movement.token == token);
+            if tx.movements.is_empty() {
+                None
+            } else {
+                Some(tx)
+            }
+        }
+        None => Some(tx),
+    });
+
+    let tokens = address::tokens();
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    let mut found = false;
+    for tx in txs {
+        found = true;
+        writeln!(
+            w,
+            "Height {}, index {}: tx {}, code {}, gas used {}",
+            tx.height, tx.index, tx.hash, tx.code, tx.gas_used
+        )
+        .unwrap();
+        for movement in tx.movements {
+            let sign = if movement.change < 0 { "-" } else { "+" };
+            let amount =
+                token::Amount::from(movement.change.unsigned_abs() as u64);
+            let currency_code = tokens
+                .get(&movement.token)
+                .map(|c| Cow::Borrowed(*c))
+                .unwrap_or_else(|| Cow::Owned(movement.token.to_string()));
+            writeln!(
+                w,
+                "  {}: {}{} {}",
+                movement.owner, sign, amount, currency_code
+            )
+            .unwrap();
+        }
+    }
+    if !found {
+        println!("No txs found");
+    }
+}
+
+/// Query the results of the txs signed by the sender indexed by the node
+async fn query_txs_by_sender(client: &HttpClient, sender: Address) {
+    let path = Path::TxsBySender(sender);
+    let responses = match query_indexer::<Vec<TxResponse>>(client, path).await {
+        Some(responses) => responses,
+        None => cli::safe_exit(1),
+    };
+    if responses.is_empty() {
+        println!("No txs found");
+    }
+    for response in responses {
+        println!(
+            "Height {}: tx {}, code {}, gas used {}",
+            response.height, response.hash, response.code, response.gas_used
+        );
+        if !response.info.is_empty() {
+            println!("  {}", response.info);
+        }
+    }
+}
+
+/// Query the token transfer history of an address indexed by the node
+pub async fn query_transfers(ctx: Context, args: args::QueryTransfers) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let owner = ctx.get(&args.address);
+    let path = Path::TransferHistory(owner.clone());
+    let transfers =
+        match query_indexer::<Vec<TransferRecord>>(&client, path).await {
+            Some(transfers) => transfers,
+            None => cli::safe_exit(1),
+        };
+    let transfers = transfers.into_iter().filter(|transfer| {
+        args.from_height
+            .map_or(true, |from_height| transfer.height >= from_height)
+            && args
+                .to_height
+                .map_or(true, |to_height| transfer.height <= to_height)
+    });
+
+    let tokens = address::tokens();
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    let mut found = false;
+    for transfer in transfers {
+        found = true;
+        let currency_code = tokens
+            .get(&transfer.token)
+            .map(|c| Cow::Borrowed(*c))
+            .unwrap_or_else(|| Cow::Owned(transfer.token.to_string()));
+        let (direction, preposition) = match transfer.direction {
+            TransferDirection::Incoming => ("Received", "from"),
+            TransferDirection::Outgoing => ("Sent", "to"),
+        };
+        let counterparty = transfer
+            .counterparty
+            .map(|counterparty| counterparty.to_string())
+            .unwrap_or_else(|| "multiple addresses".to_string());
+        writeln!(
+            w,
+            "Height {}: {} {} {} {} {} in tx {}",
+            transfer.height,
+            direction,
+            transfer.amount,
+            currency_code,
+            preposition,
+            counterparty,
+            transfer.tx_hash
+        )
+        .unwrap();
+    }
+    if !found {
+        println!("No transfers found for {}", owner);
+    }
+}
+
+/// Query the results of the txs of a block indexed by the node
+pub async fn query_block_results(args: args::QueryBlockResults) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let path = Path::BlockResults(args.height);
+    let results = match query_indexer::<BlockResults>(&client, path).await {
+        Some(results) => results,
+        None => cli::safe_exit(1),
+    };
+    if results.txs.is_empty() {
+        println!("No txs found in the block at height {}", results.height);
+        return;
+    }
+
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    writeln!(w, "Block at height {}:", results.height).unwrap();
+    for tx in results.txs {
+        writeln!(
+            w,
+            "Index {}: tx {}, code {}, gas used {}",
+            tx.index, tx.hash, tx.code, tx.gas_used
+        )
+        .unwrap();
+        if !tx.info.is_empty() {
+            writeln!(w, "  Info: {}", tx.info).unwrap();
+        }
+        for event in tx.events {
+            writeln!(w, "  Event {}:", event.event_type).unwrap();
+            for (key, value) in event.attributes {
+                writeln!(w, "    {}: {}", key, value).unwrap();
+            }
+        }
+    }
+}
+
+/// Query the indexed result of the tx with the given hash. Returns `None`
+/// if the tx is not indexed or if the node doesn't have the txs indexer
+/// enabled, in which case the result can be found in the events of the
+/// blocks instead.
+pub async fn query_tx_event(
+    client: &HttpClient,
+    hash: Hash,
+) -> Option<TxResponse> {
+    let path = Path::TxEvent(hash);
+    let data = vec![];
+    let response = abci_query(client, path, data, None, false).await.ok()?;
+    match response.code {
+        Code::Ok => Option::<TxResponse>::try_from_slice(&response.value[..])
+            .ok()
+            .flatten(),
+        Code::Err(_) => None,
+    }
+}
+
+/// Query the balances of all the tokens of an owner, with the tokens found
+/// by the txs indexer of the node. Returns `None` if the indexer is not
+/// enabled on the node or the query fails, in which case only the balances of
+/// the known tokens can be queried.
+async fn query_owner_balances(
+    client: &HttpClient,
+    owner: &Address,
+) -> Option<Vec<(Address, token::Amount)>> {
+    let path = Path::OwnerBalances(owner.clone());
+    let response = abci_query(client, path, vec![], None, false).await.ok()?;
+    match response.code {
+        Code::Ok => Vec::try_from_slice(&response.value[..]).ok(),
+        Code::Err(_) => None,
+    }
+}
+
+/// Query a value found by the txs indexer of the node at the given path
+pub async fn query_indexer<T>(client: &HttpClient, path: Path) -> Option<T>
+where
+    T: BorshDeserialize,
+{
+    let data = vec![];
+    let response = abci_query(client, path, data, None, false).await.unwrap();
+    match response.code {
+        Code::Ok => match T::try_from_slice(&response.value[..]) {
+            Ok(value) => return Some(value),
+            Err(err) => eprintln!("Error decoding the indexed value: {}", err),
+        },
+        Code::Err(err) => eprintln!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        ),
+    }
+    None
+}
+
+/// Query an account's current public keys and validity predicate code hash
+pub async fn query_account(ctx: Context, args: args::QueryAccount) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let address = ctx.get(&args.addr);
+    let path = Path::Account(address);
+    let data = vec![];
+    let response = abci_query(&client, path, data, None, false).await.unwrap();
+    let info = match response.code {
+        Code::Ok => match AccountInfo::try_from_slice(&response.value[..]) {
+            Ok(info) => info,
+            Err(err) => {
+                eprintln!("Error decoding the account info: {}", err);
+                cli::safe_exit(1)
             }
+        },
+        Code::Err(err) => {
+            eprintln!(
+                "Error in the query {} (error code {})",
+                response.info, err
+            );
+            cli::safe_exit(1)
         }
+    };
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    writeln!(w, "Account {}:", info.address).unwrap();
+    match info.public_key {
+        Some(pk) => writeln!(w, "  Public key: {}", pk).unwrap(),
+        None => writeln!(w, "  Public key: none").unwrap(),
     }
-    let total_voting_power_key = pos::total_voting_power_key();
-    let total_voting_powers = query_storage_value::<pos::TotalVotingPowers>(
-        &client,
-        &total_voting_power_key,
-    )
-    .await
-    .expect("Total voting power should always be set");
-    let total_voting_power = total_voting_powers
-        .get(epoch)
-        .expect("Total voting power should be always set in the current epoch");
-    println!("Total voting power: {}", total_voting_power);
+    if let Some(pk) = info.protocol_public_key {
+        writeln!(w, "  Protocol public key: {}", pk).unwrap();
+    }
+    match info.vp_code_hash {
+        Some(hash) => writeln!(w, "  VP code hash: {}", hash).unwrap(),
+        None => writeln!(w, "  VP code hash: none").unwrap(),
+    }
+    let usage = query_storage_usage(&client, &info.address).await;
+    writeln!(w, "  Storage: {} keys, {} bytes", usage.keys, usage.bytes)
+        .unwrap();
 }
 
-/// Query PoS slashes
-pub async fn query_slashes(ctx: Context, args: args::QuerySlashes) {
-    let client = HttpClient::new(args.query.ledger_address).unwrap();
-    match args.validator {
-        Some(validator) => {
-            let validator = ctx.get(&validator);
-            // Find slashes for the given validator
-            let slashes_key = pos::validator_slashes_key(&validator);
-            let slashes =
-                query_storage_value::<pos::Slashes>(&client, &slashes_key)
-                    .await;
-            match slashes {
-                Some(slashes) => {
-                    let stdout = io::stdout();
-                    let mut w = stdout.lock();
-                    for slash in slashes {
-                        writeln!(
-                            w,
-                            "Slash epoch {}, rate {}, type {}",
-                            slash.epoch, slash.rate, slash.r#type
-                        )
-                        .unwrap();
-                    }
-                }
-                None => {
-                    println!("No slashes found for {}", validator.encode())
-                }
-            }
+/// Query the number and the size of the keys in the subspace of an account.
+/// The keys are counted in pages, all at the height of the first one.
+pub async fn query_storage_usage(
+    client: &HttpClient,
+    address: &Address,
+) -> StorageUsage {
+    let mut usage = StorageUsage::default();
+    let mut after = None;
+    let mut height = None;
+    loop {
+        let (page, page_height) =
+            query_storage_usage_page(client, address, after, height).await;
+        if page_height.value() > 0 {
+            height = height.or(Some(page_height));
         }
-        None => {
-            // Iterate slashes for all validators
-            let slashes_prefix = pos::slashes_prefix();
-            let slashes = query_storage_prefix::<pos::Slashes>(
-                client.clone(),
-                slashes_prefix,
-            )
-            .await;
+        usage.add(&page.usage);
+        match page.next {
+            Some(next) => after = Some(next),
+            None => return usage,
+        }
+    }
+}
 
-            match slashes {
-                Some(slashes) => {
-                    let stdout = io::stdout();
-                    let mut w = stdout.lock();
-                    for (slashes_key, slashes) in slashes {
-                        if let Some(validator) =
-                            is_validator_slashes_key(&slashes_key)
-                        {
-                            for slash in slashes {
-                                writeln!(
-                                    w,
-                                    "Slash epoch {}, block height {}, rate \
-                                     {}, type {}, validator {}",
-                                    slash.epoch,
-                                    slash.block_height,
-                                    slash.rate,
-                                    slash.r#type,
-                                    validator,
-                                )
-                                .unwrap();
-                            }
-                        } else {
-                            eprintln!("Unexpected slashes key {}", slashes_key);
-                        }
-                    }
-                }
-                None => {
-                    println!("No slashes found")
+/// Query a page of the storage usage of an account, whose keys are after the
+/// `after` key, if any. Returns the page with its height.
+async fn query_storage_usage_page(
+    client: &HttpClient,
+    address: &Address,
+    after: Option<storage::Key>,
+    height: Option<Height>,
+) -> (StorageUsagePage, Height) {
+    let path = Path::StorageUsage {
+        address: address.clone(),
+        after,
+    };
+    let response = abci_query(client, path, vec![], height, false)
+        .await
+        .unwrap();
+    match response.code {
+        Code::Ok => {
+            match StorageUsagePage::try_from_slice(&response.value[..]) {
+                Ok(page) => return (page, response.height),
+                Err(err) => {
+                    eprintln!("Error decoding the storage usage: {}", err)
                 }
             }
         }
+        Code::Err(err) => eprintln!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        ),
     }
+    cli::safe_exit(1)
 }
 
 /// Dry run a transaction
 pub async fn dry_run_tx(ledger_address: &TendermintAddress, tx_bytes: Vec<u8>) {
     let client = HttpClient::new(ledger_address.clone()).unwrap();
     let path = Path::DryRunTx;
-    let response = client
-        .abci_query(Some(path.into()), tx_bytes, None, false)
+    let response = abci_query(&client, path, tx_bytes, None, false)
         .await
         .unwrap();
     println!("{:#?}", response);
@@ -1196,140 +2325,106 @@ fn apply_slashes(
     delta
 }
 
-/// Process the result of a blonds query to determine total bonds
-/// and total active bonds. This includes taking into account
-/// an aggregation of slashes since the start of the given epoch.
-fn process_bonds_query(
-    bonds: &Bonds,
-    slashes: &[Slash],
-    epoch: &Epoch,
-    source: Option<&Address>,
-    total: Option<token::Amount>,
-    total_active: Option<token::Amount>,
-    w: &mut std::io::StdoutLock,
-) -> (token::Amount, token::Amount) {
-    let mut total_active = total_active.unwrap_or_else(|| 0.into());
-    let mut current_total: token::Amount = 0.into();
-    for bond in bonds.iter() {
-        for (epoch_start, &(mut delta)) in bond.deltas.iter().sorted() {
-            writeln!(w, "  Active from epoch {}: Δ {}", epoch_start, delta)
-                .unwrap();
-            delta = apply_slashes(slashes, delta, *epoch_start, None, Some(w));
-            current_total += delta;
-            let epoch_start: Epoch = (*epoch_start).into();
-            if epoch >= &epoch_start {
-                total_active += delta;
-            }
-        }
-    }
-    let total = total.unwrap_or_else(|| 0.into()) + current_total;
-    match source {
-        Some(addr) => {
-            writeln!(w, "  Bonded total from {}: {}", addr, current_total)
-                .unwrap();
-        }
-        None => {
-            if total_active != 0.into() && total_active != total {
-                writeln!(w, "Active bonds total: {}", total_active).unwrap();
-            }
-            writeln!(w, "Bonds total: {}", total).unwrap();
-        }
-    }
-    (total, total_active)
+/// Query a storage value and decode it with [`BorshDeserialize`].
+pub async fn query_storage_value<T>(
+    client: &HttpClient,
+    key: &storage::Key,
+) -> Option<T>
+where
+    T: BorshDeserialize + Send,
+{
+    read_storage_value(client, key).await
 }
 
-/// Process the result of an unbonds query to determine total bonds
-/// and total withdrawable bonds. This includes taking into account
-/// an aggregation of slashes since the start of the given epoch up
-/// until the withdrawal epoch.
-fn process_unbonds_query(
-    unbonds: &Unbonds,
-    slashes: &[Slash],
-    epoch: &Epoch,
-    source: Option<&Address>,
-    total: Option<token::Amount>,
-    total_withdrawable: Option<token::Amount>,
-    w: &mut std::io::StdoutLock,
-) -> (token::Amount, token::Amount) {
-    let mut withdrawable = total_withdrawable.unwrap_or_else(|| 0.into());
-    let mut current_total: token::Amount = 0.into();
-    for deltas in unbonds.iter() {
-        for ((epoch_start, epoch_end), &(mut delta)) in
-            deltas.deltas.iter().sorted()
-        {
-            let withdraw_epoch = *epoch_end + 1_u64;
-            writeln!(
-                w,
-                "  Withdrawable from epoch {} (active from {}): Δ {}",
-                withdraw_epoch, epoch_start, delta
-            )
-            .unwrap();
-            delta = apply_slashes(
-                slashes,
-                delta,
-                *epoch_start,
-                Some(withdraw_epoch),
-                Some(w),
-            );
-            current_total += delta;
-            let epoch_end: Epoch = (*epoch_end).into();
-            if epoch > &epoch_end {
-                withdrawable += delta;
-            }
-        }
-    }
-    let total = total.unwrap_or_else(|| 0.into()) + current_total;
-    match source {
-        Some(addr) => {
-            writeln!(w, "  Unbonded total from {}: {}", addr, current_total)
-                .unwrap();
-        }
-        None => {
-            if withdrawable != 0.into() {
-                writeln!(w, "Withdrawable total: {}", withdrawable).unwrap();
-            }
-            writeln!(w, "Unbonded total: {}", total).unwrap();
+/// Query a storage value with a [`caching_client`] and decode it with
+/// [`BorshDeserialize`]. The value is read from the ledger only the first
+/// time at a given block.
+pub async fn query_cached_storage_value<T>(
+    client: &CachingClient<HttpClient>,
+    key: &storage::Key,
+) -> Option<T>
+where
+    T: BorshDeserialize + Send,
+{
+    read_storage_value(client, key).await
+}
+
+async fn read_storage_value<T, C>(client: &C, key: &storage::Key) -> Option<T>
+where
+    T: BorshDeserialize + Send,
+    C: QueryClient,
+{
+    match anoma::client::query_storage_value::<T, _>(client, key).await {
+        Ok(value) => Some(value),
+        Err(StorageValueError::KeyMissing(_)) => None,
+        Err(err) => {
+            eprintln!("{}", err);
+            cli::safe_exit(1)
         }
     }
-    (total, withdrawable)
 }
 
-/// Query a storage value and decode it with [`BorshDeserialize`].
-pub async fn query_storage_value<T>(
+/// Query the values of multiple storage keys at the same height and decode
+/// them with [`BorshDeserialize`]. Returns the values in the order of the
+/// keys, with `None` for the keys that have no value. The keys are read in
+/// batches of at most [`MAX_VALUES_KEYS`] keys, all at the height of the first
+/// batch.
+pub async fn query_storage_values<T>(
     client: &HttpClient,
-    key: &storage::Key,
-) -> Option<T>
+    keys: &[storage::Key],
+) -> Vec<Option<T>>
 where
     T: BorshDeserialize,
 {
-    let path = Path::Value(key.to_owned());
-    let data = vec![];
-    let response = client
-        .abci_query(Some(path.into()), data, None, false)
-        .await
-        .unwrap();
-    match response.code {
-        Code::Ok => match T::try_from_slice(&response.value[..]) {
-            Ok(value) => return Some(value),
-            Err(err) => eprintln!("Error decoding the value: {}", err),
-        },
-        Code::Err(err) => {
-            if err == 1 {
-                return None;
-            } else {
+    let mut values = Vec::with_capacity(keys.len());
+    let mut height = None;
+    for batch in keys.chunks(MAX_VALUES_KEYS) {
+        let path = Path::Values(batch.to_vec());
+        let data = vec![];
+        let response =
+            abci_query(client, path, data, height, false).await.unwrap();
+        let result = match response.code {
+            Code::Ok => {
+                match MultiValueResult::try_from_slice(&response.value[..]) {
+                    Ok(result) => result,
+                    Err(err) => {
+                        eprintln!("Error decoding the values: {}", err);
+                        cli::safe_exit(1)
+                    }
+                }
+            }
+            Code::Err(err) => {
                 eprintln!(
                     "Error in the query {} (error code {})",
                     response.info, err
-                )
+                );
+                cli::safe_exit(1)
             }
-        }
+        };
+        height = Some(Height::try_from(result.height.0).unwrap());
+        values.extend(result.values.into_iter().map(|(key, value)| {
+            let value = value?;
+            match T::try_from_slice(&value[..]) {
+                Ok(value) => Some(value),
+                Err(err) => {
+                    eprintln!(
+                        "Skipping a value for key {}. Error in decoding: {}",
+                        key, err
+                    );
+                    None
+                }
+            }
+        }));
     }
-    cli::safe_exit(1)
+    values
 }
 
 /// Query a range of storage values with a matching prefix and decode them with
 /// [`BorshDeserialize`]. Returns an iterator of the storage keys paired with
-/// their associated values.
+/// their associated values, or `None` if no value is found. The values are
+/// fetched in pages of at most [`MAX_PREFIX_PAGE_LIMIT`] values, so that a
+/// large range doesn't have to fit in a single response. The pages are read
+/// at the height of the first one.
 pub async fn query_storage_prefix<T>(
     client: HttpClient,
     key: storage::Key,
@@ -1337,45 +2432,70 @@ pub async fn query_storage_prefix<T>(
 where
     T: BorshDeserialize,
 {
-    let path = Path::Prefix(key);
-    let data = vec![];
-    let response = client
-        .abci_query(Some(path.into()), data, None, false)
-        .await
-        .unwrap();
-    match response.code {
-        Code::Ok => {
-            match Vec::<PrefixValue>::try_from_slice(&response.value[..]) {
-                Ok(values) => {
-                    let decode = |PrefixValue { key, value }: PrefixValue| {
-                        match T::try_from_slice(&value[..]) {
-                            Err(err) => {
-                                eprintln!(
-                                    "Skipping a value for key {}. Error in \
-                                     decoding: {}",
-                                    key, err
-                                );
-                                None
-                            }
-                            Ok(value) => Some((key, value)),
-                        }
-                    };
-                    return Some(values.into_iter().filter_map(decode));
-                }
-                Err(err) => eprintln!("Error decoding the values: {}", err),
-            }
-        }
-        Code::Err(err) => {
-            if err == 1 {
-                return None;
-            } else {
+    let decode = |PrefixValue { key, value }: PrefixValue| {
+        let decoded = T::try_from_slice(&value[..]);
+        match decoded {
+            Err(err) => {
                 eprintln!(
-                    "Error in the query {} (error code {})",
-                    response.info, err
-                )
+                    "Skipping a value for key {}. Error in decoding: {}",
+                    key, err
+                );
+                None
             }
+            Ok(value) => Some((key, value)),
+        }
+    };
+    let mut values = vec![];
+    let mut after = None;
+    let mut height = None;
+    loop {
+        let (page, page_height) =
+            query_storage_prefix_page(&client, key.clone(), after, height)
+                .await;
+        // A node that doesn't report the height of the pages answers them at
+        // its last block
+        if page_height.value() > 0 {
+            height = height.or(Some(page_height));
+        }
+        values.extend(page.values.into_iter().filter_map(decode));
+        match page.next {
+            Some(next) => after = Some(next),
+            None => break,
         }
     }
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.into_iter())
+    }
+}
+
+/// Query a page of the storage values with a matching prefix, whose keys are
+/// after the `after` key, if any, at the given height or at the last
+/// committed block. Returns the page with its height.
+async fn query_storage_prefix_page(
+    client: &HttpClient,
+    prefix: storage::Key,
+    after: Option<storage::Key>,
+    height: Option<Height>,
+) -> (PrefixPage, Height) {
+    let path = Path::PrefixPage {
+        prefix,
+        after,
+        limit: MAX_PREFIX_PAGE_LIMIT,
+    };
+    let data = vec![];
+    let response = abci_query(client, path, data, height, false).await.unwrap();
+    match response.code {
+        Code::Ok => match PrefixPage::try_from_slice(&response.value[..]) {
+            Ok(page) => return (page, response.height),
+            Err(err) => eprintln!("Error decoding the values: {}", err),
+        },
+        Code::Err(err) => eprintln!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        ),
+    }
     cli::safe_exit(1)
 }
 
@@ -1386,10 +2506,7 @@ pub async fn query_has_storage_key(
 ) -> bool {
     let path = Path::HasKey(key);
     let data = vec![];
-    let response = client
-        .abci_query(Some(path.into()), data, None, false)
-        .await
-        .unwrap();
+    let response = abci_query(&client, path, data, None, false).await.unwrap();
     match response.code {
         Code::Ok => match bool::try_from_slice(&response.value[..]) {
             Ok(value) => return value,
@@ -1405,6 +2522,52 @@ pub async fn query_has_storage_key(
     cli::safe_exit(1)
 }
 
+/// Query the storage keys with a matching prefix, if any, that were changed
+/// in the blocks above the `from` height up to the `to` height, ordered by
+/// their keys. The range is queried in parts of at most
+/// [`MAX_CHANGED_KEYS_BLOCKS`] blocks.
+pub async fn query_changed_keys(
+    client: &HttpClient,
+    prefix: Option<storage::Key>,
+    from: storage::BlockHeight,
+    to: storage::BlockHeight,
+) -> Vec<storage::Key> {
+    let mut keys = BTreeSet::new();
+    let mut part_from = from;
+    while part_from < to {
+        let part_to = storage::BlockHeight(
+            (part_from.0 + MAX_CHANGED_KEYS_BLOCKS).min(to.0),
+        );
+        let path = Path::ChangedKeys {
+            from: part_from,
+            to: part_to,
+            prefix: prefix.clone(),
+        };
+        let response =
+            abci_query(client, path, vec![], None, false).await.unwrap();
+        match response.code {
+            Code::Ok => {
+                match Vec::<storage::Key>::try_from_slice(&response.value[..]) {
+                    Ok(part_keys) => keys.extend(part_keys),
+                    Err(err) => {
+                        eprintln!("Error decoding the changed keys: {}", err);
+                        cli::safe_exit(1)
+                    }
+                }
+            }
+            Code::Err(err) => {
+                eprintln!(
+                    "Error in the query {} (error code {})",
+                    response.info, err
+                );
+                cli::safe_exit(1)
+            }
+        }
+        part_from = part_to;
+    }
+    keys.into_iter().collect()
+}
+
 /// Represents a query for an event pertaining to the specified transaction
 #[derive(Debug, Clone)]
 pub enum TxEventQuery {
@@ -1507,12 +2670,22 @@ pub async fn query_tx_response(
         log: event_map["log"].to_string(),
         height: event_map["height"].to_string(),
         hash: event_map["hash"].to_string(),
-        code: event_map["code"].to_string(),
+        code: event_map["code"].parse().map_err(|err| {
+            TError::server(format!("Invalid result code of the tx: {}", err))
+        })?,
         gas_used: event_map["gas_used"].to_string(),
         initialized_accounts: serde_json::from_str(
             event_map["initialized_accounts"],
         )
         .unwrap_or_default(),
+        rejected_vps: event_map
+            .get("rejected_vps")
+            .and_then(|addresses| serde_json::from_str(addresses).ok())
+            .unwrap_or_default(),
+        rejected_vp_keys: event_map
+            .get("rejected_vp_keys")
+            .and_then(|keys| serde_json::from_str(keys).ok())
+            .unwrap_or_default(),
     };
     // Signal to the driver to terminate.
     client.close()?;
@@ -1524,9 +2697,100 @@ pub async fn query_tx_response(
     Ok(result)
 }
 
+/// The maximum number of txs returned by Tendermint's query of the mempool
+const MAX_UNCONFIRMED_TXS: u64 = 100;
+
+/// Check if the mempool of the ledger contains the given tx. Returns `None`
+/// when it can't be determined, i.e. when the mempool can't be queried or
+/// when it holds more txs than the query returns.
+pub async fn is_tx_in_mempool(
+    ledger_address: &TendermintAddress,
+    tx_bytes: &[u8],
+) -> Option<bool> {
+    let (host, port) = match ledger_address {
+        TendermintAddress::Tcp { host, port, .. } => (host, port),
+        _ => return None,
+    };
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "unconfirmed_txs",
+        "params": { "limit": MAX_UNCONFIRMED_TXS.to_string() },
+    });
+    let response = reqwest::Client::new()
+        .post(format!("http://{}:{}", host, port))
+        .body(request.to_string())
+        .send()
+        .await
+        .ok()?
+        .bytes()
+        .await
+        .ok()?;
+    let response: serde_json::Value = serde_json::from_slice(&response).ok()?;
+    let result = response.get("result")?;
+    let txs = result.get("txs")?.as_array()?;
+    let tx = base64::encode(tx_bytes);
+    if txs.iter().any(|pending| pending.as_str() == Some(&tx)) {
+        return Some(true);
+    }
+    // The tx may be in the part of the mempool that wasn't returned
+    let total: usize = result.get("total")?.as_str()?.parse().ok()?;
+    (total <= txs.len()).then(|| false)
+}
+
+/// Find the results of the txs with an event of the given type, e.g.
+/// [`tendermint_compat::events::APPLIED_EVENT`], whose result code matches the
+/// filter, e.g. to alert on the txs that failed. The blocks with a matching
+/// event are searched from the most recent one, in pages of `per_page`
+/// blocks, and the txs are returned from the given page, ordered by their
+/// block.
+pub async fn find_txs_with_code(
+    ledger_address: &TendermintAddress,
+    event_type: &str,
+    filter: ResultCodeFilter,
+    page: u32,
+    per_page: u8,
+) -> Result<Vec<TxResponse>, TError> {
+    let client = HttpClient::new(ledger_address.clone())?;
+    let blocks = client
+        .block_search(
+            filter.query(event_type),
+            page,
+            per_page,
+            Order::Descending,
+        )
+        .await?
+        .blocks;
+    let mut responses = vec![];
+    for block in blocks {
+        let block_results =
+            client.block_results(block.block.header.height).await?;
+        let events = block_results.end_block_events.unwrap_or_default();
+        for event in events.iter().filter(|event| event.type_str == event_type)
+        {
+            let attributes: BTreeMap<String, String> = event
+                .attributes
+                .iter()
+                .map(|tag| {
+                    (tag.key.as_ref().to_owned(), tag.value.as_ref().to_owned())
+                })
+                .collect();
+            // An event without a known code cannot match the filter
+            if let Some(response) =
+                TxResponse::from_event_attributes(&attributes)
+            {
+                if filter.matches(response.code) {
+                    responses.push(response);
+                }
+            }
+        }
+    }
+    Ok(responses)
+}
+
 /// Lookup the results of applying the specified transaction to the
 /// blockchain.
-pub async fn query_result(_ctx: Context, args: args::QueryResult) {
+pub async fn query_result(ctx: Context, args: args::QueryResult) {
     // First try looking up application event pertaining to given hash.
     let tx_response = query_tx_response(
         &args.query.ledger_address,
@@ -1534,12 +2798,13 @@ pub async fn query_result(_ctx: Context, args: args::QueryResult) {
     )
     .await;
     match tx_response {
-        Ok(result) => {
-            println!(
-                "Transaction was applied with result: {}",
-                serde_json::to_string_pretty(&result).unwrap()
-            )
-        }
+        Ok(result) => output::print(
+            ctx.global_args.output,
+            &TxQueryResult {
+                status: TxStatus::Applied,
+                result,
+            },
+        ),
         Err(err1) => {
             // If this fails then instead look for an acceptance event.
             let tx_response = query_tx_response(
@@ -1548,9 +2813,12 @@ pub async fn query_result(_ctx: Context, args: args::QueryResult) {
             )
             .await;
             match tx_response {
-                Ok(result) => println!(
-                    "Transaction was accepted with result: {}",
-                    serde_json::to_string_pretty(&result).unwrap()
+                Ok(result) => output::print(
+                    ctx.global_args.output,
+                    &TxQueryResult {
+                        status: TxStatus::Accepted,
+                        result,
+                    },
                 ),
                 Err(err2) => {
                     // Print the errors that caused the lookups to fail
@@ -1563,7 +2831,7 @@ pub async fn query_result(_ctx: Context, args: args::QueryResult) {
 }
 
 pub async fn get_proposal_votes(
-    client: &HttpClient,
+    client: &CachingClient<HttpClient>,
     epoch: Epoch,
     proposal_id: u64,
 ) -> Votes {
@@ -1571,9 +2839,11 @@ pub async fn get_proposal_votes(
 
     let vote_prefix_key =
         gov_storage::get_proposal_vote_prefix_key(proposal_id);
-    let vote_iter =
-        query_storage_prefix::<ProposalVote>(client.clone(), vote_prefix_key)
-            .await;
+    let vote_iter = query_storage_prefix::<ProposalVote>(
+        client.inner().clone(),
+        vote_prefix_key,
+    )
+    .await;
 
     let mut yay_validators: HashMap<Address, Amount> = HashMap::new();
     let mut yay_delegators: HashMap<Address, Amount> = HashMap::new();
@@ -1621,7 +2891,7 @@ pub async fn get_proposal_votes(
 }
 
 pub async fn get_proposal_offline_votes(
-    client: &HttpClient,
+    client: &CachingClient<HttpClient>,
     proposal: OfflineProposal,
     files: HashSet<PathBuf>,
 ) -> Votes {
@@ -1639,7 +2909,7 @@ pub async fn get_proposal_offline_votes(
             .expect("JSON was not well-formatted for offline vote.");
 
         let key = pk_key(&proposal_vote.address);
-        let public_key = query_storage_value(client, &key)
+        let public_key = query_cached_storage_value(client, &key)
             .await
             .expect("Public key should exist.");
 
@@ -1660,7 +2930,7 @@ pub async fn get_proposal_offline_votes(
             .await;
             yay_validators.insert(proposal_vote.address, amount);
         } else if is_delegator_at(
-            client,
+            client.inner(),
             &proposal_vote.address,
             proposal.tally_epoch,
         )
@@ -1668,7 +2938,8 @@ pub async fn get_proposal_offline_votes(
         {
             let key = pos::bonds_for_source_prefix(&proposal_vote.address);
             let bonds_iter =
-                query_storage_prefix::<pos::Bonds>(client.clone(), key).await;
+                query_storage_prefix::<pos::Bonds>(client.inner().clone(), key)
+                    .await;
             if let Some(bonds) = bonds_iter {
                 for (key, epoched_amount) in bonds {
                     let bond = epoched_amount
@@ -1704,7 +2975,7 @@ pub async fn get_proposal_offline_votes(
 
 // Compute the result of a proposal
 pub async fn compute_tally(
-    client: &HttpClient,
+    client: &CachingClient<HttpClient>,
     epoch: Epoch,
     votes: Votes,
 ) -> TallyResult {
@@ -1745,20 +3016,22 @@ pub async fn compute_tally(
 }
 
 pub async fn get_bond_amount_at(
-    client: &HttpClient,
+    client: &CachingClient<HttpClient>,
     delegator: &Address,
     validator: &Address,
     epoch: Epoch,
 ) -> Option<token::Amount> {
     let slashes_key = pos::validator_slashes_key(validator);
-    let slashes = query_storage_value::<pos::Slashes>(client, &slashes_key)
-        .await
-        .unwrap_or_default();
+    let slashes =
+        query_cached_storage_value::<pos::Slashes>(client, &slashes_key)
+            .await
+            .unwrap_or_default();
     let bond_key = pos::bond_key(&BondId {
         source: delegator.clone(),
         validator: validator.clone(),
     });
-    let epoched_bonds = query_storage_value::<Bonds>(client, &bond_key).await;
+    let epoched_bonds =
+        query_cached_storage_value::<Bonds>(client, &bond_key).await;
     match epoched_bonds {
         Some(epoched_bonds) => {
             let mut delegated_amount: token::Amount = 0.into();
@@ -1784,14 +3057,16 @@ pub async fn get_bond_amount_at(
 }
 
 pub async fn get_all_validators(
-    client: &HttpClient,
+    client: &CachingClient<HttpClient>,
     epoch: Epoch,
 ) -> Vec<Address> {
     let validator_set_key = pos::validator_set_key();
-    let validator_sets =
-        query_storage_value::<pos::ValidatorSets>(client, &validator_set_key)
-            .await
-            .expect("Validator set should always be set");
+    let validator_sets = query_cached_storage_value::<pos::ValidatorSets>(
+        client,
+        &validator_set_key,
+    )
+    .await
+    .expect("Validator set should always be set");
     let validator_set = validator_sets
         .get(epoch)
         .expect("Validator set should be always set in the current epoch");
@@ -1802,7 +3077,7 @@ pub async fn get_all_validators(
 }
 
 pub async fn get_total_staked_tokes(
-    client: &HttpClient,
+    client: &CachingClient<HttpClient>,
     epoch: Epoch,
     validators: &[Address],
 ) -> token::Amount {
@@ -1815,15 +3090,14 @@ pub async fn get_total_staked_tokes(
 }
 
 async fn get_validator_stake(
-    client: &HttpClient,
+    client: &CachingClient<HttpClient>,
     epoch: Epoch,
     validator: &Address,
 ) -> token::Amount {
     let total_voting_power_key = pos::validator_total_deltas_key(validator);
-    let total_voting_power = query_storage_value::<pos::ValidatorTotalDeltas>(
-        client,
-        &total_voting_power_key,
-    )
+    let total_voting_power = query_cached_storage_value::<
+        pos::ValidatorTotalDeltas,
+    >(client, &total_voting_power_key)
     .await
     .expect("Total deltas should be defined");
     let epoched_total_voting_power = total_voting_power.get(epoch);
@@ -5,18 +5,16 @@ use std::rc::Rc;
 
 use anoma::proto::Tx;
 use anoma::types::address::{Address, ImplicitAddress};
+use anoma::types::chain::ChainId;
 use anoma::types::key::*;
 use anoma::types::storage::Epoch;
-use anoma::types::transaction::{hash_tx, Fee, WrapperTx};
-use borsh::BorshSerialize;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_config::net::Address as TendermintAddress;
-#[cfg(feature = "ABCI")]
-use tendermint_config_abci::net::Address as TendermintAddress;
+use anoma::types::transaction::{Fee, GasLimit, WrappedTx, WrapperTx};
+use tendermint_compat::tendermint_config::net::Address as TendermintAddress;
 
 use super::rpc;
 use crate::cli::context::WalletAddress;
 use crate::cli::{self, args, Context};
+use crate::client::preview;
 use crate::client::tendermint_rpc_types::TxBroadcastData;
 use crate::wallet::Wallet;
 
@@ -80,17 +78,48 @@ pub async fn find_keypair(
 /// hashes needed for monitoring the tx on chain.
 ///
 /// If it is a dry run, it is not put in a wrapper, but returned as is.
+///
+/// Before signing, a preview of the tx is printed and, unless `--yes` is
+/// given, the tx is only signed once confirmed.
 pub async fn sign_tx(
-    mut ctx: Context,
+    ctx: Context,
     tx: Tx,
     args: &args::Tx,
     default: Option<&WalletAddress>,
 ) -> (Context, TxBroadcastData) {
+    let (ctx, tx, keypair) = sign_inner_tx(ctx, tx, args, default).await;
+    let epoch = rpc::query_epoch(args::Query {
+        ledger_address: args.ledger_address.clone(),
+    })
+    .await;
+    let broadcast_data = if args.dry_run {
+        TxBroadcastData::DryRun(tx)
+    } else {
+        sign_wrapper(&ctx, args, epoch, tx, &keypair).await
+    };
+    (ctx, broadcast_data)
+}
+
+/// Sign a transaction like [`sign_tx`], without putting it in a wrapper.
+/// Returns the signed tx with the signing keypair, which also signs the
+/// wrapper of the tx.
+pub async fn sign_inner_tx(
+    mut ctx: Context,
+    tx: Tx,
+    args: &args::Tx,
+    default: Option<&WalletAddress>,
+) -> (Context, Tx, Rc<common::SecretKey>) {
+    if !preview::confirm_tx(&ctx, &tx, args) {
+        eprintln!("The transaction was not confirmed and it was not signed.");
+        cli::safe_exit(1)
+    }
     let (tx, keypair) = if let Some(signing_key) = &args.signing_key {
         let signing_key = ctx.get_cached(signing_key);
         (tx.sign(&signing_key), signing_key)
     } else if let Some(signer) = args.signer.as_ref().or(default) {
-        let signer = ctx.get(signer);
+        let signer =
+            rpc::resolve_address(&ctx, signer, args.ledger_address.clone())
+                .await;
         let signing_key =
             find_keypair(&mut ctx.wallet, &signer, args.ledger_address.clone())
                 .await;
@@ -101,16 +130,7 @@ pub async fn sign_tx(
              or the address from which to look up the signing key."
         );
     };
-    let epoch = rpc::query_epoch(args::Query {
-        ledger_address: args.ledger_address.clone(),
-    })
-    .await;
-    let broadcast_data = if args.dry_run {
-        TxBroadcastData::DryRun(tx)
-    } else {
-        sign_wrapper(&ctx, args, epoch, tx, &keypair).await
-    };
-    (ctx, broadcast_data)
+    (ctx, tx, keypair)
 }
 
 /// Create a wrapper tx from a normal tx. Get the hash of the
@@ -123,38 +143,50 @@ pub async fn sign_wrapper(
     tx: Tx,
     keypair: &common::SecretKey,
 ) -> TxBroadcastData {
-    let tx = {
-        WrapperTx::new(
-            Fee {
-                amount: args.fee_amount,
-                token: ctx.get(&args.fee_token),
-            },
-            keypair,
-            epoch,
-            args.gas_limit.clone(),
-            tx,
-            // TODO: Actually use the fetched encryption key
-            Default::default(),
-        )
+    let fee = Fee {
+        amount: args.fee_amount,
+        token: ctx.get(&args.fee_token),
     };
+    wrap_tx(
+        tx,
+        fee,
+        keypair,
+        epoch,
+        args.gas_limit.clone(),
+        ctx.config.ledger.chain_id.clone(),
+    )
+}
 
-    // We use this to determine when the wrapper tx makes it on-chain
-    let wrapper_hash = if !cfg!(feature = "ABCI") {
-        hash_tx(&tx.try_to_vec().unwrap()).to_string()
-    } else {
-        tx.tx_hash.to_string()
-    };
-    // We use this to determine when the decrypted inner tx makes it
-    // on-chain
-    let decrypted_hash = if !cfg!(feature = "ABCI") {
-        Some(tx.tx_hash.to_string())
-    } else {
-        None
-    };
+/// Put a signed tx in a wrapper that pays the given fee and sign the wrapper
+/// with the given keypair. Returns the wrapper with the hashes of the wrapper
+/// and of its payload, which are needed for monitoring its progress on chain.
+pub fn wrap_tx(
+    tx: Tx,
+    fee: Fee,
+    keypair: &common::SecretKey,
+    epoch: Epoch,
+    gas_limit: GasLimit,
+    chain_id: ChainId,
+) -> TxBroadcastData {
+    let tx = WrapperTx::new(
+        fee,
+        keypair,
+        epoch,
+        gas_limit,
+        tx,
+        // TODO: Actually use the fetched encryption key
+        Default::default(),
+        chain_id,
+    );
+    let WrappedTx {
+        tx,
+        wrapper_hash,
+        decrypted_hash,
+    } = tx
+        .sign_wrapped(keypair)
+        .expect("Wrapper tx signing keypair should be correct");
     TxBroadcastData::Wrapper {
-        tx: tx
-            .sign(keypair)
-            .expect("Wrapper tx signing keypair should be correct"),
+        tx,
         wrapper_hash,
         decrypted_hash,
     }
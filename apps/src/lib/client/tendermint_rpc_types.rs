@@ -1,10 +1,14 @@
+use std::collections::HashMap;
+
 use anoma::proto::Tx;
-use anoma::types::address::Address;
-use jsonpath_lib as jsonpath;
-use serde::Serialize;
+use anoma::types::transaction::{ResultCode, TxResponse};
+use serde::{Deserialize, Serialize};
+use tendermint_compat::events;
+use tendermint_compat::tendermint_rpc::query::Query;
 use thiserror::Error;
 
-use crate::cli::safe_exit;
+#[cfg(not(feature = "ABCI"))]
+use crate::client::tendermint_websocket_client::Error as WsError;
 #[cfg(not(feature = "ABCI"))]
 use crate::node::ledger::events::Attributes;
 
@@ -17,7 +21,7 @@ pub enum Error {
     Send,
     #[cfg(not(feature = "ABCI"))]
     #[error("Received an error response from Tendermint: {0:?}")]
-    Rpc(tendermint_rpc::response_error::ResponseError),
+    Rpc(tendermint_compat::tendermint_rpc::response_error::ResponseError),
     #[error("Received malformed JSON response from Tendermint")]
     MalformedJson,
     #[error("Received an empty response from Tendermint")]
@@ -26,6 +30,18 @@ pub enum Error {
     Deserialize(serde_json::Error),
     #[error("Could not find event for the given hash: {0}")]
     NotFound(String),
+    #[cfg(not(feature = "ABCI"))]
+    #[error(transparent)]
+    Broadcast(#[from] WsError),
+}
+
+/// Errors from finding a tx in the events of a block
+#[derive(Error, Debug)]
+pub enum QueryError {
+    #[error("Could not deserialize the events of the block: {0}")]
+    Deserialize(serde_json::Error),
+    #[error("Could not find event for the given hash: {0}")]
+    EventNotFound(String),
 }
 
 /// Data needed for broadcasting a tx and
@@ -44,94 +60,22 @@ pub enum TxBroadcastData {
     },
 }
 
-/// A parsed event from tendermint relating to a transaction
-#[derive(Debug, Serialize)]
-pub struct TxResponse {
-    pub info: String,
-    pub log: String,
-    pub height: String,
-    pub hash: String,
-    pub code: String,
-    pub gas_used: String,
-    pub initialized_accounts: Vec<Address>,
-}
-
-impl TxResponse {
-    /// Find a tx with a given hash from the the websocket subscription
-    /// to Tendermint events.
-    pub fn find_tx(json: serde_json::Value, tx_hash: &str) -> Self {
-        let tx_hash_json = serde_json::Value::String(tx_hash.to_string());
-        let mut selector = jsonpath::selector(&json);
-        let mut index = 0;
-        #[cfg(feature = "ABCI")]
-        let evt_key = "applied";
-        #[cfg(not(feature = "ABCI"))]
-        let evt_key = "accepted";
-        // Find the tx with a matching hash
-        let hash = loop {
-            if let Ok(hash) =
-                selector(&format!("$.events.['{}.hash'][{}]", evt_key, index))
-            {
-                let hash = hash[0].clone();
-                if hash == tx_hash_json {
-                    break hash;
-                } else {
-                    index += 1;
-                }
-            } else {
-                eprintln!(
-                    "Couldn't find tx with hash {} in the event string {}",
-                    tx_hash, json
-                );
-                safe_exit(1)
-            }
-        };
-        let info =
-            selector(&format!("$.events.['{}.info'][{}]", evt_key, index))
-                .unwrap();
-        let log = selector(&format!("$.events.['{}.log'][{}]", evt_key, index))
-            .unwrap();
-        let height =
-            selector(&format!("$.events.['{}.height'][{}]", evt_key, index))
-                .unwrap();
-        let code =
-            selector(&format!("$.events.['{}.code'][{}]", evt_key, index))
-                .unwrap();
-        let gas_used =
-            selector(&format!("$.events.['{}.gas_used'][{}]", evt_key, index))
-                .unwrap();
-        let initialized_accounts = selector(&format!(
-            "$.events.['{}.initialized_accounts'][{}]",
-            evt_key, index
-        ));
-        let initialized_accounts = match initialized_accounts {
-            Ok(values) if !values.is_empty() => {
-                // In a response, the initialized accounts are encoded as e.g.:
-                // ```
-                // "applied.initialized_accounts": Array([
-                //   String(
-                //     "[\"atest1...\"]",
-                //   ),
-                // ]),
-                // ...
-                // So we need to decode the inner string first ...
-                let raw: String =
-                    serde_json::from_value(values[0].clone()).unwrap();
-                // ... and then decode the vec from the array inside the string
-                serde_json::from_str(&raw).unwrap()
-            }
-            _ => vec![],
-        };
-        TxResponse {
-            info: serde_json::from_value(info[0].clone()).unwrap(),
-            log: serde_json::from_value(log[0].clone()).unwrap(),
-            height: serde_json::from_value(height[0].clone()).unwrap(),
-            hash: serde_json::from_value(hash).unwrap(),
-            code: serde_json::from_value(code[0].clone()).unwrap(),
-            gas_used: serde_json::from_value(gas_used[0].clone()).unwrap(),
-            initialized_accounts,
-        }
-    }
+/// Find a tx with a given hash in the events of a block, as found in a
+/// websocket subscription to Tendermint events. Returns
+/// [`QueryError::EventNotFound`] if no event of the block has the hash.
+pub fn find_tx(
+    json: &serde_json::Value,
+    tx_hash: &str,
+) -> Result<TxResponse, QueryError> {
+    let not_found = || QueryError::EventNotFound(tx_hash.to_owned());
+    let events = json.get("events").ok_or_else(not_found)?;
+    let events: HashMap<String, Vec<String>> =
+        serde_json::from_value(events.clone())
+            .map_err(QueryError::Deserialize)?;
+    TxResponse::from_block_events(&events, events::ACCEPTED_EVENT)
+        .into_iter()
+        .find(|response| response.hash == tx_hash)
+        .ok_or_else(not_found)
 }
 
 #[cfg(not(feature = "ABCI"))]
@@ -141,7 +85,7 @@ mod params {
 
     use serde::ser::SerializeTuple;
     use serde::{Deserialize, Serializer};
-    use tendermint_rpc::query::Query;
+    use tendermint_compat::tendermint_rpc::query::Query;
 
     use super::*;
 
@@ -295,14 +239,25 @@ mod params {
         let log = event.take("log").unwrap();
         let height = event.take("height").unwrap();
         let hash = event.take("hash").unwrap();
-        let code = event.take("code").unwrap();
+        let code = event
+            .take("code")
+            .unwrap()
+            .parse()
+            .expect("The code of a tx event should be a number");
         let gas_used =
             event.take("gas_used").unwrap_or_else(|| String::from("0"));
-        let initialized_accounts = event.take("initialized_accounts");
-        let initialized_accounts = match initialized_accounts {
-            Some(values) => serde_json::from_str(&values).unwrap(),
-            _ => vec![],
-        };
+        let initialized_accounts = event
+            .take("initialized_accounts")
+            .map(|values| serde_json::from_str(&values).unwrap())
+            .unwrap_or_default();
+        let rejected_vps = event
+            .take("rejected_vps")
+            .map(|values| serde_json::from_str(&values).unwrap())
+            .unwrap_or_default();
+        let rejected_vp_keys = event
+            .take("rejected_vp_keys")
+            .map(|values| serde_json::from_str(&values).unwrap())
+            .unwrap_or_default();
 
         Some(TxResponse {
             info,
@@ -312,12 +267,14 @@ mod params {
             code,
             gas_used,
             initialized_accounts,
+            rejected_vps,
+            rejected_vp_keys,
         })
     }
 
     #[cfg(test)]
     mod test_rpc_types {
-        use tendermint_rpc::query::EventType;
+        use tendermint_compat::tendermint_rpc::query::EventType;
 
         use super::*;
 
@@ -341,3 +298,100 @@ mod params {
 
 #[cfg(not(feature = "ABCI"))]
 pub use params::*;
+
+/// A filter of the tx events by the result code in their `code` attribute,
+/// which is indexed by Tendermint
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResultCodeFilter {
+    /// The txs with the given result code
+    Is(ResultCode),
+    /// The txs that were not applied, i.e. with any other code than
+    /// [`ResultCode::Ok`]
+    Failed,
+}
+
+impl ResultCodeFilter {
+    /// Check if the result code of a tx matches the filter
+    pub fn matches(&self, code: ResultCode) -> bool {
+        match self {
+            ResultCodeFilter::Is(expected) => code == *expected,
+            ResultCodeFilter::Failed => !code.is_ok(),
+        }
+    }
+
+    /// The query of the blocks with an event of the given type whose code
+    /// matches the filter. The codes are compared as numbers, since
+    /// Tendermint queries cannot exclude a value.
+    pub fn query(&self, event_type: &str) -> Query {
+        let key = format!("{}.code", event_type);
+        match self {
+            ResultCodeFilter::Is(code) => {
+                Query::default().and_eq(key, code.to_string())
+            }
+            ResultCodeFilter::Failed => Query::default()
+                .and_gt(key, u64::from(u32::from(ResultCode::Ok))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the codes of the failed txs match the filter of the failed
+    /// txs and that the queries compare the codes of the events.
+    #[test]
+    fn test_result_code_filter() {
+        let failed = ResultCodeFilter::Failed;
+        assert!(!failed.matches(ResultCode::Ok));
+        assert!(failed.matches(ResultCode::VpRejected));
+        let rejected = ResultCodeFilter::Is(ResultCode::VpRejected);
+        assert!(rejected.matches(ResultCode::VpRejected));
+        assert!(!rejected.matches(ResultCode::GasExhausted));
+
+        assert_eq!(
+            failed.query(events::APPLIED_EVENT).to_string(),
+            "applied.code > 0"
+        );
+        assert_eq!(
+            rejected.query(events::APPLIED_EVENT).to_string(),
+            "applied.code = '8'"
+        );
+    }
+
+    /// Test that a tx is found by its hash in the events of a block and that
+    /// the search ends after the last event.
+    #[test]
+    fn test_find_tx() {
+        let attribute = |attribute: &str| {
+            format!("{}.{}", events::ACCEPTED_EVENT, attribute)
+        };
+        let page = |hashes: &[&str]| {
+            let codes: Vec<&str> = hashes.iter().map(|_| "0").collect();
+            serde_json::json!({
+                "events": {
+                    attribute("hash"): hashes,
+                    attribute("code"): codes,
+                    "tm.event": ["NewBlock"],
+                }
+            })
+        };
+        let pages = [serde_json::json!({}), page(&[]), page(&["a", "b"])];
+
+        let response = find_tx(&pages[2], "b").unwrap();
+        assert_eq!(response.hash, "b");
+        assert_eq!(response.code, ResultCode::Ok);
+
+        // Without events, or with an empty array of events, no tx is found
+        for page in &pages[..2] {
+            assert!(matches!(
+                find_tx(page, "a"),
+                Err(QueryError::EventNotFound(hash)) if hash == "a"
+            ));
+        }
+        assert!(matches!(
+            find_tx(&pages[2], "c"),
+            Err(QueryError::EventNotFound(_))
+        ));
+    }
+}
@@ -6,18 +6,10 @@ use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use async_trait::async_trait;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_config::net::Address;
+use tendermint_compat::tendermint_config::net::Address;
 #[cfg(feature = "ABCI")]
-use tendermint_config_abci::net::Address;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_rpc::{
-    Client, Error as RpcError, Request, Response, SimpleRequest,
-};
-#[cfg(feature = "ABCI")]
-use tendermint_rpc_abci::query::Query;
-#[cfg(feature = "ABCI")]
-use tendermint_rpc_abci::{
+use tendermint_compat::tendermint_rpc::query::Query;
+use tendermint_compat::tendermint_rpc::{
     Client, Error as RpcError, Request, Response, SimpleRequest,
 };
 use thiserror::Error;
@@ -59,18 +51,9 @@ mod rpc_types {
     use std::str::FromStr;
 
     use serde::{de, Deserialize, Serialize, Serializer};
-    #[cfg(not(feature = "ABCI"))]
-    use tendermint_rpc::method::Method;
-    #[cfg(not(feature = "ABCI"))]
-    use tendermint_rpc::query::{EventType, Query};
-    #[cfg(not(feature = "ABCI"))]
-    use tendermint_rpc::{request, response};
-    #[cfg(feature = "ABCI")]
-    use tendermint_rpc_abci::method::Method;
-    #[cfg(feature = "ABCI")]
-    use tendermint_rpc_abci::query::{EventType, Query};
-    #[cfg(feature = "ABCI")]
-    use tendermint_rpc_abci::{request, response};
+    use tendermint_compat::tendermint_rpc::method::Method;
+    use tendermint_compat::tendermint_rpc::query::{EventType, Query};
+    use tendermint_compat::tendermint_rpc::{request, response};
 
     use super::Json;
 
@@ -484,13 +467,13 @@ mod test_tendermint_websocket_client {
     use anoma::types::transaction::hash_tx as hash_tx_bytes;
     use serde::{Deserialize, Serialize};
     #[cfg(feature = "ABCI")]
-    use tendermint_rpc_abci::endpoint::abci_info::AbciInfo;
+    use tendermint_compat::tendermint::abci::transaction;
     #[cfg(feature = "ABCI")]
-    use tendermint_rpc_abci::query::{EventType, Query};
+    use tendermint_compat::tendermint_rpc::endpoint::abci_info::AbciInfo;
     #[cfg(feature = "ABCI")]
-    use tendermint_rpc_abci::Client;
+    use tendermint_compat::tendermint_rpc::query::{EventType, Query};
     #[cfg(feature = "ABCI")]
-    use tendermint_stable::abci::transaction;
+    use tendermint_compat::tendermint_rpc::Client;
     use websocket::sync::Server;
     use websocket::{Message, OwnedMessage};
 
@@ -6,8 +6,8 @@ mod tm_jsonrpc {
 
     use curl::easy::{Easy2, Handler, WriteError};
     use serde::{Deserialize, Serialize};
-    use tendermint_config::net::Address as TendermintAddress;
-    use tendermint_rpc::query::Query;
+    use tendermint_compat::tendermint_config::net::Address as TendermintAddress;
+    use tendermint_compat::tendermint_rpc::query::Query;
 
     use crate::client::tendermint_rpc_types::{
         parse, Error, EventParams, EventReply, TxResponse,
@@ -74,7 +74,9 @@ mod tm_jsonrpc {
         /// Results of request (if successful)
         result: Option<EventReply>,
         /// Error message if unsuccessful
-        error: Option<tendermint_rpc::response_error::ResponseError>,
+        error: Option<
+            tendermint_compat::tendermint_rpc::response_error::ResponseError,
+        >,
     }
 
     impl Response {
@@ -1,9 +1,15 @@
 use std::borrow::Cow;
 use std::convert::TryFrom;
 use std::fs::File;
+use std::time::Duration;
 
 use anoma::ledger::governance::storage as gov_storage;
-use anoma::ledger::pos::{BondId, Bonds, Unbonds};
+use anoma::ledger::name_service;
+use anoma::ledger::pos::types::ValidatorState;
+use anoma::ledger::pos::{
+    BondId, Bonds, PosParams, Unbonds, ValidatorMetadata, ValidatorStates,
+};
+use anoma::ledger::token_registry::storage as registry_storage;
 use anoma::proto::Tx;
 use anoma::types::address::{xan as m1t, Address};
 use anoma::types::governance::{
@@ -11,64 +17,74 @@ use anoma::types::governance::{
 };
 use anoma::types::key::*;
 use anoma::types::nft::{self, Nft, NftToken};
-use anoma::types::storage::Epoch;
+use anoma::types::storage::{BlockHeight, Epoch};
 use anoma::types::token::Amount;
 use anoma::types::transaction::governance::{
     InitProposalData, VoteProposalData,
 };
-use anoma::types::transaction::nft::{CreateNft, MintNft};
-use anoma::types::transaction::{pos, InitAccount, InitValidator, UpdateVp};
-use anoma::types::{address, token};
+use anoma::types::transaction::nft::{CreateNft, MintNft, TransferNft};
+use anoma::types::transaction::{
+    pos, Fee, InitAccount, InitToken, InitValidator, PromoteAccount,
+    RegisterAlias, TxResponse, UpdateAccount, UpdateVp,
+};
+use anoma::types::{address, faucet, token};
 use anoma::{ledger, vm};
 use async_std::io::{self, WriteExt};
 use borsh::BorshSerialize;
 use itertools::Either::*;
 #[cfg(not(feature = "ABCI"))]
-use tendermint_config::net::Address as TendermintAddress;
-#[cfg(feature = "ABCI")]
-use tendermint_config_abci::net::Address as TendermintAddress;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_rpc::endpoint::broadcast::tx_sync::Response;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_rpc::query::{EventType, Query};
-#[cfg(not(feature = "ABCI"))]
-use tendermint_rpc::{Client, HttpClient};
-#[cfg(feature = "ABCI")]
-use tendermint_rpc_abci::endpoint::broadcast::tx_sync::Response;
-#[cfg(feature = "ABCI")]
-use tendermint_rpc_abci::query::{EventType, Query};
-#[cfg(feature = "ABCI")]
-use tendermint_rpc_abci::{Client, HttpClient};
-
-use super::rpc;
+use tendermint_compat::events::ACCEPTED_QUERY_KEY;
+use tendermint_compat::events::APPLIED_QUERY_KEY;
+use tendermint_compat::tendermint_config::net::Address as TendermintAddress;
+use tendermint_compat::tendermint_rpc::endpoint::broadcast::tx_sync::Response;
+use tendermint_compat::tendermint_rpc::query::{EventType, Query};
+use tendermint_compat::tendermint_rpc::{Client, HttpClient};
+
+use super::output::{OutputMode, TxQueryResult, TxStatus};
+use super::{output, rpc};
 use crate::cli::context::WalletAddress;
 use crate::cli::{args, safe_exit, Context};
-use crate::client::signing::{find_keypair, sign_tx};
+use crate::client::signing::{find_keypair, sign_inner_tx, sign_wrapper};
 #[cfg(not(feature = "ABCI"))]
 use crate::client::tendermint_rpc_types::Error;
-use crate::client::tendermint_rpc_types::{TxBroadcastData, TxResponse};
+use crate::client::tendermint_rpc_types::{find_tx, TxBroadcastData};
 use crate::client::tendermint_websocket_client::{
     Error as WsError, TendermintWebsocketClient, WebSocketAddress,
 };
 #[cfg(not(feature = "ABCI"))]
 use crate::client::tm_jsonrpc_client::{fetch_event, JsonRpcAddress};
+use crate::client::tx_manager::{self, TrackedStatus, TrackedTxs, TxManager};
 use crate::node::ledger::tendermint_node;
 
-#[cfg(not(feature = "ABCI"))]
-const ACCEPTED_QUERY_KEY: &str = "accepted.hash";
-const APPLIED_QUERY_KEY: &str = "applied.hash";
 const TX_INIT_ACCOUNT_WASM: &str = "tx_init_account.wasm";
+const TX_PROMOTE_ACCOUNT_WASM: &str = "tx_promote_account.wasm";
 const TX_INIT_VALIDATOR_WASM: &str = "tx_init_validator.wasm";
+const TX_INIT_TOKEN_WASM: &str = "tx_init_token.wasm";
+const TX_REGISTER_ALIAS_WASM: &str = "tx_register_alias.wasm";
 const TX_INIT_PROPOSAL: &str = "tx_init_proposal.wasm";
 const TX_VOTE_PROPOSAL: &str = "tx_vote_proposal.wasm";
 const TX_UPDATE_VP_WASM: &str = "tx_update_vp.wasm";
+const TX_UPDATE_ACCOUNT_WASM: &str = "tx_update_account.wasm";
 const TX_TRANSFER_WASM: &str = "tx_transfer.wasm";
+const TX_APPROVE_WASM: &str = "tx_approve.wasm";
+const TX_TRANSFER_FROM_WASM: &str = "tx_transfer_from.wasm";
+const TX_FAUCET_WITHDRAW_WASM: &str = "tx_faucet_withdraw.wasm";
 const TX_INIT_NFT: &str = "tx_init_nft.wasm";
 const TX_MINT_NFT: &str = "tx_mint_nft.wasm";
+const TX_TRANSFER_NFT: &str = "tx_transfer_nft.wasm";
 const VP_USER_WASM: &str = "vp_user.wasm";
+const VP_TOKEN_WASM: &str = "vp_token.wasm";
 const TX_BOND_WASM: &str = "tx_bond.wasm";
+const TX_BATCH_BOND_WASM: &str = "tx_batch_bond.wasm";
 const TX_UNBOND_WASM: &str = "tx_unbond.wasm";
 const TX_WITHDRAW_WASM: &str = "tx_withdraw.wasm";
+const TX_REDELEGATE_WASM: &str = "tx_redelegate.wasm";
+const TX_CHANGE_CONSENSUS_KEY_WASM: &str = "tx_change_consensus_key.wasm";
+const TX_CLAIM_REWARDS_WASM: &str = "tx_claim_rewards.wasm";
+const TX_CHANGE_COMMISSION_RATE_WASM: &str = "tx_change_commission_rate.wasm";
+const TX_CHANGE_VALIDATOR_METADATA_WASM: &str =
+    "tx_change_validator_metadata.wasm";
+const TX_UNJAIL_VALIDATOR_WASM: &str = "tx_unjail_validator.wasm";
 const VP_NFT: &str = "vp_nft.wasm";
 
 pub async fn submit_custom(ctx: Context, args: args::TxCustom) {
@@ -135,6 +151,67 @@ pub async fn submit_update_vp(ctx: Context, args: args::TxUpdateVp) {
     process_tx(ctx, &args.tx, tx, Some(&args.addr)).await;
 }
 
+pub async fn submit_update_account(
+    mut ctx: Context,
+    args: args::TxUpdateAccount,
+) {
+    let addr = ctx.get(&args.addr);
+
+    // Check that the address is established and exists on chain
+    match &addr {
+        Address::Established(_) => {
+            let exists =
+                rpc::known_address(&addr, args.tx.ledger_address.clone()).await;
+            if !exists {
+                eprintln!("The address {} doesn't exist on chain.", addr);
+                if !args.tx.force {
+                    safe_exit(1)
+                }
+            }
+        }
+        Address::Implicit(_) | Address::Internal(_) => {
+            eprintln!(
+                "Only an established account can be updated, got {}.",
+                addr
+            );
+            if !args.tx.force {
+                safe_exit(1)
+            }
+        }
+    }
+    if args.public_key.is_none() && args.vp_code_path.is_none() {
+        eprintln!(
+            "Nothing to update, expected a new public key and/or a new \
+             validity predicate."
+        );
+        safe_exit(1)
+    }
+
+    let public_key = ctx.get_opt_cached(&args.public_key);
+    let vp_code = args.vp_code_path.map(|path| ctx.read_wasm(path));
+    // Validate the VP code
+    if let Some(vp_code) = &vp_code {
+        if let Err(err) = vm::validate_untrusted_wasm(vp_code) {
+            eprintln!("Validity predicate code validation failed with {}", err);
+            if !args.tx.force {
+                safe_exit(1)
+            }
+        }
+    }
+
+    let tx_code = ctx.read_wasm(TX_UPDATE_ACCOUNT_WASM);
+
+    let data = UpdateAccount {
+        addr,
+        public_key,
+        vp_code,
+    };
+    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.addr)).await;
+}
+
 pub async fn submit_init_account(mut ctx: Context, args: args::TxInitAccount) {
     let public_key = ctx.get_cached(&args.public_key);
     let vp_code = args
@@ -162,6 +239,178 @@ pub async fn submit_init_account(mut ctx: Context, args: args::TxInitAccount) {
     save_initialized_accounts(ctx, &args.tx, initialized_accounts).await;
 }
 
+pub async fn submit_promote_account(
+    mut ctx: Context,
+    args: args::TxPromoteAccount,
+) {
+    let source = ctx.get(&args.source);
+    if !matches!(source, Address::Implicit(_)) {
+        eprintln!("Only an implicit account can be promoted, got {}.", source);
+        safe_exit(1)
+    }
+    // The new account is controlled by the implicit account's key
+    let keypair =
+        find_keypair(&mut ctx.wallet, &source, args.tx.ledger_address.clone())
+            .await;
+    let public_key = keypair.ref_to();
+
+    let vp_code = args
+        .vp_code_path
+        .map(|path| ctx.read_wasm(path))
+        .unwrap_or_else(|| ctx.read_wasm(VP_USER_WASM));
+    // Validate the VP code
+    if let Err(err) = vm::validate_untrusted_wasm(&vp_code) {
+        eprintln!("Validity predicate code validation failed with {}", err);
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+
+    // Find the registered tokens in which the implicit account has a balance
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    let registered_tokens = rpc::query_storage_prefix::<Address>(
+        client.clone(),
+        registry_storage::symbol_prefix(),
+    )
+    .await
+    .into_iter()
+    .flatten();
+    let mut tokens = vec![];
+    for (symbol_key, token) in registered_tokens {
+        let balance_key = token::balance_key(&token, &source);
+        let balance =
+            rpc::query_storage_value::<token::Amount>(&client, &balance_key)
+                .await
+                .unwrap_or_default();
+        if balance != token::Amount::default() {
+            let symbol = registry_storage::is_symbol_key(&symbol_key)
+                .cloned()
+                .unwrap_or_else(|| token.to_string());
+            println!("Moving {} {} to the new account", balance, symbol);
+            tokens.push(token);
+        }
+    }
+
+    let tx_code = ctx.read_wasm(TX_PROMOTE_ACCOUNT_WASM);
+    let data = PromoteAccount {
+        public_key,
+        vp_code,
+        tokens,
+    };
+    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    let (ctx, initialized_accounts) =
+        process_tx(ctx, &args.tx, tx, Some(&args.source)).await;
+    save_initialized_accounts(ctx, &args.tx, initialized_accounts).await;
+}
+
+pub async fn submit_init_token(mut ctx: Context, args: args::TxInitToken) {
+    let source = ctx.get(&args.source);
+    let minter = args.minter.as_ref().map(|minter| ctx.get(minter));
+    let metadata = token::Metadata {
+        symbol: args.symbol,
+        decimals: args.decimals,
+        max_supply: args.max_supply,
+        minter,
+    };
+    if let Err(err) = metadata.validate(args.initial_supply) {
+        eprintln!("Invalid token metadata: {}", err);
+        safe_exit(1)
+    }
+    // Check that the symbol is not taken yet
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    let symbol_key = registry_storage::symbol_key(&metadata.symbol);
+    if let Some(token) =
+        rpc::query_storage_value::<Address>(&client, &symbol_key).await
+    {
+        eprintln!(
+            "The token symbol {} is already registered for {}.",
+            metadata.symbol, token
+        );
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+    let vp_code = args
+        .vp_code_path
+        .map(|path| ctx.read_wasm(path))
+        .unwrap_or_else(|| ctx.read_wasm(VP_TOKEN_WASM));
+    // Validate the VP code
+    if let Err(err) = vm::validate_untrusted_wasm(&vp_code) {
+        eprintln!("Validity predicate code validation failed with {}", err);
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+
+    let tx_code = ctx.read_wasm(TX_INIT_TOKEN_WASM);
+    let data = InitToken {
+        metadata,
+        initial_supply: args.initial_supply,
+        initial_holder: source,
+        vp_code,
+    };
+    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    let (ctx, initialized_accounts) =
+        process_tx(ctx, &args.tx, tx, Some(&args.source)).await;
+    save_initialized_accounts(ctx, &args.tx, initialized_accounts).await;
+}
+
+pub async fn submit_register_alias(ctx: Context, args: args::TxRegisterAlias) {
+    let address = ctx.get(&args.address);
+    if !name_service::is_valid_alias(&args.alias) {
+        eprintln!(
+            "Invalid alias {}. It must be made of {} to {} lowercase letters, \
+             digits or dashes, which don't start or end the alias.",
+            args.alias,
+            name_service::MIN_ALIAS_LEN,
+            name_service::MAX_ALIAS_LEN
+        );
+        safe_exit(1)
+    }
+    // Check that the alias is not taken yet
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    if let Some(owner) = rpc::query_alias_address(&client, &args.alias).await {
+        eprintln!(
+            "The alias {} is already registered for {}.",
+            args.alias, owner
+        );
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+    // Check that the address can pay the registration fee
+    let balance_key = token::balance_key(&m1t(), &address);
+    let balance =
+        rpc::query_storage_value::<token::Amount>(&client, &balance_key)
+            .await
+            .unwrap_or_default();
+    if balance < name_service::REGISTRATION_FEE {
+        eprintln!(
+            "The balance of {} is lower than the registration fee of {} XAN.",
+            address,
+            name_service::REGISTRATION_FEE
+        );
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+
+    let tx_code = ctx.read_wasm(TX_REGISTER_ALIAS_WASM);
+    let data = RegisterAlias {
+        alias: args.alias,
+        address,
+    };
+    tracing::debug!("Register alias data {:?}", data);
+    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.address)).await;
+}
+
 pub async fn submit_init_validator(
     mut ctx: Context,
     args::TxInitValidator {
@@ -171,6 +420,8 @@ pub async fn submit_init_validator(
         consensus_key,
         rewards_account_key,
         protocol_key,
+        commission_rate,
+        max_commission_rate_change,
         validator_vp_code_path,
         rewards_vp_code_path,
         unsafe_dont_encrypt,
@@ -260,6 +511,8 @@ pub async fn submit_init_validator(
         rewards_account_key,
         protocol_key,
         dkg_key,
+        commission_rate: commission_rate.into(),
+        max_commission_rate_change: max_commission_rate_change.into(),
         validator_vp_code,
         rewards_vp_code,
     };
@@ -372,7 +625,12 @@ pub async fn submit_init_validator(
 }
 
 pub async fn submit_transfer(ctx: Context, args: args::TxTransfer) {
-    let source = ctx.get(&args.source);
+    let source = rpc::resolve_address(
+        &ctx,
+        &args.source,
+        args.tx.ledger_address.clone(),
+    )
+    .await;
     // Check that the source address exists on chain
     let source_exists =
         rpc::known_address(&source, args.tx.ledger_address.clone()).await;
@@ -382,7 +640,12 @@ pub async fn submit_transfer(ctx: Context, args: args::TxTransfer) {
             safe_exit(1)
         }
     }
-    let target = ctx.get(&args.target);
+    let target = rpc::resolve_address(
+        &ctx,
+        &args.target,
+        args.tx.ledger_address.clone(),
+    )
+    .await;
     // Check that the target address exists on chain
     let target_exists =
         rpc::known_address(&target, args.tx.ledger_address.clone()).await;
@@ -402,9 +665,17 @@ pub async fn submit_transfer(ctx: Context, args: args::TxTransfer) {
             safe_exit(1)
         }
     }
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    // Check that the amount doesn't use more decimal places than the token
+    let denomination = rpc::query_denomination(&client, &token).await;
+    if let Err(err) = denomination.check(args.amount) {
+        eprintln!("{}", err);
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
     // Check source balance
     let balance_key = token::balance_key(&token, &source);
-    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
     match rpc::query_storage_value::<token::Amount>(&client, &balance_key).await
     {
         Some(balance) => {
@@ -413,7 +684,10 @@ pub async fn submit_transfer(ctx: Context, args: args::TxTransfer) {
                     "The balance of the source {} of token {} is lower than \
                      the amount to be transferred. Amount to transfer is {} \
                      and the balance is {}.",
-                    source, token, args.amount, balance
+                    source,
+                    token,
+                    denomination.format(args.amount),
+                    denomination.format(balance)
                 );
                 if !args.tx.force {
                     safe_exit(1)
@@ -446,63 +720,281 @@ pub async fn submit_transfer(ctx: Context, args: args::TxTransfer) {
     process_tx(ctx, &args.tx, tx, Some(&args.source)).await;
 }
 
-pub async fn submit_init_nft(ctx: Context, args: args::NftCreate) {
-    let file = File::open(&args.nft_data).expect("File must exist.");
-    let nft: Nft = serde_json::from_reader(file)
-        .expect("Couldn't deserialize nft data file");
-
-    let vp_code = match &nft.vp_path {
-        Some(path) => {
-            std::fs::read(path).expect("Expected a file at given code path")
+pub async fn submit_faucet_withdraw(ctx: Context, args: args::FaucetWithdraw) {
+    let max_amount = token::Amount::from(faucet::MAX_FREE_DEBIT as u64);
+    if args.amount > max_amount {
+        eprintln!(
+            "The amount to withdraw {} is greater than the faucet's limit of \
+             {} per transaction.",
+            args.amount, max_amount
+        );
+        if !args.tx.force {
+            safe_exit(1)
         }
-        None => ctx.read_wasm(VP_NFT),
-    };
-
-    let signer = Some(WalletAddress::new(nft.creator.clone().to_string()));
-
-    let data = CreateNft {
-        tag: nft.tag.to_string(),
-        creator: nft.creator,
-        vp_code,
-        keys: nft.keys,
-        opt_keys: nft.opt_keys,
-        tokens: nft.tokens,
-    };
-
-    let data = data.try_to_vec().expect(
-        "Encoding transfer data to initialize a new account shouldn't fail",
+    }
+    let faucet_addr = ctx.get(&args.faucet);
+    // Check that the faucet address exists on chain
+    let faucet_exists =
+        rpc::known_address(&faucet_addr, args.tx.ledger_address.clone()).await;
+    if !faucet_exists {
+        eprintln!("The faucet address {} doesn't exist on chain.", faucet_addr);
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+    let target = ctx.get(&args.target);
+    // Check that the target address exists on chain
+    let target_exists =
+        rpc::known_address(&target, args.tx.ledger_address.clone()).await;
+    if !target_exists {
+        eprintln!("The target address {} doesn't exist on chain.", target);
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+    let token = ctx.get(&args.token);
+    // Show the target's previous withdrawal, as the faucet only accepts one
+    // withdrawal per target in every withdrawal interval
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    let last_withdrawal_key =
+        faucet::last_withdrawal_key(&faucet_addr, &target);
+    if let Some(height) =
+        rpc::query_storage_value::<BlockHeight>(&client, &last_withdrawal_key)
+            .await
+    {
+        println!(
+            "The target last withdrew from the faucet at height {}. The next \
+             withdrawal is accepted from height {}.",
+            height,
+            height + faucet::WITHDRAWAL_INTERVAL
+        );
+    }
+    // Check that the withdrawal fits in the faucet's withdrawals of the token
+    // in the current epoch
+    let epoch_withdrawals_key =
+        faucet::epoch_withdrawals_key(&faucet_addr, &token);
+    let epoch_withdrawals =
+        rpc::query_storage_value::<faucet::EpochWithdrawals>(
+            &client,
+            &epoch_withdrawals_key,
+        )
+        .await;
+    let epoch = rpc::query_epoch(args::Query {
+        ledger_address: args.tx.ledger_address.clone(),
+    })
+    .await;
+    let withdrawals = faucet::EpochWithdrawals::add(
+        epoch_withdrawals.as_ref(),
+        epoch,
+        args.amount,
     );
-
-    let tx_code = ctx.read_wasm(TX_INIT_NFT);
+    if !withdrawals.is_within_cap() {
+        eprintln!(
+            "The faucet's withdrawals of the token in the epoch {} would \
+             exceed its limit of {} per epoch.",
+            epoch,
+            token::Amount::from(faucet::MAX_EPOCH_WITHDRAWALS as u64)
+        );
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+    let tx_code = ctx.read_wasm(TX_FAUCET_WITHDRAW_WASM);
+    let withdrawal = faucet::Withdrawal {
+        faucet: faucet_addr,
+        target,
+        token,
+        amount: args.amount,
+    };
+    tracing::debug!("Withdrawal data {:?}", withdrawal);
+    let data = withdrawal
+        .try_to_vec()
+        .expect("Encoding tx data shouldn't fail");
 
     let tx = Tx::new(tx_code, Some(data));
-    process_tx(ctx, &args.tx, tx, signer.as_ref()).await;
+    process_tx(ctx, &args.tx, tx, Some(&args.target)).await;
 }
 
-pub async fn submit_mint_nft(ctx: Context, args: args::NftMint) {
-    let file = File::open(&args.nft_data).expect("File must exist.");
-    let nft_tokens: Vec<NftToken> =
-        serde_json::from_reader(file).expect("JSON was not well-formatted");
-
-    let nft_creator_key = nft::get_creator_key(&args.nft_address);
-    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
-    let nft_creator_address =
-        match rpc::query_storage_value::<Address>(&client, &nft_creator_key)
-            .await
-        {
-            Some(addr) => addr,
-            None => {
-                eprintln!("No creator key found for {}", &args.nft_address);
-                safe_exit(1);
-            }
-        };
-
-    let signer = Some(WalletAddress::new(nft_creator_address.to_string()));
-
-    let data = MintNft {
-        address: args.nft_address,
-        creator: nft_creator_address,
-        tokens: nft_tokens,
+pub async fn submit_approve(ctx: Context, args: args::TxApprove) {
+    let source = ctx.get(&args.source);
+    // Check that the source address exists on chain
+    let source_exists =
+        rpc::known_address(&source, args.tx.ledger_address.clone()).await;
+    if !source_exists {
+        eprintln!("The source address {} doesn't exist on chain.", source);
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+    let spender = ctx.get(&args.spender);
+    // Check that the spender address exists on chain
+    let spender_exists =
+        rpc::known_address(&spender, args.tx.ledger_address.clone()).await;
+    if !spender_exists {
+        eprintln!("The spender address {} doesn't exist on chain.", spender);
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+    let token = ctx.get(&args.token);
+    // Check that the token address exists on chain
+    let token_exists =
+        rpc::known_address(&token, args.tx.ledger_address.clone()).await;
+    if !token_exists {
+        eprintln!("The token address {} doesn't exist on chain.", token);
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+    let tx_code = ctx.read_wasm(TX_APPROVE_WASM);
+    let approval = token::Approval {
+        owner: source,
+        spender,
+        token,
+        amount: args.amount,
+    };
+    tracing::debug!("Approval data {:?}", approval);
+    let data = approval
+        .try_to_vec()
+        .expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.source)).await;
+}
+
+pub async fn submit_transfer_from(ctx: Context, args: args::TxTransferFrom) {
+    let spender = ctx.get(&args.spender);
+    let source = rpc::resolve_address(
+        &ctx,
+        &args.source,
+        args.tx.ledger_address.clone(),
+    )
+    .await;
+    let target = rpc::resolve_address(
+        &ctx,
+        &args.target,
+        args.tx.ledger_address.clone(),
+    )
+    .await;
+    // Check that the target address exists on chain
+    let target_exists =
+        rpc::known_address(&target, args.tx.ledger_address.clone()).await;
+    if !target_exists {
+        eprintln!("The target address {} doesn't exist on chain.", target);
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+    let token = ctx.get(&args.token);
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    // Check the spender's allowance
+    let allowance_key = token::allowance_key(&token, &source, &spender);
+    let allowance =
+        rpc::query_storage_value::<token::Amount>(&client, &allowance_key)
+            .await
+            .unwrap_or_default();
+    if allowance < args.amount {
+        eprintln!(
+            "The allowance of the spender {} from the source {} of token {} \
+             is lower than the amount to be transferred. Amount to transfer \
+             is {} and the allowance is {}.",
+            spender, source, token, args.amount, allowance
+        );
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+    // Check source balance
+    let balance_key = token::balance_key(&token, &source);
+    let balance =
+        rpc::query_storage_value::<token::Amount>(&client, &balance_key)
+            .await
+            .unwrap_or_default();
+    if balance < args.amount {
+        eprintln!(
+            "The balance of the source {} of token {} is lower than the \
+             amount to be transferred. Amount to transfer is {} and the \
+             balance is {}.",
+            source, token, args.amount, balance
+        );
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+    let tx_code = ctx.read_wasm(TX_TRANSFER_FROM_WASM);
+    let transfer = token::TransferFrom {
+        spender,
+        source,
+        target,
+        token,
+        amount: args.amount,
+    };
+    tracing::debug!("Transfer from data {:?}", transfer);
+    let data = transfer
+        .try_to_vec()
+        .expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.spender)).await;
+}
+
+pub async fn submit_init_nft(ctx: Context, args: args::NftCreate) {
+    let file = File::open(&args.nft_data).expect("File must exist.");
+    let nft: Nft = serde_json::from_reader(file)
+        .expect("Couldn't deserialize nft data file");
+
+    let vp_code = match &nft.vp_path {
+        Some(path) => {
+            std::fs::read(path).expect("Expected a file at given code path")
+        }
+        None => ctx.read_wasm(VP_NFT),
+    };
+
+    let signer = Some(WalletAddress::new(nft.creator.clone().to_string()));
+
+    let data = CreateNft {
+        tag: nft.tag.to_string(),
+        creator: nft.creator,
+        vp_code,
+        keys: nft.keys,
+        opt_keys: nft.opt_keys,
+        tokens: nft.tokens,
+    };
+
+    let data = data.try_to_vec().expect(
+        "Encoding transfer data to initialize a new account shouldn't fail",
+    );
+
+    let tx_code = ctx.read_wasm(TX_INIT_NFT);
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, signer.as_ref()).await;
+}
+
+pub async fn submit_mint_nft(ctx: Context, args: args::NftMint) {
+    let file = File::open(&args.nft_data).expect("File must exist.");
+    let nft_tokens: Vec<NftToken> =
+        serde_json::from_reader(file).expect("JSON was not well-formatted");
+
+    let nft_creator_key = nft::get_creator_key(&args.nft_address);
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    let nft_creator_address =
+        match rpc::query_storage_value::<Address>(&client, &nft_creator_key)
+            .await
+        {
+            Some(addr) => addr,
+            None => {
+                eprintln!("No creator key found for {}", &args.nft_address);
+                safe_exit(1);
+            }
+        };
+
+    let signer = Some(WalletAddress::new(nft_creator_address.to_string()));
+
+    let data = MintNft {
+        address: args.nft_address,
+        creator: nft_creator_address,
+        tokens: nft_tokens,
     };
 
     let data = data.try_to_vec().expect(
@@ -515,6 +1007,72 @@ pub async fn submit_mint_nft(ctx: Context, args: args::NftMint) {
     process_tx(ctx, &args.tx, tx, signer.as_ref()).await;
 }
 
+pub async fn submit_transfer_nft(ctx: Context, args: args::NftTransfer) {
+    let source = ctx.get(&args.source);
+    let target = ctx.get(&args.target);
+    let token_id = args.token_id.to_string();
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+
+    // Check that the token exists and that the source may transfer it
+    let current_owner_key =
+        nft::get_token_current_owner_key(&args.nft_address, &token_id);
+    let current_owner =
+        match rpc::query_storage_value::<Address>(&client, &current_owner_key)
+            .await
+        {
+            Some(owner) => owner,
+            None => {
+                eprintln!(
+                    "No token {} found for the nft {}",
+                    token_id, args.nft_address
+                );
+                safe_exit(1);
+            }
+        };
+    if source != current_owner {
+        let approval_key =
+            nft::get_token_approval_key(&args.nft_address, &token_id);
+        let approvals =
+            rpc::query_storage_value::<Vec<Address>>(&client, &approval_key)
+                .await
+                .unwrap_or_default();
+        if !approvals.contains(&source) {
+            eprintln!(
+                "The source {} is neither the owner {} nor an approved \
+                 address of the token {}.",
+                source, current_owner, token_id
+            );
+            if !args.tx.force {
+                safe_exit(1)
+            }
+        }
+    }
+    // Check that the target address exists on chain
+    let target_exists =
+        rpc::known_address(&target, args.tx.ledger_address.clone()).await;
+    if !target_exists {
+        eprintln!("The target address {} doesn't exist on chain.", target);
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+
+    let data = TransferNft {
+        address: args.nft_address,
+        token_id: args.token_id,
+        source,
+        target,
+    };
+    tracing::debug!("Transfer nft data {:?}", data);
+
+    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx_code = ctx.read_wasm(TX_TRANSFER_NFT);
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.source)).await;
+}
+
 pub async fn submit_init_proposal(mut ctx: Context, args: args::InitProposal) {
     let file = File::open(&args.proposal_data).expect("File must exist.");
     let proposal: Proposal =
@@ -795,7 +1353,13 @@ pub async fn submit_bond(ctx: Context, args: args::Bond) {
             safe_exit(1)
         }
     }
-    let source = ctx.get_opt(&args.source);
+    let source = match &args.source {
+        Some(source) => Some(
+            rpc::resolve_address(&ctx, source, args.tx.ledger_address.clone())
+                .await,
+        ),
+        None => None,
+    };
     // Check that the source address exists on chain
     if let Some(source) = &source {
         let source_exists =
@@ -834,6 +1398,31 @@ pub async fn submit_bond(ctx: Context, args: args::Bond) {
             }
         }
     }
+    // Check that the bond gets to at least the minimum self-bond or
+    // delegation
+    let bond_id = BondId {
+        source: bond_source.clone(),
+        validator: validator.clone(),
+    };
+    let bond_key = ledger::pos::bond_key(&bond_id);
+    let mut bond_amount = args.amount;
+    if let Some(bonds) =
+        rpc::query_storage_value::<Bonds>(&client, &bond_key).await
+    {
+        bond_amount += bonds_amount(&bonds);
+    }
+    let params = rpc::query_pos_params(&client).await;
+    let min_amount = min_bond_amount(&params, &bond_id);
+    if bond_amount < min_amount {
+        eprintln!(
+            "The total bonds of the source {} would be {}, which is lower \
+             than the minimum of {}.",
+            bond_source, bond_amount, min_amount
+        );
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
     let tx_code = ctx.read_wasm(TX_BOND_WASM);
     let bond = pos::Bond {
         validator,
@@ -847,6 +1436,106 @@ pub async fn submit_bond(ctx: Context, args: args::Bond) {
     process_tx(ctx, &args.tx, tx, Some(default_signer)).await;
 }
 
+pub async fn submit_batch_bond(ctx: Context, args: args::BatchBond) {
+    let source = rpc::resolve_address(
+        &ctx,
+        &args.source,
+        args.tx.ledger_address.clone(),
+    )
+    .await;
+    if args.bonds.is_empty() {
+        eprintln!("The batch must contain at least one bond.");
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    let params = rpc::query_pos_params(&client).await;
+    let mut bonds: Vec<(Address, token::Amount)> =
+        Vec::with_capacity(args.bonds.len());
+    let mut total_amount = token::Amount::default();
+    for entry in &args.bonds {
+        let validator = ctx.get(&entry.validator);
+        if bonds.iter().any(|(bonded, _)| bonded == &validator) {
+            eprintln!(
+                "The validator {} is in more than one bond of the batch.",
+                validator
+            );
+            if !args.tx.force {
+                safe_exit(1)
+            }
+        }
+        // Check that the validator address exists on chain
+        let is_validator =
+            rpc::is_validator(&validator, args.tx.ledger_address.clone()).await;
+        if !is_validator {
+            eprintln!(
+                "The address {} doesn't belong to any known validator \
+                 account.",
+                validator
+            );
+            if !args.tx.force {
+                safe_exit(1)
+            }
+        }
+        // Check that the bond gets to at least the minimum delegation
+        let bond_id = BondId {
+            source: source.clone(),
+            validator: validator.clone(),
+        };
+        let bond_key = ledger::pos::bond_key(&bond_id);
+        let mut bond_amount = entry.amount;
+        if let Some(bonds) =
+            rpc::query_storage_value::<Bonds>(&client, &bond_key).await
+        {
+            bond_amount += bonds_amount(&bonds);
+        }
+        let min_amount = min_bond_amount(&params, &bond_id);
+        if bond_amount < min_amount {
+            eprintln!(
+                "The total bonds of the source {} to the validator {} would \
+                 be {}, which is lower than the minimum of {}.",
+                source, validator, bond_amount, min_amount
+            );
+            if !args.tx.force {
+                safe_exit(1)
+            }
+        }
+        total_amount += entry.amount;
+        bonds.push((validator, entry.amount));
+    }
+    // Check the source's balance against the sum of the bonds
+    let balance_key = token::balance_key(&address::xan(), &source);
+    match rpc::query_storage_value::<token::Amount>(&client, &balance_key).await
+    {
+        Some(balance) => {
+            if balance < total_amount {
+                eprintln!(
+                    "The balance of the source {} is lower than the total \
+                     amount to be bonded. Amount to bond is {} and the \
+                     balance is {}.",
+                    source, total_amount, balance
+                );
+                if !args.tx.force {
+                    safe_exit(1)
+                }
+            }
+        }
+        None => {
+            eprintln!("No balance found for the source {}", source);
+            if !args.tx.force {
+                safe_exit(1)
+            }
+        }
+    }
+    let tx_code = ctx.read_wasm(TX_BATCH_BOND_WASM);
+    let batch = pos::BatchBond { bonds, source };
+    let data = batch.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.source)).await;
+}
+
 pub async fn submit_unbond(ctx: Context, args: args::Unbond) {
     let validator = ctx.get(&args.validator);
     // Check that the validator address exists on chain
@@ -862,7 +1551,13 @@ pub async fn submit_unbond(ctx: Context, args: args::Unbond) {
         }
     }
 
-    let source = ctx.get_opt(&args.source);
+    let source = match &args.source {
+        Some(source) => Some(
+            rpc::resolve_address(&ctx, source, args.tx.ledger_address.clone())
+                .await,
+        ),
+        None => None,
+    };
     let tx_code = ctx.read_wasm(TX_UNBOND_WASM);
 
     // Check the source's current bond amount
@@ -876,12 +1571,7 @@ pub async fn submit_unbond(ctx: Context, args: args::Unbond) {
     let bonds = rpc::query_storage_value::<Bonds>(&client, &bond_key).await;
     match bonds {
         Some(bonds) => {
-            let mut bond_amount: token::Amount = 0.into();
-            for bond in bonds.iter() {
-                for delta in bond.deltas.values() {
-                    bond_amount += *delta;
-                }
-            }
+            let bond_amount = bonds_amount(&bonds);
             if args.amount > bond_amount {
                 eprintln!(
                     "The total bonds of the source {} is lower than the \
@@ -892,6 +1582,23 @@ pub async fn submit_unbond(ctx: Context, args: args::Unbond) {
                 if !args.tx.force {
                     safe_exit(1)
                 }
+            } else {
+                // Check that the bond is either fully unbonded or that it
+                // stays at least at the minimum self-bond or delegation
+                let remaining = bond_amount - args.amount;
+                let params = rpc::query_pos_params(&client).await;
+                let min_amount = min_bond_amount(&params, &bond_id);
+                if remaining != 0.into() && remaining < min_amount {
+                    eprintln!(
+                        "The remaining bonds of the source {} would be {}, \
+                         which is lower than the minimum of {}. The bonds \
+                         may only be fully unbonded.",
+                        bond_source, remaining, min_amount
+                    );
+                    if !args.tx.force {
+                        safe_exit(1)
+                    }
+                }
             }
         }
         None => {
@@ -914,13 +1621,118 @@ pub async fn submit_unbond(ctx: Context, args: args::Unbond) {
     process_tx(ctx, &args.tx, tx, Some(default_signer)).await;
 }
 
-pub async fn submit_withdraw(ctx: Context, args: args::Withdraw) {
-    let epoch = rpc::query_epoch(args::Query {
-        ledger_address: args.tx.ledger_address.clone(),
-    })
-    .await;
-
-    let validator = ctx.get(&args.validator);
+/// The sum of the bonds in all the epochs, including the pending ones
+fn bonds_amount(bonds: &Bonds) -> token::Amount {
+    let mut bond_amount: token::Amount = 0.into();
+    for bond in bonds.iter() {
+        for delta in bond.deltas.values() {
+            bond_amount += *delta;
+        }
+    }
+    bond_amount
+}
+
+/// The minimum amount of a bond that isn't fully unbonded, depending on
+/// whether it's a validator's self-bond or a delegation
+fn min_bond_amount(params: &PosParams, bond_id: &BondId) -> token::Amount {
+    if bond_id.source == bond_id.validator {
+        params.min_self_bond.into()
+    } else {
+        params.min_delegation.into()
+    }
+}
+
+pub async fn submit_redelegate(ctx: Context, args: args::Redelegate) {
+    let src_validator = ctx.get(&args.src_validator);
+    let dest_validator = ctx.get(&args.dest_validator);
+    // Check that the validator addresses exist on chain
+    for validator in [&src_validator, &dest_validator] {
+        let is_validator =
+            rpc::is_validator(validator, args.tx.ledger_address.clone()).await;
+        if !is_validator {
+            eprintln!(
+                "The address {} doesn't belong to any known validator \
+                 account.",
+                validator
+            );
+            if !args.tx.force {
+                safe_exit(1)
+            }
+        }
+    }
+    if src_validator == dest_validator {
+        eprintln!(
+            "The tokens must be redelegated to a validator other than {}.",
+            src_validator
+        );
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+
+    let source = rpc::resolve_address(
+        &ctx,
+        &args.source,
+        args.tx.ledger_address.clone(),
+    )
+    .await;
+    let tx_code = ctx.read_wasm(TX_REDELEGATE_WASM);
+
+    // Check the source's current bond amount
+    let bond_id = BondId {
+        source: source.clone(),
+        validator: src_validator.clone(),
+    };
+    let bond_key = ledger::pos::bond_key(&bond_id);
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    let bonds = rpc::query_storage_value::<Bonds>(&client, &bond_key).await;
+    match bonds {
+        Some(bonds) => {
+            let mut bond_amount: token::Amount = 0.into();
+            for bond in bonds.iter() {
+                for delta in bond.deltas.values() {
+                    bond_amount += *delta;
+                }
+            }
+            if args.amount > bond_amount {
+                eprintln!(
+                    "The total bonds of the source {} is lower than the \
+                     amount to be redelegated. Amount to redelegate is {} and \
+                     the total bonds is {}.",
+                    source, args.amount, bond_amount
+                );
+                if !args.tx.force {
+                    safe_exit(1)
+                }
+            }
+        }
+        None => {
+            eprintln!("No bonds found");
+            if !args.tx.force {
+                safe_exit(1)
+            }
+        }
+    }
+
+    let data = pos::Redelegation {
+        src_validator,
+        dest_validator,
+        amount: args.amount,
+        source,
+    };
+    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.source)).await;
+}
+
+pub async fn submit_withdraw(ctx: Context, args: args::Withdraw) {
+    let epoch = rpc::query_epoch(args::Query {
+        ledger_address: args.tx.ledger_address.clone(),
+    })
+    .await;
+
+    let validator = ctx.get(&args.validator);
     // Check that the validator address exists on chain
     let is_validator =
         rpc::is_validator(&validator, args.tx.ledger_address.clone()).await;
@@ -934,7 +1746,13 @@ pub async fn submit_withdraw(ctx: Context, args: args::Withdraw) {
         }
     }
 
-    let source = ctx.get_opt(&args.source);
+    let source = match &args.source {
+        Some(source) => Some(
+            rpc::resolve_address(&ctx, source, args.tx.ledger_address.clone())
+                .await,
+        ),
+        None => None,
+    };
     let tx_code = ctx.read_wasm(TX_WITHDRAW_WASM);
 
     // Check the source's current unbond amount
@@ -981,6 +1799,374 @@ pub async fn submit_withdraw(ctx: Context, args: args::Withdraw) {
     process_tx(ctx, &args.tx, tx, Some(default_signer)).await;
 }
 
+pub async fn submit_change_consensus_key(
+    mut ctx: Context,
+    args: args::ChangeConsensusKey,
+) {
+    let validator = ctx.get(&args.validator);
+    // Check that the validator address exists on chain
+    let is_validator =
+        rpc::is_validator(&validator, args.tx.ledger_address.clone()).await;
+    if !is_validator {
+        eprintln!(
+            "The address {} doesn't belong to any known validator account.",
+            validator
+        );
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+    let consensus_key = ctx.get_cached(&args.consensus_key);
+    let tx_code = ctx.read_wasm(TX_CHANGE_CONSENSUS_KEY_WASM);
+
+    let data = pos::ConsensusKeyChange {
+        validator,
+        consensus_key,
+    };
+    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.validator)).await;
+    if !args.tx.dry_run {
+        let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+        let epoch = rpc::query_epoch(args::Query {
+            ledger_address: args.tx.ledger_address.clone(),
+        })
+        .await;
+        let params = rpc::query_pos_params(&client).await;
+        println!(
+            "The new consensus key will be used from the epoch {}. Until \
+             then, the ledger node must keep signing with the current key.",
+            epoch.0 + params.pipeline_len
+        );
+    }
+}
+
+pub async fn submit_claim_rewards(ctx: Context, args: args::Withdraw) {
+    let validator = ctx.get(&args.validator);
+    // Check that the validator address exists on chain
+    let is_validator =
+        rpc::is_validator(&validator, args.tx.ledger_address.clone()).await;
+    if !is_validator {
+        eprintln!(
+            "The address {} doesn't belong to any known validator account.",
+            validator
+        );
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+
+    let source = match &args.source {
+        Some(source) => Some(
+            rpc::resolve_address(&ctx, source, args.tx.ledger_address.clone())
+                .await,
+        ),
+        None => None,
+    };
+    let tx_code = ctx.read_wasm(TX_CLAIM_REWARDS_WASM);
+
+    // Check that the source has a bond with the validator
+    let bond_source = source.clone().unwrap_or_else(|| validator.clone());
+    let bond_id = BondId {
+        source: bond_source,
+        validator: validator.clone(),
+    };
+    let bond_key = ledger::pos::bond_key(&bond_id);
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    let bonds = rpc::query_storage_value::<Bonds>(&client, &bond_key).await;
+    if bonds.is_none() {
+        eprintln!("No bonds found");
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+
+    let data = pos::ClaimRewards { validator, source };
+    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    let default_signer = args.source.as_ref().unwrap_or(&args.validator);
+    process_tx(ctx, &args.tx, tx, Some(default_signer)).await;
+}
+
+pub async fn submit_change_commission_rate(
+    ctx: Context,
+    args: args::ChangeCommissionRate,
+) {
+    let validator = ctx.get(&args.validator);
+    // Check that the validator address exists on chain
+    let is_validator =
+        rpc::is_validator(&validator, args.tx.ledger_address.clone()).await;
+    if !is_validator {
+        eprintln!(
+            "The address {} doesn't belong to any known validator account.",
+            validator
+        );
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+    let tx_code = ctx.read_wasm(TX_CHANGE_COMMISSION_RATE_WASM);
+
+    let data = pos::CommissionRateChange {
+        validator,
+        rate: args.rate.into(),
+    };
+    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.validator)).await;
+    if !args.tx.dry_run {
+        let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+        let epoch = rpc::query_epoch(args::Query {
+            ledger_address: args.tx.ledger_address.clone(),
+        })
+        .await;
+        let params = rpc::query_pos_params(&client).await;
+        println!(
+            "The new commission rate will be used from the epoch {}.",
+            epoch.0 + params.pipeline_len
+        );
+    }
+}
+
+pub async fn submit_change_validator_metadata(
+    ctx: Context,
+    args: args::ChangeValidatorMetadata,
+) {
+    let validator = ctx.get(&args.validator);
+    // Check that the validator address exists on chain
+    let is_validator =
+        rpc::is_validator(&validator, args.tx.ledger_address.clone()).await;
+    if !is_validator {
+        eprintln!(
+            "The address {} doesn't belong to any known validator account.",
+            validator
+        );
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+    let metadata = ValidatorMetadata {
+        moniker: args.moniker,
+        website: args.website,
+        contact: args.contact,
+    };
+    if let Err(err) = metadata.validate() {
+        eprintln!("Invalid validator metadata: {}", err);
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+    let tx_code = ctx.read_wasm(TX_CHANGE_VALIDATOR_METADATA_WASM);
+
+    let data = pos::ValidatorMetadataChange {
+        validator,
+        moniker: metadata.moniker,
+        website: metadata.website,
+        contact: metadata.contact,
+    };
+    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.validator)).await;
+}
+
+pub async fn submit_unjail_validator(
+    ctx: Context,
+    args: args::UnjailValidator,
+) {
+    let validator = ctx.get(&args.validator);
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    let state_key = ledger::pos::validator_state_key(&validator);
+    let states =
+        rpc::query_storage_value::<ValidatorStates>(&client, &state_key).await;
+    // Check that the validator is jailed and that its cooldown has passed
+    let epoch = rpc::query_epoch(args::Query {
+        ledger_address: args.tx.ledger_address.clone(),
+    })
+    .await;
+    let params = rpc::query_pos_params(&client).await;
+    let pipeline_epoch = epoch + params.pipeline_len;
+    match states
+        .as_ref()
+        .and_then(|states| states.get(pipeline_epoch))
+    {
+        None => {
+            eprintln!(
+                "The address {} doesn't belong to any known validator account.",
+                validator
+            );
+            if !args.tx.force {
+                safe_exit(1)
+            }
+        }
+        Some(ValidatorState::Jailed {
+            epoch: jailed_epoch,
+        }) => {
+            let unjail_epoch: Epoch =
+                (*jailed_epoch + params.unjail_cooldown_len).into();
+            if epoch < unjail_epoch {
+                eprintln!(
+                    "The validator {} may only be unjailed from epoch {}.",
+                    validator, unjail_epoch
+                );
+                if !args.tx.force {
+                    safe_exit(1)
+                }
+            }
+        }
+        Some(_) => {
+            eprintln!("The validator {} is not jailed.", validator);
+            if !args.tx.force {
+                safe_exit(1)
+            }
+        }
+    }
+    let tx_code = ctx.read_wasm(TX_UNJAIL_VALIDATOR_WASM);
+
+    let data = pos::Unjail { validator };
+    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.validator)).await;
+    if !args.tx.dry_run {
+        println!(
+            "The validator will be back in the validator set from the epoch \
+             {}.",
+            pipeline_epoch
+        );
+    }
+}
+
+/// Check the status of the tracked txs, or of the one with the given hash.
+/// The txs that the ledger finished processing stop being tracked.
+pub async fn tx_status(ctx: Context, args: args::TxStatus) {
+    let chain_dir = ctx.config.ledger.chain_dir();
+    let tracked_txs = TrackedTxs::load(&chain_dir).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        safe_exit(1)
+    });
+    let txs: Vec<_> = match &args.tx_hash {
+        Some(hash) => match tracked_txs.get(hash) {
+            Some(tracked) => vec![tracked.clone()],
+            None => {
+                eprintln!("{}", tx_manager::Error::UnknownTx(hash.clone()));
+                safe_exit(1)
+            }
+        },
+        None => tracked_txs.iter().cloned().collect(),
+    };
+    if txs.is_empty() {
+        println!("No tracked transactions.");
+        return;
+    }
+    let manager = TxManager::new(
+        args.query.ledger_address,
+        ctx.config.ledger.chain_id.clone(),
+        chain_dir.clone(),
+        Duration::ZERO,
+        ctx.global_args.output,
+    );
+    let mut finished = vec![];
+    for mut tracked in txs {
+        if let Err(err) = manager.refresh(&mut tracked).await {
+            eprintln!("{}", err);
+            safe_exit(1)
+        }
+        output::print(ctx.global_args.output, &tracked);
+        if tracked.status.is_final() {
+            finished.push(tracked.id().to_string());
+        }
+    }
+    let result = TrackedTxs::load(&chain_dir).and_then(|mut tracked_txs| {
+        for id in &finished {
+            tracked_txs.remove(id);
+        }
+        tracked_txs.save(&chain_dir)
+    });
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        safe_exit(1)
+    }
+}
+
+/// Resubmit a tracked tx that was evicted from the mempool in a new wrapper,
+/// with a bumped fee or with the given fee
+pub async fn tx_resubmit(mut ctx: Context, args: args::TxResubmit) {
+    let chain_dir = ctx.config.ledger.chain_dir();
+    let tracked = TrackedTxs::load(&chain_dir)
+        .map(|tracked_txs| tracked_txs.get(&args.tx_hash).cloned());
+    let mut tracked = match tracked {
+        Ok(Some(tracked)) => tracked,
+        Ok(None) => {
+            eprintln!("{}", tx_manager::Error::UnknownTx(args.tx_hash));
+            safe_exit(1)
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            safe_exit(1)
+        }
+    };
+    let manager = TxManager::new(
+        args.query.ledger_address,
+        ctx.config.ledger.chain_id.clone(),
+        chain_dir,
+        args.tx_timeout.into(),
+        ctx.global_args.output,
+    );
+    if let Err(err) = manager.refresh(&mut tracked).await {
+        eprintln!("{}", err);
+        safe_exit(1)
+    }
+    if tracked.status.is_final() || tracked.status == TrackedStatus::Accepted {
+        let err = tx_manager::Error::Finished(
+            tracked.id().to_string(),
+            tracked.status,
+        );
+        eprintln!("{}", err);
+        safe_exit(1)
+    }
+    let keypair = ctx
+        .wallet
+        .find_key_by_pkh(&tracked.fee_payer)
+        .unwrap_or_else(|err| {
+            eprintln!(
+                "Unable to load the keypair of the fee payer {}: {}",
+                tracked.fee_payer, err
+            );
+            safe_exit(1)
+        });
+    let (tracked, wrapper) = manager
+        .rewrap(tracked, &keypair, args.fee_amount)
+        .await
+        .unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            safe_exit(1)
+        });
+    println!(
+        "Resubmitting the transaction {} with a fee of {}.",
+        tracked.id(),
+        tracked.fee_amount
+    );
+    let task = manager.spawn(tracked, wrapper, (*keypair).clone());
+    match task.await {
+        Ok(Ok(_)) => {}
+        Ok(Err(err)) => {
+            eprintln!(
+                "Encountered error while broadcasting transaction: {}",
+                err
+            );
+            safe_exit(1)
+        }
+        Err(err) => {
+            eprintln!("The transaction task failed: {}", err);
+            safe_exit(1)
+        }
+    }
+}
+
 /// Submit transaction and wait for result. Returns a list of addresses
 /// initialized in the transaction if any. In dry run, this is always empty.
 async fn process_tx(
@@ -989,7 +2175,7 @@ async fn process_tx(
     tx: Tx,
     default_signer: Option<&WalletAddress>,
 ) -> (Context, Vec<Address>) {
-    let (ctx, to_broadcast) = sign_tx(ctx, tx, args, default_signer).await;
+    let (ctx, tx, keypair) = sign_inner_tx(ctx, tx, args, default_signer).await;
     // NOTE: use this to print the request JSON body:
 
     // let request =
@@ -1001,42 +2187,66 @@ async fn process_tx(
     // println!("HTTP request body: {}", request_body);
 
     if args.dry_run {
-        if let TxBroadcastData::DryRun(tx) = to_broadcast {
-            rpc::dry_run_tx(&args.ledger_address, tx.to_bytes()).await;
-            (ctx, vec![])
-        } else {
+        rpc::dry_run_tx(&args.ledger_address, tx.to_bytes()).await;
+        return (ctx, vec![]);
+    }
+    let epoch = rpc::query_epoch(args::Query {
+        ledger_address: args.ledger_address.clone(),
+    })
+    .await;
+    let to_broadcast =
+        sign_wrapper(&ctx, args, epoch, tx.clone(), &keypair).await;
+
+    // The tx is tracked by the manager, so that its status can be checked
+    // and it can be resubmitted later
+    let manager = TxManager::new(
+        args.ledger_address.clone(),
+        ctx.config.ledger.chain_id.clone(),
+        ctx.config.ledger.chain_dir(),
+        args.tx_timeout.into(),
+        ctx.global_args.output,
+    );
+    let fee = Fee {
+        amount: args.fee_amount,
+        token: ctx.get(&args.fee_token),
+    };
+    let tracked = manager
+        .track(&to_broadcast, &tx, &fee, &args.gas_limit, &keypair)
+        .unwrap_or_else(|err| {
+            eprintln!("Failed to track the transaction: {}", err);
+            safe_exit(1)
+        });
+    let wrapper = match to_broadcast {
+        TxBroadcastData::Wrapper { tx, .. } => tx,
+        TxBroadcastData::DryRun(_) => {
             panic!(
-                "Expected a dry-run transaction, received a wrapper \
+                "Expected a wrapper transaction, received a dry-run \
                  transaction instead"
             );
         }
+    };
+    // Either broadcast or submit transaction and collect result into
+    // sum type
+    let result = if args.broadcast_only {
+        Left(manager.broadcast(tracked, wrapper).await)
     } else {
-        // Either broadcast or submit transaction and collect result into
-        // sum type
-        let result = if args.broadcast_only {
-            Left(broadcast_tx(args.ledger_address.clone(), &to_broadcast).await)
-        } else {
-            Right(submit_tx(args.ledger_address.clone(), to_broadcast).await)
-        };
-        // Return result based on executed operation, otherwise deal with
-        // the encountered errors uniformly
-        match result {
-            Right(Ok(result)) => (ctx, result.initialized_accounts),
-            Left(Ok(_)) => (ctx, Vec::default()),
-            Right(Err(err)) => {
-                eprintln!(
-                    "Encountered error while broadcasting transaction: {}",
-                    err
-                );
-                safe_exit(1)
-            }
-            Left(Err(err)) => {
-                eprintln!(
-                    "Encountered error while broadcasting transaction: {}",
-                    err
-                );
-                safe_exit(1)
-            }
+        let task = manager.spawn(tracked, wrapper, (*keypair).clone());
+        Right(task.await.unwrap_or_else(|err| {
+            eprintln!("The transaction task failed: {}", err);
+            safe_exit(1)
+        }))
+    };
+    // Return result based on executed operation, otherwise deal with
+    // the encountered errors uniformly
+    match result {
+        Right(Ok((_, Some(result)))) => (ctx, result.initialized_accounts),
+        Right(Ok((_, None))) | Left(Ok(_)) => (ctx, Vec::default()),
+        Right(Err(err)) | Left(Err(err)) => {
+            eprintln!(
+                "Encountered error while broadcasting transaction: {}",
+                err
+            );
+            safe_exit(1)
         }
     }
 }
@@ -1152,11 +2362,13 @@ pub async fn broadcast_tx(
 /// 2. The tx with encrypted payload has been included on the blockchain
 /// 3. The decrypted payload of the tx has been included on the blockchain.
 ///
-/// In the case of errors in any of those stages, an error message is returned
+/// The results are printed in the given output mode. In the case of errors in
+/// any of those stages, an error message is returned
 #[cfg(not(feature = "ABCI"))]
 pub async fn submit_tx(
     address: TendermintAddress,
     to_broadcast: TxBroadcastData,
+    output: OutputMode,
 ) -> Result<TxResponse, Error> {
     // the data for finding the relevant events
     let (_, wrapper_hash, decrypted_hash) = match &to_broadcast {
@@ -1178,32 +2390,39 @@ pub async fn submit_tx(
         .and_eq(APPLIED_QUERY_KEY, decrypted_hash.as_ref().unwrap().as_str());
 
     // broadcast the tx
-    if let Err(err) = broadcast_tx(address, &to_broadcast).await {
-        eprintln!("Encountered error while broadcasting transaction: {}", err);
-        safe_exit(1)
-    }
+    broadcast_tx(address.clone(), &to_broadcast).await?;
 
     // get the event for the wrapper tx
     let response =
         fetch_event(&url, wrapper_query, wrapper_hash.as_str()).await?;
-    println!(
-        "Transaction accepted with result: {}",
-        serde_json::to_string_pretty(&response).unwrap()
+    output::print(
+        output,
+        &TxQueryResult {
+            status: TxStatus::Accepted,
+            result: response.clone(),
+        },
     );
 
     // The transaction is now on chain. We wait for it to be decrypted
     // and applied
-    if response.code == 0.to_string() {
+    if response.code.is_ok() {
+        let decrypted_hash = decrypted_hash.as_ref().unwrap();
         // get the event for the inner tx
-        let response = fetch_event(
-            &url,
-            tx_query,
-            decrypted_hash.as_ref().unwrap().as_str(),
-        )
-        .await?;
-        println!(
-            "Transaction applied with result: {}",
-            serde_json::to_string_pretty(&response).unwrap()
+        let response =
+            fetch_event(&url, tx_query, decrypted_hash.as_str()).await?;
+        // The result is read from the node's txs indexer, if it's enabled,
+        // or else from the event of the block
+        let indexed = match (HttpClient::new(address), decrypted_hash.parse()) {
+            (Ok(client), Ok(hash)) => rpc::query_tx_event(&client, hash).await,
+            _ => None,
+        };
+        let response = indexed.unwrap_or(response);
+        output::print(
+            output,
+            &TxQueryResult {
+                status: TxStatus::Applied,
+                result: response.clone(),
+            },
         );
         Ok(response)
     } else {
@@ -1222,11 +2441,13 @@ pub async fn submit_tx(
 /// 2. The tx with encrypted payload has been included on the blockchain
 /// 3. The decrypted payload of the tx has been included on the blockchain.
 ///
-/// In the case of errors in any of those stages, an error message is returned
+/// The result is printed in the given output mode. In the case of errors in
+/// any of those stages, an error message is returned
 #[cfg(feature = "ABCI")]
 pub async fn submit_tx(
     address: TendermintAddress,
     to_broadcast: TxBroadcastData,
+    output: OutputMode,
 ) -> Result<TxResponse, WsError> {
     let (_, wrapper_hash, _decrypted_hash) = match &to_broadcast {
         TxBroadcastData::Wrapper {
@@ -1250,16 +2471,29 @@ pub async fn submit_tx(
     wrapper_tx_subscription.subscribe(query)?;
 
     // Broadcast the supplied transaction
-    broadcast_tx(address, &to_broadcast).await?;
+    broadcast_tx(address.clone(), &to_broadcast).await?;
 
     let parsed = {
-        let parsed = TxResponse::find_tx(
-            wrapper_tx_subscription.receive_response()?,
-            wrapper_hash,
-        );
-        println!(
-            "Transaction applied with result: {}",
-            serde_json::to_string_pretty(&parsed).unwrap()
+        let response = wrapper_tx_subscription.receive_response()?;
+        // The result is read from the node's txs indexer, if it's enabled,
+        // or else from the events of the block
+        let indexed = match (HttpClient::new(address), wrapper_hash.parse()) {
+            (Ok(client), Ok(hash)) => rpc::query_tx_event(&client, hash).await,
+            _ => None,
+        };
+        let parsed = match indexed {
+            Some(parsed) => parsed,
+            None => find_tx(&response, wrapper_hash).unwrap_or_else(|err| {
+                eprintln!("{} in the event string {}", err, response);
+                safe_exit(1)
+            }),
+        };
+        output::print(
+            output,
+            &TxQueryResult {
+                status: TxStatus::Applied,
+                result: parsed.clone(),
+            },
         );
         Ok(parsed)
     };
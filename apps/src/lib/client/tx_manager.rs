@@ -0,0 +1,657 @@
+//! The client's manager of the submitted txs. The manager runs as a task that
+//! broadcasts the wrapper of a tx and watches the ledger for the events of its
+//! acceptance and application. When the wrapper isn't accepted before the
+//! timeout and the mempool confirms that it's gone from it, or when it expires,
+//! it's considered to be evicted from the mempool and it's resubmitted with a
+//! bumped fee.
+//!
+//! The tracked txs are persisted in the chain directory, so that their status
+//! can be checked and they can be resubmitted by later runs of the client.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anoma::proto::Tx;
+use anoma::types::address::Address;
+use anoma::types::chain::ChainId;
+use anoma::types::key::*;
+use anoma::types::token;
+use anoma::types::transaction::{Fee, GasLimit, TxResponse};
+use serde::{Deserialize, Serialize};
+use tendermint_compat::tendermint_config::net::Address as TendermintAddress;
+use thiserror::Error;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use super::output::{self, OutputMode, TxQueryResult, TxStatus};
+use super::rpc::{self, TxEventQuery};
+use super::signing;
+use super::tendermint_rpc_types::TxBroadcastData;
+use super::tx::broadcast_tx;
+use crate::cli::args;
+
+/// The file of the tracked txs in the chain directory
+const FILE_NAME: &str = "pending_txs.toml";
+/// The percentage by which the fee of an evicted wrapper is bumped when it's
+/// resubmitted
+pub const FEE_BUMP_PERCENT: u64 = 20;
+/// The maximum number of times a tx is broadcast
+pub const MAX_ATTEMPTS: u32 = 5;
+/// The interval in which the ledger is queried for the events of a tx
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// The number of timeouts after which a wrapper that isn't accepted expires,
+/// when the mempool can't confirm whether it still holds the wrapper
+const EXPIRY_TIMEOUTS: u32 = 3;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Failed to read the tracked txs from {0}: {1}")]
+    Read(PathBuf, std::io::Error),
+    #[error("Failed to decode the tracked txs: {0}")]
+    Decode(toml::de::Error),
+    #[error("Failed to encode the tracked txs: {0}")]
+    Encode(toml::ser::Error),
+    #[error("Failed to write the tracked txs to {0}: {1}")]
+    Write(PathBuf, std::io::Error),
+    #[error("No tracked tx with the hash {0}")]
+    UnknownTx(String),
+    #[error("The tracked tx {0} can't be decoded: {1}")]
+    InvalidTx(String, String),
+    #[error("Failed to broadcast the tx: {0}")]
+    Broadcast(String),
+    #[error("The tracked tx {0} is already {1}")]
+    Finished(String, TrackedStatus),
+}
+
+/// Result of a function that may fail
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The status of a tracked tx
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackedStatus {
+    /// The wrapper was added to the mempool and it's waiting to be accepted
+    Broadcast,
+    /// The wrapper was accepted and its payload is waiting to be applied
+    Accepted,
+    /// The tx was applied
+    Applied,
+    /// The wrapper or its payload was rejected by the ledger
+    Rejected,
+    /// The wrapper was not accepted before the timeout and it's gone from the
+    /// mempool or it expired, so it can be resubmitted
+    Evicted,
+}
+
+impl TrackedStatus {
+    /// Check if the ledger finished processing the tx
+    pub fn is_final(&self) -> bool {
+        matches!(self, TrackedStatus::Applied | TrackedStatus::Rejected)
+    }
+}
+
+impl Display for TrackedStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            TrackedStatus::Broadcast => "broadcast",
+            TrackedStatus::Accepted => "accepted",
+            TrackedStatus::Applied => "applied",
+            TrackedStatus::Rejected => "rejected",
+            TrackedStatus::Evicted => "evicted",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+/// A tx tracked by the manager, with everything needed to resubmit it in a
+/// new wrapper
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TrackedTx {
+    /// The signed tx in the payload of the wrapper, hex encoded
+    pub tx: String,
+    /// The fee paid by the last wrapper
+    pub fee_amount: token::Amount,
+    /// The token of the fee
+    pub fee_token: Address,
+    /// The gas limit of the wrapper
+    pub gas_limit: u64,
+    /// The hash of the public key that signs the wrapper and pays its fee
+    pub fee_payer: PublicKeyHash,
+    /// The hash of the last wrapper
+    pub wrapper_hash: String,
+    /// The hash of the decrypted payload of the wrapper
+    pub decrypted_hash: Option<String>,
+    /// The status of the tx
+    pub status: TrackedStatus,
+    /// The result code of the tx, once the ledger processed it
+    pub code: Option<u32>,
+    /// The number of times the tx was broadcast
+    pub attempts: u32,
+}
+
+impl TrackedTx {
+    /// The hash that identifies the tx across its resubmissions, which is the
+    /// hash of its payload
+    pub fn id(&self) -> &str {
+        self.decrypted_hash.as_ref().unwrap_or(&self.wrapper_hash)
+    }
+
+    /// The query of the event of the wrapper's acceptance
+    fn accepted_query(&self) -> TxEventQuery {
+        TxEventQuery::Accepted(self.wrapper_hash.clone())
+    }
+
+    /// The query of the event of the payload's application
+    fn applied_query(&self) -> TxEventQuery {
+        TxEventQuery::Applied(self.id().to_string())
+    }
+
+    /// Update the status with the event of the wrapper's acceptance
+    fn set_accepted(&mut self, response: &TxResponse) {
+        if response.code.is_ok() {
+            self.status = TrackedStatus::Accepted;
+        } else {
+            self.status = TrackedStatus::Rejected;
+            self.code = Some(response.code.into());
+        }
+    }
+
+    /// Update the status with the event of the payload's application
+    fn set_applied(&mut self, response: &TxResponse) {
+        self.status = if response.code.is_ok() {
+            TrackedStatus::Applied
+        } else {
+            TrackedStatus::Rejected
+        };
+        self.code = Some(response.code.into());
+    }
+}
+
+impl Display for TrackedTx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Transaction {}: {}", self.id(), self.status)?;
+        writeln!(f, "  Wrapper hash: {}", self.wrapper_hash)?;
+        writeln!(f, "  Fee: {} {}", self.fee_amount, self.fee_token)?;
+        writeln!(f, "  Attempts: {}", self.attempts)?;
+        if let Some(code) = self.code {
+            writeln!(f, "  Result code: {}", code)?;
+        }
+        Ok(())
+    }
+}
+
+/// The tracked txs, keyed by their [`TrackedTx::id`]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TrackedTxs {
+    txs: BTreeMap<String, TrackedTx>,
+}
+
+impl TrackedTxs {
+    /// Load the tracked txs from the chain directory. Returns no txs if the
+    /// file doesn't exist.
+    pub fn load(chain_dir: impl AsRef<Path>) -> Result<Self> {
+        let path = tracked_txs_file(chain_dir);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = fs::read_to_string(&path)
+            .map_err(|err| Error::Read(path.clone(), err))?;
+        toml::from_str(&data).map_err(Error::Decode)
+    }
+
+    /// Save the tracked txs to the chain directory. The file is replaced
+    /// atomically, so that it's never left partially written.
+    pub fn save(&self, chain_dir: impl AsRef<Path>) -> Result<()> {
+        let path = tracked_txs_file(chain_dir);
+        let data = toml::to_string(self).map_err(Error::Encode)?;
+        let tmp_path = path.with_extension("toml.tmp");
+        fs::write(&tmp_path, data)
+            .map_err(|err| Error::Write(tmp_path.clone(), err))?;
+        fs::rename(&tmp_path, &path).map_err(|err| Error::Write(path, err))
+    }
+
+    /// Add or update a tracked tx
+    pub fn insert(&mut self, tx: TrackedTx) {
+        self.txs.insert(tx.id().to_string(), tx);
+    }
+
+    /// Find a tracked tx by its ID or by the hash of its last wrapper
+    pub fn get(&self, hash: &str) -> Option<&TrackedTx> {
+        self.txs
+            .get(hash)
+            .or_else(|| self.txs.values().find(|tx| tx.wrapper_hash == hash))
+    }
+
+    /// Stop tracking a tx
+    pub fn remove(&mut self, id: &str) -> Option<TrackedTx> {
+        self.txs.remove(id)
+    }
+
+    /// Iterate the tracked txs
+    pub fn iter(&self) -> impl Iterator<Item = &TrackedTx> {
+        self.txs.values()
+    }
+}
+
+/// Get the path to the file of the tracked txs
+pub fn tracked_txs_file(chain_dir: impl AsRef<Path>) -> PathBuf {
+    chain_dir.as_ref().join(FILE_NAME)
+}
+
+/// The fee of a resubmitted wrapper, bumped by [`FEE_BUMP_PERCENT`] and by at
+/// least one micro unit
+pub fn bump_fee(fee: token::Amount) -> token::Amount {
+    let micro = u64::from(fee);
+    let bump = std::cmp::max(micro / 100 * FEE_BUMP_PERCENT, 1);
+    token::Amount::from(micro.saturating_add(bump))
+}
+
+/// The manager of the submitted txs of a chain
+#[derive(Clone, Debug)]
+pub struct TxManager {
+    ledger_address: TendermintAddress,
+    chain_id: ChainId,
+    chain_dir: PathBuf,
+    timeout: Duration,
+    output: OutputMode,
+}
+
+impl TxManager {
+    /// Create a manager of the txs submitted to the ledger at the given
+    /// address, which waits for each of their events until the timeout and
+    /// prints them in the given output mode
+    pub fn new(
+        ledger_address: TendermintAddress,
+        chain_id: ChainId,
+        chain_dir: PathBuf,
+        timeout: Duration,
+        output: OutputMode,
+    ) -> Self {
+        Self {
+            ledger_address,
+            chain_id,
+            chain_dir,
+            timeout,
+            output,
+        }
+    }
+
+    /// Start tracking the broadcast data of a new tx, which must be a
+    /// wrapper signed with the given keypair
+    pub fn track(
+        &self,
+        to_broadcast: &TxBroadcastData,
+        inner_tx: &Tx,
+        fee: &Fee,
+        gas_limit: &GasLimit,
+        keypair: &common::SecretKey,
+    ) -> Result<TrackedTx> {
+        let (wrapper_hash, decrypted_hash) = match to_broadcast {
+            TxBroadcastData::Wrapper {
+                wrapper_hash,
+                decrypted_hash,
+                ..
+            } => (wrapper_hash.clone(), decrypted_hash.clone()),
+            TxBroadcastData::DryRun(_) => {
+                panic!("Cannot track a dry-run transaction")
+            }
+        };
+        let tracked = TrackedTx {
+            tx: hex::encode(inner_tx.to_bytes()),
+            fee_amount: fee.amount,
+            fee_token: fee.token.clone(),
+            gas_limit: u64::from(gas_limit),
+            fee_payer: PublicKeyHash::from(&keypair.ref_to()),
+            wrapper_hash,
+            decrypted_hash,
+            status: TrackedStatus::Broadcast,
+            code: None,
+            attempts: 0,
+        };
+        self.save(&tracked)?;
+        Ok(tracked)
+    }
+
+    /// Broadcast the wrapper of the tracked tx once, without watching its
+    /// events
+    pub async fn broadcast(
+        &self,
+        mut tracked: TrackedTx,
+        wrapper: Tx,
+    ) -> Result<TrackedTx> {
+        tracked.attempts += 1;
+        tracked.status = TrackedStatus::Broadcast;
+        self.save(&tracked)?;
+        let to_broadcast = TxBroadcastData::Wrapper {
+            tx: wrapper,
+            wrapper_hash: tracked.wrapper_hash.clone(),
+            decrypted_hash: tracked.decrypted_hash.clone(),
+        };
+        broadcast_tx(self.ledger_address.clone(), &to_broadcast)
+            .await
+            .map_err(|err| Error::Broadcast(err.to_string()))?;
+        Ok(tracked)
+    }
+
+    /// Spawn the task that broadcasts the wrapper of the tracked tx and
+    /// watches its events. The task resolves to the final state of the
+    /// tracked tx, with the response of the applied tx when it's applied
+    /// before the timeout.
+    pub fn spawn(
+        self,
+        tracked: TrackedTx,
+        wrapper: Tx,
+        keypair: common::SecretKey,
+    ) -> JoinHandle<Result<(TrackedTx, Option<TxResponse>)>> {
+        tokio::spawn(async move { self.run(tracked, wrapper, keypair).await })
+    }
+
+    /// Broadcast the wrapper and watch its events. A wrapper that is evicted
+    /// from the mempool is resubmitted with a bumped fee, up to
+    /// [`MAX_ATTEMPTS`] times in total.
+    async fn run(
+        &self,
+        mut tracked: TrackedTx,
+        mut wrapper: Tx,
+        keypair: common::SecretKey,
+    ) -> Result<(TrackedTx, Option<TxResponse>)> {
+        loop {
+            let wrapper_bytes = wrapper.to_bytes();
+            tracked = self.broadcast(tracked, wrapper).await?;
+
+            match self.wait_for_acceptance(&tracked, &wrapper_bytes).await {
+                #[cfg(not(feature = "ABCI"))]
+                Acceptance::Accepted(response) => {
+                    output::print(
+                        self.output,
+                        &TxQueryResult {
+                            status: TxStatus::Accepted,
+                            result: response.clone(),
+                        },
+                    );
+                    tracked.set_accepted(&response);
+                    self.save(&tracked)?;
+                    if tracked.status.is_final() {
+                        return Ok((tracked, None));
+                    }
+                    let applied = self.wait_for(tracked.applied_query()).await;
+                    return self.finish(tracked, applied);
+                }
+                #[cfg(feature = "ABCI")]
+                Acceptance::Accepted(response) => {
+                    return self.finish(tracked, Some(response));
+                }
+                Acceptance::Pending => {
+                    eprintln!(
+                        "The transaction {} is still in the mempool. Check \
+                         its status later with `tx-status`.",
+                        tracked.id()
+                    );
+                    self.save(&tracked)?;
+                    return Ok((tracked, None));
+                }
+                Acceptance::Evicted if tracked.attempts >= MAX_ATTEMPTS => {
+                    eprintln!(
+                        "The transaction {} was not accepted after {} \
+                         attempts. Resubmit it later with `tx-resubmit`.",
+                        tracked.id(),
+                        tracked.attempts
+                    );
+                    tracked.status = TrackedStatus::Evicted;
+                    self.save(&tracked)?;
+                    return Ok((tracked, None));
+                }
+                Acceptance::Evicted => {
+                    tracked.status = TrackedStatus::Evicted;
+                    let (new_tracked, new_wrapper) =
+                        self.rewrap(tracked, &keypair, None).await?;
+                    eprintln!(
+                        "The transaction {} was evicted from the mempool, so \
+                         it's resubmitted with a fee of {}.",
+                        new_tracked.id(),
+                        new_tracked.fee_amount
+                    );
+                    tracked = new_tracked;
+                    wrapper = new_wrapper;
+                }
+            }
+        }
+    }
+
+    /// Put the tx of an evicted wrapper in a new wrapper with a bumped fee,
+    /// or with the given fee
+    pub async fn rewrap(
+        &self,
+        mut tracked: TrackedTx,
+        keypair: &common::SecretKey,
+        fee_amount: Option<token::Amount>,
+    ) -> Result<(TrackedTx, Tx)> {
+        let tx_bytes = hex::decode(&tracked.tx).map_err(|err| {
+            Error::InvalidTx(tracked.id().to_string(), err.to_string())
+        })?;
+        let tx = Tx::try_from(&tx_bytes[..]).map_err(|err| {
+            Error::InvalidTx(tracked.id().to_string(), err.to_string())
+        })?;
+        tracked.fee_amount =
+            fee_amount.unwrap_or_else(|| bump_fee(tracked.fee_amount));
+        let epoch = rpc::query_epoch(args::Query {
+            ledger_address: self.ledger_address.clone(),
+        })
+        .await;
+        let fee = Fee {
+            amount: tracked.fee_amount,
+            token: tracked.fee_token.clone(),
+        };
+        match signing::wrap_tx(
+            tx,
+            fee,
+            keypair,
+            epoch,
+            GasLimit::from(tracked.gas_limit),
+            self.chain_id.clone(),
+        ) {
+            TxBroadcastData::Wrapper {
+                tx,
+                wrapper_hash,
+                decrypted_hash,
+            } => {
+                tracked.wrapper_hash = wrapper_hash;
+                tracked.decrypted_hash = decrypted_hash;
+                self.save(&tracked)?;
+                Ok((tracked, tx))
+            }
+            TxBroadcastData::DryRun(_) => {
+                unreachable!("A wrapped tx is never a dry-run")
+            }
+        }
+    }
+
+    /// Query the ledger for the events of the tracked tx and update its
+    /// status
+    pub async fn refresh(&self, tracked: &mut TrackedTx) -> Result<()> {
+        if tracked.status.is_final() {
+            return Ok(());
+        }
+        let applied = rpc::query_tx_response(
+            &self.ledger_address,
+            tracked.applied_query(),
+        )
+        .await;
+        if let Ok(response) = applied {
+            tracked.set_applied(&response);
+        } else if cfg!(not(feature = "ABCI")) {
+            let accepted = rpc::query_tx_response(
+                &self.ledger_address,
+                tracked.accepted_query(),
+            )
+            .await;
+            if let Ok(response) = accepted {
+                tracked.set_accepted(&response);
+            }
+        }
+        self.save(tracked)
+    }
+
+    /// Update the tracked tx with the event of its application, if any
+    fn finish(
+        &self,
+        mut tracked: TrackedTx,
+        applied: Option<TxResponse>,
+    ) -> Result<(TrackedTx, Option<TxResponse>)> {
+        match &applied {
+            Some(response) => {
+                output::print(
+                    self.output,
+                    &TxQueryResult {
+                        status: TxStatus::Applied,
+                        result: response.clone(),
+                    },
+                );
+                tracked.set_applied(response);
+            }
+            None => eprintln!(
+                "The transaction {} was not applied within {}s. Check its \
+                 status later with `tx-status`.",
+                tracked.id(),
+                self.timeout.as_secs()
+            ),
+        }
+        self.save(&tracked)?;
+        Ok((tracked, applied))
+    }
+
+    /// Wait for the acceptance of the broadcast wrapper. When it's not
+    /// accepted before the timeout, it's only considered to be evicted once
+    /// the mempool confirms that it's gone from it, or once it expires after
+    /// [`EXPIRY_TIMEOUTS`] timeouts.
+    async fn wait_for_acceptance(
+        &self,
+        tracked: &TrackedTx,
+        wrapper_bytes: &[u8],
+    ) -> Acceptance {
+        // With ABCI, there's no separate event of the wrapper's acceptance
+        #[cfg(not(feature = "ABCI"))]
+        let query = tracked.accepted_query();
+        #[cfg(feature = "ABCI")]
+        let query = tracked.applied_query();
+        let expiry = Instant::now() + self.timeout * EXPIRY_TIMEOUTS;
+        loop {
+            if let Some(response) = self.wait_for(query.clone()).await {
+                return Acceptance::Accepted(response);
+            }
+            let in_mempool =
+                rpc::is_tx_in_mempool(&self.ledger_address, wrapper_bytes)
+                    .await;
+            match in_mempool {
+                Some(false) => {
+                    // The wrapper may have been accepted since the last poll
+                    let response = rpc::query_tx_response(
+                        &self.ledger_address,
+                        query.clone(),
+                    )
+                    .await;
+                    return match response {
+                        Ok(response) => Acceptance::Accepted(response),
+                        Err(_) => Acceptance::Evicted,
+                    };
+                }
+                _ if Instant::now() < expiry => continue,
+                Some(true) => return Acceptance::Pending,
+                None => return Acceptance::Evicted,
+            }
+        }
+    }
+
+    /// Poll the ledger for the event until the timeout
+    async fn wait_for(&self, query: TxEventQuery) -> Option<TxResponse> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let response =
+                rpc::query_tx_response(&self.ledger_address, query.clone())
+                    .await;
+            if let Ok(response) = response {
+                return Some(response);
+            }
+            if Instant::now() >= deadline {
+                return None;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Save the state of the tracked tx
+    fn save(&self, tracked: &TrackedTx) -> Result<()> {
+        let mut tracked_txs = TrackedTxs::load(&self.chain_dir)?;
+        tracked_txs.insert(tracked.clone());
+        tracked_txs.save(&self.chain_dir)
+    }
+}
+
+/// The outcome of waiting for the acceptance of a broadcast wrapper
+enum Acceptance {
+    /// The event of the wrapper's acceptance, or of the tx's application with
+    /// ABCI
+    Accepted(TxResponse),
+    /// The wrapper is still in the mempool when it expires
+    Pending,
+    /// The wrapper is gone from the mempool, or it expired without the
+    /// mempool confirming that it still holds it
+    Evicted,
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::address::testing::established_address_1;
+
+    use super::*;
+
+    /// Test that the tracked txs are persisted and found by their hashes.
+    #[test]
+    fn test_tracked_txs_persistence() {
+        let chain_dir = tempfile::tempdir().unwrap();
+        let tracked = TrackedTx {
+            tx: hex::encode(b"tx"),
+            fee_amount: token::Amount::from(100),
+            fee_token: established_address_1(),
+            gas_limit: 1_000_000,
+            fee_payer: PublicKeyHash::from(&testing::keypair_1().ref_to()),
+            wrapper_hash: "WRAPPER".to_string(),
+            decrypted_hash: Some("DECRYPTED".to_string()),
+            status: TrackedStatus::Evicted,
+            code: None,
+            attempts: 2,
+        };
+        let mut tracked_txs = TrackedTxs::load(chain_dir.path()).unwrap();
+        assert_eq!(tracked_txs.iter().count(), 0);
+        tracked_txs.insert(tracked);
+        tracked_txs.save(chain_dir.path()).unwrap();
+        // The temporary file is renamed to the tracked txs file
+        let file = tracked_txs_file(chain_dir.path());
+        assert!(file.exists());
+        assert!(!file.with_extension("toml.tmp").exists());
+
+        let mut tracked_txs = TrackedTxs::load(chain_dir.path()).unwrap();
+        let tracked = tracked_txs.get("DECRYPTED").unwrap();
+        assert_eq!(tracked.status, TrackedStatus::Evicted);
+        assert_eq!(tracked.fee_amount, token::Amount::from(100));
+        assert_eq!(tracked.attempts, 2);
+        assert!(tracked_txs.get("WRAPPER").is_some());
+        assert!(tracked_txs.remove("DECRYPTED").is_some());
+        assert!(tracked_txs.get("WRAPPER").is_none());
+    }
+
+    /// Test that the fee is bumped by a percentage, and by at least one
+    /// micro unit.
+    #[test]
+    fn test_bump_fee() {
+        assert_eq!(bump_fee(token::Amount::from(0)), token::Amount::from(1));
+        assert_eq!(
+            bump_fee(token::Amount::from(1_000)),
+            token::Amount::from(1_200)
+        );
+    }
+}
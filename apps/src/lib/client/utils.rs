@@ -18,14 +18,8 @@ use rand::prelude::ThreadRng;
 use rand::thread_rng;
 use serde_json::json;
 use sha2::{Digest, Sha256};
-#[cfg(not(feature = "ABCI"))]
-use tendermint::node::Id as TendermintNodeId;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_config::net::Address as TendermintAddress;
-#[cfg(feature = "ABCI")]
-use tendermint_config_abci::net::Address as TendermintAddress;
-#[cfg(feature = "ABCI")]
-use tendermint_stable::node::Id as TendermintNodeId;
+use tendermint_compat::tendermint::node::Id as TendermintNodeId;
+use tendermint_compat::tendermint_config::net::Address as TendermintAddress;
 
 use crate::cli::context::ENV_VAR_WASM_DIR;
 use crate::cli::{self, args};
@@ -229,6 +223,26 @@ pub async fn join_network(
             .unwrap();
     }
 
+    // Verify that the genesis file matches the chain ID and that the WASM
+    // checksums match the ones committed to in the genesis file
+    let genesis_file_path =
+        base_dir_full.join(format!("{}.toml", chain_id.as_str()));
+    let errors = verify_network_release(
+        &chain_id,
+        &genesis_file_path,
+        chain_dir.join(config::DEFAULT_WASM_DIR),
+    );
+    if !errors.is_empty() {
+        eprintln!("The release for chain ID {} is not valid:", chain_id);
+        for error in errors {
+            eprintln!("  {}", error);
+        }
+        // Clean-up the unpacked chain files so that joining can be retried
+        let _ = fs::remove_dir_all(&chain_dir).await;
+        let _ = fs::remove_file(&genesis_file_path).await;
+        cli::safe_exit(1);
+    }
+
     // Move wasm-dir and update config if it's non-default
     if let Some(wasm_dir) = wasm_dir.as_ref() {
         if wasm_dir.to_string_lossy() != config::DEFAULT_WASM_DIR {
@@ -353,6 +367,69 @@ pub async fn join_network(
     println!("Successfully configured for chain ID {}", chain_id);
 }
 
+/// Verify the files unpacked from a network release. The genesis file must
+/// hash to the given chain ID and every WASM declared in it must have the same
+/// checksum in the release's WASM checksums file. Returns the errors found, if
+/// any.
+fn verify_network_release(
+    chain_id: &ChainId,
+    genesis_path: impl AsRef<Path>,
+    wasm_dir: impl AsRef<Path>,
+) -> Vec<String> {
+    let genesis_config = genesis_config::open_genesis_config(&genesis_path);
+    let genesis = genesis_config::load_genesis_config(genesis_config.clone());
+    let genesis_bytes = genesis.try_to_vec().unwrap();
+    let mut errors: Vec<String> = chain_id
+        .validate(genesis_bytes)
+        .into_iter()
+        .map(|err| format!("Genesis file: {}", err))
+        .collect();
+
+    let checksums_path =
+        wasm_dir.as_ref().join(config::DEFAULT_WASM_CHECKSUMS_FILE);
+    let checksums: wasm_loader::Checksums = match fs::read(&checksums_path)
+        .map_err(|err| err.to_string())
+        .and_then(|contents| {
+            serde_json::from_slice(&contents).map_err(|err| err.to_string())
+        }) {
+        Ok(checksums) => checksums,
+        Err(err) => {
+            errors.push(format!(
+                "Unable to read WASM checksums from {}: {}",
+                checksums_path.to_string_lossy(),
+                err
+            ));
+            return errors;
+        }
+    };
+    for (name, wasm) in genesis_config.wasm {
+        let name = format!("{}.wasm", name);
+        // Full name in format `{name}.{sha256}.wasm`
+        let hash = checksums
+            .0
+            .get(&name)
+            .and_then(|full_name| full_name.split_once('.'))
+            .and_then(|(_, rest)| rest.split_once('.'))
+            .map(|(hash, _)| hash);
+        match (hash, wasm.sha256) {
+            (Some(hash), Some(expected)) if hash == expected.0 => {}
+            (Some(hash), Some(expected)) => errors.push(format!(
+                "WASM {} checksum {} doesn't match the genesis checksum {}",
+                name, hash, expected.0
+            )),
+            (None, _) => errors.push(format!(
+                "WASM {} is missing from the checksums file",
+                name
+            )),
+            (_, None) => errors.push(format!(
+                "WASM {} has no checksum in the genesis file",
+                name
+            )),
+        }
+    }
+    errors
+}
+
 /// Length of a Tendermint Node ID in bytes
 const TENDERMINT_NODE_ID_LENGTH: usize = 20;
 
@@ -918,6 +995,9 @@ pub fn init_network(
         },
     );
 
+    let validator_names: Vec<String> =
+        config.validator.keys().cloned().collect();
+
     // Update the ledger config persistent peers and save it
     let mut config = Config::load(&global_args.base_dir, &chain_id, None);
     config.ledger.tendermint.p2p_persistent_peers = persistent_peers;
@@ -982,21 +1062,47 @@ pub fn init_network(
             .unwrap();
 
         // Gzip tar release and write to file
-        let release_file = archive_dir
-            .unwrap_or_else(|| env::current_dir().unwrap())
-            .join(format!("{}.tar.gz", chain_id));
-        let compressed_file = File::create(&release_file).unwrap();
-        let mut encoder =
-            GzEncoder::new(compressed_file, Compression::default());
-        encoder.write_all(&release.into_inner().unwrap()).unwrap();
-        encoder.finish().unwrap();
+        let archive_dir =
+            archive_dir.unwrap_or_else(|| env::current_dir().unwrap());
+        let release_file = archive_dir.join(format!("{}.tar.gz", chain_id));
+        write_tar_gz(release, &release_file);
         println!(
             "Release archive created at {}",
             release_file.to_string_lossy()
         );
+
+        // Create a bundle for each validator with its keys and configs, which
+        // is to be extracted in the working directory of the validator's node
+        for name in validator_names {
+            let validator_dir = chain_dir
+                .join(NET_ACCOUNTS_DIR)
+                .join(&name)
+                .join(config::DEFAULT_BASE_DIR);
+            let mut bundle = tar::Builder::new(Vec::new());
+            bundle
+                .append_dir_all(config::DEFAULT_BASE_DIR, &validator_dir)
+                .unwrap();
+            let bundle_file =
+                archive_dir.join(format!("{}-{}.tar.gz", chain_id, name));
+            write_tar_gz(bundle, &bundle_file);
+            println!(
+                "Validator {} bundle created at {}. It contains the \
+                 validator's secret keys, do NOT publish it.",
+                name,
+                bundle_file.to_string_lossy()
+            );
+        }
     }
 }
 
+/// Gzip the given tar archive and write it to a file at the given path
+fn write_tar_gz(archive: tar::Builder<Vec<u8>>, path: impl AsRef<Path>) {
+    let compressed_file = File::create(path).unwrap();
+    let mut encoder = GzEncoder::new(compressed_file, Compression::default());
+    encoder.write_all(&archive.into_inner().unwrap()).unwrap();
+    encoder.finish().unwrap();
+}
+
 fn init_established_account(
     name: impl AsRef<str>,
     wallet: &mut Wallet,
@@ -1113,6 +1219,37 @@ pub fn init_genesis_validator(
     );
 }
 
+/// Validate that a genesis file matches the given chain ID, i.e. that the
+/// chain ID was derived from this genesis.
+pub fn validate_genesis(
+    _global_args: args::Global,
+    args::ValidateGenesis {
+        genesis_path,
+        chain_id,
+    }: args::ValidateGenesis,
+) {
+    let genesis = genesis_config::read_genesis_config(&genesis_path);
+    let genesis_bytes = genesis.try_to_vec().unwrap();
+    let errors = chain_id.validate(genesis_bytes);
+    if errors.is_empty() {
+        println!(
+            "The genesis file {} is valid for chain ID {}.",
+            genesis_path.to_string_lossy(),
+            chain_id
+        );
+    } else {
+        eprintln!(
+            "The genesis file {} is not valid for chain ID {}:",
+            genesis_path.to_string_lossy(),
+            chain_id
+        );
+        for error in errors {
+            eprintln!("  {}", error);
+        }
+        cli::safe_exit(1)
+    }
+}
+
 async fn download_file(url: impl AsRef<str>) -> reqwest::Result<Bytes> {
     let url = url.as_ref();
     let response = reqwest::get(url).await?;
@@ -26,10 +26,11 @@ pub mod genesis_config {
     use std::path::Path;
     use std::str::FromStr;
 
+    use anoma::ledger::gas;
     use anoma::ledger::governance::parameters::GovParams;
     use anoma::ledger::parameters::{EpochDuration, Parameters};
     use anoma::ledger::pos::types::BasisPoints;
-    use anoma::ledger::pos::{GenesisValidator, PosParams};
+    use anoma::ledger::pos::{self, GenesisValidator, PosParams};
     use anoma::ledger::treasury::parameters::TreasuryParams;
     use anoma::types::address::Address;
     use anoma::types::key::dkg_session_keys::DkgPublicKey;
@@ -183,6 +184,14 @@ pub mod genesis_config {
         // Unstaked balance at genesis.
         // XXX: u64 doesn't work with toml-rs!
         pub non_staked_balance: Option<u64>,
+        // Commission rate (in basis points). (default:
+        // `pos::DEFAULT_COMMISSION_RATE`)
+        // XXX: u64 doesn't work with toml-rs!
+        pub commission_rate: Option<u64>,
+        // Maximum change of the commission rate per epoch (in basis points).
+        // (default: `pos::DEFAULT_MAX_COMMISSION_RATE_CHANGE`)
+        // XXX: u64 doesn't work with toml-rs!
+        pub max_commission_rate_change: Option<u64>,
         // Filename of validator VP. (default: default validator VP)
         pub validator_vp: Option<String>,
         // Filename of staking reward account VP. (default: user VP)
@@ -250,6 +259,12 @@ pub mod genesis_config {
         // Hashes of whitelisted txs array. `None` value or an empty array
         // disables whitelisting.
         pub tx_whitelist: Option<Vec<String>>,
+        // Maximum gas that can be used by all the txs in a block (default:
+        // `gas::BLOCK_GAS_LIMIT`).
+        pub max_block_gas: Option<u64>,
+        // Maximum gas that can be used by a single tx (default:
+        // `gas::TRANSACTION_GAS_LIMIT`).
+        pub max_tx_gas: Option<u64>,
     }
 
     #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -280,6 +295,41 @@ pub mod genesis_config {
         // light client attack (in basis points).
         // XXX: u64 doesn't work with toml-rs!
         pub light_client_attack_slash_rate: u64,
+        // Number of the last blocks in which the validators' signatures
+        // are tracked.
+        // XXX: u64 doesn't work with toml-rs!
+        pub liveness_window_len: u64,
+        // Maximum number of blocks in the liveness window that a validator
+        // may miss to sign before it's slashed and jailed.
+        // XXX: u64 doesn't work with toml-rs!
+        pub liveness_max_missed_blocks: u64,
+        // Portion of a validator's stake that should be slashed on a
+        // downtime (in basis points).
+        // XXX: u64 doesn't work with toml-rs!
+        pub downtime_slash_rate: u64,
+        // Number of epochs after the epoch in which a validator is jailed
+        // before it may be unjailed.
+        // XXX: u64 doesn't work with toml-rs!
+        pub unjail_cooldown_len: u64,
+        // Minimum amount of tokens (in micro units) of a validator's
+        // self-bond.
+        // XXX: u64 doesn't work with toml-rs!
+        pub min_self_bond: u64,
+        // Minimum amount of tokens (in micro units) of a delegation.
+        // XXX: u64 doesn't work with toml-rs!
+        pub min_delegation: u64,
+        // Maximum annual inflation rate of the staking token (in basis
+        // points).
+        // XXX: u64 doesn't work with toml-rs!
+        pub max_inflation_rate: u64,
+        // Minimum annual inflation rate of the staking token (in basis
+        // points).
+        // XXX: u64 doesn't work with toml-rs!
+        pub min_inflation_rate: u64,
+        // Target ratio of the staked tokens to the total supply of the
+        // staking token (in basis points).
+        // XXX: u64 doesn't work with toml-rs!
+        pub target_staked_ratio: u64,
     }
 
     #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -318,6 +368,14 @@ pub mod genesis_config {
                     .unwrap()
                     .to_public_key()
                     .unwrap(),
+                commission_rate: config
+                    .commission_rate
+                    .map(BasisPoints::new)
+                    .unwrap_or(pos::DEFAULT_COMMISSION_RATE),
+                max_commission_rate_change: config
+                    .max_commission_rate_change
+                    .map(BasisPoints::new)
+                    .unwrap_or(pos::DEFAULT_MAX_COMMISSION_RATE_CHANGE),
             },
             account_key: config
                 .account_public_key
@@ -543,6 +601,14 @@ pub mod genesis_config {
             .into(),
             vp_whitelist: config.parameters.vp_whitelist.unwrap_or_default(),
             tx_whitelist: config.parameters.tx_whitelist.unwrap_or_default(),
+            max_block_gas: config
+                .parameters
+                .max_block_gas
+                .unwrap_or(gas::BLOCK_GAS_LIMIT),
+            max_tx_gas: config
+                .parameters
+                .max_tx_gas
+                .unwrap_or(gas::TRANSACTION_GAS_LIMIT),
         };
 
         let gov_params = GovParams {
@@ -576,6 +642,28 @@ pub mod genesis_config {
             light_client_attack_slash_rate: BasisPoints::new(
                 config.pos_params.light_client_attack_slash_rate,
             ),
+            liveness_window_len: config.pos_params.liveness_window_len,
+            liveness_max_missed_blocks: config
+                .pos_params
+                .liveness_max_missed_blocks,
+            downtime_slash_rate: BasisPoints::new(
+                config.pos_params.downtime_slash_rate,
+            ),
+            unjail_cooldown_len: config.pos_params.unjail_cooldown_len,
+            min_self_bond: config.pos_params.min_self_bond,
+            min_delegation: config.pos_params.min_delegation,
+            max_inflation_rate: BasisPoints::new(
+                config.pos_params.max_inflation_rate,
+            ),
+            min_inflation_rate: BasisPoints::new(
+                config.pos_params.min_inflation_rate,
+            ),
+            target_staked_ratio: BasisPoints::new(
+                config.pos_params.target_staked_ratio,
+            ),
+            // Derived from the epoch duration, so that the annual inflation
+            // is minted over the epochs of a year
+            epochs_per_year: parameters.epochs_per_year(),
         };
 
         let mut genesis = Genesis {
@@ -761,6 +849,9 @@ pub fn genesis() -> Genesis {
             tokens: token::Amount::whole(200_000),
             consensus_key: consensus_keypair.ref_to(),
             staking_reward_key: staking_reward_keypair.ref_to(),
+            commission_rate: anoma::ledger::pos::DEFAULT_COMMISSION_RATE,
+            max_commission_rate_change:
+                anoma::ledger::pos::DEFAULT_MAX_COMMISSION_RATE_CHANGE,
         },
         account_key: account_keypair.ref_to(),
         protocol_key: protocol_keypair.ref_to(),
@@ -780,6 +871,8 @@ pub fn genesis() -> Genesis {
         max_expected_time_per_block: anoma::types::time::DurationSecs(30),
         vp_whitelist: vec![],
         tx_whitelist: vec![],
+        max_block_gas: anoma::ledger::gas::BLOCK_GAS_LIMIT,
+        max_tx_gas: anoma::ledger::gas::TRANSACTION_GAS_LIMIT,
     };
     let albert = EstablishedAccount {
         address: wallet::defaults::albert_address(),
@@ -849,6 +942,10 @@ pub fn genesis() -> Genesis {
             balances: balances.clone(),
         })
         .collect();
+    let pos_params = PosParams {
+        epochs_per_year: parameters.epochs_per_year(),
+        ..PosParams::default()
+    };
     Genesis {
         genesis_time: DateTimeUtc::now(),
         validators: vec![validator],
@@ -856,7 +953,7 @@ pub fn genesis() -> Genesis {
         implicit_accounts,
         token_accounts,
         parameters,
-        pos_params: PosParams::default(),
+        pos_params,
         gov_params: GovParams::default(),
         treasury_params: TreasuryParams::default(),
     }
@@ -864,8 +961,10 @@ pub fn genesis() -> Genesis {
 
 #[cfg(test)]
 pub mod tests {
+    use anoma::ledger::parameters::{EpochDuration, Parameters};
     use anoma::types::address::testing::gen_established_address;
     use anoma::types::key::*;
+    use anoma::types::time::DurationSecs;
     use borsh::BorshSerialize;
     use rand::prelude::ThreadRng;
     use rand::thread_rng;
@@ -894,4 +993,27 @@ pub mod tests {
         println!("protocol_keypair: {:?}", protocol_keypair);
         println!("dkg_keypair: {:?}", dkg_keypair.try_to_vec().unwrap());
     }
+
+    /// Test that the epochs per year are derived from the longer of the
+    /// epoch's min duration and its min number of blocks.
+    #[test]
+    fn test_epochs_per_year() {
+        let year_secs = 365 * 24 * 60 * 60;
+        let mut parameters = Parameters {
+            epoch_duration: EpochDuration {
+                min_num_of_blocks: 10,
+                min_duration: DurationSecs(60),
+            },
+            max_expected_time_per_block: DurationSecs(30),
+            vp_whitelist: vec![],
+            tx_whitelist: vec![],
+            max_block_gas: 0,
+            max_tx_gas: 0,
+        };
+        assert_eq!(parameters.epochs_per_year(), year_secs / 300);
+        parameters.epoch_duration.min_duration = DurationSecs(24 * 60 * 60);
+        assert_eq!(parameters.epochs_per_year(), 365);
+        parameters.epoch_duration.min_duration = DurationSecs(2 * year_secs);
+        assert_eq!(parameters.epochs_per_year(), 1);
+    }
 }
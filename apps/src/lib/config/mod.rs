@@ -3,8 +3,9 @@
 pub mod genesis;
 pub mod global;
 pub mod utils;
+pub mod validation;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
 use std::fs::{create_dir_all, File};
 use std::io::Write;
@@ -13,20 +14,15 @@ use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use anoma::types::chain::ChainId;
+use anoma::types::storage::BlockHeight;
 use anoma::types::time::Rfc3339String;
 use libp2p::multiaddr::{Multiaddr, Protocol};
 use libp2p::multihash::Multihash;
 use libp2p::PeerId;
 use regex::Regex;
 use serde::{de, Deserialize, Serialize};
-#[cfg(not(feature = "ABCI"))]
-use tendermint::Timeout;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_config::net::Address as TendermintAddress;
-#[cfg(feature = "ABCI")]
-use tendermint_config_abci::net::Address as TendermintAddress;
-#[cfg(feature = "ABCI")]
-use tendermint_stable::Timeout;
+use tendermint_compat::tendermint::Timeout;
+use tendermint_compat::tendermint_config::net::Address as TendermintAddress;
 use thiserror::Error;
 
 use crate::cli;
@@ -44,6 +40,20 @@ pub const FILENAME: &str = "config.toml";
 pub const TENDERMINT_DIR: &str = "tendermint";
 /// Chain-specific Anoma DB. Nested in chain dirs.
 pub const DB_DIR: &str = "db";
+/// Chain-specific directory of the read-only DB instances used for queries.
+/// Nested in chain dirs.
+pub const QUERY_DB_DIR: &str = "query_db";
+/// Chain-specific directory of the copy of the DB in which blocks are
+/// replayed. Nested in chain dirs.
+pub const REPLAY_DB_DIR: &str = "replay_db";
+/// Chain-specific directory of the copy of the DB from which the state of a
+/// past block is exported. Nested in chain dirs.
+pub const EXPORT_DB_DIR: &str = "export_db";
+/// Chain-specific DB of the txs indexer. Nested in chain dirs.
+pub const INDEXER_DB_DIR: &str = "indexer_db";
+/// Chain-specific directory of the state snapshots served to the nodes that
+/// state-sync. Nested in chain dirs.
+pub const SNAPSHOTS_DIR: &str = "snapshots";
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Config {
@@ -82,27 +92,129 @@ impl From<String> for TendermintMode {
     }
 }
 
+/// The order in which a block proposer includes the new txs from the mempool
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TxOrdering {
+    /// Include the txs in the order given by the mempool
+    Fifo,
+    /// Include the txs that offer the highest gas price first
+    FeePriority,
+}
+
+/// The backend of the DB of the ledger state
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StorageBackend {
+    /// RocksDB, which is used by default
+    RocksDB,
+    /// Sled, an embedded DB written in pure Rust, which doesn't need the
+    /// RocksDB toolchain
+    Sled,
+}
+
+impl Default for StorageBackend {
+    fn default() -> Self {
+        Self::RocksDB
+    }
+}
+
+/// The sink of the diffs of the state of the committed blocks
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StateDiffSink {
+    /// Append the diffs to the file at this path, which is created if
+    /// missing
+    File(PathBuf),
+    /// Write the diffs to the Unix socket at this path, on which an indexer
+    /// listens. The node connects again after the connection is lost.
+    #[cfg(unix)]
+    UnixSocket(PathBuf),
+    /// Publish each diff in a message on the subject of the NATS server at
+    /// this address, e.g. `127.0.0.1:4222`. The node connects again after
+    /// the connection is lost.
+    Nats { address: String, subject: String },
+}
+
+/// The action of a node on a violated invariant of the PoS state
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PosInvariantsCheck {
+    /// Log the violated invariants
+    Log,
+    /// Log the violated invariants and halt the node
+    Halt,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Ledger {
     pub genesis_time: Rfc3339String,
     pub chain_id: ChainId,
     pub shell: Shell,
     pub tendermint: Tendermint,
+    pub rpc: Rpc,
+    pub pruning: Pruning,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Shell {
     pub base_dir: PathBuf,
     pub ledger_address: SocketAddr,
-    /// RocksDB block cache maximum size in bytes.
+    /// The address of the gRPC server of the ledger queries, which is only
+    /// started by a node built with the `grpc` feature. When not set, the
+    /// queries are only served over ABCI.
+    pub grpc_address: Option<SocketAddr>,
+    /// The address of the REST server of the common ledger queries, which is
+    /// only started by a node built with the `rest` feature. When not set,
+    /// the REST server is not started.
+    pub rest_address: Option<SocketAddr>,
+    /// The backend of the DB. The DB of a chain cannot be opened with
+    /// another backend than the one it was created with, but its state can
+    /// be exported and imported into a new chain.
+    #[serde(default)]
+    pub storage_backend: StorageBackend,
+    /// DB block cache maximum size in bytes.
     /// When not set, defaults to 1/3 of the available memory.
     pub block_cache_bytes: Option<u64>,
+    /// The in-memory cache of the recently read storage values maximum size
+    /// in bytes, which is invalidated by the changes of the committed blocks.
+    /// When not set, the values are only cached by the DB.
+    #[serde(default)]
+    pub read_cache_bytes: Option<u64>,
     /// VP WASM compilation cache maximum size in bytes.
     /// When not set, defaults to 1/6 of the available memory.
     pub vp_wasm_compilation_cache_bytes: Option<u64>,
     /// Tx WASM compilation in-memory cache maximum size in bytes.
     /// When not set, defaults to 1/6 of the available memory.
     pub tx_wasm_compilation_cache_bytes: Option<u64>,
+    /// The order in which the new txs are included in a block proposed by
+    /// this node.
+    pub tx_ordering: TxOrdering,
+    /// The maximum sum of gas limits of the new txs included in a block
+    /// proposed by this node. When not set, the new txs are not limited by
+    /// their gas.
+    pub proposal_gas_limit: Option<u64>,
+    /// Index the txs of the committed blocks, so that they can be queried by
+    /// the involved addresses, by the moved tokens and by the height of their
+    /// block with their results and events.
+    pub indexer: bool,
+    /// Take a snapshot of the state every this number of blocks, which is
+    /// served to the new nodes that state-sync instead of replaying all the
+    /// blocks. When not set, no snapshots are taken.
+    pub snapshot_interval: Option<u64>,
+    /// The number of the last snapshots that are kept
+    pub snapshot_keep_recent: u64,
+    /// The log filter of the node, e.g. `info` or `anoma=debug,info`. It's
+    /// ignored when the `ANOMA_LOG` env var is set. It's reloaded on a
+    /// hangup signal.
+    pub log_level: Option<String>,
+    /// Write the diffs of the state of the committed blocks to this sink, so
+    /// that they can be indexed without querying the node. When not set, no
+    /// diffs are written.
+    #[serde(default)]
+    pub state_diff_sink: Option<StateDiffSink>,
+    /// Check the invariants of the PoS state after each epoch transition,
+    /// which is only done by a node built with the `pos-invariants` feature,
+    /// and either log them or halt the node on a violation. When not set,
+    /// the invariants are not checked.
+    #[serde(default)]
+    pub pos_invariants: Option<PosInvariantsCheck>,
     /// Use the [`Ledger::db_dir()`] method to read the value.
     db_dir: PathBuf,
     /// Use the [`Ledger::tendermint_dir()`] method to read the value.
@@ -112,6 +224,10 @@ pub struct Shell {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Tendermint {
     pub rpc_address: SocketAddr,
+    /// The origins allowed to make cross-origin requests to the RPC, e.g.
+    /// `https://wallet.example.com` or `*` for any origin. Tendermint applies
+    /// the changes on the next start of the node.
+    pub rpc_cors_allowed_origins: Vec<String>,
     pub p2p_address: SocketAddr,
     /// The persistent peers addresses must include node ID
     pub p2p_persistent_peers: Vec<TendermintAddress>,
@@ -132,6 +248,86 @@ pub struct Tendermint {
     pub instrumentation_namespace: String,
 }
 
+/// The limits and the access control of the queries served by the node. They
+/// are reloaded on a hangup signal.
+///
+/// The query paths are given either by their name, e.g. `dry_run_tx` or
+/// `prefix`, or by a prefix of the full path, e.g. `value/#atest1...`. The new
+/// gRPC subscriptions to the storage keys are limited as the `watch_keys`
+/// path. The limits and the access control of a path also apply to its
+/// paginated and batched variants, e.g. the ones of `prefix` to the
+/// `prefix_page` queries and the gRPC prefix streams and the ones of `value`
+/// to the keys of the `values` queries.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Rpc {
+    /// The maximum number of queries served per second. When not set, the
+    /// queries are not rate limited.
+    pub max_queries_per_second: Option<u32>,
+    /// The maximum number of queries served per second by the name of the
+    /// query path, on top of the limit of all the queries
+    #[serde(default)]
+    pub path_max_queries_per_second: HashMap<String, u32>,
+    /// When not empty, only the queries of these paths are served
+    #[serde(default)]
+    pub allowed_paths: Vec<String>,
+    /// The queries of these paths are not served, even if they are allowed
+    #[serde(default)]
+    pub denied_paths: Vec<String>,
+}
+
+/// The minimum number of the last committed blocks whose state is kept, so
+/// that the last block can be rolled back
+pub const MIN_KEEP_RECENT_BLOCKS: u64 = 2;
+
+/// The pruning of the state of the past blocks from the DB, which bounds the
+/// growth of the DB. The state of a pruned block can no longer be queried,
+/// replayed or rolled back to. The blocks that are no longer kept after a
+/// change of the settings are pruned on the next start of the node, down to
+/// the first block that was already pruned.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pruning {
+    /// The number of the last committed blocks whose state is kept, at least
+    /// [`MIN_KEEP_RECENT_BLOCKS`]. When not set, the state of all the blocks
+    /// is kept, as by an archive node.
+    pub keep_recent: Option<u64>,
+    /// Also keep the state of every block whose height is a multiple of this
+    /// number. The diffs of all the blocks are then kept, as they're needed to
+    /// read the state of these blocks, and only the Merkle trees and the
+    /// metadata of the other blocks are pruned.
+    pub keep_every: Option<u64>,
+}
+
+impl Pruning {
+    /// The height of the highest block whose state is no longer kept once the
+    /// block at the given height is committed, if any
+    pub fn pruned_height(
+        &self,
+        last_height: BlockHeight,
+    ) -> Option<BlockHeight> {
+        let keep_recent = self.keep_recent?.max(MIN_KEEP_RECENT_BLOCKS);
+        last_height
+            .0
+            .checked_sub(keep_recent)
+            .filter(|height| *height > 0)
+            .map(BlockHeight)
+    }
+
+    /// Whether the state of a block below the recent blocks is kept
+    pub fn keeps(&self, height: BlockHeight) -> bool {
+        self.keep_every()
+            .map_or(false, |keep_every| height.0 % keep_every == 0)
+    }
+
+    /// Whether the diffs of the pruned blocks are pruned too
+    pub fn prunes_diffs(&self) -> bool {
+        self.keep_every().is_none()
+    }
+
+    fn keep_every(&self) -> Option<u64> {
+        self.keep_every.filter(|keep_every| *keep_every > 0)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct IntentGossiper {
     // Simple values
@@ -173,9 +369,21 @@ impl Ledger {
                     IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
                     26658,
                 ),
+                grpc_address: None,
+                rest_address: None,
+                storage_backend: StorageBackend::default(),
                 block_cache_bytes: None,
+                read_cache_bytes: None,
                 vp_wasm_compilation_cache_bytes: None,
                 tx_wasm_compilation_cache_bytes: None,
+                tx_ordering: TxOrdering::Fifo,
+                proposal_gas_limit: None,
+                indexer: false,
+                snapshot_interval: None,
+                snapshot_keep_recent: 2,
+                log_level: None,
+                state_diff_sink: None,
+                pos_invariants: None,
                 db_dir: DB_DIR.into(),
                 tendermint_dir: TENDERMINT_DIR.into(),
             },
@@ -184,6 +392,7 @@ impl Ledger {
                     IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
                     26657,
                 ),
+                rpc_cors_allowed_origins: vec![],
                 p2p_address: SocketAddr::new(
                     IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
                     26656,
@@ -201,6 +410,8 @@ impl Ledger {
                 ),
                 instrumentation_namespace: "anoman_tm".to_string(),
             },
+            rpc: Rpc::default(),
+            pruning: Pruning::default(),
         }
     }
 
@@ -214,6 +425,37 @@ impl Ledger {
         self.shell.db_dir(&self.chain_id)
     }
 
+    /// Get the directory path to the read-only DB instances used for queries
+    pub fn query_db_dir(&self) -> PathBuf {
+        self.chain_dir().join(QUERY_DB_DIR)
+    }
+
+    /// Get the directory path to the DB of the txs indexer
+    pub fn indexer_db_dir(&self) -> PathBuf {
+        self.chain_dir().join(INDEXER_DB_DIR)
+    }
+
+    /// Get the directory path to the state snapshots
+    pub fn snapshots_dir(&self) -> PathBuf {
+        self.chain_dir().join(SNAPSHOTS_DIR)
+    }
+
+    /// Get the directory path to the copy of the DB from which the state of
+    /// a past block is exported
+    pub fn export_db_dir(&self) -> PathBuf {
+        self.chain_dir().join(EXPORT_DB_DIR)
+    }
+
+    /// Get a copy of the configuration for a full node using the copy of the
+    /// DB in which blocks are replayed
+    pub fn for_replay(&self) -> Self {
+        let mut config = self.clone();
+        config.shell.db_dir = REPLAY_DB_DIR.into();
+        config.shell.indexer = false;
+        config.tendermint.tendermint_mode = TendermintMode::Full;
+        config
+    }
+
     /// Get the directory path to Tendermint
     pub fn tendermint_dir(&self) -> PathBuf {
         self.shell.tendermint_dir(&self.chain_id)
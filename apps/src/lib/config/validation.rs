@@ -0,0 +1,307 @@
+//! Validation of the node configuration, which reports the settings that
+//! would prevent the node from starting or that would be rejected on a reload.
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+use tracing_subscriber::EnvFilter;
+
+use super::{Config, DEFAULT_WASM_CHECKSUMS_FILE, MIN_KEEP_RECENT_BLOCKS};
+use crate::node::ledger::rpc::{path_name, PATH_NAMES};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    #[error(
+        "`{0}` and `{1}` both listen on {2}. Change the port of one of them."
+    )]
+    DuplicateAddress(&'static str, &'static str, SocketAddr),
+    #[error(
+        "`{0}` uses the port 0, which picks a random port on every start. Set \
+         a fixed port."
+    )]
+    ZeroPort(&'static str),
+    #[error(
+        "The WASM directory {0} doesn't exist. Set `wasm_dir` to the \
+         directory with the WASM files of the chain, relative to the chain \
+         directory."
+    )]
+    MissingWasmDir(PathBuf),
+    #[error(
+        "The WASM checksums file {0} doesn't exist. Copy the checksums of the \
+         chain to the WASM directory."
+    )]
+    MissingWasmChecksums(PathBuf),
+    #[error(
+        "`ledger.tendermint.instrumentation_namespace` must not be empty when \
+         `ledger.tendermint.instrumentation_prometheus` is enabled."
+    )]
+    EmptyInstrumentationNamespace,
+    #[error(
+        "`ledger.tendermint.rpc_cors_allowed_origins` contains an invalid \
+         origin \"{0}\". Use `*` or an origin like `https://example.com`, \
+         without a path."
+    )]
+    InvalidCorsOrigin(String),
+    #[error(
+        "`ledger.shell.proposal_gas_limit` must be greater than 0. Remove it \
+         to not limit the gas of the proposed blocks."
+    )]
+    ZeroProposalGasLimit,
+    #[error(
+        "`ledger.shell.log_level` \"{0}\" is not a valid log filter: {1}. Use \
+         a level like `info` or directives like `anoma=debug,info`."
+    )]
+    InvalidLogLevel(String, String),
+    #[error(
+        "`ledger.rpc.max_queries_per_second` must be greater than 0. Remove \
+         it to not limit the queries."
+    )]
+    ZeroQueryRateLimit,
+    #[error(
+        "`ledger.rpc.{0}` contains an unknown query path \"{1}\". Use the \
+         name of a query path like `dry_run_tx` or `prefix`, optionally \
+         followed by the rest of the path like `value/#atest1...`."
+    )]
+    UnknownQueryPath(&'static str, String),
+    #[error(
+        "`ledger.rpc.path_max_queries_per_second` of \"{0}\" must be greater \
+         than 0. Add the path to `ledger.rpc.denied_paths` to not serve its \
+         queries."
+    )]
+    ZeroPathRateLimit(String),
+    #[error(
+        "`ledger.pruning.keep_recent` must be at least {0}, so that the last \
+         block can be rolled back."
+    )]
+    PruningKeepRecentTooLow(u64),
+    #[error(
+        "`ledger.pruning.keep_every` must be greater than 0. Remove it to \
+         only keep the recent blocks."
+    )]
+    ZeroPruningKeepEvery,
+    #[error(
+        "`ledger.shell.snapshot_interval` must be greater than 0. Remove it \
+         to not take snapshots."
+    )]
+    ZeroSnapshotInterval,
+}
+
+/// Validate the configuration with the given WASM directory of the chain.
+/// Returns all the errors found, which is empty for a valid configuration.
+pub fn validate(config: &Config, wasm_dir: impl AsRef<Path>) -> Vec<Error> {
+    let mut errors = vec![];
+    let ledger = &config.ledger;
+
+    let mut addresses = vec![
+        ("ledger.shell.ledger_address", ledger.shell.ledger_address),
+        (
+            "ledger.tendermint.rpc_address",
+            ledger.tendermint.rpc_address,
+        ),
+        (
+            "ledger.tendermint.p2p_address",
+            ledger.tendermint.p2p_address,
+        ),
+        (
+            "intent_gossiper.matchmakers_server_addr",
+            config.intent_gossiper.matchmakers_server_addr,
+        ),
+    ];
+    if ledger.tendermint.instrumentation_prometheus {
+        addresses.push((
+            "ledger.tendermint.instrumentation_prometheus_listen_addr",
+            ledger.tendermint.instrumentation_prometheus_listen_addr,
+        ));
+        if ledger.tendermint.instrumentation_namespace.is_empty() {
+            errors.push(Error::EmptyInstrumentationNamespace);
+        }
+    }
+    if let Some(grpc_address) = ledger.shell.grpc_address {
+        addresses.push(("ledger.shell.grpc_address", grpc_address));
+    }
+    if let Some(rest_address) = ledger.shell.rest_address {
+        addresses.push(("ledger.shell.rest_address", rest_address));
+    }
+    if let Some(rpc) = &config.intent_gossiper.rpc {
+        addresses.push(("intent_gossiper.rpc.address", rpc.address));
+    }
+    for (i, (key, address)) in addresses.iter().enumerate() {
+        if address.port() == 0 {
+            errors.push(Error::ZeroPort(key));
+            continue;
+        }
+        if let Some((other_key, _)) = addresses[..i]
+            .iter()
+            .find(|(_, other)| addresses_overlap(address, other))
+        {
+            errors.push(Error::DuplicateAddress(other_key, key, *address));
+        }
+    }
+
+    let wasm_dir = wasm_dir.as_ref();
+    if !wasm_dir.is_dir() {
+        errors.push(Error::MissingWasmDir(wasm_dir.to_path_buf()));
+    } else {
+        let checksums = wasm_dir.join(DEFAULT_WASM_CHECKSUMS_FILE);
+        if !checksums.is_file() {
+            errors.push(Error::MissingWasmChecksums(checksums));
+        }
+    }
+
+    for origin in &ledger.tendermint.rpc_cors_allowed_origins {
+        if !is_valid_cors_origin(origin) {
+            errors.push(Error::InvalidCorsOrigin(origin.clone()));
+        }
+    }
+    if ledger.shell.proposal_gas_limit == Some(0) {
+        errors.push(Error::ZeroProposalGasLimit);
+    }
+    if ledger.shell.snapshot_interval == Some(0) {
+        errors.push(Error::ZeroSnapshotInterval);
+    }
+    if let Some(log_level) = &ledger.shell.log_level {
+        if let Err(err) = EnvFilter::try_new(log_level) {
+            errors.push(Error::InvalidLogLevel(
+                log_level.clone(),
+                err.to_string(),
+            ));
+        }
+    }
+    if ledger.rpc.max_queries_per_second == Some(0) {
+        errors.push(Error::ZeroQueryRateLimit);
+    }
+    for (key, paths) in [
+        ("allowed_paths", &ledger.rpc.allowed_paths),
+        ("denied_paths", &ledger.rpc.denied_paths),
+    ] {
+        for path in paths {
+            if !PATH_NAMES.contains(&path_name(path)) {
+                errors.push(Error::UnknownQueryPath(key, path.clone()));
+            }
+        }
+    }
+    let mut path_limits: Vec<_> =
+        ledger.rpc.path_max_queries_per_second.iter().collect();
+    path_limits.sort();
+    for (path, max_per_second) in path_limits {
+        if !PATH_NAMES.contains(&path.as_str()) {
+            errors.push(Error::UnknownQueryPath(
+                "path_max_queries_per_second",
+                path.clone(),
+            ));
+        } else if *max_per_second == 0 {
+            errors.push(Error::ZeroPathRateLimit(path.clone()));
+        }
+    }
+    if matches!(
+        ledger.pruning.keep_recent,
+        Some(keep_recent) if keep_recent < MIN_KEEP_RECENT_BLOCKS
+    ) {
+        errors.push(Error::PruningKeepRecentTooLow(MIN_KEEP_RECENT_BLOCKS));
+    }
+    if ledger.pruning.keep_every == Some(0) {
+        errors.push(Error::ZeroPruningKeepEvery);
+    }
+    errors
+}
+
+/// Two addresses overlap when they have the same port and either the same IP
+/// or one of them listens on all the interfaces.
+fn addresses_overlap(a: &SocketAddr, b: &SocketAddr) -> bool {
+    a.port() == b.port()
+        && (a.ip() == b.ip()
+            || a.ip().is_unspecified()
+            || b.ip().is_unspecified())
+}
+
+fn is_valid_cors_origin(origin: &str) -> bool {
+    if origin == "*" {
+        return true;
+    }
+    let host = origin
+        .strip_prefix("https://")
+        .or_else(|| origin.strip_prefix("http://"));
+    matches!(host, Some(host) if !host.is_empty() && !host.contains('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use anoma::types::chain::ChainId;
+
+    use super::*;
+    use crate::config::TendermintMode;
+
+    #[test]
+    fn test_validate_default_config() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let wasm_dir = base_dir.path().join("wasm");
+        let config = Config::new(
+            base_dir.path(),
+            ChainId::default(),
+            TendermintMode::Full,
+        );
+        assert_eq!(
+            validate(&config, &wasm_dir),
+            vec![Error::MissingWasmDir(wasm_dir.clone())]
+        );
+        fs::create_dir(&wasm_dir).unwrap();
+        fs::write(wasm_dir.join(DEFAULT_WASM_CHECKSUMS_FILE), "{}").unwrap();
+        assert!(validate(&config, &wasm_dir).is_empty());
+    }
+
+    #[test]
+    fn test_validate_invalid_settings() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let wasm_dir = base_dir.path().join("wasm");
+        fs::create_dir(&wasm_dir).unwrap();
+        fs::write(wasm_dir.join(DEFAULT_WASM_CHECKSUMS_FILE), "{}").unwrap();
+        let mut config = Config::new(
+            base_dir.path(),
+            ChainId::default(),
+            TendermintMode::Full,
+        );
+        let rpc_address = config.ledger.tendermint.rpc_address;
+        config.ledger.tendermint.p2p_address = rpc_address;
+        config.ledger.tendermint.rpc_cors_allowed_origins =
+            vec!["*".to_string(), "example.com".to_string()];
+        config.ledger.shell.snapshot_interval = Some(0);
+        config.ledger.shell.log_level = Some("anoma=verbose".to_string());
+        config.ledger.rpc.max_queries_per_second = Some(0);
+        config.ledger.rpc.denied_paths =
+            vec!["dry_run_tx".to_string(), "prefixes/#a".to_string()];
+        config
+            .ledger
+            .rpc
+            .path_max_queries_per_second
+            .insert("prefix".to_string(), 0);
+        config.ledger.pruning.keep_recent = Some(1);
+        config.ledger.pruning.keep_every = Some(0);
+        let errors = validate(&config, &wasm_dir);
+        assert_eq!(errors.len(), 9);
+        assert_eq!(
+            errors[0],
+            Error::DuplicateAddress(
+                "ledger.tendermint.rpc_address",
+                "ledger.tendermint.p2p_address",
+                rpc_address
+            )
+        );
+        assert_eq!(errors[1], Error::InvalidCorsOrigin("example.com".into()));
+        assert_eq!(errors[2], Error::ZeroSnapshotInterval);
+        assert!(matches!(errors[3], Error::InvalidLogLevel(_, _)));
+        assert_eq!(errors[4], Error::ZeroQueryRateLimit);
+        assert_eq!(
+            errors[5],
+            Error::UnknownQueryPath("denied_paths", "prefixes/#a".into())
+        );
+        assert_eq!(errors[6], Error::ZeroPathRateLimit("prefix".into()));
+        assert_eq!(
+            errors[7],
+            Error::PruningKeepRecentTooLow(MIN_KEEP_RECENT_BLOCKS)
+        );
+        assert_eq!(errors[8], Error::ZeroPruningKeepEvery);
+    }
+}
@@ -2,16 +2,22 @@
 use std::env;
 
 use color_eyre::eyre::Result;
-use eyre::WrapErr;
+use eyre::{eyre, WrapErr};
+use once_cell::sync::OnceCell;
 use tracing_log::LogTracer;
 use tracing_subscriber::filter::{Directive, EnvFilter};
-use tracing_subscriber::fmt::Subscriber;
+use tracing_subscriber::fmt::{Formatter, Subscriber};
+use tracing_subscriber::reload;
 
 pub const ENV_KEY: &str = "ANOMA_LOG";
 
 // Env var to enable/disable color log
 const COLOR_ENV_KEY: &str = "ANOMA_LOG_COLOR";
 
+/// The handle to replace the filter of the global subscriber
+static FILTER_HANDLE: OnceCell<reload::Handle<EnvFilter, Formatter>> =
+    OnceCell::new();
+
 pub fn init_from_env_or(default: impl Into<Directive>) -> Result<()> {
     let filter = filter_from_env_or(default);
     set_subscriber(filter)?;
@@ -31,12 +37,35 @@ pub fn set_subscriber(filter: EnvFilter) -> Result<()> {
         true
     };
 
-    let my_collector = Subscriber::builder()
+    let builder = Subscriber::builder()
         .with_ansi(with_color)
         .with_env_filter(filter)
-        .finish();
+        .with_filter_reloading();
+    let handle = builder.reload_handle();
+    let my_collector = builder.finish();
     tracing::subscriber::set_global_default(my_collector)
-        .wrap_err("Failed to set log subscriber")
+        .wrap_err("Failed to set log subscriber")?;
+    // The handle is only set once, together with the global subscriber
+    let _ = FILTER_HANDLE.set(handle);
+    Ok(())
+}
+
+/// Whether the log filter is set with the env var, which takes precedence over
+/// the configured log filter
+pub fn is_filter_from_env() -> bool {
+    env::var(ENV_KEY).is_ok()
+}
+
+/// Replace the filter of the global subscriber with the given directives
+pub fn reload_filter(directives: impl AsRef<str>) -> Result<()> {
+    let directives = directives.as_ref();
+    let filter = EnvFilter::try_new(directives)
+        .wrap_err_with(|| format!("Invalid log filter {}", directives))?;
+    FILTER_HANDLE
+        .get()
+        .ok_or_else(|| eyre!("The log subscriber is not set"))?
+        .reload(filter)
+        .wrap_err("Failed to reload the log filter")
 }
 
 pub fn init_log_tracer() -> Result<()> {
@@ -14,6 +14,8 @@ pub mod proto;
 pub mod wallet;
 pub mod wasm_loader;
 
+include!("../../version.rs");
+
 // This is here only to include the std's docs in our docs.
 // Taken from <https://github.com/rust-lang/rfcs/issues/2324#issuecomment-502437904>.
 #[doc(inline)]
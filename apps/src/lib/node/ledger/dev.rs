@@ -0,0 +1,108 @@
+//! The local development mode of the ledger, started with `ledger run --dev`.
+//! The node runs a single-validator chain with the default chain ID and the
+//! "dev" genesis, in which the well-known accounts from
+//! [`crate::wallet::defaults`] are prefunded, so that the VPs and txs can be
+//! tried without setting up a network with `utils init-network`.
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anoma::types::chain::ChainId;
+use tendermint_compat::tendermint::Timeout;
+
+use crate::cli::Context;
+use crate::config::global::GlobalConfig;
+use crate::config::{Config, TendermintMode};
+use crate::wallet::Wallet;
+
+/// The Tendermint `timeout_commit` of the dev chain, for sub-second blocks
+pub const DEV_CONSENSUS_TIMEOUT_COMMIT: &str = "100ms";
+
+/// Switch the context to the dev chain and initialize the wallet of the chain
+/// with the keys and the aliases of the well-known accounts, if it doesn't
+/// exist yet. The global config is not changed, so when its default chain ID
+/// is another chain, the client commands need `--chain-id` for the dev chain.
+pub fn init(ctx: &mut Context) {
+    let base_dir = ctx.global_args.base_dir.clone();
+    let chain_id = ChainId::default();
+    if ctx.global_config.default_chain_id != chain_id {
+        println!(
+            "The default chain ID is {}. Use `--chain-id {}` with the client \
+             commands for the dev chain.",
+            ctx.global_config.default_chain_id, chain_id
+        );
+    }
+    ctx.global_config = GlobalConfig::new(chain_id.clone());
+    ctx.config = dev_config(
+        &base_dir,
+        &chain_id,
+        ctx.global_args.mode.clone(),
+        ctx.config.wasm_dir.clone(),
+    );
+
+    // In "dev" build, a new wallet is loaded with the default keys and
+    // addresses and the genesis file is not used
+    let chain_dir = base_dir.join(chain_id.as_str());
+    ctx.wallet = Wallet::load_or_new_from_genesis(&chain_dir, || {
+        unreachable!("The \"dev\" wallet doesn't load the genesis file")
+    });
+    tracing::info!(
+        "Running the dev chain {} with the wallet in {}",
+        chain_id,
+        chain_dir.display()
+    );
+}
+
+/// The config of the dev chain with sub-second blocks. The node is the only
+/// validator of the chain, unless another `mode` is given.
+fn dev_config(
+    base_dir: &Path,
+    chain_id: &ChainId,
+    mode: Option<TendermintMode>,
+    wasm_dir: PathBuf,
+) -> Config {
+    let mut config = Config::load(base_dir, chain_id, mode.clone());
+    config.wasm_dir = wasm_dir;
+    config.ledger.tendermint.tendermint_mode =
+        mode.unwrap_or(TendermintMode::Validator);
+    config.ledger.tendermint.consensus_timeout_commit =
+        Timeout::from_str(DEV_CONSENSUS_TIMEOUT_COMMIT).unwrap();
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the dev chain runs a validator with sub-second blocks,
+    /// unless another mode is given.
+    #[test]
+    fn test_dev_config() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let chain_id = ChainId::default();
+        let wasm_dir = base_dir.path().join("wasm");
+
+        let config =
+            dev_config(base_dir.path(), &chain_id, None, wasm_dir.clone());
+        assert_eq!(config.wasm_dir, wasm_dir);
+        assert!(matches!(
+            config.ledger.tendermint.tendermint_mode,
+            TendermintMode::Validator
+        ));
+        assert_eq!(
+            config.ledger.tendermint.consensus_timeout_commit,
+            Timeout::from_str(DEV_CONSENSUS_TIMEOUT_COMMIT).unwrap()
+        );
+
+        let config = dev_config(
+            base_dir.path(),
+            &chain_id,
+            Some(TendermintMode::Full),
+            wasm_dir,
+        );
+        assert!(matches!(
+            config.ledger.tendermint.tendermint_mode,
+            TendermintMode::Full
+        ));
+    }
+}
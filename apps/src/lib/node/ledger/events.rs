@@ -4,13 +4,12 @@ use std::fmt::{self, Display};
 use std::ops::{Index, IndexMut};
 
 use anoma::ledger::governance::utils::ProposalEvent;
+use anoma::types::address::Address;
 use anoma::types::ibc::IbcEvent;
 use anoma::types::transaction::{hash_tx, TxType};
 use borsh::BorshSerialize;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_proto::abci::EventAttribute;
-#[cfg(feature = "ABCI")]
-use tendermint_proto_abci::abci::EventAttribute;
+use tendermint_compat::events;
+use tendermint_compat::tendermint_proto::abci::EventAttribute;
 use thiserror::Error;
 
 /// Indicates if an event is emitted do to
@@ -41,29 +40,21 @@ pub enum EventType {
     Ibc(String),
     // The proposal that has been executed
     Proposal,
+    // The validator that has been slashed for the evidence of its misbehavior
+    Slash,
+    // The protocol upgrade that has been applied
+    Upgrade,
 }
 
-#[cfg(not(feature = "ABCI"))]
-impl Display for EventType {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            EventType::Accepted => write!(f, "accepted"),
-            EventType::Applied => write!(f, "applied"),
-            EventType::Ibc(t) => write!(f, "{}", t),
-            EventType::Proposal => write!(f, "proposal"),
-        }?;
-        Ok(())
-    }
-}
-
-#[cfg(feature = "ABCI")]
 impl Display for EventType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            EventType::Accepted => write!(f, "applied"),
-            EventType::Applied => write!(f, "applied"),
+            EventType::Accepted => write!(f, "{}", events::ACCEPTED_EVENT),
+            EventType::Applied => write!(f, "{}", events::APPLIED_EVENT),
             EventType::Ibc(t) => write!(f, "{}", t),
             EventType::Proposal => write!(f, "proposal"),
+            EventType::Slash => write!(f, "slash"),
+            EventType::Upgrade => write!(f, "upgrade"),
         }?;
         Ok(())
     }
@@ -71,7 +62,8 @@ impl Display for EventType {
 
 impl Event {
     /// Creates a new event with the hash and height of the transaction
-    /// already filled in
+    /// already filled in. The event of a wrapper has its sender too, which is
+    /// the implicit account of the signer who pays its fee.
     pub fn new_tx_event(tx: &TxType, height: u64) -> Self {
         let mut event = match tx {
             TxType::Wrapper(wrapper) => {
@@ -90,6 +82,7 @@ impl Event {
                 } else {
                     wrapper.tx_hash.to_string()
                 };
+                event["sender"] = Address::from(&wrapper.pk).encode();
                 event
             }
             TxType::Decrypted(decrypted) => {
@@ -172,7 +165,7 @@ impl From<ProposalEvent> for Event {
 
 #[cfg(not(feature = "ABCI"))]
 /// Convert our custom event into the necessary tendermint proto type
-impl From<Event> for tendermint_proto::abci::Event {
+impl From<Event> for tendermint_compat::tendermint_proto::abci::Event {
     fn from(event: Event) -> Self {
         Self {
             r#type: event.event_type.to_string(),
@@ -191,7 +184,7 @@ impl From<Event> for tendermint_proto::abci::Event {
 
 #[cfg(feature = "ABCI")]
 /// Convert our custom event into the necessary tendermint proto type
-impl From<Event> for tendermint_proto_abci::abci::Event {
+impl From<Event> for tendermint_compat::tendermint_proto::abci::Event {
     fn from(event: Event) -> Self {
         Self {
             r#type: event.event_type.to_string(),
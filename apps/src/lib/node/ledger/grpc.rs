@@ -0,0 +1,389 @@
+//! The gRPC server of the ledger queries, which is built with the `grpc`
+//! feature. It serves the same queries as the ABCI [`Path`]s, which are
+//! forwarded to the query shells with a [`QueryHandle`]. The storage values
+//! are encoded with Borsh, as in the responses of the ABCI queries. The
+//! values of a prefix are streamed page by page, so that the values of a
+//! large prefix are never all held in memory. The changes of the watched keys
+//! are streamed from the [`KeyWatcher`] as the blocks are committed.
+
+use std::convert::TryFrom;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use anoma::types::query::{MAX_PREFIX_PAGE_LIMIT, NOT_FOUND_CODE};
+use anoma::types::storage::{self, Epoch, Key};
+use borsh::BorshDeserialize;
+use futures::{Stream, TryStreamExt};
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+#[cfg(not(feature = "ABCI"))]
+use tower_abci::{request, response};
+#[cfg(feature = "ABCI")]
+use tower_abci_old::{request, response};
+
+use crate::node::ledger::key_watch::{self, KeyWatcher, WatchFilter};
+use crate::node::ledger::rpc::{Path, QueryRejection};
+use crate::node::ledger::shims::abcipp_shim::{QueryError, QueryHandle};
+use crate::proto::ledger::query_service_server::{
+    QueryService, QueryServiceServer,
+};
+use crate::proto::ledger::{
+    DryRunTxRequest, DryRunTxResponse, EpochRequest, EpochResponse,
+    HasKeyRequest, HasKeyResponse, KeyChange, PrefixRequest, PrefixValue,
+    ValueRequest, ValueResponse, WatchKeysRequest,
+};
+
+/// The path of the key watches in the rate limits and the access control of
+/// the queries. Only the new watches are counted, not their changes.
+pub const WATCH_KEYS_PATH: &str = "watch_keys";
+
+#[derive(Clone, Debug)]
+struct Queries {
+    handle: Arc<QueryHandle>,
+    key_watcher: KeyWatcher,
+}
+
+impl Queries {
+    /// Forward the query of the given path to the query shells
+    async fn query(
+        &self,
+        path: Path,
+        data: Vec<u8>,
+        height: u64,
+    ) -> Result<response::Query, Status> {
+        let height = i64::try_from(height).map_err(|_| {
+            Status::invalid_argument(format!("Invalid query height {}", height))
+        })?;
+        let query = request::Query {
+            data: data.into(),
+            path: path.to_string(),
+            height,
+            prove: false,
+        };
+        self.handle.query(query).await.map_err(error_status)
+    }
+
+    /// Query a page of the values with a matching prefix, whose keys are
+    /// after the `after` key, if any. Returns the page with its height.
+    async fn prefix_page(
+        &self,
+        prefix: Key,
+        after: Option<Key>,
+        height: u64,
+    ) -> Result<(storage::PrefixPage, u64), Status> {
+        let path = Path::PrefixPage {
+            prefix,
+            after,
+            limit: MAX_PREFIX_PAGE_LIMIT,
+        };
+        let response = self.query(path, vec![], height).await?;
+        if response.code != 0 {
+            return Err(Status::internal(response.info));
+        }
+        let page = storage::PrefixPage::try_from_slice(&response.value[..])
+            .map_err(|err| {
+                Status::internal(format!(
+                    "Error decoding the prefix values: {}",
+                    err
+                ))
+            })?;
+        Ok((page, response.height as u64))
+    }
+}
+
+#[tonic::async_trait]
+impl QueryService for Queries {
+    type PrefixStream =
+        Pin<Box<dyn Stream<Item = Result<PrefixValue, Status>> + Send>>;
+    type WatchKeysStream =
+        Pin<Box<dyn Stream<Item = Result<KeyChange, Status>> + Send>>;
+
+    async fn value(
+        &self,
+        request: Request<ValueRequest>,
+    ) -> Result<Response<ValueResponse>, Status> {
+        let ValueRequest { key, height } = request.into_inner();
+        let path = Path::Value(parse_key(&key)?);
+        let response = self.query(path, vec![], height).await?;
+        let value = match response.code {
+            0 => Some(response.value.into()),
+            NOT_FOUND_CODE => None,
+            _ => return Err(Status::internal(response.info)),
+        };
+        Ok(Response::new(ValueResponse { value }))
+    }
+
+    async fn prefix(
+        &self,
+        request: Request<PrefixRequest>,
+    ) -> Result<Response<Self::PrefixStream>, Status> {
+        let PrefixRequest { prefix, height } = request.into_inner();
+        let prefix = parse_key(&prefix)?;
+        let queries = self.clone();
+        // The next page is only read once the values of the previous one are
+        // sent, at the height of the first page. The state is the key after
+        // which the next page starts, or `None` after the last page.
+        let pages = futures::stream::try_unfold(
+            (Some(None), height),
+            move |(after, height)| {
+                let queries = queries.clone();
+                let prefix = prefix.clone();
+                async move {
+                    let after = match after {
+                        Some(after) => after,
+                        None => return Ok(None),
+                    };
+                    let (page, height) =
+                        queries.prefix_page(prefix, after, height).await?;
+                    let values = page.values.into_iter().map(
+                        |storage::PrefixValue { key, value }| {
+                            Ok::<_, Status>(PrefixValue {
+                                key: key.to_string(),
+                                value,
+                            })
+                        },
+                    );
+                    let state = (page.next.map(Some), height);
+                    Ok(Some((futures::stream::iter(values), state)))
+                }
+            },
+        );
+        Ok(Response::new(Box::pin(pages.try_flatten())))
+    }
+
+    async fn has_key(
+        &self,
+        request: Request<HasKeyRequest>,
+    ) -> Result<Response<HasKeyResponse>, Status> {
+        let HasKeyRequest { key, height } = request.into_inner();
+        let path = Path::HasKey(parse_key(&key)?);
+        let response = self.query(path, vec![], height).await?;
+        if response.code != 0 {
+            return Err(Status::internal(response.info));
+        }
+        let has_key = bool::try_from_slice(&response.value[..])
+            .map_err(|err| Status::internal(err.to_string()))?;
+        Ok(Response::new(HasKeyResponse { has_key }))
+    }
+
+    async fn epoch(
+        &self,
+        _request: Request<EpochRequest>,
+    ) -> Result<Response<EpochResponse>, Status> {
+        let response = self.query(Path::Epoch, vec![], 0).await?;
+        if response.code != 0 {
+            return Err(Status::internal(response.info));
+        }
+        let Epoch(epoch) = Epoch::try_from_slice(&response.value[..])
+            .map_err(|err| Status::internal(err.to_string()))?;
+        Ok(Response::new(EpochResponse { epoch }))
+    }
+
+    async fn dry_run_tx(
+        &self,
+        request: Request<DryRunTxRequest>,
+    ) -> Result<Response<DryRunTxResponse>, Status> {
+        let DryRunTxRequest { tx } = request.into_inner();
+        let response = self.query(Path::DryRunTx, tx, 0).await?;
+        if response.code != 0 {
+            return Err(Status::invalid_argument(response.log));
+        }
+        Ok(Response::new(DryRunTxResponse {
+            result: response.info,
+        }))
+    }
+
+    async fn watch_keys(
+        &self,
+        request: Request<WatchKeysRequest>,
+    ) -> Result<Response<Self::WatchKeysStream>, Status> {
+        self.handle
+            .try_acquire(WATCH_KEYS_PATH)
+            .map_err(error_status)?;
+        let WatchKeysRequest { keys, prefixes } = request.into_inner();
+        let filter = WatchFilter {
+            keys: keys
+                .iter()
+                .map(|key| parse_key(key))
+                .collect::<Result<_, _>>()?,
+            prefixes: prefixes
+                .iter()
+                .map(|prefix| parse_key(prefix))
+                .collect::<Result<_, _>>()?,
+        };
+        if filter.is_empty() {
+            return Err(Status::invalid_argument(
+                "No storage keys or prefixes to watch",
+            ));
+        }
+        let watch = self.key_watcher.subscribe(filter);
+        let changes =
+            futures::stream::try_unfold(watch, |mut watch| async move {
+                let changes =
+                    watch.next_changes().await.map_err(watch_error_status)?;
+                let changes = changes.into_iter().map(
+                    |key_watch::KeyChange { key, value, height }| {
+                        Ok::<_, Status>(KeyChange {
+                            key: key.to_string(),
+                            value,
+                            height: height.0,
+                        })
+                    },
+                );
+                Ok(Some((futures::stream::iter(changes), watch)))
+            });
+        Ok(Response::new(Box::pin(changes.try_flatten())))
+    }
+}
+
+fn parse_key(key: &str) -> Result<Key, Status> {
+    Key::parse(key).map_err(|err| {
+        Status::invalid_argument(format!(
+            "Invalid storage key {}: {}",
+            key, err
+        ))
+    })
+}
+
+/// The status of a query that wasn't answered by the query shells
+fn error_status(error: QueryError) -> Status {
+    match error {
+        QueryError::Rejected(QueryRejection::RateLimited) => {
+            Status::resource_exhausted(error.to_string())
+        }
+        QueryError::Rejected(QueryRejection::PathNotAllowed) => {
+            Status::permission_denied(error.to_string())
+        }
+        QueryError::ShutDown => Status::unavailable(error.to_string()),
+        QueryError::Failed(_) => Status::internal(error.to_string()),
+    }
+}
+
+/// The status of a key watch that ended
+fn watch_error_status(error: key_watch::Error) -> Status {
+    match error {
+        key_watch::Error::Lagged(_) => Status::data_loss(error.to_string()),
+        key_watch::Error::Closed => Status::unavailable(error.to_string()),
+    }
+}
+
+/// Serve the ledger queries and the key watches over gRPC at the given
+/// address, until the server fails or the task is aborted
+pub async fn run_server(
+    address: SocketAddr,
+    queries: QueryHandle,
+    key_watcher: KeyWatcher,
+) -> Result<(), tonic::transport::Error> {
+    let queries = Queries {
+        handle: Arc::new(queries),
+        key_watcher,
+    };
+    tracing::info!("gRPC query server started at {}", address);
+    Server::builder()
+        .add_service(QueryServiceServer::new(queries))
+        .serve(address)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use anoma::types::storage::BlockHeight;
+    use futures::StreamExt;
+    use tonic::Code;
+
+    use super::*;
+    use crate::config;
+    use crate::node::ledger::rate_limit::QueryRateLimiter;
+
+    /// Test that the queries of the paths that aren't allowed are rejected
+    /// before they're sent to the query shells.
+    #[test]
+    fn test_rejected_query() {
+        let (query_send, _query_recv) = std::sync::mpsc::channel();
+        let query_limiter = QueryRateLimiter::new(config::Rpc {
+            denied_paths: vec!["dry_run_tx".to_string()],
+            ..Default::default()
+        });
+        let queries = Queries {
+            handle: Arc::new(QueryHandle::new(query_send, query_limiter)),
+            key_watcher: KeyWatcher::default(),
+        };
+        let request = Request::new(DryRunTxRequest { tx: vec![] });
+        let result = futures::executor::block_on(queries.dry_run_tx(request));
+        assert_eq!(result.unwrap_err().code(), Code::PermissionDenied);
+        let request = Request::new(ValueRequest {
+            key: "#invalid".to_string(),
+            height: 0,
+        });
+        let result = futures::executor::block_on(queries.value(request));
+        assert_eq!(result.unwrap_err().code(), Code::InvalidArgument);
+    }
+
+    /// Test that the changes of the watched keys are streamed once they're
+    /// published and that a watch needs some keys or prefixes.
+    #[test]
+    fn test_watch_keys() {
+        let (query_send, _query_recv) = std::sync::mpsc::channel();
+        let query_limiter = QueryRateLimiter::new(config::Rpc::default());
+        let queries = Queries {
+            handle: Arc::new(QueryHandle::new(query_send, query_limiter)),
+            key_watcher: KeyWatcher::default(),
+        };
+        let request = Request::new(WatchKeysRequest::default());
+        let result = futures::executor::block_on(queries.watch_keys(request));
+        assert_eq!(result.err().unwrap().code(), Code::InvalidArgument);
+
+        let request = Request::new(WatchKeysRequest {
+            keys: vec![],
+            prefixes: vec!["a".to_string()],
+        });
+        let mut changes =
+            futures::executor::block_on(queries.watch_keys(request))
+                .unwrap()
+                .into_inner();
+        queries.key_watcher.publish(vec![key_watch::KeyChange {
+            key: Key::parse("a/b").unwrap(),
+            value: None,
+            height: BlockHeight(1),
+        }]);
+        let change = futures::executor::block_on(changes.next());
+        assert_eq!(
+            change.unwrap().unwrap(),
+            KeyChange {
+                key: "a/b".to_string(),
+                value: None,
+                height: 1,
+            }
+        );
+    }
+
+    /// Test that the new key watches are rate limited.
+    #[test]
+    fn test_watch_keys_rate_limit() {
+        let (query_send, _query_recv) = std::sync::mpsc::channel();
+        let query_limiter = QueryRateLimiter::new(config::Rpc {
+            path_max_queries_per_second: HashMap::from([(
+                WATCH_KEYS_PATH.to_string(),
+                1,
+            )]),
+            ..Default::default()
+        });
+        let queries = Queries {
+            handle: Arc::new(QueryHandle::new(query_send, query_limiter)),
+            key_watcher: KeyWatcher::default(),
+        };
+        let request = || {
+            Request::new(WatchKeysRequest {
+                keys: vec!["a".to_string()],
+                prefixes: vec![],
+            })
+        };
+        let result = futures::executor::block_on(queries.watch_keys(request()));
+        assert!(result.is_ok());
+        let result = futures::executor::block_on(queries.watch_keys(request()));
+        assert_eq!(result.err().unwrap().code(), Code::ResourceExhausted);
+    }
+}
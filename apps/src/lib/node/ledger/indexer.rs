@@ -0,0 +1,872 @@
+//! The indexer is an optional service of the ledger node that stores the txs
+//! included in the committed blocks with their results, the addresses
+//! involved in them and their token movements, so that they can be queried by
+//! explorers and wallets. The index is kept in its own SQLite DB, separately
+//! from the ledger's storage, as it's not a part of the state. The main shell
+//! writes the committed blocks to the DB and the query shells read it with
+//! their own read-only connections.
+//!
+//! The indexer DB contains the following tables:
+//! - `txs`: the indexed txs by their height and position in block
+//! - `tx_addresses`: the addresses involved in the txs
+//! - `token_movements`: the changes of token balances made by the txs, with
+//!   the signed change encoded as a decimal string
+//! - `tx_events`: the events emitted for the txs, with their attributes
+//!   encoded as a JSON object
+//! - `balances`: the tokens of which the owners have had a balance, so that
+//!   all the balances of an owner can be read without scanning the balances
+//!   of every token
+//!
+//! The addresses are encoded with bech32m.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+use anoma::ledger::storage::write_log::{StorageModification, WriteLog};
+use anoma::ledger::storage::{DBIter, Storage, StorageHasher, DB};
+use anoma::types::address::Address;
+use anoma::types::indexer::{
+    BlockEvent, BlockResults, BlockTxResult, IndexedTx, TokenMovement,
+    TransferRecord,
+};
+use anoma::types::storage::{BlockHeight, DbKeySeg, Key};
+use anoma::types::token;
+use borsh::BorshDeserialize;
+use rusqlite::{params, Connection, OpenFlags, ToSql};
+use thiserror::Error;
+
+use crate::node::ledger::events::Event;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Indexer DB error: {0}")]
+    DB(rusqlite::Error),
+    #[error("Cannot create the indexer DB directory: {0}")]
+    CreateDir(std::io::Error),
+    #[error("Indexer DB decoding error: {0}")]
+    Decoding(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The file of the SQLite DB in the indexer DB directory
+const DB_FILE: &str = "index.sqlite";
+
+/// The tables of the indexer DB, which are created when it's opened for
+/// writing. The heights and the positions in block are the keys of the txs.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS txs (
+    height INTEGER NOT NULL,
+    tx_index INTEGER NOT NULL,
+    hash TEXT NOT NULL,
+    code INTEGER NOT NULL,
+    gas_used INTEGER NOT NULL,
+    info TEXT NOT NULL,
+    sender TEXT,
+    PRIMARY KEY (height, tx_index)
+);
+CREATE INDEX IF NOT EXISTS txs_by_hash ON txs (hash);
+CREATE INDEX IF NOT EXISTS txs_by_sender ON txs (sender);
+CREATE TABLE IF NOT EXISTS tx_addresses (
+    address TEXT NOT NULL,
+    height INTEGER NOT NULL,
+    tx_index INTEGER NOT NULL,
+    PRIMARY KEY (address, height, tx_index)
+);
+CREATE TABLE IF NOT EXISTS token_movements (
+    height INTEGER NOT NULL,
+    tx_index INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    token TEXT NOT NULL,
+    owner TEXT NOT NULL,
+    amount_change TEXT NOT NULL,
+    PRIMARY KEY (height, tx_index, position)
+);
+CREATE INDEX IF NOT EXISTS token_movements_by_token
+    ON token_movements (token, height, tx_index);
+CREATE TABLE IF NOT EXISTS tx_events (
+    height INTEGER NOT NULL,
+    tx_index INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    event_type TEXT NOT NULL,
+    attributes TEXT NOT NULL,
+    PRIMARY KEY (height, tx_index, position)
+);
+CREATE TABLE IF NOT EXISTS balances (
+    owner TEXT NOT NULL,
+    token TEXT NOT NULL,
+    PRIMARY KEY (owner, token)
+);
+";
+
+/// The tables of the txs data, from which the txs above a height are removed
+/// on a rollback
+const TX_TABLES: [&str; 4] =
+    ["txs", "tx_addresses", "token_movements", "tx_events"];
+
+/// The indexer of the txs in committed blocks
+#[derive(Debug)]
+pub struct Indexer {
+    /// The connection isn't `Sync`, so it's shared by the queries behind a
+    /// lock
+    db: Mutex<Connection>,
+    /// The txs of the block that is being finalized, with the events emitted
+    /// for them. They are written to the DB when the block is committed.
+    block_txs: Vec<(IndexedTx, Vec<BlockEvent>)>,
+    /// The owners and the tokens of the balances written in the block that
+    /// is being committed
+    block_balances: BTreeSet<(Address, Address)>,
+}
+
+impl Indexer {
+    /// Open the indexer DB in the given directory for reading and writing,
+    /// creating its tables if needed
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        std::fs::create_dir_all(dir.as_ref()).map_err(Error::CreateDir)?;
+        let db =
+            Connection::open(dir.as_ref().join(DB_FILE)).map_err(Error::DB)?;
+        // With the write-ahead log, the read-only connections of the queries
+        // don't block the commits of the blocks
+        db.query_row("PRAGMA journal_mode = WAL", [], |_row| Ok(()))
+            .map_err(Error::DB)?;
+        db.execute_batch(SCHEMA).map_err(Error::DB)?;
+        Ok(Self::new(db))
+    }
+
+    /// Open a read-only connection to the indexer DB in the given directory,
+    /// which has to be created with [`Indexer::open`] first. The connection
+    /// sees the txs as soon as their block is committed.
+    pub fn open_read_only(dir: impl AsRef<Path>) -> Result<Self> {
+        let db = Connection::open_with_flags(
+            dir.as_ref().join(DB_FILE),
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .map_err(Error::DB)?;
+        Ok(Self::new(db))
+    }
+
+    fn new(db: Connection) -> Self {
+        Self {
+            db: Mutex::new(db),
+            block_txs: vec![],
+            block_balances: BTreeSet::new(),
+        }
+    }
+
+    /// Add a tx of the block that is being finalized, with the events
+    /// emitted for it
+    pub fn add_tx(&mut self, tx: IndexedTx, events: Vec<BlockEvent>) {
+        self.block_txs.push((tx, events));
+    }
+
+    /// Add the changes of the account subspaces of the block that is being
+    /// committed, from which the written balances are indexed
+    pub fn add_block_changes<'a>(
+        &mut self,
+        changes: impl Iterator<Item = (&'a Key, Option<&'a Vec<u8>>)>,
+    ) {
+        for (key, value) in changes {
+            // The deleted balances are kept in the index, so that it doesn't
+            // need to be reverted on a rollback
+            if value.is_none() {
+                continue;
+            }
+            if let (Some(DbKeySeg::AddressSeg(token)), Some(owner)) =
+                (key.segments.first(), token::is_any_token_balance_key(key))
+            {
+                self.block_balances.insert((owner.clone(), token.clone()));
+            }
+        }
+    }
+
+    /// Write the txs and the balances of the committed block in a single DB
+    /// transaction
+    pub fn commit_block(&mut self) -> Result<()> {
+        let block_balances = std::mem::take(&mut self.block_balances);
+        let block_txs = std::mem::take(&mut self.block_txs);
+        let db = self
+            .db
+            .get_mut()
+            .expect("The indexer DB lock shouldn't be poisoned");
+        let db_tx = db.transaction().map_err(Error::DB)?;
+        for (owner, token) in block_balances {
+            db_tx
+                .execute(
+                    "INSERT OR IGNORE INTO balances (owner, token) \
+                     VALUES (?1, ?2)",
+                    params![owner.encode(), token.encode()],
+                )
+                .map_err(Error::DB)?;
+        }
+        for (tx, events) in block_txs {
+            let height = tx.height.0 as i64;
+            db_tx
+                .execute(
+                    "INSERT OR REPLACE INTO txs (height, tx_index, hash, \
+                     code, gas_used, info, sender) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        height,
+                        tx.index,
+                        tx.hash,
+                        tx.code,
+                        tx.gas_used as i64,
+                        tx.info,
+                        tx.sender.as_ref().map(Address::encode),
+                    ],
+                )
+                .map_err(Error::DB)?;
+            for address in &tx.addresses {
+                db_tx
+                    .execute(
+                        "INSERT OR REPLACE INTO tx_addresses (address, \
+                         height, tx_index) VALUES (?1, ?2, ?3)",
+                        params![address.encode(), height, tx.index],
+                    )
+                    .map_err(Error::DB)?;
+            }
+            for (position, movement) in tx.movements.iter().enumerate() {
+                db_tx
+                    .execute(
+                        "INSERT OR REPLACE INTO token_movements (height, \
+                         tx_index, position, token, owner, amount_change) \
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                        params![
+                            height,
+                            tx.index,
+                            position as i64,
+                            movement.token.encode(),
+                            movement.owner.encode(),
+                            movement.change.to_string(),
+                        ],
+                    )
+                    .map_err(Error::DB)?;
+            }
+            for (position, event) in events.iter().enumerate() {
+                let attributes = serde_json::to_string(&event.attributes)
+                    .expect("Encoding the event attributes shouldn't fail");
+                db_tx
+                    .execute(
+                        "INSERT OR REPLACE INTO tx_events (height, \
+                         tx_index, position, event_type, attributes) \
+                         VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![
+                            height,
+                            tx.index,
+                            position as i64,
+                            event.event_type,
+                            attributes,
+                        ],
+                    )
+                    .map_err(Error::DB)?;
+            }
+        }
+        db_tx.commit().map_err(Error::DB)
+    }
+
+    /// Find the indexed txs in which the given address is involved, ordered
+    /// by their height and position in block
+    pub fn txs_by_address(&self, address: &Address) -> Result<Vec<IndexedTx>> {
+        self.read_txs(
+            "JOIN tx_addresses USING (height, tx_index) \
+             WHERE tx_addresses.address = ?1",
+            &address.encode(),
+        )
+    }
+
+    /// Find the indexed txs that moved the given token, ordered by their
+    /// height and position in block. Only the movements of this token are
+    /// retained in the txs.
+    pub fn txs_by_token(&self, token: &Address) -> Result<Vec<IndexedTx>> {
+        let mut txs = self.read_txs(
+            "WHERE EXISTS (SELECT 1 FROM token_movements \
+             WHERE token_movements.height = txs.height \
+             AND token_movements.tx_index = txs.tx_index \
+             AND token_movements.token = ?1)",
+            &token.encode(),
+        )?;
+        for tx in txs.iter_mut() {
+            tx.movements.retain(|movement| &movement.token == token);
+        }
+        Ok(txs)
+    }
+
+    /// Find the indexed txs signed by the given sender, ordered by their
+    /// height and position in block
+    pub fn txs_by_sender(&self, sender: &Address) -> Result<Vec<IndexedTx>> {
+        self.read_txs("WHERE txs.sender = ?1", &sender.encode())
+    }
+
+    /// Find the token transfers of the given owner, ordered by their height
+    /// and position in block
+    pub fn transfer_history(
+        &self,
+        owner: &Address,
+    ) -> Result<Vec<TransferRecord>> {
+        Ok(self
+            .txs_by_address(owner)?
+            .iter()
+            .flat_map(|tx| tx.transfers_of(owner))
+            .collect())
+    }
+
+    /// Find the results of the txs of a committed block. A block without
+    /// txs has no results.
+    pub fn block_results(&self, height: BlockHeight) -> Result<BlockResults> {
+        let txs = self.read_txs("WHERE txs.height = ?1", &(height.0 as i64))?;
+        Ok(BlockResults {
+            height,
+            txs: self.tx_results_of(txs)?,
+        })
+    }
+
+    /// Find the results of the txs with the given hash, ordered by their
+    /// height and position in block. A wrapper tx and its decrypted tx may
+    /// have the same hash and a replayed tx is indexed again with its
+    /// rejection.
+    pub fn tx_results(&self, hash: &str) -> Result<Vec<BlockTxResult>> {
+        let txs = self.read_txs("WHERE txs.hash = ?1", &hash)?;
+        self.tx_results_of(txs)
+    }
+
+    /// Remove the txs indexed above the given height
+    pub fn rollback(&mut self, height: BlockHeight) -> Result<()> {
+        self.block_txs.clear();
+        self.block_balances.clear();
+        let db = self
+            .db
+            .get_mut()
+            .expect("The indexer DB lock shouldn't be poisoned");
+        let db_tx = db.transaction().map_err(Error::DB)?;
+        for table in TX_TABLES {
+            db_tx
+                .execute(
+                    &format!("DELETE FROM {} WHERE height > ?1", table),
+                    params![height.0 as i64],
+                )
+                .map_err(Error::DB)?;
+        }
+        db_tx.commit().map_err(Error::DB)
+    }
+
+    /// Find the tokens of which the given owner has had a balance
+    pub fn tokens_by_owner(&self, owner: &Address) -> Result<Vec<Address>> {
+        let db = self.lock();
+        let mut statement = db
+            .prepare_cached(
+                "SELECT token FROM balances WHERE owner = ?1 ORDER BY token",
+            )
+            .map_err(Error::DB)?;
+        let tokens = statement
+            .query_map(params![owner.encode()], |row| row.get::<_, String>(0))
+            .map_err(Error::DB)?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Error::DB)?;
+        tokens.iter().map(|token| decode_address(token)).collect()
+    }
+
+    /// Read the txs selected by the given SQL clauses on the `txs` table with
+    /// a single parameter, ordered by their height and position in block,
+    /// with their involved addresses and token movements
+    fn read_txs(
+        &self,
+        clauses: &str,
+        param: &dyn ToSql,
+    ) -> Result<Vec<IndexedTx>> {
+        let db = self.lock();
+        let mut statement = db
+            .prepare_cached(&format!(
+                "SELECT txs.height, txs.tx_index, txs.hash, txs.code, \
+                 txs.gas_used, txs.info, txs.sender FROM txs {} \
+                 ORDER BY txs.height, txs.tx_index",
+                clauses
+            ))
+            .map_err(Error::DB)?;
+        let rows = statement
+            .query_map([param], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, u32>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, u32>(3)?,
+                    row.get::<_, i64>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                ))
+            })
+            .map_err(Error::DB)?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Error::DB)?;
+        let mut addresses = db
+            .prepare_cached(
+                "SELECT address FROM tx_addresses \
+                 WHERE height = ?1 AND tx_index = ?2",
+            )
+            .map_err(Error::DB)?;
+        let mut movements = db
+            .prepare_cached(
+                "SELECT token, owner, amount_change FROM token_movements \
+                 WHERE height = ?1 AND tx_index = ?2 ORDER BY position",
+            )
+            .map_err(Error::DB)?;
+        rows.into_iter()
+            .map(|(height, index, hash, code, gas_used, info, sender)| {
+                let tx_addresses = addresses
+                    .query_map(params![height, index], |row| {
+                        row.get::<_, String>(0)
+                    })
+                    .map_err(Error::DB)?
+                    .map(|address| decode_address(&address.map_err(Error::DB)?))
+                    .collect::<Result<BTreeSet<_>>>()?;
+                let tx_movements = movements
+                    .query_map(params![height, index], |row| {
+                        Ok((
+                            row.get::<_, String>(0)?,
+                            row.get::<_, String>(1)?,
+                            row.get::<_, String>(2)?,
+                        ))
+                    })
+                    .map_err(Error::DB)?
+                    .map(|movement| {
+                        let (token, owner, change) =
+                            movement.map_err(Error::DB)?;
+                        Ok(TokenMovement {
+                            token: decode_address(&token)?,
+                            owner: decode_address(&owner)?,
+                            change: change.parse().map_err(
+                                |err: std::num::ParseIntError| {
+                                    Error::Decoding(err.to_string())
+                                },
+                            )?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(IndexedTx {
+                    hash,
+                    height: BlockHeight(height as u64),
+                    index,
+                    code,
+                    gas_used: gas_used as u64,
+                    info,
+                    sender: sender
+                        .map(|sender| decode_address(&sender))
+                        .transpose()?,
+                    addresses: tx_addresses,
+                    movements: tx_movements,
+                })
+            })
+            .collect()
+    }
+
+    /// Read the events of the given txs to make their results
+    fn tx_results_of(&self, txs: Vec<IndexedTx>) -> Result<Vec<BlockTxResult>> {
+        let db = self.lock();
+        let mut events = db
+            .prepare_cached(
+                "SELECT event_type, attributes FROM tx_events \
+                 WHERE height = ?1 AND tx_index = ?2 ORDER BY position",
+            )
+            .map_err(Error::DB)?;
+        txs.into_iter()
+            .map(|tx| {
+                let tx_events = events
+                    .query_map(params![tx.height.0 as i64, tx.index], |row| {
+                        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                    })
+                    .map_err(Error::DB)?
+                    .map(|event| {
+                        let (event_type, attributes) =
+                            event.map_err(Error::DB)?;
+                        Ok(BlockEvent {
+                            event_type,
+                            attributes: serde_json::from_str(&attributes)
+                                .map_err(|err| {
+                                    Error::Decoding(err.to_string())
+                                })?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(BlockTxResult {
+                    hash: tx.hash,
+                    index: tx.index,
+                    code: tx.code,
+                    gas_used: tx.gas_used,
+                    info: tx.info,
+                    events: tx_events,
+                })
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Connection> {
+        self.db
+            .lock()
+            .expect("The indexer DB lock shouldn't be poisoned")
+    }
+}
+
+/// Make a tx to be indexed from its event
+pub fn indexed_tx(
+    tx_event: &Event,
+    height: BlockHeight,
+    index: u32,
+    addresses: BTreeSet<Address>,
+    movements: Vec<TokenMovement>,
+) -> IndexedTx {
+    let attribute = |key: &str| tx_event.get(key).cloned().unwrap_or_default();
+    IndexedTx {
+        hash: attribute("hash"),
+        height,
+        index,
+        code: attribute("code").parse().unwrap_or_default(),
+        gas_used: attribute("gas_used").parse().unwrap_or_default(),
+        info: attribute("info"),
+        sender: tx_event
+            .get("sender")
+            .and_then(|sender| Address::decode(sender).ok()),
+        addresses,
+        movements,
+    }
+}
+
+/// Convert an event emitted for a tx into the event in its block results
+pub fn block_event(event: &Event) -> BlockEvent {
+    BlockEvent {
+        event_type: event.event_type.to_string(),
+        attributes: event
+            .attributes
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect(),
+    }
+}
+
+/// Decode an address stored in the indexer DB
+fn decode_address(address: &str) -> Result<Address> {
+    Address::decode(address).map_err(|err| Error::Decoding(err.to_string()))
+}
+
+/// Find the changes of token balances made by the last applied tx, whose
+/// modifications are still in the tx write log.
+pub fn token_movements<D, H>(
+    changed_keys: &BTreeSet<Key>,
+    write_log: &WriteLog,
+    storage: &Storage<D, H>,
+) -> Vec<TokenMovement>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    changed_keys
+        .iter()
+        .filter_map(|key| {
+            let owner = token::is_any_token_balance_key(key)?;
+            let token = key.segments.first().and_then(|seg| match seg {
+                DbKeySeg::AddressSeg(token) => Some(token),
+                _ => None,
+            })?;
+            let pre = match write_log.read_pre(key).0 {
+                Some(modification) => modification_amount(modification),
+                None => storage
+                    .read(key)
+                    .ok()
+                    .and_then(|(value, _gas)| value)
+                    .and_then(|value| {
+                        token::Amount::try_from_slice(&value[..]).ok()
+                    })
+                    .unwrap_or_default(),
+            };
+            let post = match write_log.read(key).0 {
+                Some(modification) => modification_amount(modification),
+                None => pre,
+            };
+            let change = token::Change::from(post) - token::Change::from(pre);
+            (change != 0).then(|| TokenMovement {
+                token: token.clone(),
+                owner: owner.clone(),
+                change,
+            })
+        })
+        .collect()
+}
+
+/// The token amount written by a storage modification of a balance key
+fn modification_amount(modification: &StorageModification) -> token::Amount {
+    match modification {
+        StorageModification::Write { value }
+        | StorageModification::Temp { value } => {
+            token::Amount::try_from_slice(&value[..]).unwrap_or_default()
+        }
+        StorageModification::Delete
+        | StorageModification::InitAccount { .. } => token::Amount::default(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anoma::types::address;
+    use borsh::BorshSerialize;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn indexed_tx(
+        height: u64,
+        index: u32,
+        addresses: BTreeSet<Address>,
+        movements: Vec<TokenMovement>,
+    ) -> IndexedTx {
+        IndexedTx {
+            hash: format!("{}-{}", height, index),
+            height: BlockHeight(height),
+            index,
+            code: 0,
+            gas_used: 0,
+            info: String::new(),
+            sender: None,
+            addresses,
+            movements,
+        }
+    }
+
+    /// Test that the tokens of the written balances of an owner are indexed,
+    /// and that they're kept when the balances are deleted.
+    #[test]
+    fn test_tokens_by_owner() {
+        let dir = tempdir().unwrap();
+        let mut indexer = Indexer::open(dir.path()).unwrap();
+
+        let owner = address::testing::established_address_1();
+        let other = address::testing::established_address_2();
+        let xan = address::xan();
+        let btc = address::btc();
+        let value = token::Amount::whole(1).try_to_vec().unwrap();
+        let xan_key = token::balance_key(&xan, &owner);
+        let btc_key = token::balance_key(&btc, &owner);
+        let other_key = token::balance_key(&xan, &other);
+        let not_balance = token::total_supply_key(&btc);
+        indexer.add_block_changes(
+            [
+                (&xan_key, Some(&value)),
+                (&other_key, Some(&value)),
+                (&not_balance, Some(&value)),
+            ]
+            .into_iter(),
+        );
+        indexer.commit_block().unwrap();
+        indexer.add_block_changes(
+            [(&xan_key, None), (&btc_key, Some(&value))].into_iter(),
+        );
+        indexer.commit_block().unwrap();
+
+        let tokens: BTreeSet<Address> = indexer
+            .tokens_by_owner(&owner)
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(tokens, BTreeSet::from([xan.clone(), btc]));
+        assert_eq!(indexer.tokens_by_owner(&other).unwrap(), vec![xan]);
+    }
+
+    /// Test that the indexed txs can be found by address and by token and
+    /// that they can be rolled back.
+    #[test]
+    fn test_index_txs() {
+        let dir = tempdir().unwrap();
+        let mut indexer = Indexer::open(dir.path()).unwrap();
+
+        let source = address::testing::established_address_1();
+        let target = address::testing::established_address_2();
+        let xan = address::xan();
+        let btc = address::btc();
+        let movements = |token: &Address| {
+            vec![
+                TokenMovement {
+                    token: token.clone(),
+                    owner: source.clone(),
+                    change: -10,
+                },
+                TokenMovement {
+                    token: token.clone(),
+                    owner: target.clone(),
+                    change: 10,
+                },
+            ]
+        };
+        let both: BTreeSet<Address> =
+            [source.clone(), target.clone()].into_iter().collect();
+
+        let tx1 = indexed_tx(1, 0, both.clone(), movements(&xan));
+        let tx2 = indexed_tx(
+            1,
+            1,
+            [source.clone()].into_iter().collect(),
+            Vec::new(),
+        );
+        indexer.add_tx(tx1.clone(), vec![]);
+        indexer.add_tx(tx2.clone(), vec![]);
+        indexer.commit_block().unwrap();
+
+        let mut tx3 = indexed_tx(2, 0, both, movements(&xan));
+        tx3.movements.extend(movements(&btc));
+        indexer.add_tx(tx3.clone(), vec![]);
+        indexer.commit_block().unwrap();
+
+        assert_eq!(
+            indexer.txs_by_address(&source).unwrap(),
+            vec![tx1.clone(), tx2, tx3.clone()]
+        );
+        assert_eq!(
+            indexer.txs_by_address(&target).unwrap(),
+            vec![tx1.clone(), tx3.clone()]
+        );
+        assert_eq!(
+            indexer.txs_by_token(&xan).unwrap(),
+            vec![
+                tx1.clone(),
+                indexed_tx(2, 0, tx3.addresses, movements(&xan))
+            ]
+        );
+        assert_eq!(indexer.txs_by_token(&btc).unwrap().len(), 1);
+
+        indexer.rollback(BlockHeight(1)).unwrap();
+        assert_eq!(indexer.txs_by_address(&target).unwrap(), vec![tx1]);
+        assert!(indexer.txs_by_token(&btc).unwrap().is_empty());
+    }
+
+    /// Test that the indexed txs can be found by their sender and that they
+    /// can be rolled back.
+    #[test]
+    fn test_txs_by_sender() {
+        let dir = tempdir().unwrap();
+        let mut indexer = Indexer::open(dir.path()).unwrap();
+        let sender = address::testing::established_address_1();
+        let tx =
+            |height: u64, index: u32, sender: Option<&Address>| IndexedTx {
+                sender: sender.cloned(),
+                ..indexed_tx(height, index, BTreeSet::new(), vec![])
+            };
+
+        let tx1 = tx(1, 0, Some(&sender));
+        indexer.add_tx(tx1.clone(), vec![]);
+        indexer.add_tx(tx(1, 1, None), vec![]);
+        indexer.commit_block().unwrap();
+        let tx2 = tx(2, 0, Some(&sender));
+        indexer.add_tx(tx2.clone(), vec![]);
+        indexer.commit_block().unwrap();
+
+        assert_eq!(
+            indexer.txs_by_sender(&sender).unwrap(),
+            vec![tx1.clone(), tx2]
+        );
+        let other = address::testing::established_address_2();
+        assert!(indexer.txs_by_sender(&other).unwrap().is_empty());
+
+        indexer.rollback(BlockHeight(1)).unwrap();
+        assert_eq!(indexer.txs_by_sender(&sender).unwrap(), vec![tx1]);
+    }
+
+    /// Test that the results of the txs are found by the height of their
+    /// block and that they can be rolled back.
+    #[test]
+    fn test_block_results() {
+        let dir = tempdir().unwrap();
+        let mut indexer = Indexer::open(dir.path()).unwrap();
+        let event = BlockEvent {
+            event_type: "applied".to_string(),
+            attributes: [("code".to_string(), "0".to_string())]
+                .into_iter()
+                .collect(),
+        };
+
+        indexer.add_tx(
+            indexed_tx(1, 0, BTreeSet::new(), vec![]),
+            vec![event.clone()],
+        );
+        indexer.add_tx(indexed_tx(1, 1, BTreeSet::new(), vec![]), vec![]);
+        indexer.commit_block().unwrap();
+        // A block without txs
+        indexer.commit_block().unwrap();
+        indexer.add_tx(indexed_tx(3, 0, BTreeSet::new(), vec![]), vec![]);
+        indexer.commit_block().unwrap();
+
+        let results = indexer.block_results(BlockHeight(1)).unwrap();
+        assert_eq!(results.height, BlockHeight(1));
+        assert_eq!(results.txs.len(), 2);
+        assert_eq!(results.txs[0].hash, "1-0");
+        assert_eq!(results.txs[0].events, vec![event]);
+        assert_eq!(results.txs[1].index, 1);
+        assert!(results.txs[1].events.is_empty());
+        let results = indexer.block_results(BlockHeight(2)).unwrap();
+        assert_eq!(results.height, BlockHeight(2));
+        assert!(results.txs.is_empty());
+        assert_eq!(indexer.block_results(BlockHeight(3)).unwrap().txs.len(), 1);
+
+        indexer.rollback(BlockHeight(1)).unwrap();
+        assert!(indexer
+            .block_results(BlockHeight(3))
+            .unwrap()
+            .txs
+            .is_empty());
+        assert_eq!(indexer.block_results(BlockHeight(1)).unwrap().txs.len(), 2);
+    }
+
+    /// Test that the results of the txs are found by their hash and that
+    /// they can be rolled back.
+    #[test]
+    fn test_tx_results() {
+        let dir = tempdir().unwrap();
+        let mut indexer = Indexer::open(dir.path()).unwrap();
+        let event = |event_type: &str| BlockEvent {
+            event_type: event_type.to_string(),
+            attributes: [("hash".to_string(), "tx".to_string())]
+                .into_iter()
+                .collect(),
+        };
+        let tx = |height: u64| IndexedTx {
+            hash: "tx".to_string(),
+            ..indexed_tx(height, 0, BTreeSet::new(), vec![])
+        };
+
+        indexer.add_tx(tx(1), vec![event("accepted")]);
+        indexer.add_tx(indexed_tx(1, 1, BTreeSet::new(), vec![]), vec![]);
+        indexer.commit_block().unwrap();
+        indexer.add_tx(tx(2), vec![event("applied")]);
+        indexer.commit_block().unwrap();
+
+        let results = indexer.tx_results("tx").unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].events, vec![event("accepted")]);
+        assert_eq!(results[1].events, vec![event("applied")]);
+        assert_eq!(indexer.tx_results("1-1").unwrap().len(), 1);
+        assert!(indexer.tx_results("unknown").unwrap().is_empty());
+
+        indexer.rollback(BlockHeight(1)).unwrap();
+        let results = indexer.tx_results("tx").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].events, vec![event("accepted")]);
+    }
+
+    /// Test that a read-only connection sees the txs of every committed
+    /// block.
+    #[test]
+    fn test_read_only() {
+        let dir = tempdir().unwrap();
+        let mut indexer = Indexer::open(dir.path()).unwrap();
+        let source = address::testing::established_address_1();
+        let tx = |height: u64| {
+            indexed_tx(height, 0, BTreeSet::from([source.clone()]), vec![])
+        };
+
+        indexer.add_tx(tx(1), vec![]);
+        indexer.commit_block().unwrap();
+        let mut reader = Indexer::open_read_only(dir.path()).unwrap();
+        assert_eq!(reader.txs_by_address(&source).unwrap(), vec![tx(1)]);
+
+        indexer.add_tx(tx(2), vec![]);
+        indexer.commit_block().unwrap();
+        assert_eq!(reader.txs_by_address(&source).unwrap(), vec![tx(1), tx(2)]);
+        assert!(reader.rollback(BlockHeight(1)).is_err());
+    }
+}
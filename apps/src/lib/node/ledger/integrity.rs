@@ -0,0 +1,178 @@
+//! Verification of the integrity of the ledger's DB. The Merkle tree of the
+//! last committed block is recomputed from all the key-vals of the account
+//! subspaces and the epoch data stored in the DB, and its root is compared
+//! with the root of the committed tree, which is the app hash of the block.
+//! The keys whose values don't match their leafs in the committed tree are
+//! reported, so that a silent corruption of the DB is detected before it
+//! causes a consensus failure.
+
+use anoma::ledger::storage::{
+    self as ledger_storage, BlockStateRead, DBIter, MerkleRoot, MerkleTree,
+    Sha256Hasher, DB,
+};
+use anoma::types::storage::{BlockHeight, Key};
+use thiserror::Error;
+
+use super::storage::{self, PersistentBackend};
+use crate::config::{self, StorageBackend};
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Storage error: {0}")]
+    Storage(ledger_storage::Error),
+    #[error("No block has been committed to the DB")]
+    NoBlock,
+    #[error("Invalid storage key {0}: {1}")]
+    InvalidKey(String, anoma::types::storage::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The result of a verification of the DB
+#[derive(Clone, Debug)]
+pub struct IntegrityReport {
+    /// The height of the last committed block
+    pub height: BlockHeight,
+    /// The root of the committed Merkle tree, i.e. the app hash of the block
+    pub committed_root: MerkleRoot,
+    /// The root of the tree recomputed from the key-vals in the DB
+    pub computed_root: MerkleRoot,
+    /// The number of the verified key-vals
+    pub keys: usize,
+    /// The keys whose values in the DB are not in the committed tree
+    pub divergent_keys: Vec<Key>,
+}
+
+impl IntegrityReport {
+    /// Check if the recomputed tree matches the committed one. The roots may
+    /// differ even without any divergent key, if a committed key is missing
+    /// from the DB.
+    pub fn is_valid(&self) -> bool {
+        self.committed_root == self.computed_root
+            && self.divergent_keys.is_empty()
+    }
+}
+
+/// Verify the state of the last committed block in the DB. The ledger node
+/// must not be running.
+pub fn verify_storage(config: config::Ledger) -> Result<IntegrityReport> {
+    match config.shell.storage_backend {
+        StorageBackend::RocksDB => verify_db::<storage::RocksDB>(config),
+        StorageBackend::Sled => verify_db::<storage::SledDB>(config),
+    }
+}
+
+/// Verify the DB of the given backend
+fn verify_db<D: PersistentBackend>(
+    config: config::Ledger,
+) -> Result<IntegrityReport> {
+    let mut db =
+        D::open_primary(&config.db_dir(), None).map_err(Error::Storage)?;
+    verify_state(&mut db)
+}
+
+/// Recompute the Merkle tree of the last committed block from the DB and
+/// compare it with the committed tree
+pub fn verify_state<D>(db: &mut D) -> Result<IntegrityReport>
+where
+    D: DB + for<'iter> DBIter<'iter>,
+{
+    let BlockStateRead {
+        merkle_tree_stores,
+        height,
+        epoch,
+        next_epoch_min_start_height,
+        next_epoch_min_start_time,
+        ..
+    } = db
+        .read_last_block()
+        .map_err(Error::Storage)?
+        .ok_or(Error::NoBlock)?;
+    let committed = MerkleTree::<Sha256Hasher>::new(merkle_tree_stores);
+    let mut computed = MerkleTree::<Sha256Hasher>::default();
+    let mut keys = 0;
+    let mut divergent_keys = vec![];
+    // The empty prefix of all the keys
+    let all_keys = Key { segments: vec![] };
+    for (key, value, _gas) in db.iter_prefix(&all_keys) {
+        let key =
+            Key::parse(&key).map_err(|err| Error::InvalidKey(key, err))?;
+        if !committed
+            .has_value(&key, &value)
+            .map_err(|err| Error::Storage(err.into()))?
+        {
+            divergent_keys.push(key.clone());
+        }
+        computed
+            .update(&key, value)
+            .map_err(|err| Error::Storage(err.into()))?;
+        keys += 1;
+    }
+    ledger_storage::update_epoch_in_merkle_tree(
+        &mut computed,
+        epoch,
+        next_epoch_min_start_height,
+        next_epoch_min_start_time,
+    )
+    .map_err(Error::Storage)?;
+    Ok(IntegrityReport {
+        height,
+        committed_root: committed.root(),
+        computed_root: computed.root(),
+        keys,
+        divergent_keys,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::ledger::storage::testing::TestStorage;
+    use anoma::types::time::DateTimeUtc;
+    use anoma::types::{address, token};
+    use borsh::BorshSerialize;
+
+    use super::*;
+    use crate::config::genesis;
+    use crate::wallet::defaults;
+
+    /// Test that a committed state is valid and that a value changed in the
+    /// DB after the commit is reported as divergent.
+    #[test]
+    fn test_verify_state() {
+        let mut storage = TestStorage::default();
+        storage.block.height = BlockHeight(1);
+        storage
+            .init_genesis_epoch(
+                BlockHeight(1),
+                DateTimeUtc::now(),
+                &genesis::genesis().parameters,
+            )
+            .unwrap();
+        let keys: Vec<Key> =
+            [defaults::albert_address(), defaults::bertha_address()]
+                .iter()
+                .map(|owner| token::balance_key(&address::xan(), owner))
+                .collect();
+        for key in &keys {
+            let amount = token::Amount::whole(1_000);
+            storage.write(key, amount.try_to_vec().unwrap()).unwrap();
+        }
+        storage.commit().unwrap();
+
+        let report = verify_state(&mut storage.db).unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.height, BlockHeight(1));
+        assert_eq!(report.keys, 2);
+
+        // Corrupt a value without updating the tree
+        let corrupted = token::Amount::whole(2_000).try_to_vec().unwrap();
+        storage
+            .db
+            .write_subspace_val(BlockHeight(1), &keys[1], corrupted)
+            .unwrap();
+        let report = verify_state(&mut storage.db).unwrap();
+        assert!(!report.is_valid());
+        assert_ne!(report.committed_root, report.computed_root);
+        assert_eq!(report.divergent_keys, vec![keys[1].clone()]);
+    }
+}
@@ -0,0 +1,173 @@
+//! The subscriptions of the clients to the changes of storage keys. The shell
+//! publishes the changes of every committed block to the [`KeyWatcher`] and
+//! each [`KeyWatch`] receives the changes of the keys and of the key prefixes
+//! it watches, so that the clients don't have to poll their values every
+//! block. The changes are only collected while there are subscribers.
+
+use std::sync::Arc;
+
+use anoma::types::storage::{BlockHeight, Key};
+use thiserror::Error;
+use tokio::sync::broadcast;
+
+/// The number of the committed blocks whose changes are buffered for a slow
+/// subscriber, after which it misses the changes of the oldest blocks
+pub const WATCH_BUFFER_BLOCKS: usize = 16;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("The changes of {0} blocks were missed by a slow subscriber")]
+    Lagged(u64),
+    #[error("The ledger has shut down")]
+    Closed,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A change of a storage key in a committed block
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyChange {
+    /// The changed key
+    pub key: Key,
+    /// The new value of the key, or `None` if the key was deleted
+    pub value: Option<Vec<u8>>,
+    /// The height of the block in which the key changed
+    pub height: BlockHeight,
+}
+
+/// The keys and the key prefixes watched by a subscriber
+#[derive(Clone, Debug, Default)]
+pub struct WatchFilter {
+    /// The exact keys
+    pub keys: Vec<Key>,
+    /// The prefixes of the keys, matched segment by segment
+    pub prefixes: Vec<Key>,
+}
+
+impl WatchFilter {
+    /// Check if the filter doesn't watch any key
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.prefixes.is_empty()
+    }
+
+    /// Check if the key is one of the watched keys or has one of the watched
+    /// prefixes
+    pub fn matches(&self, key: &Key) -> bool {
+        self.keys.contains(key)
+            || self
+                .prefixes
+                .iter()
+                .any(|prefix| key.segments.starts_with(&prefix.segments))
+    }
+}
+
+/// The publisher of the changes of the committed blocks to the
+/// [`KeyWatch`]es. It's shared by the shell and the servers of the
+/// subscriptions.
+#[derive(Clone, Debug)]
+pub struct KeyWatcher {
+    sender: broadcast::Sender<Arc<Vec<KeyChange>>>,
+}
+
+impl Default for KeyWatcher {
+    fn default() -> Self {
+        let (sender, _receiver) = broadcast::channel(WATCH_BUFFER_BLOCKS);
+        Self { sender }
+    }
+}
+
+impl KeyWatcher {
+    /// Check if any key is watched, otherwise the changes of a block don't
+    /// have to be collected
+    pub fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    /// Publish the changes of a committed block to the subscribers
+    pub fn publish(&self, changes: Vec<KeyChange>) {
+        // Sending only fails when there are no subscribers
+        let _ = self.sender.send(Arc::new(changes));
+    }
+
+    /// Subscribe to the changes of the keys matching the filter in the blocks
+    /// committed from now on
+    pub fn subscribe(&self, filter: WatchFilter) -> KeyWatch {
+        KeyWatch {
+            filter,
+            receiver: self.sender.subscribe(),
+        }
+    }
+}
+
+/// A subscription to the changes of the watched keys
+#[derive(Debug)]
+pub struct KeyWatch {
+    filter: WatchFilter,
+    receiver: broadcast::Receiver<Arc<Vec<KeyChange>>>,
+}
+
+impl KeyWatch {
+    /// Wait for the next committed block that changed any of the watched
+    /// keys and return their changes, ordered by their keys. A subscriber
+    /// that falls behind by more than [`WATCH_BUFFER_BLOCKS`] blocks gets an
+    /// [`Error::Lagged`], after which it has to read the values of the
+    /// watched keys again.
+    pub async fn next_changes(&mut self) -> Result<Vec<KeyChange>> {
+        loop {
+            let changes =
+                self.receiver.recv().await.map_err(|err| match err {
+                    broadcast::error::RecvError::Lagged(blocks) => {
+                        Error::Lagged(blocks)
+                    }
+                    broadcast::error::RecvError::Closed => Error::Closed,
+                })?;
+            let watched: Vec<KeyChange> = changes
+                .iter()
+                .filter(|change| self.filter.matches(&change.key))
+                .cloned()
+                .collect();
+            if !watched.is_empty() {
+                return Ok(watched);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that a subscriber only receives the changes of the watched keys
+    /// and prefixes and that the blocks without such changes are skipped.
+    #[test]
+    fn test_key_watch() {
+        let key = |key: &str| Key::parse(key).unwrap();
+        let change = |k: &str, height| KeyChange {
+            key: key(k),
+            value: Some(vec![height as u8]),
+            height: BlockHeight(height),
+        };
+        let watcher = KeyWatcher::default();
+        assert!(!watcher.has_subscribers());
+        let mut watch = watcher.subscribe(WatchFilter {
+            keys: vec![key("a/b")],
+            prefixes: vec![key("c")],
+        });
+        assert!(watcher.has_subscribers());
+
+        watcher.publish(vec![change("a/bc", 1), change("cd", 1)]);
+        watcher.publish(vec![
+            change("a/b", 2),
+            change("a/b/c", 2),
+            change("c/d", 2),
+        ]);
+        let changes = futures::executor::block_on(watch.next_changes());
+        assert_eq!(changes.unwrap(), vec![change("a/b", 2), change("c/d", 2)]);
+
+        for height in 0..=WATCH_BUFFER_BLOCKS as u64 {
+            watcher.publish(vec![change("c", height)]);
+        }
+        let result = futures::executor::block_on(watch.next_changes());
+        assert!(matches!(result, Err(Error::Lagged(1))));
+    }
+}
@@ -0,0 +1,308 @@
+//! An in-process ledger node for tests, which stands in for Tendermint. The
+//! txs are added to a mock mempool and the blocks are only produced when the
+//! test asks for them, with a fixed interval between the times of the blocks,
+//! so that the tests are deterministic.
+//!
+//! The node uses the dev genesis and a mock DB, which is not persisted.
+
+use std::path::Path;
+
+use anoma::ledger::storage::mockdb::MockDB;
+use anoma::ledger::storage::{Sha256Hasher, Storage};
+use anoma::types::chain::ChainId;
+use anoma::types::hash::Hash;
+use anoma::types::storage::{BlockHash, BlockHeight, Epoch, Header, Key};
+use anoma::types::time::{DateTimeUtc, DurationSecs, TimeZone, Utc};
+use anoma::types::transaction::hash_tx;
+use borsh::BorshDeserialize;
+#[cfg(feature = "ABCI")]
+use tendermint_compat::tendermint_proto::abci::RequestDeliverTx;
+use tendermint_compat::tendermint_proto::abci::RequestInitChain;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_compat::tendermint_proto::abci::{
+    response_process_proposal::ProposalStatus, tx_record::TxAction,
+    RequestPrepareProposal, RequestProcessProposal,
+};
+use tendermint_compat::tendermint_proto::google::protobuf::Timestamp;
+use thiserror::Error;
+use tokio::sync::mpsc::UnboundedReceiver;
+#[cfg(not(feature = "ABCI"))]
+use tower_abci::{request, response};
+#[cfg(feature = "ABCI")]
+use tower_abci_old::{request, response};
+
+use super::events::Event;
+use super::rpc;
+use super::shell::{self, MempoolTxType, Shell};
+use super::shims::abcipp_shim_types::shim::request::{
+    FinalizeBlock, ProcessedTx,
+};
+#[cfg(not(feature = "ABCI"))]
+use super::shims::abcipp_shim_types::shim::response::TxResult;
+use crate::config::{self, TendermintMode};
+
+/// The size of each of the WASM compilation caches of the node
+const WASM_COMPILATION_CACHE_BYTES: u64 = 50 * 1024 * 1024;
+
+/// The maximum number of blocks produced while waiting for a new epoch
+const MAX_BLOCKS_PER_EPOCH: u64 = 10_000;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("The tx was rejected by the mempool with code {code}: {log}")]
+    MempoolRejected { code: u32, log: String },
+    #[cfg(not(feature = "ABCI"))]
+    #[error("The proposal of the block at height {0} was rejected: {1:?}")]
+    ProposalRejected(BlockHeight, Vec<ProcessedTx>),
+    #[error("Error finalizing the block at height {0}: {1}")]
+    FinalizeBlock(BlockHeight, shell::Error),
+    #[error("No new epoch after {0} blocks")]
+    NoNewEpoch(u64),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A committed block of the mock node
+#[derive(Debug, Clone)]
+pub struct Block {
+    /// The height of the block
+    pub height: BlockHeight,
+    /// The time of the block
+    pub time: DateTimeUtc,
+    /// The events emitted by the block, including the events of its txs
+    pub events: Vec<Event>,
+}
+
+/// An in-process ledger node without Tendermint
+#[derive(Debug)]
+pub struct MockNode {
+    shell: Shell<MockDB, Sha256Hasher>,
+    /// The protocol txs broadcast by the shell. The receiver is kept so that
+    /// the shell can send them.
+    _broadcast_receiver: UnboundedReceiver<Vec<u8>>,
+    /// The wrapper txs accepted by the mempool, which are proposed in the
+    /// next block
+    mempool: Vec<Vec<u8>>,
+    /// The time of the last produced block
+    time: DateTimeUtc,
+    /// The interval between the times of the produced blocks
+    block_interval: DurationSecs,
+}
+
+impl MockNode {
+    /// Start a node of the dev genesis with the given directories. The base
+    /// directory should be empty and is used for the WASM compilation caches.
+    pub fn new(base_dir: impl AsRef<Path>, wasm_dir: impl AsRef<Path>) -> Self {
+        let (broadcast_sender, broadcast_receiver) =
+            tokio::sync::mpsc::unbounded_channel();
+        let mut shell = Shell::<MockDB, Sha256Hasher>::new(
+            config::Ledger::new(
+                base_dir.as_ref(),
+                ChainId::default(),
+                TendermintMode::Validator,
+            ),
+            wasm_dir.as_ref().to_path_buf(),
+            broadcast_sender,
+            None,
+            WASM_COMPILATION_CACHE_BYTES,
+            WASM_COMPILATION_CACHE_BYTES,
+        );
+        let genesis_time = Timestamp {
+            seconds: 0,
+            nanos: 0,
+        };
+        shell
+            .init_chain(RequestInitChain {
+                time: Some(genesis_time),
+                chain_id: ChainId::default().to_string(),
+                ..Default::default()
+            })
+            .expect("The mock node's chain should be initialized");
+        Self {
+            shell,
+            _broadcast_receiver: broadcast_receiver,
+            mempool: vec![],
+            time: Utc.timestamp(0, 0).into(),
+            block_interval: DurationSecs(1),
+        }
+    }
+
+    /// Set the interval between the times of the next produced blocks
+    pub fn set_block_interval(&mut self, interval: DurationSecs) {
+        self.block_interval = interval;
+    }
+
+    /// The ID of the chain
+    pub fn chain_id(&self) -> &ChainId {
+        &self.shell.storage.chain_id
+    }
+
+    /// The height of the last committed block
+    pub fn last_height(&self) -> BlockHeight {
+        self.shell.storage.last_height
+    }
+
+    /// The epoch of the last committed block
+    pub fn last_epoch(&self) -> Epoch {
+        self.shell.storage.last_epoch
+    }
+
+    /// The storage of the node, to assert on its committed state
+    pub fn storage(&self) -> &Storage<MockDB, Sha256Hasher> {
+        &self.shell.storage
+    }
+
+    /// Read and decode a value from the committed storage
+    pub fn read<T: BorshDeserialize>(&self, key: &Key) -> Option<T> {
+        let (value, _gas) = self
+            .shell
+            .storage
+            .read(key)
+            .expect("Reading the mock storage shouldn't fail");
+        value.map(|bytes| {
+            T::try_from_slice(&bytes).expect("The value should be decodable")
+        })
+    }
+
+    /// Validate a tx and add it to the mempool, so that it's proposed in the
+    /// next block
+    pub fn submit_tx(&mut self, tx_bytes: Vec<u8>) -> Result<()> {
+        let response = self
+            .shell
+            .mempool_validate(&tx_bytes, MempoolTxType::NewTransaction);
+        if response.code != 0 {
+            return Err(Error::MempoolRejected {
+                code: response.code,
+                log: response.log,
+            });
+        }
+        self.mempool.push(tx_bytes);
+        Ok(())
+    }
+
+    /// Serve a query of the given path, like the ABCI queries of the RPC
+    pub fn query(
+        &self,
+        path: &rpc::Path,
+        data: Vec<u8>,
+        height: Option<BlockHeight>,
+        prove: bool,
+    ) -> response::Query {
+        self.shell.query(request::Query {
+            data: data.into(),
+            path: path.to_string(),
+            height: height.map(|height| height.0 as i64).unwrap_or_default(),
+            prove,
+        })
+    }
+
+    /// Produce and commit a new block with the txs of the mempool
+    pub fn produce_block(&mut self) -> Result<Block> {
+        let height = self.last_height() + 1;
+        let time = self.time + self.block_interval;
+        let txs = self.propose_block(height)?;
+        let hash = hash_tx(
+            &txs.iter()
+                .flat_map(|processed| processed.tx.clone())
+                .collect::<Vec<u8>>(),
+        );
+        let request = FinalizeBlock {
+            hash: BlockHash::from(hash.clone()),
+            header: Header {
+                hash,
+                time,
+                next_validators_hash: Hash([0; 32]),
+            },
+            byzantine_validators: vec![],
+            votes: vec![],
+            txs,
+        };
+        self.shell.load_proposals();
+        let response = self
+            .shell
+            .finalize_block(request)
+            .map_err(|err| Error::FinalizeBlock(height, err))?;
+        self.shell.commit();
+        self.time = time;
+        Ok(Block {
+            height,
+            time,
+            events: response.events,
+        })
+    }
+
+    /// Produce the given number of blocks
+    pub fn produce_blocks(&mut self, num: u64) -> Result<Vec<Block>> {
+        (0..num).map(|_| self.produce_block()).collect()
+    }
+
+    /// Produce blocks until the epoch changes. Returns the new epoch.
+    pub fn produce_epoch(&mut self) -> Result<Epoch> {
+        let epoch = self.last_epoch();
+        for _ in 0..MAX_BLOCKS_PER_EPOCH {
+            self.produce_block()?;
+            if self.last_epoch() != epoch {
+                return Ok(self.last_epoch());
+            }
+        }
+        Err(Error::NoNewEpoch(MAX_BLOCKS_PER_EPOCH))
+    }
+
+    /// Prepare and process the proposal of the block with the txs of the
+    /// mempool and the decrypted txs of the previous block. The txs left out
+    /// of the proposal stay in the mempool.
+    #[cfg(not(feature = "ABCI"))]
+    fn propose_block(
+        &mut self,
+        height: BlockHeight,
+    ) -> Result<Vec<ProcessedTx>> {
+        let mempool = std::mem::take(&mut self.mempool);
+        let tx_records = self
+            .shell
+            .prepare_proposal(RequestPrepareProposal {
+                txs: mempool.clone(),
+                ..Default::default()
+            })
+            .tx_records;
+        self.mempool = mempool
+            .into_iter()
+            .filter(|tx| !tx_records.iter().any(|record| &record.tx == tx))
+            .collect();
+        let txs: Vec<Vec<u8>> = tx_records
+            .into_iter()
+            .filter(|record| record.action != TxAction::Removed as i32)
+            .map(|record| record.tx)
+            .collect();
+        let response = self.shell.process_proposal(RequestProcessProposal {
+            txs: txs.clone(),
+            ..Default::default()
+        });
+        let processed: Vec<ProcessedTx> = response
+            .tx_results
+            .iter()
+            .map(TxResult::from)
+            .zip(txs.into_iter())
+            .map(|(result, tx)| ProcessedTx { tx, result })
+            .collect();
+        if response.status != ProposalStatus::Accept as i32 {
+            return Err(Error::ProposalRejected(height, processed));
+        }
+        Ok(processed)
+    }
+
+    /// Process all the txs of the mempool together with their decryption,
+    /// like the ABCI shim does on `DeliverTx`
+    #[cfg(feature = "ABCI")]
+    fn propose_block(
+        &mut self,
+        _height: BlockHeight,
+    ) -> Result<Vec<ProcessedTx>> {
+        Ok(std::mem::take(&mut self.mempool)
+            .into_iter()
+            .map(|tx| {
+                self.shell
+                    .process_and_decode_proposal(RequestDeliverTx { tx })
+            })
+            .collect())
+    }
+}
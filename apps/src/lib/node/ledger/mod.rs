@@ -1,27 +1,43 @@
 mod broadcaster;
+#[cfg(feature = "dev")]
+pub mod dev;
 pub mod events;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod indexer;
+mod integrity;
+pub mod key_watch;
+#[cfg(feature = "testing")]
+pub mod mock_node;
+pub mod node_status;
+mod pos_invariants;
 pub mod protocol;
+mod rate_limit;
+mod replay;
+#[cfg(feature = "rest")]
+mod rest;
 pub mod rpc;
 mod shell;
 mod shims;
+mod snapshots;
+pub mod state_diff;
+mod state_dump;
 pub mod storage;
 pub mod tendermint_node;
 
 use std::convert::TryInto;
 use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use anoma::ledger::governance::storage as gov_storage;
-use anoma::types::storage::Key;
+use anoma::ledger::storage::{DBIter, StorageHasher, DB};
+use anoma::types::storage::{BlockHeight, Key};
 use byte_unit::Byte;
 use futures::future::TryFutureExt;
 use once_cell::unsync::Lazy;
 use sysinfo::{RefreshKind, System, SystemExt};
-#[cfg(not(feature = "ABCI"))]
-use tendermint_proto::abci::CheckTxType;
-#[cfg(feature = "ABCI")]
-use tendermint_proto_abci::abci::CheckTxType;
+use tendermint_compat::tendermint_proto::abci::CheckTxType;
 use tower::ServiceBuilder;
 #[cfg(not(feature = "ABCI"))]
 use tower_abci::{response, split, Server};
@@ -29,13 +45,16 @@ use tower_abci::{response, split, Server};
 use tower_abci_old::{response, split, Server};
 
 use self::shims::abcipp_shim::AbciService;
+pub use self::state_dump::StateDumpFormat;
 use crate::config::utils::num_of_threads;
-use crate::config::TendermintMode;
+use crate::config::{StorageBackend, TendermintMode};
 use crate::node::ledger::broadcaster::Broadcaster;
+use crate::node::ledger::rate_limit::QueryRateLimiter;
 use crate::node::ledger::shell::{Error, MempoolTxType, Shell};
 use crate::node::ledger::shims::abcipp_shim::AbcippShim;
 use crate::node::ledger::shims::abcipp_shim_types::shim::{Request, Response};
-use crate::{config, wasm_loader};
+use crate::node::ledger::storage::{schema, PersistentBackend};
+use crate::{config, logging, wasm_loader};
 
 /// Env. var to set a number of Tokio RT worker threads
 const ENV_VAR_TOKIO_THREADS: &str = "ANOMA_TOKIO_THREADS";
@@ -43,6 +62,9 @@ const ENV_VAR_TOKIO_THREADS: &str = "ANOMA_TOKIO_THREADS";
 /// Env. var to set a number of Rayon global worker threads
 const ENV_VAR_RAYON_THREADS: &str = "ANOMA_RAYON_THREADS";
 
+/// Env. var to set a number of threads serving the ABCI queries
+const ENV_VAR_QUERY_THREADS: &str = "ANOMA_QUERY_THREADS";
+
 // Until ABCI++ is ready, the shim provides the service implementation.
 // We will add this part back in once the shim is no longer needed.
 //```
@@ -60,7 +82,11 @@ const ENV_VAR_RAYON_THREADS: &str = "ANOMA_RAYON_THREADS";
 //     }
 //```
 
-impl Shell {
+impl<D, H> Shell<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
     fn load_proposals(&mut self) {
         let proposals_key = gov_storage::get_commiting_proposals_prefix(
             self.storage.last_epoch.0,
@@ -88,7 +114,9 @@ impl Shell {
             }
         }
     }
+}
 
+impl Shell {
     fn call(&mut self, req: Request) -> Result<Response, Error> {
         match req {
             Request::InitChain(init) => {
@@ -125,6 +153,10 @@ impl Shell {
             ),
             Request::FinalizeBlock(finalize) => {
                 self.load_proposals();
+                // The txs of the block are removed from the mempool
+                for tx in &finalize.txs {
+                    self.node_stats.remove_mempool_tx(&tx.tx);
+                }
                 self.finalize_block(finalize).map(Response::FinalizeBlock)
             }
             Request::Commit(_) => Ok(Response::Commit(self.commit())),
@@ -139,19 +171,29 @@ impl Shell {
                     CheckTxType::New => MempoolTxType::NewTransaction,
                     CheckTxType::Recheck => MempoolTxType::RecheckTransaction,
                 };
-                Ok(Response::CheckTx(self.mempool_validate(&*tx.tx, r#type)))
+                let recheck =
+                    matches!(r#type, MempoolTxType::RecheckTransaction);
+                let response = self.mempool_validate(&*tx.tx, r#type);
+                // Count the txs that are kept in the mempool and stop counting
+                // the ones evicted by a recheck
+                if response.code == 0 {
+                    self.node_stats.add_mempool_tx(&tx.tx);
+                } else if recheck {
+                    self.node_stats.remove_mempool_tx(&tx.tx);
+                }
+                Ok(Response::CheckTx(response))
             }
             Request::ListSnapshots(_) => {
-                Ok(Response::ListSnapshots(Default::default()))
+                Ok(Response::ListSnapshots(self.list_snapshots()))
             }
-            Request::OfferSnapshot(_) => {
-                Ok(Response::OfferSnapshot(Default::default()))
+            Request::OfferSnapshot(req) => {
+                Ok(Response::OfferSnapshot(self.offer_snapshot(req)))
             }
-            Request::LoadSnapshotChunk(_) => {
-                Ok(Response::LoadSnapshotChunk(Default::default()))
+            Request::LoadSnapshotChunk(req) => {
+                Ok(Response::LoadSnapshotChunk(self.load_snapshot_chunk(req)))
             }
-            Request::ApplySnapshotChunk(_) => {
-                Ok(Response::ApplySnapshotChunk(Default::default()))
+            Request::ApplySnapshotChunk(req) => {
+                Ok(Response::ApplySnapshotChunk(self.apply_snapshot_chunk(req)))
             }
         }
     }
@@ -159,6 +201,8 @@ impl Shell {
 
 /// Run the ledger with an async runtime
 pub fn run(config: config::Ledger, wasm_dir: PathBuf) {
+    apply_log_level(&config);
+
     let logical_cores = num_cpus::get();
     tracing::info!("Available logical cores: {}", logical_cores);
 
@@ -184,15 +228,23 @@ pub fn run(config: config::Ledger, wasm_dir: PathBuf) {
         .build_global()
         .unwrap();
 
-    // Start tokio runtime with the `run_aux` function
-    tokio::runtime::Builder::new_multi_thread()
+    // Start tokio runtime with the `run_aux` function for the DB backend
+    let runtime = tokio::runtime::Builder::new_multi_thread()
         .worker_threads(tokio_threads)
         .thread_name("ledger-tokio-worker")
         // Enable time and I/O drivers
         .enable_all()
         .build()
-        .unwrap()
-        .block_on(run_aux(config, wasm_dir));
+        .unwrap();
+    tracing::info!("Using the {:?} DB backend.", config.shell.storage_backend);
+    match config.shell.storage_backend {
+        StorageBackend::RocksDB => {
+            runtime.block_on(run_aux::<storage::RocksDB>(config, wasm_dir))
+        }
+        StorageBackend::Sled => {
+            runtime.block_on(run_aux::<storage::SledDB>(config, wasm_dir))
+        }
+    }
 }
 
 /// Resets the tendermint_node state and removes database files
@@ -200,11 +252,80 @@ pub fn reset(config: config::Ledger) -> Result<(), shell::Error> {
     shell::reset(config)
 }
 
+/// Rollback the blocks above the given height, or the last committed block
+/// when not given, and return the height of the block that becomes the last
+/// one
+pub fn rollback(
+    config: config::Ledger,
+    height: Option<BlockHeight>,
+) -> Result<BlockHeight, shell::Error> {
+    shell::rollback(config, height)
+}
+
+/// Migrate the DB to the schema version of the node, or with `dry_run` only
+/// report the pending schema migrations
+pub fn migrate(
+    config: config::Ledger,
+    dry_run: bool,
+) -> Result<schema::MigrationReport, shell::Error> {
+    shell::migrate(config, dry_run)
+}
+
+/// Recompute the Merkle tree of the last committed block from the DB and
+/// compare it with the committed tree
+pub fn verify_storage(
+    config: config::Ledger,
+) -> Result<integrity::IntegrityReport, integrity::Error> {
+    integrity::verify_storage(config)
+}
+
+/// Check the invariants of the PoS state of the last committed block in the
+/// DB
+pub fn check_pos(
+    config: config::Ledger,
+) -> Result<pos_invariants::PosInvariantsReport, pos_invariants::Error> {
+    pos_invariants::check_pos(config)
+}
+
+/// Re-execute the committed blocks in the given range of heights and verify
+/// their app hashes
+pub fn replay(
+    config: config::Ledger,
+    wasm_dir: PathBuf,
+    from_height: BlockHeight,
+    to_height: BlockHeight,
+) -> Result<(), replay::Error> {
+    replay::replay(config, wasm_dir, from_height, to_height)
+}
+
+/// Export the state at the given height, or at the last committed height if
+/// none given, to a file in the given format
+pub fn export_state(
+    config: config::Ledger,
+    height: Option<BlockHeight>,
+    path: &Path,
+    format: StateDumpFormat,
+) -> Result<state_dump::StateDump, state_dump::Error> {
+    state_dump::export_state(config, height, path, format)
+}
+
+/// Import the state from a file into a new chain, which is initialized from
+/// it when the node is started
+pub fn import_state(
+    config: config::Ledger,
+    path: &Path,
+) -> Result<state_dump::StateDump, state_dump::Error> {
+    state_dump::import_state(config, path)
+}
+
 /// Runs three concurrent tasks: A tendermint node, a shell which contains an
 /// ABCI, server for talking to the tendermint node, and a broadcaster so that
 /// the ledger may submit txs to the chain. All must be alive for correct
 /// functioning.
-async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
+async fn run_aux<D: PersistentBackend>(
+    config: config::Ledger,
+    wasm_dir: PathBuf,
+) {
     // Prefetch needed wasm artifacts
     wasm_loader::pre_fetch_wasm(&wasm_dir).await;
 
@@ -281,12 +402,25 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
         }
     };
     tracing::info!(
-        "RocksDB block cache size: {}",
+        "DB block cache size: {}",
         Byte::from_bytes(block_cache_size_bytes as u128)
             .get_appropriate_unit(true)
     );
-    let db_cache =
-        rocksdb::Cache::new_lru_cache(block_cache_size_bytes as usize).unwrap();
+    let db_cache = D::new_cache(block_cache_size_bytes);
+
+    // Migrate the DB before the shell opens it
+    match shell::migrate_db::<D>(&config, false) {
+        Ok(report) if !report.migrations.is_empty() => tracing::info!(
+            "Migrated the DB from schema version {} to {}",
+            report.from_version,
+            report.to_version
+        ),
+        Ok(_) => {}
+        Err(err) => {
+            tracing::error!("{}", err);
+            panic!("The DB cannot be migrated to the schema of the node");
+        }
+    }
 
     let tendermint_dir = config.tendermint_dir();
     let ledger_address = config.shell.ledger_address.to_string();
@@ -370,17 +504,53 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
         None
     };
 
+    let query_threads = num_of_threads(
+        ENV_VAR_QUERY_THREADS,
+        // If not set, default to a quarter of logical CPUs count
+        num_cpus::get() / 4,
+    );
+    tracing::info!("Using {} threads for queries.", query_threads);
+
     // Construct our ABCI application.
     let ledger_address = config.shell.ledger_address;
-    let (shell, abci_service) = AbcippShim::new(
+    let query_limiter = QueryRateLimiter::new(config.rpc.clone());
+    let mut current_config = config.clone();
+    let (shell, query_shims, abci_service) = AbcippShim::<D>::new(
         config,
-        wasm_dir,
+        wasm_dir.clone(),
         broadcaster_sender,
         &db_cache,
         vp_wasm_compilation_cache,
         tx_wasm_compilation_cache,
+        query_threads,
+        query_limiter.clone(),
     );
 
+    // Start the gRPC query server, if it's configured
+    #[cfg(feature = "grpc")]
+    let grpc = current_config.shell.grpc_address.map(|grpc_address| {
+        let queries = abci_service.query_handle();
+        let key_watcher = abci_service.key_watcher();
+        tokio::spawn(async move {
+            if let Err(err) =
+                grpc::run_server(grpc_address, queries, key_watcher).await
+            {
+                tracing::error!("The gRPC query server failed: {}", err);
+            }
+        })
+    });
+    // Start the REST server, if it's configured
+    #[cfg(feature = "rest")]
+    let rest = current_config.shell.rest_address.map(|rest_address| {
+        let queries = abci_service.query_handle();
+        tokio::spawn(async move {
+            if let Err(err) = rest::run_server(rest_address, queries).await {
+                tracing::error!("The REST server failed: {}", err);
+            }
+        })
+    });
+
+    let abort_send_for_shell = abort_send.clone();
     // Start the ABCI server
     let abci = tokio::spawn(async move {
         // On panic or exit, the `Drop` of `AbortSender` will send abort
@@ -401,16 +571,49 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
         std::thread::Builder::new().name("ledger-shell".into());
     let shell_handler = thread_builder
         .spawn(move || {
+            // When the shell halts, the `Drop` of `AbortSender` will send
+            // abort message
+            let aborter = Aborter {
+                sender: abort_send_for_shell,
+                who: "Shell",
+            };
             tracing::info!("Anoma ledger node started.");
-            shell.run()
+            shell.run();
+            drop(aborter);
         })
         .expect("Must be able to start a thread for the shell");
 
-    // Wait for interrupt signal or abort message
-    let aborted = wait_for_abort(abort_recv).await;
+    // Serve the queries from separate threads, so that they cannot delay the
+    // shell
+    let query_handlers: Vec<_> = query_shims
+        .into_iter()
+        .enumerate()
+        .map(|(i, query_shim)| {
+            std::thread::Builder::new()
+                .name(format!("ledger-query-{}", i))
+                .spawn(move || query_shim.run())
+                .expect("Must be able to start a thread for the queries")
+        })
+        .collect();
+
+    // Wait for interrupt signal or abort message, reloading the config on a
+    // hangup signal
+    let aborted = wait_for_abort(abort_recv, || {
+        current_config =
+            reload_config(current_config.clone(), &wasm_dir, &query_limiter);
+    })
+    .await;
 
     // Abort the ABCI service task
     abci.abort();
+    #[cfg(feature = "grpc")]
+    if let Some(grpc) = grpc {
+        grpc.abort();
+    }
+    #[cfg(feature = "rest")]
+    if let Some(rest) = rest {
+        rest.abort();
+    }
 
     // Shutdown tendermint_node via a message to ensure that the child process
     // is properly cleaned-up.
@@ -468,6 +671,11 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
     if let Err(err) = shell_handler.join() {
         std::panic::resume_unwind(err)
     }
+    for query_handler in query_handlers {
+        if let Err(err) = query_handler.join() {
+            std::panic::resume_unwind(err)
+        }
+    }
 }
 
 /// Runs the an asynchronous ABCI server with four sub-components for consensus,
@@ -507,6 +715,76 @@ async fn run_abci(
         .map_err(|err| Error::TowerServer(err.to_string()))
 }
 
+/// Apply the configured log filter, unless it's set with the env var
+fn apply_log_level(config: &config::Ledger) {
+    if let Some(log_level) = &config.shell.log_level {
+        if logging::is_filter_from_env() {
+            tracing::info!(
+                "The log filter is set with the {} env var, ignoring the \
+                 configured log level",
+                logging::ENV_KEY
+            );
+        } else if let Err(err) = logging::reload_filter(log_level) {
+            tracing::error!(
+                "Failed to apply the configured log level: {}",
+                err
+            );
+        }
+    }
+}
+
+/// Re-read the configuration file and apply the settings that can be changed
+/// while the node is running, i.e. the log level and the query limits.
+/// An invalid configuration is not applied. Returns the configuration in use
+/// after the reload.
+fn reload_config(
+    current: config::Ledger,
+    wasm_dir: &Path,
+    query_limiter: &QueryRateLimiter,
+) -> config::Ledger {
+    let base_dir = current.shell.base_dir.clone();
+    let mode = current.tendermint.tendermint_mode.clone();
+    let config =
+        match config::Config::read(&base_dir, &current.chain_id, Some(mode)) {
+            Ok(config) => config,
+            Err(err) => {
+                tracing::error!(
+                    "Failed to read the configuration, keeping the current \
+                     settings: {}",
+                    err
+                );
+                return current;
+            }
+        };
+    let errors = config::validation::validate(&config, wasm_dir);
+    if !errors.is_empty() {
+        for err in errors {
+            tracing::error!("{}", err);
+        }
+        tracing::error!(
+            "The configuration is invalid, keeping the current settings"
+        );
+        return current;
+    }
+    let new = config.ledger;
+    if new.tendermint.rpc_cors_allowed_origins
+        != current.tendermint.rpc_cors_allowed_origins
+    {
+        tracing::warn!(
+            "The RPC CORS origins changed. Tendermint applies them on the next \
+             start of the node."
+        );
+    }
+    // Only the settings that can be changed while running are replaced
+    let mut reloaded = current;
+    reloaded.shell.log_level = new.shell.log_level;
+    reloaded.rpc = new.rpc;
+    apply_log_level(&reloaded);
+    query_limiter.set_config(reloaded.rpc.clone());
+    tracing::info!("Reloaded the configuration");
+    reloaded
+}
+
 /// A panic-proof handle for aborting a future. Will abort during stack
 /// unwinding and its drop method sends abort message with `who` inside it.
 struct Aborter {
@@ -525,50 +803,58 @@ impl Drop for Aborter {
 ///   1. User sends a shutdown signal
 ///   2. One of the child processes terminates, sending a message on `drop`
 /// Returns a boolean to indicate which scenario occurred.
-/// `true` means that the latter happened
+/// `true` means that the latter happened. On a hangup signal, `on_reload` is
+/// called and it keeps waiting.
 #[cfg(unix)]
 async fn wait_for_abort(
     mut abort_recv: tokio::sync::mpsc::UnboundedReceiver<&'static str>,
+    mut on_reload: impl FnMut(),
 ) -> bool {
     use tokio::signal::unix::{signal, SignalKind};
     let mut sigterm = signal(SignalKind::terminate()).unwrap();
     let mut sighup = signal(SignalKind::hangup()).unwrap();
     let mut sigpipe = signal(SignalKind::pipe()).unwrap();
-    tokio::select! {
-        signal = tokio::signal::ctrl_c() => {
-            match signal {
-                Ok(()) => tracing::info!("Received interrupt signal, exiting..."),
-                Err(err) => tracing::error!("Failed to listen for CTRL+C signal: {}", err),
-            }
-        },
-        signal = sigterm.recv() => {
-            match signal {
-                Some(()) => tracing::info!("Received termination signal, exiting..."),
-                None => tracing::error!("Termination signal cannot be caught anymore, exiting..."),
-            }
-        },
-        signal = sighup.recv() => {
-            match signal {
-                Some(()) => tracing::info!("Received hangup signal, exiting..."),
-                None => tracing::error!("Hangup signal cannot be caught anymore, exiting..."),
-            }
-        },
-        signal = sigpipe.recv() => {
-            match signal {
-                Some(()) => tracing::info!("Received pipe signal, exiting..."),
-                None => tracing::error!("Pipe signal cannot be caught anymore, exiting..."),
-            }
-        },
-        msg = abort_recv.recv() => {
-            // When the msg is `None`, there are no more abort senders, so both
-            // Tendermint and the shell must have already exited
-            if let Some(who) = msg {
-                 tracing::info!("{} has exited, shutting down...", who);
+    loop {
+        tokio::select! {
+            signal = tokio::signal::ctrl_c() => {
+                match signal {
+                    Ok(()) => tracing::info!("Received interrupt signal, exiting..."),
+                    Err(err) => tracing::error!("Failed to listen for CTRL+C signal: {}", err),
+                }
+            },
+            signal = sigterm.recv() => {
+                match signal {
+                    Some(()) => tracing::info!("Received termination signal, exiting..."),
+                    None => tracing::error!("Termination signal cannot be caught anymore, exiting..."),
+                }
+            },
+            signal = sighup.recv() => {
+                match signal {
+                    Some(()) => {
+                        tracing::info!("Received hangup signal, reloading the configuration...");
+                        on_reload();
+                        continue;
+                    }
+                    None => tracing::error!("Hangup signal cannot be caught anymore, exiting..."),
+                }
+            },
+            signal = sigpipe.recv() => {
+                match signal {
+                    Some(()) => tracing::info!("Received pipe signal, exiting..."),
+                    None => tracing::error!("Pipe signal cannot be caught anymore, exiting..."),
+                }
+            },
+            msg = abort_recv.recv() => {
+                // When the msg is `None`, there are no more abort senders, so both
+                // Tendermint and the shell must have already exited
+                if let Some(who) = msg {
+                     tracing::info!("{} has exited, shutting down...", who);
+                }
+                return true;
             }
-            return true;
-        }
-    };
-    false
+        };
+        return false;
+    }
 }
 
 /// Function that blocks until either
@@ -579,6 +865,7 @@ async fn wait_for_abort(
 #[cfg(windows)]
 async fn wait_for_abort(
     mut abort_recv: tokio::sync::mpsc::UnboundedReceiver<&'static str>,
+    _on_reload: impl FnMut(),
 ) -> bool {
     let mut sigbreak = tokio::signal::windows::ctrl_break().unwrap();
     let _ = tokio::select! {
@@ -614,6 +901,7 @@ async fn wait_for_abort(
 #[cfg(not(any(unix, windows)))]
 async fn wait_for_abort(
     mut abort_recv: tokio::sync::mpsc::UnboundedReceiver<&'static str>,
+    _on_reload: impl FnMut(),
 ) -> bool {
     let _ = tokio::select! {
         signal = tokio::signal::ctrl_c() => {
@@ -0,0 +1,198 @@
+//! The status of the node for its operators: the size of the mempool and the
+//! gas and the txs of the recent blocks. The stats are collected by the main
+//! shell in memory since the node started and shared with the query shells.
+//! The [`NodeStatus`] sums up the state of the node's chain for monitoring.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use anoma::types::hash::Hash;
+use anoma::types::storage::{BlockHeight, Epoch};
+use anoma::types::time::DateTimeUtc;
+use anoma::types::transaction::hash_tx;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// The number of the recent blocks whose stats are kept
+pub const RECENT_BLOCKS: usize = 100;
+
+/// The status of the node and of the chain at its last committed block
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct NodeStatus {
+    /// The ID of the chain
+    pub chain_id: String,
+    /// The height of the last committed block
+    pub last_height: BlockHeight,
+    /// The app hash of the last committed block, which is the root of the
+    /// merkle tree of the storage. Empty before the first block.
+    pub app_hash: Vec<u8>,
+    /// The epoch of the last committed block
+    pub epoch: Epoch,
+    /// The height of the first block of the epoch
+    pub epoch_start_height: Option<BlockHeight>,
+    /// The time of the last committed block, if its header is stored
+    pub last_block_time: Option<DateTimeUtc>,
+    /// The version of the node
+    pub version: String,
+    /// Whether the node is still catching up with the chain, as reported by
+    /// the `catching_up` of its Tendermint status. The ledger doesn't know
+    /// it, so it's `None` in the query response and set by the clients that
+    /// also query Tendermint.
+    pub syncing: Option<bool>,
+}
+
+/// The txs in the mempool of the node that passed the mempool validation
+#[derive(
+    Clone, Debug, Default, PartialEq, BorshSerialize, BorshDeserialize,
+)]
+pub struct MempoolStatus {
+    /// The number of txs
+    pub txs: u64,
+    /// The sum of the sizes of the txs in bytes
+    pub bytes: u64,
+}
+
+/// The stats of a finalized block
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct BlockStats {
+    /// The height of the block
+    pub height: BlockHeight,
+    /// The gas used by the txs of the block
+    pub used_gas: u64,
+    /// The block gas limit
+    pub max_block_gas: u64,
+    /// The number of the wrapper txs applied in the block
+    pub wrapper_txs: u64,
+    /// The number of the decrypted txs applied in the block
+    pub decrypted_txs: u64,
+}
+
+impl BlockStats {
+    /// The share of the block gas limit used by the block, in percent
+    pub fn fullness(&self) -> f64 {
+        if self.max_block_gas == 0 {
+            return 0.0;
+        }
+        self.used_gas as f64 * 100.0 / self.max_block_gas as f64
+    }
+}
+
+/// A shared collector of the node's stats. The clones share the same stats.
+#[derive(Clone, Debug, Default)]
+pub struct NodeStats {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    mempool: MempoolStatus,
+    /// The sizes of the txs in the mempool, by their hashes
+    mempool_txs: HashMap<Hash, u64>,
+    /// The stats of the recent blocks, from the oldest
+    recent_blocks: VecDeque<BlockStats>,
+}
+
+impl NodeStats {
+    /// Count a tx that passed the mempool validation. A tx that is already
+    /// counted, e.g. when it's rechecked, isn't counted again.
+    pub fn add_mempool_tx(&self, tx_bytes: &[u8]) {
+        let mut state = self.lock();
+        let tx_len = tx_bytes.len() as u64;
+        if state
+            .mempool_txs
+            .insert(hash_tx(tx_bytes), tx_len)
+            .is_none()
+        {
+            state.mempool.txs += 1;
+            state.mempool.bytes += tx_len;
+        }
+    }
+
+    /// Stop counting a tx that left the mempool, because it failed a recheck
+    /// or because it's included in a block. A tx that isn't counted is
+    /// ignored.
+    pub fn remove_mempool_tx(&self, tx_bytes: &[u8]) {
+        let mut state = self.lock();
+        if let Some(tx_len) = state.mempool_txs.remove(&hash_tx(tx_bytes)) {
+            state.mempool.txs -= 1;
+            state.mempool.bytes -= tx_len;
+        }
+    }
+
+    /// Add the stats of a finalized block. Only the stats of the last
+    /// [`RECENT_BLOCKS`] are kept.
+    pub fn add_block(&self, stats: BlockStats) {
+        let mut state = self.lock();
+        if state.recent_blocks.len() == RECENT_BLOCKS {
+            state.recent_blocks.pop_front();
+        }
+        state.recent_blocks.push_back(stats);
+    }
+
+    /// The current size of the mempool
+    pub fn mempool(&self) -> MempoolStatus {
+        self.lock().mempool.clone()
+    }
+
+    /// The stats of the recent blocks, from the oldest
+    pub fn recent_blocks(&self) -> Vec<BlockStats> {
+        self.lock().recent_blocks.iter().cloned().collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("The node stats lock shouldn't be poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_stats(height: u64) -> BlockStats {
+        BlockStats {
+            height: BlockHeight(height),
+            used_gas: 250,
+            max_block_gas: 1000,
+            wrapper_txs: 2,
+            decrypted_txs: 1,
+        }
+    }
+
+    #[test]
+    fn test_mempool() {
+        let stats = NodeStats::default();
+        let shared = stats.clone();
+        let tx1 = vec![1_u8; 100];
+        let tx2 = vec![2_u8; 50];
+        stats.add_mempool_tx(&tx1);
+        stats.add_mempool_tx(&tx2);
+        assert_eq!(shared.mempool(), MempoolStatus { txs: 2, bytes: 150 });
+        // A rechecked tx is not counted twice
+        stats.add_mempool_tx(&tx1);
+        assert_eq!(shared.mempool(), MempoolStatus { txs: 2, bytes: 150 });
+        stats.remove_mempool_tx(&tx1);
+        assert_eq!(shared.mempool(), MempoolStatus { txs: 1, bytes: 50 });
+        // A tx that isn't counted is ignored
+        stats.remove_mempool_tx(&tx1);
+        assert_eq!(shared.mempool(), MempoolStatus { txs: 1, bytes: 50 });
+        stats.remove_mempool_tx(&tx2);
+        assert_eq!(shared.mempool(), MempoolStatus::default());
+    }
+
+    #[test]
+    fn test_recent_blocks() {
+        let stats = NodeStats::default();
+        for height in 1..=RECENT_BLOCKS as u64 + 2 {
+            stats.add_block(block_stats(height));
+        }
+        let recent = stats.recent_blocks();
+        assert_eq!(recent.len(), RECENT_BLOCKS);
+        assert_eq!(recent[0].height, BlockHeight(3));
+        assert_eq!(
+            recent.last().unwrap().height,
+            BlockHeight(RECENT_BLOCKS as u64 + 2)
+        );
+        assert!((recent[0].fullness() - 25.0).abs() < f64::EPSILON);
+    }
+}
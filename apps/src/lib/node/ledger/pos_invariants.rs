@@ -0,0 +1,60 @@
+//! The offline check of the invariants of the PoS state of the last committed
+//! block in the ledger's DB, which a node built with the `pos-invariants`
+//! feature otherwise checks after each epoch transition.
+
+use anoma::ledger::pos::invariants::{check_invariants, InvariantViolation};
+use anoma::ledger::storage::{self as ledger_storage, Sha256Hasher, Storage};
+use anoma::types::storage::{BlockHeight, Epoch};
+use thiserror::Error;
+
+use super::storage::{self, PersistentBackend};
+use crate::config::{self, StorageBackend};
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Storage error: {0}")]
+    Storage(ledger_storage::Error),
+    #[error("No block has been committed to the DB")]
+    NoBlock,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The result of a check of the PoS invariants
+#[derive(Clone, Debug)]
+pub struct PosInvariantsReport {
+    /// The height of the last committed block
+    pub height: BlockHeight,
+    /// The epoch of the last committed block, from which the invariants are
+    /// checked
+    pub epoch: Epoch,
+    /// The violated invariants
+    pub violations: Vec<InvariantViolation>,
+}
+
+/// Check the PoS invariants of the last committed block in the DB. The
+/// ledger node must not be running.
+pub fn check_pos(config: config::Ledger) -> Result<PosInvariantsReport> {
+    match config.shell.storage_backend {
+        StorageBackend::RocksDB => check_db::<storage::RocksDB>(config),
+        StorageBackend::Sled => check_db::<storage::SledDB>(config),
+    }
+}
+
+/// Check the DB of the given backend, with the hasher of the node's storage
+fn check_db<D: PersistentBackend>(
+    config: config::Ledger,
+) -> Result<PosInvariantsReport> {
+    let db = D::open_primary(&config.db_dir(), None).map_err(Error::Storage)?;
+    let mut storage = Storage::<D, Sha256Hasher>::with_db(db, config.chain_id);
+    storage.load_last_state().map_err(Error::Storage)?;
+    if storage.last_height == BlockHeight(0) {
+        return Err(Error::NoBlock);
+    }
+    let violations = check_invariants(&storage).map_err(Error::Storage)?;
+    Ok(PosInvariantsReport {
+        height: storage.last_height,
+        epoch: storage.last_epoch,
+        violations,
+    })
+}
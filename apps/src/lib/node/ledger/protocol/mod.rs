@@ -6,11 +6,13 @@ use anoma::ledger::eth_bridge::vp::EthBridge;
 use anoma::ledger::gas::{self, BlockGasMeter, VpGasMeter};
 use anoma::ledger::governance::GovernanceVp;
 use anoma::ledger::ibc::vp::{Ibc, IbcToken};
+use anoma::ledger::name_service::vp::NameServiceVp;
 use anoma::ledger::native_vp::{self, NativeVp};
 use anoma::ledger::parameters::{self, ParametersVp};
 use anoma::ledger::pos::{self, PosVP};
 use anoma::ledger::storage::write_log::WriteLog;
 use anoma::ledger::storage::{DBIter, Storage, StorageHasher, DB};
+use anoma::ledger::token_registry::vp::TokenRegistryVp;
 use anoma::ledger::treasury::TreasuryVp;
 use anoma::proto::{self, Tx};
 use anoma::types::address::{Address, InternalAddress};
@@ -53,6 +55,10 @@ pub enum Error {
     TreasuryNativeVpError(anoma::ledger::treasury::Error),
     #[error("Ethereum bridge native VP error: {0}")]
     EthBridgeNativeVpError(anoma::ledger::eth_bridge::vp::Error),
+    #[error("Token registry native VP error: {0}")]
+    TokenRegistryNativeVpError(anoma::ledger::token_registry::vp::Error),
+    #[error("Name service native VP error: {0}")]
+    NameServiceNativeVpError(anoma::ledger::name_service::vp::Error),
     #[error("Access to an internal address {0} is forbidden")]
     AccessForbidden(InternalAddress),
 }
@@ -352,6 +358,22 @@ where
                             gas_meter = bridge.ctx.gas_meter.into_inner();
                             result
                         }
+                        InternalAddress::TokenRegistry => {
+                            let registry = TokenRegistryVp { ctx };
+                            let result = registry
+                                .validate_tx(tx_data, &keys_changed, &verifiers)
+                                .map_err(Error::TokenRegistryNativeVpError);
+                            gas_meter = registry.ctx.gas_meter.into_inner();
+                            result
+                        }
+                        InternalAddress::NameService => {
+                            let name_service = NameServiceVp { ctx };
+                            let result = name_service
+                                .validate_tx(tx_data, &keys_changed, &verifiers)
+                                .map_err(Error::NameServiceNativeVpError);
+                            gas_meter = name_service.ctx.gas_meter.into_inner();
+                            result
+                        }
                     };
 
                     accepted
@@ -368,6 +390,10 @@ where
                 Ok(accepted) => {
                     if !accepted {
                         result.rejected_vps.insert(addr.clone());
+                        result.rejected_vp_keys.insert(
+                            addr.clone(),
+                            examined_keys(addr, &keys_changed),
+                        );
                     } else {
                         result.accepted_vps.insert(addr.clone());
                     }
@@ -377,6 +403,10 @@ where
                     Error::GasError(_) => Err(err),
                     _ => {
                         result.rejected_vps.insert(addr.clone());
+                        result.rejected_vp_keys.insert(
+                            addr.clone(),
+                            examined_keys(addr, &keys_changed),
+                        );
                         result.errors.push((addr.clone(), err.to_string()));
                         Ok(result)
                     }
@@ -388,6 +418,19 @@ where
         })
 }
 
+/// The changed keys examined by the VP of the given address, which are the
+/// changed keys that contain the address
+fn examined_keys(
+    addr: &Address,
+    keys_changed: &BTreeSet<storage::Key>,
+) -> BTreeSet<storage::Key> {
+    keys_changed
+        .iter()
+        .filter(|key| key.find_addresses().contains(addr))
+        .cloned()
+        .collect()
+}
+
 /// Merge VP results from parallel runs
 fn merge_vp_results(
     a: VpsResult,
@@ -396,8 +439,10 @@ fn merge_vp_results(
 ) -> Result<VpsResult> {
     let mut accepted_vps = a.accepted_vps;
     let mut rejected_vps = a.rejected_vps;
+    let mut rejected_vp_keys = a.rejected_vp_keys;
     accepted_vps.extend(b.accepted_vps);
     rejected_vps.extend(b.rejected_vps);
+    rejected_vp_keys.extend(b.rejected_vp_keys);
     let mut errors = a.errors;
     errors.append(&mut b.errors);
     let mut gas_used = a.gas_used;
@@ -413,6 +458,7 @@ fn merge_vp_results(
     Ok(VpsResult {
         accepted_vps,
         rejected_vps,
+        rejected_vp_keys,
         gas_used,
         errors,
     })
@@ -0,0 +1,282 @@
+//! The rate limits and the access control of the queries served by the node,
+//! which can be changed while the node is running.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config;
+use crate::node::ledger::rpc::{path_name, variant_base_paths, QueryRejection};
+
+/// A shared limiter of the queries served by the node. The clones share the
+/// same configuration and count of queries.
+#[derive(Clone, Debug)]
+pub struct QueryRateLimiter {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug)]
+struct State {
+    config: config::Rpc,
+    /// The window of all the queries
+    window: Window,
+    /// The windows of the rate limited query paths, by their name
+    path_windows: HashMap<String, Window>,
+}
+
+/// The count of the queries served in the current one second window
+#[derive(Debug)]
+struct Window {
+    start: Instant,
+    count: u32,
+}
+
+impl Window {
+    fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            count: 0,
+        }
+    }
+
+    /// Check if a new query at the given time is within the maximum of the
+    /// window, without counting it. The window is restarted once it's over.
+    fn has_capacity(&mut self, now: Instant, max_per_second: u32) -> bool {
+        if now.duration_since(self.start) >= Duration::from_secs(1) {
+            self.start = now;
+            self.count = 0;
+        }
+        self.count < max_per_second
+    }
+}
+
+impl QueryRateLimiter {
+    /// Create a limiter with the given configuration
+    pub fn new(config: config::Rpc) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                config,
+                window: Window::new(),
+                path_windows: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Change the configuration of the limiter
+    pub fn set_config(&self, config: config::Rpc) {
+        let mut state = self.lock();
+        state.config = config;
+        state.path_windows.clear();
+    }
+
+    /// Check that a query of the given path is allowed and count it. Returns
+    /// the reason for which the query must be rejected, if any.
+    ///
+    /// A paginated or a batched query is also subject to the access control
+    /// and the rate limits of the paths of the queries that it reads, e.g. a
+    /// page of a prefix to the ones of the `prefix` path. The query is only
+    /// counted once it's within all of its windows.
+    pub fn try_acquire(&self, path: &str) -> Result<(), QueryRejection> {
+        let mut state = self.lock();
+        let State {
+            config,
+            window,
+            path_windows,
+        } = &mut *state;
+        let base_paths = variant_base_paths(path);
+        let matches_allowed = |path: &str| {
+            config
+                .allowed_paths
+                .iter()
+                .any(|allowed| path_matches(path, allowed))
+        };
+        let is_allowed = config.allowed_paths.is_empty()
+            || matches_allowed(path)
+            || (!base_paths.is_empty()
+                && base_paths.iter().all(|path| matches_allowed(path)));
+        let is_denied = std::iter::once(path)
+            .chain(base_paths.iter().map(String::as_str))
+            .any(|path| {
+                config
+                    .denied_paths
+                    .iter()
+                    .any(|denied| path_matches(path, denied))
+            });
+        if !is_allowed || is_denied {
+            return Err(QueryRejection::PathNotAllowed);
+        }
+
+        // The limited names of the query and of the paths that it reads
+        let mut names: Vec<&str> = std::iter::once(path)
+            .chain(base_paths.iter().map(String::as_str))
+            .map(path_name)
+            .filter(|name| {
+                config.path_max_queries_per_second.contains_key(*name)
+            })
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+
+        let now = Instant::now();
+        for name in &names {
+            let max_per_second = config.path_max_queries_per_second[*name];
+            let path_window = path_windows
+                .entry(name.to_string())
+                .or_insert_with(Window::new);
+            if !path_window.has_capacity(now, max_per_second) {
+                return Err(QueryRejection::RateLimited);
+            }
+        }
+        if let Some(max_per_second) = config.max_queries_per_second {
+            if !window.has_capacity(now, max_per_second) {
+                return Err(QueryRejection::RateLimited);
+            }
+            window.count += 1;
+        }
+        for name in names {
+            if let Some(path_window) = path_windows.get_mut(name) {
+                path_window.count += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("The rate limiter lock shouldn't be poisoned")
+    }
+}
+
+/// A path matches a configured path when they're equal or when the configured
+/// path is a prefix of its segments, e.g. `prefix` matches `prefix/#a/b`.
+fn path_matches(path: &str, configured: &str) -> bool {
+    match path.strip_prefix(configured) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::storage;
+
+    use super::*;
+    use crate::node::ledger::rpc::Path;
+
+    #[test]
+    fn test_query_rate_limit() {
+        let limiter = QueryRateLimiter::new(config::Rpc {
+            max_queries_per_second: Some(2),
+            path_max_queries_per_second: HashMap::from([(
+                "prefix".to_string(),
+                1,
+            )]),
+            ..Default::default()
+        });
+        assert_eq!(limiter.try_acquire("prefix/#a"), Ok(()));
+        assert_eq!(
+            limiter.clone().try_acquire("prefix/#b"),
+            Err(QueryRejection::RateLimited)
+        );
+        assert_eq!(limiter.try_acquire("epoch"), Ok(()));
+        assert_eq!(
+            limiter.try_acquire("epoch"),
+            Err(QueryRejection::RateLimited)
+        );
+
+        limiter.set_config(config::Rpc::default());
+        assert_eq!(limiter.try_acquire("epoch"), Ok(()));
+    }
+
+    /// Test that a query rejected by the limit of all the queries isn't
+    /// counted in the window of its path.
+    #[test]
+    fn test_query_rate_limit_checks_all_windows() {
+        let limiter = QueryRateLimiter::new(config::Rpc {
+            max_queries_per_second: Some(1),
+            path_max_queries_per_second: HashMap::from([(
+                "prefix".to_string(),
+                2,
+            )]),
+            ..Default::default()
+        });
+        assert_eq!(limiter.try_acquire("epoch"), Ok(()));
+        assert_eq!(
+            limiter.try_acquire("prefix/#a"),
+            Err(QueryRejection::RateLimited)
+        );
+        let state = limiter.lock();
+        assert!(state
+            .path_windows
+            .get("prefix")
+            .map_or(true, |window| window.count == 0));
+    }
+
+    /// Test that the limits and the access control of the paths also apply to
+    /// their paginated and batched variants.
+    #[test]
+    fn test_query_variants_limits() {
+        let prefix = storage::Key::parse("a/b").unwrap();
+        let page = Path::PrefixPage {
+            prefix: prefix.clone(),
+            after: None,
+            limit: 10,
+        }
+        .to_string();
+        let limiter = QueryRateLimiter::new(config::Rpc {
+            path_max_queries_per_second: HashMap::from([(
+                "prefix".to_string(),
+                1,
+            )]),
+            ..Default::default()
+        });
+        assert_eq!(limiter.try_acquire(&page), Ok(()));
+        assert_eq!(
+            limiter.try_acquire(&Path::Prefix(prefix.clone()).to_string()),
+            Err(QueryRejection::RateLimited)
+        );
+
+        let denied = storage::Key::parse("c/d").unwrap();
+        let limiter = QueryRateLimiter::new(config::Rpc {
+            allowed_paths: vec!["value".to_string(), "prefix".to_string()],
+            denied_paths: vec![Path::Value(denied.clone()).to_string()],
+            ..Default::default()
+        });
+        assert_eq!(limiter.try_acquire(&page), Ok(()));
+        assert_eq!(
+            limiter
+                .try_acquire(&Path::Values(vec![prefix.clone()]).to_string()),
+            Ok(())
+        );
+        assert_eq!(
+            limiter
+                .try_acquire(&Path::Values(vec![prefix, denied]).to_string()),
+            Err(QueryRejection::PathNotAllowed)
+        );
+    }
+
+    #[test]
+    fn test_query_access_control() {
+        let limiter = QueryRateLimiter::new(config::Rpc {
+            allowed_paths: vec!["value".to_string(), "prefix".to_string()],
+            denied_paths: vec!["prefix/#a".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(limiter.try_acquire("value/#a/b"), Ok(()));
+        assert_eq!(limiter.try_acquire("prefix/#b"), Ok(()));
+        assert_eq!(
+            limiter.try_acquire("prefix/#a/b"),
+            Err(QueryRejection::PathNotAllowed)
+        );
+        assert_eq!(
+            limiter.try_acquire("dry_run_tx"),
+            Err(QueryRejection::PathNotAllowed)
+        );
+        // Only whole segments of a path match
+        assert_eq!(
+            limiter.try_acquire("value_of"),
+            Err(QueryRejection::PathNotAllowed)
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! Replay of the committed blocks. The blocks are re-executed from their
+//! persisted requests in a copy of the DB rolled back to the state before the
+//! first replayed block and the resulting app hashes are compared with the
+//! committed ones. This can be used to debug consensus failures and to verify
+//! that the execution is deterministic across versions. On a mismatch, the
+//! diffs of the replayed block are compared with the committed diffs, to
+//! report the keys whose changes diverged.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use anoma::bytes::ByteBuf;
+use anoma::ledger::storage::{DBIter, MerkleTree, Sha256Hasher, DB};
+use anoma::types::storage::{BlockHeight, Key};
+use thiserror::Error;
+
+use super::shell::{self, Shell};
+use super::shims::abcipp_shim_types::shim::request::FinalizeBlock;
+use super::shims::abcipp_shim_types::shim::{Request, Response};
+use super::storage::{self, PersistentBackend};
+use crate::config::{self, StorageBackend};
+
+/// The size of each of the WASM compilation caches used in the replay, if not
+/// configured.
+const DEFAULT_WASM_COMPILATION_CACHE_BYTES: u64 = 256 * 1024 * 1024;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error(
+        "Invalid range of heights to replay from {0} to {1}. The first height \
+         must be greater than 1 and not greater than the last height."
+    )]
+    InvalidRange(BlockHeight, BlockHeight),
+    #[error("Storage error: {0}")]
+    Storage(anoma::ledger::storage::Error),
+    #[error("Error removing the replay DB data: {0}")]
+    RemoveDB(std::io::Error),
+    #[error("The request of the block at height {0} is not in the DB")]
+    MissingRequest(BlockHeight),
+    #[error("Error decoding the request of the block at height {0}: {1}")]
+    DecodeRequest(BlockHeight, std::io::Error),
+    #[error("The Merkle tree of the block at height {0} is not in the DB")]
+    MissingMerkleTree(BlockHeight),
+    #[error("Error re-executing the block at height {0}: {1}")]
+    Shell(BlockHeight, shell::Error),
+    #[error(
+        "App hash mismatch at height {height}: committed {committed}, \
+         replayed {replayed}, diverged keys: [{}]",
+        .divergent_keys.join(", ")
+    )]
+    AppHashMismatch {
+        height: BlockHeight,
+        committed: String,
+        replayed: String,
+        divergent_keys: Vec<String>,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Re-execute the blocks in the given range of heights (inclusive) and check
+/// that their app hashes match the committed ones. Stops at the first
+/// mismatch. The ledger node must not be running.
+pub fn replay(
+    config: config::Ledger,
+    wasm_dir: PathBuf,
+    from_height: BlockHeight,
+    to_height: BlockHeight,
+) -> Result<()> {
+    if from_height.0 <= 1 || from_height > to_height {
+        return Err(Error::InvalidRange(from_height, to_height));
+    }
+    match config.shell.storage_backend {
+        StorageBackend::RocksDB => replay_with::<storage::RocksDB>(
+            config,
+            wasm_dir,
+            from_height,
+            to_height,
+        ),
+        StorageBackend::Sled => replay_with::<storage::SledDB>(
+            config,
+            wasm_dir,
+            from_height,
+            to_height,
+        ),
+    }
+}
+
+/// Replay the blocks with the DB of the given backend
+fn replay_with<D: PersistentBackend>(
+    config: config::Ledger,
+    wasm_dir: PathBuf,
+    from_height: BlockHeight,
+    to_height: BlockHeight,
+) -> Result<()> {
+    let replay_config = config.for_replay();
+    let replay_db_dir = replay_config.db_dir();
+    remove_replay_db(&replay_db_dir)?;
+
+    let db = D::open_primary(&config.db_dir(), None).map_err(Error::Storage)?;
+    // Copy the DB and rollback the copy to the state before the first block
+    db.checkpoint(&replay_db_dir).map_err(Error::Storage)?;
+    {
+        let mut replay_db =
+            D::open_primary(&replay_db_dir, None).map_err(Error::Storage)?;
+        replay_db
+            .rollback(BlockHeight(from_height.0 - 1))
+            .map_err(Error::Storage)?;
+    }
+
+    let result =
+        replay_blocks(&db, replay_config, wasm_dir, from_height, to_height);
+    remove_replay_db(&replay_db_dir)?;
+    result
+}
+
+fn replay_blocks<D: PersistentBackend>(
+    db: &D,
+    replay_config: config::Ledger,
+    wasm_dir: PathBuf,
+    from_height: BlockHeight,
+    to_height: BlockHeight,
+) -> Result<()> {
+    let vp_wasm_compilation_cache = replay_config
+        .shell
+        .vp_wasm_compilation_cache_bytes
+        .unwrap_or(DEFAULT_WASM_COMPILATION_CACHE_BYTES);
+    let tx_wasm_compilation_cache = replay_config
+        .shell
+        .tx_wasm_compilation_cache_bytes
+        .unwrap_or(DEFAULT_WASM_COMPILATION_CACHE_BYTES);
+    // The replayed blocks must not broadcast any txs
+    let (broadcast_sender, _broadcast_receiver) =
+        tokio::sync::mpsc::unbounded_channel();
+    let mut shell: Shell<D> = Shell::new(
+        replay_config,
+        wasm_dir,
+        broadcast_sender,
+        None,
+        vp_wasm_compilation_cache,
+        tx_wasm_compilation_cache,
+    );
+
+    for height in from_height.0..=to_height.0 {
+        let height = BlockHeight(height);
+        let request = db
+            .read_block_request(height)
+            .map_err(Error::Storage)?
+            .ok_or(Error::MissingRequest(height))?;
+        let request = FinalizeBlock::decode(&request)
+            .map_err(|err| Error::DecodeRequest(height, err))?;
+        let committed = db
+            .read_merkle_tree_stores(height)
+            .map_err(Error::Storage)?
+            .map(MerkleTree::<Sha256Hasher>::new)
+            .ok_or(Error::MissingMerkleTree(height))?
+            .root();
+
+        shell
+            .call(Request::FinalizeBlock(request))
+            .map_err(|err| Error::Shell(height, err))?;
+        let replayed = match shell
+            .call(Request::Commit(Default::default()))
+            .map_err(|err| Error::Shell(height, err))?
+        {
+            Response::Commit(commit) => commit.data,
+            _ => unreachable!("The commit request must get a commit response"),
+        };
+
+        if replayed != committed.0 {
+            return Err(Error::AppHashMismatch {
+                height,
+                committed: committed.to_string(),
+                replayed: ByteBuf(&replayed).to_string(),
+                divergent_keys: divergent_keys(db, &shell.storage.db, height),
+            });
+        }
+        println!(
+            "Block at height {} replayed with app hash {}",
+            height, committed
+        );
+    }
+    Ok(())
+}
+
+/// The previous and the new values of the account subspace keys that were
+/// changed in a block, read from the diffs of the block
+type BlockDiffs = BTreeMap<String, (Option<Vec<u8>>, Option<Vec<u8>>)>;
+
+/// Read the diffs of the block at the given height
+fn block_diffs<D>(db: &D, height: BlockHeight) -> BlockDiffs
+where
+    D: DB + for<'iter> DBIter<'iter>,
+{
+    // The empty prefix of all the keys
+    let all_keys = Key { segments: vec![] };
+    let mut diffs = BlockDiffs::new();
+    for (key, old, _gas) in db.iter_old_diffs(height, &all_keys) {
+        diffs.entry(key).or_default().0 = Some(old);
+    }
+    for (key, new, _gas) in db.iter_new_diffs(height, &all_keys) {
+        diffs.entry(key).or_default().1 = Some(new);
+    }
+    diffs
+}
+
+/// Find the keys whose changes in the block at the given height differ
+/// between the committed and the replayed DB
+fn divergent_keys<D>(
+    committed: &D,
+    replayed: &D,
+    height: BlockHeight,
+) -> Vec<String>
+where
+    D: DB + for<'iter> DBIter<'iter>,
+{
+    let committed = block_diffs(committed, height);
+    let replayed = block_diffs(replayed, height);
+    let keys: BTreeSet<&String> =
+        committed.keys().chain(replayed.keys()).collect();
+    keys.into_iter()
+        .filter(|key| committed.get(*key) != replayed.get(*key))
+        .cloned()
+        .collect()
+}
+
+fn remove_replay_db(replay_db_dir: &std::path::Path) -> Result<()> {
+    match std::fs::remove_dir_all(replay_db_dir) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        res => res.map_err(Error::RemoveDB),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::ledger::storage::mockdb::MockDB;
+
+    use super::*;
+
+    /// Test that the keys whose values were changed differently, or only in
+    /// one of the DBs, are divergent.
+    #[test]
+    fn test_divergent_keys() {
+        let height = BlockHeight(1);
+        let key = |key: &str| Key::parse(key).unwrap();
+        let mut committed = MockDB::default();
+        let mut replayed = MockDB::default();
+        for db in [&mut committed, &mut replayed] {
+            db.write_subspace_val(height, &key("same"), vec![1])
+                .unwrap();
+        }
+        committed
+            .write_subspace_val(height, &key("changed"), vec![1])
+            .unwrap();
+        replayed
+            .write_subspace_val(height, &key("changed"), vec![2])
+            .unwrap();
+        replayed
+            .write_subspace_val(height, &key("replayed"), vec![1])
+            .unwrap();
+        assert!(divergent_keys(&committed, &committed, height).is_empty());
+        assert_eq!(
+            divergent_keys(&committed, &replayed, height),
+            vec!["changed".to_string(), "replayed".to_string()]
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! The REST server of the common ledger queries, which is built with the
+//! `rest` feature, for the wallets and the explorers that don't embed a
+//! Tendermint RPC client. The requests are mapped onto the ABCI [`Path`]s,
+//! which are forwarded to the query shells with a [`QueryHandle`], and the
+//! results are returned in the JSON format of the client's `json` output
+//! mode. The served requests are:
+//!
+//! - `GET /epoch`: the epoch of the last committed block
+//! - `GET /balance/{owner}/{token}`: the balance of a token owned by an address
+//! - `GET /tx/{hash}`: the result of a tx found by the txs indexer
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anoma::types::address::{self, Address};
+use anoma::types::hash::{self, Hash};
+use anoma::types::query::{Balance, EpochQueryResult, NOT_FOUND_CODE};
+use anoma::types::storage::Epoch;
+use anoma::types::token;
+use anoma::types::transaction::TxResponse;
+use borsh::BorshDeserialize;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{header, Body, Method, Request, Response, Server, StatusCode};
+use serde::Serialize;
+use thiserror::Error;
+#[cfg(not(feature = "ABCI"))]
+use tower_abci::request;
+#[cfg(feature = "ABCI")]
+use tower_abci_old::request;
+
+use crate::node::ledger::rpc::{Path, QueryRejection};
+use crate::node::ledger::shims::abcipp_shim::{QueryError, QueryHandle};
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+enum Error {
+    #[error("Only GET requests are served")]
+    InvalidMethod,
+    #[error("Unknown request path {0}")]
+    UnknownPath(String),
+    #[error("Invalid address {0}: {1}")]
+    InvalidAddress(String, address::Error),
+    #[error("Invalid tx hash {0}: {1}")]
+    InvalidHash(String, hash::Error),
+    #[error("{0}")]
+    Query(QueryError),
+    #[error("The query {path} failed with code {code}: {info}")]
+    QueryFailed {
+        path: String,
+        code: u32,
+        info: String,
+    },
+    #[error("The query {0} returned no value")]
+    MissingValue(String),
+    #[error("Error decoding the response of the query {path}: {error}")]
+    Decoding { path: String, error: std::io::Error },
+    #[error("No tx found with hash {0}, or the node doesn't index the txs")]
+    TxNotFound(Hash),
+}
+
+impl Error {
+    /// The HTTP status of the error response
+    fn status(&self) -> StatusCode {
+        match self {
+            Error::InvalidMethod => StatusCode::METHOD_NOT_ALLOWED,
+            Error::UnknownPath(_) | Error::TxNotFound(_) => {
+                StatusCode::NOT_FOUND
+            }
+            Error::InvalidAddress(_, _) | Error::InvalidHash(_, _) => {
+                StatusCode::BAD_REQUEST
+            }
+            Error::Query(QueryError::Rejected(QueryRejection::RateLimited)) => {
+                StatusCode::TOO_MANY_REQUESTS
+            }
+            Error::Query(QueryError::Rejected(
+                QueryRejection::PathNotAllowed,
+            )) => StatusCode::FORBIDDEN,
+            Error::Query(QueryError::ShutDown) => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            Error::Query(QueryError::Failed(_))
+            | Error::QueryFailed { .. }
+            | Error::MissingValue(_)
+            | Error::Decoding { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// A request served by the REST server
+#[derive(Clone, Debug, PartialEq)]
+enum Route {
+    Epoch,
+    Balance { owner: Address, token: Address },
+    Tx(Hash),
+}
+
+impl Route {
+    /// Parse the path of a request
+    fn parse(path: &str) -> Result<Self> {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match segments.as_slice() {
+            ["epoch"] => Ok(Route::Epoch),
+            ["balance", owner, token] => Ok(Route::Balance {
+                owner: parse_address(owner)?,
+                token: parse_address(token)?,
+            }),
+            ["tx", hash] => Hash::from_str(hash)
+                .map(Route::Tx)
+                .map_err(|err| Error::InvalidHash(hash.to_string(), err)),
+            _ => Err(Error::UnknownPath(path.to_owned())),
+        }
+    }
+
+    /// Serve the request with the queries of the ledger. Returns the JSON
+    /// body of the response.
+    async fn serve(self, queries: &QueryHandle) -> Result<String> {
+        match self {
+            Route::Epoch => {
+                let epoch: Epoch =
+                    query(queries, Path::Epoch).await?.ok_or_else(|| {
+                        Error::MissingValue(Path::Epoch.to_string())
+                    })?;
+                Ok(to_json(&EpochQueryResult { epoch }))
+            }
+            Route::Balance { owner, token } => {
+                let key = token::balance_key(&token, &owner);
+                // An owner without a balance owns no tokens
+                let amount: token::Amount =
+                    query(queries, Path::Value(key)).await?.unwrap_or_default();
+                let symbol = match token::Denomination::native(&token) {
+                    Some(denomination) => denomination.symbol,
+                    None => {
+                        let key = token::metadata_key(&token);
+                        query::<token::Metadata>(queries, Path::Value(key))
+                            .await?
+                            .map(|metadata| {
+                                token::Denomination::from(&metadata).symbol
+                            })
+                            .unwrap_or_else(|| token.encode())
+                    }
+                };
+                Ok(to_json(&Balance {
+                    token,
+                    symbol,
+                    owner,
+                    amount,
+                }))
+            }
+            Route::Tx(hash) => {
+                let response: Option<Option<TxResponse>> =
+                    query(queries, Path::TxEvent(hash.clone())).await?;
+                match response.flatten() {
+                    Some(response) => Ok(to_json(&response)),
+                    None => Err(Error::TxNotFound(hash)),
+                }
+            }
+        }
+    }
+}
+
+fn parse_address(address: &str) -> Result<Address> {
+    Address::decode(address)
+        .map_err(|err| Error::InvalidAddress(address.to_owned(), err))
+}
+
+/// Encode the body of a response as pretty-printed JSON, like the results
+/// are printed by the client with the `json` output mode
+fn to_json<T: Serialize>(result: &T) -> String {
+    serde_json::to_string_pretty(result).unwrap()
+}
+
+/// Send the query of the given path at the last committed block and decode
+/// its value. Returns `None` if the query found no value.
+async fn query<T: BorshDeserialize>(
+    queries: &QueryHandle,
+    path: Path,
+) -> Result<Option<T>> {
+    let path = path.to_string();
+    let response = queries
+        .query(request::Query {
+            data: Default::default(),
+            path: path.clone(),
+            height: 0,
+            prove: false,
+        })
+        .await
+        .map_err(Error::Query)?;
+    match response.code {
+        0 => T::try_from_slice(&response.value[..])
+            .map(Some)
+            .map_err(|error| Error::Decoding { path, error }),
+        NOT_FOUND_CODE => Ok(None),
+        code => Err(Error::QueryFailed {
+            path,
+            code,
+            info: response.info,
+        }),
+    }
+}
+
+/// Handle a request. The errors are returned as a JSON object with the
+/// message of the error.
+async fn handle(
+    queries: &QueryHandle,
+    request: Request<Body>,
+) -> Response<Body> {
+    let result = if request.method() == Method::GET {
+        match Route::parse(request.uri().path()) {
+            Ok(route) => route.serve(queries).await,
+            Err(err) => Err(err),
+        }
+    } else {
+        Err(Error::InvalidMethod)
+    };
+    let (status, body) = match result {
+        Ok(body) => (StatusCode::OK, body),
+        Err(err) => (
+            err.status(),
+            to_json(&serde_json::json!({ "error": err.to_string() })),
+        ),
+    };
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .expect("The response should be valid")
+}
+
+/// Serve the REST requests at the given address, until the server fails or
+/// the task is aborted
+pub async fn run_server(
+    address: SocketAddr,
+    queries: QueryHandle,
+) -> std::result::Result<(), hyper::Error> {
+    let queries = Arc::new(queries);
+    let make_service = make_service_fn(move |_conn| {
+        let queries = queries.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let queries = queries.clone();
+                async move {
+                    let response = handle(&queries, request).await;
+                    Ok::<_, Infallible>(response)
+                }
+            }))
+        }
+    });
+    let server = Server::try_bind(&address)?;
+    tracing::info!("REST server started at {}", address);
+    server.serve(make_service).await
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::address::testing::established_address_1;
+    use anoma::types::address::xan;
+
+    use super::*;
+
+    #[test]
+    fn test_route_parse() {
+        assert_eq!(Route::parse("/epoch").unwrap(), Route::Epoch);
+        assert_eq!(Route::parse("/epoch/").unwrap(), Route::Epoch);
+
+        let owner = established_address_1();
+        let token = xan();
+        let path = format!("/balance/{}/{}", owner.encode(), token.encode());
+        assert_eq!(
+            Route::parse(&path).unwrap(),
+            Route::Balance {
+                owner: owner.clone(),
+                token
+            }
+        );
+        let path = format!("/balance/{}/invalid", owner.encode());
+        let err = Route::parse(&path).unwrap_err();
+        assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+
+        let hash = Hash([1; 32]);
+        let path = format!("/tx/{}", hash);
+        assert_eq!(Route::parse(&path).unwrap(), Route::Tx(hash));
+
+        let err = Route::parse("/balance").unwrap_err();
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -1,14 +1,18 @@
 //! RPC endpoint is used for ledger state queries
 
 use std::fmt::Display;
+use std::num::ParseIntError;
 use std::str::FromStr;
 
-use anoma::types::address::Address;
+use anoma::ledger::pos::SlashesCursor;
+use anoma::types::address::{self, Address};
+use anoma::types::hash::{self, Hash};
+use anoma::types::query::{
+    DRY_RUN_TX_PATH, EPOCH_PATH, PATH_NOT_ALLOWED_CODE, PREFIX_PAGE_PREFIX,
+    PREFIX_PREFIX, RATE_LIMITED_CODE, VALUE_PREFIX,
+};
 use anoma::types::storage;
-#[cfg(not(feature = "ABCI"))]
-use tendermint::abci::Path as AbciPath;
-#[cfg(feature = "ABCI")]
-use tendermint_stable::abci::Path as AbciPath;
+use tendermint_compat::tendermint::abci::Path as AbciPath;
 use thiserror::Error;
 
 /// RPC query path
@@ -18,12 +22,106 @@ pub enum Path {
     DryRunTx,
     /// Epoch of the last committed block
     Epoch,
+    /// Read the start of the epoch of the last committed block, the earliest
+    /// start of the next epoch and the epoch duration parameters
+    EpochInfo,
+    /// Read the gas used by a committed block and the current gas limits
+    BlockGas,
+    /// Read the protocol version of the chain and the pending upgrade
+    Upgrade,
+    /// Read the number and the size of the txs in the node's mempool
+    Mempool,
+    /// Read the gas and the txs of the recent blocks finalized by the node
+    RecentBlocks,
+    /// Read the status of the node and of the chain at its last block
+    Status,
     /// Read a storage value with exact storage key
     Value(storage::Key),
+    /// Read the storage values of multiple keys at the same height
+    Values(Vec<storage::Key>),
     /// Read a range of storage values with a matching key prefix
     Prefix(storage::Key),
+    /// Read a page of the storage values with a matching key prefix, whose
+    /// keys are after the `after` key, if any
+    PrefixPage {
+        prefix: storage::Key,
+        after: Option<storage::Key>,
+        limit: u64,
+    },
     /// Check if the given storage key exists
     HasKey(storage::Key),
+    /// Read the ICS-23 proof specs of the Merkle proofs of the storage
+    /// values
+    ProofSpecs,
+    /// Find the storage keys with a matching prefix, if any, that were
+    /// written or deleted in the blocks above the `from` height up to the
+    /// `to` height
+    ChangedKeys {
+        from: storage::BlockHeight,
+        to: storage::BlockHeight,
+        prefix: Option<storage::Key>,
+    },
+    /// Read the current public keys and VP code hash of an account
+    Account(Address),
+    /// Read the number and the size of a page of the keys in the subspace of
+    /// an account, whose keys are after the `after` key, if any
+    StorageUsage {
+        address: Address,
+        after: Option<storage::Key>,
+    },
+    /// Read the bonds and the unbonds of the owner to the validator, with the
+    /// slashes applied. Without an owner, the self-bonds of the validator are
+    /// read. Without a validator, the bonds of the owner to any validator are
+    /// read, or all the bonds without an owner either.
+    Bonds {
+        owner: Option<Address>,
+        validator: Option<Address>,
+    },
+    /// Read the pending rewards of the bonds of the owner to the validator,
+    /// with the same selection of the bonds as [`Path::Bonds`]
+    Rewards {
+        owner: Option<Address>,
+        validator: Option<Address>,
+    },
+    /// Read the validator set of the given epoch, or of the last committed
+    /// epoch, with the consensus keys and the voting powers
+    ValidatorSet(Option<storage::Epoch>),
+    /// Read the validator set of the given epoch, with the consensus keys and
+    /// the voting powers that the validators had in that epoch, which is also
+    /// kept for the past epochs within the unbonding length
+    ValidatorSetAt(storage::Epoch),
+    /// Read the PoS parameters
+    PosParams,
+    /// Estimate the inflation of the staking token in the last committed
+    /// epoch and the annual rate of the inflation rewards
+    Inflation,
+    /// Read a page of at most `limit` slashes of the validator, or of all the
+    /// validators, that occurred in the epochs from the `from_epoch` up to
+    /// the `to_epoch`, if any, from the `start` of the page, if any
+    Slashes {
+        validator: Option<Address>,
+        from_epoch: Option<storage::Epoch>,
+        to_epoch: Option<storage::Epoch>,
+        start: Option<SlashesCursor>,
+        limit: u64,
+    },
+    /// Read the total supply of the given token
+    TotalSupply(Address),
+    /// Find the indexed txs in which the given address is involved
+    TxsByAddress(Address),
+    /// Find the indexed txs that moved the given token
+    TxsByToken(Address),
+    /// Find the indexed results of the txs signed by the given sender
+    TxsBySender(Address),
+    /// Find the indexed token transfers of the given owner
+    TransferHistory(Address),
+    /// Read the balances of all the tokens of the given owner, with the
+    /// tokens found by the txs indexer
+    OwnerBalances(Address),
+    /// Find the indexed results of the txs of the block at the given height
+    BlockResults(storage::BlockHeight),
+    /// Find the indexed result of the tx with the given hash
+    TxEvent(Hash),
 }
 
 #[derive(Debug, Clone)]
@@ -34,26 +132,299 @@ pub struct BalanceQuery {
     token: Option<Address>,
 }
 
-const DRY_RUN_TX_PATH: &str = "dry_run_tx";
-const EPOCH_PATH: &str = "epoch";
-const VALUE_PREFIX: &str = "value";
-const PREFIX_PREFIX: &str = "prefix";
+const EPOCH_INFO_PATH: &str = "epoch_info";
+const BLOCK_GAS_PATH: &str = "block_gas";
+const UPGRADE_PATH: &str = "upgrade";
+const MEMPOOL_PATH: &str = "mempool";
+const RECENT_BLOCKS_PATH: &str = "recent_blocks";
+const STATUS_PATH: &str = "status";
+const VALUES_PREFIX: &str = "values";
 const HAS_KEY_PREFIX: &str = "has_key";
+const PROOF_SPECS_PATH: &str = "proof_specs";
+const CHANGED_KEYS_PREFIX: &str = "changed_keys";
+const ACCOUNT_PREFIX: &str = "account";
+const STORAGE_USAGE_PREFIX: &str = "storage_usage";
+const BONDS_PREFIX: &str = "bonds";
+const REWARDS_PREFIX: &str = "rewards";
+const VALIDATOR_SET_PATH: &str = "validator_set";
+const VALIDATOR_SET_AT_PREFIX: &str = "validator_set_at";
+const POS_PARAMS_PATH: &str = "pos_params";
+const INFLATION_PATH: &str = "inflation";
+const SLASHES_PREFIX: &str = "slashes";
+const TOTAL_SUPPLY_PREFIX: &str = "total_supply";
+const TXS_BY_ADDRESS_PREFIX: &str = "txs_by_address";
+const TXS_BY_TOKEN_PREFIX: &str = "txs_by_token";
+const TXS_BY_SENDER_PREFIX: &str = "txs_by_sender";
+const TRANSFER_HISTORY_PREFIX: &str = "transfer_history";
+const OWNER_BALANCES_PREFIX: &str = "owner_balances";
+const BLOCK_RESULTS_PREFIX: &str = "block_results";
+const TX_EVENT_PREFIX: &str = "tx_event";
+
+/// The names of all the query paths, which is the first segment of a path.
+/// They are used in the node's configuration of the allowed and rate limited
+/// query paths.
+pub const PATH_NAMES: [&str; 32] = [
+    DRY_RUN_TX_PATH,
+    EPOCH_PATH,
+    EPOCH_INFO_PATH,
+    BLOCK_GAS_PATH,
+    UPGRADE_PATH,
+    MEMPOOL_PATH,
+    RECENT_BLOCKS_PATH,
+    STATUS_PATH,
+    VALUE_PREFIX,
+    VALUES_PREFIX,
+    PREFIX_PREFIX,
+    PREFIX_PAGE_PREFIX,
+    HAS_KEY_PREFIX,
+    PROOF_SPECS_PATH,
+    CHANGED_KEYS_PREFIX,
+    ACCOUNT_PREFIX,
+    STORAGE_USAGE_PREFIX,
+    BONDS_PREFIX,
+    REWARDS_PREFIX,
+    VALIDATOR_SET_PATH,
+    VALIDATOR_SET_AT_PREFIX,
+    POS_PARAMS_PATH,
+    INFLATION_PATH,
+    SLASHES_PREFIX,
+    TOTAL_SUPPLY_PREFIX,
+    TXS_BY_ADDRESS_PREFIX,
+    TXS_BY_TOKEN_PREFIX,
+    TXS_BY_SENDER_PREFIX,
+    TRANSFER_HISTORY_PREFIX,
+    OWNER_BALANCES_PREFIX,
+    BLOCK_RESULTS_PREFIX,
+    TX_EVENT_PREFIX,
+];
+
+/// The maximum number of balances summed by a [`Path::TotalSupply`] query of a
+/// token that doesn't track its supply
+pub const MAX_TOTAL_SUPPLY_BALANCES: usize = 10_000;
+
+/// The maximum number of keys counted by a page of [`Path::StorageUsage`]
+pub const MAX_STORAGE_USAGE_PAGE_KEYS: usize = 10_000;
+
+/// The maximum number of slashes in a page of [`Path::Slashes`]. A larger
+/// limit is reduced to it.
+pub const MAX_SLASHES_PAGE_LIMIT: u64 = 1000;
+
+/// The maximum number of blocks in the range of a [`Path::ChangedKeys`]
+/// query
+pub const MAX_CHANGED_KEYS_BLOCKS: u64 = 1000;
+
+/// The maximum number of keys read by a single [`Path::Values`] query
+pub const MAX_VALUES_KEYS: usize = 256;
+
+/// Get the name of a query path, e.g. `prefix` for `prefix/#a/b`
+pub fn path_name(path: &str) -> &str {
+    match path.split_once('/') {
+        Some((name, _)) => name,
+        None => path,
+    }
+}
+
+/// Get the paths of the queries that a paginated or a batched query of the
+/// given path reads, e.g. `prefix/#a/b` for a page of the `#a/b` prefix (also
+/// used by the gRPC prefix streams) or `value/#a/b` for the `values` of a
+/// batch with the `#a/b` key. The limits and the access control of these
+/// paths apply to the query too. Other queries have none.
+pub fn variant_base_paths(path: &str) -> Vec<String> {
+    match path_name(path) {
+        PREFIX_PAGE_PREFIX | VALUES_PREFIX => {}
+        _ => return vec![],
+    }
+    match Path::from_str(path) {
+        Ok(Path::PrefixPage { prefix, .. }) => {
+            vec![Path::Prefix(prefix).to_string()]
+        }
+        Ok(Path::Values(keys)) => keys
+            .into_iter()
+            .map(|key| Path::Value(key).to_string())
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// The reasons for which the node refuses to serve a query. They are returned
+/// in the code of the query response, so that the clients can tell them apart
+/// from the errors of the query itself, which use the codes `1`, `2` and `5`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryRejection {
+    /// The query rate limit of the node or of the query path is exceeded
+    RateLimited = RATE_LIMITED_CODE as isize,
+    /// The query path is not allowed on the node
+    PathNotAllowed = PATH_NOT_ALLOWED_CODE as isize,
+}
+
+impl QueryRejection {
+    /// The code of the query response
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+
+    /// Find the rejection with the code of a query response, if any
+    pub fn from_code(code: u32) -> Option<Self> {
+        match code {
+            PATH_NOT_ALLOWED_CODE => Some(Self::PathNotAllowed),
+            RATE_LIMITED_CODE => Some(Self::RateLimited),
+            _ => None,
+        }
+    }
+}
+
+impl Display for QueryRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RateLimited => write!(
+                f,
+                "The node's query rate limit is exceeded, try again later"
+            ),
+            Self::PathNotAllowed => {
+                write!(f, "The query path is not allowed on this node")
+            }
+        }
+    }
+}
 
 impl Display for Path {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Path::DryRunTx => write!(f, "{}", DRY_RUN_TX_PATH),
             Path::Epoch => write!(f, "{}", EPOCH_PATH),
+            Path::EpochInfo => write!(f, "{}", EPOCH_INFO_PATH),
+            Path::BlockGas => write!(f, "{}", BLOCK_GAS_PATH),
+            Path::Upgrade => write!(f, "{}", UPGRADE_PATH),
+            Path::Mempool => write!(f, "{}", MEMPOOL_PATH),
+            Path::RecentBlocks => write!(f, "{}", RECENT_BLOCKS_PATH),
+            Path::Status => write!(f, "{}", STATUS_PATH),
             Path::Value(storage_key) => {
                 write!(f, "{}/{}", VALUE_PREFIX, storage_key)
             }
+            Path::Values(keys) => {
+                // The keys are hex encoded, so that each of them is a single
+                // segment of the path
+                write!(f, "{}", VALUES_PREFIX)?;
+                for key in keys {
+                    write!(f, "/{}", encode_hex_key(key))?;
+                }
+                Ok(())
+            }
             Path::Prefix(storage_key) => {
                 write!(f, "{}/{}", PREFIX_PREFIX, storage_key)
             }
+            Path::PrefixPage {
+                prefix,
+                after,
+                limit,
+            } => {
+                // The `after` key is hex encoded, so that it's a single
+                // segment of the path
+                let after =
+                    after.as_ref().map(encode_hex_key).unwrap_or_default();
+                write!(
+                    f,
+                    "{}/{}/{}/{}",
+                    PREFIX_PAGE_PREFIX, limit, after, prefix
+                )
+            }
             Path::HasKey(storage_key) => {
                 write!(f, "{}/{}", HAS_KEY_PREFIX, storage_key)
             }
+            Path::ProofSpecs => write!(f, "{}", PROOF_SPECS_PATH),
+            Path::ChangedKeys { from, to, prefix } => {
+                // A missing prefix is an empty segment
+                let prefix = prefix
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default();
+                write!(f, "{}/{}/{}/{}", CHANGED_KEYS_PREFIX, from, to, prefix)
+            }
+            Path::Account(address) => {
+                write!(f, "{}/{}", ACCOUNT_PREFIX, address)
+            }
+            Path::StorageUsage { address, after } => {
+                let after =
+                    after.as_ref().map(encode_hex_key).unwrap_or_default();
+                write!(f, "{}/{}/{}", STORAGE_USAGE_PREFIX, after, address)
+            }
+            Path::Bonds { owner, validator }
+            | Path::Rewards { owner, validator } => {
+                let prefix = if matches!(self, Path::Bonds { .. }) {
+                    BONDS_PREFIX
+                } else {
+                    REWARDS_PREFIX
+                };
+                // A missing address is an empty segment
+                let encode = |address: &Option<Address>| {
+                    address.as_ref().map(Address::encode).unwrap_or_default()
+                };
+                write!(f, "{}/{}/{}", prefix, encode(owner), encode(validator))
+            }
+            Path::ValidatorSet(None) => write!(f, "{}", VALIDATOR_SET_PATH),
+            Path::ValidatorSet(Some(epoch)) => {
+                write!(f, "{}/{}", VALIDATOR_SET_PATH, epoch)
+            }
+            Path::ValidatorSetAt(epoch) => {
+                write!(f, "{}/{}", VALIDATOR_SET_AT_PREFIX, epoch)
+            }
+            Path::PosParams => write!(f, "{}", POS_PARAMS_PATH),
+            Path::Inflation => write!(f, "{}", INFLATION_PATH),
+            Path::Slashes {
+                validator,
+                from_epoch,
+                to_epoch,
+                start,
+                limit,
+            } => {
+                // A missing epoch, validator or start is an empty segment.
+                // The start is the index of its slash and its validator.
+                let encode_epoch = |epoch: &Option<storage::Epoch>| {
+                    epoch.as_ref().map(ToString::to_string).unwrap_or_default()
+                };
+                let validator =
+                    validator.as_ref().map(Address::encode).unwrap_or_default();
+                let start = start
+                    .as_ref()
+                    .map(|start| {
+                        format!("{}:{}", start.index, start.validator.encode())
+                    })
+                    .unwrap_or_default();
+                write!(
+                    f,
+                    "{}/{}/{}/{}/{}/{}",
+                    SLASHES_PREFIX,
+                    limit,
+                    start,
+                    encode_epoch(from_epoch),
+                    encode_epoch(to_epoch),
+                    validator
+                )
+            }
+            Path::TotalSupply(token) => {
+                write!(f, "{}/{}", TOTAL_SUPPLY_PREFIX, token)
+            }
+            Path::TxsByAddress(address) => {
+                write!(f, "{}/{}", TXS_BY_ADDRESS_PREFIX, address)
+            }
+            Path::TxsByToken(token) => {
+                write!(f, "{}/{}", TXS_BY_TOKEN_PREFIX, token)
+            }
+            Path::TxsBySender(sender) => {
+                write!(f, "{}/{}", TXS_BY_SENDER_PREFIX, sender)
+            }
+            Path::TransferHistory(owner) => {
+                write!(f, "{}/{}", TRANSFER_HISTORY_PREFIX, owner)
+            }
+            Path::OwnerBalances(owner) => {
+                write!(f, "{}/{}", OWNER_BALANCES_PREFIX, owner)
+            }
+            Path::BlockResults(height) => {
+                write!(f, "{}/{}", BLOCK_RESULTS_PREFIX, height)
+            }
+            Path::TxEvent(hash) => {
+                write!(f, "{}/{}", TX_EVENT_PREFIX, hash)
+            }
         }
     }
 }
@@ -65,6 +436,17 @@ impl FromStr for Path {
         match s {
             DRY_RUN_TX_PATH => Ok(Self::DryRunTx),
             EPOCH_PATH => Ok(Self::Epoch),
+            EPOCH_INFO_PATH => Ok(Self::EpochInfo),
+            BLOCK_GAS_PATH => Ok(Self::BlockGas),
+            UPGRADE_PATH => Ok(Self::Upgrade),
+            MEMPOOL_PATH => Ok(Self::Mempool),
+            RECENT_BLOCKS_PATH => Ok(Self::RecentBlocks),
+            STATUS_PATH => Ok(Self::Status),
+            VALUES_PREFIX => Ok(Self::Values(vec![])),
+            PROOF_SPECS_PATH => Ok(Self::ProofSpecs),
+            VALIDATOR_SET_PATH => Ok(Self::ValidatorSet(None)),
+            POS_PARAMS_PATH => Ok(Self::PosParams),
+            INFLATION_PATH => Ok(Self::Inflation),
             _ => match s.split_once('/') {
                 Some((VALUE_PREFIX, storage_key)) => {
                     let key = storage::Key::parse(storage_key)
@@ -76,17 +458,259 @@ impl FromStr for Path {
                         .map_err(PathParseError::InvalidStorageKey)?;
                     Ok(Self::Prefix(key))
                 }
+                Some((VALUES_PREFIX, keys)) => {
+                    let keys = keys
+                        .split('/')
+                        .map(decode_hex_key)
+                        .collect::<Result<_, _>>()?;
+                    Ok(Self::Values(keys))
+                }
+                Some((PREFIX_PAGE_PREFIX, page)) => {
+                    let mut segments = page.splitn(3, '/');
+                    let (limit, after, prefix) = match (
+                        segments.next(),
+                        segments.next(),
+                        segments.next(),
+                    ) {
+                        (Some(limit), Some(after), Some(prefix)) => {
+                            (limit, after, prefix)
+                        }
+                        _ => {
+                            return Err(PathParseError::InvalidPath(
+                                s.to_string(),
+                            ));
+                        }
+                    };
+                    let limit = u64::from_str(limit)
+                        .map_err(PathParseError::InvalidPageLimit)?;
+                    let after = if after.is_empty() {
+                        None
+                    } else {
+                        Some(decode_hex_key(after)?)
+                    };
+                    let prefix = storage::Key::parse(prefix)
+                        .map_err(PathParseError::InvalidStorageKey)?;
+                    Ok(Self::PrefixPage {
+                        prefix,
+                        after,
+                        limit,
+                    })
+                }
                 Some((HAS_KEY_PREFIX, storage_key)) => {
                     let key = storage::Key::parse(storage_key)
                         .map_err(PathParseError::InvalidStorageKey)?;
                     Ok(Self::HasKey(key))
                 }
+                Some((CHANGED_KEYS_PREFIX, range)) => {
+                    let mut segments = range.splitn(3, '/');
+                    let (from, to, prefix) = match (
+                        segments.next(),
+                        segments.next(),
+                        segments.next(),
+                    ) {
+                        (Some(from), Some(to), Some(prefix)) => {
+                            (from, to, prefix)
+                        }
+                        _ => {
+                            return Err(PathParseError::InvalidPath(
+                                s.to_string(),
+                            ));
+                        }
+                    };
+                    let parse_height = |height: &str| {
+                        storage::BlockHeight::from_str(height)
+                            .map_err(PathParseError::InvalidHeight)
+                    };
+                    let prefix = if prefix.is_empty() {
+                        None
+                    } else {
+                        Some(
+                            storage::Key::parse(prefix)
+                                .map_err(PathParseError::InvalidStorageKey)?,
+                        )
+                    };
+                    Ok(Self::ChangedKeys {
+                        from: parse_height(from)?,
+                        to: parse_height(to)?,
+                        prefix,
+                    })
+                }
+                Some((ACCOUNT_PREFIX, address)) => {
+                    let address = Address::decode(address)
+                        .map_err(PathParseError::InvalidAddress)?;
+                    Ok(Self::Account(address))
+                }
+                Some((STORAGE_USAGE_PREFIX, page)) => {
+                    let (after, address) =
+                        page.split_once('/').ok_or_else(|| {
+                            PathParseError::InvalidPath(s.to_string())
+                        })?;
+                    let after = if after.is_empty() {
+                        None
+                    } else {
+                        Some(decode_hex_key(after)?)
+                    };
+                    let address = Address::decode(address)
+                        .map_err(PathParseError::InvalidAddress)?;
+                    Ok(Self::StorageUsage { address, after })
+                }
+                Some((prefix @ (BONDS_PREFIX | REWARDS_PREFIX), addresses)) => {
+                    let (owner, validator) =
+                        addresses.split_once('/').ok_or_else(|| {
+                            PathParseError::InvalidPath(s.to_string())
+                        })?;
+                    let decode = |address: &str| {
+                        if address.is_empty() {
+                            Ok(None)
+                        } else {
+                            Address::decode(address)
+                                .map(Some)
+                                .map_err(PathParseError::InvalidAddress)
+                        }
+                    };
+                    let owner = decode(owner)?;
+                    let validator = decode(validator)?;
+                    if prefix == BONDS_PREFIX {
+                        Ok(Self::Bonds { owner, validator })
+                    } else {
+                        Ok(Self::Rewards { owner, validator })
+                    }
+                }
+                Some((SLASHES_PREFIX, page)) => {
+                    let mut segments = page.split('/');
+                    let (limit, start, from, to, validator) = match (
+                        segments.next(),
+                        segments.next(),
+                        segments.next(),
+                        segments.next(),
+                        segments.next(),
+                        segments.next(),
+                    ) {
+                        (
+                            Some(limit),
+                            Some(start),
+                            Some(from),
+                            Some(to),
+                            Some(validator),
+                            None,
+                        ) => (limit, start, from, to, validator),
+                        _ => {
+                            return Err(PathParseError::InvalidPath(
+                                s.to_string(),
+                            ));
+                        }
+                    };
+                    let limit = u64::from_str(limit)
+                        .map_err(PathParseError::InvalidPageLimit)?;
+                    let start = if start.is_empty() {
+                        None
+                    } else {
+                        let (index, validator) =
+                            start.split_once(':').ok_or_else(|| {
+                                PathParseError::InvalidPath(s.to_string())
+                            })?;
+                        Some(SlashesCursor {
+                            validator: Address::decode(validator)
+                                .map_err(PathParseError::InvalidAddress)?,
+                            index: u64::from_str(index)
+                                .map_err(PathParseError::InvalidPageOffset)?,
+                        })
+                    };
+                    let parse_epoch = |epoch: &str| {
+                        if epoch.is_empty() {
+                            Ok(None)
+                        } else {
+                            u64::from_str(epoch)
+                                .map(|epoch| Some(storage::Epoch(epoch)))
+                                .map_err(PathParseError::InvalidEpoch)
+                        }
+                    };
+                    let validator = if validator.is_empty() {
+                        None
+                    } else {
+                        Some(
+                            Address::decode(validator)
+                                .map_err(PathParseError::InvalidAddress)?,
+                        )
+                    };
+                    Ok(Self::Slashes {
+                        validator,
+                        from_epoch: parse_epoch(from)?,
+                        to_epoch: parse_epoch(to)?,
+                        start,
+                        limit,
+                    })
+                }
+                Some((VALIDATOR_SET_PATH, epoch)) => {
+                    let epoch = u64::from_str(epoch)
+                        .map_err(PathParseError::InvalidEpoch)?;
+                    Ok(Self::ValidatorSet(Some(storage::Epoch(epoch))))
+                }
+                Some((VALIDATOR_SET_AT_PREFIX, epoch)) => {
+                    let epoch = u64::from_str(epoch)
+                        .map_err(PathParseError::InvalidEpoch)?;
+                    Ok(Self::ValidatorSetAt(storage::Epoch(epoch)))
+                }
+                Some((TXS_BY_ADDRESS_PREFIX, address)) => {
+                    let address = Address::decode(address)
+                        .map_err(PathParseError::InvalidAddress)?;
+                    Ok(Self::TxsByAddress(address))
+                }
+                Some((TXS_BY_TOKEN_PREFIX, token)) => {
+                    let token = Address::decode(token)
+                        .map_err(PathParseError::InvalidAddress)?;
+                    Ok(Self::TxsByToken(token))
+                }
+                Some((TXS_BY_SENDER_PREFIX, sender)) => {
+                    let sender = Address::decode(sender)
+                        .map_err(PathParseError::InvalidAddress)?;
+                    Ok(Self::TxsBySender(sender))
+                }
+                Some((TOTAL_SUPPLY_PREFIX, token)) => {
+                    let token = Address::decode(token)
+                        .map_err(PathParseError::InvalidAddress)?;
+                    Ok(Self::TotalSupply(token))
+                }
+                Some((TRANSFER_HISTORY_PREFIX, owner)) => {
+                    let owner = Address::decode(owner)
+                        .map_err(PathParseError::InvalidAddress)?;
+                    Ok(Self::TransferHistory(owner))
+                }
+                Some((OWNER_BALANCES_PREFIX, owner)) => {
+                    let owner = Address::decode(owner)
+                        .map_err(PathParseError::InvalidAddress)?;
+                    Ok(Self::OwnerBalances(owner))
+                }
+                Some((BLOCK_RESULTS_PREFIX, height)) => {
+                    let height = storage::BlockHeight::from_str(height)
+                        .map_err(PathParseError::InvalidHeight)?;
+                    Ok(Self::BlockResults(height))
+                }
+                Some((TX_EVENT_PREFIX, hash)) => {
+                    let hash = Hash::from_str(hash)
+                        .map_err(PathParseError::InvalidHash)?;
+                    Ok(Self::TxEvent(hash))
+                }
                 _ => Err(PathParseError::InvalidPath(s.to_string())),
             },
         }
     }
 }
 
+/// Encode a storage key as hex, so that it can be a single segment of a path
+fn encode_hex_key(key: &storage::Key) -> String {
+    hex::encode(key.to_string())
+}
+
+/// Decode a storage key encoded with [`encode_hex_key`]
+fn decode_hex_key(segment: &str) -> Result<storage::Key, PathParseError> {
+    let key = hex::decode(segment)
+        .ok()
+        .and_then(|key| String::from_utf8(key).ok())
+        .ok_or_else(|| PathParseError::InvalidHexKey(segment.to_string()))?;
+    storage::Key::parse(key).map_err(PathParseError::InvalidStorageKey)
+}
+
 impl From<Path> for AbciPath {
     fn from(path: Path) -> Self {
         let path = path.to_string();
@@ -103,4 +727,355 @@ pub enum PathParseError {
     InvalidPath(String),
     #[error("Invalid storage key: {0}")]
     InvalidStorageKey(storage::Error),
+    #[error("Invalid address: {0}")]
+    InvalidAddress(address::Error),
+    #[error("Invalid block height: {0}")]
+    InvalidHeight(ParseIntError),
+    #[error("Invalid epoch: {0}")]
+    InvalidEpoch(ParseIntError),
+    #[error("Invalid tx hash: {0}")]
+    InvalidHash(hash::Error),
+    #[error("Invalid limit of a page: {0}")]
+    InvalidPageLimit(ParseIntError),
+    #[error("Invalid offset of a page: {0}")]
+    InvalidPageOffset(ParseIntError),
+    #[error("Invalid hex encoded storage key: {0}")]
+    InvalidHexKey(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_name() {
+        assert_eq!(path_name("dry_run_tx"), DRY_RUN_TX_PATH);
+        assert_eq!(path_name("prefix/#a/b"), PREFIX_PREFIX);
+        // The codes of the queries' own errors aren't rejections
+        for code in [0, 1, 2] {
+            assert_eq!(QueryRejection::from_code(code), None);
+        }
+        for rejection in
+            [QueryRejection::RateLimited, QueryRejection::PathNotAllowed]
+        {
+            assert_eq!(
+                QueryRejection::from_code(rejection.code()),
+                Some(rejection)
+            );
+        }
+    }
+
+    /// Test that the paginated and batched query paths are variants of the
+    /// paths of the queries that they read.
+    #[test]
+    fn test_variant_base_paths() {
+        let prefix = storage::Key::parse("a/b").unwrap();
+        let page = Path::PrefixPage {
+            prefix: prefix.clone(),
+            after: None,
+            limit: 10,
+        }
+        .to_string();
+        assert_eq!(
+            variant_base_paths(&page),
+            vec![Path::Prefix(prefix.clone()).to_string()]
+        );
+        let key = storage::Key::parse("c/d").unwrap();
+        let values =
+            Path::Values(vec![prefix.clone(), key.clone()]).to_string();
+        assert_eq!(
+            variant_base_paths(&values),
+            vec![
+                Path::Value(prefix.clone()).to_string(),
+                Path::Value(key).to_string()
+            ]
+        );
+        assert!(
+            variant_base_paths(&Path::Prefix(prefix).to_string()).is_empty()
+        );
+        assert!(variant_base_paths(EPOCH_PATH).is_empty());
+    }
+
+    /// Test that a prefix page path is parsed from its string, with or
+    /// without the key after which the page starts.
+    #[test]
+    fn test_prefix_page_path() {
+        let prefix = storage::Key::parse("a/b").unwrap();
+        let after = storage::Key::parse("a/b/c/d").unwrap();
+        for after in [None, Some(after)] {
+            let path = Path::PrefixPage {
+                prefix: prefix.clone(),
+                after: after.clone(),
+                limit: 10,
+            }
+            .to_string();
+            assert_eq!(path_name(&path), PREFIX_PAGE_PREFIX);
+            assert!(matches!(
+                Path::from_str(&path),
+                Ok(Path::PrefixPage {
+                    prefix: parsed_prefix,
+                    after: parsed_after,
+                    limit: 10,
+                }) if parsed_prefix == prefix && parsed_after == after
+            ));
+        }
+        assert!(matches!(
+            Path::from_str("prefix_page/10/zz/a/b"),
+            Err(PathParseError::InvalidHexKey(_))
+        ));
+        assert!(matches!(
+            Path::from_str("prefix_page/ten//a/b"),
+            Err(PathParseError::InvalidPageLimit(_))
+        ));
+    }
+
+    /// Test that a storage usage path is parsed from its string, with or
+    /// without the key after which the page starts.
+    #[test]
+    fn test_storage_usage_path() {
+        let address = address::testing::established_address_1();
+        let after = storage::Key::validity_predicate(&address);
+        for after in [None, Some(after)] {
+            let path = Path::StorageUsage {
+                address: address.clone(),
+                after: after.clone(),
+            }
+            .to_string();
+            assert_eq!(path_name(&path), STORAGE_USAGE_PREFIX);
+            assert!(matches!(
+                Path::from_str(&path),
+                Ok(Path::StorageUsage {
+                    address: parsed_address,
+                    after: parsed_after,
+                }) if parsed_address == address && parsed_after == after
+            ));
+        }
+    }
+
+    /// Test that a slashes path is parsed from its string with or without the
+    /// validator, the epochs of the range and the start of the page.
+    #[test]
+    fn test_slashes_path() {
+        let validator = address::testing::established_address_1();
+        let start = SlashesCursor {
+            validator: address::testing::established_address_2(),
+            index: 20,
+        };
+        for (validator, from_epoch, to_epoch, start) in [
+            (None, None, None, None),
+            (Some(validator), Some(storage::Epoch(2)), None, Some(start)),
+            (None, None, Some(storage::Epoch(5)), None),
+        ] {
+            let path = Path::Slashes {
+                validator: validator.clone(),
+                from_epoch,
+                to_epoch,
+                start: start.clone(),
+                limit: 10,
+            }
+            .to_string();
+            assert_eq!(path_name(&path), SLASHES_PREFIX);
+            assert!(matches!(
+                Path::from_str(&path),
+                Ok(Path::Slashes {
+                    validator: parsed_validator,
+                    from_epoch: parsed_from_epoch,
+                    to_epoch: parsed_to_epoch,
+                    start: parsed_start,
+                    limit: 10,
+                }) if parsed_validator == validator
+                    && parsed_from_epoch == from_epoch
+                    && parsed_to_epoch == to_epoch
+                    && parsed_start == start
+            ));
+        }
+        let path = format!(
+            "slashes/10/twenty:{}///",
+            address::testing::established_address_2().encode()
+        );
+        assert!(matches!(
+            Path::from_str(&path),
+            Err(PathParseError::InvalidPageOffset(_))
+        ));
+        assert!(matches!(
+            Path::from_str("slashes/10/20///"),
+            Err(PathParseError::InvalidPath(_))
+        ));
+        assert!(matches!(
+            Path::from_str("slashes/10//two//"),
+            Err(PathParseError::InvalidEpoch(_))
+        ));
+        assert!(matches!(
+            Path::from_str("slashes/10/0"),
+            Err(PathParseError::InvalidPath(_))
+        ));
+    }
+
+    /// Test that a changed keys path is parsed from its string, with or
+    /// without a prefix.
+    #[test]
+    fn test_changed_keys_path() {
+        let prefix = storage::Key::parse("a/b").unwrap();
+        for prefix in [None, Some(prefix)] {
+            let path = Path::ChangedKeys {
+                from: storage::BlockHeight(1),
+                to: storage::BlockHeight(5),
+                prefix: prefix.clone(),
+            }
+            .to_string();
+            assert_eq!(path_name(&path), CHANGED_KEYS_PREFIX);
+            assert!(matches!(
+                Path::from_str(&path),
+                Ok(Path::ChangedKeys {
+                    from: storage::BlockHeight(1),
+                    to: storage::BlockHeight(5),
+                    prefix: parsed_prefix,
+                }) if parsed_prefix == prefix
+            ));
+        }
+        assert!(matches!(
+            Path::from_str("changed_keys/1/five/a"),
+            Err(PathParseError::InvalidHeight(_))
+        ));
+        assert!(matches!(
+            Path::from_str("changed_keys/1"),
+            Err(PathParseError::InvalidPath(_))
+        ));
+    }
+
+    /// Test that a path of multiple keys is parsed from its string with the
+    /// keys in the same order.
+    #[test]
+    fn test_values_path() {
+        let keys = vec![
+            storage::Key::parse("a/b").unwrap(),
+            storage::Key::parse("c").unwrap(),
+        ];
+        for keys in [vec![], keys] {
+            let path = Path::Values(keys.clone()).to_string();
+            assert_eq!(path_name(&path), VALUES_PREFIX);
+            assert!(matches!(
+                Path::from_str(&path),
+                Ok(Path::Values(parsed)) if parsed == keys
+            ));
+        }
+        assert!(matches!(
+            Path::from_str("values/zz"),
+            Err(PathParseError::InvalidHexKey(_))
+        ));
+    }
+
+    /// Test that a bonds path is parsed from its string with or without the
+    /// owner and the validator.
+    #[test]
+    fn test_bonds_path() {
+        let owner = address::testing::established_address_1();
+        let validator = address::testing::established_address_2();
+        for (owner, validator) in [
+            (None, None),
+            (Some(owner.clone()), None),
+            (None, Some(validator.clone())),
+            (Some(owner), Some(validator)),
+        ] {
+            let path = Path::Bonds {
+                owner: owner.clone(),
+                validator: validator.clone(),
+            }
+            .to_string();
+            assert_eq!(path_name(&path), BONDS_PREFIX);
+            assert!(matches!(
+                Path::from_str(&path),
+                Ok(Path::Bonds {
+                    owner: parsed_owner,
+                    validator: parsed_validator,
+                }) if parsed_owner == owner && parsed_validator == validator
+            ));
+        }
+        assert!(matches!(
+            Path::from_str("bonds/abc/"),
+            Err(PathParseError::InvalidAddress(_))
+        ));
+        assert!(matches!(
+            Path::from_str("bonds/"),
+            Err(PathParseError::InvalidPath(_))
+        ));
+    }
+
+    /// Test that a rewards path is parsed from its string with or without the
+    /// owner and the validator.
+    #[test]
+    fn test_rewards_path() {
+        let owner = address::testing::established_address_1();
+        for validator in [None, Some(address::testing::established_address_2())]
+        {
+            let path = Path::Rewards {
+                owner: Some(owner.clone()),
+                validator: validator.clone(),
+            }
+            .to_string();
+            assert_eq!(path_name(&path), REWARDS_PREFIX);
+            assert!(matches!(
+                Path::from_str(&path),
+                Ok(Path::Rewards {
+                    owner: Some(parsed_owner),
+                    validator: parsed_validator,
+                }) if parsed_owner == owner && parsed_validator == validator
+            ));
+        }
+        assert!(matches!(
+            Path::from_str("rewards/"),
+            Err(PathParseError::InvalidPath(_))
+        ));
+    }
+
+    /// Test that a validator set path is parsed from its string with or
+    /// without the epoch and a validator set at path with its epoch.
+    #[test]
+    fn test_validator_set_path() {
+        for epoch in [None, Some(storage::Epoch(3))] {
+            let path = Path::ValidatorSet(epoch).to_string();
+            assert_eq!(path_name(&path), VALIDATOR_SET_PATH);
+            assert!(matches!(
+                Path::from_str(&path),
+                Ok(Path::ValidatorSet(parsed)) if parsed == epoch
+            ));
+        }
+        assert!(matches!(
+            Path::from_str("validator_set/three"),
+            Err(PathParseError::InvalidEpoch(_))
+        ));
+
+        let path = Path::ValidatorSetAt(storage::Epoch(3)).to_string();
+        assert_eq!(path_name(&path), VALIDATOR_SET_AT_PREFIX);
+        assert!(matches!(
+            Path::from_str(&path),
+            Ok(Path::ValidatorSetAt(storage::Epoch(3)))
+        ));
+        assert!(matches!(
+            Path::from_str("validator_set_at"),
+            Err(PathParseError::InvalidPath(_))
+        ));
+    }
+
+    /// Test that a tx event path is parsed from its string with the hash of
+    /// the tx, like it's found in the tx events.
+    #[test]
+    fn test_tx_event_path() {
+        let hash = Hash::sha256(b"tx");
+        let path = Path::TxEvent(hash.clone()).to_string();
+        assert_eq!(path_name(&path), TX_EVENT_PREFIX);
+        assert!(matches!(
+            Path::from_str(&path),
+            Ok(Path::TxEvent(parsed)) if parsed == hash
+        ));
+        let lowercase = format!("{}/{}", TX_EVENT_PREFIX, hex::encode(&*hash));
+        assert!(matches!(
+            Path::from_str(&lowercase),
+            Ok(Path::TxEvent(parsed)) if parsed == hash
+        ));
+        assert!(matches!(
+            Path::from_str("tx_event/abc"),
+            Err(PathParseError::InvalidHash(_))
+        ));
+    }
 }
@@ -0,0 +1,310 @@
+//! Handling of the evidence of byzantine validators, which is received from
+//! Tendermint with the `BeginBlock` (ABCI) or `FinalizeBlock` (ABCI++)
+//! request. The validator of every valid evidence is slashed in PoS and a
+//! slash event is emitted with the block.
+
+use anoma::ledger::light_client;
+use anoma::ledger::pos::types::SlashType;
+use anoma::ledger::pos::PosParams;
+use anoma::types::address::Address;
+use anoma::types::storage::Epoch;
+
+use super::*;
+use crate::node::ledger::events::{Event, EventLevel, EventType};
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum EvidenceError {
+    #[error("Invalid evidence block height {0}")]
+    InvalidHeight(i64),
+    #[error("Couldn't find the epoch of the evidence block height {0}")]
+    UnknownEpoch(BlockHeight),
+    #[error(
+        "The evidence from epoch {evidence_epoch} expired in epoch \
+         {current_epoch}"
+    )]
+    Expired {
+        evidence_epoch: Epoch,
+        current_epoch: Epoch,
+    },
+    #[error("Unknown evidence type {0}")]
+    UnknownType(i32),
+    #[error("The evidence has no validator")]
+    MissingValidator,
+    #[error(
+        "No validator with the Tendermint address {tm_address} in epoch \
+         {epoch}"
+    )]
+    UnknownValidator { tm_address: String, epoch: Epoch },
+}
+
+/// The number of blocks after which Tendermint applies the validator set
+/// updates returned with a block
+const VALIDATOR_UPDATES_DELAY: u64 = 2;
+
+/// A valid evidence of a misbehavior of a PoS validator
+#[derive(Clone, Debug)]
+pub struct ValidEvidence {
+    /// The address of the PoS validator
+    pub validator: Address,
+    /// The Tendermint address of the validator, from its consensus key
+    pub tm_address: String,
+    /// The epoch of the misbehavior
+    pub epoch: Epoch,
+    /// The block height of the misbehavior
+    pub height: BlockHeight,
+    /// The type of the misbehavior
+    pub slash_type: SlashType,
+}
+
+impl<D, H> Shell<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    /// Apply PoS slashes from the evidence received with the block at the
+    /// given height. Returns the events of the applied slashes. An invalid
+    /// evidence is only logged, because the block has been decided already.
+    pub(super) fn slash(&mut self, height: BlockHeight) -> Vec<Event> {
+        let byzantine_validators = mem::take(&mut self.byzantine_validators);
+        if byzantine_validators.is_empty() {
+            return vec![];
+        }
+        let pos_params = self.storage.read_pos_params();
+        let current_epoch = self.storage.block.epoch;
+        let mut events = vec![];
+        for evidence in byzantine_validators {
+            let evidence = match self.validate_evidence(&evidence, &pos_params)
+            {
+                Ok(evidence) => evidence,
+                Err(err) => {
+                    tracing::error!(
+                        "Ignoring invalid evidence {:#?}: {}",
+                        evidence,
+                        err
+                    );
+                    continue;
+                }
+            };
+            tracing::info!(
+                "Slashing {} for {} in epoch {}, block height {}",
+                evidence.validator,
+                evidence.slash_type,
+                evidence.epoch,
+                evidence.height
+            );
+            match self.storage.slash(
+                &pos_params,
+                current_epoch,
+                evidence.epoch,
+                evidence.height.0,
+                evidence.slash_type.clone(),
+                &evidence.validator,
+            ) {
+                Ok(()) => {
+                    events.push(slash_event(&evidence, &pos_params, height))
+                }
+                Err(err) => tracing::error!("Error in slashing: {}", err),
+            }
+        }
+        events
+    }
+
+    /// Validate an evidence received from Tendermint. The evidence must be
+    /// for a known type of misbehavior of a validator that was in the
+    /// validator set of the evidence epoch, and the epoch must be within the
+    /// unbonding length of the current epoch, after which the bonds of the
+    /// validator may have been withdrawn.
+    pub(super) fn validate_evidence(
+        &self,
+        evidence: &Evidence,
+        pos_params: &PosParams,
+    ) -> std::result::Result<ValidEvidence, EvidenceError> {
+        let height = u64::try_from(evidence.height)
+            .map(BlockHeight)
+            .map_err(|_| EvidenceError::InvalidHeight(evidence.height))?;
+        let epoch = self
+            .storage
+            .block
+            .pred_epochs
+            .get_epoch(height)
+            .ok_or(EvidenceError::UnknownEpoch(height))?;
+        let current_epoch = self.storage.block.epoch;
+        if epoch.0 + pos_params.unbonding_len <= current_epoch.0 {
+            return Err(EvidenceError::Expired {
+                evidence_epoch: epoch,
+                current_epoch,
+            });
+        }
+        let slash_type = match EvidenceType::from_i32(evidence.r#type) {
+            Some(EvidenceType::DuplicateVote) => SlashType::DuplicateVote,
+            Some(EvidenceType::LightClientAttack) => {
+                SlashType::LightClientAttack
+            }
+            Some(EvidenceType::Unknown) | None => {
+                return Err(EvidenceError::UnknownType(evidence.r#type));
+            }
+        };
+        let tm_address = evidence
+            .validator
+            .as_ref()
+            .map(|validator| hex::encode_upper(&validator.address))
+            .ok_or(EvidenceError::MissingValidator)?;
+        let validator = self
+            .find_validator_by_tm_address(height, epoch, &tm_address)
+            .ok_or_else(|| EvidenceError::UnknownValidator {
+                tm_address: tm_address.clone(),
+                epoch,
+            })?;
+        Ok(ValidEvidence {
+            validator,
+            tm_address,
+            epoch,
+            height,
+            slash_type,
+        })
+    }
+}
+
+impl<D, H> Shell<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    /// Find the validator whose consensus key has the given Tendermint
+    /// address in the epoch of the block at the given height. The validator
+    /// set updates of an epoch are only applied by Tendermint after the first
+    /// blocks of the epoch, which are still signed with the consensus keys of
+    /// the previous epoch, so for these blocks a validator that changed its
+    /// consensus key is also found by its previous key.
+    pub(super) fn find_validator_by_tm_address(
+        &self,
+        height: BlockHeight,
+        epoch: Epoch,
+        tm_address: &str,
+    ) -> Option<Address> {
+        let is_tm_address = |consensus_key: &common::PublicKey| {
+            light_client::tm_address_of(consensus_key) == tm_address
+        };
+        self.storage
+            .find_validator_by_consensus_key(epoch, is_tm_address)
+            .or_else(|| {
+                let start_height =
+                    self.storage.block.pred_epochs.get_start_height(epoch)?;
+                if height.0 >= start_height.0 + VALIDATOR_UPDATES_DELAY {
+                    return None;
+                }
+                let prev_epoch = Epoch(epoch.0.checked_sub(1)?);
+                self.storage
+                    .find_validator_by_consensus_key(prev_epoch, is_tm_address)
+            })
+    }
+}
+
+/// The event of a slash applied for the evidence in the block at the given
+/// height
+pub(super) fn slash_event(
+    evidence: &ValidEvidence,
+    pos_params: &PosParams,
+    height: BlockHeight,
+) -> Event {
+    let mut event = Event {
+        event_type: EventType::Slash,
+        level: EventLevel::Block,
+        attributes: Default::default(),
+    };
+    event["validator"] = evidence.validator.encode();
+    event["tm_address"] = evidence.tm_address.clone();
+    event["type"] = evidence.slash_type.to_string();
+    event["rate"] = evidence.slash_type.get_slash_rate(pos_params).to_string();
+    event["evidence_epoch"] = evidence.epoch.to_string();
+    event["evidence_height"] = evidence.height.to_string();
+    event["height"] = height.to_string();
+    event
+}
+
+#[cfg(test)]
+mod test_evidence {
+    use tendermint_compat::tendermint_proto::abci::Validator;
+
+    use super::*;
+    use crate::node::ledger::shell::test_utils::*;
+    use crate::wallet;
+
+    /// An evidence of a duplicate vote at the height 1 of the validator with
+    /// the given consensus key
+    fn duplicate_vote(consensus_key: &common::PublicKey) -> Evidence {
+        Evidence {
+            r#type: EvidenceType::DuplicateVote as i32,
+            validator: Some(Validator {
+                address: hex::decode(light_client::tm_address_of(
+                    consensus_key,
+                ))
+                .unwrap(),
+                power: 100,
+            }),
+            height: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Test that an evidence is only valid for a known validator, within the
+    /// unbonding length of the evidence epoch.
+    #[test]
+    fn test_validate_evidence() {
+        let (mut shell, _) = setup();
+        let pos_params = shell.storage.read_pos_params();
+        let consensus_key = wallet::defaults::validator_keypair().ref_to();
+        let evidence = duplicate_vote(&consensus_key);
+
+        let valid = shell.validate_evidence(&evidence, &pos_params).unwrap();
+        assert_eq!(valid.validator, wallet::defaults::validator_address());
+        assert_eq!(valid.epoch, Epoch(0));
+        assert_eq!(valid.height, BlockHeight(1));
+        assert!(matches!(valid.slash_type, SlashType::DuplicateVote));
+
+        // The evidence of a key that's not in the validator set
+        let unknown = duplicate_vote(&gen_keypair().ref_to());
+        assert!(matches!(
+            shell.validate_evidence(&unknown, &pos_params),
+            Err(EvidenceError::UnknownValidator { .. })
+        ));
+
+        let unknown_type = Evidence {
+            r#type: EvidenceType::Unknown as i32,
+            ..evidence.clone()
+        };
+        assert!(matches!(
+            shell.validate_evidence(&unknown_type, &pos_params),
+            Err(EvidenceError::UnknownType(_))
+        ));
+
+        shell.storage.block.epoch = Epoch(pos_params.unbonding_len);
+        assert!(matches!(
+            shell.validate_evidence(&evidence, &pos_params),
+            Err(EvidenceError::Expired { .. })
+        ));
+    }
+
+    /// Test that the validator of a valid evidence is slashed with an
+    /// event and that an invalid evidence is ignored.
+    #[test]
+    fn test_slash() {
+        let (mut shell, _) = setup();
+        let consensus_key = wallet::defaults::validator_keypair().ref_to();
+        shell.byzantine_validators = vec![
+            duplicate_vote(&consensus_key),
+            duplicate_vote(&gen_keypair().ref_to()),
+        ];
+
+        let events = shell.slash(BlockHeight(2));
+        assert_eq!(events.len(), 1);
+        let validator = wallet::defaults::validator_address();
+        assert_eq!(events[0]["validator"], validator.encode());
+        assert_eq!(events[0]["evidence_height"], "1");
+        assert_eq!(events[0]["height"], "2");
+        let slashes = shell.storage.read_validator_slashes(&validator);
+        assert_eq!(slashes.len(), 1);
+        assert!(shell.byzantine_validators.is_empty());
+    }
+}
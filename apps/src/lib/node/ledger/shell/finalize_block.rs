@@ -1,26 +1,29 @@
 //! Implementation of the `FinalizeBlock` ABCI++ method for the Shell
 
+use std::collections::{BTreeMap, BTreeSet};
+
+use anoma::ledger::gas;
 use anoma::ledger::governance::storage as gov_storage;
 use anoma::ledger::governance::utils::{
     compute_tally, get_proposal_votes, ProposalEvent,
 };
 use anoma::ledger::governance::vp::ADDRESS as gov_address;
 use anoma::ledger::storage::types::encode;
+use anoma::ledger::storage::write_log::StorageModification;
 use anoma::ledger::treasury::ADDRESS as treasury_address;
 use anoma::types::address::{xan as m1t, Address};
 use anoma::types::governance::TallyResult;
+use anoma::types::indexer::TokenMovement;
 use anoma::types::storage::{BlockHash, Epoch, Header};
-#[cfg(not(feature = "ABCI"))]
-use tendermint_proto::abci::Misbehavior as Evidence;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_proto::crypto::PublicKey as TendermintPublicKey;
 #[cfg(feature = "ABCI")]
-use tendermint_proto_abci::abci::Evidence;
-#[cfg(feature = "ABCI")]
-use tendermint_proto_abci::crypto::PublicKey as TendermintPublicKey;
+use tendermint_compat::tendermint_proto::abci::Evidence;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_compat::tendermint_proto::abci::Misbehavior as Evidence;
+use tendermint_compat::tendermint_proto::crypto::PublicKey as TendermintPublicKey;
 
 use super::*;
 use crate::node::ledger::events::EventType;
+use crate::node::ledger::node_status::BlockStats;
 
 impl<D, H> Shell<D, H>
 where
@@ -47,14 +50,61 @@ where
     ///   3: Wasm runtime error
     ///   4: Invalid order of decrypted txs
     ///   5. More decrypted txs than expected
+    ///   6. The payload of the tx could not be decrypted. The fee of its
+    ///      wrapper is charged, but nothing else is applied.
     pub fn finalize_block(
         &mut self,
         req: shim::request::FinalizeBlock,
     ) -> Result<shim::response::FinalizeBlock> {
         let mut response = shim::response::FinalizeBlock::default();
+        // persist the request with the block, so that it can be re-executed
+        self.storage.block_request = Some(req.encode());
         // begin the next block and check if a new epoch began
-        let (height, new_epoch) =
-            self.update_state(req.header, req.hash, req.byzantine_validators);
+        let (height, new_epoch) = self.update_state(
+            req.header,
+            req.hash,
+            req.byzantine_validators,
+            req.votes,
+        );
+        // Check the PoS invariants of the state committed in the last epoch
+        #[cfg(feature = "pos-invariants")]
+        if new_epoch {
+            self.check_pos_invariants()?;
+        }
+        // Apply the protocol upgrade scheduled at this height, if any, or halt
+        // if this node doesn't run the upgraded protocol version
+        self.upgrade_protocol(height, &mut response)
+            .map_err(Error::Upgrade)?;
+        // Keep the validator set of the new epoch for the queries of the past
+        // epochs and the verification of the evidence
+        if new_epoch {
+            let pos_params = self.storage.read_pos_params();
+            let current_epoch = self.storage.block.epoch;
+            self.storage
+                .record_validator_set_snapshot(&pos_params, current_epoch);
+            // Drop the rewards that all the bonds have already claimed
+            pos::prune_validator_rewards(&mut self.storage, current_epoch)
+                .map_err(Error::Storage)?;
+            // Mint the inflation of the last epoch into the staking rewards,
+            // before its stakes are changed in the new epoch
+            self.storage
+                .mint_inflation_rewards(&pos_params, current_epoch);
+        }
+        // Slash the validators of the valid evidence
+        response.events.extend(self.slash(height));
+        // Record the validators' liveness from the last block's commit and at
+        // the end of an epoch, slash and jail the validators that missed to
+        // sign too many blocks
+        self.record_liveness(height);
+        if new_epoch {
+            response
+                .events
+                .extend(self.jail_downtime_validators(height));
+        }
+        // Accrue the rewards of this block to the active validators
+        let pos_params = self.storage.read_pos_params();
+        let current_epoch = self.storage.block.epoch;
+        self.storage.accrue_block_rewards(&pos_params, current_epoch);
 
         if new_epoch {
             for id in std::mem::take(&mut self.proposal_data) {
@@ -226,7 +276,11 @@ where
             }
         }
 
-        for processed_tx in &req.txs {
+        // The numbers of the wrapper and decrypted txs applied in the block
+        let mut wrapper_txs = 0;
+        let mut decrypted_txs = 0;
+        for (tx_index, processed_tx) in req.txs.iter().enumerate() {
+            let tx_index = tx_index as u32;
             let tx = if let Ok(tx) = Tx::try_from(processed_tx.tx.as_ref()) {
                 tx
             } else {
@@ -240,8 +294,8 @@ where
             let tx_length = processed_tx.tx.len();
             // If [`process_proposal`] rejected a Tx due to invalid signature,
             // emit an event here and move on to next tx.
-            if ErrorCodes::from_u32(processed_tx.result.code).unwrap()
-                == ErrorCodes::InvalidSig
+            if ResultCode::from_u32(processed_tx.result.code).unwrap()
+                == ResultCode::InvalidSig
             {
                 let mut tx_event = match process_tx(tx.clone()) {
                     Ok(tx @ TxType::Wrapper(_))
@@ -268,6 +322,14 @@ where
                 tx_event["info"] =
                     format!("Tx rejected: {}", &processed_tx.result.info);
                 tx_event["gas_used"] = "0".into();
+                self.index_tx(
+                    &tx_event,
+                    &[],
+                    height,
+                    tx_index,
+                    BTreeSet::new(),
+                    vec![],
+                );
                 response.events.push(tx_event);
                 continue;
             }
@@ -283,14 +345,22 @@ where
             };
             // If [`process_proposal`] rejected a Tx, emit an event here and
             // move on to next tx
-            if ErrorCodes::from_u32(processed_tx.result.code).unwrap()
-                != ErrorCodes::Ok
+            if ResultCode::from_u32(processed_tx.result.code).unwrap()
+                != ResultCode::Ok
             {
                 let mut tx_event = Event::new_tx_event(&tx_type, height.0);
                 tx_event["code"] = processed_tx.result.code.to_string();
                 tx_event["info"] =
                     format!("Tx rejected: {}", &processed_tx.result.info);
                 tx_event["gas_used"] = "0".into();
+                self.index_tx(
+                    &tx_event,
+                    &[],
+                    height,
+                    tx_index,
+                    BTreeSet::new(),
+                    vec![],
+                );
                 response.events.push(tx_event);
                 // if the rejected tx was decrypted, remove it
                 // from the queue of txs to be processed
@@ -305,18 +375,32 @@ where
                     if !cfg!(feature = "ABCI") {
                         self.storage.tx_queue.push(_wrapper.clone());
                     }
+                    wrapper_txs += 1;
                     Event::new_tx_event(&tx_type, height.0)
                 }
                 TxType::Decrypted(inner) => {
+                    decrypted_txs += 1;
                     // We remove the corresponding wrapper tx from the queue
-                    if !cfg!(feature = "ABCI") {
-                        self.storage.tx_queue.pop();
-                    }
+                    let wrapper = if !cfg!(feature = "ABCI") {
+                        self.storage.tx_queue.pop()
+                    } else {
+                        None
+                    };
                     let mut event = Event::new_tx_event(&tx_type, height.0);
-                    if let DecryptedTx::Undecryptable(_) = inner {
-                        event["log"] =
-                            "Transaction could not be decrypted.".into();
-                        event["code"] = ErrorCodes::Undecryptable.into();
+                    // The sender of a decrypted tx is the signer of its
+                    // wrapper
+                    if let Some(wrapper) = wrapper {
+                        event["sender"] = Address::from(&wrapper.pk).encode();
+                    }
+                    if let DecryptedTx::Undecryptable(wrapper) = inner {
+                        let fee = self.charge_undecryptable_fee(wrapper);
+                        event["log"] = format!(
+                            "Transaction could not be decrypted. The fee of \
+                             {} was charged and the payload was not applied.",
+                            fee
+                        );
+                        event["code"] = ResultCode::Undecryptable.into();
+                        event["fee_charged"] = fee.to_string();
                     }
                     event
                 }
@@ -336,6 +420,10 @@ where
                 }
             };
 
+            let mut addresses = BTreeSet::new();
+            let mut movements = vec![];
+            // The events emitted besides the tx event, e.g. an IBC event
+            let events_start = response.events.len();
             match protocol::apply_tx(
                 tx_type,
                 tx_length,
@@ -348,6 +436,16 @@ where
             .map_err(Error::TxApply)
             {
                 Ok(result) => {
+                    if self.indexer.is_some() {
+                        addresses = result
+                            .vps_result
+                            .accepted_vps
+                            .iter()
+                            .chain(&result.vps_result.rejected_vps)
+                            .chain(&result.initialized_accounts)
+                            .cloned()
+                            .collect();
+                    }
                     if result.is_accepted() {
                         tracing::info!(
                             "all VPs accepted transaction {} storage \
@@ -355,9 +453,16 @@ where
                             tx_event["hash"],
                             result
                         );
+                        if self.indexer.is_some() {
+                            movements = indexer::token_movements(
+                                &result.changed_keys,
+                                &self.write_log,
+                                &self.storage,
+                            );
+                        }
                         self.write_log.commit_tx();
                         if !tx_event.contains_key("code") {
-                            tx_event["code"] = ErrorCodes::Ok.into();
+                            tx_event["code"] = ResultCode::Ok.into();
                         }
                         if let Some(ibc_event) = &result.ibc_event {
                             // Add the IBC event besides the tx_event
@@ -387,7 +492,45 @@ where
                             result.vps_result.rejected_vps
                         );
                         self.write_log.drop_tx();
-                        tx_event["code"] = ErrorCodes::InvalidTx.into();
+                        tx_event["code"] = ResultCode::VpRejected.into();
+                        match serde_json::to_string(
+                            &result.vps_result.rejected_vps,
+                        ) {
+                            Ok(rejected_vps) => {
+                                tx_event["rejected_vps"] = rejected_vps;
+                            }
+                            Err(err) => {
+                                tracing::error!(
+                                    "Failed to serialize the rejected VPs: {}",
+                                    err
+                                );
+                            }
+                        }
+                        let rejected_vp_keys: BTreeMap<&Address, Vec<String>> =
+                            result
+                                .vps_result
+                                .rejected_vp_keys
+                                .iter()
+                                .map(|(addr, keys)| {
+                                    let keys = keys
+                                        .iter()
+                                        .map(ToString::to_string)
+                                        .collect();
+                                    (addr, keys)
+                                })
+                                .collect();
+                        match serde_json::to_string(&rejected_vp_keys) {
+                            Ok(rejected_vp_keys) => {
+                                tx_event["rejected_vp_keys"] = rejected_vp_keys;
+                            }
+                            Err(err) => {
+                                tracing::error!(
+                                    "Failed to serialize the keys examined by \
+                                     the rejected VPs: {}",
+                                    err
+                                );
+                            }
+                        }
                     }
                     tx_event["gas_used"] = result.gas_used.to_string();
                     tx_event["info"] = result.to_string();
@@ -399,14 +542,34 @@ where
                         msg
                     );
                     self.write_log.drop_tx();
+                    let gas_exhausted =
+                        matches!(
+                            msg,
+                            Error::TxApply(protocol::Error::GasError(_))
+                        ) || self.gas_meter.is_transaction_gas_exceeded();
                     tx_event["gas_used"] = self
                         .gas_meter
                         .get_current_transaction_gas()
                         .to_string();
+                    // The gas used by the failed tx still counts towards the
+                    // block's gas, unless it doesn't fit in the block
+                    let _ = self.gas_meter.finalize_transaction();
                     tx_event["info"] = msg.to_string();
-                    tx_event["code"] = ErrorCodes::WasmRuntimeError.into();
+                    tx_event["code"] = if gas_exhausted {
+                        ResultCode::GasExhausted.into()
+                    } else {
+                        ResultCode::WasmRuntimeError.into()
+                    };
                 }
             }
+            self.index_tx(
+                &tx_event,
+                &response.events[events_start..],
+                height,
+                tx_index,
+                addresses,
+                movements,
+            );
             response.events.push(tx_event);
         }
         self.reset_tx_queue_iter();
@@ -415,16 +578,91 @@ where
             self.update_epoch(&mut response);
         }
 
-        let _ = self
-            .gas_meter
-            .finalize_transaction()
-            .map_err(|_| Error::GasOverflow)?;
+        match self.gas_meter.finalize_transaction() {
+            // The tx that went over the block gas limit has been rejected
+            Ok(_) | Err(gas::Error::BlockGasExceeded) => {}
+            Err(_) => return Err(Error::GasOverflow),
+        }
+        let gas_usage = self.gas_meter.get_block_gas_usage();
+        self.storage.block_gas = gas_usage.used_gas;
+        self.node_stats.add_block(BlockStats {
+            height,
+            used_gas: gas_usage.used_gas,
+            max_block_gas: gas_usage.max_block_gas,
+            wrapper_txs,
+            decrypted_txs,
+        });
         Ok(response)
     }
 
+    /// Charge the fee of a wrapper tx whose payload could not be decrypted.
+    /// The fee is moved from the fee payer to the treasury, up to the payer's
+    /// current balance. Returns the charged amount.
+    fn charge_undecryptable_fee(
+        &mut self,
+        wrapper: &WrapperTx,
+    ) -> token::Amount {
+        let read_balance = |shell: &Self, key: &Key| -> token::Amount {
+            match shell.write_log.read(key).0 {
+                Some(StorageModification::Write { value }) => {
+                    token::Amount::try_from_slice(value).unwrap_or_default()
+                }
+                Some(_) => token::Amount::default(),
+                None => shell.read_storage_key(key).unwrap_or_default(),
+            }
+        };
+        let payer_key =
+            token::balance_key(&wrapper.fee.token, &wrapper.fee_payer());
+        let treasury_key =
+            token::balance_key(&wrapper.fee.token, &treasury_address);
+        let mut payer_balance = read_balance(self, &payer_key);
+        let fee = std::cmp::min(wrapper.fee.amount, payer_balance);
+        if fee == token::Amount::default() {
+            return fee;
+        }
+        let mut treasury_balance = read_balance(self, &treasury_key);
+        payer_balance.spend(&fee);
+        treasury_balance.receive(&fee);
+        self.write_log
+            .write(&payer_key, payer_balance.try_to_vec().unwrap())
+            .expect("Writing the fee payer's balance shouldn't fail");
+        self.write_log
+            .write(&treasury_key, treasury_balance.try_to_vec().unwrap())
+            .expect("Writing the treasury's balance shouldn't fail");
+        self.write_log.commit_tx();
+        fee
+    }
+
+    /// Add a finalized tx to the indexer, if it's enabled, with its event
+    /// and the other events emitted for it
+    fn index_tx(
+        &mut self,
+        tx_event: &Event,
+        other_events: &[Event],
+        height: BlockHeight,
+        index: u32,
+        addresses: BTreeSet<Address>,
+        movements: Vec<TokenMovement>,
+    ) {
+        if let Some(indexer) = &mut self.indexer {
+            let events = std::iter::once(tx_event)
+                .chain(other_events)
+                .map(indexer::block_event)
+                .collect();
+            indexer.add_tx(
+                indexer::indexed_tx(
+                    tx_event, height, index, addresses, movements,
+                ),
+                events,
+            );
+        }
+    }
+
     /// Sets the metadata necessary for a new block, including
-    /// the hash, height, validator changes, and evidence of
-    /// byzantine behavior. Applies slashes if necessary.
+    /// the hash, height, validator changes, evidence of
+    /// byzantine behavior, which is slashed by [`Shell::slash`], and the
+    /// votes of the last commit, which are recorded by
+    /// [`Shell::record_liveness`].
     /// Returns a bool indicating if a new epoch began and
     /// the height of the new block.
     fn update_state(
@@ -432,10 +670,14 @@ where
         header: Header,
         hash: BlockHash,
         byzantine_validators: Vec<Evidence>,
+        votes: Vec<VoteInfo>,
     ) -> (BlockHeight, bool) {
         let height = self.storage.last_height + 1;
 
-        self.gas_meter.reset();
+        // The gas limits may have been changed in the previous block
+        let (max_block_gas, max_tx_gas) = self.gas_limits();
+        self.gas_meter = BlockGasMeter::new(max_block_gas, max_tx_gas);
+        self.proposal_gas = 0;
 
         self.storage
             .begin_block(hash, height)
@@ -446,6 +688,7 @@ where
             .expect("Setting a header shouldn't fail");
 
         self.byzantine_validators = byzantine_validators;
+        self.last_commit_votes = votes;
 
         let header = self
             .storage
@@ -458,7 +701,6 @@ where
             .update_epoch(height, time)
             .expect("Must be able to update epoch");
 
-        self.slash();
         (height, new_epoch)
     }
 
@@ -551,6 +793,7 @@ mod test_finalize_block {
                 0.into(),
                 raw_tx.clone(),
                 Default::default(),
+                ChainId::default(),
             );
             let tx = wrapper.sign(&keypair).expect("Test failed");
             if i > 1 {
@@ -624,6 +867,7 @@ mod test_finalize_block {
                 0.into(),
                 raw_tx.clone(),
                 Default::default(),
+                ChainId::default(),
             );
             let tx = wrapper.sign(&keypair).expect("Test failed");
             if i > 1 {
@@ -678,13 +922,14 @@ mod test_finalize_block {
             0.into(),
             raw_tx.clone(),
             Default::default(),
+            ChainId::default(),
         );
 
         let processed_tx = ProcessedTx {
             tx: Tx::from(TxType::Decrypted(DecryptedTx::Decrypted(raw_tx)))
                 .to_bytes(),
             result: TxResult {
-                code: ErrorCodes::InvalidTx.into(),
+                code: ResultCode::InvalidTx.into(),
                 info: "".into(),
             },
         };
@@ -700,7 +945,7 @@ mod test_finalize_block {
         {
             assert_eq!(event.event_type.to_string(), String::from("applied"));
             let code = event.attributes.get("code").expect("Test failed");
-            assert_eq!(code, &String::from(ErrorCodes::InvalidTx));
+            assert_eq!(code, &String::from(ResultCode::InvalidTx));
         }
         // check that the corresponding wrapper tx was removed from the queue
         assert!(shell.next_wrapper().is_none());
@@ -720,7 +965,7 @@ mod test_finalize_block {
             tx: Tx::from(TxType::Decrypted(DecryptedTx::Decrypted(raw_tx)))
                 .to_bytes(),
             result: TxResult {
-                code: ErrorCodes::InvalidTx.into(),
+                code: ResultCode::InvalidTx.into(),
                 info: "".into(),
             },
         };
@@ -736,7 +981,7 @@ mod test_finalize_block {
             assert_eq!(event.event_type.to_string(), String::from("applied"));
             let code =
                 event.attributes.get("code").expect("Test failed").as_str();
-            assert_eq!(code, String::from(ErrorCodes::InvalidTx).as_str());
+            assert_eq!(code, String::from(ResultCode::InvalidTx).as_str());
         }
         // check that the corresponding wrapper tx was removed from the queue
         assert!(shell.next_wrapper().is_none());
@@ -767,6 +1012,7 @@ mod test_finalize_block {
             gas_limit: 0.into(),
             inner_tx,
             tx_hash: hash_tx(&tx),
+            chain_id: ChainId::default(),
         };
         let processed_tx = ProcessedTx {
             tx: Tx::from(TxType::Decrypted(DecryptedTx::Undecryptable(
@@ -774,7 +1020,7 @@ mod test_finalize_block {
             )))
             .to_bytes(),
             result: TxResult {
-                code: ErrorCodes::Ok.into(),
+                code: ResultCode::Ok.into(),
                 info: "".into(),
             },
         };
@@ -791,7 +1037,7 @@ mod test_finalize_block {
         {
             assert_eq!(event.event_type.to_string(), String::from("applied"));
             let code = event.attributes.get("code").expect("Test failed");
-            assert_eq!(code, &String::from(ErrorCodes::Undecryptable));
+            assert_eq!(code, &String::from(ResultCode::Undecryptable));
             let log = event.attributes.get("log").expect("Test failed");
             assert!(log.contains("Transaction could not be decrypted."))
         }
@@ -824,6 +1070,7 @@ mod test_finalize_block {
             gas_limit: 0.into(),
             inner_tx,
             tx_hash: hash_tx(&tx),
+            chain_id: ChainId::default(),
         };
         let processed_tx = ProcessedTx {
             tx: Tx::from(TxType::Decrypted(DecryptedTx::Undecryptable(
@@ -831,7 +1078,7 @@ mod test_finalize_block {
             )))
             .to_bytes(),
             result: TxResult {
-                code: ErrorCodes::Ok.into(),
+                code: ResultCode::Ok.into(),
                 info: "".into(),
             },
         };
@@ -847,7 +1094,7 @@ mod test_finalize_block {
             assert_eq!(event.event_type.to_string(), String::from("applied"));
             let code =
                 event.attributes.get("code").expect("Test failed").as_str();
-            assert_eq!(code, String::from(ErrorCodes::Undecryptable).as_str());
+            assert_eq!(code, String::from(ResultCode::Undecryptable).as_str());
 
             let log = event.attributes.get("log").expect("Test failed").clone();
             assert!(log.contains("Transaction could not be decrypted."))
@@ -856,6 +1103,79 @@ mod test_finalize_block {
         assert!(shell.next_wrapper().is_none());
     }
 
+    #[cfg(not(feature = "ABCI"))]
+    /// Test that the fee of a wrapper tx whose payload could not be
+    /// decrypted is moved from the fee payer to the treasury.
+    #[test]
+    fn test_undecryptable_charges_fee() {
+        let (mut shell, _) = setup();
+
+        let keypair = crate::wallet::defaults::daewon_keypair();
+        let pubkey = EncryptionKey::default();
+        // not valid tx bytes
+        let tx = "garbage data".as_bytes().to_owned();
+        let inner_tx =
+            anoma::types::transaction::encrypted::EncryptedTx::encrypt(
+                &tx, pubkey,
+            );
+        let fee = token::Amount::from(1_000_000);
+        let wrapper = WrapperTx {
+            fee: Fee {
+                amount: fee,
+                token: xan(),
+            },
+            pk: keypair.ref_to(),
+            epoch: Epoch(0),
+            gas_limit: 0.into(),
+            inner_tx,
+            tx_hash: hash_tx(&tx),
+            chain_id: ChainId::default(),
+        };
+        let payer_key = token::balance_key(&xan(), &wrapper.fee_payer());
+        let treasury_key = token::balance_key(&xan(), &treasury_address);
+        let read_balance = |shell: &TestShell, key: &Key| -> token::Amount {
+            match shell.write_log.read(key).0 {
+                Some(StorageModification::Write { value }) => {
+                    token::Amount::try_from_slice(value).expect("Test failed")
+                }
+                _ => shell.read_storage_key(key).unwrap_or_default(),
+            }
+        };
+        let payer_balance = read_balance(&shell, &payer_key);
+        let treasury_balance = read_balance(&shell, &treasury_key);
+        assert!(payer_balance >= fee);
+
+        let processed_tx = ProcessedTx {
+            tx: Tx::from(TxType::Decrypted(DecryptedTx::Undecryptable(
+                wrapper.clone(),
+            )))
+            .to_bytes(),
+            result: TxResult {
+                code: ResultCode::Ok.into(),
+                info: "".into(),
+            },
+        };
+        shell.enqueue_tx(wrapper);
+
+        let events = shell
+            .finalize_block(FinalizeBlock {
+                txs: vec![processed_tx],
+                ..Default::default()
+            })
+            .expect("Test failed");
+        assert_eq!(events.len(), 1);
+        let code = events[0].attributes.get("code").expect("Test failed");
+        assert_eq!(code, &String::from(ResultCode::Undecryptable));
+        let fee_charged = events[0]
+            .attributes
+            .get("fee_charged")
+            .expect("Test failed");
+        assert_eq!(fee_charged, &fee.to_string());
+
+        assert_eq!(read_balance(&shell, &payer_key), payer_balance - fee);
+        assert_eq!(read_balance(&shell, &treasury_key), treasury_balance + fee);
+    }
+
     /// Test that the wrapper txs are queued in the order they
     /// are received from the block. Tests that the previously
     /// decrypted txs are de-queued.
@@ -890,13 +1210,14 @@ mod test_finalize_block {
                 0.into(),
                 raw_tx.clone(),
                 Default::default(),
+                ChainId::default(),
             );
             shell.enqueue_tx(wrapper_tx);
             processed_txs.push(ProcessedTx {
                 tx: Tx::from(TxType::Decrypted(DecryptedTx::Decrypted(raw_tx)))
                     .to_bytes(),
                 result: TxResult {
-                    code: ErrorCodes::Ok.into(),
+                    code: ResultCode::Ok.into(),
                     info: "".into(),
                 },
             });
@@ -921,13 +1242,14 @@ mod test_finalize_block {
                 0.into(),
                 raw_tx.clone(),
                 Default::default(),
+                ChainId::default(),
             );
             let wrapper = wrapper_tx.sign(&keypair).expect("Test failed");
             valid_txs.push(wrapper_tx);
             processed_txs.push(ProcessedTx {
                 tx: wrapper.to_bytes(),
                 result: TxResult {
-                    code: ErrorCodes::Ok.into(),
+                    code: ResultCode::Ok.into(),
                     info: "".into(),
                 },
             });
@@ -959,7 +1281,7 @@ mod test_finalize_block {
                 }
                 let code =
                     event.attributes.get("code").expect("Test failed").as_str();
-                assert_eq!(code, String::from(ErrorCodes::Ok).as_str());
+                assert_eq!(code, String::from(ResultCode::Ok).as_str());
             } else {
                 // these should be accepted decrypted txs
                 assert_eq!(
@@ -968,7 +1290,7 @@ mod test_finalize_block {
                 );
                 let code =
                     event.attributes.get("code").expect("Test failed").as_str();
-                assert_eq!(code, String::from(ErrorCodes::Ok).as_str());
+                assert_eq!(code, String::from(ResultCode::Ok).as_str());
             }
         }
 
@@ -989,4 +1311,80 @@ mod test_finalize_block {
             assert_eq!(counter, 2);
         }
     }
+
+    /// Test that the event of a decrypted tx rejected by a VP reports the
+    /// rejecting VP and the changed keys that it examined
+    #[test]
+    fn test_rejected_vp_keys_in_event() {
+        let (mut shell, _) = setup();
+        let keypair = gen_keypair();
+
+        // An account whose VP rejects any change
+        let addr = address::testing::established_address_1();
+        let mut vp_path = top_level_directory();
+        vp_path.push("wasm_for_tests/vp_always_false.wasm");
+        let vp_code =
+            std::fs::read(vp_path).expect("Expected a file at given code path");
+        shell
+            .storage
+            .write(&Key::validity_predicate(&addr), vp_code)
+            .expect("Test failed");
+
+        // A tx that writes a key of the account
+        let key = Key::from(addr.to_db_key())
+            .push(&"test".to_string())
+            .expect("Test failed");
+        let mut wasm_path = top_level_directory();
+        wasm_path.push("wasm_for_tests/tx_write_storage_key.wasm");
+        let tx_code = std::fs::read(wasm_path)
+            .expect("Expected a file at given code path");
+        let raw_tx =
+            Tx::new(tx_code, Some(key.to_string().into_bytes())).sign(&keypair);
+        let wrapper_tx = WrapperTx::new(
+            Fee {
+                amount: 0.into(),
+                token: xan(),
+            },
+            &keypair,
+            Epoch(0),
+            0.into(),
+            raw_tx.clone(),
+            Default::default(),
+            ChainId::default(),
+        );
+        shell.enqueue_tx(wrapper_tx);
+        let processed_tx = ProcessedTx {
+            tx: Tx::from(TxType::Decrypted(DecryptedTx::Decrypted(raw_tx)))
+                .to_bytes(),
+            result: TxResult {
+                code: ResultCode::Ok.into(),
+                info: "".into(),
+            },
+        };
+
+        let events = shell
+            .finalize_block(FinalizeBlock {
+                txs: vec![processed_tx],
+                ..Default::default()
+            })
+            .expect("Test failed");
+        let event = events
+            .iter()
+            .find(|event| event.event_type.to_string() == "applied")
+            .expect("Test failed");
+        let code = event.attributes.get("code").expect("Test failed");
+        assert_eq!(code, &String::from(ResultCode::VpRejected));
+        let rejected_vp_keys: BTreeMap<Address, Vec<String>> =
+            serde_json::from_str(
+                event
+                    .attributes
+                    .get("rejected_vp_keys")
+                    .expect("Test failed"),
+            )
+            .expect("Test failed");
+        assert_eq!(
+            rejected_vp_keys,
+            BTreeMap::from([(addr, vec![key.to_string()])])
+        );
+    }
 }
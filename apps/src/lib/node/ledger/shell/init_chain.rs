@@ -2,21 +2,17 @@
 use std::collections::HashMap;
 use std::hash::Hash;
 
+use anoma::ledger::parameters::EpochDuration;
+use anoma::ledger::pos::PosParams;
+use anoma::ledger::token_registry::storage as registry_storage;
 use anoma::types::key::*;
+use anoma::types::storage::Epochs;
 #[cfg(not(feature = "dev"))]
 use sha2::{Digest, Sha256};
-#[cfg(not(feature = "ABCI"))]
-use tendermint_proto::abci;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_proto::crypto::PublicKey as TendermintPublicKey;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_proto::google::protobuf;
-#[cfg(feature = "ABCI")]
-use tendermint_proto_abci::abci;
-#[cfg(feature = "ABCI")]
-use tendermint_proto_abci::crypto::PublicKey as TendermintPublicKey;
-#[cfg(feature = "ABCI")]
-use tendermint_proto_abci::google::protobuf;
+use tendermint_compat::tendermint_proto::abci;
+use tendermint_compat::tendermint_proto::crypto::PublicKey as TendermintPublicKey;
+use tendermint_compat::tendermint_proto::google::protobuf;
+use tendermint_compat::tendermint_proto::types::VersionParams;
 
 use super::*;
 use crate::wasm_loader;
@@ -42,6 +38,29 @@ where
                 current_chain_id, init.chain_id
             )));
         }
+
+        let ts: protobuf::Timestamp = init.time.expect("Missing genesis time");
+        let initial_height = init
+            .initial_height
+            .try_into()
+            .expect("Unexpected block height");
+        // TODO hacky conversion, depends on https://github.com/informalsystems/tendermint-rs/issues/870
+        let genesis_time: DateTimeUtc =
+            (Utc.timestamp(ts.seconds, ts.nanos as u32)).into();
+
+        // A chain with an imported state is initialized from it instead of
+        // the genesis
+        let chain_dir = self.base_dir.join(self.storage.chain_id.as_str());
+        if let Some(dump) = state_dump::read_imported_state(chain_dir)
+            .map_err(Error::StateDump)?
+        {
+            return self.init_chain_from_state(
+                dump,
+                initial_height,
+                genesis_time,
+            );
+        }
+
         #[cfg(not(feature = "dev"))]
         let genesis = genesis::genesis(&self.base_dir, &self.storage.chain_id);
         #[cfg(not(feature = "dev"))]
@@ -58,15 +77,6 @@ where
         #[cfg(feature = "dev")]
         let genesis = genesis::genesis();
 
-        let ts: protobuf::Timestamp = init.time.expect("Missing genesis time");
-        let initial_height = init
-            .initial_height
-            .try_into()
-            .expect("Unexpected block height");
-        // TODO hacky conversion, depends on https://github.com/informalsystems/tendermint-rs/issues/870
-        let genesis_time: DateTimeUtc =
-            (Utc.timestamp(ts.seconds, ts.nanos as u32)).into();
-
         genesis.parameters.init_storage(&mut self.storage);
         genesis.gov_params.init_storage(&mut self.storage);
         genesis.treasury_params.init_storage(&mut self.storage);
@@ -140,6 +150,10 @@ where
         }
 
         // Initialize genesis token accounts
+        let known_tokens = address::tokens();
+        // The total supply of each token that is credited at genesis
+        let mut supplies: HashMap<address::Address, token::Amount> =
+            HashMap::new();
         for genesis::TokenAccount {
             address,
             vp_code_path,
@@ -179,6 +193,29 @@ where
                         amount.try_to_vec().unwrap(),
                     )
                     .unwrap();
+                *supplies.entry(address.clone()).or_default() += amount;
+            }
+
+            // Register the known tokens with a fixed supply
+            if let Some(symbol) = known_tokens.get(&address) {
+                let metadata = token::Metadata {
+                    symbol: symbol.to_string(),
+                    decimals: token::MAX_DECIMAL_PLACES as u8,
+                    max_supply: None,
+                    minter: None,
+                };
+                self.storage
+                    .write(
+                        &token::metadata_key(&address),
+                        metadata.try_to_vec().unwrap(),
+                    )
+                    .unwrap();
+                self.storage
+                    .write(
+                        &registry_storage::symbol_key(symbol),
+                        address.try_to_vec().unwrap(),
+                    )
+                    .unwrap();
             }
         }
 
@@ -232,6 +269,8 @@ where
                         .expect("encode token amount"),
                 )
                 .expect("Unable to set genesis balance");
+            *supplies.entry(address::xan()).or_default() +=
+                validator.non_staked_balance;
             self.storage
                 .write(
                     &protocol_pk_key(addr),
@@ -253,6 +292,17 @@ where
                 .expect("Unable to set genesis user public DKG session key");
         }
 
+        // The PoS system raises the staking token's supply by the bonded
+        // tokens it's credited
+        for (token, supply) in supplies {
+            self.storage
+                .write(
+                    &token::total_supply_key(&token),
+                    supply.try_to_vec().expect("encode token amount"),
+                )
+                .expect("Unable to set genesis token supply");
+        }
+
         // PoS system depends on epoch being initialized
         let (current_epoch, _gas) = self.storage.get_current_epoch();
         pos::init_genesis_storage(
@@ -266,14 +316,11 @@ where
         );
         ibc::init_genesis_storage(&mut self.storage);
 
-        let evidence_params = self.get_evidence_params(
+        self.set_consensus_params(
+            &mut response,
             &genesis.parameters.epoch_duration,
             &genesis.pos_params,
         );
-        response.consensus_params = Some(ConsensusParams {
-            evidence: Some(evidence_params),
-            ..response.consensus_params.unwrap_or_default()
-        });
 
         // Set the initial validator set
         for validator in genesis.validators {
@@ -293,6 +340,81 @@ where
         }
         Ok(response)
     }
+
+    /// Initialize the chain from an imported state dump, instead of the
+    /// genesis. The chain continues from the epoch of the dump, in which the
+    /// epoched PoS data are valid, and the active validators of the epoch
+    /// become the initial validator set.
+    fn init_chain_from_state(
+        &mut self,
+        dump: state_dump::StateDump,
+        initial_height: BlockHeight,
+        genesis_time: DateTimeUtc,
+    ) -> Result<response::InitChain> {
+        let mut response = response::InitChain::default();
+        tracing::info!(
+            "Initializing the chain from the state of the chain {} at height \
+             {}",
+            dump.chain_id,
+            dump.height
+        );
+        for (key, value) in dump.key_vals().map_err(Error::StateDump)? {
+            self.storage.write(&key, value).map_err(Error::Storage)?;
+        }
+        self.storage.block.epoch = dump.epoch;
+        self.storage.last_epoch = dump.epoch;
+        self.storage.block.pred_epochs = Epochs::starting_from(dump.epoch);
+        self.storage.address_gen = dump.address_gen;
+
+        // Depends on parameters being imported
+        let (parameters, _gas) = parameters::read(&self.storage)
+            .expect("Couldn't read the imported protocol parameters");
+        self.storage
+            .init_genesis_epoch(initial_height, genesis_time, &parameters)
+            .map_err(Error::Storage)?;
+
+        let pos_params = self.storage.read_pos_params();
+        self.set_consensus_params(
+            &mut response,
+            &parameters.epoch_duration,
+            &pos_params,
+        );
+
+        // Set the initial validator set
+        for validator in self.storage.read_active_validators(dump.epoch) {
+            let pub_key = TendermintPublicKey {
+                sum: Some(key_to_tendermint(&validator.consensus_key).unwrap()),
+            };
+            let power: u64 = validator.voting_power.into();
+            response.validators.push(abci::ValidatorUpdate {
+                pub_key: Some(pub_key),
+                power: power
+                    .try_into()
+                    .expect("unexpected validator's voting power"),
+            });
+        }
+        Ok(response)
+    }
+
+    /// Set the evidence params and the protocol version of the consensus
+    /// params in the response
+    fn set_consensus_params(
+        &self,
+        response: &mut response::InitChain,
+        epoch_duration: &EpochDuration,
+        pos_params: &PosParams,
+    ) {
+        let evidence_params =
+            self.get_evidence_params(epoch_duration, pos_params);
+        // The protocol version is embedded in the block headers by Tendermint
+        response.consensus_params = Some(ConsensusParams {
+            evidence: Some(evidence_params),
+            version: Some(VersionParams {
+                app_version: PROTOCOL_VERSION,
+            }),
+            ..response.consensus_params.take().unwrap_or_default()
+        });
+    }
 }
 
 trait HashMapExt<K, V>
@@ -318,3 +440,51 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod test_init_chain {
+    use super::*;
+    use crate::node::ledger::shell::test_utils::*;
+    use crate::wallet::defaults;
+
+    /// Test that a new chain is initialized from the imported state of
+    /// another chain, with its balances, epoch and validator set.
+    #[test]
+    fn test_init_chain_from_state() {
+        let (mut shell, _) = setup();
+        shell.storage.commit().unwrap();
+        let dump = state_dump::StateDump::read_from_db(
+            &mut shell.storage.db,
+            shell.chain_id.clone(),
+        )
+        .unwrap();
+        dump.verify().unwrap();
+
+        let (mut imported, _) = TestShell::new();
+        let chain_dir =
+            imported.base_dir.join(imported.storage.chain_id.as_str());
+        std::fs::create_dir_all(&chain_dir).unwrap();
+        dump.write(state_dump::imported_state_file(&chain_dir))
+            .unwrap();
+        let response = imported
+            .shell
+            .init_chain(request::InitChain {
+                time: Some(protobuf::Timestamp {
+                    seconds: 0,
+                    nanos: 0,
+                }),
+                chain_id: ChainId::default().to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(response.validators.len(), 1);
+        assert_eq!(imported.storage.get_current_epoch().0, dump.epoch);
+        let balance_key =
+            token::balance_key(&address::xan(), &defaults::albert_address());
+        assert_eq!(
+            imported.storage.read(&balance_key).unwrap().0,
+            shell.storage.read(&balance_key).unwrap().0
+        );
+    }
+}
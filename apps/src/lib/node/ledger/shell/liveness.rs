@@ -0,0 +1,257 @@
+//! Tracking of the liveness of the validators from the votes of the last
+//! block's commit, which are received from Tendermint with the `BeginBlock`
+//! (ABCI) or `FinalizeBlock` (ABCI++) request. At the end of an epoch, the
+//! active validators that missed to sign too many blocks of their liveness
+//! window are slashed and jailed in PoS and a slash event is emitted with the
+//! block.
+
+use anoma::ledger::light_client;
+use anoma::ledger::pos::types::SlashType;
+use anoma::types::storage::Epoch;
+
+use super::evidence::{slash_event, ValidEvidence};
+use super::*;
+
+impl<D, H> Shell<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    /// Record the votes of the commit of the last block, which is the block
+    /// before the given height, in the liveness of their validators. A vote
+    /// of an unknown validator is only logged.
+    pub(super) fn record_liveness(&mut self, height: BlockHeight) {
+        let votes = mem::take(&mut self.last_commit_votes);
+        let last_height = match height.0.checked_sub(1) {
+            Some(last_height) if !votes.is_empty() => BlockHeight(last_height),
+            _ => return,
+        };
+        let epoch = match self.storage.block.pred_epochs.get_epoch(last_height)
+        {
+            Some(epoch) => epoch,
+            None => return,
+        };
+        let pos_params = self.storage.read_pos_params();
+        for vote in votes {
+            let tm_address = match vote.validator.as_ref() {
+                Some(validator) => hex::encode_upper(&validator.address),
+                None => continue,
+            };
+            match self.find_validator_by_tm_address(
+                last_height,
+                epoch,
+                &tm_address,
+            ) {
+                Some(validator) => self.storage.record_validator_liveness(
+                    &pos_params,
+                    last_height.0,
+                    &validator,
+                    vote.signed_last_block,
+                ),
+                None => tracing::error!(
+                    "Ignoring the vote of an unknown validator with the \
+                     Tendermint address {} in epoch {}",
+                    tm_address,
+                    epoch
+                ),
+            }
+        }
+    }
+
+    /// Slash and jail the active validators of the epoch that ended before
+    /// the block at the given height, which missed to sign more blocks of
+    /// their liveness window than the PoS parameters allow. Returns the
+    /// events of the applied slashes.
+    pub(super) fn jail_downtime_validators(
+        &mut self,
+        height: BlockHeight,
+    ) -> Vec<Event> {
+        let current_epoch = self.storage.block.epoch;
+        let epoch = match current_epoch.0.checked_sub(1) {
+            Some(epoch) => Epoch(epoch),
+            None => return vec![],
+        };
+        let pos_params = self.storage.read_pos_params();
+        let mut events = vec![];
+        for validator in
+            self.storage.find_downtime_validators(&pos_params, epoch)
+        {
+            tracing::info!(
+                "Slashing and jailing {} for downtime in epoch {}",
+                validator,
+                epoch
+            );
+            if let Err(err) = self.storage.slash(
+                &pos_params,
+                current_epoch,
+                epoch,
+                height.0,
+                SlashType::Downtime,
+                &validator,
+            ) {
+                tracing::error!("Error in slashing: {}", err);
+                continue;
+            }
+            if let Err(err) = self.storage.jail_validator(
+                &pos_params,
+                current_epoch,
+                &validator,
+            ) {
+                tracing::error!("Error in jailing: {}", err);
+            }
+            let tm_address = self
+                .storage
+                .read_validator_consensus_key(&validator)
+                .and_then(|keys| {
+                    keys.get(epoch).map(light_client::tm_address_of)
+                })
+                .unwrap_or_default();
+            let downtime = ValidEvidence {
+                validator,
+                tm_address,
+                epoch,
+                height,
+                slash_type: SlashType::Downtime,
+            };
+            events.push(slash_event(&downtime, &pos_params, height));
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod test_liveness {
+    use anoma::ledger::pos::types::ValidatorState;
+    use tendermint_compat::tendermint_proto::abci::Validator;
+
+    use super::*;
+    use crate::node::ledger::shell::test_utils::*;
+    use crate::wallet;
+
+    /// A vote of the validator with the given consensus key
+    fn vote(consensus_key: &common::PublicKey, signed: bool) -> VoteInfo {
+        VoteInfo {
+            validator: Some(Validator {
+                address: hex::decode(light_client::tm_address_of(
+                    consensus_key,
+                ))
+                .unwrap(),
+                power: 100,
+            }),
+            signed_last_block: signed,
+        }
+    }
+
+    /// Test that the missed blocks of a validator are recorded from the
+    /// votes and that a validator that missed too many blocks is slashed,
+    /// jailed and removed from the validator set.
+    #[test]
+    fn test_jail_downtime_validators() {
+        let (mut shell, _) = setup();
+        let mut pos_params = shell.storage.read_pos_params();
+        pos_params.liveness_window_len = 4;
+        pos_params.liveness_max_missed_blocks = 1;
+        shell.storage.write_pos_params(&pos_params);
+        let validator = wallet::defaults::validator_address();
+        let consensus_key = wallet::defaults::validator_keypair().ref_to();
+
+        for height in 2..4 {
+            shell.last_commit_votes = vec![vote(&consensus_key, false)];
+            shell.record_liveness(BlockHeight(height));
+        }
+        assert!(shell.last_commit_votes.is_empty());
+        let liveness = shell.storage.read_validator_liveness(&validator);
+        assert_eq!(liveness.missed_count(), 2);
+
+        // Nothing is jailed in the first epoch
+        assert!(shell.jail_downtime_validators(BlockHeight(4)).is_empty());
+
+        shell.storage.block.epoch = Epoch(1);
+        let events = shell.jail_downtime_validators(BlockHeight(4));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["validator"], validator.encode());
+        assert_eq!(events[0]["type"], SlashType::Downtime.to_string());
+        let slashes = shell.storage.read_validator_slashes(&validator);
+        assert_eq!(slashes.len(), 1);
+        let liveness = shell.storage.read_validator_liveness(&validator);
+        assert_eq!(liveness.missed_count(), 0);
+        let pipeline_epoch = Epoch(1 + pos_params.pipeline_len);
+        let validator_set = shell.storage.read_validator_set();
+        let validator_set = validator_set.get(pipeline_epoch).unwrap();
+        assert!(validator_set
+            .active
+            .iter()
+            .chain(validator_set.inactive.iter())
+            .all(|weighted| weighted.address != validator));
+        let state = shell.storage.read_validator_state(&validator).unwrap();
+        assert!(matches!(
+            state.get(pipeline_epoch),
+            Some(ValidatorState::Jailed { .. })
+        ));
+    }
+
+    /// Test that the votes of a validator that changed its consensus key are
+    /// recorded with its previous key in the first blocks of the epoch in
+    /// which the new key is used, before Tendermint applies the change.
+    #[test]
+    fn test_record_liveness_after_consensus_key_change() {
+        let (mut shell, _) = setup();
+        let pos_params = shell.storage.read_pos_params();
+        let validator = wallet::defaults::validator_address();
+        let consensus_key = wallet::defaults::validator_keypair().ref_to();
+        let new_consensus_key = gen_keypair().ref_to();
+        let mut consensus_keys = shell
+            .storage
+            .read_validator_consensus_key(&validator)
+            .unwrap();
+        consensus_keys.set(new_consensus_key.clone(), Epoch(0), &pos_params);
+        shell
+            .storage
+            .write_validator_consensus_key(&validator, &consensus_keys);
+
+        // The new key is used from the pipeline epoch, which starts at the
+        // height 10 times its number
+        let epoch = Epoch(pos_params.pipeline_len);
+        for epoch in 1..=epoch.0 {
+            shell
+                .storage
+                .block
+                .pred_epochs
+                .new_epoch(BlockHeight(10 * epoch), u64::MAX);
+        }
+        let start_height = 10 * epoch.0;
+        let tm_address = light_client::tm_address_of(&consensus_key);
+        let new_tm_address = light_client::tm_address_of(&new_consensus_key);
+        assert_eq!(
+            shell.find_validator_by_tm_address(
+                BlockHeight(start_height + 1),
+                epoch,
+                &tm_address
+            ),
+            Some(validator.clone())
+        );
+        assert_eq!(
+            shell.find_validator_by_tm_address(
+                BlockHeight(start_height + 2),
+                epoch,
+                &tm_address
+            ),
+            None
+        );
+        assert_eq!(
+            shell.find_validator_by_tm_address(
+                BlockHeight(start_height + 2),
+                epoch,
+                &new_tm_address
+            ),
+            Some(validator.clone())
+        );
+
+        // The vote for the first block of the epoch is still signed with the
+        // previous key
+        shell.last_commit_votes = vec![vote(&consensus_key, false)];
+        shell.record_liveness(BlockHeight(start_height + 1));
+        let liveness = shell.storage.read_validator_liveness(&validator);
+        assert_eq!(liveness.missed_count(), 1);
+    }
+}
@@ -0,0 +1,302 @@
+//! Protocol upgrades with storage migrations. A governance proposal schedules
+//! an upgrade to a new protocol version at a block height by writing a
+//! [`ProtocolUpgrade`] into the parameters storage. At the upgrade height, a
+//! node that doesn't run the new protocol version halts without applying the
+//! block, until it's restarted with the binary of the new version. The
+//! upgraded node then applies the storage migrations of the new version before
+//! the block, so that every node migrates the state at the same height.
+
+use anoma::ledger::parameters::ProtocolUpgrade;
+use tendermint_compat::tendermint_proto::types::VersionParams;
+
+use super::*;
+use crate::node::ledger::events::{Event, EventLevel, EventType};
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum UpgradeError {
+    #[error(
+        "The protocol upgrade to version {version} is scheduled at height \
+         {height}, the node must be restarted with the binary of this version"
+    )]
+    UpgradeRequired { version: u64, height: BlockHeight },
+    #[error(
+        "The chain is at protocol version {chain_version}, but the node runs \
+         the version {binary_version}"
+    )]
+    VersionMismatch {
+        chain_version: u64,
+        binary_version: u64,
+    },
+    #[error("Error reading the protocol upgrade parameters: {0}")]
+    ReadParameters(parameters::ReadError),
+    #[error("Error writing the protocol version: {0}")]
+    WriteParameters(parameters::WriteError),
+    #[error(
+        "Error in the storage migration of protocol version {version}: \
+         {error}"
+    )]
+    Migration {
+        version: u64,
+        error: anoma::ledger::storage::Error,
+    },
+}
+
+/// The storage migration of a protocol version
+pub struct Migration<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter>,
+    H: StorageHasher,
+{
+    /// The protocol version that requires the migration
+    pub version: u64,
+    /// A short description of the changes of the storage layout
+    pub description: &'static str,
+    /// Migrate the storage from the layout of the previous version
+    pub migrate: fn(&mut Storage<D, H>) -> anoma::ledger::storage::Result<()>,
+}
+
+/// The storage migrations of all the protocol versions, ordered by version.
+/// A protocol version that changes the storage layout must add its migration
+/// here together with the increment of [`PROTOCOL_VERSION`].
+pub fn migrations<D, H>() -> Vec<Migration<D, H>>
+where
+    D: DB + for<'iter> DBIter<'iter>,
+    H: StorageHasher,
+{
+    vec![]
+}
+
+impl<D, H> Shell<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    /// Apply the protocol upgrade scheduled at the given height, if any,
+    /// before the block is applied. The response is updated with the event of
+    /// the upgrade and the new protocol version of the block headers. Fails
+    /// if the node doesn't run the protocol version of the chain or of the
+    /// upgrade, so that the node halts without applying the block.
+    pub(super) fn upgrade_protocol(
+        &mut self,
+        height: BlockHeight,
+        response: &mut shim::response::FinalizeBlock,
+    ) -> std::result::Result<(), UpgradeError> {
+        let applied =
+            self.apply_upgrade(height, PROTOCOL_VERSION, &migrations())?;
+        if let Some((from_version, upgrade)) = applied {
+            response
+                .events
+                .push(upgrade_event(from_version, &upgrade, height));
+            response.consensus_param_updates = Some(ConsensusParams {
+                version: Some(VersionParams {
+                    app_version: upgrade.version,
+                }),
+                ..response.consensus_param_updates.take().unwrap_or_default()
+            });
+        }
+        Ok(())
+    }
+
+    /// Apply the storage migrations of the protocol upgrade that's due at the
+    /// given height with a node that runs the `binary_version` of the
+    /// protocol. Returns the previous protocol version of the chain and the
+    /// applied upgrade.
+    fn apply_upgrade(
+        &mut self,
+        height: BlockHeight,
+        binary_version: u64,
+        migrations: &[Migration<D, H>],
+    ) -> std::result::Result<Option<(u64, ProtocolUpgrade)>, UpgradeError> {
+        let (chain_version, _gas) =
+            parameters::read_protocol_version(&self.storage)
+                .map_err(UpgradeError::ReadParameters)?;
+        let (upgrade, _gas) = parameters::read_upgrade_parameter(&self.storage)
+            .map_err(UpgradeError::ReadParameters)?;
+        let upgrade = match upgrade {
+            Some(upgrade) if upgrade.height <= height => upgrade,
+            _ => {
+                if chain_version != binary_version {
+                    return Err(UpgradeError::VersionMismatch {
+                        chain_version,
+                        binary_version,
+                    });
+                }
+                return Ok(None);
+            }
+        };
+        if upgrade.version <= chain_version {
+            // An upgrade to the current or an older version cannot be
+            // applied, so it's removed without halting the chain
+            tracing::warn!(
+                "Ignoring the protocol upgrade to version {}, the chain is \
+                 already at version {}",
+                upgrade.version,
+                chain_version
+            );
+            parameters::update_protocol_version(
+                &mut self.storage,
+                chain_version,
+            )
+            .map_err(UpgradeError::WriteParameters)?;
+            return Ok(None);
+        }
+        if upgrade.version != binary_version {
+            return Err(UpgradeError::UpgradeRequired {
+                version: upgrade.version,
+                height: upgrade.height,
+            });
+        }
+        for migration in migrations.iter().filter(|migration| {
+            migration.version > chain_version
+                && migration.version <= upgrade.version
+        }) {
+            tracing::info!(
+                "Applying the storage migration of protocol version {}: {}",
+                migration.version,
+                migration.description
+            );
+            (migration.migrate)(&mut self.storage).map_err(|error| {
+                UpgradeError::Migration {
+                    version: migration.version,
+                    error,
+                }
+            })?;
+        }
+        parameters::update_protocol_version(&mut self.storage, upgrade.version)
+            .map_err(UpgradeError::WriteParameters)?;
+        tracing::info!(
+            "Upgraded the protocol from version {} to {} at height {}",
+            chain_version,
+            upgrade.version,
+            height
+        );
+        Ok(Some((chain_version, upgrade)))
+    }
+}
+
+/// The event of a protocol upgrade applied at the given height
+fn upgrade_event(
+    from_version: u64,
+    upgrade: &ProtocolUpgrade,
+    height: BlockHeight,
+) -> Event {
+    let mut event = Event {
+        event_type: EventType::Upgrade,
+        level: EventLevel::Block,
+        attributes: Default::default(),
+    };
+    event["from_version"] = from_version.to_string();
+    event["version"] = upgrade.version.to_string();
+    event["height"] = height.to_string();
+    event
+}
+
+#[cfg(test)]
+mod test_migrations {
+    use anoma::ledger::parameters::storage as parameters_storage;
+
+    use super::*;
+    use crate::node::ledger::shell::test_utils::*;
+
+    /// A storage migration for the tests that moves a value to a new key
+    fn test_migration<D, H>() -> Migration<D, H>
+    where
+        D: DB + for<'iter> DBIter<'iter>,
+        H: StorageHasher,
+    {
+        Migration {
+            version: 2,
+            description: "Move the test value",
+            migrate: |storage| {
+                let old_key = Key::parse("test/old").unwrap();
+                let new_key = Key::parse("test/new").unwrap();
+                let (value, _gas) = storage.read(&old_key)?;
+                if let Some(value) = value {
+                    storage.write(&new_key, value)?;
+                    storage.delete(&old_key)?;
+                }
+                Ok(())
+            },
+        }
+    }
+
+    fn schedule_upgrade(shell: &mut TestShell, version: u64, height: u64) {
+        parameters::update_upgrade_parameter(
+            &mut shell.storage,
+            &ProtocolUpgrade {
+                version,
+                height: BlockHeight(height),
+            },
+        )
+        .unwrap();
+    }
+
+    /// Test that a node that doesn't run the protocol version of a scheduled
+    /// upgrade halts at the upgrade height and not before.
+    #[test]
+    fn test_upgrade_required() {
+        let (mut shell, _) = setup();
+        schedule_upgrade(&mut shell, 2, 5);
+
+        let result = shell.apply_upgrade(BlockHeight(4), 1, &[]);
+        assert!(matches!(result, Ok(None)));
+        let result = shell.apply_upgrade(BlockHeight(5), 1, &[]);
+        assert!(matches!(
+            result,
+            Err(UpgradeError::UpgradeRequired { version: 2, .. })
+        ));
+        // The upgraded binary cannot run the blocks before the upgrade
+        let result =
+            shell.apply_upgrade(BlockHeight(4), 2, &[test_migration()]);
+        assert!(matches!(result, Err(UpgradeError::VersionMismatch { .. })));
+    }
+
+    /// Test that the upgraded node applies the migrations of the new version
+    /// at the upgrade height and removes the scheduled upgrade.
+    #[test]
+    fn test_apply_upgrade() {
+        let (mut shell, _) = setup();
+        let old_key = Key::parse("test/old").unwrap();
+        let new_key = Key::parse("test/new").unwrap();
+        shell.storage.write(&old_key, vec![1_u8]).unwrap();
+        schedule_upgrade(&mut shell, 2, 5);
+
+        let (from_version, upgrade) = shell
+            .apply_upgrade(BlockHeight(5), 2, &[test_migration()])
+            .unwrap()
+            .unwrap();
+        assert_eq!(from_version, 1);
+        assert_eq!(upgrade.version, 2);
+        assert_eq!(shell.storage.read(&old_key).unwrap().0, None);
+        assert_eq!(shell.storage.read(&new_key).unwrap().0, Some(vec![1_u8]));
+        let (version, _gas) =
+            parameters::read_protocol_version(&shell.storage).unwrap();
+        assert_eq!(version, 2);
+        assert!(
+            !shell
+                .storage
+                .has_key(&parameters_storage::get_upgrade_key())
+                .unwrap()
+                .0
+        );
+        // The following blocks run with the new version
+        let result =
+            shell.apply_upgrade(BlockHeight(6), 2, &[test_migration()]);
+        assert!(matches!(result, Ok(None)));
+    }
+
+    /// Test that an upgrade to the current version is removed without
+    /// halting the chain.
+    #[test]
+    fn test_ignore_stale_upgrade() {
+        let (mut shell, _) = setup();
+        schedule_upgrade(&mut shell, 1, 5);
+
+        let result = shell.apply_upgrade(BlockHeight(5), 1, &[]);
+        assert!(matches!(result, Ok(None)));
+        let (upgrade, _gas) =
+            parameters::read_upgrade_parameter(&shell.storage).unwrap();
+        assert_eq!(upgrade, None);
+    }
+}
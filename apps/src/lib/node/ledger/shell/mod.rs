@@ -5,12 +5,18 @@
 //! (ABCI++), [`Shell::process_and_decode_proposal`] must be also reverted
 //! (unless we can simply overwrite them in the next block).
 //! More info in <https://github.com/anoma/anoma/issues/362>.
+mod evidence;
 mod finalize_block;
 mod init_chain;
+mod liveness;
+mod migrations;
+#[cfg(feature = "pos-invariants")]
+mod pos_invariants;
 #[cfg(not(feature = "ABCI"))]
 mod prepare_proposal;
 mod process_proposal;
 mod queries;
+mod state_sync;
 
 use std::collections::HashSet;
 use std::convert::{TryFrom, TryInto};
@@ -25,43 +31,42 @@ use anoma::ledger::pos::anoma_proof_of_stake::types::{
     ActiveValidator, ValidatorSetUpdate,
 };
 use anoma::ledger::pos::anoma_proof_of_stake::PosBase;
+use anoma::ledger::storage::read_cache::ReadCache;
 use anoma::ledger::storage::write_log::WriteLog;
 use anoma::ledger::storage::{
     DBIter, Sha256Hasher, Storage, StorageHasher, DB,
 };
 use anoma::ledger::{ibc, parameters, pos};
 use anoma::proto::{self, Tx};
-use anoma::types::chain::ChainId;
+use anoma::types::chain::{ChainId, PROTOCOL_VERSION};
 use anoma::types::key::*;
 use anoma::types::storage::{BlockHeight, Key};
 use anoma::types::time::{DateTimeUtc, TimeZone, Utc};
 use anoma::types::transaction::{
     hash_tx, process_tx, verify_decrypted_correctly, AffineCurve, DecryptedTx,
-    EllipticCurve, PairingEngine, TxType, WrapperTx,
+    EllipticCurve, PairingEngine, ResultCode, TxType, WrapperTx,
 };
 use anoma::types::{address, token};
 use anoma::vm::wasm::{TxCache, VpCache};
 use anoma::vm::WasmCacheRwAccess;
 use borsh::{BorshDeserialize, BorshSerialize};
-use num_derive::{FromPrimitive, ToPrimitive};
-use num_traits::{FromPrimitive, ToPrimitive};
 #[cfg(not(feature = "ABCI"))]
-use tendermint_proto::abci::response_verify_vote_extension::VerifyStatus;
+use tendermint_compat::tendermint_proto::abci::response_verify_vote_extension::VerifyStatus;
+#[cfg(feature = "ABCI")]
+use tendermint_compat::tendermint_proto::abci::ConsensusParams;
+use tendermint_compat::tendermint_proto::abci::VoteInfo;
+#[cfg(feature = "ABCI")]
+use tendermint_compat::tendermint_proto::abci::{
+    Evidence, EvidenceType, ValidatorUpdate,
+};
 #[cfg(not(feature = "ABCI"))]
-use tendermint_proto::abci::{
+use tendermint_compat::tendermint_proto::abci::{
     Misbehavior as Evidence, MisbehaviorType as EvidenceType,
     RequestPrepareProposal, ValidatorUpdate,
 };
+use tendermint_compat::tendermint_proto::crypto::public_key;
 #[cfg(not(feature = "ABCI"))]
-use tendermint_proto::crypto::public_key;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_proto::types::ConsensusParams;
-#[cfg(feature = "ABCI")]
-use tendermint_proto_abci::abci::ConsensusParams;
-#[cfg(feature = "ABCI")]
-use tendermint_proto_abci::abci::{Evidence, EvidenceType, ValidatorUpdate};
-#[cfg(feature = "ABCI")]
-use tendermint_proto_abci::crypto::public_key;
+use tendermint_compat::tendermint_proto::types::ConsensusParams;
 use thiserror::Error;
 use tokio::sync::mpsc::UnboundedSender;
 #[cfg(not(feature = "ABCI"))]
@@ -70,11 +75,19 @@ use tower_abci::{request, response};
 use tower_abci_old::{request, response};
 
 use super::rpc;
-use crate::config::{genesis, TendermintMode};
+use crate::config::{
+    genesis, StorageBackend, TendermintMode, TxOrdering, SNAPSHOTS_DIR,
+};
 use crate::node::ledger::events::Event;
+use crate::node::ledger::indexer::{self, Indexer};
+use crate::node::ledger::key_watch::{KeyChange, KeyWatcher};
+use crate::node::ledger::node_status::NodeStats;
 use crate::node::ledger::shims::abcipp_shim_types::shim;
 use crate::node::ledger::shims::abcipp_shim_types::shim::response::TxResult;
+use crate::node::ledger::state_diff::{StateDiff, StateDiffWriter};
+use crate::node::ledger::storage::{schema, PersistentBackend};
 use crate::node::ledger::{protocol, storage, tendermint_node};
+use crate::node::ledger::{snapshots, state_dump};
 #[allow(unused_imports)]
 use crate::wallet::ValidatorData;
 use crate::{config, wallet};
@@ -106,6 +119,18 @@ pub enum Error {
     Broadcaster(tokio::sync::mpsc::error::TryRecvError),
     #[error("Error executing proposal {0}: {1}")]
     BadProposal(u64, String),
+    #[error("Storage error: {0}")]
+    Storage(anoma::ledger::storage::Error),
+    #[error("Indexer error: {0}")]
+    Indexer(indexer::Error),
+    #[error("Imported state error: {0}")]
+    StateDump(state_dump::Error),
+    #[error("Protocol upgrade error: {0}")]
+    Upgrade(migrations::UpgradeError),
+    #[error("DB schema error: {0}")]
+    Schema(schema::Error),
+    #[error("{0} PoS invariants are violated")]
+    PosInvariants(usize),
 }
 
 impl From<Error> for TxResult {
@@ -117,45 +142,114 @@ impl From<Error> for TxResult {
     }
 }
 
-/// The different error codes that the ledger may
-/// send back to a client indicating the status
-/// of their submitted tx
-#[derive(Debug, Clone, FromPrimitive, ToPrimitive, PartialEq)]
-pub enum ErrorCodes {
-    Ok = 0,
-    InvalidTx = 1,
-    InvalidSig = 2,
-    WasmRuntimeError = 3,
-    InvalidOrder = 4,
-    ExtraTxs = 5,
-    Undecryptable = 6,
-}
+pub type Result<T> = std::result::Result<T, Error>;
 
-impl From<ErrorCodes> for u32 {
-    fn from(code: ErrorCodes) -> u32 {
-        code.to_u32().unwrap()
+pub fn reset(config: config::Ledger) -> Result<()> {
+    // simply nuke the DB files
+    for db_path in [
+        config.db_dir(),
+        config.query_db_dir(),
+        config.indexer_db_dir(),
+    ] {
+        match std::fs::remove_dir_all(&db_path) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+            res => res.map_err(Error::RemoveDB)?,
+        };
     }
+    // reset Tendermint state
+    tendermint_node::reset(config.tendermint_dir())
+        .map_err(Error::Tendermint)?;
+    Ok(())
 }
 
-impl From<ErrorCodes> for String {
-    fn from(code: ErrorCodes) -> String {
-        u32::from(code).to_string()
+/// Rollback the DB to the state of the block committed at the given height,
+/// or when not given, to the state before the last committed block, so that
+/// the node can re-apply the reverted blocks from its peers. The data of the
+/// block at the given height must not have been pruned. The ledger node must
+/// not be running.
+pub fn rollback(
+    config: config::Ledger,
+    height: Option<BlockHeight>,
+) -> Result<BlockHeight> {
+    match config.shell.storage_backend {
+        StorageBackend::RocksDB => {
+            rollback_db::<storage::RocksDB>(config, height)
+        }
+        StorageBackend::Sled => rollback_db::<storage::SledDB>(config, height),
     }
 }
 
-pub type Result<T> = std::result::Result<T, Error>;
-
-pub fn reset(config: config::Ledger) -> Result<()> {
-    // simply nuke the DB files
-    let db_path = &config.db_dir();
-    match std::fs::remove_dir_all(&db_path) {
+/// Rollback the DB of the given backend to the state of the block at the
+/// given height or before the last committed block
+fn rollback_db<D: PersistentBackend>(
+    config: config::Ledger,
+    height: Option<BlockHeight>,
+) -> Result<BlockHeight> {
+    let mut db =
+        D::open_primary(&config.db_dir(), None).map_err(Error::Storage)?;
+    let last_height = db
+        .read_last_block()
+        .map_err(Error::Storage)?
+        .map(|state| state.height)
+        .ok_or_else(|| {
+            Error::Storage(anoma::ledger::storage::Error::Temporary {
+                error: "No block has been committed to the DB".to_string(),
+            })
+        })?;
+    let height = height
+        .or_else(|| last_height.0.checked_sub(1).map(BlockHeight))
+        .filter(|height| height.0 > 0)
+        .ok_or_else(|| {
+            Error::Storage(anoma::ledger::storage::Error::Temporary {
+                error: "The first block cannot be rolled back, use `reset` \
+                        instead"
+                    .to_string(),
+            })
+        })?;
+    db.rollback(height).map_err(Error::Storage)?;
+    drop(db);
+    if config.shell.indexer {
+        Indexer::open(config.indexer_db_dir())
+            .and_then(|mut indexer| indexer.rollback(height))
+            .map_err(Error::Indexer)?;
+    }
+    // The query shells' secondary DB instances will be re-created from the
+    // rolled back primary DB
+    match std::fs::remove_dir_all(config.query_db_dir()) {
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
         res => res.map_err(Error::RemoveDB)?,
     };
-    // reset Tendermint state
-    tendermint_node::reset(config.tendermint_dir())
-        .map_err(Error::Tendermint)?;
-    Ok(())
+    Ok(height)
+}
+
+/// Migrate the DB to the schema version of the node, or with `dry_run` only
+/// report the pending schema migrations. The ledger node must not be running.
+pub fn migrate(
+    config: config::Ledger,
+    dry_run: bool,
+) -> Result<schema::MigrationReport> {
+    match config.shell.storage_backend {
+        StorageBackend::RocksDB => {
+            migrate_db::<storage::RocksDB>(&config, dry_run)
+        }
+        StorageBackend::Sled => migrate_db::<storage::SledDB>(&config, dry_run),
+    }
+}
+
+/// Migrate the DB of the given backend to the schema version of the node
+pub fn migrate_db<D: PersistentBackend>(
+    config: &config::Ledger,
+    dry_run: bool,
+) -> Result<schema::MigrationReport> {
+    let mut db = D::open_primary_unchecked(&config.db_dir(), None)
+        .map_err(Error::Storage)?;
+    schema::migrate(
+        &mut db,
+        schema::SCHEMA_VERSION,
+        &schema::schema_migrations(),
+        dry_run,
+    )
+    .map_err(Error::Schema)
 }
 
 #[derive(Debug)]
@@ -196,19 +290,23 @@ where
     H: StorageHasher + Sync + 'static,
 {
     /// The id of the current chain
-    #[allow(dead_code)]
     chain_id: ChainId,
     /// The persistent storage
     pub(super) storage: Storage<D, H>,
     /// Gas meter for the current block
     gas_meter: BlockGasMeter,
+    /// The sum of the gas limits of the wrapper txs accepted so far in the
+    /// block proposal that's being processed
+    proposal_gas: u64,
     /// Write log for the current block
     write_log: WriteLog,
-    /// Byzantine validators given from ABCI++ `prepare_proposal` are stored in
-    /// this field. They will be slashed when we finalize the block.
+    /// Byzantine validators given with the block are stored in this field.
+    /// They will be slashed when we finalize the block.
     byzantine_validators: Vec<Evidence>,
+    /// The votes of the last block's commit given with the block, which are
+    /// recorded in the validators' liveness when we finalize the block
+    last_commit_votes: Vec<VoteInfo>,
     /// Path to the base directory with DB data and configs
-    #[allow(dead_code)]
     base_dir: PathBuf,
     /// Path to the WASM directory for files used in the genesis block.
     wasm_dir: PathBuf,
@@ -221,6 +319,36 @@ where
     tx_wasm_cache: TxCache<WasmCacheRwAccess>,
     /// Proposal execution tracking
     pub proposal_data: HashSet<u64>,
+    /// The order of the new txs in a block proposal
+    #[allow(dead_code)]
+    tx_ordering: TxOrdering,
+    /// The maximum sum of gas limits of the new txs in a block proposal
+    #[allow(dead_code)]
+    proposal_gas_limit: Option<u64>,
+    /// The txs indexer, if enabled
+    indexer: Option<Indexer>,
+    /// The writer of the state diffs of the committed blocks, if enabled
+    state_diff_writer: Option<StateDiffWriter>,
+    /// The check of the PoS invariants after each epoch transition, if
+    /// enabled
+    #[cfg(feature = "pos-invariants")]
+    pos_invariants: Option<config::PosInvariantsCheck>,
+    /// The pruning of the state of the past blocks
+    pruning: config::Pruning,
+    /// The interval of the heights of the state snapshots, if enabled
+    snapshot_interval: Option<u64>,
+    /// The directory of the state snapshots of the chain
+    snapshots_dir: PathBuf,
+    /// The taker of the state snapshots, which is only started for the main
+    /// shell of a node with the snapshots enabled
+    snapshot_worker: Option<snapshots::SnapshotWorker>,
+    /// The snapshot whose chunks are being applied by the state sync
+    snapshot_restore: Option<snapshots::Restore>,
+    /// The stats of the node for its operators, shared with the query shells
+    pub(super) node_stats: NodeStats,
+    /// The publisher of the changes of the committed blocks to the clients
+    /// that watch them
+    pub(super) key_watcher: KeyWatcher,
 }
 
 impl<D, H> Shell<D, H>
@@ -238,8 +366,34 @@ where
         vp_wasm_compilation_cache: u64,
         tx_wasm_compilation_cache: u64,
     ) -> Self {
+        let indexer = config.shell.indexer.then(|| {
+            Indexer::open(config.indexer_db_dir())
+                .map_err(|e| {
+                    tracing::error!("Cannot open the indexer DB {}", e);
+                })
+                .expect("The indexer cannot be initialized")
+        });
+        let state_diff_writer =
+            config.shell.state_diff_sink.clone().map(|sink| {
+                StateDiffWriter::start(sink)
+                    .map_err(|e| {
+                        tracing::error!(
+                            "Cannot open the state diff sink {}",
+                            e
+                        );
+                    })
+                    .expect("The state diff writer cannot be initialized")
+            });
+        let snapshots_dir = config.snapshots_dir();
         let chain_id = config.chain_id;
         let db_path = config.shell.db_dir(&chain_id);
+        let tx_ordering = config.shell.tx_ordering;
+        let proposal_gas_limit = config.shell.proposal_gas_limit;
+        let pruning = config.pruning;
+        let snapshot_interval = config.shell.snapshot_interval;
+        #[cfg(feature = "pos-invariants")]
+        let pos_invariants = config.shell.pos_invariants;
+        let read_cache_bytes = config.shell.read_cache_bytes;
         let base_dir = config.shell.base_dir;
         let mode = config.tendermint.tendermint_mode;
         if !Path::new(&base_dir).is_dir() {
@@ -254,6 +408,11 @@ where
                 tracing::error!("Cannot load the last state from the DB {}", e);
             })
             .expect("PersistentStorage cannot be initialized");
+        // The read cache is only used by the main shell, whose storage is the
+        // only one that changes the DB
+        storage.read_cache = read_cache_bytes
+            .filter(|bytes| *bytes > 0)
+            .map(|bytes| ReadCache::new(bytes as usize));
 
         let vp_wasm_cache_dir =
             base_dir.join(chain_id.as_str()).join("vp_wasm_cache");
@@ -309,12 +468,14 @@ where
             TendermintMode::Seed => ShellMode::Seed,
         };
 
-        Self {
+        let mut shell = Self {
             chain_id,
             storage,
             gas_meter: BlockGasMeter::default(),
+            proposal_gas: 0,
             write_log: WriteLog::default(),
             byzantine_validators: vec![],
+            last_commit_votes: vec![],
             base_dir,
             wasm_dir,
             mode,
@@ -327,9 +488,155 @@ where
                 tx_wasm_compilation_cache as usize,
             ),
             proposal_data: HashSet::new(),
+            tx_ordering,
+            proposal_gas_limit,
+            indexer,
+            state_diff_writer,
+            #[cfg(feature = "pos-invariants")]
+            pos_invariants,
+            pruning,
+            snapshot_interval,
+            snapshots_dir,
+            snapshot_worker: None,
+            snapshot_restore: None,
+            node_stats: NodeStats::default(),
+            key_watcher: KeyWatcher::default(),
+        };
+        // Prune the blocks that are no longer kept after a change of the
+        // pruning settings
+        shell.prune_blocks();
+        shell
+    }
+}
+
+impl<D: PersistentBackend> Shell<D> {
+    /// Create a new read-only shell which can only be used for queries. It
+    /// reads the state from a secondary instance of the DB opened by the
+    /// shell created with [`Shell::new`], so that the queries don't block
+    /// the main shell. The `index` must be unique for every query shell. The
+    /// `node_stats` are shared with the main shell.
+    pub fn new_query(
+        config: config::Ledger,
+        wasm_dir: PathBuf,
+        db_cache: Option<&D::Cache>,
+        index: usize,
+        vp_wasm_compilation_cache: u64,
+        tx_wasm_compilation_cache: u64,
+        node_stats: NodeStats,
+    ) -> Self {
+        let chain_id = config.chain_id.clone();
+        let db_path = config.db_dir();
+        let secondary_path = config.query_db_dir().join(index.to_string());
+        let snapshots_dir = config.snapshots_dir();
+        let indexer = config.shell.indexer.then(|| {
+            Indexer::open_read_only(config.indexer_db_dir())
+                .map_err(|e| {
+                    tracing::error!(
+                        "Cannot open the indexer DB for queries {}",
+                        e
+                    );
+                })
+                .expect("The indexer cannot be initialized for queries")
+        });
+        let base_dir = config.shell.base_dir;
+        let db = D::open_secondary(&db_path, &secondary_path, db_cache)
+            .map_err(|e| {
+                tracing::error!("Cannot open the DB for queries {}", e);
+            })
+            .expect("PersistentStorage cannot be initialized for queries");
+        let mut storage = Storage::with_db(db, chain_id.clone());
+        storage
+            .load_last_state()
+            .map_err(|e| {
+                tracing::error!("Cannot load the last state from the DB {}", e);
+            })
+            .expect("PersistentStorage cannot be initialized for queries");
+
+        let vp_wasm_cache_dir =
+            base_dir.join(chain_id.as_str()).join("vp_wasm_cache");
+        let tx_wasm_cache_dir =
+            base_dir.join(chain_id.as_str()).join("tx_wasm_cache");
+
+        Self {
+            chain_id,
+            storage,
+            gas_meter: BlockGasMeter::default(),
+            proposal_gas: 0,
+            write_log: WriteLog::default(),
+            byzantine_validators: vec![],
+            last_commit_votes: vec![],
+            base_dir,
+            wasm_dir,
+            mode: ShellMode::Full,
+            vp_wasm_cache: VpCache::new(
+                vp_wasm_cache_dir,
+                vp_wasm_compilation_cache as usize,
+            ),
+            tx_wasm_cache: TxCache::new(
+                tx_wasm_cache_dir,
+                tx_wasm_compilation_cache as usize,
+            ),
+            proposal_data: HashSet::new(),
+            tx_ordering: config.shell.tx_ordering,
+            proposal_gas_limit: config.shell.proposal_gas_limit,
+            indexer,
+            // The diffs are only written by the main shell
+            state_diff_writer: None,
+            #[cfg(feature = "pos-invariants")]
+            pos_invariants: None,
+            pruning: config.pruning,
+            snapshot_interval: config.shell.snapshot_interval,
+            snapshots_dir,
+            // The snapshots are only taken by the main shell
+            snapshot_worker: None,
+            snapshot_restore: None,
+            node_stats,
+            key_watcher: KeyWatcher::default(),
         }
     }
 
+    /// Start taking the state snapshots of the main shell in the background,
+    /// if they're enabled. The snapshots are read from a secondary instance
+    /// of the DB, so that they don't block the commit of the blocks.
+    pub fn start_snapshot_worker(
+        &mut self,
+        config: &config::Ledger,
+        db_cache: Option<&D::Cache>,
+    ) {
+        if self.snapshot_interval.is_none() {
+            return;
+        }
+        let secondary_path = config.query_db_dir().join(SNAPSHOTS_DIR);
+        let db = D::open_secondary(&config.db_dir(), &secondary_path, db_cache)
+            .map_err(|e| {
+                tracing::error!("Cannot open the DB for the snapshots {}", e);
+            })
+            .expect("The snapshots cannot be initialized");
+        self.snapshot_worker = Some(snapshots::SnapshotWorker::start(
+            db,
+            |db: &D| db.catch_up_with_primary(),
+            self.snapshots_dir.clone(),
+            self.chain_id.clone(),
+            config.shell.snapshot_keep_recent,
+        ));
+    }
+
+    /// Catch up the storage of a shell created with [`Shell::new_query`] with
+    /// the last block committed by the main shell.
+    pub fn catch_up_with_primary(&mut self) -> Result<()> {
+        self.storage
+            .db
+            .catch_up_with_primary()
+            .map_err(Error::Storage)?;
+        self.storage.load_last_state().map_err(Error::Storage)
+    }
+}
+
+impl<D, H> Shell<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
     /// Iterate lazily over the wrapper txs in order
     #[cfg(not(feature = "ABCI"))]
     fn next_wrapper(&mut self) -> Option<&WrapperTx> {
@@ -351,7 +658,10 @@ where
     /// Load the Merkle root hash and the height of the last committed block, if
     /// any. This is returned when ABCI sends an `info` request.
     pub fn last_state(&mut self) -> response::Info {
-        let mut response = response::Info::default();
+        let mut response = response::Info {
+            app_version: PROTOCOL_VERSION,
+            ..Default::default()
+        };
         let result = self.storage.get_state();
 
         match result {
@@ -404,117 +714,13 @@ where
         }
     }
 
-    /// Apply PoS slashes from the evidence
-    fn slash(&mut self) {
-        if !self.byzantine_validators.is_empty() {
-            let byzantine_validators =
-                mem::take(&mut self.byzantine_validators);
-            let pos_params = self.storage.read_pos_params();
-            let current_epoch = self.storage.block.epoch;
-            for evidence in byzantine_validators {
-                let evidence_height = match u64::try_from(evidence.height) {
-                    Ok(height) => height,
-                    Err(err) => {
-                        tracing::error!(
-                            "Unexpected evidence block height {}",
-                            err
-                        );
-                        continue;
-                    }
-                };
-                let evidence_epoch = match self
-                    .storage
-                    .block
-                    .pred_epochs
-                    .get_epoch(BlockHeight(evidence_height))
-                {
-                    Some(epoch) => epoch,
-                    None => {
-                        tracing::error!(
-                            "Couldn't find epoch for evidence block height {}",
-                            evidence_height
-                        );
-                        continue;
-                    }
-                };
-                let slash_type = match EvidenceType::from_i32(evidence.r#type) {
-                    Some(r#type) => match r#type {
-                        EvidenceType::DuplicateVote => {
-                            pos::types::SlashType::DuplicateVote
-                        }
-                        EvidenceType::LightClientAttack => {
-                            pos::types::SlashType::LightClientAttack
-                        }
-                        EvidenceType::Unknown => {
-                            tracing::error!(
-                                "Unknown evidence: {:#?}",
-                                evidence
-                            );
-                            continue;
-                        }
-                    },
-                    None => {
-                        tracing::error!(
-                            "Unexpected evidence type {}",
-                            evidence.r#type
-                        );
-                        continue;
-                    }
-                };
-                let validator_raw_hash = match evidence.validator {
-                    Some(validator) => {
-                        match String::from_utf8(validator.address) {
-                            Ok(raw_hash) => raw_hash,
-                            Err(err) => {
-                                tracing::error!(
-                                    "Evidence failed to decode validator \
-                                     address from utf-8 with {}",
-                                    err
-                                );
-                                continue;
-                            }
-                        }
-                    }
-                    None => {
-                        tracing::error!(
-                            "Evidence without a validator {:#?}",
-                            evidence
-                        );
-                        continue;
-                    }
-                };
-                let validator = match self
-                    .storage
-                    .read_validator_address_raw_hash(&validator_raw_hash)
-                {
-                    Some(validator) => validator,
-                    None => {
-                        tracing::error!(
-                            "Cannot find validator's address from raw hash {}",
-                            validator_raw_hash
-                        );
-                        continue;
-                    }
-                };
-                tracing::info!(
-                    "Slashing {} for {} in epoch {}, block height {}",
-                    evidence_epoch,
-                    slash_type,
-                    validator,
-                    evidence_height
-                );
-                if let Err(err) = self.storage.slash(
-                    &pos_params,
-                    current_epoch,
-                    evidence_epoch,
-                    evidence_height,
-                    slash_type,
-                    &validator,
-                ) {
-                    tracing::error!("Error in slashing: {}", err);
-                }
-            }
-        }
+    /// Read the maximum block gas and the maximum tx gas from the protocol
+    /// parameters
+    fn gas_limits(&self) -> (u64, u64) {
+        let ((max_block_gas, max_tx_gas), _gas) =
+            parameters::read_gas_limit_parameters(&self.storage)
+                .expect("Couldn't read the gas limit parameters");
+        (max_block_gas, max_tx_gas)
     }
 
     #[cfg(not(feature = "ABCI"))]
@@ -545,6 +751,27 @@ where
         self.write_log
             .commit_block(&mut self.storage)
             .expect("Expected committing block write log success");
+        // The changes are collected before they're flushed to the DB
+        let key_changes = self.key_watcher.has_subscribers().then(|| {
+            let height = self.storage.block.height;
+            self.storage
+                .block_changes()
+                .map(|(key, value)| KeyChange {
+                    key: key.clone(),
+                    value: value.cloned(),
+                    height,
+                })
+                .collect()
+        });
+        if let Some(indexer) = &mut self.indexer {
+            indexer.add_block_changes(self.storage.block_changes());
+        }
+        let state_diff = self.state_diff_writer.is_some().then(|| {
+            StateDiff::new(
+                self.storage.block.height,
+                self.storage.block_changes(),
+            )
+        });
         // store the block's data in DB
         self.storage.commit().unwrap_or_else(|e| {
             tracing::error!(
@@ -552,6 +779,24 @@ where
                 e
             )
         });
+        if let Some(indexer) = &mut self.indexer {
+            indexer.commit_block().unwrap_or_else(|e| {
+                tracing::error!(
+                    "Encountered an indexer error while committing a block {}",
+                    e
+                )
+            });
+        }
+        if let Some(key_changes) = key_changes {
+            self.key_watcher.publish(key_changes);
+        }
+        if let (Some(writer), Some(diff)) =
+            (&self.state_diff_writer, state_diff)
+        {
+            writer.send(diff);
+        }
+        self.take_snapshot();
+        self.prune_blocks();
 
         let root = self.storage.merkle_root();
         tracing::info!(
@@ -563,29 +808,171 @@ where
         response
     }
 
+    /// Prune the state of the past blocks that are no longer kept with the
+    /// last committed block. The blocks are pruned from the highest one down
+    /// to the first one that is already pruned, so that the blocks that were
+    /// kept before the pruning was enabled are also pruned. The blocks kept
+    /// every few blocks below the first pruned one are not.
+    fn prune_blocks(&mut self) {
+        let pruned_height =
+            match self.pruning.pruned_height(self.storage.last_height) {
+                Some(height) => height,
+                None => return,
+            };
+        let prune_diffs = self.pruning.prunes_diffs();
+        for height in (1..=pruned_height.0).rev().map(BlockHeight) {
+            if self.pruning.keeps(height) {
+                continue;
+            }
+            match self.storage.has_state_at_height(height) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => {
+                    tracing::error!(
+                        "Encountered a storage error while pruning the block \
+                         at height {}: {}",
+                        height,
+                        e
+                    );
+                    break;
+                }
+            }
+            if let Err(e) = self.storage.db.prune_block(height, prune_diffs) {
+                tracing::error!(
+                    "Encountered a storage error while pruning the block at \
+                     height {}: {}",
+                    height,
+                    e
+                );
+                break;
+            }
+            tracing::debug!("Pruned the block at height {}", height);
+        }
+    }
+
     /// Validate a transaction request. On success, the transaction will
     /// included in the mempool and propagated to peers, otherwise it will be
     /// rejected.
+    ///
+    /// Only wrapper txs are accepted. We check the signature, the chain ID,
+    /// the epoch, the gas limit, the ciphertext and the balance of the fee
+    /// payer and reject the txs whose payload is already in the queue of txs
+    /// to be decrypted.
+    ///
+    /// Error codes:
+    ///   0: Ok
+    ///   1: Invalid tx
+    ///   2: Tx is invalidly signed
+    ///   9: The payload of the tx is already queued
+    ///   10: The tx was made for an epoch that already ended
     pub fn mempool_validate(
         &self,
         tx_bytes: &[u8],
         r#_type: MempoolTxType,
     ) -> response::CheckTx {
         let mut response = response::CheckTx::default();
-        match Tx::try_from(tx_bytes).map_err(Error::TxDecoding) {
-            Ok(_) => response.log = String::from("Mempool validation passed"),
+        let tx = match Tx::try_from(tx_bytes).map_err(Error::TxDecoding) {
+            Ok(tx) => tx,
             Err(msg) => {
-                response.code = 1;
+                response.code = ResultCode::InvalidTx.into();
                 response.log = msg.to_string();
+                return response;
+            }
+        };
+        let wrapper = match process_tx(tx) {
+            Ok(TxType::Wrapper(wrapper)) => wrapper,
+            Ok(_) => {
+                response.code = ResultCode::InvalidTx.into();
+                response.log = "Mempool validation failed: Only wrapper \
+                                transactions can be submitted"
+                    .into();
+                return response;
+            }
+            // This occurs if the wrapper / protocol tx signature is invalid
+            Err(err) => {
+                response.code = ResultCode::InvalidSig.into();
+                response.log = format!("Mempool validation failed: {}", err);
+                return response;
             }
+        };
+
+        if wrapper.chain_id != self.chain_id {
+            response.code = ResultCode::InvalidTx.into();
+            response.log = format!(
+                "Mempool validation failed: The tx was made for chain ID {}, \
+                 but this chain ID is {}",
+                wrapper.chain_id, self.chain_id
+            );
+            return response;
+        }
+
+        if wrapper.epoch < self.storage.block.epoch {
+            response.code = ResultCode::Expired.into();
+            response.log = format!(
+                "Mempool validation failed: The tx was made for epoch {}, \
+                 but the current epoch is {}",
+                wrapper.epoch, self.storage.block.epoch
+            );
+            return response;
+        }
+
+        let gas_limit = u64::from(&wrapper.gas_limit);
+        let (_, max_tx_gas) = self.gas_limits();
+        if gas_limit > max_tx_gas {
+            response.code = ResultCode::InvalidTx.into();
+            response.log = format!(
+                "Mempool validation failed: The gas limit {} exceeds the \
+                 maximum transaction gas limit {}",
+                gas_limit, max_tx_gas
+            );
+            return response;
+        }
+
+        if !wrapper.validate_ciphertext() {
+            response.code = ResultCode::InvalidTx.into();
+            response.log = format!(
+                "Mempool validation failed: The ciphertext of the wrapped tx \
+                 {} is invalid",
+                hash_tx(tx_bytes)
+            );
+            return response;
         }
+
+        let balance = self
+            .get_balance(&wrapper.fee.token, &wrapper.fee_payer())
+            .unwrap_or_default();
+        if wrapper.fee.amount > balance {
+            response.code = ResultCode::InvalidTx.into();
+            response.log = "Mempool validation failed: The address given \
+                            does not have sufficient balance to pay fee"
+                .into();
+            return response;
+        }
+
+        if self
+            .storage
+            .tx_queue
+            .iter()
+            .any(|queued| queued.tx_hash == wrapper.tx_hash)
+        {
+            response.code = ResultCode::Replay.into();
+            response.log = format!(
+                "Mempool validation failed: The wrapped tx {} has already \
+                 been included in a block",
+                wrapper.tx_hash
+            );
+            return response;
+        }
+
+        response.log = String::from("Mempool validation passed");
         response
     }
 
     /// Simulate validation and application of a transaction.
     fn dry_run_tx(&self, tx_bytes: &[u8]) -> response::Query {
         let mut response = response::Query::default();
-        let mut gas_meter = BlockGasMeter::default();
+        let (max_block_gas, max_tx_gas) = self.gas_limits();
+        let mut gas_meter = BlockGasMeter::new(max_block_gas, max_tx_gas);
         let mut write_log = WriteLog::default();
         let mut vp_wasm_cache = self.vp_wasm_cache.read_only();
         let mut tx_wasm_cache = self.tx_wasm_cache.read_only();
@@ -672,14 +1059,15 @@ mod test_utils {
     use anoma::types::storage::{BlockHash, Epoch, Header};
     use anoma::types::transaction::Fee;
     use tempfile::tempdir;
-    #[cfg(not(feature = "ABCI"))]
-    use tendermint_proto::abci::{RequestInitChain, RequestProcessProposal};
-    #[cfg(not(feature = "ABCI"))]
-    use tendermint_proto::google::protobuf::Timestamp;
-    #[cfg(feature = "ABCI")]
-    use tendermint_proto_abci::abci::{RequestDeliverTx, RequestInitChain};
     #[cfg(feature = "ABCI")]
-    use tendermint_proto_abci::google::protobuf::Timestamp;
+    use tendermint_compat::tendermint_proto::abci::{
+        RequestDeliverTx, RequestInitChain,
+    };
+    #[cfg(not(feature = "ABCI"))]
+    use tendermint_compat::tendermint_proto::abci::{
+        RequestInitChain, RequestProcessProposal,
+    };
+    use tendermint_compat::tendermint_proto::google::protobuf::Timestamp;
     use tokio::sync::mpsc::UnboundedReceiver;
 
     use super::*;
@@ -871,6 +1259,7 @@ mod test_utils {
                     next_validators_hash: Hash([0; 32]),
                 },
                 byzantine_validators: vec![],
+                votes: vec![],
                 txs: vec![],
             }
         }
@@ -913,6 +1302,7 @@ mod test_utils {
             0.into(),
             tx,
             Default::default(),
+            ChainId::default(),
         );
         shell.storage.tx_queue.push(wrapper);
         // Artificially increase the block height so that chain
@@ -928,6 +1318,8 @@ mod test_utils {
             .write_block(BlockStateWrite {
                 merkle_tree_stores: stores,
                 header: None,
+                block_request: None,
+                block_gas: 0,
                 hash: &hash,
                 height: BlockHeight(1),
                 epoch: Epoch(0),
@@ -957,4 +1349,335 @@ mod test_utils {
         );
         assert!(!shell.storage.tx_queue.is_empty());
     }
+
+    /// Test that the blocks below the recent ones are pruned, except for the
+    /// ones that are kept every few blocks.
+    #[test]
+    fn test_prune_blocks() {
+        let (mut shell, _) = setup();
+        for height in 1..=6 {
+            shell
+                .storage
+                .begin_block(BlockHash::default(), BlockHeight(height))
+                .unwrap();
+            shell.storage.commit().unwrap();
+        }
+        shell.pruning = config::Pruning {
+            keep_recent: Some(3),
+            keep_every: Some(2),
+        };
+        shell.prune_blocks();
+        let has_state = |shell: &TestShell, height| {
+            shell
+                .storage
+                .has_state_at_height(BlockHeight(height))
+                .unwrap()
+        };
+        assert!(!has_state(&shell, 1));
+        assert!(has_state(&shell, 2));
+        assert!(!has_state(&shell, 3));
+        assert!((4..=6).all(|height| has_state(&shell, height)));
+        // The diffs are needed to read the state of the kept blocks
+        assert!(!shell.pruning.prunes_diffs());
+
+        shell
+            .storage
+            .begin_block(BlockHash::default(), BlockHeight(7))
+            .unwrap();
+        shell.storage.commit().unwrap();
+        shell.prune_blocks();
+        assert!((4..=7).all(|height| has_state(&shell, height)));
+
+        // Once the blocks are no longer kept every few blocks, the block 4 is
+        // pruned, but not the block 2 that is below the pruned block 3
+        shell.pruning.keep_every = None;
+        shell.prune_blocks();
+        assert!(!has_state(&shell, 4));
+        assert!(has_state(&shell, 2));
+
+        // Pruning the blocks with multi-digit heights doesn't touch the
+        // recent ones
+        for height in 8..=120 {
+            shell
+                .storage
+                .begin_block(BlockHash::default(), BlockHeight(height))
+                .unwrap();
+            shell.storage.commit().unwrap();
+        }
+        shell.prune_blocks();
+        assert!((5..=117).all(|height| !has_state(&shell, height)));
+        assert!((118..=120).all(|height| has_state(&shell, height)));
+        assert!(has_state(&shell, 2));
+    }
+
+    /// Test that the changes of the watched keys in a block are published on
+    /// its commit.
+    #[test]
+    fn test_commit_publishes_key_changes() {
+        use crate::node::ledger::key_watch::WatchFilter;
+
+        let (mut shell, _) = setup();
+        let key = Key::parse("watched/key").unwrap();
+        let mut watch = shell.key_watcher.subscribe(WatchFilter {
+            keys: vec![key.clone()],
+            prefixes: vec![],
+        });
+        shell
+            .storage
+            .begin_block(BlockHash::default(), BlockHeight(1))
+            .unwrap();
+        shell.storage.write(&key, vec![1]).unwrap();
+        shell.commit();
+        let changes =
+            futures::executor::block_on(watch.next_changes()).unwrap();
+        assert_eq!(
+            changes,
+            vec![KeyChange {
+                key,
+                value: Some(vec![1]),
+                height: BlockHeight(1),
+            }]
+        );
+    }
+
+    /// Test that the diff of the state of a block is written to the state
+    /// diff sink on its commit.
+    #[test]
+    fn test_commit_writes_state_diff() {
+        use std::time::Duration;
+
+        use crate::config::StateDiffSink;
+        use crate::node::ledger::state_diff::read_state_diff;
+
+        let (mut shell, _) = setup();
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state-diffs");
+        shell.state_diff_writer = Some(
+            StateDiffWriter::start(StateDiffSink::File(path.clone())).unwrap(),
+        );
+        let key = Key::parse("changed/key").unwrap();
+        shell
+            .storage
+            .begin_block(BlockHash::default(), BlockHeight(1))
+            .unwrap();
+        shell.storage.write(&key, vec![1]).unwrap();
+        shell.commit();
+
+        // The diff is written from the writer's thread
+        let mut diff = None;
+        for _ in 0..100 {
+            diff = std::fs::File::open(&path)
+                .ok()
+                .and_then(|file| read_state_diff(file).ok().flatten());
+            if diff.is_some() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let diff = diff.expect("The state diff should be written");
+        assert_eq!(diff.height, BlockHeight(1));
+        assert!(diff.changes.contains(&(key.to_string(), Some(vec![1]))));
+    }
+}
+
+/// Test the mempool validation of txs submitted to the ledger
+#[cfg(test)]
+mod test_mempool_validate {
+    use anoma::ledger::gas::TRANSACTION_GAS_LIMIT;
+    use anoma::types::address::xan;
+    use anoma::types::storage::Epoch;
+    use anoma::types::token::Amount;
+    use anoma::types::transaction::Fee;
+
+    use super::test_utils::{gen_keypair, setup};
+    use super::*;
+
+    /// Create a signed wrapper around a dummy tx with the given fee
+    /// amount and gas limit
+    fn signed_wrapper(
+        keypair: &common::SecretKey,
+        amount: Amount,
+        gas_limit: u64,
+    ) -> (WrapperTx, Tx) {
+        signed_wrapper_for_chain(keypair, amount, gas_limit, ChainId::default())
+    }
+
+    /// Create a signed wrapper around a dummy tx for the given chain
+    fn signed_wrapper_for_chain(
+        keypair: &common::SecretKey,
+        amount: Amount,
+        gas_limit: u64,
+        chain_id: ChainId,
+    ) -> (WrapperTx, Tx) {
+        let tx = Tx::new(
+            "wasm_code".as_bytes().to_owned(),
+            Some("transaction data".as_bytes().to_owned()),
+        );
+        let wrapper = WrapperTx::new(
+            Fee {
+                amount,
+                token: xan(),
+            },
+            keypair,
+            Epoch(0),
+            gas_limit.into(),
+            tx,
+            Default::default(),
+            chain_id,
+        );
+        let signed = wrapper.sign(keypair).expect("Test failed");
+        (wrapper, signed)
+    }
+
+    /// Test that a valid wrapper tx is admitted to the mempool
+    #[test]
+    fn test_valid_wrapper_accepted() {
+        let (shell, _) = setup();
+        let keypair = crate::wallet::defaults::daewon_keypair();
+        let (_, tx) = signed_wrapper(&keypair, Amount::whole(100), 0);
+
+        let response = shell
+            .mempool_validate(&tx.to_bytes(), MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ResultCode::Ok));
+    }
+
+    /// Test that the txs that cannot be decoded or that are not wrapper
+    /// txs are rejected
+    #[test]
+    fn test_invalid_tx_rejected() {
+        let (shell, _) = setup();
+
+        let response = shell.mempool_validate(
+            "not a tx".as_bytes(),
+            MempoolTxType::NewTransaction,
+        );
+        assert_eq!(response.code, u32::from(ResultCode::InvalidTx));
+
+        let tx = Tx::new(
+            "wasm_code".as_bytes().to_owned(),
+            Some("transaction data".as_bytes().to_owned()),
+        );
+        let tx = Tx::from(TxType::Raw(tx));
+        let response = shell
+            .mempool_validate(&tx.to_bytes(), MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ResultCode::InvalidTx));
+    }
+
+    /// Test that an unsigned wrapper tx is rejected
+    #[test]
+    fn test_unsigned_wrapper_rejected() {
+        let (shell, _) = setup();
+        let keypair = gen_keypair();
+        let (wrapper, _) = signed_wrapper(&keypair, 0.into(), 0);
+        let tx = Tx::new(
+            vec![],
+            Some(TxType::Wrapper(wrapper).try_to_vec().expect("Test failed")),
+        );
+
+        let response = shell
+            .mempool_validate(&tx.to_bytes(), MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ResultCode::InvalidSig));
+    }
+
+    /// Test that a wrapper tx is rejected if its fee payer cannot pay the
+    /// fee
+    #[test]
+    fn test_insufficient_balance_rejected() {
+        let (shell, _) = setup();
+        let keypair = gen_keypair();
+        let (_, tx) = signed_wrapper(&keypair, 100.into(), 0);
+
+        let response = shell
+            .mempool_validate(&tx.to_bytes(), MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ResultCode::InvalidTx));
+        assert_eq!(
+            response.log,
+            "Mempool validation failed: The address given does not have \
+             sufficient balance to pay fee"
+        );
+    }
+
+    /// Test that a wrapper tx made for another chain is rejected
+    #[test]
+    fn test_other_chain_id_rejected() {
+        let (shell, _) = setup();
+        let keypair = crate::wallet::defaults::daewon_keypair();
+        let chain_id = ChainId::from_genesis(
+            FromStr::from_str("other-chain").unwrap(),
+            "other genesis",
+        );
+        let (_, tx) =
+            signed_wrapper_for_chain(&keypair, Amount::whole(100), 0, chain_id);
+
+        let response = shell
+            .mempool_validate(&tx.to_bytes(), MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ResultCode::InvalidTx));
+    }
+
+    /// Test that a wrapper tx is rejected if its gas limit is above the
+    /// maximum transaction gas limit
+    #[test]
+    fn test_gas_limit_too_high_rejected() {
+        let (shell, _) = setup();
+        let keypair = crate::wallet::defaults::daewon_keypair();
+        let (_, tx) = signed_wrapper(
+            &keypair,
+            Amount::whole(100),
+            TRANSACTION_GAS_LIMIT + 1,
+        );
+
+        let response = shell
+            .mempool_validate(&tx.to_bytes(), MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ResultCode::InvalidTx));
+    }
+
+    /// Test that a wrapper tx is rejected if its gas limit is above the
+    /// maximum transaction gas limit set in the protocol parameters
+    #[test]
+    fn test_gas_limit_above_max_tx_gas_rejected() {
+        let (mut shell, _) = setup();
+        parameters::update_max_tx_gas_parameter(&mut shell.storage, 1_000_000)
+            .expect("Test failed");
+        let keypair = crate::wallet::defaults::daewon_keypair();
+
+        let (_, tx) = signed_wrapper(&keypair, Amount::whole(100), 1_000_000);
+        let response = shell
+            .mempool_validate(&tx.to_bytes(), MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ResultCode::Ok));
+
+        let (_, tx) = signed_wrapper(&keypair, Amount::whole(100), 2_000_000);
+        let response = shell
+            .mempool_validate(&tx.to_bytes(), MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ResultCode::InvalidTx));
+    }
+
+    /// Test that a wrapper tx whose payload is already in the tx queue is
+    /// rejected
+    #[test]
+    fn test_queued_tx_rejected() {
+        let (mut shell, _) = setup();
+        let keypair = crate::wallet::defaults::daewon_keypair();
+        let (wrapper, tx) = signed_wrapper(&keypair, Amount::whole(100), 0);
+        shell.enqueue_tx(wrapper);
+
+        let response = shell.mempool_validate(
+            &tx.to_bytes(),
+            MempoolTxType::RecheckTransaction,
+        );
+        assert_eq!(response.code, u32::from(ResultCode::Replay));
+    }
+
+    /// Test that a wrapper tx made for an epoch that already ended is
+    /// rejected as expired
+    #[test]
+    fn test_expired_wrapper_rejected() {
+        let (mut shell, _) = setup();
+        let keypair = crate::wallet::defaults::daewon_keypair();
+        let (_, tx) = signed_wrapper(&keypair, Amount::whole(100), 0);
+        shell.storage.block.epoch = Epoch(1);
+
+        let response = shell
+            .mempool_validate(&tx.to_bytes(), MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ResultCode::Expired));
+    }
 }
@@ -0,0 +1,33 @@
+//! The check of the invariants of the PoS state after each epoch transition,
+//! which is only built with the `pos-invariants` feature. The violated
+//! invariants are logged and, if configured, the node is halted, so that a
+//! bug in the PoS state transitions isn't committed to more blocks.
+
+use anoma::ledger::pos::invariants::check_invariants;
+
+use super::*;
+use crate::config::PosInvariantsCheck;
+
+impl<D, H> Shell<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    /// Check the PoS invariants, if enabled in the config, and log the
+    /// violated ones. Fails on a violation if the node must halt.
+    pub(super) fn check_pos_invariants(&self) -> Result<()> {
+        let check = match self.pos_invariants {
+            Some(check) => check,
+            None => return Ok(()),
+        };
+        let violations =
+            check_invariants(&self.storage).map_err(Error::Storage)?;
+        for violation in &violations {
+            tracing::error!("PoS invariant violated: {}", violation);
+        }
+        if !violations.is_empty() && check == PosInvariantsCheck::Halt {
+            return Err(Error::PosInvariants(violations.len()));
+        }
+        Ok(())
+    }
+}
@@ -2,9 +2,12 @@
 
 #[cfg(not(feature = "ABCI"))]
 mod prepare_block {
-    use tendermint_proto::abci::TxRecord;
+    use std::cmp::Ordering;
+
+    use tendermint_compat::tendermint_proto::abci::TxRecord;
 
     use super::super::*;
+    use crate::config::TxOrdering;
     use crate::node::ledger::shims::abcipp_shim_types::shim::TxBytes;
 
     impl<D, H> Shell<D, H>
@@ -18,6 +21,13 @@ mod prepare_block {
         /// by tendermint. The rest of the block is filled with decryptions
         /// of the wrapper txs from the previously committed block.
         ///
+        /// The new wrapper txs are included in the order given by the
+        /// configured [`TxOrdering`] until the sum of their gas limits
+        /// reaches the maximum block gas or the configured proposal gas
+        /// limit, if it's lower. The wrapper txs whose gas limit is above the
+        /// maximum tx gas are removed. The decrypted txs must always follow
+        /// the order of their wrappers.
+        ///
         /// INVARIANT: Any changes applied in this method must be reverted if
         /// the proposal is rejected (unless we can simply overwrite
         /// them in the next block).
@@ -37,20 +47,49 @@ mod prepare_block {
                 // filter in half of the new txs from Tendermint, only keeping
                 // wrappers
                 let number_of_new_txs = 1 + req.txs.len() / 2;
-                let mut txs: Vec<TxRecord> = req
-                    .txs
-                    .into_iter()
-                    .take(number_of_new_txs)
-                    .map(|tx_bytes| {
-                        if let Ok(Ok(TxType::Wrapper(_))) =
-                            Tx::try_from(tx_bytes.as_slice()).map(process_tx)
-                        {
-                            record::keep(tx_bytes)
-                        } else {
-                            record::remove(tx_bytes)
+                let mut txs: Vec<TxRecord> = vec![];
+                let mut wrappers: Vec<(TxBytes, WrapperTx)> = vec![];
+                for tx_bytes in req.txs {
+                    if let Ok(Ok(TxType::Wrapper(wrapper))) =
+                        Tx::try_from(tx_bytes.as_slice()).map(process_tx)
+                    {
+                        wrappers.push((tx_bytes, wrapper));
+                    } else {
+                        txs.push(record::remove(tx_bytes));
+                    }
+                }
+                if let TxOrdering::FeePriority = self.tx_ordering {
+                    // the sort is stable, so the txs with the same gas price
+                    // stay in the order given by the mempool
+                    wrappers.sort_by(|(_, a), (_, b)| cmp_gas_price(b, a));
+                }
+                let (max_block_gas, max_tx_gas) = self.gas_limits();
+                let proposal_gas_limit = match self.proposal_gas_limit {
+                    Some(limit) => std::cmp::min(limit, max_block_gas),
+                    None => max_block_gas,
+                };
+                let mut proposal_gas: u64 = 0;
+                let mut num_included = 0;
+                for (tx_bytes, wrapper) in wrappers {
+                    if num_included >= number_of_new_txs {
+                        break;
+                    }
+                    let gas_limit = u64::from(&wrapper.gas_limit);
+                    if gas_limit > max_tx_gas {
+                        // the tx would be rejected by process proposal
+                        txs.push(record::remove(tx_bytes));
+                        continue;
+                    }
+                    match proposal_gas.checked_add(gas_limit) {
+                        Some(gas) if gas <= proposal_gas_limit => {
+                            proposal_gas = gas
                         }
-                    })
-                    .collect();
+                        // the tx is left in the mempool for a next block
+                        _ => continue,
+                    }
+                    txs.push(record::keep(tx_bytes));
+                    num_included += 1;
+                }
 
                 // decrypt the wrapper txs included in the previous block
                 let mut decrypted_txs = self
@@ -80,10 +119,20 @@ mod prepare_block {
         }
     }
 
+    /// Compare the gas prices offered by two wrapper txs, i.e. their fee
+    /// amounts per unit of their gas limits
+    fn cmp_gas_price(a: &WrapperTx, b: &WrapperTx) -> Ordering {
+        let a_gas = std::cmp::max(u64::from(&a.gas_limit), 1) as u128;
+        let b_gas = std::cmp::max(u64::from(&b.gas_limit), 1) as u128;
+        let a_fee = u64::from(a.fee.amount) as u128;
+        let b_fee = u64::from(b.fee.amount) as u128;
+        (a_fee * b_gas).cmp(&(b_fee * a_gas))
+    }
+
     /// Functions for creating the appropriate TxRecord given the
     /// numeric code
     pub(super) mod record {
-        use tendermint_proto::abci::tx_record::TxAction;
+        use tendermint_compat::tendermint_proto::abci::tx_record::TxAction;
 
         use super::*;
 
@@ -119,7 +168,7 @@ mod prepare_block {
         use anoma::types::address::xan;
         use anoma::types::storage::Epoch;
         use anoma::types::transaction::Fee;
-        use tendermint_proto::abci::tx_record::TxAction;
+        use tendermint_compat::tendermint_proto::abci::tx_record::TxAction;
 
         use super::*;
         use crate::node::ledger::shell::test_utils::{gen_keypair, TestShell};
@@ -170,6 +219,7 @@ mod prepare_block {
                         0.into(),
                         tx,
                         Default::default(),
+                        ChainId::default(),
                     )
                     .try_to_vec()
                     .expect("Test failed"),
@@ -225,6 +275,7 @@ mod prepare_block {
                     0.into(),
                     tx,
                     Default::default(),
+                    ChainId::default(),
                 );
                 let wrapper = wrapper_tx.sign(&keypair).expect("Test failed");
                 shell.enqueue_tx(wrapper_tx);
@@ -267,6 +318,145 @@ mod prepare_block {
             // check that the order of the txs is correct
             assert_eq!(received, expected_txs);
         }
+
+        /// Create a signed wrapper tx with the given fee amount and gas limit
+        fn signed_wrapper(
+            keypair: &common::SecretKey,
+            amount: u64,
+            gas_limit: u64,
+        ) -> Tx {
+            let tx = Tx::new(
+                "wasm_code".as_bytes().to_owned(),
+                Some(format!("transaction data: {}", amount).into_bytes()),
+            );
+            WrapperTx::new(
+                Fee {
+                    amount: amount.into(),
+                    token: xan(),
+                },
+                keypair,
+                Epoch(0),
+                gas_limit.into(),
+                tx,
+                Default::default(),
+                ChainId::default(),
+            )
+            .sign(keypair)
+            .expect("Test failed")
+        }
+
+        /// Get the data of the txs kept in the proposal
+        fn kept_txs_data(tx_records: Vec<TxRecord>) -> Vec<Vec<u8>> {
+            tx_records
+                .into_iter()
+                .filter(|record| record.action == TxAction::Unmodified as i32)
+                .map(|record| {
+                    Tx::try_from(record.tx.as_slice())
+                        .expect("Test failed")
+                        .data
+                        .expect("Test failed")
+                })
+                .collect()
+        }
+
+        /// Test that with the fee priority ordering, the new wrapper txs
+        /// with the highest gas price are included first
+        #[test]
+        fn test_fee_priority_ordering() {
+            let (mut shell, _) = TestShell::new();
+            shell.tx_ordering = TxOrdering::FeePriority;
+            let keypair = gen_keypair();
+            let txs: Vec<Tx> = [1, 3, 2]
+                .into_iter()
+                .map(|amount| signed_wrapper(&keypair, amount, 1_000_000))
+                .collect();
+            let req = RequestPrepareProposal {
+                txs: txs.iter().map(|tx| tx.to_bytes()).collect(),
+                max_tx_bytes: 0,
+                ..Default::default()
+            };
+            // half of the new txs are included
+            let expected: Vec<Vec<u8>> = [&txs[1], &txs[2]]
+                .into_iter()
+                .map(|tx| tx.data.clone().expect("Test failed"))
+                .collect();
+            assert_eq!(
+                kept_txs_data(shell.prepare_proposal(req).tx_records),
+                expected
+            );
+        }
+
+        /// Test that the new wrapper txs are not included beyond the
+        /// proposal gas limit
+        #[test]
+        fn test_proposal_gas_limit() {
+            let (mut shell, _) = TestShell::new();
+            shell.proposal_gas_limit = Some(2_000_000);
+            let keypair = gen_keypair();
+            let txs: Vec<Tx> = [1, 2, 3, 4]
+                .into_iter()
+                .map(|amount| signed_wrapper(&keypair, amount, 1_000_000))
+                .collect();
+            let req = RequestPrepareProposal {
+                txs: txs.iter().map(|tx| tx.to_bytes()).collect(),
+                max_tx_bytes: 0,
+                ..Default::default()
+            };
+            let expected: Vec<Vec<u8>> = txs[..2]
+                .iter()
+                .map(|tx| tx.data.clone().expect("Test failed"))
+                .collect();
+            assert_eq!(
+                kept_txs_data(shell.prepare_proposal(req).tx_records),
+                expected
+            );
+        }
+
+        /// Test that the new wrapper txs are not included beyond the
+        /// maximum block gas and that the ones over the maximum tx gas are
+        /// removed
+        #[test]
+        fn test_gas_limit_parameters() {
+            let (mut shell, _) = TestShell::new();
+            parameters::update_max_block_gas_parameter(
+                &mut shell.storage,
+                3_000_000,
+            )
+            .expect("Test failed");
+            parameters::update_max_tx_gas_parameter(
+                &mut shell.storage,
+                2_000_000,
+            )
+            .expect("Test failed");
+            let keypair = gen_keypair();
+            let txs: Vec<Tx> = [
+                (1, 2_000_000),
+                (2, 3_000_000),
+                (3, 1_000_000),
+                (4, 1_000_000),
+            ]
+            .into_iter()
+            .map(|(amount, gas_limit)| {
+                signed_wrapper(&keypair, amount, gas_limit)
+            })
+            .collect();
+            let req = RequestPrepareProposal {
+                txs: txs.iter().map(|tx| tx.to_bytes()).collect(),
+                max_tx_bytes: 0,
+                ..Default::default()
+            };
+            let tx_records = shell.prepare_proposal(req).tx_records;
+            let expected: Vec<Vec<u8>> = [&txs[0], &txs[2]]
+                .iter()
+                .map(|tx| tx.data.clone().expect("Test failed"))
+                .collect();
+            assert_eq!(kept_txs_data(tx_records.clone()), expected);
+            let removed = tx_records
+                .iter()
+                .filter(|record| record.action == TxAction::Removed as i32)
+                .count();
+            assert_eq!(removed, 1);
+        }
     }
 }
 
@@ -1,13 +1,13 @@
 //! Implementation of the ['VerifyHeader`], [`ProcessProposal`],
 //! and [`RevertProposal`] ABCI++ methods for the Shell
 #[cfg(not(feature = "ABCI"))]
-use tendermint_proto::abci::response_process_proposal::ProposalStatus;
+use tendermint_compat::tendermint_proto::abci::response_process_proposal::ProposalStatus;
+#[cfg(feature = "ABCI")]
+use tendermint_compat::tendermint_proto::abci::RequestDeliverTx;
 #[cfg(not(feature = "ABCI"))]
-use tendermint_proto::abci::{
+use tendermint_compat::tendermint_proto::abci::{
     ExecTxResult, RequestProcessProposal, ResponseProcessProposal,
 };
-#[cfg(feature = "ABCI")]
-use tendermint_proto_abci::abci::RequestDeliverTx;
 
 use super::*;
 
@@ -33,6 +33,7 @@ where
         &mut self,
         req: RequestProcessProposal,
     ) -> ResponseProcessProposal {
+        self.proposal_gas = 0;
         let tx_results: Vec<ExecTxResult> = req
             .txs
             .iter()
@@ -54,6 +55,9 @@ where
 
     /// Checks if the Tx can be deserialized from bytes. Checks the fees and
     /// signatures of the fee payer for a transaction if it is a wrapper tx.
+    /// The gas limit of a wrapper tx must not exceed the maximum tx gas and,
+    /// together with the gas limits of the wrapper txs accepted before it in
+    /// the block, the maximum block gas.
     ///
     /// Checks validity of a decrypted tx or that a tx marked un-decryptable
     /// is in fact so. Also checks that decrypted txs were submitted in
@@ -75,7 +79,7 @@ where
             Ok(tx) => tx,
             Err(_) => {
                 return TxResult {
-                    code: ErrorCodes::InvalidTx.into(),
+                    code: ResultCode::InvalidTx.into(),
                     info: "The submitted transaction was not deserializable"
                         .into(),
                 };
@@ -87,19 +91,19 @@ where
         match process_tx(tx) {
             // This occurs if the wrapper / protocol tx signature is invalid
             Err(err) => TxResult {
-                code: ErrorCodes::InvalidSig.into(),
+                code: ResultCode::InvalidSig.into(),
                 info: err.to_string(),
             },
             Ok(result) => match result {
                 // If it is a raw transaction, we do no further validation
                 TxType::Raw(_) => TxResult {
-                    code: ErrorCodes::InvalidTx.into(),
+                    code: ResultCode::InvalidTx.into(),
                     info: "Transaction rejected: Non-encrypted transactions \
                            are not supported"
                         .into(),
                 },
                 TxType::Protocol(_) => TxResult {
-                    code: ErrorCodes::InvalidTx.into(),
+                    code: ResultCode::InvalidTx.into(),
                     info: "Protocol transactions are a fun new feature that \
                            is coming soon to a blockchain near you. Patience."
                         .into(),
@@ -108,7 +112,7 @@ where
                     Some(wrapper) => {
                         if wrapper.tx_hash != tx.hash_commitment() {
                             TxResult {
-                                code: ErrorCodes::InvalidOrder.into(),
+                                code: ResultCode::InvalidOrder.into(),
                                 info: "Process proposal rejected a decrypted \
                                        transaction that violated the tx order \
                                        determined in the previous block"
@@ -116,14 +120,14 @@ where
                             }
                         } else if verify_decrypted_correctly(&tx, privkey) {
                             TxResult {
-                                code: ErrorCodes::Ok.into(),
+                                code: ResultCode::Ok.into(),
                                 info: "Process Proposal accepted this \
                                        transaction"
                                     .into(),
                             }
                         } else {
                             TxResult {
-                                code: ErrorCodes::InvalidTx.into(),
+                                code: ResultCode::InvalidTx.into(),
                                 info: "The encrypted payload of tx was \
                                        incorrectly marked as un-decryptable"
                                     .into(),
@@ -131,22 +135,87 @@ where
                         }
                     }
                     None => TxResult {
-                        code: ErrorCodes::ExtraTxs.into(),
+                        code: ResultCode::ExtraTxs.into(),
                         info: "Received more decrypted txs than expected"
                             .into(),
                     },
                 },
                 TxType::Wrapper(tx) => {
-                    // validate the ciphertext via Ferveo
-                    if !tx.validate_ciphertext() {
+                    let gas_limit = u64::from(&tx.gas_limit);
+                    let (max_block_gas, max_tx_gas) = self.gas_limits();
+                    let proposal_gas = self.proposal_gas.checked_add(gas_limit);
+                    // check the chain ID and validate the ciphertext via
+                    // Ferveo
+                    if tx.chain_id != self.chain_id {
+                        TxResult {
+                            code: ResultCode::InvalidTx.into(),
+                            info: format!(
+                                "The wrapped tx {} was made for chain ID {}, \
+                                 but this chain ID is {}",
+                                hash_tx(tx_bytes),
+                                tx.chain_id,
+                                self.chain_id
+                            ),
+                        }
+                    } else if tx.epoch < self.storage.block.epoch {
+                        TxResult {
+                            code: ResultCode::Expired.into(),
+                            info: format!(
+                                "The wrapped tx {} was made for epoch {}, \
+                                 but the current epoch is {}",
+                                hash_tx(tx_bytes),
+                                tx.epoch,
+                                self.storage.block.epoch
+                            ),
+                        }
+                    } else if self
+                        .storage
+                        .tx_queue
+                        .iter()
+                        .any(|queued| queued.tx_hash == tx.tx_hash)
+                    {
+                        TxResult {
+                            code: ResultCode::Replay.into(),
+                            info: format!(
+                                "The payload {} of the wrapped tx {} is \
+                                 already queued",
+                                tx.tx_hash,
+                                hash_tx(tx_bytes)
+                            ),
+                        }
+                    } else if !tx.validate_ciphertext() {
                         TxResult {
-                            code: ErrorCodes::InvalidTx.into(),
+                            code: ResultCode::InvalidTx.into(),
                             info: format!(
                                 "The ciphertext of the wrapped tx {} is \
                                  invalid",
                                 hash_tx(tx_bytes)
                             ),
                         }
+                    } else if gas_limit > max_tx_gas {
+                        TxResult {
+                            code: ResultCode::InvalidTx.into(),
+                            info: format!(
+                                "The gas limit {} of the wrapped tx {} \
+                                 exceeds the maximum tx gas {}",
+                                gas_limit,
+                                hash_tx(tx_bytes),
+                                max_tx_gas
+                            ),
+                        }
+                    } else if proposal_gas
+                        .map_or(true, |gas| gas > max_block_gas)
+                    {
+                        TxResult {
+                            code: ResultCode::InvalidTx.into(),
+                            info: format!(
+                                "The gas limit {} of the wrapped tx {} \
+                                 exceeds the remaining block gas {}",
+                                gas_limit,
+                                hash_tx(tx_bytes),
+                                max_block_gas.saturating_sub(self.proposal_gas)
+                            ),
+                        }
                     } else {
                         // check that the fee payer has sufficient balance
                         let balance = self
@@ -154,15 +223,17 @@ where
                             .unwrap_or_default();
 
                         if tx.fee.amount <= balance {
+                            self.proposal_gas =
+                                proposal_gas.unwrap_or_default();
                             TxResult {
-                                code: ErrorCodes::Ok.into(),
+                                code: ResultCode::Ok.into(),
                                 info: "Process proposal accepted this \
                                        transaction"
                                     .into(),
                             }
                         } else {
                             TxResult {
-                                code: ErrorCodes::InvalidTx.into(),
+                                code: ResultCode::InvalidTx.into(),
                                 info: "The address given does not have \
                                        sufficient balance to pay fee"
                                     .into(),
@@ -187,7 +258,7 @@ where
             Err(_) => {
                 return shim::request::ProcessedTx {
                     result: TxResult {
-                        code: ErrorCodes::InvalidTx.into(),
+                        code: ResultCode::InvalidTx.into(),
                         info: "The submitted transaction was not \
                                deserializable"
                             .into(),
@@ -206,7 +277,7 @@ where
             Ok(_) => {
                 return shim::request::ProcessedTx {
                     result: TxResult {
-                        code: ErrorCodes::InvalidTx.into(),
+                        code: ResultCode::InvalidTx.into(),
                         info: "Transaction rejected: Non-encrypted \
                                transactions are not supported"
                             .into(),
@@ -278,14 +349,8 @@ mod test_process_proposal {
     use anoma::types::transaction::encrypted::EncryptedTx;
     use anoma::types::transaction::{EncryptionKey, Fee};
     use borsh::BorshDeserialize;
-    #[cfg(not(feature = "ABCI"))]
-    use tendermint_proto::abci::RequestInitChain;
-    #[cfg(not(feature = "ABCI"))]
-    use tendermint_proto::google::protobuf::Timestamp;
-    #[cfg(feature = "ABCI")]
-    use tendermint_proto_abci::abci::RequestInitChain;
-    #[cfg(feature = "ABCI")]
-    use tendermint_proto_abci::google::protobuf::Timestamp;
+    use tendermint_compat::tendermint_proto::abci::RequestInitChain;
+    use tendermint_compat::tendermint_proto::google::protobuf::Timestamp;
 
     use super::*;
     #[cfg(not(feature = "ABCI"))]
@@ -314,6 +379,7 @@ mod test_process_proposal {
             0.into(),
             tx,
             Default::default(),
+            ChainId::default(),
         );
         let tx = Tx::new(
             vec![],
@@ -334,7 +400,7 @@ mod test_process_proposal {
         } else {
             panic!("Test failed")
         };
-        assert_eq!(response.result.code, u32::from(ErrorCodes::InvalidSig));
+        assert_eq!(response.result.code, u32::from(ResultCode::InvalidSig));
         assert_eq!(
             response.result.info,
             String::from("Wrapper transactions must be signed")
@@ -366,6 +432,7 @@ mod test_process_proposal {
             0.into(),
             tx,
             Default::default(),
+            ChainId::default(),
         )
         .sign(&keypair)
         .expect("Test failed");
@@ -419,7 +486,7 @@ mod test_process_proposal {
             panic!("Test failed")
         };
         let expected_error = "Signature verification failed: Invalid signature";
-        assert_eq!(response.result.code, u32::from(ErrorCodes::InvalidSig));
+        assert_eq!(response.result.code, u32::from(ResultCode::InvalidSig));
         assert!(
             response.result.info.contains(expected_error),
             "Result info {} doesn't contain the expected error {}",
@@ -433,6 +500,95 @@ mod test_process_proposal {
         }
     }
 
+    /// Test that a wrapper tx made for another chain is rejected by
+    /// [`process_proposal`]
+    #[test]
+    fn test_wrapper_other_chain_id_rejected() {
+        let (mut shell, _) = TestShell::new();
+        let keypair = gen_keypair();
+        let tx = Tx::new(
+            "wasm_code".as_bytes().to_owned(),
+            Some("transaction data".as_bytes().to_owned()),
+        );
+        let wrapper = WrapperTx::new(
+            Fee {
+                amount: 0.into(),
+                token: xan(),
+            },
+            &keypair,
+            Epoch(0),
+            0.into(),
+            tx,
+            Default::default(),
+            ChainId::from_genesis(
+                FromStr::from_str("other-chain").unwrap(),
+                "other genesis",
+            ),
+        )
+        .sign(&keypair)
+        .expect("Test failed");
+        let request = ProcessProposal {
+            txs: vec![wrapper.to_bytes()],
+        };
+
+        let response = if let [resp] = shell
+            .process_proposal(request)
+            .expect("Test failed")
+            .as_slice()
+        {
+            resp.clone()
+        } else {
+            panic!("Test failed")
+        };
+        assert_eq!(response.result.code, u32::from(ResultCode::InvalidTx));
+        #[cfg(feature = "ABCI")]
+        {
+            assert_eq!(response.tx, wrapper.to_bytes());
+            assert!(shell.shell.storage.tx_queue.is_empty())
+        }
+    }
+
+    /// Test that a wrapper made for an epoch that already ended is rejected
+    /// as expired
+    #[test]
+    fn test_expired_wrapper_rejected() {
+        let (mut shell, _) = TestShell::new();
+        let keypair = gen_keypair();
+        let tx = Tx::new(
+            "wasm_code".as_bytes().to_owned(),
+            Some("transaction data".as_bytes().to_owned()),
+        );
+        let wrapper = WrapperTx::new(
+            Fee {
+                amount: 0.into(),
+                token: xan(),
+            },
+            &keypair,
+            Epoch(0),
+            0.into(),
+            tx,
+            Default::default(),
+            ChainId::default(),
+        )
+        .sign(&keypair)
+        .expect("Test failed");
+        shell.shell.storage.block.epoch = Epoch(1);
+        let request = ProcessProposal {
+            txs: vec![wrapper.to_bytes()],
+        };
+
+        let response = if let [resp] = shell
+            .process_proposal(request)
+            .expect("Test failed")
+            .as_slice()
+        {
+            resp.clone()
+        } else {
+            panic!("Test failed")
+        };
+        assert_eq!(response.result.code, u32::from(ResultCode::Expired));
+    }
+
     /// Test that if the account submitting the tx is not known and the fee is
     /// non-zero, [`process_proposal`] rejects that tx
     #[test]
@@ -453,6 +609,7 @@ mod test_process_proposal {
             0.into(),
             tx,
             Default::default(),
+            ChainId::default(),
         )
         .sign(&keypair)
         .expect("Test failed");
@@ -468,7 +625,7 @@ mod test_process_proposal {
         } else {
             panic!("Test failed")
         };
-        assert_eq!(response.result.code, u32::from(ErrorCodes::InvalidTx));
+        assert_eq!(response.result.code, u32::from(ResultCode::InvalidTx));
         assert_eq!(
             response.result.info,
             "The address given does not have sufficient balance to pay fee"
@@ -511,6 +668,7 @@ mod test_process_proposal {
             0.into(),
             tx,
             Default::default(),
+            ChainId::default(),
         )
         .sign(&keypair)
         .expect("Test failed");
@@ -528,7 +686,7 @@ mod test_process_proposal {
         } else {
             panic!("Test failed")
         };
-        assert_eq!(response.result.code, u32::from(ErrorCodes::InvalidTx));
+        assert_eq!(response.result.code, u32::from(ResultCode::InvalidTx));
         assert_eq!(
             response.result.info,
             String::from(
@@ -542,6 +700,70 @@ mod test_process_proposal {
         }
     }
 
+    #[cfg(not(feature = "ABCI"))]
+    /// Test that a wrapper tx whose gas limit exceeds the maximum tx gas or
+    /// the remaining block gas is rejected by [`process_proposal`]
+    #[test]
+    fn test_wrapper_over_gas_limits() {
+        let (mut shell, _) = TestShell::new();
+        shell.init_chain(RequestInitChain {
+            time: Some(Timestamp {
+                seconds: 0,
+                nanos: 0,
+            }),
+            chain_id: ChainId::default().to_string(),
+            ..Default::default()
+        });
+        parameters::update_max_block_gas_parameter(
+            &mut shell.storage,
+            3_000_000,
+        )
+        .expect("Test failed");
+        parameters::update_max_tx_gas_parameter(&mut shell.storage, 2_000_000)
+            .expect("Test failed");
+        let keypair = crate::wallet::defaults::daewon_keypair();
+        let txs = [2_000_000, 3_000_000, 2_000_000, 1_000_000]
+            .into_iter()
+            .enumerate()
+            .map(|(i, gas_limit)| {
+                let tx = Tx::new(
+                    "wasm_code".as_bytes().to_owned(),
+                    Some(format!("transaction data: {}", i).into_bytes()),
+                );
+                WrapperTx::new(
+                    Fee {
+                        amount: Amount::whole(100),
+                        token: xan(),
+                    },
+                    &keypair,
+                    Epoch(0),
+                    gas_limit.into(),
+                    tx,
+                    Default::default(),
+                    ChainId::default(),
+                )
+                .sign(&keypair)
+                .expect("Test failed")
+                .to_bytes()
+            })
+            .collect();
+
+        let response = shell
+            .process_proposal(ProcessProposal { txs })
+            .expect("Test failed");
+        let codes: Vec<u32> =
+            response.iter().map(|resp| resp.result.code).collect();
+        assert_eq!(
+            codes,
+            vec![
+                u32::from(ResultCode::Ok),
+                u32::from(ResultCode::InvalidTx),
+                u32::from(ResultCode::InvalidTx),
+                u32::from(ResultCode::Ok),
+            ]
+        );
+    }
+
     #[cfg(not(feature = "ABCI"))]
     /// Test that if the expected order of decrypted txs is
     /// validated, [`process_proposal`] rejects it
@@ -565,6 +787,7 @@ mod test_process_proposal {
                 0.into(),
                 tx.clone(),
                 Default::default(),
+                ChainId::default(),
             );
             shell.enqueue_tx(wrapper);
             txs.push(Tx::from(TxType::Decrypted(DecryptedTx::Decrypted(tx))));
@@ -581,7 +804,7 @@ mod test_process_proposal {
         } else {
             panic!("Test failed")
         };
-        assert_eq!(response_1.result.code, u32::from(ErrorCodes::Ok));
+        assert_eq!(response_1.result.code, u32::from(ResultCode::Ok));
 
         let req_2 = ProcessProposal {
             txs: vec![txs[2].to_bytes()],
@@ -598,7 +821,7 @@ mod test_process_proposal {
         } else {
             panic!("Test failed")
         };
-        assert_eq!(response_2.result.code, u32::from(ErrorCodes::InvalidOrder));
+        assert_eq!(response_2.result.code, u32::from(ResultCode::InvalidOrder));
         assert_eq!(
             response_2.result.info,
             String::from(
@@ -630,6 +853,7 @@ mod test_process_proposal {
             0.into(),
             tx,
             Default::default(),
+            ChainId::default(),
         );
         shell.enqueue_tx(wrapper.clone());
 
@@ -649,7 +873,7 @@ mod test_process_proposal {
         } else {
             panic!("Test failed")
         };
-        assert_eq!(response.result.code, u32::from(ErrorCodes::InvalidTx));
+        assert_eq!(response.result.code, u32::from(ResultCode::InvalidTx));
         assert_eq!(
             response.result.info,
             String::from(
@@ -689,6 +913,7 @@ mod test_process_proposal {
             0.into(),
             tx,
             Default::default(),
+            ChainId::default(),
         );
         wrapper.tx_hash = Hash([0; 32]);
 
@@ -714,7 +939,7 @@ mod test_process_proposal {
         } else {
             panic!("Test failed")
         };
-        assert_eq!(response.result.code, u32::from(ErrorCodes::Ok));
+        assert_eq!(response.result.code, u32::from(ResultCode::Ok));
         #[cfg(feature = "ABCI")]
         {
             match process_tx(
@@ -763,6 +988,7 @@ mod test_process_proposal {
             gas_limit: 0.into(),
             inner_tx,
             tx_hash: hash_tx(&tx),
+            chain_id: ChainId::default(),
         };
 
         let signed = if !cfg!(feature = "ABCI") {
@@ -786,7 +1012,7 @@ mod test_process_proposal {
         } else {
             panic!("Test failed")
         };
-        assert_eq!(response.result.code, u32::from(ErrorCodes::Ok));
+        assert_eq!(response.result.code, u32::from(ResultCode::Ok));
         #[cfg(feature = "ABCI")]
         {
             match process_tx(
@@ -834,7 +1060,7 @@ mod test_process_proposal {
         } else {
             panic!("Test failed")
         };
-        assert_eq!(response.result.code, u32::from(ErrorCodes::ExtraTxs));
+        assert_eq!(response.result.code, u32::from(ResultCode::ExtraTxs));
         assert_eq!(
             response.result.info,
             String::from("Received more decrypted txs than expected"),
@@ -863,7 +1089,7 @@ mod test_process_proposal {
         } else {
             panic!("Test failed")
         };
-        assert_eq!(response.result.code, u32::from(ErrorCodes::InvalidTx));
+        assert_eq!(response.result.code, u32::from(ResultCode::InvalidTx));
         assert_eq!(
             response.result.info,
             String::from(
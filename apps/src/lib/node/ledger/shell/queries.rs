@@ -1,30 +1,39 @@
 //! Shell methods for querying state
 use std::cmp::max;
 
-use anoma::ledger::parameters::EpochDuration;
+use anoma::ledger::gas::BlockGasUsage;
+use anoma::ledger::parameters::{EpochDuration, EpochInfo, UpgradeInfo};
 use anoma::ledger::pos::PosParams;
-use anoma::types::address::Address;
+use anoma::types::account::{AccountInfo, StorageUsage, StorageUsagePage};
+use anoma::types::address::{Address, InternalAddress};
+use anoma::types::hash::Hash;
+use anoma::types::indexer::BlockResults;
 use anoma::types::key;
 use anoma::types::key::dkg_session_keys::DkgPublicKey;
-use anoma::types::storage::{Key, PrefixValue};
+use anoma::types::query::{
+    INVALID_QUERY_CODE, MAX_PREFIX_PAGE_LIMIT, NOT_FOUND_CODE,
+    STORAGE_ERROR_CODE,
+};
+use anoma::types::storage::{
+    Epoch, Key, MultiValueResult, PrefixPage, PrefixValue,
+};
 use anoma::types::token::{self, Amount};
+use anoma::types::transaction::TxResponse;
 use borsh::{BorshDeserialize, BorshSerialize};
 use ferveo_common::TendermintValidator;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_proto::crypto::{ProofOp, ProofOps};
-#[cfg(not(feature = "ABCI"))]
-use tendermint_proto::google::protobuf;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_proto::types::EvidenceParams;
-#[cfg(feature = "ABCI")]
-use tendermint_proto_abci::crypto::{ProofOp, ProofOps};
-#[cfg(feature = "ABCI")]
-use tendermint_proto_abci::google::protobuf;
-#[cfg(feature = "ABCI")]
-use tendermint_proto_abci::types::EvidenceParams;
+use prost::Message;
+use tendermint_compat::tendermint_proto::crypto::{ProofOp, ProofOps};
+use tendermint_compat::tendermint_proto::google::protobuf;
+use tendermint_compat::tendermint_proto::types::EvidenceParams;
 
 use super::*;
+use crate::node::ledger::events::EventType;
+use crate::node::ledger::node_status::NodeStatus;
 use crate::node::ledger::response;
+use crate::node::ledger::rpc::{
+    MAX_CHANGED_KEYS_BLOCKS, MAX_SLASHES_PAGE_LIMIT,
+    MAX_STORAGE_USAGE_PAGE_KEYS, MAX_TOTAL_SUPPLY_BALANCES, MAX_VALUES_KEYS,
+};
 
 impl<D, H> Shell<D, H>
 where
@@ -42,7 +51,7 @@ where
             1.. => BlockHeight(query.height as u64),
             _ => {
                 return response::Query {
-                    code: 1,
+                    code: INVALID_QUERY_CODE,
                     info: format!(
                         "The query height is invalid: {}",
                         query.height
@@ -51,6 +60,9 @@ where
                 };
             }
         };
+        if let Err(response) = self.check_query_height(height) {
+            return response;
+        }
         match Path::from_str(&query.path) {
             Ok(path) => match path {
                 Path::DryRunTx => self.dry_run_tx(&query.data),
@@ -62,22 +74,139 @@ where
                         ..Default::default()
                     }
                 }
+                Path::EpochInfo => self.epoch_info(),
+                Path::BlockGas => self.block_gas(height),
+                Path::Upgrade => self.upgrade_info(),
+                Path::Mempool => response::Query {
+                    value: self.node_stats.mempool().try_to_vec().unwrap(),
+                    ..Default::default()
+                },
+                Path::RecentBlocks => response::Query {
+                    value: self
+                        .node_stats
+                        .recent_blocks()
+                        .try_to_vec()
+                        .unwrap(),
+                    ..Default::default()
+                },
+                Path::Status => self.node_status(),
                 Path::Value(storage_key) => {
                     self.read_storage_value(&storage_key, height, query.prove)
                 }
+                Path::Values(keys) => self.read_storage_values(&keys, height),
                 Path::Prefix(storage_key) => {
                     self.read_storage_prefix(&storage_key, height, query.prove)
                 }
-                Path::HasKey(storage_key) => self.has_storage_key(&storage_key),
+                Path::PrefixPage {
+                    prefix,
+                    after,
+                    limit,
+                } => self.read_storage_prefix_page(
+                    &prefix,
+                    after.as_ref(),
+                    limit,
+                    height,
+                    query.prove,
+                ),
+                Path::HasKey(storage_key) => {
+                    self.has_storage_key(&storage_key, height, query.prove)
+                }
+                Path::ProofSpecs => self.proof_specs(),
+                Path::ChangedKeys { from, to, prefix } => {
+                    self.changed_keys(prefix.as_ref(), from, to)
+                }
+                Path::Account(address) => self.account_info(&address),
+                Path::StorageUsage { address, after } => self.storage_usage(
+                    &address,
+                    after.as_ref(),
+                    MAX_STORAGE_USAGE_PAGE_KEYS,
+                    height,
+                ),
+                Path::Bonds { owner, validator } => {
+                    self.bonds(owner.as_ref(), validator.as_ref())
+                }
+                Path::Rewards { owner, validator } => {
+                    self.rewards(owner.as_ref(), validator.as_ref())
+                }
+                Path::ValidatorSet(epoch) => self.validator_set(epoch),
+                Path::ValidatorSetAt(epoch) => self.validator_set_at(epoch),
+                Path::PosParams => self.pos_params(),
+                Path::Inflation => self.inflation(),
+                Path::Slashes {
+                    validator,
+                    from_epoch,
+                    to_epoch,
+                    start,
+                    limit,
+                } => self.slashes(
+                    validator.as_ref(),
+                    from_epoch,
+                    to_epoch,
+                    start.as_ref(),
+                    limit,
+                ),
+                Path::TotalSupply(token) => {
+                    self.total_supply(&token, MAX_TOTAL_SUPPLY_BALANCES, height)
+                }
+                Path::TxsByAddress(address) => self
+                    .query_indexer(|indexer| indexer.txs_by_address(&address)),
+                Path::TxsByToken(token) => {
+                    self.query_indexer(|indexer| indexer.txs_by_token(&token))
+                }
+                Path::TxsBySender(sender) => self.txs_by_sender(&sender),
+                Path::TransferHistory(owner) => self
+                    .query_indexer(|indexer| indexer.transfer_history(&owner)),
+                Path::OwnerBalances(owner) => {
+                    self.owner_balances(&owner, height)
+                }
+                Path::BlockResults(height) => self.block_results(height),
+                Path::TxEvent(hash) => self.tx_event(&hash),
             },
             Err(err) => response::Query {
-                code: 1,
+                code: INVALID_QUERY_CODE,
                 info: format!("RPC error: {}", err),
                 ..Default::default()
             },
         }
     }
 
+    /// Check that the state of the query height can be read. The height must
+    /// not be above the last block and the state of a past block must still
+    /// be stored. On failure, returns the error response of the query.
+    fn check_query_height(
+        &self,
+        height: BlockHeight,
+    ) -> std::result::Result<(), response::Query> {
+        let last_height = self.storage.get_block_height().0;
+        if height > last_height {
+            return Err(response::Query {
+                code: 2,
+                info: format!(
+                    "The query height {} is above the last block height {}",
+                    height, last_height
+                ),
+                ..Default::default()
+            });
+        }
+        match self.storage.has_state_at_height(height) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(response::Query {
+                code: 2,
+                info: format!(
+                    "The state at height {} is pruned or not available on \
+                     this node",
+                    height
+                ),
+                ..Default::default()
+            }),
+            Err(err) => Err(response::Query {
+                code: 2,
+                info: format!("Storage error: {}", err),
+                ..Default::default()
+            }),
+        }
+    }
+
     /// Simple helper function for the ledger to get balances
     /// of the specified token at the specified address
     pub fn get_balance(
@@ -169,6 +298,48 @@ where
         }
     }
 
+    /// Query to read the values of multiple keys from storage at the same
+    /// height. The value in successful response is a [`MultiValueResult`]
+    /// encoded with [`BorshSerialize`].
+    pub fn read_storage_values(
+        &self,
+        keys: &[Key],
+        height: BlockHeight,
+    ) -> response::Query {
+        if keys.len() > MAX_VALUES_KEYS {
+            return response::Query {
+                code: INVALID_QUERY_CODE,
+                info: format!(
+                    "Cannot read {} keys in a single query, the maximum is {}",
+                    keys.len(),
+                    MAX_VALUES_KEYS
+                ),
+                ..Default::default()
+            };
+        }
+        let values: std::result::Result<Vec<_>, _> = keys
+            .iter()
+            .map(|key| {
+                self.storage
+                    .read_with_height(key, height)
+                    .map(|(value, _gas)| (key.clone(), value))
+            })
+            .collect();
+        match values {
+            Ok(values) => response::Query {
+                value: MultiValueResult { height, values }
+                    .try_to_vec()
+                    .unwrap(),
+                ..Default::default()
+            },
+            Err(err) => response::Query {
+                code: 2,
+                info: format!("Storage error: {}", err),
+                ..Default::default()
+            },
+        }
+    }
+
     /// Query to read a range of values from storage with a matching prefix. The
     /// value in successful response is a [`Vec<PrefixValue>`] encoded with
     /// [`BorshSerialize`].
@@ -178,95 +349,912 @@ where
         height: BlockHeight,
         is_proven: bool,
     ) -> response::Query {
-        if height != self.storage.get_block_height().0 {
+        let values =
+            match self.read_prefix_values(key, None, usize::MAX, height) {
+                Ok(values) => values,
+                Err(response) => return response,
+            };
+        if values.is_empty() {
             return response::Query {
+                code: 1,
+                info: format!("No value found for key: {}", key),
+                ..Default::default()
+            };
+        }
+        let proof_ops = if is_proven {
+            match self.prefix_proof_ops(&values, height) {
+                Ok(proof_ops) => Some(proof_ops),
+                Err(response) => return response,
+            }
+        } else {
+            None
+        };
+        let value = values.try_to_vec().unwrap();
+        response::Query {
+            value,
+            proof_ops,
+            ..Default::default()
+        }
+    }
+
+    /// Query to read a page of the values from storage with a matching
+    /// prefix, whose keys are after the `after` key, if any. At most `limit`
+    /// values are read, up to [`MAX_PREFIX_PAGE_LIMIT`]. The value in
+    /// successful response is a [`PrefixPage`] encoded with
+    /// [`BorshSerialize`], which is empty if no value is found. The response
+    /// has the height of the page, at which the next pages can be read.
+    pub fn read_storage_prefix_page(
+        &self,
+        key: &Key,
+        after: Option<&Key>,
+        limit: u64,
+        height: BlockHeight,
+        is_proven: bool,
+    ) -> response::Query {
+        let limit = limit.clamp(1, MAX_PREFIX_PAGE_LIMIT) as usize;
+        // One more value is read to find out if there's a next page
+        let mut values =
+            match self.read_prefix_values(key, after, limit + 1, height) {
+                Ok(values) => values,
+                Err(response) => return response,
+            };
+        let next = if values.len() > limit {
+            values.truncate(limit);
+            values.last().map(|value| value.key.clone())
+        } else {
+            None
+        };
+        let proof_ops = if is_proven && !values.is_empty() {
+            match self.prefix_proof_ops(&values, height) {
+                Ok(proof_ops) => Some(proof_ops),
+                Err(response) => return response,
+            }
+        } else {
+            None
+        };
+        response::Query {
+            value: PrefixPage { values, next }.try_to_vec().unwrap(),
+            proof_ops,
+            height: height.0 as i64,
+            ..Default::default()
+        }
+    }
+
+    /// Read at most `limit` values with a matching prefix at the given
+    /// height, whose keys are after the `after` key, if any. The values of
+    /// the last block are read from an iterator that starts from the `after`
+    /// key, while the values of a past height are collected from the diffs
+    /// of the blocks above it. On failure, returns the error response of the
+    /// query.
+    fn read_prefix_values(
+        &self,
+        key: &Key,
+        after: Option<&Key>,
+        limit: usize,
+        height: BlockHeight,
+    ) -> std::result::Result<Vec<PrefixValue>, response::Query> {
+        let values = self
+            .storage
+            .read_prefix_page_with_height(key, after, limit, height)
+            .map(|(key_vals, _gas)| {
+                key_vals
+                    .into_iter()
+                    .map(|(key, value)| PrefixValue { key, value })
+                    .collect()
+            });
+        values.map_err(|err: anoma::ledger::storage::Error| response::Query {
+            code: 2,
+            info: format!("Storage error: {}", err),
+            ..Default::default()
+        })
+    }
+
+    /// The existence proofs of the values read with a matching prefix. On
+    /// failure, returns the error response of the query.
+    fn prefix_proof_ops(
+        &self,
+        values: &[PrefixValue],
+        height: BlockHeight,
+    ) -> std::result::Result<ProofOps, response::Query> {
+        let mut ops = vec![];
+        for PrefixValue { key, value } in values {
+            match self.storage.get_existence_proof(key, value.clone(), height) {
+                Ok(p) => {
+                    let mut cur_ops: Vec<ProofOp> =
+                        p.ops.into_iter().map(|op| op.into()).collect();
+                    ops.append(&mut cur_ops);
+                }
+                Err(err) => {
+                    return Err(response::Query {
+                        code: 2,
+                        info: format!("Storage error: {}", err),
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+        // ops is not empty in this case
+        Ok(ProofOps { ops })
+    }
+
+    /// Query the ICS-23 proof specs of the Merkle proofs of the storage
+    /// values, with which the proofs can be verified, e.g. by an IBC client of
+    /// the chain. The first spec is for the proof of a value in its subtree
+    /// and the second one for the proofs of the roots of the subtrees. The
+    /// value in successful response is a `Vec` of the specs encoded with
+    /// protobuf, encoded with [`BorshSerialize`].
+    fn proof_specs(&self) -> response::Query {
+        let specs: Vec<Vec<u8>> = self
+            .storage
+            .block
+            .tree
+            .proof_specs()
+            .iter()
+            .map(|spec| spec.encode_to_vec())
+            .collect();
+        response::Query {
+            value: specs.try_to_vec().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    /// Query to check if a storage key exists. When a proof is requested,
+    /// the key is checked at the given height, with the proof of its value or
+    /// of its absence.
+    fn has_storage_key(
+        &self,
+        key: &Key,
+        height: BlockHeight,
+        is_proven: bool,
+    ) -> response::Query {
+        if !is_proven {
+            return match self.storage.has_key(key) {
+                Ok((has_key, _gas)) => response::Query {
+                    value: has_key.try_to_vec().unwrap(),
+                    ..Default::default()
+                },
+                Err(err) => response::Query {
+                    code: 2,
+                    info: format!("Storage error: {}", err),
+                    ..Default::default()
+                },
+            };
+        }
+        let proof = match self.storage.read_with_height(key, height) {
+            Ok((Some(value), _gas)) => self
+                .storage
+                .get_existence_proof(key, value, height)
+                .map(|proof| (true, proof)),
+            Ok((None, _gas)) => self
+                .storage
+                .get_non_existence_proof(key, height)
+                .map(|proof| (false, proof)),
+            Err(err) => Err(err),
+        };
+        match proof {
+            Ok((has_key, proof)) => response::Query {
+                value: has_key.try_to_vec().unwrap(),
+                proof_ops: Some(proof.into()),
+                ..Default::default()
+            },
+            Err(err) => response::Query {
                 code: 2,
+                info: format!("Storage error: {}", err),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Query the storage keys with a matching prefix, if any, that were
+    /// changed in the blocks above the `from` height up to the `to` height,
+    /// so that an indexer can sync the state of a block from the previous
+    /// one. The range is limited to [`MAX_CHANGED_KEYS_BLOCKS`] blocks whose
+    /// state is still stored. The value in successful response is a
+    /// `Vec<Key>`, ordered by the keys, encoded with [`BorshSerialize`].
+    fn changed_keys(
+        &self,
+        prefix: Option<&Key>,
+        from: BlockHeight,
+        to: BlockHeight,
+    ) -> response::Query {
+        if from > to || to.0 - from.0 > MAX_CHANGED_KEYS_BLOCKS {
+            return response::Query {
+                code: INVALID_QUERY_CODE,
                 info: format!(
-                    "Prefix read works with only the latest height: height {}",
-                    height
+                    "Invalid range of blocks from {} to {}, the range must \
+                     be ascending and of at most {} blocks",
+                    from, to, MAX_CHANGED_KEYS_BLOCKS
                 ),
                 ..Default::default()
             };
         }
-        let (iter, _gas) = self.storage.iter_prefix(key);
-        let mut iter = iter.peekable();
-        if iter.peek().is_none() {
-            response::Query {
+        for height in [from, to] {
+            if let Err(response) = self.check_query_height(height) {
+                return response;
+            }
+        }
+        match self.storage.changed_keys(prefix, from, to) {
+            Ok(keys) => response::Query {
+                value: keys
+                    .into_iter()
+                    .collect::<Vec<_>>()
+                    .try_to_vec()
+                    .unwrap(),
+                height: to.0 as i64,
+                ..Default::default()
+            },
+            Err(err) => response::Query {
+                code: 2,
+                info: format!("Storage error: {}", err),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Query the gas used by the block at the given height together with the
+    /// current block and tx gas limits. The value in successful response is a
+    /// [`BlockGasUsage`] encoded with [`BorshSerialize`].
+    fn block_gas(&self, height: BlockHeight) -> response::Query {
+        let used_gas = match self.storage.db.read_block_gas(height) {
+            Ok(Some(used_gas)) => used_gas,
+            Ok(None) => {
+                return response::Query {
+                    code: 1,
+                    info: format!("No block gas found for height: {}", height),
+                    ..Default::default()
+                };
+            }
+            Err(err) => {
+                return response::Query {
+                    code: 2,
+                    info: format!("Storage error: {}", err),
+                    ..Default::default()
+                };
+            }
+        };
+        let (max_block_gas, max_tx_gas) = self.gas_limits();
+        let usage = BlockGasUsage {
+            max_block_gas,
+            max_tx_gas,
+            used_gas,
+        };
+        response::Query {
+            value: usage.try_to_vec().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    /// Query the status of the node and of the chain at its last committed
+    /// block. The value in successful response is a [`NodeStatus`] encoded
+    /// with [`BorshSerialize`].
+    fn node_status(&self) -> response::Query {
+        let last_height = self.storage.last_height;
+        let header = match self.storage.db.read_block_header(last_height) {
+            Ok(header) => header,
+            Err(err) => {
+                return response::Query {
+                    code: STORAGE_ERROR_CODE,
+                    info: format!("Storage error: {}", err),
+                    ..Default::default()
+                };
+            }
+        };
+        let last_block_time = header.map(|header| header.time);
+        let (epoch, _gas) = self.storage.get_last_epoch();
+        let status = NodeStatus {
+            chain_id: self.storage.chain_id.to_string(),
+            last_height,
+            app_hash: self
+                .storage
+                .get_state()
+                .map(|(root, _height)| root.0)
+                .unwrap_or_default(),
+            epoch,
+            epoch_start_height: self
+                .storage
+                .block
+                .pred_epochs
+                .get_start_height(epoch),
+            last_block_time,
+            version: crate::anoma_version().to_string(),
+            syncing: None,
+        };
+        response::Query {
+            value: status.try_to_vec().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    /// Query the start of the epoch of the last committed block and the
+    /// earliest start of the next epoch, with the parameters to estimate when
+    /// the next epochs start. The value in successful response is an
+    /// [`EpochInfo`] encoded with [`BorshSerialize`].
+    fn epoch_info(&self) -> response::Query {
+        let (epoch, _gas) = self.storage.get_last_epoch();
+        let start_height =
+            self.storage.block.pred_epochs.get_start_height(epoch);
+        let info = parameters::read_epoch_parameter(&self.storage).and_then(
+            |(epoch_duration, _gas)| {
+                let (max_expected_time_per_block, _gas) =
+                    parameters::read_max_expected_time_per_block_parameter(
+                        &self.storage,
+                    )?;
+                let start_header = match start_height {
+                    Some(height) => {
+                        self.storage
+                            .db
+                            .read_block_header(height)
+                            .map_err(parameters::ReadError::StorageError)?
+                    }
+                    None => None,
+                };
+                Ok(EpochInfo {
+                    epoch,
+                    height: self.storage.last_height,
+                    start_height,
+                    start_time: start_header.map(|header| header.time),
+                    next_epoch_min_start_height: self
+                        .storage
+                        .next_epoch_min_start_height,
+                    next_epoch_min_start_time: self
+                        .storage
+                        .next_epoch_min_start_time,
+                    epoch_duration,
+                    max_expected_time_per_block,
+                })
+            },
+        );
+        match info {
+            Ok(info) => response::Query {
+                value: info.try_to_vec().unwrap(),
+                ..Default::default()
+            },
+            Err(err) => response::Query {
+                code: 2,
+                info: format!("Storage error: {}", err),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Query the protocol version of the chain and the pending protocol
+    /// upgrade. The value in successful response is an [`UpgradeInfo`]
+    /// encoded with [`BorshSerialize`].
+    fn upgrade_info(&self) -> response::Query {
+        let info = parameters::read_protocol_version(&self.storage).and_then(
+            |(protocol_version, _gas)| {
+                let (pending_upgrade, _gas) =
+                    parameters::read_upgrade_parameter(&self.storage)?;
+                Ok(UpgradeInfo {
+                    protocol_version,
+                    pending_upgrade,
+                })
+            },
+        );
+        match info {
+            Ok(info) => response::Query {
+                value: info.try_to_vec().unwrap(),
+                ..Default::default()
+            },
+            Err(err) => response::Query {
+                code: 2,
+                info: format!("Storage error: {}", err),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Query the bonds and the unbonds of the owner to the validator, grouped
+    /// by validator. The value in successful response is a vector of
+    /// [`pos::BondQueryResult`] encoded with [`BorshSerialize`].
+    fn bonds(
+        &self,
+        owner: Option<&Address>,
+        validator: Option<&Address>,
+    ) -> response::Query {
+        match pos::query_bonds(&self.storage, owner, validator) {
+            Ok(results) if results.is_empty() => response::Query {
                 code: 1,
-                info: format!("No value found for key: {}", key),
+                info: "No bonds found".to_string(),
+                ..Default::default()
+            },
+            Ok(results) => response::Query {
+                value: results.try_to_vec().unwrap(),
+                ..Default::default()
+            },
+            Err(err) => response::Query {
+                code: 2,
+                info: format!("Storage error: {}", err),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Query the pending rewards of the bonds of the owner to the validator.
+    /// The value in successful response is a vector of
+    /// [`pos::RewardsQueryResult`] encoded with [`BorshSerialize`].
+    fn rewards(
+        &self,
+        owner: Option<&Address>,
+        validator: Option<&Address>,
+    ) -> response::Query {
+        match pos::query_rewards(&self.storage, owner, validator) {
+            Ok(results) if results.is_empty() => response::Query {
+                code: 1,
+                info: "No bonds found".to_string(),
+                ..Default::default()
+            },
+            Ok(results) => response::Query {
+                value: results.try_to_vec().unwrap(),
+                ..Default::default()
+            },
+            Err(err) => response::Query {
+                code: 2,
+                info: format!("Storage error: {}", err),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Query the validator set of the given epoch, or of the last committed
+    /// epoch. The value in successful response is a
+    /// [`pos::ValidatorSetQueryResult`] encoded with [`BorshSerialize`].
+    fn validator_set(&self, epoch: Option<Epoch>) -> response::Query {
+        match pos::query_validator_set(&self.storage, epoch) {
+            Ok(Some(result)) => response::Query {
+                value: result.try_to_vec().unwrap(),
+                ..Default::default()
+            },
+            Ok(None) => response::Query {
+                code: 1,
+                info: format!(
+                    "No validator set found for epoch: {}",
+                    epoch.unwrap_or_else(|| self.storage.get_last_epoch().0)
+                ),
+                ..Default::default()
+            },
+            Err(err) => response::Query {
+                code: 2,
+                info: format!("Storage error: {}", err),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Query the validator set of the given epoch, which may be a past epoch
+    /// within the unbonding length. The value in successful response is a
+    /// [`pos::ValidatorSetQueryResult`] encoded with [`BorshSerialize`].
+    fn validator_set_at(&self, epoch: Epoch) -> response::Query {
+        match pos::query_validator_set_at(&self.storage, epoch) {
+            Ok(Some(result)) => response::Query {
+                value: result.try_to_vec().unwrap(),
+                ..Default::default()
+            },
+            Ok(None) => response::Query {
+                code: 1,
+                info: format!("No validator set is kept for epoch: {}", epoch),
+                ..Default::default()
+            },
+            Err(err) => response::Query {
+                code: 2,
+                info: format!("Storage error: {}", err),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Query a page of the slashes of the validator, or of all the
+    /// validators, in the range of epochs. At most `limit` slashes are read,
+    /// up to [`MAX_SLASHES_PAGE_LIMIT`]. The value in successful response is
+    /// a [`pos::SlashesPage`] encoded with [`BorshSerialize`], which is empty
+    /// if no slash is found.
+    fn slashes(
+        &self,
+        validator: Option<&Address>,
+        from_epoch: Option<Epoch>,
+        to_epoch: Option<Epoch>,
+        start: Option<&pos::SlashesCursor>,
+        limit: u64,
+    ) -> response::Query {
+        let limit = limit.clamp(1, MAX_SLASHES_PAGE_LIMIT);
+        match pos::query_slashes(
+            &self.storage,
+            validator,
+            from_epoch,
+            to_epoch,
+            start,
+            limit,
+        ) {
+            Ok(page) => response::Query {
+                value: page.try_to_vec().unwrap(),
+                ..Default::default()
+            },
+            Err(err) => response::Query {
+                code: 2,
+                info: format!("Storage error: {}", err),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Query the PoS parameters. The value in successful response is a
+    /// [`PosParams`] encoded with [`BorshSerialize`].
+    fn pos_params(&self) -> response::Query {
+        match pos::query_pos_params(&self.storage) {
+            Ok(Some(params)) => response::Query {
+                value: params.try_to_vec().unwrap(),
+                ..Default::default()
+            },
+            Ok(None) => response::Query {
+                code: 1,
+                info: "No PoS parameters found".to_string(),
+                ..Default::default()
+            },
+            Err(err) => response::Query {
+                code: 2,
+                info: format!("Storage error: {}", err),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Query the estimate of the inflation of the staking token in the last
+    /// committed epoch. The value in successful response is a
+    /// [`pos::InflationQueryResult`] encoded with [`BorshSerialize`].
+    fn inflation(&self) -> response::Query {
+        match pos::query_inflation(&self.storage) {
+            Ok(Some(inflation)) => response::Query {
+                value: inflation.try_to_vec().unwrap(),
+                ..Default::default()
+            },
+            Ok(None) => response::Query {
+                code: 1,
+                info: "No PoS parameters found".to_string(),
+                ..Default::default()
+            },
+            Err(err) => response::Query {
+                code: 2,
+                info: format!("Storage error: {}", err),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Query the total supply of a token at the given height. The supply
+    /// tracked by the token is read, if any. Otherwise, e.g. for the tokens of
+    /// the genesis, it's the sum of the balances of the token, without the
+    /// balances of the IBC mint and burn accounts, which aren't in
+    /// circulation. The balances are only summed up to `max_balances`, so a
+    /// token with more balances and without a tracked supply isn't found. The
+    /// value in successful response is an [`Amount`] encoded with
+    /// [`BorshSerialize`].
+    fn total_supply(
+        &self,
+        token: &Address,
+        max_balances: usize,
+        height: BlockHeight,
+    ) -> response::Query {
+        let storage_error = |info: String| response::Query {
+            code: 2,
+            info,
+            ..Default::default()
+        };
+        let supply_key = token::total_supply_key(token);
+        match self.storage.read_with_height(&supply_key, height) {
+            Ok((Some(value), _gas)) => {
+                return response::Query {
+                    value,
+                    ..Default::default()
+                };
+            }
+            Ok((None, _gas)) => {}
+            Err(err) => {
+                return storage_error(format!("Storage error: {}", err))
+            }
+        }
+        let prefix = token::balance_prefix(token);
+        // One more balance is read to find out if there are too many
+        let balances = match self.read_prefix_values(
+            &prefix,
+            None,
+            max_balances + 1,
+            height,
+        ) {
+            Ok(balances) => balances,
+            Err(response) => return response,
+        };
+        if balances.is_empty() {
+            return response::Query {
+                code: 1,
+                info: format!("No balances found for token: {}", token),
+                ..Default::default()
+            };
+        }
+        if balances.len() > max_balances {
+            return response::Query {
+                code: 1,
+                info: format!(
+                    "The total supply of token {} isn't tracked and it has \
+                     more than {} balances to sum",
+                    token, max_balances
+                ),
                 ..Default::default()
+            };
+        }
+        let mut supply = Amount::default();
+        for PrefixValue { key, value } in balances {
+            match token::is_balance_key(token, &key) {
+                Some(Address::Internal(InternalAddress::IbcMint))
+                | Some(Address::Internal(InternalAddress::IbcBurn))
+                | None => continue,
+                Some(_) => {}
             }
+            let amount = match Amount::try_from_slice(&value[..]) {
+                Ok(amount) => amount,
+                Err(err) => {
+                    return storage_error(format!(
+                        "Error decoding the balance {}: {}",
+                        key, err
+                    ));
+                }
+            };
+            supply = match supply.checked_add(amount) {
+                Some(supply) => supply,
+                None => {
+                    return storage_error(format!(
+                        "The total supply of token {} overflows",
+                        token
+                    ));
+                }
+            };
+        }
+        response::Query {
+            value: supply.try_to_vec().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    /// Query the public keys and the VP code hash of an account. The value
+    /// in successful response is an [`AccountInfo`] encoded with
+    /// [`BorshSerialize`].
+    fn account_info(&self, address: &Address) -> response::Query {
+        let read = |key: &Key| match self.storage.read(key) {
+            Ok((value, _gas)) => Ok(value),
+            Err(err) => Err(response::Query {
+                code: 2,
+                info: format!("Storage error: {}", err),
+                ..Default::default()
+            }),
+        };
+        let decode_pk = |value: Option<Vec<u8>>| {
+            value.and_then(|value| {
+                key::common::PublicKey::try_from_slice(&value[..]).ok()
+            })
+        };
+        let public_key = match read(&key::pk_key(address)) {
+            Ok(value) => decode_pk(value),
+            Err(response) => return response,
+        };
+        let protocol_public_key = match read(&key::protocol_pk_key(address)) {
+            Ok(value) => decode_pk(value),
+            Err(response) => return response,
+        };
+        let vp_code_hash = match read(&Key::validity_predicate(address)) {
+            Ok(value) => value.map(Hash::sha256),
+            Err(response) => return response,
+        };
+        if public_key.is_none() && vp_code_hash.is_none() {
+            return response::Query {
+                code: 1,
+                info: format!("No account found for address: {}", address),
+                ..Default::default()
+            };
+        }
+        let info = AccountInfo {
+            address: address.clone(),
+            public_key,
+            protocol_public_key,
+            vp_code_hash,
+        };
+        response::Query {
+            value: info.try_to_vec().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    /// Query the number and the size of at most `limit` keys in the subspace
+    /// of an account at the given height, after the `after` key, if any. The
+    /// value in successful response is a [`StorageUsagePage`] encoded with
+    /// [`BorshSerialize`].
+    fn storage_usage(
+        &self,
+        address: &Address,
+        after: Option<&Key>,
+        limit: usize,
+        height: BlockHeight,
+    ) -> response::Query {
+        let prefix = Key::from(address.to_db_key());
+        // One more value is read to find out if there's a next page
+        let mut values =
+            match self.read_prefix_values(&prefix, after, limit + 1, height) {
+                Ok(values) => values,
+                Err(response) => return response,
+            };
+        let next = if values.len() > limit {
+            values.truncate(limit);
+            values.last().map(|value| value.key.clone())
         } else {
-            let values: std::result::Result<
-                Vec<PrefixValue>,
-                anoma::types::storage::Error,
-            > = iter
-                .map(|(key, value, _gas)| {
-                    let key = Key::parse(key)?;
-                    Ok(PrefixValue { key, value })
+            None
+        };
+        let usage = values.iter().fold(
+            StorageUsage::default(),
+            |usage, PrefixValue { key, value }| StorageUsage {
+                keys: usage.keys + 1,
+                bytes: usage.bytes + (key.len() + value.len()) as u64,
+            },
+        );
+        response::Query {
+            value: StorageUsagePage { usage, next }.try_to_vec().unwrap(),
+            height: height.0 as i64,
+            ..Default::default()
+        }
+    }
+
+    /// Query the indexed results of the txs of a committed block
+    fn block_results(&self, height: BlockHeight) -> response::Query {
+        let last_height = self.storage.last_height;
+        if height > last_height {
+            return response::Query {
+                code: 1,
+                info: format!(
+                    "The block at height {} is not committed yet, the last \
+                     committed height is {}",
+                    height, last_height
+                ),
+                ..Default::default()
+            };
+        }
+        self.query_indexer(|indexer| indexer.block_results(height))
+    }
+
+    /// Query the indexed result of the tx with the given hash. The result of
+    /// applying the tx is preferred over the acceptance of its wrapper, which
+    /// may have the same hash, and over the rejections of its replays. The
+    /// value in successful response is an optional [`TxResponse`] that's
+    /// `None` if the tx is not indexed, encoded with [`BorshSerialize`].
+    fn tx_event(&self, hash: &Hash) -> response::Query {
+        self.query_indexer(|indexer| {
+            let results = indexer.tx_results(&hash.to_string())?;
+            let applied = EventType::Applied.to_string();
+            let result = results
+                .iter()
+                .find(|result| {
+                    result
+                        .events
+                        .first()
+                        .map_or(false, |event| event.event_type == applied)
                 })
-                .collect();
-            match values {
-                Ok(values) => {
-                    let proof_ops = if is_proven {
-                        let mut ops = vec![];
-                        for PrefixValue { key, value } in &values {
-                            match self.storage.get_existence_proof(
-                                key,
-                                value.clone(),
-                                height,
-                            ) {
-                                Ok(p) => {
-                                    let mut cur_ops: Vec<ProofOp> = p
-                                        .ops
-                                        .into_iter()
-                                        .map(|op| op.into())
-                                        .collect();
-                                    ops.append(&mut cur_ops);
-                                }
-                                Err(err) => {
-                                    return response::Query {
-                                        code: 2,
-                                        info: format!("Storage error: {}", err),
-                                        ..Default::default()
-                                    };
-                                }
-                            }
-                        }
-                        // ops is not empty in this case
-                        Some(ProofOps { ops })
-                    } else {
-                        None
-                    };
-                    let value = values.try_to_vec().unwrap();
-                    response::Query {
-                        value,
-                        proof_ops,
-                        ..Default::default()
-                    }
+                .or_else(|| results.first());
+            Ok(result.and_then(|result| {
+                let event = result.events.first()?;
+                TxResponse::from_event_attributes(&event.attributes)
+            }))
+        })
+    }
+
+    /// Query the indexed results of the txs signed by the given sender,
+    /// ordered by their height and position in block. The value in
+    /// successful response is a `Vec<TxResponse>` encoded with
+    /// [`BorshSerialize`].
+    fn txs_by_sender(&self, sender: &Address) -> response::Query {
+        self.query_indexer(|indexer| {
+            let mut responses = vec![];
+            let mut block: Option<BlockResults> = None;
+            for tx in indexer.txs_by_sender(sender)? {
+                // The txs of the same block are found in its results
+                if block
+                    .as_ref()
+                    .map_or(true, |block| block.height != tx.height)
+                {
+                    block = Some(indexer.block_results(tx.height)?);
                 }
-                Err(err) => response::Query {
-                    code: 1,
-                    info: format!(
-                        "Error parsing a storage key {}: {}",
-                        key, err
-                    ),
+                let response = block
+                    .as_ref()
+                    .and_then(|block| {
+                        block.txs.iter().find(|result| result.index == tx.index)
+                    })
+                    .and_then(|result| result.events.first())
+                    .and_then(|event| {
+                        TxResponse::from_event_attributes(&event.attributes)
+                    });
+                responses.extend(response);
+            }
+            Ok(responses)
+        })
+    }
+
+    /// Query the balances of all the tokens of an owner at the given height.
+    /// The tokens are found by the txs indexer and a token whose balance is
+    /// not found at the height is skipped. The value in successful response
+    /// is a `Vec<(Address, Amount)>` of the tokens and their balances encoded
+    /// with [`BorshSerialize`].
+    fn owner_balances(
+        &self,
+        owner: &Address,
+        height: BlockHeight,
+    ) -> response::Query {
+        let storage_error = |info: String| response::Query {
+            code: STORAGE_ERROR_CODE,
+            info,
+            ..Default::default()
+        };
+        let tokens = match &self.indexer {
+            Some(indexer) => match indexer.tokens_by_owner(owner) {
+                Ok(tokens) => tokens,
+                Err(err) => {
+                    return storage_error(format!("Indexer error: {}", err));
+                }
+            },
+            None => {
+                return response::Query {
+                    code: NOT_FOUND_CODE,
+                    info: "The txs indexer is not enabled on this node"
+                        .to_string(),
                     ..Default::default()
-                },
+                };
+            }
+        };
+        let mut balances: Vec<(Address, Amount)> = vec![];
+        for token in tokens {
+            let key = token::balance_key(&token, owner);
+            let value = match self.storage.read_with_height(&key, height) {
+                Ok((Some(value), _gas)) => value,
+                Ok((None, _gas)) => continue,
+                Err(err) => {
+                    return storage_error(format!("Storage error: {}", err));
+                }
+            };
+            match Amount::try_from_slice(&value[..]) {
+                Ok(amount) => balances.push((token, amount)),
+                Err(err) => {
+                    return storage_error(format!(
+                        "Error decoding the balance {}: {}",
+                        key, err
+                    ));
+                }
             }
         }
+        response::Query {
+            value: balances.try_to_vec().unwrap(),
+            ..Default::default()
+        }
     }
 
-    /// Query to check if a storage key exists.
-    fn has_storage_key(&self, key: &Key) -> response::Query {
-        match self.storage.has_key(key) {
-            Ok((has_key, _gas)) => response::Query {
-                value: has_key.try_to_vec().unwrap(),
+    /// Query the txs indexer. The value in successful response is the found
+    /// value encoded with [`BorshSerialize`].
+    fn query_indexer<T: BorshSerialize>(
+        &self,
+        find: impl FnOnce(&Indexer) -> indexer::Result<T>,
+    ) -> response::Query {
+        let indexer = match &self.indexer {
+            Some(indexer) => indexer,
+            None => {
+                return response::Query {
+                    code: 1,
+                    info: "The txs indexer is not enabled on this node"
+                        .to_string(),
+                    ..Default::default()
+                };
+            }
+        };
+        match find(indexer) {
+            Ok(found) => response::Query {
+                value: found.try_to_vec().unwrap(),
                 ..Default::default()
             },
             Err(err) => response::Query {
                 code: 2,
-                info: format!("Storage error: {}", err),
+                info: format!("Indexer error: {}", err),
                 ..Default::default()
             },
         }
@@ -343,3 +1331,613 @@ where
             })
     }
 }
+
+#[cfg(test)]
+mod test_queries {
+    use anoma::ledger::storage::ProofSpec;
+    use anoma::types::storage::BlockHash;
+    use anoma::types::time::{DateTimeUtc, DurationSecs};
+
+    use super::*;
+    use crate::node::ledger::shell::test_utils::*;
+
+    /// Test that the storage values with a matching prefix are read page by
+    /// page, each page starting after the last key of the previous one.
+    #[test]
+    fn test_read_storage_prefix_page() {
+        let (mut shell, _) = setup();
+        for i in 0..5_u8 {
+            let key = Key::parse(format!("test/{}", i)).unwrap();
+            shell.storage.write(&key, vec![i]).unwrap();
+        }
+        let prefix = Key::parse("test").unwrap();
+        let height = shell.storage.get_block_height().0;
+        let read_page = |after: Option<&Key>| {
+            let response = shell
+                .read_storage_prefix_page(&prefix, after, 2, height, false);
+            assert_eq!(response.code, 0);
+            assert_eq!(response.height, height.0 as i64);
+            PrefixPage::try_from_slice(&response.value[..]).unwrap()
+        };
+
+        let mut values = vec![];
+        let mut page = read_page(None);
+        let mut pages = 1;
+        while let Some(next) = page.next.take() {
+            values.extend(page.values.drain(..));
+            page = read_page(Some(&next));
+            pages += 1;
+        }
+        values.extend(page.values);
+        assert_eq!(pages, 3);
+        let values: Vec<u8> =
+            values.into_iter().map(|value| value.value[0]).collect();
+        assert_eq!(values, vec![0, 1, 2, 3, 4]);
+
+        // An empty page is found after the last key
+        let last = Key::parse("test/4").unwrap();
+        let page = read_page(Some(&last));
+        assert!(page.values.is_empty());
+        assert!(page.next.is_none());
+    }
+
+    /// Test that the values of multiple keys are read in the order of the
+    /// keys, with the keys that have no value.
+    #[test]
+    fn test_read_storage_values() {
+        let (mut shell, _) = setup();
+        let key = |i: u8| Key::parse(format!("test/{}", i)).unwrap();
+        shell.storage.write(&key(0), vec![0]).unwrap();
+        shell.storage.write(&key(2), vec![2]).unwrap();
+        let height = shell.storage.get_block_height().0;
+
+        let keys = vec![key(2), key(1), key(0)];
+        let response = shell.read_storage_values(&keys, height);
+        assert_eq!(response.code, 0);
+        let result =
+            MultiValueResult::try_from_slice(&response.value[..]).unwrap();
+        assert_eq!(result.height, height);
+        assert_eq!(
+            result.values,
+            vec![
+                (key(2), Some(vec![2])),
+                (key(1), None),
+                (key(0), Some(vec![0]))
+            ]
+        );
+
+        let keys = vec![key(0); MAX_VALUES_KEYS + 1];
+        assert_eq!(
+            shell.read_storage_values(&keys, height).code,
+            INVALID_QUERY_CODE
+        );
+    }
+
+    /// Test that the bonds query finds the self-bonds of the genesis
+    /// validators, with or without the validator.
+    #[test]
+    fn test_query_bonds() {
+        let (shell, _) = setup();
+        let decode = |response: response::Query| {
+            assert_eq!(response.code, 0);
+            Vec::<pos::BondQueryResult>::try_from_slice(&response.value[..])
+                .unwrap()
+        };
+        let results = decode(shell.bonds(None, None));
+        assert!(!results.is_empty());
+        for result in results {
+            assert!(!result.bonds.is_empty());
+            let self_bonds = decode(shell.bonds(None, Some(&result.validator)));
+            assert_eq!(self_bonds, vec![result.clone()]);
+            let owner_bonds =
+                decode(shell.bonds(Some(&result.validator), None));
+            assert!(owner_bonds.contains(&result));
+        }
+        let owner = address::testing::established_address_1();
+        assert_eq!(shell.bonds(Some(&owner), None).code, 1);
+    }
+
+    /// Test that the rewards query finds the self-bonds of the genesis
+    /// validators, which have no rewards before any block is finalized.
+    #[test]
+    fn test_query_rewards() {
+        let (shell, _) = setup();
+        let response = shell.rewards(None, None);
+        assert_eq!(response.code, 0);
+        let results =
+            Vec::<pos::RewardsQueryResult>::try_from_slice(&response.value[..])
+                .unwrap();
+        assert!(!results.is_empty());
+        for result in results {
+            assert_eq!(result.source, result.validator);
+            assert_eq!(result.claimed_epoch, None);
+            assert_eq!(result.pending, token::Amount::default());
+        }
+        let owner = address::testing::established_address_1();
+        assert_eq!(shell.rewards(Some(&owner), None).code, 1);
+    }
+
+    /// Test that the validator set of the last epoch has the genesis
+    /// validators with their consensus keys.
+    #[test]
+    fn test_query_validator_set() {
+        let (shell, _) = setup();
+        let response = shell.validator_set(None);
+        assert_eq!(response.code, 0);
+        let result =
+            pos::ValidatorSetQueryResult::try_from_slice(&response.value[..])
+                .unwrap();
+        assert_eq!(result.epoch, shell.storage.get_last_epoch().0);
+        assert!(!result.validators.is_empty());
+        for validator in &result.validators {
+            assert!(validator.active);
+            assert!(validator.consensus_key.is_some());
+            // The genesis validators haven't set any metadata
+            assert_eq!(validator.metadata, None);
+        }
+        let response = shell.validator_set(Some(result.epoch));
+        assert_eq!(
+            pos::ValidatorSetQueryResult::try_from_slice(&response.value[..])
+                .unwrap(),
+            result
+        );
+    }
+
+    /// Test that the validator set of an epoch is found in its snapshot, even
+    /// after the epoched validator sets changed.
+    #[test]
+    fn test_query_validator_set_at() {
+        let (mut shell, _) = setup();
+        let decode = |response: response::Query| {
+            assert_eq!(response.code, 0);
+            pos::ValidatorSetQueryResult::try_from_slice(&response.value[..])
+                .unwrap()
+        };
+        let epoch = shell.storage.get_last_epoch().0;
+        let result = decode(shell.validator_set_at(epoch));
+        assert!(!result.validators.is_empty());
+        assert_eq!(result, decode(shell.validator_set(Some(epoch))));
+
+        // Validator sets without any validators
+        shell.storage.write_validator_set(
+            &pos::ValidatorSets::init_at_genesis(
+                pos::types::ValidatorSet {
+                    active: Default::default(),
+                    inactive: Default::default(),
+                },
+                epoch,
+            ),
+        );
+        assert!(decode(shell.validator_set(Some(epoch)))
+            .validators
+            .is_empty());
+        assert_eq!(decode(shell.validator_set_at(epoch)), result);
+    }
+
+    /// Test that the slashes are found by their validator and their epoch,
+    /// in pages of at most the requested number of slashes.
+    #[test]
+    fn test_query_slashes() {
+        let (mut shell, _) = setup();
+        let validator = crate::wallet::defaults::validator_address();
+        for epoch in 1..=3 {
+            shell.storage.write_validator_slash(
+                &validator,
+                pos::Slash {
+                    epoch: epoch.into(),
+                    block_height: 10 * epoch,
+                    r#type: pos::types::SlashType::DuplicateVote,
+                    rate: pos::BasisPoints::new(100),
+                },
+            );
+        }
+        let query = |validator: Option<&Address>,
+                     from_epoch: Option<u64>,
+                     to_epoch: Option<u64>,
+                     start: Option<&pos::SlashesCursor>,
+                     limit: u64| {
+            let response = shell.slashes(
+                validator,
+                from_epoch.map(Epoch),
+                to_epoch.map(Epoch),
+                start,
+                limit,
+            );
+            assert_eq!(response.code, 0);
+            pos::SlashesPage::try_from_slice(&response.value[..]).unwrap()
+        };
+        let heights = |page: &pos::SlashesPage| -> Vec<u64> {
+            page.slashes
+                .iter()
+                .map(|slash| slash.slash.block_height)
+                .collect()
+        };
+
+        let page = query(None, None, None, None, 2);
+        assert_eq!(heights(&page), vec![10, 20]);
+        assert!(page
+            .slashes
+            .iter()
+            .all(|slash| slash.validator == validator));
+        let next = pos::SlashesCursor {
+            validator: validator.clone(),
+            index: 2,
+        };
+        assert_eq!(page.next, Some(next.clone()));
+        let page = query(None, None, None, Some(&next), 2);
+        assert_eq!(heights(&page), vec![30]);
+        assert_eq!(page.next, None);
+        let page = query(Some(&validator), None, None, Some(&next), 2);
+        assert_eq!(heights(&page), vec![30]);
+        assert_eq!(page.next, None);
+
+        let page = query(Some(&validator), Some(2), Some(2), None, 10);
+        assert_eq!(heights(&page), vec![20]);
+        let page = query(None, Some(3), None, None, 10);
+        assert_eq!(heights(&page), vec![30]);
+
+        let other = address::testing::established_address_1();
+        let page = query(Some(&other), None, None, None, 10);
+        assert!(page.slashes.is_empty());
+        assert_eq!(page.next, None);
+    }
+
+    /// Test that the PoS parameters are read as they're written in the
+    /// genesis.
+    #[test]
+    fn test_query_pos_params() {
+        let (shell, _) = setup();
+        let response = shell.pos_params();
+        assert_eq!(response.code, 0);
+        let params = PosParams::try_from_slice(&response.value[..]).unwrap();
+        let genesis_params = shell.storage.read_pos_params();
+        assert_eq!(params.pipeline_len, genesis_params.pipeline_len);
+        assert_eq!(params.unbonding_len, genesis_params.unbonding_len);
+        assert_eq!(params.votes_per_token, genesis_params.votes_per_token);
+    }
+
+    /// Test that the inflation is estimated from the genesis stakes and the
+    /// supply of the staking token and that it's minted into the staking
+    /// rewards at the end of the epoch, raising the tracked supply.
+    #[test]
+    fn test_query_inflation() {
+        let (mut shell, _) = setup();
+        let response = shell.inflation();
+        assert_eq!(response.code, 0);
+        let inflation =
+            pos::InflationQueryResult::try_from_slice(&response.value[..])
+                .unwrap();
+        let params = shell.storage.read_pos_params();
+        let (epoch, _gas) = shell.storage.get_last_epoch();
+        assert_eq!(inflation.epoch, epoch);
+        assert!(inflation.staked <= inflation.total_supply);
+        assert_ne!(inflation.total_supply, Amount::default());
+        let staked: u64 = inflation.staked.into();
+        let total_supply: u64 = inflation.total_supply.into();
+        assert_eq!(
+            inflation.inflation_rate,
+            params.inflation_rate(staked, total_supply)
+        );
+        assert_eq!(
+            u64::from(inflation.epoch_inflation),
+            params.epoch_inflation(inflation.inflation_rate, total_supply)
+        );
+
+        let minted =
+            shell.storage.mint_inflation_rewards(&params, epoch.next());
+        assert!(minted <= inflation.epoch_inflation);
+        if staked != 0 {
+            assert_ne!(minted, Amount::default());
+        }
+        assert_eq!(
+            shell.storage.read_staking_token_supply(),
+            inflation.total_supply + minted
+        );
+    }
+
+    /// Test that the proof specs are decoded from the response.
+    #[test]
+    fn test_query_proof_specs() {
+        let (shell, _) = setup();
+        let response = shell.proof_specs();
+        assert_eq!(response.code, 0);
+        let specs: Vec<ProofSpec> =
+            Vec::<Vec<u8>>::try_from_slice(&response.value[..])
+                .unwrap()
+                .iter()
+                .map(|spec| ProofSpec::decode(&spec[..]).unwrap())
+                .collect();
+        assert_eq!(specs.len(), 2);
+        assert_eq!(specs, shell.storage.block.tree.proof_specs());
+    }
+
+    /// Test that the epoch info is read from the genesis epoch and that the
+    /// start of the next epochs is estimated from it.
+    #[test]
+    fn test_query_epoch_info() {
+        let (shell, _) = setup();
+        let response = shell.epoch_info();
+        assert_eq!(response.code, 0);
+        let mut info = EpochInfo::try_from_slice(&response.value[..]).unwrap();
+        assert_eq!(info.epoch, Epoch(0));
+        assert_eq!(info.start_height, Some(BlockHeight(0)));
+        assert_eq!(
+            info.next_epoch_min_start_height,
+            shell.storage.next_epoch_min_start_height
+        );
+        assert_eq!(
+            info.next_epoch_min_start_time,
+            shell.storage.next_epoch_min_start_time
+        );
+
+        let now = DateTimeUtc::now();
+        info.height = BlockHeight(10);
+        info.next_epoch_min_start_height = BlockHeight(12);
+        info.next_epoch_min_start_time = now;
+        info.max_expected_time_per_block = DurationSecs(5);
+        info.epoch_duration = EpochDuration {
+            min_num_of_blocks: 10,
+            min_duration: DurationSecs(100),
+        };
+        assert_eq!(info.estimated_start_time(Epoch(0), now), None);
+        // The 2 remaining blocks end after the minimum start time
+        assert_eq!(
+            info.estimated_start_time(Epoch(1), now),
+            Some(now + DurationSecs(10))
+        );
+        // The later epochs last their minimum duration
+        assert_eq!(
+            info.estimated_start_time(Epoch(3), now),
+            Some(now + DurationSecs(210))
+        );
+    }
+
+    /// Test that the public key and the VP code hash of an account are read
+    /// together and that an address without either isn't an account.
+    #[test]
+    fn test_query_account() {
+        use anoma::types::key::RefTo;
+
+        let (mut shell, _) = setup();
+        let address = address::testing::established_address_1();
+        assert_eq!(shell.account_info(&address).code, 1);
+
+        let public_key = key::testing::keypair_1().ref_to();
+        let vp_code = vec![1, 2, 3];
+        shell
+            .storage
+            .write(&key::pk_key(&address), public_key.try_to_vec().unwrap())
+            .unwrap();
+        shell
+            .storage
+            .write(&Key::validity_predicate(&address), vp_code.clone())
+            .unwrap();
+        let response = shell.account_info(&address);
+        assert_eq!(response.code, 0);
+        let info = AccountInfo::try_from_slice(&response.value[..]).unwrap();
+        assert_eq!(
+            info,
+            AccountInfo {
+                address,
+                public_key: Some(public_key),
+                protocol_public_key: None,
+                vp_code_hash: Some(Hash::sha256(vp_code)),
+            }
+        );
+    }
+
+    /// Test that the storage usage of an account counts the keys and the
+    /// bytes of its subspace only, page by page.
+    #[test]
+    fn test_query_storage_usage() {
+        let (mut shell, _) = setup();
+        let address = address::testing::established_address_1();
+        let height = shell.storage.get_block_height().0;
+        let storage_usage = |shell: &TestShell,
+                             after: Option<&Key>,
+                             limit: usize| {
+            let response = shell.storage_usage(&address, after, limit, height);
+            assert_eq!(response.code, 0);
+            StorageUsagePage::try_from_slice(&response.value[..]).unwrap()
+        };
+        assert_eq!(
+            storage_usage(&shell, None, 10),
+            StorageUsagePage::default()
+        );
+
+        let vp_key = Key::validity_predicate(&address);
+        let counter_key = Key::from(address.to_db_key())
+            .push(&"counter".to_owned())
+            .unwrap();
+        let other_key =
+            Key::validity_predicate(&address::testing::established_address_2());
+        for key in [&vp_key, &counter_key, &other_key] {
+            shell.storage.write(key, vec![1, 2, 3]).unwrap();
+        }
+        let page = storage_usage(&shell, None, 10);
+        assert_eq!(
+            page.usage,
+            StorageUsage {
+                keys: 2,
+                bytes: (vp_key.len() + counter_key.len() + 6) as u64,
+            }
+        );
+        assert_eq!(page.next, None);
+
+        // The usage of the pages adds up to the usage of the subspace
+        let first = storage_usage(&shell, None, 1);
+        assert_eq!(first.usage.keys, 1);
+        let second = storage_usage(&shell, first.next.as_ref(), 1);
+        assert_eq!(second.next, None);
+        let mut usage = first.usage;
+        usage.add(&second.usage);
+        assert_eq!(usage, page.usage);
+    }
+
+    /// Test that the total supply of a token is the sum of its balances in
+    /// circulation, unless it's tracked by the token, and that it isn't found
+    /// when there are too many balances to sum.
+    #[test]
+    fn test_query_total_supply() {
+        let (mut shell, _) = setup();
+        let token = address::testing::established_address_1();
+        let height = shell.storage.get_block_height().0;
+        assert_eq!(shell.total_supply(&token, 10, height).code, 1);
+
+        let owners = [
+            address::testing::established_address_2(),
+            address::testing::established_address_3(),
+            Address::Internal(InternalAddress::IbcBurn),
+        ];
+        for owner in &owners {
+            shell
+                .storage
+                .write(
+                    &token::balance_key(&token, owner),
+                    Amount::from(10).try_to_vec().unwrap(),
+                )
+                .unwrap();
+        }
+        let total_supply = |shell: &TestShell| {
+            let response = shell.total_supply(&token, 10, height);
+            assert_eq!(response.code, 0);
+            Amount::try_from_slice(&response.value[..]).unwrap()
+        };
+        assert_eq!(total_supply(&shell), Amount::from(20));
+        assert_eq!(shell.total_supply(&token, 2, height).code, 1);
+
+        shell
+            .storage
+            .write(
+                &token::total_supply_key(&token),
+                Amount::from(100).try_to_vec().unwrap(),
+            )
+            .unwrap();
+        assert_eq!(total_supply(&shell), Amount::from(100));
+    }
+
+    /// Test that the values and the prefixes are read at a past block height
+    /// and that a height above the last block is rejected.
+    #[test]
+    fn test_historical_queries() {
+        let (mut shell, _) = setup();
+        let key = |i: u8| Key::parse(format!("test/{}", i)).unwrap();
+        let prefix = Key::parse("test").unwrap();
+        shell
+            .storage
+            .begin_block(BlockHash::default(), BlockHeight(1))
+            .unwrap();
+        shell.storage.write(&key(0), vec![0]).unwrap();
+        shell.storage.write(&key(1), vec![1]).unwrap();
+        shell.storage.commit().unwrap();
+        shell
+            .storage
+            .begin_block(BlockHash::default(), BlockHeight(2))
+            .unwrap();
+        shell.storage.write(&key(0), vec![10]).unwrap();
+        shell.storage.delete(&key(1)).unwrap();
+        shell.storage.write(&key(2), vec![2]).unwrap();
+        shell.storage.commit().unwrap();
+
+        let response = shell.read_storage_value(&key(0), BlockHeight(1), false);
+        assert_eq!(response.value, vec![0]);
+        let response = shell.read_storage_value(&key(1), BlockHeight(1), false);
+        assert_eq!(response.value, vec![1]);
+        let response = shell.read_storage_value(&key(2), BlockHeight(1), false);
+        assert_eq!(response.code, NOT_FOUND_CODE);
+        let response = shell.read_storage_value(&key(0), BlockHeight(2), false);
+        assert_eq!(response.value, vec![10]);
+
+        let read_prefix = |height: u64| {
+            let response =
+                shell.read_storage_prefix(&prefix, BlockHeight(height), false);
+            assert_eq!(response.code, 0);
+            Vec::<PrefixValue>::try_from_slice(&response.value[..])
+                .unwrap()
+                .into_iter()
+                .map(|PrefixValue { key, value }| (key, value))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(read_prefix(1), vec![(key(0), vec![0]), (key(1), vec![1])]);
+        assert_eq!(read_prefix(2), vec![(key(0), vec![10]), (key(2), vec![2])]);
+
+        let response = shell.query(request::Query {
+            data: Default::default(),
+            path: format!("value/{}", key(0)),
+            height: 10,
+            prove: false,
+        });
+        assert_eq!(response.code, 2);
+    }
+
+    /// Test that the node status is read from the last committed block.
+    #[test]
+    fn test_query_node_status() {
+        let (mut shell, _) = setup();
+        shell
+            .storage
+            .begin_block(BlockHash::default(), BlockHeight(1))
+            .unwrap();
+        shell.storage.commit().unwrap();
+
+        let response = shell.node_status();
+        assert_eq!(response.code, 0);
+        let status = NodeStatus::try_from_slice(&response.value[..]).unwrap();
+        assert_eq!(status.chain_id, shell.storage.chain_id.to_string());
+        assert_eq!(status.last_height, BlockHeight(1));
+        assert_eq!(status.app_hash, shell.storage.merkle_root().0);
+        assert_eq!(status.epoch, shell.storage.last_epoch);
+        assert_eq!(status.version, crate::anoma_version());
+        // The block has no header, so its time is unknown
+        assert_eq!(status.last_block_time, None);
+        assert_eq!(status.syncing, None);
+    }
+
+    /// Test that the changed keys of a range of blocks are found, and that
+    /// an invalid range is rejected.
+    #[test]
+    fn test_query_changed_keys() {
+        let (mut shell, _) = setup();
+        let key = |i: u8| Key::parse(format!("test/{}", i)).unwrap();
+        let prefix = Key::parse("test").unwrap();
+        shell
+            .storage
+            .begin_block(BlockHash::default(), BlockHeight(1))
+            .unwrap();
+        shell.storage.write(&key(0), vec![0]).unwrap();
+        shell.storage.commit().unwrap();
+        shell
+            .storage
+            .begin_block(BlockHash::default(), BlockHeight(2))
+            .unwrap();
+        shell.storage.write(&key(1), vec![1]).unwrap();
+        shell.storage.delete(&key(0)).unwrap();
+        shell.storage.commit().unwrap();
+        shell
+            .storage
+            .begin_block(BlockHash::default(), BlockHeight(3))
+            .unwrap();
+        shell.storage.write(&key(2), vec![2]).unwrap();
+        shell.storage.commit().unwrap();
+
+        let changed_keys = |from: u64, to: u64| {
+            let response = shell.changed_keys(
+                Some(&prefix),
+                BlockHeight(from),
+                BlockHeight(to),
+            );
+            assert_eq!(response.code, 0);
+            Vec::<Key>::try_from_slice(&response.value[..]).unwrap()
+        };
+        assert_eq!(changed_keys(1, 2), vec![key(0), key(1)]);
+        assert_eq!(changed_keys(2, 3), vec![key(2)]);
+        assert_eq!(changed_keys(1, 3), vec![key(0), key(1), key(2)]);
+        assert_eq!(changed_keys(3, 3), vec![]);
+
+        let response = shell.changed_keys(None, BlockHeight(2), BlockHeight(1));
+        assert_eq!(response.code, INVALID_QUERY_CODE);
+        let response =
+            shell.changed_keys(None, BlockHeight(1), BlockHeight(10));
+        assert_eq!(response.code, 2);
+    }
+}
@@ -0,0 +1,304 @@
+//! Shell methods of the Tendermint state sync, which take the snapshots of
+//! the state of the committed blocks and restore the state of a new node from
+//! the snapshot of another node
+
+use anoma::ledger::storage::update_epoch_in_merkle_tree;
+use tendermint_compat::tendermint_proto::abci::{
+    response_apply_snapshot_chunk, response_offer_snapshot,
+    RequestApplySnapshotChunk, RequestLoadSnapshotChunk, RequestOfferSnapshot,
+    ResponseApplySnapshotChunk, ResponseListSnapshots,
+    ResponseLoadSnapshotChunk, ResponseOfferSnapshot,
+};
+
+use super::*;
+
+impl<D, H> Shell<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    /// Request a snapshot of the state of the last committed block from the
+    /// snapshot worker, if its height is a multiple of the snapshot interval
+    pub(super) fn take_snapshot(&mut self) {
+        let height = self.storage.last_height;
+        match self.snapshot_interval {
+            Some(interval) if interval > 0 && height.0 % interval == 0 => {}
+            _ => return,
+        }
+        if let Some(worker) = &self.snapshot_worker {
+            worker.request(height);
+        }
+    }
+
+    /// List the snapshots of the node, which are offered to the nodes that
+    /// state-sync
+    pub fn list_snapshots(&self) -> ResponseListSnapshots {
+        let snapshots = match snapshots::list_snapshots(&self.snapshots_dir) {
+            Ok(snapshots) => snapshots,
+            Err(err) => {
+                tracing::error!("Failed to list the snapshots: {}", err);
+                vec![]
+            }
+        };
+        ResponseListSnapshots {
+            snapshots: snapshots
+                .iter()
+                .map(snapshots::SnapshotMetadata::to_abci)
+                .collect(),
+        }
+    }
+
+    /// Load a chunk of a snapshot of the node. An empty chunk is returned if
+    /// the snapshot or the chunk are not found.
+    pub fn load_snapshot_chunk(
+        &self,
+        req: RequestLoadSnapshotChunk,
+    ) -> ResponseLoadSnapshotChunk {
+        let chunk = if req.format == state_dump::STATE_DUMP_VERSION {
+            snapshots::load_chunk(
+                &self.snapshots_dir,
+                BlockHeight(req.height),
+                req.chunk,
+            )
+            .unwrap_or_else(|err| {
+                tracing::error!("Failed to load a snapshot chunk: {}", err);
+                None
+            })
+        } else {
+            None
+        };
+        ResponseLoadSnapshotChunk {
+            chunk: chunk.unwrap_or_default(),
+        }
+    }
+
+    /// Accept to restore the state from a snapshot offered by Tendermint, if
+    /// it's in a supported format and the chain has no state yet
+    pub fn offer_snapshot(
+        &mut self,
+        req: RequestOfferSnapshot,
+    ) -> ResponseOfferSnapshot {
+        use response_offer_snapshot::Result as OfferResult;
+
+        let result = match req.snapshot {
+            _ if self.storage.last_height.0 > 0 => {
+                tracing::error!(
+                    "A snapshot can only be restored on a new node, the last \
+                     committed height is {}",
+                    self.storage.last_height
+                );
+                OfferResult::Abort
+            }
+            Some(snapshot) => {
+                match snapshots::Restore::new(&snapshot, req.app_hash) {
+                    Ok(restore) => {
+                        tracing::info!(
+                            "Restoring the snapshot at height {} with {} \
+                             chunks",
+                            snapshot.height,
+                            snapshot.chunks
+                        );
+                        self.snapshot_restore = Some(restore);
+                        OfferResult::Accept
+                    }
+                    Err(snapshots::Error::UnsupportedFormat(_)) => {
+                        OfferResult::RejectFormat
+                    }
+                    Err(err) => {
+                        tracing::info!("Rejected a snapshot: {}", err);
+                        OfferResult::Reject
+                    }
+                }
+            }
+            None => OfferResult::Reject,
+        };
+        ResponseOfferSnapshot {
+            result: result as i32,
+        }
+    }
+
+    /// Apply a chunk of the snapshot that is being restored. Once all the
+    /// chunks are applied, the state of the snapshot is verified with the
+    /// trusted app hash and committed as the last block.
+    pub fn apply_snapshot_chunk(
+        &mut self,
+        req: RequestApplySnapshotChunk,
+    ) -> ResponseApplySnapshotChunk {
+        use response_apply_snapshot_chunk::Result as ApplyResult;
+
+        let mut response = ResponseApplySnapshotChunk::default();
+        let restore = match self.snapshot_restore.as_mut() {
+            Some(restore) => restore,
+            None => {
+                tracing::error!("No snapshot is being restored");
+                response.result = ApplyResult::Abort as i32;
+                return response;
+            }
+        };
+        let result = match restore.apply_chunk(req.index, req.chunk) {
+            Ok(()) if restore.is_complete() => {
+                let restore = self
+                    .snapshot_restore
+                    .take()
+                    .expect("The snapshot is being restored");
+                match restore
+                    .finish(&self.chain_id)
+                    .map_err(|err| err.to_string())
+                    .and_then(|dump| {
+                        self.restore_state(dump).map_err(|err| err.to_string())
+                    }) {
+                    Ok(()) => ApplyResult::Accept,
+                    Err(err) => {
+                        tracing::error!(
+                            "Failed to restore the snapshot: {}",
+                            err
+                        );
+                        ApplyResult::RejectSnapshot
+                    }
+                }
+            }
+            Ok(()) => ApplyResult::Accept,
+            Err(snapshots::Error::ChunkHashMismatch(index)) => {
+                // The chunk is fetched again from another peer
+                response.refetch_chunks = vec![index];
+                response.reject_senders = vec![req.sender];
+                ApplyResult::Retry
+            }
+            Err(err) => {
+                tracing::error!("Rejected the snapshot: {}", err);
+                self.snapshot_restore = None;
+                ApplyResult::RejectSnapshot
+            }
+        };
+        response.result = result as i32;
+        response
+    }
+
+    /// Write the verified state of a snapshot to the storage and commit it
+    /// as the last block, from which the chain continues
+    fn restore_state(&mut self, dump: state_dump::StateDump) -> Result<()> {
+        for (key, value) in dump.key_vals().map_err(Error::StateDump)? {
+            self.storage.write(&key, value).map_err(Error::Storage)?;
+        }
+        self.storage.block.height = dump.height;
+        self.storage.block.epoch = dump.epoch;
+        self.storage.block.pred_epochs = dump.pred_epochs;
+        self.storage.last_epoch = dump.epoch;
+        self.storage.next_epoch_min_start_height =
+            dump.next_epoch_min_start_height;
+        self.storage.next_epoch_min_start_time = dump.next_epoch_min_start_time;
+        self.storage.address_gen = dump.address_gen;
+        update_epoch_in_merkle_tree(
+            &mut self.storage.block.tree,
+            dump.epoch,
+            dump.next_epoch_min_start_height,
+            dump.next_epoch_min_start_time,
+        )
+        .map_err(Error::Storage)?;
+        self.storage.commit().map_err(Error::Storage)?;
+        tracing::info!(
+            "Restored the state at height {} with the root {}",
+            dump.height,
+            self.storage.merkle_root()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_state_sync {
+    use std::time::Duration;
+
+    use anoma::types::storage::BlockHash;
+    use anoma::types::{address, token};
+
+    use super::*;
+    use crate::node::ledger::shell::test_utils::*;
+    use crate::wallet::defaults;
+
+    /// Test that a new node restores the state of the snapshot of another
+    /// node, which is taken in the background, and that a chunk that doesn't
+    /// match its hash is fetched again.
+    #[test]
+    fn test_state_sync() {
+        let (mut shell, _) = setup();
+        shell.snapshot_interval = Some(1);
+        shell
+            .storage
+            .begin_block(BlockHash::default(), BlockHeight(1))
+            .unwrap();
+        shell.storage.commit().unwrap();
+        // The worker reads the state from a copy of the committed DB
+        shell.snapshot_worker = Some(snapshots::SnapshotWorker::start(
+            shell.storage.db.clone(),
+            |_| Ok(()),
+            shell.snapshots_dir.clone(),
+            shell.chain_id.clone(),
+            2,
+        ));
+        // The request is skipped until the worker waits for it
+        let mut snapshots = vec![];
+        for _ in 0..100 {
+            snapshots = shell.list_snapshots().snapshots;
+            if !snapshots.is_empty() {
+                break;
+            }
+            shell.take_snapshot();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(snapshots.len(), 1);
+        let snapshot = snapshots[0].clone();
+        let chunk = shell
+            .load_snapshot_chunk(RequestLoadSnapshotChunk {
+                height: snapshot.height,
+                format: snapshot.format,
+                chunk: 0,
+            })
+            .chunk;
+        assert!(!chunk.is_empty());
+
+        let (mut new, _) = TestShell::new();
+        let response = new.offer_snapshot(RequestOfferSnapshot {
+            snapshot: Some(snapshot),
+            app_hash: shell.storage.merkle_root().0,
+        });
+        assert_eq!(
+            response.result,
+            response_offer_snapshot::Result::Accept as i32
+        );
+        let mut modified = chunk.clone();
+        modified[0] ^= 1;
+        let response = new.apply_snapshot_chunk(RequestApplySnapshotChunk {
+            index: 0,
+            chunk: modified,
+            sender: "peer".to_string(),
+        });
+        assert_eq!(
+            response.result,
+            response_apply_snapshot_chunk::Result::Retry as i32
+        );
+        assert_eq!(response.refetch_chunks, vec![0]);
+        let response = new.apply_snapshot_chunk(RequestApplySnapshotChunk {
+            index: 0,
+            chunk,
+            sender: "another peer".to_string(),
+        });
+        assert_eq!(
+            response.result,
+            response_apply_snapshot_chunk::Result::Accept as i32
+        );
+
+        assert_eq!(new.storage.last_height, BlockHeight(1));
+        assert_eq!(new.storage.merkle_root(), shell.storage.merkle_root());
+        assert_eq!(
+            new.storage.block.pred_epochs,
+            shell.storage.block.pred_epochs
+        );
+        let balance_key =
+            token::balance_key(&address::xan(), &defaults::albert_address());
+        assert_eq!(
+            new.storage.read(&balance_key).unwrap().0,
+            shell.storage.read(&balance_key).unwrap().0
+        );
+    }
+}
@@ -2,6 +2,8 @@ use std::convert::TryFrom;
 use std::future::Future;
 use std::path::PathBuf;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
 
 #[cfg(feature = "ABCI")]
@@ -12,13 +14,17 @@ use anoma::types::storage::BlockHash;
 use anoma::types::transaction::hash_tx;
 use futures::future::FutureExt;
 #[cfg(feature = "ABCI")]
-use tendermint_proto_abci::abci::RequestBeginBlock;
+use tendermint_compat::tendermint_proto::abci::RequestBeginBlock;
 use tokio::sync::mpsc::UnboundedSender;
 use tower::Service;
 #[cfg(not(feature = "ABCI"))]
-use tower_abci::{BoxError, Request as Req, Response as Resp};
+use tower_abci::{
+    request, response, BoxError, Request as Req, Response as Resp,
+};
 #[cfg(feature = "ABCI")]
-use tower_abci_old::{BoxError, Request as Req, Response as Resp};
+use tower_abci_old::{
+    request, response, BoxError, Request as Req, Response as Resp,
+};
 
 use super::super::Shell;
 use super::abcipp_shim_types::shim::request::{FinalizeBlock, ProcessedTx};
@@ -26,13 +32,18 @@ use super::abcipp_shim_types::shim::request::{FinalizeBlock, ProcessedTx};
 use super::abcipp_shim_types::shim::response::TxResult;
 use super::abcipp_shim_types::shim::{Error, Request, Response};
 use crate::config;
+#[cfg(feature = "grpc")]
+use crate::node::ledger::key_watch::KeyWatcher;
+use crate::node::ledger::rate_limit::QueryRateLimiter;
+use crate::node::ledger::shell;
+use crate::node::ledger::storage::{PersistentBackend, PersistentDB};
 
 /// The shim wraps the shell, which implements ABCI++.
 /// The shim makes a crude translation between the ABCI interface currently used
 /// by tendermint and the shell's interface.
 #[derive(Debug)]
-pub struct AbcippShim {
-    service: Shell,
+pub struct AbcippShim<D = PersistentDB> {
+    service: Shell<D>,
     #[cfg(feature = "ABCI")]
     begin_block_request: Option<RequestBeginBlock>,
     processed_txs: Vec<ProcessedTx>,
@@ -40,38 +51,79 @@ pub struct AbcippShim {
         Req,
         tokio::sync::oneshot::Sender<Result<Resp, BoxError>>,
     )>,
+    /// The height of the last committed block, shared with the
+    /// [`QueryShim`]s
+    last_committed_height: Arc<AtomicU64>,
 }
 
-impl AbcippShim {
+impl<D: PersistentBackend> AbcippShim<D> {
     /// Create a shell with a ABCI service that passes messages to and from the
-    /// shell.
+    /// shell. The queries are passed to the given number of query shims
+    /// instead, each of which has its own read-only shell, unless they exceed
+    /// the rate limit.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         config: config::Ledger,
         wasm_dir: PathBuf,
         broadcast_sender: UnboundedSender<Vec<u8>>,
-        db_cache: &rocksdb::Cache,
+        db_cache: &D::Cache,
         vp_wasm_compilation_cache: u64,
         tx_wasm_compilation_cache: u64,
-    ) -> (Self, AbciService) {
+        num_query_shims: usize,
+        query_limiter: QueryRateLimiter,
+    ) -> (Self, Vec<QueryShim<D>>, AbciService) {
         // We can use an unbounded channel here, because tower-abci limits the
         // the number of requests that can come in
         let (shell_send, shell_recv) = std::sync::mpsc::channel();
-        (
-            Self {
-                service: Shell::new(
-                    config,
-                    wasm_dir,
-                    broadcast_sender,
+        let (query_send, query_recv) = std::sync::mpsc::channel();
+        // The main shell must be opened before the query shells, which follow
+        // its DB
+        let mut service = Shell::new(
+            config.clone(),
+            wasm_dir.clone(),
+            broadcast_sender,
+            Some(db_cache),
+            vp_wasm_compilation_cache,
+            tx_wasm_compilation_cache,
+        );
+        service.start_snapshot_worker(&config, Some(db_cache));
+        let last_committed_height =
+            Arc::new(AtomicU64::new(service.storage.last_height.0));
+        let query_recv = Arc::new(Mutex::new(query_recv));
+        let query_shims = (0..num_query_shims)
+            .map(|index| QueryShim {
+                service: Shell::new_query(
+                    config.clone(),
+                    wasm_dir.clone(),
                     Some(db_cache),
+                    index,
                     vp_wasm_compilation_cache,
                     tx_wasm_compilation_cache,
+                    service.node_stats.clone(),
                 ),
+                query_recv: query_recv.clone(),
+                last_committed_height: last_committed_height.clone(),
+            })
+            .collect();
+        #[cfg(feature = "grpc")]
+        let key_watcher = service.key_watcher.clone();
+        (
+            Self {
+                service,
                 #[cfg(feature = "ABCI")]
                 begin_block_request: None,
                 processed_txs: vec![],
                 shell_recv,
+                last_committed_height,
+            },
+            query_shims,
+            AbciService {
+                shell_send,
+                query_send,
+                query_limiter,
+                #[cfg(feature = "grpc")]
+                key_watcher,
             },
-            AbciService { shell_send },
         )
     }
 
@@ -90,6 +142,7 @@ impl AbcippShim {
     /// [`AbciService`].
     pub fn run(mut self) {
         while let Ok((req, resp_sender)) = self.shell_recv.recv() {
+            let is_commit = matches!(req, Req::Commit(_));
             let resp = match req {
                 #[cfg(not(feature = "ABCI"))]
                 Req::ProcessProposal(proposal) => {
@@ -180,7 +233,78 @@ impl AbcippShim {
                     Err(err) => Err(err),
                 },
             };
+            // At a protocol upgrade that this node doesn't run, the shell halts
+            // without committing the block
+            let halt = match &resp {
+                Err(Error::Shell(err @ shell::Error::Upgrade(_))) => {
+                    tracing::error!("Halting the ledger: {}", err);
+                    true
+                }
+                _ => false,
+            };
             let resp = resp.map_err(|e| e.into());
+            if is_commit {
+                // Let the query shims know that they can catch up
+                self.last_committed_height.store(
+                    self.service.storage.last_height.0,
+                    Ordering::SeqCst,
+                );
+            }
+            if resp_sender.send(resp).is_err() {
+                tracing::info!("ABCI response channel is closed")
+            }
+            if halt {
+                break;
+            }
+        }
+    }
+}
+
+/// The query shim serves the ABCI queries with a read-only shell, so that even
+/// heavy queries don't delay the consensus requests handled by the
+/// [`AbcippShim`].
+#[derive(Debug)]
+pub struct QueryShim<D = PersistentDB> {
+    service: Shell<D>,
+    query_recv: Arc<
+        Mutex<
+            std::sync::mpsc::Receiver<(
+                request::Query,
+                tokio::sync::oneshot::Sender<Result<Resp, BoxError>>,
+            )>,
+        >,
+    >,
+    last_committed_height: Arc<AtomicU64>,
+}
+
+impl<D: PersistentBackend> QueryShim<D> {
+    /// Run the query shell's blocking loop that receives queries from the
+    /// [`AbciService`]. The query shim shares the queue of queries with the
+    /// other query shims.
+    pub fn run(mut self) {
+        loop {
+            let msg = self
+                .query_recv
+                .lock()
+                .expect("The query channel lock shouldn't be poisoned")
+                .recv();
+            let (query, resp_sender) = match msg {
+                Ok(msg) => msg,
+                // The ABCI service has shut-down
+                Err(_) => break,
+            };
+            let last_committed_height =
+                self.last_committed_height.load(Ordering::SeqCst);
+            if self.service.storage.last_height.0 < last_committed_height {
+                if let Err(err) = self.service.catch_up_with_primary() {
+                    tracing::error!(
+                        "The query shell failed to catch up with the last \
+                         committed block: {}",
+                        err
+                    );
+                }
+            }
+            let resp = Ok(Resp::Query(self.service.query(query)));
             if resp_sender.send(resp).is_err() {
                 tracing::info!("ABCI response channel is closed")
             }
@@ -188,16 +312,114 @@ impl AbcippShim {
     }
 }
 
+/// The sender of the queries to the [`QueryShim`]s, with the channel of the
+/// response of each query
+pub type QuerySender = std::sync::mpsc::Sender<(
+    request::Query,
+    tokio::sync::oneshot::Sender<Result<Resp, BoxError>>,
+)>;
+
 #[derive(Debug)]
 pub struct AbciService {
     shell_send: std::sync::mpsc::Sender<(
         Req,
         tokio::sync::oneshot::Sender<Result<Resp, BoxError>>,
     )>,
+    query_send: QuerySender,
+    query_limiter: QueryRateLimiter,
+    #[cfg(feature = "grpc")]
+    key_watcher: KeyWatcher,
+}
+
+impl AbciService {
+    /// Get a handle to the [`QueryShim`]s for the queries served by other
+    /// servers than ABCI
+    #[cfg(any(feature = "grpc", feature = "rest"))]
+    pub fn query_handle(&self) -> QueryHandle {
+        QueryHandle::new(self.query_send.clone(), self.query_limiter.clone())
+    }
+
+    /// Get the publisher of the changes of the committed blocks, to which the
+    /// clients of the gRPC server subscribe
+    #[cfg(feature = "grpc")]
+    pub fn key_watcher(&self) -> KeyWatcher {
+        self.key_watcher.clone()
+    }
+}
+
+/// The error of a query sent with a [`QueryHandle`]
+#[cfg(any(feature = "grpc", feature = "rest"))]
+#[allow(missing_docs)]
+#[derive(thiserror::Error, Debug)]
+pub enum QueryError {
+    #[error("{0}")]
+    Rejected(crate::node::ledger::rpc::QueryRejection),
+    #[error("The ledger has shut down")]
+    ShutDown,
+    #[error("The query failed: {0}")]
+    Failed(BoxError),
+}
+
+/// A handle to the [`QueryShim`]s for the queries served by other servers
+/// than ABCI, with the rate limits and the access control of the ABCI queries
+#[cfg(any(feature = "grpc", feature = "rest"))]
+#[derive(Debug)]
+pub struct QueryHandle {
+    /// The sender isn't `Sync`, so it's shared by the requests behind a lock
+    query_send: Mutex<QuerySender>,
+    query_limiter: QueryRateLimiter,
+}
+
+#[cfg(any(feature = "grpc", feature = "rest"))]
+impl QueryHandle {
+    /// Create a handle that sends the queries to the given channel
+    pub fn new(
+        query_send: QuerySender,
+        query_limiter: QueryRateLimiter,
+    ) -> Self {
+        Self {
+            query_send: Mutex::new(query_send),
+            query_limiter,
+        }
+    }
+
+    /// Send the query to the query shims, unless it's rejected by the rate
+    /// limiter, and wait for its response
+    pub async fn query(
+        &self,
+        query: request::Query,
+    ) -> Result<response::Query, QueryError> {
+        self.try_acquire(&query.path)?;
+        let (resp_send, resp_recv) = tokio::sync::oneshot::channel();
+        let sent = self
+            .query_send
+            .lock()
+            .expect("The query channel lock shouldn't be poisoned")
+            .send((query, resp_send));
+        if sent.is_err() {
+            return Err(QueryError::ShutDown);
+        }
+        match resp_recv.await {
+            Ok(Ok(Resp::Query(response))) => Ok(response),
+            Ok(Ok(_)) => unreachable!("The query shims only answer queries"),
+            Ok(Err(err)) => Err(QueryError::Failed(err)),
+            Err(_) => Err(QueryError::ShutDown),
+        }
+    }
+
+    /// Check a request of the given path that isn't answered by the query
+    /// shims, e.g. a subscription, against the rate limits and the access
+    /// control of the queries and count it
+    pub fn try_acquire(&self, path: &str) -> Result<(), QueryError> {
+        self.query_limiter
+            .try_acquire(path)
+            .map_err(QueryError::Rejected)
+    }
 }
 
 /// The ABCI tower service implementation sends and receives messages to and
-/// from the [`AbcippShim`] for requests from Tendermint.
+/// from the [`AbcippShim`] for requests from Tendermint. The queries are sent
+/// to the [`QueryShim`]s instead.
 impl Service<Req> for AbciService {
     type Error = BoxError;
     type Future =
@@ -213,13 +435,33 @@ impl Service<Req> for AbciService {
     }
 
     fn call(&mut self, req: Req) -> Self::Future {
+        if let Req::Query(query) = &req {
+            if let Err(rejection) = self.query_limiter.try_acquire(&query.path)
+            {
+                let resp = Resp::Query(response::Query {
+                    code: rejection.code(),
+                    info: rejection.to_string(),
+                    ..Default::default()
+                });
+                return Box::pin(async move { Ok(resp) }.boxed());
+            }
+        }
         let (resp_send, recv) = tokio::sync::oneshot::channel();
-        let result = self.shell_send.send((req, resp_send));
+        let result: Result<(), BoxError> = match req {
+            Req::Query(query) => self
+                .query_send
+                .send((query, resp_send))
+                .map_err(|err| err.into()),
+            req => self
+                .shell_send
+                .send((req, resp_send))
+                .map_err(|err| err.into()),
+        };
         Box::pin(
             async move {
                 if let Err(err) = result {
                     // The shell has shut-down
-                    return Err(err.into());
+                    return Err(err);
                 }
                 match recv.await {
                     Ok(resp) => resp,
@@ -6,8 +6,18 @@ use tower_abci_old::{Request, Response};
 pub mod shim {
     use std::convert::TryFrom;
 
+    #[cfg(feature = "ABCI")]
+    use tendermint_compat::tendermint_proto::abci::{
+        RequestApplySnapshotChunk, RequestCheckTx, RequestCommit,
+        RequestDeliverTx, RequestEcho, RequestFlush, RequestInfo,
+        RequestInitChain, RequestListSnapshots, RequestLoadSnapshotChunk,
+        RequestOfferSnapshot, RequestQuery, ResponseApplySnapshotChunk,
+        ResponseCheckTx, ResponseCommit, ResponseEcho, ResponseEndBlock,
+        ResponseFlush, ResponseInfo, ResponseInitChain, ResponseListSnapshots,
+        ResponseLoadSnapshotChunk, ResponseOfferSnapshot, ResponseQuery,
+    };
     #[cfg(not(feature = "ABCI"))]
-    use tendermint_proto::abci::{
+    use tendermint_compat::tendermint_proto::abci::{
         RequestApplySnapshotChunk, RequestCheckTx, RequestCommit, RequestEcho,
         RequestExtendVote, RequestFlush, RequestInfo, RequestInitChain,
         RequestListSnapshots, RequestLoadSnapshotChunk, RequestOfferSnapshot,
@@ -19,16 +29,6 @@ pub mod shim {
         ResponsePrepareProposal, ResponseProcessProposal, ResponseQuery,
         ResponseVerifyVoteExtension,
     };
-    #[cfg(feature = "ABCI")]
-    use tendermint_proto_abci::abci::{
-        RequestApplySnapshotChunk, RequestCheckTx, RequestCommit,
-        RequestDeliverTx, RequestEcho, RequestFlush, RequestInfo,
-        RequestInitChain, RequestListSnapshots, RequestLoadSnapshotChunk,
-        RequestOfferSnapshot, RequestQuery, ResponseApplySnapshotChunk,
-        ResponseCheckTx, ResponseCommit, ResponseEcho, ResponseEndBlock,
-        ResponseFlush, ResponseInfo, ResponseInitChain, ResponseListSnapshots,
-        ResponseLoadSnapshotChunk, ResponseOfferSnapshot, ResponseQuery,
-    };
     use thiserror::Error;
 
     use super::{Request as Req, Response as Resp};
@@ -208,12 +208,17 @@ pub mod shim {
         use anoma::types::hash::Hash;
         use anoma::types::storage::{BlockHash, Header};
         use anoma::types::time::DateTimeUtc;
+        use borsh::{BorshDeserialize, BorshSerialize};
+        use prost::Message;
+        use tendermint_compat::tendermint_proto::abci::VoteInfo;
+        #[cfg(feature = "ABCI")]
+        use tendermint_compat::tendermint_proto::abci::{
+            Evidence, RequestBeginBlock,
+        };
         #[cfg(not(feature = "ABCI"))]
-        use tendermint_proto::abci::{
+        use tendermint_compat::tendermint_proto::abci::{
             Misbehavior as Evidence, RequestFinalizeBlock,
         };
-        #[cfg(feature = "ABCI")]
-        use tendermint_proto_abci::abci::{Evidence, RequestBeginBlock};
 
         pub struct VerifyHeader;
 
@@ -231,9 +236,131 @@ pub mod shim {
             pub hash: BlockHash,
             pub header: Header,
             pub byzantine_validators: Vec<Evidence>,
+            /// The signatures of the validators on the last block's commit
+            pub votes: Vec<VoteInfo>,
             pub txs: Vec<ProcessedTx>,
         }
 
+        /// The [`FinalizeBlock`] request in a form that can be persisted with
+        /// its block
+        #[derive(BorshSerialize, BorshDeserialize)]
+        struct EncodedFinalizeBlock {
+            hash: BlockHash,
+            header: Header,
+            /// Protobuf encoded evidence
+            byzantine_validators: Vec<Vec<u8>>,
+            /// The txs with the code and info of their results
+            txs: Vec<(super::TxBytes, u32, String)>,
+            /// Protobuf encoded votes of the last block's commit
+            votes: Vec<Vec<u8>>,
+        }
+
+        /// The [`EncodedFinalizeBlock`] of the requests persisted before the
+        /// votes were added, which are decoded without votes
+        #[derive(BorshSerialize, BorshDeserialize)]
+        struct EncodedFinalizeBlockWithoutVotes {
+            hash: BlockHash,
+            header: Header,
+            byzantine_validators: Vec<Vec<u8>>,
+            txs: Vec<(super::TxBytes, u32, String)>,
+        }
+
+        impl From<EncodedFinalizeBlockWithoutVotes> for EncodedFinalizeBlock {
+            fn from(req: EncodedFinalizeBlockWithoutVotes) -> Self {
+                Self {
+                    hash: req.hash,
+                    header: req.header,
+                    byzantine_validators: req.byzantine_validators,
+                    txs: req.txs,
+                    votes: vec![],
+                }
+            }
+        }
+
+        impl FinalizeBlock {
+            /// Encode the request, so that the block can be re-executed from
+            /// it
+            pub fn encode(&self) -> Vec<u8> {
+                EncodedFinalizeBlock {
+                    hash: self.hash.clone(),
+                    header: self.header.clone(),
+                    byzantine_validators: self
+                        .byzantine_validators
+                        .iter()
+                        .map(|evidence| evidence.encode_to_vec())
+                        .collect(),
+                    txs: self
+                        .txs
+                        .iter()
+                        .map(|ProcessedTx { tx, result }| {
+                            (tx.clone(), result.code, result.info.clone())
+                        })
+                        .collect(),
+                    votes: self
+                        .votes
+                        .iter()
+                        .map(|vote| vote.encode_to_vec())
+                        .collect(),
+                }
+                .try_to_vec()
+                .expect("Encoding a block request shouldn't fail")
+            }
+
+            /// Decode a request encoded with [`FinalizeBlock::encode`], or
+            /// with the encoding of the versions without the votes, from
+            /// which the request is decoded without votes
+            pub fn decode(bytes: &[u8]) -> std::io::Result<Self> {
+                let EncodedFinalizeBlock {
+                    hash,
+                    header,
+                    byzantine_validators,
+                    txs,
+                    votes,
+                } = EncodedFinalizeBlock::try_from_slice(bytes).or_else(
+                    |err| {
+                        EncodedFinalizeBlockWithoutVotes::try_from_slice(bytes)
+                            .map(EncodedFinalizeBlock::from)
+                            // The error of the current encoding is reported
+                            .map_err(|_| err)
+                    },
+                )?;
+                let byzantine_validators = byzantine_validators
+                    .iter()
+                    .map(|bytes| Evidence::decode(&bytes[..]))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|err| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            err,
+                        )
+                    })?;
+                let votes = votes
+                    .iter()
+                    .map(|bytes| VoteInfo::decode(&bytes[..]))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|err| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            err,
+                        )
+                    })?;
+                let txs = txs
+                    .into_iter()
+                    .map(|(tx, code, info)| ProcessedTx {
+                        tx,
+                        result: super::response::TxResult { code, info },
+                    })
+                    .collect();
+                Ok(Self {
+                    hash,
+                    header,
+                    byzantine_validators,
+                    votes,
+                    txs,
+                })
+            }
+        }
+
         #[cfg(not(feature = "ABCI"))]
         impl From<RequestFinalizeBlock> for FinalizeBlock {
             fn from(req: RequestFinalizeBlock) -> FinalizeBlock {
@@ -248,6 +375,10 @@ pub mod shim {
                         .unwrap(),
                     },
                     byzantine_validators: req.byzantine_validators,
+                    votes: req
+                        .decided_last_commit
+                        .map(|commit| commit.votes)
+                        .unwrap_or_default(),
                     txs: vec![],
                 }
             }
@@ -269,25 +400,79 @@ pub mod shim {
                         .unwrap(),
                     },
                     byzantine_validators: req.byzantine_validators,
+                    votes: req
+                        .last_commit_info
+                        .map(|commit| commit.votes)
+                        .unwrap_or_default(),
+                    txs: vec![],
+                }
+            }
+        }
+
+        #[cfg(test)]
+        mod test_finalize_block_encoding {
+            use super::*;
+
+            fn header() -> Header {
+                Header {
+                    hash: Hash::default(),
+                    time: DateTimeUtc::now(),
+                    next_validators_hash: Hash::default(),
+                }
+            }
+
+            /// Test that a request is decoded with its votes and that a
+            /// request persisted without votes is decoded without votes.
+            #[test]
+            fn test_decode_with_and_without_votes() {
+                let request = FinalizeBlock {
+                    hash: BlockHash::default(),
+                    header: header(),
+                    byzantine_validators: vec![],
+                    votes: vec![VoteInfo {
+                        validator: None,
+                        signed_last_block: true,
+                    }],
                     txs: vec![],
+                };
+                let decoded = FinalizeBlock::decode(&request.encode()).unwrap();
+                assert_eq!(decoded.votes, request.votes);
+
+                let tx = (vec![1, 2, 3], 0, String::from("ok"));
+                let old_request = EncodedFinalizeBlockWithoutVotes {
+                    hash: BlockHash::default(),
+                    header: header(),
+                    byzantine_validators: vec![],
+                    txs: vec![tx.clone()],
                 }
+                .try_to_vec()
+                .unwrap();
+                let decoded = FinalizeBlock::decode(&old_request).unwrap();
+                assert!(decoded.votes.is_empty());
+                assert_eq!(decoded.txs.len(), 1);
+                assert_eq!(decoded.txs[0].tx, tx.0);
+                assert_eq!(decoded.txs[0].result.info, tx.2);
+
+                assert!(FinalizeBlock::decode(&[1, 2, 3]).is_err());
             }
         }
     }
 
     /// Custom types for response payloads
     pub mod response {
+        #[cfg(feature = "ABCI")]
+        use tendermint_compat::tendermint_proto::abci::ConsensusParams;
         #[cfg(not(feature = "ABCI"))]
-        use tendermint_proto::abci::{
+        use tendermint_compat::tendermint_proto::abci::{
             Event as TmEvent, ExecTxResult, ResponseFinalizeBlock,
             ValidatorUpdate,
         };
-        #[cfg(not(feature = "ABCI"))]
-        use tendermint_proto::types::ConsensusParams;
-        #[cfg(feature = "ABCI")]
-        use tendermint_proto_abci::abci::ConsensusParams;
         #[cfg(feature = "ABCI")]
-        use tendermint_proto_abci::abci::{Event as TmEvent, ValidatorUpdate};
+        use tendermint_compat::tendermint_proto::abci::{
+            Event as TmEvent, ValidatorUpdate,
+        };
+        #[cfg(not(feature = "ABCI"))]
+        use tendermint_compat::tendermint_proto::types::ConsensusParams;
         #[cfg(feature = "ABCI")]
         use tower_abci_old::response;
 
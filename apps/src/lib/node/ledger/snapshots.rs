@@ -0,0 +1,472 @@
+//! The snapshots of the state of the ledger, which are served to the new
+//! nodes that state-sync with Tendermint instead of replaying all the blocks
+//! from the genesis. A snapshot is the [`StateDump`] of a committed block,
+//! split into chunks of [`CHUNK_SIZE`] bytes. The format of a snapshot is the
+//! version of the state dump and its metadata has the hashes of its chunks,
+//! so that every chunk is verified before it's accepted.
+//!
+//! The snapshots are stored in the snapshots directory of the chain, with a
+//! directory per height that has the encoded state dump and its metadata.
+//! They're taken by a [`SnapshotWorker`] in the background, from its own
+//! instance of the DB.
+
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::thread;
+
+use anoma::bytes::ByteBuf;
+use anoma::ledger::storage::{self as ledger_storage, DBIter, DB};
+use anoma::types::chain::ChainId;
+use anoma::types::hash::Hash;
+use anoma::types::storage::BlockHeight;
+use borsh::{BorshDeserialize, BorshSerialize};
+use tendermint_compat::tendermint_proto::abci::Snapshot;
+use thiserror::Error;
+
+use super::state_dump::{self, StateDump, STATE_DUMP_VERSION};
+
+/// The maximum size of a chunk of a snapshot, below the 16 MiB limit of the
+/// chunks of Tendermint
+pub const CHUNK_SIZE: usize = 10 * 1024 * 1024;
+
+/// The file of the encoded state dump of a snapshot
+const STATE_FILE: &str = "state.borsh";
+/// The file of the metadata of a snapshot
+const METADATA_FILE: &str = "metadata.borsh";
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("{0}")]
+    StateDump(state_dump::Error),
+    #[error("Error catching up with the committed blocks: {0}")]
+    CatchUp(ledger_storage::Error),
+    #[error("Error encoding the snapshot: {0}")]
+    Encode(std::io::Error),
+    #[error("Error decoding the state of the snapshot: {0}")]
+    Decode(std::io::Error),
+    #[error("Invalid snapshot metadata: {0}")]
+    InvalidMetadata(std::io::Error),
+    #[error("Error reading the snapshot from {0}: {1}")]
+    Read(PathBuf, std::io::Error),
+    #[error("Error writing the snapshot to {0}: {1}")]
+    Write(PathBuf, std::io::Error),
+    #[error("Error removing the snapshot {0}: {1}")]
+    Remove(PathBuf, std::io::Error),
+    #[error(
+        "Unsupported snapshot format {0}, the supported format is {}",
+        STATE_DUMP_VERSION
+    )]
+    UnsupportedFormat(u32),
+    #[error("The metadata of the snapshot doesn't match the snapshot")]
+    MetadataMismatch,
+    #[error("The snapshot has no chunk {0}")]
+    InvalidChunkIndex(u32),
+    #[error("The chunk {0} doesn't match its hash in the snapshot metadata")]
+    ChunkHashMismatch(u32),
+    #[error("The chunks of the snapshot don't match the snapshot hash")]
+    HashMismatch,
+    #[error(
+        "The state of the snapshot is at height {state}, but the snapshot is \
+         at height {snapshot}"
+    )]
+    HeightMismatch {
+        state: BlockHeight,
+        snapshot: BlockHeight,
+    },
+    #[error("The snapshot is of another chain {0}")]
+    ChainIdMismatch(ChainId),
+    #[error(
+        "The Merkle root of the snapshot state {root} doesn't match the \
+         trusted app hash {app_hash}"
+    )]
+    AppHashMismatch { root: String, app_hash: String },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The metadata of a snapshot, which is also sent in the metadata of the
+/// snapshot offered to Tendermint
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct SnapshotMetadata {
+    /// The height of the block of the snapshot
+    pub height: BlockHeight,
+    /// The hash of the encoded state dump
+    pub hash: Hash,
+    /// The hashes of the chunks of the encoded state dump, in order
+    pub chunk_hashes: Vec<Hash>,
+}
+
+impl SnapshotMetadata {
+    /// The snapshot described by the metadata, as offered to Tendermint
+    pub fn to_abci(&self) -> Snapshot {
+        Snapshot {
+            height: self.height.0,
+            format: STATE_DUMP_VERSION,
+            chunks: self.chunk_hashes.len() as u32,
+            hash: self.hash.0.to_vec(),
+            metadata: self
+                .try_to_vec()
+                .expect("Encoding the snapshot metadata shouldn't fail"),
+        }
+    }
+}
+
+/// Take a snapshot of the state of the last block committed to the DB into
+/// the snapshots directory. The oldest snapshots are removed, so that only
+/// the `keep_recent` last ones are kept.
+pub fn take_snapshot<D>(
+    dir: &Path,
+    db: &mut D,
+    chain_id: ChainId,
+    keep_recent: u64,
+) -> Result<SnapshotMetadata>
+where
+    D: DB + for<'iter> DBIter<'iter>,
+{
+    let dump =
+        StateDump::read_from_db(db, chain_id).map_err(Error::StateDump)?;
+    let bytes = dump.try_to_vec().map_err(Error::Encode)?;
+    let metadata = SnapshotMetadata {
+        height: dump.height,
+        hash: Hash::sha256(&bytes),
+        chunk_hashes: bytes.chunks(CHUNK_SIZE).map(Hash::sha256).collect(),
+    };
+    let snapshot_dir = dir.join(dump.height.to_string());
+    fs::create_dir_all(&snapshot_dir)
+        .map_err(|err| Error::Write(snapshot_dir.clone(), err))?;
+    write_file(&snapshot_dir.join(STATE_FILE), &bytes)?;
+    // The metadata is written last, so that an incomplete snapshot is not
+    // listed
+    let metadata_bytes = metadata.try_to_vec().map_err(Error::Encode)?;
+    write_file(&snapshot_dir.join(METADATA_FILE), &metadata_bytes)?;
+
+    let snapshots = list_snapshots(dir)?;
+    let removed = snapshots.len().saturating_sub(keep_recent as usize);
+    for snapshot in &snapshots[..removed] {
+        let snapshot_dir = dir.join(snapshot.height.to_string());
+        fs::remove_dir_all(&snapshot_dir)
+            .map_err(|err| Error::Remove(snapshot_dir, err))?;
+    }
+    Ok(metadata)
+}
+
+/// The taker of the snapshots in a background thread, so that the commit of
+/// the blocks isn't delayed by them. The thread reads the state from its own
+/// instance of the DB, which is caught up with the committed blocks before
+/// every snapshot.
+#[derive(Debug)]
+pub struct SnapshotWorker {
+    sender: SyncSender<BlockHeight>,
+}
+
+impl SnapshotWorker {
+    /// Start the thread that takes the snapshots of the `db` into the
+    /// snapshots directory, keeping the `keep_recent` last ones. The `db` is
+    /// caught up with `catch_up` before every snapshot.
+    pub fn start<D, F>(
+        mut db: D,
+        mut catch_up: F,
+        dir: PathBuf,
+        chain_id: ChainId,
+        keep_recent: u64,
+    ) -> Self
+    where
+        D: DB + for<'iter> DBIter<'iter> + Send + 'static,
+        F: FnMut(&D) -> ledger_storage::Result<()> + Send + 'static,
+    {
+        // A snapshot is only requested while the thread waits for it
+        let (sender, receiver) = mpsc::sync_channel(0);
+        thread::Builder::new()
+            .name("snapshots".into())
+            .spawn(move || {
+                for height in receiver {
+                    let result =
+                        catch_up(&db).map_err(Error::CatchUp).and_then(|()| {
+                            take_snapshot(
+                                &dir,
+                                &mut db,
+                                chain_id.clone(),
+                                keep_recent,
+                            )
+                        });
+                    log_snapshot(height, result);
+                }
+            })
+            .expect("Must be able to start a thread for the snapshots");
+        Self { sender }
+    }
+
+    /// Request a snapshot of the last committed block at the given height.
+    /// The request is skipped while another snapshot is being taken.
+    pub fn request(&self, height: BlockHeight) {
+        match self.sender.try_send(height) {
+            Ok(()) => {}
+            Err(TrySendError::Full(height)) => tracing::info!(
+                "Skipped the snapshot at height {}, the previous snapshot is \
+                 still being taken",
+                height
+            ),
+            Err(TrySendError::Disconnected(height)) => tracing::error!(
+                "Skipped the snapshot at height {}, the snapshot worker has \
+                 stopped",
+                height
+            ),
+        }
+    }
+}
+
+/// Log the result of a snapshot requested at the given height. The snapshot
+/// is of the last block committed when it's taken, which may be later.
+fn log_snapshot(height: BlockHeight, result: Result<SnapshotMetadata>) {
+    match result {
+        Ok(metadata) => tracing::info!(
+            "Took a snapshot of the state at height {} with {} chunks",
+            metadata.height,
+            metadata.chunk_hashes.len()
+        ),
+        // The state of a block with wrapper txs to be decrypted in the next
+        // block can't be restored without them, so no snapshot is taken
+        Err(Error::StateDump(state_dump::Error::PendingTxs(height, _))) => {
+            tracing::debug!(
+                "No snapshot of the state at height {}, which has txs to be \
+                 decrypted",
+                height
+            )
+        }
+        Err(err) => tracing::error!(
+            "Failed to take a snapshot of the state at height {}: {}",
+            height,
+            err
+        ),
+    }
+}
+
+/// List the metadata of the snapshots in the snapshots directory, ordered by
+/// their height
+pub fn list_snapshots(dir: &Path) -> Result<Vec<SnapshotMetadata>> {
+    let entries = match fs::read_dir(dir) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(vec![]);
+        }
+        res => res.map_err(|err| Error::Read(dir.into(), err))?,
+    };
+    let mut snapshots = vec![];
+    for entry in entries {
+        let entry = entry.map_err(|err| Error::Read(dir.into(), err))?;
+        let path = entry.path().join(METADATA_FILE);
+        if !path.is_file() {
+            continue;
+        }
+        let bytes =
+            fs::read(&path).map_err(|err| Error::Read(path.clone(), err))?;
+        let metadata = SnapshotMetadata::try_from_slice(&bytes)
+            .map_err(Error::InvalidMetadata)?;
+        snapshots.push(metadata);
+    }
+    snapshots.sort_by_key(|metadata| metadata.height);
+    Ok(snapshots)
+}
+
+/// Load a chunk of the snapshot at the given height. Returns `None` if there
+/// is no such snapshot or chunk.
+pub fn load_chunk(
+    dir: &Path,
+    height: BlockHeight,
+    index: u32,
+) -> Result<Option<Vec<u8>>> {
+    let path = dir.join(height.to_string()).join(STATE_FILE);
+    let read_err = |err| Error::Read(path.clone(), err);
+    let mut file = match File::open(&path) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(None);
+        }
+        res => res.map_err(read_err)?,
+    };
+    let start = index as u64 * CHUNK_SIZE as u64;
+    if start >= file.metadata().map_err(read_err)?.len() {
+        return Ok(None);
+    }
+    file.seek(SeekFrom::Start(start)).map_err(read_err)?;
+    let mut chunk = vec![];
+    file.take(CHUNK_SIZE as u64)
+        .read_to_end(&mut chunk)
+        .map_err(read_err)?;
+    Ok(Some(chunk))
+}
+
+fn write_file(path: &Path, bytes: &[u8]) -> Result<()> {
+    fs::write(path, bytes).map_err(|err| Error::Write(path.into(), err))
+}
+
+/// A snapshot offered by Tendermint that is restored from its chunks
+#[derive(Debug)]
+pub struct Restore {
+    metadata: SnapshotMetadata,
+    /// The trusted app hash of the block of the snapshot
+    app_hash: Vec<u8>,
+    /// The chunks applied so far
+    chunks: Vec<Option<Vec<u8>>>,
+}
+
+impl Restore {
+    /// Start restoring the offered snapshot, whose state must have the given
+    /// trusted app hash
+    pub fn new(snapshot: &Snapshot, app_hash: Vec<u8>) -> Result<Self> {
+        if snapshot.format != STATE_DUMP_VERSION {
+            return Err(Error::UnsupportedFormat(snapshot.format));
+        }
+        let metadata = SnapshotMetadata::try_from_slice(&snapshot.metadata)
+            .map_err(Error::InvalidMetadata)?;
+        if metadata.height.0 != snapshot.height
+            || metadata.hash.0[..] != snapshot.hash[..]
+            || metadata.chunk_hashes.len() != snapshot.chunks as usize
+        {
+            return Err(Error::MetadataMismatch);
+        }
+        let chunks = vec![None; metadata.chunk_hashes.len()];
+        Ok(Self {
+            metadata,
+            app_hash,
+            chunks,
+        })
+    }
+
+    /// Verify a chunk with its hash in the metadata and keep it
+    pub fn apply_chunk(&mut self, index: u32, chunk: Vec<u8>) -> Result<()> {
+        let hash = self
+            .metadata
+            .chunk_hashes
+            .get(index as usize)
+            .ok_or(Error::InvalidChunkIndex(index))?;
+        if Hash::sha256(&chunk) != *hash {
+            return Err(Error::ChunkHashMismatch(index));
+        }
+        self.chunks[index as usize] = Some(chunk);
+        Ok(())
+    }
+
+    /// Whether all the chunks of the snapshot are applied
+    pub fn is_complete(&self) -> bool {
+        self.chunks.iter().all(Option::is_some)
+    }
+
+    /// Decode the state of the snapshot from its chunks once they're all
+    /// applied. The state is verified with its Merkle root, which must be the
+    /// trusted app hash.
+    pub fn finish(self, chain_id: &ChainId) -> Result<StateDump> {
+        let chunks: Vec<Vec<u8>> = self.chunks.into_iter().flatten().collect();
+        let bytes = chunks.concat();
+        if Hash::sha256(&bytes) != self.metadata.hash {
+            return Err(Error::HashMismatch);
+        }
+        let dump = StateDump::try_from_slice(&bytes).map_err(Error::Decode)?;
+        dump.verify().map_err(Error::StateDump)?;
+        if dump.height != self.metadata.height {
+            return Err(Error::HeightMismatch {
+                state: dump.height,
+                snapshot: self.metadata.height,
+            });
+        }
+        if dump.chain_id != *chain_id {
+            return Err(Error::ChainIdMismatch(dump.chain_id));
+        }
+        if dump.merkle_root != self.app_hash {
+            return Err(Error::AppHashMismatch {
+                root: ByteBuf(&dump.merkle_root).to_string(),
+                app_hash: ByteBuf(&self.app_hash).to_string(),
+            });
+        }
+        Ok(dump)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::ledger::storage::testing::TestStorage;
+    use anoma::types::time::DateTimeUtc;
+    use anoma::types::{address, token};
+
+    use super::*;
+    use crate::config::genesis;
+    use crate::wallet::defaults;
+
+    /// A storage with a balance committed in the block at the given height
+    fn committed_storage(height: u64) -> TestStorage {
+        let mut storage = TestStorage::default();
+        storage.block.height = BlockHeight(height);
+        storage
+            .init_genesis_epoch(
+                BlockHeight(height),
+                DateTimeUtc::now(),
+                &genesis::genesis().parameters,
+            )
+            .unwrap();
+        let key =
+            token::balance_key(&address::xan(), &defaults::albert_address());
+        let amount = token::Amount::whole(height);
+        storage.write(&key, amount.try_to_vec().unwrap()).unwrap();
+        storage.commit().unwrap();
+        storage
+    }
+
+    /// Test that a snapshot is taken, listed and restored from its chunks,
+    /// and that only the last snapshots are kept.
+    #[test]
+    fn test_take_and_restore_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app_hash = vec![];
+        for height in 1..=3 {
+            let mut storage = committed_storage(height);
+            take_snapshot(dir.path(), &mut storage.db, ChainId::default(), 2)
+                .unwrap();
+            app_hash = storage.merkle_root().0;
+        }
+        let snapshots = list_snapshots(dir.path()).unwrap();
+        let heights: Vec<_> = snapshots.iter().map(|s| s.height).collect();
+        assert_eq!(heights, vec![BlockHeight(2), BlockHeight(3)]);
+
+        let metadata = &snapshots[1];
+        let snapshot = metadata.to_abci();
+        assert_eq!(snapshot.chunks, 1);
+        let chunk =
+            load_chunk(dir.path(), metadata.height, 0).unwrap().unwrap();
+        assert_eq!(load_chunk(dir.path(), metadata.height, 1).unwrap(), None);
+        assert_eq!(load_chunk(dir.path(), BlockHeight(1), 0).unwrap(), None);
+
+        let mut restore = Restore::new(&snapshot, app_hash.clone()).unwrap();
+        assert!(!restore.is_complete());
+        let mut modified = chunk.clone();
+        modified[0] ^= 1;
+        assert!(matches!(
+            restore.apply_chunk(0, modified),
+            Err(Error::ChunkHashMismatch(0))
+        ));
+        assert!(matches!(
+            restore.apply_chunk(1, chunk.clone()),
+            Err(Error::InvalidChunkIndex(1))
+        ));
+        restore.apply_chunk(0, chunk.clone()).unwrap();
+        assert!(restore.is_complete());
+        let dump = restore.finish(&ChainId::default()).unwrap();
+        assert_eq!(dump.height, BlockHeight(3));
+
+        // The state must have the trusted app hash
+        let mut restore = Restore::new(&snapshot, vec![0; 32]).unwrap();
+        restore.apply_chunk(0, chunk).unwrap();
+        assert!(matches!(
+            restore.finish(&ChainId::default()),
+            Err(Error::AppHashMismatch { .. })
+        ));
+
+        let unsupported = Snapshot {
+            format: STATE_DUMP_VERSION + 1,
+            ..snapshot
+        };
+        assert!(matches!(
+            Restore::new(&unsupported, app_hash),
+            Err(Error::UnsupportedFormat(_))
+        ));
+    }
+}
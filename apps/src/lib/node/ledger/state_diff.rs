@@ -0,0 +1,470 @@
+//! The diffs of the state of the committed blocks, which are written to a
+//! sink configured in the node, so that the explorers and the other external
+//! indexers can follow the state without querying every block's keys from an
+//! archive node. A [`StateDiff`] has all the keys of the account subspaces
+//! that were written or deleted in a block with their new values.
+//!
+//! A sink is either an append-only file, a Unix socket on which the indexer
+//! listens or a subject of a NATS server. In a file or a socket, each diff is
+//! a frame of its Borsh encoded length as `u32` followed by the Borsh encoded
+//! diff, which can be read back with [`read_state_diff`]. On NATS, each diff
+//! is published in a message of its Borsh encoding, which can be decoded with
+//! [`decode_state_diff`]. The diffs are written from a separate thread, so
+//! that a slow sink doesn't delay the commit of the blocks. When more than
+//! [`STATE_DIFF_BUFFER_BLOCKS`] diffs are waiting to be written, the diffs of
+//! the next blocks are dropped and counted, which the indexer can detect from
+//! the gap in the heights.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::net::TcpStream;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::thread;
+use std::time::Duration;
+
+use anoma::types::storage::{BlockHeight, Key};
+use borsh::{BorshDeserialize, BorshSerialize};
+use thiserror::Error;
+
+use crate::config::StateDiffSink;
+
+/// The version of the state diff format. It must be incremented on any change
+/// of the encoding of [`StateDiff`].
+pub const STATE_DIFF_VERSION: u32 = 1;
+
+/// The number of the diffs of the committed blocks that may wait to be
+/// written to the sink
+pub const STATE_DIFF_BUFFER_BLOCKS: usize = 64;
+
+/// The timeout of the reads from a NATS server
+const NATS_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Error opening the state diff file {0}: {1}")]
+    OpenFile(PathBuf, io::Error),
+    #[error("Error connecting to the state diff socket {0}: {1}")]
+    Connect(PathBuf, io::Error),
+    #[error("Error connecting to the NATS server {0}: {1}")]
+    ConnectNats(String, io::Error),
+    #[error("Error encoding the state diff: {0}")]
+    Encode(io::Error),
+    #[error("Error decoding the state diff: {0}")]
+    Decode(io::Error),
+    #[error("Error writing the state diff: {0}")]
+    Write(io::Error),
+    #[error("Error reading the state diff: {0}")]
+    Read(io::Error),
+    #[error(
+        "Unsupported state diff version {0}, the supported version is {}",
+        STATE_DIFF_VERSION
+    )]
+    UnsupportedVersion(u32),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The changes of the account subspaces in a committed block
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct StateDiff {
+    /// The version of the format, see [`STATE_DIFF_VERSION`]
+    pub version: u32,
+    /// The height of the committed block
+    pub height: BlockHeight,
+    /// The changed keys, sorted, with their new values or `None` for the
+    /// deleted keys
+    pub changes: Vec<(String, Option<Vec<u8>>)>,
+}
+
+impl StateDiff {
+    /// Make the diff of a block from its changed keys, which must be sorted
+    pub fn new<'a>(
+        height: BlockHeight,
+        changes: impl Iterator<Item = (&'a Key, Option<&'a Vec<u8>>)>,
+    ) -> Self {
+        Self {
+            version: STATE_DIFF_VERSION,
+            height,
+            changes: changes
+                .map(|(key, value)| (key.to_string(), value.cloned()))
+                .collect(),
+        }
+    }
+}
+
+/// Write a diff to a sink in a frame of its length and its encoding
+pub fn write_state_diff(
+    mut writer: impl Write,
+    diff: &StateDiff,
+) -> Result<()> {
+    let bytes = diff.try_to_vec().map_err(Error::Encode)?;
+    let len = (bytes.len() as u32).try_to_vec().map_err(Error::Encode)?;
+    writer.write_all(&len).map_err(Error::Write)?;
+    writer.write_all(&bytes).map_err(Error::Write)?;
+    writer.flush().map_err(Error::Write)
+}
+
+/// Read the next diff from a sink. Returns `None` at the end of the sink.
+pub fn read_state_diff(mut reader: impl Read) -> Result<Option<StateDiff>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+            return Ok(None);
+        }
+        Err(err) => return Err(Error::Read(err)),
+    }
+    let len = u32::from_le_bytes(len) as usize;
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes).map_err(Error::Read)?;
+    decode_state_diff(&bytes).map(Some)
+}
+
+/// Decode a diff from its Borsh encoding, e.g. from a NATS message
+pub fn decode_state_diff(bytes: &[u8]) -> Result<StateDiff> {
+    // The version is decoded first, so that a diff in another format is
+    // rejected with its version
+    let version = u32::deserialize(&mut &bytes[..]).map_err(Error::Decode)?;
+    if version != STATE_DIFF_VERSION {
+        return Err(Error::UnsupportedVersion(version));
+    }
+    StateDiff::try_from_slice(bytes).map_err(Error::Decode)
+}
+
+/// The writer of the diffs of the committed blocks to the configured sink
+#[derive(Debug)]
+pub struct StateDiffWriter {
+    sender: SyncSender<StateDiff>,
+    /// The number of the diffs that have been dropped
+    dropped: AtomicU64,
+}
+
+impl StateDiffWriter {
+    /// Start the thread that writes the diffs to the sink. A file sink must be
+    /// opened, while a socket or a NATS sink is connected once there's a diff
+    /// to write.
+    pub fn start(sink: StateDiffSink) -> Result<Self> {
+        let mut writer = SinkWriter::new(sink)?;
+        let (sender, receiver) = mpsc::sync_channel(STATE_DIFF_BUFFER_BLOCKS);
+        thread::Builder::new()
+            .name("state-diff".into())
+            .spawn(move || {
+                for diff in receiver {
+                    writer.write(&diff);
+                }
+            })
+            .expect("Must be able to start a thread for the state diffs");
+        Ok(Self {
+            sender,
+            dropped: AtomicU64::new(0),
+        })
+    }
+
+    /// Queue the diff of a committed block to be written. The diff is dropped
+    /// when the buffer is full.
+    pub fn send(&self, diff: StateDiff) {
+        match self.sender.try_send(diff) {
+            Ok(()) => {}
+            Err(TrySendError::Full(diff)) => {
+                let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                tracing::warn!(
+                    "The state diff sink is too slow, dropping the diff of \
+                     the block at height {} ({} diffs dropped in total)",
+                    diff.height,
+                    dropped
+                );
+            }
+            Err(TrySendError::Disconnected(diff)) => {
+                let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                tracing::error!(
+                    "The state diff writer has stopped, dropping the diff of \
+                     the block at height {} ({} diffs dropped in total)",
+                    diff.height,
+                    dropped
+                );
+            }
+        }
+    }
+
+    /// The number of the diffs that have been dropped since the start
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// The open sink of the writer thread
+enum SinkWriter {
+    File(BufWriter<File>),
+    #[cfg(unix)]
+    UnixSocket {
+        path: PathBuf,
+        stream: Option<UnixStream>,
+    },
+    Nats {
+        address: String,
+        subject: String,
+        connection: Option<NatsConnection>,
+    },
+}
+
+impl SinkWriter {
+    fn new(sink: StateDiffSink) -> Result<Self> {
+        match sink {
+            StateDiffSink::File(path) => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .map_err(|err| Error::OpenFile(path, err))?;
+                Ok(Self::File(BufWriter::new(file)))
+            }
+            #[cfg(unix)]
+            StateDiffSink::UnixSocket(path) => {
+                Ok(Self::UnixSocket { path, stream: None })
+            }
+            StateDiffSink::Nats { address, subject } => Ok(Self::Nats {
+                address,
+                subject,
+                connection: None,
+            }),
+        }
+    }
+
+    fn write(&mut self, diff: &StateDiff) {
+        let result = match self {
+            Self::File(file) => write_state_diff(file, diff),
+            #[cfg(unix)]
+            Self::UnixSocket { path, stream } => {
+                let connected = match stream {
+                    Some(stream) => Ok(stream),
+                    None => UnixStream::connect(&*path)
+                        .map(|connected| stream.insert(connected))
+                        .map_err(|err| Error::Connect(path.clone(), err)),
+                };
+                let result = connected
+                    .and_then(|connected| write_state_diff(connected, diff));
+                if result.is_err() {
+                    // Connect again for the next diff
+                    *stream = None;
+                }
+                result
+            }
+            Self::Nats {
+                address,
+                subject,
+                connection,
+            } => {
+                let connected = match connection {
+                    Some(connection) => Ok(connection),
+                    None => NatsConnection::connect(address)
+                        .map(|connected| connection.insert(connected))
+                        .map_err(|err| {
+                            Error::ConnectNats(address.clone(), err)
+                        }),
+                };
+                let result = connected.and_then(|connected| {
+                    let bytes = diff.try_to_vec().map_err(Error::Encode)?;
+                    connected.publish(subject, &bytes).map_err(Error::Write)
+                });
+                if result.is_err() {
+                    // Connect again for the next diff
+                    *connection = None;
+                }
+                result
+            }
+        };
+        if let Err(err) = result {
+            tracing::error!(
+                "Failed to write the state diff of the block at height {}: {}",
+                diff.height,
+                err
+            );
+        }
+    }
+}
+
+/// A connection to a NATS server, on which the diffs are published with the
+/// client protocol. The server's pings are answered before each publish.
+struct NatsConnection {
+    stream: TcpStream,
+    /// The received bytes that don't yet form a complete line
+    received: Vec<u8>,
+}
+
+impl NatsConnection {
+    fn connect(address: &str) -> io::Result<Self> {
+        let mut stream = TcpStream::connect(address)?;
+        stream.set_read_timeout(Some(NATS_READ_TIMEOUT))?;
+        // The server greets a client with its info, after which it waits for
+        // the client's options
+        let mut info = String::new();
+        BufReader::new(&mut stream).read_line(&mut info)?;
+        if !info.starts_with("INFO") {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unexpected greeting from the NATS server: {}", info),
+            ));
+        }
+        stream
+            .write_all(b"CONNECT {\"verbose\":false,\"pedantic\":false}\r\n")?;
+        Ok(Self {
+            stream,
+            received: vec![],
+        })
+    }
+
+    fn publish(&mut self, subject: &str, payload: &[u8]) -> io::Result<()> {
+        self.handle_received()?;
+        write!(self.stream, "PUB {} {}\r\n", subject, payload.len())?;
+        self.stream.write_all(payload)?;
+        self.stream.write_all(b"\r\n")?;
+        self.stream.flush()
+    }
+
+    /// Read what the server has sent without blocking, answer its pings and
+    /// fail on its errors
+    fn handle_received(&mut self) -> io::Result<()> {
+        self.stream.set_nonblocking(true)?;
+        let mut buf = [0u8; 1024];
+        let read = loop {
+            match self.stream.read(&mut buf) {
+                Ok(0) => {
+                    break Err(io::Error::from(io::ErrorKind::ConnectionReset));
+                }
+                Ok(len) => self.received.extend_from_slice(&buf[..len]),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    break Ok(());
+                }
+                Err(err) => break Err(err),
+            }
+        };
+        self.stream.set_nonblocking(false)?;
+        read?;
+        while let Some(end) =
+            self.received.windows(2).position(|bytes| bytes == b"\r\n")
+        {
+            let line: Vec<u8> = self.received.drain(..end + 2).collect();
+            if line.starts_with(b"PING") {
+                self.stream.write_all(b"PONG\r\n")?;
+            } else if line.starts_with(b"-ERR") {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    String::from_utf8_lossy(&line[..end]).into_owned(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    #[cfg(unix)]
+    use std::os::unix::net::UnixListener;
+
+    use super::*;
+
+    fn diff(height: u64) -> StateDiff {
+        let key = Key::parse("key").unwrap();
+        let deleted = Key::parse("deleted").unwrap();
+        let value = vec![height as u8];
+        StateDiff::new(
+            BlockHeight(height),
+            [(&deleted, None), (&key, Some(&value))].into_iter(),
+        )
+    }
+
+    /// Test that the diffs are appended to a file sink and read back in
+    /// order.
+    #[test]
+    fn test_state_diff_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state-diffs");
+        let mut writer =
+            SinkWriter::new(StateDiffSink::File(path.clone())).unwrap();
+        writer.write(&diff(1));
+        writer.write(&diff(2));
+        // A re-opened file is appended to
+        let mut writer =
+            SinkWriter::new(StateDiffSink::File(path.clone())).unwrap();
+        writer.write(&diff(3));
+
+        let mut file = File::open(&path).unwrap();
+        for height in 1..=3 {
+            let read = read_state_diff(&mut file).unwrap();
+            assert_eq!(read, Some(diff(height)));
+        }
+        assert_eq!(read_state_diff(&mut file).unwrap(), None);
+    }
+
+    /// Test that the diffs are written to a socket sink, which is connected
+    /// once it's listening.
+    #[cfg(unix)]
+    #[test]
+    fn test_state_diff_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state-diffs.sock");
+        let mut writer =
+            SinkWriter::new(StateDiffSink::UnixSocket(path.clone())).unwrap();
+        // Nothing is listening yet
+        writer.write(&diff(1));
+
+        let listener = UnixListener::bind(&path).unwrap();
+        writer.write(&diff(2));
+        let (mut stream, _addr) = listener.accept().unwrap();
+        assert_eq!(read_state_diff(&mut stream).unwrap(), Some(diff(2)));
+    }
+
+    /// Test that the diffs are published on the subject of a NATS server and
+    /// that the server's pings are answered.
+    #[test]
+    fn test_state_diff_nats() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let (pinged, ping_sent) = mpsc::channel();
+        fn read_line(reader: &mut impl BufRead) -> String {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            line
+        }
+        let server = thread::spawn(move || {
+            let (mut stream, _addr) = listener.accept().unwrap();
+            stream.write_all(b"INFO {}\r\n").unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            assert!(read_line(&mut reader).starts_with("CONNECT "));
+            let mut messages = vec![];
+            for _ in 0..2 {
+                let mut line = read_line(&mut reader);
+                if messages.len() == 1 {
+                    assert_eq!(line, "PONG\r\n");
+                    line = read_line(&mut reader);
+                }
+                let len = line.trim_end().strip_prefix("PUB diffs ").unwrap();
+                let mut payload = vec![0u8; len.parse::<usize>().unwrap() + 2];
+                reader.read_exact(&mut payload).unwrap();
+                payload.truncate(payload.len() - 2);
+                messages.push(decode_state_diff(&payload).unwrap());
+                if messages.len() == 1 {
+                    stream.write_all(b"PING\r\n").unwrap();
+                    pinged.send(()).unwrap();
+                }
+            }
+            messages
+        });
+
+        let mut writer = SinkWriter::new(StateDiffSink::Nats {
+            address,
+            subject: "diffs".into(),
+        })
+        .unwrap();
+        writer.write(&diff(1));
+        ping_sent.recv().unwrap();
+        writer.write(&diff(2));
+        assert_eq!(server.join().unwrap(), vec![diff(1), diff(2)]);
+    }
+}
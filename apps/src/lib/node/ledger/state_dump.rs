@@ -0,0 +1,623 @@
+//! Export and import of the state of the ledger in a portable format. A state
+//! dump contains all the key-vals of the account subspaces at a block height
+//! with the epoch data of the block and the root of its Merkle tree, from
+//! which the dump is verified. A new chain can be initialized from a dump
+//! instead of its genesis accounts, e.g. in a coordinated network upgrade or
+//! to move the state into a DB of a different storage backend version.
+//!
+//! The dump is either Borsh encoded, starting with its format version, or
+//! JSON encoded with the bytes in hex, for inspection and editing. The
+//! key-vals are sorted by their keys, so the same state always gives the same
+//! dump in either encoding.
+
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anoma::bytes::ByteBuf;
+use anoma::ledger::storage::{
+    self as ledger_storage, BlockStateRead, DBIter, MerkleTree, Sha256Hasher,
+    DB,
+};
+use anoma::types::address::EstablishedAddressGen;
+use anoma::types::chain::ChainId;
+use anoma::types::storage::{BlockHeight, Epoch, Epochs, Key};
+use anoma::types::time::{DateTimeUtc, Rfc3339String};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::storage::{self, PersistentBackend};
+use crate::config::{self, StorageBackend};
+
+/// The version of the state dump format. It must be incremented on any change
+/// of the encoding of [`StateDump`].
+pub const STATE_DUMP_VERSION: u32 = 2;
+
+/// The file in the chain directory with the imported state dump, from which
+/// the chain is initialized
+pub const IMPORTED_STATE_FILE: &str = "imported-state.borsh";
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Storage error: {0}")]
+    Storage(ledger_storage::Error),
+    #[error("No block has been committed to the DB")]
+    NoBlock,
+    #[error(
+        "Cannot export the state at height {height}, the last committed \
+         height is {last_height}"
+    )]
+    InvalidHeight {
+        height: BlockHeight,
+        last_height: BlockHeight,
+    },
+    #[error(
+        "The state at height {0} has {1} wrapper txs to be decrypted in the \
+         next block, export the state of another height"
+    )]
+    PendingTxs(BlockHeight, usize),
+    #[error("Invalid storage key {0}: {1}")]
+    InvalidKey(String, anoma::types::storage::Error),
+    #[error(
+        "Unsupported state dump version {0}, the supported version is {}",
+        STATE_DUMP_VERSION
+    )]
+    UnsupportedVersion(u32),
+    #[error(
+        "The Merkle root of the state dump {computed} doesn't match its \
+         exported root {exported}"
+    )]
+    RootMismatch { exported: String, computed: String },
+    #[error(
+        "The chain {0} already has committed blocks, a state can only be \
+         imported into a new chain"
+    )]
+    ChainInitialized(ChainId),
+    #[error("Error encoding the state dump: {0}")]
+    Encode(std::io::Error),
+    #[error("Error decoding the state dump: {0}")]
+    Decode(std::io::Error),
+    #[error("Error encoding the state dump as JSON: {0}")]
+    EncodeJson(serde_json::Error),
+    #[error("Error decoding the JSON state dump: {0}")]
+    DecodeJson(serde_json::Error),
+    #[error("Invalid hex {0} in the JSON state dump: {1}")]
+    InvalidHex(String, hex::FromHexError),
+    #[error("Invalid RFC 3339 time {0} in the JSON state dump")]
+    InvalidTime(String),
+    #[error("Invalid predecessor epochs in the JSON state dump: {0}")]
+    InvalidPredEpochs(std::io::Error),
+    #[error(
+        "The predecessor epochs of the state dump don't have its epoch {0} at \
+         its height {1}"
+    )]
+    PredEpochsMismatch(Epoch, BlockHeight),
+    #[error("Unknown state dump format {0}, expected \"borsh\" or \"json\"")]
+    UnknownFormat(String),
+    #[error("Error reading the state dump from {0}: {1}")]
+    Read(PathBuf, std::io::Error),
+    #[error("Error writing the state dump to {0}: {1}")]
+    Write(PathBuf, std::io::Error),
+    #[error("Error removing the DB data: {0}")]
+    RemoveDB(std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The encoding of a state dump file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateDumpFormat {
+    /// Compact binary encoding
+    Borsh,
+    /// Human-readable JSON encoding
+    Json,
+}
+
+impl FromStr for StateDumpFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "borsh" => Ok(Self::Borsh),
+            "json" => Ok(Self::Json),
+            _ => Err(Error::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+impl Display for StateDumpFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Borsh => write!(f, "borsh"),
+            Self::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// The state of the ledger at a block height
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct StateDump {
+    /// The version of the format, see [`STATE_DUMP_VERSION`]
+    pub version: u32,
+    /// The ID of the chain from which the state was exported
+    pub chain_id: ChainId,
+    /// The height of the exported block
+    pub height: BlockHeight,
+    /// The epoch of the exported block
+    pub epoch: Epoch,
+    /// The epochs of the predecessor blocks, which are needed to continue
+    /// the chain from the state, but not to initialize a new chain with it
+    pub pred_epochs: Epochs,
+    /// Minimum block height at which the next epoch may start
+    pub next_epoch_min_start_height: BlockHeight,
+    /// Minimum block time at which the next epoch may start
+    pub next_epoch_min_start_time: DateTimeUtc,
+    /// The established address generator
+    pub address_gen: EstablishedAddressGen,
+    /// The root of the Merkle tree of the exported block
+    pub merkle_root: Vec<u8>,
+    /// The key-vals of the account subspaces, sorted by their keys
+    pub entries: Vec<(String, Vec<u8>)>,
+}
+
+impl StateDump {
+    /// Read the state of the last committed block from the DB
+    pub fn read_from_db<D>(db: &mut D, chain_id: ChainId) -> Result<Self>
+    where
+        D: DB + for<'iter> DBIter<'iter>,
+    {
+        let BlockStateRead {
+            merkle_tree_stores,
+            height,
+            epoch,
+            pred_epochs,
+            next_epoch_min_start_height,
+            next_epoch_min_start_time,
+            address_gen,
+            tx_queue,
+            ..
+        } = db
+            .read_last_block()
+            .map_err(Error::Storage)?
+            .ok_or(Error::NoBlock)?;
+        let pending_txs = tx_queue.iter().count();
+        if pending_txs > 0 {
+            return Err(Error::PendingTxs(height, pending_txs));
+        }
+        let merkle_root =
+            MerkleTree::<Sha256Hasher>::new(merkle_tree_stores).root().0;
+        // The empty prefix of all the keys
+        let all_keys = Key { segments: vec![] };
+        let mut entries: Vec<(String, Vec<u8>)> = db
+            .iter_prefix(&all_keys)
+            .map(|(key, value, _gas)| (key, value))
+            .collect();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(Self {
+            version: STATE_DUMP_VERSION,
+            chain_id,
+            height,
+            epoch,
+            pred_epochs,
+            next_epoch_min_start_height,
+            next_epoch_min_start_time,
+            address_gen,
+            merkle_root,
+            entries,
+        })
+    }
+
+    /// The key-vals of the account subspaces with the parsed keys
+    pub fn key_vals(&self) -> Result<Vec<(Key, &[u8])>> {
+        self.entries
+            .iter()
+            .map(|(key, value)| {
+                Key::parse(key)
+                    .map(|key| (key, value.as_slice()))
+                    .map_err(|err| Error::InvalidKey(key.clone(), err))
+            })
+            .collect()
+    }
+
+    /// Verify that the version of the dump is supported and that the Merkle
+    /// tree of its data has the exported root. The predecessor epochs are not
+    /// in the Merkle tree, so they're only checked to have the epoch of the
+    /// dump at its height.
+    pub fn verify(&self) -> Result<()> {
+        if self.version != STATE_DUMP_VERSION {
+            return Err(Error::UnsupportedVersion(self.version));
+        }
+        if self.pred_epochs.get_epoch(self.height) != Some(self.epoch) {
+            return Err(Error::PredEpochsMismatch(self.epoch, self.height));
+        }
+        let mut tree = MerkleTree::<Sha256Hasher>::default();
+        for (key, value) in self.key_vals()? {
+            tree.update(&key, value)
+                .map_err(|err| Error::Storage(err.into()))?;
+        }
+        ledger_storage::update_epoch_in_merkle_tree(
+            &mut tree,
+            self.epoch,
+            self.next_epoch_min_start_height,
+            self.next_epoch_min_start_time,
+        )
+        .map_err(Error::Storage)?;
+        let computed = tree.root();
+        if computed.0 != self.merkle_root {
+            return Err(Error::RootMismatch {
+                exported: ByteBuf(&self.merkle_root).to_string(),
+                computed: computed.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Read a dump from a file in either format. A JSON dump is recognized
+    /// by its opening brace, which cannot start a Borsh dump.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes =
+            fs::read(path).map_err(|err| Error::Read(path.into(), err))?;
+        let first_char = bytes.iter().find(|byte| !byte.is_ascii_whitespace());
+        if first_char == Some(&b'{') {
+            return Self::decode_json(&bytes);
+        }
+        // The version is decoded first, so that a dump in another format is
+        // rejected with its version
+        let version =
+            u32::deserialize(&mut bytes.as_slice()).map_err(Error::Decode)?;
+        if version != STATE_DUMP_VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+        Self::try_from_slice(&bytes).map_err(Error::Decode)
+    }
+
+    /// Write the dump to a file in the Borsh format
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.write_as(path, StateDumpFormat::Borsh)
+    }
+
+    /// Write the dump to a file in the given format
+    pub fn write_as(
+        &self,
+        path: impl AsRef<Path>,
+        format: StateDumpFormat,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        let bytes = match format {
+            StateDumpFormat::Borsh => {
+                self.try_to_vec().map_err(Error::Encode)?
+            }
+            StateDumpFormat::Json => self.encode_json()?,
+        };
+        fs::write(path, bytes).map_err(|err| Error::Write(path.into(), err))
+    }
+
+    fn encode_json(&self) -> Result<Vec<u8>> {
+        let json = JsonStateDump {
+            version: self.version,
+            chain_id: self.chain_id.clone(),
+            height: self.height,
+            epoch: self.epoch,
+            pred_epochs: hex::encode(
+                self.pred_epochs.try_to_vec().map_err(Error::Encode)?,
+            ),
+            next_epoch_min_start_height: self.next_epoch_min_start_height,
+            next_epoch_min_start_time: self.next_epoch_min_start_time.into(),
+            address_gen: self.address_gen.clone(),
+            merkle_root: hex::encode(&self.merkle_root),
+            entries: self
+                .entries
+                .iter()
+                .map(|(key, value)| (key.clone(), hex::encode(value)))
+                .collect(),
+        };
+        serde_json::to_vec_pretty(&json).map_err(Error::EncodeJson)
+    }
+
+    fn decode_json(bytes: &[u8]) -> Result<Self> {
+        // The version is decoded first, like in the Borsh format
+        let JsonVersion { version } =
+            serde_json::from_slice(bytes).map_err(Error::DecodeJson)?;
+        if version != STATE_DUMP_VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+        let json: JsonStateDump =
+            serde_json::from_slice(bytes).map_err(Error::DecodeJson)?;
+        let decode_hex = |hex: String| {
+            hex::decode(&hex).map_err(|err| Error::InvalidHex(hex, err))
+        };
+        let next_epoch_min_start_time = json.next_epoch_min_start_time.0;
+        let pred_epochs =
+            Epochs::try_from_slice(&decode_hex(json.pred_epochs)?)
+                .map_err(Error::InvalidPredEpochs)?;
+        Ok(Self {
+            version: json.version,
+            chain_id: json.chain_id,
+            height: json.height,
+            epoch: json.epoch,
+            pred_epochs,
+            next_epoch_min_start_height: json.next_epoch_min_start_height,
+            next_epoch_min_start_time: DateTimeUtc::try_from(Rfc3339String(
+                next_epoch_min_start_time.clone(),
+            ))
+            .map_err(|_| Error::InvalidTime(next_epoch_min_start_time))?,
+            address_gen: json.address_gen,
+            merkle_root: decode_hex(json.merkle_root)?,
+            entries: json
+                .entries
+                .into_iter()
+                .map(|(key, value)| decode_hex(value).map(|value| (key, value)))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+/// The JSON encoding of a [`StateDump`], with the bytes in hex
+#[derive(Serialize, Deserialize)]
+struct JsonStateDump {
+    version: u32,
+    chain_id: ChainId,
+    height: BlockHeight,
+    epoch: Epoch,
+    /// The Borsh encoding of the [`Epochs`] in hex
+    pred_epochs: String,
+    next_epoch_min_start_height: BlockHeight,
+    next_epoch_min_start_time: Rfc3339String,
+    address_gen: EstablishedAddressGen,
+    merkle_root: String,
+    entries: Vec<(String, String)>,
+}
+
+/// The version of a JSON state dump, which is decoded before the rest of it
+#[derive(Deserialize)]
+struct JsonVersion {
+    version: u32,
+}
+
+/// Export the state at the given height, or at the last committed height if
+/// none given, to a file in the given format. The state of a past height is
+/// read from a copy of the DB rolled back to that height. The ledger node
+/// must not be running.
+pub fn export_state(
+    config: config::Ledger,
+    height: Option<BlockHeight>,
+    path: impl AsRef<Path>,
+    format: StateDumpFormat,
+) -> Result<StateDump> {
+    match config.shell.storage_backend {
+        StorageBackend::RocksDB => {
+            export_state_with::<storage::RocksDB>(config, height, path, format)
+        }
+        StorageBackend::Sled => {
+            export_state_with::<storage::SledDB>(config, height, path, format)
+        }
+    }
+}
+
+/// Export the state from the DB of the given backend
+fn export_state_with<D: PersistentBackend>(
+    config: config::Ledger,
+    height: Option<BlockHeight>,
+    path: impl AsRef<Path>,
+    format: StateDumpFormat,
+) -> Result<StateDump> {
+    let mut db =
+        D::open_primary(&config.db_dir(), None).map_err(Error::Storage)?;
+    let last_height = db
+        .read_last_block()
+        .map_err(Error::Storage)?
+        .map(|state| state.height)
+        .ok_or(Error::NoBlock)?;
+    let height = height.unwrap_or(last_height);
+    if height.0 == 0 || height > last_height {
+        return Err(Error::InvalidHeight {
+            height,
+            last_height,
+        });
+    }
+    let dump = if height == last_height {
+        StateDump::read_from_db(&mut db, config.chain_id.clone())?
+    } else {
+        let export_db_dir = config.export_db_dir();
+        remove_export_db(&export_db_dir)?;
+        db.checkpoint(&export_db_dir).map_err(Error::Storage)?;
+        drop(db);
+        let dump = read_rolled_back_state::<D>(
+            &export_db_dir,
+            height,
+            config.chain_id.clone(),
+        );
+        remove_export_db(&export_db_dir)?;
+        dump?
+    };
+    dump.write_as(path, format)?;
+    Ok(dump)
+}
+
+/// Verify the state dump from the file in either format and import it into
+/// the chain of the config, which must not have any committed blocks. The
+/// chain is initialized from the imported state when the ledger node is
+/// started.
+pub fn import_state(
+    config: config::Ledger,
+    path: impl AsRef<Path>,
+) -> Result<StateDump> {
+    let dump = StateDump::read(path)?;
+    dump.verify()?;
+    let initialized = match config.shell.storage_backend {
+        StorageBackend::RocksDB => {
+            has_committed_block::<storage::RocksDB>(&config)?
+        }
+        StorageBackend::Sled => {
+            has_committed_block::<storage::SledDB>(&config)?
+        }
+    };
+    if initialized {
+        return Err(Error::ChainInitialized(config.chain_id));
+    }
+    dump.write(imported_state_file(config.chain_dir()))?;
+    Ok(dump)
+}
+
+/// Read the imported state dump from the chain directory, if any
+pub fn read_imported_state(
+    chain_dir: impl AsRef<Path>,
+) -> Result<Option<StateDump>> {
+    let path = imported_state_file(chain_dir);
+    if !path.exists() {
+        return Ok(None);
+    }
+    StateDump::read(path).map(Some)
+}
+
+/// The path to the imported state dump in the chain directory
+pub fn imported_state_file(chain_dir: impl AsRef<Path>) -> PathBuf {
+    chain_dir.as_ref().join(IMPORTED_STATE_FILE)
+}
+
+/// Check if the DB of the chain of the config has any committed block
+fn has_committed_block<D: PersistentBackend>(
+    config: &config::Ledger,
+) -> Result<bool> {
+    let mut db =
+        D::open_primary(&config.db_dir(), None).map_err(Error::Storage)?;
+    Ok(db.read_last_block().map_err(Error::Storage)?.is_some())
+}
+
+/// Rollback the copy of the DB to the given height and read its state
+fn read_rolled_back_state<D: PersistentBackend>(
+    export_db_dir: &Path,
+    height: BlockHeight,
+    chain_id: ChainId,
+) -> Result<StateDump> {
+    let mut export_db =
+        D::open_primary(export_db_dir, None).map_err(Error::Storage)?;
+    export_db.rollback(height).map_err(Error::Storage)?;
+    StateDump::read_from_db(&mut export_db, chain_id)
+}
+
+fn remove_export_db(export_db_dir: &Path) -> Result<()> {
+    match fs::remove_dir_all(export_db_dir) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        res => res.map_err(Error::RemoveDB),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::ledger::storage::testing::TestStorage;
+    use anoma::types::{address, token};
+
+    use super::*;
+    use crate::config::genesis;
+    use crate::wallet::defaults;
+
+    /// A storage with the balances of the default accounts committed in the
+    /// block at height 1
+    fn committed_storage() -> TestStorage {
+        let mut storage = TestStorage::default();
+        storage.block.height = BlockHeight(1);
+        storage
+            .init_genesis_epoch(
+                BlockHeight(1),
+                DateTimeUtc::now(),
+                &genesis::genesis().parameters,
+            )
+            .unwrap();
+        for owner in [defaults::albert_address(), defaults::bertha_address()] {
+            let key = token::balance_key(&address::xan(), &owner);
+            let amount = token::Amount::whole(1_000);
+            storage.write(&key, amount.try_to_vec().unwrap()).unwrap();
+        }
+        storage.commit().unwrap();
+        storage
+    }
+
+    /// Test that the dump of a committed state is sorted and verified by its
+    /// Merkle root and that a modified dump is rejected.
+    #[test]
+    fn test_verify_state_dump() {
+        let mut storage = committed_storage();
+        let dump = StateDump::read_from_db(&mut storage.db, ChainId::default())
+            .unwrap();
+        assert_eq!(dump.height, BlockHeight(1));
+        assert_eq!(dump.merkle_root, storage.merkle_root().0);
+        assert_eq!(dump.entries.len(), 2);
+        assert!(dump.entries.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        dump.verify().unwrap();
+
+        let mut modified = dump.clone();
+        modified.entries[0].1 =
+            token::Amount::whole(2_000).try_to_vec().unwrap();
+        assert!(matches!(modified.verify(), Err(Error::RootMismatch { .. })));
+
+        let mut modified = dump.clone();
+        modified.pred_epochs = Epochs::starting_from(dump.epoch.next());
+        assert!(matches!(
+            modified.verify(),
+            Err(Error::PredEpochsMismatch(_, _))
+        ));
+
+        let unsupported = StateDump {
+            version: STATE_DUMP_VERSION + 1,
+            ..dump
+        };
+        assert!(matches!(
+            unsupported.verify(),
+            Err(Error::UnsupportedVersion(_))
+        ));
+    }
+
+    /// Test that a dump is written to and read from a file and that the dump
+    /// of an unsupported version is rejected on read.
+    #[test]
+    fn test_state_dump_file() {
+        let mut storage = committed_storage();
+        let dump = StateDump::read_from_db(&mut storage.db, ChainId::default())
+            .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.borsh");
+        dump.write(&path).unwrap();
+        assert_eq!(StateDump::read(&path).unwrap(), dump);
+
+        let unsupported = StateDump {
+            version: STATE_DUMP_VERSION + 1,
+            ..dump
+        };
+        unsupported.write(&path).unwrap();
+        assert!(matches!(
+            StateDump::read(&path),
+            Err(Error::UnsupportedVersion(_))
+        ));
+    }
+
+    /// Test that a JSON dump is deterministic and read back into the same
+    /// dump as its Borsh encoding.
+    #[test]
+    fn test_json_state_dump_file() {
+        let mut storage = committed_storage();
+        let dump = StateDump::read_from_db(&mut storage.db, ChainId::default())
+            .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        dump.write_as(&path, StateDumpFormat::Json).unwrap();
+        let json = fs::read(&path).unwrap();
+        assert_eq!(json, dump.encode_json().unwrap());
+        let read = StateDump::read(&path).unwrap();
+        assert_eq!(read, dump);
+        read.verify().unwrap();
+
+        let unsupported = StateDump {
+            version: STATE_DUMP_VERSION + 1,
+            ..dump
+        };
+        unsupported.write_as(&path, StateDumpFormat::Json).unwrap();
+        assert!(matches!(
+            StateDump::read(&path),
+            Err(Error::UnsupportedVersion(_))
+        ));
+    }
+}
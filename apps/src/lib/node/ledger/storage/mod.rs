@@ -1,23 +1,135 @@
 //! The storage module handles both the current state in-memory and the stored
-//! state in DB.
+//! state in DB. The DB is stored with the [`PersistentBackend`] selected in
+//! the config.
 
 mod rocksdb;
+pub mod schema;
+mod sled;
 
 use std::fmt;
+use std::path::Path;
 
-use anoma::ledger::storage::{Storage, StorageHasher};
+use anoma::ledger::storage::{
+    DBIter, Error, Result, Storage, StorageHasher, DB,
+};
+use anoma::types::storage::BlockHeight;
 use blake2b_rs::{Blake2b, Blake2bBuilder};
 use sparse_merkle_tree::blake2b::Blake2bHasher;
 use sparse_merkle_tree::traits::Hasher;
 use sparse_merkle_tree::H256;
 
+pub use self::rocksdb::RocksDB;
+pub use self::sled::SledDB;
+use crate::config::StorageBackend;
+
 #[derive(Default)]
 pub struct PersistentStorageHasher(Blake2bHasher);
 
+/// The DB of the default backend
 pub type PersistentDB = rocksdb::RocksDB;
 
 pub type PersistentStorage = Storage<PersistentDB, PersistentStorageHasher>;
 
+/// The mode in which a DB instance is opened
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DbMode {
+    /// The primary instance with the read and write access
+    Primary,
+    /// A read-only secondary instance that follows the primary instance
+    Secondary,
+}
+
+/// A backend of the persistent DB. On top of the [`DB`] used by the storage,
+/// the ledger follows the DB of the main shell with the read-only instances
+/// of the query shells, and it copies and rolls back the DB to export the
+/// state and to replay the blocks.
+pub trait PersistentBackend:
+    DB + for<'iter> DBIter<'iter> + fmt::Debug + Send + Sync + Sized + 'static
+{
+    /// The backend of the DB in the config
+    const BACKEND: StorageBackend;
+
+    /// Create a block cache of the given size in bytes
+    fn new_cache(size_bytes: u64) -> Self::Cache;
+
+    /// Open the primary instance of the DB at the given path, with the read
+    /// and write access. A new DB is created at the [`schema::SCHEMA_VERSION`]
+    /// of the node and an existing DB must be at the same version, so a DB
+    /// of an older version must be migrated first.
+    fn open_primary(path: &Path, cache: Option<&Self::Cache>) -> Result<Self> {
+        let is_new = std::fs::read_dir(path)
+            .map(|mut entries| entries.next().is_none())
+            .unwrap_or(true);
+        let mut db = Self::open_primary_unchecked(path, cache)?;
+        if is_new {
+            db.write_schema_version(schema::SCHEMA_VERSION)?;
+            db.flush(true)?;
+        } else {
+            schema::check_version(&db)
+                .map_err(|err| Error::DBError(err.to_string()))?;
+        }
+        Ok(db)
+    }
+
+    /// Open the primary instance of the DB at the given path, with the read
+    /// and write access, without checking its schema version. This is only
+    /// used to migrate the DB.
+    fn open_primary_unchecked(
+        path: &Path,
+        cache: Option<&Self::Cache>,
+    ) -> Result<Self>;
+
+    /// Open a read-only secondary instance of the DB whose primary instance
+    /// is at the `primary_path`. The `secondary_path` is used by the backends
+    /// that keep some data of the secondary instances.
+    fn open_secondary(
+        primary_path: &Path,
+        secondary_path: &Path,
+        cache: Option<&Self::Cache>,
+    ) -> Result<Self>;
+
+    /// Catch up a secondary instance with the latest changes of the primary
+    /// instance. This is a no-op for the primary instance.
+    fn catch_up_with_primary(&self) -> Result<()>;
+
+    /// Create a copy of the DB in the given directory, which must not exist
+    /// yet. The copy can be opened as an independent DB.
+    fn checkpoint(&self, path: &Path) -> Result<()>;
+
+    /// Rollback the DB to the block committed at the given height
+    fn rollback(&mut self, height: BlockHeight) -> Result<()>;
+
+    /// Move the key-vals of the DB to the column families of the backend, in
+    /// the schema migration to the version 2. This is a no-op for the
+    /// backends without column families.
+    fn split_column_families(&mut self) -> Result<()>;
+}
+
+/// Check that the DB at the given path, if there's one already, was created
+/// with the given backend. The DB of another backend cannot be opened, but
+/// its state can be exported and imported into a new chain.
+fn check_backend(path: &Path, backend: StorageBackend) -> Result<()> {
+    // RocksDB keeps the name of its current manifest in a `CURRENT` file and
+    // sled keeps its config in a `conf` file
+    let found = if path.join("CURRENT").is_file() {
+        Some(StorageBackend::RocksDB)
+    } else if path.join("conf").is_file() {
+        Some(StorageBackend::Sled)
+    } else {
+        None
+    };
+    match found {
+        Some(found) if found != backend => Err(Error::DBError(format!(
+            "The DB at {} was created with the {:?} backend, but the {:?} \
+             backend is configured",
+            path.display(),
+            found,
+            backend
+        ))),
+        _ => Ok(()),
+    }
+}
+
 impl Hasher for PersistentStorageHasher {
     fn write_h256(&mut self, h: &H256) {
         self.0.write_h256(h)
@@ -50,6 +162,10 @@ fn new_blake2b() -> Blake2b {
 
 #[cfg(test)]
 mod tests {
+    use std::path::PathBuf;
+    use std::process::{Command, Stdio};
+    use std::time::Duration;
+
     use anoma::ledger::storage::types;
     use anoma::types::chain::ChainId;
     use anoma::types::storage::{BlockHash, BlockHeight, Key};
@@ -57,6 +173,17 @@ mod tests {
 
     use super::*;
 
+    /// Env. var with the DB directory of the child process of
+    /// `test_crash_consistency`, which commits blocks until it's killed
+    const ENV_VAR_CRASH_TEST_DB: &str = "ANOMA_CRASH_TEST_DB";
+
+    /// The number of keys written in each block of the crash test
+    const CRASH_TEST_KEYS: usize = 1000;
+
+    /// The file created by the child process of the crash test once some
+    /// blocks are committed
+    const CRASH_TEST_READY_FILE: &str = "ready";
+
     #[test]
     fn test_crud_value() {
         let db_path =
@@ -210,4 +337,82 @@ mod tests {
         assert_eq!(vp.expect("no VP"), vp1);
         assert_eq!(gas, (key.len() + vp1.len()) as u64);
     }
+
+    /// Test that the DB has the state of the last fully committed block
+    /// after the process is killed in the middle of the commits. The blocks
+    /// are committed in a child process running this test, in which every
+    /// block writes the keys with its height.
+    #[test]
+    fn test_crash_consistency() {
+        if let Some(db_path) = std::env::var_os(ENV_VAR_CRASH_TEST_DB) {
+            commit_blocks_until_killed(PathBuf::from(db_path));
+        }
+        let db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let test_name = format!(
+            "{}::test_crash_consistency",
+            module_path!().split_once("::").unwrap().1
+        );
+        let mut child = Command::new(std::env::current_exe().unwrap())
+            .args([test_name.as_str(), "--exact", "--test-threads=1"])
+            .env(ENV_VAR_CRASH_TEST_DB, db_path.path())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Unable to start the child process");
+        while !db_path.path().join(CRASH_TEST_READY_FILE).exists() {
+            assert!(
+                child.try_wait().unwrap().is_none(),
+                "The child process exited"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        // Kill the child in the middle of the next commits
+        std::thread::sleep(Duration::from_millis(200));
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        let mut storage =
+            PersistentStorage::open(db_path.path(), ChainId::default(), None);
+        storage.load_last_state().expect("loading the state failed");
+        let last_height = storage.last_height;
+        assert!(last_height.0 >= 10);
+        for key in crash_test_keys() {
+            let (value, _gas) = storage.read(&key).expect("read failed");
+            let height: u64 =
+                types::decode(value.expect("the key must be written"))
+                    .expect("decoding failed");
+            assert_eq!(height, last_height.0, "torn write of the key {}", key);
+        }
+    }
+
+    /// Commit the blocks of the crash test in the child process
+    fn commit_blocks_until_killed(db_path: PathBuf) -> ! {
+        let mut storage =
+            PersistentStorage::open(&db_path, ChainId::default(), None);
+        let keys = crash_test_keys();
+        for height in 1_u64.. {
+            storage
+                .begin_block(BlockHash::default(), BlockHeight(height))
+                .expect("begin_block failed");
+            for key in &keys {
+                storage
+                    .write(key, types::encode(&height))
+                    .expect("write failed");
+            }
+            storage.commit().expect("commit failed");
+            if height == 10 {
+                storage.db.flush(true).expect("flush failed");
+                std::fs::write(db_path.join(CRASH_TEST_READY_FILE), [])
+                    .expect("writing the ready file failed");
+            }
+        }
+        unreachable!("the blocks are committed until the process is killed")
+    }
+
+    fn crash_test_keys() -> Vec<Key> {
+        (0..CRASH_TEST_KEYS)
+            .map(|i| Key::parse(format!("key{}", i)).unwrap())
+            .collect()
+    }
 }
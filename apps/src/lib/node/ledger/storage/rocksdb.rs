@@ -1,52 +1,72 @@
 //! The persistent storage in RocksDB.
 //!
-//! The current storage tree is:
-//! - `chain_id`
-//! - `height`: the last committed block height
-//! - `tx_queue`: txs to be decrypted in the next block
-//! - `pred`: predecessor values of the top-level keys of the same name
-//!   - `tx_queue`
-//! - `next_epoch_min_start_height`: minimum block height from which the next
-//!   epoch can start
-//! - `next_epoch_min_start_time`: minimum block time from which the next epoch
-//!   can start
-//! - `pred`: predecessor values of the top-level keys of the same name
-//!   - `next_epoch_min_start_height`
-//!   - `next_epoch_min_start_time`
-//! - `subspace`: accounts sub-spaces
+//! The state is split into column families, so that each of them has its own
+//! compaction settings and the iterations of a subspace don't have to skip
+//! the keys of the others. The current storage tree is:
+//! - the default column family:
+//!   - `chain_id`
+//!   - `height`: the last committed block height
+//!   - `tx_queue`: txs to be decrypted in the next block
+//!   - `next_epoch_min_start_height`: minimum block height from which the
+//!     next epoch can start
+//!   - `next_epoch_min_start_time`: minimum block time from which the next
+//!     epoch can start
+//!   - `pred`: predecessor values of the top-level keys of the same name
+//!     - `tx_queue`
+//!     - `next_epoch_min_start_height`
+//!     - `next_epoch_min_start_time`
+//!   - `schema_version`: the schema version of the DB layout
+//! - the `subspace` column family: accounts sub-spaces, other than the PoS
+//!   and IBC ones
 //!   - `{address}/{dyn}`: any byte data associated with accounts
-//! - `h`: for each block at height `h`:
-//!   - `tree`: merkle tree
-//!     - `root`: root hash
-//!     - `store`: the tree's store
-//!   - `hash`: block hash
-//!   - `epoch`: block epoch
-//!   - `address_gen`: established address generator
-//!   - `diffs`: diffs in account subspaces' key-vals
+//! - the `pos` column family: the PoS account sub-space
+//! - the `ibc` column family: the IBC account sub-space
+//! - the `block` column family:
+//!   - `h`: for each block at height `h`:
+//!     - `tree`: merkle tree
+//!       - `{store_type}/root`: root hash
+//!       - `{store_type}/store`: the tree's store
+//!       - `account/subtrees`: roots and stores of the per-account subtrees
+//!     - `hash`: block hash
+//!     - `epoch`: block epoch
+//!     - `address_gen`: established address generator
+//!     - `header`: block's header
+//!     - `request`: the encoded request from which the block was finalized
+//!     - `gas`: the gas used by the block's txs
+//!     - `next_epoch_min_start_height`, `next_epoch_min_start_time` and
+//!       `tx_queue`: the values of the top-level keys of the same name after
+//!       the block was committed, used to rollback to this block
+//! - the `diffs` column family: diffs in account subspaces' key-vals
+//!   - `h`: for each block at height `h`:
 //!     - `new/{dyn}`: value set in block height `h`
 //!     - `old/{dyn}`: value from predecessor block height
-//!   - `header`: block's header
 
 use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::iter::Peekable;
 use std::path::Path;
 use std::str::FromStr;
 
-use anoma::ledger::storage::types::PrefixIterator;
+use anoma::ledger::storage::types::{KVBytes, PrefixIterator};
 use anoma::ledger::storage::{
     types, BlockStateRead, BlockStateWrite, DBIter, DBWriteBatch, Error,
     MerkleTreeStoresRead, Result, StoreType, DB,
 };
+use anoma::types::address::{Address, InternalAddress};
 use anoma::types::storage::{
-    BlockHeight, Header, Key, KeySeg, TxQueue, KEY_SEGMENT_SEPARATOR,
+    BlockHeight, DbKeySeg, Header, Key, KeySeg, TxQueue, KEY_SEGMENT_SEPARATOR,
 };
 use anoma::types::time::DateTimeUtc;
 use borsh::{BorshDeserialize, BorshSerialize};
 use rocksdb::{
-    BlockBasedOptions, Direction, FlushOptions, IteratorMode, Options,
-    ReadOptions, SliceTransform, WriteBatch, WriteOptions,
+    BlockBasedOptions, ColumnFamily, ColumnFamilyDescriptor, Direction,
+    FlushOptions, IteratorMode, Options, ReadOptions, SliceTransform,
+    WriteBatch, WriteOptions,
 };
 
+use super::{check_backend, DbMode, PersistentBackend};
 use crate::config::utils::num_of_threads;
+use crate::config::StorageBackend;
 
 // TODO the DB schema will probably need some kind of versioning
 
@@ -54,9 +74,31 @@ use crate::config::utils::num_of_threads;
 const ENV_VAR_ROCKSDB_COMPACTION_THREADS: &str =
     "ANOMA_ROCKSDB_COMPACTION_THREADS";
 
+/// The column family of the account subspaces, other than the PoS and IBC
+/// ones
+const SUBSPACE_CF: &str = "subspace";
+/// The column family of the PoS account subspace
+const POS_CF: &str = "pos";
+/// The column family of the IBC account subspace
+const IBC_CF: &str = "ibc";
+/// The column family of the data of the blocks
+const BLOCK_CF: &str = "block";
+/// The column family of the diffs of the account subspaces
+const DIFFS_CF: &str = "diffs";
+
+/// The column families of the account subspaces
+const SUBSPACE_CFS: [&str; 3] = [SUBSPACE_CF, POS_CF, IBC_CF];
+/// All the column families, other than the default one
+const COLUMN_FAMILIES: [&str; 5] =
+    [SUBSPACE_CF, POS_CF, IBC_CF, BLOCK_CF, DIFFS_CF];
+
+/// The number of the key-vals moved in a batch by the migration to the
+/// column families
+const MIGRATION_BATCH_KEYS: usize = 10_000;
+
 /// RocksDB handle
 #[derive(Debug)]
-pub struct RocksDB(rocksdb::DB);
+pub struct RocksDB(rocksdb::DB, DbMode);
 
 /// DB Handle for batch writes.
 #[derive(Default)]
@@ -67,6 +109,41 @@ pub fn open(
     path: impl AsRef<Path>,
     cache: Option<&rocksdb::Cache>,
 ) -> Result<RocksDB> {
+    check_backend(path.as_ref(), StorageBackend::RocksDB)?;
+    let db_opts = db_options(cache);
+    let cfs = COLUMN_FAMILIES.iter().map(|name| {
+        ColumnFamilyDescriptor::new(*name, cf_options(name, cache))
+    });
+    rocksdb::DB::open_cf_descriptors(&db_opts, path, cfs)
+        .map(|db| RocksDB(db, DbMode::Primary))
+        .map_err(|e| Error::DBError(e.into_string()))
+}
+
+/// Open a read-only secondary instance of the RocksDB at the `primary_path`.
+/// The secondary instance keeps its own info logs in the `secondary_path` and
+/// it has to be explicitly caught up with the primary instance with
+/// [`RocksDB::catch_up_with_primary`] to see the new writes.
+pub fn open_secondary(
+    primary_path: impl AsRef<Path>,
+    secondary_path: impl AsRef<Path>,
+    cache: Option<&rocksdb::Cache>,
+) -> Result<RocksDB> {
+    let mut cf_opts = db_options(cache);
+    // A secondary instance must keep all the table files open
+    cf_opts.set_max_open_files(-1);
+    rocksdb::DB::open_cf_as_secondary(
+        &cf_opts,
+        primary_path.as_ref(),
+        secondary_path.as_ref(),
+        COLUMN_FAMILIES,
+    )
+    .map(|db| RocksDB(db, DbMode::Secondary))
+    .map_err(|e| Error::DBError(e.into_string()))
+}
+
+/// The options for opening the DB, which are also the options of its default
+/// column family
+fn db_options(cache: Option<&rocksdb::Cache>) -> Options {
     let logical_cores = num_cpus::get();
     let compaction_threads = num_of_threads(
         ENV_VAR_ROCKSDB_COMPACTION_THREADS,
@@ -93,6 +170,51 @@ pub fn open(
     cf_opts.set_compression_options(0, 0, 0, 1024 * 1024);
     // TODO the recommended default `options.compaction_pri =
     // kMinOverlappingRatio` doesn't seem to be available in Rust
+    let table_opts = table_options(cache);
+    cf_opts.set_block_based_table_factory(&table_opts);
+
+    cf_opts.create_missing_column_families(true);
+    cf_opts.create_if_missing(true);
+    cf_opts.set_atomic_flush(true);
+
+    // The secondary instances open all the column families with these
+    // options, so all of them must have the same comparator
+    cf_opts.set_comparator("key_comparator", key_comparator);
+    let extractor = SliceTransform::create_fixed_prefix(20);
+    cf_opts.set_prefix_extractor(extractor);
+
+    cf_opts
+}
+
+/// The options of a column family other than the default one
+fn cf_options(name: &str, cache: Option<&rocksdb::Cache>) -> Options {
+    let mut cf_opts = Options::default();
+    cf_opts.set_level_compaction_dynamic_level_bytes(true);
+    cf_opts.set_compression_type(rocksdb::DBCompressionType::Zstd);
+    cf_opts.set_compression_options(0, 0, 0, 1024 * 1024);
+    cf_opts.set_comparator("key_comparator", key_comparator);
+
+    let mut table_opts = table_options(cache);
+    if SUBSPACE_CFS.contains(&name) {
+        // The subspaces are read by the keys of the txs and the VPs, so
+        // their point lookups are sped up with bloom filters. Their prefix
+        // iterations seek in the total order, so there's no prefix extractor.
+        table_opts.set_bloom_filter(10, false);
+        table_opts.set_whole_key_filtering(true);
+        // The hottest data is kept in memory for longer before it's flushed
+        cf_opts.set_write_buffer_size(128 * 1024 * 1024);
+    } else {
+        // The data of the blocks and the diffs are sought by the prefixes of
+        // their heights
+        let extractor = SliceTransform::create_fixed_prefix(20);
+        cf_opts.set_prefix_extractor(extractor);
+    }
+    cf_opts.set_block_based_table_factory(&table_opts);
+    cf_opts
+}
+
+/// The options of the tables, using the given block cache
+fn table_options(cache: Option<&rocksdb::Cache>) -> BlockBasedOptions {
     let mut table_opts = BlockBasedOptions::default();
     table_opts.set_block_size(16 * 1024);
     table_opts.set_cache_index_and_filter_blocks(true);
@@ -102,20 +224,30 @@ pub fn open(
     }
     // latest format versions https://github.com/facebook/rocksdb/blob/d1c510baecc1aef758f91f786c4fbee3bc847a63/include/rocksdb/table.h#L394
     table_opts.set_format_version(5);
-    cf_opts.set_block_based_table_factory(&table_opts);
-
-    cf_opts.create_missing_column_families(true);
-    cf_opts.create_if_missing(true);
-    cf_opts.set_atomic_flush(true);
+    table_opts
+}
 
-    cf_opts.set_comparator("key_comparator", key_comparator);
-    let extractor = SliceTransform::create_fixed_prefix(20);
-    cf_opts.set_prefix_extractor(extractor);
-    // TODO use column families
+/// The column family of the account subspace of the given key
+fn subspace_cf(key: &Key) -> &'static str {
+    match key.segments.first() {
+        Some(DbKeySeg::AddressSeg(Address::Internal(InternalAddress::PoS))) => {
+            POS_CF
+        }
+        Some(DbKeySeg::AddressSeg(Address::Internal(InternalAddress::Ibc))) => {
+            IBC_CF
+        }
+        _ => SUBSPACE_CF,
+    }
+}
 
-    rocksdb::DB::open_cf_descriptors(&cf_opts, path, vec![])
-        .map(RocksDB)
-        .map_err(|e| Error::DBError(e.into_string()))
+/// The column families of the account subspaces that may have the keys with
+/// the given prefix
+fn subspace_cfs(prefix: &Key) -> Vec<&'static str> {
+    if prefix.segments.is_empty() {
+        SUBSPACE_CFS.to_vec()
+    } else {
+        vec![subspace_cf(prefix)]
+    }
 }
 
 /// A custom key comparator is used to sort keys by the height. In
@@ -145,21 +277,322 @@ fn key_comparator(a: &[u8], b: &[u8]) -> Ordering {
     }
 }
 
+/// The bounds of the range of the keys of the block and its diffs at the given
+/// height, i.e. of the keys with the `"{height}/"` prefix. The heights aren't
+/// padded, but the column families are ordered by the `key_comparator` that
+/// compares the heights as numbers, so the range is exact with the next
+/// height as its exclusive upper bound, e.g. the keys of the block 1 end
+/// before `"2/"` and the ones of the block 10 aren't in it. A lexicographic
+/// bound such as `"{height}0"` would instead cover the heights below 10 times
+/// the height in this order.
+fn height_prefix_range(height: BlockHeight) -> (String, String) {
+    (format!("{}/", height), format!("{}/", height.next_height()))
+}
+
 impl Drop for RocksDB {
     fn drop(&mut self) {
-        self.flush(true).expect("flush failed");
+        // A secondary instance doesn't have anything to flush
+        if self.1 == DbMode::Primary {
+            self.flush(true).expect("flush failed");
+        }
     }
 }
 
 impl RocksDB {
+    /// Catch up a secondary instance with the latest changes of the primary
+    /// instance. This is a no-op for the primary instance.
+    pub fn catch_up_with_primary(&self) -> Result<()> {
+        match self.1 {
+            DbMode::Primary => Ok(()),
+            DbMode::Secondary => self
+                .0
+                .try_catch_up_with_primary()
+                .map_err(|e| Error::DBError(e.into_string())),
+        }
+    }
+
+    /// Flush all the column families. The default one, with the height of the
+    /// last block, is flushed last.
     fn flush(&self, wait: bool) -> Result<()> {
         let mut flush_opts = FlushOptions::default();
         flush_opts.set_wait(wait);
+        for name in COLUMN_FAMILIES {
+            self.0
+                .flush_cf_opt(self.cf(name)?, &flush_opts)
+                .map_err(|e| Error::DBError(e.into_string()))?;
+        }
         self.0
             .flush_opt(&flush_opts)
             .map_err(|e| Error::DBError(e.into_string()))
     }
 
+    /// Get the handle of a column family
+    fn cf(&self, name: &str) -> Result<&ColumnFamily> {
+        self.0.cf_handle(name).ok_or_else(|| {
+            Error::DBError(format!("Missing the column family {}", name))
+        })
+    }
+
+    /// Create a checkpoint of the DB in the given directory, which must not
+    /// exist yet. The checkpoint can be opened as an independent DB.
+    pub fn checkpoint(&self, path: impl AsRef<Path>) -> Result<()> {
+        let checkpoint = rocksdb::checkpoint::Checkpoint::new(&self.0)
+            .map_err(|e| Error::DBError(e.into_string()))?;
+        checkpoint
+            .create_checkpoint(path)
+            .map_err(|e| Error::DBError(e.into_string()))
+    }
+
+    /// Iterate the key-vals of the given column families with the given
+    /// prefix under the `db_prefix`, with the keys stripped of the
+    /// `db_prefix`
+    fn iter_db_prefix(
+        &self,
+        cfs: &[&str],
+        db_prefix: String,
+        prefix: &Key,
+        reverse: bool,
+    ) -> PersistentPrefixIterator<'_> {
+        let prefix = format!("{}{}", db_prefix, prefix).into_bytes();
+        let mut upper_prefix = prefix.clone();
+        // No upper bound for an empty prefix
+        let upper_prefix = upper_prefix.pop().map(|last| {
+            upper_prefix.push(last + 1);
+            upper_prefix
+        });
+        self.iter_db_range(cfs, db_prefix, prefix, upper_prefix, reverse)
+    }
+
+    /// Iterate the key-vals of the given column families from the `lower`
+    /// key (inclusive) up to the `upper` key (exclusive) in the given order,
+    /// with the keys stripped of the `db_prefix`
+    fn iter_db_range(
+        &self,
+        cfs: &[&str],
+        db_prefix: String,
+        lower: Vec<u8>,
+        upper: Option<Vec<u8>>,
+        reverse: bool,
+    ) -> PersistentPrefixIterator<'_> {
+        let iters = cfs
+            .iter()
+            .map(|name| {
+                let mut read_opts = ReadOptions::default();
+                // don't use the prefix bloom filter
+                read_opts.set_total_order_seek(true);
+                if let Some(upper) = &upper {
+                    // An empty range if the upper bound is not above the
+                    // lower bound
+                    let upper = std::cmp::max(upper, &lower);
+                    read_opts.set_iterate_upper_bound(upper.clone());
+                }
+                read_opts.set_iterate_lower_bound(lower.clone());
+
+                let mode = if reverse {
+                    IteratorMode::End
+                } else {
+                    IteratorMode::Start
+                };
+                let cf = self
+                    .cf(name)
+                    .expect("The column families must have been created");
+                self.0.iterator_cf_opt(cf, read_opts, mode).peekable()
+            })
+            .collect();
+        let iter = MergedIterator { iters, reverse };
+        PersistentPrefixIterator(PrefixIterator::new(iter, db_prefix))
+    }
+
+    /// Move the key-vals of a DB in the layout before the column families,
+    /// i.e. of the schema version 1, from the default column family to their
+    /// column families. The key-vals are moved in batches, so that an
+    /// interrupted migration can be resumed.
+    pub fn split_column_families(&mut self) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        let mut moved = 0_usize;
+        let mut read_opts = ReadOptions::default();
+        read_opts.set_total_order_seek(true);
+        for (key, value) in self.0.iterator_opt(IteratorMode::Start, read_opts)
+        {
+            let key = String::from_utf8((*key).to_vec()).map_err(|e| {
+                Error::Temporary {
+                    error: format!(
+                        "Cannot convert path from utf8 bytes to string: {}",
+                        e
+                    ),
+                }
+            })?;
+            let (cf, new_key) = match key.strip_prefix("subspace/") {
+                Some(subspace_key) => {
+                    let parsed =
+                        Key::parse(subspace_key).map_err(Error::KeyError)?;
+                    (subspace_cf(&parsed), subspace_key.to_owned())
+                }
+                None => match key.split_once(KEY_SEGMENT_SEPARATOR) {
+                    Some((height, rest)) if height.parse::<u64>().is_ok() => {
+                        match rest.strip_prefix("diffs/") {
+                            Some(diff) => {
+                                (DIFFS_CF, format!("{}/{}", height, diff))
+                            }
+                            None => (BLOCK_CF, key.clone()),
+                        }
+                    }
+                    // The top-level keys stay in the default column family
+                    _ => continue,
+                },
+            };
+            batch.put_cf(self.cf(cf)?, new_key, value);
+            batch.delete(key);
+            moved += 1;
+            if moved % MIGRATION_BATCH_KEYS == 0 {
+                self.0
+                    .write(std::mem::take(&mut batch))
+                    .map_err(|e| Error::DBError(e.into_string()))?;
+            }
+        }
+        self.0
+            .write(batch)
+            .map_err(|e| Error::DBError(e.into_string()))?;
+        tracing::info!("Moved {} key-vals to the column families", moved);
+        self.flush(true)
+    }
+
+    /// Rollback the DB to the block committed at the given height. The
+    /// account subspaces are reverted with the diffs of the blocks above it,
+    /// the data of these blocks is removed and the last block's metadata is
+    /// restored from the block at the given height. All the changes are
+    /// written atomically.
+    pub fn rollback(&mut self, height: BlockHeight) -> Result<()> {
+        let last_height: BlockHeight = match self
+            .0
+            .get("height")
+            .map_err(|e| Error::DBError(e.into_string()))?
+        {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => {
+                return Err(Error::Temporary {
+                    error: "No block has been committed to the DB".to_string(),
+                });
+            }
+        };
+        if height >= last_height {
+            return Err(Error::Temporary {
+                error: format!(
+                    "Cannot rollback to height {}, the last committed height \
+                     is {}",
+                    height, last_height
+                ),
+            });
+        }
+
+        let mut batch = WriteBatch::default();
+
+        // Restore the top-level keys and their predecessor values
+        for name in [
+            "next_epoch_min_start_height",
+            "next_epoch_min_start_time",
+            "tx_queue",
+        ] {
+            match self.read_block_value(height, name)? {
+                Some(value) => batch.put(name, value),
+                None => {
+                    return Err(Error::Temporary {
+                        error: format!(
+                            "The {} of the block at height {} is not in the \
+                             DB, the block cannot be restored",
+                            name, height
+                        ),
+                    });
+                }
+            }
+            let pred_key = format!("pred/{}", name);
+            let pred_value = match height.0.checked_sub(1) {
+                Some(pred_height) => {
+                    self.read_block_value(BlockHeight(pred_height), name)?
+                }
+                None => None,
+            };
+            match pred_value {
+                Some(value) => batch.put(pred_key, value),
+                None => batch.delete(pred_key),
+            }
+        }
+
+        // Revert the blocks from the last one down to the given height
+        let block_cf = self.cf(BLOCK_CF)?;
+        let diffs_cf = self.cf(DIFFS_CF)?;
+        for reverted_height in (height.0 + 1..=last_height.0).rev() {
+            let (prefix, next_prefix) =
+                height_prefix_range(BlockHeight(reverted_height));
+            batch.delete_range_cf(block_cf, &prefix, &next_prefix);
+
+            let old_diffs_prefix = format!("{}/old/", reverted_height);
+            let new_diffs_prefix = format!("{}/new/", reverted_height);
+            // The diffs of the block are iterated in the exact range of its
+            // height prefix, so the diffs of the other blocks are left intact
+            let mut read_opts = ReadOptions::default();
+            read_opts.set_total_order_seek(true);
+            read_opts.set_iterate_lower_bound(prefix.clone());
+            read_opts.set_iterate_upper_bound(next_prefix);
+            let mut old_values: HashMap<String, Vec<u8>> = HashMap::new();
+            let mut changed_keys: BTreeSet<String> = BTreeSet::new();
+            for (key, value) in self.0.iterator_cf_opt(
+                diffs_cf,
+                read_opts,
+                IteratorMode::From(prefix.as_bytes(), Direction::Forward),
+            ) {
+                let key = String::from_utf8((*key).to_vec()).map_err(|e| {
+                    Error::Temporary {
+                        error: format!(
+                            "Cannot convert path from utf8 bytes to string: \
+                             {}",
+                            e
+                        ),
+                    }
+                })?;
+                if let Some(subspace_key) = key.strip_prefix(&old_diffs_prefix)
+                {
+                    old_values.insert(subspace_key.to_owned(), value.to_vec());
+                    changed_keys.insert(subspace_key.to_owned());
+                } else if let Some(subspace_key) =
+                    key.strip_prefix(&new_diffs_prefix)
+                {
+                    changed_keys.insert(subspace_key.to_owned());
+                }
+                batch.delete_cf(diffs_cf, key);
+            }
+            // A key without an old value didn't exist before the block
+            for key in changed_keys {
+                let cf = self.cf(subspace_cf(
+                    &Key::parse(&key).map_err(Error::KeyError)?,
+                ))?;
+                match old_values.remove(&key) {
+                    Some(value) => batch.put_cf(cf, key, value),
+                    None => batch.delete_cf(cf, key),
+                }
+            }
+        }
+
+        batch.put("height", types::encode(&height));
+
+        self.exec_batch(batch)?;
+        self.flush(true)
+    }
+
+    /// Read a value persisted with the block at the given height
+    fn read_block_value(
+        &self,
+        height: BlockHeight,
+        name: &str,
+    ) -> Result<Option<Vec<u8>>> {
+        let key = Key::from(height.to_db_key())
+            .push(&name.to_owned())
+            .map_err(Error::KeyError)?;
+        self.0
+            .get_cf(self.cf(BLOCK_CF)?, key.to_string())
+            .map_err(|e| Error::DBError(e.into_string()))
+    }
+
     /// Persist the diff of an account subspace key-val under the height where
     /// it was changed.
     fn write_subspace_diff(
@@ -169,9 +602,8 @@ impl RocksDB {
         old_value: Option<&[u8]>,
         new_value: Option<&[u8]>,
     ) -> Result<()> {
-        let key_prefix = Key::from(height.to_db_key())
-            .push(&"diffs".to_owned())
-            .map_err(Error::KeyError)?;
+        let key_prefix = Key::from(height.to_db_key());
+        let diffs_cf = self.cf(DIFFS_CF)?;
 
         if let Some(old_value) = old_value {
             let old_val_key = key_prefix
@@ -180,7 +612,7 @@ impl RocksDB {
                 .join(key)
                 .to_string();
             self.0
-                .put(old_val_key, old_value)
+                .put_cf(diffs_cf, old_val_key, old_value)
                 .map_err(|e| Error::DBError(e.into_string()))?;
         }
 
@@ -191,7 +623,7 @@ impl RocksDB {
                 .join(key)
                 .to_string();
             self.0
-                .put(new_val_key, new_value)
+                .put_cf(diffs_cf, new_val_key, new_value)
                 .map_err(|e| Error::DBError(e.into_string()))?;
         }
         Ok(())
@@ -200,15 +632,15 @@ impl RocksDB {
     /// Persist the diff of an account subspace key-val under the height where
     /// it was changed in a batch write.
     fn batch_write_subspace_diff(
+        &self,
         batch: &mut RocksDBWriteBatch,
         height: BlockHeight,
         key: &Key,
         old_value: Option<&[u8]>,
         new_value: Option<&[u8]>,
     ) -> Result<()> {
-        let key_prefix = Key::from(height.to_db_key())
-            .push(&"diffs".to_owned())
-            .map_err(Error::KeyError)?;
+        let key_prefix = Key::from(height.to_db_key());
+        let diffs_cf = self.cf(DIFFS_CF)?;
 
         if let Some(old_value) = old_value {
             let old_val_key = key_prefix
@@ -216,7 +648,7 @@ impl RocksDB {
                 .map_err(Error::KeyError)?
                 .join(key)
                 .to_string();
-            batch.0.put(old_val_key, old_value);
+            batch.0.put_cf(diffs_cf, old_val_key, old_value);
         }
 
         if let Some(new_value) = new_value {
@@ -225,7 +657,7 @@ impl RocksDB {
                 .map_err(Error::KeyError)?
                 .join(key)
                 .to_string();
-            batch.0.put(new_val_key, new_value);
+            batch.0.put_cf(diffs_cf, new_val_key, new_value);
         }
         Ok(())
     }
@@ -251,11 +683,7 @@ impl DB for RocksDB {
     }
 
     fn flush(&self, wait: bool) -> Result<()> {
-        let mut flush_opts = FlushOptions::default();
-        flush_opts.set_wait(wait);
-        self.0
-            .flush_opt(&flush_opts)
-            .map_err(|e| Error::DBError(e.into_string()))
+        RocksDB::flush(self, wait)
     }
 
     fn read_last_block(&mut self) -> Result<Option<BlockStateRead>> {
@@ -323,9 +751,10 @@ impl DB for RocksDB {
         let mut epoch = None;
         let mut pred_epochs = None;
         let mut address_gen = None;
-        for (key, bytes) in self.0.iterator_opt(
-            IteratorMode::From(prefix.as_bytes(), Direction::Forward),
+        for (key, bytes) in self.0.iterator_cf_opt(
+            self.cf(BLOCK_CF)?,
             read_opts,
+            IteratorMode::From(prefix.as_bytes(), Direction::Forward),
         ) {
             let path = &String::from_utf8((*key).to_vec()).map_err(|e| {
                 Error::Temporary {
@@ -353,13 +782,27 @@ impl DB for RocksDB {
                                     types::decode(bytes)
                                         .map_err(Error::CodingError)?,
                                 ),
+                                Some(&"subtrees")
+                                    if st == StoreType::Account =>
+                                {
+                                    merkle_tree_stores.set_account_subtrees(
+                                        types::decode(bytes)
+                                            .map_err(Error::CodingError)?,
+                                    )
+                                }
                                 _ => unknown_key_error(path)?,
                             }
                         }
                         None => unknown_key_error(path)?,
                     },
-                    "header" => {
-                        // the block header doesn't have to be restored
+                    "header" | "request" | "gas" => {
+                        // the block header, request and gas don't have to be
+                        // restored
+                    }
+                    "next_epoch_min_start_height"
+                    | "next_epoch_min_start_time"
+                    | "tx_queue" => {
+                        // these are restored from the top-level keys
                     }
                     "hash" => {
                         hash = Some(
@@ -381,9 +824,6 @@ impl DB for RocksDB {
                             types::decode(bytes).map_err(Error::CodingError)?,
                         );
                     }
-                    "diffs" => {
-                        // ignore the diffs
-                    }
                     _ => unknown_key_error(path)?,
                 },
                 None => unknown_key_error(path)?,
@@ -411,10 +851,24 @@ impl DB for RocksDB {
     }
 
     fn write_block(&mut self, state: BlockStateWrite) -> Result<()> {
-        let mut batch = WriteBatch::default();
+        let mut batch = Self::batch();
+        self.batch_write_block(&mut batch, state)?;
+        self.exec_batch(batch.0)?;
+        // Flush without waiting
+        self.flush(false)
+    }
+
+    fn batch_write_block(
+        &self,
+        batch: &mut Self::WriteBatch,
+        state: BlockStateWrite,
+    ) -> Result<()> {
+        let batch = &mut batch.0;
         let BlockStateWrite {
             merkle_tree_stores,
             header,
+            block_request,
+            block_gas,
             hash,
             height,
             epoch,
@@ -462,6 +916,7 @@ impl DB for RocksDB {
         }
         batch.put("tx_queue", types::encode(&tx_queue));
 
+        let block_cf = self.cf(BLOCK_CF)?;
         let prefix_key = Key::from(height.to_db_key());
         // Merkle tree
         {
@@ -475,18 +930,29 @@ impl DB for RocksDB {
                 let root_key = prefix_key
                     .push(&"root".to_owned())
                     .map_err(Error::KeyError)?;
-                batch.put(
+                batch.put_cf(
+                    block_cf,
                     root_key.to_string(),
                     types::encode(merkle_tree_stores.root(st)),
                 );
                 let store_key = prefix_key
                     .push(&"store".to_owned())
                     .map_err(Error::KeyError)?;
-                batch.put(
+                batch.put_cf(
+                    block_cf,
                     store_key.to_string(),
                     types::encode(merkle_tree_stores.store(st)),
                 );
             }
+            let subtrees_key = prefix_key
+                .push(&StoreType::Account.to_string())
+                .and_then(|key| key.push(&"subtrees".to_owned()))
+                .map_err(Error::KeyError)?;
+            batch.put_cf(
+                block_cf,
+                subtrees_key.to_string(),
+                types::encode(merkle_tree_stores.account_subtrees()),
+            );
         }
         // Block header
         {
@@ -494,49 +960,92 @@ impl DB for RocksDB {
                 let key = prefix_key
                     .push(&"header".to_owned())
                     .map_err(Error::KeyError)?;
-                batch.put(
+                batch.put_cf(
+                    block_cf,
                     key.to_string(),
                     h.try_to_vec().expect("serialization failed"),
                 );
             }
         }
+        // Block request
+        {
+            if let Some(request) = block_request {
+                let key = prefix_key
+                    .push(&"request".to_owned())
+                    .map_err(Error::KeyError)?;
+                batch.put_cf(block_cf, key.to_string(), request);
+            }
+        }
+        // Block gas
+        {
+            let key = prefix_key
+                .push(&"gas".to_owned())
+                .map_err(Error::KeyError)?;
+            batch.put_cf(block_cf, key.to_string(), types::encode(&block_gas));
+        }
         // Block hash
         {
             let key = prefix_key
                 .push(&"hash".to_owned())
                 .map_err(Error::KeyError)?;
-            batch.put(key.to_string(), types::encode(&hash));
+            batch.put_cf(block_cf, key.to_string(), types::encode(&hash));
+        }
+        // The values of the top-level keys at this block
+        {
+            let key = prefix_key
+                .push(&"next_epoch_min_start_height".to_owned())
+                .map_err(Error::KeyError)?;
+            batch.put_cf(
+                block_cf,
+                key.to_string(),
+                types::encode(&next_epoch_min_start_height),
+            );
+            let key = prefix_key
+                .push(&"next_epoch_min_start_time".to_owned())
+                .map_err(Error::KeyError)?;
+            batch.put_cf(
+                block_cf,
+                key.to_string(),
+                types::encode(&next_epoch_min_start_time),
+            );
+            let key = prefix_key
+                .push(&"tx_queue".to_owned())
+                .map_err(Error::KeyError)?;
+            batch.put_cf(block_cf, key.to_string(), types::encode(&tx_queue));
         }
         // Block epoch
         {
             let key = prefix_key
                 .push(&"epoch".to_owned())
                 .map_err(Error::KeyError)?;
-            batch.put(key.to_string(), types::encode(&epoch));
+            batch.put_cf(block_cf, key.to_string(), types::encode(&epoch));
         }
         // Predecessor block epochs
         {
             let key = prefix_key
                 .push(&"pred_epochs".to_owned())
                 .map_err(Error::KeyError)?;
-            batch.put(key.to_string(), types::encode(&pred_epochs));
+            batch.put_cf(
+                block_cf,
+                key.to_string(),
+                types::encode(&pred_epochs),
+            );
         }
         // Address gen
         {
             let key = prefix_key
                 .push(&"address_gen".to_owned())
                 .map_err(Error::KeyError)?;
-            batch.put(key.to_string(), types::encode(&address_gen));
+            batch.put_cf(
+                block_cf,
+                key.to_string(),
+                types::encode(&address_gen),
+            );
         }
 
         // Block height
         batch.put("height", types::encode(&height));
-
-        // Write the batch
-        self.exec_batch(batch)?;
-
-        // Flush without waiting
-        self.flush(false)
+        Ok(())
     }
 
     fn read_block_header(&self, height: BlockHeight) -> Result<Option<Header>> {
@@ -546,7 +1055,7 @@ impl DB for RocksDB {
             .map_err(Error::KeyError)?;
         let value = self
             .0
-            .get(key.to_string())
+            .get_cf(self.cf(BLOCK_CF)?, key.to_string())
             .map_err(|e| Error::DBError(e.into_string()))?;
         match value {
             Some(v) => Ok(Some(
@@ -557,6 +1066,22 @@ impl DB for RocksDB {
         }
     }
 
+    fn read_block_request(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<Vec<u8>>> {
+        self.read_block_value(height, "request")
+    }
+
+    fn read_block_gas(&self, height: BlockHeight) -> Result<Option<u64>> {
+        match self.read_block_value(height, "gas")? {
+            Some(bytes) => {
+                Ok(Some(types::decode(bytes).map_err(Error::CodingError)?))
+            }
+            None => Ok(None),
+        }
+    }
+
     fn read_merkle_tree_stores(
         &self,
         height: BlockHeight,
@@ -574,7 +1099,7 @@ impl DB for RocksDB {
                 .map_err(Error::KeyError)?;
             let bytes = self
                 .0
-                .get(root_key.to_string())
+                .get_cf(self.cf(BLOCK_CF)?, root_key.to_string())
                 .map_err(|e| Error::DBError(e.into_string()))?;
             match bytes {
                 Some(b) => {
@@ -589,7 +1114,7 @@ impl DB for RocksDB {
                 .map_err(Error::KeyError)?;
             let bytes = self
                 .0
-                .get(store_key.to_string())
+                .get_cf(self.cf(BLOCK_CF)?, store_key.to_string())
                 .map_err(|e| Error::DBError(e.into_string()))?;
             match bytes {
                 Some(b) => {
@@ -599,14 +1124,28 @@ impl DB for RocksDB {
                 None => return Ok(None),
             }
         }
+        let subtrees_key = tree_key
+            .push(&StoreType::Account.to_string())
+            .and_then(|key| key.push(&"subtrees".to_owned()))
+            .map_err(Error::KeyError)?;
+        let bytes = self
+            .0
+            .get_cf(self.cf(BLOCK_CF)?, subtrees_key.to_string())
+            .map_err(|e| Error::DBError(e.into_string()))?;
+        match bytes {
+            Some(b) => {
+                let subtrees = types::decode(b).map_err(Error::CodingError)?;
+                merkle_tree_stores.set_account_subtrees(subtrees);
+            }
+            None => return Ok(None),
+        }
         Ok(Some(merkle_tree_stores))
     }
 
     fn read_subspace_val(&self, key: &Key) -> Result<Option<Vec<u8>>> {
-        let subspace_key =
-            Key::parse("subspace").map_err(Error::KeyError)?.join(key);
+        let cf = self.cf(subspace_cf(key))?;
         self.0
-            .get(subspace_key.to_string())
+            .get_cf(cf, key.to_string())
             .map_err(|e| Error::DBError(e.into_string()))
     }
 
@@ -614,31 +1153,39 @@ impl DB for RocksDB {
         &self,
         key: &Key,
         height: BlockHeight,
+        last_height: BlockHeight,
     ) -> Result<Option<Vec<u8>>> {
-        if self.read_subspace_val(key)?.is_none() {
-            return Ok(None);
-        }
-
-        let mut height = height.0;
-        while height > 0 {
-            let key_prefix = Key::from(BlockHeight(height).to_db_key())
-                .push(&"diffs".to_owned())
-                .map_err(Error::KeyError)?;
+        // The first block above the height that changed the key has its
+        // value at the height as the old value of the diff, or no old value
+        // if the key didn't exist yet
+        for changed_height in (height.0 + 1)..=last_height.0 {
+            let key_prefix = Key::from(BlockHeight(changed_height).to_db_key());
+            let old_val_key = key_prefix
+                .push(&"old".to_owned())
+                .map_err(Error::KeyError)?
+                .join(key)
+                .to_string();
+            let old_val = self
+                .0
+                .get_cf(self.cf(DIFFS_CF)?, old_val_key)
+                .map_err(|e| Error::DBError(e.into_string()))?;
+            if old_val.is_some() {
+                return Ok(old_val);
+            }
             let new_val_key = key_prefix
                 .push(&"new".to_owned())
                 .map_err(Error::KeyError)?
                 .join(key)
                 .to_string();
-            let val = self
+            let new_val = self
                 .0
-                .get(new_val_key)
+                .get_cf(self.cf(DIFFS_CF)?, new_val_key)
                 .map_err(|e| Error::DBError(e.into_string()))?;
-            match val {
-                Some(bytes) => return Ok(Some(bytes)),
-                None => height -= 1,
+            if new_val.is_some() {
+                return Ok(None);
             }
         }
-        Ok(None)
+        self.read_subspace_val(key)
     }
 
     fn write_subspace_val(
@@ -648,11 +1195,10 @@ impl DB for RocksDB {
         value: impl AsRef<[u8]>,
     ) -> Result<i64> {
         let value = value.as_ref();
-        let subspace_key =
-            Key::parse("subspace").map_err(Error::KeyError)?.join(key);
+        let cf = self.cf(subspace_cf(key))?;
         let size_diff = match self
             .0
-            .get(subspace_key.to_string())
+            .get_cf(cf, key.to_string())
             .map_err(|e| Error::DBError(e.into_string()))?
         {
             Some(prev_value) => {
@@ -673,7 +1219,7 @@ impl DB for RocksDB {
 
         // Write the new key-val
         self.0
-            .put(&subspace_key.to_string(), value)
+            .put_cf(self.cf(subspace_cf(key))?, key.to_string(), value)
             .map_err(|e| Error::DBError(e.into_string()))?;
 
         Ok(size_diff)
@@ -684,13 +1230,12 @@ impl DB for RocksDB {
         height: BlockHeight,
         key: &Key,
     ) -> Result<i64> {
-        let subspace_key =
-            Key::parse("subspace").map_err(Error::KeyError)?.join(key);
+        let cf = self.cf(subspace_cf(key))?;
 
         // Check the length of previous value, if any
         let prev_len = match self
             .0
-            .get(key.to_string())
+            .get_cf(cf, key.to_string())
             .map_err(|e| Error::DBError(e.into_string()))?
         {
             Some(prev_value) => {
@@ -703,7 +1248,7 @@ impl DB for RocksDB {
 
         // Delete the key-val
         self.0
-            .delete(subspace_key.to_string())
+            .delete_cf(self.cf(subspace_cf(key))?, key.to_string())
             .map_err(|e| Error::DBError(e.into_string()))?;
 
         Ok(prev_len)
@@ -725,17 +1270,16 @@ impl DB for RocksDB {
         value: impl AsRef<[u8]>,
     ) -> Result<i64> {
         let value = value.as_ref();
-        let subspace_key =
-            Key::parse("subspace").map_err(Error::KeyError)?.join(key);
+        let cf = self.cf(subspace_cf(key))?;
         let size_diff = match self
             .0
-            .get(subspace_key.to_string())
+            .get_cf(cf, key.to_string())
             .map_err(|e| Error::DBError(e.into_string()))?
         {
             Some(old_value) => {
                 let size_diff = value.len() as i64 - old_value.len() as i64;
                 // Persist the previous value
-                Self::batch_write_subspace_diff(
+                self.batch_write_subspace_diff(
                     batch,
                     height,
                     key,
@@ -745,7 +1289,7 @@ impl DB for RocksDB {
                 size_diff
             }
             None => {
-                Self::batch_write_subspace_diff(
+                self.batch_write_subspace_diff(
                     batch,
                     height,
                     key,
@@ -757,7 +1301,7 @@ impl DB for RocksDB {
         };
 
         // Write the new key-val
-        batch.put(&subspace_key.to_string(), value);
+        batch.0.put_cf(cf, key.to_string(), value);
 
         Ok(size_diff)
     }
@@ -768,19 +1312,18 @@ impl DB for RocksDB {
         height: BlockHeight,
         key: &Key,
     ) -> Result<i64> {
-        let subspace_key =
-            Key::parse("subspace").map_err(Error::KeyError)?.join(key);
+        let cf = self.cf(subspace_cf(key))?;
 
         // Check the length of previous value, if any
         let prev_len = match self
             .0
-            .get(key.to_string())
+            .get_cf(cf, key.to_string())
             .map_err(|e| Error::DBError(e.into_string()))?
         {
             Some(prev_value) => {
                 let prev_len = prev_value.len() as i64;
                 // Persist the previous value
-                Self::batch_write_subspace_diff(
+                self.batch_write_subspace_diff(
                     batch,
                     height,
                     key,
@@ -793,10 +1336,86 @@ impl DB for RocksDB {
         };
 
         // Delete the key-val
-        batch.delete(subspace_key.to_string());
+        batch.0.delete_cf(cf, key.to_string());
 
         Ok(prev_len)
     }
+
+    fn prune_block(
+        &mut self,
+        height: BlockHeight,
+        prune_diffs: bool,
+    ) -> Result<()> {
+        // A range deletion is cheap even for a block with many diffs. The
+        // space of the deleted data is reclaimed by the background
+        // compactions.
+        let mut batch = WriteBatch::default();
+        let (from, to) = height_prefix_range(height);
+        batch.delete_range_cf(self.cf(BLOCK_CF)?, &from, &to);
+        if prune_diffs {
+            batch.delete_range_cf(self.cf(DIFFS_CF)?, &from, &to);
+        }
+        self.exec_batch(batch)
+    }
+
+    fn read_schema_version(&self) -> Result<Option<u64>> {
+        match self
+            .0
+            .get("schema_version")
+            .map_err(|e| Error::DBError(e.into_string()))?
+        {
+            Some(bytes) => {
+                Ok(Some(types::decode(bytes).map_err(Error::CodingError)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn write_schema_version(&mut self, version: u64) -> Result<()> {
+        self.0
+            .put("schema_version", types::encode(&version))
+            .map_err(|e| Error::DBError(e.into_string()))
+    }
+}
+
+impl PersistentBackend for RocksDB {
+    const BACKEND: StorageBackend = StorageBackend::RocksDB;
+
+    fn new_cache(size_bytes: u64) -> rocksdb::Cache {
+        rocksdb::Cache::new_lru_cache(size_bytes as usize)
+            .expect("cannot create the DB cache")
+    }
+
+    fn open_primary_unchecked(
+        path: &Path,
+        cache: Option<&rocksdb::Cache>,
+    ) -> Result<Self> {
+        open(path, cache)
+    }
+
+    fn open_secondary(
+        primary_path: &Path,
+        secondary_path: &Path,
+        cache: Option<&rocksdb::Cache>,
+    ) -> Result<Self> {
+        open_secondary(primary_path, secondary_path, cache)
+    }
+
+    fn catch_up_with_primary(&self) -> Result<()> {
+        RocksDB::catch_up_with_primary(self)
+    }
+
+    fn checkpoint(&self, path: &Path) -> Result<()> {
+        RocksDB::checkpoint(self, path)
+    }
+
+    fn rollback(&mut self, height: BlockHeight) -> Result<()> {
+        RocksDB::rollback(self, height)
+    }
+
+    fn split_column_families(&mut self) -> Result<()> {
+        RocksDB::split_column_families(self)
+    }
 }
 
 impl<'iter> DBIter<'iter> for RocksDB {
@@ -806,30 +1425,124 @@ impl<'iter> DBIter<'iter> for RocksDB {
         &'iter self,
         prefix: &Key,
     ) -> PersistentPrefixIterator<'iter> {
-        let db_prefix = "subspace/".to_owned();
-        let prefix = format!("{}{}", db_prefix, prefix);
+        self.iter_db_prefix(&subspace_cfs(prefix), String::new(), prefix, false)
+    }
 
-        let mut read_opts = ReadOptions::default();
-        // don't use the prefix bloom filter
-        read_opts.set_total_order_seek(true);
-        let mut upper_prefix = prefix.clone().into_bytes();
-        if let Some(last) = upper_prefix.pop() {
-            upper_prefix.push(last + 1);
-        }
-        read_opts.set_iterate_upper_bound(upper_prefix);
+    fn rev_iter_prefix(
+        &'iter self,
+        prefix: &Key,
+    ) -> PersistentPrefixIterator<'iter> {
+        self.iter_db_prefix(&subspace_cfs(prefix), String::new(), prefix, true)
+    }
 
-        let iter = self.0.iterator_opt(
-            IteratorMode::From(prefix.as_bytes(), Direction::Forward),
-            read_opts,
-        );
-        PersistentPrefixIterator(PrefixIterator::new(iter, db_prefix))
+    fn iter_prefix_from(
+        &'iter self,
+        prefix: &Key,
+        start: &Key,
+    ) -> PersistentPrefixIterator<'iter> {
+        let prefix_bytes = prefix.to_string().into_bytes();
+        let mut upper = prefix_bytes.clone();
+        // No upper bound for an empty prefix
+        let upper = upper.pop().map(|last| {
+            upper.push(last + 1);
+            upper
+        });
+        let lower = std::cmp::max(prefix_bytes, start.to_string().into_bytes());
+        self.iter_db_range(
+            &subspace_cfs(prefix),
+            String::new(),
+            lower,
+            upper,
+            false,
+        )
+    }
+
+    fn iter_range(
+        &'iter self,
+        start: &Key,
+        end: &Key,
+    ) -> PersistentPrefixIterator<'iter> {
+        let lower = start.to_string().into_bytes();
+        let upper = end.to_string().into_bytes();
+        // A range may span the keys of all the subspaces
+        self.iter_db_range(
+            &SUBSPACE_CFS,
+            String::new(),
+            lower,
+            Some(upper),
+            false,
+        )
+    }
+
+    fn rev_iter_range(
+        &'iter self,
+        start: &Key,
+        end: &Key,
+    ) -> PersistentPrefixIterator<'iter> {
+        let lower = start.to_string().into_bytes();
+        let upper = end.to_string().into_bytes();
+        self.iter_db_range(
+            &SUBSPACE_CFS,
+            String::new(),
+            lower,
+            Some(upper),
+            true,
+        )
+    }
+
+    fn iter_old_diffs(
+        &'iter self,
+        height: BlockHeight,
+        prefix: &Key,
+    ) -> PersistentPrefixIterator<'iter> {
+        let db_prefix = format!("{}/old/", height);
+        self.iter_db_prefix(&[DIFFS_CF], db_prefix, prefix, false)
+    }
+
+    fn iter_new_diffs(
+        &'iter self,
+        height: BlockHeight,
+        prefix: &Key,
+    ) -> PersistentPrefixIterator<'iter> {
+        let db_prefix = format!("{}/new/", height);
+        self.iter_db_prefix(&[DIFFS_CF], db_prefix, prefix, false)
+    }
+}
+
+/// The iterator of the key-vals of several column families in the order of
+/// their keys. The key-vals of the column families must be disjoint.
+pub struct MergedIterator<'a> {
+    iters: Vec<Peekable<rocksdb::DBIterator<'a>>>,
+    reverse: bool,
+}
+
+impl<'a> Iterator for MergedIterator<'a> {
+    type Item = KVBytes;
+
+    fn next(&mut self) -> Option<KVBytes> {
+        let reverse = self.reverse;
+        // The iterator with the lowest next key, or the highest one in
+        // reverse
+        let next = self
+            .iters
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(ix, iter)| iter.peek().map(|(key, _)| (ix, key)))
+            .reduce(|next, other| {
+                let is_lower = key_comparator(other.1, next.1).is_lt();
+                if is_lower != reverse {
+                    other
+                } else {
+                    next
+                }
+            })
+            .map(|(ix, _)| ix)?;
+        self.iters[next].next()
     }
 }
 
 #[derive(Debug)]
-pub struct PersistentPrefixIterator<'a>(
-    PrefixIterator<rocksdb::DBIterator<'a>>,
-);
+pub struct PersistentPrefixIterator<'a>(PrefixIterator<MergedIterator<'a>>);
 
 impl<'a> Iterator for PersistentPrefixIterator<'a> {
     type Item = (String, Vec<u8>, u64);
@@ -971,6 +1684,8 @@ mod test {
         let block = BlockStateWrite {
             merkle_tree_stores,
             header: None,
+            block_request: None,
+            block_gas: 0,
             hash: &hash,
             height,
             epoch,
@@ -1019,7 +1734,7 @@ mod test {
         db.exec_batch(batch.0).unwrap();
 
         let prev_value = db
-            .read_subspace_val_with_height(&key, BlockHeight(100))
+            .read_subspace_val_with_height(&key, BlockHeight(100), last_height)
             .expect("read should succeed");
         assert_eq!(prev_value, Some(vec![1_u8, 1, 1, 1]));
 
@@ -1027,4 +1742,299 @@ mod test {
             db.read_subspace_val(&key).expect("read should succeed");
         assert_eq!(latest_value, Some(vec![2_u8, 2, 2, 2]));
     }
+
+    /// Test that the values of the past heights are read from the diffs,
+    /// including the values of the keys that were deleted since then.
+    #[test]
+    fn test_read_with_height() {
+        let dir = tempdir().unwrap();
+        let mut db = open(dir.path(), None).unwrap();
+
+        let key1 = Key::parse("test/key1").unwrap();
+        let key2 = Key::parse("test/key2").unwrap();
+        let prefix = Key::parse("test").unwrap();
+
+        // Block 1 writes `key1`, block 2 updates it and writes `key2`, block 3
+        // deletes `key1`
+        db.write_subspace_val(BlockHeight(1), &key1, vec![1_u8])
+            .unwrap();
+        db.write_subspace_val(BlockHeight(2), &key1, vec![2_u8])
+            .unwrap();
+        db.write_subspace_val(BlockHeight(2), &key2, vec![2_u8])
+            .unwrap();
+        db.delete_subspace_val(BlockHeight(3), &key1).unwrap();
+        let last_height = BlockHeight(3);
+
+        let read = |key: &Key, height: u64| {
+            db.read_subspace_val_with_height(
+                key,
+                BlockHeight(height),
+                last_height,
+            )
+            .unwrap()
+        };
+        assert_eq!(read(&key1, 1), Some(vec![1_u8]));
+        assert_eq!(read(&key1, 2), Some(vec![2_u8]));
+        assert_eq!(read(&key1, 3), None);
+        assert_eq!(read(&key2, 1), None);
+        assert_eq!(read(&key2, 2), Some(vec![2_u8]));
+        assert_eq!(read(&key2, 3), Some(vec![2_u8]));
+
+        let deleted: Vec<String> = db
+            .iter_old_diffs(BlockHeight(3), &prefix)
+            .map(|(key, _value, _gas)| key)
+            .collect();
+        assert_eq!(deleted, vec![key1.to_string()]);
+        let written: Vec<String> = db
+            .iter_new_diffs(BlockHeight(2), &prefix)
+            .map(|(key, _value, _gas)| key)
+            .collect();
+        assert_eq!(written, vec![key1.to_string(), key2.to_string()]);
+    }
+
+    /// Test that the DB can be rolled back to a previous block.
+    #[test]
+    fn test_rollback() {
+        let dir = tempdir().unwrap();
+        let mut db = open(dir.path(), None).unwrap();
+
+        let key1 = Key::parse("key1").unwrap();
+        let key2 = Key::parse("key2").unwrap();
+
+        // Block 1 writes `key1`
+        db.write_subspace_val(BlockHeight(1), &key1, vec![1_u8])
+            .unwrap();
+        write_empty_block(&mut db, BlockHeight(1));
+        // Block 2 updates `key1` and writes `key2`
+        db.write_subspace_val(BlockHeight(2), &key1, vec![2_u8])
+            .unwrap();
+        db.write_subspace_val(BlockHeight(2), &key2, vec![2_u8])
+            .unwrap();
+        write_empty_block(&mut db, BlockHeight(2));
+        // Block 3 deletes `key1`
+        db.delete_subspace_val(BlockHeight(3), &key1).unwrap();
+        write_empty_block(&mut db, BlockHeight(3));
+
+        db.rollback(BlockHeight(1))
+            .expect("rollback should succeed");
+
+        let state = db
+            .read_last_block()
+            .expect("Should be able to read last block")
+            .expect("Block should have been restored");
+        assert_eq!(state.height, BlockHeight(1));
+        assert_eq!(db.read_subspace_val(&key1).unwrap(), Some(vec![1_u8]));
+        assert_eq!(db.read_subspace_val(&key2).unwrap(), None);
+        assert!(db
+            .read_merkle_tree_stores(BlockHeight(2))
+            .unwrap()
+            .is_none());
+
+        // The last block cannot be rolled back to
+        assert!(db.rollback(BlockHeight(1)).is_err());
+    }
+
+    /// Test that a rollback from a multi-digit height only reverts the diffs
+    /// of the blocks above the given height.
+    #[test]
+    fn test_rollback_multi_digit_heights() {
+        let dir = tempdir().unwrap();
+        let mut db = open(dir.path(), None).unwrap();
+
+        let key = Key::parse("key").unwrap();
+        let other_key = Key::parse("other_key").unwrap();
+        // Block 1 writes `other_key` and every block up to 11 writes `key`
+        db.write_subspace_val(BlockHeight(1), &other_key, vec![1_u8])
+            .unwrap();
+        for height in 1..=11 {
+            db.write_subspace_val(
+                BlockHeight(height),
+                &key,
+                vec![height as u8],
+            )
+            .unwrap();
+            write_empty_block(&mut db, BlockHeight(height));
+        }
+
+        db.rollback(BlockHeight(9))
+            .expect("rollback should succeed");
+
+        let state = db
+            .read_last_block()
+            .expect("Should be able to read last block")
+            .expect("Block should have been restored");
+        assert_eq!(state.height, BlockHeight(9));
+        assert_eq!(db.read_subspace_val(&key).unwrap(), Some(vec![9_u8]));
+        assert_eq!(db.read_subspace_val(&other_key).unwrap(), Some(vec![1_u8]));
+        for height in 1..=11 {
+            let is_reverted = height > 9;
+            assert_eq!(
+                db.read_block_gas(BlockHeight(height)).unwrap(),
+                if is_reverted { None } else { Some(0) },
+                "Unexpected block data at height {}",
+                height
+            );
+            assert_eq!(
+                db.iter_new_diffs(BlockHeight(height), &key).count(),
+                if is_reverted { 0 } else { 1 },
+                "Unexpected diffs at height {}",
+                height
+            );
+        }
+        assert_eq!(db.iter_new_diffs(BlockHeight(1), &other_key).count(), 1);
+    }
+
+    /// Test that the data of a pruned block is removed, with its diffs only
+    /// when they're pruned too.
+    #[test]
+    fn test_prune_block() {
+        let dir = tempdir().unwrap();
+        let mut db = open(dir.path(), None).unwrap();
+
+        let key = Key::parse("key").unwrap();
+        for height in 1..=3 {
+            db.write_subspace_val(
+                BlockHeight(height),
+                &key,
+                vec![height as u8],
+            )
+            .unwrap();
+            write_empty_block(&mut db, BlockHeight(height));
+        }
+        let last_height = BlockHeight(3);
+
+        // The state below a block pruned without its diffs can still be read
+        db.prune_block(BlockHeight(2), false).unwrap();
+        assert_eq!(db.read_block_gas(BlockHeight(2)).unwrap(), None);
+        assert!(db
+            .read_merkle_tree_stores(BlockHeight(2))
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            db.read_subspace_val_with_height(&key, BlockHeight(1), last_height)
+                .unwrap(),
+            Some(vec![1_u8])
+        );
+
+        db.prune_block(BlockHeight(1), true).unwrap();
+        assert_eq!(db.read_block_gas(BlockHeight(1)).unwrap(), None);
+        assert_eq!(
+            db.iter_new_diffs(BlockHeight(1), &key).count(),
+            0,
+            "The diffs of the block should be pruned"
+        );
+        assert_eq!(db.iter_new_diffs(BlockHeight(2), &key).count(), 1);
+
+        // The last block is untouched
+        let state = db.read_last_block().unwrap().unwrap();
+        assert_eq!(state.height, last_height);
+        assert_eq!(db.read_block_gas(last_height).unwrap(), Some(0));
+    }
+
+    /// Test that pruning a block doesn't touch the blocks whose heights start
+    /// with its height, nor the ones whose heights have more digits.
+    #[test]
+    fn test_prune_block_with_multi_digit_heights() {
+        let dir = tempdir().unwrap();
+        let mut db = open(dir.path(), None).unwrap();
+
+        let key = Key::parse("key").unwrap();
+        for height in 1..=120 {
+            db.write_subspace_val(
+                BlockHeight(height),
+                &key,
+                vec![height as u8],
+            )
+            .unwrap();
+            write_empty_block(&mut db, BlockHeight(height));
+        }
+
+        for pruned in [1, 9, 10, 99] {
+            db.prune_block(BlockHeight(pruned), true).unwrap();
+        }
+        for height in 1..=120 {
+            let is_pruned = [1, 9, 10, 99].contains(&height);
+            assert_eq!(
+                db.read_block_gas(BlockHeight(height)).unwrap(),
+                if is_pruned { None } else { Some(0) },
+                "Unexpected block data at height {}",
+                height
+            );
+            assert_eq!(
+                db.iter_new_diffs(BlockHeight(height), &key).count(),
+                if is_pruned { 0 } else { 1 },
+                "Unexpected diffs at height {}",
+                height
+            );
+        }
+    }
+
+    /// Test that the key-vals of the layout before the column families are
+    /// moved to their column families and that the subspaces are iterated
+    /// in the order of their keys across the column families.
+    #[test]
+    fn test_split_column_families() {
+        let dir = tempdir().unwrap();
+        let mut db = open(dir.path(), None).unwrap();
+
+        let key = Key::parse("key").unwrap();
+        let pos_key =
+            Key::from(Address::Internal(InternalAddress::PoS).to_db_key())
+                .push(&"key".to_owned())
+                .unwrap();
+        // The layout of the schema version 1
+        db.0.put(format!("subspace/{}", pos_key), vec![1_u8])
+            .unwrap();
+        db.0.put("subspace/key", vec![2_u8]).unwrap();
+        db.0.put("1/diffs/new/key", vec![2_u8]).unwrap();
+        db.0.put("1/gas", types::encode(&0_u64)).unwrap();
+        db.0.put("height", types::encode(&BlockHeight(1))).unwrap();
+
+        db.split_column_families().unwrap();
+        assert_eq!(db.read_subspace_val(&pos_key).unwrap(), Some(vec![1_u8]));
+        assert_eq!(db.read_subspace_val(&key).unwrap(), Some(vec![2_u8]));
+        assert_eq!(db.read_block_gas(BlockHeight(1)).unwrap(), Some(0));
+        assert_eq!(db.iter_new_diffs(BlockHeight(1), &key).count(), 1);
+        assert_eq!(db.0.get("subspace/key").unwrap(), None);
+        // The top-level keys stay in the default column family
+        assert!(db.0.get("height").unwrap().is_some());
+
+        let all_keys = Key { segments: vec![] };
+        let mut expected = vec![key.to_string(), pos_key.to_string()];
+        expected.sort();
+        let keys: Vec<String> = db
+            .iter_prefix(&all_keys)
+            .map(|(key, _value, _gas)| key)
+            .collect();
+        assert_eq!(keys, expected);
+        expected.reverse();
+        let keys: Vec<String> = db
+            .rev_iter_prefix(&all_keys)
+            .map(|(key, _value, _gas)| key)
+            .collect();
+        assert_eq!(keys, expected);
+    }
+
+    fn write_empty_block(db: &mut RocksDB, height: BlockHeight) {
+        let merkle_tree = MerkleTree::<Sha256Hasher>::default();
+        let hash = BlockHash::default();
+        let pred_epochs = Epochs::default();
+        let address_gen = EstablishedAddressGen::new("whatever");
+        let tx_queue = TxQueue::default();
+        db.write_block(BlockStateWrite {
+            merkle_tree_stores: merkle_tree.stores(),
+            header: None,
+            block_request: None,
+            block_gas: 0,
+            hash: &hash,
+            height,
+            epoch: Epoch::default(),
+            pred_epochs: &pred_epochs,
+            next_epoch_min_start_height: BlockHeight::default(),
+            next_epoch_min_start_time: DateTimeUtc::now(),
+            address_gen: &address_gen,
+            tx_queue: &tx_queue,
+        })
+        .unwrap();
+    }
 }
@@ -0,0 +1,243 @@
+//! The versioned layout of the ledger's DB. The schema version of the layout
+//! is stored in the DB and the schema migrations of the newer versions are
+//! applied when the node starts, or with the `ledger migrate` command, so
+//! that a change of the layout doesn't require to restart the chain from its
+//! genesis. Unlike the storage migrations of the protocol upgrades, the
+//! schema migrations only change how the node stores the state, not the
+//! state itself, so every node migrates its DB on its own.
+
+use std::cmp::Ordering;
+
+use anoma::ledger::storage::{self, DB};
+use thiserror::Error;
+
+use super::PersistentBackend;
+
+/// The schema version of the DB layout written by this node. A change of the
+/// layout must increment it and add its migration to [`schema_migrations`].
+pub const SCHEMA_VERSION: u64 = 2;
+
+/// The schema version of the DBs that were created before the version was
+/// stored
+pub const INITIAL_SCHEMA_VERSION: u64 = 1;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error(
+        "The DB is at schema version {db_version}, which is newer than the \
+         version {node_version} of the node"
+    )]
+    NewerVersion { db_version: u64, node_version: u64 },
+    #[error(
+        "The DB is at schema version {db_version}, which is older than the \
+         version {node_version} of the node. Run `anoma ledger migrate` to \
+         migrate the DB."
+    )]
+    OlderVersion { db_version: u64, node_version: u64 },
+    #[error("Error in the schema migration to version {version}: {error}")]
+    Migration { version: u64, error: storage::Error },
+    #[error("Storage error: {0}")]
+    Storage(storage::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A migration of the DB to the layout of a schema version
+pub struct SchemaMigration<D> {
+    /// The schema version of the migrated layout
+    pub version: u64,
+    /// A short description of the changes of the layout
+    pub description: &'static str,
+    /// Migrate the DB from the layout of the previous version. It must also
+    /// succeed on an empty DB.
+    pub migrate: fn(&mut D) -> storage::Result<()>,
+}
+
+/// The schema migrations of all the versions, ordered by version
+pub fn schema_migrations<D: PersistentBackend>() -> Vec<SchemaMigration<D>> {
+    vec![SchemaMigration {
+        version: 2,
+        description: "Split the state into the column families of the \
+                      subspaces, the blocks and the diffs",
+        migrate: D::split_column_families,
+    }]
+}
+
+/// The schema migrations of a DB, which are pending or applied
+#[derive(Clone, Debug, PartialEq)]
+pub struct MigrationReport {
+    /// The schema version of the DB before the migrations
+    pub from_version: u64,
+    /// The schema version of the DB after the migrations
+    pub to_version: u64,
+    /// The versions and the descriptions of the migrations
+    pub migrations: Vec<(u64, &'static str)>,
+}
+
+/// Read the schema version of the DB. A DB without a stored version is at
+/// the [`INITIAL_SCHEMA_VERSION`].
+pub fn read_version<D: DB>(db: &D) -> Result<u64> {
+    Ok(db
+        .read_schema_version()
+        .map_err(Error::Storage)?
+        .unwrap_or(INITIAL_SCHEMA_VERSION))
+}
+
+/// Check that the DB is at the [`SCHEMA_VERSION`] of the node
+pub fn check_version<D: DB>(db: &D) -> Result<()> {
+    let db_version = read_version(db)?;
+    let node_version = SCHEMA_VERSION;
+    match db_version.cmp(&node_version) {
+        Ordering::Less => Err(Error::OlderVersion {
+            db_version,
+            node_version,
+        }),
+        Ordering::Greater => Err(Error::NewerVersion {
+            db_version,
+            node_version,
+        }),
+        Ordering::Equal => Ok(()),
+    }
+}
+
+/// Migrate the DB to the `node_version` with the given migrations. The version
+/// of the DB is written after every applied migration, so that a failed
+/// migration is resumed from the last applied one. With `dry_run`, the
+/// pending migrations are only reported. Fails if the DB is newer than the
+/// node.
+pub fn migrate<D: DB>(
+    db: &mut D,
+    node_version: u64,
+    migrations: &[SchemaMigration<D>],
+    dry_run: bool,
+) -> Result<MigrationReport> {
+    let db_version = read_version(db)?;
+    if db_version > node_version {
+        return Err(Error::NewerVersion {
+            db_version,
+            node_version,
+        });
+    }
+    let pending: Vec<&SchemaMigration<D>> = migrations
+        .iter()
+        .filter(|migration| {
+            migration.version > db_version && migration.version <= node_version
+        })
+        .collect();
+    let report = MigrationReport {
+        from_version: db_version,
+        to_version: node_version,
+        migrations: pending
+            .iter()
+            .map(|migration| (migration.version, migration.description))
+            .collect(),
+    };
+    if dry_run {
+        return Ok(report);
+    }
+    for migration in pending {
+        tracing::info!(
+            "Applying the schema migration to version {}: {}",
+            migration.version,
+            migration.description
+        );
+        (migration.migrate)(db).map_err(|error| Error::Migration {
+            version: migration.version,
+            error,
+        })?;
+        db.write_schema_version(migration.version)
+            .and_then(|()| db.flush(true))
+            .map_err(Error::Storage)?;
+    }
+    if db.read_schema_version().map_err(Error::Storage)? != Some(node_version) {
+        db.write_schema_version(node_version)
+            .and_then(|()| db.flush(true))
+            .map_err(Error::Storage)?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::ledger::storage::mockdb::MockDB;
+
+    use super::*;
+
+    /// Test that the pending migrations are applied in order, from the
+    /// version of the DB up to the version of the node, and that a dry run
+    /// doesn't change the DB.
+    #[test]
+    fn test_migrate() {
+        let migrations: Vec<SchemaMigration<MockDB>> = vec![
+            SchemaMigration {
+                version: 2,
+                description: "two",
+                migrate: |db| db.write_schema_version(20),
+            },
+            SchemaMigration {
+                version: 3,
+                description: "three",
+                migrate: |db| {
+                    // The version of the previous migration is written
+                    assert_eq!(db.read_schema_version()?, Some(2));
+                    Ok(())
+                },
+            },
+            SchemaMigration {
+                version: 4,
+                description: "four",
+                migrate: |_db| unreachable!("The node is at version 3"),
+            },
+        ];
+        let mut db = MockDB::default();
+        assert_eq!(read_version(&db).unwrap(), INITIAL_SCHEMA_VERSION);
+
+        let report = migrate(&mut db, 3, &migrations, true).unwrap();
+        let expected = MigrationReport {
+            from_version: 1,
+            to_version: 3,
+            migrations: vec![(2, "two"), (3, "three")],
+        };
+        assert_eq!(report, expected);
+        assert_eq!(db.read_schema_version().unwrap(), None);
+
+        let report = migrate(&mut db, 3, &migrations, false).unwrap();
+        assert_eq!(report, expected);
+        assert_eq!(read_version(&db).unwrap(), 3);
+
+        // Nothing is pending once the DB is migrated
+        let report = migrate(&mut db, 3, &migrations, false).unwrap();
+        assert!(report.migrations.is_empty());
+
+        // A DB that is newer than the node isn't opened
+        let result = migrate(&mut db, 2, &migrations, false);
+        assert!(matches!(
+            result,
+            Err(Error::NewerVersion {
+                db_version: 3,
+                node_version: 2
+            })
+        ));
+    }
+
+    /// Test that only a DB at the schema version of the node passes the
+    /// version check.
+    #[test]
+    fn test_check_version() {
+        let mut db = MockDB::default();
+        assert!(matches!(
+            check_version(&db),
+            Err(Error::OlderVersion {
+                db_version: INITIAL_SCHEMA_VERSION,
+                node_version: SCHEMA_VERSION
+            })
+        ));
+        db.write_schema_version(SCHEMA_VERSION).unwrap();
+        assert!(check_version(&db).is_ok());
+        db.write_schema_version(SCHEMA_VERSION + 1).unwrap();
+        assert!(matches!(
+            check_version(&db),
+            Err(Error::NewerVersion { .. })
+        ));
+    }
+}
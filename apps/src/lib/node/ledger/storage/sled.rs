@@ -0,0 +1,1359 @@
+//! The persistent storage in sled, an embedded DB written in pure Rust.
+//!
+//! The storage tree is the same as the one of the RocksDB storage. The keys
+//! are sorted in the lexicographical order of their bytes, so the data of a
+//! block height is always read with the prefix of the height rather than with
+//! a range of the heights.
+//!
+//! A sled DB can only be opened once by a process, so the secondary instances
+//! of the query shells and of the snapshots are opened from the handle of the
+//! primary instance opened by the main shell. A secondary instance keeps its
+//! own copy of the account subspace and of the last block's metadata, which
+//! is only updated when it catches up with the primary instance, while the
+//! data of the committed blocks is read from the primary instance.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use anoma::ledger::storage::types::PrefixIterator;
+use anoma::ledger::storage::{
+    types, BlockStateRead, BlockStateWrite, DBIter, DBWriteBatch, Error,
+    MerkleTreeStoresRead, Result, StoreType, DB,
+};
+use anoma::types::storage::{
+    BlockHeight, Header, Key, KeySeg, TxQueue, KEY_SEGMENT_SEPARATOR,
+};
+use anoma::types::time::DateTimeUtc;
+use borsh::{BorshDeserialize, BorshSerialize};
+use once_cell::sync::Lazy;
+use sled::transaction::{ConflictableTransactionError, TransactionError};
+
+use super::{check_backend, DbMode, PersistentBackend};
+use crate::config::StorageBackend;
+
+/// The handles of the primary sled DBs opened by this process, by their
+/// canonical paths, which are shared with their secondary instances
+static PRIMARY_DBS: Lazy<Mutex<HashMap<PathBuf, sled::Db>>> =
+    Lazy::new(Default::default);
+
+/// The keys of the last block's metadata, which are not under the prefix of
+/// its height
+const LAST_BLOCK_KEYS: [&str; 8] = [
+    "height",
+    "next_epoch_min_start_height",
+    "next_epoch_min_start_time",
+    "tx_queue",
+    "pred/next_epoch_min_start_height",
+    "pred/next_epoch_min_start_time",
+    "pred/tx_queue",
+    "schema_version",
+];
+
+/// Sled handle
+#[derive(Debug)]
+pub struct SledDB {
+    db: sled::Db,
+    /// The copy of the account subspace and of the last block's metadata of
+    /// a secondary instance, as of its last catch up
+    replica: Option<sled::Db>,
+    mode: DbMode,
+    path: PathBuf,
+}
+
+/// DB Handle for batch writes.
+#[derive(Default)]
+pub struct SledDBWriteBatch(sled::Batch);
+
+/// Open sled for the DB. The size of the cache is given in bytes.
+pub fn open(path: impl AsRef<Path>, cache: Option<&u64>) -> Result<SledDB> {
+    let path = path.as_ref();
+    check_backend(path, StorageBackend::Sled)?;
+    let mut config = sled::Config::new().path(path);
+    if let Some(cache) = cache {
+        config = config.cache_capacity(*cache);
+    }
+    let db = config.open().map_err(|e| Error::DBError(e.to_string()))?;
+    let path = canonical_path(path)?;
+    PRIMARY_DBS
+        .lock()
+        .expect("The lock of the primary DBs shouldn't be poisoned")
+        .insert(path.clone(), db.clone());
+    Ok(SledDB {
+        db,
+        replica: None,
+        mode: DbMode::Primary,
+        path,
+    })
+}
+
+/// Open a read-only secondary instance of the sled DB at the `primary_path`,
+/// which must be opened by the primary instance in this process first. The
+/// copy of the secondary instance is kept at the `secondary_path`, which is
+/// removed when the instance is dropped. The size of the cache of the copy
+/// is given in bytes.
+pub fn open_secondary(
+    primary_path: impl AsRef<Path>,
+    secondary_path: impl AsRef<Path>,
+    cache: Option<&u64>,
+) -> Result<SledDB> {
+    let path = canonical_path(primary_path.as_ref())?;
+    let db = PRIMARY_DBS
+        .lock()
+        .expect("The lock of the primary DBs shouldn't be poisoned")
+        .get(&path)
+        .cloned()
+        .ok_or_else(|| {
+            Error::DBError(format!(
+                "The sled DB at {} must be opened by its primary instance \
+                 before its secondary instances",
+                path.display()
+            ))
+        })?;
+
+    // A copy left by a previous run is outdated
+    let secondary_path = secondary_path.as_ref();
+    if secondary_path.exists() {
+        std::fs::remove_dir_all(secondary_path).map_err(|e| {
+            Error::DBError(format!(
+                "Cannot remove the outdated copy of the DB at {}: {}",
+                secondary_path.display(),
+                e
+            ))
+        })?;
+    }
+    let mut config = sled::Config::new().path(secondary_path).temporary(true);
+    if let Some(cache) = cache {
+        config = config.cache_capacity(*cache);
+    }
+    let replica = config.open().map_err(|e| Error::DBError(e.to_string()))?;
+    // The last block's metadata is copied first, so that the subspace keys
+    // changed by the blocks committed while the subspace is copied are
+    // updated on the next catch up
+    for (key, value) in LAST_BLOCK_KEYS.iter().zip(read_last_block_keys(&db)?) {
+        if let Some(value) = value {
+            replica
+                .insert(*key, value)
+                .map_err(|e| Error::DBError(e.to_string()))?;
+        }
+    }
+    for kv in db.scan_prefix("subspace/") {
+        let (key, value) = kv.map_err(|e| Error::DBError(e.to_string()))?;
+        replica
+            .insert(key, value)
+            .map_err(|e| Error::DBError(e.to_string()))?;
+    }
+    Ok(SledDB {
+        db,
+        replica: Some(replica),
+        mode: DbMode::Secondary,
+        path,
+    })
+}
+
+/// Read the values of the last block's metadata together, so that they're
+/// all of the same block
+fn read_last_block_keys(db: &sled::Db) -> Result<Vec<Option<sled::IVec>>> {
+    db.transaction(|db| {
+        LAST_BLOCK_KEYS
+            .iter()
+            .map(|key| db.get(*key).map_err(ConflictableTransactionError::from))
+            .collect::<std::result::Result<
+                Vec<_>,
+                ConflictableTransactionError<sled::Error>,
+            >>()
+    })
+    .map_err(|e: TransactionError<sled::Error>| Error::DBError(e.to_string()))
+}
+
+fn canonical_path(path: &Path) -> Result<PathBuf> {
+    path.canonicalize().map_err(|e| {
+        Error::DBError(format!(
+            "Cannot find the DB directory {}: {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+impl Drop for SledDB {
+    fn drop(&mut self) {
+        // A secondary instance doesn't have anything to flush
+        if self.mode == DbMode::Primary {
+            PRIMARY_DBS
+                .lock()
+                .expect("The lock of the primary DBs shouldn't be poisoned")
+                .remove(&self.path);
+            if let Err(e) = self.flush(true) {
+                tracing::error!("Failed to flush the DB on drop: {}", e);
+            }
+        }
+    }
+}
+
+impl SledDB {
+    /// The keyspace of a DB key. The account subspace and the last block's
+    /// metadata of a secondary instance are read from its copy, while the
+    /// data of the committed blocks, which is under the prefix of their
+    /// height and isn't changed after their commit, is read from the primary
+    /// instance.
+    fn keyspace(&self, key: &[u8]) -> &sled::Db {
+        match &self.replica {
+            Some(replica) if !key.first().map_or(false, u8::is_ascii_digit) => {
+                replica
+            }
+            _ => &self.db,
+        }
+    }
+
+    fn get(&self, key: impl AsRef<[u8]>) -> Result<Option<Vec<u8>>> {
+        let key = key.as_ref();
+        self.keyspace(key)
+            .get(key)
+            .map(|value| value.map(|value| value.to_vec()))
+            .map_err(|e| Error::DBError(e.to_string()))
+    }
+
+    /// Iterate the key-vals with the given prefix of the DB keys, with the
+    /// keys converted to strings
+    fn iter_with_prefix(
+        &self,
+        prefix: &str,
+    ) -> impl Iterator<Item = Result<(String, sled::IVec)>> {
+        self.keyspace(prefix.as_bytes())
+            .scan_prefix(prefix)
+            .map(|kv| {
+                let (key, value) =
+                    kv.map_err(|e| Error::DBError(e.to_string()))?;
+                let key = String::from_utf8(key.to_vec()).map_err(|e| {
+                    Error::Temporary {
+                        error: format!(
+                            "Cannot convert path from utf8 bytes to string: {}",
+                            e
+                        ),
+                    }
+                })?;
+                Ok((key, value))
+            })
+    }
+
+    /// Iterate the key-vals with the given prefix under the `db_prefix`, with
+    /// the keys stripped of the `db_prefix`
+    fn iter_db_prefix(
+        &self,
+        db_prefix: String,
+        prefix: &Key,
+        reverse: bool,
+    ) -> SledPrefixIterator {
+        let prefix = format!("{}{}", db_prefix, prefix);
+        let iter = self.keyspace(prefix.as_bytes()).scan_prefix(prefix);
+        SledPrefixIterator {
+            iter: PrefixIterator { iter, db_prefix },
+            reverse,
+        }
+    }
+
+    /// Iterate the account subspace key-vals with the keys from `start`
+    /// (inclusive) up to `end` (exclusive) in the given order
+    fn iter_subspace_range(
+        &self,
+        start: &Key,
+        end: &Key,
+        reverse: bool,
+    ) -> SledPrefixIterator {
+        let db_prefix = "subspace/".to_owned();
+        let lower = format!("{}{}", db_prefix, start).into_bytes();
+        let upper = format!("{}{}", db_prefix, end).into_bytes();
+        // An empty range if the upper bound is not above the lower bound
+        let upper = std::cmp::max(upper, lower.clone());
+        let iter = self.keyspace(&lower).range(lower..upper);
+        SledPrefixIterator {
+            iter: PrefixIterator { iter, db_prefix },
+            reverse,
+        }
+    }
+
+    /// Catch up a secondary instance with the blocks committed by the primary
+    /// instance since its last catch up. The subspace keys changed by these
+    /// blocks are copied from the primary instance together with the last
+    /// block's metadata in a single batch, so the copy isn't changed by the
+    /// writes of the primary instance between the catch ups.
+    pub fn catch_up_with_primary(&self) -> Result<()> {
+        let replica = match &self.replica {
+            Some(replica) => replica,
+            // A primary instance is always up to date
+            None => return Ok(()),
+        };
+        let last_block = read_last_block_keys(&self.db)?;
+        let height: BlockHeight = match &last_block[0] {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => return Ok(()),
+        };
+        let caught_up_height = match self.get("height")? {
+            Some(bytes) => {
+                let caught_up_height: BlockHeight =
+                    types::decode(bytes).map_err(Error::CodingError)?;
+                caught_up_height.0 + 1
+            }
+            None => 0,
+        };
+
+        let mut batch = sled::Batch::default();
+        let mut changed_keys: BTreeSet<String> = BTreeSet::new();
+        for changed_height in caught_up_height..=height.0 {
+            let diffs_prefix = format!("{}/diffs/", changed_height);
+            for kv in self.iter_with_prefix(&diffs_prefix) {
+                let (key, _value) = kv?;
+                // The diffs are under the `old` or `new` prefix
+                if let Some(subspace_key) = key.splitn(4, '/').nth(3) {
+                    changed_keys.insert(subspace_key.to_owned());
+                }
+            }
+        }
+        for key in changed_keys {
+            let subspace_key = format!("subspace/{}", key);
+            let value = self
+                .db
+                .get(&subspace_key)
+                .map_err(|e| Error::DBError(e.to_string()))?;
+            match value {
+                Some(value) => batch.insert(subspace_key.as_bytes(), value),
+                None => batch.remove(subspace_key.as_bytes()),
+            }
+        }
+        for (key, value) in LAST_BLOCK_KEYS.iter().zip(last_block) {
+            match value {
+                Some(value) => batch.insert(*key, value),
+                None => batch.remove(*key),
+            }
+        }
+        replica
+            .apply_batch(batch)
+            .map_err(|e| Error::DBError(e.to_string()))
+    }
+
+    /// Rollback the DB to the block committed at the given height. The
+    /// account subspaces are reverted with the diffs of the blocks above it,
+    /// the data of these blocks is removed and the last block's metadata is
+    /// restored from the block at the given height. All the changes are
+    /// written atomically.
+    pub fn rollback(&mut self, height: BlockHeight) -> Result<()> {
+        let last_height: BlockHeight = match self.get("height")? {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => {
+                return Err(Error::Temporary {
+                    error: "No block has been committed to the DB".to_string(),
+                });
+            }
+        };
+        if height >= last_height {
+            return Err(Error::Temporary {
+                error: format!(
+                    "Cannot rollback to height {}, the last committed height \
+                     is {}",
+                    height, last_height
+                ),
+            });
+        }
+
+        let mut batch = SledDBWriteBatch::default();
+
+        // Restore the top-level keys and their predecessor values
+        for name in [
+            "next_epoch_min_start_height",
+            "next_epoch_min_start_time",
+            "tx_queue",
+        ] {
+            match self.read_block_value(height, name)? {
+                Some(value) => batch.put(name, value),
+                None => {
+                    return Err(Error::Temporary {
+                        error: format!(
+                            "The {} of the block at height {} is not in the \
+                             DB, the block cannot be restored",
+                            name, height
+                        ),
+                    });
+                }
+            }
+            let pred_key = format!("pred/{}", name);
+            let pred_value = match height.0.checked_sub(1) {
+                Some(pred_height) => {
+                    self.read_block_value(BlockHeight(pred_height), name)?
+                }
+                None => None,
+            };
+            match pred_value {
+                Some(value) => batch.put(pred_key, value),
+                None => batch.delete(pred_key),
+            }
+        }
+
+        // Revert the blocks from the last one down to the given height
+        for reverted_height in (height.0 + 1..=last_height.0).rev() {
+            let prefix = format!("{}/", reverted_height);
+            let old_diffs_prefix = format!("{}/diffs/old/", reverted_height);
+            let new_diffs_prefix = format!("{}/diffs/new/", reverted_height);
+            let mut old_values: HashMap<String, Vec<u8>> = HashMap::new();
+            let mut changed_keys: BTreeSet<String> = BTreeSet::new();
+            for kv in self.iter_with_prefix(&prefix) {
+                let (key, value) = kv?;
+                if let Some(subspace_key) = key.strip_prefix(&old_diffs_prefix)
+                {
+                    old_values.insert(subspace_key.to_owned(), value.to_vec());
+                    changed_keys.insert(subspace_key.to_owned());
+                } else if let Some(subspace_key) =
+                    key.strip_prefix(&new_diffs_prefix)
+                {
+                    changed_keys.insert(subspace_key.to_owned());
+                }
+                batch.delete(key);
+            }
+            // A key without an old value didn't exist before the block
+            for key in changed_keys {
+                let subspace_key = format!("subspace/{}", key);
+                match old_values.remove(&key) {
+                    Some(value) => batch.put(subspace_key, value),
+                    None => batch.delete(subspace_key),
+                }
+            }
+        }
+
+        batch.put("height", types::encode(&height));
+
+        self.exec_batch(batch)?;
+        self.flush(true)
+    }
+
+    /// Read a value persisted with the block at the given height
+    fn read_block_value(
+        &self,
+        height: BlockHeight,
+        name: &str,
+    ) -> Result<Option<Vec<u8>>> {
+        let key = Key::from(height.to_db_key())
+            .push(&name.to_owned())
+            .map_err(Error::KeyError)?;
+        self.get(key.to_string())
+    }
+
+    /// Persist the diff of an account subspace key-val under the height where
+    /// it was changed in a batch write.
+    fn batch_write_subspace_diff(
+        batch: &mut SledDBWriteBatch,
+        height: BlockHeight,
+        key: &Key,
+        old_value: Option<&[u8]>,
+        new_value: Option<&[u8]>,
+    ) -> Result<()> {
+        let key_prefix = Key::from(height.to_db_key())
+            .push(&"diffs".to_owned())
+            .map_err(Error::KeyError)?;
+
+        if let Some(old_value) = old_value {
+            let old_val_key = key_prefix
+                .push(&"old".to_owned())
+                .map_err(Error::KeyError)?
+                .join(key)
+                .to_string();
+            batch.put(old_val_key, old_value);
+        }
+
+        if let Some(new_value) = new_value {
+            let new_val_key = key_prefix
+                .push(&"new".to_owned())
+                .map_err(Error::KeyError)?
+                .join(key)
+                .to_string();
+            batch.put(new_val_key, new_value);
+        }
+        Ok(())
+    }
+}
+
+impl DB for SledDB {
+    /// The size of the cache in bytes
+    type Cache = u64;
+    type WriteBatch = SledDBWriteBatch;
+
+    fn open(
+        db_path: impl AsRef<std::path::Path>,
+        cache: Option<&Self::Cache>,
+    ) -> Self {
+        open(db_path, cache).expect("cannot open the DB")
+    }
+
+    fn flush(&self, wait: bool) -> Result<()> {
+        // Without waiting, the writes are left to the periodic flushes of
+        // sled
+        if wait {
+            self.db.flush().map_err(|e| Error::DBError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn read_last_block(&mut self) -> Result<Option<BlockStateRead>> {
+        // Block height
+        let height: BlockHeight = match self.get("height")? {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => return Ok(None),
+        };
+
+        // Epoch start height and time
+        let next_epoch_min_start_height: BlockHeight = match self
+            .get("next_epoch_min_start_height")?
+        {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => {
+                tracing::error!(
+                    "Couldn't load next epoch start height from the DB"
+                );
+                return Ok(None);
+            }
+        };
+        let next_epoch_min_start_time: DateTimeUtc = match self
+            .get("next_epoch_min_start_time")?
+        {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => {
+                tracing::error!(
+                    "Couldn't load next epoch start time from the DB"
+                );
+                return Ok(None);
+            }
+        };
+        let tx_queue: TxQueue = match self.get("tx_queue")? {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => {
+                tracing::error!("Couldn't load tx queue from the DB");
+                return Ok(None);
+            }
+        };
+
+        // Load data at the height
+        let prefix = format!("{}/", height.raw());
+        let mut merkle_tree_stores = MerkleTreeStoresRead::default();
+        let mut hash = None;
+        let mut epoch = None;
+        let mut pred_epochs = None;
+        let mut address_gen = None;
+        for kv in self.iter_with_prefix(&prefix) {
+            let (path, bytes) = kv?;
+            let segments: Vec<&str> =
+                path.split(KEY_SEGMENT_SEPARATOR).collect();
+            match segments.get(1) {
+                Some(prefix) => match *prefix {
+                    "tree" => match segments.get(2) {
+                        Some(s) => {
+                            let st = StoreType::from_str(s)?;
+                            match segments.get(3) {
+                                Some(&"root") => merkle_tree_stores.set_root(
+                                    &st,
+                                    types::decode(bytes)
+                                        .map_err(Error::CodingError)?,
+                                ),
+                                Some(&"store") => merkle_tree_stores.set_store(
+                                    &st,
+                                    types::decode(bytes)
+                                        .map_err(Error::CodingError)?,
+                                ),
+                                Some(&"subtrees")
+                                    if st == StoreType::Account =>
+                                {
+                                    merkle_tree_stores.set_account_subtrees(
+                                        types::decode(bytes)
+                                            .map_err(Error::CodingError)?,
+                                    )
+                                }
+                                _ => unknown_key_error(&path)?,
+                            }
+                        }
+                        None => unknown_key_error(&path)?,
+                    },
+                    "header" | "request" | "gas" => {
+                        // the block header, request and gas don't have to be
+                        // restored
+                    }
+                    "next_epoch_min_start_height"
+                    | "next_epoch_min_start_time"
+                    | "tx_queue" => {
+                        // these are restored from the top-level keys
+                    }
+                    "hash" => {
+                        hash = Some(
+                            types::decode(bytes).map_err(Error::CodingError)?,
+                        )
+                    }
+                    "epoch" => {
+                        epoch = Some(
+                            types::decode(bytes).map_err(Error::CodingError)?,
+                        )
+                    }
+                    "pred_epochs" => {
+                        pred_epochs = Some(
+                            types::decode(bytes).map_err(Error::CodingError)?,
+                        )
+                    }
+                    "address_gen" => {
+                        address_gen = Some(
+                            types::decode(bytes).map_err(Error::CodingError)?,
+                        );
+                    }
+                    "diffs" => {
+                        // ignore the diffs
+                    }
+                    _ => unknown_key_error(&path)?,
+                },
+                None => unknown_key_error(&path)?,
+            }
+        }
+        match (hash, epoch, pred_epochs, address_gen) {
+            (Some(hash), Some(epoch), Some(pred_epochs), Some(address_gen)) => {
+                Ok(Some(BlockStateRead {
+                    merkle_tree_stores,
+                    hash,
+                    height,
+                    epoch,
+                    pred_epochs,
+                    next_epoch_min_start_height,
+                    next_epoch_min_start_time,
+                    address_gen,
+                    tx_queue,
+                }))
+            }
+            _ => Err(Error::Temporary {
+                error: "Essential data couldn't be read from the DB"
+                    .to_string(),
+            }),
+        }
+    }
+
+    fn write_block(&mut self, state: BlockStateWrite) -> Result<()> {
+        let mut batch = Self::batch();
+        self.batch_write_block(&mut batch, state)?;
+        self.exec_batch(batch)?;
+        // Flush without waiting
+        self.flush(false)
+    }
+
+    fn batch_write_block(
+        &self,
+        batch: &mut Self::WriteBatch,
+        state: BlockStateWrite,
+    ) -> Result<()> {
+        let BlockStateWrite {
+            merkle_tree_stores,
+            header,
+            block_request,
+            block_gas,
+            hash,
+            height,
+            epoch,
+            pred_epochs,
+            next_epoch_min_start_height,
+            next_epoch_min_start_time,
+            address_gen,
+            tx_queue,
+        }: BlockStateWrite = state;
+
+        // Epoch start height and time, with their predecessor values for
+        // rollback
+        if let Some(current_value) = self.get("next_epoch_min_start_height")? {
+            batch.put("pred/next_epoch_min_start_height", current_value);
+        }
+        batch.put(
+            "next_epoch_min_start_height",
+            types::encode(&next_epoch_min_start_height),
+        );
+        if let Some(current_value) = self.get("next_epoch_min_start_time")? {
+            batch.put("pred/next_epoch_min_start_time", current_value);
+        }
+        batch.put(
+            "next_epoch_min_start_time",
+            types::encode(&next_epoch_min_start_time),
+        );
+        // Tx queue
+        if let Some(pred_tx_queue) = self.get("tx_queue")? {
+            batch.put("pred/tx_queue", pred_tx_queue);
+        }
+        batch.put("tx_queue", types::encode(&tx_queue));
+
+        let prefix_key = Key::from(height.to_db_key());
+        // Merkle tree
+        {
+            let prefix_key = prefix_key
+                .push(&"tree".to_owned())
+                .map_err(Error::KeyError)?;
+            for st in StoreType::iter() {
+                let prefix_key = prefix_key
+                    .push(&st.to_string())
+                    .map_err(Error::KeyError)?;
+                let root_key = prefix_key
+                    .push(&"root".to_owned())
+                    .map_err(Error::KeyError)?;
+                batch.put(
+                    root_key.to_string(),
+                    types::encode(merkle_tree_stores.root(st)),
+                );
+                let store_key = prefix_key
+                    .push(&"store".to_owned())
+                    .map_err(Error::KeyError)?;
+                batch.put(
+                    store_key.to_string(),
+                    types::encode(merkle_tree_stores.store(st)),
+                );
+            }
+            let subtrees_key = prefix_key
+                .push(&StoreType::Account.to_string())
+                .and_then(|key| key.push(&"subtrees".to_owned()))
+                .map_err(Error::KeyError)?;
+            batch.put(
+                subtrees_key.to_string(),
+                types::encode(merkle_tree_stores.account_subtrees()),
+            );
+        }
+        // Block header
+        if let Some(h) = header {
+            let key = prefix_key
+                .push(&"header".to_owned())
+                .map_err(Error::KeyError)?;
+            batch.put(
+                key.to_string(),
+                h.try_to_vec().expect("serialization failed"),
+            );
+        }
+        // Block request
+        if let Some(request) = block_request {
+            let key = prefix_key
+                .push(&"request".to_owned())
+                .map_err(Error::KeyError)?;
+            batch.put(key.to_string(), request);
+        }
+        // The other values of the block
+        for (name, value) in [
+            ("gas", types::encode(&block_gas)),
+            ("hash", types::encode(hash)),
+            (
+                "next_epoch_min_start_height",
+                types::encode(&next_epoch_min_start_height),
+            ),
+            (
+                "next_epoch_min_start_time",
+                types::encode(&next_epoch_min_start_time),
+            ),
+            ("tx_queue", types::encode(tx_queue)),
+            ("epoch", types::encode(&epoch)),
+            ("pred_epochs", types::encode(pred_epochs)),
+            ("address_gen", types::encode(address_gen)),
+        ] {
+            let key =
+                prefix_key.push(&name.to_owned()).map_err(Error::KeyError)?;
+            batch.put(key.to_string(), value);
+        }
+
+        // Block height
+        batch.put("height", types::encode(&height));
+        Ok(())
+    }
+
+    fn read_block_header(&self, height: BlockHeight) -> Result<Option<Header>> {
+        match self.read_block_value(height, "header")? {
+            Some(v) => Ok(Some(
+                Header::try_from_slice(&v[..])
+                    .map_err(Error::BorshCodingError)?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    fn read_block_request(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<Vec<u8>>> {
+        self.read_block_value(height, "request")
+    }
+
+    fn read_block_gas(&self, height: BlockHeight) -> Result<Option<u64>> {
+        match self.read_block_value(height, "gas")? {
+            Some(bytes) => {
+                Ok(Some(types::decode(bytes).map_err(Error::CodingError)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn read_merkle_tree_stores(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<MerkleTreeStoresRead>> {
+        let mut merkle_tree_stores = MerkleTreeStoresRead::default();
+        let height_key = Key::from(height.to_db_key());
+        let tree_key = height_key
+            .push(&"tree".to_owned())
+            .map_err(Error::KeyError)?;
+        for st in StoreType::iter() {
+            let prefix_key =
+                tree_key.push(&st.to_string()).map_err(Error::KeyError)?;
+            let root_key = prefix_key
+                .push(&"root".to_owned())
+                .map_err(Error::KeyError)?;
+            match self.get(root_key.to_string())? {
+                Some(b) => {
+                    let root = types::decode(b).map_err(Error::CodingError)?;
+                    merkle_tree_stores.set_root(st, root);
+                }
+                None => return Ok(None),
+            }
+
+            let store_key = prefix_key
+                .push(&"store".to_owned())
+                .map_err(Error::KeyError)?;
+            match self.get(store_key.to_string())? {
+                Some(b) => {
+                    let store = types::decode(b).map_err(Error::CodingError)?;
+                    merkle_tree_stores.set_store(st, store);
+                }
+                None => return Ok(None),
+            }
+        }
+        let subtrees_key = tree_key
+            .push(&StoreType::Account.to_string())
+            .and_then(|key| key.push(&"subtrees".to_owned()))
+            .map_err(Error::KeyError)?;
+        match self.get(subtrees_key.to_string())? {
+            Some(b) => {
+                let subtrees = types::decode(b).map_err(Error::CodingError)?;
+                merkle_tree_stores.set_account_subtrees(subtrees);
+            }
+            None => return Ok(None),
+        }
+        Ok(Some(merkle_tree_stores))
+    }
+
+    fn read_subspace_val(&self, key: &Key) -> Result<Option<Vec<u8>>> {
+        let subspace_key =
+            Key::parse("subspace").map_err(Error::KeyError)?.join(key);
+        self.get(subspace_key.to_string())
+    }
+
+    fn read_subspace_val_with_height(
+        &self,
+        key: &Key,
+        height: BlockHeight,
+        last_height: BlockHeight,
+    ) -> Result<Option<Vec<u8>>> {
+        // The first block above the height that changed the key has its
+        // value at the height as the old value of the diff, or no old value
+        // if the key didn't exist yet
+        for changed_height in (height.0 + 1)..=last_height.0 {
+            let key_prefix = Key::from(BlockHeight(changed_height).to_db_key())
+                .push(&"diffs".to_owned())
+                .map_err(Error::KeyError)?;
+            let old_val_key = key_prefix
+                .push(&"old".to_owned())
+                .map_err(Error::KeyError)?
+                .join(key)
+                .to_string();
+            let old_val = self.get(old_val_key)?;
+            if old_val.is_some() {
+                return Ok(old_val);
+            }
+            let new_val_key = key_prefix
+                .push(&"new".to_owned())
+                .map_err(Error::KeyError)?
+                .join(key)
+                .to_string();
+            if self.get(new_val_key)?.is_some() {
+                return Ok(None);
+            }
+        }
+        self.read_subspace_val(key)
+    }
+
+    fn write_subspace_val(
+        &mut self,
+        height: BlockHeight,
+        key: &Key,
+        value: impl AsRef<[u8]>,
+    ) -> Result<i64> {
+        let mut batch = SledDBWriteBatch::default();
+        let size_diff =
+            self.batch_write_subspace_val(&mut batch, height, key, value)?;
+        self.exec_batch(batch)?;
+        Ok(size_diff)
+    }
+
+    fn delete_subspace_val(
+        &mut self,
+        height: BlockHeight,
+        key: &Key,
+    ) -> Result<i64> {
+        let mut batch = SledDBWriteBatch::default();
+        let prev_len =
+            self.batch_delete_subspace_val(&mut batch, height, key)?;
+        self.exec_batch(batch)?;
+        Ok(prev_len)
+    }
+
+    fn batch() -> Self::WriteBatch {
+        SledDBWriteBatch::default()
+    }
+
+    fn exec_batch(&mut self, batch: Self::WriteBatch) -> Result<()> {
+        self.db
+            .apply_batch(batch.0)
+            .map_err(|e| Error::DBError(e.to_string()))
+    }
+
+    fn batch_write_subspace_val(
+        &self,
+        batch: &mut Self::WriteBatch,
+        height: BlockHeight,
+        key: &Key,
+        value: impl AsRef<[u8]>,
+    ) -> Result<i64> {
+        let value = value.as_ref();
+        let subspace_key =
+            Key::parse("subspace").map_err(Error::KeyError)?.join(key);
+        let size_diff = match self.get(subspace_key.to_string())? {
+            Some(old_value) => {
+                let size_diff = value.len() as i64 - old_value.len() as i64;
+                // Persist the previous value
+                Self::batch_write_subspace_diff(
+                    batch,
+                    height,
+                    key,
+                    Some(&old_value),
+                    Some(value),
+                )?;
+                size_diff
+            }
+            None => {
+                Self::batch_write_subspace_diff(
+                    batch,
+                    height,
+                    key,
+                    None,
+                    Some(value),
+                )?;
+                value.len() as i64
+            }
+        };
+
+        // Write the new key-val
+        batch.put(subspace_key.to_string(), value);
+
+        Ok(size_diff)
+    }
+
+    fn batch_delete_subspace_val(
+        &self,
+        batch: &mut Self::WriteBatch,
+        height: BlockHeight,
+        key: &Key,
+    ) -> Result<i64> {
+        let subspace_key =
+            Key::parse("subspace").map_err(Error::KeyError)?.join(key);
+
+        // Check the length of previous value, if any
+        let prev_len = match self.get(subspace_key.to_string())? {
+            Some(prev_value) => {
+                let prev_len = prev_value.len() as i64;
+                // Persist the previous value
+                Self::batch_write_subspace_diff(
+                    batch,
+                    height,
+                    key,
+                    Some(&prev_value),
+                    None,
+                )?;
+                prev_len
+            }
+            None => 0,
+        };
+
+        // Delete the key-val
+        batch.delete(subspace_key.to_string());
+
+        Ok(prev_len)
+    }
+
+    fn prune_block(
+        &mut self,
+        height: BlockHeight,
+        prune_diffs: bool,
+    ) -> Result<()> {
+        let mut batch = SledDBWriteBatch::default();
+        let diffs_prefix = format!("{}/diffs/", height);
+        for kv in self.iter_with_prefix(&format!("{}/", height)) {
+            let (key, _value) = kv?;
+            if prune_diffs || !key.starts_with(&diffs_prefix) {
+                batch.delete(key);
+            }
+        }
+        self.exec_batch(batch)
+    }
+
+    fn read_schema_version(&self) -> Result<Option<u64>> {
+        match self.get("schema_version")? {
+            Some(bytes) => {
+                Ok(Some(types::decode(bytes).map_err(Error::CodingError)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn write_schema_version(&mut self, version: u64) -> Result<()> {
+        self.db
+            .insert("schema_version", types::encode(&version))
+            .map_err(|e| Error::DBError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+impl PersistentBackend for SledDB {
+    const BACKEND: StorageBackend = StorageBackend::Sled;
+
+    fn new_cache(size_bytes: u64) -> u64 {
+        size_bytes
+    }
+
+    fn open_primary_unchecked(
+        path: &Path,
+        cache: Option<&u64>,
+    ) -> Result<Self> {
+        open(path, cache)
+    }
+
+    fn open_secondary(
+        primary_path: &Path,
+        secondary_path: &Path,
+        cache: Option<&u64>,
+    ) -> Result<Self> {
+        open_secondary(primary_path, secondary_path, cache)
+    }
+
+    fn catch_up_with_primary(&self) -> Result<()> {
+        SledDB::catch_up_with_primary(self)
+    }
+
+    fn checkpoint(&self, path: &Path) -> Result<()> {
+        if path.exists() {
+            return Err(Error::DBError(format!(
+                "The checkpoint directory {} already exists",
+                path.display()
+            )));
+        }
+        let checkpoint =
+            sled::open(path).map_err(|e| Error::DBError(e.to_string()))?;
+        checkpoint.import(self.db.export());
+        checkpoint
+            .flush()
+            .map(|_| ())
+            .map_err(|e| Error::DBError(e.to_string()))
+    }
+
+    fn rollback(&mut self, height: BlockHeight) -> Result<()> {
+        SledDB::rollback(self, height)
+    }
+
+    fn split_column_families(&mut self) -> Result<()> {
+        // The state is kept in a single keyspace
+        Ok(())
+    }
+}
+
+impl<'iter> DBIter<'iter> for SledDB {
+    type PrefixIter = SledPrefixIterator;
+
+    fn iter_prefix(&'iter self, prefix: &Key) -> SledPrefixIterator {
+        self.iter_db_prefix("subspace/".to_owned(), prefix, false)
+    }
+
+    fn rev_iter_prefix(&'iter self, prefix: &Key) -> SledPrefixIterator {
+        self.iter_db_prefix("subspace/".to_owned(), prefix, true)
+    }
+
+    fn iter_prefix_from(
+        &'iter self,
+        prefix: &Key,
+        start: &Key,
+    ) -> SledPrefixIterator {
+        let db_prefix = "subspace/".to_owned();
+        let prefix = format!("{}{}", db_prefix, prefix).into_bytes();
+        let start = format!("{}{}", db_prefix, start).into_bytes();
+        let mut upper = prefix.clone();
+        // The prefix is never empty, as it has the subspace prefix
+        let last = upper.pop().expect("The prefix must not be empty");
+        upper.push(last + 1);
+        // An empty range if the start is not below the end of the prefix
+        let lower = std::cmp::min(std::cmp::max(prefix, start), upper.clone());
+        let iter = self.keyspace(&lower).range(lower..upper);
+        SledPrefixIterator {
+            iter: PrefixIterator { iter, db_prefix },
+            reverse: false,
+        }
+    }
+
+    fn iter_range(&'iter self, start: &Key, end: &Key) -> SledPrefixIterator {
+        self.iter_subspace_range(start, end, false)
+    }
+
+    fn rev_iter_range(
+        &'iter self,
+        start: &Key,
+        end: &Key,
+    ) -> SledPrefixIterator {
+        self.iter_subspace_range(start, end, true)
+    }
+
+    fn iter_old_diffs(
+        &'iter self,
+        height: BlockHeight,
+        prefix: &Key,
+    ) -> SledPrefixIterator {
+        let db_prefix = format!("{}/diffs/old/", height);
+        self.iter_db_prefix(db_prefix, prefix, false)
+    }
+
+    fn iter_new_diffs(
+        &'iter self,
+        height: BlockHeight,
+        prefix: &Key,
+    ) -> SledPrefixIterator {
+        let db_prefix = format!("{}/diffs/new/", height);
+        self.iter_db_prefix(db_prefix, prefix, false)
+    }
+}
+
+#[derive(Debug)]
+pub struct SledPrefixIterator {
+    iter: PrefixIterator<sled::Iter>,
+    /// Whether the keys are iterated in the reverse order
+    reverse: bool,
+}
+
+impl Iterator for SledPrefixIterator {
+    type Item = (String, Vec<u8>, u64);
+
+    /// Returns the next pair and the gas cost
+    fn next(&mut self) -> Option<(String, Vec<u8>, u64)> {
+        let next = if self.reverse {
+            self.iter.iter.next_back()
+        } else {
+            self.iter.iter.next()
+        };
+        match next {
+            Some(kv) => {
+                let (key, val) = kv.expect("Cannot read from the DB");
+                let key = String::from_utf8(key.to_vec())
+                    .expect("Cannot convert from bytes to key string");
+                match key.strip_prefix(&self.iter.db_prefix) {
+                    Some(k) => {
+                        let gas = k.len() + val.len();
+                        Some((k.to_owned(), val.to_vec(), gas as _))
+                    }
+                    None => self.next(),
+                }
+            }
+            None => None,
+        }
+    }
+}
+
+impl DBWriteBatch for SledDBWriteBatch {
+    fn put<K, V>(&mut self, key: K, value: V)
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        self.0.insert(key.as_ref(), value.as_ref())
+    }
+
+    fn delete<K: AsRef<[u8]>>(&mut self, key: K) {
+        self.0.remove(key.as_ref())
+    }
+}
+
+fn unknown_key_error(key: &str) -> Result<()> {
+    Err(Error::UnknownKey {
+        key: key.to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use anoma::ledger::storage::{MerkleTree, Sha256Hasher};
+    use anoma::types::address::EstablishedAddressGen;
+    use anoma::types::storage::{BlockHash, Epoch, Epochs};
+    use tempfile::tempdir;
+
+    use super::*;
+
+    /// Test that a block written can be loaded back from DB, also by a
+    /// secondary instance, which only reads the writes of the primary
+    /// instance after it catches up.
+    #[test]
+    fn test_load_state() {
+        let dir = tempdir().unwrap();
+        let mut db = open(dir.path(), None).unwrap();
+        let key = Key::parse("test").unwrap();
+        db.write_subspace_val(BlockHeight(1), &key, vec![1_u8, 1, 1, 1])
+            .unwrap();
+        write_empty_block(&mut db, BlockHeight(1));
+
+        let state = db
+            .read_last_block()
+            .expect("Should be able to read last block")
+            .expect("Block should have been written");
+        assert_eq!(state.height, BlockHeight(1));
+
+        let secondary_dir = tempdir().unwrap();
+        let secondary_path = secondary_dir.path().join("secondary");
+        let mut secondary =
+            open_secondary(dir.path(), &secondary_path, None).unwrap();
+        let state = secondary.read_last_block().unwrap().unwrap();
+        assert_eq!(state.height, BlockHeight(1));
+        assert_eq!(
+            secondary.read_subspace_val(&key).unwrap(),
+            Some(vec![1_u8, 1, 1, 1])
+        );
+
+        db.write_subspace_val(BlockHeight(2), &key, vec![2_u8])
+            .unwrap();
+        assert_eq!(
+            secondary.read_subspace_val(&key).unwrap(),
+            Some(vec![1_u8, 1, 1, 1])
+        );
+        write_empty_block(&mut db, BlockHeight(2));
+        secondary.catch_up_with_primary().unwrap();
+        let state = secondary.read_last_block().unwrap().unwrap();
+        assert_eq!(state.height, BlockHeight(2));
+        assert_eq!(
+            secondary.read_subspace_val(&key).unwrap(),
+            Some(vec![2_u8])
+        );
+
+        // The copy of the secondary instance is removed with it
+        drop(secondary);
+        assert!(!secondary_path.exists());
+
+        // The DB cannot be opened with another backend
+        drop(db);
+        assert!(
+            super::super::rocksdb::open(dir.path(), None).is_err(),
+            "A sled DB must not be opened by RocksDB"
+        );
+    }
+
+    /// Test that the values of the past heights are read from the diffs,
+    /// including the values of the keys that were deleted since then.
+    #[test]
+    fn test_read_with_height() {
+        let dir = tempdir().unwrap();
+        let mut db = open(dir.path(), None).unwrap();
+
+        let key1 = Key::parse("test/key1").unwrap();
+        let key2 = Key::parse("test/key2").unwrap();
+        let prefix = Key::parse("test").unwrap();
+
+        // Block 1 writes `key1`, block 2 updates it and writes `key2`, block 3
+        // deletes `key1`
+        db.write_subspace_val(BlockHeight(1), &key1, vec![1_u8])
+            .unwrap();
+        db.write_subspace_val(BlockHeight(2), &key1, vec![2_u8])
+            .unwrap();
+        db.write_subspace_val(BlockHeight(2), &key2, vec![2_u8])
+            .unwrap();
+        db.delete_subspace_val(BlockHeight(3), &key1).unwrap();
+        let last_height = BlockHeight(3);
+
+        let read = |key: &Key, height: u64| {
+            db.read_subspace_val_with_height(
+                key,
+                BlockHeight(height),
+                last_height,
+            )
+            .unwrap()
+        };
+        assert_eq!(read(&key1, 1), Some(vec![1_u8]));
+        assert_eq!(read(&key1, 2), Some(vec![2_u8]));
+        assert_eq!(read(&key1, 3), None);
+        assert_eq!(read(&key2, 1), None);
+        assert_eq!(read(&key2, 3), Some(vec![2_u8]));
+
+        let written: Vec<String> = db
+            .iter_new_diffs(BlockHeight(2), &prefix)
+            .map(|(key, _value, _gas)| key)
+            .collect();
+        assert_eq!(written, vec![key1.to_string(), key2.to_string()]);
+    }
+
+    /// Test that the data of a pruned block is removed without the data of
+    /// the heights that start with the same digits, and that the DB can be
+    /// rolled back to a previous block.
+    #[test]
+    fn test_prune_and_rollback() {
+        let dir = tempdir().unwrap();
+        let mut db = open(dir.path(), None).unwrap();
+
+        let key = Key::parse("key").unwrap();
+        for height in 1..=11 {
+            db.write_subspace_val(
+                BlockHeight(height),
+                &key,
+                vec![height as u8],
+            )
+            .unwrap();
+            write_empty_block(&mut db, BlockHeight(height));
+        }
+
+        db.prune_block(BlockHeight(1), false).unwrap();
+        assert_eq!(db.read_block_gas(BlockHeight(1)).unwrap(), None);
+        assert_eq!(db.iter_new_diffs(BlockHeight(1), &key).count(), 1);
+        db.prune_block(BlockHeight(1), true).unwrap();
+        assert_eq!(db.iter_new_diffs(BlockHeight(1), &key).count(), 0);
+        for height in [10, 11] {
+            assert_eq!(
+                db.read_block_gas(BlockHeight(height)).unwrap(),
+                Some(0)
+            );
+            assert_eq!(db.iter_new_diffs(BlockHeight(height), &key).count(), 1);
+        }
+
+        db.rollback(BlockHeight(9))
+            .expect("rollback should succeed");
+        let state = db.read_last_block().unwrap().unwrap();
+        assert_eq!(state.height, BlockHeight(9));
+        assert_eq!(db.read_subspace_val(&key).unwrap(), Some(vec![9_u8]));
+        assert!(db
+            .read_merkle_tree_stores(BlockHeight(10))
+            .unwrap()
+            .is_none());
+        assert_eq!(db.iter_new_diffs(BlockHeight(11), &key).count(), 0);
+    }
+
+    fn write_empty_block(db: &mut SledDB, height: BlockHeight) {
+        let merkle_tree = MerkleTree::<Sha256Hasher>::default();
+        let hash = BlockHash::default();
+        let pred_epochs = Epochs::default();
+        let address_gen = EstablishedAddressGen::new("whatever");
+        let tx_queue = TxQueue::default();
+        db.write_block(BlockStateWrite {
+            merkle_tree_stores: merkle_tree.stores(),
+            header: None,
+            block_request: None,
+            block_gas: 0,
+            hash: &hash,
+            height,
+            epoch: Epoch::default(),
+            pred_epochs: &pred_epochs,
+            next_epoch_min_start_height: BlockHeight::default(),
+            next_epoch_min_start_time: DateTimeUtc::now(),
+            address_gen: &address_gen,
+            tx_queue: &tx_queue,
+        })
+        .unwrap();
+    }
+}
@@ -7,22 +7,11 @@ use anoma::types::key::*;
 use anoma::types::time::DateTimeUtc;
 use borsh::BorshSerialize;
 use serde_json::json;
-#[cfg(not(feature = "ABCI"))]
-use tendermint::Genesis;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_config::net::Address as TendermintAddress;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_config::Error as TendermintError;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_config::TendermintConfig;
-#[cfg(feature = "ABCI")]
-use tendermint_config_abci::net::Address as TendermintAddress;
-#[cfg(feature = "ABCI")]
-use tendermint_config_abci::Error as TendermintError;
-#[cfg(feature = "ABCI")]
-use tendermint_config_abci::TendermintConfig;
-#[cfg(feature = "ABCI")]
-use tendermint_stable::Genesis;
+use tendermint_compat::tendermint::Genesis;
+use tendermint_compat::tendermint_config::net::Address as TendermintAddress;
+use tendermint_compat::tendermint_config::{
+    Error as TendermintError, TendermintConfig,
+};
 use thiserror::Error;
 use tokio::fs::{self, File, OpenOptions};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -374,6 +363,12 @@ async fn update_tendermint_config(
     // Bumped from the default `1_000_000`, because some WASMs can be
     // quite large
     config.rpc.max_body_bytes = 2_000_000;
+    // The origins are validated with the ledger config, so they can be
+    // deserialized into Tendermint's type
+    config.rpc.cors_allowed_origins = serde_json::from_value(
+        serde_json::Value::from(tendermint_config.rpc_cors_allowed_origins),
+    )
+    .expect("The RPC CORS origins should be valid");
 
     config.instrumentation.prometheus =
         tendermint_config.instrumentation_prometheus;
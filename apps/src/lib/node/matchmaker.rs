@@ -3,9 +3,12 @@ use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 
+use anoma::client::{CachingClient, QueryClient};
 use anoma::proto::Tx;
 use anoma::types::address::{self, Address};
+use anoma::types::chain::ChainId;
 use anoma::types::dylib;
 use anoma::types::intent::{IntentTransfers, MatchedExchanges};
 use anoma::types::key::*;
@@ -14,24 +17,22 @@ use anoma::types::transaction::{hash_tx, Fee, WrapperTx};
 use borsh::{BorshDeserialize, BorshSerialize};
 use libc::c_void;
 use libloading::Library;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_config::net;
-#[cfg(not(feature = "ABCI"))]
-use tendermint_config::net::Address as TendermintAddress;
-#[cfg(feature = "ABCI")]
-use tendermint_config_abci::net;
-#[cfg(feature = "ABCI")]
-use tendermint_config_abci::net::Address as TendermintAddress;
+use tendermint_compat::tendermint_config::net;
+use tendermint_compat::tendermint_config::net::Address as TendermintAddress;
+use tendermint_compat::tendermint_rpc::HttpClient;
 
 use super::gossip::rpc::matchmakers::{
     ClientDialer, ClientListener, MsgFromClient, MsgFromServer,
 };
-use crate::cli::args;
-use crate::client::rpc;
 use crate::client::tendermint_rpc_types::TxBroadcastData;
 use crate::client::tx::broadcast_tx;
 use crate::{cli, config, wasm_loader};
 
+/// The maximum age of the cached epoch of the last committed block. The epoch
+/// is read at most once per block, so that the txs crafted from a burst of
+/// matches don't query it again.
+const EPOCH_CACHE_MAX_AGE: Duration = Duration::from_secs(1);
+
 /// Run a matchmaker
 #[tokio::main]
 pub async fn run(
@@ -41,6 +42,7 @@ pub async fn run(
     }: config::Matchmaker,
     intent_gossiper_addr: SocketAddr,
     ledger_addr: TendermintAddress,
+    chain_id: ChainId,
     tx_signing_key: Rc<common::SecretKey>,
     tx_source_address: Address,
     wasm_dir: impl AsRef<Path>,
@@ -59,6 +61,7 @@ pub async fn run(
         matchmaker_path,
         tx_code_path,
         ledger_addr,
+        chain_id,
         tx_signing_key,
         tx_source_address,
         wasm_dir,
@@ -99,6 +102,10 @@ pub struct ResultHandler {
     result_recv: tokio::sync::mpsc::UnboundedReceiver<AddIntentResult>,
     /// The ledger address to send any crafted transaction to
     ledger_address: net::Address,
+    /// The client of the ledger's queries, which caches the epoch
+    query_client: CachingClient<HttpClient>,
+    /// The ID of the chain for which the transactions are crafted
+    chain_id: ChainId,
     /// The code of the transaction that is going to be send to a ledger.
     tx_code: Vec<u8>,
     /// A source address for transactions created from intents.
@@ -132,6 +139,7 @@ impl Runner {
         matchmaker_path: PathBuf,
         tx_code_path: PathBuf,
         ledger_address: TendermintAddress,
+        chain_id: ChainId,
         tx_signing_key: Rc<common::SecretKey>,
         tx_source_address: Address,
         wasm_dir: impl AsRef<Path>,
@@ -144,6 +152,11 @@ impl Runner {
         let (listener, dialer) = ClientListener::new_pair(intent_gossiper_addr);
 
         let tx_code = wasm_loader::read_wasm(&wasm_dir, tx_code_path);
+        let query_client = CachingClient::new(
+            HttpClient::new(ledger_address.clone()).unwrap(),
+            1,
+        )
+        .with_max_age(EPOCH_CACHE_MAX_AGE);
 
         (
             Self {
@@ -155,6 +168,8 @@ impl Runner {
                 dialer,
                 result_recv,
                 ledger_address,
+                query_client,
+                chain_id,
                 tx_code,
                 tx_source_address,
                 tx_signing_key,
@@ -314,11 +329,17 @@ impl ResultHandler {
             source: self.tx_source_address.clone(),
         };
         let tx_data = intent_transfers.try_to_vec().unwrap();
+        let epoch = match self.query_client.query_epoch().await {
+            Ok(epoch) => epoch,
+            Err(err) => {
+                tracing::error!(
+                    "Matchmaker error in querying the epoch of the ledger: {}",
+                    err
+                );
+                return;
+            }
+        };
         let to_broadcast = {
-            let epoch = rpc::query_epoch(args::Query {
-                ledger_address: self.ledger_address.clone(),
-            })
-            .await;
             let tx = WrapperTx::new(
                 Fee {
                     amount: 0.into(),
@@ -330,6 +351,7 @@ impl ResultHandler {
                 Tx::new(tx_code, Some(tx_data)).sign(&self.tx_signing_key),
                 // TODO: Actually use the fetched encryption key
                 Default::default(),
+                self.chain_id.clone(),
             );
             let wrapper_hash = if !cfg!(feature = "ABCI") {
                 hash_tx(&tx.try_to_vec().unwrap()).to_string()
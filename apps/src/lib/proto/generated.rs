@@ -1 +1,3 @@
+#[cfg(feature = "grpc")]
+pub mod ledger;
 pub mod services;
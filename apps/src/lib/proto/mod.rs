@@ -1,5 +1,7 @@
 mod generated;
 mod types;
 
+#[cfg(feature = "grpc")]
+pub use generated::ledger;
 pub use generated::services;
 pub use types::{IntentMessage, RpcMessage, SubscribeTopicMessage};
@@ -24,6 +24,14 @@ pub enum Error {
     WasmNotFound(String),
     #[error("Error while downloading {0}: {1}")]
     ServerError(String, String),
+    #[error("Can't find checksums at {0}")]
+    ChecksumsNotFound(String),
+    #[error("Can't read checksums from {0}")]
+    ChecksumsRead(String),
+    #[error("File {0} not found.")]
+    WasmFileNotFound(String),
+    #[error("Could not read file {0}.")]
+    WasmFileRead(String),
 }
 
 /// A hash map where keys are simple file names and values their full file name
@@ -65,6 +73,39 @@ impl Checksums {
         Self::read_checksums_file(checksums_path)
     }
 
+    /// Try to read WASM checksums from "checksums.json" in the given
+    /// directory
+    pub fn try_read_checksums(
+        wasm_directory: impl AsRef<Path>,
+    ) -> Result<Self, Error> {
+        let checksums_path =
+            wasm_directory.as_ref().join(DEFAULT_WASM_CHECKSUMS_FILE);
+        match fs::File::open(&checksums_path) {
+            Ok(file) => serde_json::from_reader(file).map_err(|_| {
+                Error::ChecksumsRead(
+                    checksums_path.to_string_lossy().into_owned(),
+                )
+            }),
+            Err(_) => Err(Error::ChecksumsNotFound(
+                checksums_path.to_string_lossy().into_owned(),
+            )),
+        }
+    }
+
+    /// Find the simple file name of the WASM with the given code, if the code
+    /// matches any of the checksums
+    pub fn find_name(&self, code: &[u8]) -> Option<&str> {
+        let hash = code_hash(code);
+        self.0.iter().find_map(|(name, full_name)| {
+            let derived_name = format!(
+                "{}.{}.wasm",
+                &name.split('.').collect::<Vec<&str>>()[0],
+                hash
+            );
+            (*full_name == derived_name).then(|| name.as_str())
+        })
+    }
+
     pub async fn read_checksums_async(
         wasm_directory: impl AsRef<Path>,
     ) -> Self {
@@ -142,9 +183,7 @@ pub async fn pre_fetch_wasm(wasm_directory: impl AsRef<Path>) {
                 // if the file exist, first check the hash. If not matching
                 // download it again.
                 Ok(bytes) => {
-                    let mut hasher = Sha256::new();
-                    hasher.update(bytes);
-                    let result = hex::encode(hasher.finalize());
+                    let result = code_hash(&bytes);
                     let derived_name = format!(
                         "{}.{}.wasm",
                         &name.split('.').collect::<Vec<&str>>()[0],
@@ -257,70 +296,62 @@ pub async fn pre_fetch_wasm(wasm_directory: impl AsRef<Path>) {
     .await;
 }
 
+/// Read the WASM file at the given path. A file name that has an entry in the
+/// checksums of the given directory is read from the hashed file name in it.
+/// Exits the process if the file cannot be read.
 pub fn read_wasm(
     wasm_directory: impl AsRef<Path>,
     file_path: impl AsRef<Path>,
 ) -> Vec<u8> {
+    try_read_wasm(wasm_directory, file_path).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        safe_exit(1)
+    })
+}
+
+/// Read the WASM file at the given path, like [`read_wasm`], but return an
+/// error if it cannot be read.
+pub fn try_read_wasm(
+    wasm_directory: impl AsRef<Path>,
+    file_path: impl AsRef<Path>,
+) -> Result<Vec<u8>, Error> {
     // load json with wasm hashes
-    let checksums = Checksums::read_checksums(&wasm_directory);
+    let checksums = Checksums::try_read_checksums(&wasm_directory)?;
 
-    if let Some(os_name) = file_path.as_ref().file_name() {
-        if let Some(name) = os_name.to_str() {
-            match checksums.0.get(name) {
-                Some(wasm_filename) => {
-                    let wasm_path = wasm_directory.as_ref().join(wasm_filename);
-                    match fs::read(&wasm_path) {
-                        Ok(bytes) => {
-                            return bytes;
-                        }
-                        Err(_) => {
-                            eprintln!(
-                                "File {} not found. ",
-                                wasm_path.to_string_lossy()
-                            );
-                            safe_exit(1);
-                        }
-                    }
-                }
-                None => {
-                    if !file_path.as_ref().is_absolute() {
-                        match fs::read(
-                            wasm_directory.as_ref().join(file_path.as_ref()),
-                        ) {
-                            Ok(bytes) => {
-                                return bytes;
-                            }
-                            Err(_) => {
-                                eprintln!(
-                                    "Could not read file {}. ",
-                                    file_path.as_ref().to_string_lossy()
-                                );
-                                safe_exit(1);
-                            }
-                        }
-                    } else {
-                        match fs::read(file_path.as_ref()) {
-                            Ok(bytes) => {
-                                return bytes;
-                            }
-                            Err(_) => {
-                                eprintln!(
-                                    "Could not read file {}. ",
-                                    file_path.as_ref().to_string_lossy()
-                                );
-                                safe_exit(1);
-                            }
-                        }
-                    }
-                }
-            }
+    let name = file_path
+        .as_ref()
+        .file_name()
+        .and_then(|name| name.to_str());
+    let path = match name.and_then(|name| checksums.0.get(name)) {
+        Some(wasm_filename) => {
+            let wasm_path = wasm_directory.as_ref().join(wasm_filename);
+            return fs::read(&wasm_path).map_err(|_| {
+                Error::WasmFileNotFound(
+                    wasm_path.to_string_lossy().into_owned(),
+                )
+            });
         }
-    }
-    eprintln!(
-        "File  {} does not exist.",
-        file_path.as_ref().to_string_lossy()
-    );
-    safe_exit(1);
+        None if name.is_none() => {
+            return Err(Error::WasmFileNotFound(
+                file_path.as_ref().to_string_lossy().into_owned(),
+            ));
+        }
+        None if !file_path.as_ref().is_absolute() => {
+            wasm_directory.as_ref().join(file_path.as_ref())
+        }
+        None => file_path.as_ref().to_path_buf(),
+    };
+    fs::read(path).map_err(|_| {
+        Error::WasmFileRead(file_path.as_ref().to_string_lossy().into_owned())
+    })
+}
+
+/// The hex encoded SHA256 hash of the given WASM code, which is used in the
+/// file names of the pre-built WASMs
+pub fn code_hash(code: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(code);
+    hex::encode(hasher.finalize())
 }
 
 async fn download_wasm(url: String) -> Result<Vec<u8>, Error> {
@@ -76,7 +76,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let token_transfer_schema = token::Transfer::schema_container();
     let update_vp_schema = transaction::UpdateVp::schema_container();
     let pos_bond_schema = pos::Bond::schema_container();
+    let pos_batch_bond_schema = pos::BatchBond::schema_container();
     let pos_withdraw_schema = pos::Withdraw::schema_container();
+    let pos_redelegation_schema = pos::Redelegation::schema_container();
+    let pos_consensus_key_change_schema =
+        pos::ConsensusKeyChange::schema_container();
+    let pos_commission_rate_change_schema =
+        pos::CommissionRateChange::schema_container();
+    let pos_validator_metadata_change_schema =
+        pos::ValidatorMetadataChange::schema_container();
+    let pos_unjail_schema = pos::Unjail::schema_container();
     let wrapper_tx_schema = transaction::WrapperTx::schema_container();
     // TODO derive BorshSchema after <https://github.com/near/borsh-rs/issues/82>
     // let tx_result_schema = transaction::TxResult::schema_container();
@@ -102,7 +111,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     definitions.extend(token_transfer_schema.definitions);
     definitions.extend(update_vp_schema.definitions);
     definitions.extend(pos_bond_schema.definitions);
+    definitions.extend(pos_batch_bond_schema.definitions);
     definitions.extend(pos_withdraw_schema.definitions);
+    definitions.extend(pos_redelegation_schema.definitions);
+    definitions.extend(pos_consensus_key_change_schema.definitions);
+    definitions.extend(pos_commission_rate_change_schema.definitions);
+    definitions.extend(pos_validator_metadata_change_schema.definitions);
+    definitions.extend(pos_unjail_schema.definitions);
     definitions.extend(wrapper_tx_schema.definitions);
     // definitions.extend(tx_result_schema.definitions);
     definitions.extend(tx_type_schema.definitions);
@@ -202,6 +217,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         definition_to_table(pos_bond_schema.declaration, pos_bond_definition).with_rust_doc_link("https://dev.anoma.net/master/rustdoc/anoma/types/transaction/pos/struct.Bond.html");
     tables.push(pos_bond_table);
 
+    let pos_batch_bond_definition = definitions
+        .remove(&pos_batch_bond_schema.declaration)
+        .unwrap();
+    let pos_batch_bond_table = definition_to_table(
+        pos_batch_bond_schema.declaration,
+        pos_batch_bond_definition,
+    ).with_rust_doc_link("https://dev.anoma.net/master/rustdoc/anoma/types/transaction/pos/struct.BatchBond.html");
+    tables.push(pos_batch_bond_table);
+
     let pos_withdraw_definition = definitions
         .remove(&pos_withdraw_schema.declaration)
         .unwrap();
@@ -211,6 +235,50 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     ).with_rust_doc_link("https://dev.anoma.net/master/rustdoc/anoma/types/transaction/pos/struct.Withdraw.html");
     tables.push(pos_withdraw_table);
 
+    let pos_redelegation_definition = definitions
+        .remove(&pos_redelegation_schema.declaration)
+        .unwrap();
+    let pos_redelegation_table = definition_to_table(
+        pos_redelegation_schema.declaration,
+        pos_redelegation_definition,
+    ).with_rust_doc_link("https://dev.anoma.net/master/rustdoc/anoma/types/transaction/pos/struct.Redelegation.html");
+    tables.push(pos_redelegation_table);
+
+    let pos_consensus_key_change_definition = definitions
+        .remove(&pos_consensus_key_change_schema.declaration)
+        .unwrap();
+    let pos_consensus_key_change_table = definition_to_table(
+        pos_consensus_key_change_schema.declaration,
+        pos_consensus_key_change_definition,
+    ).with_rust_doc_link("https://dev.anoma.net/master/rustdoc/anoma/types/transaction/pos/struct.ConsensusKeyChange.html");
+    tables.push(pos_consensus_key_change_table);
+
+    let pos_commission_rate_change_definition = definitions
+        .remove(&pos_commission_rate_change_schema.declaration)
+        .unwrap();
+    let pos_commission_rate_change_table = definition_to_table(
+        pos_commission_rate_change_schema.declaration,
+        pos_commission_rate_change_definition,
+    ).with_rust_doc_link("https://dev.anoma.net/master/rustdoc/anoma/types/transaction/pos/struct.CommissionRateChange.html");
+    tables.push(pos_commission_rate_change_table);
+
+    let pos_validator_metadata_change_definition = definitions
+        .remove(&pos_validator_metadata_change_schema.declaration)
+        .unwrap();
+    let pos_validator_metadata_change_table = definition_to_table(
+        pos_validator_metadata_change_schema.declaration,
+        pos_validator_metadata_change_definition,
+    ).with_rust_doc_link("https://dev.anoma.net/master/rustdoc/anoma/types/transaction/pos/struct.ValidatorMetadataChange.html");
+    tables.push(pos_validator_metadata_change_table);
+
+    let pos_unjail_definition =
+        definitions.remove(&pos_unjail_schema.declaration).unwrap();
+    let pos_unjail_table = definition_to_table(
+        pos_unjail_schema.declaration,
+        pos_unjail_definition,
+    ).with_rust_doc_link("https://dev.anoma.net/master/rustdoc/anoma/types/transaction/pos/struct.Unjail.html");
+    tables.push(pos_unjail_table);
+
     let wrapper_tx_definition =
         definitions.remove(&wrapper_tx_schema.declaration).unwrap();
     let wrapper_tx_table = definition_to_table(
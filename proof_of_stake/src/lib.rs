@@ -33,10 +33,13 @@ use epoched::{
 use parameters::PosParams;
 use thiserror::Error;
 use types::{
-    ActiveValidator, Bonds, Epoch, GenesisValidator, Slash, SlashType, Slashes,
-    TotalVotingPowers, Unbond, Unbonds, ValidatorConsensusKeys, ValidatorSet,
-    ValidatorSetUpdate, ValidatorSets, ValidatorState, ValidatorStates,
-    ValidatorTotalDeltas, ValidatorVotingPowers, VotingPower, VotingPowerDelta,
+    ActiveValidator, BasisPoints, Bonds, CommissionRates, Epoch, EpochRewards,
+    GenesisValidator, MetadataError, Redelegation, Redelegations, Slash,
+    SlashType, Slashes, TotalVotingPowers, Unbond, Unbonds,
+    ValidatorConsensusKeys, ValidatorLiveness, ValidatorMetadata,
+    ValidatorRewards, ValidatorSet, ValidatorSetSnapshot, ValidatorSetUpdate,
+    ValidatorSets, ValidatorState, ValidatorStates, ValidatorTotalDeltas,
+    ValidatorVotingPowers, VotingPower, VotingPowerDelta,
 };
 
 use crate::btree_set::BTreeSetShims;
@@ -90,6 +93,7 @@ pub trait PosReadOnly {
     /// Cryptographic public key type
     type PublicKey: Debug
         + Clone
+        + PartialEq
         + BorshDeserialize
         + BorshSerialize
         + BorshSchema;
@@ -142,6 +146,36 @@ pub trait PosReadOnly {
         &self,
         key: &BondId<Self::Address>,
     ) -> Option<Unbonds<Self::TokenAmount>>;
+    /// Read PoS redelegations from a validator.
+    fn read_validator_outgoing_redelegations(
+        &self,
+        key: &Self::Address,
+    ) -> Redelegations<Self::Address, Self::TokenAmount>;
+    /// Read PoS redelegations to a validator.
+    fn read_validator_incoming_redelegations(
+        &self,
+        key: &Self::Address,
+    ) -> Redelegations<Self::Address, Self::TokenAmount>;
+    /// Read PoS validator's commission rate.
+    fn read_validator_commission_rate(
+        &self,
+        key: &Self::Address,
+    ) -> Option<CommissionRates>;
+    /// Read PoS validator's maximum change of the commission rate per epoch.
+    fn read_validator_max_commission_rate_change(
+        &self,
+        key: &Self::Address,
+    ) -> Option<BasisPoints>;
+    /// Read PoS rewards of validator's bonds.
+    fn read_validator_rewards(
+        &self,
+        key: &Self::Address,
+    ) -> ValidatorRewards<Self::TokenAmount>;
+    /// Read PoS epoch before which the rewards of a bond have been claimed.
+    fn read_rewards_claimed_epoch(
+        &self,
+        key: &BondId<Self::Address>,
+    ) -> Option<Epoch>;
     /// Read PoS validator set (active and inactive).
     fn read_validator_set(&self) -> ValidatorSets<Self::Address>;
     /// Read PoS total voting power of all validators (active and inactive).
@@ -200,6 +234,42 @@ pub trait PosActions: PosReadOnly {
         key: &BondId<Self::Address>,
         value: Unbonds<Self::TokenAmount>,
     );
+    /// Write PoS redelegations from a validator.
+    fn write_validator_outgoing_redelegations(
+        &mut self,
+        key: &Self::Address,
+        value: Redelegations<Self::Address, Self::TokenAmount>,
+    );
+    /// Write PoS redelegations to a validator.
+    fn write_validator_incoming_redelegations(
+        &mut self,
+        key: &Self::Address,
+        value: Redelegations<Self::Address, Self::TokenAmount>,
+    );
+    /// Write PoS validator's commission rate.
+    fn write_validator_commission_rate(
+        &mut self,
+        key: &Self::Address,
+        value: CommissionRates,
+    );
+    /// Write PoS validator's maximum change of the commission rate per epoch.
+    fn write_validator_max_commission_rate_change(
+        &mut self,
+        key: &Self::Address,
+        value: BasisPoints,
+    );
+    /// Write PoS epoch before which the rewards of a bond have been claimed.
+    fn write_rewards_claimed_epoch(
+        &mut self,
+        key: &BondId<Self::Address>,
+        value: Epoch,
+    );
+    /// Write PoS validator's display metadata.
+    fn write_validator_metadata(
+        &mut self,
+        key: &Self::Address,
+        value: ValidatorMetadata,
+    );
     /// Write PoS validator set (active and inactive).
     fn write_validator_set(&mut self, value: ValidatorSets<Self::Address>);
     /// Write PoS total voting power of all validators (active and inactive).
@@ -226,6 +296,8 @@ pub trait PosActions: PosReadOnly {
         address: &Self::Address,
         staking_reward_address: &Self::Address,
         consensus_key: &Self::PublicKey,
+        commission_rate: BasisPoints,
+        max_commission_rate_change: BasisPoints,
         current_epoch: impl Into<Epoch>,
     ) -> Result<(), BecomeValidatorError<Self::Address>> {
         let current_epoch = current_epoch.into();
@@ -243,6 +315,16 @@ pub trait PosActions: PosReadOnly {
                 ),
             );
         }
+        if commission_rate > BasisPoints::MAX_RATE {
+            return Err(BecomeValidatorError::InvalidCommissionRate(
+                commission_rate,
+            ));
+        }
+        if max_commission_rate_change > BasisPoints::MAX_RATE {
+            return Err(BecomeValidatorError::InvalidMaxCommissionRateChange(
+                max_commission_rate_change,
+            ));
+        }
         let BecomeValidatorData {
             consensus_key,
             state,
@@ -265,6 +347,14 @@ pub trait PosActions: PosReadOnly {
         self.write_validator_address_raw_hash(address);
         self.write_validator_total_deltas(address, total_deltas);
         self.write_validator_voting_power(address, voting_power);
+        self.write_validator_commission_rate(
+            address,
+            CommissionRates::init(commission_rate, current_epoch, &params),
+        );
+        self.write_validator_max_commission_rate_change(
+            address,
+            max_commission_rate_change,
+        );
         Ok(())
     }
 
@@ -274,6 +364,155 @@ pub trait PosActions: PosReadOnly {
         self.read_validator_state(address).is_some()
     }
 
+    /// Change the consensus key of a validator. The new key is used for
+    /// signing the blocks from the pipeline epoch, in which the validator's
+    /// old key is replaced with the new one in the Tendermint validator set.
+    fn change_consensus_key(
+        &mut self,
+        validator: &Self::Address,
+        consensus_key: &Self::PublicKey,
+        current_epoch: impl Into<Epoch>,
+    ) -> Result<(), ConsensusKeyChangeError<Self::Address>> {
+        let current_epoch = current_epoch.into();
+        let params = self.read_pos_params();
+        let mut consensus_keys = self
+            .read_validator_consensus_key(validator)
+            .ok_or_else(|| {
+            ConsensusKeyChangeError::NotAValidator(validator.clone())
+        })?;
+        // A key may only be used by one validator, including the validator's
+        // own keys that are still in use until the pipeline epoch
+        let pipeline_offset = DynEpochOffset::PipelineLen.value(&params);
+        let validator_set = self.read_validator_set();
+        let validators: BTreeSet<&Self::Address> =
+            Epoch::iter_range(current_epoch, pipeline_offset + 1)
+                .filter_map(|epoch| validator_set.get(epoch))
+                .flat_map(|validators| {
+                    validators.active.iter().chain(validators.inactive.iter())
+                })
+                .map(|validator| &validator.address)
+                .chain(std::iter::once(validator))
+                .collect();
+        for address in validators {
+            let keys = match self.read_validator_consensus_key(address) {
+                Some(keys) => keys,
+                None => continue,
+            };
+            let is_used = Epoch::iter_range(current_epoch, pipeline_offset + 1)
+                .any(|epoch| keys.get(epoch) == Some(consensus_key));
+            if is_used {
+                return Err(ConsensusKeyChangeError::KeyAlreadyUsed(
+                    address.clone(),
+                ));
+            }
+        }
+        consensus_keys.set(consensus_key.clone(), current_epoch, &params);
+        self.write_validator_consensus_key(validator, consensus_keys);
+        Ok(())
+    }
+
+    /// Change the commission rate of a validator. The new rate is used from
+    /// the pipeline epoch. It may differ from the rate in the epoch before
+    /// the pipeline epoch at most by the validator's maximum change of the
+    /// commission rate.
+    fn change_commission_rate(
+        &mut self,
+        validator: &Self::Address,
+        rate: BasisPoints,
+        current_epoch: impl Into<Epoch>,
+    ) -> Result<(), CommissionRateChangeError<Self::Address>> {
+        let current_epoch = current_epoch.into();
+        if rate > BasisPoints::MAX_RATE {
+            return Err(CommissionRateChangeError::InvalidRate(rate));
+        }
+        let params = self.read_pos_params();
+        let mut rates =
+            self.read_validator_commission_rate(validator).ok_or_else(
+                || CommissionRateChangeError::NotAValidator(validator.clone()),
+            )?;
+        let max_change = self
+            .read_validator_max_commission_rate_change(validator)
+            .ok_or_else(|| {
+                CommissionRateChangeError::NotAValidator(validator.clone())
+            })?;
+        if let Some(prev_rate) =
+            prev_commission_rate(&params, &rates, current_epoch)
+        {
+            let change = rate.abs_diff(prev_rate);
+            if change > max_change {
+                return Err(CommissionRateChangeError::RateChangeTooLarge {
+                    change,
+                    max_change,
+                });
+            }
+        }
+        rates.set(rate, current_epoch, &params);
+        self.write_validator_commission_rate(validator, rates);
+        Ok(())
+    }
+
+    /// Set the display metadata of a validator, which replaces its previous
+    /// metadata.
+    fn set_validator_metadata(
+        &mut self,
+        validator: &Self::Address,
+        metadata: ValidatorMetadata,
+    ) -> Result<(), ValidatorMetadataError<Self::Address>> {
+        if !self.is_validator(validator) {
+            return Err(ValidatorMetadataError::NotAValidator(
+                validator.clone(),
+            ));
+        }
+        metadata
+            .validate()
+            .map_err(ValidatorMetadataError::InvalidMetadata)?;
+        self.write_validator_metadata(validator, metadata);
+        Ok(())
+    }
+
+    /// Unjail a validator, which becomes a candidate and is inserted into the
+    /// validator set from the pipeline epoch. The validator may be unjailed
+    /// once `unjail_cooldown_len` epochs passed from the epoch in which it was
+    /// jailed.
+    fn unjail_validator(
+        &mut self,
+        validator: &Self::Address,
+        current_epoch: impl Into<Epoch>,
+    ) -> Result<(), UnjailError<Self::Address>> {
+        let current_epoch = current_epoch.into();
+        let params = self.read_pos_params();
+        let mut state = self
+            .read_validator_state(validator)
+            .ok_or_else(|| UnjailError::NotAValidator(validator.clone()))?;
+        let pipeline_epoch = current_epoch + params.pipeline_len;
+        let jailed_epoch = match state.get(pipeline_epoch) {
+            Some(ValidatorState::Jailed { epoch }) => *epoch,
+            _ => return Err(UnjailError::NotJailed(validator.clone())),
+        };
+        let unjail_epoch = jailed_epoch + params.unjail_cooldown_len;
+        if current_epoch < unjail_epoch {
+            return Err(UnjailError::CooldownNotPassed {
+                validator: validator.clone(),
+                unjail_epoch,
+            });
+        }
+        let total_deltas = self
+            .read_validator_total_deltas(validator)
+            .ok_or_else(|| UnjailError::NotAValidator(validator.clone()))?;
+        state.set(ValidatorState::Candidate, current_epoch, &params);
+        let mut validator_set = self.read_validator_set();
+        insert_into_validator_set(
+            &params,
+            validator,
+            &mut validator_set,
+            &total_deltas,
+            current_epoch,
+        );
+        self.write_validator_state(validator, state);
+        self.write_validator_set(validator_set);
+        Ok(())
+    }
+
     /// Self-bond tokens to a validator when `source` is `None` or equal to
     /// the `validator` address, or delegate tokens from the `source` to the
     /// `validator`.
@@ -360,6 +599,34 @@ pub trait PosActions: PosReadOnly {
         };
         let mut bond =
             self.read_bond(&bond_id).ok_or(UnbondError::NoBondFound)?;
+        // The rewards are claimed before the bond is changed, because they are
+        // computed from the bond's deltas
+        self.withdraw_rewards(&bond_id, &bond, current_epoch);
+        // The tokens redelegated to the validator that are still slashable
+        // for the validator from which they were redelegated cannot be
+        // unbonded yet
+        let slashable = slashable_redelegated_amount(
+            &params,
+            &self.read_validator_incoming_redelegations(validator),
+            source,
+            current_epoch,
+        );
+        if slashable != Self::TokenAmount::default() {
+            let bonded = bond
+                .get_at_offset(
+                    current_epoch,
+                    DynEpochOffset::PipelineLen,
+                    &params,
+                )
+                .unwrap_or_default()
+                .sum();
+            if bonded < amount + slashable {
+                return Err(UnbondError::SlashableRedelegation(
+                    amount,
+                    unslashable_amount(bonded, slashable),
+                ));
+            }
+        }
         let unbond = self.read_unbond(&bond_id);
         let mut validator_total_deltas =
             self.read_validator_total_deltas(validator).ok_or_else(|| {
@@ -410,6 +677,152 @@ pub trait PosActions: PosReadOnly {
         Ok(())
     }
 
+    /// Redelegate tokens delegated from the `source` to the `src_validator`
+    /// to the `dest_validator`, without unbonding them. The tokens leave the
+    /// stake of the `src_validator` and join the stake of the
+    /// `dest_validator` at the pipeline offset. Until the end of the
+    /// unbonding period, they remain slashable for the infractions of the
+    /// `src_validator`, so they cannot be redelegated again or unbonded.
+    fn redelegate_tokens(
+        &mut self,
+        source: &Self::Address,
+        src_validator: &Self::Address,
+        dest_validator: &Self::Address,
+        amount: Self::TokenAmount,
+        current_epoch: impl Into<Epoch>,
+    ) -> Result<(), RedelegationError<Self::Address, Self::TokenAmount>> {
+        let current_epoch = current_epoch.into();
+        if self.is_validator(source) {
+            return Err(RedelegationError::SourceMustNotBeAValidator(
+                source.clone(),
+            ));
+        }
+        if src_validator == dest_validator {
+            return Err(RedelegationError::SameValidator(
+                src_validator.clone(),
+            ));
+        }
+        let params = self.read_pos_params();
+        let src_bond_id = BondId {
+            source: source.clone(),
+            validator: src_validator.clone(),
+        };
+        let dest_bond_id = BondId {
+            source: source.clone(),
+            validator: dest_validator.clone(),
+        };
+        let mut src_bond = self
+            .read_bond(&src_bond_id)
+            .ok_or(RedelegationError::NoBondFound)?;
+        // The rewards are claimed before the bond is changed, because they are
+        // computed from the bond's deltas
+        self.withdraw_rewards(&src_bond_id, &src_bond, current_epoch);
+        // The tokens redelegated to the source validator that are still
+        // slashable cannot be redelegated again
+        let slashable = slashable_redelegated_amount(
+            &params,
+            &self.read_validator_incoming_redelegations(src_validator),
+            source,
+            current_epoch,
+        );
+        let mut src_total_deltas = self
+            .read_validator_total_deltas(src_validator)
+            .ok_or_else(|| {
+                RedelegationError::ValidatorHasNoBonds(src_validator.clone())
+            })?;
+        let mut src_voting_power = self
+            .read_validator_voting_power(src_validator)
+            .ok_or_else(|| {
+                RedelegationError::ValidatorHasNoVotingPower(
+                    src_validator.clone(),
+                )
+            })?;
+        let slashes = self.read_validator_slashes(src_validator);
+        let mut total_voting_power = self.read_total_voting_power();
+        let mut validator_set = self.read_validator_set();
+
+        let redelegated = redelegate_tokens(
+            &params,
+            &src_bond_id,
+            &mut src_bond,
+            amount,
+            slashable,
+            slashes,
+            &mut src_total_deltas,
+            &mut src_voting_power,
+            &mut total_voting_power,
+            &mut validator_set,
+            current_epoch,
+        )?;
+        let BondData {
+            bond: dest_bond,
+            validator_total_deltas: dest_total_deltas,
+            validator_voting_power: dest_voting_power,
+        } = bond_tokens(
+            &params,
+            self.read_validator_state(dest_validator),
+            &dest_bond_id,
+            self.read_bond(&dest_bond_id),
+            redelegated,
+            self.read_validator_total_deltas(dest_validator),
+            self.read_validator_voting_power(dest_validator),
+            &mut total_voting_power,
+            &mut validator_set,
+            current_epoch,
+        )
+        .map_err(RedelegationError::Bond)?;
+
+        let total_bonds = src_bond.get_at_offset(
+            current_epoch,
+            DynEpochOffset::PipelineLen,
+            &params,
+        );
+        match total_bonds {
+            Some(total_bonds) if total_bonds.sum() != 0.into() => {
+                self.write_bond(&src_bond_id, src_bond);
+            }
+            _ => {
+                // If the bond is left empty, delete it
+                self.delete_bond(&src_bond_id)
+            }
+        }
+        self.write_bond(&dest_bond_id, dest_bond);
+        self.write_validator_total_deltas(src_validator, src_total_deltas);
+        self.write_validator_voting_power(src_validator, src_voting_power);
+        self.write_validator_total_deltas(dest_validator, dest_total_deltas);
+        self.write_validator_voting_power(dest_validator, dest_voting_power);
+        self.write_total_voting_power(total_voting_power);
+        self.write_validator_set(validator_set);
+
+        // Record the redelegation for the slashes of the source validator,
+        // and drop the records that are no longer slashable
+        let redelegation = Redelegation {
+            source: source.clone(),
+            src_validator: src_validator.clone(),
+            dest_validator: dest_validator.clone(),
+            epoch: current_epoch,
+            amount: redelegated,
+        };
+        let mut src_outgoing =
+            self.read_validator_outgoing_redelegations(src_validator);
+        src_outgoing.retain(|r| r.is_slashable(&params, current_epoch));
+        src_outgoing.push(redelegation.clone());
+        self.write_validator_outgoing_redelegations(
+            src_validator,
+            src_outgoing,
+        );
+        let mut dest_incoming =
+            self.read_validator_incoming_redelegations(dest_validator);
+        dest_incoming.retain(|r| r.is_slashable(&params, current_epoch));
+        dest_incoming.push(redelegation);
+        self.write_validator_incoming_redelegations(
+            dest_validator,
+            dest_incoming,
+        );
+
+        Ok(())
+    }
+
     /// Withdraw unbonded tokens from a self-bond to a validator when `source`
     /// is `None` or equal to the `validator` address, or withdraw unbonded
     /// tokens delegated to the `validator` to the `source`.
@@ -467,6 +880,63 @@ pub trait PosActions: PosReadOnly {
 
         Ok(slashed)
     }
+
+    /// Claim the rewards of a self-bond to a validator when `source` is `None`
+    /// or equal to the `validator` address, or of the tokens delegated from
+    /// the `source` to the `validator`. The rewards accrued before the current
+    /// epoch are transferred to the source. Returns the claimed amount.
+    fn claim_rewards(
+        &mut self,
+        source: Option<&Self::Address>,
+        validator: &Self::Address,
+        current_epoch: impl Into<Epoch>,
+    ) -> Result<Self::TokenAmount, ClaimRewardsError<Self::Address>> {
+        let current_epoch = current_epoch.into();
+        let source = source.unwrap_or(validator);
+        let bond_id = BondId {
+            source: source.clone(),
+            validator: validator.clone(),
+        };
+        let bond = self
+            .read_bond(&bond_id)
+            .ok_or_else(|| ClaimRewardsError::NoBondFound(bond_id.clone()))?;
+        let claimed = self.withdraw_rewards(&bond_id, &bond, current_epoch);
+        if claimed == Self::TokenAmount::default() {
+            return Err(ClaimRewardsError::NoRewards(bond_id));
+        }
+        Ok(claimed)
+    }
+
+    /// Transfer the pending rewards of a bond from PoS to its source and
+    /// record that the rewards accrued before the current epoch have been
+    /// claimed. Returns the transferred amount.
+    fn withdraw_rewards(
+        &mut self,
+        bond_id: &BondId<Self::Address>,
+        bond: &Bonds<Self::TokenAmount>,
+        current_epoch: Epoch,
+    ) -> Self::TokenAmount {
+        let claimed_epoch = self.read_rewards_claimed_epoch(bond_id);
+        let pending = pending_rewards(
+            bond,
+            &self.read_validator_slashes(&bond_id.validator),
+            &self.read_validator_rewards(&bond_id.validator),
+            claimed_epoch,
+            current_epoch,
+        );
+        if pending != Self::TokenAmount::default() {
+            self.transfer(
+                &Self::staking_token_address(),
+                pending,
+                &Self::POS_ADDRESS,
+                &bond_id.source,
+            );
+        }
+        if claimed_epoch != Some(current_epoch) {
+            self.write_rewards_claimed_epoch(bond_id, current_epoch);
+        }
+        pending
+    }
 }
 
 /// PoS system base trait for system initialization on genesis block, updating
@@ -524,6 +994,7 @@ pub trait PosBase {
     type PublicKey: 'static
         + Debug
         + Clone
+        + PartialEq
         + BorshDeserialize
         + BorshSerialize
         + BorshSchema;
@@ -544,6 +1015,11 @@ pub trait PosBase {
         &self,
         raw_hash: impl AsRef<str>,
     ) -> Option<Self::Address>;
+    /// Read PoS validator's staking reward address.
+    fn read_validator_staking_reward_address(
+        &self,
+        key: &Self::Address,
+    ) -> Option<Self::Address>;
     /// Read PoS validator's consensus key (used for signing block votes).
     fn read_validator_consensus_key(
         &self,
@@ -565,12 +1041,48 @@ pub trait PosBase {
         &self,
         key: &Self::Address,
     ) -> Option<ValidatorVotingPowers>;
+    /// Read PoS validator's commission rate.
+    fn read_validator_commission_rate(
+        &self,
+        key: &Self::Address,
+    ) -> Option<CommissionRates>;
+    /// Read PoS rewards of validator's bonds.
+    fn read_validator_rewards(
+        &self,
+        key: &Self::Address,
+    ) -> ValidatorRewards<Self::TokenAmount>;
+    /// Read PoS validator's missed blocks in the liveness window.
+    fn read_validator_liveness(&self, key: &Self::Address)
+        -> ValidatorLiveness;
     /// Read PoS slashes applied to a validator.
     fn read_validator_slashes(&self, key: &Self::Address) -> Slashes;
+    /// Read PoS bond (validator self-bond or a delegation).
+    fn read_bond(
+        &self,
+        key: &BondId<Self::Address>,
+    ) -> Option<Bonds<Self::TokenAmount>>;
+    /// Read PoS redelegations from a validator.
+    fn read_validator_outgoing_redelegations(
+        &self,
+        key: &Self::Address,
+    ) -> Redelegations<Self::Address, Self::TokenAmount>;
+    /// Read PoS redelegations to a validator.
+    fn read_validator_incoming_redelegations(
+        &self,
+        key: &Self::Address,
+    ) -> Redelegations<Self::Address, Self::TokenAmount>;
     /// Read PoS validator set (active and inactive).
     fn read_validator_set(&self) -> ValidatorSets<Self::Address>;
     /// Read PoS total voting power of all validators (active and inactive).
     fn read_total_voting_power(&self) -> TotalVotingPowers;
+    /// Read PoS validator set snapshot of an epoch, if it's still kept.
+    fn read_validator_set_snapshot(
+        &self,
+        epoch: Epoch,
+    ) -> Option<ValidatorSetSnapshot<Self::Address, Self::PublicKey>>;
+    /// Read the total supply of the staking token, of which the inflation is
+    /// minted.
+    fn read_staking_token_supply(&self) -> Self::TokenAmount;
 
     /// Write PoS parameters.
     fn write_pos_params(&mut self, params: &PosParams);
@@ -608,6 +1120,30 @@ pub trait PosBase {
         key: &Self::Address,
         value: &ValidatorVotingPowers,
     );
+    /// Write PoS validator's commission rate.
+    fn write_validator_commission_rate(
+        &mut self,
+        key: &Self::Address,
+        value: &CommissionRates,
+    );
+    /// Write PoS validator's maximum change of the commission rate per epoch.
+    fn write_validator_max_commission_rate_change(
+        &mut self,
+        key: &Self::Address,
+        value: &BasisPoints,
+    );
+    /// Write PoS rewards of validator's bonds.
+    fn write_validator_rewards(
+        &mut self,
+        key: &Self::Address,
+        value: &ValidatorRewards<Self::TokenAmount>,
+    );
+    /// Write PoS validator's missed blocks in the liveness window.
+    fn write_validator_liveness(
+        &mut self,
+        key: &Self::Address,
+        value: &ValidatorLiveness,
+    );
     /// Write (append) PoS slash applied to a validator.
     fn write_validator_slash(
         &mut self,
@@ -620,18 +1156,39 @@ pub trait PosBase {
         key: &BondId<Self::Address>,
         value: &Bonds<Self::TokenAmount>,
     );
+    /// Write PoS redelegations from a validator.
+    fn write_validator_outgoing_redelegations(
+        &mut self,
+        key: &Self::Address,
+        value: &Redelegations<Self::Address, Self::TokenAmount>,
+    );
+    /// Write PoS redelegations to a validator.
+    fn write_validator_incoming_redelegations(
+        &mut self,
+        key: &Self::Address,
+        value: &Redelegations<Self::Address, Self::TokenAmount>,
+    );
     /// Write PoS validator set (active and inactive).
     fn write_validator_set(&mut self, value: &ValidatorSets<Self::Address>);
     /// Read PoS total voting power of all validators (active and inactive).
     fn write_total_voting_power(&mut self, value: &TotalVotingPowers);
+    /// Write PoS validator set snapshot of an epoch.
+    fn write_validator_set_snapshot(
+        &mut self,
+        epoch: Epoch,
+        value: &ValidatorSetSnapshot<Self::Address, Self::PublicKey>,
+    );
+    /// Delete PoS validator set snapshot of an epoch.
+    fn delete_validator_set_snapshot(&mut self, epoch: Epoch);
     /// Initialize staking reward account with the given public key.
     fn init_staking_reward_account(
         &mut self,
         address: &Self::Address,
         pk: &Self::PublicKey,
     );
-    /// Credit tokens to the `target` account. This should only be used at
-    /// genesis.
+    /// Credit tokens to the `target` account. The tokens are minted, so the
+    /// token's total supply is raised by the amount. This should only be used
+    /// at genesis and for the block and the inflation rewards.
     fn credit_tokens(
         &mut self,
         token: &Self::Address,
@@ -683,6 +1240,8 @@ pub trait PosBase {
                 state,
                 total_deltas,
                 voting_power,
+                commission_rate,
+                max_commission_rate_change,
                 bond: (bond_id, bond),
             } = res?;
             self.write_validator_address_raw_hash(address);
@@ -694,6 +1253,11 @@ pub trait PosBase {
             self.write_validator_state(address, &state);
             self.write_validator_total_deltas(address, &total_deltas);
             self.write_validator_voting_power(address, &voting_power);
+            self.write_validator_commission_rate(address, &commission_rate);
+            self.write_validator_max_commission_rate_change(
+                address,
+                &max_commission_rate_change,
+            );
             self.write_bond(&bond_id, &bond);
             self.init_staking_reward_account(
                 &staking_reward_address,
@@ -702,6 +1266,7 @@ pub trait PosBase {
         }
         self.write_validator_set(&validator_set);
         self.write_total_voting_power(&total_voting_power);
+        self.record_validator_set_snapshot(params, current_epoch);
         // Credit the bonded tokens to the PoS account
         self.credit_tokens(
             &Self::staking_token_address(),
@@ -742,20 +1307,37 @@ pub trait PosBase {
         // For active validators, this would only ever happen until all the
         // validator slots are filled with non-0 voting power validators, but we
         // still need to guard against it.
-        let active_validators = cur_validators.active.iter().filter_map(
+        let consensus_key_at = |address: &Self::Address, epoch: Epoch| {
+            self.read_validator_consensus_key(address)
+                .and_then(|keys| keys.get(epoch).cloned())
+        };
+        let active_validators = cur_validators.active.iter().flat_map(
             |validator: &WeightedValidator<_>| {
-                // If the validators set from previous epoch contains the same
-                // validator, it means its voting power hasn't changed and hence
-                // doesn't need to updated.
+                let consensus_key =
+                    consensus_key_at(&validator.address, current_epoch)
+                        .unwrap();
+                let mut updates = vec![];
                 if let (Some(prev_epoch), Some(prev_validators)) =
                     (previous_epoch, prev_validators)
                 {
-                    if prev_validators.active.contains(validator) {
+                    let prev_consensus_key =
+                        consensus_key_at(&validator.address, prev_epoch);
+                    let key_changed = prev_consensus_key
+                        .as_ref()
+                        .map(|prev_key| *prev_key != consensus_key)
+                        .unwrap_or_default();
+                    // If the validators set from previous epoch contains the
+                    // same validator with the same consensus key, it means
+                    // its voting power hasn't changed and hence doesn't need
+                    // to updated.
+                    if prev_validators.active.contains(validator)
+                        && !key_changed
+                    {
                         println!(
                             "skipping validator update, still the same {}",
                             validator.address
                         );
-                        return None;
+                        return updates;
                     }
                     if validator.voting_power == 0.into() {
                         // If the validator was `Pending` in the previous epoch,
@@ -771,25 +1353,36 @@ pub trait PosBase {
                                     "skipping validator update, it's new {}",
                                     validator.address
                                 );
-                                return None;
+                                return updates;
                             }
                         }
                     }
+                    // When the consensus key of an active validator changed,
+                    // its Tendermint validator with the old key is removed
+                    let was_active = prev_validators
+                        .active
+                        .iter()
+                        .any(|prev| prev.address == validator.address);
+                    if key_changed && was_active {
+                        if let Some(prev_consensus_key) = prev_consensus_key {
+                            updates.push(ValidatorSetUpdate::Deactivated(
+                                prev_consensus_key,
+                            ));
+                        }
+                    }
                 }
-                let consensus_key = self
-                    .read_validator_consensus_key(&validator.address)
-                    .unwrap()
-                    .get(current_epoch)
-                    .unwrap()
-                    .clone();
-                Some(ValidatorSetUpdate::Active(ActiveValidator {
+                updates.push(ValidatorSetUpdate::Active(ActiveValidator {
                     consensus_key,
                     voting_power: validator.voting_power,
-                }))
+                }));
+                updates
             },
         );
         let inactive_validators = cur_validators.inactive.iter().filter_map(
             |validator: &WeightedValidator<Self::Address>| {
+                // The validator is deactivated with the key it had in the
+                // previous epoch, in case its consensus key changed
+                let mut consensus_key_epoch = current_epoch;
                 // If the validators set from previous epoch contains the same
                 // validator, it means its voting power hasn't changed and hence
                 // doesn't need to updated.
@@ -813,17 +1406,365 @@ pub trait PosBase {
                             }
                         }
                     }
+                    consensus_key_epoch = prev_epoch;
                 }
+                let consensus_key =
+                    consensus_key_at(&validator.address, consensus_key_epoch)
+                        .or_else(|| {
+                            consensus_key_at(&validator.address, current_epoch)
+                        })
+                        .unwrap();
+                Some(ValidatorSetUpdate::Deactivated(consensus_key))
+            },
+        );
+        // The validators that were active in the previous epoch, but are not
+        // in the validator set anymore, because they've been jailed
+        let jailed_validators = previous_epoch
+            .zip(prev_validators)
+            .into_iter()
+            .flat_map(|(prev_epoch, prev_validators)| {
+                prev_validators
+                    .active
+                    .iter()
+                    .filter(move |validator| {
+                        !cur_validators
+                            .active
+                            .iter()
+                            .chain(cur_validators.inactive.iter())
+                            .any(|cur| cur.address == validator.address)
+                    })
+                    .filter_map(move |validator| {
+                        consensus_key_at(&validator.address, prev_epoch)
+                    })
+            })
+            .map(ValidatorSetUpdate::Deactivated);
+        active_validators
+            .chain(inactive_validators)
+            .chain(jailed_validators)
+            .for_each(f)
+    }
+
+    /// Record the snapshot of the validator set of the current epoch, which
+    /// must be done at the beginning of every epoch. The snapshot of the
+    /// epoch that is no longer within the unbonding length, in which the
+    /// evidence of misbehaviours is valid, is deleted.
+    fn record_validator_set_snapshot(
+        &mut self,
+        params: &PosParams,
+        current_epoch: impl Into<Epoch>,
+    ) {
+        let current_epoch = current_epoch.into();
+        let validator_set = match self.read_validator_set().get(current_epoch) {
+            Some(validator_set) => validator_set.clone(),
+            None => return,
+        };
+        let consensus_keys = validator_set
+            .active
+            .iter()
+            .chain(validator_set.inactive.iter())
+            .filter_map(|validator| {
                 let consensus_key = self
-                    .read_validator_consensus_key(&validator.address)
-                    .unwrap()
-                    .get(current_epoch)
-                    .unwrap()
+                    .read_validator_consensus_key(&validator.address)?
+                    .get(current_epoch)?
                     .clone();
-                Some(ValidatorSetUpdate::Deactivated(consensus_key))
+                Some((validator.address.clone(), consensus_key))
+            })
+            .collect();
+        let total_voting_power = self
+            .read_total_voting_power()
+            .get(current_epoch)
+            .and_then(|total| VotingPower::try_from(total).ok())
+            .unwrap_or_default();
+        self.write_validator_set_snapshot(
+            current_epoch,
+            &ValidatorSetSnapshot {
+                validator_set,
+                consensus_keys,
+                total_voting_power,
             },
         );
-        active_validators.chain(inactive_validators).for_each(f)
+        let current_epoch_u64: u64 = current_epoch.into();
+        if let Some(expired) =
+            current_epoch_u64.checked_sub(params.unbonding_len)
+        {
+            self.delete_validator_set_snapshot(Epoch::from(expired));
+        }
+    }
+
+    /// Find the validator in the validator set of the given epoch whose
+    /// consensus key in that epoch satisfies the predicate, e.g. the key with
+    /// the Tendermint address of a byzantine validator. The validator set of
+    /// a past epoch is found in its snapshot, if it's still kept.
+    fn find_validator_by_consensus_key(
+        &self,
+        epoch: impl Into<Epoch>,
+        mut predicate: impl FnMut(&Self::PublicKey) -> bool,
+    ) -> Option<Self::Address> {
+        let epoch = epoch.into();
+        if let Some(snapshot) = self.read_validator_set_snapshot(epoch) {
+            return snapshot
+                .consensus_keys
+                .iter()
+                .find(|(_address, consensus_key)| predicate(consensus_key))
+                .map(|(address, _consensus_key)| address.clone());
+        }
+        let validator_set = self.read_validator_set();
+        let validators = validator_set.get(epoch)?;
+        validators
+            .active
+            .iter()
+            .chain(validators.inactive.iter())
+            .find(|validator| {
+                self.read_validator_consensus_key(&validator.address)
+                    .and_then(|keys| keys.get(epoch).map(&mut predicate))
+                    .unwrap_or_default()
+            })
+            .map(|validator| validator.address.clone())
+    }
+
+    /// Read the active validators with a non-zero voting power in the
+    /// validator set of the given epoch, with their consensus keys in that
+    /// epoch, e.g. for the initial validator set of a chain initialized from
+    /// an exported state.
+    fn read_active_validators(
+        &self,
+        epoch: impl Into<Epoch>,
+    ) -> Vec<ActiveValidator<Self::PublicKey>> {
+        let epoch = epoch.into();
+        let validator_set = self.read_validator_set();
+        let validators = match validator_set.get(epoch) {
+            Some(validators) => validators,
+            None => return vec![],
+        };
+        validators
+            .active
+            .iter()
+            .filter(|validator| validator.voting_power != 0.into())
+            .filter_map(|validator| {
+                let consensus_key = self
+                    .read_validator_consensus_key(&validator.address)?
+                    .get(epoch)?
+                    .clone();
+                Some(ActiveValidator {
+                    consensus_key,
+                    voting_power: validator.voting_power,
+                })
+            })
+            .collect()
+    }
+
+    /// Accrue the rewards of a block to the active validators of the current
+    /// epoch. Each validator with a non-zero voting power gets the block vote
+    /// reward and a part of the block proposer reward proportional to its
+    /// voting power. The validator's commission is credited to its staking
+    /// reward address and the rest is added to the validator's reward pool
+    /// of the current epoch, from which the bonds claim their shares. The
+    /// rewards are minted into the PoS account.
+    fn accrue_block_rewards(
+        &mut self,
+        params: &PosParams,
+        current_epoch: impl Into<Epoch>,
+    ) {
+        let current_epoch = current_epoch.into();
+        let validator_set = self.read_validator_set();
+        let validators = match validator_set.get(current_epoch) {
+            Some(validators) => validators,
+            None => return,
+        };
+        let total_voting_power: u128 = validators
+            .active
+            .iter()
+            .map(|validator| u128::from(u64::from(validator.voting_power)))
+            .sum();
+        if total_voting_power == 0 {
+            return;
+        }
+        let mut total_rewards = 0_u64;
+        for validator in &validators.active {
+            let voting_power = u128::from(u64::from(validator.voting_power));
+            if voting_power == 0 {
+                continue;
+            }
+            let address = &validator.address;
+            let stake: i128 = match self
+                .read_validator_total_deltas(address)
+                .and_then(|deltas| deltas.get(current_epoch))
+            {
+                Some(stake) => stake.into(),
+                None => continue,
+            };
+            let stake = match u64::try_from(stake) {
+                Ok(stake) if stake != 0 => stake,
+                _ => continue,
+            };
+            let proposer_reward = u128::from(params.block_proposer_reward)
+                * voting_power
+                / total_voting_power;
+            let reward = params.block_vote_reward
+                + u64::try_from(proposer_reward).unwrap_or_default();
+            total_rewards += self.accrue_validator_reward(
+                address,
+                current_epoch,
+                stake,
+                reward,
+            );
+        }
+        if total_rewards != 0 {
+            self.credit_tokens(
+                &Self::staking_token_address(),
+                &Self::POS_ADDRESS,
+                total_rewards.into(),
+            );
+        }
+    }
+
+    /// Mint the inflation of the staking token of the epoch that has just
+    /// ended before the current epoch into the staking rewards. The annual
+    /// inflation rate is given by the ratio of the tokens staked with the
+    /// validators in the epoch to the total supply of the staking token (see
+    /// [`PosParams::inflation_rate`]). The inflation is accrued to the active
+    /// validators of the epoch in proportion to their stake, with their
+    /// commissions, as the block rewards are. Returns the minted amount.
+    fn mint_inflation_rewards(
+        &mut self,
+        params: &PosParams,
+        current_epoch: impl Into<Epoch>,
+    ) -> Self::TokenAmount {
+        let current_epoch: Epoch = current_epoch.into();
+        let current_epoch: u64 = current_epoch.into();
+        let last_epoch = match current_epoch.checked_sub(1) {
+            Some(last_epoch) => Epoch::from(last_epoch),
+            None => return Self::TokenAmount::default(),
+        };
+        let (active_stakes, total_stake) = self.read_epoch_stakes(last_epoch);
+        let active_stake: u64 =
+            active_stakes.iter().map(|(_address, stake)| stake).sum();
+        let total_supply: u64 = self.read_staking_token_supply().into();
+        let rate = params.inflation_rate(total_stake, total_supply);
+        let inflation = params.epoch_inflation(rate, total_supply);
+        if inflation == 0 || active_stake == 0 {
+            return Self::TokenAmount::default();
+        }
+        let mut minted = 0_u64;
+        let mut total_rewards = 0_u64;
+        for (address, stake) in active_stakes {
+            let reward = u128::from(inflation) * u128::from(stake)
+                / u128::from(active_stake);
+            // The reward is at most the inflation
+            let reward = reward as u64;
+            if reward == 0 {
+                continue;
+            }
+            total_rewards += self
+                .accrue_validator_reward(&address, last_epoch, stake, reward);
+            minted += reward;
+        }
+        if total_rewards != 0 {
+            self.credit_tokens(
+                &Self::staking_token_address(),
+                &Self::POS_ADDRESS,
+                total_rewards.into(),
+            );
+        }
+        minted.into()
+    }
+
+    /// Read the non-zero stakes of the active validators in the given epoch
+    /// and the total tokens staked with the active and the inactive
+    /// validators in the epoch. The validator set of a past epoch is found in
+    /// its snapshot, if it's still kept.
+    fn read_epoch_stakes(
+        &self,
+        epoch: impl Into<Epoch>,
+    ) -> (Vec<(Self::Address, u64)>, u64) {
+        let epoch = epoch.into();
+        let validators = match self.read_validator_set_snapshot(epoch) {
+            Some(snapshot) => snapshot.validator_set,
+            None => match self.read_validator_set().get(epoch) {
+                Some(validators) => validators.clone(),
+                None => return (vec![], 0),
+            },
+        };
+        let stake_in_epoch = |address: &Self::Address| -> u64 {
+            self.read_validator_total_deltas(address)
+                .and_then(|deltas| deltas.get(epoch))
+                .and_then(|stake| {
+                    let stake: i128 = stake.into();
+                    u64::try_from(stake).ok()
+                })
+                .unwrap_or_default()
+        };
+        let active_stakes: Vec<(Self::Address, u64)> = validators
+            .active
+            .iter()
+            .map(|validator| {
+                let stake = stake_in_epoch(&validator.address);
+                (validator.address.clone(), stake)
+            })
+            .filter(|(_address, stake)| *stake != 0)
+            .collect();
+        let inactive_stake: u64 = validators
+            .inactive
+            .iter()
+            .map(|validator| stake_in_epoch(&validator.address))
+            .sum();
+        let active_stake: u64 =
+            active_stakes.iter().map(|(_address, stake)| stake).sum();
+        (active_stakes, active_stake + inactive_stake)
+    }
+
+    /// Accrue a reward to a validator with the given stake in the given epoch.
+    /// The validator's commission is credited to its staking reward address
+    /// and the rest is added to the validator's reward pool of the epoch.
+    /// Returns the amount added to the reward pool, which the caller must
+    /// mint into the PoS account.
+    fn accrue_validator_reward(
+        &mut self,
+        address: &Self::Address,
+        epoch: Epoch,
+        stake: u64,
+        reward: u64,
+    ) -> u64 {
+        let rate = self
+            .read_validator_commission_rate(address)
+            .and_then(|rates| rates.get(epoch).copied())
+            .unwrap_or(BasisPoints::new(0));
+        let mut commission = rate * reward;
+        match self.read_validator_staking_reward_address(address) {
+            Some(staking_reward_address) if commission != 0 => {
+                self.credit_tokens(
+                    &Self::staking_token_address(),
+                    &staking_reward_address,
+                    commission.into(),
+                );
+            }
+            _ => commission = 0,
+        }
+        let mut rewards = self.read_validator_rewards(address);
+        let epoch_rewards =
+            rewards.entry(epoch).or_insert_with(|| EpochRewards {
+                rewards: Self::TokenAmount::default(),
+                stake: stake.into(),
+            });
+        epoch_rewards.rewards += (reward - commission).into();
+        self.write_validator_rewards(address, &rewards);
+        reward - commission
+    }
+
+    /// Prune the rewards of a validator accrued before the given epoch, which
+    /// must be the first epoch whose rewards some bond to the validator has
+    /// yet to claim (see [`first_unclaimed_epoch`]).
+    fn prune_validator_rewards(
+        &mut self,
+        address: &Self::Address,
+        before: Epoch,
+    ) {
+        let mut rewards = self.read_validator_rewards(address);
+        if rewards.keys().next().map_or(true, |first| *first >= before) {
+            return;
+        }
+        let rewards = rewards.split_off(&before);
+        self.write_validator_rewards(address, &rewards);
     }
 
     /// Apply a slash to a byzantine validator for the given evidence.
@@ -870,11 +1811,75 @@ pub trait PosBase {
         let slashed_change: i128 = slashed_change.into();
         let slashed_amount = u64::try_from(slashed_change)
             .map_err(|_err| SlashError::InvalidSlashChange(slashed_change))?;
-        let slashed_amount = Self::TokenAmount::from(slashed_amount);
+        let mut slashed_amount = Self::TokenAmount::from(slashed_amount);
 
         self.write_validator_total_deltas(validator, &total_deltas);
         self.write_validator_voting_power(validator, &voting_power);
         self.write_validator_slash(validator, validator_slash);
+
+        // The tokens that were redelegated from the validator after the
+        // infraction are slashed from the bonds to which they were
+        // redelegated, so that a redelegation cannot evade the slash
+        let mut outgoing =
+            self.read_validator_outgoing_redelegations(validator);
+        outgoing.retain(|r| r.is_slashable(params, current_epoch));
+        for redelegation in outgoing.iter_mut() {
+            if evidence_epoch >= redelegation.src_stake_end(params) {
+                continue;
+            }
+            let raw_amount: u64 = redelegation.amount.into();
+            let redelegation_slashed =
+                Self::TokenAmount::from(rate * raw_amount);
+            if redelegation_slashed == Self::TokenAmount::default() {
+                continue;
+            }
+            let dest = &redelegation.dest_validator;
+            let bond_id = BondId {
+                source: redelegation.source.clone(),
+                validator: dest.clone(),
+            };
+            let mut dest_total_deltas =
+                self.read_validator_total_deltas(dest).ok_or_else(|| {
+                    SlashError::ValidatorHasNoTotalDeltas(dest.clone())
+                })?;
+            let mut dest_voting_power =
+                self.read_validator_voting_power(dest).ok_or_else(|| {
+                    SlashError::ValidatorHasNoVotingPower(dest.clone())
+                })?;
+            slash_stake(
+                params,
+                current_epoch,
+                dest,
+                -Self::TokenChange::from(redelegation_slashed),
+                &mut dest_total_deltas,
+                &mut dest_voting_power,
+                &mut validator_set,
+                &mut total_voting_power,
+            )?;
+            if let Some(mut bond) = self.read_bond(&bond_id) {
+                slash_bond(
+                    params,
+                    &mut bond,
+                    redelegation_slashed,
+                    current_epoch,
+                );
+                self.write_bond(&bond_id, &bond);
+            }
+            self.write_validator_total_deltas(dest, &dest_total_deltas);
+            self.write_validator_voting_power(dest, &dest_voting_power);
+
+            let mut incoming = self.read_validator_incoming_redelegations(dest);
+            if let Some(incoming) =
+                incoming.iter_mut().find(|r| **r == *redelegation)
+            {
+                incoming.amount -= redelegation_slashed;
+            }
+            redelegation.amount -= redelegation_slashed;
+            self.write_validator_incoming_redelegations(dest, &incoming);
+            slashed_amount += redelegation_slashed;
+        }
+        self.write_validator_outgoing_redelegations(validator, &outgoing);
+
         self.write_validator_set(&validator_set);
         self.write_total_voting_power(&total_voting_power);
         // Transfer the slashed tokens to the PoS slash pool
@@ -886,6 +1891,84 @@ pub trait PosBase {
         );
         Ok(())
     }
+
+    /// Record whether a validator signed the block at the given height in its
+    /// liveness window.
+    fn record_validator_liveness(
+        &mut self,
+        params: &PosParams,
+        block_height: impl Into<u64>,
+        validator: &Self::Address,
+        signed: bool,
+    ) {
+        let liveness_pre = self.read_validator_liveness(validator);
+        let mut liveness = liveness_pre.clone();
+        liveness.record(
+            params.liveness_window_len,
+            block_height.into(),
+            signed,
+        );
+        if liveness != liveness_pre {
+            self.write_validator_liveness(validator, &liveness);
+        }
+    }
+
+    /// Find the active validators of the given epoch that missed to sign more
+    /// blocks in their liveness window than the PoS parameters allow.
+    fn find_downtime_validators(
+        &self,
+        params: &PosParams,
+        epoch: impl Into<Epoch>,
+    ) -> Vec<Self::Address> {
+        let validator_set = self.read_validator_set();
+        match validator_set.get(epoch.into()) {
+            Some(validator_set) => validator_set
+                .active
+                .iter()
+                .filter(|validator| {
+                    self.read_validator_liveness(&validator.address)
+                        .missed_count()
+                        > params.liveness_max_missed_blocks
+                })
+                .map(|validator| validator.address.clone())
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Jail a validator, which becomes jailed and is removed from the
+    /// validator set from the pipeline epoch, until it's unjailed with
+    /// [`PosActions::unjail_validator`]. Its liveness is tracked again from an
+    /// empty window.
+    fn jail_validator(
+        &mut self,
+        params: &PosParams,
+        current_epoch: impl Into<Epoch>,
+        validator: &Self::Address,
+    ) -> Result<(), JailError<Self::Address>> {
+        let current_epoch = current_epoch.into();
+        let mut state = self
+            .read_validator_state(validator)
+            .ok_or_else(|| JailError::NotAValidator(validator.clone()))?;
+        state.set(
+            ValidatorState::Jailed {
+                epoch: current_epoch,
+            },
+            current_epoch,
+            params,
+        );
+        let mut validator_set = self.read_validator_set();
+        remove_from_validator_set(
+            params,
+            validator,
+            &mut validator_set,
+            current_epoch,
+        );
+        self.write_validator_state(validator, &state);
+        self.write_validator_set(&validator_set);
+        self.write_validator_liveness(validator, &ValidatorLiveness::default());
+        Ok(())
+    }
 }
 
 #[allow(missing_docs)]
@@ -893,6 +1976,11 @@ pub trait PosBase {
 pub enum GenesisError {
     #[error("Voting power overflow: {0}")]
     VotingPowerOverflow(TryFromIntError),
+    #[error(
+        "The commission rate {0} or its maximum change is greater than {}",
+        BasisPoints::MAX_RATE
+    )]
+    InvalidCommissionRate(BasisPoints),
 }
 
 #[allow(missing_docs)]
@@ -905,6 +1993,45 @@ pub enum BecomeValidatorError<Address: Display + Debug> {
          address {0}"
     )]
     StakingRewardAddressEqValidatorAddress(Address),
+    #[error(
+        "The commission rate {0} is greater than {}",
+        BasisPoints::MAX_RATE
+    )]
+    InvalidCommissionRate(BasisPoints),
+    #[error(
+        "The maximum change of the commission rate {0} is greater than {}",
+        BasisPoints::MAX_RATE
+    )]
+    InvalidMaxCommissionRateChange(BasisPoints),
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum ConsensusKeyChangeError<Address: Display + Debug> {
+    #[error("The given address {0} is not a validator address")]
+    NotAValidator(Address),
+    #[error("The consensus key is already used by the validator {0}")]
+    KeyAlreadyUsed(Address),
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum CommissionRateChangeError<Address: Display + Debug> {
+    #[error("The given address {0} is not a validator address")]
+    NotAValidator(Address),
+    #[error(
+        "The commission rate {0} is greater than {}",
+        BasisPoints::MAX_RATE
+    )]
+    InvalidRate(BasisPoints),
+    #[error(
+        "The commission rate change {change} is greater than the validator's \
+         maximum change {max_change}"
+    )]
+    RateChangeTooLarge {
+        change: BasisPoints,
+        max_change: BasisPoints,
+    },
 }
 
 #[allow(missing_docs)]
@@ -942,6 +2069,43 @@ pub enum UnbondError<Address: Display + Debug, TokenAmount: Display + Debug> {
     VotingPowerOverflow(TryFromIntError),
     #[error("Given zero amount to unbond")]
     ZeroAmount,
+    #[error(
+        "Trying to unbond more tokens ({0}) than the amount that is not \
+         slashable for the validators from which it was redelegated ({1})"
+    )]
+    SlashableRedelegation(TokenAmount, TokenAmount),
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum RedelegationError<
+    Address: Display + Debug,
+    TokenAmount: Display + Debug,
+> {
+    #[error(
+        "The given source address {0} is a validator address. Validators may \
+         not redelegate."
+    )]
+    SourceMustNotBeAValidator(Address),
+    #[error("The tokens cannot be redelegated to the same validator {0}")]
+    SameValidator(Address),
+    #[error("No bond could be found")]
+    NoBondFound,
+    #[error(
+        "Trying to redelegate more tokens ({0}) than the amount that can be \
+         redelegated ({1})"
+    )]
+    RedelegationAmountGreaterThanBond(TokenAmount, TokenAmount),
+    #[error("No bonds found for the validator {0}")]
+    ValidatorHasNoBonds(Address),
+    #[error("Voting power not found for the validator {0}")]
+    ValidatorHasNoVotingPower(Address),
+    #[error("Voting power overflow: {0}")]
+    VotingPowerOverflow(TryFromIntError),
+    #[error("Given zero amount to redelegate")]
+    ZeroAmount,
+    #[error("The tokens cannot be bonded to the new validator: {0}")]
+    Bond(BondError<Address>),
 }
 
 #[allow(missing_docs)]
@@ -964,6 +2128,26 @@ where
     NoWithdrawableUnbond(BondId<Address>),
 }
 
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum ClaimRewardsError<Address>
+where
+    Address: Display
+        + Debug
+        + Clone
+        + PartialOrd
+        + Ord
+        + Hash
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+{
+    #[error("No bond could be found for {0}")]
+    NoBondFound(BondId<Address>),
+    #[error("No rewards may be claimed yet for {0}")]
+    NoRewards(BondId<Address>),
+}
+
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
 pub enum SlashError<Address>
@@ -982,6 +2166,39 @@ where
     NegativeStake(i128, Address),
 }
 
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum JailError<Address: Display + Debug> {
+    #[error("The given address {0} is not a validator address")]
+    NotAValidator(Address),
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum ValidatorMetadataError<Address: Display + Debug> {
+    #[error("The given address {0} is not a validator address")]
+    NotAValidator(Address),
+    #[error("Invalid validator metadata: {0}")]
+    InvalidMetadata(MetadataError),
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum UnjailError<Address: Display + Debug> {
+    #[error("The given address {0} is not a validator address")]
+    NotAValidator(Address),
+    #[error("The validator {0} is not jailed")]
+    NotJailed(Address),
+    #[error(
+        "The validator {validator} may only be unjailed from epoch \
+         {unjail_epoch}"
+    )]
+    CooldownNotPassed {
+        validator: Address,
+        unjail_epoch: Epoch,
+    },
+}
+
 struct GenesisData<Validators, Address, TokenAmount, TokenChange, PK>
 where
     Validators: Iterator<
@@ -1055,6 +2272,8 @@ where
     state: ValidatorStates,
     total_deltas: ValidatorTotalDeltas<TokenChange>,
     voting_power: ValidatorVotingPowers,
+    commission_rate: CommissionRates,
+    max_commission_rate_change: BasisPoints,
     bond: (BondId<Address>, Bonds<TokenAmount>),
 }
 
@@ -1155,7 +2374,19 @@ where
                   tokens,
                   consensus_key,
                   staking_reward_key,
+                  commission_rate,
+                  max_commission_rate_change,
               }| {
+            if *commission_rate > BasisPoints::MAX_RATE {
+                return Err(GenesisError::InvalidCommissionRate(
+                    *commission_rate,
+                ));
+            }
+            if *max_commission_rate_change > BasisPoints::MAX_RATE {
+                return Err(GenesisError::InvalidCommissionRate(
+                    *max_commission_rate_change,
+                ));
+            }
             let consensus_key =
                 Epoched::init_at_genesis(consensus_key.clone(), current_epoch);
             let state = Epoched::init_at_genesis(
@@ -1170,6 +2401,8 @@ where
                     .map_err(GenesisError::VotingPowerOverflow)?;
             let voting_power =
                 EpochedDelta::init_at_genesis(voting_power, current_epoch);
+            let commission_rate =
+                Epoched::init_at_genesis(*commission_rate, current_epoch);
             let bond_id = BondId {
                 source: address.clone(),
                 validator: address.clone(),
@@ -1186,6 +2419,8 @@ where
                 state,
                 total_deltas,
                 voting_power,
+                commission_rate,
+                max_commission_rate_change: *max_commission_rate_change,
                 bond: (bond_id, bond),
             })
         },
@@ -1244,8 +2479,101 @@ where
     }
     let raw_current_stake: i128 = current_stake.into();
     let slashed_amount: TokenChange = (slash.rate * raw_current_stake).into();
-    let token_change = -slashed_amount;
+    slash_stake(
+        params,
+        current_epoch,
+        validator,
+        -slashed_amount,
+        total_deltas,
+        voting_power,
+        validator_set,
+        total_voting_power,
+    )?;
+    Ok(slashed_amount)
+}
 
+/// Remove the slashed tokens of a redelegation from the bond to which they
+/// were redelegated, starting from the rightmost bond delta.
+fn slash_bond<TokenAmount>(
+    params: &PosParams,
+    bond: &mut Bonds<TokenAmount>,
+    amount: TokenAmount,
+    current_epoch: Epoch,
+) where
+    TokenAmount: Debug
+        + Default
+        + Clone
+        + Copy
+        + PartialOrd
+        + Add<Output = TokenAmount>
+        + AddAssign
+        + From<u64>
+        + SubAssign
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+{
+    let mut to_slash = amount;
+    let to_slash = &mut to_slash;
+    bond.rev_update_while(
+        |bonds, _epoch| {
+            bonds.deltas.retain(|_epoch_start, bond_delta| {
+                if *to_slash == 0.into() {
+                    return true;
+                }
+                if to_slash > bond_delta {
+                    *to_slash -= *bond_delta;
+                    *bond_delta = 0.into();
+                } else {
+                    *bond_delta -= *to_slash;
+                    *to_slash = 0.into();
+                }
+                // Remove bonds with no tokens left
+                *bond_delta != 0.into()
+            });
+            *to_slash != 0.into()
+        },
+        current_epoch,
+        params,
+    );
+}
+
+/// Remove the slashed tokens from the stake of a validator at the pipeline
+/// offset.
+#[allow(clippy::too_many_arguments)]
+fn slash_stake<Address, TokenChange>(
+    params: &PosParams,
+    current_epoch: Epoch,
+    validator: &Address,
+    token_change: TokenChange,
+    total_deltas: &mut ValidatorTotalDeltas<TokenChange>,
+    voting_power: &mut ValidatorVotingPowers,
+    validator_set: &mut ValidatorSets<Address>,
+    total_voting_power: &mut TotalVotingPowers,
+) -> Result<(), SlashError<Address>>
+where
+    Address: Display
+        + Debug
+        + Clone
+        + Ord
+        + Hash
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+    TokenChange: Display
+        + Debug
+        + Copy
+        + Default
+        + Neg<Output = TokenChange>
+        + Add<Output = TokenChange>
+        + Sub<Output = TokenChange>
+        + From<i128>
+        + Into<i128>
+        + PartialOrd
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+{
     // Apply slash at pipeline offset
     let update_offset = DynEpochOffset::PipelineLen;
 
@@ -1279,9 +2607,7 @@ where
         total_voting_power,
         current_epoch,
     )
-    .map_err(SlashError::VotingPowerOverflow)?;
-
-    Ok(slashed_amount)
+    .map_err(SlashError::VotingPowerOverflow)
 }
 
 struct BecomeValidatorData<PK, TokenChange>
@@ -1731,6 +3057,205 @@ where
     Ok(UnbondData { unbond })
 }
 
+/// The sum of the redelegations of the `source` in `redelegations` that are
+/// still slashable for their source validators.
+fn slashable_redelegated_amount<Address, TokenAmount>(
+    params: &PosParams,
+    redelegations: &Redelegations<Address, TokenAmount>,
+    source: &Address,
+    current_epoch: Epoch,
+) -> TokenAmount
+where
+    Address: PartialEq,
+    TokenAmount: Default + Copy + AddAssign,
+{
+    let mut slashable = TokenAmount::default();
+    for redelegation in redelegations {
+        if &redelegation.source == source
+            && redelegation.is_slashable(params, current_epoch)
+        {
+            slashable += redelegation.amount;
+        }
+    }
+    slashable
+}
+
+/// The bonded amount without the slashable amount, or zero if everything is
+/// slashable.
+fn unslashable_amount<TokenAmount>(
+    bonded: TokenAmount,
+    slashable: TokenAmount,
+) -> TokenAmount
+where
+    TokenAmount: Default + PartialOrd + SubAssign,
+{
+    if slashable < bonded {
+        let mut unslashable = bonded;
+        unslashable -= slashable;
+        unslashable
+    } else {
+        TokenAmount::default()
+    }
+}
+
+/// Remove tokens from a delegation to the source validator of a
+/// redelegation, without unbonding them. The tokens leave the validator's
+/// stake at the pipeline offset. Returns the redelegated amount, which is the
+/// removed bonded amount with the slashes of the validator applied to it.
+#[allow(clippy::too_many_arguments)]
+fn redelegate_tokens<Address, TokenAmount, TokenChange>(
+    params: &PosParams,
+    bond_id: &BondId<Address>,
+    bond: &mut Bonds<TokenAmount>,
+    amount: TokenAmount,
+    slashable: TokenAmount,
+    slashes: Slashes,
+    validator_total_deltas: &mut ValidatorTotalDeltas<TokenChange>,
+    validator_voting_power: &mut ValidatorVotingPowers,
+    total_voting_power: &mut TotalVotingPowers,
+    validator_set: &mut ValidatorSets<Address>,
+    current_epoch: Epoch,
+) -> Result<TokenAmount, RedelegationError<Address, TokenAmount>>
+where
+    Address: Display
+        + Debug
+        + Clone
+        + PartialEq
+        + Eq
+        + PartialOrd
+        + Ord
+        + Hash
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+    TokenAmount: Display
+        + Debug
+        + Default
+        + Clone
+        + Copy
+        + PartialOrd
+        + Add<Output = TokenAmount>
+        + AddAssign
+        + Into<u64>
+        + From<u64>
+        + SubAssign
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+    TokenChange: Display
+        + Debug
+        + Default
+        + Clone
+        + Copy
+        + Add<Output = TokenChange>
+        + Sub
+        + From<TokenAmount>
+        + Neg<Output = TokenChange>
+        + Into<i128>
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+{
+    if amount == TokenAmount::default() {
+        return Err(RedelegationError::ZeroAmount);
+    }
+    // Like in unbonding, the tokens that are bonded for a future epoch can be
+    // redelegated too, but not the tokens that are still slashable for the
+    // validator from which they were redelegated to this validator
+    let bonded = bond
+        .get_at_offset(current_epoch, DynEpochOffset::PipelineLen, params)
+        .unwrap_or_default()
+        .sum();
+    let redelegatable = unslashable_amount(bonded, slashable);
+    if amount > redelegatable {
+        return Err(RedelegationError::RedelegationAmountGreaterThanBond(
+            amount,
+            redelegatable,
+        ));
+    }
+
+    let update_offset = DynEpochOffset::PipelineLen;
+    let mut to_redelegate = amount;
+    let to_redelegate = &mut to_redelegate;
+    let mut redelegated = TokenAmount::default();
+    // Decrement the bond deltas starting from the rightmost value, as in
+    // unbonding
+    bond.rev_update_while(
+        |bonds, _epoch| {
+            bonds.deltas.retain(|epoch_start, bond_delta| {
+                if *to_redelegate == 0.into() {
+                    return true;
+                }
+                let applied_delta = if to_redelegate > bond_delta {
+                    *to_redelegate -= *bond_delta;
+                    let applied_delta = *bond_delta;
+                    *bond_delta = 0.into();
+                    applied_delta
+                } else {
+                    *bond_delta -= *to_redelegate;
+                    let applied_delta = *to_redelegate;
+                    *to_redelegate = 0.into();
+                    applied_delta
+                };
+                // The slashes of the validator that apply to the bond delta
+                // are not redelegated
+                let mut slashed_bond_delta = applied_delta;
+                for slash in &slashes {
+                    if slash.epoch >= *epoch_start {
+                        let raw_delta: u64 = slashed_bond_delta.into();
+                        let raw_slashed_delta = slash.rate * raw_delta;
+                        let slashed_delta =
+                            TokenAmount::from(raw_slashed_delta);
+                        slashed_bond_delta -= slashed_delta;
+                    }
+                }
+                redelegated += slashed_bond_delta;
+                // Remove bonds with no tokens left
+                *bond_delta != 0.into()
+            });
+            // Stop the update once all the tokens are redelegated
+            *to_redelegate != 0.into()
+        },
+        current_epoch,
+        params,
+    );
+
+    // Update validator set. This has to be done before we update the
+    // `validator_total_deltas`, because we need to look-up the validator with
+    // its voting power before the change.
+    let token_change = -TokenChange::from(redelegated);
+    update_validator_set(
+        params,
+        &bond_id.validator,
+        token_change,
+        update_offset,
+        validator_set,
+        Some(validator_total_deltas),
+        current_epoch,
+    );
+
+    // Update validator's total deltas
+    validator_total_deltas.add_at_offset(
+        token_change,
+        current_epoch,
+        update_offset,
+        params,
+    );
+
+    // Update the validator's and the total voting power.
+    update_voting_powers(
+        params,
+        update_offset,
+        validator_total_deltas,
+        validator_voting_power,
+        total_voting_power,
+        current_epoch,
+    )
+    .map_err(RedelegationError::VotingPowerOverflow)?;
+
+    Ok(redelegated)
+}
+
 /// Update validator set when a validator's receives a new bond and when its
 /// bond is unbonded (self-bond or delegation).
 fn update_validator_set<Address, TokenChange>(
@@ -1790,6 +3315,12 @@ fn update_validator_set<Address, TokenChange>(
                     address: validator.clone(),
                 };
 
+                // A jailed validator is not in the validator set
+                if !validator_set.inactive.contains(&validator_pre)
+                    && !validator_set.active.contains(&validator_pre)
+                {
+                    return;
+                }
                 if validator_set.inactive.contains(&validator_pre) {
                     let min_active_validator =
                         validator_set.active.first_shim();
@@ -1842,6 +3373,119 @@ fn update_validator_set<Address, TokenChange>(
     )
 }
 
+/// Remove a validator from the active or the inactive validator set from the
+/// pipeline epoch. A removed active validator is replaced by the inactive
+/// validator with the most voting power.
+fn remove_from_validator_set<Address>(
+    params: &PosParams,
+    validator: &Address,
+    validator_set: &mut ValidatorSets<Address>,
+    current_epoch: Epoch,
+) where
+    Address: Display
+        + Debug
+        + Clone
+        + PartialEq
+        + Eq
+        + PartialOrd
+        + Ord
+        + Hash
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+{
+    validator_set.update_from_offset(
+        |validator_set, _epoch| {
+            let find = |set: &BTreeSet<WeightedValidator<Address>>| {
+                set.iter().find(|v| v.address == *validator).cloned()
+            };
+            if let Some(active) = find(&validator_set.active) {
+                validator_set.active.remove(&active);
+                if let Some(activate_max) =
+                    validator_set.inactive.pop_last_shim()
+                {
+                    validator_set.active.insert(activate_max);
+                }
+            } else if let Some(inactive) = find(&validator_set.inactive) {
+                validator_set.inactive.remove(&inactive);
+            }
+        },
+        current_epoch,
+        DynEpochOffset::PipelineLen,
+        params,
+    )
+}
+
+/// Insert a validator into the active or the inactive validator set from the
+/// pipeline epoch with the voting power of its total deltas in each epoch. An
+/// inserted validator with more voting power than the active validator with
+/// the least voting power replaces it, when no validator slot is free.
+fn insert_into_validator_set<Address, TokenChange>(
+    params: &PosParams,
+    validator: &Address,
+    validator_set: &mut ValidatorSets<Address>,
+    validator_total_deltas: &ValidatorTotalDeltas<TokenChange>,
+    current_epoch: Epoch,
+) where
+    Address: Display
+        + Debug
+        + Clone
+        + PartialEq
+        + Eq
+        + PartialOrd
+        + Ord
+        + Hash
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+    TokenChange: Display
+        + Default
+        + Debug
+        + Clone
+        + Copy
+        + Add<Output = TokenChange>
+        + Sub
+        + Into<i128>
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+{
+    validator_set.update_from_offset(
+        |validator_set, epoch| {
+            let tokens: i128 =
+                validator_total_deltas.get(epoch).unwrap_or_default().into();
+            let tokens: u64 = TryFrom::try_from(tokens).unwrap();
+            let validator = WeightedValidator {
+                voting_power: VotingPower::from_tokens(tokens, params),
+                address: validator.clone(),
+            };
+            if validator_set.active.len() < params.max_validator_slots as usize
+            {
+                validator_set.active.insert(validator);
+                return;
+            }
+            let min_active_validator =
+                validator_set.active.first_shim().cloned();
+            match min_active_validator {
+                Some(min_active_validator)
+                    if validator.voting_power
+                        > min_active_validator.voting_power =>
+                {
+                    validator_set.active.remove(&min_active_validator);
+                    validator_set.inactive.insert(min_active_validator);
+                    validator_set.active.insert(validator);
+                }
+                _ => {
+                    validator_set.inactive.insert(validator);
+                }
+            }
+        },
+        current_epoch,
+        DynEpochOffset::PipelineLen,
+        params,
+    )
+}
+
 /// Update the validator's voting power and the total voting power.
 fn update_voting_powers<TokenChange>(
     params: &PosParams,
@@ -1985,3 +3629,108 @@ where
         slashed,
     })
 }
+
+/// Find the commission rate in the epoch before the pipeline epoch, from
+/// which a change of the commission rate at the pipeline epoch is limited by
+/// the validator's maximum change. For a new validator, whose rate is only
+/// set from the pipeline epoch, this is the rate at the pipeline epoch.
+pub fn prev_commission_rate(
+    params: &PosParams,
+    rates: &CommissionRates,
+    current_epoch: Epoch,
+) -> Option<BasisPoints> {
+    let pipeline_offset = DynEpochOffset::PipelineLen.value(params);
+    rates
+        .get(current_epoch + (pipeline_offset - 1))
+        .or_else(|| rates.get(current_epoch + pipeline_offset))
+        .copied()
+}
+
+/// Find the first epoch whose rewards a bond has yet to claim, i.e. the
+/// `claimed_epoch`, if any, or the first epoch in which any of the bond's
+/// deltas is at stake, whichever is later. The validator's rewards accrued
+/// before the first unclaimed epoch of all its bonds are no longer needed.
+pub fn first_unclaimed_epoch<TokenAmount>(
+    bond: &Bonds<TokenAmount>,
+    claimed_epoch: Option<Epoch>,
+) -> Option<Epoch>
+where
+    TokenAmount: Debug
+        + Default
+        + Clone
+        + AddAssign
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+{
+    let start = bond
+        .iter()
+        .flat_map(|bond| bond.deltas.keys())
+        .min()
+        .copied()?;
+    Some(claimed_epoch.map_or(start, |claimed| claimed.max(start)))
+}
+
+/// Find the rewards of a bond that have not been claimed yet, i.e. its
+/// shares of the validator's rewards accrued from the `claimed_epoch`, if
+/// any, and before the `current_epoch`. The share of an epoch's rewards is
+/// the bond's part of the validator's stake in the epoch. The validator's
+/// slashes are applied to the bond's deltas, so that the shares don't add
+/// up to more than the rewards.
+pub fn pending_rewards<TokenAmount>(
+    bond: &Bonds<TokenAmount>,
+    slashes: &[Slash],
+    rewards: &ValidatorRewards<TokenAmount>,
+    claimed_epoch: Option<Epoch>,
+    current_epoch: Epoch,
+) -> TokenAmount
+where
+    TokenAmount: Debug
+        + Default
+        + Clone
+        + Copy
+        + Add<Output = TokenAmount>
+        + AddAssign
+        + Into<u64>
+        + From<u64>
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+{
+    let from_epoch = claimed_epoch.unwrap_or_default();
+    if from_epoch >= current_epoch {
+        return TokenAmount::default();
+    }
+    // The bond's deltas with their start epochs, after the slashes
+    let deltas: Vec<(Epoch, u64)> = bond
+        .iter()
+        .flat_map(|bond| bond.deltas.iter())
+        .map(|(start, delta)| {
+            let mut delta: u64 = (*delta).into();
+            for slash in slashes {
+                if slash.epoch >= *start {
+                    delta -= slash.rate * delta;
+                }
+            }
+            (*start, delta)
+        })
+        .collect();
+    let mut pending: u128 = 0;
+    for (epoch, EpochRewards { rewards, stake }) in
+        rewards.range(from_epoch..current_epoch)
+    {
+        let stake: u64 = (*stake).into();
+        if stake == 0 {
+            continue;
+        }
+        let bonded: u64 = deltas
+            .iter()
+            .filter(|(start, _)| start <= epoch)
+            .map(|(_, delta)| delta)
+            .sum();
+        let rewards: u64 = (*rewards).into();
+        pending += u128::from(rewards) * u128::from(bonded.min(stake))
+            / u128::from(stake);
+    }
+    TokenAmount::from(u64::try_from(pending).unwrap_or(u64::MAX))
+}
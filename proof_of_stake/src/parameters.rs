@@ -32,6 +32,41 @@ pub struct PosParams {
     /// Portion of validator's stake that should be slashed on a light client
     /// attack. Given in basis points (slashed amount per ten thousand tokens).
     pub light_client_attack_slash_rate: BasisPoints,
+    /// Number of the last blocks in which the validators' signatures are
+    /// tracked to detect their downtime.
+    pub liveness_window_len: u64,
+    /// Maximum number of blocks in the liveness window that an active
+    /// validator may miss to sign. A validator that missed more blocks is
+    /// slashed and jailed at the end of the epoch.
+    pub liveness_max_missed_blocks: u64,
+    /// Portion of validator's stake that should be slashed on a downtime.
+    /// Given in basis points (slashed amount per ten thousand tokens).
+    pub downtime_slash_rate: BasisPoints,
+    /// Number of epochs after the epoch in which a validator is jailed
+    /// before it may be unjailed.
+    pub unjail_cooldown_len: u64,
+    /// Minimum amount of tokens of a validator's self-bond. A self-bond that
+    /// isn't fully unbonded must not get below it.
+    pub min_self_bond: u64,
+    /// Minimum amount of tokens of a delegation. A delegation that isn't
+    /// fully unbonded must not get below it.
+    pub min_delegation: u64,
+    /// Maximum annual inflation rate of the staking token, at which it's
+    /// minted into the staking rewards when no tokens are staked. Given in
+    /// basis points (minted amount per ten thousand tokens of the total
+    /// supply).
+    pub max_inflation_rate: BasisPoints,
+    /// Minimum annual inflation rate of the staking token, at which it's
+    /// minted into the staking rewards when at least the target staked ratio
+    /// of the total supply is staked. Given in basis points.
+    pub min_inflation_rate: BasisPoints,
+    /// Target ratio of the staked tokens to the total supply of the staking
+    /// token. Below it, the inflation rate rises linearly up to the maximum
+    /// inflation rate. Given in basis points.
+    pub target_staked_ratio: BasisPoints,
+    /// Number of epochs in a year, over which the annual inflation is minted
+    /// at the end of each epoch.
+    pub epochs_per_year: u64,
 }
 
 impl Default for PosParams {
@@ -48,6 +83,24 @@ impl Default for PosParams {
             duplicate_vote_slash_rate: BasisPoints::new(500),
             // slash 5%
             light_client_attack_slash_rate: BasisPoints::new(500),
+            liveness_window_len: 10_000,
+            // sign at least half of the blocks
+            liveness_max_missed_blocks: 5_000,
+            // slash 0.1%
+            downtime_slash_rate: BasisPoints::new(10),
+            unjail_cooldown_len: 2,
+            // 1 token
+            min_self_bond: 1_000_000,
+            // 1 token
+            min_delegation: 1_000_000,
+            // 10%
+            max_inflation_rate: BasisPoints::new(1_000),
+            // 2%
+            min_inflation_rate: BasisPoints::new(200),
+            // 2/3 of the total supply
+            target_staked_ratio: BasisPoints::new(6_667),
+            // epochs of a day
+            epochs_per_year: 365,
         }
     }
 }
@@ -69,6 +122,20 @@ pub enum ValidationError {
          pipeline: {1}"
     )]
     UnbondingLenTooShort(u64, u64),
+    #[error(
+        "Liveness max missed blocks must be < liveness window length. Got \
+         max missed blocks: {0}, window length: {1}"
+    )]
+    LivenessMaxMissedBlocksTooLarge(u64, u64),
+    #[error(
+        "Min inflation rate must be <= max inflation rate. Got min: {0}, \
+         max: {1}"
+    )]
+    MinInflationRateTooLarge(BasisPoints, BasisPoints),
+    #[error("Target staked ratio must be > 0 and <= 1, got {0}")]
+    InvalidTargetStakedRatio(BasisPoints),
+    #[error("Epochs per year must be > 0")]
+    EpochsPerYearZero,
 }
 
 /// From Tendermint: <https://github.com/tendermint/tendermint/blob/master/spec/abci/apps.md#updating-the-validator-set>
@@ -95,6 +162,32 @@ impl PosParams {
             ))
         }
 
+        if self.liveness_max_missed_blocks >= self.liveness_window_len {
+            errors.push(ValidationError::LivenessMaxMissedBlocksTooLarge(
+                self.liveness_max_missed_blocks,
+                self.liveness_window_len,
+            ))
+        }
+
+        if self.min_inflation_rate > self.max_inflation_rate {
+            errors.push(ValidationError::MinInflationRateTooLarge(
+                self.min_inflation_rate,
+                self.max_inflation_rate,
+            ))
+        }
+
+        if self.target_staked_ratio == BasisPoints::new(0)
+            || self.target_staked_ratio > BasisPoints::MAX_RATE
+        {
+            errors.push(ValidationError::InvalidTargetStakedRatio(
+                self.target_staked_ratio,
+            ))
+        }
+
+        if self.epochs_per_year == 0 {
+            errors.push(ValidationError::EpochsPerYearZero)
+        }
+
         // Check maximum total voting power cannot get larger than what
         // Tendermint allows
         let max_total_voting_power = self.max_validator_slots
@@ -121,6 +214,45 @@ impl PosParams {
 
         errors
     }
+
+    /// The annual inflation rate of the staking token for the given amount of
+    /// staked tokens of its total supply. It decreases linearly from the
+    /// maximum inflation rate when no tokens are staked to the minimum
+    /// inflation rate at the target staked ratio, above which it stays at the
+    /// minimum.
+    pub fn inflation_rate(
+        &self,
+        staked: u64,
+        total_supply: u64,
+    ) -> BasisPoints {
+        let max_rate: u64 = self.max_inflation_rate.into();
+        let min_rate: u64 = self.min_inflation_rate.into();
+        let min_rate = min_rate.min(max_rate);
+        let target: u64 = self.target_staked_ratio.into();
+        if target == 0 {
+            return BasisPoints::new(min_rate);
+        }
+        let staked_ratio: u64 =
+            BasisPoints::from_ratio(staked, total_supply).into();
+        let decrease = u128::from(max_rate - min_rate)
+            * u128::from(staked_ratio.min(target))
+            / u128::from(target);
+        // The decrease is at most `max_rate - min_rate`
+        BasisPoints::new(max_rate - decrease as u64)
+    }
+
+    /// The amount of the staking token minted at the end of an epoch at the
+    /// given annual inflation rate of its total supply.
+    pub fn epoch_inflation(&self, rate: BasisPoints, total_supply: u64) -> u64 {
+        if self.epochs_per_year == 0 {
+            return 0;
+        }
+        let rate: u64 = rate.into();
+        let minted = u128::from(total_supply) * u128::from(rate)
+            / 10_000
+            / u128::from(self.epochs_per_year);
+        u64::try_from(minted).unwrap_or(u64::MAX)
+    }
 }
 
 #[cfg(test)]
@@ -143,6 +275,42 @@ mod tests {
             );
         }
     }
+
+    /// Test that the inflation rate decreases linearly from the max rate to
+    /// the min rate at the target staked ratio and stays at it above the
+    /// target, and that the annual inflation is split over the epochs.
+    #[test]
+    fn test_inflation_rate() {
+        let params = PosParams {
+            max_inflation_rate: BasisPoints::new(1_000),
+            min_inflation_rate: BasisPoints::new(200),
+            target_staked_ratio: BasisPoints::new(5_000),
+            epochs_per_year: 100,
+            ..Default::default()
+        };
+        let supply = 1_000_000;
+        assert_eq!(params.inflation_rate(0, supply), BasisPoints::new(1_000));
+        assert_eq!(
+            params.inflation_rate(250_000, supply),
+            BasisPoints::new(600)
+        );
+        assert_eq!(
+            params.inflation_rate(500_000, supply),
+            BasisPoints::new(200)
+        );
+        assert_eq!(
+            params.inflation_rate(900_000, supply),
+            BasisPoints::new(200)
+        );
+        assert_eq!(params.inflation_rate(0, 0), BasisPoints::new(1_000));
+
+        assert_eq!(
+            params.epoch_inflation(BasisPoints::new(1_000), supply),
+            1_000
+        );
+        assert_eq!(params.epoch_inflation(BasisPoints::new(1_000), 0), 0);
+        assert!(params.validate().is_empty());
+    }
 }
 
 /// Testing helpers
@@ -167,6 +335,9 @@ pub mod testing {
                 pipeline_len,
                 unbonding_len,
                 votes_per_token: BasisPoints::new(votes_per_token),
+                // The generated bonds and unbonds may be of any amount
+                min_self_bond: 0,
+                min_delegation: 0,
                 // The rest of the parameters that are not being used in the PoS
                 // VP are constant for now
                 ..Default::default()
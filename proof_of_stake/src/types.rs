@@ -1,14 +1,16 @@
 //! Proof of Stake data types
 
 use core::fmt::Debug;
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::TryFrom;
 use std::fmt::Display;
 use std::hash::Hash;
 use std::num::TryFromIntError;
 use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
+use std::str::FromStr;
 
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use thiserror::Error;
 
 use crate::epoched::{
     Epoched, EpochedDelta, OffsetPipelineLen, OffsetUnboundingLen,
@@ -20,6 +22,8 @@ pub type ValidatorConsensusKeys<PublicKey> =
     Epoched<PublicKey, OffsetPipelineLen>;
 /// Epoched validator's state.
 pub type ValidatorStates = Epoched<ValidatorState, OffsetPipelineLen>;
+/// Epoched validator's commission rate.
+pub type CommissionRates = Epoched<BasisPoints, OffsetPipelineLen>;
 /// Epoched validator's total deltas.
 pub type ValidatorTotalDeltas<TokenChange> =
     EpochedDelta<TokenChange, OffsetUnboundingLen>;
@@ -38,6 +42,10 @@ pub type ValidatorSets<Address> =
 /// Epoched total voting power.
 pub type TotalVotingPowers =
     EpochedDelta<VotingPowerDelta, OffsetUnboundingLen>;
+/// Rewards of validator's bonds accrued in each epoch, from the first epoch
+/// whose rewards some bond to the validator has yet to claim.
+pub type ValidatorRewards<TokenAmount> =
+    BTreeMap<Epoch, EpochRewards<TokenAmount>>;
 
 /// Epoch identifier. Epochs are identified by consecutive natural numbers.
 ///
@@ -118,6 +126,11 @@ pub struct GenesisValidator<Address, Token, PK> {
     pub consensus_key: PK,
     /// An public key associated with the staking reward address
     pub staking_reward_key: PK,
+    /// A portion of the validator's rewards credited to the staking reward
+    /// address, before the rest is shared by the validator's bonds
+    pub commission_rate: BasisPoints,
+    /// A maximum change of the commission rate per epoch
+    pub max_commission_rate_change: BasisPoints,
 }
 
 /// An update of the active and inactive validator set.
@@ -260,6 +273,35 @@ where
     pub inactive: BTreeSet<WeightedValidator<Address>>,
 }
 
+/// The validator set of an epoch with the consensus keys of its validators
+/// in that epoch. Unlike the epoched data, which only keeps the values from
+/// its last update onwards, it is kept for the past epochs, e.g. for the light
+/// clients and the verification of the evidence of misbehaviours.
+#[derive(
+    Debug, Clone, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema,
+)]
+pub struct ValidatorSetSnapshot<Address, PublicKey>
+where
+    Address: Debug
+        + Clone
+        + PartialEq
+        + Eq
+        + PartialOrd
+        + Ord
+        + Hash
+        + BorshDeserialize
+        + BorshSchema
+        + BorshSerialize,
+    PublicKey: Debug + Clone + BorshDeserialize + BorshSchema + BorshSerialize,
+{
+    /// The active and the inactive validators of the epoch
+    pub validator_set: ValidatorSet<Address>,
+    /// The consensus keys of the validators in the epoch
+    pub consensus_keys: BTreeMap<Address, PublicKey>,
+    /// The total voting power of the validators in the epoch
+    pub total_voting_power: VotingPower,
+}
+
 /// Validator's state.
 #[derive(
     Debug,
@@ -279,7 +321,14 @@ pub enum ValidatorState {
     /// A `Candidate` validator may participate in the consensus. It is either
     /// in the active or inactive validator set.
     Candidate,
-    // TODO consider adding `Jailed`
+    /// A `Jailed` validator may not participate in the consensus and it's
+    /// not in the validator set. It may be unjailed once
+    /// `unjail_cooldown_len` epochs passed from the epoch in which it was
+    /// jailed.
+    Jailed {
+        /// The epoch in which the validator was jailed
+        epoch: Epoch,
+    },
 }
 
 /// A bond is validator's self-bond or a delegation from a regular account to a
@@ -310,6 +359,51 @@ pub struct Unbond<Token: Default> {
     pub deltas: HashMap<(Epoch, Epoch), Token>,
 }
 
+/// A redelegation of a delegator's bonded tokens from a validator to another
+/// validator, without unbonding them. The redelegated tokens remain slashable
+/// for the infractions of the source validator committed before the tokens
+/// left its stake, until the end of the unbonding period of the redelegation.
+#[derive(
+    Debug, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize, BorshSchema,
+)]
+pub struct Redelegation<Address, Token> {
+    /// The owner of the redelegated tokens
+    pub source: Address,
+    /// The validator from which the tokens were redelegated
+    pub src_validator: Address,
+    /// The validator to which the tokens were redelegated
+    pub dest_validator: Address,
+    /// The epoch in which the tokens were redelegated
+    pub epoch: Epoch,
+    /// The redelegated amount, which is reduced by the slashes of the source
+    /// validator applied after the redelegation
+    pub amount: Token,
+}
+
+/// The redelegations from a validator, or to a validator, that are still
+/// slashable for the infractions of their source validators.
+pub type Redelegations<Address, Token> = Vec<Redelegation<Address, Token>>;
+
+/// The rewards accrued to a validator's bonds in an epoch, after the
+/// validator's commission. They are shared by the bonds in proportion to
+/// their part of the validator's stake in the epoch.
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    Eq,
+    BorshDeserialize,
+    BorshSerialize,
+    BorshSchema,
+)]
+pub struct EpochRewards<Token> {
+    /// The rewards accrued in the epoch
+    pub rewards: Token,
+    /// The validator's stake in the epoch
+    pub stake: Token,
+}
+
 /// A slash applied to validator, to punish byzantine behavior by removing
 /// their staked tokens at and before the epoch of the slash.
 #[derive(Debug, Clone, BorshDeserialize, BorshSerialize, BorshSchema)]
@@ -335,6 +429,58 @@ pub enum SlashType {
     DuplicateVote,
     /// Light client attack.
     LightClientAttack,
+    /// Missing to sign too many blocks in the liveness window.
+    Downtime,
+}
+
+/// The blocks that a validator missed to sign in the window of the last
+/// blocks whose liveness is tracked. A block is recorded at the position of
+/// its height modulo the window length, which replaces the block at the same
+/// position in the previous window.
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    Eq,
+    BorshDeserialize,
+    BorshSerialize,
+    BorshSchema,
+)]
+pub struct ValidatorLiveness {
+    /// The length of the window in blocks
+    window_len: u64,
+    /// A bitmap of the missed blocks of the window
+    missed: Vec<u8>,
+    /// The number of the missed blocks of the window
+    missed_count: u64,
+}
+
+/// The maximum length in bytes of a validator's moniker
+pub const MAX_MONIKER_LEN: usize = 64;
+
+/// The maximum length in bytes of a validator's website and contact
+pub const MAX_METADATA_FIELD_LEN: usize = 256;
+
+/// The display metadata of a validator, which the explorers can show without
+/// an off-chain registry.
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    Eq,
+    BorshDeserialize,
+    BorshSerialize,
+    BorshSchema,
+)]
+pub struct ValidatorMetadata {
+    /// The display name of the validator
+    pub moniker: String,
+    /// The website of the validator
+    pub website: Option<String>,
+    /// The contact of the validator, e.g. an e-mail address
+    pub contact: Option<String>,
 }
 
 /// ‱ (Parts per ten thousand). This can be multiplied by any type that
@@ -702,6 +848,26 @@ where
     }
 }
 
+impl<Address, Token> Redelegation<Address, Token> {
+    /// The epoch from which the redelegated tokens are no longer in the stake
+    /// of the source validator. The source validator's infractions committed
+    /// before this epoch are slashed from the redelegated tokens.
+    pub fn src_stake_end(&self, params: &PosParams) -> Epoch {
+        self.epoch + params.pipeline_len
+    }
+
+    /// Check if the redelegated tokens are still slashable for the
+    /// infractions of the source validator in the given epoch, i.e. if the
+    /// unbonding period of the redelegation has not ended yet.
+    pub fn is_slashable(
+        &self,
+        params: &PosParams,
+        current_epoch: Epoch,
+    ) -> bool {
+        current_epoch < self.epoch + params.unbonding_len
+    }
+}
+
 impl SlashType {
     /// Get the slash rate applicable to the given slash type from the PoS
     /// parameters.
@@ -711,6 +877,7 @@ impl SlashType {
             SlashType::LightClientAttack => {
                 params.light_client_attack_slash_rate
             }
+            SlashType::Downtime => params.downtime_slash_rate,
         }
     }
 }
@@ -720,15 +887,153 @@ impl Display for SlashType {
         match self {
             SlashType::DuplicateVote => write!(f, "Duplicate vote"),
             SlashType::LightClientAttack => write!(f, "Light client attack"),
+            SlashType::Downtime => write!(f, "Downtime"),
+        }
+    }
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    #[error("Unknown slash type {0}")]
+    UnknownSlashType(String),
+    #[error("Invalid basis points {0}")]
+    InvalidBasisPoints(String),
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MetadataError {
+    #[error("The moniker must not be empty")]
+    EmptyMoniker,
+    #[error(
+        "The {field} is {len} bytes long, which is more than the maximum \
+         {max_len} bytes"
+    )]
+    TooLong {
+        field: &'static str,
+        len: usize,
+        max_len: usize,
+    },
+    #[error("The {0} must not contain control characters")]
+    ControlCharacter(&'static str),
+}
+
+/// Parsed from its [`Display`] string
+impl FromStr for SlashType {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Duplicate vote" => Ok(SlashType::DuplicateVote),
+            "Light client attack" => Ok(SlashType::LightClientAttack),
+            "Downtime" => Ok(SlashType::Downtime),
+            _ => Err(ParseError::UnknownSlashType(s.to_string())),
+        }
+    }
+}
+
+impl ValidatorLiveness {
+    /// Record whether the validator signed the block at the given height in a
+    /// window of the given length. A change of the window length clears the
+    /// recorded blocks.
+    pub fn record(&mut self, window_len: u64, height: u64, signed: bool) {
+        if window_len == 0 {
+            return;
+        }
+        if self.window_len != window_len {
+            let bytes = (window_len + 7) / 8;
+            *self = Self {
+                window_len,
+                missed: vec![0; bytes as usize],
+                missed_count: 0,
+            };
+        }
+        let index = height % window_len;
+        let byte = &mut self.missed[(index / 8) as usize];
+        let mask = 1_u8 << (index % 8);
+        let was_missed = *byte & mask != 0;
+        match (was_missed, signed) {
+            (false, false) => {
+                *byte |= mask;
+                self.missed_count += 1;
+            }
+            (true, true) => {
+                *byte &= !mask;
+                self.missed_count -= 1;
+            }
+            _ => {}
+        }
+    }
+
+    /// The number of the missed blocks in the window
+    pub fn missed_count(&self) -> u64 {
+        self.missed_count
+    }
+}
+
+impl ValidatorMetadata {
+    /// Check that the moniker is not empty and that the fields are within
+    /// their maximum lengths and free of control characters.
+    pub fn validate(&self) -> Result<(), MetadataError> {
+        if self.moniker.is_empty() {
+            return Err(MetadataError::EmptyMoniker);
+        }
+        let fields = [
+            ("moniker", Some(&self.moniker), MAX_MONIKER_LEN),
+            ("website", self.website.as_ref(), MAX_METADATA_FIELD_LEN),
+            ("contact", self.contact.as_ref(), MAX_METADATA_FIELD_LEN),
+        ];
+        for (field, value, max_len) in fields {
+            let value = match value {
+                Some(value) => value,
+                None => continue,
+            };
+            if value.len() > max_len {
+                return Err(MetadataError::TooLong {
+                    field,
+                    len: value.len(),
+                    max_len,
+                });
+            }
+            if value.chars().any(char::is_control) {
+                return Err(MetadataError::ControlCharacter(field));
+            }
         }
+        Ok(())
     }
 }
 
 impl BasisPoints {
+    /// 100%, the maximum of a rate, e.g. of a commission rate.
+    pub const MAX_RATE: Self = Self(10_000);
+
     /// Initialize basis points from an integer.
-    pub fn new(value: u64) -> Self {
+    pub const fn new(value: u64) -> Self {
         Self(value)
     }
+
+    /// The absolute difference of two basis points.
+    pub fn abs_diff(self, other: Self) -> Self {
+        Self(self.0.abs_diff(other.0))
+    }
+
+    /// The basis points of the ratio of the `part` to the `total`, rounded
+    /// down, or zero if the `total` is zero. The ratio may be greater than
+    /// 100%.
+    pub fn from_ratio(part: u64, total: u64) -> Self {
+        if total == 0 {
+            return Self(0);
+        }
+        let ratio = u128::from(part) * 10_000 / u128::from(total);
+        Self(u64::try_from(ratio).unwrap_or(u64::MAX))
+    }
+}
+
+impl From<BasisPoints> for u64 {
+    fn from(value: BasisPoints) -> Self {
+        value.0
+    }
 }
 
 impl Display for BasisPoints {
@@ -737,6 +1042,18 @@ impl Display for BasisPoints {
     }
 }
 
+/// Parsed from its [`Display`] string, with or without the `‱` sign
+impl FromStr for BasisPoints {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim_end_matches('‱')
+            .parse()
+            .map(Self)
+            .map_err(|_| ParseError::InvalidBasisPoints(s.to_string()))
+    }
+}
+
 impl Mul<u64> for BasisPoints {
     type Output = u64;
 
@@ -768,4 +1085,65 @@ pub mod tests {
     pub fn arb_epoch(range: Range<u64>) -> impl Strategy<Value = Epoch> {
         range.prop_map(Epoch)
     }
+
+    /// Test that the missed blocks are counted in the window and replaced by
+    /// the blocks at the same position in the next window.
+    #[test]
+    fn test_validator_liveness() {
+        let mut liveness = ValidatorLiveness::default();
+        for height in 0..10 {
+            liveness.record(10, height, height % 3 != 0);
+        }
+        // Missed the heights 0, 3, 6 and 9
+        assert_eq!(liveness.missed_count(), 4);
+        // Recording the same block again doesn't change the count
+        liveness.record(10, 3, false);
+        assert_eq!(liveness.missed_count(), 4);
+        // The next window replaces the heights 0 and 1
+        liveness.record(10, 10, true);
+        liveness.record(10, 11, false);
+        assert_eq!(liveness.missed_count(), 4);
+        // A new window length clears the recorded blocks
+        liveness.record(20, 12, false);
+        assert_eq!(liveness.missed_count(), 1);
+    }
+
+    /// Test that the metadata with an empty moniker, too long fields or
+    /// control characters is invalid.
+    #[test]
+    fn test_validate_validator_metadata() {
+        let metadata = ValidatorMetadata {
+            moniker: "validator".to_string(),
+            website: Some("https://validator.example".to_string()),
+            contact: None,
+        };
+        assert_eq!(metadata.validate(), Ok(()));
+
+        let empty = ValidatorMetadata {
+            moniker: String::new(),
+            ..metadata.clone()
+        };
+        assert_eq!(empty.validate(), Err(MetadataError::EmptyMoniker));
+
+        let too_long = ValidatorMetadata {
+            moniker: "v".repeat(MAX_MONIKER_LEN + 1),
+            ..metadata.clone()
+        };
+        assert!(matches!(
+            too_long.validate(),
+            Err(MetadataError::TooLong {
+                field: "moniker",
+                ..
+            })
+        ));
+
+        let control = ValidatorMetadata {
+            contact: Some("line\nbreak".to_string()),
+            ..metadata
+        };
+        assert_eq!(
+            control.validate(),
+            Err(MetadataError::ControlCharacter("contact"))
+        );
+    }
 }
@@ -14,11 +14,15 @@ use crate::btree_set::BTreeSetShims;
 use crate::epoched::DynEpochOffset;
 use crate::parameters::PosParams;
 use crate::types::{
-    BondId, Bonds, Epoch, Slashes, TotalVotingPowers, Unbonds,
-    ValidatorConsensusKeys, ValidatorSets, ValidatorState, ValidatorStates,
+    BasisPoints, BondId, Bonds, CommissionRates, Epoch, Redelegation,
+    Redelegations, Slashes, TotalVotingPowers, Unbonds, ValidatorConsensusKeys,
+    ValidatorRewards, ValidatorSets, ValidatorState, ValidatorStates,
     ValidatorTotalDeltas, ValidatorVotingPowers, VotingPower, VotingPowerDelta,
     WeightedValidator,
 };
+use crate::{
+    pending_rewards, prev_commission_rate, slashable_redelegated_amount,
+};
 
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
@@ -47,6 +51,10 @@ where
     MissingNewValidatorConsensusKey(u64),
     #[error("Invalid validator consensus key update in epoch {0}")]
     InvalidValidatorConsensusKeyUpdate(u64),
+    #[error(
+        "The consensus key of validator {0} is already used by validator {1}"
+    )]
+    DuplicateConsensusKey(Address, Address),
     #[error("Validator staking reward address is required for validator {0}")]
     StakingRewardAddressIsRequired(Address),
     #[error(
@@ -62,14 +70,43 @@ where
     MissingBalance,
     #[error("Last update should be equal to the current epoch")]
     InvalidLastUpdate,
+    #[error("Missing new validator commission rate in epoch {0}")]
+    MissingNewValidatorCommissionRate(u64),
+    #[error("Invalid validator commission rate update in epoch {0}")]
+    InvalidCommissionRateUpdate(u64),
+    #[error("Invalid commission rate {0}")]
+    InvalidCommissionRate(BasisPoints),
+    #[error(
+        "The commission rate change {change} is greater than the maximum \
+         change {max_change:?}"
+    )]
+    CommissionRateChangeTooLarge {
+        change: BasisPoints,
+        max_change: Option<BasisPoints>,
+    },
+    #[error("Invalid update of the maximum commission rate change of {0}")]
+    InvalidMaxCommissionRateChangeUpdate(Address),
+    #[error(
+        "The rewards of {id} claimed before epoch {got} must be claimed \
+         before the current epoch {expected}"
+    )]
+    InvalidRewardsClaimedEpoch {
+        id: BondId<Address>,
+        got: u64,
+        expected: u64,
+    },
+    #[error("The claimed epoch of the rewards of {0} must not be deleted")]
+    RewardsClaimedEpochDeleted(BondId<Address>),
     #[error(
         "Invalid staking token balances. Balance Δ {balance_delta}, bonds Δ \
-         {bond_delta}, unbonds Δ {unbond_delta}"
+         {bond_delta}, unbonds Δ {unbond_delta}, claimed rewards \
+         {claimed_rewards}"
     )]
     InvalidBalances {
         balance_delta: TokenChange,
         bond_delta: TokenChange,
         unbond_delta: TokenChange,
+        claimed_rewards: TokenChange,
     },
     #[error(
         "Data must be set or updated in the correct epoch. Got epoch {got}, \
@@ -78,6 +115,24 @@ where
     EpochedDataWrongEpoch { got: u64, expected: Vec<u64> },
     #[error("Empty bond {0} must be deleted")]
     EmptyBond(BondId<Address>),
+    #[error(
+        "The self-bond {id} of {amount} must be fully unbonded or at least \
+         the minimum self-bond {min}"
+    )]
+    SelfBondBelowMinimum {
+        id: BondId<Address>,
+        amount: u64,
+        min: u64,
+    },
+    #[error(
+        "The delegation {id} of {amount} must be fully unbonded or at least \
+         the minimum delegation {min}"
+    )]
+    DelegationBelowMinimum {
+        id: BondId<Address>,
+        amount: u64,
+        min: u64,
+    },
     #[error(
         "Bond ID {id} must start at the correct epoch. Got epoch {got}, \
          expected {expected}"
@@ -163,6 +218,45 @@ where
     NewValidatorMissingInValidatorSet(Address),
     #[error("Validator set has not been updated for new validators.")]
     MissingValidatorSetUpdate,
+    #[error("Invalid redelegations update of validator {0}")]
+    InvalidRedelegationsUpdate(Address),
+    #[error(
+        "The redelegation from validator {0} to validator {1} must be \
+         recorded for both validators"
+    )]
+    UnmatchedRedelegation(Address, Address),
+    #[error(
+        "The redelegated bond {id} changed by {got}, expected the redelegated \
+         {expected}"
+    )]
+    InvalidRedelegatedBondDelta {
+        id: BondId<Address>,
+        got: TokenChange,
+        expected: TokenChange,
+    },
+    #[error(
+        "Validator {address} total deltas decreased by {decrease} at the \
+         pipeline epoch, but only {redelegated} was redelegated from it"
+    )]
+    TotalDeltasDecreaseNotRedelegated {
+        address: Address,
+        decrease: TokenChange,
+        redelegated: TokenChange,
+    },
+    #[error(
+        "The bond {id} of {bonded} must not be lower than its redelegated \
+         tokens {slashable} that are still slashable"
+    )]
+    SlashableRedelegation {
+        id: BondId<Address>,
+        bonded: u64,
+        slashable: u64,
+    },
+    #[error(
+        "Validator {0} is unjailed before the end of its cooldown in epoch \
+         {1}"
+    )]
+    UnjailCooldownNotPassed(Address, u64),
 }
 
 /// An update of PoS data.
@@ -216,6 +310,8 @@ where
         data: Data<Bonds<TokenAmount>>,
         /// List of slashes applied to the bond's validator
         slashes: Slashes,
+        /// The redelegations to the bond's validator in the prior state
+        redelegations: Redelegations<Address, TokenAmount>,
     },
     /// Unbond update
     Unbond {
@@ -226,6 +322,20 @@ where
         /// List of slashes applied to the bond's validator
         slashes: Slashes,
     },
+    /// Update of the epoch before which the rewards of a bond have been
+    /// claimed
+    RewardsClaim {
+        /// Bond ID
+        id: BondId<Address>,
+        /// Claimed epoch prior and posterior state
+        data: Data<Epoch>,
+        /// The bond in the prior state
+        bond: Option<Bonds<TokenAmount>>,
+        /// List of slashes applied to the bond's validator
+        slashes: Slashes,
+        /// The rewards of the bond's validator in the prior state
+        rewards: ValidatorRewards<TokenAmount>,
+    },
     /// A validator update
     Validator {
         /// Validator's address
@@ -233,6 +343,20 @@ where
         /// Validator's data update
         update: ValidatorUpdate<Address, TokenChange, PublicKey>,
     },
+    /// Update of the redelegations from a validator
+    OutgoingRedelegations {
+        /// The validator from which the tokens are redelegated
+        validator: Address,
+        /// Redelegations prior and posterior state
+        data: Data<Redelegations<Address, TokenAmount>>,
+    },
+    /// Update of the redelegations to a validator
+    IncomingRedelegations {
+        /// The validator to which the tokens are redelegated
+        validator: Address,
+        /// Redelegations prior and posterior state
+        data: Data<Redelegations<Address, TokenAmount>>,
+    },
     /// Validator set update
     ValidatorSet(Data<ValidatorSets<Address>>),
     /// Total voting power update
@@ -266,15 +390,40 @@ where
     PublicKey: Debug + Clone + BorshDeserialize + BorshSerialize + BorshSchema,
 {
     /// Validator's state update
-    State(Data<ValidatorStates>),
+    State {
+        /// Validator state prior and posterior state
+        data: Data<ValidatorStates>,
+        /// The validator's total deltas in the posterior state
+        total_deltas: Option<ValidatorTotalDeltas<TokenChange>>,
+    },
     /// Consensus key update
-    ConsensusKey(Data<ValidatorConsensusKeys<PublicKey>>),
+    ConsensusKey {
+        /// Consensus key prior and posterior state
+        data: Data<ValidatorConsensusKeys<PublicKey>>,
+        /// The posterior consensus keys of the other validators in the
+        /// validator set from the current epoch up to the pipeline epoch
+        other_keys: Vec<(Address, ValidatorConsensusKeys<PublicKey>)>,
+    },
     /// Staking reward address update
     StakingRewardAddress(Data<Address>),
     /// Total deltas update
-    TotalDeltas(Data<ValidatorTotalDeltas<TokenChange>>),
+    TotalDeltas {
+        /// Total deltas prior and posterior state
+        data: Data<ValidatorTotalDeltas<TokenChange>>,
+        /// The validator's state in the posterior state
+        state: Option<ValidatorStates>,
+    },
     /// Voting power update
     VotingPowerUpdate(Data<ValidatorVotingPowers>),
+    /// Commission rate update
+    CommissionRate {
+        /// Commission rate prior and posterior state
+        data: Data<CommissionRates>,
+        /// The maximum change of the commission rate per epoch, if any
+        max_change: Option<BasisPoints>,
+    },
+    /// Maximum commission rate change update
+    MaxCommissionRateChange(Data<BasisPoints>),
 }
 
 /// Data update with prior and posterior state.
@@ -299,6 +448,8 @@ pub struct NewValidator {
     has_voting_power: bool,
     has_staking_reward_address: bool,
     has_address_raw_hash: bool,
+    has_commission_rate: bool,
+    has_max_commission_rate_change: bool,
     voting_power: VotingPower,
 }
 
@@ -376,6 +527,8 @@ where
     let mut errors = vec![];
 
     let mut balance_delta = TokenChange::default();
+    // Rewards claimed from the PoS account
+    let mut claimed_rewards = TokenChange::default();
     // Changes of validators' bonds
     let mut bond_delta: HashMap<Address, TokenChange> = HashMap::default();
     // Changes of validators' unbonds
@@ -409,11 +562,87 @@ where
     > = HashMap::default();
 
     let mut new_validators: HashMap<Address, NewValidator> = HashMap::default();
+    // The jailed validators that are unjailed from the pipeline epoch, with
+    // their total deltas
+    let mut unjailed_validators: HashMap<
+        Address,
+        ValidatorTotalDeltas<TokenChange>,
+    > = HashMap::default();
+    // The posterior states of the validators whose total deltas changed
+    let mut validator_states: HashMap<Address, ValidatorStates> =
+        HashMap::default();
+    // Changes of bonds by their ID
+    let mut bond_delta_by_id: HashMap<BondId<Address>, TokenChange> =
+        HashMap::default();
+    // Decreases of validators' total deltas at the pipeline epoch, which can
+    // only come from redelegations
+    let mut pipeline_decrease: HashMap<Address, TokenChange> =
+        HashMap::default();
+    // Redelegations added to the validators' outgoing and incoming
+    // redelegations
+    let mut new_outgoing: Vec<Redelegation<Address, TokenAmount>> = vec![];
+    let mut new_incoming: Vec<Redelegation<Address, TokenAmount>> = vec![];
+
+    // Find the error of a bond that isn't deleted, if its amount at the
+    // pipeline epoch is lower than the minimum self-bond or delegation
+    let bond_below_minimum =
+        |id: &BondId<Address>, post: &Bonds<TokenAmount>| {
+            let amount: u64 = post
+                .get(pipeline_epoch)
+                .map(|bond| bond.sum())
+                .unwrap_or_default()
+                .into();
+            if id.source == id.validator {
+                if amount < params.min_self_bond {
+                    return Some(Error::SelfBondBelowMinimum {
+                        id: id.clone(),
+                        amount,
+                        min: params.min_self_bond,
+                    });
+                }
+            } else if amount < params.min_delegation {
+                return Some(Error::DelegationBelowMinimum {
+                    id: id.clone(),
+                    amount,
+                    min: params.min_delegation,
+                });
+            }
+            None
+        };
+
+    // Find the error of a decreased bond, if its amount at the pipeline epoch
+    // is lower than the tokens redelegated to its validator that are still
+    // slashable for the validators from which they were redelegated
+    let bond_below_slashable =
+        |id: &BondId<Address>,
+         post: Option<&Bonds<TokenAmount>>,
+         redelegations: &Redelegations<Address, TokenAmount>| {
+            let slashable: u64 = slashable_redelegated_amount(
+                params,
+                redelegations,
+                &id.source,
+                current_epoch,
+            )
+            .into();
+            let bonded: u64 = post
+                .and_then(|post| post.get(pipeline_epoch))
+                .map(|bond| bond.sum())
+                .unwrap_or_default()
+                .into();
+            if bonded < slashable {
+                return Some(Error::SlashableRedelegation {
+                    id: id.clone(),
+                    bonded,
+                    slashable,
+                });
+            }
+            None
+        };
 
     for change in changes {
         match change {
             Validator { address, update } => match update {
-                State(data) => match (data.pre, data.post) {
+                State { data, total_deltas } => match (data.pre, data.post) {
                     (None, Some(post)) => {
                         if post.last_update() != current_epoch {
                             errors.push(Error::InvalidLastUpdate)
@@ -477,6 +706,31 @@ where
                                 Some(Inactive),
                                 Some(Candidate) | Some(Pending),
                             ) => {}
+                            // A jailed validator may be unjailed once its
+                            // cooldown has passed
+                            (Some(Jailed { epoch }), Some(Candidate)) => {
+                                let unjail_epoch =
+                                    *epoch + params.unjail_cooldown_len;
+                                if current_epoch < unjail_epoch {
+                                    errors.push(Error::UnjailCooldownNotPassed(
+                                        address.clone(),
+                                        unjail_epoch.into(),
+                                    ))
+                                }
+                                match total_deltas.as_ref() {
+                                    Some(total_deltas) => {
+                                        unjailed_validators.insert(
+                                            address.clone(),
+                                            total_deltas.clone(),
+                                        );
+                                    }
+                                    None => errors.push(
+                                        Error::MissingValidatorTotalDeltas(
+                                            address.clone(),
+                                        ),
+                                    ),
+                                }
+                            }
                             _ => errors.push(Error::InvalidNewValidatorState(
                                 pipeline_epoch.into(),
                             )),
@@ -486,50 +740,102 @@ where
                         .push(Error::ValidatorStateIsRequired(address.clone())),
                     (None, None) => continue,
                 },
-                ConsensusKey(data) => match (data.pre, data.post) {
-                    (None, Some(post)) => {
-                        if post.last_update() != current_epoch {
-                            errors.push(Error::InvalidLastUpdate)
-                        }
-                        // The value must be known at pipeline epoch
-                        match post.get(pipeline_epoch) {
-                            Some(_) => {}
-                            _ => errors.push(
-                                Error::MissingNewValidatorConsensusKey(
-                                    pipeline_epoch.into(),
-                                ),
-                            ),
-                        }
-                        let validator =
-                            new_validators.entry(address.clone()).or_default();
-                        validator.has_consensus_key = true;
-                    }
-                    (Some(pre), Some(post)) => {
-                        if post.last_update() != current_epoch {
-                            errors.push(Error::InvalidLastUpdate)
-                        }
-                        // Before pipeline epoch, the key must not change
-                        for epoch in
-                            Epoch::iter_range(current_epoch, pipeline_offset)
-                        {
-                            match (pre.get(epoch), post.get(epoch)) {
-                                (Some(key_pre), Some(key_post))
-                                    if key_pre == key_post =>
-                                {
-                                    continue;
+                ConsensusKey { data, other_keys } => {
+                    // Is the key used from the current epoch up to the
+                    // pipeline epoch?
+                    let is_key_used =
+                        |keys: &ValidatorConsensusKeys<PublicKey>,
+                         key: &PublicKey| {
+                            Epoch::iter_range(
+                                current_epoch,
+                                pipeline_offset + 1,
+                            )
+                            .any(|epoch| keys.get(epoch) == Some(key))
+                        };
+                    // A key may only be used by one validator
+                    let key_user = |key: &PublicKey| {
+                        other_keys
+                            .iter()
+                            .find(|(_, keys)| is_key_used(keys, key))
+                            .map(|(other, _)| other.clone())
+                    };
+                    match (data.pre, data.post) {
+                        (None, Some(post)) => {
+                            if post.last_update() != current_epoch {
+                                errors.push(Error::InvalidLastUpdate)
+                            }
+                            // The value must be known at pipeline epoch
+                            match post.get(pipeline_epoch) {
+                                Some(key) => {
+                                    if let Some(other) = key_user(key) {
+                                        errors.push(
+                                            Error::DuplicateConsensusKey(
+                                                address.clone(),
+                                                other,
+                                            ),
+                                        )
+                                    }
                                 }
                                 _ => errors.push(
-                                    Error::InvalidValidatorConsensusKeyUpdate(
-                                        epoch.into(),
+                                    Error::MissingNewValidatorConsensusKey(
+                                        pipeline_epoch.into(),
                                     ),
                                 ),
                             }
+                            let validator = new_validators
+                                .entry(address.clone())
+                                .or_default();
+                            validator.has_consensus_key = true;
                         }
+                        (Some(pre), Some(post)) => {
+                            if post.last_update() != current_epoch {
+                                errors.push(Error::InvalidLastUpdate)
+                            }
+                            // Before pipeline epoch, the key must not change
+                            for epoch in Epoch::iter_range(
+                                current_epoch,
+                                pipeline_offset,
+                            ) {
+                                match (pre.get(epoch), post.get(epoch)) {
+                                    (Some(key_pre), Some(key_post))
+                                        if key_pre == key_post =>
+                                    {
+                                        continue;
+                                    }
+                                    _ => errors.push(
+                                        Error::InvalidValidatorConsensusKeyUpdate(
+                                            epoch.into(),
+                                        ),
+                                    ),
+                                }
+                            }
+                            // A new key at pipeline epoch must not be used by
+                            // any validator, including the validator's own
+                            // keys that are still in use
+                            if let Some(key) = post.get(pipeline_epoch) {
+                                if pre.get(pipeline_epoch) != Some(key) {
+                                    let other = if is_key_used(&pre, key) {
+                                        Some(address.clone())
+                                    } else {
+                                        key_user(key)
+                                    };
+                                    if let Some(other) = other {
+                                        errors.push(
+                                            Error::DuplicateConsensusKey(
+                                                address.clone(),
+                                                other,
+                                            ),
+                                        )
+                                    }
+                                }
+                            }
+                        }
+                        (Some(_), None) => errors.push(
+                            Error::ValidatorStateIsRequired(address.clone()),
+                        ),
+                        (None, None) => continue,
                     }
-                    (Some(_), None) => errors
-                        .push(Error::ValidatorStateIsRequired(address.clone())),
-                    (None, None) => continue,
-                },
+                }
                 StakingRewardAddress(data) => match (data.pre, data.post) {
                     (Some(_), Some(post)) => {
                         if post == address {
@@ -556,11 +862,14 @@ where
                         address.clone(),
                     )),
                 },
-                TotalDeltas(data) => match (data.pre, data.post) {
+                TotalDeltas { data, state } => match (data.pre, data.post) {
                     (Some(pre), Some(post)) => {
                         if post.last_update() != current_epoch {
                             errors.push(Error::InvalidLastUpdate)
                         }
+                        if let Some(state) = state {
+                            validator_states.insert(address.clone(), state);
+                        }
                         // Changes of all total deltas (up to `unbonding_epoch`)
                         let mut deltas = TokenChange::default();
                         // Sum of pre total deltas
@@ -627,6 +936,7 @@ where
                             deltas += delta;
                             // A total delta can only be increased at
                             // `pipeline_offset` from bonds and decreased at
+                            // `pipeline_offset` from redelegations or at
                             // `unbonding_offset` from unbonding
                             if delta > TokenChange::default()
                                 && epoch != pipeline_epoch
@@ -637,13 +947,24 @@ where
                                 })
                             }
                             if delta < TokenChange::default()
+                                && epoch != pipeline_epoch
                                 && epoch != unbonding_epoch
                             {
                                 errors.push(Error::EpochedDataWrongEpoch {
                                     got: epoch.into(),
-                                    expected: vec![unbonding_epoch.into()],
+                                    expected: vec![
+                                        pipeline_epoch.into(),
+                                        unbonding_epoch.into(),
+                                    ],
                                 })
                             }
+                            if delta < TokenChange::default()
+                                && epoch == pipeline_epoch
+                            {
+                                *pipeline_decrease
+                                    .entry(address.clone())
+                                    .or_default() -= delta;
+                            }
                         }
                         if post_deltas_sum < TokenChange::default() {
                             errors.push(Error::NegativeValidatorTotalDeltasSum(
@@ -661,6 +982,9 @@ where
                         if post.last_update() != current_epoch {
                             errors.push(Error::InvalidLastUpdate)
                         }
+                        if let Some(state) = state {
+                            validator_states.insert(address.clone(), state);
+                        }
                         // Changes of all total deltas (up to `unbonding_epoch`)
                         let mut deltas = TokenChange::default();
                         for epoch in Epoch::iter_range(
@@ -806,6 +1130,103 @@ where
                     ),
                     (None, None) => continue,
                 },
+                CommissionRate { data, max_change } => {
+                    match (data.pre, data.post) {
+                        (None, Some(post)) => {
+                            if post.last_update() != current_epoch {
+                                errors.push(Error::InvalidLastUpdate)
+                            }
+                            // The value must be known at pipeline epoch
+                            match post.get(pipeline_epoch) {
+                                Some(rate)
+                                    if *rate <= BasisPoints::MAX_RATE => {}
+                                Some(rate) => errors
+                                    .push(Error::InvalidCommissionRate(*rate)),
+                                None => errors.push(
+                                    Error::MissingNewValidatorCommissionRate(
+                                        pipeline_epoch.into(),
+                                    ),
+                                ),
+                            }
+                            let validator = new_validators
+                                .entry(address.clone())
+                                .or_default();
+                            validator.has_commission_rate = true;
+                        }
+                        (Some(pre), Some(post)) => {
+                            if post.last_update() != current_epoch {
+                                errors.push(Error::InvalidLastUpdate)
+                            }
+                            // Before pipeline epoch, the rate must not change
+                            for epoch in Epoch::iter_range(
+                                current_epoch,
+                                pipeline_offset,
+                            ) {
+                                if pre.get(epoch) != post.get(epoch) {
+                                    errors.push(
+                                        Error::InvalidCommissionRateUpdate(
+                                            epoch.into(),
+                                        ),
+                                    )
+                                }
+                            }
+                            // At pipeline epoch, the rate may only change up
+                            // to the validator's maximum change
+                            match post.get(pipeline_epoch) {
+                                Some(rate) if *rate > BasisPoints::MAX_RATE => {
+                                    errors.push(Error::InvalidCommissionRate(
+                                        *rate,
+                                    ))
+                                }
+                                Some(rate) => {
+                                    if let Some(change) =
+                                        too_large_commission_rate_change(
+                                            params,
+                                            current_epoch,
+                                            &pre,
+                                            *rate,
+                                            max_change,
+                                        )
+                                    {
+                                        errors.push(
+                                            Error::CommissionRateChangeTooLarge {
+                                                change,
+                                                max_change,
+                                            },
+                                        )
+                                    }
+                                }
+                                None => errors.push(
+                                    Error::InvalidCommissionRateUpdate(
+                                        pipeline_epoch.into(),
+                                    ),
+                                ),
+                            }
+                        }
+                        (Some(_), None) => {
+                            errors.push(Error::InvalidCommissionRateUpdate(
+                                current_epoch.into(),
+                            ))
+                        }
+                        (None, None) => continue,
+                    }
+                }
+                MaxCommissionRateChange(data) => match (data.pre, data.post) {
+                    (None, Some(post)) => {
+                        if post > BasisPoints::MAX_RATE {
+                            errors.push(Error::InvalidCommissionRate(post))
+                        }
+                        let validator =
+                            new_validators.entry(address.clone()).or_default();
+                        validator.has_max_commission_rate_change = true;
+                    }
+                    (pre, post) if pre != post => errors.push(
+                        Error::InvalidMaxCommissionRateChangeUpdate(
+                            address.clone(),
+                        ),
+                    ),
+                    _ => continue,
+                },
             },
             Balance(data) => match (data.pre, data.post) {
                 (None, Some(post)) => balance_delta += TokenChange::from(post),
@@ -816,7 +1237,48 @@ where
                 (Some(_), None) => errors.push(Error::MissingBalance),
                 (None, None) => continue,
             },
-            Bond { id, data, slashes } => match (data.pre, data.post) {
+            RewardsClaim {
+                id,
+                data,
+                bond,
+                slashes,
+                rewards,
+            } => match (data.pre, data.post) {
+                (pre, Some(post)) if pre != Some(post) => {
+                    // The rewards can only be claimed up to the current epoch
+                    if post != current_epoch {
+                        errors.push(Error::InvalidRewardsClaimedEpoch {
+                            id,
+                            got: post.into(),
+                            expected: current_epoch.into(),
+                        });
+                        continue;
+                    }
+                    if let Some(bond) = bond {
+                        let pending = pending_rewards(
+                            &bond,
+                            &slashes,
+                            &rewards,
+                            pre,
+                            current_epoch,
+                        );
+                        claimed_rewards += TokenChange::from(pending);
+                    }
+                }
+                (Some(_), None) => {
+                    // The record may only be deleted with its bond
+                    if bond.is_some() {
+                        errors.push(Error::RewardsClaimedEpochDeleted(id))
+                    }
+                }
+                _ => continue,
+            },
+            Bond {
+                id,
+                data,
+                slashes,
+                redelegations,
+            } => match (data.pre, data.post) {
                 // Bond may be updated from newly bonded tokens and unbonding
                 (Some(pre), Some(post)) => {
                     if post.last_update() != current_epoch {
@@ -927,6 +1389,7 @@ where
                             }
                         }
                     }
+                    errors.extend(bond_below_minimum(&id, &post));
                     // Check slashes
                     for (start_epoch, delta) in slashed_deltas.iter_mut() {
                         for slash in &slashes {
@@ -943,7 +1406,16 @@ where
                         .fold(TokenChange::default(), |acc, delta| {
                             acc + *delta
                         });
+                    if total < TokenChange::default() {
+                        errors.extend(bond_below_slashable(
+                            &id,
+                            Some(&post),
+                            &redelegations,
+                        ));
+                    }
                     if total != TokenChange::default() {
+                        *bond_delta_by_id.entry(id.clone()).or_default() +=
+                            total;
                         let bond_entry =
                             bond_delta.entry(id.validator).or_default();
                         *bond_entry += total;
@@ -995,6 +1467,9 @@ where
                     if total_delta == TokenChange::default() {
                         errors.push(Error::EmptyBond(id.clone()))
                     }
+                    errors.extend(bond_below_minimum(&id, &post));
+                    *bond_delta_by_id.entry(id.clone()).or_default() +=
+                        total_delta;
                     let bond_entry =
                         bond_delta.entry(id.validator).or_default();
                     *bond_entry += total_delta;
@@ -1023,6 +1498,13 @@ where
                             }
                         }
                     }
+                    errors.extend(bond_below_slashable(
+                        &id,
+                        None,
+                        &redelegations,
+                    ));
+                    *bond_delta_by_id.entry(id.clone()).or_default() +=
+                        total_delta;
                     let bond_entry =
                         bond_delta.entry(id.validator).or_default();
                     *bond_entry += total_delta;
@@ -1195,6 +1677,34 @@ where
                 }
                 _ => errors.push(Error::MissingValidatorSet),
             },
+            OutgoingRedelegations { validator, data } => {
+                let (added, removed) = redelegations_diff(data);
+                if !valid_redelegations_update(
+                    params,
+                    current_epoch,
+                    &validator,
+                    true,
+                    &added,
+                    &removed,
+                ) {
+                    errors.push(Error::InvalidRedelegationsUpdate(validator))
+                }
+                new_outgoing.extend(added);
+            }
+            IncomingRedelegations { validator, data } => {
+                let (added, removed) = redelegations_diff(data);
+                if !valid_redelegations_update(
+                    params,
+                    current_epoch,
+                    &validator,
+                    false,
+                    &added,
+                    &removed,
+                ) {
+                    errors.push(Error::InvalidRedelegationsUpdate(validator))
+                }
+                new_incoming.extend(added);
+            }
             TotalVotingPower(data) => match (data.pre, data.post) {
                 (Some(pre), Some(post)) => {
                     if post.last_update() != current_epoch {
@@ -1280,6 +1790,91 @@ where
         }
     }
 
+    // Check that every new redelegation is recorded for both of its
+    // validators
+    let mut unmatched_incoming = new_incoming;
+    // The bond changes expected from the new redelegations
+    let mut redelegated_bond_delta: HashMap<BondId<Address>, TokenChange> =
+        HashMap::default();
+    // The tokens redelegated from each validator
+    let mut redelegated_out: HashMap<Address, TokenChange> = HashMap::default();
+    for redelegation in new_outgoing {
+        match unmatched_incoming.iter().position(|r| *r == redelegation) {
+            Some(index) => {
+                unmatched_incoming.swap_remove(index);
+            }
+            None => errors.push(Error::UnmatchedRedelegation(
+                redelegation.src_validator.clone(),
+                redelegation.dest_validator.clone(),
+            )),
+        }
+        let amount = TokenChange::from(redelegation.amount);
+        *redelegated_out
+            .entry(redelegation.src_validator.clone())
+            .or_default() += amount;
+        *redelegated_bond_delta
+            .entry(BondId {
+                source: redelegation.source.clone(),
+                validator: redelegation.src_validator,
+            })
+            .or_default() -= amount;
+        *redelegated_bond_delta
+            .entry(BondId {
+                source: redelegation.source,
+                validator: redelegation.dest_validator,
+            })
+            .or_default() += amount;
+    }
+    for redelegation in unmatched_incoming {
+        errors.push(Error::UnmatchedRedelegation(
+            redelegation.src_validator,
+            redelegation.dest_validator,
+        ))
+    }
+    // The source bond of a redelegation must be decreased and its
+    // destination bond increased by the redelegated amount
+    for (id, expected) in redelegated_bond_delta {
+        let got = bond_delta_by_id.get(&id).copied().unwrap_or_default();
+        if got != expected {
+            errors.push(Error::InvalidRedelegatedBondDelta {
+                id,
+                got,
+                expected,
+            })
+        }
+    }
+    // A validator's total deltas can only be decreased at the pipeline epoch
+    // by the tokens redelegated from it
+    for (address, decrease) in pipeline_decrease {
+        let redelegated =
+            redelegated_out.get(&address).copied().unwrap_or_default();
+        if decrease > redelegated {
+            errors.push(Error::TotalDeltasDecreaseNotRedelegated {
+                address,
+                decrease,
+                redelegated,
+            })
+        }
+    }
+
+    // Check that an unjailed validator has the voting power of its total
+    // deltas at the given epoch
+    let unjailed_voting_power_valid =
+        |validator: &WeightedValidator<Address>, epoch: Epoch| {
+            unjailed_validators
+                .get(&validator.address)
+                .map(|total_deltas| {
+                    let stake: i128 =
+                        total_deltas.get(epoch).unwrap_or_default().into();
+                    u64::try_from(stake)
+                        .map(|stake| {
+                            validator.voting_power
+                                == VotingPower::from_tokens(stake, params)
+                        })
+                        .unwrap_or_default()
+                })
+        };
+
     // Check validator sets against validator total stakes.
     // Iter from the first epoch to the last epoch of `validator_set_post`
     if let Some(post) = &validator_set_post {
@@ -1332,10 +1927,25 @@ where
                                     }
                                 }
                                 None => {
+                                    // An unjailed validator must have the
+                                    // voting power of its total deltas
+                                    if let Some(is_valid) =
+                                        unjailed_voting_power_valid(
+                                            validator, epoch,
+                                        )
+                                    {
+                                        if !is_valid {
+                                            errors.push(
+                                                Error::InvalidActiveValidator(
+                                                    validator.clone(),
+                                                ),
+                                            )
+                                        }
+                                    }
                                     // Others must be have the same voting power
                                     // as in pre (active or inactive), or be a
                                     // newly added validator
-                                    if !pre.active.contains(validator)
+                                    else if !pre.active.contains(validator)
                                         && !pre.inactive.contains(validator)
                                         && !new_validators
                                             .contains_key(&validator.address)
@@ -1406,10 +2016,25 @@ where
                                     }
                                 }
                                 None => {
+                                    // An unjailed validator must have the
+                                    // voting power of its total deltas
+                                    if let Some(is_valid) =
+                                        unjailed_voting_power_valid(
+                                            validator, epoch,
+                                        )
+                                    {
+                                        if !is_valid {
+                                            errors.push(
+                                                Error::InvalidInactiveValidator(
+                                                    validator.clone(),
+                                                ),
+                                            )
+                                        }
+                                    }
                                     // Others must be have the same voting power
                                     // as in pre (active or inactive), or be a
                                     // newly added validator
-                                    if !pre.active.contains(validator)
+                                    else if !pre.active.contains(validator)
                                         && !pre.inactive.contains(validator)
                                         && !new_validators
                                             .contains_key(&validator.address)
@@ -1482,6 +2107,24 @@ where
                                 voting_power,
                                 address: validator.clone(),
                             };
+                            // A jailed validator is not in the validator set
+                            let is_in_set = post
+                                .active
+                                .iter()
+                                .chain(post.inactive.iter())
+                                .any(|v| v.address == *validator);
+                            let is_jailed = matches!(
+                                validator_states
+                                    .get(validator)
+                                    .and_then(|states| states.get(epoch)),
+                                Some(ValidatorState::Jailed { .. })
+                            );
+                            if !is_in_set
+                                && is_jailed
+                                && !new_validators.contains_key(validator)
+                            {
+                                continue;
+                            }
                             if !post.active.contains(&weighted_validator) {
                                 if !post.inactive.contains(&weighted_validator)
                                 {
@@ -1637,6 +2280,8 @@ where
                         has_voting_power,
                         has_staking_reward_address,
                         has_address_raw_hash,
+                        has_commission_rate,
+                        has_max_commission_rate_change,
                         voting_power,
                     } = &new_validator;
                     // The new validator must have set all the required fields
@@ -1645,7 +2290,9 @@ where
                         && *has_total_deltas
                         && *has_voting_power
                         && *has_staking_reward_address
-                        && *has_address_raw_hash)
+                        && *has_address_raw_hash
+                        && *has_commission_rate
+                        && *has_max_commission_rate_change)
                     {
                         errors.push(Error::InvalidNewValidator(
                             address.clone(),
@@ -1685,13 +2332,89 @@ where
         .into_iter()
         .fold(TokenChange::default(), |acc, delta| acc + (*delta));
 
-    if balance_delta != bond_delta + unbond_delta {
+    if balance_delta + claimed_rewards != bond_delta + unbond_delta {
         errors.push(Error::InvalidBalances {
             balance_delta,
             bond_delta,
             unbond_delta,
+            claimed_rewards,
         })
     }
 
     errors
 }
+
+/// Find the change of the commission rate at the pipeline epoch to the given
+/// `rate` from the rate in the prior state, if it's greater than the
+/// validator's maximum change. A validator without a maximum change cannot
+/// change its rate.
+fn too_large_commission_rate_change(
+    params: &PosParams,
+    current_epoch: Epoch,
+    pre: &CommissionRates,
+    rate: BasisPoints,
+    max_change: Option<BasisPoints>,
+) -> Option<BasisPoints> {
+    let prev_rate = prev_commission_rate(params, pre, current_epoch)?;
+    let change = rate.abs_diff(prev_rate);
+    match max_change {
+        Some(max_change) if change <= max_change => None,
+        _ => Some(change),
+    }
+}
+
+/// Find the redelegations added and removed in an update of redelegations.
+fn redelegations_diff<Address, TokenAmount>(
+    data: Data<Redelegations<Address, TokenAmount>>,
+) -> (
+    Redelegations<Address, TokenAmount>,
+    Redelegations<Address, TokenAmount>,
+)
+where
+    Address: Clone + Debug + PartialEq,
+    TokenAmount: Clone + Debug + PartialEq,
+{
+    let mut added = data.post.unwrap_or_default();
+    let mut removed = vec![];
+    for redelegation in data.pre.unwrap_or_default() {
+        match added.iter().position(|r| *r == redelegation) {
+            Some(index) => {
+                added.remove(index);
+            }
+            None => removed.push(redelegation),
+        }
+    }
+    (added, removed)
+}
+
+/// Check the redelegations added to and removed from the redelegations from
+/// (if `outgoing`) or to a validator. A redelegation can only be added in the
+/// current epoch and only removed once it's no longer slashable.
+fn valid_redelegations_update<Address, TokenAmount>(
+    params: &PosParams,
+    current_epoch: Epoch,
+    validator: &Address,
+    outgoing: bool,
+    added: &[Redelegation<Address, TokenAmount>],
+    removed: &[Redelegation<Address, TokenAmount>],
+) -> bool
+where
+    Address: Clone + Debug + PartialEq,
+    TokenAmount: Clone + Debug + Default + PartialEq,
+{
+    let added_valid = added.iter().all(|r| {
+        let redelegation_validator = if outgoing {
+            &r.src_validator
+        } else {
+            &r.dest_validator
+        };
+        r.epoch == current_epoch
+            && redelegation_validator == validator
+            && r.src_validator != r.dest_validator
+            && r.amount != TokenAmount::default()
+    });
+    let removed_valid = removed
+        .iter()
+        .all(|r| !r.is_slashable(params, current_epoch));
+    added_valid && removed_valid
+}
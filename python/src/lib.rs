@@ -0,0 +1,401 @@
+//! Python bindings of the Anoma types and the query client.
+//!
+//! The `anoma_py` module is built with `maturin` and exposes the addresses,
+//! token amounts, signing keys, the construction and signing of txs and a
+//! client of a ledger node, so that the txs and queries don't have to be
+//! encoded in Python.
+
+#![doc(html_favicon_url = "https://dev.anoma.net/master/favicon.png")]
+#![doc(html_logo_url = "https://dev.anoma.net/master/rustdoc-logo.png")]
+#![deny(rustdoc::broken_intra_doc_links)]
+#![deny(rustdoc::private_intra_doc_links)]
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use anoma::proto;
+use anoma::types::chain::ChainId;
+use anoma::types::key::{common, RefTo};
+use anoma::types::storage::Epoch;
+use anoma::types::transaction::GasLimit;
+use anoma::types::{address, storage, token};
+use anoma_sdk::{tx, web, TxOptions, WebSdk};
+use pyo3::basic::CompareOp;
+use pyo3::exceptions::{PyRuntimeError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+/// An address of an account
+#[pyclass(module = "anoma_py")]
+#[derive(Clone)]
+struct Address(address::Address);
+
+#[pymethods]
+impl Address {
+    /// Decode an address from its bech32m encoding
+    #[new]
+    fn new(encoded: &str) -> PyResult<Self> {
+        address::Address::decode(encoded)
+            .map(Self)
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    /// The address of the native token
+    #[staticmethod]
+    fn xan() -> Self {
+        Self(address::xan())
+    }
+
+    /// Encode the address with bech32m
+    fn encode(&self) -> String {
+        self.0.encode()
+    }
+
+    fn __str__(&self) -> String {
+        self.0.encode()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Address('{}')", self.0.encode())
+    }
+
+    fn __richcmp__(&self, other: &Self, op: CompareOp) -> PyResult<bool> {
+        match op {
+            CompareOp::Eq => Ok(self.0 == other.0),
+            CompareOp::Ne => Ok(self.0 != other.0),
+            _ => Err(PyTypeError::new_err("Addresses are not ordered")),
+        }
+    }
+
+    fn __hash__(&self) -> isize {
+        let mut hasher = DefaultHasher::new();
+        self.0.hash(&mut hasher);
+        hasher.finish() as isize
+    }
+}
+
+/// An amount of a token in micro units
+#[pyclass(module = "anoma_py")]
+#[derive(Clone, Copy)]
+struct Amount(token::Amount);
+
+#[pymethods]
+impl Amount {
+    /// Parse an amount from a decimal string with at most 6 decimal places
+    #[new]
+    fn new(amount: &str) -> PyResult<Self> {
+        token::Amount::from_str(amount)
+            .map(Self)
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    /// Create an amount from a number of micro units
+    #[staticmethod]
+    fn from_micro(micro: u64) -> Self {
+        Self(token::Amount::from(micro))
+    }
+
+    /// The number of micro units of the amount
+    #[getter]
+    fn micro(&self) -> u64 {
+        u64::from(self.0)
+    }
+
+    fn __add__(&self, other: &Self) -> PyResult<Self> {
+        self.micro()
+            .checked_add(other.micro())
+            .map(Self::from_micro)
+            .ok_or_else(|| PyValueError::new_err("The amount overflowed"))
+    }
+
+    fn __sub__(&self, other: &Self) -> PyResult<Self> {
+        self.micro()
+            .checked_sub(other.micro())
+            .map(Self::from_micro)
+            .ok_or_else(|| {
+                PyValueError::new_err(
+                    "The subtracted amount is greater than the amount",
+                )
+            })
+    }
+
+    fn __richcmp__(&self, other: &Self, op: CompareOp) -> bool {
+        match op {
+            CompareOp::Lt => self.0 < other.0,
+            CompareOp::Le => self.0 <= other.0,
+            CompareOp::Eq => self.0 == other.0,
+            CompareOp::Ne => self.0 != other.0,
+            CompareOp::Gt => self.0 > other.0,
+            CompareOp::Ge => self.0 >= other.0,
+        }
+    }
+
+    fn __hash__(&self) -> isize {
+        self.micro() as isize
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Amount('{}')", self.0)
+    }
+}
+
+/// A secret key for signing txs
+#[pyclass(module = "anoma_py")]
+struct SecretKey(common::SecretKey);
+
+#[pymethods]
+impl SecretKey {
+    /// Decode a secret key from its hex encoding, as found in the wallet
+    #[new]
+    fn new(encoded: &str) -> PyResult<Self> {
+        common::SecretKey::from_str(encoded)
+            .map(Self)
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    /// The encoded public key of the secret key
+    fn public_key(&self) -> String {
+        self.0.ref_to().to_string()
+    }
+
+    /// The implicit address of the public key of the secret key
+    fn address(&self) -> Address {
+        Address((&self.0.ref_to()).into())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("SecretKey(public_key='{}')", self.public_key())
+    }
+}
+
+/// A tx that can be signed and put in a wrapper
+#[pyclass(module = "anoma_py")]
+#[derive(Clone)]
+struct Tx(proto::Tx);
+
+#[pymethods]
+impl Tx {
+    /// Create a tx with the given WASM code and data
+    #[new]
+    fn new(code: &[u8], data: Option<&[u8]>) -> Self {
+        Self(proto::Tx::new(code.to_vec(), data.map(<[u8]>::to_vec)))
+    }
+
+    /// The encoded tx
+    fn to_bytes<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.0.to_bytes())
+    }
+}
+
+/// A signed tx in a signed wrapper, ready to be broadcast
+#[pyclass(module = "anoma_py")]
+struct WrappedTx {
+    tx: proto::Tx,
+    /// The hash of the wrapper, which identifies its event on chain
+    #[pyo3(get)]
+    wrapper_hash: String,
+    /// The hash of the decrypted payload, which identifies its event on
+    /// chain, if it's applied separately from the wrapper
+    #[pyo3(get)]
+    decrypted_hash: Option<String>,
+}
+
+#[pymethods]
+impl WrappedTx {
+    /// The encoded wrapper tx
+    fn to_bytes<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.tx.to_bytes())
+    }
+}
+
+impl From<tx::WrappedTx> for WrappedTx {
+    fn from(wrapped: tx::WrappedTx) -> Self {
+        Self {
+            tx: wrapped.tx,
+            wrapper_hash: wrapped.wrapper_hash,
+            decrypted_hash: wrapped.decrypted_hash,
+        }
+    }
+}
+
+/// Build a tx that transfers the `amount` of the `token` from the `source` to
+/// the `target`, with the given WASM code of the transfer tx
+#[pyfunction]
+fn transfer_tx(
+    tx_code: &[u8],
+    source: &Address,
+    target: &Address,
+    token: &Address,
+    amount: &Amount,
+) -> Tx {
+    Tx(tx::transfer(
+        tx_code.to_vec(),
+        source.0.clone(),
+        target.0.clone(),
+        token.0.clone(),
+        amount.0,
+    ))
+}
+
+/// Sign the given tx with the secret key and put it in a wrapper for the
+/// given epoch and chain, which pays the fee from the key's implicit account
+#[pyfunction(fee_amount = "None", fee_token = "None", gas_limit = "0")]
+fn sign_and_wrap(
+    tx: &Tx,
+    secret_key: &SecretKey,
+    epoch: u64,
+    chain_id: &str,
+    fee_amount: Option<Amount>,
+    fee_token: Option<Address>,
+    gas_limit: u64,
+) -> PyResult<WrappedTx> {
+    let chain_id = parse_chain_id(chain_id)?;
+    let options = tx_options(fee_amount, fee_token, gas_limit);
+    Ok(tx::sign_and_wrap(
+        tx.0.clone(),
+        &secret_key.0,
+        Epoch(epoch),
+        &options,
+        chain_id,
+    )
+    .into())
+}
+
+/// A client of the JSON-RPC endpoint of a ledger node. The requests block
+/// until the node responds.
+#[pyclass(module = "anoma_py")]
+struct Client {
+    sdk: WebSdk,
+    runtime: tokio::runtime::Runtime,
+}
+
+#[pymethods]
+impl Client {
+    /// Create a client of the node with the JSON-RPC endpoint at the given
+    /// URL of the given chain, e.g. `http://127.0.0.1:26657`
+    #[new]
+    #[args(fee_amount = "None", fee_token = "None", gas_limit = "0")]
+    fn new(
+        url: &str,
+        chain_id: &str,
+        fee_amount: Option<Amount>,
+        fee_token: Option<Address>,
+        gas_limit: u64,
+    ) -> PyResult<Self> {
+        let chain_id = parse_chain_id(chain_id)?;
+        let options = tx_options(fee_amount, fee_token, gas_limit);
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|err| PyRuntimeError::new_err(err.to_string()))?;
+        Ok(Self {
+            sdk: WebSdk::new(url, chain_id).with_options(options),
+            runtime,
+        })
+    }
+
+    /// Query the last committed epoch
+    fn epoch(&self) -> PyResult<u64> {
+        let epoch = self.runtime.block_on(self.sdk.query_epoch());
+        epoch.map(|epoch| epoch.0).map_err(sdk_error)
+    }
+
+    /// Query the balance of the owner of the token
+    fn balance(&self, token: &Address, owner: &Address) -> PyResult<Amount> {
+        let balance = self
+            .runtime
+            .block_on(self.sdk.query_balance(&token.0, &owner.0));
+        balance.map(Amount).map_err(sdk_error)
+    }
+
+    /// Query the Borsh encoded value of the given storage key, if any
+    fn storage_value<'py>(
+        &self,
+        py: Python<'py>,
+        key: &str,
+    ) -> PyResult<Option<&'py PyBytes>> {
+        let key = storage::Key::parse(key)
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        let value = self
+            .runtime
+            .block_on(self.sdk.query_storage_bytes(&key))
+            .map_err(sdk_error)?;
+        Ok(value.map(|value| PyBytes::new(py, &value)))
+    }
+
+    /// Sign the given tx with the secret key, put it in a wrapper that pays
+    /// the fee from the key's implicit account and broadcast it. Returns the
+    /// hashes with which the progress of the tx on chain can be followed.
+    fn submit(&self, tx: &Tx, secret_key: &SecretKey) -> PyResult<Submitted> {
+        let submitted = self
+            .runtime
+            .block_on(self.sdk.submit(tx.0.clone(), &secret_key.0))
+            .map_err(sdk_error)?;
+        Ok(submitted.into())
+    }
+}
+
+/// A tx accepted into the mempool of a node
+#[pyclass(module = "anoma_py")]
+struct Submitted {
+    /// The hash of the wrapper, which identifies its event on chain
+    #[pyo3(get)]
+    wrapper_hash: String,
+    /// The hash of the decrypted payload, which identifies its event on
+    /// chain, if it's applied separately from the wrapper
+    #[pyo3(get)]
+    decrypted_hash: Option<String>,
+}
+
+impl From<web::SubmittedTx> for Submitted {
+    fn from(submitted: web::SubmittedTx) -> Self {
+        Self {
+            wrapper_hash: submitted.wrapper_hash,
+            decrypted_hash: submitted.decrypted_hash,
+        }
+    }
+}
+
+fn parse_chain_id(chain_id: &str) -> PyResult<ChainId> {
+    ChainId::from_str(chain_id)
+        .map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+fn tx_options(
+    fee_amount: Option<Amount>,
+    fee_token: Option<Address>,
+    gas_limit: u64,
+) -> TxOptions {
+    let default = TxOptions::default();
+    TxOptions {
+        fee_amount: fee_amount
+            .map(|amount| amount.0)
+            .unwrap_or(default.fee_amount),
+        fee_token: fee_token.map(|token| token.0).unwrap_or(default.fee_token),
+        gas_limit: GasLimit::from(gas_limit),
+    }
+}
+
+fn sdk_error(err: anoma_sdk::Error) -> PyErr {
+    PyRuntimeError::new_err(err.to_string())
+}
+
+/// The Python module
+#[pymodule]
+fn anoma_py(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<Address>()?;
+    m.add_class::<Amount>()?;
+    m.add_class::<SecretKey>()?;
+    m.add_class::<Tx>()?;
+    m.add_class::<WrappedTx>()?;
+    m.add_class::<Client>()?;
+    m.add_class::<Submitted>()?;
+    m.add_function(wrap_pyfunction!(transfer_tx, m)?)?;
+    m.add_function(wrap_pyfunction!(sign_and_wrap, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,94 @@
+//! A library for building, signing and submitting transactions to an Anoma
+//! ledger, for integrations that cannot shell out to the `anomac` client.
+//!
+//! With the `native` feature, the `Sdk` is configured with the address of a
+//! ledger node, the chain ID and the directory with the pre-built WASM code of
+//! the transactions. Every submission waits for the result of the tx on chain.
+//!
+//! With the `web` feature, the crate compiles to `wasm32-unknown-unknown`, and
+//! the `WebSdk` sends the txs to the JSON-RPC endpoint of a node over HTTP,
+//! which uses the fetch API in a browser. The caller gives it the WASM code of
+//! the txs and it only broadcasts them, without waiting for their results.
+//!
+//! In both cases, the txs are signed with the given keypair, which also signs
+//! their wrappers and pays their fee.
+
+#![doc(html_favicon_url = "https://dev.anoma.net/master/favicon.png")]
+#![doc(html_logo_url = "https://dev.anoma.net/master/rustdoc-logo.png")]
+#![warn(missing_docs)]
+#![deny(rustdoc::broken_intra_doc_links)]
+#![deny(rustdoc::private_intra_doc_links)]
+
+#[cfg(feature = "native")]
+mod native;
+#[cfg(feature = "native")]
+pub mod result;
+#[cfg(feature = "native")]
+pub mod subscription;
+pub mod tx;
+#[cfg(feature = "web")]
+pub mod web;
+
+use anoma::types::address::{self, Address};
+use anoma::types::token;
+use anoma::types::transaction::GasLimit;
+#[cfg(feature = "native")]
+pub use native::{LedgerAddress, Sdk, SubmitError};
+#[cfg(feature = "native")]
+pub use result::TxQueryResult;
+#[cfg(feature = "native")]
+pub use subscription::{Backoff, TxEventKind};
+use thiserror::Error;
+#[cfg(feature = "web")]
+pub use web::WebSdk;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[cfg(feature = "native")]
+    #[error("Failed to read the WASM code of the tx: {0}")]
+    ReadWasm(anoma_apps::wasm_loader::Error),
+    #[error("Failed to query the ledger: {0}")]
+    Query(String),
+    #[error("Failed to decode the response of the ledger: {0}")]
+    Decode(std::io::Error),
+    #[cfg(feature = "native")]
+    #[error("Failed to submit the tx: {0}")]
+    Submit(SubmitError),
+    #[cfg(feature = "native")]
+    #[error("Received an invalid tx result: {0}")]
+    InvalidResult(String),
+    #[cfg(feature = "native")]
+    #[error("Failed to subscribe to the tx events: {0}")]
+    Subscribe(String),
+    #[cfg(feature = "web")]
+    #[error("Failed to send a request to the ledger: {0}")]
+    Transport(String),
+    #[cfg(feature = "web")]
+    #[error("The tx was rejected by the mempool: {0}")]
+    Rejected(String),
+}
+
+/// Result of a function that may fail
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The fee and gas settings of the wrappers of the submitted txs
+#[derive(Clone, Debug)]
+pub struct TxOptions {
+    /// The amount of the fee paid for each tx
+    pub fee_amount: token::Amount,
+    /// The token in which the fee is paid
+    pub fee_token: Address,
+    /// The maximum amount of gas the tx may use
+    pub gas_limit: GasLimit,
+}
+
+impl Default for TxOptions {
+    fn default() -> Self {
+        Self {
+            fee_amount: token::Amount::default(),
+            fee_token: address::xan(),
+            gas_limit: GasLimit::from(0),
+        }
+    }
+}
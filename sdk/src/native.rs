@@ -0,0 +1,154 @@
+//! The ledger client of the native targets, which uses the Tendermint RPC
+//! client of the `anomac` client.
+
+use std::path::{Path, PathBuf};
+
+use anoma::proto::Tx;
+use anoma::types::address::Address;
+use anoma::types::chain::ChainId;
+use anoma::types::key::common;
+use anoma::types::storage::Epoch;
+use anoma::types::token;
+use anoma_apps::client::output::OutputMode;
+use anoma_apps::client::tendermint_rpc_types::TxBroadcastData;
+use anoma_apps::client::tx::submit_tx;
+use anoma_apps::node::ledger::rpc;
+use anoma_apps::wasm_loader;
+use borsh::BorshDeserialize;
+use futures::Stream;
+use tendermint_compat::tendermint::abci::Code;
+pub use tendermint_compat::tendermint_config::net::Address as LedgerAddress;
+use tendermint_compat::tendermint_rpc::{Client, HttpClient};
+
+/// The error of submitting a tx to the ledger and waiting for its result
+#[cfg(not(feature = "ABCI"))]
+pub use anoma_apps::client::tendermint_rpc_types::Error as SubmitError;
+/// The error of submitting a tx to the ledger and waiting for its result
+#[cfg(feature = "ABCI")]
+pub use anoma_apps::client::tendermint_websocket_client::Error as SubmitError;
+
+use crate::subscription::{self, Backoff, TxEventKind};
+use crate::tx::{self, WrappedTx};
+use crate::{Error, Result, TxOptions, TxQueryResult};
+
+/// A client for building and submitting txs to a ledger node
+#[derive(Clone, Debug)]
+pub struct Sdk {
+    ledger_address: LedgerAddress,
+    chain_id: ChainId,
+    wasm_dir: PathBuf,
+    options: TxOptions,
+}
+
+impl Sdk {
+    /// Create a client of the ledger node at the given address of the given
+    /// chain, which reads the WASM code of the txs from the given directory.
+    /// The txs are submitted with the default [`TxOptions`].
+    pub fn new(
+        ledger_address: LedgerAddress,
+        chain_id: ChainId,
+        wasm_dir: impl AsRef<Path>,
+    ) -> Self {
+        Self {
+            ledger_address,
+            chain_id,
+            wasm_dir: wasm_dir.as_ref().to_path_buf(),
+            options: TxOptions::default(),
+        }
+    }
+
+    /// Set the fee and gas settings of the submitted txs
+    pub fn with_options(mut self, options: TxOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Query the last committed epoch
+    pub async fn query_epoch(&self) -> Result<Epoch> {
+        let client = HttpClient::new(self.ledger_address.clone())
+            .map_err(|err| Error::Query(err.to_string()))?;
+        let response = client
+            .abci_query(Some(rpc::Path::Epoch.into()), vec![], None, false)
+            .await
+            .map_err(|err| Error::Query(err.to_string()))?;
+        match response.code {
+            Code::Ok => Epoch::try_from_slice(&response.value[..])
+                .map_err(Error::Decode),
+            Code::Err(code) => Err(Error::Query(format!(
+                "{} (error code {})",
+                response.info, code
+            ))),
+        }
+    }
+
+    /// Transfer the `amount` of the `token` from the `source` to the
+    /// `target`. The tx is signed with the given keypair, which must be
+    /// authorized by the validity predicate of the source.
+    pub async fn transfer(
+        &self,
+        keypair: &common::SecretKey,
+        source: Address,
+        target: Address,
+        token: Address,
+        amount: token::Amount,
+    ) -> Result<TxQueryResult> {
+        let tx_code = self.read_wasm(tx::TX_TRANSFER_WASM)?;
+        let tx = tx::transfer(tx_code, source, target, token, amount);
+        self.submit(tx, keypair).await
+    }
+
+    /// Sign the given tx with the keypair, put it in a wrapper that pays the
+    /// fee from the keypair's implicit account, submit it and wait for its
+    /// result.
+    pub async fn submit(
+        &self,
+        tx: Tx,
+        keypair: &common::SecretKey,
+    ) -> Result<TxQueryResult> {
+        let epoch = self.query_epoch().await?;
+        let WrappedTx {
+            tx,
+            wrapper_hash,
+            decrypted_hash,
+        } = tx::sign_and_wrap(
+            tx,
+            keypair,
+            epoch,
+            &self.options,
+            self.chain_id.clone(),
+        );
+        let to_broadcast = TxBroadcastData::Wrapper {
+            tx,
+            wrapper_hash,
+            decrypted_hash,
+        };
+        let response = submit_tx(
+            self.ledger_address.clone(),
+            to_broadcast,
+            OutputMode::Text,
+        )
+        .await
+        .map_err(Error::Submit)?;
+        TxQueryResult::try_from(response)
+    }
+
+    /// Subscribe to the results of the txs with an event of the given kind in
+    /// the new blocks. The subscription reconnects to the node with the
+    /// default [`Backoff`] when the connection fails.
+    pub fn subscribe_txs(
+        &self,
+        kind: TxEventKind,
+    ) -> impl Stream<Item = Result<TxQueryResult>> {
+        subscription::subscribe_txs(
+            self.ledger_address.clone(),
+            kind,
+            Backoff::default(),
+        )
+    }
+
+    /// Read the WASM code of a tx from the WASM directory
+    pub fn read_wasm(&self, file_name: impl AsRef<Path>) -> Result<Vec<u8>> {
+        wasm_loader::try_read_wasm(&self.wasm_dir, file_name)
+            .map_err(Error::ReadWasm)
+    }
+}
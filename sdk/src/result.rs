@@ -0,0 +1,140 @@
+//! The results of the txs submitted to the ledger.
+
+use std::collections::BTreeMap;
+
+use anoma::types::address::Address;
+use anoma::types::storage::BlockHeight;
+use anoma::types::transaction::{ResultCode, TxResponse};
+
+use crate::Error;
+
+/// The result of a tx on chain. When the wrapper of a tx is rejected, this is
+/// the result of the wrapper, because its payload is never applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxQueryResult {
+    /// The hash of the tx
+    pub hash: String,
+    /// The height of the block in which the tx was applied
+    pub height: BlockHeight,
+    /// The result code of the tx
+    pub code: ResultCode,
+    /// The gas used by the tx
+    pub gas_used: u64,
+    /// The result info of the tx
+    pub info: String,
+    /// The log of the tx
+    pub log: String,
+    /// The accounts initialized by the tx
+    pub initialized_accounts: Vec<Address>,
+    /// The addresses whose validity predicates rejected the tx
+    pub rejected_vps: Vec<Address>,
+    /// The changed keys examined by each of the rejecting validity
+    /// predicates
+    pub rejected_vp_keys: BTreeMap<Address, Vec<String>>,
+}
+
+impl TxQueryResult {
+    /// Check if the tx was applied successfully
+    pub fn is_applied(&self) -> bool {
+        self.code.is_ok()
+    }
+
+    /// Check if the payload of the tx could not be decrypted. The fee of its
+    /// wrapper has been charged, but the payload was not applied.
+    pub fn is_undecryptable(&self) -> bool {
+        self.code == ResultCode::Undecryptable
+    }
+}
+
+impl TryFrom<TxResponse> for TxQueryResult {
+    type Error = Error;
+
+    fn try_from(response: TxResponse) -> Result<Self, Error> {
+        let parse = |field: &str, value: &str| {
+            value.parse::<u64>().map_err(|err| {
+                Error::InvalidResult(format!(
+                    "the {} {} is not a number: {}",
+                    field, value, err
+                ))
+            })
+        };
+        let height = BlockHeight(parse("height", &response.height)?);
+        let gas_used = parse("gas used", &response.gas_used)?;
+        Ok(Self {
+            hash: response.hash,
+            height,
+            code: response.code,
+            gas_used,
+            info: response.info,
+            log: response.log,
+            initialized_accounts: response.initialized_accounts,
+            rejected_vps: response.rejected_vps,
+            rejected_vp_keys: response.rejected_vp_keys,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::address;
+
+    use super::*;
+
+    /// Test that the fields of a tx response are parsed into the result.
+    #[test]
+    fn test_tx_query_result_from_response() {
+        let account = address::testing::established_address_1();
+        let response = TxResponse {
+            info: "Transaction is valid.".to_string(),
+            log: String::new(),
+            height: "10".to_string(),
+            hash: "hash".to_string(),
+            code: ResultCode::Ok,
+            gas_used: "1000".to_string(),
+            initialized_accounts: vec![account.clone()],
+            rejected_vps: vec![],
+            rejected_vp_keys: BTreeMap::new(),
+        };
+        let result = TxQueryResult::try_from(response).unwrap();
+        assert!(result.is_applied());
+        assert_eq!(result.height, BlockHeight(10));
+        assert_eq!(result.gas_used, 1000);
+        assert_eq!(result.initialized_accounts, vec![account]);
+
+        let balance_key = format!("#{}/balance", account);
+        let response = TxResponse {
+            info: "Transaction is invalid.".to_string(),
+            log: String::new(),
+            height: "10".to_string(),
+            hash: "hash".to_string(),
+            code: ResultCode::VpRejected,
+            gas_used: "1000".to_string(),
+            initialized_accounts: vec![],
+            rejected_vps: vec![account.clone()],
+            rejected_vp_keys: BTreeMap::from([(
+                account.clone(),
+                vec![balance_key.clone()],
+            )]),
+        };
+        let result = TxQueryResult::try_from(response).unwrap();
+        assert!(!result.is_applied());
+        assert_eq!(result.rejected_vps, vec![account.clone()]);
+        assert_eq!(result.rejected_vp_keys[&account], vec![balance_key]);
+
+        let response = TxResponse {
+            info: String::new(),
+            log: String::new(),
+            height: "not a height".to_string(),
+            hash: "hash".to_string(),
+            code: ResultCode::Ok,
+            gas_used: "0".to_string(),
+            initialized_accounts: vec![],
+            rejected_vps: vec![],
+            rejected_vp_keys: BTreeMap::new(),
+        };
+        assert!(matches!(
+            TxQueryResult::try_from(response),
+            Err(Error::InvalidResult(_))
+        ));
+    }
+}
@@ -0,0 +1,217 @@
+//! Subscriptions to the results of the txs on chain, over the websocket
+//! endpoint of a ledger node.
+//!
+//! A subscription yields the result of every tx with an event of the given
+//! kind in the new blocks. When the connection to the node fails, it's
+//! reopened after a backoff delay, which doubles after every failed attempt.
+//! The txs of the blocks committed while the subscription reconnects are not
+//! yielded.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use anoma::types::transaction::TxResponse;
+use futures::{Stream, StreamExt};
+use tendermint_compat::events::{
+    ACCEPTED_EVENT, ACCEPTED_QUERY_KEY, APPLIED_EVENT, APPLIED_QUERY_KEY,
+};
+use tendermint_compat::tendermint_rpc::query::{EventType, Query};
+use tendermint_compat::tendermint_rpc::{
+    Subscription, SubscriptionClient, WebSocketClient,
+};
+
+use crate::{Error, LedgerAddress, Result, TxQueryResult};
+
+/// The kind of the tx events of a subscription
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxEventKind {
+    /// A wrapper tx was included in a block. With ABCI, the wrappers and
+    /// their payloads are applied together, so these are the same events as
+    /// [`TxEventKind::Applied`].
+    Accepted,
+    /// A tx was applied
+    Applied,
+}
+
+impl TxEventKind {
+    fn event_type(&self) -> &'static str {
+        match self {
+            TxEventKind::Accepted => ACCEPTED_EVENT,
+            TxEventKind::Applied => APPLIED_EVENT,
+        }
+    }
+
+    fn query(&self) -> Query {
+        let key = match self {
+            TxEventKind::Accepted => ACCEPTED_QUERY_KEY,
+            TxEventKind::Applied => APPLIED_QUERY_KEY,
+        };
+        Query::from(EventType::NewBlock).and_exists(key)
+    }
+}
+
+/// The settings of the reconnection of a subscription
+#[derive(Clone, Debug)]
+pub struct Backoff {
+    /// The delay before the first attempt to reconnect
+    pub initial: Duration,
+    /// The maximum delay between two attempts
+    pub max: Duration,
+    /// The number of consecutive failed attempts after which the
+    /// subscription ends with an error
+    pub max_retries: u32,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(500),
+            max: Duration::from_secs(30),
+            max_retries: 10,
+        }
+    }
+}
+
+impl Backoff {
+    /// The delay before the attempt that follows the given number of
+    /// consecutive failures
+    fn delay(&self, failures: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(failures.saturating_sub(1));
+        self.initial.saturating_mul(factor).min(self.max)
+    }
+}
+
+/// An open subscription
+struct Connection {
+    client: WebSocketClient,
+    subscription: Subscription,
+}
+
+impl Connection {
+    async fn open(address: &LedgerAddress, kind: TxEventKind) -> Result<Self> {
+        let (client, driver) = WebSocketClient::new(address.clone())
+            .await
+            .map_err(|err| Error::Subscribe(err.to_string()))?;
+        tokio::spawn(async move { driver.run().await });
+        let subscription = client
+            .subscribe(kind.query())
+            .await
+            .map_err(|err| Error::Subscribe(err.to_string()))?;
+        Ok(Self {
+            client,
+            subscription,
+        })
+    }
+
+    fn close(self) {
+        // The connection may already be closed by the node
+        let _ = self.client.close();
+    }
+}
+
+/// The state of a subscription between the items of its stream
+struct State {
+    address: LedgerAddress,
+    kind: TxEventKind,
+    backoff: Backoff,
+    connection: Option<Connection>,
+    /// The results of the last received block that are not yielded yet
+    pending: VecDeque<Result<TxQueryResult>>,
+    /// The number of consecutive connection failures
+    failures: u32,
+    done: bool,
+}
+
+/// Subscribe to the results of the txs with an event of the given kind in
+/// the blocks committed by the ledger node at the given address. The stream
+/// ends with an error after `backoff.max_retries` consecutive failures to
+/// connect to the node. It must be polled in a tokio runtime, which runs the
+/// websocket connection.
+pub fn subscribe_txs(
+    address: LedgerAddress,
+    kind: TxEventKind,
+    backoff: Backoff,
+) -> impl Stream<Item = Result<TxQueryResult>> {
+    let state = State {
+        address,
+        kind,
+        backoff,
+        connection: None,
+        pending: VecDeque::new(),
+        failures: 0,
+        done: false,
+    };
+    futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(result) = state.pending.pop_front() {
+                return Some((result, state));
+            }
+            if state.done {
+                return None;
+            }
+            if state.connection.is_none() {
+                if state.failures > 0 {
+                    let delay = state.backoff.delay(state.failures);
+                    tokio::time::sleep(delay).await;
+                }
+                match Connection::open(&state.address, state.kind).await {
+                    Ok(connection) => state.connection = Some(connection),
+                    Err(err) => {
+                        state.failures += 1;
+                        if state.failures > state.backoff.max_retries {
+                            state.done = true;
+                            return Some((Err(err), state));
+                        }
+                        continue;
+                    }
+                }
+            }
+            let connection =
+                state.connection.as_mut().expect("The connection is open");
+            match connection.subscription.next().await {
+                Some(Ok(event)) => {
+                    state.failures = 0;
+                    let responses = event
+                        .events
+                        .map(|events| {
+                            TxResponse::from_block_events(
+                                &events,
+                                state.kind.event_type(),
+                            )
+                        })
+                        .unwrap_or_default();
+                    state.pending.extend(
+                        responses.into_iter().map(TxQueryResult::try_from),
+                    );
+                }
+                Some(Err(_)) | None => {
+                    // The connection is lost, it's reopened after a delay
+                    if let Some(connection) = state.connection.take() {
+                        connection.close();
+                    }
+                    state.failures += 1;
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the reconnection delay doubles up to the maximum delay.
+    #[test]
+    fn test_backoff_delay() {
+        let backoff = Backoff {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(10),
+            max_retries: 10,
+        };
+        assert_eq!(backoff.delay(1), Duration::from_secs(1));
+        assert_eq!(backoff.delay(2), Duration::from_secs(2));
+        assert_eq!(backoff.delay(4), Duration::from_secs(8));
+        assert_eq!(backoff.delay(5), Duration::from_secs(10));
+        assert_eq!(backoff.delay(100), Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,68 @@
+//! Building and signing txs, which doesn't need a connection to the ledger.
+
+use anoma::proto::Tx;
+use anoma::types::address::Address;
+use anoma::types::chain::ChainId;
+use anoma::types::key::common;
+use anoma::types::storage::Epoch;
+use anoma::types::token;
+use anoma::types::transaction::{Fee, WrapperTx};
+use borsh::BorshSerialize;
+
+use crate::TxOptions;
+
+/// A signed tx in a signed wrapper, ready to be broadcast
+pub use anoma::types::transaction::WrappedTx;
+
+/// The file name of the WASM code of a token transfer
+pub const TX_TRANSFER_WASM: &str = "tx_transfer.wasm";
+
+/// Build a tx that transfers the `amount` of the `token` from the `source` to
+/// the `target`, with the given WASM code of the transfer tx.
+pub fn transfer(
+    tx_code: Vec<u8>,
+    source: Address,
+    target: Address,
+    token: Address,
+    amount: token::Amount,
+) -> Tx {
+    let transfer = token::Transfer {
+        source,
+        target,
+        token,
+        amount,
+    };
+    let data = transfer
+        .try_to_vec()
+        .expect("Encoding tx data shouldn't fail");
+    Tx::new(tx_code, Some(data))
+}
+
+/// Sign the given tx with the keypair and put it in a wrapper that pays the
+/// fee of the given options from the keypair's implicit account. The wrapper
+/// is signed with the same keypair.
+pub fn sign_and_wrap(
+    tx: Tx,
+    keypair: &common::SecretKey,
+    epoch: Epoch,
+    options: &TxOptions,
+    chain_id: ChainId,
+) -> WrappedTx {
+    let tx = tx.sign(keypair);
+    let wrapper = WrapperTx::new(
+        Fee {
+            amount: options.fee_amount,
+            token: options.fee_token.clone(),
+        },
+        keypair,
+        epoch,
+        options.gas_limit.clone(),
+        tx,
+        // TODO: Actually use the fetched encryption key
+        Default::default(),
+        chain_id,
+    );
+    wrapper
+        .sign_wrapped(keypair)
+        .expect("Wrapper tx signing keypair should be correct")
+}
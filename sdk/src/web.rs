@@ -0,0 +1,272 @@
+//! A ledger client that compiles to `wasm32-unknown-unknown`, e.g. for browser
+//! wallets. The requests are sent to the JSON-RPC endpoint of a Tendermint node
+//! over HTTP, which uses the fetch API in a browser.
+
+use anoma::proto::Tx;
+use anoma::types::address::Address;
+use anoma::types::chain::ChainId;
+use anoma::types::key::common;
+use anoma::types::query::{EPOCH_PATH, NOT_FOUND_CODE, VALUE_PREFIX};
+use anoma::types::storage::{self, Epoch};
+use anoma::types::token;
+use borsh::BorshDeserialize;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::tx::{self, WrappedTx};
+use crate::{Error, Result, TxOptions};
+
+/// A transport to the JSON-RPC endpoint of a Tendermint node
+#[derive(Clone, Debug)]
+pub struct HttpTransport {
+    url: String,
+    client: reqwest::Client,
+}
+
+/// A response of the JSON-RPC endpoint
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<serde_json::Value>,
+}
+
+/// The result of the `abci_query` method
+#[derive(Deserialize)]
+struct AbciQuery {
+    response: AbciQueryResponse,
+}
+
+#[derive(Deserialize)]
+struct AbciQueryResponse {
+    #[serde(default)]
+    code: u32,
+    #[serde(default)]
+    info: String,
+    /// The base64 encoded value
+    value: Option<String>,
+}
+
+/// The response of a node to a tx broadcast to its mempool
+#[derive(Clone, Debug, Deserialize)]
+pub struct BroadcastResponse {
+    /// The result code of the check of the tx
+    pub code: u32,
+    /// The log of the check of the tx
+    #[serde(default)]
+    pub log: String,
+    /// The hash of the tx
+    pub hash: String,
+}
+
+impl HttpTransport {
+    /// Create a transport to the JSON-RPC endpoint at the given URL, e.g.
+    /// `http://127.0.0.1:26657`
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Query the given ABCI path with the given data and return the value of
+    /// the response. Returns [`None`] when the queried storage key has no
+    /// value.
+    pub async fn abci_query(
+        &self,
+        path: &str,
+        data: &[u8],
+    ) -> Result<Option<Vec<u8>>> {
+        let params = json!({
+            "path": path,
+            "data": hex::encode(data),
+            "prove": false,
+        });
+        let query: AbciQuery = self.request("abci_query", params).await?;
+        let response = query.response;
+        match response.code {
+            0 => base64::decode(response.value.unwrap_or_default())
+                .map(Some)
+                .map_err(|err| Error::Transport(err.to_string())),
+            NOT_FOUND_CODE => Ok(None),
+            code => Err(Error::Query(format!(
+                "{} (error code {})",
+                response.info, code
+            ))),
+        }
+    }
+
+    /// Broadcast the tx to the mempool of the node and return the result of
+    /// its check
+    pub async fn broadcast_tx_sync(
+        &self,
+        tx: &Tx,
+    ) -> Result<BroadcastResponse> {
+        self.request(
+            "broadcast_tx_sync",
+            json!({"tx": base64::encode(tx.to_bytes())}),
+        )
+        .await
+    }
+
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": method,
+            "params": params,
+        });
+        let response: RpcResponse<T> = self
+            .client
+            .post(&self.url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|err| Error::Transport(err.to_string()))?
+            .json()
+            .await
+            .map_err(|err| Error::Transport(err.to_string()))?;
+        match (response.result, response.error) {
+            (Some(result), _) => Ok(result),
+            (None, Some(error)) => Err(Error::Transport(error.to_string())),
+            (None, None) => {
+                Err(Error::Transport("Received an empty response".into()))
+            }
+        }
+    }
+}
+
+/// A tx accepted into the mempool of a node. Its progress on chain can be
+/// followed with the hashes of the wrapper and of its payload.
+#[derive(Clone, Debug)]
+pub struct SubmittedTx {
+    /// The hash of the wrapper, which identifies its event on chain
+    pub wrapper_hash: String,
+    /// The hash of the decrypted payload, which identifies its event on
+    /// chain, if it's applied separately from the wrapper
+    pub decrypted_hash: Option<String>,
+    /// The response of the node to the broadcast
+    pub response: BroadcastResponse,
+}
+
+/// A client for building, signing and broadcasting txs, which doesn't wait for
+/// the results of the txs
+#[derive(Clone, Debug)]
+pub struct WebSdk {
+    transport: HttpTransport,
+    chain_id: ChainId,
+    options: TxOptions,
+}
+
+impl WebSdk {
+    /// Create a client of the node with the JSON-RPC endpoint at the given
+    /// URL of the given chain. The txs are submitted with the default
+    /// [`TxOptions`].
+    pub fn new(url: impl Into<String>, chain_id: ChainId) -> Self {
+        Self {
+            transport: HttpTransport::new(url),
+            chain_id,
+            options: TxOptions::default(),
+        }
+    }
+
+    /// Set the fee and gas settings of the submitted txs
+    pub fn with_options(mut self, options: TxOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Query the last committed epoch
+    pub async fn query_epoch(&self) -> Result<Epoch> {
+        let value = self
+            .transport
+            .abci_query(EPOCH_PATH, &[])
+            .await?
+            .unwrap_or_default();
+        Epoch::try_from_slice(&value[..]).map_err(Error::Decode)
+    }
+
+    /// Query the value of the given storage key, if any
+    pub async fn query_storage_value<T: BorshDeserialize>(
+        &self,
+        key: &storage::Key,
+    ) -> Result<Option<T>> {
+        match self.query_storage_bytes(key).await? {
+            Some(value) => T::try_from_slice(&value[..])
+                .map(Some)
+                .map_err(Error::Decode),
+            None => Ok(None),
+        }
+    }
+
+    /// Query the encoded value of the given storage key, if any
+    pub async fn query_storage_bytes(
+        &self,
+        key: &storage::Key,
+    ) -> Result<Option<Vec<u8>>> {
+        let path = format!("{}/{}", VALUE_PREFIX, key);
+        self.transport.abci_query(&path, &[]).await
+    }
+
+    /// Query the balance of the owner of the token, which is zero if the
+    /// owner has never held the token
+    pub async fn query_balance(
+        &self,
+        token: &Address,
+        owner: &Address,
+    ) -> Result<token::Amount> {
+        let key = token::balance_key(token, owner);
+        Ok(self.query_storage_value(&key).await?.unwrap_or_default())
+    }
+
+    /// Transfer the `amount` of the `token` from the `source` to the
+    /// `target`, using the given WASM code of the transfer tx. The tx is
+    /// signed with the given keypair, which must be authorized by the
+    /// validity predicate of the source.
+    pub async fn transfer(
+        &self,
+        tx_code: Vec<u8>,
+        keypair: &common::SecretKey,
+        source: Address,
+        target: Address,
+        token: Address,
+        amount: token::Amount,
+    ) -> Result<SubmittedTx> {
+        let tx = tx::transfer(tx_code, source, target, token, amount);
+        self.submit(tx, keypair).await
+    }
+
+    /// Sign the given tx with the keypair, put it in a wrapper that pays the
+    /// fee from the keypair's implicit account and broadcast it
+    pub async fn submit(
+        &self,
+        tx: Tx,
+        keypair: &common::SecretKey,
+    ) -> Result<SubmittedTx> {
+        let epoch = self.query_epoch().await?;
+        let WrappedTx {
+            tx,
+            wrapper_hash,
+            decrypted_hash,
+        } = tx::sign_and_wrap(
+            tx,
+            keypair,
+            epoch,
+            &self.options,
+            self.chain_id.clone(),
+        );
+        let response = self.transport.broadcast_tx_sync(&tx).await?;
+        if response.code != 0 {
+            return Err(Error::Rejected(response.log));
+        }
+        Ok(SubmittedTx {
+            wrapper_hash,
+            decrypted_hash,
+            response,
+        })
+    }
+}
@@ -0,0 +1,1176 @@
+//! An async client of the ledger's RPC queries. The [`QueryClient`] trait
+//! abstracts over the Tendermint RPC client, so that the code reading the
+//! ledger can be written once for any backend and tested with a mock client.
+//!
+//! The trait is implemented for every Tendermint RPC [`Client`], i.e. the HTTP
+//! and the websocket clients of the tendermint-rs version selected with the
+//! `rpc-client` or the `rpc-client-abci` feature.
+//!
+//! A [`CachingClient`] can wrap any client to answer the repeated queries of
+//! the same block from memory, and a [`RetryingClient`] to retry the queries
+//! that fail with a transient error.
+//!
+//! The values of a large prefix can be consumed as a [`Stream`] with
+//! [`query_prefix_stream`], which fetches them page by page.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use borsh::BorshDeserialize;
+use futures::Stream;
+use rand::Rng;
+use tendermint_compat::tendermint_rpc::{self, Client};
+use thiserror::Error;
+
+use crate::tendermint::abci::{Code, Path as AbciPath};
+use crate::tendermint::block::Height;
+use crate::types::query::{
+    DRY_RUN_TX_PATH, EPOCH_PATH, NOT_FOUND_CODE, PREFIX_PAGE_PREFIX,
+    PREFIX_PREFIX, RATE_LIMITED_CODE, VALUE_PREFIX,
+};
+use crate::types::storage::{BlockHeight, Epoch, Key, PrefixPage, PrefixValue};
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("RPC error: {0}")]
+    Rpc(tendermint_rpc::Error),
+    #[error("Invalid query path {0}")]
+    InvalidPath(String),
+    #[error("Invalid query height {0}")]
+    InvalidHeight(BlockHeight),
+    #[error("The query {path} failed with code {code}: {info}")]
+    Query {
+        path: String,
+        code: u32,
+        info: String,
+    },
+    #[error("Error decoding the response of the query {path}: {error}")]
+    Decoding { path: String, error: std::io::Error },
+    #[error("The query didn't complete within its deadline of {0:?}")]
+    Timeout(Duration),
+}
+
+impl Error {
+    /// Whether the error may be transient, so that the query can be retried.
+    /// The RPC errors, e.g. of the connection to the node, are retryable, and
+    /// so are the queries rejected by the rate limiter of the node.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Rpc(_) => true,
+            Error::Query { code, .. } => *code == RATE_LIMITED_CODE,
+            Error::InvalidPath(_)
+            | Error::InvalidHeight(_)
+            | Error::Decoding { .. }
+            | Error::Timeout(_) => false,
+        }
+    }
+}
+
+/// Result of a query that may fail
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The error of a typed storage value query, see [`query_storage_value`]
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum StorageValueError {
+    #[error("No value found for key {0}")]
+    KeyMissing(Key),
+    #[error("Error decoding the value of key {key} as {type_name}: {error}")]
+    Decoding {
+        key: Key,
+        type_name: &'static str,
+        error: std::io::Error,
+    },
+    #[error("Error querying the value of key {key}: {error}")]
+    Query { key: Key, error: Error },
+}
+
+/// The response of an ABCI query
+#[derive(Clone, Debug, Default)]
+pub struct QueryResponse {
+    /// The result code, `0` on success
+    pub code: u32,
+    /// The info about the result, e.g. the error of a failed query
+    pub info: String,
+    /// The log of the query
+    pub log: String,
+    /// The encoded value
+    pub value: Vec<u8>,
+    /// The height at which the query was answered
+    pub height: BlockHeight,
+}
+
+/// An async client of the ledger's RPC queries. Only
+/// [`QueryClient::query_raw`] has to be implemented, e.g. by a mock client in
+/// tests, the other methods are built on it.
+#[async_trait]
+pub trait QueryClient: Sync {
+    /// Send an ABCI query for the given path and data, at the given height or
+    /// at the last committed block
+    async fn query_raw(
+        &self,
+        path: String,
+        data: Vec<u8>,
+        height: Option<BlockHeight>,
+        prove: bool,
+    ) -> Result<QueryResponse>;
+
+    /// Read the storage value of the given key and decode it with
+    /// [`BorshDeserialize`]. Returns `None` if the key has no value.
+    async fn query_value<T>(&self, key: &Key) -> Result<Option<T>>
+    where
+        T: BorshDeserialize + Send,
+    {
+        let path = format!("{}/{}", VALUE_PREFIX, key);
+        let response =
+            self.query_raw(path.clone(), vec![], None, false).await?;
+        match response.code {
+            0 => T::try_from_slice(&response.value[..])
+                .map(Some)
+                .map_err(|error| Error::Decoding { path, error }),
+            NOT_FOUND_CODE => Ok(None),
+            code => Err(Error::Query {
+                path,
+                code,
+                info: response.info,
+            }),
+        }
+    }
+
+    /// Read the storage values whose keys match the given prefix and decode
+    /// them with [`BorshDeserialize`]. The values are ordered by their keys.
+    async fn query_prefix<T>(&self, prefix: &Key) -> Result<Vec<(Key, T)>>
+    where
+        T: BorshDeserialize + Send,
+    {
+        let path = format!("{}/{}", PREFIX_PREFIX, prefix);
+        let response =
+            self.query_raw(path.clone(), vec![], None, false).await?;
+        match response.code {
+            0 => {
+                let values =
+                    Vec::<PrefixValue>::try_from_slice(&response.value[..])
+                        .map_err(|error| Error::Decoding {
+                            path: path.clone(),
+                            error,
+                        })?;
+                values
+                    .into_iter()
+                    .map(|PrefixValue { key, value }| {
+                        let value =
+                            T::try_from_slice(&value[..]).map_err(|error| {
+                                Error::Decoding {
+                                    path: path.clone(),
+                                    error,
+                                }
+                            })?;
+                        Ok((key, value))
+                    })
+                    .collect()
+            }
+            NOT_FOUND_CODE => Ok(vec![]),
+            code => Err(Error::Query {
+                path,
+                code,
+                info: response.info,
+            }),
+        }
+    }
+
+    /// Read the epoch of the last committed block
+    async fn query_epoch(&self) -> Result<Epoch> {
+        let path = EPOCH_PATH.to_owned();
+        let response =
+            self.query_raw(path.clone(), vec![], None, false).await?;
+        match response.code {
+            0 => Epoch::try_from_slice(&response.value[..])
+                .map_err(|error| Error::Decoding { path, error }),
+            code => Err(Error::Query {
+                path,
+                code,
+                info: response.info,
+            }),
+        }
+    }
+
+    /// Simulate the validation and the application of the given encoded
+    /// transaction. Returns the description of the tx result.
+    async fn dry_run_tx(&self, tx_bytes: Vec<u8>) -> Result<String> {
+        let path = DRY_RUN_TX_PATH.to_owned();
+        let response =
+            self.query_raw(path.clone(), tx_bytes, None, false).await?;
+        if response.code == 0 {
+            Ok(response.info)
+        } else {
+            Err(Error::Query {
+                path,
+                code: response.code,
+                info: response.log,
+            })
+        }
+    }
+}
+
+/// The state of a [`query_prefix_stream`] between the items of its stream
+struct PrefixStreamState {
+    /// The key after which the next page starts, if any
+    after: Option<Key>,
+    /// The height of the first page, at which the next pages are read
+    height: Option<BlockHeight>,
+    /// The values of the last received page that are not yielded yet
+    pending: VecDeque<PrefixValue>,
+    /// The path of the last page query
+    path: String,
+    done: bool,
+}
+
+/// Stream the storage values whose keys match the given prefix and decode
+/// them with [`BorshDeserialize`]. The values are ordered by their keys.
+/// Unlike [`QueryClient::query_prefix`], the values are fetched in pages of
+/// at most `page_limit` values, up to `MAX_PREFIX_PAGE_LIMIT`, and the next
+/// page is only requested once the values of the previous one are consumed,
+/// so that neither the node nor the client holds all the values of a large
+/// prefix in memory. The pages are read at the height of the first page, so
+/// that the values are consistent when new blocks are committed in between.
+/// The stream ends after the first error.
+pub fn query_prefix_stream<'a, T, C>(
+    client: &'a C,
+    prefix: Key,
+    page_limit: u64,
+) -> impl Stream<Item = Result<(Key, T)>> + 'a
+where
+    T: BorshDeserialize + 'a,
+    C: QueryClient + ?Sized,
+{
+    let state = PrefixStreamState {
+        after: None,
+        height: None,
+        pending: VecDeque::new(),
+        path: String::new(),
+        done: false,
+    };
+    futures::stream::unfold(state, move |mut state| {
+        let prefix = prefix.clone();
+        async move {
+            loop {
+                if let Some(PrefixValue { key, value }) =
+                    state.pending.pop_front()
+                {
+                    let result = T::try_from_slice(&value[..])
+                        .map(|value| (key, value))
+                        .map_err(|error| Error::Decoding {
+                            path: state.path.clone(),
+                            error,
+                        });
+                    if result.is_err() {
+                        state.done = true;
+                        state.pending.clear();
+                    }
+                    return Some((result, state));
+                }
+                if state.done {
+                    return None;
+                }
+                // The `after` key is hex encoded, so that it's a single
+                // segment of the path
+                let after = state
+                    .after
+                    .as_ref()
+                    .map(|after| hex::encode(after.to_string()))
+                    .unwrap_or_default();
+                state.path = format!(
+                    "{}/{}/{}/{}",
+                    PREFIX_PAGE_PREFIX, page_limit, after, prefix
+                );
+                let response = client
+                    .query_raw(state.path.clone(), vec![], state.height, false)
+                    .await;
+                let page = match response {
+                    Ok(response) if response.code == 0 => {
+                        // A node that doesn't report the height of its
+                        // responses answers every page at its last block
+                        if state.height.is_none() && response.height.0 > 0 {
+                            state.height = Some(response.height);
+                        }
+                        PrefixPage::try_from_slice(&response.value[..]).map_err(
+                            |error| Error::Decoding {
+                                path: state.path.clone(),
+                                error,
+                            },
+                        )
+                    }
+                    Ok(response) => Err(Error::Query {
+                        path: state.path.clone(),
+                        code: response.code,
+                        info: response.info,
+                    }),
+                    Err(err) => Err(err),
+                };
+                match page {
+                    Ok(PrefixPage { values, next }) => {
+                        state.done = next.is_none();
+                        state.after = next;
+                        state.pending.extend(values);
+                    }
+                    Err(err) => {
+                        state.done = true;
+                        return Some((Err(err), state));
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Read the storage value of the given key at the last committed block and
+/// decode it as `T` with [`BorshDeserialize`]. Unlike
+/// [`QueryClient::query_value`], a missing key is an error, which is told
+/// apart from a value that doesn't decode as `T` and from a failed query.
+pub async fn query_storage_value<T, C>(
+    client: &C,
+    key: &Key,
+) -> std::result::Result<T, StorageValueError>
+where
+    T: BorshDeserialize,
+    C: QueryClient + ?Sized,
+{
+    let path = format!("{}/{}", VALUE_PREFIX, key);
+    let response = client
+        .query_raw(path.clone(), vec![], None, false)
+        .await
+        .map_err(|error| StorageValueError::Query {
+            key: key.clone(),
+            error,
+        })?;
+    match response.code {
+        0 => T::try_from_slice(&response.value[..]).map_err(|error| {
+            StorageValueError::Decoding {
+                key: key.clone(),
+                type_name: std::any::type_name::<T>(),
+                error,
+            }
+        }),
+        NOT_FOUND_CODE => Err(StorageValueError::KeyMissing(key.clone())),
+        code => Err(StorageValueError::Query {
+            key: key.clone(),
+            error: Error::Query {
+                path,
+                code,
+                info: response.info,
+            },
+        }),
+    }
+}
+
+/// The policy of the retries of the queries that fail with a transient error
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// The delay before the first retry
+    pub initial_delay: Duration,
+    /// The maximum delay between two attempts
+    pub max_delay: Duration,
+    /// The maximum number of retries of a query
+    pub max_retries: u32,
+    /// The maximum duration of a query with its retries. When not set, only
+    /// the number of retries is limited.
+    pub deadline: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(5),
+            max_retries: 5,
+            deadline: Some(Duration::from_secs(60)),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The delay before the given retry, counted from `1`, which doubles
+    /// after every retry up to the maximum delay. A random jitter of up to a
+    /// half of the delay is subtracted, so that the clients that failed
+    /// together don't retry together.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(retry.saturating_sub(1));
+        let delay = self
+            .initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay);
+        let jitter: f64 = rand::thread_rng().gen_range(0.0..0.5);
+        delay.mul_f64(1.0 - jitter)
+    }
+
+    /// Call the query until its result isn't retryable according to
+    /// `is_retryable`, the retries are exhausted or the deadline passes.
+    /// Returns the last result of the query.
+    pub async fn retry<T, F, Fut>(
+        &self,
+        mut query: F,
+        is_retryable: impl Fn(&Result<T>) -> bool,
+    ) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let attempts = async {
+            let mut retries = 0;
+            loop {
+                let result = query().await;
+                if retries >= self.max_retries || !is_retryable(&result) {
+                    return result;
+                }
+                retries += 1;
+                tokio::time::sleep(self.delay(retries)).await;
+            }
+        };
+        match self.deadline {
+            Some(deadline) => tokio::time::timeout(deadline, attempts)
+                .await
+                .unwrap_or(Err(Error::Timeout(deadline))),
+            None => attempts.await,
+        }
+    }
+}
+
+/// Whether the result of a raw query may be transient: either an error that
+/// is retryable, or a response rejected by the rate limiter of the node
+pub fn is_transient(result: &Result<QueryResponse>) -> bool {
+    match result {
+        Ok(response) => response.code == RATE_LIMITED_CODE,
+        Err(err) => err.is_retryable(),
+    }
+}
+
+/// A [`QueryClient`] that retries the queries with a transient result, see
+/// [`is_transient`], with a [`RetryPolicy`]
+#[derive(Debug)]
+pub struct RetryingClient<C> {
+    client: C,
+    policy: RetryPolicy,
+}
+
+impl<C> RetryingClient<C> {
+    /// Wrap the client to retry its queries with the given policy
+    pub fn new(client: C, policy: RetryPolicy) -> Self {
+        Self { client, policy }
+    }
+
+    /// The wrapped client
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+}
+
+#[async_trait]
+impl<C> QueryClient for RetryingClient<C>
+where
+    C: QueryClient + Send,
+{
+    async fn query_raw(
+        &self,
+        path: String,
+        data: Vec<u8>,
+        height: Option<BlockHeight>,
+        prove: bool,
+    ) -> Result<QueryResponse> {
+        self.policy
+            .retry(
+                || {
+                    self.client.query_raw(
+                        path.clone(),
+                        data.clone(),
+                        height,
+                        prove,
+                    )
+                },
+                is_transient,
+            )
+            .await
+    }
+}
+
+/// The cache key of a query: its path, data, height and whether it's proven
+type CacheKey = (String, Vec<u8>, Option<BlockHeight>, bool);
+
+/// A [`QueryClient`] that caches the responses of the queries by their path,
+/// data and height. The responses of the queries at the last committed block
+/// are cached until a new block is observed, either with
+/// [`CachingClient::observe_block`], e.g. on the new block headers of a
+/// subscription, or from the height of a response, or until they're older
+/// than the maximum age set with [`CachingClient::with_max_age`]. When the
+/// cache is full, the least recently used response is evicted. Only the
+/// successful responses are cached, and the dry runs of txs aren't cached.
+#[derive(Debug)]
+pub struct CachingClient<C> {
+    client: C,
+    max_entries: usize,
+    max_age: Option<Duration>,
+    cache: Mutex<QueryCache>,
+}
+
+#[derive(Debug, Default)]
+struct QueryCache {
+    /// The height of the last observed block
+    last_height: Option<BlockHeight>,
+    responses: HashMap<CacheKey, CachedResponse>,
+    /// The keys of the cached responses by the tick of their last use, the
+    /// least recently used first
+    recency: BTreeMap<u64, CacheKey>,
+    /// The tick of the last use of a response
+    tick: u64,
+}
+
+#[derive(Debug)]
+struct CachedResponse {
+    response: QueryResponse,
+    /// The tick of the last use of the response
+    last_used: u64,
+    cached_at: Instant,
+}
+
+impl QueryCache {
+    /// Invalidate the responses of the last committed block when a newer
+    /// block is observed
+    fn observe_block(&mut self, height: BlockHeight) {
+        match self.last_height {
+            Some(last_height) if last_height >= height => {}
+            _ => {
+                self.last_height = Some(height);
+                self.responses
+                    .retain(|(_, _, height, _), _| height.is_some());
+                self.recency.retain(|_, (_, _, height, _)| height.is_some());
+            }
+        }
+    }
+
+    /// Find the cached response of the query and mark it as the most
+    /// recently used. A response of the last committed block older than the
+    /// maximum age is evicted.
+    fn get(
+        &mut self,
+        key: &CacheKey,
+        max_age: Option<Duration>,
+    ) -> Option<QueryResponse> {
+        let (_, _, height, _) = key;
+        let cached = self.responses.get(key)?;
+        let expired = height.is_none()
+            && max_age
+                .map_or(false, |max_age| cached.cached_at.elapsed() >= max_age);
+        if expired {
+            self.remove(key);
+            return None;
+        }
+        self.tick += 1;
+        let cached = self.responses.get_mut(key)?;
+        self.recency.remove(&cached.last_used);
+        cached.last_used = self.tick;
+        self.recency.insert(self.tick, key.clone());
+        Some(cached.response.clone())
+    }
+
+    /// Cache the response of the query, evicting the least recently used
+    /// response if the cache is full
+    fn insert(
+        &mut self,
+        key: CacheKey,
+        response: QueryResponse,
+        max_entries: usize,
+    ) {
+        self.remove(&key);
+        if max_entries == 0 {
+            return;
+        }
+        if self.responses.len() >= max_entries {
+            let least_recent = self.recency.keys().next().copied();
+            if let Some(tick) = least_recent {
+                if let Some(evicted) = self.recency.remove(&tick) {
+                    self.responses.remove(&evicted);
+                }
+            }
+        }
+        self.tick += 1;
+        self.recency.insert(self.tick, key.clone());
+        self.responses.insert(
+            key,
+            CachedResponse {
+                response,
+                last_used: self.tick,
+                cached_at: Instant::now(),
+            },
+        );
+    }
+
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(cached) = self.responses.remove(key) {
+            self.recency.remove(&cached.last_used);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.responses.clear();
+        self.recency.clear();
+    }
+}
+
+impl<C> CachingClient<C> {
+    /// Wrap the client with a cache of at most `max_entries` responses
+    pub fn new(client: C, max_entries: usize) -> Self {
+        Self {
+            client,
+            max_entries,
+            max_age: None,
+            cache: Mutex::new(QueryCache::default()),
+        }
+    }
+
+    /// Keep the responses of the queries at the last committed block for at
+    /// most `max_age`, for a client that doesn't observe the new blocks
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Observe a new block, which invalidates the cached responses of the
+    /// queries at the last committed block
+    pub fn observe_block(&self, height: BlockHeight) {
+        self.lock().observe_block(height)
+    }
+
+    /// Clear all the cached responses
+    pub fn clear(&self) {
+        self.lock().clear()
+    }
+
+    /// The wrapped client
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, QueryCache> {
+        self.cache
+            .lock()
+            .expect("The query cache lock shouldn't be poisoned")
+    }
+}
+
+#[async_trait]
+impl<C> QueryClient for CachingClient<C>
+where
+    C: QueryClient + Send,
+{
+    async fn query_raw(
+        &self,
+        path: String,
+        data: Vec<u8>,
+        height: Option<BlockHeight>,
+        prove: bool,
+    ) -> Result<QueryResponse> {
+        if path == DRY_RUN_TX_PATH {
+            return self.client.query_raw(path, data, height, prove).await;
+        }
+        let key = (path, data, height, prove);
+        let cached = self.lock().get(&key, self.max_age);
+        if let Some(response) = cached {
+            return Ok(response);
+        }
+        let (path, data, height, prove) = key;
+        let response = self
+            .client
+            .query_raw(path.clone(), data.clone(), height, prove)
+            .await?;
+        if response.code == 0 {
+            let mut cache = self.lock();
+            if height.is_none() {
+                cache.observe_block(response.height);
+            }
+            // A response of an older block than the last observed one isn't
+            // cached as the response of the last block
+            if height.is_some() || cache.last_height == Some(response.height) {
+                cache.insert(
+                    (path, data, height, prove),
+                    response.clone(),
+                    self.max_entries,
+                );
+            }
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl<C> QueryClient for C
+where
+    C: Client + Sync,
+{
+    async fn query_raw(
+        &self,
+        path: String,
+        data: Vec<u8>,
+        height: Option<BlockHeight>,
+        prove: bool,
+    ) -> Result<QueryResponse> {
+        let abci_path = AbciPath::from_str(&path)
+            .map_err(|_| Error::InvalidPath(path.clone()))?;
+        let height = height
+            .map(|height| {
+                Height::try_from(height.0)
+                    .map_err(|_| Error::InvalidHeight(height))
+            })
+            .transpose()?;
+        let response = self
+            .abci_query(Some(abci_path), data, height, prove)
+            .await
+            .map_err(Error::Rpc)?;
+        let code = match response.code {
+            Code::Ok => 0,
+            Code::Err(code) => code,
+        };
+        Ok(QueryResponse {
+            code,
+            info: response.info,
+            log: response.log.to_string(),
+            value: response.value,
+            height: BlockHeight(response.height.value()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use borsh::BorshSerialize;
+
+    use super::*;
+
+    /// The height of the pages of the mock client queried at the last block
+    const MOCK_PAGE_HEIGHT: BlockHeight = BlockHeight(10);
+
+    /// A mock client that answers the value and prefix queries from a map
+    #[derive(Default)]
+    struct MockClient {
+        storage: BTreeMap<String, Vec<u8>>,
+        /// The number of the received queries
+        queries: AtomicUsize,
+        /// The heights of the received queries
+        heights: Mutex<Vec<Option<BlockHeight>>>,
+    }
+
+    #[async_trait]
+    impl QueryClient for MockClient {
+        async fn query_raw(
+            &self,
+            path: String,
+            data: Vec<u8>,
+            height: Option<BlockHeight>,
+            _prove: bool,
+        ) -> Result<QueryResponse> {
+            self.queries.fetch_add(1, Ordering::SeqCst);
+            self.heights.lock().unwrap().push(height);
+            let not_found = QueryResponse {
+                code: NOT_FOUND_CODE,
+                ..Default::default()
+            };
+            let response = match path.split_once('/') {
+                Some((VALUE_PREFIX, key)) => match self.storage.get(key) {
+                    Some(value) => QueryResponse {
+                        value: value.clone(),
+                        ..Default::default()
+                    },
+                    None => not_found,
+                },
+                Some((PREFIX_PREFIX, prefix)) => {
+                    let values: Vec<PrefixValue> = self
+                        .storage
+                        .iter()
+                        .filter(|(key, _)| key.starts_with(prefix))
+                        .map(|(key, value)| PrefixValue {
+                            key: Key::parse(key).unwrap(),
+                            value: value.clone(),
+                        })
+                        .collect();
+                    if values.is_empty() {
+                        not_found
+                    } else {
+                        QueryResponse {
+                            value: values.try_to_vec().unwrap(),
+                            ..Default::default()
+                        }
+                    }
+                }
+                Some((PREFIX_PAGE_PREFIX, page)) => {
+                    let segments: Vec<&str> = page.splitn(3, '/').collect();
+                    let limit: usize = segments[0].parse().unwrap();
+                    let after =
+                        String::from_utf8(hex::decode(segments[1]).unwrap())
+                            .unwrap();
+                    let prefix = segments[2];
+                    let mut values: Vec<PrefixValue> = self
+                        .storage
+                        .iter()
+                        .filter(|(key, _)| {
+                            key.starts_with(prefix) && **key > after
+                        })
+                        .take(limit + 1)
+                        .map(|(key, value)| PrefixValue {
+                            key: Key::parse(key).unwrap(),
+                            value: value.clone(),
+                        })
+                        .collect();
+                    let next = if values.len() > limit {
+                        values.truncate(limit);
+                        values.last().map(|value| value.key.clone())
+                    } else {
+                        None
+                    };
+                    QueryResponse {
+                        value: PrefixPage { values, next }
+                            .try_to_vec()
+                            .unwrap(),
+                        height: height.unwrap_or(MOCK_PAGE_HEIGHT),
+                        ..Default::default()
+                    }
+                }
+                _ if path == EPOCH_PATH => QueryResponse {
+                    value: Epoch(3).try_to_vec().unwrap(),
+                    ..Default::default()
+                },
+                _ if path == DRY_RUN_TX_PATH => QueryResponse {
+                    info: format!("Applied a tx of {} bytes", data.len()),
+                    ..Default::default()
+                },
+                _ => return Err(Error::InvalidPath(path)),
+            };
+            Ok(response)
+        }
+    }
+
+    #[test]
+    fn test_query_mock_client() {
+        let mut client = MockClient::default();
+        client
+            .storage
+            .insert("a/b".to_owned(), 1_u64.try_to_vec().unwrap());
+        client
+            .storage
+            .insert("a/c".to_owned(), 2_u64.try_to_vec().unwrap());
+
+        futures::executor::block_on(async {
+            let key = Key::parse("a/b").unwrap();
+            let value: Option<u64> = client.query_value(&key).await.unwrap();
+            assert_eq!(value, Some(1));
+            let key = Key::parse("a/d").unwrap();
+            let value: Option<u64> = client.query_value(&key).await.unwrap();
+            assert_eq!(value, None);
+
+            let prefix = Key::parse("a").unwrap();
+            let values: Vec<(Key, u64)> =
+                client.query_prefix(&prefix).await.unwrap();
+            assert_eq!(values.len(), 2);
+            assert_eq!(values[0].0, Key::parse("a/b").unwrap());
+            assert_eq!(values[1].1, 2);
+            let prefix = Key::parse("b").unwrap();
+            let values: Vec<(Key, u64)> =
+                client.query_prefix(&prefix).await.unwrap();
+            assert!(values.is_empty());
+
+            // A value that doesn't decode as the requested type
+            let key = Key::parse("a/b").unwrap();
+            let result: Result<Option<String>> = client.query_value(&key).await;
+            assert!(matches!(result, Err(Error::Decoding { .. })));
+
+            assert_eq!(client.query_epoch().await.unwrap(), Epoch(3));
+
+            let info = client.dry_run_tx(vec![0; 3]).await.unwrap();
+            assert_eq!(info, "Applied a tx of 3 bytes");
+        });
+    }
+
+    /// Test that a typed storage value query tells apart a missing key from a
+    /// value of another type.
+    #[test]
+    fn test_query_storage_value() {
+        let mut client = MockClient::default();
+        client
+            .storage
+            .insert("a/b".to_owned(), 1_u64.try_to_vec().unwrap());
+
+        futures::executor::block_on(async {
+            let key = Key::parse("a/b").unwrap();
+            let value = query_storage_value::<u64, _>(&client, &key).await;
+            assert_eq!(value.unwrap(), 1);
+            let result = query_storage_value::<String, _>(&client, &key).await;
+            assert!(matches!(
+                result,
+                Err(StorageValueError::Decoding { type_name, .. })
+                    if type_name.ends_with("String")
+            ));
+
+            let key = Key::parse("a/c").unwrap();
+            let result = query_storage_value::<u64, _>(&client, &key).await;
+            assert!(matches!(
+                result,
+                Err(StorageValueError::KeyMissing(missing)) if missing == key
+            ));
+        });
+    }
+
+    /// Test that the values of a prefix are streamed page by page, all the
+    /// pages being read at the height of the first one.
+    #[test]
+    fn test_query_prefix_stream() {
+        use futures::{StreamExt, TryStreamExt};
+
+        let mut client = MockClient::default();
+        for i in 0..5_u64 {
+            client
+                .storage
+                .insert(format!("a/{}", i), i.try_to_vec().unwrap());
+        }
+        client
+            .storage
+            .insert("b/0".to_owned(), 0_u64.try_to_vec().unwrap());
+
+        futures::executor::block_on(async {
+            let prefix = Key::parse("a").unwrap();
+            let values: Vec<(Key, u64)> =
+                query_prefix_stream(&client, prefix.clone(), 2)
+                    .try_collect()
+                    .await
+                    .unwrap();
+            let expected: Vec<(Key, u64)> = (0..5_u64)
+                .map(|i| (Key::parse(format!("a/{}", i)).unwrap(), i))
+                .collect();
+            assert_eq!(values, expected);
+            assert_eq!(
+                *client.heights.lock().unwrap(),
+                vec![None, Some(MOCK_PAGE_HEIGHT), Some(MOCK_PAGE_HEIGHT)]
+            );
+
+            // A prefix without values is an empty stream
+            let prefix = Key::parse("c").unwrap();
+            let count = query_prefix_stream::<u64, _>(&client, prefix, 2)
+                .count()
+                .await;
+            assert_eq!(count, 0);
+
+            // The stream ends after a value that doesn't decode
+            let prefix = Key::parse("a").unwrap();
+            let results: Vec<Result<(Key, String)>> =
+                query_prefix_stream(&client, prefix, 2).collect().await;
+            assert_eq!(results.len(), 1);
+            assert!(matches!(results[0], Err(Error::Decoding { .. })));
+        });
+    }
+
+    /// Test that the responses of the last block are cached until a new block
+    /// is observed and that the values not found and the dry runs aren't
+    /// cached.
+    #[test]
+    fn test_caching_client() {
+        let mut client = MockClient::default();
+        client
+            .storage
+            .insert("a/b".to_owned(), 1_u64.try_to_vec().unwrap());
+        let client = CachingClient::new(client, 10);
+        let queries = || client.inner().queries.load(Ordering::SeqCst);
+
+        futures::executor::block_on(async {
+            let key = Key::parse("a/b").unwrap();
+            let missing = Key::parse("a/c").unwrap();
+            for _ in 0..2 {
+                let value: Option<u64> =
+                    client.query_value(&key).await.unwrap();
+                assert_eq!(value, Some(1));
+                let value: Option<u64> =
+                    client.query_value(&missing).await.unwrap();
+                assert_eq!(value, None);
+            }
+            assert_eq!(queries(), 3);
+
+            // A query at a given height isn't invalidated by a new block
+            let path = format!("{}/{}", VALUE_PREFIX, key);
+            let height = Some(BlockHeight(0));
+            client
+                .query_raw(path.clone(), vec![], height, false)
+                .await
+                .unwrap();
+            client.observe_block(BlockHeight(1));
+            client.query_raw(path, vec![], height, false).await.unwrap();
+            assert_eq!(queries(), 4);
+
+            // The responses of the last block are invalidated
+            let value: Option<u64> = client.query_value(&key).await.unwrap();
+            assert_eq!(value, Some(1));
+            assert_eq!(queries(), 5);
+
+            client.dry_run_tx(vec![]).await.unwrap();
+            client.dry_run_tx(vec![]).await.unwrap();
+            assert_eq!(queries(), 7);
+        });
+    }
+
+    /// Test that the least recently used response is evicted from a full
+    /// cache and that the responses of the last block expire after the
+    /// maximum age.
+    #[test]
+    fn test_caching_client_eviction() {
+        let mut client = MockClient::default();
+        for key in ["a", "b", "c"] {
+            client
+                .storage
+                .insert(format!("x/{}", key), 1_u64.try_to_vec().unwrap());
+        }
+        let client = CachingClient::new(client, 2);
+        let queries = || client.inner().queries.load(Ordering::SeqCst);
+
+        futures::executor::block_on(async {
+            let read = |key: &str| {
+                let key = Key::parse(format!("x/{}", key)).unwrap();
+                let client = &client;
+                async move {
+                    let _: Option<u64> =
+                        client.query_value(&key).await.unwrap();
+                }
+            };
+            read("a").await;
+            read("b").await;
+            read("a").await;
+            assert_eq!(queries(), 2);
+            // "b" is the least recently used
+            read("c").await;
+            read("a").await;
+            assert_eq!(queries(), 3);
+            read("b").await;
+            assert_eq!(queries(), 4);
+        });
+
+        let client =
+            CachingClient::new(client.client, 10).with_max_age(Duration::ZERO);
+        futures::executor::block_on(async {
+            let before = client.inner().queries.load(Ordering::SeqCst);
+            for _ in 0..2 {
+                client.query_epoch().await.unwrap();
+            }
+            let after = client.inner().queries.load(Ordering::SeqCst);
+            assert_eq!(after - before, 2);
+        });
+    }
+
+    /// A mock client whose queries are rejected by the rate limiter of the
+    /// node a number of times before they're answered
+    struct FlakyClient {
+        rejections: AtomicUsize,
+        queries: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl QueryClient for FlakyClient {
+        async fn query_raw(
+            &self,
+            path: String,
+            _data: Vec<u8>,
+            _height: Option<BlockHeight>,
+            _prove: bool,
+        ) -> Result<QueryResponse> {
+            self.queries.fetch_add(1, Ordering::SeqCst);
+            if path == "invalid" {
+                return Err(Error::InvalidPath(path));
+            }
+            if path == "storage_error" {
+                return Ok(QueryResponse {
+                    code: 2,
+                    ..Default::default()
+                });
+            }
+            let rejected = self
+                .rejections
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                    left.checked_sub(1)
+                })
+                .is_ok();
+            let code = if rejected { RATE_LIMITED_CODE } else { 0 };
+            Ok(QueryResponse {
+                code,
+                ..Default::default()
+            })
+        }
+    }
+
+    /// Test that the transient failures are retried up to the maximum number
+    /// of retries and that the other errors aren't retried.
+    #[test]
+    fn test_retrying_client() {
+        let policy = RetryPolicy {
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(2),
+            max_retries: 3,
+            deadline: None,
+        };
+        let client = RetryingClient::new(
+            FlakyClient {
+                rejections: AtomicUsize::new(3),
+                queries: AtomicUsize::new(0),
+            },
+            policy,
+        );
+        let queries = || client.inner().queries.load(Ordering::SeqCst);
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+
+        runtime.block_on(async {
+            let path = "epoch".to_owned();
+            let response = client
+                .query_raw(path.clone(), vec![], None, false)
+                .await
+                .unwrap();
+            assert_eq!(response.code, 0);
+            assert_eq!(queries(), 4);
+
+            client.inner().rejections.store(5, Ordering::SeqCst);
+            let response =
+                client.query_raw(path, vec![], None, false).await.unwrap();
+            assert_eq!(response.code, RATE_LIMITED_CODE);
+            assert_eq!(queries(), 8);
+
+            let result =
+                client.query_raw("invalid".to_owned(), vec![], None, false);
+            assert!(matches!(result.await, Err(Error::InvalidPath(_))));
+            assert_eq!(queries(), 9);
+
+            // The storage errors of the queries aren't retried
+            let response = client
+                .query_raw("storage_error".to_owned(), vec![], None, false)
+                .await
+                .unwrap();
+            assert_eq!(response.code, 2);
+            assert_eq!(queries(), 10);
+        });
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let policy = RetryPolicy {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(10),
+            ..Default::default()
+        };
+        let delay = policy.delay(3);
+        assert!(
+            delay > Duration::from_secs(2) && delay <= Duration::from_secs(4)
+        );
+        let delay = policy.delay(100);
+        assert!(delay > Duration::from_secs(5));
+        assert!(delay <= Duration::from_secs(10));
+    }
+}
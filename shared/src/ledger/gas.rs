@@ -21,10 +21,14 @@ const COMPILE_GAS_PER_BYTE: u64 = 1;
 const BASE_TRANSACTION_FEE: u64 = 2;
 const PARALLEL_GAS_DIVIDER: u64 = 10;
 
+/// The maximum gas that can be used by all the transactions in a block. It's
+/// the default of the `max_block_gas` protocol parameter and its upper bound.
 /// The maximum value should be less or equal to i64::MAX
 /// to avoid the gas overflow when sending this to ABCI
-const BLOCK_GAS_LIMIT: u64 = 10_000_000_000_000;
-const TRANSACTION_GAS_LIMIT: u64 = 10_000_000_000;
+pub const BLOCK_GAS_LIMIT: u64 = 10_000_000_000_000;
+/// The maximum gas that can be used by a single transaction. It's the default
+/// of the `max_tx_gas` protocol parameter and its upper bound.
+pub const TRANSACTION_GAS_LIMIT: u64 = 10_000_000_000;
 
 /// The minimum gas cost for accessing the storage
 pub const MIN_STORAGE_GAS: u64 = 1;
@@ -34,10 +38,26 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 /// Gas metering in a block. Tracks the gas in a current block and a current
 /// transaction.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Clone)]
 pub struct BlockGasMeter {
     block_gas: u64,
     transaction_gas: u64,
+    block_gas_limit: u64,
+    transaction_gas_limit: u64,
+}
+
+/// The gas used by the transactions of a committed block, together with the
+/// gas limits that applied to it
+#[derive(
+    Clone, Debug, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize,
+)]
+pub struct BlockGasUsage {
+    /// The maximum gas that can be used by all the transactions in a block
+    pub max_block_gas: u64,
+    /// The maximum gas that can be used by a single transaction
+    pub max_tx_gas: u64,
+    /// The gas used by the transactions of the block
+    pub used_gas: u64,
 }
 
 /// Gas metering in a validity predicate
@@ -58,7 +78,28 @@ pub struct VpsGas {
     rest: Vec<u64>,
 }
 
+impl Default for BlockGasMeter {
+    fn default() -> Self {
+        Self::new(BLOCK_GAS_LIMIT, TRANSACTION_GAS_LIMIT)
+    }
+}
+
 impl BlockGasMeter {
+    /// Initialize a new block gas meter with the given block and transaction
+    /// gas limits. The limits are capped at [`BLOCK_GAS_LIMIT`] and
+    /// [`TRANSACTION_GAS_LIMIT`].
+    pub fn new(block_gas_limit: u64, transaction_gas_limit: u64) -> Self {
+        Self {
+            block_gas: 0,
+            transaction_gas: 0,
+            block_gas_limit: std::cmp::min(block_gas_limit, BLOCK_GAS_LIMIT),
+            transaction_gas_limit: std::cmp::min(
+                transaction_gas_limit,
+                TRANSACTION_GAS_LIMIT,
+            ),
+        }
+    }
+
     /// Add gas cost for the current transaction. It will return error when the
     /// consumed gas exceeds the transaction gas limit, but the state will still
     /// be updated.
@@ -68,7 +109,7 @@ impl BlockGasMeter {
             .checked_add(gas)
             .ok_or(Error::GasOverflow)?;
 
-        if self.transaction_gas > TRANSACTION_GAS_LIMIT {
+        if self.transaction_gas > self.transaction_gas_limit {
             return Err(Error::TransactionGasExceedededError);
         }
         Ok(())
@@ -88,19 +129,21 @@ impl BlockGasMeter {
 
     /// Add the transaction gas to the block's total gas. Returns the
     /// transaction's gas cost and resets the transaction meter. It will return
-    /// error when the consumed gas exceeds the block gas limit, but the state
-    /// will still be updated.
+    /// error when the consumed gas would exceed the block gas limit, in which
+    /// case the transaction's gas is not added, so that the transactions
+    /// after it can still fit in the block.
     pub fn finalize_transaction(&mut self) -> Result<u64> {
-        self.block_gas = self
+        let block_gas = self
             .block_gas
             .checked_add(self.transaction_gas)
             .ok_or(Error::GasOverflow)?;
 
         let transaction_gas = self.transaction_gas;
         self.transaction_gas = 0;
-        if self.block_gas > BLOCK_GAS_LIMIT {
+        if block_gas > self.block_gas_limit {
             return Err(Error::BlockGasExceeded);
         }
+        self.block_gas = block_gas;
         Ok(transaction_gas)
     }
 
@@ -115,12 +158,34 @@ impl BlockGasMeter {
         self.transaction_gas
     }
 
+    /// Check if the current transaction used more gas than its limit.
+    pub fn is_transaction_gas_exceeded(&self) -> bool {
+        self.transaction_gas > self.transaction_gas_limit
+    }
+
+    /// Get the gas used by the finalized transactions of the current block,
+    /// together with its gas limits.
+    pub fn get_block_gas_usage(&self) -> BlockGasUsage {
+        BlockGasUsage {
+            max_block_gas: self.block_gas_limit,
+            max_tx_gas: self.transaction_gas_limit,
+            used_gas: self.block_gas,
+        }
+    }
+
     /// Add the gas cost used in validity predicates to the current transaction.
     pub fn add_vps_gas(&mut self, vps_gas: &VpsGas) -> Result<()> {
         self.add(vps_gas.get_current_gas()?)
     }
 }
 
+impl BlockGasUsage {
+    /// Get the gas that's left in the block for more transactions
+    pub fn remaining_gas(&self) -> u64 {
+        self.max_block_gas.saturating_sub(self.used_gas)
+    }
+}
+
 impl VpGasMeter {
     /// Initialize a new VP gas meter, starting with the gas consumed in the
     /// transaction so far.
@@ -309,6 +374,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_configured_gas_limits() {
+        let mut meter = BlockGasMeter::new(300, 200);
+        meter.add(200).expect("over the tx gas limit");
+        meter
+            .finalize_transaction()
+            .expect("over the block gas limit");
+        meter.add(1).expect("over the tx gas limit");
+        assert_matches!(
+            meter.add(200),
+            Err(Error::TransactionGasExceedededError)
+        );
+        meter.reset();
+        assert_eq!(meter.get_block_gas_usage().used_gas, 0);
+
+        meter.add(200).expect("over the tx gas limit");
+        meter
+            .finalize_transaction()
+            .expect("over the block gas limit");
+        meter.add(150).expect("over the tx gas limit");
+        assert_matches!(
+            meter.finalize_transaction(),
+            Err(Error::BlockGasExceeded)
+        );
+        let usage = meter.get_block_gas_usage();
+        assert_eq!(usage.used_gas, 200);
+        assert_eq!(usage.remaining_gas(), 100);
+
+        // Only the tx over the block gas limit is rejected
+        meter.add(100).expect("over the tx gas limit");
+        assert_eq!(meter.finalize_transaction().unwrap(), 100);
+        assert_eq!(meter.get_block_gas_usage().remaining_gas(), 0);
+
+        // The configured limits cannot go over the hard limits
+        let usage =
+            BlockGasMeter::new(u64::MAX, u64::MAX).get_block_gas_usage();
+        assert_eq!(usage.max_block_gas, BLOCK_GAS_LIMIT);
+        assert_eq!(usage.max_tx_gas, TRANSACTION_GAS_LIMIT);
+    }
+
     /// Test that the function [`as_i64`] cannot fail for transaction and block
     /// gas limit + some "tolerance" for gas exhaustion.
     #[test]
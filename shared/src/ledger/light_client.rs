@@ -0,0 +1,381 @@
+//! Light client verification of the Tendermint headers. A header is verified
+//! against a trusted validator set, whose voting powers come from PoS, by
+//! checking that validators with more than 2/3 of the voting power signed the
+//! commit of the header.
+//!
+//! The verification only needs the signed header and the validator set, so it
+//! can be used by the clients and by the counterparties of the chain, e.g. a
+//! bridge or an IBC light client. The storage values can then be verified
+//! with their Merkle proofs against the app hash of a verified header.
+
+use std::collections::HashSet;
+
+use borsh::BorshDeserialize;
+use prost::Message;
+use thiserror::Error;
+
+use crate::ledger::pos::types::VotingPower;
+use crate::ledger::storage::{MerkleTree, Sha256Hasher};
+use crate::tendermint::block::Header;
+use crate::tendermint::merkle::proof::Proof;
+use crate::tendermint_proto::google::protobuf::Timestamp;
+use crate::tendermint_proto::types::{
+    BlockIdFlag, CanonicalBlockId, CanonicalPartSetHeader, CanonicalVote,
+    Commit, SignedHeader, SignedMsgType,
+};
+use crate::types::key::{common, ed25519, PublicKeyHash, SigScheme};
+use crate::types::storage::{BlockHeight, Key};
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("The signed header has no header")]
+    MissingHeader,
+    #[error("The signed header has no commit")]
+    MissingCommit,
+    #[error("The commit has no block ID")]
+    MissingBlockId,
+    #[error("Invalid header: {0}")]
+    InvalidHeader(String),
+    #[error("The header is at height {header} but the commit at {commit}")]
+    HeightMismatch { header: i64, commit: i64 },
+    #[error(
+        "The commit is for the block {commit}, not for the header {header}"
+    )]
+    HashMismatch { header: String, commit: String },
+    #[error("Validator {0} signed the commit more than once")]
+    DuplicateSignature(String),
+    #[error("Invalid signature of validator {0}")]
+    InvalidSignature(String),
+    #[error(
+        "The signatures have a voting power of {signed} out of {total}, which \
+         is not more than 2/3"
+    )]
+    InsufficientVotingPower { signed: u64, total: u64 },
+    #[error("Invalid Merkle proof of the storage key {key}: {error}")]
+    InvalidProof { key: String, error: String },
+}
+
+/// Result of a function that may fail
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A validator of a trusted validator set
+#[derive(Clone, Debug)]
+pub struct TrustedValidator {
+    /// The key with which the validator signs the blocks
+    pub consensus_key: common::PublicKey,
+    /// The voting power of the validator in PoS
+    pub voting_power: VotingPower,
+}
+
+/// A set of validators trusted to sign the headers, e.g. the active validator
+/// set of an epoch
+#[derive(Clone, Debug, Default)]
+pub struct TrustedValidatorSet {
+    validators: Vec<TrustedValidator>,
+}
+
+impl TrustedValidatorSet {
+    /// Create a validator set from its validators
+    pub fn new(validators: Vec<TrustedValidator>) -> Self {
+        Self { validators }
+    }
+
+    /// The sum of the voting powers of the validators
+    pub fn total_voting_power(&self) -> u64 {
+        self.validators
+            .iter()
+            .map(|validator| u64::from(validator.voting_power))
+            .sum()
+    }
+
+    /// Find the validator with the given Tendermint address
+    fn find(&self, tm_address: &[u8]) -> Option<&TrustedValidator> {
+        let address = hex::encode_upper(tm_address);
+        self.validators.iter().find(|validator| {
+            tm_address_of(&validator.consensus_key) == address
+        })
+    }
+}
+
+/// A header verified against a trusted validator set
+#[derive(Clone, Debug)]
+pub struct VerifiedHeader {
+    /// The verified header
+    pub header: Header,
+    /// The voting power of the validators that signed the commit
+    pub signed_voting_power: u64,
+    /// The total voting power of the trusted validator set
+    pub total_voting_power: u64,
+}
+
+impl VerifiedHeader {
+    /// The height of the header
+    pub fn height(&self) -> BlockHeight {
+        BlockHeight(self.header.height.value())
+    }
+}
+
+/// Verify that the header is signed by more than 2/3 of the voting power of
+/// the trusted validator set
+pub fn verify_signed_header(
+    signed_header: SignedHeader,
+    validators: &TrustedValidatorSet,
+) -> Result<VerifiedHeader> {
+    let header = signed_header.header.ok_or(Error::MissingHeader)?;
+    let commit = signed_header.commit.ok_or(Error::MissingCommit)?;
+    if header.height != commit.height {
+        return Err(Error::HeightMismatch {
+            header: header.height,
+            commit: commit.height,
+        });
+    }
+    let chain_id = header.chain_id.clone();
+    let header = Header::try_from(header)
+        .map_err(|err| Error::InvalidHeader(err.to_string()))?;
+    let header_hash = header.hash();
+    let signed_voting_power =
+        verify_commit(&chain_id, &commit, header_hash.as_bytes(), validators)?;
+    Ok(VerifiedHeader {
+        header,
+        signed_voting_power,
+        total_voting_power: validators.total_voting_power(),
+    })
+}
+
+/// Verify that the commit of the block with the given hash is signed by more
+/// than 2/3 of the voting power of the trusted validator set. Returns the
+/// voting power of the validators that signed it.
+///
+/// The signatures of the validators that are not in the set and the votes for
+/// no block are ignored.
+pub fn verify_commit(
+    chain_id: &str,
+    commit: &Commit,
+    header_hash: &[u8],
+    validators: &TrustedValidatorSet,
+) -> Result<u64> {
+    let block_id = commit.block_id.as_ref().ok_or(Error::MissingBlockId)?;
+    if block_id.hash != header_hash {
+        return Err(Error::HashMismatch {
+            header: hex::encode_upper(header_hash),
+            commit: hex::encode_upper(&block_id.hash),
+        });
+    }
+    let canonical_block_id = CanonicalBlockId {
+        hash: block_id.hash.clone(),
+        part_set_header: block_id.part_set_header.as_ref().map(|header| {
+            CanonicalPartSetHeader {
+                total: header.total,
+                hash: header.hash.clone(),
+            }
+        }),
+    };
+
+    let mut signers = HashSet::new();
+    let mut signed_voting_power = 0_u64;
+    for commit_sig in &commit.signatures {
+        if commit_sig.block_id_flag != BlockIdFlag::Commit as i32 {
+            continue;
+        }
+        let validator = match validators.find(&commit_sig.validator_address) {
+            Some(validator) => validator,
+            None => continue,
+        };
+        let address = hex::encode_upper(&commit_sig.validator_address);
+        if !signers.insert(address.clone()) {
+            return Err(Error::DuplicateSignature(address));
+        }
+        let sign_bytes = vote_sign_bytes(
+            chain_id,
+            commit,
+            &canonical_block_id,
+            commit_sig.timestamp.clone(),
+        );
+        let signature =
+            ed25519::Signature::try_from_slice(&commit_sig.signature)
+                .map_err(|_| Error::InvalidSignature(address.clone()))?;
+        common::SigScheme::verify_signature_raw(
+            &validator.consensus_key,
+            &sign_bytes,
+            &common::Signature::Ed25519(signature),
+        )
+        .map_err(|_| Error::InvalidSignature(address))?;
+        signed_voting_power += u64::from(validator.voting_power);
+    }
+
+    let total_voting_power = validators.total_voting_power();
+    // More than 2/3 of the voting power, without rounding
+    if signed_voting_power as u128 * 3 <= total_voting_power as u128 * 2 {
+        return Err(Error::InsufficientVotingPower {
+            signed: signed_voting_power,
+            total: total_voting_power,
+        });
+    }
+    Ok(signed_voting_power)
+}
+
+/// Verify the Merkle proof of the value of a storage key, or of the absence
+/// of the key if the value is `None`, against the app hash of a header. The
+/// app hash of a header is the Merkle root of the state committed in the
+/// previous block, so the proof has to be queried at the height before the
+/// header's height.
+pub fn verify_proof(
+    header: &Header,
+    key: &Key,
+    value: Option<&[u8]>,
+    proof: &Proof,
+) -> Result<()> {
+    MerkleTree::<Sha256Hasher>::verify_proof(
+        key,
+        value,
+        proof,
+        &header.app_hash.value(),
+    )
+    .map_err(|err| Error::InvalidProof {
+        key: key.to_string(),
+        error: err.to_string(),
+    })
+}
+
+/// The Tendermint address of a consensus key, as upper case hex. It's the
+/// hash of the raw Ed25519 key, without the scheme prefix of the common key.
+pub fn tm_address_of(consensus_key: &common::PublicKey) -> String {
+    match consensus_key {
+        common::PublicKey::Ed25519(pk) => PublicKeyHash::from(pk).into(),
+    }
+}
+
+/// The bytes signed by a validator for a precommit vote of the commit, which
+/// are the length-delimited Protobuf encoding of the canonical vote
+fn vote_sign_bytes(
+    chain_id: &str,
+    commit: &Commit,
+    block_id: &CanonicalBlockId,
+    timestamp: Option<Timestamp>,
+) -> Vec<u8> {
+    CanonicalVote {
+        r#type: SignedMsgType::Precommit as i32,
+        height: commit.height,
+        round: commit.round as i64,
+        block_id: Some(block_id.clone()),
+        timestamp,
+        chain_id: chain_id.to_string(),
+    }
+    .encode_length_delimited_to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tendermint_proto::types::{BlockId, CommitSig, PartSetHeader};
+    use crate::types::key::{testing, RefTo};
+
+    use super::*;
+
+    const CHAIN_ID: &str = "test-chain";
+
+    /// A commit of the given hash signed by the given keys
+    fn signed_commit(hash: &[u8], signers: &[common::SecretKey]) -> Commit {
+        let block_id = BlockId {
+            hash: hash.to_vec(),
+            part_set_header: Some(PartSetHeader {
+                total: 1,
+                hash: vec![1; 32],
+            }),
+        };
+        let mut commit = Commit {
+            height: 10,
+            round: 0,
+            block_id: Some(block_id.clone()),
+            signatures: vec![],
+        };
+        let canonical_block_id = CanonicalBlockId {
+            hash: block_id.hash,
+            part_set_header: Some(CanonicalPartSetHeader {
+                total: 1,
+                hash: vec![1; 32],
+            }),
+        };
+        let timestamp = Timestamp {
+            seconds: 1,
+            nanos: 0,
+        };
+        commit.signatures = signers
+            .iter()
+            .map(|keypair| {
+                let sign_bytes = vote_sign_bytes(
+                    CHAIN_ID,
+                    &commit,
+                    &canonical_block_id,
+                    Some(timestamp.clone()),
+                );
+                let common::Signature::Ed25519(signature) =
+                    common::SigScheme::sign(keypair, sign_bytes);
+                let address =
+                    hex::decode(tm_address_of(&keypair.ref_to())).unwrap();
+                CommitSig {
+                    block_id_flag: BlockIdFlag::Commit as i32,
+                    validator_address: address,
+                    timestamp: Some(timestamp.clone()),
+                    signature: borsh::BorshSerialize::try_to_vec(&signature)
+                        .unwrap(),
+                }
+            })
+            .collect();
+        commit
+    }
+
+    fn validator(keypair: &common::SecretKey, power: u64) -> TrustedValidator {
+        TrustedValidator {
+            consensus_key: keypair.ref_to(),
+            voting_power: VotingPower::from(power),
+        }
+    }
+
+    /// Test that a commit is only verified with the signatures of more than
+    /// 2/3 of the voting power of the trusted validators.
+    #[test]
+    fn test_verify_commit() {
+        let keypair_1 = testing::keypair_1();
+        let keypair_2 = testing::keypair_2();
+        let validators = TrustedValidatorSet::new(vec![
+            validator(&keypair_1, 70),
+            validator(&keypair_2, 30),
+        ]);
+        let hash = [2; 32];
+
+        let commit =
+            signed_commit(&hash, &[keypair_1.clone(), keypair_2.clone()]);
+        let signed =
+            verify_commit(CHAIN_ID, &commit, &hash, &validators).unwrap();
+        assert_eq!(signed, 100);
+
+        // 70 out of 100 is more than 2/3
+        let commit = signed_commit(&hash, &[keypair_1.clone()]);
+        let signed =
+            verify_commit(CHAIN_ID, &commit, &hash, &validators).unwrap();
+        assert_eq!(signed, 70);
+
+        let commit = signed_commit(&hash, &[keypair_2.clone()]);
+        assert!(matches!(
+            verify_commit(CHAIN_ID, &commit, &hash, &validators),
+            Err(Error::InsufficientVotingPower {
+                signed: 30,
+                total: 100
+            })
+        ));
+
+        // The commit of another block
+        assert!(matches!(
+            verify_commit(CHAIN_ID, &commit, &[3; 32], &validators),
+            Err(Error::HashMismatch { .. })
+        ));
+
+        // A signature for another chain
+        let commit = signed_commit(&hash, &[keypair_1]);
+        assert!(matches!(
+            verify_commit("other-chain", &commit, &hash, &validators),
+            Err(Error::InvalidSignature(_))
+        ));
+    }
+}
@@ -0,0 +1,44 @@
+//! Name service of the human-readable aliases of the addresses
+
+pub mod storage;
+pub mod vp;
+
+use crate::types::token;
+
+/// The minimum length of an alias
+pub const MIN_ALIAS_LEN: usize = 3;
+/// The maximum length of an alias
+pub const MAX_ALIAS_LEN: usize = 64;
+
+/// The fee in XAN that must be paid to the name service to register an alias
+pub const REGISTRATION_FEE: token::Amount = token::Amount::whole(10);
+
+/// Check that the alias is between [`MIN_ALIAS_LEN`] and [`MAX_ALIAS_LEN`]
+/// characters long and that it only contains lowercase ASCII letters, digits
+/// and dashes, which don't start or end the alias. An alias is shorter than
+/// an encoded address, so it can't be mistaken for one.
+pub fn is_valid_alias(alias: &str) -> bool {
+    (MIN_ALIAS_LEN..=MAX_ALIAS_LEN).contains(&alias.len())
+        && alias
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !alias.starts_with('-')
+        && !alias.ends_with('-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_alias() {
+        assert!(is_valid_alias("alice"));
+        assert!(is_valid_alias("bob-2"));
+        assert!(!is_valid_alias("al"));
+        assert!(!is_valid_alias("Alice"));
+        assert!(!is_valid_alias("-alice"));
+        assert!(!is_valid_alias("alice-"));
+        assert!(!is_valid_alias("alice.bob"));
+        assert!(!is_valid_alias(&"a".repeat(MAX_ALIAS_LEN + 1)));
+    }
+}
@@ -0,0 +1,45 @@
+//! Name service storage keys
+
+use super::vp::ADDRESS;
+use crate::types::storage::{DbKeySeg, Key, KeySeg};
+
+const ALIAS_STORAGE_KEY: &str = "alias";
+
+/// Obtain a storage key for the address registered with the given alias.
+pub fn alias_key(alias: &str) -> Key {
+    alias_prefix()
+        .push(&alias.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Obtain a storage key prefix for all the registered aliases.
+pub fn alias_prefix() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&ALIAS_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Check if the given storage key is an alias key. If it is, returns the
+/// alias.
+pub fn is_alias_key(key: &Key) -> Option<&String> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::StringSeg(alias),
+        ] if addr == &ADDRESS && prefix == ALIAS_STORAGE_KEY => Some(alias),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alias_key() {
+        let key = alias_key("alice");
+        assert_eq!(is_alias_key(&key), Some(&"alice".to_string()));
+        assert_eq!(is_alias_key(&alias_prefix()), None);
+    }
+}
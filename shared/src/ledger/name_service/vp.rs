@@ -0,0 +1,137 @@
+//! Validity predicate for the name service
+
+use std::collections::BTreeSet;
+
+use borsh::BorshDeserialize;
+use thiserror::Error;
+
+use super::storage as name_storage;
+use crate::ledger::native_vp::{self, Ctx, NativeVp};
+use crate::ledger::storage::{self as ledger_storage, StorageHasher};
+use crate::types::address::{self, Address, InternalAddress};
+use crate::types::storage::Key;
+use crate::types::token;
+use crate::vm::WasmCacheAccess;
+
+/// Internal address for the name service VP
+pub const ADDRESS: Address = Address::Internal(InternalAddress::NameService);
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Native VP error: {0}")]
+    NativeVpError(native_vp::Error),
+}
+
+/// Name service functions result
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Validity predicate for the name service. An alias can only be registered
+/// once, with the authorization of the address it resolves to and with the
+/// [`super::REGISTRATION_FEE`] paid to the name service, and it can never be
+/// changed or removed. The fees paid to the name service can't be spent.
+///
+/// The address must be a verifier of the tx, so that its VP checks that the
+/// registration is signed by it.
+pub struct NameServiceVp<'a, DB, H, CA>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    /// Context to interact with the host structures.
+    pub ctx: Ctx<'a, DB, H, CA>,
+}
+
+impl<'a, DB, H, CA> NativeVp for NameServiceVp<'a, DB, H, CA>
+where
+    DB: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    type Error = Error;
+
+    const ADDR: InternalAddress = InternalAddress::NameService;
+
+    fn validate_tx(
+        &self,
+        _tx_data: &[u8],
+        keys_changed: &BTreeSet<Key>,
+        verifiers: &BTreeSet<Address>,
+    ) -> Result<bool> {
+        let mut registered = 0_u64;
+        for key in keys_changed {
+            if key.segments.get(0) != Some(&ADDRESS.to_db_key()) {
+                continue;
+            }
+            let alias = match name_storage::is_alias_key(key) {
+                Some(alias) => alias,
+                None => return Ok(false),
+            };
+            if self.ctx.has_key_pre(key)? {
+                tracing::info!("The alias {} is already taken", alias);
+                return Ok(false);
+            }
+            if !super::is_valid_alias(alias) {
+                tracing::info!("The alias {} is not valid", alias);
+                return Ok(false);
+            }
+            let owner = match self.ctx.read_post(key)? {
+                Some(bytes) => match Address::try_from_slice(&bytes[..]) {
+                    Ok(owner) => owner,
+                    Err(_) => return Ok(false),
+                },
+                None => return Ok(false),
+            };
+            if !verifiers.contains(&owner) {
+                tracing::info!(
+                    "The registration of the alias {} is not authorized by \
+                     {}",
+                    alias,
+                    owner
+                );
+                return Ok(false);
+            }
+            registered += 1;
+        }
+        self.is_fee_paid(registered)
+    }
+}
+
+impl<'a, DB, H, CA> NameServiceVp<'a, DB, H, CA>
+where
+    DB: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    /// Check that the balance of the name service didn't decrease and that it
+    /// increased by the registration fee of every alias registered in this
+    /// tx.
+    fn is_fee_paid(&self, registered: u64) -> Result<bool> {
+        let balance_key = token::balance_key(&address::xan(), &ADDRESS);
+        let read_balance = |bytes: Option<Vec<u8>>| {
+            bytes
+                .and_then(|bytes| {
+                    token::Amount::try_from_slice(&bytes[..]).ok()
+                })
+                .unwrap_or_default()
+        };
+        let pre = read_balance(self.ctx.read_pre(&balance_key)?);
+        let post = read_balance(self.ctx.read_post(&balance_key)?);
+        let paid = match post.checked_sub(pre) {
+            Some(paid) => paid,
+            None => return Ok(false),
+        };
+        let required = (0..registered)
+            .fold(token::Amount::default(), |acc, _| {
+                acc + super::REGISTRATION_FEE
+            });
+        Ok(paid >= required)
+    }
+}
+
+impl From<native_vp::Error> for Error {
+    fn from(err: native_vp::Error) -> Self {
+        Self::NativeVpError(err)
+    }
+}
@@ -200,7 +200,7 @@ where
     pub fn iter_prefix(
         &self,
         prefix: &Key,
-    ) -> Result<<DB as storage::DBIter<'a>>::PrefixIter> {
+    ) -> Result<storage::PrefixIter<'a, DB>> {
         vp_env::iter_prefix(
             &mut *self.gas_meter.borrow_mut(),
             self.storage,
@@ -209,11 +209,58 @@ where
         .map_err(Error::ContextError)
     }
 
+    /// Storage prefix iterator in the reverse order of the keys. It will try
+    /// to get an iterator from the storage.
+    pub fn rev_iter_prefix(
+        &self,
+        prefix: &Key,
+    ) -> Result<storage::PrefixIter<'a, DB>> {
+        vp_env::rev_iter_prefix(
+            &mut *self.gas_meter.borrow_mut(),
+            self.storage,
+            prefix,
+        )
+        .map_err(Error::ContextError)
+    }
+
+    /// Storage range iterator of the keys from `start` (inclusive) up to
+    /// `end` (exclusive). It will try to get an iterator from the storage.
+    pub fn iter_range(
+        &self,
+        start: &Key,
+        end: &Key,
+    ) -> Result<storage::PrefixIter<'a, DB>> {
+        vp_env::iter_range(
+            &mut *self.gas_meter.borrow_mut(),
+            self.storage,
+            start,
+            end,
+        )
+        .map_err(Error::ContextError)
+    }
+
+    /// Storage range iterator of the keys from `start` (inclusive) up to
+    /// `end` (exclusive) in the reverse order. It will try to get an iterator
+    /// from the storage.
+    pub fn rev_iter_range(
+        &self,
+        start: &Key,
+        end: &Key,
+    ) -> Result<storage::PrefixIter<'a, DB>> {
+        vp_env::rev_iter_range(
+            &mut *self.gas_meter.borrow_mut(),
+            self.storage,
+            start,
+            end,
+        )
+        .map_err(Error::ContextError)
+    }
+
     /// Storage prefix iterator for prior state (before tx execution). It will
     /// try to read from the storage.
     pub fn iter_pre_next(
         &self,
-        iter: &mut <DB as storage::DBIter<'_>>::PrefixIter,
+        iter: &mut storage::PrefixIter<'_, DB>,
     ) -> Result<Option<(String, Vec<u8>)>> {
         vp_env::iter_pre_next::<DB>(&mut *self.gas_meter.borrow_mut(), iter)
             .map_err(Error::ContextError)
@@ -224,7 +271,7 @@ where
     /// then from the storage.
     pub fn iter_post_next(
         &self,
-        iter: &mut <DB as storage::DBIter<'_>>::PrefixIter,
+        iter: &mut storage::PrefixIter<'_, DB>,
     ) -> Result<Option<(String, Vec<u8>)>> {
         vp_env::iter_post_next::<DB>(
             &mut *self.gas_meter.borrow_mut(),
@@ -10,11 +10,13 @@ use self::storage as parameter_storage;
 use super::governance::vp::is_proposal_accepted;
 use super::storage::types::{decode, encode};
 use super::storage::{types, Storage};
+use crate::ledger::gas;
 use crate::ledger::native_vp::{self, Ctx, NativeVp};
 use crate::ledger::storage::{self as ledger_storage, StorageHasher};
 use crate::types::address::{Address, InternalAddress};
-use crate::types::storage::Key;
-use crate::types::time::DurationSecs;
+use crate::types::chain::PROTOCOL_VERSION;
+use crate::types::storage::{BlockHeight, Epoch, Key};
+use crate::types::time::{DateTimeUtc, DurationSecs};
 use crate::vm::WasmCacheAccess;
 
 const ADDRESS: Address = Address::Internal(InternalAddress::Parameters);
@@ -122,6 +124,10 @@ pub struct Parameters {
     pub vp_whitelist: Vec<String>,
     /// Whitelisted tx hashes
     pub tx_whitelist: Vec<String>,
+    /// Maximum gas that can be used by all the txs in a block
+    pub max_block_gas: u64,
+    /// Maximum gas that can be used by a single tx
+    pub max_tx_gas: u64,
 }
 
 /// Epoch duration. A new epoch begins as soon as both the `min_num_of_blocks`
@@ -145,7 +151,105 @@ pub struct EpochDuration {
     pub min_duration: DurationSecs,
 }
 
+/// A protocol upgrade scheduled by a governance proposal. At the upgrade
+/// height, the nodes that don't run the upgraded protocol version halt and the
+/// nodes that do apply the storage migrations of the new version before the
+/// block.
+#[derive(
+    Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+pub struct ProtocolUpgrade {
+    /// The protocol version to upgrade to
+    pub version: u64,
+    /// The height of the first block of the upgraded protocol version
+    pub height: BlockHeight,
+}
+
+/// The protocol version of a chain and its pending protocol upgrade
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct UpgradeInfo {
+    /// The current protocol version of the chain
+    pub protocol_version: u64,
+    /// The scheduled protocol upgrade that hasn't been applied yet, if any
+    pub pending_upgrade: Option<ProtocolUpgrade>,
+}
+
+/// The epoch of the last committed block with its start and the earliest
+/// start of the next epoch
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
+pub struct EpochInfo {
+    /// The epoch of the last committed block
+    pub epoch: Epoch,
+    /// The height of the last committed block
+    pub height: BlockHeight,
+    /// The height of the first block of the epoch, if the epoch is known
+    pub start_height: Option<BlockHeight>,
+    /// The time of the first block of the epoch, if its header is stored
+    pub start_time: Option<DateTimeUtc>,
+    /// The next epoch starts with the first block that has at least this
+    /// height and at least the `next_epoch_min_start_time`
+    pub next_epoch_min_start_height: BlockHeight,
+    /// The earliest time of the first block of the next epoch
+    pub next_epoch_min_start_time: DateTimeUtc,
+    /// The epoch duration parameters of the next epochs
+    pub epoch_duration: EpochDuration,
+    /// The max expected time per block parameter
+    pub max_expected_time_per_block: DurationSecs,
+}
+
+impl EpochInfo {
+    /// Estimate the latest time at which the given future epoch starts,
+    /// from the current time. Every block until then is expected to take
+    /// at most the max expected time per block, and every epoch after the
+    /// next one to be as short as the epoch duration parameters allow.
+    /// Returns `None` if the epoch isn't after the current one.
+    pub fn estimated_start_time(
+        &self,
+        epoch: Epoch,
+        now: DateTimeUtc,
+    ) -> Option<DateTimeUtc> {
+        let later_epochs = epoch.0.checked_sub(self.epoch.0)?.checked_sub(1)?;
+        let blocks_time = |blocks: u64| {
+            DurationSecs(
+                self.max_expected_time_per_block.0.saturating_mul(blocks),
+            )
+        };
+        let remaining_blocks = self
+            .next_epoch_min_start_height
+            .0
+            .saturating_sub(self.height.0);
+        let mut start_time = (now + blocks_time(remaining_blocks))
+            .max(self.next_epoch_min_start_time);
+        let EpochDuration {
+            min_num_of_blocks,
+            min_duration,
+        } = &self.epoch_duration;
+        let epoch_time = blocks_time(*min_num_of_blocks).max(*min_duration);
+        for _ in 0..later_epochs {
+            start_time = start_time + epoch_time;
+        }
+        Some(start_time)
+    }
+}
+
 impl Parameters {
+    /// The number of epochs in a year, if every epoch lasts as long as the
+    /// epoch duration parameters allow with blocks taking the max expected
+    /// time per block. It's at least 1.
+    pub fn epochs_per_year(&self) -> u64 {
+        const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+        let EpochDuration {
+            min_num_of_blocks,
+            min_duration,
+        } = &self.epoch_duration;
+        let epoch_secs = self
+            .max_expected_time_per_block
+            .0
+            .saturating_mul(*min_num_of_blocks)
+            .max(min_duration.0);
+        (SECONDS_PER_YEAR / epoch_secs.max(1)).max(1)
+    }
+
     /// Initialize parameters in storage in the genesis block.
     pub fn init_storage<DB, H>(&self, storage: &mut Storage<DB, H>)
     where
@@ -187,6 +291,28 @@ impl Parameters {
                 "Max expected time per block parameters must be initialized \
                  in the genesis block",
             );
+
+        // write gas limit parameters
+        let max_block_gas_key = storage::get_max_block_gas_key();
+        let max_block_gas_value = encode(&self.max_block_gas);
+        storage
+            .write(&max_block_gas_key, max_block_gas_value)
+            .expect(
+            "Max block gas parameter must be initialized in the genesis block",
+        );
+        let max_tx_gas_key = storage::get_max_tx_gas_key();
+        let max_tx_gas_value = encode(&self.max_tx_gas);
+        storage.write(&max_tx_gas_key, max_tx_gas_value).expect(
+            "Max tx gas parameter must be initialized in the genesis block",
+        );
+
+        // write the protocol version of the genesis
+        let protocol_version_key = storage::get_protocol_version_key();
+        storage
+            .write(&protocol_version_key, encode(&PROTOCOL_VERSION))
+            .expect(
+                "Protocol version must be initialized in the genesis block",
+            );
     }
 }
 
@@ -232,6 +358,34 @@ where
     update(storage, &value, key)
 }
 
+/// Update the max block gas parameter in storage. Returns the parameters and
+/// gas cost.
+pub fn update_max_block_gas_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: u64,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let key = storage::get_max_block_gas_key();
+    update(storage, &value, key)
+}
+
+/// Update the max tx gas parameter in storage. Returns the parameters and gas
+/// cost.
+pub fn update_max_tx_gas_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: u64,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let key = storage::get_max_tx_gas_key();
+    update(storage, &value, key)
+}
+
 /// Update the epoch parameter in storage. Returns the parameters and gas
 /// cost.
 pub fn update_epoch_parameter<DB, H>(
@@ -246,6 +400,37 @@ where
     update(storage, value, key)
 }
 
+/// Schedule a protocol upgrade in storage. Returns the gas cost.
+pub fn update_upgrade_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &ProtocolUpgrade,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let key = storage::get_upgrade_key();
+    update(storage, value, key)
+}
+
+/// Update the protocol version of the chain in storage and remove the
+/// scheduled upgrade, once the upgrade has been applied. Returns the gas cost.
+pub fn update_protocol_version<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: u64,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let key = storage::get_protocol_version_key();
+    let gas = update(storage, &value, key)?;
+    let (delete_gas, _size_diff) = storage
+        .delete(&storage::get_upgrade_key())
+        .map_err(WriteError::StorageError)?;
+    Ok(gas + delete_gas)
+}
+
 /// Update the  parameters in storage. Returns the parameters and gas
 /// cost.
 pub fn update<DB, H, T>(
@@ -286,6 +471,93 @@ where
     Ok((epoch_duration, gas))
 }
 
+/// Read the max expected time per block parameter from store
+pub fn read_max_expected_time_per_block_parameter<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(DurationSecs, u64), ReadError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let key = storage::get_max_expected_time_per_block_key();
+    let (value, gas) = storage.read(&key).map_err(ReadError::StorageError)?;
+    let max_expected_time_per_block: DurationSecs =
+        decode(value.ok_or(ReadError::ParametersMissing)?)
+            .map_err(ReadError::StorageTypeError)?;
+
+    Ok((max_expected_time_per_block, gas))
+}
+
+/// Read the block and tx gas limit parameters from store. The limits default
+/// to [`gas::BLOCK_GAS_LIMIT`] and [`gas::TRANSACTION_GAS_LIMIT`] on chains
+/// initialized without them.
+pub fn read_gas_limit_parameters<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<((u64, u64), u64), ReadError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let max_block_gas_key = storage::get_max_block_gas_key();
+    let (value, gas_block) = storage
+        .read(&max_block_gas_key)
+        .map_err(ReadError::StorageError)?;
+    let max_block_gas: u64 = match value {
+        Some(value) => decode(value).map_err(ReadError::StorageTypeError)?,
+        None => gas::BLOCK_GAS_LIMIT,
+    };
+
+    let max_tx_gas_key = storage::get_max_tx_gas_key();
+    let (value, gas_tx) = storage
+        .read(&max_tx_gas_key)
+        .map_err(ReadError::StorageError)?;
+    let max_tx_gas: u64 = match value {
+        Some(value) => decode(value).map_err(ReadError::StorageTypeError)?,
+        None => gas::TRANSACTION_GAS_LIMIT,
+    };
+
+    Ok(((max_block_gas, max_tx_gas), gas_block + gas_tx))
+}
+
+/// Read the scheduled protocol upgrade from store, if any
+pub fn read_upgrade_parameter<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(Option<ProtocolUpgrade>, u64), ReadError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let upgrade_key = storage::get_upgrade_key();
+    let (value, gas) = storage
+        .read(&upgrade_key)
+        .map_err(ReadError::StorageError)?;
+    let upgrade: Option<ProtocolUpgrade> = value
+        .map(decode)
+        .transpose()
+        .map_err(ReadError::StorageTypeError)?;
+    Ok((upgrade, gas))
+}
+
+/// Read the protocol version of the chain from store. The chains initialized
+/// without it are at the version `1`.
+pub fn read_protocol_version<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(u64, u64), ReadError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let protocol_version_key = storage::get_protocol_version_key();
+    let (value, gas) = storage
+        .read(&protocol_version_key)
+        .map_err(ReadError::StorageError)?;
+    let version: u64 = match value {
+        Some(value) => decode(value).map_err(ReadError::StorageTypeError)?,
+        None => 1,
+    };
+    Ok((version, gas))
+}
+
 // Read the all the parameters from storage. Returns the parameters and gas
 /// cost.
 pub fn read<DB, H>(
@@ -326,14 +598,19 @@ where
         decode(value.ok_or(ReadError::ParametersMissing)?)
             .map_err(ReadError::StorageTypeError)?;
 
+    let ((max_block_gas, max_tx_gas), gas_limits) =
+        read_gas_limit_parameters(storage)?;
+
     Ok((
         Parameters {
             epoch_duration,
             max_expected_time_per_block,
             vp_whitelist,
             tx_whitelist,
+            max_block_gas,
+            max_tx_gas,
         },
-        gas_epoch + gas_tx + gas_vp + gas_time,
+        gas_epoch + gas_tx + gas_vp + gas_time + gas_limits,
     ))
 }
 
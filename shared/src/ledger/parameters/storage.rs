@@ -6,6 +6,10 @@ const EPOCH_DURATION_KEY: &str = "epoch_duration";
 const VP_WHITELIST_KEY: &str = "vp_whitelist";
 const TX_WHITELIST_KEY: &str = "tx_whitelist";
 const MAX_EXPECTED_TIME_PER_BLOCK_KEY: &str = "max_expected_time_per_block";
+const MAX_BLOCK_GAS_KEY: &str = "max_block_gas";
+const MAX_TX_GAS_KEY: &str = "max_tx_gas";
+const UPGRADE_KEY: &str = "upgrade";
+const PROTOCOL_VERSION_KEY: &str = "protocol_version";
 
 /// Returns if the key is a parameter key.
 pub fn is_parameter_key(key: &Key) -> bool {
@@ -18,6 +22,9 @@ pub fn is_protocol_parameter_key(key: &Key) -> bool {
         || is_max_expected_time_per_block_key(key)
         || is_tx_whitelist_key(key)
         || is_vp_whitelist_key(key)
+        || is_max_block_gas_key(key)
+        || is_max_tx_gas_key(key)
+        || is_upgrade_key(key)
 }
 
 /// Returns if the key is an epoch storage key.
@@ -36,6 +43,30 @@ pub fn is_max_expected_time_per_block_key(key: &Key) -> bool {
     ] if addr == &ADDRESS && max_expected_time_per_block == MAX_EXPECTED_TIME_PER_BLOCK_KEY)
 }
 
+/// Returns if the key is the max_block_gas key.
+pub fn is_max_block_gas_key(key: &Key) -> bool {
+    matches!(&key.segments[..], [
+        DbKeySeg::AddressSeg(addr),
+        DbKeySeg::StringSeg(max_block_gas),
+    ] if addr == &ADDRESS && max_block_gas == MAX_BLOCK_GAS_KEY)
+}
+
+/// Returns if the key is the max_tx_gas key.
+pub fn is_max_tx_gas_key(key: &Key) -> bool {
+    matches!(&key.segments[..], [
+        DbKeySeg::AddressSeg(addr),
+        DbKeySeg::StringSeg(max_tx_gas),
+    ] if addr == &ADDRESS && max_tx_gas == MAX_TX_GAS_KEY)
+}
+
+/// Returns if the key is the key of the scheduled protocol upgrade.
+pub fn is_upgrade_key(key: &Key) -> bool {
+    matches!(&key.segments[..], [
+        DbKeySeg::AddressSeg(addr),
+        DbKeySeg::StringSeg(upgrade),
+    ] if addr == &ADDRESS && upgrade == UPGRADE_KEY)
+}
+
 /// Returns if the key is the tx_whitelist key.
 pub fn is_tx_whitelist_key(key: &Key) -> bool {
     matches!(&key.segments[..], [
@@ -91,3 +122,45 @@ pub fn get_max_expected_time_per_block_key() -> Key {
         ],
     }
 }
+
+/// Storage key used for the max block gas parameter.
+pub fn get_max_block_gas_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(ADDRESS),
+            DbKeySeg::StringSeg(MAX_BLOCK_GAS_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for the max tx gas parameter.
+pub fn get_max_tx_gas_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(ADDRESS),
+            DbKeySeg::StringSeg(MAX_TX_GAS_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for the scheduled protocol upgrade.
+pub fn get_upgrade_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(ADDRESS),
+            DbKeySeg::StringSeg(UPGRADE_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for the protocol version of the chain. It's not a
+/// protocol parameter, it's only updated by the ledger when it applies a
+/// protocol upgrade.
+pub fn get_protocol_version_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(ADDRESS),
+            DbKeySeg::StringSeg(PROTOCOL_VERSION_KEY.to_string()),
+        ],
+    }
+}
@@ -0,0 +1,305 @@
+//! Checks of the invariants of the PoS state, which must hold in every
+//! committed block. A node built with the `pos-invariants` feature checks
+//! them after each epoch transition and the `ledger check-pos` command checks
+//! them offline, so that a bug in the PoS state transitions is detected
+//! before its effects spread.
+//!
+//! The invariants are checked in the epochs from the last committed epoch up
+//! to the unbonding epoch:
+//! - a validator's stake, the sum of its total deltas, is not negative
+//! - a validator's voting power is the voting power of its stake
+//! - the total voting power is the sum of the validators' voting powers
+//!
+//! and in the pipeline epoch, in which all the changes of the bonds apply:
+//! - a validator's stake is the sum of the tokens bonded to it and of the
+//!   tokens unbonded from it that are still at stake. A slash is applied to
+//!   the stake of a validator at once, but to its bonds only when they are
+//!   unbonded, so the stake of a slashed validator may be lower.
+
+use std::collections::BTreeMap;
+
+use anoma_proof_of_stake::types::{Epoch as PosEpoch, VotingPowerDelta};
+use thiserror::Error;
+
+use super::query::{read_prefix, read_value};
+use super::{
+    bonds_prefix, is_bond_key, is_unbond_key, is_validator_total_deltas_key,
+    query_pos_params, total_voting_power_key, unbonds_prefix,
+    validator_slashes_key, validator_voting_power_key, validators_prefix,
+    BondId, Bonds, Slashes, TotalVotingPowers, Unbonds, ValidatorTotalDeltas,
+    ValidatorVotingPowers,
+};
+use crate::ledger::storage::types::decode;
+use crate::ledger::storage::{self, Storage, StorageHasher};
+use crate::types::address::Address;
+use crate::types::storage::{Epoch, Key};
+use crate::types::token;
+
+#[allow(missing_docs)]
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum InvariantViolation {
+    #[error(
+        "The stake {stake} of validator {validator} in epoch {epoch} is \
+         negative"
+    )]
+    NegativeStake {
+        validator: Address,
+        epoch: Epoch,
+        stake: token::Change,
+    },
+    #[error(
+        "The voting power {voting_power} of validator {validator} in epoch \
+         {epoch} doesn't match its stake {stake}"
+    )]
+    VotingPowerMismatch {
+        validator: Address,
+        epoch: Epoch,
+        stake: token::Change,
+        voting_power: i64,
+    },
+    #[error(
+        "The total voting power {total} in epoch {epoch} doesn't match the \
+         sum {sum} of the validators' voting powers"
+    )]
+    TotalVotingPowerMismatch { epoch: Epoch, total: i64, sum: i64 },
+    #[error(
+        "The stake {stake} of validator {validator} in epoch {epoch} doesn't \
+         match the {bonded} tokens bonded to it"
+    )]
+    BondedMismatch {
+        validator: Address,
+        epoch: Epoch,
+        stake: token::Change,
+        bonded: token::Amount,
+    },
+}
+
+/// Check the invariants of the PoS state in the last committed epoch.
+/// Returns the violated invariants, which are none before the genesis.
+pub fn check_invariants<D, H>(
+    storage: &Storage<D, H>,
+) -> storage::Result<Vec<InvariantViolation>>
+where
+    D: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    let params = match query_pos_params(storage)? {
+        Some(params) => params,
+        None => return Ok(vec![]),
+    };
+    let (current_epoch, _gas) = storage.get_last_epoch();
+    let pipeline_epoch = current_epoch + params.pipeline_len;
+    let epochs: Vec<Epoch> = (0..=params.unbonding_len)
+        .map(|offset| current_epoch + offset)
+        .collect();
+    let mut violations = vec![];
+
+    // The tokens at stake with each validator in the pipeline epoch
+    let mut bonded: BTreeMap<Address, token::Amount> = BTreeMap::new();
+    let bonds: Vec<(BondId, Bonds)> =
+        read_prefix(storage, &bonds_prefix(), is_bond_key)?;
+    for (bond_id, bonds) in bonds {
+        let amount = bonds
+            .get(pipeline_epoch)
+            .map(|bond| bond.sum())
+            .unwrap_or_default();
+        *bonded.entry(bond_id.validator).or_default() += amount;
+    }
+    let unbonds: Vec<(BondId, Unbonds)> =
+        read_prefix(storage, &unbonds_prefix(), is_unbond_key)?;
+    let pipeline: PosEpoch = pipeline_epoch.into();
+    for (bond_id, unbonds) in unbonds {
+        let entry = bonded.entry(bond_id.validator).or_default();
+        for unbond in unbonds.iter() {
+            for ((start_epoch, end_epoch), delta) in &unbond.deltas {
+                if *start_epoch <= pipeline && pipeline <= *end_epoch {
+                    *entry += *delta;
+                }
+            }
+        }
+    }
+
+    let mut validators: Vec<(Address, ValidatorTotalDeltas)> = vec![];
+    let (iter, _gas) = storage.iter_prefix(&validators_prefix());
+    for (key, value, _gas) in iter {
+        let key = Key::parse(key).map_err(storage::Error::KeyError)?;
+        if let Some(validator) = is_validator_total_deltas_key(&key) {
+            let total_deltas =
+                decode(value).map_err(storage::Error::CodingError)?;
+            validators.push((validator.clone(), total_deltas));
+        }
+    }
+
+    let mut voting_power_sums = vec![0_i64; epochs.len()];
+    for (validator, total_deltas) in validators {
+        let voting_powers: Option<ValidatorVotingPowers> =
+            read_value(storage, &validator_voting_power_key(&validator))?;
+        for (epoch, sum) in epochs.iter().zip(voting_power_sums.iter_mut()) {
+            let stake = total_deltas.get(*epoch).unwrap_or_default();
+            let voting_power: i64 = voting_powers
+                .as_ref()
+                .and_then(|voting_powers| voting_powers.get(*epoch))
+                .unwrap_or_default()
+                .into();
+            *sum += voting_power;
+            if stake < 0 {
+                violations.push(InvariantViolation::NegativeStake {
+                    validator: validator.clone(),
+                    epoch: *epoch,
+                    stake,
+                });
+                continue;
+            }
+            let expected = u64::try_from(stake).ok().and_then(|tokens| {
+                VotingPowerDelta::try_from_tokens(tokens, &params).ok()
+            });
+            if expected.map(i64::from) != Some(voting_power) {
+                violations.push(InvariantViolation::VotingPowerMismatch {
+                    validator: validator.clone(),
+                    epoch: *epoch,
+                    stake,
+                    voting_power,
+                });
+            }
+        }
+
+        let stake = total_deltas.get(pipeline_epoch).unwrap_or_default();
+        let bonded = bonded.remove(&validator).unwrap_or_default();
+        let slashes: Slashes =
+            read_value(storage, &validator_slashes_key(&validator))?
+                .unwrap_or_default();
+        let valid = if slashes.is_empty() {
+            stake == bonded.change()
+        } else {
+            stake <= bonded.change()
+        };
+        if !valid {
+            violations.push(InvariantViolation::BondedMismatch {
+                validator,
+                epoch: pipeline_epoch,
+                stake,
+                bonded,
+            });
+        }
+    }
+    // The tokens bonded to an address that has no stake
+    for (validator, bonded) in bonded {
+        if bonded != token::Amount::default() {
+            violations.push(InvariantViolation::BondedMismatch {
+                validator,
+                epoch: pipeline_epoch,
+                stake: 0,
+                bonded,
+            });
+        }
+    }
+
+    let total_voting_powers: Option<TotalVotingPowers> =
+        read_value(storage, &total_voting_power_key())?;
+    for (epoch, sum) in epochs.into_iter().zip(voting_power_sums) {
+        let total: i64 = total_voting_powers
+            .as_ref()
+            .and_then(|total_voting_powers| total_voting_powers.get(epoch))
+            .unwrap_or_default()
+            .into();
+        if total != sum {
+            violations.push(InvariantViolation::TotalVotingPowerMismatch {
+                epoch,
+                total,
+                sum,
+            });
+        }
+    }
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma_proof_of_stake::epoched::DynEpochOffset;
+    use anoma_proof_of_stake::types::Bond;
+    use anoma_proof_of_stake::PosBase;
+
+    use super::*;
+    use crate::ledger::pos::{
+        bond_key, init_genesis_storage, BasisPoints, GenesisValidator,
+        PosParams,
+    };
+    use crate::ledger::storage::testing::TestStorage;
+    use crate::ledger::storage::types::encode;
+    use crate::types::address::testing::{
+        established_address_1, established_address_2,
+    };
+    use crate::types::key::testing::{keypair_1, keypair_2};
+    use crate::types::key::RefTo;
+
+    /// Test that the invariants hold in the genesis PoS state and that the
+    /// voting powers and the bonds that don't match the validators' stake
+    /// are reported.
+    #[test]
+    fn test_check_invariants() {
+        let mut storage = TestStorage::default();
+        assert!(check_invariants(&storage).unwrap().is_empty());
+
+        let params = PosParams::default();
+        let validator = GenesisValidator {
+            address: established_address_1(),
+            staking_reward_address: established_address_2(),
+            tokens: token::Amount::whole(200_000),
+            consensus_key: keypair_1().ref_to(),
+            staking_reward_key: keypair_2().ref_to(),
+            commission_rate: BasisPoints::new(500),
+            max_commission_rate_change: BasisPoints::new(100),
+        };
+        init_genesis_storage(
+            &mut storage,
+            &params,
+            [validator.clone()].iter(),
+            Epoch(0),
+        );
+        assert_eq!(check_invariants(&storage).unwrap(), vec![]);
+
+        // A total voting power that isn't the sum of the validators'
+        let mut total_voting_power = storage.read_total_voting_power();
+        total_voting_power.add_at_epoch(
+            VotingPowerDelta::from(1),
+            PosEpoch::from(0),
+            PosEpoch::from(params.pipeline_len),
+            &params,
+        );
+        storage
+            .write(&total_voting_power_key(), encode(&total_voting_power))
+            .unwrap();
+        let violations = check_invariants(&storage).unwrap();
+        assert!(!violations.is_empty());
+        assert!(violations.iter().all(|violation| matches!(
+            violation,
+            InvariantViolation::TotalVotingPowerMismatch { epoch, .. }
+                if *epoch >= Epoch(params.pipeline_len)
+        )));
+
+        // A self-bond that isn't in the validator's stake
+        let bond_id = BondId {
+            source: validator.address.clone(),
+            validator: validator.address.clone(),
+        };
+        let mut bonds = storage.read_bond(&bond_id).unwrap();
+        bonds.add_at_offset(
+            Bond {
+                deltas: [(PosEpoch::from(params.pipeline_len), 10.into())]
+                    .into_iter()
+                    .collect(),
+            },
+            PosEpoch::from(0),
+            DynEpochOffset::PipelineLen,
+            &params,
+        );
+        storage.write(&bond_key(&bond_id), encode(&bonds)).unwrap();
+        let violations = check_invariants(&storage).unwrap();
+        assert!(violations.contains(&InvariantViolation::BondedMismatch {
+            validator: validator.address.clone(),
+            epoch: Epoch(params.pipeline_len),
+            stake: validator.tokens.change(),
+            bonded: validator.tokens + 10.into(),
+        }));
+    }
+}
@@ -1,21 +1,27 @@
 //! Proof-of-Stake integration as a native validity predicate
 
+pub mod invariants;
+mod query;
 mod storage;
 pub mod vp;
 
+use std::collections::BTreeMap;
+
 pub use anoma_proof_of_stake;
 pub use anoma_proof_of_stake::parameters::PosParams;
 pub use anoma_proof_of_stake::types::{
-    self, Slash, Slashes, TotalVotingPowers, ValidatorStates,
-    ValidatorVotingPowers,
+    self, BasisPoints, CommissionRates, Slash, Slashes, TotalVotingPowers,
+    ValidatorMetadata, ValidatorStates, ValidatorVotingPowers,
 };
-use anoma_proof_of_stake::PosBase;
+use anoma_proof_of_stake::{first_unclaimed_epoch, PosBase};
+pub use query::*;
+use query::{read_prefix, read_value};
 pub use storage::*;
 pub use vp::PosVP;
 
 use crate::ledger::storage::{self as ledger_storage, Storage, StorageHasher};
 use crate::types::address::{self, Address, InternalAddress};
-use crate::types::storage::Epoch;
+use crate::types::storage::{Epoch, Key};
 use crate::types::{key, token};
 
 /// Address of the PoS account implemented as a native VP
@@ -25,6 +31,14 @@ pub const ADDRESS: Address = Address::Internal(InternalAddress::PoS);
 pub const SLASH_POOL_ADDRESS: Address =
     Address::Internal(InternalAddress::PosSlashPool);
 
+/// The commission rate of the validators that don't set their own (5%)
+pub const DEFAULT_COMMISSION_RATE: BasisPoints = BasisPoints::new(500);
+
+/// The maximum change of the commission rate per epoch of the validators
+/// that don't set their own (1%)
+pub const DEFAULT_MAX_COMMISSION_RATE_CHANGE: BasisPoints =
+    BasisPoints::new(100);
+
 /// Address of the staking token (XAN)
 pub fn staking_token_address() -> Address {
     address::xan()
@@ -45,6 +59,50 @@ pub fn init_genesis_storage<'a, DB, H>(
         .expect("Initialize PoS genesis storage")
 }
 
+/// Prune the validators' rewards that all their bonds have already claimed,
+/// which must be done at the beginning of every epoch, so that the rewards
+/// don't grow with every epoch. The rewards of a validator that has no bonds
+/// are pruned up to the current epoch.
+pub fn prune_validator_rewards<DB, H>(
+    storage: &mut Storage<DB, H>,
+    current_epoch: Epoch,
+) -> ledger_storage::Result<()>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    let current_epoch: types::Epoch = current_epoch.into();
+    let mut first_unclaimed: BTreeMap<Address, types::Epoch> = BTreeMap::new();
+    let bonds: Vec<(BondId, Bonds)> =
+        read_prefix(storage, &bonds_prefix(), is_bond_key)?;
+    for (bond_id, bond) in bonds {
+        let claimed_epoch =
+            read_value(storage, &rewards_claimed_epoch_key(&bond_id))?;
+        if let Some(epoch) = first_unclaimed_epoch(&bond, claimed_epoch) {
+            let first = first_unclaimed
+                .entry(bond_id.validator)
+                .or_insert(current_epoch);
+            *first = (*first).min(epoch);
+        }
+    }
+    let mut validators = vec![];
+    let (iter, _gas) = storage.iter_prefix(&validators_prefix());
+    for (key, _value, _gas) in iter {
+        let key = Key::parse(key).map_err(ledger_storage::Error::KeyError)?;
+        if let Some(validator) = is_validator_rewards_key(&key) {
+            validators.push(validator.clone());
+        }
+    }
+    for validator in validators {
+        let before = first_unclaimed
+            .get(&validator)
+            .copied()
+            .unwrap_or(current_epoch);
+        storage.prune_validator_rewards(&validator, before);
+    }
+    Ok(())
+}
+
 /// Alias for a PoS type with the same name with concrete type parameters
 pub type ValidatorConsensusKeys =
     anoma_proof_of_stake::types::ValidatorConsensusKeys<key::common::PublicKey>;
@@ -59,9 +117,24 @@ pub type Bonds = anoma_proof_of_stake::types::Bonds<token::Amount>;
 /// Alias for a PoS type with the same name with concrete type parameters
 pub type Unbonds = anoma_proof_of_stake::types::Unbonds<token::Amount>;
 
+/// Alias for a PoS type with the same name with concrete type parameters
+pub type Redelegations =
+    anoma_proof_of_stake::types::Redelegations<Address, token::Amount>;
+
+/// Alias for a PoS type with the same name with concrete type parameters
+pub type ValidatorRewards =
+    anoma_proof_of_stake::types::ValidatorRewards<token::Amount>;
+
 /// Alias for a PoS type with the same name with concrete type parameters
 pub type ValidatorSets = anoma_proof_of_stake::types::ValidatorSets<Address>;
 
+/// Alias for a PoS type with the same name with concrete type parameters
+pub type ValidatorSetSnapshot =
+    anoma_proof_of_stake::types::ValidatorSetSnapshot<
+        Address,
+        key::common::PublicKey,
+    >;
+
 /// Alias for a PoS type with the same name with concrete type parameters
 pub type BondId = anoma_proof_of_stake::types::BondId<Address>;
 
@@ -85,3 +158,71 @@ impl From<anoma_proof_of_stake::types::Epoch> for Epoch {
         Epoch(epoch)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use anoma_proof_of_stake::types::EpochRewards;
+
+    use super::*;
+    use crate::ledger::storage::testing::TestStorage;
+    use crate::ledger::storage::types::encode;
+    use crate::types::address::testing::{
+        established_address_1, established_address_2,
+    };
+    use crate::types::key::testing::{keypair_1, keypair_2};
+    use crate::types::key::RefTo;
+
+    /// Test that the rewards of a validator are only pruned up to the first
+    /// epoch whose rewards its bond has yet to claim.
+    #[test]
+    fn test_prune_validator_rewards() {
+        let mut storage = TestStorage::default();
+        let params = PosParams::default();
+        let validator = GenesisValidator {
+            address: established_address_1(),
+            staking_reward_address: established_address_2(),
+            tokens: token::Amount::whole(200_000),
+            consensus_key: keypair_1().ref_to(),
+            staking_reward_key: keypair_2().ref_to(),
+            commission_rate: BasisPoints::new(500),
+            max_commission_rate_change: BasisPoints::new(100),
+        };
+        init_genesis_storage(
+            &mut storage,
+            &params,
+            [validator.clone()].iter(),
+            Epoch(0),
+        );
+        let rewards: ValidatorRewards = (0..5_u64)
+            .map(|epoch| {
+                let rewards = EpochRewards {
+                    rewards: token::Amount::whole(1),
+                    stake: validator.tokens,
+                };
+                (types::Epoch::from(epoch), rewards)
+            })
+            .collect();
+        storage.write_validator_rewards(&validator.address, &rewards);
+
+        // The self-bond hasn't claimed any rewards since the genesis
+        prune_validator_rewards(&mut storage, Epoch(5)).unwrap();
+        assert_eq!(storage.read_validator_rewards(&validator.address), rewards);
+
+        let bond_id = BondId {
+            source: validator.address.clone(),
+            validator: validator.address.clone(),
+        };
+        storage
+            .write(
+                &rewards_claimed_epoch_key(&bond_id),
+                encode(&types::Epoch::from(3)),
+            )
+            .unwrap();
+        prune_validator_rewards(&mut storage, Epoch(5)).unwrap();
+        let pruned = storage.read_validator_rewards(&validator.address);
+        assert_eq!(
+            pruned.keys().copied().collect::<Vec<_>>(),
+            vec![types::Epoch::from(3), types::Epoch::from(4)]
+        );
+    }
+}
@@ -0,0 +1,627 @@
+//! Proof-of-Stake queries of the bonds and the unbonds, which are read from
+//! the PoS storage and returned with the slashes of their validator applied,
+//! of the pending rewards of the bonds, of the validator set of an epoch,
+//! including the past epochs whose snapshots are kept, of the pages of the
+//! slashes, of the current inflation and of the PoS parameters.
+
+use std::collections::BTreeMap;
+
+use anoma_proof_of_stake::types::{
+    BasisPoints, Epoch as PosEpoch, Slash, ValidatorSet,
+};
+use anoma_proof_of_stake::{pending_rewards, PosBase};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use super::{
+    bond_key, bonds_for_source_prefix, bonds_prefix, is_bond_key,
+    is_unbond_key, is_validator_slashes_key, params_key,
+    rewards_claimed_epoch_key, slashes_prefix, unbond_key,
+    unbonds_for_source_prefix, unbonds_prefix, validator_consensus_key_key,
+    validator_metadata_key, validator_set_key, validator_set_snapshot_key,
+    validator_slashes_key, BondId, Bonds, PosParams, Slashes, Unbonds,
+    ValidatorConsensusKeys, ValidatorMetadata, ValidatorSetSnapshot,
+    ValidatorSets,
+};
+use crate::ledger::storage::types::decode;
+use crate::ledger::storage::{self, Storage, StorageHasher};
+use crate::types::address::Address;
+use crate::types::key::common;
+use crate::types::storage::{Epoch, Key};
+use crate::types::token;
+
+/// A bonded delta of tokens, after the slashes of the validator
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct BondDelta {
+    /// The owner of the bonded tokens
+    pub source: Address,
+    /// The epoch from which the bond is active
+    pub start_epoch: Epoch,
+    /// The bonded amount, after the slashes
+    pub amount: token::Amount,
+    /// The amount slashed from the bond
+    pub slashed: token::Amount,
+    /// Whether the bond is active in the epoch of the query
+    pub active: bool,
+}
+
+/// An unbonded delta of tokens, after the slashes of the validator
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct UnbondDelta {
+    /// The owner of the unbonded tokens
+    pub source: Address,
+    /// The epoch from which the unbonded tokens were active
+    pub start_epoch: Epoch,
+    /// The epoch from which the unbonded tokens can be withdrawn
+    pub withdraw_epoch: Epoch,
+    /// The unbonded amount, after the slashes
+    pub amount: token::Amount,
+    /// The amount slashed from the unbond
+    pub slashed: token::Amount,
+    /// Whether the tokens can be withdrawn in the epoch of the query
+    pub withdrawable: bool,
+}
+
+/// The bonds and the unbonds found for a validator
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct BondQueryResult {
+    /// The validator the tokens are bonded to
+    pub validator: Address,
+    /// The epoch of the query, in which the bonds are active or the unbonds
+    /// withdrawable
+    pub epoch: Epoch,
+    /// The bonded deltas, ordered by their owner and their start epoch
+    pub bonds: Vec<BondDelta>,
+    /// The unbonded deltas, ordered by their owner and their epochs
+    pub unbonds: Vec<UnbondDelta>,
+}
+
+/// The pending rewards of a bond
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct RewardsQueryResult {
+    /// The owner of the bond
+    pub source: Address,
+    /// The validator the tokens are bonded to
+    pub validator: Address,
+    /// The epoch of the query, before which the rewards are pending
+    pub epoch: Epoch,
+    /// The epoch before which the rewards have been claimed, if any
+    pub claimed_epoch: Option<Epoch>,
+    /// The rewards of the bond that can be claimed
+    pub pending: token::Amount,
+}
+
+/// A validator of the validator set of an epoch
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct ValidatorSetEntry {
+    /// The address of the validator
+    pub address: Address,
+    /// The consensus key of the validator in the epoch
+    pub consensus_key: Option<common::PublicKey>,
+    /// The voting power of the validator in the epoch
+    pub voting_power: u64,
+    /// Whether the validator is in the active set, or else in the inactive
+    /// set
+    pub active: bool,
+    /// The current metadata of the validator, if it has set any
+    pub metadata: Option<ValidatorMetadata>,
+}
+
+/// The validator set of an epoch
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct ValidatorSetQueryResult {
+    /// The epoch of the validator set
+    pub epoch: Epoch,
+    /// The active validators followed by the inactive ones, each ordered by
+    /// descending voting power
+    pub validators: Vec<ValidatorSetEntry>,
+}
+
+/// A slash of a validator
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
+pub struct ValidatorSlash {
+    /// The slashed validator
+    pub validator: Address,
+    /// The slash
+    pub slash: Slash,
+}
+
+/// The position of the first slash of a page of the slashes found by
+/// [`query_slashes`], which is stable as the slashes of a validator are only
+/// ever appended
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct SlashesCursor {
+    /// The validator of the slash
+    pub validator: Address,
+    /// The index of the slash in the slashes of the validator
+    pub index: u64,
+}
+
+/// A page of the slashes found by [`query_slashes`]
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
+pub struct SlashesPage {
+    /// The slashes of the page, ordered by their validator and in the order
+    /// in which they were applied
+    pub slashes: Vec<ValidatorSlash>,
+    /// The position of the next page, if this is not the last page
+    pub next: Option<SlashesCursor>,
+}
+
+/// The inflation of the staking token in the last committed epoch
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct InflationQueryResult {
+    /// The last committed epoch
+    pub epoch: Epoch,
+    /// The tokens staked with the active and the inactive validators in the
+    /// epoch
+    pub staked: token::Amount,
+    /// The total supply of the staking token
+    pub total_supply: token::Amount,
+    /// The ratio of the staked tokens to the total supply
+    pub staked_ratio: BasisPoints,
+    /// The annual inflation rate at the staked ratio
+    pub inflation_rate: BasisPoints,
+    /// The amount of the staking token to be minted into the staking rewards
+    /// at the end of the epoch
+    pub epoch_inflation: token::Amount,
+    /// The estimated annual rate of the inflation rewards of the tokens
+    /// staked with the active validators, before the validators' commissions
+    /// and without the block rewards. Zero if no tokens are staked with them.
+    pub apr: BasisPoints,
+}
+
+/// Read the PoS parameters. Returns `None` if they're not stored, i.e. before
+/// the genesis.
+pub fn query_pos_params<D, H>(
+    storage: &Storage<D, H>,
+) -> storage::Result<Option<PosParams>>
+where
+    D: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    read_value(storage, &params_key())
+}
+
+/// Find the validator set of the given epoch, or of the last committed epoch,
+/// with the consensus keys and the voting powers of the validators. Returns
+/// `None` if the validator set of the epoch is not stored.
+pub fn query_validator_set<D, H>(
+    storage: &Storage<D, H>,
+    epoch: Option<Epoch>,
+) -> storage::Result<Option<ValidatorSetQueryResult>>
+where
+    D: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    let epoch = epoch.unwrap_or_else(|| storage.get_last_epoch().0);
+    let validator_sets: Option<ValidatorSets> =
+        read_value(storage, &validator_set_key())?;
+    let validator_set =
+        match validator_sets.as_ref().and_then(|sets| sets.get(epoch)) {
+            Some(validator_set) => validator_set,
+            None => return Ok(None),
+        };
+    let result =
+        validator_set_result(storage, epoch, validator_set, |address| {
+            let consensus_keys: Option<ValidatorConsensusKeys> =
+                read_value(storage, &validator_consensus_key_key(address))?;
+            Ok(consensus_keys.and_then(|keys| keys.get(epoch).cloned()))
+        })?;
+    Ok(Some(result))
+}
+
+/// Find the validator set of the given epoch, with the consensus keys and the
+/// voting powers that the validators had in that epoch. The validator set of
+/// a past epoch is found in its snapshot, which is kept for the unbonding
+/// length, and of the last committed or a future epoch as with
+/// [`query_validator_set`]. Returns `None` if the validator set of the epoch
+/// is not kept.
+pub fn query_validator_set_at<D, H>(
+    storage: &Storage<D, H>,
+    epoch: Epoch,
+) -> storage::Result<Option<ValidatorSetQueryResult>>
+where
+    D: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    let snapshot: Option<ValidatorSetSnapshot> =
+        read_value(storage, &validator_set_snapshot_key(epoch))?;
+    let snapshot = match snapshot {
+        Some(snapshot) => snapshot,
+        None if epoch >= storage.get_last_epoch().0 => {
+            return query_validator_set(storage, Some(epoch));
+        }
+        None => return Ok(None),
+    };
+    let result = validator_set_result(
+        storage,
+        epoch,
+        &snapshot.validator_set,
+        |address| Ok(snapshot.consensus_keys.get(address).cloned()),
+    )?;
+    Ok(Some(result))
+}
+
+/// Estimate the inflation of the staking token in the last committed epoch
+/// from its current stakes and total supply, at which it's minted at the end
+/// of the epoch unless the stakes change. Returns `None` before the genesis.
+pub fn query_inflation<D, H>(
+    storage: &Storage<D, H>,
+) -> storage::Result<Option<InflationQueryResult>>
+where
+    D: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    let params = match query_pos_params(storage)? {
+        Some(params) => params,
+        None => return Ok(None),
+    };
+    let (epoch, _gas) = storage.get_last_epoch();
+    let (active_stakes, staked) = storage.read_epoch_stakes(epoch);
+    let active_stake: u64 =
+        active_stakes.iter().map(|(_address, stake)| stake).sum();
+    let total_supply: u64 = storage.read_staking_token_supply().into();
+    let inflation_rate = params.inflation_rate(staked, total_supply);
+    let epoch_inflation = params.epoch_inflation(inflation_rate, total_supply);
+    // The annual inflation rate of the total supply as a rate of the stake
+    let apr = if active_stake == 0 {
+        0
+    } else {
+        let rate: u64 = inflation_rate.into();
+        let apr = u128::from(rate) * u128::from(total_supply)
+            / u128::from(active_stake);
+        u64::try_from(apr).unwrap_or(u64::MAX)
+    };
+    Ok(Some(InflationQueryResult {
+        epoch,
+        staked: staked.into(),
+        total_supply: total_supply.into(),
+        staked_ratio: BasisPoints::from_ratio(staked, total_supply),
+        inflation_rate,
+        epoch_inflation: epoch_inflation.into(),
+        apr: BasisPoints::new(apr),
+    }))
+}
+
+/// Find the bonds and the unbonds of the given owner to the given validator
+/// in the last committed epoch. Without an owner, the self-bonds of the
+/// validator are found. Without a validator, the bonds of the owner to any
+/// validator are found, or all the bonds without an owner either. The results
+/// are ordered by their validator.
+pub fn query_bonds<D, H>(
+    storage: &Storage<D, H>,
+    owner: Option<&Address>,
+    validator: Option<&Address>,
+) -> storage::Result<Vec<BondQueryResult>>
+where
+    D: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    let (epoch, _gas) = storage.get_last_epoch();
+    let (bonds, unbonds): (Vec<(BondId, Bonds)>, Vec<(BondId, Unbonds)>) =
+        match validator {
+            Some(validator) => {
+                let bond_id = BondId {
+                    source: owner.unwrap_or(validator).clone(),
+                    validator: validator.clone(),
+                };
+                let bonds = read_value(storage, &bond_key(&bond_id))?;
+                let unbonds = read_value(storage, &unbond_key(&bond_id))?;
+                (
+                    bonds
+                        .map(|bonds| (bond_id.clone(), bonds))
+                        .into_iter()
+                        .collect(),
+                    unbonds
+                        .map(|unbonds| (bond_id, unbonds))
+                        .into_iter()
+                        .collect(),
+                )
+            }
+            None => {
+                let (bonds_prefix, unbonds_prefix) = match owner {
+                    Some(owner) => (
+                        bonds_for_source_prefix(owner),
+                        unbonds_for_source_prefix(owner),
+                    ),
+                    None => (bonds_prefix(), unbonds_prefix()),
+                };
+                (
+                    read_prefix(storage, &bonds_prefix, is_bond_key)?,
+                    read_prefix(storage, &unbonds_prefix, is_unbond_key)?,
+                )
+            }
+        };
+
+    let mut results: BTreeMap<Address, BondQueryResult> = BTreeMap::new();
+    let new_result = |validator: &Address| BondQueryResult {
+        validator: validator.clone(),
+        epoch,
+        bonds: vec![],
+        unbonds: vec![],
+    };
+    for (BondId { source, validator }, bonds) in bonds {
+        let slashes = storage.read_validator_slashes(&validator);
+        let result = results
+            .entry(validator.clone())
+            .or_insert_with(|| new_result(&validator));
+        for bond in bonds.iter() {
+            let mut deltas: Vec<_> = bond.deltas.iter().collect();
+            deltas.sort();
+            for (start_epoch, &delta) in deltas {
+                let amount = apply_slashes(&slashes, delta, *start_epoch, None);
+                let start_epoch: Epoch = (*start_epoch).into();
+                result.bonds.push(BondDelta {
+                    source: source.clone(),
+                    start_epoch,
+                    amount,
+                    slashed: delta - amount,
+                    active: epoch >= start_epoch,
+                });
+            }
+        }
+    }
+    for (BondId { source, validator }, unbonds) in unbonds {
+        let slashes = storage.read_validator_slashes(&validator);
+        let result = results
+            .entry(validator.clone())
+            .or_insert_with(|| new_result(&validator));
+        for unbond in unbonds.iter() {
+            let mut deltas: Vec<_> = unbond.deltas.iter().collect();
+            deltas.sort();
+            for ((start_epoch, end_epoch), &delta) in deltas {
+                let withdraw_epoch = *end_epoch + 1_u64;
+                let amount = apply_slashes(
+                    &slashes,
+                    delta,
+                    *start_epoch,
+                    Some(withdraw_epoch),
+                );
+                let end_epoch: Epoch = (*end_epoch).into();
+                result.unbonds.push(UnbondDelta {
+                    source: source.clone(),
+                    start_epoch: (*start_epoch).into(),
+                    withdraw_epoch: withdraw_epoch.into(),
+                    amount,
+                    slashed: delta - amount,
+                    withdrawable: epoch > end_epoch,
+                });
+            }
+        }
+    }
+    Ok(results.into_values().collect())
+}
+
+/// Find the pending rewards of the bonds of the given owner to the given
+/// validator in the last committed epoch, with the same selection of the
+/// bonds as [`query_bonds`]. The results are ordered by their owner and their
+/// validator.
+pub fn query_rewards<D, H>(
+    storage: &Storage<D, H>,
+    owner: Option<&Address>,
+    validator: Option<&Address>,
+) -> storage::Result<Vec<RewardsQueryResult>>
+where
+    D: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    let (epoch, _gas) = storage.get_last_epoch();
+    let bonds: Vec<(BondId, Bonds)> = match validator {
+        Some(validator) => {
+            let bond_id = BondId {
+                source: owner.unwrap_or(validator).clone(),
+                validator: validator.clone(),
+            };
+            let bonds = read_value(storage, &bond_key(&bond_id))?;
+            bonds.map(|bonds| (bond_id, bonds)).into_iter().collect()
+        }
+        None => {
+            let prefix = match owner {
+                Some(owner) => bonds_for_source_prefix(owner),
+                None => bonds_prefix(),
+            };
+            read_prefix(storage, &prefix, is_bond_key)?
+        }
+    };
+
+    let mut results = vec![];
+    for (bond_id, bonds) in bonds {
+        let slashes = storage.read_validator_slashes(&bond_id.validator);
+        let rewards = storage.read_validator_rewards(&bond_id.validator);
+        let claimed_epoch: Option<PosEpoch> =
+            read_value(storage, &rewards_claimed_epoch_key(&bond_id))?;
+        let pending = pending_rewards(
+            &bonds,
+            &slashes,
+            &rewards,
+            claimed_epoch,
+            epoch.into(),
+        );
+        results.push(RewardsQueryResult {
+            source: bond_id.source,
+            validator: bond_id.validator,
+            epoch,
+            claimed_epoch: claimed_epoch.map(Into::into),
+            pending,
+        });
+    }
+    Ok(results)
+}
+
+/// Find a page of the slashes of the given validator, or of all the
+/// validators, whose slashable events occurred in the epochs from the
+/// `from_epoch` up to the `to_epoch`, if any. The page has at most `limit`
+/// slashes, from the `start` of the page, if any. The slashes of the
+/// validators before the `start` are not read again, so that reading all the
+/// slashes page by page reads each of them once.
+pub fn query_slashes<D, H>(
+    storage: &Storage<D, H>,
+    validator: Option<&Address>,
+    from_epoch: Option<Epoch>,
+    to_epoch: Option<Epoch>,
+    start: Option<&SlashesCursor>,
+    limit: u64,
+) -> storage::Result<SlashesPage>
+where
+    D: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    let in_range = |slash: &Slash| {
+        let epoch: Epoch = slash.epoch.into();
+        from_epoch.map_or(true, |from_epoch| epoch >= from_epoch)
+            && to_epoch.map_or(true, |to_epoch| epoch <= to_epoch)
+    };
+    let mut page = SlashesPage {
+        slashes: vec![],
+        next: None,
+    };
+    // Add the slashes of a validator to the page, until it's full. Returns
+    // whether the page is full.
+    let mut add_slashes = |validator: &Address, slashes: Slashes| {
+        let first = match start {
+            Some(start) if &start.validator == validator => start.index,
+            _ => 0,
+        };
+        for (index, slash) in slashes.into_iter().enumerate() {
+            let index = index as u64;
+            if index < first || !in_range(&slash) {
+                continue;
+            }
+            if page.slashes.len() as u64 >= limit {
+                page.next = Some(SlashesCursor {
+                    validator: validator.clone(),
+                    index,
+                });
+                return true;
+            }
+            page.slashes.push(ValidatorSlash {
+                validator: validator.clone(),
+                slash,
+            });
+        }
+        false
+    };
+    match validator {
+        Some(validator) => {
+            let slashes: Option<Slashes> =
+                read_value(storage, &validator_slashes_key(validator))?;
+            if let Some(slashes) = slashes {
+                add_slashes(validator, slashes);
+            }
+        }
+        None => {
+            let prefix = slashes_prefix();
+            let (iter, _gas) = match start {
+                Some(start) => storage.iter_prefix_from(
+                    &prefix,
+                    &validator_slashes_key(&start.validator),
+                ),
+                None => storage.iter_prefix(&prefix),
+            };
+            for (key, value, _gas) in iter {
+                let key = Key::parse(key).map_err(storage::Error::KeyError)?;
+                if let Some(validator) = is_validator_slashes_key(&key) {
+                    let slashes =
+                        decode(value).map_err(storage::Error::CodingError)?;
+                    if add_slashes(validator, slashes) {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    Ok(page)
+}
+
+/// The validator set of an epoch with the consensus key of each validator
+/// found by `consensus_key` and with its current metadata
+fn validator_set_result<D, H, F>(
+    storage: &Storage<D, H>,
+    epoch: Epoch,
+    validator_set: &ValidatorSet<Address>,
+    consensus_key: F,
+) -> storage::Result<ValidatorSetQueryResult>
+where
+    D: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    F: Fn(&Address) -> storage::Result<Option<common::PublicKey>>,
+{
+    let active = validator_set.active.iter().rev().map(|v| (v, true));
+    let inactive = validator_set.inactive.iter().rev().map(|v| (v, false));
+    let validators = active
+        .chain(inactive)
+        .map(|(validator, active)| {
+            let metadata = read_value(
+                storage,
+                &validator_metadata_key(&validator.address),
+            )?;
+            Ok(ValidatorSetEntry {
+                address: validator.address.clone(),
+                consensus_key: consensus_key(&validator.address)?,
+                voting_power: validator.voting_power.into(),
+                active,
+                metadata,
+            })
+        })
+        .collect::<storage::Result<_>>()?;
+    Ok(ValidatorSetQueryResult { epoch, validators })
+}
+
+/// Read and decode the value of the given key, if any
+pub(super) fn read_value<D, H, T>(
+    storage: &Storage<D, H>,
+    key: &Key,
+) -> storage::Result<Option<T>>
+where
+    D: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    T: BorshDeserialize,
+{
+    let (value, _gas) = storage.read(key)?;
+    value
+        .map(|value| decode(value).map_err(storage::Error::CodingError))
+        .transpose()
+}
+
+/// Read and decode the values with the given prefix, whose keys are parsed
+/// into a bond ID with the given function
+pub(super) fn read_prefix<D, H, T>(
+    storage: &Storage<D, H>,
+    prefix: &Key,
+    parse_key: fn(&Key) -> Option<BondId>,
+) -> storage::Result<Vec<(BondId, T)>>
+where
+    D: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    T: BorshDeserialize,
+{
+    let (iter, _gas) = storage.iter_prefix(prefix);
+    let mut values = vec![];
+    for (key, value, _gas) in iter {
+        let key = Key::parse(key).map_err(storage::Error::KeyError)?;
+        // The keys with a matching prefix that aren't bond keys are skipped
+        if let Some(bond_id) = parse_key(&key) {
+            let value = decode(value).map_err(storage::Error::CodingError)?;
+            values.push((bond_id, value));
+        }
+    }
+    Ok(values)
+}
+
+/// Apply the slashes of a validator that occurred from the start epoch of a
+/// delta and before its withdrawal epoch, if any
+fn apply_slashes(
+    slashes: &[Slash],
+    mut delta: token::Amount,
+    start_epoch: PosEpoch,
+    withdraw_epoch: Option<PosEpoch>,
+) -> token::Amount {
+    for slash in slashes {
+        if slash.epoch >= start_epoch
+            && withdraw_epoch.map_or(true, |end| slash.epoch < end)
+        {
+            let raw_delta: u64 = delta.into();
+            delta -= token::Amount::from(slash.rate * raw_delta);
+        }
+    }
+    delta
+}
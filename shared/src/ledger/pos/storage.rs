@@ -2,18 +2,19 @@
 
 use anoma_proof_of_stake::parameters::PosParams;
 use anoma_proof_of_stake::types::{
-    TotalVotingPowers, ValidatorStates, ValidatorVotingPowers,
+    BasisPoints, CommissionRates, TotalVotingPowers, ValidatorStates,
+    ValidatorVotingPowers,
 };
 use anoma_proof_of_stake::{types, PosBase};
 
 use super::{
-    BondId, Bonds, ValidatorConsensusKeys, ValidatorSets, ValidatorTotalDeltas,
-    ADDRESS,
+    BondId, Bonds, Redelegations, ValidatorConsensusKeys, ValidatorRewards,
+    ValidatorSetSnapshot, ValidatorSets, ValidatorTotalDeltas, ADDRESS,
 };
 use crate::ledger::storage::types::{decode, encode};
 use crate::ledger::storage::{self, Storage, StorageHasher};
 use crate::types::address::Address;
-use crate::types::storage::{DbKeySeg, Key, KeySeg};
+use crate::types::storage::{DbKeySeg, Epoch, Key, KeySeg};
 use crate::types::{key, token};
 
 const PARAMS_STORAGE_KEY: &str = "params";
@@ -25,10 +26,22 @@ const VALIDATOR_CONSENSUS_KEY_STORAGE_KEY: &str = "consensus_key";
 const VALIDATOR_STATE_STORAGE_KEY: &str = "state";
 const VALIDATOR_TOTAL_DELTAS_STORAGE_KEY: &str = "total_deltas";
 const VALIDATOR_VOTING_POWER_STORAGE_KEY: &str = "voting_power";
+const VALIDATOR_OUTGOING_REDELEGATIONS_STORAGE_KEY: &str =
+    "outgoing_redelegations";
+const VALIDATOR_INCOMING_REDELEGATIONS_STORAGE_KEY: &str =
+    "incoming_redelegations";
+const VALIDATOR_COMMISSION_RATE_STORAGE_KEY: &str = "commission_rate";
+const VALIDATOR_MAX_COMMISSION_RATE_CHANGE_STORAGE_KEY: &str =
+    "max_commission_rate_change";
+const VALIDATOR_REWARDS_STORAGE_KEY: &str = "rewards";
+const VALIDATOR_LIVENESS_STORAGE_KEY: &str = "liveness";
+const VALIDATOR_METADATA_STORAGE_KEY: &str = "metadata";
 const SLASHES_PREFIX: &str = "slash";
 const BOND_STORAGE_KEY: &str = "bond";
 const UNBOND_STORAGE_KEY: &str = "unbond";
+const REWARDS_CLAIMED_EPOCH_STORAGE_KEY: &str = "rewards_claimed_epoch";
 const VALIDATOR_SET_STORAGE_KEY: &str = "validator_set";
+const VALIDATOR_SET_SNAPSHOT_STORAGE_PREFIX: &str = "validator_set_snapshot";
 const TOTAL_VOTING_POWER_STORAGE_KEY: &str = "total_voting_power";
 
 /// Is the given key a PoS storage key?
@@ -60,11 +73,16 @@ pub fn is_params_key(key: &Key) -> bool {
 
 /// Storage key prefix for validator data.
 fn validator_prefix(validator: &Address) -> Key {
+    validators_prefix()
+        .push(&validator.to_db_key())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Storage key prefix for all validators.
+pub fn validators_prefix() -> Key {
     Key::from(ADDRESS.to_db_key())
         .push(&VALIDATOR_STORAGE_PREFIX.to_owned())
         .expect("Cannot obtain a storage key")
-        .push(&validator.to_db_key())
-        .expect("Cannot obtain a storage key")
 }
 
 /// Storage key for validator's address raw hash for look-up from raw hash of an
@@ -217,6 +235,185 @@ pub fn is_validator_voting_power_key(key: &Key) -> Option<&Address> {
     }
 }
 
+/// Storage key for the redelegations from a validator.
+pub fn validator_outgoing_redelegations_key(validator: &Address) -> Key {
+    validator_prefix(validator)
+        .push(&VALIDATOR_OUTGOING_REDELEGATIONS_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for the redelegations from a validator?
+pub fn is_validator_outgoing_redelegations_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(validator),
+            DbKeySeg::StringSeg(key),
+        ] if addr == &ADDRESS
+            && prefix == VALIDATOR_STORAGE_PREFIX
+            && key == VALIDATOR_OUTGOING_REDELEGATIONS_STORAGE_KEY =>
+        {
+            Some(validator)
+        }
+        _ => None,
+    }
+}
+
+/// Storage key for the redelegations to a validator.
+pub fn validator_incoming_redelegations_key(validator: &Address) -> Key {
+    validator_prefix(validator)
+        .push(&VALIDATOR_INCOMING_REDELEGATIONS_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for the redelegations to a validator?
+pub fn is_validator_incoming_redelegations_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(validator),
+            DbKeySeg::StringSeg(key),
+        ] if addr == &ADDRESS
+            && prefix == VALIDATOR_STORAGE_PREFIX
+            && key == VALIDATOR_INCOMING_REDELEGATIONS_STORAGE_KEY =>
+        {
+            Some(validator)
+        }
+        _ => None,
+    }
+}
+
+/// Storage key for validator's commission rate.
+pub fn validator_commission_rate_key(validator: &Address) -> Key {
+    validator_prefix(validator)
+        .push(&VALIDATOR_COMMISSION_RATE_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for validator's commission rate?
+pub fn is_validator_commission_rate_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(validator),
+            DbKeySeg::StringSeg(key),
+        ] if addr == &ADDRESS
+            && prefix == VALIDATOR_STORAGE_PREFIX
+            && key == VALIDATOR_COMMISSION_RATE_STORAGE_KEY =>
+        {
+            Some(validator)
+        }
+        _ => None,
+    }
+}
+
+/// Storage key for validator's maximum change of the commission rate per
+/// epoch.
+pub fn validator_max_commission_rate_change_key(validator: &Address) -> Key {
+    validator_prefix(validator)
+        .push(&VALIDATOR_MAX_COMMISSION_RATE_CHANGE_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for validator's maximum change of the commission rate per
+/// epoch?
+pub fn is_validator_max_commission_rate_change_key(
+    key: &Key,
+) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(validator),
+            DbKeySeg::StringSeg(key),
+        ] if addr == &ADDRESS
+            && prefix == VALIDATOR_STORAGE_PREFIX
+            && key == VALIDATOR_MAX_COMMISSION_RATE_CHANGE_STORAGE_KEY =>
+        {
+            Some(validator)
+        }
+        _ => None,
+    }
+}
+
+/// Storage key for the rewards of validator's bonds.
+pub fn validator_rewards_key(validator: &Address) -> Key {
+    validator_prefix(validator)
+        .push(&VALIDATOR_REWARDS_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for the rewards of validator's bonds?
+pub fn is_validator_rewards_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(validator),
+            DbKeySeg::StringSeg(key),
+        ] if addr == &ADDRESS
+            && prefix == VALIDATOR_STORAGE_PREFIX
+            && key == VALIDATOR_REWARDS_STORAGE_KEY =>
+        {
+            Some(validator)
+        }
+        _ => None,
+    }
+}
+
+/// Storage key for validator's missed blocks in the liveness window.
+pub fn validator_liveness_key(validator: &Address) -> Key {
+    validator_prefix(validator)
+        .push(&VALIDATOR_LIVENESS_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for validator's missed blocks in the liveness window?
+pub fn is_validator_liveness_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(validator),
+            DbKeySeg::StringSeg(key),
+        ] if addr == &ADDRESS
+            && prefix == VALIDATOR_STORAGE_PREFIX
+            && key == VALIDATOR_LIVENESS_STORAGE_KEY =>
+        {
+            Some(validator)
+        }
+        _ => None,
+    }
+}
+
+/// Storage key for validator's display metadata.
+pub fn validator_metadata_key(validator: &Address) -> Key {
+    validator_prefix(validator)
+        .push(&VALIDATOR_METADATA_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for validator's display metadata?
+pub fn is_validator_metadata_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(validator),
+            DbKeySeg::StringSeg(key),
+        ] if addr == &ADDRESS
+            && prefix == VALIDATOR_STORAGE_PREFIX
+            && key == VALIDATOR_METADATA_STORAGE_KEY =>
+        {
+            Some(validator)
+        }
+        _ => None,
+    }
+}
+
 /// Storage prefix for slashes.
 pub fn slashes_prefix() -> Key {
     Key::from(ADDRESS.to_db_key())
@@ -317,6 +514,43 @@ pub fn is_unbond_key(key: &Key) -> Option<BondId> {
     }
 }
 
+/// Storage key prefix for the epochs of the claimed rewards of all bonds.
+pub fn rewards_claimed_epochs_prefix() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&REWARDS_CLAIMED_EPOCH_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Storage key for the epoch before which the rewards of a bond with the
+/// given ID (source and validator) have been claimed.
+pub fn rewards_claimed_epoch_key(bond_id: &BondId) -> Key {
+    rewards_claimed_epochs_prefix()
+        .push(&bond_id.source.to_db_key())
+        .expect("Cannot obtain a storage key")
+        .push(&bond_id.validator.to_db_key())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for the epoch of the claimed rewards of a bond?
+pub fn is_rewards_claimed_epoch_key(key: &Key) -> Option<BondId> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(source),
+            DbKeySeg::AddressSeg(validator),
+        ] if addr == &ADDRESS
+            && prefix == REWARDS_CLAIMED_EPOCH_STORAGE_KEY =>
+        {
+            Some(BondId {
+                source: source.clone(),
+                validator: validator.clone(),
+            })
+        }
+        _ => None,
+    }
+}
+
 /// Storage key for validator set (active and inactive).
 pub fn validator_set_key() -> Key {
     Key::from(ADDRESS.to_db_key())
@@ -336,6 +570,36 @@ pub fn is_validator_set_key(key: &Key) -> bool {
     }
 }
 
+/// Storage key prefix for the validator set snapshots of the epochs.
+pub fn validator_set_snapshots_prefix() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&VALIDATOR_SET_SNAPSHOT_STORAGE_PREFIX.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Storage key for the validator set snapshot of an epoch.
+pub fn validator_set_snapshot_key(epoch: Epoch) -> Key {
+    validator_set_snapshots_prefix()
+        .push(&epoch.0.to_string())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for a validator set snapshot?
+pub fn is_validator_set_snapshot_key(key: &Key) -> Option<Epoch> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::StringSeg(epoch),
+        ] if addr == &ADDRESS
+            && prefix == VALIDATOR_SET_SNAPSHOT_STORAGE_PREFIX =>
+        {
+            epoch.parse().ok().map(Epoch)
+        }
+        _ => None,
+    }
+}
+
 /// Storage key for total voting power.
 pub fn total_voting_power_key() -> Key {
     Key::from(ADDRESS.to_db_key())
@@ -398,6 +662,16 @@ where
         value.map(|value| decode(value).unwrap())
     }
 
+    fn read_validator_staking_reward_address(
+        &self,
+        key: &Self::Address,
+    ) -> Option<Self::Address> {
+        let (value, _gas) = self
+            .read(&validator_staking_reward_address_key(key))
+            .unwrap();
+        value.map(|value| decode(value).unwrap())
+    }
+
     fn read_validator_consensus_key(
         &self,
         key: &Self::Address,
@@ -433,6 +707,32 @@ where
         value.map(|value| decode(value).unwrap())
     }
 
+    fn read_validator_commission_rate(
+        &self,
+        key: &Self::Address,
+    ) -> Option<CommissionRates> {
+        let (value, _gas) =
+            self.read(&validator_commission_rate_key(key)).unwrap();
+        value.map(|value| decode(value).unwrap())
+    }
+
+    fn read_validator_rewards(&self, key: &Self::Address) -> ValidatorRewards {
+        let (value, _gas) = self.read(&validator_rewards_key(key)).unwrap();
+        value
+            .map(|value| decode(value).unwrap())
+            .unwrap_or_default()
+    }
+
+    fn read_validator_liveness(
+        &self,
+        key: &Self::Address,
+    ) -> types::ValidatorLiveness {
+        let (value, _gas) = self.read(&validator_liveness_key(key)).unwrap();
+        value
+            .map(|value| decode(value).unwrap())
+            .unwrap_or_default()
+    }
+
     fn read_validator_slashes(&self, key: &Self::Address) -> types::Slashes {
         let (value, _gas) = self.read(&validator_slashes_key(key)).unwrap();
         value
@@ -440,6 +740,35 @@ where
             .unwrap_or_default()
     }
 
+    fn read_bond(&self, key: &BondId) -> Option<Bonds> {
+        let (value, _gas) = self.read(&bond_key(key)).unwrap();
+        value.map(|value| decode(value).unwrap())
+    }
+
+    fn read_validator_outgoing_redelegations(
+        &self,
+        key: &Self::Address,
+    ) -> Redelegations {
+        let (value, _gas) = self
+            .read(&validator_outgoing_redelegations_key(key))
+            .unwrap();
+        value
+            .map(|value| decode(value).unwrap())
+            .unwrap_or_default()
+    }
+
+    fn read_validator_incoming_redelegations(
+        &self,
+        key: &Self::Address,
+    ) -> Redelegations {
+        let (value, _gas) = self
+            .read(&validator_incoming_redelegations_key(key))
+            .unwrap();
+        value
+            .map(|value| decode(value).unwrap())
+            .unwrap_or_default()
+    }
+
     fn read_validator_set(&self) -> ValidatorSets {
         let (value, _gas) = self.read(&validator_set_key()).unwrap();
         decode(value.unwrap()).unwrap()
@@ -450,6 +779,26 @@ where
         decode(value.unwrap()).unwrap()
     }
 
+    fn read_validator_set_snapshot(
+        &self,
+        epoch: types::Epoch,
+    ) -> Option<ValidatorSetSnapshot> {
+        let (value, _gas) = self
+            .read(&validator_set_snapshot_key(epoch.into()))
+            .unwrap();
+        value.map(|value| decode(value).unwrap())
+    }
+
+    /// The staking token's total supply is tracked at genesis and raised
+    /// with the tokens credited by the PoS system
+    fn read_staking_token_supply(&self) -> Self::TokenAmount {
+        let key = token::total_supply_key(&super::staking_token_address());
+        let (value, _gas) = self.read(&key).unwrap_or_default();
+        value
+            .and_then(|value| decode(value).ok())
+            .unwrap_or_default()
+    }
+
     fn write_pos_params(&mut self, params: &PosParams) {
         self.write(&params_key(), encode(params)).unwrap();
     }
@@ -505,6 +854,45 @@ where
             .unwrap();
     }
 
+    fn write_validator_commission_rate(
+        &mut self,
+        key: &Self::Address,
+        value: &CommissionRates,
+    ) {
+        self.write(&validator_commission_rate_key(key), encode(value))
+            .unwrap();
+    }
+
+    fn write_validator_max_commission_rate_change(
+        &mut self,
+        key: &Self::Address,
+        value: &BasisPoints,
+    ) {
+        self.write(
+            &validator_max_commission_rate_change_key(key),
+            encode(value),
+        )
+        .unwrap();
+    }
+
+    fn write_validator_rewards(
+        &mut self,
+        key: &Self::Address,
+        value: &ValidatorRewards,
+    ) {
+        self.write(&validator_rewards_key(key), encode(value))
+            .unwrap();
+    }
+
+    fn write_validator_liveness(
+        &mut self,
+        key: &Self::Address,
+        value: &types::ValidatorLiveness,
+    ) {
+        self.write(&validator_liveness_key(key), encode(value))
+            .unwrap();
+    }
+
     fn write_validator_slash(
         &mut self,
         validator: &Self::Address,
@@ -520,6 +908,24 @@ where
         self.write(&bond_key(key), encode(value)).unwrap();
     }
 
+    fn write_validator_outgoing_redelegations(
+        &mut self,
+        key: &Self::Address,
+        value: &Redelegations,
+    ) {
+        self.write(&validator_outgoing_redelegations_key(key), encode(value))
+            .unwrap();
+    }
+
+    fn write_validator_incoming_redelegations(
+        &mut self,
+        key: &Self::Address,
+        value: &Redelegations,
+    ) {
+        self.write(&validator_incoming_redelegations_key(key), encode(value))
+            .unwrap();
+    }
+
     fn write_validator_set(&mut self, value: &ValidatorSets) {
         self.write(&validator_set_key(), encode(value)).unwrap();
     }
@@ -529,6 +935,20 @@ where
             .unwrap();
     }
 
+    fn write_validator_set_snapshot(
+        &mut self,
+        epoch: types::Epoch,
+        value: &ValidatorSetSnapshot,
+    ) {
+        self.write(&validator_set_snapshot_key(epoch.into()), encode(value))
+            .unwrap();
+    }
+
+    fn delete_validator_set_snapshot(&mut self, epoch: types::Epoch) {
+        self.delete(&validator_set_snapshot_key(epoch.into()))
+            .unwrap();
+    }
+
     fn init_staking_reward_account(
         &mut self,
         address: &Self::Address,
@@ -565,6 +985,17 @@ where
         };
         self.write(&key, encode(&new_balance))
             .expect("Unable to write token balance for PoS system");
+        // The credited tokens are minted
+        let supply_key = token::total_supply_key(token);
+        let supply: Self::TokenAmount = match self
+            .read(&supply_key)
+            .expect("Unable to read token supply for PoS system")
+        {
+            (Some(supply), _gas) => decode(supply).unwrap_or_default(),
+            _ => Self::TokenAmount::default(),
+        };
+        self.write(&supply_key, encode(&(supply + amount)))
+            .expect("Unable to write token supply for PoS system");
     }
 
     fn transfer(
@@ -4,10 +4,11 @@ use std::collections::BTreeSet;
 use std::panic::{RefUnwindSafe, UnwindSafe};
 
 pub use anoma_proof_of_stake;
+use anoma_proof_of_stake::epoched::DynEpochOffset;
 pub use anoma_proof_of_stake::parameters::PosParams;
 pub use anoma_proof_of_stake::types::{
-    self, Slash, Slashes, TotalVotingPowers, ValidatorStates,
-    ValidatorVotingPowers,
+    self, BasisPoints, CommissionRates, Slash, Slashes, TotalVotingPowers,
+    ValidatorMetadata, ValidatorStates, ValidatorVotingPowers,
 };
 use anoma_proof_of_stake::validation::validate;
 use anoma_proof_of_stake::{validation, PosReadOnly};
@@ -16,26 +17,34 @@ use itertools::Itertools;
 use thiserror::Error;
 
 use super::{
-    bond_key, is_bond_key, is_params_key, is_total_voting_power_key,
-    is_unbond_key, is_validator_set_key,
-    is_validator_staking_reward_address_key, is_validator_total_deltas_key,
-    is_validator_voting_power_key, params_key, staking_token_address,
-    total_voting_power_key, unbond_key, validator_consensus_key_key,
+    bond_key, is_bond_key, is_params_key, is_rewards_claimed_epoch_key,
+    is_total_voting_power_key, is_unbond_key, is_validator_commission_rate_key,
+    is_validator_incoming_redelegations_key, is_validator_liveness_key,
+    is_validator_max_commission_rate_change_key,
+    is_validator_outgoing_redelegations_key, is_validator_rewards_key,
+    is_validator_set_key, is_validator_staking_reward_address_key,
+    is_validator_total_deltas_key, is_validator_voting_power_key, params_key,
+    rewards_claimed_epoch_key, staking_token_address, total_voting_power_key,
+    unbond_key, validator_commission_rate_key, validator_consensus_key_key,
+    validator_incoming_redelegations_key,
+    validator_max_commission_rate_change_key,
+    validator_outgoing_redelegations_key, validator_rewards_key,
     validator_set_key, validator_slashes_key,
     validator_staking_reward_address_key, validator_state_key,
     validator_total_deltas_key, validator_voting_power_key, BondId, Bonds,
-    Unbonds, ValidatorConsensusKeys, ValidatorSets, ValidatorTotalDeltas,
+    Redelegations, Unbonds, ValidatorConsensusKeys, ValidatorRewards,
+    ValidatorSets, ValidatorTotalDeltas,
 };
 use crate::ledger::governance::vp::is_proposal_accepted;
 use crate::ledger::native_vp::{self, Ctx, NativeVp};
 use crate::ledger::pos::{
     is_validator_address_raw_hash_key, is_validator_consensus_key_key,
-    is_validator_state_key,
+    is_validator_metadata_key, is_validator_state_key,
 };
 use crate::ledger::storage::types::decode;
 use crate::ledger::storage::{self as ledger_storage, StorageHasher};
 use crate::types::address::{Address, InternalAddress};
-use crate::types::storage::{Key, KeySeg};
+use crate::types::storage::{Epoch, Key, KeySeg};
 use crate::types::{key, token};
 use crate::vm::WasmCacheAccess;
 
@@ -44,6 +53,8 @@ use crate::vm::WasmCacheAccess;
 pub enum Error {
     #[error("Native VP error: {0}")]
     NativeVpError(native_vp::Error),
+    #[error("Bond below the minimum: {0}")]
+    BondBelowMinimum(String),
 }
 
 /// PoS functions result
@@ -70,6 +81,49 @@ where
     pub fn new(ctx: Ctx<'a, DB, H, CA>) -> Self {
         Self { ctx }
     }
+
+    /// Read the posterior consensus keys of the validators other than the
+    /// given `validator` that are in the validator set from the current epoch
+    /// up to the pipeline epoch.
+    fn read_other_consensus_keys(
+        &self,
+        validator: &Address,
+        current_epoch: Epoch,
+    ) -> Result<Vec<(Address, ValidatorConsensusKeys)>> {
+        let params = self.read_pos_params();
+        let pipeline_offset = DynEpochOffset::PipelineLen.value(&params);
+        let current_epoch: types::Epoch = current_epoch.into();
+        let validator_set = self
+            .ctx
+            .read_post(&validator_set_key())?
+            .and_then(|bytes| ValidatorSets::try_from_slice(&bytes[..]).ok());
+        let validators: BTreeSet<&Address> = validator_set
+            .iter()
+            .flat_map(|validator_set| {
+                current_epoch
+                    .iter_range(pipeline_offset + 1)
+                    .filter_map(|epoch| validator_set.get(epoch))
+            })
+            .flat_map(|validators| {
+                validators.active.iter().chain(validators.inactive.iter())
+            })
+            .map(|validator| &validator.address)
+            .filter(|address| *address != validator)
+            .collect();
+        let mut other_keys = vec![];
+        for address in validators {
+            let keys = self
+                .ctx
+                .read_post(&validator_consensus_key_key(address))?
+                .and_then(|bytes| {
+                    ValidatorConsensusKeys::try_from_slice(&bytes[..]).ok()
+                });
+            if let Some(keys) = keys {
+                other_keys.push((address.clone(), keys));
+            }
+        }
+        Ok(other_keys)
+    }
 }
 
 // TODO this is temporarily to run PoS native VP in a new thread to avoid
@@ -151,9 +205,18 @@ where
                 let post = self.ctx.read_post(key)?.and_then(|bytes| {
                     ValidatorStates::try_from_slice(&bytes[..]).ok()
                 });
+                let total_deltas = self
+                    .ctx
+                    .read_post(&validator_total_deltas_key(validator))?
+                    .and_then(|bytes| {
+                        ValidatorTotalDeltas::try_from_slice(&bytes[..]).ok()
+                    });
                 changes.push(Validator {
                     address: validator.clone(),
-                    update: State(Data { pre, post }),
+                    update: State {
+                        data: Data { pre, post },
+                        total_deltas,
+                    },
                 });
             } else if let Some(validator) =
                 is_validator_staking_reward_address_key(key)
@@ -178,9 +241,14 @@ where
                 let post = self.ctx.read_post(key)?.and_then(|bytes| {
                     ValidatorConsensusKeys::try_from_slice(&bytes[..]).ok()
                 });
+                let other_keys =
+                    self.read_other_consensus_keys(validator, current_epoch)?;
                 changes.push(Validator {
                     address: validator.clone(),
-                    update: ConsensusKey(Data { pre, post }),
+                    update: ConsensusKey {
+                        data: Data { pre, post },
+                        other_keys,
+                    },
                 });
             } else if let Some(validator) = is_validator_total_deltas_key(key) {
                 let pre = self.ctx.read_pre(key)?.and_then(|bytes| {
@@ -189,9 +257,18 @@ where
                 let post = self.ctx.read_post(key)?.and_then(|bytes| {
                     ValidatorTotalDeltas::try_from_slice(&bytes[..]).ok()
                 });
+                let state = self
+                    .ctx
+                    .read_post(&validator_state_key(validator))?
+                    .and_then(|bytes| {
+                        ValidatorStates::try_from_slice(&bytes[..]).ok()
+                    });
                 changes.push(Validator {
                     address: validator.clone(),
-                    update: TotalDeltas(Data { pre, post }),
+                    update: TotalDeltas {
+                        data: Data { pre, post },
+                        state,
+                    },
                 });
             } else if let Some(validator) = is_validator_voting_power_key(key) {
                 let pre = self.ctx.read_pre(key)?.and_then(|bytes| {
@@ -204,6 +281,96 @@ where
                     address: validator.clone(),
                     update: VotingPowerUpdate(Data { pre, post }),
                 });
+            } else if let Some(validator) =
+                is_validator_outgoing_redelegations_key(key)
+            {
+                let pre = self.ctx.read_pre(key)?.and_then(|bytes| {
+                    Redelegations::try_from_slice(&bytes[..]).ok()
+                });
+                let post = self.ctx.read_post(key)?.and_then(|bytes| {
+                    Redelegations::try_from_slice(&bytes[..]).ok()
+                });
+                changes.push(OutgoingRedelegations {
+                    validator: validator.clone(),
+                    data: Data { pre, post },
+                });
+            } else if let Some(validator) =
+                is_validator_incoming_redelegations_key(key)
+            {
+                let pre = self.ctx.read_pre(key)?.and_then(|bytes| {
+                    Redelegations::try_from_slice(&bytes[..]).ok()
+                });
+                let post = self.ctx.read_post(key)?.and_then(|bytes| {
+                    Redelegations::try_from_slice(&bytes[..]).ok()
+                });
+                changes.push(IncomingRedelegations {
+                    validator: validator.clone(),
+                    data: Data { pre, post },
+                });
+            } else if let Some(validator) =
+                is_validator_commission_rate_key(key)
+            {
+                let pre = self.ctx.read_pre(key)?.and_then(|bytes| {
+                    CommissionRates::try_from_slice(&bytes[..]).ok()
+                });
+                let post = self.ctx.read_post(key)?.and_then(|bytes| {
+                    CommissionRates::try_from_slice(&bytes[..]).ok()
+                });
+                // For the commission rate, we need to look-up its maximum
+                // change, which is only set with a new validator
+                let max_change = self
+                    .ctx
+                    .read_post(&validator_max_commission_rate_change_key(
+                        validator,
+                    ))?
+                    .and_then(|bytes| {
+                        BasisPoints::try_from_slice(&bytes[..]).ok()
+                    });
+                changes.push(Validator {
+                    address: validator.clone(),
+                    update: CommissionRate {
+                        data: Data { pre, post },
+                        max_change,
+                    },
+                });
+            } else if let Some(validator) =
+                is_validator_max_commission_rate_change_key(key)
+            {
+                let pre = self.ctx.read_pre(key)?.and_then(|bytes| {
+                    BasisPoints::try_from_slice(&bytes[..]).ok()
+                });
+                let post = self.ctx.read_post(key)?.and_then(|bytes| {
+                    BasisPoints::try_from_slice(&bytes[..]).ok()
+                });
+                changes.push(Validator {
+                    address: validator.clone(),
+                    update: MaxCommissionRateChange(Data { pre, post }),
+                });
+            } else if is_validator_rewards_key(key).is_some() {
+                // The rewards are only accrued by the protocol
+                tracing::info!("PoS rewards change {} rejected", key);
+                return Ok(false);
+            } else if is_validator_liveness_key(key).is_some() {
+                // The liveness is only recorded by the protocol
+                tracing::info!("PoS liveness change {} rejected", key);
+                return Ok(false);
+            } else if let Some(validator) = is_validator_metadata_key(key) {
+                // The metadata of a validator must be valid and it cannot be
+                // deleted. The signature of the validator is checked by its
+                // VP.
+                let metadata = self.ctx.read_post(key)?.and_then(|bytes| {
+                    ValidatorMetadata::try_from_slice(&bytes[..]).ok()
+                });
+                let is_validator =
+                    self.ctx.has_key_post(&validator_state_key(validator))?;
+                let is_valid = is_validator
+                    && metadata
+                        .map(|metadata| metadata.validate().is_ok())
+                        .unwrap_or_default();
+                if !is_valid {
+                    tracing::info!("PoS metadata change {} rejected", key);
+                    return Ok(false);
+                }
             } else if let Some(raw_hash) =
                 is_validator_address_raw_hash_key(key)
             {
@@ -252,16 +419,27 @@ where
                     .ctx
                     .read_post(key)?
                     .and_then(|bytes| Bonds::try_from_slice(&bytes[..]).ok());
-                // For bonds, we need to look-up slashes
+                // For bonds, we need to look-up slashes and the redelegations
+                // to the validator
                 let slashes = self
                     .ctx
                     .read_pre(&validator_slashes_key(&bond_id.validator))?
                     .and_then(|bytes| Slashes::try_from_slice(&bytes[..]).ok())
                     .unwrap_or_default();
+                let redelegations = self
+                    .ctx
+                    .read_pre(&validator_incoming_redelegations_key(
+                        &bond_id.validator,
+                    ))?
+                    .and_then(|bytes| {
+                        Redelegations::try_from_slice(&bytes[..]).ok()
+                    })
+                    .unwrap_or_default();
                 changes.push(Bond {
                     id: bond_id.clone(),
                     data: Data { pre, post },
                     slashes,
+                    redelegations,
                 });
             } else if let Some(unbond_id) = is_unbond_key(key) {
                 let pre = self
@@ -283,6 +461,38 @@ where
                     data: Data { pre, post },
                     slashes,
                 });
+            } else if let Some(bond_id) = is_rewards_claimed_epoch_key(key) {
+                let pre = self.ctx.read_pre(key)?.and_then(|bytes| {
+                    types::Epoch::try_from_slice(&bytes[..]).ok()
+                });
+                let post = self.ctx.read_post(key)?.and_then(|bytes| {
+                    types::Epoch::try_from_slice(&bytes[..]).ok()
+                });
+                // For the claimed rewards, we need to look-up the bond, its
+                // validator's slashes and rewards
+                let bond = self
+                    .ctx
+                    .read_pre(&bond_key(&bond_id))?
+                    .and_then(|bytes| Bonds::try_from_slice(&bytes[..]).ok());
+                let slashes = self
+                    .ctx
+                    .read_pre(&validator_slashes_key(&bond_id.validator))?
+                    .and_then(|bytes| Slashes::try_from_slice(&bytes[..]).ok())
+                    .unwrap_or_default();
+                let rewards = self
+                    .ctx
+                    .read_pre(&validator_rewards_key(&bond_id.validator))?
+                    .and_then(|bytes| {
+                        ValidatorRewards::try_from_slice(&bytes[..]).ok()
+                    })
+                    .unwrap_or_default();
+                changes.push(RewardsClaim {
+                    id: bond_id,
+                    data: Data { pre, post },
+                    bond,
+                    slashes,
+                    rewards,
+                });
             } else if is_total_voting_power_key(key) {
                 let pre = self.ctx.read_pre(key)?.and_then(|bytes| {
                     TotalVotingPowers::try_from_slice(&bytes[..]).ok()
@@ -297,12 +507,28 @@ where
                 return Ok(false);
             } else {
                 // Unknown changes anywhere else are permitted
-                return Ok(true);
+                continue;
             }
         }
 
         let params = self.read_pos_params();
         let errors = validate(&params, changes, current_epoch);
+        // Bonds below the minimum are rejected with their own reason, so that
+        // the tx result tells it apart from other invalid PoS changes
+        let below_minimum = errors.iter().find(|error| {
+            matches!(
+                error,
+                validation::Error::SelfBondBelowMinimum { .. }
+                    | validation::Error::DelegationBelowMinimum { .. }
+            )
+        });
+        if let Some(error) = below_minimum {
+            tracing::info!(
+                "PoS validation errors:\n - {}",
+                errors.iter().format("\n - ")
+            );
+            return Err(Error::BondBelowMinimum(error.to_string()));
+        }
         Ok(if errors.is_empty() {
             true
         } else {
@@ -402,6 +628,66 @@ where
         value.map(|value| decode(value).unwrap())
     }
 
+    fn read_validator_outgoing_redelegations(
+        &self,
+        key: &Self::Address,
+    ) -> Redelegations {
+        let value = self
+            .ctx
+            .read_pre(&validator_outgoing_redelegations_key(key))
+            .unwrap();
+        value
+            .map(|value| decode(value).unwrap())
+            .unwrap_or_default()
+    }
+
+    fn read_validator_incoming_redelegations(
+        &self,
+        key: &Self::Address,
+    ) -> Redelegations {
+        let value = self
+            .ctx
+            .read_pre(&validator_incoming_redelegations_key(key))
+            .unwrap();
+        value
+            .map(|value| decode(value).unwrap())
+            .unwrap_or_default()
+    }
+
+    fn read_validator_commission_rate(
+        &self,
+        key: &Self::Address,
+    ) -> Option<CommissionRates> {
+        let value = self
+            .ctx
+            .read_pre(&validator_commission_rate_key(key))
+            .unwrap();
+        value.map(|value| decode(value).unwrap())
+    }
+
+    fn read_validator_max_commission_rate_change(
+        &self,
+        key: &Self::Address,
+    ) -> Option<BasisPoints> {
+        let value = self
+            .ctx
+            .read_pre(&validator_max_commission_rate_change_key(key))
+            .unwrap();
+        value.map(|value| decode(value).unwrap())
+    }
+
+    fn read_validator_rewards(&self, key: &Self::Address) -> ValidatorRewards {
+        let value = self.ctx.read_pre(&validator_rewards_key(key)).unwrap();
+        value
+            .map(|value| decode(value).unwrap())
+            .unwrap_or_default()
+    }
+
+    fn read_rewards_claimed_epoch(&self, key: &BondId) -> Option<types::Epoch> {
+        let value = self.ctx.read_pre(&rewards_claimed_epoch_key(key)).unwrap();
+        value.map(|value| decode(value).unwrap())
+    }
+
     fn read_validator_set(&self) -> ValidatorSets {
         let value = self.ctx.read_pre(&validator_set_key()).unwrap().unwrap();
         decode(value).unwrap()
@@ -1,14 +1,16 @@
 //! The merkle tree in the storage
 
+use std::collections::BTreeMap;
 use std::convert::TryInto;
 use std::fmt;
 use std::str::FromStr;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use ics23::commitment_proof::Proof as Ics23Proof;
+pub use ics23::ProofSpec;
 use ics23::{
     CommitmentProof, ExistenceProof, HashOp, LeafOp, LengthOp,
-    NonExistenceProof, ProofSpec,
+    NonExistenceProof,
 };
 use prost::Message;
 use sha2::{Digest, Sha256};
@@ -16,15 +18,12 @@ use sparse_merkle_tree::default_store::DefaultStore;
 use sparse_merkle_tree::error::Error as SmtError;
 use sparse_merkle_tree::traits::Hasher;
 use sparse_merkle_tree::{SparseMerkleTree, H256};
-#[cfg(not(feature = "ABCI"))]
-use tendermint::merkle::proof::{Proof, ProofOp};
-#[cfg(feature = "ABCI")]
-use tendermint_stable::merkle::proof::{Proof, ProofOp};
 use thiserror::Error;
 
 use crate::bytes::ByteBuf;
+use crate::tendermint::merkle::proof::{Proof, ProofOp};
 use crate::types::address::{Address, InternalAddress};
-use crate::types::storage::{DbKeySeg, Error as StorageError, Key};
+use crate::types::storage::{DbKeySeg, Error as StorageError, Key, KeySeg};
 
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
@@ -37,6 +36,8 @@ pub enum Error {
     Smt(SmtError),
     #[error("Invalid store type: {0}")]
     StoreType(String),
+    #[error("Invalid proof: {0}")]
+    InvalidProof(String),
 }
 
 /// Result for functions that may fail
@@ -57,7 +58,8 @@ type Result<T> = std::result::Result<T, Error>;
 pub enum StoreType {
     /// Base tree, which has roots of the subtrees
     Base,
-    /// For Account and other data
+    /// For Account and other data, which has roots of the per-account
+    /// subtrees
     Account,
     /// For IBC-related data
     Ibc,
@@ -125,13 +127,31 @@ impl fmt::Display for StoreType {
     }
 }
 
+/// Get the key of the account subtree in which the given key of the
+/// [`StoreType::Account`] store belongs. Every key whose first segment is the
+/// same (typically an address) belongs to the same account subtree.
+fn account_subtree_key(key: &Key) -> Result<String> {
+    match key.segments.get(0) {
+        Some(seg) => Ok(seg.raw()),
+        None => Err(Error::EmptyKey("the key is empty".to_owned())),
+    }
+}
+
+type Smt<H> = SparseMerkleTree<H, H256, DefaultStore<H256>>;
+
+/// The roots and stores of the per-account subtrees
+pub type AccountSubtreeStores = BTreeMap<String, (H256, DefaultStore<H256>)>;
+
 /// Merkle tree storage
 #[derive(Default)]
 pub struct MerkleTree<H: StorageHasher + Default> {
-    base: SparseMerkleTree<H, H256, DefaultStore<H256>>,
-    account: SparseMerkleTree<H, H256, DefaultStore<H256>>,
-    ibc: SparseMerkleTree<H, H256, DefaultStore<H256>>,
-    pos: SparseMerkleTree<H, H256, DefaultStore<H256>>,
+    base: Smt<H>,
+    account: Smt<H>,
+    ibc: Smt<H>,
+    pos: Smt<H>,
+    /// The per-account subtrees. Their roots are the values of the account
+    /// tree, so that an update of one account doesn't touch the others.
+    accounts: BTreeMap<String, Smt<H>>,
 }
 
 impl<H: StorageHasher + Default> core::fmt::Debug for MerkleTree<H> {
@@ -150,76 +170,114 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
         let account = SparseMerkleTree::new(stores.account.0, stores.account.1);
         let ibc = SparseMerkleTree::new(stores.ibc.0, stores.ibc.1);
         let pos = SparseMerkleTree::new(stores.pos.0, stores.pos.1);
+        let accounts = stores
+            .accounts
+            .into_iter()
+            .map(|(account_key, (root, store))| {
+                (account_key, SparseMerkleTree::new(root, store))
+            })
+            .collect();
 
         Self {
             base,
             account,
             ibc,
             pos,
+            accounts,
         }
     }
 
+    /// Get the tree which directly contains the given sub key. For the
+    /// account store, this is the account's subtree, if any.
     fn tree(
         &self,
         store_type: &StoreType,
-    ) -> &SparseMerkleTree<H, H256, DefaultStore<H256>> {
-        match store_type {
-            StoreType::Base => &self.base,
-            StoreType::Account => &self.account,
-            StoreType::Ibc => &self.ibc,
-            StoreType::PoS => &self.pos,
-        }
+        sub_key: &Key,
+    ) -> Result<Option<&Smt<H>>> {
+        Ok(match store_type {
+            StoreType::Base => Some(&self.base),
+            StoreType::Account => {
+                self.accounts.get(&account_subtree_key(sub_key)?)
+            }
+            StoreType::Ibc => Some(&self.ibc),
+            StoreType::PoS => Some(&self.pos),
+        })
     }
 
-    fn update_tree(
-        &mut self,
-        store_type: &StoreType,
-        key: H256,
-        value: H256,
-    ) -> Result<()> {
-        let tree = match store_type {
-            StoreType::Account => &mut self.account,
-            StoreType::Ibc => &mut self.ibc,
-            StoreType::PoS => &mut self.pos,
+    fn update_tree(&mut self, key: &Key, value: H256) -> Result<()> {
+        let (store_type, sub_key) = StoreType::sub_key(key)?;
+        let hashed_sub_key = H::hash(sub_key.to_string());
+        let sub_root = match store_type {
+            StoreType::Account => {
+                let account_key = account_subtree_key(&sub_key)?;
+                let account_tree =
+                    self.accounts.entry(account_key.clone()).or_default();
+                let account_root = *account_tree
+                    .update(hashed_sub_key, value)
+                    .map_err(Error::Smt)?;
+                if account_root.is_zero() {
+                    // The account subtree is empty
+                    self.accounts.remove(&account_key);
+                }
+                // update the account tree with the updated account root
+                // without hashing
+                *self
+                    .account
+                    .update(H::hash(&account_key), account_root)
+                    .map_err(Error::Smt)?
+            }
+            StoreType::Ibc => {
+                *self.ibc.update(hashed_sub_key, value).map_err(Error::Smt)?
+            }
+            StoreType::PoS => {
+                *self.pos.update(hashed_sub_key, value).map_err(Error::Smt)?
+            }
             // base tree should not be directly updated
             StoreType::Base => unreachable!(),
         };
-        let sub_root = tree.update(key, value).map_err(Error::Smt)?;
 
         // update the base tree with the updated sub root without hashing
-        if *store_type != StoreType::Base {
-            let base_key = H::hash(&store_type.to_string());
-            self.base.update(base_key, *sub_root)?;
-        }
+        let base_key = H::hash(&store_type.to_string());
+        self.base.update(base_key, sub_root)?;
         Ok(())
     }
 
     /// Check if the key exists in the tree
     pub fn has_key(&self, key: &Key) -> Result<bool> {
         let (store_type, sub_key) = StoreType::sub_key(key)?;
-        let subtree = self.tree(&store_type);
-        let value = subtree.get(&H::hash(sub_key.to_string()))?;
-        Ok(!value.is_zero())
+        match self.tree(&store_type, &sub_key)? {
+            Some(subtree) => {
+                let value = subtree.get(&H::hash(sub_key.to_string()))?;
+                Ok(!value.is_zero())
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Check if the key exists in the tree with the given value
+    pub fn has_value(
+        &self,
+        key: &Key,
+        value: impl AsRef<[u8]>,
+    ) -> Result<bool> {
+        let (store_type, sub_key) = StoreType::sub_key(key)?;
+        match self.tree(&store_type, &sub_key)? {
+            Some(subtree) => {
+                let hash = subtree.get(&H::hash(sub_key.to_string()))?;
+                Ok(hash == H::hash(value))
+            }
+            None => Ok(false),
+        }
     }
 
     /// Update the tree with the given key and value
     pub fn update(&mut self, key: &Key, value: impl AsRef<[u8]>) -> Result<()> {
-        let (store_type, sub_key) = StoreType::sub_key(key)?;
-        self.update_tree(
-            &store_type,
-            H::hash(sub_key.to_string()),
-            H::hash(value),
-        )
+        self.update_tree(key, H::hash(value))
     }
 
     /// Delete the value corresponding to the given key
     pub fn delete(&mut self, key: &Key) -> Result<()> {
-        let (store_type, sub_key) = StoreType::sub_key(key)?;
-        self.update_tree(
-            &store_type,
-            H::hash(sub_key.to_string()),
-            H256::zero(),
-        )
+        self.update_tree(key, H256::zero())
     }
 
     /// Get the root
@@ -234,6 +292,13 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
             account: (self.account.root(), self.account.store()),
             ibc: (self.ibc.root(), self.ibc.store()),
             pos: (self.pos.root(), self.pos.store()),
+            accounts: self
+                .accounts
+                .iter()
+                .map(|(account_key, tree)| {
+                    (account_key.as_str(), (tree.root(), tree.store()))
+                })
+                .collect(),
         }
     }
 
@@ -244,7 +309,8 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
         value: Vec<u8>,
     ) -> Result<Proof> {
         let (store_type, sub_key) = StoreType::sub_key(key)?;
-        let subtree = self.tree(&store_type);
+        let empty_tree = Smt::<H>::default();
+        let subtree = self.tree(&store_type, &sub_key)?.unwrap_or(&empty_tree);
 
         // Get a proof of the sub tree
         let hashed_sub_key = H::hash(&sub_key.to_string());
@@ -255,7 +321,7 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
                 proof: Some(Ics23Proof::Exist(ExistenceProof {
                     key: sub_key.to_string().as_bytes().to_vec(),
                     value,
-                    leaf: Some(self.leaf_spec()),
+                    leaf: Some(Self::leaf_spec()),
                     ..ep
                 })),
             },
@@ -268,16 +334,21 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
     /// Get the non-existence proof
     pub fn get_non_existence_proof(&self, key: &Key) -> Result<Proof> {
         let (store_type, sub_key) = StoreType::sub_key(key)?;
-        let subtree = self.tree(&store_type);
+        // When the account subtree doesn't exist, prove that its root isn't
+        // in the account tree instead
+        let (subtree, proof_key) = match self.tree(&store_type, &sub_key)? {
+            Some(subtree) => (subtree, sub_key.to_string()),
+            None => (&self.account, account_subtree_key(&sub_key)?),
+        };
 
         // Get a proof of the sub tree
-        let hashed_sub_key = H::hash(&sub_key.to_string());
-        let cp = subtree.non_membership_proof(&hashed_sub_key)?;
+        let hashed_proof_key = H::hash(&proof_key);
+        let cp = subtree.non_membership_proof(&hashed_proof_key)?;
         // Replace the key with the non-hashed key for the verification
         let sub_proof = match cp.proof.expect("The proof should exist") {
             Ics23Proof::Nonexist(nep) => CommitmentProof {
                 proof: Some(Ics23Proof::Nonexist(NonExistenceProof {
-                    key: sub_key.to_string().as_bytes().to_vec(),
+                    key: proof_key.as_bytes().to_vec(),
                     ..nep
                 })),
             },
@@ -287,7 +358,9 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
         self.get_proof(key, sub_proof)
     }
 
-    /// Get the Tendermint proof with the base proof
+    /// Get the Tendermint proof with the base proof. For a key in an existing
+    /// account subtree, the proof of the account subtree's root in the
+    /// account tree is included between the sub proof and the base proof.
     fn get_proof(
         &self,
         key: &Key,
@@ -302,18 +375,48 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
             key: key.to_string().as_bytes().to_vec(),
             data,
         };
+        let mut ops = vec![sub_proof_op];
+
+        let (store_type, sub_key) = StoreType::sub_key(key)?;
+        if store_type == StoreType::Account {
+            let account_key = account_subtree_key(&sub_key)?;
+            if self.accounts.contains_key(&account_key) {
+                // Get a membership proof of the account tree because the
+                // account root should exist
+                ops.push(Self::root_proof_op(
+                    &self.account,
+                    &account_key,
+                    key,
+                )?);
+            }
+        }
 
         // Get a membership proof of the base tree because the sub root should
         // exist
-        let (store_type, _) = StoreType::sub_key(key)?;
-        let base_key = store_type.to_string();
-        let cp = self.base.membership_proof(&H::hash(&base_key))?;
+        ops.push(Self::root_proof_op(
+            &self.base,
+            &store_type.to_string(),
+            key,
+        )?);
+
+        // Set ProofOps from leaf to root
+        Ok(Proof { ops })
+    }
+
+    /// Get a proof op of the existence of a subtree root with the given key in
+    /// the given tree.
+    fn root_proof_op(
+        tree: &Smt<H>,
+        root_key: &str,
+        key: &Key,
+    ) -> Result<ProofOp> {
+        let cp = tree.membership_proof(&H::hash(root_key))?;
         // Replace the values and the leaf op for the verification
-        let base_proof = match cp.proof.expect("The proof should exist") {
+        let proof = match cp.proof.expect("The proof should exist") {
             Ics23Proof::Exist(ep) => CommitmentProof {
                 proof: Some(Ics23Proof::Exist(ExistenceProof {
-                    key: base_key.as_bytes().to_vec(),
-                    leaf: Some(self.base_leaf_spec()),
+                    key: root_key.as_bytes().to_vec(),
+                    leaf: Some(Self::base_leaf_spec()),
                     ..ep
                 })),
             },
@@ -322,38 +425,168 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
         };
 
         let mut data = vec![];
-        base_proof
+        proof
             .encode(&mut data)
             .expect("Encoding proof shouldn't fail");
-        let base_proof_op = ProofOp {
+        Ok(ProofOp {
             field_type: "ics23_CommitmentProof".to_string(),
             key: key.to_string().as_bytes().to_vec(),
             data,
-        };
-
-        // Set ProofOps from leaf to root
-        Ok(Proof {
-            ops: vec![sub_proof_op, base_proof_op],
         })
     }
 
-    /// Get the proof specs
+    /// Get the proof specs. The first spec is for the sub proof and the
+    /// second spec is for every following proof of a subtree's root, i.e. the
+    /// account tree's proof (if any) and the base tree's proof.
     pub fn proof_specs(&self) -> Vec<ProofSpec> {
-        let spec = sparse_merkle_tree::proof_ics23::get_spec(H::hash_op());
-        let sub_tree_spec = ProofSpec {
-            leaf_spec: Some(self.leaf_spec()),
-            ..spec.clone()
-        };
-        let base_tree_spec = ProofSpec {
-            leaf_spec: Some(self.base_leaf_spec()),
-            ..spec
+        vec![Self::sub_tree_spec(), Self::base_tree_spec()]
+    }
+
+    /// Verify a proof of the given value of the key made by
+    /// [`MerkleTree::get_existence_proof`], or a proof that the key doesn't
+    /// exist made by [`MerkleTree::get_non_existence_proof`] if the value is
+    /// `None`, against the root of a tree.
+    pub fn verify_proof(
+        key: &Key,
+        value: Option<&[u8]>,
+        proof: &Proof,
+        root: &[u8],
+    ) -> Result<()> {
+        let (store_type, sub_key) = StoreType::sub_key(key)?;
+        let (sub_op, root_ops) = proof
+            .ops
+            .split_first()
+            .ok_or_else(|| Error::InvalidProof("The proof is empty".into()))?;
+        let sub_proof = decode_proof_op(sub_op)?;
+        // The keys of the subtree roots that are proven after the sub proof
+        let mut root_keys = vec![];
+        let mut sub_root = match (value, &sub_proof.proof) {
+            (Some(value), Some(Ics23Proof::Exist(ep))) => {
+                let sub_root = existence_root(ep)?;
+                if !ics23::verify_membership(
+                    &sub_proof,
+                    &Self::sub_tree_spec(),
+                    &sub_root,
+                    sub_key.to_string().as_bytes(),
+                    value,
+                ) {
+                    return Err(Error::InvalidProof(format!(
+                        "The value of the key {} is not proven",
+                        key
+                    )));
+                }
+                if store_type == StoreType::Account {
+                    root_keys.push(account_subtree_key(&sub_key)?);
+                }
+                sub_root
+            }
+            (None, Some(Ics23Proof::Nonexist(nep))) => {
+                // When the account subtree doesn't exist, the proof is of the
+                // absence of its root in the account tree
+                let spec = if nep.key == sub_key.to_string().as_bytes() {
+                    if store_type == StoreType::Account {
+                        root_keys.push(account_subtree_key(&sub_key)?);
+                    }
+                    Self::sub_tree_spec()
+                } else if store_type == StoreType::Account
+                    && nep.key == account_subtree_key(&sub_key)?.as_bytes()
+                {
+                    Self::base_tree_spec()
+                } else {
+                    return Err(Error::InvalidProof(format!(
+                        "The non-existence proof is not for the key {}",
+                        key
+                    )));
+                };
+                let neighbor = nep
+                    .left
+                    .as_ref()
+                    .or(nep.right.as_ref())
+                    .ok_or_else(|| {
+                        Error::InvalidProof(
+                            "The non-existence proof has no neighbor".into(),
+                        )
+                    })?;
+                let sub_root = existence_root(neighbor)?;
+                if !ics23::verify_non_membership(
+                    &sub_proof, &spec, &sub_root, &nep.key,
+                ) {
+                    return Err(Error::InvalidProof(format!(
+                        "The absence of the key {} is not proven",
+                        key
+                    )));
+                }
+                sub_root
+            }
+            _ => {
+                return Err(Error::InvalidProof(
+                    "The kind of the sub proof doesn't match the value".into(),
+                ));
+            }
         };
-        vec![sub_tree_spec, base_tree_spec]
+        root_keys.push(store_type.to_string());
+        if root_ops.len() != root_keys.len() {
+            return Err(Error::InvalidProof(format!(
+                "Expected {} proofs of the subtree roots, got {}",
+                root_keys.len(),
+                root_ops.len()
+            )));
+        }
+        for (op, root_key) in root_ops.iter().zip(root_keys) {
+            let root_proof = decode_proof_op(op)?;
+            let ep = match &root_proof.proof {
+                Some(Ics23Proof::Exist(ep)) => ep,
+                _ => {
+                    return Err(Error::InvalidProof(format!(
+                        "The proof of the subtree root {} is not an \
+                         existence proof",
+                        root_key
+                    )));
+                }
+            };
+            let tree_root = existence_root(ep)?;
+            if !ics23::verify_membership(
+                &root_proof,
+                &Self::base_tree_spec(),
+                &tree_root,
+                root_key.as_bytes(),
+                &sub_root,
+            ) {
+                return Err(Error::InvalidProof(format!(
+                    "The subtree root {} is not proven",
+                    root_key
+                )));
+            }
+            sub_root = tree_root;
+        }
+        if sub_root != root {
+            return Err(Error::InvalidProof(
+                "The proof is not for the given root".into(),
+            ));
+        }
+        Ok(())
     }
 
-    /// Get the leaf spec for the base tree. The key is stored after hashing,
-    /// but the stored value is the subtree's root without hashing.
-    fn base_leaf_spec(&self) -> LeafOp {
+    /// Get the proof spec of the sub proof
+    fn sub_tree_spec() -> ProofSpec {
+        ProofSpec {
+            leaf_spec: Some(Self::leaf_spec()),
+            ..sparse_merkle_tree::proof_ics23::get_spec(H::hash_op())
+        }
+    }
+
+    /// Get the proof spec of the proofs of the subtree roots
+    fn base_tree_spec() -> ProofSpec {
+        ProofSpec {
+            leaf_spec: Some(Self::base_leaf_spec()),
+            ..sparse_merkle_tree::proof_ics23::get_spec(H::hash_op())
+        }
+    }
+
+    /// Get the leaf spec for the base tree and the account tree. The key is
+    /// stored after hashing, but the stored value is the subtree's root
+    /// without hashing.
+    fn base_leaf_spec() -> LeafOp {
         LeafOp {
             hash: H::hash_op().into(),
             prehash_key: H::hash_op().into(),
@@ -366,7 +599,7 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
     /// Get the leaf spec for the subtree. Non-hashed values are used for the
     /// verification with this spec because a subtree stores the key-value pairs
     /// after hashing.
-    fn leaf_spec(&self) -> LeafOp {
+    fn leaf_spec() -> LeafOp {
         LeafOp {
             hash: H::hash_op().into(),
             prehash_key: H::hash_op().into(),
@@ -377,7 +610,21 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
     }
 }
 
+/// Decode the commitment proof of a proof op
+fn decode_proof_op(op: &ProofOp) -> Result<CommitmentProof> {
+    CommitmentProof::decode(&*op.data).map_err(|err| {
+        Error::InvalidProof(format!("Error decoding a proof op: {}", err))
+    })
+}
+
+/// Calculate the root of a tree from an existence proof
+fn existence_root(proof: &ExistenceProof) -> Result<Vec<u8>> {
+    ics23::calculate_existence_root(proof)
+        .map_err(|err| Error::InvalidProof(err.to_string()))
+}
+
 /// The root hash of the merkle tree as bytes
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct MerkleRoot(pub Vec<u8>);
 
 impl From<H256> for MerkleRoot {
@@ -393,12 +640,13 @@ impl fmt::Display for MerkleRoot {
 }
 
 /// The root and store pairs to restore the trees
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct MerkleTreeStoresRead {
     base: (H256, DefaultStore<H256>),
     account: (H256, DefaultStore<H256>),
     ibc: (H256, DefaultStore<H256>),
     pos: (H256, DefaultStore<H256>),
+    accounts: AccountSubtreeStores,
 }
 
 impl MerkleTreeStoresRead {
@@ -425,6 +673,11 @@ impl MerkleTreeStoresRead {
             StoreType::PoS => self.pos.1 = store,
         }
     }
+
+    /// Set the roots and stores of the per-account subtrees
+    pub fn set_account_subtrees(&mut self, accounts: AccountSubtreeStores) {
+        self.accounts = accounts;
+    }
 }
 
 /// The root and store pairs to be persistent
@@ -433,6 +686,7 @@ pub struct MerkleTreeStoresWrite<'a> {
     account: (&'a H256, &'a DefaultStore<H256>),
     ibc: (&'a H256, &'a DefaultStore<H256>),
     pos: (&'a H256, &'a DefaultStore<H256>),
+    accounts: BTreeMap<&'a str, (&'a H256, &'a DefaultStore<H256>)>,
 }
 
 impl<'a> MerkleTreeStoresWrite<'a> {
@@ -455,6 +709,13 @@ impl<'a> MerkleTreeStoresWrite<'a> {
             StoreType::PoS => self.pos.1,
         }
     }
+
+    /// Get the roots and stores of the per-account subtrees
+    pub fn account_subtrees(
+        &self,
+    ) -> &BTreeMap<&'a str, (&'a H256, &'a DefaultStore<H256>)> {
+        &self.accounts
+    }
 }
 
 /// The storage hasher used for the merkle tree.
@@ -515,7 +776,6 @@ impl From<SmtError> for Error {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::types::storage::KeySeg;
 
     #[test]
     fn test_crud_value() {
@@ -533,6 +793,8 @@ mod test {
         // update IBC tree
         tree.update(&ibc_key, [1u8; 8]).unwrap();
         assert!(tree.has_key(&ibc_key).unwrap());
+        assert!(tree.has_value(&ibc_key, [1u8; 8]).unwrap());
+        assert!(!tree.has_value(&ibc_key, [2u8; 8]).unwrap());
         assert!(!tree.has_key(&pos_key).unwrap());
         // update another tree
         tree.update(&pos_key, [2u8; 8]).unwrap();
@@ -618,4 +880,220 @@ mod test {
         // Check the base root
         assert_eq!(sub_root, tree.root().0);
     }
+
+    #[test]
+    fn test_account_subtrees() {
+        let mut tree = MerkleTree::<Sha256Hasher>::default();
+
+        let addr1 = crate::types::address::testing::established_address_1();
+        let addr2 = crate::types::address::testing::established_address_2();
+        let key1 = Key::from(addr1.to_db_key())
+            .push(&"test1".to_string())
+            .unwrap();
+        let key2 = Key::from(addr1.to_db_key())
+            .push(&"test2".to_string())
+            .unwrap();
+        let key3 = Key::from(addr2.to_db_key())
+            .push(&"test".to_string())
+            .unwrap();
+
+        let val1 = [1u8; 8].to_vec();
+        tree.update(&key1, val1.clone()).unwrap();
+        tree.update(&key2, [2u8; 8]).unwrap();
+        assert_eq!(tree.accounts.len(), 1);
+
+        // An update of another account doesn't change the first account's
+        // subtree
+        let account_root_before =
+            *tree.accounts[&addr1.to_db_key().raw()].root();
+        tree.update(&key3, [3u8; 8]).unwrap();
+        assert_eq!(tree.accounts.len(), 2);
+        assert_eq!(
+            *tree.accounts[&addr1.to_db_key().raw()].root(),
+            account_root_before
+        );
+
+        // An empty account subtree is removed
+        tree.delete(&key3).unwrap();
+        assert!(!tree.has_key(&key3).unwrap());
+        assert_eq!(tree.accounts.len(), 1);
+
+        // Restore the tree with the account subtrees
+        let stores_write = tree.stores();
+        let mut stores_read = MerkleTreeStoresRead::default();
+        for st in StoreType::iter() {
+            stores_read.set_root(st, *stores_write.root(st));
+            stores_read.set_store(st, stores_write.store(st).clone());
+        }
+        stores_read.set_account_subtrees(
+            stores_write
+                .account_subtrees()
+                .iter()
+                .map(|(key, (root, store))| {
+                    (key.to_string(), (**root, (*store).clone()))
+                })
+                .collect(),
+        );
+        let restored_tree = MerkleTree::<Sha256Hasher>::new(stores_read);
+        assert!(restored_tree.has_key(&key1).unwrap());
+        assert!(restored_tree.has_key(&key2).unwrap());
+        assert!(!restored_tree.has_key(&key3).unwrap());
+        assert_eq!(restored_tree.root().0, tree.root().0);
+
+        // The proof consists of the account subtree's proof, the account
+        // tree's proof and the base tree's proof
+        let specs = tree.proof_specs();
+        let proof = tree.get_existence_proof(&key1, val1.clone()).unwrap();
+        assert_eq!(proof.ops.len(), 3);
+        let paths = vec![
+            key1.to_string(),
+            addr1.to_db_key().raw(),
+            StoreType::Account.to_string(),
+        ];
+        let mut sub_root = val1.clone();
+        let mut value = val1;
+        for (i, (p, key)) in proof.ops.iter().zip(paths.iter()).enumerate() {
+            let spec = &specs[i.min(1)];
+            let commitment_proof = CommitmentProof::decode(&*p.data).unwrap();
+            let existence_proof = match commitment_proof.clone().proof.unwrap()
+            {
+                Ics23Proof::Exist(ep) => ep,
+                _ => unreachable!(),
+            };
+            sub_root =
+                ics23::calculate_existence_root(&existence_proof).unwrap();
+            assert!(ics23::verify_membership(
+                &commitment_proof,
+                spec,
+                &sub_root,
+                key.as_bytes(),
+                &value,
+            ));
+            value = sub_root.clone();
+        }
+        assert_eq!(sub_root, tree.root().0);
+
+        // The non-existence proof of a key in a non-existent account subtree
+        // doesn't include the account tree's proof
+        let proof = tree.get_non_existence_proof(&key3).unwrap();
+        assert_eq!(proof.ops.len(), 2);
+    }
+
+    /// Test that the existence proofs of the values in the IBC tree and in
+    /// an account subtree are verified against the root of the tree, and
+    /// that a proof of another value or root is rejected.
+    #[test]
+    fn test_verify_proof() {
+        let mut tree = MerkleTree::<Sha256Hasher>::default();
+        let key_prefix: Key =
+            Address::Internal(InternalAddress::Ibc).to_db_key().into();
+        let ibc_key = key_prefix.push(&"test".to_string()).unwrap();
+        let addr = crate::types::address::testing::established_address_1();
+        let account_key = Key::from(addr.to_db_key())
+            .push(&"test".to_string())
+            .unwrap();
+        let ibc_val = [1u8; 8].to_vec();
+        let account_val = [2u8; 8].to_vec();
+        tree.update(&ibc_key, ibc_val.clone()).unwrap();
+        tree.update(&account_key, account_val.clone()).unwrap();
+        let root = tree.root().0;
+
+        for (key, value) in [(&ibc_key, &ibc_val), (&account_key, &account_val)]
+        {
+            let proof = tree.get_existence_proof(key, value.clone()).unwrap();
+            MerkleTree::<Sha256Hasher>::verify_proof(
+                key,
+                Some(value),
+                &proof,
+                &root,
+            )
+            .unwrap();
+            assert!(matches!(
+                MerkleTree::<Sha256Hasher>::verify_proof(
+                    key,
+                    Some(&[3u8; 8]),
+                    &proof,
+                    &root,
+                ),
+                Err(Error::InvalidProof(_))
+            ));
+            assert!(matches!(
+                MerkleTree::<Sha256Hasher>::verify_proof(
+                    key,
+                    Some(value),
+                    &proof,
+                    &[0u8; 32],
+                ),
+                Err(Error::InvalidProof(_))
+            ));
+        }
+        // The proof of a value is not a proof that the key doesn't exist
+        let proof = tree.get_existence_proof(&ibc_key, ibc_val).unwrap();
+        assert!(matches!(
+            MerkleTree::<Sha256Hasher>::verify_proof(
+                &ibc_key, None, &proof, &root,
+            ),
+            Err(Error::InvalidProof(_))
+        ));
+    }
+
+    /// Test that the non-existence proofs of the keys missing from the IBC
+    /// tree, from an account subtree and from an account without a subtree
+    /// are verified against the root of the tree, and that they're rejected
+    /// for another key, for a value or for another root.
+    #[test]
+    fn test_verify_non_existence_proof() {
+        let mut tree = MerkleTree::<Sha256Hasher>::default();
+        let key_prefix: Key =
+            Address::Internal(InternalAddress::Ibc).to_db_key().into();
+        let ibc_key = key_prefix.push(&"test".to_string()).unwrap();
+        let addr = crate::types::address::testing::established_address_1();
+        let account_key = Key::from(addr.to_db_key())
+            .push(&"test".to_string())
+            .unwrap();
+        tree.update(&ibc_key, [1u8; 8].to_vec()).unwrap();
+        tree.update(&account_key, [2u8; 8].to_vec()).unwrap();
+        let root = tree.root().0;
+
+        let missing_ibc_key = key_prefix.push(&"missing".to_string()).unwrap();
+        let missing_account_key = Key::from(addr.to_db_key())
+            .push(&"missing".to_string())
+            .unwrap();
+        let other_addr =
+            crate::types::address::testing::established_address_2();
+        let missing_subtree_key = Key::from(other_addr.to_db_key())
+            .push(&"test".to_string())
+            .unwrap();
+        for key in
+            [&missing_ibc_key, &missing_account_key, &missing_subtree_key]
+        {
+            let proof = tree.get_non_existence_proof(key).unwrap();
+            MerkleTree::<Sha256Hasher>::verify_proof(key, None, &proof, &root)
+                .unwrap();
+            assert!(matches!(
+                MerkleTree::<Sha256Hasher>::verify_proof(
+                    key,
+                    Some(&[1u8; 8]),
+                    &proof,
+                    &root,
+                ),
+                Err(Error::InvalidProof(_))
+            ));
+            assert!(matches!(
+                MerkleTree::<Sha256Hasher>::verify_proof(
+                    key, None, &proof, &[0u8; 32],
+                ),
+                Err(Error::InvalidProof(_))
+            ));
+        }
+        // The proof of a missing key is not a proof that another key doesn't
+        // exist
+        let proof = tree.get_non_existence_proof(&missing_ibc_key).unwrap();
+        assert!(matches!(
+            MerkleTree::<Sha256Hasher>::verify_proof(
+                &ibc_key, None, &proof, &root,
+            ),
+            Err(Error::InvalidProof(_))
+        ));
+    }
 }
@@ -21,7 +21,7 @@ use crate::types::storage::{
 use crate::types::time::DateTimeUtc;
 
 /// An in-memory DB for testing.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct MockDB(
     // The state is wrapped in `RefCell` to allow modifying it directly from
     // batch write method (which requires immutable self ref).
@@ -110,13 +110,22 @@ impl DB for MockDB {
                                     types::decode(bytes)
                                         .map_err(Error::CodingError)?,
                                 ),
+                                Some(&"subtrees")
+                                    if st == StoreType::Account =>
+                                {
+                                    merkle_tree_stores.set_account_subtrees(
+                                        types::decode(bytes)
+                                            .map_err(Error::CodingError)?,
+                                    )
+                                }
                                 _ => unknown_key_error(path)?,
                             }
                         }
                         None => unknown_key_error(path)?,
                     },
-                    "header" => {
-                        // the block header doesn't have to be restored
+                    "header" | "request" | "gas" | "diffs" => {
+                        // the block header, request, gas and diffs don't have
+                        // to be restored
                     }
                     "hash" => {
                         hash = Some(
@@ -166,9 +175,20 @@ impl DB for MockDB {
     }
 
     fn write_block(&mut self, state: BlockStateWrite) -> Result<()> {
+        self.batch_write_block(&mut Self::batch(), state)
+    }
+
+    fn batch_write_block(
+        &self,
+        _batch: &mut Self::WriteBatch,
+        state: BlockStateWrite,
+    ) -> Result<()> {
+        // The block is written directly, as for the other batch writes
         let BlockStateWrite {
             merkle_tree_stores,
             header,
+            block_request,
+            block_gas,
             hash,
             height,
             epoch,
@@ -221,6 +241,14 @@ impl DB for MockDB {
                     types::encode(merkle_tree_stores.store(st)),
                 );
             }
+            let subtrees_key = prefix_key
+                .push(&StoreType::Account.to_string())
+                .and_then(|key| key.push(&"subtrees".to_owned()))
+                .map_err(Error::KeyError)?;
+            self.0.borrow_mut().insert(
+                subtrees_key.to_string(),
+                types::encode(merkle_tree_stores.account_subtrees()),
+            );
         }
         // Block header
         {
@@ -234,6 +262,26 @@ impl DB for MockDB {
                 );
             }
         }
+        // Block request
+        {
+            if let Some(request) = block_request {
+                let key = prefix_key
+                    .push(&"request".to_owned())
+                    .map_err(Error::KeyError)?;
+                self.0
+                    .borrow_mut()
+                    .insert(key.to_string(), request.to_vec());
+            }
+        }
+        // Block gas
+        {
+            let key = prefix_key
+                .push(&"gas".to_owned())
+                .map_err(Error::KeyError)?;
+            self.0
+                .borrow_mut()
+                .insert(key.to_string(), types::encode(&block_gas));
+        }
         // Block hash
         {
             let key = prefix_key
@@ -292,6 +340,28 @@ impl DB for MockDB {
         }
     }
 
+    fn read_block_request(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<Vec<u8>>> {
+        let key = Key::from(height.to_db_key())
+            .push(&"request".to_owned())
+            .map_err(Error::KeyError)?;
+        Ok(self.0.borrow().get(&key.to_string()).cloned())
+    }
+
+    fn read_block_gas(&self, height: BlockHeight) -> Result<Option<u64>> {
+        let key = Key::from(height.to_db_key())
+            .push(&"gas".to_owned())
+            .map_err(Error::KeyError)?;
+        match self.0.borrow().get(&key.to_string()) {
+            Some(bytes) => {
+                Ok(Some(types::decode(bytes).map_err(Error::CodingError)?))
+            }
+            None => Ok(None),
+        }
+    }
+
     fn read_merkle_tree_stores(
         &self,
         height: BlockHeight,
@@ -328,6 +398,18 @@ impl DB for MockDB {
                 None => return Ok(None),
             }
         }
+        let subtrees_key = tree_key
+            .push(&StoreType::Account.to_string())
+            .and_then(|key| key.push(&"subtrees".to_owned()))
+            .map_err(Error::KeyError)?;
+        let bytes = self.0.borrow().get(&subtrees_key.to_string()).cloned();
+        match bytes {
+            Some(b) => {
+                let subtrees = types::decode(b).map_err(Error::CodingError)?;
+                merkle_tree_stores.set_account_subtrees(subtrees);
+            }
+            None => return Ok(None),
+        }
         Ok(Some(merkle_tree_stores))
     }
 
@@ -338,44 +420,52 @@ impl DB for MockDB {
 
     fn read_subspace_val_with_height(
         &self,
-        _key: &Key,
-        _height: BlockHeight,
+        key: &Key,
+        height: BlockHeight,
+        last_height: BlockHeight,
     ) -> Result<Option<Vec<u8>>> {
-        // Mock DB can read only the latest value for now
-        unimplemented!()
+        // The first block above the height that changed the key has its
+        // value at the height as the old value of the diff, or no old value
+        // if the key didn't exist yet
+        for changed_height in (height.0 + 1)..=last_height.0 {
+            let key_prefix = Key::from(BlockHeight(changed_height).to_db_key())
+                .push(&"diffs".to_owned())
+                .map_err(Error::KeyError)?;
+            let old_val_key = key_prefix
+                .push(&"old".to_owned())
+                .map_err(Error::KeyError)?
+                .join(key);
+            if let Some(old_value) =
+                self.0.borrow().get(&old_val_key.to_string())
+            {
+                return Ok(Some(old_value.clone()));
+            }
+            let new_val_key = key_prefix
+                .push(&"new".to_owned())
+                .map_err(Error::KeyError)?
+                .join(key);
+            if self.0.borrow().contains_key(&new_val_key.to_string()) {
+                return Ok(None);
+            }
+        }
+        self.read_subspace_val(key)
     }
 
     fn write_subspace_val(
         &mut self,
-        _height: BlockHeight,
+        height: BlockHeight,
         key: &Key,
         value: impl AsRef<[u8]>,
     ) -> Result<i64> {
-        let value = value.as_ref();
-        let key = Key::parse("subspace").map_err(Error::KeyError)?.join(key);
-        let current_len = value.len() as i64;
-        Ok(
-            match self
-                .0
-                .borrow_mut()
-                .insert(key.to_string(), value.to_owned())
-            {
-                Some(prev_value) => current_len - prev_value.len() as i64,
-                None => current_len,
-            },
-        )
+        self.write_subspace_val_with_diff(height, key, value.as_ref())
     }
 
     fn delete_subspace_val(
         &mut self,
-        _height: BlockHeight,
+        height: BlockHeight,
         key: &Key,
     ) -> Result<i64> {
-        let key = Key::parse("subspace").map_err(Error::KeyError)?.join(key);
-        Ok(match self.0.borrow_mut().remove(&key.to_string()) {
-            Some(value) => value.len() as i64,
-            None => 0,
-        })
+        self.delete_subspace_val_with_diff(height, key)
     }
 
     fn batch() -> Self::WriteBatch {
@@ -391,37 +481,163 @@ impl DB for MockDB {
     fn batch_write_subspace_val(
         &self,
         _batch: &mut Self::WriteBatch,
-        _height: BlockHeight,
+        height: BlockHeight,
         key: &Key,
         value: impl AsRef<[u8]>,
     ) -> Result<i64> {
-        let value = value.as_ref();
-        let key = Key::parse("subspace").map_err(Error::KeyError)?.join(key);
-        let current_len = value.len() as i64;
-        Ok(
-            match self
-                .0
-                .borrow_mut()
-                .insert(key.to_string(), value.to_owned())
-            {
-                Some(prev_value) => current_len - prev_value.len() as i64,
-                None => current_len,
-            },
-        )
+        self.write_subspace_val_with_diff(height, key, value.as_ref())
     }
 
     fn batch_delete_subspace_val(
         &self,
         _batch: &mut Self::WriteBatch,
-        _height: BlockHeight,
+        height: BlockHeight,
         key: &Key,
     ) -> Result<i64> {
-        let key = Key::parse("subspace").map_err(Error::KeyError)?.join(key);
-        Ok(match self.0.borrow_mut().remove(&key.to_string()) {
-            Some(value) => value.len() as i64,
+        self.delete_subspace_val_with_diff(height, key)
+    }
+
+    fn prune_block(
+        &mut self,
+        height: BlockHeight,
+        prune_diffs: bool,
+    ) -> Result<()> {
+        let prefix = format!("{}/", height);
+        let diffs_prefix = format!("{}/diffs/", height);
+        self.0.borrow_mut().retain(|key, _value| {
+            !key.starts_with(&prefix)
+                || (!prune_diffs && key.starts_with(&diffs_prefix))
+        });
+        Ok(())
+    }
+
+    fn read_schema_version(&self) -> Result<Option<u64>> {
+        match self.0.borrow().get("schema_version") {
+            Some(bytes) => {
+                Ok(Some(types::decode(bytes).map_err(Error::CodingError)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn write_schema_version(&mut self, version: u64) -> Result<()> {
+        self.0
+            .borrow_mut()
+            .insert("schema_version".to_owned(), types::encode(&version));
+        Ok(())
+    }
+}
+
+impl MockDB {
+    /// Iterate the account subspace key-vals with the keys from `start`
+    /// (inclusive) up to `end` (exclusive)
+    fn iter_subspace_range(
+        &self,
+        start: &Key,
+        end: &Key,
+        reverse: bool,
+    ) -> MockPrefixIterator {
+        let db_prefix = "subspace/".to_owned();
+        let start = format!("{}{}", db_prefix, start);
+        let end = format!("{}{}", db_prefix, end);
+        let iter = self
+            .0
+            .borrow()
+            .iter()
+            .filter(|(key, _val)| **key >= start && **key < end)
+            .map(|(key, val)| (key.clone(), val.clone()))
+            .collect::<BTreeMap<_, _>>()
+            .into_iter();
+        MockPrefixIterator::new(
+            MockIterator {
+                prefix: db_prefix.clone(),
+                iter,
+                reverse,
+            },
+            db_prefix,
+        )
+    }
+
+    /// Write the value of an account subspace key and its diff under the
+    /// height where it was changed. Returns the size difference from the
+    /// previous value, if any, or the size of the value otherwise.
+    fn write_subspace_val_with_diff(
+        &self,
+        height: BlockHeight,
+        key: &Key,
+        value: &[u8],
+    ) -> Result<i64> {
+        let subspace_key =
+            Key::parse("subspace").map_err(Error::KeyError)?.join(key);
+        let current_len = value.len() as i64;
+        let prev_value = self
+            .0
+            .borrow_mut()
+            .insert(subspace_key.to_string(), value.to_owned());
+        self.write_subspace_diff(
+            height,
+            key,
+            prev_value.as_deref(),
+            Some(value),
+        )?;
+        Ok(match prev_value {
+            Some(prev_value) => current_len - prev_value.len() as i64,
+            None => current_len,
+        })
+    }
+
+    /// Delete the value of an account subspace key and write its diff under
+    /// the height where it was deleted. Returns the size of the removed
+    /// value, if any, 0 if no previous value was found.
+    fn delete_subspace_val_with_diff(
+        &self,
+        height: BlockHeight,
+        key: &Key,
+    ) -> Result<i64> {
+        let subspace_key =
+            Key::parse("subspace").map_err(Error::KeyError)?.join(key);
+        let prev_value = self.0.borrow_mut().remove(&subspace_key.to_string());
+        Ok(match prev_value {
+            Some(prev_value) => {
+                self.write_subspace_diff(height, key, Some(&prev_value), None)?;
+                prev_value.len() as i64
+            }
             None => 0,
         })
     }
+
+    /// Persist the diff of an account subspace key-val under the height where
+    /// it was changed.
+    fn write_subspace_diff(
+        &self,
+        height: BlockHeight,
+        key: &Key,
+        old_value: Option<&[u8]>,
+        new_value: Option<&[u8]>,
+    ) -> Result<()> {
+        let key_prefix = Key::from(height.to_db_key())
+            .push(&"diffs".to_owned())
+            .map_err(Error::KeyError)?;
+        if let Some(old_value) = old_value {
+            let old_val_key = key_prefix
+                .push(&"old".to_owned())
+                .map_err(Error::KeyError)?
+                .join(key);
+            self.0
+                .borrow_mut()
+                .insert(old_val_key.to_string(), old_value.to_owned());
+        }
+        if let Some(new_value) = new_value {
+            let new_val_key = key_prefix
+                .push(&"new".to_owned())
+                .map_err(Error::KeyError)?
+                .join(key);
+            self.0
+                .borrow_mut()
+                .insert(new_val_key.to_string(), new_value.to_owned());
+        }
+        Ok(())
+    }
 }
 
 impl<'iter> DBIter<'iter> for MockDB {
@@ -431,7 +647,101 @@ impl<'iter> DBIter<'iter> for MockDB {
         let db_prefix = "subspace/".to_owned();
         let prefix = format!("{}{}", db_prefix, prefix);
         let iter = self.0.borrow().clone().into_iter();
-        MockPrefixIterator::new(MockIterator { prefix, iter }, db_prefix)
+        MockPrefixIterator::new(
+            MockIterator {
+                prefix,
+                iter,
+                reverse: false,
+            },
+            db_prefix,
+        )
+    }
+
+    fn rev_iter_prefix(&'iter self, prefix: &Key) -> MockPrefixIterator {
+        let db_prefix = "subspace/".to_owned();
+        let prefix = format!("{}{}", db_prefix, prefix);
+        let iter = self.0.borrow().clone().into_iter();
+        MockPrefixIterator::new(
+            MockIterator {
+                prefix,
+                iter,
+                reverse: true,
+            },
+            db_prefix,
+        )
+    }
+
+    fn iter_prefix_from(
+        &'iter self,
+        prefix: &Key,
+        start: &Key,
+    ) -> MockPrefixIterator {
+        let db_prefix = "subspace/".to_owned();
+        let prefix = format!("{}{}", db_prefix, prefix);
+        let start = format!("{}{}", db_prefix, start);
+        let iter = self
+            .0
+            .borrow()
+            .range(start..)
+            .map(|(key, val)| (key.clone(), val.clone()))
+            .collect::<BTreeMap<_, _>>()
+            .into_iter();
+        MockPrefixIterator::new(
+            MockIterator {
+                prefix,
+                iter,
+                reverse: false,
+            },
+            db_prefix,
+        )
+    }
+
+    fn iter_range(&'iter self, start: &Key, end: &Key) -> MockPrefixIterator {
+        self.iter_subspace_range(start, end, false)
+    }
+
+    fn rev_iter_range(
+        &'iter self,
+        start: &Key,
+        end: &Key,
+    ) -> MockPrefixIterator {
+        self.iter_subspace_range(start, end, true)
+    }
+
+    fn iter_old_diffs(
+        &'iter self,
+        height: BlockHeight,
+        prefix: &Key,
+    ) -> MockPrefixIterator {
+        let db_prefix = format!("{}/diffs/old/", height);
+        let prefix = format!("{}{}", db_prefix, prefix);
+        let iter = self.0.borrow().clone().into_iter();
+        MockPrefixIterator::new(
+            MockIterator {
+                prefix,
+                iter,
+                reverse: false,
+            },
+            db_prefix,
+        )
+    }
+
+    fn iter_new_diffs(
+        &'iter self,
+        height: BlockHeight,
+        prefix: &Key,
+    ) -> MockPrefixIterator {
+        let db_prefix = format!("{}/diffs/new/", height);
+        let prefix = format!("{}{}", db_prefix, prefix);
+        let iter = self.0.borrow().clone().into_iter();
+        MockPrefixIterator::new(
+            MockIterator {
+                prefix,
+                iter,
+                reverse: false,
+            },
+            db_prefix,
+        )
     }
 }
 
@@ -441,6 +751,8 @@ pub struct MockIterator {
     prefix: String,
     /// The concrete iterator
     pub iter: btree_map::IntoIter<String, Vec<u8>>,
+    /// Whether the keys are iterated in the reverse order
+    reverse: bool,
 }
 
 /// A prefix iterator for the [`MockDB`].
@@ -450,7 +762,12 @@ impl Iterator for MockIterator {
     type Item = KVBytes;
 
     fn next(&mut self) -> Option<Self::Item> {
-        for (key, val) in &mut self.iter {
+        loop {
+            let (key, val) = if self.reverse {
+                self.iter.next_back()?
+            } else {
+                self.iter.next()?
+            };
             if key.starts_with(&self.prefix) {
                 return Some((
                     Box::from(key.as_bytes()),
@@ -458,7 +775,6 @@ impl Iterator for MockIterator {
                 ));
             }
         }
-        None
     }
 }
 
@@ -3,28 +3,28 @@
 mod merkle_tree;
 #[cfg(any(test, feature = "testing"))]
 pub mod mockdb;
+pub mod read_cache;
 pub mod types;
 pub mod write_log;
 
 use core::fmt::Debug;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::iter::Peekable;
 
-#[cfg(not(feature = "ABCI"))]
-use tendermint::merkle::proof::Proof;
-#[cfg(feature = "ABCI")]
-use tendermint_stable::merkle::proof::Proof;
 use thiserror::Error;
 
 use super::parameters;
 use super::parameters::Parameters;
 use crate::ledger::gas::MIN_STORAGE_GAS;
 use crate::ledger::parameters::EpochDuration;
-use crate::ledger::storage::merkle_tree::{
-    Error as MerkleTreeError, MerkleRoot,
-};
+use crate::ledger::storage::merkle_tree::Error as MerkleTreeError;
 pub use crate::ledger::storage::merkle_tree::{
-    MerkleTree, MerkleTreeStoresRead, MerkleTreeStoresWrite, Sha256Hasher,
-    StorageHasher, StoreType,
+    AccountSubtreeStores, MerkleRoot, MerkleTree, MerkleTreeStoresRead,
+    MerkleTreeStoresWrite, ProofSpec, Sha256Hasher, StorageHasher, StoreType,
 };
+use crate::ledger::storage::read_cache::ReadCache;
+use crate::tendermint::merkle::proof::Proof;
 use crate::types::address::{Address, EstablishedAddressGen, InternalAddress};
 use crate::types::chain::{ChainId, CHAIN_ID_LENGTH};
 #[cfg(feature = "ferveo-tpke")]
@@ -53,6 +53,12 @@ where
     pub block: BlockStorage<H>,
     /// The latest block header
     pub header: Option<Header>,
+    /// The encoded request from which the current block is being finalized.
+    /// It's persisted with the block so that the block can be re-executed.
+    pub block_request: Option<Vec<u8>>,
+    /// The gas used by the txs of the current block. It's persisted with the
+    /// block.
+    pub block_gas: u64,
     /// The height of the committed block
     pub last_height: BlockHeight,
     /// The epoch of the committed block
@@ -66,6 +72,12 @@ where
     /// Wrapper txs to be decrypted in the next block proposal
     #[cfg(feature = "ferveo-tpke")]
     pub tx_queue: TxQueue,
+    /// The write-back cache of the account subspace for the current block.
+    /// A `None` value is a deleted key. The cache is flushed into the DB on
+    /// [`Storage::commit`].
+    write_cache: BTreeMap<Key, Option<Vec<u8>>>,
+    /// The cache of the account subspace values read from the DB, if enabled
+    pub read_cache: Option<ReadCache>,
 }
 
 /// The block storage data
@@ -133,6 +145,10 @@ pub struct BlockStateWrite<'a> {
     pub merkle_tree_stores: MerkleTreeStoresWrite<'a>,
     /// Header of the block
     pub header: Option<&'a Header>,
+    /// The encoded request from which the block was finalized
+    pub block_request: Option<&'a [u8]>,
+    /// The gas used by the txs of the block
+    pub block_gas: u64,
     /// Hash of the block
     pub hash: &'a BlockHash,
     /// Height of the block
@@ -174,9 +190,28 @@ pub trait DB: std::fmt::Debug {
     /// Write block's metadata
     fn write_block(&mut self, state: BlockStateWrite) -> Result<()>;
 
+    /// Batch write block's metadata, so that it's written atomically with the
+    /// other changes of the block in the batch
+    fn batch_write_block(
+        &self,
+        batch: &mut Self::WriteBatch,
+        state: BlockStateWrite,
+    ) -> Result<()>;
+
     /// Read the block header with the given height from the DB
     fn read_block_header(&self, height: BlockHeight) -> Result<Option<Header>>;
 
+    /// Read the encoded request of the block with the given height from the
+    /// DB
+    fn read_block_request(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<Vec<u8>>>;
+
+    /// Read the gas used by the txs of the block with the given height from
+    /// the DB
+    fn read_block_gas(&self, height: BlockHeight) -> Result<Option<u64>>;
+
     /// Read the merkle tree stores with the given height
     fn read_merkle_tree_stores(
         &self,
@@ -186,11 +221,14 @@ pub trait DB: std::fmt::Debug {
     /// Read the latest value for account subspace key from the DB
     fn read_subspace_val(&self, key: &Key) -> Result<Option<Vec<u8>>>;
 
-    /// Read the value for account subspace key at the given height from the DB
+    /// Read the value for account subspace key at the given height from the
+    /// DB. The value is found from the diffs of the blocks above the height up
+    /// to the `last_height` of the last block committed to the DB.
     fn read_subspace_val_with_height(
         &self,
         key: &Key,
-        _height: BlockHeight,
+        height: BlockHeight,
+        last_height: BlockHeight,
     ) -> Result<Option<Vec<u8>>>;
 
     /// Write the value with the given height and account subspace key to the
@@ -238,6 +276,22 @@ pub trait DB: std::fmt::Debug {
         height: BlockHeight,
         key: &Key,
     ) -> Result<i64>;
+
+    /// Remove the data of the block at the given height from the DB, so that
+    /// its state can no longer be read. The diffs of the block are only
+    /// removed with `prune_diffs`, as they're needed to read the state of the
+    /// blocks below it.
+    fn prune_block(
+        &mut self,
+        height: BlockHeight,
+        prune_diffs: bool,
+    ) -> Result<()>;
+
+    /// Read the version of the layout of the DB, if it's stored
+    fn read_schema_version(&self) -> Result<Option<u64>>;
+
+    /// Write the version of the layout of the DB
+    fn write_schema_version(&mut self, version: u64) -> Result<()>;
 }
 
 /// A database prefix iterator.
@@ -247,6 +301,44 @@ pub trait DBIter<'iter> {
 
     /// Read account subspace key value pairs with the given prefix from the DB
     fn iter_prefix(&'iter self, prefix: &Key) -> Self::PrefixIter;
+
+    /// Read account subspace key value pairs with the given prefix from the DB
+    /// in the reverse order of the keys
+    fn rev_iter_prefix(&'iter self, prefix: &Key) -> Self::PrefixIter;
+
+    /// Read account subspace key value pairs with the given prefix from the DB
+    /// with the keys from `start` (inclusive), so that an iteration can be
+    /// resumed without iterating the keys before it again
+    fn iter_prefix_from(
+        &'iter self,
+        prefix: &Key,
+        start: &Key,
+    ) -> Self::PrefixIter;
+
+    /// Read account subspace key value pairs with the keys from `start`
+    /// (inclusive) up to `end` (exclusive) from the DB
+    fn iter_range(&'iter self, start: &Key, end: &Key) -> Self::PrefixIter;
+
+    /// Read account subspace key value pairs with the keys from `start`
+    /// (inclusive) up to `end` (exclusive) from the DB in the reverse order
+    /// of the keys
+    fn rev_iter_range(&'iter self, start: &Key, end: &Key) -> Self::PrefixIter;
+
+    /// Read the previous values of the account subspace keys with the given
+    /// prefix that were changed or deleted in the block at the given height
+    fn iter_old_diffs(
+        &'iter self,
+        height: BlockHeight,
+        prefix: &Key,
+    ) -> Self::PrefixIter;
+
+    /// Read the new values of the account subspace keys with the given prefix
+    /// that were written in the block at the given height
+    fn iter_new_diffs(
+        &'iter self,
+        height: BlockHeight,
+        prefix: &Key,
+    ) -> Self::PrefixIter;
 }
 
 /// Atomic batch write.
@@ -273,6 +365,12 @@ where
         chain_id: ChainId,
         cache: Option<&D::Cache>,
     ) -> Self {
+        Self::with_db(D::open(db_path, cache), chain_id)
+    }
+
+    /// Create a new instance of the storage given an already opened db and
+    /// chain id
+    pub fn with_db(db: D, chain_id: ChainId) -> Self {
         let block = BlockStorage {
             tree: MerkleTree::default(),
             hash: BlockHash::default(),
@@ -281,10 +379,12 @@ where
             pred_epochs: Epochs::default(),
         };
         Storage::<D, H> {
-            db: D::open(db_path, cache),
+            db,
             chain_id,
             block,
             header: None,
+            block_request: None,
+            block_gas: 0,
             last_height: BlockHeight(0),
             last_epoch: Epoch::default(),
             next_epoch_min_start_height: BlockHeight::default(),
@@ -294,6 +394,8 @@ where
             ),
             #[cfg(feature = "ferveo-tpke")]
             tx_queue: TxQueue::default(),
+            write_cache: BTreeMap::default(),
+            read_cache: None,
         }
     }
 
@@ -344,11 +446,17 @@ where
         }
     }
 
-    /// Persist the current block's state to the database
+    /// Persist the current block's state to the database. The changes of the
+    /// block are written in a single batch, so that a crash during the commit
+    /// cannot leave a partially written block in the DB.
     pub fn commit(&mut self) -> Result<()> {
+        let mut batch = D::batch();
+        self.batch_write_cache(&mut batch)?;
         let state = BlockStateWrite {
             merkle_tree_stores: self.block.tree.stores(),
             header: self.header.as_ref(),
+            block_request: self.block_request.as_deref(),
+            block_gas: self.block_gas,
             hash: &self.block.hash,
             height: self.block.height,
             epoch: self.block.epoch,
@@ -359,9 +467,14 @@ where
             #[cfg(feature = "ferveo-tpke")]
             tx_queue: &self.tx_queue,
         };
-        self.db.write_block(state)?;
+        self.db.batch_write_block(&mut batch, state)?;
+        self.db.exec_batch(batch)?;
+        // Flush without waiting
+        self.db.flush(false)?;
         self.last_height = self.block.height;
         self.header = None;
+        self.block_request = None;
+        self.block_gas = 0;
         Ok(())
     }
 
@@ -384,7 +497,7 @@ where
             return Ok((None, gas));
         }
 
-        match self.db.read_subspace_val(key)? {
+        match self.read_subspace_val(key)? {
             Some(v) => {
                 let gas = key.len() + v.len();
                 Ok((Some(v), gas as _))
@@ -403,7 +516,11 @@ where
         if height >= self.get_block_height().0 {
             self.read(key)
         } else {
-            match self.db.read_subspace_val_with_height(key, height)? {
+            match self.db.read_subspace_val_with_height(
+                key,
+                height,
+                self.last_height,
+            )? {
                 Some(v) => {
                     let gas = key.len() + v.len();
                     Ok((Some(v), gas as _))
@@ -413,12 +530,217 @@ where
         }
     }
 
-    /// Returns a prefix iterator and the gas cost
-    pub fn iter_prefix(
+    /// Returns the key-vals with the given prefix at the given height, ordered
+    /// by their keys, and the gas cost. The keys of a past height are the ones
+    /// that still exist and the ones deleted in the blocks above the height,
+    /// so the cost grows with the number of these blocks.
+    pub fn read_prefix_with_height(
+        &self,
+        prefix: &Key,
+        height: BlockHeight,
+    ) -> Result<(Vec<(Key, Vec<u8>)>, u64)> {
+        self.read_prefix_page_with_height(prefix, None, usize::MAX, height)
+    }
+
+    /// Returns at most `limit` key-vals with the given prefix at the given
+    /// height whose keys are after the `after` key, if any, ordered by their
+    /// keys, and the gas cost. The iteration starts from the `after` key, so
+    /// that reading a prefix page by page doesn't iterate the keys of the
+    /// previous pages again. See [`Storage::read_prefix_with_height`].
+    pub fn read_prefix_page_with_height(
+        &self,
+        prefix: &Key,
+        after: Option<&Key>,
+        limit: usize,
+        height: BlockHeight,
+    ) -> Result<(Vec<(Key, Vec<u8>)>, u64)> {
+        let mut gas = prefix.len() as u64;
+        let after_str = after.map(|after| after.to_string());
+        let is_after =
+            |key: &str| after_str.as_deref().map_or(true, |after| key > after);
+        if height >= self.get_block_height().0 {
+            let (iter, _gas) = match after {
+                Some(after) => self.iter_prefix_from(prefix, after),
+                None => self.iter_prefix(prefix),
+            };
+            let key_vals = iter
+                .filter(|(key, _value, _gas)| is_after(key))
+                .take(limit)
+                .map(|(key, value, key_val_gas)| {
+                    gas += key_val_gas;
+                    Ok((Key::parse(key).map_err(Error::KeyError)?, value))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            return Ok((key_vals, gas));
+        }
+        let iter = match after {
+            Some(after) => self.db.iter_prefix_from(prefix, after),
+            None => self.db.iter_prefix(prefix),
+        };
+        let mut keys: BTreeSet<String> = iter
+            .map(|(key, _value, _gas)| key)
+            .filter(|key| is_after(key))
+            .collect();
+        for changed_height in (height.0 + 1)..=self.last_height.0 {
+            keys.extend(
+                self.db
+                    .iter_old_diffs(BlockHeight(changed_height), prefix)
+                    .map(|(key, _value, _gas)| key)
+                    .filter(|key| is_after(key)),
+            );
+        }
+        let mut key_vals = vec![];
+        for key in keys {
+            if key_vals.len() >= limit {
+                break;
+            }
+            let key = Key::parse(key).map_err(Error::KeyError)?;
+            let (value, key_val_gas) = self.read_with_height(&key, height)?;
+            gas += key_val_gas;
+            if let Some(value) = value {
+                key_vals.push((key, value));
+            }
+        }
+        Ok((key_vals, gas))
+    }
+
+    /// Returns the keys with the given prefix, or any key without a prefix,
+    /// that were written or deleted in the committed blocks above the `from`
+    /// height up to the `to` height, ordered by their keys. The keys are
+    /// found from the diffs of these blocks, so a key that was changed and
+    /// then reverted to its previous value is included.
+    pub fn changed_keys(
+        &self,
+        prefix: Option<&Key>,
+        from: BlockHeight,
+        to: BlockHeight,
+    ) -> Result<BTreeSet<Key>> {
+        let any_key = Key { segments: vec![] };
+        let prefix = prefix.unwrap_or(&any_key);
+        let mut keys = BTreeSet::new();
+        for changed_height in (from.0 + 1)..=to.0.min(self.last_height.0) {
+            let height = BlockHeight(changed_height);
+            // A key without an old value was created in the block and one
+            // without a new value was deleted in it
+            let old_keys = self.db.iter_old_diffs(height, prefix);
+            let new_keys = self.db.iter_new_diffs(height, prefix);
+            for (key, _value, _gas) in old_keys.chain(new_keys) {
+                keys.insert(Key::parse(key).map_err(Error::KeyError)?);
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Check if the state of the given height is stored. The state of a past
+    /// block can only be read if the block is stored in the DB.
+    pub fn has_state_at_height(&self, height: BlockHeight) -> Result<bool> {
+        if height >= self.last_height {
+            return Ok(true);
+        }
+        // The gas is stored with every committed block
+        Ok(self.db.read_block_gas(height)?.is_some())
+    }
+
+    /// Returns a prefix iterator and the gas cost. The iterator includes the
+    /// values from the write cache that haven't been committed yet.
+    pub fn iter_prefix(&self, prefix: &Key) -> (PrefixIter<'_, D>, u64) {
+        let prefix_str = prefix.to_string();
+        let iter = self.with_write_cache(
+            self.db.iter_prefix(prefix),
+            |key| key.starts_with(&prefix_str),
+            false,
+        );
+        (iter, prefix.len() as _)
+    }
+
+    /// Returns a prefix iterator of the keys from `start` (inclusive) and the
+    /// gas cost. The iterator includes the values from the write cache that
+    /// haven't been committed yet.
+    pub fn iter_prefix_from(
         &self,
         prefix: &Key,
-    ) -> (<D as DBIter<'_>>::PrefixIter, u64) {
-        (self.db.iter_prefix(prefix), prefix.len() as _)
+        start: &Key,
+    ) -> (PrefixIter<'_, D>, u64) {
+        let (prefix_str, start_str) = (prefix.to_string(), start.to_string());
+        let iter = self.with_write_cache(
+            self.db.iter_prefix_from(prefix, start),
+            |key| key.starts_with(&prefix_str) && *key >= start_str,
+            false,
+        );
+        (iter, (prefix.len() + start.len()) as _)
+    }
+
+    /// Returns a prefix iterator in the reverse order of the keys and the gas
+    /// cost. The iterator includes the values from the write cache that
+    /// haven't been committed yet.
+    pub fn rev_iter_prefix(&self, prefix: &Key) -> (PrefixIter<'_, D>, u64) {
+        let prefix_str = prefix.to_string();
+        let iter = self.with_write_cache(
+            self.db.rev_iter_prefix(prefix),
+            |key| key.starts_with(&prefix_str),
+            true,
+        );
+        (iter, prefix.len() as _)
+    }
+
+    /// Returns an iterator of the keys from `start` (inclusive) up to `end`
+    /// (exclusive) and the gas cost. The iterator includes the values from
+    /// the write cache that haven't been committed yet.
+    pub fn iter_range(
+        &self,
+        start: &Key,
+        end: &Key,
+    ) -> (PrefixIter<'_, D>, u64) {
+        let (start_str, end_str) = (start.to_string(), end.to_string());
+        let iter = self.with_write_cache(
+            self.db.iter_range(start, end),
+            |key| *key >= start_str && *key < end_str,
+            false,
+        );
+        (iter, (start.len() + end.len()) as _)
+    }
+
+    /// Returns an iterator of the keys from `start` (inclusive) up to `end`
+    /// (exclusive) in the reverse order and the gas cost. The iterator
+    /// includes the values from the write cache that haven't been committed
+    /// yet.
+    pub fn rev_iter_range(
+        &self,
+        start: &Key,
+        end: &Key,
+    ) -> (PrefixIter<'_, D>, u64) {
+        let (start_str, end_str) = (start.to_string(), end.to_string());
+        let iter = self.with_write_cache(
+            self.db.rev_iter_range(start, end),
+            |key| *key >= start_str && *key < end_str,
+            true,
+        );
+        (iter, (start.len() + end.len()) as _)
+    }
+
+    /// Merge the iterator of the DB with the entries of the write cache whose
+    /// keys match the filter, in the same order
+    fn with_write_cache<'iter>(
+        &self,
+        db_iter: <D as DBIter<'iter>>::PrefixIter,
+        filter: impl Fn(&String) -> bool,
+        reverse: bool,
+    ) -> PrefixIter<'iter, D> {
+        let mut cached: Vec<(String, Option<Vec<u8>>)> = self
+            .write_cache
+            .iter()
+            .map(|(key, val)| (key.to_string(), val.clone()))
+            .filter(|(key, _val)| filter(key))
+            .collect();
+        cached.sort_by(|(a, _), (b, _)| a.cmp(b));
+        if reverse {
+            cached.reverse();
+        }
+        PrefixIter {
+            db_iter: db_iter.peekable(),
+            cache_iter: cached.into_iter().peekable(),
+            reverse,
+        }
     }
 
     /// Write a value to the specified subspace and returns the gas cost and the
@@ -433,8 +755,12 @@ where
 
         let len = value.as_ref().len();
         let gas = key.len() + len;
-        let size_diff =
-            self.db.write_subspace_val(self.last_height, key, value)?;
+        let size_diff = match self.read_subspace_val(key)? {
+            Some(prev_value) => len as i64 - prev_value.len() as i64,
+            None => len as i64,
+        };
+        self.write_cache
+            .insert(key.clone(), Some(value.as_ref().to_vec()));
         Ok((gas as _, size_diff))
     }
 
@@ -444,13 +770,72 @@ where
         let mut deleted_bytes_len = 0;
         if self.has_key(key)?.0 {
             self.block.tree.delete(key)?;
-            deleted_bytes_len =
-                self.db.delete_subspace_val(self.last_height, key)?;
+            deleted_bytes_len = self
+                .read_subspace_val(key)?
+                .map(|prev_value| prev_value.len() as i64)
+                .unwrap_or_default();
+            self.write_cache.insert(key.clone(), None);
         }
         let gas = key.len() + deleted_bytes_len as usize;
         Ok((gas as _, deleted_bytes_len))
     }
 
+    /// The changes of the account subspace in the current block, ordered by
+    /// their keys, which are written to the DB on [`Storage::commit`]. A
+    /// `None` value is a deleted key.
+    pub fn block_changes(
+        &self,
+    ) -> impl Iterator<Item = (&Key, Option<&Vec<u8>>)> {
+        self.write_cache
+            .iter()
+            .map(|(key, val)| (key, val.as_ref()))
+    }
+
+    /// Read the latest value for account subspace key, first from the write
+    /// cache and then from the DB.
+    fn read_subspace_val(&self, key: &Key) -> Result<Option<Vec<u8>>> {
+        if let Some(cached) = self.write_cache.get(key) {
+            return Ok(cached.clone());
+        }
+        let read_cache = match &self.read_cache {
+            Some(read_cache) => read_cache,
+            None => return self.db.read_subspace_val(key),
+        };
+        if let Some(cached) = read_cache.get(key) {
+            return Ok(cached);
+        }
+        let value = self.db.read_subspace_val(key)?;
+        read_cache.put(key.clone(), value.clone());
+        Ok(value)
+    }
+
+    /// Write all the cached changes of the current block into the batch
+    fn batch_write_cache(&mut self, batch: &mut D::WriteBatch) -> Result<()> {
+        for (key, val) in std::mem::take(&mut self.write_cache) {
+            if let Some(read_cache) = &self.read_cache {
+                read_cache.invalidate(&key);
+            }
+            match val {
+                Some(value) => {
+                    self.db.batch_write_subspace_val(
+                        batch,
+                        self.block.height,
+                        &key,
+                        value,
+                    )?;
+                }
+                None => {
+                    self.db.batch_delete_subspace_val(
+                        batch,
+                        self.block.height,
+                        &key,
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Set the block header.
     /// The header is not in the Merkle tree as it's tracked by Tendermint.
     /// Hence, we don't update the tree when this is set.
@@ -622,68 +1007,92 @@ where
 
     /// Update the merkle tree with epoch data
     fn update_epoch_in_merkle_tree(&mut self) -> Result<()> {
-        let key_prefix: Key =
-            Address::Internal(InternalAddress::PoS).to_db_key().into();
-
-        let key = key_prefix
-            .push(&"epoch_start_height".to_string())
-            .map_err(Error::KeyError)?;
-        self.block
-            .tree
-            .update(&key, types::encode(&self.next_epoch_min_start_height))?;
-
-        let key = key_prefix
-            .push(&"epoch_start_time".to_string())
-            .map_err(Error::KeyError)?;
-        self.block
-            .tree
-            .update(&key, types::encode(&self.next_epoch_min_start_time))?;
-
-        let key = key_prefix
-            .push(&"current_epoch".to_string())
-            .map_err(Error::KeyError)?;
-        self.block
-            .tree
-            .update(&key, types::encode(&self.block.epoch))?;
-        Ok(())
-    }
-
-    /// Start write batch.
-    fn batch() -> D::WriteBatch {
-        D::batch()
+        update_epoch_in_merkle_tree(
+            &mut self.block.tree,
+            self.block.epoch,
+            self.next_epoch_min_start_height,
+            self.next_epoch_min_start_time,
+        )
     }
+}
 
-    /// Execute write batch.
-    fn exec_batch(&mut self, batch: D::WriteBatch) -> Result<()> {
-        self.db.exec_batch(batch)
-    }
+/// Update the merkle tree with epoch data. Besides the account subspaces, this
+/// is the only data in the tree, so the root of a state can be computed from
+/// its key-vals and its epoch data, e.g. to verify an exported state.
+pub fn update_epoch_in_merkle_tree<H: StorageHasher>(
+    tree: &mut MerkleTree<H>,
+    epoch: Epoch,
+    next_epoch_min_start_height: BlockHeight,
+    next_epoch_min_start_time: DateTimeUtc,
+) -> Result<()> {
+    let key_prefix: Key =
+        Address::Internal(InternalAddress::PoS).to_db_key().into();
+
+    let key = key_prefix
+        .push(&"epoch_start_height".to_string())
+        .map_err(Error::KeyError)?;
+    tree.update(&key, types::encode(&next_epoch_min_start_height))?;
+
+    let key = key_prefix
+        .push(&"epoch_start_time".to_string())
+        .map_err(Error::KeyError)?;
+    tree.update(&key, types::encode(&next_epoch_min_start_time))?;
+
+    let key = key_prefix
+        .push(&"current_epoch".to_string())
+        .map_err(Error::KeyError)?;
+    tree.update(&key, types::encode(&epoch))?;
+    Ok(())
+}
 
-    /// Batch write the value with the given height and account subspace key to
-    /// the DB. Returns the size difference from previous value, if any, or
-    /// the size of the value otherwise.
-    fn batch_write_subspace_val(
-        &mut self,
-        batch: &mut D::WriteBatch,
-        key: &Key,
-        value: impl AsRef<[u8]>,
-    ) -> Result<i64> {
-        let value = value.as_ref();
-        self.block.tree.update(key, value)?;
-        self.db
-            .batch_write_subspace_val(batch, self.block.height, key, value)
-    }
+/// A prefix iterator over the account subspace of the [`Storage`]. The
+/// entries from the block's write cache shadow the entries from the DB.
+#[derive(Debug)]
+pub struct PrefixIter<'iter, D>
+where
+    D: DBIter<'iter>,
+{
+    db_iter: Peekable<D::PrefixIter>,
+    cache_iter: Peekable<std::vec::IntoIter<(String, Option<Vec<u8>>)>>,
+    /// Whether the keys are iterated in the reverse order
+    reverse: bool,
+}
 
-    /// Batch delete the value with the given height and account subspace key
-    /// from the DB. Returns the size of the removed value, if any, 0 if no
-    /// previous value was found.
-    fn batch_delete_subspace_val(
-        &mut self,
-        batch: &mut D::WriteBatch,
-        key: &Key,
-    ) -> Result<i64> {
-        self.block.tree.delete(key)?;
-        self.db
-            .batch_delete_subspace_val(batch, self.block.height, key)
+impl<'iter, D> Iterator for PrefixIter<'iter, D>
+where
+    D: DBIter<'iter>,
+{
+    type Item = (String, Vec<u8>, u64);
+
+    /// Returns the next pair and the gas cost
+    fn next(&mut self) -> Option<(String, Vec<u8>, u64)> {
+        loop {
+            let ordering = match (self.db_iter.peek(), self.cache_iter.peek()) {
+                (Some((db_key, _, _)), Some((cache_key, _))) => {
+                    db_key.cmp(cache_key)
+                }
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => return None,
+            };
+            // In the reverse order, the greater key is the next one
+            let ordering = if self.reverse {
+                ordering.reverse()
+            } else {
+                ordering
+            };
+            if ordering == Ordering::Less {
+                return self.db_iter.next();
+            }
+            if ordering == Ordering::Equal {
+                // The cached value shadows the value from the DB
+                self.db_iter.next();
+            }
+            if let Some((key, Some(val))) = self.cache_iter.next() {
+                let gas = key.len() + val.len();
+                return Some((key, val, gas as _));
+            }
+        }
     }
 }
 
@@ -700,6 +1109,7 @@ pub mod testing {
 
     use super::mockdb::MockDB;
     use super::*;
+    use crate::types::hash::Hash;
 
     /// Storage with a mock DB for testing
     pub type TestStorage = Storage<MockDB, Sha256Hasher>;
@@ -720,6 +1130,8 @@ pub mod testing {
                 chain_id,
                 block,
                 header: None,
+                block_request: None,
+                block_gas: 0,
                 last_height: BlockHeight(0),
                 last_epoch: Epoch::default(),
                 next_epoch_min_start_height: BlockHeight::default(),
@@ -729,6 +1141,138 @@ pub mod testing {
                 ),
                 #[cfg(feature = "ferveo-tpke")]
                 tx_queue: TxQueue::default(),
+                write_cache: BTreeMap::default(),
+                read_cache: None,
+            }
+        }
+    }
+
+    /// A copy of the whole state of a [`TestStorage`], from which it can be
+    /// restored to run test cases from the same state
+    pub struct TestStorageSnapshot {
+        db: MockDB,
+        tree: MerkleTreeStoresRead,
+        hash: BlockHash,
+        height: BlockHeight,
+        epoch: Epoch,
+        pred_epochs: Epochs,
+        header: Option<Header>,
+        block_gas: u64,
+        last_height: BlockHeight,
+        last_epoch: Epoch,
+        next_epoch_min_start_height: BlockHeight,
+        next_epoch_min_start_time: DateTimeUtc,
+        address_gen: EstablishedAddressGen,
+        #[cfg(feature = "ferveo-tpke")]
+        tx_queue: TxQueue,
+        write_cache: BTreeMap<Key, Option<Vec<u8>>>,
+    }
+
+    /// Move the test storage in time, so that the epoched logic can be tested
+    /// without running the blocks in between
+    impl TestStorage {
+        /// Set the height of the current block. The previous height becomes
+        /// the last committed height.
+        pub fn set_block_height(&mut self, height: BlockHeight) {
+            self.block.height = height;
+            self.last_height = BlockHeight(height.0.saturating_sub(1));
+        }
+
+        /// Set the epoch of the current block, which then starts at the
+        /// current height. Moving to an earlier epoch forgets the heights of
+        /// the predecessor epochs.
+        pub fn set_epoch(&mut self, epoch: Epoch) -> Result<()> {
+            if epoch > self.block.epoch {
+                for _ in self.block.epoch.0..epoch.0 {
+                    // Every epoch in between starts and ends at this height
+                    self.block
+                        .pred_epochs
+                        .new_epoch(self.block.height, u64::MAX);
+                }
+            } else if epoch < self.block.epoch {
+                self.block.pred_epochs = Epochs::starting_from(epoch);
+            }
+            self.block.epoch = epoch;
+            self.last_epoch = epoch;
+            self.update_epoch_in_merkle_tree()
+        }
+
+        /// Set the time of the current block in its header
+        pub fn set_block_time(&mut self, time: DateTimeUtc) {
+            match self.header.as_mut() {
+                Some(header) => header.time = time,
+                None => {
+                    self.header = Some(Header {
+                        hash: Hash::default(),
+                        time,
+                        next_validators_hash: Hash::default(),
+                    })
+                }
+            }
+        }
+
+        /// Take a snapshot of the state, including the uncommitted writes
+        pub fn snapshot(&self) -> TestStorageSnapshot {
+            let stores_write = self.block.tree.stores();
+            let mut tree = MerkleTreeStoresRead::default();
+            for st in StoreType::iter() {
+                tree.set_root(st, *stores_write.root(st));
+                tree.set_store(st, stores_write.store(st).clone());
+            }
+            tree.set_account_subtrees(
+                stores_write
+                    .account_subtrees()
+                    .iter()
+                    .map(|(account_key, (root, store))| {
+                        (account_key.to_string(), (**root, (*store).clone()))
+                    })
+                    .collect(),
+            );
+            TestStorageSnapshot {
+                db: self.db.clone(),
+                tree,
+                hash: self.block.hash.clone(),
+                height: self.block.height,
+                epoch: self.block.epoch,
+                pred_epochs: self.block.pred_epochs.clone(),
+                header: self.header.clone(),
+                block_gas: self.block_gas,
+                last_height: self.last_height,
+                last_epoch: self.last_epoch,
+                next_epoch_min_start_height: self.next_epoch_min_start_height,
+                next_epoch_min_start_time: self.next_epoch_min_start_time,
+                address_gen: self.address_gen.clone(),
+                #[cfg(feature = "ferveo-tpke")]
+                tx_queue: self.tx_queue.clone(),
+                write_cache: self.write_cache.clone(),
+            }
+        }
+
+        /// Restore the state from a snapshot, which can be restored again
+        pub fn restore(&mut self, snapshot: &TestStorageSnapshot) {
+            self.db = snapshot.db.clone();
+            self.block = BlockStorage {
+                tree: MerkleTree::new(snapshot.tree.clone()),
+                hash: snapshot.hash.clone(),
+                height: snapshot.height,
+                epoch: snapshot.epoch,
+                pred_epochs: snapshot.pred_epochs.clone(),
+            };
+            self.header = snapshot.header.clone();
+            self.block_gas = snapshot.block_gas;
+            self.last_height = snapshot.last_height;
+            self.last_epoch = snapshot.last_epoch;
+            self.next_epoch_min_start_height =
+                snapshot.next_epoch_min_start_height;
+            self.next_epoch_min_start_time = snapshot.next_epoch_min_start_time;
+            self.address_gen = snapshot.address_gen.clone();
+            #[cfg(feature = "ferveo-tpke")]
+            {
+                self.tx_queue = snapshot.tx_queue.clone();
+            }
+            self.write_cache = snapshot.write_cache.clone();
+            if let Some(read_cache) = &self.read_cache {
+                read_cache.clear();
             }
         }
     }
@@ -744,6 +1288,230 @@ mod tests {
     use crate::ledger::parameters::{self, Parameters};
     use crate::types::time::{self, Duration};
 
+    #[test]
+    fn test_write_cache_flushed_on_commit() {
+        let mut storage = TestStorage::default();
+        let key1 = Key::parse("key1").expect("cannot parse the key string");
+        let key2 = Key::parse("key2").expect("cannot parse the key string");
+        let key3 = Key::parse("key3").expect("cannot parse the key string");
+        let val1 = "val1".as_bytes().to_vec();
+        let val2 = "val2".as_bytes().to_vec();
+        let val3 = "val3".as_bytes().to_vec();
+
+        // key1 and key2 are committed in the first block
+        storage.write(&key1, val1.clone()).expect("write failed");
+        storage.write(&key2, val2.clone()).expect("write failed");
+        assert!(storage.db.read_subspace_val(&key1).unwrap().is_none());
+        storage.commit().expect("commit failed");
+        assert_eq!(
+            storage.db.read_subspace_val(&key1).unwrap(),
+            Some(val1.clone())
+        );
+
+        // Changes in the next block are only visible from the cache
+        let (_gas, size_diff) =
+            storage.write(&key2, val3.clone()).expect("write failed");
+        assert_eq!(size_diff, 0);
+        storage.write(&key3, val3.clone()).expect("write failed");
+        let (_gas, size_diff) = storage.delete(&key1).expect("delete failed");
+        assert_eq!(size_diff, val1.len() as i64);
+        let (value, _gas) = storage.read(&key1).expect("read failed");
+        assert!(value.is_none());
+        let (value, _gas) = storage.read(&key2).expect("read failed");
+        assert_eq!(value, Some(val3.clone()));
+        assert_eq!(storage.db.read_subspace_val(&key2).unwrap(), Some(val2));
+        assert!(storage.db.read_subspace_val(&key1).unwrap().is_some());
+
+        // The prefix iterator merges the cache over the DB
+        let prefix = Key::parse("key").expect("cannot parse the key string");
+        let (iter, _gas) = storage.iter_prefix(&prefix);
+        let expected = vec![
+            ("key2".to_owned(), val3.clone()),
+            ("key3".to_owned(), val3.clone()),
+        ];
+        let actual: Vec<(String, Vec<u8>)> =
+            iter.map(|(key, val, _gas)| (key, val)).collect();
+        assert_eq!(actual, expected);
+
+        storage.commit().expect("commit failed");
+        assert!(storage.db.read_subspace_val(&key1).unwrap().is_none());
+        assert_eq!(
+            storage.db.read_subspace_val(&key2).unwrap(),
+            Some(val3.clone())
+        );
+        assert_eq!(storage.db.read_subspace_val(&key3).unwrap(), Some(val3));
+    }
+
+    /// Test that the values read from the DB are served from the read cache
+    /// until they're changed by a committed block.
+    #[test]
+    fn test_read_cache() {
+        let mut storage = TestStorage {
+            read_cache: Some(ReadCache::new(1024)),
+            ..Default::default()
+        };
+        let key = Key::parse("key").expect("cannot parse the key string");
+        let missing = Key::parse("missing").expect("cannot parse the key");
+        storage.write(&key, vec![1]).expect("write failed");
+        storage.commit().expect("commit failed");
+        let read = |storage: &TestStorage, key| {
+            storage.read(key).expect("read failed").0
+        };
+        assert_eq!(read(&storage, &key), Some(vec![1]));
+        // The keys without value aren't in the merkle tree and aren't read
+        assert_eq!(read(&storage, &missing), None);
+        let read_cache = storage.read_cache.as_ref().unwrap();
+        assert_eq!(read_cache.len(), 1);
+
+        // A value written to the DB directly isn't seen over the cached one
+        storage
+            .db
+            .write_subspace_val(storage.block.height, &key, vec![2])
+            .expect("write failed");
+        assert_eq!(read(&storage, &key), Some(vec![1]));
+
+        // The keys changed by a block are invalidated on its commit
+        storage.write(&key, vec![3]).expect("write failed");
+        storage.write(&missing, vec![4]).expect("write failed");
+        assert_eq!(read(&storage, &key), Some(vec![3]));
+        storage.commit().expect("commit failed");
+        assert!(storage.read_cache.as_ref().unwrap().is_empty());
+        assert_eq!(read(&storage, &key), Some(vec![3]));
+        assert_eq!(read(&storage, &missing), Some(vec![4]));
+    }
+
+    /// Test that the reverse and the range iterators merge the write cache
+    /// over the DB in the order of the keys.
+    #[test]
+    fn test_rev_and_range_iter() {
+        let mut storage = TestStorage::default();
+        let key = |i: u64| {
+            Key::parse(format!("key{}", i)).expect("cannot parse the key")
+        };
+        let collect = |iter: PrefixIter<'_, mockdb::MockDB>| -> Vec<String> {
+            iter.map(|(key, _val, _gas)| key).collect()
+        };
+        for i in [1, 3, 5, 7] {
+            storage.write(&key(i), vec![]).expect("write failed");
+        }
+        storage.commit().expect("commit failed");
+        // Some keys are only in the write cache, one is deleted
+        for i in [2, 6, 8] {
+            storage.write(&key(i), vec![]).expect("write failed");
+        }
+        storage.delete(&key(3)).expect("delete failed");
+
+        let prefix = Key::parse("key").expect("cannot parse the key");
+        let (iter, _gas) = storage.rev_iter_prefix(&prefix);
+        assert_eq!(
+            collect(iter),
+            vec!["key8", "key7", "key6", "key5", "key2", "key1"]
+        );
+        let (iter, _gas) = storage.iter_prefix_from(&prefix, &key(3));
+        assert_eq!(collect(iter), vec!["key5", "key6", "key7", "key8"]);
+        let (iter, _gas) = storage.iter_range(&key(2), &key(7));
+        assert_eq!(collect(iter), vec!["key2", "key5", "key6"]);
+        let (iter, _gas) = storage.rev_iter_range(&key(2), &key(7));
+        assert_eq!(collect(iter), vec!["key6", "key5", "key2"]);
+        // An end before the start yields no keys
+        let (iter, _gas) = storage.iter_range(&key(7), &key(2));
+        assert!(collect(iter).is_empty());
+    }
+
+    /// Test that the test storage can be moved to an arbitrary height, epoch
+    /// and block time and restored from a snapshot.
+    #[test]
+    fn test_time_travel() {
+        let mut storage = TestStorage::default();
+        let key = Key::parse("key").expect("cannot parse the key string");
+        let read =
+            |storage: &TestStorage| storage.read(&key).expect("read failed").0;
+        storage.write(&key, vec![1]).expect("write failed");
+        storage.commit().expect("commit failed");
+        storage.write(&key, vec![2]).expect("write failed");
+        let snapshot = storage.snapshot();
+        let root = storage.merkle_root();
+
+        storage.set_block_height(BlockHeight(10));
+        storage.set_epoch(Epoch(3)).expect("set epoch failed");
+        let time: DateTimeUtc = Utc.timestamp(1_000_000, 0).into();
+        storage.set_block_time(time);
+        assert_eq!(storage.get_block_height().0, BlockHeight(10));
+        assert_eq!(storage.last_height, BlockHeight(9));
+        assert_eq!(storage.get_current_epoch().0, Epoch(3));
+        let pred_epochs = &storage.block.pred_epochs;
+        assert_eq!(pred_epochs.get_epoch(BlockHeight(9)), Some(Epoch(0)));
+        assert_eq!(pred_epochs.get_epoch(BlockHeight(10)), Some(Epoch(3)));
+        let (header, _gas) =
+            storage.get_block_header(None).expect("header read failed");
+        assert_eq!(header.expect("header must be set").time, time);
+
+        // Moving back in time only keeps the current epoch
+        storage.set_epoch(Epoch(1)).expect("set epoch failed");
+        let pred_epochs = &storage.block.pred_epochs;
+        assert_eq!(pred_epochs.get_epoch(BlockHeight(10)), Some(Epoch(1)));
+
+        storage.write(&key, vec![3]).expect("write failed");
+        storage.commit().expect("commit failed");
+        assert_eq!(read(&storage), Some(vec![3]));
+
+        // The snapshot can be restored more than once
+        for _ in 0..2 {
+            storage.restore(&snapshot);
+            assert_eq!(read(&storage), Some(vec![2]));
+            assert_eq!(storage.merkle_root(), root);
+            assert_eq!(storage.get_block_height().0, BlockHeight::default());
+            assert_eq!(storage.get_current_epoch().0, Epoch::default());
+            assert!(storage.header.is_none());
+            storage.delete(&key).expect("delete failed");
+        }
+    }
+
+    /// Test that the changed keys of a range of blocks are found from the
+    /// diffs of the blocks, with or without a prefix.
+    #[test]
+    fn test_changed_keys() {
+        let mut storage = TestStorage::default();
+        let key = |key: &str| Key::parse(key).expect("cannot parse the key");
+        let val = vec![1_u8];
+
+        // Block 1 writes `a/1`, block 2 writes `a/2` and `b/1`, block 3
+        // deletes `a/1`
+        storage.block.height = BlockHeight(1);
+        storage
+            .write(&key("a/1"), val.clone())
+            .expect("write failed");
+        storage.commit().expect("commit failed");
+        storage.block.height = BlockHeight(2);
+        storage
+            .write(&key("a/2"), val.clone())
+            .expect("write failed");
+        storage.write(&key("b/1"), val).expect("write failed");
+        storage.commit().expect("commit failed");
+        storage.block.height = BlockHeight(3);
+        storage.delete(&key("a/1")).expect("delete failed");
+        storage.commit().expect("commit failed");
+
+        let changed_keys = |prefix: Option<&Key>, from: u64, to: u64| {
+            storage
+                .changed_keys(prefix, BlockHeight(from), BlockHeight(to))
+                .expect("changed keys failed")
+                .into_iter()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            changed_keys(None, 0, 3),
+            vec![key("a/1"), key("a/2"), key("b/1")]
+        );
+        assert_eq!(changed_keys(None, 1, 2), vec![key("a/2"), key("b/1")]);
+        assert_eq!(
+            changed_keys(Some(&key("a")), 1, 3),
+            vec![key("a/1"), key("a/2")]
+        );
+        assert_eq!(changed_keys(Some(&key("b")), 2, 3), vec![]);
+        assert_eq!(changed_keys(None, 3, 3), vec![]);
+    }
+
     prop_compose! {
         /// Setup test input data with arbitrary epoch duration, epoch start
         /// height and time, and a block height and time that are greater than
@@ -808,7 +1576,9 @@ mod tests {
                 epoch_duration: epoch_duration.clone(),
                 max_expected_time_per_block: Duration::seconds(max_expected_time_per_block).into(),
                 vp_whitelist: vec![],
-                tx_whitelist: vec![]
+                tx_whitelist: vec![],
+                max_block_gas: crate::ledger::gas::BLOCK_GAS_LIMIT,
+                max_tx_gas: crate::ledger::gas::TRANSACTION_GAS_LIMIT,
             };
             parameters.init_storage(&mut storage);
 
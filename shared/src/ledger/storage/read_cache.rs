@@ -0,0 +1,124 @@
+//! An in-memory LRU cache of the values of the account subspace that are read
+//! from the DB, which is limited by the size of the cached keys and values.
+//! A key that has no value is cached too. The cache only holds the values of
+//! the committed blocks, so the keys changed in a block are invalidated when
+//! the block is committed.
+
+use std::cmp;
+use std::collections::hash_map::RandomState;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+use clru::{CLruCache, CLruCacheConfig, WeightScale};
+
+use crate::types::storage::Key;
+
+/// The cached values, `None` for a key without value
+type ValueCache = CLruCache<Key, Option<Vec<u8>>, RandomState, ValueScale>;
+
+/// Configures the cache scale of the values that limits the maximum capacity
+/// of the cache (CLruCache::len + CLruCache::weight <= CLruCache::capacity).
+#[derive(Debug)]
+struct ValueScale;
+
+impl WeightScale<Key, Option<Vec<u8>>> for ValueScale {
+    fn weight(&self, key: &Key, value: &Option<Vec<u8>>) -> usize {
+        // Only the size of the keys and the values is limited, so 1 is
+        // subtracted from it to negate the increment of the cache length
+        let size = key.len() + value.as_ref().map_or(0, Vec::len);
+        cmp::max(1, size) - 1
+    }
+}
+
+/// The read cache of the storage. Thread-safe.
+#[derive(Debug)]
+pub struct ReadCache {
+    values: Mutex<ValueCache>,
+}
+
+impl ReadCache {
+    /// Create a read cache with a given size limit.
+    ///
+    /// # Panics
+    /// The `max_bytes` must be non-zero.
+    pub fn new(max_bytes: usize) -> Self {
+        let values = CLruCache::with_config(
+            CLruCacheConfig::new(NonZeroUsize::new(max_bytes).unwrap())
+                .with_scale(ValueScale),
+        );
+        Self {
+            values: Mutex::new(values),
+        }
+    }
+
+    /// Get the cached value of a key, which is `Some(None)` for a key without
+    /// value, and update its position in the LRU cache. Returns `None` if the
+    /// key isn't cached.
+    pub fn get(&self, key: &Key) -> Option<Option<Vec<u8>>> {
+        self.lock().get(key).cloned()
+    }
+
+    /// Cache the value of a key read from the DB. A value that is larger than
+    /// the cache is not cached.
+    pub fn put(&self, key: Key, value: Option<Vec<u8>>) {
+        let _ = self.lock().put_with_weight(key, value);
+    }
+
+    /// Remove the value of a key that has changed
+    pub fn invalidate(&self, key: &Key) {
+        self.lock().pop(key);
+    }
+
+    /// Remove all the cached values
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// The number of the cached keys
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Check if no key is cached
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ValueCache> {
+        self.values
+            .lock()
+            .expect("The read cache lock shouldn't be poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the least recently used values are evicted once the size of
+    /// the cached keys and values exceeds the limit.
+    #[test]
+    fn test_read_cache_eviction() {
+        let key = |key: &str| Key::parse(key).unwrap();
+        // Each entry takes the size of its key and value
+        let cache = ReadCache::new(12);
+        cache.put(key("a"), Some(vec![0; 3]));
+        cache.put(key("b"), None);
+        cache.put(key("c"), Some(vec![0; 3]));
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.get(&key("a")), Some(Some(vec![0; 3])));
+        assert_eq!(cache.get(&key("b")), Some(None));
+
+        // The least recently used "c" is evicted
+        cache.put(key("d"), Some(vec![0; 3]));
+        assert_eq!(cache.get(&key("c")), None);
+        assert!(cache.get(&key("a")).is_some());
+
+        cache.invalidate(&key("a"));
+        assert_eq!(cache.get(&key("a")), None);
+
+        // A value larger than the cache is not cached
+        cache.put(key("e"), Some(vec![0; 12]));
+        assert_eq!(cache.get(&key("e")), None);
+    }
+}
@@ -354,33 +354,25 @@ impl WriteLog {
             + for<'iter> ledger::storage::DBIter<'iter>,
         H: StorageHasher,
     {
-        let mut batch = Storage::<DB, H>::batch();
         for (key, entry) in self.block_write_log.iter() {
             match entry {
                 StorageModification::Write { value } => {
                     storage
-                        .batch_write_subspace_val(
-                            &mut batch,
-                            key,
-                            value.clone(),
-                        )
+                        .write(key, value.clone())
                         .map_err(Error::StorageError)?;
                 }
                 StorageModification::Delete => {
-                    storage
-                        .batch_delete_subspace_val(&mut batch, key)
-                        .map_err(Error::StorageError)?;
+                    storage.delete(key).map_err(Error::StorageError)?;
                 }
                 StorageModification::InitAccount { vp } => {
                     storage
-                        .batch_write_subspace_val(&mut batch, key, vp.clone())
+                        .write(key, vp.clone())
                         .map_err(Error::StorageError)?;
                 }
                 // temporary value isn't persisted
                 StorageModification::Temp { .. } => {}
             }
         }
-        storage.exec_batch(batch).map_err(Error::StorageError)?;
         if let Some(address_gen) = self.address_gen.take() {
             storage.address_gen = address_gen
         }
@@ -0,0 +1,4 @@
+//! Registry of the tokens' symbols
+
+pub mod storage;
+pub mod vp;
@@ -0,0 +1,46 @@
+//! Token registry storage keys
+
+use super::vp::ADDRESS;
+use crate::types::storage::{DbKeySeg, Key, KeySeg};
+
+const SYMBOL_STORAGE_KEY: &str = "symbol";
+
+/// Obtain a storage key for the address of the token registered with the given
+/// symbol.
+pub fn symbol_key(symbol: &str) -> Key {
+    symbol_prefix()
+        .push(&symbol.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Obtain a storage key prefix for all the registered symbols.
+pub fn symbol_prefix() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&SYMBOL_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Check if the given storage key is a symbol key. If it is, returns the
+/// symbol.
+pub fn is_symbol_key(key: &Key) -> Option<&String> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::StringSeg(symbol),
+        ] if addr == &ADDRESS && prefix == SYMBOL_STORAGE_KEY => Some(symbol),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_key() {
+        let key = symbol_key("XAN");
+        assert_eq!(is_symbol_key(&key), Some(&"XAN".to_string()));
+        assert_eq!(is_symbol_key(&symbol_prefix()), None);
+    }
+}
@@ -0,0 +1,114 @@
+//! Validity predicate for the token registry
+
+use std::collections::BTreeSet;
+
+use borsh::BorshDeserialize;
+use thiserror::Error;
+
+use super::storage as registry_storage;
+use crate::ledger::native_vp::{self, Ctx, NativeVp};
+use crate::ledger::storage::{self as ledger_storage, StorageHasher};
+use crate::types::address::{Address, InternalAddress};
+use crate::types::storage::Key;
+use crate::types::token;
+use crate::vm::WasmCacheAccess;
+
+/// Internal address for the token registry VP
+pub const ADDRESS: Address = Address::Internal(InternalAddress::TokenRegistry);
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Native VP error: {0}")]
+    NativeVpError(native_vp::Error),
+}
+
+/// Token registry functions result
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Validity predicate for the token registry. A symbol can only be registered
+/// once, together with the initialization of the token with the same symbol
+/// in its metadata, and it can never be changed or removed.
+pub struct TokenRegistryVp<'a, DB, H, CA>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    /// Context to interact with the host structures.
+    pub ctx: Ctx<'a, DB, H, CA>,
+}
+
+impl<'a, DB, H, CA> NativeVp for TokenRegistryVp<'a, DB, H, CA>
+where
+    DB: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    type Error = Error;
+
+    const ADDR: InternalAddress = InternalAddress::TokenRegistry;
+
+    fn validate_tx(
+        &self,
+        _tx_data: &[u8],
+        keys_changed: &BTreeSet<Key>,
+        _verifiers: &BTreeSet<Address>,
+    ) -> Result<bool> {
+        for key in keys_changed {
+            if key.segments.get(0) != Some(&ADDRESS.to_db_key()) {
+                continue;
+            }
+            let symbol = match registry_storage::is_symbol_key(key) {
+                Some(symbol) => symbol,
+                None => return Ok(false),
+            };
+            if self.ctx.has_key_pre(key)? {
+                tracing::info!("The token symbol {} is already taken", symbol);
+                return Ok(false);
+            }
+            let token = match self.ctx.read_post(key)? {
+                Some(bytes) => match Address::try_from_slice(&bytes[..]) {
+                    Ok(token) => token,
+                    Err(_) => return Ok(false),
+                },
+                None => return Ok(false),
+            };
+            if !self.is_new_token_with_symbol(&token, symbol)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl<'a, DB, H, CA> TokenRegistryVp<'a, DB, H, CA>
+where
+    DB: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    /// Check that the token's metadata are written in this tx and that they
+    /// have the registered symbol.
+    fn is_new_token_with_symbol(
+        &self,
+        token: &Address,
+        symbol: &str,
+    ) -> Result<bool> {
+        let metadata_key = token::metadata_key(token);
+        if self.ctx.has_key_pre(&metadata_key)? {
+            return Ok(false);
+        }
+        let metadata = self
+            .ctx
+            .read_post(&metadata_key)?
+            .and_then(|bytes| token::Metadata::try_from_slice(&bytes[..]).ok());
+        Ok(matches!(metadata, Some(metadata) if metadata.symbol == symbol))
+    }
+}
+
+impl From<native_vp::Error> for Error {
+    fn from(err: native_vp::Error) -> Self {
+        Self::NativeVpError(err)
+    }
+}
@@ -272,7 +272,7 @@ pub fn iter_prefix<'a, DB, H>(
     gas_meter: &mut VpGasMeter,
     storage: &'a Storage<DB, H>,
     prefix: &Key,
-) -> Result<<DB as storage::DBIter<'a>>::PrefixIter>
+) -> Result<storage::PrefixIter<'a, DB>>
 where
     DB: storage::DB + for<'iter> storage::DBIter<'iter>,
     H: StorageHasher,
@@ -282,11 +282,62 @@ where
     Ok(iter)
 }
 
+/// Storage prefix iterator in the reverse order of the keys. It will try to
+/// get an iterator from the storage.
+pub fn rev_iter_prefix<'a, DB, H>(
+    gas_meter: &mut VpGasMeter,
+    storage: &'a Storage<DB, H>,
+    prefix: &Key,
+) -> Result<storage::PrefixIter<'a, DB>>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    let (iter, gas) = storage.rev_iter_prefix(prefix);
+    add_gas(gas_meter, gas)?;
+    Ok(iter)
+}
+
+/// Storage range iterator of the keys from `start` (inclusive) up to `end`
+/// (exclusive). It will try to get an iterator from the storage.
+pub fn iter_range<'a, DB, H>(
+    gas_meter: &mut VpGasMeter,
+    storage: &'a Storage<DB, H>,
+    start: &Key,
+    end: &Key,
+) -> Result<storage::PrefixIter<'a, DB>>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    let (iter, gas) = storage.iter_range(start, end);
+    add_gas(gas_meter, gas)?;
+    Ok(iter)
+}
+
+/// Storage range iterator of the keys from `start` (inclusive) up to `end`
+/// (exclusive) in the reverse order. It will try to get an iterator from the
+/// storage.
+pub fn rev_iter_range<'a, DB, H>(
+    gas_meter: &mut VpGasMeter,
+    storage: &'a Storage<DB, H>,
+    start: &Key,
+    end: &Key,
+) -> Result<storage::PrefixIter<'a, DB>>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    let (iter, gas) = storage.rev_iter_range(start, end);
+    add_gas(gas_meter, gas)?;
+    Ok(iter)
+}
+
 /// Storage prefix iterator for prior state (before tx execution). It will try
 /// to read from the storage.
 pub fn iter_pre_next<DB>(
     gas_meter: &mut VpGasMeter,
-    iter: &mut <DB as storage::DBIter<'_>>::PrefixIter,
+    iter: &mut storage::PrefixIter<'_, DB>,
 ) -> Result<Option<(String, Vec<u8>)>>
 where
     DB: storage::DB + for<'iter> storage::DBIter<'iter>,
@@ -304,7 +355,7 @@ where
 pub fn iter_post_next<DB>(
     gas_meter: &mut VpGasMeter,
     write_log: &WriteLog,
-    iter: &mut <DB as storage::DBIter<'_>>::PrefixIter,
+    iter: &mut storage::PrefixIter<'_, DB>,
 ) -> Result<Option<(String, Vec<u8>)>>
 where
     DB: storage::DB + for<'iter> storage::DBIter<'iter>,
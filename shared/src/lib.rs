@@ -14,16 +14,11 @@ pub use ibc_abci as ibc;
 pub use ibc_proto;
 #[cfg(feature = "ABCI")]
 pub use ibc_proto_abci as ibc_proto;
-#[cfg(not(feature = "ABCI"))]
-pub use tendermint;
-#[cfg(not(feature = "ABCI"))]
-pub use tendermint_proto;
-#[cfg(feature = "ABCI")]
-pub use tendermint_proto_abci as tendermint_proto;
-#[cfg(feature = "ABCI")]
-pub use tendermint_stable as tendermint;
+pub use tendermint_compat::{tendermint, tendermint_proto};
 
 pub mod bytes;
+#[cfg(any(feature = "rpc-client", feature = "rpc-client-abci"))]
+pub mod client;
 pub mod ledger;
 pub mod proto;
 pub mod types;
@@ -0,0 +1,57 @@
+//! Types of the state of accounts exposed by the ledger's queries.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::types::address::Address;
+use crate::types::hash::Hash;
+use crate::types::key::common;
+use crate::types::storage::Key;
+
+/// The current public keys and validity predicate of an account. The txs of
+/// an account are authorized by a single public key, so there's no threshold
+/// of signatures to configure.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct AccountInfo {
+    /// The address of the account
+    pub address: Address,
+    /// The public key used to verify the signatures of the account's txs
+    pub public_key: Option<common::PublicKey>,
+    /// The protocol public key, only set for validator accounts
+    pub protocol_public_key: Option<common::PublicKey>,
+    /// The hash of the account's validity predicate code. Implicit accounts
+    /// don't have a validity predicate.
+    pub vp_code_hash: Option<Hash>,
+}
+
+/// The storage used by an account, i.e. by the keys prefixed with its address
+/// and their values. The balances of an account are in the subspaces of the
+/// tokens, so they are not included.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, BorshSerialize, BorshDeserialize,
+)]
+pub struct StorageUsage {
+    /// The number of the keys
+    pub keys: u64,
+    /// The total size of the keys and the values in bytes
+    pub bytes: u64,
+}
+
+/// A page of the storage usage of an account, which counts the keys of a
+/// range of its subspace
+#[derive(
+    Clone, Debug, Default, PartialEq, BorshSerialize, BorshDeserialize,
+)]
+pub struct StorageUsagePage {
+    /// The usage of the keys in the range of the page
+    pub usage: StorageUsage,
+    /// The last key of the page, after which the next page starts, if any
+    pub next: Option<Key>,
+}
+
+impl StorageUsage {
+    /// Add the usage of another range of keys
+    pub fn add(&mut self, other: &StorageUsage) {
+        self.keys += other.keys;
+        self.bytes += other.bytes;
+    }
+}
@@ -67,6 +67,10 @@ mod internal {
         "ano::IBC Mint Address                        ";
     pub const ETH_BRIDGE: &str =
         "ano::ETH Bridge Address                      ";
+    pub const TOKEN_REGISTRY: &str =
+        "ano::Token Registry                          ";
+    pub const NAME_SERVICE: &str =
+        "ano::Name Service                            ";
 }
 
 /// Fixed-length address strings prefix for established addresses.
@@ -194,6 +198,12 @@ impl Address {
                     InternalAddress::EthBridge => {
                         internal::ETH_BRIDGE.to_string()
                     }
+                    InternalAddress::TokenRegistry => {
+                        internal::TOKEN_REGISTRY.to_string()
+                    }
+                    InternalAddress::NameService => {
+                        internal::NAME_SERVICE.to_string()
+                    }
                 };
                 debug_assert_eq!(string.len(), FIXED_LEN_STRING_BYTES);
                 string
@@ -254,6 +264,12 @@ impl Address {
                 internal::ETH_BRIDGE => {
                     Ok(Address::Internal(InternalAddress::EthBridge))
                 }
+                internal::TOKEN_REGISTRY => {
+                    Ok(Address::Internal(InternalAddress::TokenRegistry))
+                }
+                internal::NAME_SERVICE => {
+                    Ok(Address::Internal(InternalAddress::NameService))
+                }
                 _ if raw.len() == HASH_LEN => Ok(Address::Internal(
                     InternalAddress::IbcEscrow(raw.to_string()),
                 )),
@@ -353,7 +369,15 @@ pub struct EstablishedAddress {
 }
 
 /// A generator of established addresses
-#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
 pub struct EstablishedAddressGen {
     last_hash: String,
 }
@@ -451,6 +475,10 @@ pub enum InternalAddress {
     Treasury,
     /// Bridge to Ethereum
     EthBridge,
+    /// Registry of the tokens' symbols
+    TokenRegistry,
+    /// Name service of the addresses' aliases
+    NameService,
 }
 
 impl InternalAddress {
@@ -480,6 +508,8 @@ impl Display for InternalAddress {
                 Self::IbcBurn => "IbcBurn".to_string(),
                 Self::IbcMint => "IbcMint".to_string(),
                 Self::EthBridge => "EthBridge".to_string(),
+                Self::TokenRegistry => "TokenRegistry".to_string(),
+                Self::NameService => "NameService".to_string(),
             }
         )
     }
@@ -717,8 +747,10 @@ pub mod testing {
             InternalAddress::IbcEscrow(_) => {}
             InternalAddress::IbcBurn => {}
             InternalAddress::IbcMint => {}
-            InternalAddress::EthBridge => {} /* Add new addresses in the
-                                              * `prop_oneof` below. */
+            InternalAddress::EthBridge => {}
+            InternalAddress::TokenRegistry => {}
+            InternalAddress::NameService => {} /* Add new addresses in the
+                                                * `prop_oneof` below. */
         };
         prop_oneof![
             Just(InternalAddress::PoS),
@@ -732,6 +764,8 @@ pub mod testing {
             Just(InternalAddress::Governance),
             Just(InternalAddress::Treasury),
             Just(InternalAddress::EthBridge),
+            Just(InternalAddress::TokenRegistry),
+            Just(InternalAddress::NameService),
         ]
     }
 
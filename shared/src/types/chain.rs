@@ -4,7 +4,7 @@
 use std::fmt::Display;
 use std::str::FromStr;
 
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use thiserror::Error;
@@ -15,6 +15,11 @@ pub const CHAIN_ID_LENGTH: usize = 30;
 pub const CHAIN_ID_PREFIX_MAX_LEN: usize = 19;
 /// Separator between chain ID prefix and the generated hash
 pub const CHAIN_ID_PREFIX_SEP: char = '.';
+/// The version of the ledger protocol, which is embedded in the block headers.
+/// It must be incremented with every change that breaks consensus with the
+/// previous version, so that the nodes running different versions of the
+/// protocol reject each other's blocks.
+pub const PROTOCOL_VERSION: u64 = 1;
 
 /// Development default chain ID. Must be [`CHAIN_ID_LENGTH`] long.
 #[cfg(feature = "dev")]
@@ -32,6 +37,7 @@ pub const DEFAULT_CHAIN_ID: &str = "anoma-internal.000000000000000";
     Deserialize,
     BorshSerialize,
     BorshDeserialize,
+    BorshSchema,
     PartialOrd,
     Ord,
     PartialEq,
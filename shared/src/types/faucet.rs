@@ -0,0 +1,191 @@
+//! Types of a testnet faucet account, that allows anyone to withdraw a limited
+//! amount of tokens without the faucet's signature.
+//!
+//! The withdrawals are rate limited per target and also in total per epoch,
+//! so that the faucet cannot be drained by withdrawing to many new addresses.
+
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+use crate::types::address::Address;
+use crate::types::storage::{DbKeySeg, Epoch, Key, KeySeg};
+use crate::types::token;
+
+/// Allows anyone to withdraw up to 1_000 tokens in a single tx
+pub const MAX_FREE_DEBIT: i128 = 1_000_000_000; // in micro units
+/// The minimum number of blocks between two free withdrawals of the same
+/// target
+pub const WITHDRAWAL_INTERVAL: u64 = 100;
+/// Allows anyone to withdraw up to 100_000 tokens of every token from a faucet
+/// in total in an epoch
+pub const MAX_EPOCH_WITHDRAWALS: i128 = 100 * MAX_FREE_DEBIT; // in micro units
+
+const LAST_WITHDRAWAL_STORAGE_KEY: &str = "last_withdrawal";
+const EPOCH_WITHDRAWALS_STORAGE_KEY: &str = "epoch_withdrawals";
+
+/// Obtain a storage key of the height of the block in which the given target
+/// has last withdrawn from the faucet.
+pub fn last_withdrawal_key(faucet: &Address, target: &Address) -> Key {
+    Key::from(faucet.to_db_key())
+        .push(&LAST_WITHDRAWAL_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+        .push(&target.to_db_key())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Check if the given storage key is a last withdrawal key of the given
+/// faucet. If it is, returns the target.
+pub fn is_last_withdrawal_key<'a>(
+    faucet: &Address,
+    key: &'a Key,
+) -> Option<&'a Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(key),
+            DbKeySeg::AddressSeg(target),
+        ] if key == LAST_WITHDRAWAL_STORAGE_KEY && addr == faucet => {
+            Some(target)
+        }
+        _ => None,
+    }
+}
+
+/// Obtain a storage key of the total amount of the given token withdrawn from
+/// the faucet in the current epoch.
+pub fn epoch_withdrawals_key(faucet: &Address, token: &Address) -> Key {
+    Key::from(faucet.to_db_key())
+        .push(&EPOCH_WITHDRAWALS_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+        .push(&token.to_db_key())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Check if the given storage key is an epoch withdrawals key of the given
+/// faucet. If it is, returns the token.
+pub fn is_epoch_withdrawals_key<'a>(
+    faucet: &Address,
+    key: &'a Key,
+) -> Option<&'a Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(key),
+            DbKeySeg::AddressSeg(token),
+        ] if key == EPOCH_WITHDRAWALS_STORAGE_KEY && addr == faucet => {
+            Some(token)
+        }
+        _ => None,
+    }
+}
+
+/// The total amount of a token withdrawn from a faucet without its signature
+/// in an epoch.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct EpochWithdrawals {
+    /// The epoch of the withdrawals
+    pub epoch: Epoch,
+    /// The total amount withdrawn in the epoch
+    pub amount: token::Amount,
+}
+
+impl EpochWithdrawals {
+    /// The total after a withdrawal of the amount in the given epoch. The
+    /// total of a previous epoch is reset.
+    pub fn add(
+        previous: Option<&Self>,
+        epoch: Epoch,
+        amount: token::Amount,
+    ) -> Self {
+        let amount = match previous {
+            Some(previous) if previous.epoch == epoch => {
+                previous.amount + amount
+            }
+            _ => amount,
+        };
+        Self { epoch, amount }
+    }
+
+    /// Check if the total is within [`MAX_EPOCH_WITHDRAWALS`]
+    pub fn is_within_cap(&self) -> bool {
+        self.amount.change() <= MAX_EPOCH_WITHDRAWALS
+    }
+}
+
+/// A withdrawal of tokens from a faucet, that doesn't need the faucet's
+/// signature if it's within [`MAX_FREE_DEBIT`], the target hasn't withdrawn
+/// in the last [`WITHDRAWAL_INTERVAL`] blocks and the faucet's withdrawals of
+/// the token in the epoch stay within [`MAX_EPOCH_WITHDRAWALS`].
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+)]
+pub struct Withdrawal {
+    /// Faucet address
+    pub faucet: Address,
+    /// Target address
+    pub target: Address,
+    /// Token's address
+    pub token: Address,
+    /// The amount of tokens
+    pub amount: token::Amount,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::address;
+
+    #[test]
+    fn test_last_withdrawal_key() {
+        let faucet = address::testing::established_address_1();
+        let target = address::testing::established_address_2();
+        let key = last_withdrawal_key(&faucet, &target);
+        assert_eq!(is_last_withdrawal_key(&faucet, &key), Some(&target));
+        assert_eq!(is_last_withdrawal_key(&target, &key), None);
+    }
+
+    #[test]
+    fn test_epoch_withdrawals() {
+        let faucet = address::testing::established_address_1();
+        let token = address::testing::established_address_2();
+        let key = epoch_withdrawals_key(&faucet, &token);
+        assert_eq!(is_epoch_withdrawals_key(&faucet, &key), Some(&token));
+        assert_eq!(is_last_withdrawal_key(&faucet, &key), None);
+        assert_eq!(is_epoch_withdrawals_key(&token, &key), None);
+
+        let amount = token::Amount::from(MAX_FREE_DEBIT as u64);
+        let first = EpochWithdrawals::add(None, Epoch(1), amount);
+        assert_eq!(first.amount, amount);
+        let second = EpochWithdrawals::add(Some(&first), Epoch(1), amount);
+        assert_eq!(second.amount, amount + amount);
+        // The total is reset in a new epoch
+        let next = EpochWithdrawals::add(Some(&second), Epoch(2), amount);
+        assert_eq!(next.amount, amount);
+
+        let max = token::Amount::from(MAX_EPOCH_WITHDRAWALS as u64);
+        assert!(EpochWithdrawals::add(None, Epoch(1), max).is_within_cap());
+        assert!(
+            !EpochWithdrawals::add(Some(&first), Epoch(1), max).is_within_cap()
+        );
+    }
+}
@@ -2,20 +2,16 @@
 
 use std::fmt::{self, Display};
 use std::ops::Deref;
+use std::str::FromStr;
 
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-#[cfg(not(feature = "ABCI"))]
-use tendermint::abci::transaction;
-#[cfg(not(feature = "ABCI"))]
-use tendermint::Hash as TmHash;
-#[cfg(feature = "ABCI")]
-use tendermint_stable::abci::transaction;
-#[cfg(feature = "ABCI")]
-use tendermint_stable::Hash as TmHash;
 use thiserror::Error;
 
+use crate::tendermint::abci::transaction;
+use crate::tendermint::Hash as TmHash;
+
 /// The length of the transaction hash string
 pub const HASH_LENGTH: usize = 32;
 
@@ -26,6 +22,8 @@ pub enum Error {
     Temporary { error: String },
     #[error("Failed trying to convert slice to a hash: {0}")]
     ConversionFailed(std::array::TryFromSliceError),
+    #[error("Failed to convert string into a hash: {0}")]
+    FromStringError(hex::FromHexError),
 }
 
 /// Result for functions that may fail
@@ -89,6 +87,16 @@ impl TryFrom<&[u8]> for Hash {
     }
 }
 
+/// Parsed from its hex encoding, like in the events of the txs
+impl FromStr for Hash {
+    type Err = self::Error;
+
+    fn from_str(s: &str) -> HashResult<Self> {
+        let bytes = hex::decode(s).map_err(Error::FromStringError)?;
+        Self::try_from(&bytes[..])
+    }
+}
+
 impl From<Hash> for transaction::Hash {
     fn from(hash: Hash) -> Self {
         Self::new(hash.0)
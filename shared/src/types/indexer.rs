@@ -0,0 +1,204 @@
+//! Types of the txs indexed by a ledger node that has the indexer enabled.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::types::address::Address;
+use crate::types::storage::BlockHeight;
+use crate::types::token;
+
+/// A tx included in a committed block, together with its result
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct IndexedTx {
+    /// The hash of the tx, as found in its event
+    pub hash: String,
+    /// The height of the block in which the tx is included
+    pub height: BlockHeight,
+    /// The position of the tx in the block
+    pub index: u32,
+    /// The result code of the tx
+    pub code: u32,
+    /// The gas used by the tx
+    pub gas_used: u64,
+    /// The result info of the tx
+    pub info: String,
+    /// The signer of the wrapper of the tx, who paid its fee, if any
+    pub sender: Option<Address>,
+    /// The addresses whose validity predicates were triggered by the tx and
+    /// the accounts initialized by it
+    pub addresses: BTreeSet<Address>,
+    /// The changes of token balances made by the tx, if it was applied
+    pub movements: Vec<TokenMovement>,
+}
+
+/// The results of the txs included in a committed block, in the order in
+/// which they were applied
+#[derive(
+    Clone, Debug, Default, PartialEq, BorshSerialize, BorshDeserialize,
+)]
+pub struct BlockResults {
+    /// The height of the block
+    pub height: BlockHeight,
+    /// The results of the block's txs
+    pub txs: Vec<BlockTxResult>,
+}
+
+/// The result of a tx included in a block, with the events emitted for it
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct BlockTxResult {
+    /// The hash of the tx, as found in its event
+    pub hash: String,
+    /// The position of the tx in the block
+    pub index: u32,
+    /// The result code of the tx
+    pub code: u32,
+    /// The gas used by the tx
+    pub gas_used: u64,
+    /// The result info of the tx
+    pub info: String,
+    /// The events emitted for the tx, starting with the event of its result
+    pub events: Vec<BlockEvent>,
+}
+
+/// An event emitted by the ledger for a tx
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct BlockEvent {
+    /// The type of the event, e.g. `applied` or the type of an IBC event
+    pub event_type: String,
+    /// The attributes of the event
+    pub attributes: BTreeMap<String, String>,
+}
+
+/// A change of token balance of an owner made by a tx
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct TokenMovement {
+    /// The token address
+    pub token: Address,
+    /// The owner of the balance
+    pub owner: Address,
+    /// The signed change of the balance
+    pub change: token::Change,
+}
+
+/// The direction of a token transfer relative to the queried owner
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize,
+)]
+pub enum TransferDirection {
+    /// The owner received the tokens
+    Incoming,
+    /// The owner sent the tokens
+    Outgoing,
+}
+
+/// A token transfer of an owner, derived from the token movements of an
+/// indexed tx
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct TransferRecord {
+    /// The height of the block in which the transfer's tx is included
+    pub height: BlockHeight,
+    /// The hash of the transfer's tx
+    pub tx_hash: String,
+    /// The direction of the transfer
+    pub direction: TransferDirection,
+    /// The other side of the transfer. It's [`None`] when the owner's
+    /// balance change was matched by more than one opposite change of the
+    /// same token in the tx.
+    pub counterparty: Option<Address>,
+    /// The token address
+    pub token: Address,
+    /// The transferred amount
+    pub amount: token::Amount,
+}
+
+impl IndexedTx {
+    /// Get the token transfers of the given owner made by this tx
+    pub fn transfers_of(&self, owner: &Address) -> Vec<TransferRecord> {
+        self.movements
+            .iter()
+            .filter(|movement| &movement.owner == owner && movement.change != 0)
+            .map(|movement| {
+                let direction = if movement.change > 0 {
+                    TransferDirection::Incoming
+                } else {
+                    TransferDirection::Outgoing
+                };
+                let mut counterparties =
+                    self.movements.iter().filter(|other| {
+                        other.token == movement.token
+                            && &other.owner != owner
+                            && other.change.signum()
+                                == -movement.change.signum()
+                    });
+                let counterparty =
+                    match (counterparties.next(), counterparties.next()) {
+                        (Some(other), None) => Some(other.owner.clone()),
+                        _ => None,
+                    };
+                TransferRecord {
+                    height: self.height,
+                    tx_hash: self.hash.clone(),
+                    direction,
+                    counterparty,
+                    token: movement.token.clone(),
+                    amount: token::Amount::from(
+                        movement.change.unsigned_abs() as u64
+                    ),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::address;
+
+    /// Test that the transfers of an owner get the counterparty of the
+    /// matching opposite movement.
+    #[test]
+    fn test_transfers_of() {
+        let source = address::testing::established_address_1();
+        let target = address::testing::established_address_2();
+        let other = address::testing::established_address_3();
+        let xan = address::xan();
+        let movement = |owner: &Address, change| TokenMovement {
+            token: xan.clone(),
+            owner: owner.clone(),
+            change,
+        };
+        let mut tx = IndexedTx {
+            hash: "hash".to_string(),
+            height: BlockHeight(1),
+            index: 0,
+            code: 0,
+            gas_used: 0,
+            info: String::new(),
+            sender: None,
+            addresses: BTreeSet::new(),
+            movements: vec![movement(&source, -10), movement(&target, 10)],
+        };
+
+        let transfers = tx.transfers_of(&source);
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].direction, TransferDirection::Outgoing);
+        assert_eq!(transfers[0].counterparty, Some(target.clone()));
+        assert_eq!(transfers[0].amount, token::Amount::from(10));
+
+        let transfers = tx.transfers_of(&target);
+        assert_eq!(transfers[0].direction, TransferDirection::Incoming);
+        assert_eq!(transfers[0].counterparty, Some(source.clone()));
+
+        // With more than one receiver, the counterparty is ambiguous
+        tx.movements = vec![
+            movement(&source, -10),
+            movement(&target, 5),
+            movement(&other, 5),
+        ];
+        assert_eq!(tx.transfers_of(&source)[0].counterparty, None);
+        assert_eq!(tx.transfers_of(&other)[0].counterparty, Some(source));
+        assert!(tx.transfers_of(&xan).is_empty());
+    }
+}
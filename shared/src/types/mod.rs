@@ -1,16 +1,20 @@
 //! Types definitions.
 
+pub mod account;
 pub mod address;
 pub mod chain;
 pub mod dylib;
+pub mod faucet;
 pub mod governance;
 pub mod hash;
 pub mod ibc;
+pub mod indexer;
 pub mod intent;
 pub mod internal;
 pub mod key;
 pub mod matchmaker;
 pub mod nft;
+pub mod query;
 pub mod storage;
 pub mod time;
 pub mod token;
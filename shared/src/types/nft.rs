@@ -161,11 +161,16 @@ pub fn _nft_prefix(address: &Address) -> Key {
         .expect("Cannot obtain a storage key")
 }
 
-/// Get the nft token prefix
-pub fn _nft_token_prefix(address: &Address, token_id: &str) -> Key {
+/// Get the prefix of all the tokens of a nft
+pub fn get_tokens_prefix(address: &Address) -> Key {
     _nft_prefix(address)
         .push(&IDS_KEY.to_owned())
         .expect("Cannot obtain a storage key")
+}
+
+/// Get the nft token prefix
+pub fn _nft_token_prefix(address: &Address, token_id: &str) -> Key {
+    get_tokens_prefix(address)
         .push(&token_id.to_owned())
         .expect("Cannot obtain a storage key")
 }
@@ -0,0 +1,75 @@
+//! The names of the ledger's RPC query paths, the error codes of their
+//! responses and the results of the common queries, which are shared by the
+//! node that serves the queries and by its clients.
+
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::address::Address;
+use crate::types::storage::Epoch;
+use crate::types::token;
+
+/// The query path of a dry run of a tx
+pub const DRY_RUN_TX_PATH: &str = "dry_run_tx";
+/// The query path of the last committed epoch
+pub const EPOCH_PATH: &str = "epoch";
+/// The prefix of the query path of a storage value
+pub const VALUE_PREFIX: &str = "value";
+/// The prefix of the query path of the storage values with a key prefix
+pub const PREFIX_PREFIX: &str = "prefix";
+/// The prefix of the query path of a page of the storage values with a key
+/// prefix
+pub const PREFIX_PAGE_PREFIX: &str = "prefix_page";
+
+/// The maximum number of values in a page of a prefix, as served by the
+/// ledger. A larger limit is reduced to it.
+pub const MAX_PREFIX_PAGE_LIMIT: u64 = 1000;
+
+/// The error code of a query for data that the node doesn't have, e.g. a
+/// storage key or a prefix without values
+pub const NOT_FOUND_CODE: u32 = 1;
+
+/// The error code of a query that failed to read or to decode the storage of
+/// the node
+pub const STORAGE_ERROR_CODE: u32 = 2;
+
+/// The error code of a query of a path that is not allowed on the node
+pub const PATH_NOT_ALLOWED_CODE: u32 = 3;
+
+/// The error code of a query rejected by the rate limiter of the node, which
+/// is distinct from the codes of the errors of the queries themselves
+pub const RATE_LIMITED_CODE: u32 = 4;
+
+/// The error code of a query that the node cannot serve as requested, e.g.
+/// with an invalid path or height, which is distinct from the code of the
+/// data that the node doesn't have
+pub const INVALID_QUERY_CODE: u32 = 5;
+
+/// The result of the epoch query
+#[derive(Debug, Serialize)]
+pub struct EpochQueryResult {
+    /// The epoch of the last committed block
+    pub epoch: Epoch,
+}
+
+impl Display for EpochQueryResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Last committed epoch: {}", self.epoch)
+    }
+}
+
+/// The balance of a token owned by an address. The amount is serialized as
+/// its decimal string, so it doesn't lose precision.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Balance {
+    /// The address of the token
+    pub token: Address,
+    /// The symbol of the token, or its address for the tokens without a
+    /// known symbol
+    pub symbol: String,
+    /// The owner of the balance
+    pub owner: Address,
+    /// The amount of the token owned
+    pub amount: token::Amount,
+}
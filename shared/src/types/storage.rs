@@ -82,6 +82,15 @@ impl From<BlockHeight> for u64 {
     }
 }
 
+impl FromStr for BlockHeight {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let raw: u64 = u64::from_str(s)?;
+        Ok(Self(raw))
+    }
+}
+
 /// Hash of a block as fixed-size byte array
 #[derive(
     Clone,
@@ -630,6 +639,16 @@ impl Default for Epochs {
 }
 
 impl Epochs {
+    /// Initialize predecessor epochs, assuming starting on the given epoch and
+    /// block height 0, e.g. for a chain initialized from the state of another
+    /// chain.
+    pub fn starting_from(first_known_epoch: Epoch) -> Self {
+        Self {
+            first_known_epoch,
+            first_block_heights: vec![BlockHeight::default()],
+        }
+    }
+
     /// Record start of a new epoch at the given block height and trim any
     /// epochs that ended more than `max_age_num_blocks` ago.
     pub fn new_epoch(
@@ -676,6 +695,13 @@ impl Epochs {
         }
         None
     }
+
+    /// Look-up the height of the first block of a given epoch, if the epoch
+    /// is known
+    pub fn get_start_height(&self, epoch: Epoch) -> Option<BlockHeight> {
+        let index = epoch.0.checked_sub(self.first_known_epoch.0)?;
+        self.first_block_heights.get(index as usize).copied()
+    }
 }
 
 #[cfg(feature = "ferveo-tpke")]
@@ -738,6 +764,26 @@ pub struct PrefixValue {
     pub value: Vec<u8>,
 }
 
+/// A page of the values of a storage prefix iterator, ordered by their keys.
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct PrefixPage {
+    /// The values of the page
+    pub values: Vec<PrefixValue>,
+    /// The key of the last value of the page, after which the next page
+    /// starts. `None` if this is the last page.
+    pub next: Option<Key>,
+}
+
+/// The values of multiple storage keys read at the same height
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct MultiValueResult {
+    /// The height at which the values were read
+    pub height: BlockHeight,
+    /// The keys paired with their raw values, in the order in which they
+    /// were requested. The value is `None` for a key that has no value.
+    pub values: Vec<(Key, Option<Vec<u8>>)>,
+}
+
 #[cfg(test)]
 mod tests {
     use proptest::prelude::*;
@@ -843,9 +889,14 @@ mod tests {
         assert_eq!(epochs.get_epoch(BlockHeight(20)), Some(Epoch(2)));
         assert_eq!(epochs.get_epoch(BlockHeight(100)), Some(Epoch(2)));
 
+        assert_eq!(epochs.get_start_height(Epoch(2)), Some(BlockHeight(20)));
+        assert_eq!(epochs.get_start_height(Epoch(3)), None);
+
         // epoch 3, epoch 0 and 1 should be trimmed
         epochs.new_epoch(BlockHeight(200), max_age_num_blocks);
         println!("epochs {:#?}", epochs);
+        assert_eq!(epochs.get_start_height(Epoch(1)), None);
+        assert_eq!(epochs.get_start_height(Epoch(3)), Some(BlockHeight(200)));
         assert_eq!(epochs.get_epoch(BlockHeight(0)), None);
         assert_eq!(epochs.get_epoch(BlockHeight(9)), None);
         assert_eq!(epochs.get_epoch(BlockHeight(10)), None);
@@ -9,7 +9,9 @@ use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::types::address::{Address, Error as AddressError, InternalAddress};
+use crate::types::address::{
+    self, Address, Error as AddressError, InternalAddress,
+};
 use crate::types::ibc::data::FungibleTokenPacketData;
 use crate::types::storage::{DbKeySeg, Key, KeySeg};
 
@@ -82,6 +84,29 @@ impl Amount {
             micro: change as u64,
         }
     }
+
+    /// Add the amounts, returning [`None`] on overflow
+    pub fn checked_add(&self, amount: Amount) -> Option<Self> {
+        self.micro
+            .checked_add(amount.micro)
+            .map(|micro| Self { micro })
+    }
+
+    /// Subtract the amounts, returning [`None`] on underflow
+    pub fn checked_sub(&self, amount: Amount) -> Option<Self> {
+        self.micro
+            .checked_sub(amount.micro)
+            .map(|micro| Self { micro })
+    }
+
+    /// Check if the amount can be expressed with the given number of decimal
+    /// places, i.e. if none of its lower decimal places is used
+    pub fn fits_decimals(&self, decimals: u8) -> bool {
+        match MAX_DECIMAL_PLACES.checked_sub(decimals as u32) {
+            Some(unused) => self.micro % 10_u64.pow(unused) == 0,
+            None => true,
+        }
+    }
 }
 
 impl serde::Serialize for Amount {
@@ -190,6 +215,13 @@ pub enum AmountParseError {
     ScaleTooLarge(u32),
     #[error("Error decoding token amount, the value is within invalid range.")]
     InvalidRange,
+    #[error(
+        "Error decoding token amount, too many decimal places for the token: \
+         {0}. Maximum {1}"
+    )]
+    TooManyDecimals(u32, u8),
+    #[error("Error decoding token amount, expected {0}, got {1}")]
+    UnexpectedSymbol(String, String),
 }
 
 impl FromStr for Amount {
@@ -202,8 +234,9 @@ impl FromStr for Amount {
                 if scale > 6 {
                     return Err(AmountParseError::ScaleTooLarge(scale));
                 }
-                let whole =
-                    decimal * rust_decimal::Decimal::new(SCALE as i64, 0);
+                let whole = decimal
+                    .checked_mul(rust_decimal::Decimal::new(SCALE as i64, 0))
+                    .ok_or(AmountParseError::InvalidRange)?;
                 let micro: u64 =
                     rust_decimal::prelude::ToPrimitive::to_u64(&whole)
                         .ok_or(AmountParseError::InvalidRange)?;
@@ -297,6 +330,270 @@ pub fn is_non_owner_balance_key(key: &Key) -> Option<&Address> {
     }
 }
 
+/// Key segment for an allowance key
+pub const ALLOWANCE_STORAGE_KEY: &str = "allowance";
+
+/// Obtain a storage key for the amount of the owner's tokens that the spender
+/// is approved to transfer.
+pub fn allowance_key(
+    token_addr: &Address,
+    owner: &Address,
+    spender: &Address,
+) -> Key {
+    allowance_prefix(token_addr, owner)
+        .push(&spender.to_db_key())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Obtain a storage key prefix for all the allowances given by the owner.
+pub fn allowance_prefix(token_addr: &Address, owner: &Address) -> Key {
+    allowances_prefix(token_addr)
+        .push(&owner.to_db_key())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Obtain a storage key prefix for all owners' allowances.
+pub fn allowances_prefix(token_addr: &Address) -> Key {
+    Key::from(token_addr.to_db_key())
+        .push(&ALLOWANCE_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Check if the given storage key is allowance key for the given token. If it
+/// is, returns the owner and the spender.
+pub fn is_allowance_key<'a>(
+    token_addr: &Address,
+    key: &'a Key,
+) -> Option<(&'a Address, &'a Address)> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(key),
+            DbKeySeg::AddressSeg(owner),
+            DbKeySeg::AddressSeg(spender),
+        ] if key == ALLOWANCE_STORAGE_KEY && addr == token_addr => {
+            Some((owner, spender))
+        }
+        _ => None,
+    }
+}
+
+/// Check if the given storage key is allowance key for unspecified token. If it
+/// is, returns the token, the owner and the spender.
+pub fn is_any_token_allowance_key(
+    key: &Key,
+) -> Option<(&Address, &Address, &Address)> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(token),
+            DbKeySeg::StringSeg(key),
+            DbKeySeg::AddressSeg(owner),
+            DbKeySeg::AddressSeg(spender),
+        ] if key == ALLOWANCE_STORAGE_KEY => Some((token, owner, spender)),
+        _ => None,
+    }
+}
+
+/// Key segment for a token's metadata key
+pub const METADATA_STORAGE_KEY: &str = "metadata";
+
+/// Key segment for a token's total supply key
+pub const TOTAL_SUPPLY_STORAGE_KEY: &str = "total_supply";
+
+/// The maximum length of a token's symbol
+pub const MAX_SYMBOL_LEN: usize = 12;
+
+/// Obtain a storage key for the token's metadata.
+pub fn metadata_key(token_addr: &Address) -> Key {
+    Key::from(token_addr.to_db_key())
+        .push(&METADATA_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Obtain a storage key for the token's total supply.
+pub fn total_supply_key(token_addr: &Address) -> Key {
+    Key::from(token_addr.to_db_key())
+        .push(&TOTAL_SUPPLY_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Check if the given storage key is the metadata key for the given token.
+pub fn is_metadata_key(token_addr: &Address, key: &Key) -> bool {
+    match &key.segments[..] {
+        [DbKeySeg::AddressSeg(addr), DbKeySeg::StringSeg(key)] => {
+            key == METADATA_STORAGE_KEY && addr == token_addr
+        }
+        _ => false,
+    }
+}
+
+/// Check if the given storage key is the total supply key for the given token.
+pub fn is_total_supply_key(token_addr: &Address, key: &Key) -> bool {
+    match &key.segments[..] {
+        [DbKeySeg::AddressSeg(addr), DbKeySeg::StringSeg(key)] => {
+            key == TOTAL_SUPPLY_STORAGE_KEY && addr == token_addr
+        }
+        _ => false,
+    }
+}
+
+/// Check if the given storage key is the total supply key of any token. If it
+/// is, returns the token.
+pub fn is_any_total_supply_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [DbKeySeg::AddressSeg(addr), DbKeySeg::StringSeg(key)]
+            if key == TOTAL_SUPPLY_STORAGE_KEY =>
+        {
+            Some(addr)
+        }
+        _ => None,
+    }
+}
+
+/// The metadata of a token, written into its storage when it's initialized.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+)]
+pub struct Metadata {
+    /// The token's symbol, unique in the token registry
+    pub symbol: String,
+    /// The number of decimal places used to display the token's amounts, up
+    /// to [`MAX_DECIMAL_PLACES`]
+    pub decimals: u8,
+    /// The maximum total supply of the token, if limited
+    pub max_supply: Option<Amount>,
+    /// The address that is allowed to mint and burn the token, if any. When
+    /// [`None`], the total supply is fixed.
+    pub minter: Option<Address>,
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum MetadataError {
+    #[error(
+        "Invalid token symbol {0}. It must be 1 to {MAX_SYMBOL_LEN} ASCII \
+         uppercase letters or digits."
+    )]
+    InvalidSymbol(String),
+    #[error("Too many decimal places: {0}. Maximum {MAX_DECIMAL_PLACES}")]
+    TooManyDecimals(u8),
+    #[error("The initial supply {0} exceeds the maximum supply {1}")]
+    SupplyExceeded(Amount, Amount),
+}
+
+impl Metadata {
+    /// Check that the metadata are valid for a new token with the given initial
+    /// supply.
+    pub fn validate(
+        &self,
+        initial_supply: Amount,
+    ) -> Result<(), MetadataError> {
+        let valid_symbol = !self.symbol.is_empty()
+            && self.symbol.len() <= MAX_SYMBOL_LEN
+            && self
+                .symbol
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+        if !valid_symbol {
+            return Err(MetadataError::InvalidSymbol(self.symbol.clone()));
+        }
+        if self.decimals as u32 > MAX_DECIMAL_PLACES {
+            return Err(MetadataError::TooManyDecimals(self.decimals));
+        }
+        match self.max_supply {
+            Some(max_supply) if initial_supply > max_supply => {
+                Err(MetadataError::SupplyExceeded(initial_supply, max_supply))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The symbol and the number of decimal places of a token, which are used to
+/// display the token's amounts and to parse them from the token's units.
+/// The amounts are always stored in micro units, so the decimal places only
+/// limit the precision of the amounts of the token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Denomination {
+    /// The token's symbol
+    pub symbol: String,
+    /// The number of decimal places of the token's amounts, up to
+    /// [`MAX_DECIMAL_PLACES`]
+    pub decimals: u8,
+}
+
+impl Denomination {
+    /// The denomination of the native tokens with a hard-coded currency code,
+    /// which use all the [`MAX_DECIMAL_PLACES`]
+    pub fn native(token: &Address) -> Option<Self> {
+        address::tokens().get(token).map(|symbol| Self {
+            symbol: symbol.to_string(),
+            decimals: MAX_DECIMAL_PLACES as u8,
+        })
+    }
+
+    /// Format the amount in the units of the token, e.g. `12.5 XAN`
+    pub fn format(&self, amount: Amount) -> String {
+        format!("{} {}", amount, self.symbol)
+    }
+
+    /// Parse an amount in the units of the token, optionally followed by the
+    /// token's symbol, e.g. `12.5` or `12.5 XAN`. The amount must not use more
+    /// decimal places than the token has.
+    pub fn parse(&self, amount: &str) -> Result<Amount, AmountParseError> {
+        let mut parts = amount.split_whitespace();
+        let value = parts.next().unwrap_or_default();
+        match (parts.next(), parts.next()) {
+            (None, _) => {}
+            (Some(symbol), None) if symbol == self.symbol => {}
+            (Some(_), _) => {
+                return Err(AmountParseError::UnexpectedSymbol(
+                    self.symbol.clone(),
+                    amount.to_string(),
+                ));
+            }
+        }
+        let amount = Amount::from_str(value)?;
+        self.check(amount)?;
+        Ok(amount)
+    }
+
+    /// Check that the amount doesn't use more decimal places than the token
+    /// has
+    pub fn check(&self, amount: Amount) -> Result<(), AmountParseError> {
+        if amount.fits_decimals(self.decimals) {
+            Ok(())
+        } else {
+            let scale = rust_decimal::Decimal::from_i128_with_scale(
+                amount.micro as i128,
+                MAX_DECIMAL_PLACES,
+            )
+            .normalize()
+            .scale();
+            Err(AmountParseError::TooManyDecimals(scale, self.decimals))
+        }
+    }
+}
+
+impl From<&Metadata> for Denomination {
+    fn from(metadata: &Metadata) -> Self {
+        Self {
+            symbol: metadata.symbol.clone(),
+            decimals: metadata.decimals,
+        }
+    }
+}
+
 /// A simple bilateral token transfer
 #[derive(
     Debug,
@@ -322,6 +619,59 @@ pub struct Transfer {
     pub amount: Amount,
 }
 
+/// An approval of a spender to transfer up to the given amount of the owner's
+/// tokens. The approved amount replaces any previous allowance.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+)]
+pub struct Approval {
+    /// The owner of the tokens
+    pub owner: Address,
+    /// The spender that is approved to transfer the owner's tokens
+    pub spender: Address,
+    /// Token's address
+    pub token: Address,
+    /// The approved amount of tokens
+    pub amount: Amount,
+}
+
+/// A token transfer made by a spender from the owner's allowance
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+)]
+pub struct TransferFrom {
+    /// The spender that is approved to transfer the owner's tokens
+    pub spender: Address,
+    /// Source address is the owner of the tokens
+    pub source: Address,
+    /// Target address will receive the tokens
+    pub target: Address,
+    /// Token's address
+    pub token: Address,
+    /// The amount of tokens
+    pub amount: Amount,
+}
+
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
 pub enum TransferError {
@@ -392,4 +742,104 @@ mod tests {
         let zero = Amount::from(0);
         assert_eq!("0", zero.to_string());
     }
+
+    #[test]
+    fn test_token_parse_overflow() {
+        assert!(matches!(
+            Amount::from_str("18446744073709.551616"),
+            Err(AmountParseError::InvalidRange)
+        ));
+        assert!(matches!(
+            Amount::from_str("79228162514264337593543950335"),
+            Err(AmountParseError::InvalidRange)
+        ));
+        assert!(matches!(
+            Amount::from_str("-1"),
+            Err(AmountParseError::InvalidRange)
+        ));
+        let max = Amount::max();
+        assert_eq!(max.checked_add(Amount::from(1)), None);
+        assert_eq!(Amount::from(0).checked_sub(Amount::from(1)), None);
+    }
+
+    #[test]
+    fn test_denomination() {
+        let xan = Denomination::native(&crate::types::address::xan()).unwrap();
+        assert_eq!(xan.parse("12.5").unwrap(), Amount::from(12_500_000));
+        assert_eq!(xan.parse("12.5 XAN").unwrap(), Amount::from(12_500_000));
+        assert_eq!(xan.format(Amount::from(12_500_000)), "12.5 XAN");
+        assert!(matches!(
+            xan.parse("12.5 BTC"),
+            Err(AmountParseError::UnexpectedSymbol(_, _))
+        ));
+
+        let cents = Denomination {
+            symbol: "USD".to_string(),
+            decimals: 2,
+        };
+        assert_eq!(cents.parse("0.01").unwrap(), Amount::from(10_000));
+        assert!(matches!(
+            cents.parse("0.001"),
+            Err(AmountParseError::TooManyDecimals(3, 2))
+        ));
+        assert!(cents.check(Amount::from(10_001)).is_err());
+        assert!(Amount::from(1).fits_decimals(6));
+        assert!(Amount::from(1_000_000).fits_decimals(0));
+        assert!(!Amount::from(1_000_001).fits_decimals(0));
+    }
+
+    #[test]
+    fn test_metadata_validation() {
+        let metadata = Metadata {
+            symbol: "ABC1".to_string(),
+            decimals: 6,
+            max_supply: Some(Amount::from(100)),
+            minter: None,
+        };
+        assert!(metadata.validate(Amount::from(100)).is_ok());
+        assert!(matches!(
+            metadata.validate(Amount::from(101)),
+            Err(MetadataError::SupplyExceeded(_, _))
+        ));
+        for symbol in ["", "abc", "A/B", "ABCDEFGHIJKLM"] {
+            let metadata = Metadata {
+                symbol: symbol.to_string(),
+                ..metadata.clone()
+            };
+            assert!(matches!(
+                metadata.validate(Amount::default()),
+                Err(MetadataError::InvalidSymbol(_))
+            ));
+        }
+        let metadata = Metadata {
+            decimals: 7,
+            ..metadata
+        };
+        assert!(matches!(
+            metadata.validate(Amount::default()),
+            Err(MetadataError::TooManyDecimals(7))
+        ));
+    }
+
+    #[test]
+    fn test_allowance_key() {
+        let token = crate::types::address::xan();
+        let owner = crate::types::address::testing::established_address_1();
+        let spender = crate::types::address::testing::established_address_2();
+        let key = allowance_key(&token, &owner, &spender);
+        assert_eq!(is_allowance_key(&token, &key), Some((&owner, &spender)));
+        assert_eq!(
+            is_any_token_allowance_key(&key),
+            Some((&token, &owner, &spender))
+        );
+        assert!(key
+            .to_string()
+            .starts_with(&allowance_prefix(&token, &owner).to_string()));
+        assert_eq!(is_allowance_key(&spender, &key), None);
+        assert_eq!(is_any_token_balance_key(&key), None);
+        assert_eq!(
+            is_allowance_key(&token, &balance_key(&token, &owner)),
+            None
+        );
+    }
 }
@@ -12,10 +12,12 @@ pub mod nft;
 pub mod pos;
 /// transaction protocols made by validators
 pub mod protocol;
+pub mod response;
+pub mod result_code;
 /// wrapper txs with encrypted payloads
 pub mod wrapper;
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
@@ -23,6 +25,8 @@ pub use decrypted::*;
 #[cfg(feature = "ferveo-tpke")]
 pub use encrypted::EncryptionKey;
 pub use protocol::UpdateDkgSessionKey;
+pub use response::TxResponse;
+pub use result_code::ResultCode;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 pub use wrapper::*;
@@ -33,6 +37,7 @@ use crate::ledger::gas::VpsGas;
 use crate::types::address::Address;
 use crate::types::hash::Hash;
 use crate::types::key::*;
+use crate::types::token;
 
 /// Get the hash of a transaction
 pub fn hash_tx(tx_bytes: &[u8]) -> Hash {
@@ -71,6 +76,9 @@ pub struct VpsResult {
     pub accepted_vps: BTreeSet<Address>,
     /// The addresses whose VPs rejected the transaction
     pub rejected_vps: BTreeSet<Address>,
+    /// The changed keys examined by each of the rejecting VPs, which are the
+    /// changed keys that contain its address
+    pub rejected_vp_keys: BTreeMap<Address, BTreeSet<storage::Key>>,
     /// The total gas used by all the VPs
     pub gas_used: VpsGas,
     /// Errors occurred in any of the VPs, if any
@@ -98,9 +106,17 @@ impl fmt::Display for VpsResult {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "{}{}{}",
+            "{}{}{}{}",
             iterable_to_string("Accepted", self.accepted_vps.iter()),
             iterable_to_string("Rejected", self.rejected_vps.iter()),
+            iterable_to_string(
+                "Rejected keys",
+                self.rejected_vp_keys.iter().map(|(addr, keys)| {
+                    let keys: Vec<String> =
+                        keys.iter().map(ToString::to_string).collect();
+                    format!("{} [{}]", addr, keys.join(", "))
+                })
+            ),
             iterable_to_string(
                 "Errors",
                 self.errors
@@ -148,6 +164,28 @@ pub struct UpdateVp {
     pub vp_code: Vec<u8>,
 }
 
+/// A tx data type to update an established account's public key and/or
+/// validity predicate, without changing its address
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct UpdateAccount {
+    /// An address of the account
+    pub addr: Address,
+    /// The new public key that replaces the account's current key, which must
+    /// sign this tx
+    pub public_key: Option<common::PublicKey>,
+    /// The new VP code
+    pub vp_code: Option<Vec<u8>>,
+}
+
 /// A tx data type to initialize a new established account
 #[derive(
     Debug,
@@ -168,6 +206,78 @@ pub struct InitAccount {
     pub vp_code: Vec<u8>,
 }
 
+/// A tx data type to promote an implicit account to a new established account.
+/// The established account is controlled by the implicit account's key and
+/// receives all the implicit account's balances of the given tokens.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct PromoteAccount {
+    /// The public key of the implicit account, to be written into the new
+    /// account's storage
+    pub public_key: common::PublicKey,
+    /// The VP code of the new account
+    pub vp_code: Vec<u8>,
+    /// The tokens whose balances are moved to the new account
+    pub tokens: Vec<Address>,
+}
+
+impl PromoteAccount {
+    /// The implicit account derived from the public key
+    pub fn source(&self) -> Address {
+        Address::from(&self.public_key)
+    }
+}
+
+/// A tx data type to initialize a new token account
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct InitToken {
+    /// The token's metadata. The token's symbol gets registered in the token
+    /// registry.
+    pub metadata: token::Metadata,
+    /// The amount of the token minted when it's initialized
+    pub initial_supply: token::Amount,
+    /// The address that receives the initial supply
+    pub initial_holder: Address,
+    /// The VP code
+    pub vp_code: Vec<u8>,
+}
+
+/// A tx data type to register an alias of an address in the name service
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct RegisterAlias {
+    /// The alias to register. It must not be taken yet.
+    pub alias: String,
+    /// The address that the alias resolves to, which pays the registration
+    /// fee and must authorize the tx
+    pub address: Address,
+}
+
 /// A tx data type to initialize a new validator account and its staking reward
 /// account.
 #[derive(
@@ -195,6 +305,11 @@ pub struct InitValidator {
     pub protocol_key: common::PublicKey,
     /// Serialization of the public session key used in the DKG
     pub dkg_key: DkgPublicKey,
+    /// The validator's commission rate in basis points
+    pub commission_rate: u64,
+    /// The validator's maximum change of the commission rate per epoch in
+    /// basis points
+    pub max_commission_rate_change: u64,
     /// The VP code for validator account
     pub validator_vp_code: Vec<u8>,
     /// The VP code for validator's staking reward account
@@ -344,6 +459,7 @@ pub mod tx_types {
     mod test_process_tx {
         use super::*;
         use crate::types::address::xan;
+        use crate::types::chain::ChainId;
         use crate::types::storage::Epoch;
 
         fn gen_keypair() -> common::SecretKey {
@@ -434,6 +550,7 @@ pub mod tx_types {
                 0.into(),
                 tx.clone(),
                 Default::default(),
+                ChainId::default(),
             )
             .sign(&keypair)
             .expect("Test failed");
@@ -469,6 +586,7 @@ pub mod tx_types {
                 0.into(),
                 tx,
                 Default::default(),
+                ChainId::default(),
             );
 
             let tx = Tx::new(
@@ -47,3 +47,25 @@ pub struct MintNft {
     /// The nft tokens
     pub tokens: Vec<NftToken>,
 }
+
+/// A tx data type to transfer a nft token to a new owner
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct TransferNft {
+    /// The nft address
+    pub address: Address,
+    /// The id of the transferred token
+    pub token_id: u64,
+    /// The address authorizing the transfer, either the current owner of the
+    /// token or one of its approved addresses
+    pub source: Address,
+    /// The new owner of the token
+    pub target: Address,
+}
@@ -4,6 +4,7 @@ use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use serde::{Deserialize, Serialize};
 
 use crate::types::address::Address;
+use crate::types::key::common;
 use crate::types::token;
 
 /// A bond is a validator's self-bond or a delegation from non-validator to a
@@ -33,6 +34,54 @@ pub struct Bond {
 /// An unbond of a bond.
 pub type Unbond = Bond;
 
+/// A batch of bonds from the same source to several validators, which are
+/// either all applied in the same tx or none of them is.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct BatchBond {
+    /// The validators' addresses with the amounts of tokens bonded to them
+    pub bonds: Vec<(Address, token::Amount)>,
+    /// Source address of the delegations, which must not be a validator,
+    /// because a validator can only bond tokens to itself
+    pub source: Address,
+}
+
+/// A redelegation of a delegation from a validator to another validator,
+/// without unbonding it.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct Redelegation {
+    /// The address of the validator from which the tokens are redelegated
+    pub src_validator: Address,
+    /// The address of the validator to which the tokens are redelegated
+    pub dest_validator: Address,
+    /// The amount of tokens
+    pub amount: token::Amount,
+    /// Source address of the delegation. A validator's self-bond cannot be
+    /// redelegated.
+    pub source: Address,
+}
+
 /// A withdrawal of an unbond.
 #[derive(
     Debug,
@@ -53,3 +102,92 @@ pub struct Withdraw {
     /// from self-bonds, the validator is also the source
     pub source: Option<Address>,
 }
+
+/// A change of the consensus key of a validator. The new key is used from the
+/// pipeline epoch.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct ConsensusKeyChange {
+    /// Validator address
+    pub validator: Address,
+    /// The new consensus key of the validator
+    pub consensus_key: common::PublicKey,
+}
+
+/// A claim of the rewards of a bond.
+pub type ClaimRewards = Withdraw;
+
+/// A change of the commission rate of a validator. The new rate is used from
+/// the pipeline epoch.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct CommissionRateChange {
+    /// Validator address
+    pub validator: Address,
+    /// The new commission rate of the validator in basis points
+    pub rate: u64,
+}
+
+/// A change of the display metadata of a validator, which replaces its
+/// previous metadata.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct ValidatorMetadataChange {
+    /// Validator address
+    pub validator: Address,
+    /// The display name of the validator
+    pub moniker: String,
+    /// The website of the validator
+    pub website: Option<String>,
+    /// The contact of the validator, e.g. an e-mail address
+    pub contact: Option<String>,
+}
+
+/// An unjailing of a jailed validator. The validator is back in the validator
+/// set from the pipeline epoch.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct Unjail {
+    /// Validator address
+    pub validator: Address,
+}
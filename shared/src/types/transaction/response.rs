@@ -0,0 +1,193 @@
+//! The results of the txs, as parsed from their events by the clients and as
+//! served from the indexed events by the ledger.
+
+use std::collections::{BTreeMap, HashMap};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::types::address::Address;
+use crate::types::transaction::ResultCode;
+
+/// The attributes of a tx event that make up a [`TxResponse`]
+const TX_EVENT_ATTRIBUTES: [&str; 9] = [
+    "hash",
+    "code",
+    "info",
+    "log",
+    "height",
+    "gas_used",
+    "initialized_accounts",
+    "rejected_vps",
+    "rejected_vp_keys",
+];
+
+/// A parsed event from tendermint relating to a transaction
+#[allow(missing_docs)]
+#[derive(
+    Clone, Debug, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+pub struct TxResponse {
+    pub info: String,
+    pub log: String,
+    pub height: String,
+    pub hash: String,
+    pub code: ResultCode,
+    pub gas_used: String,
+    pub initialized_accounts: Vec<Address>,
+    /// The addresses whose validity predicates rejected the tx
+    pub rejected_vps: Vec<Address>,
+    /// The changed keys examined by each of the rejecting validity predicates
+    pub rejected_vp_keys: BTreeMap<Address, Vec<String>>,
+}
+
+impl TxResponse {
+    /// Check if the payload of the tx could not be decrypted. The fee of its
+    /// wrapper has been charged, but the payload was not applied.
+    pub fn is_undecryptable(&self) -> bool {
+        self.code == ResultCode::Undecryptable
+    }
+
+    /// A message that explains why the tx was not applied and what can be
+    /// done about it. Returns `None` for an applied tx.
+    pub fn error_message(&self) -> Option<String> {
+        if self.code.is_ok() {
+            return None;
+        }
+        let mut message = format!(
+            "The transaction was not applied (code {}). {}",
+            self.code,
+            self.code.description()
+        );
+        if !self.rejected_vps.is_empty() {
+            let rejected_vps: Vec<String> =
+                self.rejected_vps.iter().map(Address::encode).collect();
+            message.push_str(&format!(
+                " Rejected by the validity predicates of: {}.",
+                rejected_vps.join(", ")
+            ));
+        }
+        for (address, keys) in &self.rejected_vp_keys {
+            if !keys.is_empty() {
+                message.push_str(&format!(
+                    " The validity predicate of {} examined the changed keys: \
+                     {}.",
+                    address,
+                    keys.join(", ")
+                ));
+            }
+        }
+        Some(message)
+    }
+
+    /// Make a response from the attributes of a tx event, as found in the
+    /// indexed results of the txs. Returns `None` if the event doesn't have a
+    /// numeric result code.
+    pub fn from_event_attributes(
+        attributes: &BTreeMap<String, String>,
+    ) -> Option<Self> {
+        let attribute = |key: &str| attributes.get(key).cloned();
+        let code = attribute("code")?.parse().ok()?;
+        Some(TxResponse {
+            info: attribute("info").unwrap_or_default(),
+            log: attribute("log").unwrap_or_default(),
+            height: attribute("height").unwrap_or_default(),
+            hash: attribute("hash").unwrap_or_default(),
+            code,
+            gas_used: attribute("gas_used")
+                .unwrap_or_else(|| String::from("0")),
+            initialized_accounts: json_attribute(
+                attributes,
+                "initialized_accounts",
+            ),
+            rejected_vps: json_attribute(attributes, "rejected_vps"),
+            rejected_vp_keys: json_attribute(attributes, "rejected_vp_keys"),
+        })
+    }
+
+    /// Make the responses of all the txs with an event of the given type in
+    /// the events of a block, as found in a websocket subscription to
+    /// Tendermint events. The events are grouped by their attributes, e.g.
+    /// `applied.hash`, with a value for each event, in the order of the
+    /// events. An attribute that isn't set on every event cannot be matched
+    /// with its event, so it's left out of the responses.
+    pub fn from_block_events(
+        events: &HashMap<String, Vec<String>>,
+        event_type: &str,
+    ) -> Vec<Self> {
+        let attribute_values = |attribute: &str| {
+            events.get(&format!("{}.{}", event_type, attribute))
+        };
+        let count = match attribute_values("hash") {
+            Some(hashes) => hashes.len(),
+            None => return vec![],
+        };
+        (0..count)
+            .filter_map(|index| {
+                let attributes: BTreeMap<String, String> = TX_EVENT_ATTRIBUTES
+                    .iter()
+                    .filter_map(|attribute| {
+                        let values = attribute_values(attribute)
+                            .filter(|values| values.len() == count)?;
+                        Some((attribute.to_string(), values[index].clone()))
+                    })
+                    .collect();
+                Self::from_event_attributes(&attributes)
+            })
+            .collect()
+    }
+}
+
+/// Decode an attribute of a tx event that's encoded as JSON, or the default
+/// value if the event doesn't have it
+fn json_attribute<T: DeserializeOwned + Default>(
+    attributes: &BTreeMap<String, String>,
+    key: &str,
+) -> T {
+    attributes
+        .get(key)
+        .and_then(|value| serde_json::from_str(value).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::address;
+
+    /// Test that the responses of the txs are made from the attributes of
+    /// the events of a block, leaving out the attributes that are not set on
+    /// every event.
+    #[test]
+    fn test_from_block_events() {
+        let account = address::testing::established_address_1();
+        let to_values = |values: &[&str]| -> Vec<String> {
+            values.iter().map(|value| value.to_string()).collect()
+        };
+        let events = HashMap::from([
+            ("applied.hash".to_owned(), to_values(&["a", "b"])),
+            ("applied.code".to_owned(), to_values(&["0", "8"])),
+            ("applied.info".to_owned(), to_values(&["ok", "rejected"])),
+            ("applied.log".to_owned(), to_values(&["", ""])),
+            ("applied.height".to_owned(), to_values(&["5", "5"])),
+            ("applied.gas_used".to_owned(), to_values(&["10", "20"])),
+            (
+                "applied.initialized_accounts".to_owned(),
+                vec![serde_json::to_string(&vec![account]).unwrap()],
+            ),
+            ("tm.event".to_owned(), to_values(&["NewBlock"])),
+        ]);
+
+        let responses = TxResponse::from_block_events(&events, "applied");
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0].hash, "a");
+        assert_eq!(responses[0].code, ResultCode::Ok);
+        assert_eq!(responses[1].hash, "b");
+        assert_eq!(responses[1].code, ResultCode::VpRejected);
+        assert_eq!(responses[1].gas_used, "20");
+        assert!(responses[0].initialized_accounts.is_empty());
+
+        assert!(TxResponse::from_block_events(&events, "accepted").is_empty());
+    }
+}
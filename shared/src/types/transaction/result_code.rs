@@ -0,0 +1,240 @@
+//! The result codes of the txs, which are set by the ledger in the events of
+//! the txs and decoded by the clients.
+
+use std::fmt;
+use std::str::FromStr;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+/// The result code of a tx. In the events, the code is encoded as its number.
+/// The numbers that are not known to this version are decoded as
+/// [`ResultCode::Unknown`], so that a client can still read the results from
+/// a newer ledger.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, BorshSerialize, BorshDeserialize,
+)]
+pub enum ResultCode {
+    /// The tx was applied
+    Ok,
+    /// The tx is malformed or can't be applied in the current state
+    InvalidTx,
+    /// The signature of the tx is invalid
+    InvalidSig,
+    /// The WASM code of the tx failed
+    WasmRuntimeError,
+    /// The tx was proposed in the wrong order
+    InvalidOrder,
+    /// The block contains more decrypted txs than the wrappers of the
+    /// previous block
+    ExtraTxs,
+    /// The payload of the tx could not be decrypted
+    Undecryptable,
+    /// The tx used more gas than its gas limit
+    GasExhausted,
+    /// The validity predicates of some addresses rejected the tx. The
+    /// addresses are in the `rejected_vps` attribute of the event.
+    VpRejected,
+    /// The tx was already applied
+    Replay,
+    /// The tx was made for an epoch that already ended
+    Expired,
+    /// A code that is not known to this version
+    Unknown(u32),
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseResultCodeError {
+    #[error("The result code {0} is not a number")]
+    NotANumber(String),
+}
+
+impl ResultCode {
+    /// All the known result codes
+    pub const ALL: [ResultCode; 11] = [
+        ResultCode::Ok,
+        ResultCode::InvalidTx,
+        ResultCode::InvalidSig,
+        ResultCode::WasmRuntimeError,
+        ResultCode::InvalidOrder,
+        ResultCode::ExtraTxs,
+        ResultCode::Undecryptable,
+        ResultCode::GasExhausted,
+        ResultCode::VpRejected,
+        ResultCode::Replay,
+        ResultCode::Expired,
+    ];
+
+    /// Find the known result code with the given number
+    pub fn from_u32(code: u32) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|known| u32::from(*known) == code)
+    }
+
+    /// Check if the code is for an applied tx
+    pub fn is_ok(&self) -> bool {
+        *self == ResultCode::Ok
+    }
+
+    /// A message that explains the result to the user, with what can be done
+    /// about it
+    pub fn description(&self) -> &'static str {
+        match self {
+            ResultCode::Ok => "The transaction was applied.",
+            ResultCode::InvalidTx => {
+                "The transaction is invalid. Check the transaction data and \
+                 the info of the result."
+            }
+            ResultCode::InvalidSig => {
+                "The signature of the transaction is invalid. Sign it with \
+                 the key of the fee payer and the chain ID of the ledger."
+            }
+            ResultCode::WasmRuntimeError => {
+                "The transaction code failed. Check the transaction data, \
+                 e.g. that the addresses and tokens exist."
+            }
+            ResultCode::InvalidOrder => {
+                "The transaction was proposed in the wrong order. Submit it \
+                 again."
+            }
+            ResultCode::ExtraTxs => {
+                "The transaction was proposed without a wrapper. Submit it \
+                 again."
+            }
+            ResultCode::Undecryptable => {
+                "The transaction could not be decrypted. The fee of its \
+                 wrapper was charged, but the transaction was not applied."
+            }
+            ResultCode::GasExhausted => {
+                "The transaction ran out of gas. Submit it again with a \
+                 higher gas limit."
+            }
+            ResultCode::VpRejected => {
+                "The validity predicates of some accounts rejected the \
+                 transaction. Check that it's signed with the keys of the \
+                 involved accounts and that it satisfies their rules."
+            }
+            ResultCode::Replay => {
+                "The transaction was already applied. Submit a new \
+                 transaction instead."
+            }
+            ResultCode::Expired => {
+                "The transaction was made for an epoch that already ended. \
+                 Submit it again."
+            }
+            ResultCode::Unknown(_) => {
+                "The result code is not known to this client. Check the info \
+                 of the result, or update the client."
+            }
+        }
+    }
+}
+
+impl From<ResultCode> for u32 {
+    fn from(code: ResultCode) -> u32 {
+        match code {
+            ResultCode::Ok => 0,
+            ResultCode::InvalidTx => 1,
+            ResultCode::InvalidSig => 2,
+            ResultCode::WasmRuntimeError => 3,
+            ResultCode::InvalidOrder => 4,
+            ResultCode::ExtraTxs => 5,
+            ResultCode::Undecryptable => 6,
+            ResultCode::GasExhausted => 7,
+            ResultCode::VpRejected => 8,
+            ResultCode::Replay => 9,
+            ResultCode::Expired => 10,
+            ResultCode::Unknown(code) => code,
+        }
+    }
+}
+
+impl From<ResultCode> for String {
+    fn from(code: ResultCode) -> String {
+        u32::from(code).to_string()
+    }
+}
+
+impl From<u32> for ResultCode {
+    fn from(code: u32) -> Self {
+        Self::from_u32(code).unwrap_or(ResultCode::Unknown(code))
+    }
+}
+
+impl FromStr for ResultCode {
+    type Err = ParseResultCodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let code = s
+            .parse::<u32>()
+            .map_err(|_| ParseResultCodeError::NotANumber(s.to_string()))?;
+        Ok(Self::from(code))
+    }
+}
+
+/// Displayed as its number, like in the events
+impl fmt::Display for ResultCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", u32::from(*self))
+    }
+}
+
+/// Serialized as its number, like in the events
+impl Serialize for ResultCode {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for ResultCode {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        Self::from_str(&code).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the result codes are decoded from their number.
+    #[test]
+    fn test_result_code_from_str() {
+        for code in ResultCode::ALL {
+            assert_eq!(code.to_string().parse::<ResultCode>(), Ok(code));
+        }
+        assert_eq!("8".parse::<ResultCode>(), Ok(ResultCode::VpRejected));
+        assert_eq!("100".parse::<ResultCode>(), Ok(ResultCode::Unknown(100)));
+        assert_eq!(ResultCode::Unknown(100).to_string(), "100");
+        assert!(matches!(
+            "vp-rejected".parse::<ResultCode>(),
+            Err(ParseResultCodeError::NotANumber(_))
+        ));
+    }
+
+    /// Test that the result codes are deserialized from their JSON strings.
+    #[test]
+    fn test_result_code_json() {
+        for code in ResultCode::ALL {
+            let json = serde_json::to_string(&code).unwrap();
+            assert_eq!(
+                serde_json::from_str::<ResultCode>(&json).unwrap(),
+                code
+            );
+        }
+        assert_eq!(
+            serde_json::from_str::<ResultCode>("\"100\"").unwrap(),
+            ResultCode::Unknown(100)
+        );
+        assert!(serde_json::from_str::<ResultCode>("\"ok\"").is_err());
+    }
+}
@@ -13,6 +13,7 @@ pub mod wrapper_tx {
 
     use crate::proto::Tx;
     use crate::types::address::Address;
+    use crate::types::chain::ChainId;
     use crate::types::key::*;
     use crate::types::storage::Epoch;
     use crate::types::token::Amount;
@@ -176,6 +177,24 @@ pub mod wrapper_tx {
         /// sha-2 hash of the inner transaction acting as a commitment
         /// the contents of the encrypted payload
         pub tx_hash: Hash,
+        /// The chain on which the tx is to be submitted. Because it's
+        /// covered by the signature, the tx cannot be replayed on another
+        /// chain
+        pub chain_id: ChainId,
+    }
+
+    /// A signed wrapper tx with the hashes of the wrapper and of its payload,
+    /// which are needed for monitoring its progress on chain
+    #[derive(Clone, Debug)]
+    pub struct WrappedTx {
+        /// The signed wrapper tx
+        pub tx: Tx,
+        /// The hash that identifies the event of the wrapper on chain
+        pub wrapper_hash: String,
+        /// The hash that identifies the event of the decrypted payload on
+        /// chain. With ABCI, the wrapper and its payload are applied
+        /// together, so there is no separate event for the payload.
+        pub decrypted_hash: Option<String>,
     }
 
     impl WrapperTx {
@@ -190,6 +209,7 @@ pub mod wrapper_tx {
             gas_limit: GasLimit,
             tx: Tx,
             encryption_key: EncryptionKey,
+            chain_id: ChainId,
         ) -> WrapperTx {
             let inner_tx = EncryptedTx::encrypt(&tx.to_bytes(), encryption_key);
             Self {
@@ -199,6 +219,7 @@ pub mod wrapper_tx {
                 gas_limit,
                 inner_tx,
                 tx_hash: hash_tx(&tx.to_bytes()),
+                chain_id,
             }
         }
 
@@ -255,6 +276,30 @@ pub mod wrapper_tx {
             .sign(keypair))
         }
 
+        /// Sign the wrapper transaction together with the hashes of the
+        /// wrapper and of its payload. With ABCI, the wrapper is identified
+        /// on chain by the hash of its payload.
+        pub fn sign_wrapped(
+            &self,
+            keypair: &common::SecretKey,
+        ) -> Result<WrappedTx, WrapperTxErr> {
+            let wrapper_hash = if !cfg!(feature = "ABCI") {
+                hash_tx(&self.try_to_vec().unwrap()).to_string()
+            } else {
+                self.tx_hash.to_string()
+            };
+            let decrypted_hash = if !cfg!(feature = "ABCI") {
+                Some(self.tx_hash.to_string())
+            } else {
+                None
+            };
+            Ok(WrappedTx {
+                tx: self.sign(keypair)?,
+                wrapper_hash,
+                decrypted_hash,
+            })
+        }
+
         /// Validate the signature of a wrapper tx
         pub fn validate_sig(
             &self,
@@ -347,6 +392,48 @@ pub mod wrapper_tx {
             ed25519::SigScheme::generate(&mut rng).try_to_sk().unwrap()
         }
 
+        /// Test that a wrapper signed with its hashes is identified on chain
+        /// by the hash of its payload with ABCI, or by its own hash with
+        /// ABCI++, and that it must be signed by its fee payer.
+        #[test]
+        fn test_sign_wrapped() {
+            let keypair = gen_keypair();
+            let tx = Tx::new(
+                "wasm code".as_bytes().to_owned(),
+                Some("transaction data".as_bytes().to_owned()),
+            );
+            let wrapper = WrapperTx::new(
+                Fee {
+                    amount: 10.into(),
+                    token: xan(),
+                },
+                &keypair,
+                Epoch(0),
+                0.into(),
+                tx.clone(),
+                Default::default(),
+                ChainId::default(),
+            );
+
+            let wrapped = wrapper.sign_wrapped(&keypair).expect("Test failed");
+            let tx_hash = hash_tx(&tx.to_bytes()).to_string();
+            if cfg!(feature = "ABCI") {
+                assert_eq!(wrapped.wrapper_hash, tx_hash);
+                assert_eq!(wrapped.decrypted_hash, None);
+            } else {
+                assert_eq!(
+                    wrapped.wrapper_hash,
+                    hash_tx(&wrapper.try_to_vec().unwrap()).to_string()
+                );
+                assert_eq!(wrapped.decrypted_hash, Some(tx_hash));
+            }
+
+            assert!(matches!(
+                wrapper.sign_wrapped(&gen_keypair()),
+                Err(WrapperTxErr::InvalidKeyPair)
+            ));
+        }
+
         /// We test that when we feed in a Tx and then decrypt it again
         /// that we get what we started with.
         #[test]
@@ -367,6 +454,7 @@ pub mod wrapper_tx {
                 0.into(),
                 tx.clone(),
                 Default::default(),
+                ChainId::default(),
             );
             assert!(wrapper.validate_ciphertext());
             let privkey = <EllipticCurve as PairingEngine>::G2Affine::prime_subgroup_generator();
@@ -393,6 +481,7 @@ pub mod wrapper_tx {
                 0.into(),
                 tx,
                 Default::default(),
+                ChainId::default(),
             );
             // give a incorrect commitment to the decrypted contents of the tx
             wrapper.tx_hash = Hash([0u8; 32]);
@@ -425,6 +514,7 @@ pub mod wrapper_tx {
                 0.into(),
                 tx,
                 Default::default(),
+                ChainId::default(),
             )
             .sign(&keypair)
             .expect("Test failed");
@@ -695,6 +695,123 @@ where
     Ok(iterators.insert(iter).id())
 }
 
+/// Storage reverse prefix iterator function exposed to the wasm VM Tx
+/// environment. It will try to get an iterator of the keys in the reverse
+/// order from the storage and return the corresponding ID of the iterator.
+pub fn tx_rev_iter_prefix<MEM, DB, H, CA>(
+    env: &TxEnv<MEM, DB, H, CA>,
+    prefix_ptr: u64,
+    prefix_len: u64,
+) -> TxResult<u64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let (prefix, gas) = env
+        .memory
+        .read_string(prefix_ptr, prefix_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_add_gas(env, gas)?;
+
+    tracing::debug!("tx_rev_iter_prefix {}", prefix);
+
+    let prefix =
+        Key::parse(prefix).map_err(TxRuntimeError::StorageDataError)?;
+
+    let storage = unsafe { env.ctx.storage.get() };
+    let iterators = unsafe { env.ctx.iterators.get() };
+    let (iter, gas) = storage.rev_iter_prefix(&prefix);
+    tx_add_gas(env, gas)?;
+    Ok(iterators.insert(iter).id())
+}
+
+/// Storage range iterator function exposed to the wasm VM Tx environment. It
+/// will try to get an iterator of the keys from `start` (inclusive) up to
+/// `end` (exclusive) from the storage and return the corresponding ID of the
+/// iterator.
+pub fn tx_iter_range<MEM, DB, H, CA>(
+    env: &TxEnv<MEM, DB, H, CA>,
+    start_ptr: u64,
+    start_len: u64,
+    end_ptr: u64,
+    end_len: u64,
+) -> TxResult<u64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let (start, end) =
+        tx_read_range(env, start_ptr, start_len, end_ptr, end_len)?;
+    tracing::debug!("tx_iter_range {}, {}", start, end);
+
+    let storage = unsafe { env.ctx.storage.get() };
+    let iterators = unsafe { env.ctx.iterators.get() };
+    let (iter, gas) = storage.iter_range(&start, &end);
+    tx_add_gas(env, gas)?;
+    Ok(iterators.insert(iter).id())
+}
+
+/// Storage reverse range iterator function exposed to the wasm VM Tx
+/// environment. It will try to get an iterator of the keys from `start`
+/// (inclusive) up to `end` (exclusive) in the reverse order from the storage
+/// and return the corresponding ID of the iterator.
+pub fn tx_rev_iter_range<MEM, DB, H, CA>(
+    env: &TxEnv<MEM, DB, H, CA>,
+    start_ptr: u64,
+    start_len: u64,
+    end_ptr: u64,
+    end_len: u64,
+) -> TxResult<u64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let (start, end) =
+        tx_read_range(env, start_ptr, start_len, end_ptr, end_len)?;
+    tracing::debug!("tx_rev_iter_range {}, {}", start, end);
+
+    let storage = unsafe { env.ctx.storage.get() };
+    let iterators = unsafe { env.ctx.iterators.get() };
+    let (iter, gas) = storage.rev_iter_range(&start, &end);
+    tx_add_gas(env, gas)?;
+    Ok(iterators.insert(iter).id())
+}
+
+/// Read the start and the end keys of a range iterator from the memory
+fn tx_read_range<MEM, DB, H, CA>(
+    env: &TxEnv<MEM, DB, H, CA>,
+    start_ptr: u64,
+    start_len: u64,
+    end_ptr: u64,
+    end_len: u64,
+) -> TxResult<(Key, Key)>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let (start, gas) = env
+        .memory
+        .read_string(start_ptr, start_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_add_gas(env, gas)?;
+    let (end, gas) = env
+        .memory
+        .read_string(end_ptr, end_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_add_gas(env, gas)?;
+    let start = Key::parse(start).map_err(TxRuntimeError::StorageDataError)?;
+    let end = Key::parse(end).map_err(TxRuntimeError::StorageDataError)?;
+    Ok((start, end))
+}
+
 /// Storage prefix iterator next function exposed to the wasm VM Tx environment.
 /// It will try to read from the write log first and if no entry found then from
 /// the storage.
@@ -1225,6 +1342,129 @@ where
     Ok(iterators.insert(iter).id())
 }
 
+/// Storage reverse prefix iterator function exposed to the wasm VM VP
+/// environment. It will try to get an iterator of the keys in the reverse
+/// order from the storage and return the corresponding ID of the iterator.
+pub fn vp_rev_iter_prefix<MEM, DB, H, EVAL, CA>(
+    env: &VpEnv<MEM, DB, H, EVAL, CA>,
+    prefix_ptr: u64,
+    prefix_len: u64,
+) -> vp_env::Result<u64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let (prefix, gas) = env
+        .memory
+        .read_string(prefix_ptr, prefix_len as _)
+        .map_err(|e| vp_env::RuntimeError::MemoryError(Box::new(e)))?;
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    vp_env::add_gas(gas_meter, gas)?;
+
+    let prefix =
+        Key::parse(prefix).map_err(vp_env::RuntimeError::StorageDataError)?;
+    tracing::debug!("vp_rev_iter_prefix {}", prefix);
+
+    let storage = unsafe { env.ctx.storage.get() };
+    let iter = vp_env::rev_iter_prefix(gas_meter, storage, &prefix)?;
+    let iterators = unsafe { env.ctx.iterators.get() };
+    Ok(iterators.insert(iter).id())
+}
+
+/// Storage range iterator function exposed to the wasm VM VP environment. It
+/// will try to get an iterator of the keys from `start` (inclusive) up to
+/// `end` (exclusive) from the storage and return the corresponding ID of the
+/// iterator.
+pub fn vp_iter_range<MEM, DB, H, EVAL, CA>(
+    env: &VpEnv<MEM, DB, H, EVAL, CA>,
+    start_ptr: u64,
+    start_len: u64,
+    end_ptr: u64,
+    end_len: u64,
+) -> vp_env::Result<u64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let (start, end) =
+        vp_read_range(env, start_ptr, start_len, end_ptr, end_len)?;
+    tracing::debug!("vp_iter_range {}, {}", start, end);
+
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let storage = unsafe { env.ctx.storage.get() };
+    let iter = vp_env::iter_range(gas_meter, storage, &start, &end)?;
+    let iterators = unsafe { env.ctx.iterators.get() };
+    Ok(iterators.insert(iter).id())
+}
+
+/// Storage reverse range iterator function exposed to the wasm VM VP
+/// environment. It will try to get an iterator of the keys from `start`
+/// (inclusive) up to `end` (exclusive) in the reverse order from the storage
+/// and return the corresponding ID of the iterator.
+pub fn vp_rev_iter_range<MEM, DB, H, EVAL, CA>(
+    env: &VpEnv<MEM, DB, H, EVAL, CA>,
+    start_ptr: u64,
+    start_len: u64,
+    end_ptr: u64,
+    end_len: u64,
+) -> vp_env::Result<u64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let (start, end) =
+        vp_read_range(env, start_ptr, start_len, end_ptr, end_len)?;
+    tracing::debug!("vp_rev_iter_range {}, {}", start, end);
+
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let storage = unsafe { env.ctx.storage.get() };
+    let iter = vp_env::rev_iter_range(gas_meter, storage, &start, &end)?;
+    let iterators = unsafe { env.ctx.iterators.get() };
+    Ok(iterators.insert(iter).id())
+}
+
+/// Read the start and the end keys of a range iterator from the memory
+fn vp_read_range<MEM, DB, H, EVAL, CA>(
+    env: &VpEnv<MEM, DB, H, EVAL, CA>,
+    start_ptr: u64,
+    start_len: u64,
+    end_ptr: u64,
+    end_len: u64,
+) -> vp_env::Result<(Key, Key)>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let (start, gas) = env
+        .memory
+        .read_string(start_ptr, start_len as _)
+        .map_err(|e| vp_env::RuntimeError::MemoryError(Box::new(e)))?;
+    vp_env::add_gas(gas_meter, gas)?;
+    let (end, gas) = env
+        .memory
+        .read_string(end_ptr, end_len as _)
+        .map_err(|e| vp_env::RuntimeError::MemoryError(Box::new(e)))?;
+    vp_env::add_gas(gas_meter, gas)?;
+    let start =
+        Key::parse(start).map_err(vp_env::RuntimeError::StorageDataError)?;
+    let end =
+        Key::parse(end).map_err(vp_env::RuntimeError::StorageDataError)?;
+    Ok((start, end))
+}
+
 /// Storage prefix iterator for prior state (before tx execution) function
 /// exposed to the wasm VM VP environment. It will try to read from the storage.
 ///
@@ -13,7 +13,7 @@ where
     DB: storage::DBIter<'iter>,
 {
     index: PrefixIteratorId,
-    iterators: HashMap<PrefixIteratorId, DB::PrefixIter>,
+    iterators: HashMap<PrefixIteratorId, storage::PrefixIter<'iter, DB>>,
 }
 
 impl<'iter, DB> PrefixIterators<'iter, DB>
@@ -21,7 +21,10 @@ where
     DB: storage::DBIter<'iter>,
 {
     /// Insert a new prefix iterator to the temporary storage.
-    pub fn insert(&mut self, iter: DB::PrefixIter) -> PrefixIteratorId {
+    pub fn insert(
+        &mut self,
+        iter: storage::PrefixIter<'iter, DB>,
+    ) -> PrefixIteratorId {
         let id = self.index;
         self.iterators.insert(id, iter);
         self.index = id.next_id();
@@ -32,7 +35,7 @@ where
     pub fn next(
         &mut self,
         id: PrefixIteratorId,
-    ) -> Option<<DB::PrefixIter as Iterator>::Item> {
+    ) -> Option<<storage::PrefixIter<'iter, DB> as Iterator>::Item> {
         self.iterators.get_mut(&id).and_then(|i| i.next())
     }
 
@@ -40,7 +43,7 @@ where
     pub fn get_mut(
         &mut self,
         id: PrefixIteratorId,
-    ) -> Option<&mut DB::PrefixIter> {
+    ) -> Option<&mut storage::PrefixIter<'iter, DB>> {
         self.iterators.get_mut(&id)
     }
 }
@@ -0,0 +1,30 @@
+//! The names of the tx events emitted by the ledger.
+//!
+//! With ABCI, a wrapper tx and its payload are applied together in the same
+//! block, so both of them emit an "applied" event. With ABCI++, a wrapper tx
+//! emits an "accepted" event when it's included in a block and its decrypted
+//! payload emits an "applied" event in a later block.
+//!
+//! Every tx event has an indexed `code` attribute with the number of its
+//! result code, so that the blocks with failed txs can be searched with a
+//! query like `applied.code > 0`.
+
+/// The type of the event emitted for a wrapper tx included in a block
+#[cfg(not(feature = "ABCI"))]
+pub const ACCEPTED_EVENT: &str = "accepted";
+/// The type of the event emitted for a wrapper tx included in a block
+#[cfg(feature = "ABCI")]
+pub const ACCEPTED_EVENT: &str = "applied";
+
+/// The type of the event emitted for an applied tx
+pub const APPLIED_EVENT: &str = "applied";
+
+/// The query key of the hash of an included wrapper tx
+#[cfg(not(feature = "ABCI"))]
+pub const ACCEPTED_QUERY_KEY: &str = "accepted.hash";
+/// The query key of the hash of an included wrapper tx
+#[cfg(feature = "ABCI")]
+pub const ACCEPTED_QUERY_KEY: &str = "applied.hash";
+
+/// The query key of the hash of an applied tx
+pub const APPLIED_QUERY_KEY: &str = "applied.hash";
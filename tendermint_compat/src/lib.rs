@@ -0,0 +1,36 @@
+//! A single set of tendermint types for the ABCI and ABCI++ builds.
+//!
+//! The ABCI build uses a stable fork of tendermint-rs, while the ABCI++ build
+//! uses a newer one. This crate re-exports the crates of the version
+//! selected with the `ABCI` or `ABCI-plus-plus` feature under the same names,
+//! so that the code using them doesn't have to be written for each version.
+//! The config and RPC client crates are behind the `rpc-abci` and `rpc`
+//! features, respectively.
+
+#![doc(html_favicon_url = "https://dev.anoma.net/master/favicon.png")]
+#![doc(html_logo_url = "https://dev.anoma.net/master/rustdoc-logo.png")]
+#![warn(missing_docs)]
+#![deny(rustdoc::broken_intra_doc_links)]
+#![deny(rustdoc::private_intra_doc_links)]
+
+#[cfg(all(feature = "ABCI", feature = "ABCI-plus-plus"))]
+compile_error!("`ABCI` and `ABCI-plus-plus` may not be used at the same time");
+
+#[cfg(not(feature = "ABCI"))]
+pub use tendermint;
+#[cfg(all(feature = "rpc", not(feature = "ABCI")))]
+pub use tendermint_config;
+#[cfg(all(feature = "rpc-abci", feature = "ABCI"))]
+pub use tendermint_config_abci as tendermint_config;
+#[cfg(not(feature = "ABCI"))]
+pub use tendermint_proto;
+#[cfg(feature = "ABCI")]
+pub use tendermint_proto_abci as tendermint_proto;
+#[cfg(all(feature = "rpc", not(feature = "ABCI")))]
+pub use tendermint_rpc;
+#[cfg(all(feature = "rpc-abci", feature = "ABCI"))]
+pub use tendermint_rpc_abci as tendermint_rpc;
+#[cfg(feature = "ABCI")]
+pub use tendermint_stable as tendermint;
+
+pub mod events;
@@ -0,0 +1,270 @@
+//! A harness for integration tests against an in-process Anoma ledger node.
+//!
+//! The [`TestNode`] runs the ledger's shell with a mock DB and stands in for
+//! Tendermint, so the blocks are only produced when the test asks for them.
+//! The txs are signed, wrapped and submitted the same way as by the `anomac`
+//! client and the state can be checked with the typed [`QueryClient`] or read
+//! directly from the storage of the node.
+//!
+//! The node is initialized with the dev genesis, whose accounts and keys are in
+//! [`anoma_apps::wallet::defaults`]. It needs a directory with the pre-built
+//! WASM code of the genesis VPs and of the submitted txs, which for this
+//! repository is built with `make build-wasm-scripts`.
+
+#![doc(html_favicon_url = "https://dev.anoma.net/master/favicon.png")]
+#![doc(html_logo_url = "https://dev.anoma.net/master/rustdoc-logo.png")]
+#![warn(missing_docs)]
+#![deny(rustdoc::broken_intra_doc_links)]
+#![deny(rustdoc::private_intra_doc_links)]
+
+pub mod query;
+
+use std::path::{Path, PathBuf};
+
+use anoma::proto::Tx;
+use anoma::types::address::Address;
+use anoma::types::key::common;
+use anoma::types::token;
+use anoma::types::transaction::result_code::ParseResultCodeError;
+use anoma::types::transaction::TxResponse;
+use anoma_apps::node::ledger::events::{Event, EventType};
+use anoma_apps::node::ledger::mock_node::{self, MockNode};
+use anoma_apps::wasm_loader;
+use anoma_sdk::tx::{self, WrappedTx};
+use anoma_sdk::{TxOptions, TxQueryResult};
+pub use query::QueryClient;
+use tempfile::TempDir;
+use thiserror::Error;
+
+/// The maximum number of blocks produced while waiting for the result of a tx
+const MAX_BLOCKS_PER_TX: u64 = 10;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Failed to create the base directory of the node: {0}")]
+    BaseDir(std::io::Error),
+    #[error("Failed to read the WASM code of the tx: {0}")]
+    ReadWasm(wasm_loader::Error),
+    #[error("The node failed: {0}")]
+    Node(mock_node::Error),
+    #[error("The query failed with code {code}: {info}")]
+    Query { code: u32, info: String },
+    #[error("Failed to decode the response of the query: {0}")]
+    Decode(std::io::Error),
+    #[error("No result of the tx {0} after {1} blocks")]
+    NoTxResult(String, u64),
+    #[error("Received an invalid tx result: {0}")]
+    InvalidResult(anoma_sdk::Error),
+    #[error("Received an invalid tx result code: {0}")]
+    InvalidResultCode(ParseResultCodeError),
+}
+
+/// Result of a function that may fail
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// An in-process ledger node with the helpers to submit txs to it
+#[derive(Debug)]
+pub struct TestNode {
+    node: MockNode,
+    wasm_dir: PathBuf,
+    options: TxOptions,
+    /// The base directory of the node, which is removed on drop
+    _base_dir: TempDir,
+}
+
+impl TestNode {
+    /// Start a node with the WASM code from the given directory. The txs are
+    /// submitted with the default [`TxOptions`].
+    pub fn new(wasm_dir: impl AsRef<Path>) -> Result<Self> {
+        let base_dir = tempfile::tempdir().map_err(Error::BaseDir)?;
+        let node = MockNode::new(base_dir.path(), wasm_dir.as_ref());
+        Ok(Self {
+            node,
+            wasm_dir: wasm_dir.as_ref().to_path_buf(),
+            options: TxOptions::default(),
+            _base_dir: base_dir,
+        })
+    }
+
+    /// Set the fee and gas settings of the submitted txs
+    pub fn with_options(mut self, options: TxOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// The in-process node, to read its state
+    pub fn node(&self) -> &MockNode {
+        &self.node
+    }
+
+    /// The in-process node, to produce blocks or change the block interval
+    pub fn node_mut(&mut self) -> &mut MockNode {
+        &mut self.node
+    }
+
+    /// The client of the queries of the node
+    pub fn query(&self) -> QueryClient<'_> {
+        QueryClient::new(&self.node)
+    }
+
+    /// Read the WASM code of a tx from the WASM directory
+    pub fn read_wasm(&self, file_name: impl AsRef<Path>) -> Result<Vec<u8>> {
+        wasm_loader::try_read_wasm(&self.wasm_dir, file_name)
+            .map_err(Error::ReadWasm)
+    }
+
+    /// Sign the given tx with the keypair, put it in a wrapper that pays the
+    /// fee from the keypair's implicit account and add it to the mempool of
+    /// the node. It's applied in the next produced blocks.
+    pub fn submit(
+        &mut self,
+        tx: Tx,
+        keypair: &common::SecretKey,
+    ) -> Result<WrappedTx> {
+        let wrapped = tx::sign_and_wrap(
+            tx,
+            keypair,
+            self.node.last_epoch(),
+            &self.options,
+            self.node.chain_id().clone(),
+        );
+        self.node
+            .submit_tx(wrapped.tx.to_bytes())
+            .map_err(Error::Node)?;
+        Ok(wrapped)
+    }
+
+    /// Submit the given tx like [`TestNode::submit`] and produce blocks until
+    /// it's applied. Returns the result of the tx, or the result of its
+    /// wrapper if the wrapper is rejected.
+    pub fn execute(
+        &mut self,
+        tx: Tx,
+        keypair: &common::SecretKey,
+    ) -> Result<TxQueryResult> {
+        let WrappedTx {
+            wrapper_hash,
+            decrypted_hash,
+            ..
+        } = self.submit(tx, keypair)?;
+        let tx_hash = decrypted_hash.as_ref().unwrap_or(&wrapper_hash);
+        for _ in 0..MAX_BLOCKS_PER_TX {
+            let block = self.node.produce_block().map_err(Error::Node)?;
+            for event in block.events {
+                let is_applied = matches!(event.event_type, EventType::Applied)
+                    && event.get("hash") == Some(tx_hash);
+                let is_rejected_wrapper = event.get("hash")
+                    == Some(&wrapper_hash)
+                    && event.get("code").map(String::as_str) != Some("0");
+                if is_applied || is_rejected_wrapper {
+                    return TxQueryResult::try_from(tx_response(event)?)
+                        .map_err(Error::InvalidResult);
+                }
+            }
+        }
+        Err(Error::NoTxResult(tx_hash.clone(), MAX_BLOCKS_PER_TX))
+    }
+
+    /// Transfer the `amount` of the `token` from the `source` to the `target`
+    /// and produce blocks until it's applied. The tx is signed with the given
+    /// keypair, which must be authorized by the validity predicate of the
+    /// source.
+    pub fn transfer(
+        &mut self,
+        keypair: &common::SecretKey,
+        source: Address,
+        target: Address,
+        token: Address,
+        amount: token::Amount,
+    ) -> Result<TxQueryResult> {
+        let tx_code = self.read_wasm(tx::TX_TRANSFER_WASM)?;
+        let tx = tx::transfer(tx_code, source, target, token, amount);
+        self.execute(tx, keypair)
+    }
+}
+
+/// Convert the event of a tx into the response of the tx found by the client
+fn tx_response(event: Event) -> Result<TxResponse> {
+    let attribute = |key: &str| event.get(key).cloned().unwrap_or_default();
+    Ok(TxResponse {
+        info: attribute("info"),
+        log: attribute("log"),
+        height: attribute("height"),
+        hash: attribute("hash"),
+        code: attribute("code")
+            .parse()
+            .map_err(Error::InvalidResultCode)?,
+        gas_used: attribute("gas_used"),
+        initialized_accounts: event
+            .get("initialized_accounts")
+            .and_then(|accounts| serde_json::from_str(accounts).ok())
+            .unwrap_or_default(),
+        rejected_vps: event
+            .get("rejected_vps")
+            .and_then(|addresses| serde_json::from_str(addresses).ok())
+            .unwrap_or_default(),
+        rejected_vp_keys: event
+            .get("rejected_vp_keys")
+            .and_then(|keys| serde_json::from_str(keys).ok())
+            .unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::address;
+    use anoma::types::storage::{BlockHeight, Epoch};
+    use anoma_apps::wallet::defaults;
+
+    use super::*;
+
+    /// The WASM directory of this repository
+    fn wasm_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../wasm")
+    }
+
+    /// Test that the blocks are only produced on demand and that the state
+    /// of the genesis can be queried.
+    #[test]
+    fn test_genesis_queries() {
+        let mut test = TestNode::new(wasm_dir()).unwrap();
+        assert_eq!(test.node().last_height(), BlockHeight(0));
+        assert_eq!(test.query().epoch().unwrap(), Epoch(0));
+        let balance = test
+            .query()
+            .balance(&address::xan(), &defaults::albert_address())
+            .unwrap();
+        assert_eq!(balance, token::Amount::whole(1_000_000));
+
+        test.node_mut().produce_blocks(2).unwrap();
+        assert_eq!(test.node().last_height(), BlockHeight(2));
+    }
+
+    /// Test that a transfer is applied and changes the balances.
+    #[test]
+    fn test_transfer() {
+        let mut test = TestNode::new(wasm_dir()).unwrap();
+        let amount = token::Amount::whole(10);
+        let result = test
+            .transfer(
+                &defaults::albert_keypair(),
+                defaults::albert_address(),
+                defaults::bertha_address(),
+                address::xan(),
+                amount,
+            )
+            .unwrap();
+        assert!(result.is_applied(), "{:?}", result);
+
+        let query = test.query();
+        let albert_balance = query
+            .balance(&address::xan(), &defaults::albert_address())
+            .unwrap();
+        let bertha_balance = query
+            .balance(&address::xan(), &defaults::bertha_address())
+            .unwrap();
+        assert_eq!(albert_balance, token::Amount::whole(1_000_000) - amount);
+        assert_eq!(bertha_balance, token::Amount::whole(1_000_000) + amount);
+    }
+}
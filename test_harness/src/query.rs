@@ -0,0 +1,109 @@
+//! A typed client of the queries served by the in-process node, which mirrors
+//! the ABCI queries of the `anomac` client.
+
+use anoma::proto::Tx;
+use anoma::types::address::Address;
+use anoma::types::storage::{self, Epoch, PrefixValue};
+use anoma::types::token;
+use anoma_apps::node::ledger::mock_node::MockNode;
+use anoma_apps::node::ledger::rpc::Path;
+use borsh::BorshDeserialize;
+
+use crate::{Error, Result};
+
+/// The client of the queries of a [`MockNode`]
+#[derive(Clone, Copy, Debug)]
+pub struct QueryClient<'a> {
+    node: &'a MockNode,
+}
+
+impl<'a> QueryClient<'a> {
+    /// Create a client of the given node
+    pub fn new(node: &'a MockNode) -> Self {
+        Self { node }
+    }
+
+    /// Query the epoch of the last committed block
+    pub fn epoch(&self) -> Result<Epoch> {
+        let value = self.query(Path::Epoch, vec![])?;
+        Epoch::try_from_slice(&value).map_err(Error::Decode)
+    }
+
+    /// Query a storage value and decode it with [`BorshDeserialize`]. Returns
+    /// `None` if the key has no value.
+    pub fn value<T: BorshDeserialize>(
+        &self,
+        key: &storage::Key,
+    ) -> Result<Option<T>> {
+        match self.query(Path::Value(key.clone()), vec![]) {
+            Ok(value) => {
+                T::try_from_slice(&value).map(Some).map_err(Error::Decode)
+            }
+            Err(Error::Query { code: 1, .. }) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Query the storage values with a matching key prefix and decode them
+    /// with [`BorshDeserialize`]
+    pub fn prefix<T: BorshDeserialize>(
+        &self,
+        prefix: &storage::Key,
+    ) -> Result<Vec<(storage::Key, T)>> {
+        let value = match self.query(Path::Prefix(prefix.clone()), vec![]) {
+            Ok(value) => value,
+            Err(Error::Query { code: 1, .. }) => return Ok(vec![]),
+            Err(err) => return Err(err),
+        };
+        Vec::<PrefixValue>::try_from_slice(&value)
+            .map_err(Error::Decode)?
+            .into_iter()
+            .map(|PrefixValue { key, value }| {
+                T::try_from_slice(&value)
+                    .map(|value| (key, value))
+                    .map_err(Error::Decode)
+            })
+            .collect()
+    }
+
+    /// Query if the given storage key has a value
+    pub fn has_key(&self, key: &storage::Key) -> Result<bool> {
+        let value = self.query(Path::HasKey(key.clone()), vec![])?;
+        bool::try_from_slice(&value).map_err(Error::Decode)
+    }
+
+    /// Query the balance of a token owned by the given address
+    pub fn balance(
+        &self,
+        token: &Address,
+        owner: &Address,
+    ) -> Result<token::Amount> {
+        let key = token::balance_key(token, owner);
+        Ok(self.value(&key)?.unwrap_or_default())
+    }
+
+    /// Dry run a tx against the last committed state. Returns the result of
+    /// the tx, without applying it.
+    pub fn dry_run(&self, tx: &Tx) -> Result<String> {
+        let response =
+            self.node.query(&Path::DryRunTx, tx.to_bytes(), None, false);
+        match response.code {
+            0 => Ok(response.info),
+            code => Err(Error::Query {
+                code,
+                info: response.log,
+            }),
+        }
+    }
+
+    fn query(&self, path: Path, data: Vec<u8>) -> Result<Vec<u8>> {
+        let response = self.node.query(&path, data, None, false);
+        match response.code {
+            0 => Ok(response.value.to_vec()),
+            code => Err(Error::Query {
+                code,
+                info: response.info,
+            }),
+        }
+    }
+}
@@ -0,0 +1,297 @@
+//! Property tests of the determinism of the txs and VPs execution. Every
+//! validator must get the same result from the same tx applied on the same
+//! state, so the tests execute arbitrary txs with arbitrary gas limits twice
+//! on identical storages and check that the results, the gas used, the changes
+//! of the tx and the resulting Merkle root are the same.
+//!
+//! The second execution uses the WASM compilation caches warmed up by the
+//! first one, so that the tests also check that the cached modules behave the
+//! same as the freshly compiled ones.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anoma::ledger::gas::{BlockGasMeter, VpGasMeter};
+use anoma::ledger::storage::testing::TestStorage;
+use anoma::ledger::storage::write_log::{StorageModification, WriteLog};
+use anoma::proto::Tx;
+use anoma::types::address::testing::arb_non_internal_address;
+use anoma::types::address::Address;
+use anoma::types::key;
+use anoma::types::storage::testing::arb_key_no_vp;
+use anoma::types::storage::Key;
+use anoma::vm::wasm::compilation_cache::common::testing::cache;
+use anoma::vm::wasm::{run, TxCache, VpCache};
+use anoma::vm::WasmCacheRwAccess;
+use borsh::BorshSerialize;
+use proptest::collection;
+use proptest::prelude::*;
+
+const TX_MEMORY_LIMIT_WASM: &str = "../wasm_for_tests/tx_memory_limit.wasm";
+const TX_NO_OP_WASM: &str = "../wasm_for_tests/tx_no_op.wasm";
+const TX_READ_STORAGE_KEY_WASM: &str =
+    "../wasm_for_tests/tx_read_storage_key.wasm";
+const TX_WRITE_STORAGE_KEY_WASM: &str =
+    "../wasm_for_tests/tx_write_storage_key.wasm";
+const VP_ALWAYS_TRUE_WASM: &str = "../wasm_for_tests/vp_always_true.wasm";
+const VP_ALWAYS_FALSE_WASM: &str = "../wasm_for_tests/vp_always_false.wasm";
+const VP_READ_STORAGE_KEY_WASM: &str =
+    "../wasm_for_tests/vp_read_storage_key.wasm";
+
+/// The maximum gas of a block, which is also the upper bound of the generated
+/// tx gas limits
+const MAX_BLOCK_GAS: u64 = 10_000_000;
+
+/// A tx to execute, with the data of its WASM code
+#[derive(Clone, Debug)]
+enum TestTx {
+    /// A tx that does nothing with arbitrary data
+    NoOp(Vec<u8>),
+    /// A tx that reads the key and fails if it has no value
+    ReadKey(Key),
+    /// A tx that writes an arbitrary value to the key
+    WriteKey(Key),
+    /// A tx that allocates the given number of bytes
+    AllocateMemory(usize),
+    /// An arbitrary code, which is most likely not a valid WASM module
+    ArbitraryCode(Vec<u8>),
+}
+
+/// A VP to execute after the tx, with the data of its tx
+#[derive(Clone, Debug)]
+enum TestVp {
+    AlwaysTrue,
+    AlwaysFalse,
+    /// A VP that reads the prior value of the key and fails if it has none
+    ReadKey(Key),
+}
+
+/// Everything observable from the execution of a tx and a VP
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    /// The verifiers of the tx or the message of its error
+    tx_result: Result<BTreeSet<Address>, String>,
+    tx_gas: u64,
+    /// The modified keys with their new value, `None` for a deleted key
+    changes: BTreeMap<Key, Option<Vec<u8>>>,
+    /// The result of the VP or the message of its error
+    vp_result: Result<bool, String>,
+    vp_gas: u64,
+    /// The Merkle root after the changes of an accepted tx are committed
+    merkle_root: Vec<u8>,
+}
+
+/// Generate an arbitrary storage state, a tx and a VP that refer to its keys
+/// and the gas limit of the tx.
+fn arb_execution(
+) -> impl Strategy<Value = (BTreeMap<Key, Vec<u8>>, TestTx, TestVp, u64)> {
+    collection::btree_map(
+        arb_key_no_vp(),
+        collection::vec(any::<u8>(), 0..64),
+        1..20,
+    )
+    .prop_flat_map(|state| {
+        let keys: Vec<Key> = state.keys().cloned().collect();
+        // A key of the state or a key without a value
+        let arb_key = prop_oneof![
+            3 => proptest::sample::select(keys),
+            1 => arb_key_no_vp(),
+        ]
+        .boxed();
+        let arb_tx = prop_oneof![
+            collection::vec(any::<u8>(), 0..256).prop_map(TestTx::NoOp),
+            arb_key.clone().prop_map(TestTx::ReadKey),
+            arb_key.clone().prop_map(TestTx::WriteKey),
+            (0..1024 * 1024_usize).prop_map(TestTx::AllocateMemory),
+            collection::vec(any::<u8>(), 0..256)
+                .prop_map(TestTx::ArbitraryCode),
+        ];
+        let arb_vp = prop_oneof![
+            Just(TestVp::AlwaysTrue),
+            Just(TestVp::AlwaysFalse),
+            arb_key.prop_map(TestVp::ReadKey),
+        ];
+        (Just(state), arb_tx, arb_vp, 0..MAX_BLOCK_GAS)
+    })
+}
+
+/// Apply the state to a new storage and execute the tx and the VP on it
+fn execute(
+    state: &BTreeMap<Key, Vec<u8>>,
+    (tx_code, tx_data): &(Vec<u8>, Vec<u8>),
+    (vp_code, vp_tx): &(Vec<u8>, Tx),
+    vp_owner: &Address,
+    tx_gas_limit: u64,
+    vp_cache: &mut VpCache<WasmCacheRwAccess>,
+    tx_cache: &mut TxCache<WasmCacheRwAccess>,
+) -> Outcome {
+    let mut storage = TestStorage::default();
+    for (key, value) in state {
+        storage.write(key, value).expect("write to the storage");
+    }
+    let mut write_log = WriteLog::default();
+    let mut gas_meter = BlockGasMeter::new(MAX_BLOCK_GAS, tx_gas_limit);
+
+    let tx_result = run::tx(
+        &storage,
+        &mut write_log,
+        &mut gas_meter,
+        tx_code,
+        tx_data,
+        vp_cache,
+        tx_cache,
+    )
+    .map_err(|err| err.to_string());
+    let tx_gas = gas_meter.get_current_transaction_gas();
+    let changes = write_log
+        .get_keys()
+        .into_iter()
+        .map(|key| {
+            let (value, _gas) = write_log.read(&key);
+            let value = value.and_then(|modification| match modification {
+                StorageModification::Write { value }
+                | StorageModification::Temp { value }
+                | StorageModification::InitAccount { vp: value } => {
+                    Some(value.clone())
+                }
+                StorageModification::Delete => None,
+            });
+            (key, value)
+        })
+        .collect();
+
+    let verifiers = tx_result.clone().unwrap_or_default();
+    let mut vp_gas_meter = VpGasMeter::new(tx_gas);
+    let vp_result = run::vp(
+        vp_code,
+        vp_tx,
+        vp_owner,
+        &storage,
+        &write_log,
+        &mut vp_gas_meter,
+        &write_log.get_keys(),
+        &verifiers,
+        vp_cache.clone(),
+    )
+    .map_err(|err| err.to_string());
+
+    if tx_result.is_ok() {
+        write_log.commit_tx();
+    } else {
+        write_log.drop_tx();
+    }
+    write_log
+        .commit_block(&mut storage)
+        .expect("commit the block write log");
+
+    Outcome {
+        tx_result,
+        tx_gas,
+        changes,
+        vp_result,
+        vp_gas: vp_gas_meter.current_gas,
+        merkle_root: storage.merkle_root().0,
+    }
+}
+
+/// The WASM code and the data of the tx
+fn tx_code_and_data(tx: TestTx) -> (Vec<u8>, Vec<u8>) {
+    let read = |path| std::fs::read(path).expect("cannot load wasm");
+    match tx {
+        TestTx::NoOp(data) => (read(TX_NO_OP_WASM), data),
+        TestTx::ReadKey(key) => {
+            (read(TX_READ_STORAGE_KEY_WASM), key.try_to_vec().unwrap())
+        }
+        TestTx::WriteKey(key) => {
+            // The data is signed only once, so that both the executions get
+            // the same timestamp
+            let signed = Tx::new(vec![], Some(key.to_string().into_bytes()))
+                .sign(&key::testing::keypair_1());
+            (read(TX_WRITE_STORAGE_KEY_WASM), signed.data.unwrap())
+        }
+        TestTx::AllocateMemory(len) => {
+            (read(TX_MEMORY_LIMIT_WASM), len.try_to_vec().unwrap())
+        }
+        TestTx::ArbitraryCode(code) => (code, vec![]),
+    }
+}
+
+/// The WASM code of the VP and the tx it validates
+fn vp_code_and_tx(vp: TestVp) -> (Vec<u8>, Tx) {
+    let read = |path| std::fs::read(path).expect("cannot load wasm");
+    match vp {
+        TestVp::AlwaysTrue => {
+            (read(VP_ALWAYS_TRUE_WASM), Tx::new(vec![], None))
+        }
+        TestVp::AlwaysFalse => {
+            (read(VP_ALWAYS_FALSE_WASM), Tx::new(vec![], None))
+        }
+        TestVp::ReadKey(key) => (
+            read(VP_READ_STORAGE_KEY_WASM),
+            Tx::new(vec![], Some(key.try_to_vec().unwrap())),
+        ),
+    }
+}
+
+proptest! {
+    // The WASM modules are compiled in every case, so the cases are fewer
+    // than the default
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    /// Test that executing the same tx and VP twice on the same state gives
+    /// the same outcome.
+    #[test]
+    fn test_tx_and_vp_determinism(
+        (state, tx, vp, tx_gas_limit) in arb_execution(),
+        vp_owner in arb_non_internal_address(),
+    ) {
+        let tx = tx_code_and_data(tx);
+        let vp = vp_code_and_tx(vp);
+        let (mut vp_cache, _vp_cache_dir) = cache();
+        let (mut tx_cache, _tx_cache_dir) = cache();
+
+        let first = execute(
+            &state,
+            &tx,
+            &vp,
+            &vp_owner,
+            tx_gas_limit,
+            &mut vp_cache,
+            &mut tx_cache,
+        );
+        let second = execute(
+            &state,
+            &tx,
+            &vp,
+            &vp_owner,
+            tx_gas_limit,
+            &mut vp_cache,
+            &mut tx_cache,
+        );
+        prop_assert_eq!(first, second);
+    }
+
+    /// Test that the Merkle root of a storage doesn't depend on the order in
+    /// which its values are written.
+    #[test]
+    fn test_merkle_root_determinism(
+        state in collection::vec(
+            (arb_key_no_vp(), collection::vec(any::<u8>(), 0..64)),
+            1..20,
+        ),
+    ) {
+        // Keep only the last value of a duplicate key, like the storage does
+        let expected: BTreeMap<Key, Vec<u8>> = state.iter().cloned().collect();
+        let mut storage = TestStorage::default();
+        for (key, value) in &state {
+            storage.write(key, value).unwrap();
+        }
+        let mut reversed_storage = TestStorage::default();
+        for (key, value) in expected.iter().rev() {
+            reversed_storage.write(key, value).unwrap();
+        }
+        prop_assert_eq!(
+            storage.merkle_root().0,
+            reversed_storage.merkle_root().0
+        );
+    }
+}
@@ -371,7 +371,7 @@ fn ledger_txs_and_queries() -> Result<()> {
                 &validator_one_rpc,
             ],
             // expect a decimal
-            r"XAN: \d+(\.\d+)?",
+            r"\d+(\.\d+)? XAN",
         ),
     ];
     for (query_args, expected) in &query_args_and_expected_response {
@@ -476,7 +476,8 @@ fn invalid_transactions() -> Result<()> {
     }
     client.exp_string("Transaction applied")?;
     client.exp_string("Transaction is invalid")?;
-    client.exp_string(r#""code": "1"#)?;
+    client.exp_string(r#""code": "8"#)?;
+    client.exp_string("rejected_vp_keys")?;
 
     client.assert_success();
     let mut ledger = bg_ledger.foreground();
@@ -1145,7 +1146,7 @@ fn proposal_submission() -> Result<()> {
     ];
 
     let mut client = run!(test, Bin::Client, query_balance_args, Some(40))?;
-    client.exp_string("XAN: 999500")?;
+    client.exp_string("999500 XAN")?;
     client.assert_success();
 
     // 5. Query token balance governance
@@ -1160,7 +1161,7 @@ fn proposal_submission() -> Result<()> {
     ];
 
     let mut client = run!(test, Bin::Client, query_balance_args, Some(40))?;
-    client.exp_string("XAN: 500")?;
+    client.exp_string("500 XAN")?;
     client.assert_success();
 
     // 6. Submit an invalid proposal
@@ -1243,7 +1244,7 @@ fn proposal_submission() -> Result<()> {
     ];
 
     let mut client = run!(test, Bin::Client, query_balance_args, Some(40))?;
-    client.exp_string("XAN: 999500")?;
+    client.exp_string("999500 XAN")?;
     client.assert_success();
 
     // 9. Send a yay vote from a validator
@@ -1348,7 +1349,7 @@ fn proposal_submission() -> Result<()> {
     ];
 
     let mut client = run!(test, Bin::Client, query_balance_args, Some(30))?;
-    client.exp_string("XAN: 1000000")?;
+    client.exp_string("1000000 XAN")?;
     client.assert_success();
 
     // 13. Check if governance funds are 0
@@ -1363,7 +1364,7 @@ fn proposal_submission() -> Result<()> {
     ];
 
     let mut client = run!(test, Bin::Client, query_balance_args, Some(30))?;
-    client.exp_string("XAN: 0")?;
+    client.exp_string("0 XAN")?;
     client.assert_success();
 
     // // 14. Query parameters
@@ -661,6 +661,8 @@ where
 
     run_cmd
         .env("ANOMA_LOG", "anoma=info")
+        // Sign the txs without asking for a confirmation
+        .env("ANOMA_ASSUME_YES", "true")
         .current_dir(working_dir)
         .args(&[
             "--base-dir",
@@ -8,6 +8,8 @@
 mod vm_host_env;
 pub use vm_host_env::{ibc, tx, vp};
 #[cfg(test)]
+mod determinism;
+#[cfg(test)]
 mod e2e;
 #[cfg(test)]
 mod native_vp;
@@ -1651,3 +1651,411 @@ pub mod testing {
             > validator_set.active.len().try_into().unwrap()
     }
 }
+
+/// Tests of the PoS VP with the storage changes of concrete PoS txs
+#[cfg(test)]
+mod tx_tests {
+    use anoma::ledger::pos::anoma_proof_of_stake::PosBase;
+    use anoma::ledger::pos::types::ValidatorState;
+    use anoma::ledger::pos::vp as pos_vp;
+    use anoma::ledger::pos::{
+        bond_key, BasisPoints, BondId, GenesisValidator, PosParams,
+    };
+    use anoma::types::address::testing::{
+        established_address_1, gen_established_address,
+    };
+    use anoma::types::key::testing::{gen_keypair, keypair_1, keypair_2};
+    use anoma::types::key::{common, RefTo};
+    use anoma::types::storage::Epoch;
+    use anoma::types::token;
+    use anoma_vm_env::proof_of_stake::{
+        self as pos, staking_token_address, PosVP,
+    };
+    use anoma_vm_env::tx_prelude::{Address, PoS, PosRead, PosWrite};
+    use test_log::test;
+
+    use crate::native_vp::TestNativeVpEnv;
+    use crate::tx::{tx_host_env, TestTxEnv};
+
+    /// Initialize the PoS genesis with two validators and credit the
+    /// `delegator` with tokens to bond. Returns the validators' addresses.
+    fn init_pos(params: &PosParams, delegator: &Address) -> [Address; 2] {
+        let validators =
+            [keypair_1(), keypair_2()].map(|keypair| GenesisValidator {
+                address: gen_established_address(),
+                staking_reward_address: gen_established_address(),
+                tokens: token::Amount::whole(200_000),
+                consensus_key: keypair.ref_to(),
+                staking_reward_key: keypair.ref_to(),
+                commission_rate: BasisPoints::new(500),
+                max_commission_rate_change: BasisPoints::new(100),
+            });
+
+        let mut tx_env = TestTxEnv::default();
+        tx_env
+            .storage
+            .init_genesis(params, validators.iter(), Epoch(0))
+            .unwrap();
+        tx_env.spawn_accounts([staking_token_address(), delegator.clone()]);
+        tx_env.spawn_accounts(validators.iter().map(|v| &v.address));
+        tx_env.credit_tokens(
+            delegator,
+            &staking_token_address(),
+            token::Amount::whole(1_000),
+        );
+        tx_host_env::set(tx_env);
+        tx_host_env::commit_tx_and_block();
+
+        validators.map(|validator| validator.address)
+    }
+
+    /// Validate the changes of the current tx with the PoS VP
+    fn validate_tx() -> bool {
+        try_validate_tx().expect("Validation must not fail")
+    }
+
+    /// Validate the changes of the current tx with the PoS VP, which may fail
+    /// with the reason of the rejection
+    fn try_validate_tx() -> Result<bool, pos_vp::Error> {
+        let tx_env = tx_host_env::take();
+        let vp_env = TestNativeVpEnv::new(tx_env);
+        let result = vp_env.validate_tx(PosVP::new, |_tx_data| {});
+        // Put the tx_env back before checking the result
+        tx_host_env::set(vp_env.tx_env);
+        result
+    }
+
+    /// Delegate tokens from the `delegator` to the `validator` and commit
+    /// the tx
+    fn delegate(delegator: &Address, validator: &Address, amount: u64) {
+        pos::bond_tokens(
+            Some(delegator),
+            validator,
+            token::Amount::whole(amount),
+        )
+        .unwrap();
+        assert!(validate_tx());
+        tx_host_env::commit_tx_and_block();
+    }
+
+    /// Test that a redelegation is accepted
+    #[test]
+    fn test_redelegation_accepted() {
+        let params = PosParams::default();
+        let delegator = established_address_1();
+        let [src, dest] = init_pos(&params, &delegator);
+        delegate(&delegator, &src, 100);
+
+        pos::redelegate_tokens(
+            &delegator,
+            &src,
+            &dest,
+            token::Amount::whole(40),
+        )
+        .unwrap();
+        assert!(validate_tx());
+    }
+
+    /// Test that a redelegation without the records of the redelegated tokens
+    /// is rejected
+    #[test]
+    fn test_redelegation_without_records_rejected() {
+        let params = PosParams::default();
+        let delegator = established_address_1();
+        let [src, dest] = init_pos(&params, &delegator);
+        delegate(&delegator, &src, 100);
+
+        pos::redelegate_tokens(
+            &delegator,
+            &src,
+            &dest,
+            token::Amount::whole(40),
+        )
+        .unwrap();
+        PoS.write_validator_outgoing_redelegations(&src, vec![]);
+        PoS.write_validator_incoming_redelegations(&dest, vec![]);
+        assert!(!validate_tx());
+    }
+
+    /// Test that a redelegation recorded only for the source validator is
+    /// rejected
+    #[test]
+    fn test_unmatched_redelegation_rejected() {
+        let params = PosParams::default();
+        let delegator = established_address_1();
+        let [src, dest] = init_pos(&params, &delegator);
+        delegate(&delegator, &src, 100);
+
+        pos::redelegate_tokens(
+            &delegator,
+            &src,
+            &dest,
+            token::Amount::whole(40),
+        )
+        .unwrap();
+        PoS.write_validator_incoming_redelegations(&dest, vec![]);
+        assert!(!validate_tx());
+    }
+
+    /// Test that a redelegation whose bond changes don't match the
+    /// redelegated amount is rejected
+    #[test]
+    fn test_redelegation_bond_mismatch_rejected() {
+        let params = PosParams::default();
+        let delegator = established_address_1();
+        let [src, dest] = init_pos(&params, &delegator);
+        delegate(&delegator, &src, 100);
+
+        pos::redelegate_tokens(
+            &delegator,
+            &src,
+            &dest,
+            token::Amount::whole(40),
+        )
+        .unwrap();
+        let mut outgoing = PoS.read_validator_outgoing_redelegations(&src);
+        let mut incoming = PoS.read_validator_incoming_redelegations(&dest);
+        for redelegation in outgoing.iter_mut().chain(incoming.iter_mut()) {
+            redelegation.amount += token::Amount::whole(1);
+        }
+        PoS.write_validator_outgoing_redelegations(&src, outgoing);
+        PoS.write_validator_incoming_redelegations(&dest, incoming);
+        assert!(!validate_tx());
+    }
+
+    /// Test that unbonding the redelegated tokens that are still slashable
+    /// for the source validator is rejected
+    #[test]
+    fn test_unbond_slashable_redelegation_rejected() {
+        let params = PosParams::default();
+        let delegator = established_address_1();
+        let [src, dest] = init_pos(&params, &delegator);
+        delegate(&delegator, &src, 100);
+        pos::redelegate_tokens(
+            &delegator,
+            &src,
+            &dest,
+            token::Amount::whole(40),
+        )
+        .unwrap();
+        assert!(validate_tx());
+        tx_host_env::commit_tx_and_block();
+
+        // Hide the redelegation from the unbonding
+        let incoming = PoS.read_validator_incoming_redelegations(&dest);
+        PoS.write_validator_incoming_redelegations(&dest, vec![]);
+        pos::unbond_tokens(Some(&delegator), &dest, token::Amount::whole(40))
+            .unwrap();
+        PoS.write_validator_incoming_redelegations(&dest, incoming);
+        assert!(!validate_tx());
+    }
+
+    /// Jail the `validator` in the current epoch and commit the block and
+    /// then advance the block epoch to the given `epoch`
+    fn jail(params: &PosParams, validator: &Address, epoch: Epoch) {
+        tx_host_env::with(|env| {
+            let current_epoch = env.storage.block.epoch;
+            env.storage
+                .jail_validator(params, current_epoch, validator)
+                .unwrap();
+        });
+        tx_host_env::commit_tx_and_block();
+        tx_host_env::with(|env| env.storage.block.epoch = epoch);
+    }
+
+    /// Test that unjailing a validator after its cooldown is accepted
+    #[test]
+    fn test_unjail_validator_accepted() {
+        let params = PosParams::default();
+        let delegator = established_address_1();
+        let [validator, _] = init_pos(&params, &delegator);
+        jail(&params, &validator, Epoch(params.unjail_cooldown_len));
+
+        pos::unjail_validator(&validator).unwrap();
+        assert!(validate_tx());
+    }
+
+    /// Test that unjailing a validator before its cooldown has passed is
+    /// rejected
+    #[test]
+    fn test_unjail_validator_before_cooldown_rejected() {
+        let params = PosParams::default();
+        let delegator = established_address_1();
+        let [validator, _] = init_pos(&params, &delegator);
+        jail(&params, &validator, Epoch(params.unjail_cooldown_len));
+
+        // Move the jailed epoch of the committed state forward, so that the
+        // cooldown hasn't passed yet
+        let mut state = PoS.read_validator_state(&validator).unwrap();
+        let jailed_state = state.clone();
+        state.set(
+            ValidatorState::Jailed {
+                epoch: Epoch(1).into(),
+            },
+            Epoch(params.unjail_cooldown_len),
+            &params,
+        );
+        tx_host_env::with(|env| {
+            env.storage.write_validator_state(&validator, &state)
+        });
+        tx_host_env::commit_tx_and_block();
+
+        // Bypass the cooldown check of the tx with the original jailed state
+        PoS.write_validator_state(&validator, jailed_state);
+        pos::unjail_validator(&validator).unwrap();
+        assert!(!validate_tx());
+    }
+
+    /// Test that changing a validator's consensus key to a new key is
+    /// accepted
+    #[test]
+    fn test_change_consensus_key_accepted() {
+        let params = PosParams::default();
+        let delegator = established_address_1();
+        let [validator, _] = init_pos(&params, &delegator);
+
+        let consensus_key = gen_keypair::<common::SigScheme>().ref_to();
+        pos::change_consensus_key(&validator, &consensus_key).unwrap();
+        assert!(validate_tx());
+    }
+
+    /// Test that changing a validator's consensus key to a key used by
+    /// another validator is rejected
+    #[test]
+    fn test_duplicate_consensus_key_rejected() {
+        let params = PosParams::default();
+        let delegator = established_address_1();
+        let [validator, other] = init_pos(&params, &delegator);
+
+        // Bypass the check of the tx by writing the key directly
+        let other_key = PoS
+            .read_validator_consensus_key(&other)
+            .unwrap()
+            .get(Epoch(0))
+            .cloned()
+            .unwrap();
+        let mut consensus_keys =
+            PoS.read_validator_consensus_key(&validator).unwrap();
+        consensus_keys.set(other_key, Epoch(0), &params);
+        PoS.write_validator_consensus_key(&validator, consensus_keys);
+        assert!(!validate_tx());
+    }
+
+    /// PoS parameters with a non-zero minimum delegation
+    fn params_with_min_delegation() -> PosParams {
+        PosParams {
+            min_delegation: token::Amount::whole(100).into(),
+            ..PosParams::default()
+        }
+    }
+
+    /// Assert that the current tx is rejected because of a bond below the
+    /// minimum
+    fn assert_below_minimum_rejected() {
+        match try_validate_tx() {
+            Err(pos_vp::Error::BondBelowMinimum(_)) => {}
+            result => panic!("Expected a bond below minimum, got {:?}", result),
+        }
+    }
+
+    /// Test that a dust delegation below the minimum delegation is rejected
+    #[test]
+    fn test_dust_delegation_rejected() {
+        let params = params_with_min_delegation();
+        let delegator = established_address_1();
+        let [validator, _] = init_pos(&params, &delegator);
+
+        pos::bond_tokens(Some(&delegator), &validator, token::Amount::from(1))
+            .unwrap();
+        assert_below_minimum_rejected();
+    }
+
+    /// Test that a partial unbond leaving the delegation below the minimum
+    /// delegation is rejected
+    #[test]
+    fn test_partial_unbond_below_minimum_rejected() {
+        let params = params_with_min_delegation();
+        let delegator = established_address_1();
+        let [validator, _] = init_pos(&params, &delegator);
+        delegate(&delegator, &validator, 150);
+
+        pos::unbond_tokens(
+            Some(&delegator),
+            &validator,
+            token::Amount::whole(100),
+        )
+        .unwrap();
+        assert_below_minimum_rejected();
+    }
+
+    /// Test that fully unbonding a delegation is accepted with a non-zero
+    /// minimum delegation
+    #[test]
+    fn test_full_unbond_accepted() {
+        let params = params_with_min_delegation();
+        let delegator = established_address_1();
+        let [validator, _] = init_pos(&params, &delegator);
+        delegate(&delegator, &validator, 150);
+
+        pos::unbond_tokens(
+            Some(&delegator),
+            &validator,
+            token::Amount::whole(150),
+        )
+        .unwrap();
+        assert!(validate_tx());
+    }
+
+    /// Bond tokens from the `delegator` to several validators in the current
+    /// tx, like the batch bond tx does
+    fn batch_bond(delegator: &Address, bonds: &[(&Address, token::Amount)]) {
+        for (validator, amount) in bonds {
+            pos::bond_tokens(Some(delegator), validator, *amount).unwrap();
+        }
+    }
+
+    /// Test that a batch of bonds to several validators is accepted, with the
+    /// combined deltas of all its bonds validated in a single run of the VP
+    #[test]
+    fn test_batch_bond_accepted() {
+        let params = PosParams::default();
+        let delegator = established_address_1();
+        let [first, second] = init_pos(&params, &delegator);
+
+        batch_bond(
+            &delegator,
+            &[
+                (&first, token::Amount::whole(100)),
+                (&second, token::Amount::whole(200)),
+            ],
+        );
+        let keys_changed =
+            tx_host_env::with(|env| env.all_touched_storage_keys());
+        for validator in [first, second] {
+            let bond_id = BondId {
+                source: delegator.clone(),
+                validator,
+            };
+            assert!(keys_changed.contains(&bond_key(&bond_id)));
+        }
+        assert!(validate_tx());
+    }
+
+    /// Test that a batch of bonds with a single bond below the minimum
+    /// delegation is rejected as a whole
+    #[test]
+    fn test_batch_bond_with_dust_bond_rejected() {
+        let params = params_with_min_delegation();
+        let delegator = established_address_1();
+        let [first, second] = init_pos(&params, &delegator);
+
+        batch_bond(
+            &delegator,
+            &[
+                (&first, token::Amount::whole(150)),
+                (&second, token::Amount::from(1)),
+            ],
+        );
+        assert_below_minimum_rejected();
+    }
+}
@@ -172,6 +172,43 @@ mod tests {
         itertools::assert_equal(iter.sorted(), expected.sorted());
     }
 
+    #[test]
+    fn test_tx_rev_and_range_iter() {
+        // The environment must be initialized first
+        tx_host_env::init();
+
+        // Write some values directly into the storage first
+        let prefix = Key::parse("prefix").unwrap();
+        let key = |i: i32| prefix.join(&Key::parse(i.to_string()).unwrap());
+        tx_host_env::with(|env| {
+            for i in 0..10_i32 {
+                let value = i.try_to_vec().unwrap();
+                env.storage.write(&key(i), value).unwrap();
+            }
+            env.storage.commit().unwrap();
+        });
+        let key_val = |i: i32| (key(i).to_string(), i);
+
+        // The keys are iterated in the reverse order
+        let iter: KeyValIterator<i32> =
+            tx_host_env::rev_iter_prefix(prefix.to_string());
+        itertools::assert_equal(iter, (0..10).rev().map(key_val));
+
+        // Only the keys from the start up to the end are iterated
+        let iter: KeyValIterator<i32> =
+            tx_host_env::iter_range(key(3).to_string(), key(7).to_string());
+        itertools::assert_equal(iter, (3..7).map(key_val));
+
+        let iter: KeyValIterator<i32> =
+            tx_host_env::rev_iter_range(key(3).to_string(), key(7).to_string());
+        itertools::assert_equal(iter, (3..7).rev().map(key_val));
+
+        // An empty range yields no keys
+        let iter: KeyValIterator<i32> =
+            tx_host_env::iter_range(key(7).to_string(), key(3).to_string());
+        assert_eq!(iter.count(), 0);
+    }
+
     #[test]
     fn test_tx_insert_verifier() {
         // The environment must be initialized first
@@ -389,6 +426,49 @@ mod tests {
         itertools::assert_equal(iter_post.sorted(), expected_post.sorted());
     }
 
+    #[test]
+    fn test_vp_rev_and_range_iter() {
+        let mut tx_env = TestTxEnv::default();
+
+        let addr = address::testing::established_address_1();
+        let addr_key = Key::from(addr.to_db_key());
+
+        // Write some value to storage
+        let prefix = addr_key.join(&Key::parse("prefix").unwrap());
+        let key = |i: i32| prefix.join(&Key::parse(i.to_string()).unwrap());
+        for i in 0..10_i32 {
+            let value = i.try_to_vec().unwrap();
+            tx_env.storage.write(&key(i), value).unwrap();
+        }
+        tx_env.storage.commit().unwrap();
+
+        // In a transaction, override one of the existing keys' value
+        let existing_key_raw = key(5).to_string();
+        vp_host_env::init_from_tx(addr, tx_env, |_addr| {
+            tx_host_env::write(&existing_key_raw, 100_i32);
+        });
+        let key_val = |i: i32| (key(i).to_string(), i);
+        let post_key_val =
+            |i: i32| (key(i).to_string(), if i == 5 { 100 } else { i });
+
+        let iter_pre: PreKeyValIterator<i32> =
+            vp_host_env::rev_iter_prefix_pre(prefix.to_string());
+        itertools::assert_equal(iter_pre, (0..10).rev().map(key_val));
+
+        let iter_post: PostKeyValIterator<i32> =
+            vp_host_env::rev_iter_prefix_post(prefix.to_string());
+        itertools::assert_equal(iter_post, (0..10).rev().map(post_key_val));
+
+        let (start, end) = (key(3).to_string(), key(7).to_string());
+        let iter_pre: PreKeyValIterator<i32> =
+            vp_host_env::iter_range_pre(&start, &end);
+        itertools::assert_equal(iter_pre, (3..7).map(key_val));
+
+        let iter_post: PostKeyValIterator<i32> =
+            vp_host_env::rev_iter_range_post(&start, &end);
+        itertools::assert_equal(iter_post, (3..7).rev().map(post_key_val));
+    }
+
     #[test]
     fn test_vp_verify_tx_signature() {
         let mut env = TestVpEnv::default();
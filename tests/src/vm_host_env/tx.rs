@@ -140,6 +140,20 @@ impl TestTxEnv {
             .unwrap();
     }
 
+    /// Approve the spender to transfer the owner's tokens.
+    pub fn approve_tokens(
+        &mut self,
+        owner: &Address,
+        spender: &Address,
+        token: &Address,
+        amount: token::Amount,
+    ) {
+        let storage_key = token::allowance_key(token, owner, spender);
+        self.storage
+            .write(&storage_key, amount.try_to_vec().unwrap())
+            .unwrap();
+    }
+
     /// Set public key for the address.
     pub fn write_public_key(
         &mut self,
@@ -326,6 +340,22 @@ mod native_tx_host_env {
     ));
     native_host_fn!(tx_delete(key_ptr: u64, key_len: u64));
     native_host_fn!(tx_iter_prefix(prefix_ptr: u64, prefix_len: u64) -> u64);
+    native_host_fn!(tx_rev_iter_prefix(
+        prefix_ptr: u64,
+        prefix_len: u64
+    ) -> u64);
+    native_host_fn!(tx_iter_range(
+        start_ptr: u64,
+        start_len: u64,
+        end_ptr: u64,
+        end_len: u64
+    ) -> u64);
+    native_host_fn!(tx_rev_iter_range(
+        start_ptr: u64,
+        start_len: u64,
+        end_ptr: u64,
+        end_len: u64
+    ) -> u64);
     native_host_fn!(tx_iter_next(iter_id: u64) -> i64);
     native_host_fn!(tx_insert_verifier(addr_ptr: u64, addr_len: u64));
     native_host_fn!(tx_update_validity_predicate(
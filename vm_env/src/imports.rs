@@ -157,6 +157,55 @@ pub mod tx {
         KeyValIterator(iter_id, PhantomData)
     }
 
+    /// Get an iterator with the given prefix in the reverse order of the
+    /// keys. The same note as for [`iter_prefix`] applies.
+    pub fn rev_iter_prefix<T: BorshDeserialize>(
+        prefix: impl AsRef<str>,
+    ) -> KeyValIterator<T> {
+        let prefix = prefix.as_ref();
+        let iter_id = unsafe {
+            anoma_tx_rev_iter_prefix(prefix.as_ptr() as _, prefix.len() as _)
+        };
+        KeyValIterator(iter_id, PhantomData)
+    }
+
+    /// Get an iterator of the keys from `start` (inclusive) up to `end`
+    /// (exclusive). The same note as for [`iter_prefix`] applies.
+    pub fn iter_range<T: BorshDeserialize>(
+        start: impl AsRef<str>,
+        end: impl AsRef<str>,
+    ) -> KeyValIterator<T> {
+        let (start, end) = (start.as_ref(), end.as_ref());
+        let iter_id = unsafe {
+            anoma_tx_iter_range(
+                start.as_ptr() as _,
+                start.len() as _,
+                end.as_ptr() as _,
+                end.len() as _,
+            )
+        };
+        KeyValIterator(iter_id, PhantomData)
+    }
+
+    /// Get an iterator of the keys from `start` (inclusive) up to `end`
+    /// (exclusive) in the reverse order. The same note as for
+    /// [`iter_prefix`] applies.
+    pub fn rev_iter_range<T: BorshDeserialize>(
+        start: impl AsRef<str>,
+        end: impl AsRef<str>,
+    ) -> KeyValIterator<T> {
+        let (start, end) = (start.as_ref(), end.as_ref());
+        let iter_id = unsafe {
+            anoma_tx_rev_iter_range(
+                start.as_ptr() as _,
+                start.len() as _,
+                end.as_ptr() as _,
+                end.len() as _,
+            )
+        };
+        KeyValIterator(iter_id, PhantomData)
+    }
+
     impl<T: BorshDeserialize> Iterator for KeyValIterator<T> {
         type Item = (String, T);
 
@@ -312,6 +361,27 @@ pub mod tx {
         // Get an ID of a data iterator with key prefix
         fn anoma_tx_iter_prefix(prefix_ptr: u64, prefix_len: u64) -> u64;
 
+        // Get an ID of a data iterator with key prefix in reverse order
+        fn anoma_tx_rev_iter_prefix(prefix_ptr: u64, prefix_len: u64) -> u64;
+
+        // Get an ID of a data iterator of the keys in the range from start
+        // (inclusive) to end (exclusive)
+        fn anoma_tx_iter_range(
+            start_ptr: u64,
+            start_len: u64,
+            end_ptr: u64,
+            end_len: u64,
+        ) -> u64;
+
+        // Get an ID of a data iterator of the keys in the range from start
+        // (inclusive) to end (exclusive) in reverse order
+        fn anoma_tx_rev_iter_range(
+            start_ptr: u64,
+            start_len: u64,
+            end_ptr: u64,
+            end_len: u64,
+        ) -> u64;
+
         // Returns the size of the value (can be 0), or -1 if there's no next
         // value. If a value is found, it will be placed in the read
         // cache, because we cannot allocate a buffer for it before we know
@@ -489,6 +559,80 @@ pub mod vp {
         }
     }
 
+    /// Get an iterator with the given prefix in the reverse order of the keys
+    /// before transaction execution
+    pub fn rev_iter_prefix_pre<T: BorshDeserialize>(
+        prefix: impl AsRef<str>,
+    ) -> PreKeyValIterator<T> {
+        PreKeyValIterator(rev_iter_prefix_id(prefix.as_ref()), PhantomData)
+    }
+
+    /// Get an iterator with the given prefix in the reverse order of the keys
+    /// after transaction execution
+    pub fn rev_iter_prefix_post<T: BorshDeserialize>(
+        prefix: impl AsRef<str>,
+    ) -> PostKeyValIterator<T> {
+        PostKeyValIterator(rev_iter_prefix_id(prefix.as_ref()), PhantomData)
+    }
+
+    /// Get an iterator of the keys from `start` (inclusive) up to `end`
+    /// (exclusive) before transaction execution
+    pub fn iter_range_pre<T: BorshDeserialize>(
+        start: impl AsRef<str>,
+        end: impl AsRef<str>,
+    ) -> PreKeyValIterator<T> {
+        let iter_id = range_iter_id(start.as_ref(), end.as_ref(), false);
+        PreKeyValIterator(iter_id, PhantomData)
+    }
+
+    /// Get an iterator of the keys from `start` (inclusive) up to `end`
+    /// (exclusive) after transaction execution
+    pub fn iter_range_post<T: BorshDeserialize>(
+        start: impl AsRef<str>,
+        end: impl AsRef<str>,
+    ) -> PostKeyValIterator<T> {
+        let iter_id = range_iter_id(start.as_ref(), end.as_ref(), false);
+        PostKeyValIterator(iter_id, PhantomData)
+    }
+
+    /// Get an iterator of the keys from `start` (inclusive) up to `end`
+    /// (exclusive) in the reverse order before transaction execution
+    pub fn rev_iter_range_pre<T: BorshDeserialize>(
+        start: impl AsRef<str>,
+        end: impl AsRef<str>,
+    ) -> PreKeyValIterator<T> {
+        let iter_id = range_iter_id(start.as_ref(), end.as_ref(), true);
+        PreKeyValIterator(iter_id, PhantomData)
+    }
+
+    /// Get an iterator of the keys from `start` (inclusive) up to `end`
+    /// (exclusive) in the reverse order after transaction execution
+    pub fn rev_iter_range_post<T: BorshDeserialize>(
+        start: impl AsRef<str>,
+        end: impl AsRef<str>,
+    ) -> PostKeyValIterator<T> {
+        let iter_id = range_iter_id(start.as_ref(), end.as_ref(), true);
+        PostKeyValIterator(iter_id, PhantomData)
+    }
+
+    fn rev_iter_prefix_id(prefix: &str) -> u64 {
+        unsafe {
+            anoma_vp_rev_iter_prefix(prefix.as_ptr() as _, prefix.len() as _)
+        }
+    }
+
+    fn range_iter_id(start: &str, end: &str, reverse: bool) -> u64 {
+        let (start_ptr, start_len) = (start.as_ptr() as _, start.len() as _);
+        let (end_ptr, end_len) = (end.as_ptr() as _, end.len() as _);
+        unsafe {
+            if reverse {
+                anoma_vp_rev_iter_range(start_ptr, start_len, end_ptr, end_len)
+            } else {
+                anoma_vp_iter_range(start_ptr, start_len, end_ptr, end_len)
+            }
+        }
+    }
+
     /// Get the chain ID
     pub fn get_chain_id() -> String {
         let result = Vec::with_capacity(CHAIN_ID_LENGTH);
@@ -615,6 +759,27 @@ pub mod vp {
         // Get an ID of a data iterator with key prefix
         fn anoma_vp_iter_prefix(prefix_ptr: u64, prefix_len: u64) -> u64;
 
+        // Get an ID of a data iterator with key prefix in reverse order
+        fn anoma_vp_rev_iter_prefix(prefix_ptr: u64, prefix_len: u64) -> u64;
+
+        // Get an ID of a data iterator of the keys in the range from start
+        // (inclusive) to end (exclusive)
+        fn anoma_vp_iter_range(
+            start_ptr: u64,
+            start_len: u64,
+            end_ptr: u64,
+            end_len: u64,
+        ) -> u64;
+
+        // Get an ID of a data iterator of the keys in the range from start
+        // (inclusive) to end (exclusive) in reverse order
+        fn anoma_vp_rev_iter_range(
+            start_ptr: u64,
+            start_len: u64,
+            end_ptr: u64,
+            end_len: u64,
+        ) -> u64;
+
         // Read variable-length prior state when we don't know the size
         // up-front, returns the size of the value (can be 0), or -1 if
         // the key is not present. If a value is found, it will be placed in the
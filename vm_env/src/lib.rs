@@ -11,6 +11,7 @@ pub mod ibc;
 pub mod imports;
 pub mod intent;
 pub mod key;
+pub mod name_service;
 pub mod nft;
 pub mod proof_of_stake;
 pub mod token;
@@ -30,6 +31,7 @@ pub mod tx_prelude {
     pub use crate::ibc::{Ibc, IbcActions};
     pub use crate::imports::tx::*;
     pub use crate::intent::tx as intent;
+    pub use crate::name_service::tx as name_service;
     pub use crate::nft::tx as nft;
     pub use crate::proof_of_stake::{self, PoS, PosRead, PosWrite};
     pub use crate::token::tx as token;
@@ -40,6 +42,7 @@ pub mod vp_prelude {
     pub use std::collections::{BTreeSet, HashSet};
 
     pub use anoma::ledger::governance::storage as gov_storage;
+    pub use anoma::ledger::name_service::storage as name_service_storage;
     pub use anoma::ledger::{parameters, pos as proof_of_stake};
     pub use anoma::proto::{Signed, SignedTxData};
     pub use anoma::types::address::Address;
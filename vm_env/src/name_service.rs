@@ -0,0 +1,33 @@
+/// Tx imports and functions.
+pub mod tx {
+    use anoma::ledger::name_service::vp::ADDRESS as name_service_address;
+    use anoma::ledger::name_service::{self, storage};
+    use anoma::types::address::xan as m1t;
+    use anoma::types::transaction::RegisterAlias;
+
+    use crate::imports::tx;
+    use crate::token::tx::transfer;
+
+    /// Register the alias of the address, paying the registration fee from
+    /// the address' balance.
+    pub fn register_alias(data: RegisterAlias) {
+        let RegisterAlias { alias, address } = data;
+        if !name_service::is_valid_alias(&alias) {
+            tx::log_string(format!("invalid alias {}", alias));
+            unreachable!()
+        }
+        let alias_key = storage::alias_key(&alias);
+        if tx::has_key(&alias_key.to_string()) {
+            tx::log_string(format!("the alias {} is already taken", alias));
+            unreachable!()
+        }
+        transfer(
+            &address,
+            &name_service_address,
+            &m1t(),
+            name_service::REGISTRATION_FEE,
+        );
+        tx::write(&alias_key.to_string(), &address);
+        tx::insert_verifier(&address);
+    }
+}
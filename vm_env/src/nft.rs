@@ -4,7 +4,7 @@ use anoma::types::nft;
 pub mod tx {
     use anoma::types::address::Address;
     use anoma::types::nft::NftToken;
-    use anoma::types::transaction::nft::{CreateNft, MintNft};
+    use anoma::types::transaction::nft::{CreateNft, MintNft, TransferNft};
 
     use super::*;
     use crate::imports::tx;
@@ -39,6 +39,33 @@ pub mod tx {
         aux_mint_token(&nft.address, &nft.creator, nft.tokens, &nft.creator);
     }
 
+    /// Transfer a nft token to a new owner. The previous owner is appended to
+    /// the token's past owners and the token's approvals are cleared.
+    pub fn transfer_token(transfer: TransferNft) {
+        let token_id = transfer.token_id.to_string();
+        let current_owner_key =
+            nft::get_token_current_owner_key(&transfer.address, &token_id)
+                .to_string();
+        let past_owners_key =
+            nft::get_token_past_owners_key(&transfer.address, &token_id)
+                .to_string();
+        let approval_key =
+            nft::get_token_approval_key(&transfer.address, &token_id)
+                .to_string();
+
+        let current_owner: Address = tx::read(&current_owner_key)
+            .expect("The token to transfer must have an owner");
+        let mut past_owners: Vec<Address> =
+            tx::read(&past_owners_key).unwrap_or_default();
+        past_owners.push(current_owner);
+
+        tx::write(&past_owners_key, past_owners);
+        tx::write(&current_owner_key, &transfer.target);
+        tx::write(&approval_key, Vec::<Address>::new());
+
+        tx::insert_verifier(&transfer.source);
+    }
+
     fn aux_mint_token(
         nft_address: &Address,
         creator_address: &Address,
@@ -93,11 +120,14 @@ pub mod tx {
 pub mod vp {
     use std::collections::BTreeSet;
 
+    use anoma::proto::SignedTxData;
     use anoma::types::address::Address;
     pub use anoma::types::nft::*;
     use anoma::types::storage::Key;
+    use borsh::BorshDeserialize;
 
     use crate::imports::vp;
+    use crate::key::vp as key;
 
     enum KeyType {
         Metadata(Address, String),
@@ -109,11 +139,12 @@ pub mod vp {
     }
 
     pub fn vp(
-        _tx_da_ta: Vec<u8>,
+        tx_data: Vec<u8>,
         nft_address: &Address,
         keys_changed: &BTreeSet<Key>,
         verifiers: &BTreeSet<Address>,
     ) -> bool {
+        let signed_tx_data = SignedTxData::try_from_slice(&tx_data[..]).ok();
         keys_changed
             .iter()
             .all(|key| match get_key_type(key, nft_address) {
@@ -128,10 +159,15 @@ pub mod vp {
                     ));
 
                     is_creator(&nft_address, verifiers)
+                        || is_owner(
+                            &nft_address,
+                            token_id.as_ref(),
+                            &signed_tx_data,
+                        )
                         || is_approved(
                             &nft_address,
                             token_id.as_ref(),
-                            verifiers,
+                            &signed_tx_data,
                         )
                 }
                 KeyType::Metadata(nft_address, token_id) => {
@@ -141,14 +177,58 @@ pub mod vp {
                     ));
                     is_creator(&nft_address, verifiers)
                 }
+                KeyType::CurrentOwner(nft_address, token_id)
+                | KeyType::PastOwners(nft_address, token_id) => {
+                    vp::log_string(format!(
+                        "nft vp, checking ownership change of token id: {}",
+                        token_id
+                    ));
+                    match current_owner(&nft_address, token_id.as_ref()) {
+                        // an owned token can only be transferred with a
+                        // signature of its owner or of one of its approved
+                        // addresses
+                        Some(owner) => {
+                            is_signed_by(&owner, &signed_tx_data)
+                                || is_approved(
+                                    &nft_address,
+                                    token_id.as_ref(),
+                                    &signed_tx_data,
+                                )
+                        }
+                        // a newly minted token
+                        None => is_creator(&nft_address, verifiers),
+                    }
+                }
                 _ => is_creator(nft_address, verifiers),
             })
     }
 
+    /// Get the current owner of the token whose ownership or approvals are
+    /// stored under the given key, if any.
+    pub fn token_owner(key: &Key, nft_address: &Address) -> Option<Address> {
+        let (nft_address, token_id) =
+            is_nft_current_owner_key(key, nft_address)
+                .or_else(|| is_nft_past_owners_key(key, nft_address))
+                .or_else(|| is_nft_approval_key(key, nft_address))?;
+        current_owner(&nft_address, &token_id)
+    }
+
+    fn is_signed_by(
+        address: &Address,
+        signed_tx_data: &Option<SignedTxData>,
+    ) -> bool {
+        match (signed_tx_data, key::get(address)) {
+            (Some(signed_tx_data), Some(pk)) => {
+                vp::verify_tx_signature(&pk, &signed_tx_data.sig)
+            }
+            _ => false,
+        }
+    }
+
     fn is_approved(
         nft_address: &Address,
         nft_token_id: &str,
-        verifiers: &BTreeSet<Address>,
+        signed_tx_data: &Option<SignedTxData>,
     ) -> bool {
         let approvals_key =
             get_token_approval_key(nft_address, nft_token_id).to_string();
@@ -156,7 +236,26 @@ pub mod vp {
             vp::read_pre(approvals_key).unwrap_or_default();
         return approval_addresses
             .iter()
-            .any(|addr| verifiers.contains(addr));
+            .any(|addr| is_signed_by(addr, signed_tx_data));
+    }
+
+    fn current_owner(
+        nft_address: &Address,
+        nft_token_id: &str,
+    ) -> Option<Address> {
+        let current_owner_key =
+            get_token_current_owner_key(nft_address, nft_token_id).to_string();
+        vp::read_pre(current_owner_key)
+    }
+
+    fn is_owner(
+        nft_address: &Address,
+        nft_token_id: &str,
+        signed_tx_data: &Option<SignedTxData>,
+    ) -> bool {
+        current_owner(nft_address, nft_token_id)
+            .map(|owner| is_signed_by(&owner, signed_tx_data))
+            .unwrap_or_default()
     }
 
     fn is_creator(
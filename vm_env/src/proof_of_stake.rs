@@ -1,13 +1,19 @@
 //! Proof of Stake system integration with functions for transactions
 
 use anoma::ledger::pos::anoma_proof_of_stake::{
-    BecomeValidatorError, BondError, UnbondError, WithdrawError,
+    BecomeValidatorError, BondError, ClaimRewardsError,
+    CommissionRateChangeError, ConsensusKeyChangeError, RedelegationError,
+    UnbondError, UnjailError, ValidatorMetadataError, WithdrawError,
 };
 use anoma::ledger::pos::types::Slash;
 pub use anoma::ledger::pos::*;
 use anoma::ledger::pos::{
-    anoma_proof_of_stake, bond_key, params_key, total_voting_power_key,
-    unbond_key, validator_address_raw_hash_key, validator_consensus_key_key,
+    anoma_proof_of_stake, bond_key, params_key, rewards_claimed_epoch_key,
+    total_voting_power_key, unbond_key, validator_address_raw_hash_key,
+    validator_commission_rate_key, validator_consensus_key_key,
+    validator_incoming_redelegations_key,
+    validator_max_commission_rate_change_key, validator_metadata_key,
+    validator_outgoing_redelegations_key, validator_rewards_key,
     validator_set_key, validator_slashes_key,
     validator_staking_reward_address_key, validator_state_key,
     validator_total_deltas_key, validator_voting_power_key,
@@ -45,6 +51,25 @@ pub fn unbond_tokens(
     PoS.unbond_tokens(source, validator, amount, current_epoch)
 }
 
+/// Redelegate tokens delegated from the `source` to the `src_validator` to
+/// the `dest_validator`. The tokens are moved to the `dest_validator`'s stake
+/// at the pipeline epoch, without unbonding them.
+pub fn redelegate_tokens(
+    source: &Address,
+    src_validator: &Address,
+    dest_validator: &Address,
+    amount: token::Amount,
+) -> Result<(), RedelegationError<Address, token::Amount>> {
+    let current_epoch = tx::get_block_epoch();
+    PoS.redelegate_tokens(
+        source,
+        src_validator,
+        dest_validator,
+        amount,
+        current_epoch,
+    )
+}
+
 /// Withdraw unbonded tokens from a self-bond to a validator when `source`
 /// is `None` or equal to the `validator` address, or withdraw unbonded
 /// tokens delegated to the `validator` to the `source`.
@@ -56,6 +81,55 @@ pub fn withdraw_tokens(
     PoS.withdraw_tokens(source, validator, current_epoch)
 }
 
+/// Claim the rewards of a self-bond to a validator when `source` is `None` or
+/// equal to the `validator` address, or of the tokens delegated from the
+/// `source` to the `validator`. Returns the claimed amount.
+pub fn claim_rewards(
+    source: Option<&Address>,
+    validator: &Address,
+) -> Result<token::Amount, ClaimRewardsError<Address>> {
+    let current_epoch = tx::get_block_epoch();
+    PoS.claim_rewards(source, validator, current_epoch)
+}
+
+/// Change the commission rate of a validator. The new rate is used from the
+/// pipeline epoch.
+pub fn change_commission_rate(
+    validator: &Address,
+    rate: BasisPoints,
+) -> Result<(), CommissionRateChangeError<Address>> {
+    let current_epoch = tx::get_block_epoch();
+    PoS.change_commission_rate(validator, rate, current_epoch)
+}
+
+/// Set the display metadata of a validator, which replaces its previous
+/// metadata.
+pub fn set_validator_metadata(
+    validator: &Address,
+    metadata: ValidatorMetadata,
+) -> Result<(), ValidatorMetadataError<Address>> {
+    PoS.set_validator_metadata(validator, metadata)
+}
+
+/// Unjail a jailed validator, which is back in the validator set from the
+/// pipeline epoch.
+pub fn unjail_validator(
+    validator: &Address,
+) -> Result<(), UnjailError<Address>> {
+    let current_epoch = tx::get_block_epoch();
+    PoS.unjail_validator(validator, current_epoch)
+}
+
+/// Change the consensus key of a validator. The new key is used from the
+/// pipeline epoch.
+pub fn change_consensus_key(
+    validator: &Address,
+    consensus_key: &key::common::PublicKey,
+) -> Result<(), ConsensusKeyChangeError<Address>> {
+    let current_epoch = tx::get_block_epoch();
+    PoS.change_consensus_key(validator, consensus_key, current_epoch)
+}
+
 /// Attempt to initialize a validator account. On success, returns the
 /// initialized validator account's address and its staking reward address.
 pub fn init_validator(
@@ -65,6 +139,8 @@ pub fn init_validator(
         rewards_account_key,
         protocol_key,
         dkg_key,
+        commission_rate,
+        max_commission_rate_change,
         validator_vp_code,
         rewards_vp_code,
     }: InitValidator,
@@ -88,6 +164,8 @@ pub fn init_validator(
         &validator_address,
         &rewards_address,
         &consensus_key,
+        BasisPoints::new(commission_rate),
+        BasisPoints::new(max_commission_rate_change),
         current_epoch,
     )?;
     Ok((validator_address, rewards_address))
@@ -160,6 +238,44 @@ impl anoma_proof_of_stake::PosReadOnly for PoS {
         tx::read(unbond_key(key).to_string())
     }
 
+    fn read_validator_outgoing_redelegations(
+        &self,
+        key: &Self::Address,
+    ) -> Redelegations {
+        tx::read(validator_outgoing_redelegations_key(key).to_string())
+            .unwrap_or_default()
+    }
+
+    fn read_validator_incoming_redelegations(
+        &self,
+        key: &Self::Address,
+    ) -> Redelegations {
+        tx::read(validator_incoming_redelegations_key(key).to_string())
+            .unwrap_or_default()
+    }
+
+    fn read_validator_commission_rate(
+        &self,
+        key: &Self::Address,
+    ) -> Option<CommissionRates> {
+        tx::read(validator_commission_rate_key(key).to_string())
+    }
+
+    fn read_validator_max_commission_rate_change(
+        &self,
+        key: &Self::Address,
+    ) -> Option<BasisPoints> {
+        tx::read(validator_max_commission_rate_change_key(key).to_string())
+    }
+
+    fn read_validator_rewards(&self, key: &Self::Address) -> ValidatorRewards {
+        tx::read(validator_rewards_key(key).to_string()).unwrap_or_default()
+    }
+
+    fn read_rewards_claimed_epoch(&self, key: &BondId) -> Option<types::Epoch> {
+        tx::read(rewards_claimed_epoch_key(key).to_string())
+    }
+
     fn read_validator_set(&self) -> ValidatorSets {
         tx::read(validator_set_key().to_string()).unwrap()
     }
@@ -233,6 +349,63 @@ impl anoma_proof_of_stake::PosActions for PoS {
         tx::write(unbond_key(key).to_string(), &value)
     }
 
+    fn write_validator_outgoing_redelegations(
+        &mut self,
+        key: &Self::Address,
+        value: Redelegations,
+    ) {
+        tx::write(
+            validator_outgoing_redelegations_key(key).to_string(),
+            &value,
+        )
+    }
+
+    fn write_validator_incoming_redelegations(
+        &mut self,
+        key: &Self::Address,
+        value: Redelegations,
+    ) {
+        tx::write(
+            validator_incoming_redelegations_key(key).to_string(),
+            &value,
+        )
+    }
+
+    fn write_validator_commission_rate(
+        &mut self,
+        key: &Self::Address,
+        value: CommissionRates,
+    ) {
+        tx::write(validator_commission_rate_key(key).to_string(), &value)
+    }
+
+    fn write_validator_max_commission_rate_change(
+        &mut self,
+        key: &Self::Address,
+        value: BasisPoints,
+    ) {
+        tx::write(
+            validator_max_commission_rate_change_key(key).to_string(),
+            &value,
+        )
+    }
+
+    fn write_rewards_claimed_epoch(
+        &mut self,
+        key: &BondId,
+        value: types::Epoch,
+    ) {
+        tx::write(rewards_claimed_epoch_key(key).to_string(), &value)
+    }
+
+    fn write_validator_metadata(
+        &mut self,
+        key: &Self::Address,
+        value: ValidatorMetadata,
+    ) {
+        tx::write(validator_metadata_key(key).to_string(), &value)
+    }
+
     fn write_validator_set(&mut self, value: ValidatorSets) {
         tx::write(validator_set_key().to_string(), &value)
     }
@@ -19,7 +19,37 @@ pub mod vp {
         verifiers: &BTreeSet<Address>,
     ) -> bool {
         let mut change: Change = 0;
+        let mut supply_change: Change = 0;
         let all_checked = keys_changed.iter().all(|key| {
+            if token::is_metadata_key(token, key) {
+                // the metadata can only be written when the token is
+                // initialized
+                return false;
+            }
+            if token::is_total_supply_key(token, key) {
+                let key = key.to_string();
+                let pre: Amount = vp::read_pre(&key).unwrap_or_default();
+                let post: Amount = vp::read_post(&key).unwrap_or_default();
+                supply_change += post.change() - pre.change();
+                return is_supply_change_valid(token, pre, post, verifiers);
+            }
+            if let Some((owner, spender)) = token::is_allowance_key(token, key)
+            {
+                let key = key.to_string();
+                let pre: Amount = vp::read_pre(&key).unwrap_or_default();
+                let post: Amount = vp::read_post(&key).unwrap_or_default();
+                // an allowance may be changed in any way by the owner, but
+                // the spender may only use it up by a matching debit of the
+                // owner's balance
+                return verifiers.contains(owner)
+                    || (post < pre
+                        && verifiers.contains(spender)
+                        && are_allowances_spent_debited(
+                            token,
+                            owner,
+                            keys_changed,
+                        ));
+            }
             match token::is_balance_key(token, key) {
                 None => {
                     // Unknown changes to this address space are disallowed, but
@@ -57,12 +87,86 @@ pub mod vp {
                 }
             }
         });
-        all_checked && change == 0
+        // the balances may only change in total by the minted or burned amount
+        all_checked && change == supply_change
+    }
+
+    /// Check that a change of the token's total supply is allowed. The supply
+    /// can only be raised by the minter stored in the token's metadata up to
+    /// the maximum supply, whose VP has to approve the change of the supply
+    /// (the user VP requires the minter's signature for it). A decrease is
+    /// allowed, because the burned tokens have to be debited from
+    /// their owners.
+    fn is_supply_change_valid(
+        token: &Address,
+        pre: Amount,
+        post: Amount,
+        verifiers: &BTreeSet<Address>,
+    ) -> bool {
+        if post <= pre {
+            return true;
+        }
+        let metadata: Option<Metadata> =
+            vp::read_pre(&token::metadata_key(token).to_string());
+        match metadata {
+            Some(Metadata {
+                minter: Some(minter),
+                max_supply,
+                ..
+            }) => {
+                verifiers.contains(&minter)
+                    && max_supply.map(|max| post <= max).unwrap_or(true)
+            }
+            _ => false,
+        }
+    }
+
+    /// Get the total amount by which the allowances given by the owner for the
+    /// token have been decreased in the current transaction, i.e. the amount
+    /// of the owner's tokens that the spenders may transfer.
+    pub fn allowances_spent(
+        token: &Address,
+        owner: &Address,
+        keys_changed: &BTreeSet<Key>,
+    ) -> Amount {
+        let mut spent = Amount::default();
+        for key in keys_changed {
+            if let Some((key_owner, _spender)) =
+                token::is_allowance_key(token, key)
+            {
+                if key_owner == owner {
+                    let key = key.to_string();
+                    let pre: Amount = vp::read_pre(&key).unwrap_or_default();
+                    let post: Amount = vp::read_post(&key).unwrap_or_default();
+                    if post < pre {
+                        spent += pre - post;
+                    }
+                }
+            }
+        }
+        spent
+    }
+
+    /// Check that the total decrease of the allowances given by the owner for
+    /// the token in the current transaction is covered by the debit of the
+    /// owner's balance, i.e. that the spenders only used them up to transfer
+    /// the owner's tokens.
+    pub fn are_allowances_spent_debited(
+        token: &Address,
+        owner: &Address,
+        keys_changed: &BTreeSet<Key>,
+    ) -> bool {
+        let key = token::balance_key(token, owner).to_string();
+        let pre: Amount = vp::read_pre(&key).unwrap_or_default();
+        let post: Amount = vp::read_post(&key).unwrap_or_default();
+        let debit = pre.change() - post.change();
+        allowances_spent(token, owner, keys_changed).change() <= debit
     }
 }
 
 /// Tx imports and functions.
 pub mod tx {
+    use anoma::ledger::token_registry::storage as registry_storage;
     pub use anoma::types::token::*;
 
     use super::*;
@@ -110,4 +214,100 @@ pub mod tx {
             _ => tx::write(&dest_key.to_string(), dest_bal),
         }
     }
+
+    /// Initialize the storage of a newly initialized token account with its
+    /// metadata, register its symbol and credit its initial supply to the
+    /// initial holder.
+    pub fn init_token(
+        token: &Address,
+        metadata: &Metadata,
+        initial_supply: Amount,
+        initial_holder: &Address,
+    ) {
+        if let Err(err) = metadata.validate(initial_supply) {
+            tx::log_string(format!("invalid token metadata: {}", err));
+            unreachable!()
+        }
+        let symbol_key = registry_storage::symbol_key(&metadata.symbol);
+        if tx::has_key(&symbol_key.to_string()) {
+            tx::log_string(format!(
+                "the token symbol {} is already taken",
+                metadata.symbol
+            ));
+            unreachable!()
+        }
+        tx::write(&symbol_key.to_string(), token);
+        tx::write(&token::metadata_key(token).to_string(), metadata);
+        tx::write(&token::total_supply_key(token).to_string(), initial_supply);
+        let holder_key = token::balance_key(token, initial_holder);
+        tx::write(&holder_key.to_string(), initial_supply);
+    }
+
+    /// Mint new tokens to the target. The token's VP requires the minter from
+    /// the token's metadata to validate the transaction, so minting with any
+    /// other minter is rejected.
+    pub fn mint(
+        minter: &Address,
+        target: &Address,
+        token: &Address,
+        amount: Amount,
+    ) {
+        tx::insert_verifier(minter);
+        let supply_key = token::total_supply_key(token).to_string();
+        let mut supply: Amount = tx::read(&supply_key).unwrap_or_default();
+        supply.receive(&amount);
+        tx::write(&supply_key, supply);
+        let target_key = token::balance_key(token, target).to_string();
+        let mut target_bal: Amount = tx::read(&target_key).unwrap_or_default();
+        target_bal.receive(&amount);
+        tx::write(&target_key, target_bal);
+    }
+
+    /// Burn the owner's tokens.
+    pub fn burn(owner: &Address, token: &Address, amount: Amount) {
+        let owner_key = token::balance_key(token, owner).to_string();
+        let mut owner_bal: Amount = tx::read(&owner_key).unwrap_or_default();
+        owner_bal.spend(&amount);
+        tx::write(&owner_key, owner_bal);
+        let supply_key = token::total_supply_key(token).to_string();
+        let mut supply: Amount = tx::read(&supply_key).unwrap_or_default();
+        supply.spend(&amount);
+        tx::write(&supply_key, supply);
+    }
+
+    /// Approve the spender to transfer up to the given amount of the owner's
+    /// tokens. The amount replaces any previous allowance.
+    pub fn approve(
+        owner: &Address,
+        spender: &Address,
+        token: &Address,
+        amount: Amount,
+    ) {
+        let allowance_key = token::allowance_key(token, owner, spender);
+        tx::write(&allowance_key.to_string(), amount)
+    }
+
+    /// A token transfer by the spender from the owner's allowance that can be
+    /// used in a transaction.
+    pub fn transfer_from(
+        spender: &Address,
+        src: &Address,
+        dest: &Address,
+        token: &Address,
+        amount: Amount,
+    ) {
+        let allowance_key = token::allowance_key(token, src, spender);
+        let allowance: Amount =
+            tx::read(&allowance_key.to_string()).unwrap_or_default();
+        if allowance < amount {
+            tx::log_string(format!(
+                "the allowance {} of spender {} from {} is lower than the \
+                 amount {}",
+                allowance, spender, src, amount
+            ));
+            unreachable!()
+        }
+        tx::write(&allowance_key.to_string(), allowance - amount);
+        transfer(src, dest, token, amount)
+    }
 }
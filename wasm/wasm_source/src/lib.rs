@@ -1,5 +1,19 @@
+#[cfg(feature = "tx_approve")]
+pub mod tx_approve;
+#[cfg(feature = "tx_batch_bond")]
+pub mod tx_batch_bond;
 #[cfg(feature = "tx_bond")]
 pub mod tx_bond;
+#[cfg(feature = "tx_change_commission_rate")]
+pub mod tx_change_commission_rate;
+#[cfg(feature = "tx_change_consensus_key")]
+pub mod tx_change_consensus_key;
+#[cfg(feature = "tx_change_validator_metadata")]
+pub mod tx_change_validator_metadata;
+#[cfg(feature = "tx_claim_rewards")]
+pub mod tx_claim_rewards;
+#[cfg(feature = "tx_faucet_withdraw")]
+pub mod tx_faucet_withdraw;
 #[cfg(feature = "tx_from_intent")]
 pub mod tx_from_intent;
 #[cfg(feature = "tx_ibc")]
@@ -10,14 +24,30 @@ pub mod tx_init_account;
 pub mod tx_init_nft;
 #[cfg(feature = "tx_init_proposal")]
 pub mod tx_init_proposal;
+#[cfg(feature = "tx_init_token")]
+pub mod tx_init_token;
 #[cfg(feature = "tx_init_validator")]
 pub mod tx_init_validator;
 #[cfg(feature = "tx_mint_nft")]
 pub mod tx_mint_nft;
+#[cfg(feature = "tx_promote_account")]
+pub mod tx_promote_account;
+#[cfg(feature = "tx_redelegate")]
+pub mod tx_redelegate;
+#[cfg(feature = "tx_register_alias")]
+pub mod tx_register_alias;
 #[cfg(feature = "tx_transfer")]
 pub mod tx_transfer;
+#[cfg(feature = "tx_transfer_from")]
+pub mod tx_transfer_from;
+#[cfg(feature = "tx_transfer_nft")]
+pub mod tx_transfer_nft;
 #[cfg(feature = "tx_unbond")]
 pub mod tx_unbond;
+#[cfg(feature = "tx_unjail_validator")]
+pub mod tx_unjail_validator;
+#[cfg(feature = "tx_update_account")]
+pub mod tx_update_account;
 #[cfg(feature = "tx_update_vp")]
 pub mod tx_update_vp;
 #[cfg(feature = "tx_vote_proposal")]
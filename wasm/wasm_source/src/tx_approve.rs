@@ -0,0 +1,20 @@
+//! A tx to approve a spender to transfer the owner's tokens.
+//! This tx uses `token::Approval` wrapped inside `SignedTxData`
+//! as its input as declared in `shared` crate.
+
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let approval =
+        token::Approval::try_from_slice(&signed.data.unwrap()[..]).unwrap();
+    debug_log!("apply_tx called with approval: {:#?}", approval);
+    let token::Approval {
+        owner,
+        spender,
+        token,
+        amount,
+    } = approval;
+    token::approve(&owner, &spender, &token, amount)
+}
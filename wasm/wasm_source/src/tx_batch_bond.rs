@@ -0,0 +1,20 @@
+//! A tx for a batch of PoS bonds that stakes tokens from the same source with
+//! several validators. If any of the bonds fails, none of them is applied.
+
+use anoma_tx_prelude::proof_of_stake::bond_tokens;
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let batch =
+        transaction::pos::BatchBond::try_from_slice(&signed.data.unwrap()[..])
+            .unwrap();
+
+    for (validator, amount) in &batch.bonds {
+        if let Err(err) = bond_tokens(Some(&batch.source), validator, *amount) {
+            debug_log!("Bond to validator {} failed with: {}", validator, err);
+            panic!()
+        }
+    }
+}
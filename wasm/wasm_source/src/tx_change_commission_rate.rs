@@ -0,0 +1,21 @@
+//! A tx for a PoS validator to change its commission rate, which is used from
+//! the pipeline epoch.
+
+use anoma_tx_prelude::proof_of_stake::{change_commission_rate, BasisPoints};
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let change = transaction::pos::CommissionRateChange::try_from_slice(
+        &signed.data.unwrap()[..],
+    )
+    .unwrap();
+
+    if let Err(err) =
+        change_commission_rate(&change.validator, BasisPoints::new(change.rate))
+    {
+        debug_log!("Commission rate change failed with: {}", err);
+        panic!()
+    }
+}
@@ -0,0 +1,25 @@
+//! A tx for a PoS validator to change its display metadata.
+
+use anoma_tx_prelude::proof_of_stake::{
+    set_validator_metadata, ValidatorMetadata,
+};
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let change = transaction::pos::ValidatorMetadataChange::try_from_slice(
+        &signed.data.unwrap()[..],
+    )
+    .unwrap();
+    let metadata = ValidatorMetadata {
+        moniker: change.moniker,
+        website: change.website,
+        contact: change.contact,
+    };
+
+    if let Err(err) = set_validator_metadata(&change.validator, metadata) {
+        debug_log!("Validator metadata change failed with: {}", err);
+        panic!()
+    }
+}
@@ -0,0 +1,24 @@
+//! A tx for a PoS rewards claim that transfers the rewards accrued to a
+//! self-bond or a delegation to its source.
+
+use anoma_tx_prelude::proof_of_stake::claim_rewards;
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let claim = transaction::pos::ClaimRewards::try_from_slice(
+        &signed.data.unwrap()[..],
+    )
+    .unwrap();
+
+    match claim_rewards(claim.source.as_ref(), &claim.validator) {
+        Ok(claimed) => {
+            debug_log!("Claimed rewards {}", claimed);
+        }
+        Err(err) => {
+            debug_log!("Rewards claim failed with: {}", err);
+            panic!()
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! A tx for a token withdrawal from a testnet faucet.
+//! This tx uses `faucet::Withdrawal` wrapped inside `SignedTxData`
+//! as its input as declared in `shared` crate.
+
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let withdrawal =
+        faucet::Withdrawal::try_from_slice(&signed.data.unwrap()[..]).unwrap();
+    debug_log!("apply_tx called with withdrawal: {:#?}", withdrawal);
+    let faucet::Withdrawal {
+        faucet,
+        target,
+        token,
+        amount,
+    } = withdrawal;
+    // Record the withdrawal for the faucet's rate limit
+    let last_withdrawal_key = faucet::last_withdrawal_key(&faucet, &target);
+    write(&last_withdrawal_key.to_string(), get_block_height());
+    let epoch_withdrawals_key =
+        faucet::epoch_withdrawals_key(&faucet, &token).to_string();
+    let epoch_withdrawals: Option<faucet::EpochWithdrawals> =
+        read(&epoch_withdrawals_key);
+    write(
+        &epoch_withdrawals_key,
+        faucet::EpochWithdrawals::add(
+            epoch_withdrawals.as_ref(),
+            get_block_epoch(),
+            amount,
+        ),
+    );
+    token::transfer(&faucet, &target, &token, amount)
+}
@@ -0,0 +1,24 @@
+//! A tx to initialize a new token account with its metadata and a validity
+//! predicate.
+
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let tx_data =
+        transaction::InitToken::try_from_slice(&signed.data.unwrap()[..])
+            .unwrap();
+    debug_log!(
+        "apply_tx called to init a new token with metadata: {:#?}",
+        tx_data.metadata
+    );
+
+    let address = init_account(&tx_data.vp_code);
+    token::init_token(
+        &address,
+        &tx_data.metadata,
+        tx_data.initial_supply,
+        &tx_data.initial_holder,
+    );
+}
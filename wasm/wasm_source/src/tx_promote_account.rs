@@ -0,0 +1,26 @@
+//! A tx to initialize a new established address controlled by the key of an
+//! implicit address, moving the implicit address' token balances to it.
+
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let tx_data =
+        transaction::PromoteAccount::try_from_slice(&signed.data.unwrap()[..])
+            .unwrap();
+    let source = tx_data.source();
+    debug_log!("apply_tx called to promote the implicit account {}", source);
+
+    let address = init_account(&tx_data.vp_code);
+    let pk_key = key::pk_key(&address);
+    write(&pk_key.to_string(), &tx_data.public_key);
+
+    for token in tx_data.tokens {
+        let balance_key = token::balance_key(&token, &source);
+        let balance: Option<token::Amount> = read(&balance_key.to_string());
+        if let Some(balance) = balance {
+            token::transfer(&source, &address, &token, balance);
+        }
+    }
+}
@@ -0,0 +1,24 @@
+//! A tx for a PoS redelegation that moves delegated tokens from a validator
+//! to another validator without unbonding them.
+
+use anoma_tx_prelude::proof_of_stake::redelegate_tokens;
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let redelegation = transaction::pos::Redelegation::try_from_slice(
+        &signed.data.unwrap()[..],
+    )
+    .unwrap();
+
+    if let Err(err) = redelegate_tokens(
+        &redelegation.source,
+        &redelegation.src_validator,
+        &redelegation.dest_validator,
+        redelegation.amount,
+    ) {
+        debug_log!("Redelegation failed with: {}", err);
+        panic!()
+    }
+}
@@ -0,0 +1,15 @@
+//! A tx to register an alias of an address in the name service.
+//! This tx uses `transaction::RegisterAlias` wrapped inside `SignedTxData`
+//! as its input as declared in `shared` crate.
+
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let data =
+        transaction::RegisterAlias::try_from_slice(&signed.data.unwrap()[..])
+            .unwrap();
+    debug_log!("apply_tx called to register an alias: {:#?}", data);
+    name_service::register_alias(data)
+}
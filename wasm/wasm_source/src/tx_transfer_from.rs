@@ -0,0 +1,21 @@
+//! A tx for token transfer by a spender from the owner's allowance.
+//! This tx uses `token::TransferFrom` wrapped inside `SignedTxData`
+//! as its input as declared in `shared` crate.
+
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let transfer =
+        token::TransferFrom::try_from_slice(&signed.data.unwrap()[..]).unwrap();
+    debug_log!("apply_tx called with transfer from: {:#?}", transfer);
+    let token::TransferFrom {
+        spender,
+        source,
+        target,
+        token,
+        amount,
+    } = transfer;
+    token::transfer_from(&spender, &source, &target, &token, amount)
+}
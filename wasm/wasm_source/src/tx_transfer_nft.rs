@@ -0,0 +1,15 @@
+//! A tx to transfer a NFT token to a new owner.
+
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let tx_data = transaction::nft::TransferNft::try_from_slice(
+        &signed.data.unwrap()[..],
+    )
+    .unwrap();
+    log_string("apply_tx called to transfer a NFT token");
+
+    nft::transfer_token(tx_data);
+}
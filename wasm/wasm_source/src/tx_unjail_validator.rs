@@ -0,0 +1,19 @@
+//! A tx for a jailed PoS validator to unjail itself once its cooldown has
+//! passed. The validator is back in the validator set from the pipeline
+//! epoch.
+
+use anoma_tx_prelude::proof_of_stake::unjail_validator;
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let unjail =
+        transaction::pos::Unjail::try_from_slice(&signed.data.unwrap()[..])
+            .unwrap();
+
+    if let Err(err) = unjail_validator(&unjail.validator) {
+        debug_log!("Unjailing failed with: {}", err);
+        panic!()
+    }
+}
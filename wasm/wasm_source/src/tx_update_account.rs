@@ -0,0 +1,22 @@
+//! A tx for updating an established account's public key and/or validity
+//! predicate. A new public key has to be signed for with the account's current
+//! key.
+
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let update =
+        transaction::UpdateAccount::try_from_slice(&signed.data.unwrap()[..])
+            .unwrap();
+    debug_log!("update account: {:#?}", update.addr);
+
+    if let Some(public_key) = update.public_key {
+        let pk_key = key::pk_key(&update.addr);
+        write(&pk_key.to_string(), &public_key);
+    }
+    if let Some(vp_code) = update.vp_code {
+        update_validity_predicate(&update.addr, vp_code);
+    }
+}
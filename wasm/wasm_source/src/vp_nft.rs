@@ -36,10 +36,11 @@ fn validate_tx(
 #[cfg(test)]
 mod tests {
     use anoma::types::nft::{self, NftToken};
-    use anoma::types::transaction::nft::{CreateNft, MintNft};
+    use anoma::types::transaction::nft::{CreateNft, MintNft, TransferNft};
     use anoma_tests::log::test;
     use anoma_tests::tx::{tx_host_env, TestTxEnv};
     use anoma_tests::vp::*;
+    use anoma_vp_prelude::key::RefTo;
 
     use super::*;
 
@@ -290,6 +291,9 @@ mod tests {
         let mut tx_env = tx_host_env::take();
         tx_env.commit_tx_and_block();
 
+        let keypair = key::testing::keypair_1();
+        tx_env.write_public_key(&nft_token_approval, &keypair.ref_to());
+
         vp_host_env::init_from_tx(nft_address.clone(), tx_env, |_| {
             let approval_key =
                 nft::get_token_approval_key(&nft_address, "1").to_string();
@@ -300,8 +304,10 @@ mod tests {
             tx_host_env::insert_verifier(&nft_token_approval);
         });
 
-        let vp_env = vp_host_env::take();
-        let tx_data: Vec<u8> = vec![];
+        let mut vp_env = vp_host_env::take();
+        let signed_tx = vp_env.tx.clone().sign(&keypair);
+        let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
+        vp_env.tx = signed_tx;
         let keys_changed: BTreeSet<storage::Key> =
             vp_env.all_touched_storage_keys();
         let verifiers: BTreeSet<Address> = vp_env.get_verifiers();
@@ -422,4 +428,183 @@ mod tests {
 
         assert!(!validate_tx(tx_data, nft_address, keys_changed, verifiers));
     }
+
+    /// Create a nft with a single token owned by the given owner and
+    /// approved for the given approvals
+    fn init_nft_with_token(
+        tx_env: TestTxEnv,
+        nft_creator: &Address,
+        nft_token_owner: &Address,
+        approvals: Vec<Address>,
+    ) -> (Address, TestTxEnv) {
+        // just a dummy vp, its not used during testing
+        let vp_code =
+            std::fs::read(VP_ALWAYS_TRUE_WASM).expect("cannot load wasm");
+
+        tx_host_env::set(tx_env);
+        let nft_address = tx_host_env::nft::init_nft(CreateNft {
+            tag: "v1".to_string(),
+            creator: nft_creator.clone(),
+            vp_code,
+            keys: vec![],
+            opt_keys: vec![],
+            tokens: vec![NftToken {
+                id: 1,
+                values: vec![],
+                opt_values: vec![],
+                metadata: "".to_string(),
+                approvals,
+                current_owner: Some(nft_token_owner.clone()),
+                past_owners: vec![],
+                burnt: false,
+            }],
+        });
+
+        let mut tx_env = tx_host_env::take();
+        tx_env.commit_tx_and_block();
+        (nft_address, tx_env)
+    }
+
+    /// Test that the owner and an approved address can transfer a token with
+    /// a valid signature
+    #[test]
+    fn test_transfer_token_authorized() {
+        let nft_creator = address::testing::established_address_2();
+        let nft_token_owner = address::testing::established_address_1();
+        let nft_token_approval = address::testing::established_address_3();
+        let target = address::testing::established_address_4();
+        let owner_keypair = key::testing::keypair_1();
+        let approval_keypair = key::testing::keypair_2();
+
+        for (source, keypair) in [
+            (&nft_token_owner, &owner_keypair),
+            (&nft_token_approval, &approval_keypair),
+        ] {
+            let mut tx_env = TestTxEnv::default();
+            tx_env.spawn_accounts([
+                &nft_creator,
+                &nft_token_owner,
+                &nft_token_approval,
+                &target,
+            ]);
+            tx_env.write_public_key(&nft_token_owner, &owner_keypair.ref_to());
+            tx_env.write_public_key(
+                &nft_token_approval,
+                &approval_keypair.ref_to(),
+            );
+            let (nft_address, tx_env) = init_nft_with_token(
+                tx_env,
+                &nft_creator,
+                &nft_token_owner,
+                vec![nft_token_approval.clone()],
+            );
+
+            vp_host_env::init_from_tx(nft_address.clone(), tx_env, |_| {
+                tx_host_env::nft::transfer_token(TransferNft {
+                    address: nft_address.clone(),
+                    token_id: 1,
+                    source: source.clone(),
+                    target: target.clone(),
+                });
+            });
+
+            let mut vp_env = vp_host_env::take();
+            let signed_tx = vp_env.tx.clone().sign(keypair);
+            let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
+            vp_env.tx = signed_tx;
+            let keys_changed: BTreeSet<storage::Key> =
+                vp_env.all_touched_storage_keys();
+            let verifiers: BTreeSet<Address> = vp_env.get_verifiers();
+            vp_host_env::set(vp_env);
+
+            let owner_key =
+                nft::get_token_current_owner_key(&nft_address, "1").to_string();
+            let new_owner: Address = read_post(owner_key).unwrap();
+            assert_eq!(new_owner, target);
+            let past_owners_key =
+                nft::get_token_past_owners_key(&nft_address, "1").to_string();
+            let past_owners: Vec<Address> = read_post(past_owners_key).unwrap();
+            assert_eq!(past_owners, vec![nft_token_owner.clone()]);
+
+            assert!(validate_tx(tx_data, nft_address, keys_changed, verifiers));
+        }
+    }
+
+    /// Test that neither the creator nor any other address can transfer a
+    /// token owned by someone else
+    #[test]
+    fn test_transfer_token_not_authorized() {
+        let nft_creator = address::testing::established_address_2();
+        let nft_token_owner = address::testing::established_address_1();
+        let target = address::testing::established_address_4();
+
+        for source in [&nft_creator, &target] {
+            let mut tx_env = TestTxEnv::default();
+            tx_env.spawn_accounts([&nft_creator, &nft_token_owner, &target]);
+            let (nft_address, tx_env) = init_nft_with_token(
+                tx_env,
+                &nft_creator,
+                &nft_token_owner,
+                vec![],
+            );
+
+            vp_host_env::init_from_tx(nft_address.clone(), tx_env, |_| {
+                tx_host_env::nft::transfer_token(TransferNft {
+                    address: nft_address.clone(),
+                    token_id: 1,
+                    source: source.clone(),
+                    target: target.clone(),
+                });
+            });
+
+            let vp_env = vp_host_env::take();
+            let tx_data: Vec<u8> = vec![];
+            let keys_changed: BTreeSet<storage::Key> =
+                vp_env.all_touched_storage_keys();
+            let verifiers: BTreeSet<Address> = vp_env.get_verifiers();
+            vp_host_env::set(vp_env);
+
+            assert!(!validate_tx(
+                tx_data,
+                nft_address,
+                keys_changed,
+                verifiers
+            ));
+        }
+    }
+
+    /// Test that a transfer that is not signed by the owner is rejected, even
+    /// though the owner is inserted into the verifiers by the transfer
+    #[test]
+    fn test_unsigned_transfer_token_rejected() {
+        let nft_creator = address::testing::established_address_2();
+        let nft_token_owner = address::testing::established_address_1();
+        let target = address::testing::established_address_4();
+        let keypair = key::testing::keypair_1();
+
+        let mut tx_env = TestTxEnv::default();
+        tx_env.spawn_accounts([&nft_creator, &nft_token_owner, &target]);
+        tx_env.write_public_key(&nft_token_owner, &keypair.ref_to());
+        let (nft_address, tx_env) =
+            init_nft_with_token(tx_env, &nft_creator, &nft_token_owner, vec![]);
+
+        vp_host_env::init_from_tx(nft_address.clone(), tx_env, |_| {
+            tx_host_env::nft::transfer_token(TransferNft {
+                address: nft_address.clone(),
+                token_id: 1,
+                source: nft_token_owner.clone(),
+                target: target.clone(),
+            });
+        });
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = vp_env.get_verifiers();
+        vp_host_env::set(vp_env);
+
+        assert!(verifiers.contains(&nft_token_owner));
+        assert!(!validate_tx(tx_data, nft_address, keys_changed, verifiers));
+    }
 }
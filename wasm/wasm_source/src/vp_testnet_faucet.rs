@@ -1,16 +1,24 @@
 //! A "faucet" account for testnet.
 //!
 //! This VP allows anyone to withdraw up to [`MAX_FREE_DEBIT`] tokens without
-//! the faucet's signature.
+//! the faucet's signature. The withdrawals are rate limited: every target
+//! credited by an unsigned withdrawal has to record it in the faucet's storage
+//! and it cannot withdraw again in the next [`WITHDRAWAL_INTERVAL`] blocks.
+//! An unsigned record must belong to an actual withdrawal of its target in the
+//! same transaction. On top of that, every unsigned withdrawal has to be added
+//! to the faucet's total of the token withdrawn in the current epoch, which
+//! cannot exceed [`faucet::MAX_EPOCH_WITHDRAWALS`], so that the faucet cannot
+//! be drained by withdrawing to many new targets.
 //!
 //! Any other storage key changes are allowed only with a valid signature.
 
+use anoma_vp_prelude::faucet::{
+    EpochWithdrawals, MAX_FREE_DEBIT, WITHDRAWAL_INTERVAL,
+};
+use anoma_vp_prelude::storage::BlockHeight;
 use anoma_vp_prelude::{SignedTxData, *};
 use once_cell::unsync::Lazy;
 
-/// Allows anyone to withdraw up to 1_000 tokens in a single tx
-pub const MAX_FREE_DEBIT: i128 = 1_000_000_000; // in micro units
-
 #[validity_predicate]
 fn validate_tx(
     tx_data: Vec<u8>,
@@ -40,6 +48,9 @@ fn validate_tx(
         _ => false,
     });
 
+    let valid_withdrawals =
+        Lazy::new(|| are_withdrawals_rate_limited(&addr, &keys_changed));
+
     if !is_tx_whitelisted() {
         return false;
     }
@@ -52,12 +63,31 @@ fn validate_tx(
                 let pre: token::Amount = read_pre(&key).unwrap_or_default();
                 let post: token::Amount = read_post(&key).unwrap_or_default();
                 let change = post.change() - pre.change();
-                // Debit over `MAX_FREE_DEBIT` has to signed, credit doesn't
-                change >= -MAX_FREE_DEBIT || change >= 0 || *valid_sig
+                // Debit over `MAX_FREE_DEBIT`, of a target that is not rate
+                // limited or over the epoch's cap has to signed, credit
+                // doesn't
+                change >= 0
+                    || *valid_sig
+                    || (change >= -MAX_FREE_DEBIT
+                        && *valid_withdrawals
+                        && match key.segments.first() {
+                            Some(storage::DbKeySeg::AddressSeg(token)) => {
+                                is_epoch_withdrawal_recorded(&addr, token)
+                            }
+                            _ => false,
+                        })
             } else {
                 // If this is not the owner, allow any change
                 true
             }
+        } else if let Some(target) = faucet::is_last_withdrawal_key(&addr, key)
+        {
+            *valid_sig
+                || (is_withdrawal_recorded(&addr, target)
+                    && is_withdrawn_to(&addr, target, &keys_changed))
+        } else if let Some(token) = faucet::is_epoch_withdrawals_key(&addr, key)
+        {
+            *valid_sig || is_epoch_withdrawal_recorded(&addr, token)
         } else if let Some(owner) = key.is_validity_predicate() {
             let key = key.to_string();
             let has_post: bool = has_key_post(&key);
@@ -84,6 +114,112 @@ fn validate_tx(
     true
 }
 
+/// Check that every address credited in the transaction has recorded its
+/// withdrawal from the faucet, and that none of them has withdrawn in the last
+/// [`WITHDRAWAL_INTERVAL`] blocks.
+fn are_withdrawals_rate_limited(
+    faucet: &Address,
+    keys_changed: &BTreeSet<storage::Key>,
+) -> bool {
+    let targets: HashSet<&Address> = keys_changed
+        .iter()
+        .filter_map(|key| faucet::is_last_withdrawal_key(faucet, key))
+        .collect();
+    if targets.is_empty() {
+        return false;
+    }
+    keys_changed
+        .iter()
+        .all(|key| match token::is_any_token_balance_key(key) {
+            Some(owner) if owner != faucet => {
+                let key = key.to_string();
+                let pre: token::Amount = read_pre(&key).unwrap_or_default();
+                let post: token::Amount = read_post(&key).unwrap_or_default();
+                post <= pre || targets.contains(owner)
+            }
+            _ => true,
+        })
+        && targets
+            .iter()
+            .all(|target| is_withdrawal_recorded(faucet, target))
+}
+
+/// Check that the target is credited with some tokens debited from the faucet
+/// in the transaction, i.e. that the recorded withdrawal is an actual one.
+fn is_withdrawn_to(
+    faucet: &Address,
+    target: &Address,
+    keys_changed: &BTreeSet<storage::Key>,
+) -> bool {
+    keys_changed.iter().any(|key| {
+        let token = match key.segments.first() {
+            Some(storage::DbKeySeg::AddressSeg(token))
+                if token::is_balance_key(token, key) == Some(target) =>
+            {
+                token
+            }
+            _ => return false,
+        };
+        let credit = balance_change(key);
+        let debit = -balance_change(&token::balance_key(token, faucet));
+        credit > 0 && debit >= credit
+    })
+}
+
+/// The change of the balance at the given key in the transaction
+fn balance_change(key: &storage::Key) -> token::Change {
+    let key = key.to_string();
+    let pre: token::Amount = read_pre(&key).unwrap_or_default();
+    let post: token::Amount = read_post(&key).unwrap_or_default();
+    post.change() - pre.change()
+}
+
+/// Check that the target's withdrawal is recorded at the current block height
+/// and that its previous withdrawal is at least [`WITHDRAWAL_INTERVAL`] blocks
+/// old.
+fn is_withdrawal_recorded(faucet: &Address, target: &Address) -> bool {
+    let key = faucet::last_withdrawal_key(faucet, target).to_string();
+    let pre: Option<BlockHeight> = read_pre(&key);
+    let post: Option<BlockHeight> = read_post(&key);
+    let height = get_block_height();
+    debug_log!(
+        "faucet withdrawal of {}, last: {:?}, recorded: {:?}, height: {}",
+        target,
+        pre,
+        post,
+        height
+    );
+    post == Some(height)
+        && pre.map_or(true, |pre| pre.0 + WITHDRAWAL_INTERVAL <= height.0)
+}
+
+/// Check that the faucet's debit of the token in the transaction is added to
+/// its total withdrawn in the current epoch and that the total stays within
+/// [`faucet::MAX_EPOCH_WITHDRAWALS`].
+fn is_epoch_withdrawal_recorded(faucet: &Address, token: &Address) -> bool {
+    let debit = -balance_change(&token::balance_key(token, faucet));
+    if debit <= 0 || debit > MAX_FREE_DEBIT {
+        return false;
+    }
+    let key = faucet::epoch_withdrawals_key(faucet, token).to_string();
+    let pre: Option<EpochWithdrawals> = read_pre(&key);
+    let post: Option<EpochWithdrawals> = read_post(&key);
+    let expected = EpochWithdrawals::add(
+        pre.as_ref(),
+        get_block_epoch(),
+        token::Amount::from(debit as u64),
+    );
+    debug_log!(
+        "faucet withdrawals of {} in the epoch, last: {:?}, recorded: {:?}, \
+         expected: {:?}",
+        token,
+        pre,
+        post,
+        expected
+    );
+    post.as_ref() == Some(&expected) && expected.is_within_cap()
+}
+
 #[cfg(test)]
 mod tests {
     use address::testing::arb_non_internal_address;
@@ -93,6 +229,7 @@ mod tests {
     use anoma_tests::vp::vp_host_env::storage::Key;
     use anoma_tests::vp::*;
     use anoma_vp_prelude::key::RefTo;
+    use borsh::BorshSerialize;
     use proptest::prelude::*;
     use storage::testing::arb_account_storage_key_no_vp;
 
@@ -101,6 +238,95 @@ mod tests {
     const VP_ALWAYS_TRUE_WASM: &str =
         "../../wasm_for_tests/vp_always_true.wasm";
 
+    /// Withdraw tokens from the faucet in a transaction, recording the
+    /// target's withdrawal and the faucet's withdrawals in the epoch
+    fn withdraw(
+        faucet: &Address,
+        target: &Address,
+        token: &Address,
+        amount: token::Amount,
+    ) {
+        let last_withdrawal_key = faucet::last_withdrawal_key(faucet, target);
+        tx_host_env::write(
+            last_withdrawal_key.to_string(),
+            tx_host_env::get_block_height(),
+        );
+        let epoch_withdrawals_key =
+            faucet::epoch_withdrawals_key(faucet, token).to_string();
+        let epoch_withdrawals: Option<EpochWithdrawals> =
+            tx_host_env::read(&epoch_withdrawals_key);
+        tx_host_env::write(
+            &epoch_withdrawals_key,
+            EpochWithdrawals::add(
+                epoch_withdrawals.as_ref(),
+                tx_host_env::get_block_epoch(),
+                amount,
+            ),
+        );
+        tx_host_env::token::transfer(faucet, target, token, amount);
+    }
+
+    /// Test that withdrawals to many distinct targets are rejected once the
+    /// faucet's total of the token withdrawn in the epoch reaches
+    /// [`faucet::MAX_EPOCH_WITHDRAWALS`].
+    #[test]
+    fn test_withdrawals_over_epoch_cap_rejected() {
+        let vp_owner = address::testing::established_address_1();
+        let token = address::xan();
+        let amount = token::Amount::from(MAX_FREE_DEBIT as u64);
+        let max_withdrawals =
+            (faucet::MAX_EPOCH_WITHDRAWALS / MAX_FREE_DEBIT) as u64;
+
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+        tx_env.spawn_accounts([&vp_owner, &token]);
+        tx_env.credit_tokens(
+            &vp_owner,
+            &token,
+            token::Amount::from(faucet::MAX_EPOCH_WITHDRAWALS as u64 * 2),
+        );
+
+        for withdrawal in 0..=max_withdrawals {
+            // Every withdrawal is to a new target
+            let target = address::testing::gen_established_address();
+            tx_env.spawn_accounts([&target]);
+
+            // Initialize VP environment from a transaction
+            vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+                // Apply a recorded withdrawal in a transaction
+                withdraw(address, &target, &token, amount);
+            });
+
+            let vp_env = vp_host_env::take();
+            let tx_data: Vec<u8> = vec![];
+            let keys_changed: BTreeSet<storage::Key> =
+                vp_env.all_touched_storage_keys();
+            let verifiers: BTreeSet<Address> = BTreeSet::default();
+            vp_host_env::set(vp_env);
+            let is_valid =
+                validate_tx(tx_data, vp_owner.clone(), keys_changed, verifiers);
+            assert_eq!(
+                is_valid,
+                withdrawal < max_withdrawals,
+                "Unexpected validity of the withdrawal {}",
+                withdrawal
+            );
+
+            // Commit the accepted withdrawal for the next one
+            let mut vp_env = vp_host_env::take();
+            if is_valid {
+                vp_env.write_log.commit_tx();
+            } else {
+                vp_env.write_log.drop_tx();
+            }
+            tx_env = TestTxEnv {
+                storage: vp_env.storage,
+                write_log: vp_env.write_log,
+                ..TestTxEnv::default()
+            };
+        }
+    }
+
     /// Test that no-op transaction (i.e. no storage modifications) accepted.
     #[test]
     fn test_no_op_transaction() {
@@ -265,7 +491,40 @@ mod tests {
 
     /// Test that a debit of less than or equal to [`MAX_FREE_DEBIT`] tokens without a valid signature is accepted.
     #[test]
-    fn test_unsigned_debit_under_limit_accepted(amount in (..MAX_FREE_DEBIT as u64 + 1)) {
+    fn test_unsigned_debit_under_limit_accepted(amount in (1..MAX_FREE_DEBIT as u64 + 1)) {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let target = address::testing::established_address_2();
+        let token = address::xan();
+        let amount = token::Amount::from(amount);
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &target, &token]);
+
+        // Credit the tokens to the VP owner before running the transaction to
+        // be able to transfer from it
+        tx_env.credit_tokens(&vp_owner, &token, amount);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+        // Apply a recorded withdrawal in a transaction
+        withdraw(address, &target, &token, amount);
+        });
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+        vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        assert!(validate_tx(tx_data, vp_owner, keys_changed, verifiers));
+    }
+
+    /// Test that a debit under [`MAX_FREE_DEBIT`] tokens without a valid signature is rejected if the target's withdrawal is not recorded.
+    #[test]
+    fn test_unsigned_unrecorded_debit_rejected(amount in (1..MAX_FREE_DEBIT as u64 + 1)) {
         // Initialize a tx environment
         let mut tx_env = TestTxEnv::default();
 
@@ -293,7 +552,89 @@ mod tests {
         vp_env.all_touched_storage_keys();
         let verifiers: BTreeSet<Address> = BTreeSet::default();
         vp_host_env::set(vp_env);
-        assert!(validate_tx(tx_data, vp_owner, keys_changed, verifiers));
+        assert!(!validate_tx(tx_data, vp_owner, keys_changed, verifiers));
+    }
+
+    /// Test that a withdrawal record without a valid signature is rejected if the target isn't credited with the tokens debited from the faucet.
+    #[test]
+    fn test_unsigned_record_without_debit_rejected(amount in (1..MAX_FREE_DEBIT as u64 + 1)) {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let target = address::testing::established_address_2();
+        let source = address::testing::established_address_3();
+        let token = address::xan();
+        let amount = token::Amount::from(amount);
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &target, &source, &token]);
+
+        // Credit the tokens to another source before running the transaction
+        // to be able to transfer from it
+        tx_env.credit_tokens(&source, &token, amount);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+        // Record the target's withdrawal, but credit it from another source
+        let last_withdrawal_key = faucet::last_withdrawal_key(address, &target);
+        tx_host_env::write(
+            last_withdrawal_key.to_string(),
+            tx_host_env::get_block_height(),
+        );
+        tx_host_env::token::transfer(&source, &target, &token, amount);
+        });
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+        vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        assert!(!validate_tx(tx_data, vp_owner, keys_changed, verifiers));
+    }
+
+    /// Test that a recorded debit under [`MAX_FREE_DEBIT`] tokens without a valid signature is rejected if the target has withdrawn in the last [`WITHDRAWAL_INTERVAL`] blocks.
+    #[test]
+    fn test_unsigned_debit_too_soon_rejected(amount in (1..MAX_FREE_DEBIT as u64 + 1)) {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let target = address::testing::established_address_2();
+        let token = address::xan();
+        let amount = token::Amount::from(amount);
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &target, &token]);
+
+        // Credit the tokens to the VP owner before running the transaction to
+        // be able to transfer from it
+        tx_env.credit_tokens(&vp_owner, &token, amount);
+
+        // Record a withdrawal of the target in the current block
+        let (last_withdrawal, _gas) = tx_env.storage.get_block_height();
+        tx_env
+            .storage
+            .write(
+                &faucet::last_withdrawal_key(&vp_owner, &target),
+                last_withdrawal.try_to_vec().unwrap(),
+            )
+            .unwrap();
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+        // Apply a recorded withdrawal in a transaction
+        withdraw(address, &target, &token, amount);
+        });
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+        vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        assert!(!validate_tx(tx_data, vp_owner, keys_changed, verifiers));
     }
 
         /// Test that a signed tx that performs arbitrary storage writes or
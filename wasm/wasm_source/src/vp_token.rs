@@ -1,5 +1,7 @@
 //! A VP for a fungible token. Enforces that the total supply is unchanged in a
-//! transaction that moves balance(s).
+//! transaction that moves balance(s), unless the tokens are minted by the
+//! token's minter or burned by their owners. The token's metadata cannot be
+//! changed after it's initialized.
 
 use anoma_vp_prelude::*;
 
@@ -35,3 +37,105 @@ fn validate_tx(
 
     vp_check && token::vp(&addr, &keys_changed, &verifiers)
 }
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::token::Metadata;
+    // Use this as `#[test]` annotation to enable logging
+    use anoma_tests::log::test;
+    use anoma_tests::tx::{tx_host_env, TestTxEnv};
+    use anoma_tests::vp::*;
+    use borsh::BorshSerialize;
+
+    use super::*;
+
+    /// Initialize a tx environment with a token whose metadata designate the
+    /// given minter.
+    fn init_token_env(
+        token: &Address,
+        minter: &Address,
+        max_supply: Option<token::Amount>,
+    ) -> TestTxEnv {
+        let mut tx_env = TestTxEnv::default();
+        let target = address::testing::established_address_3();
+        tx_env.spawn_accounts([token, minter, &target]);
+        let metadata = Metadata {
+            symbol: "TEST".to_string(),
+            decimals: 6,
+            max_supply,
+            minter: Some(minter.clone()),
+        };
+        tx_env
+            .storage
+            .write(&token::metadata_key(token), metadata.try_to_vec().unwrap())
+            .unwrap();
+        tx_env
+    }
+
+    /// Test that minting by the token's minter is accepted.
+    #[test]
+    fn test_mint_by_minter_accepted() {
+        let token = address::testing::established_address_1();
+        let minter = address::testing::established_address_2();
+        let target = address::testing::established_address_3();
+        let amount = token::Amount::from(10_098_123);
+        let tx_env = init_token_env(&token, &minter, None);
+
+        vp_host_env::init_from_tx(token.clone(), tx_env, |address| {
+            tx_host_env::token::mint(&minter, &target, address, amount);
+        });
+
+        let vp_env = vp_host_env::take();
+        let keys_changed = vp_env.all_touched_storage_keys();
+        let verifiers = vp_env.get_verifiers();
+        vp_host_env::set(vp_env);
+        assert!(validate_tx(vec![], token, keys_changed, verifiers));
+    }
+
+    /// Test that minting without the minter's validation, by an unauthorized
+    /// minter or above the maximum supply is rejected.
+    #[test]
+    fn test_invalid_mint_rejected() {
+        let token = address::testing::established_address_1();
+        let minter = address::testing::established_address_2();
+        let target = address::testing::established_address_3();
+        let amount = token::Amount::from(10_098_123);
+
+        // The minter is not a verifier
+        let tx_env = init_token_env(&token, &minter, None);
+        vp_host_env::init_from_tx(token.clone(), tx_env, |address| {
+            tx_host_env::token::mint(&minter, &target, address, amount);
+        });
+        let vp_env = vp_host_env::take();
+        let keys_changed = vp_env.all_touched_storage_keys();
+        let verifiers = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        assert!(!validate_tx(vec![], token.clone(), keys_changed, verifiers));
+
+        // Another account that isn't the token's minter mints and validates
+        // the tx
+        let other = address::testing::established_address_4();
+        let mut tx_env = init_token_env(&token, &minter, None);
+        tx_env.spawn_accounts([&other]);
+        vp_host_env::init_from_tx(token.clone(), tx_env, |address| {
+            tx_host_env::token::mint(&other, &target, address, amount);
+        });
+        let vp_env = vp_host_env::take();
+        let keys_changed = vp_env.all_touched_storage_keys();
+        let verifiers = vp_env.get_verifiers();
+        vp_host_env::set(vp_env);
+        assert!(!validate_tx(vec![], token.clone(), keys_changed, verifiers));
+
+        // The maximum supply is exceeded
+        let max_supply = Some(token::Amount::from(1));
+        let tx_env = init_token_env(&token, &minter, max_supply);
+        vp_host_env::init_from_tx(token.clone(), tx_env, |address| {
+            tx_host_env::token::mint(&minter, &target, address, amount);
+        });
+        let vp_env = vp_host_env::take();
+        let keys_changed = vp_env.all_touched_storage_keys();
+        let verifiers = vp_env.get_verifiers();
+        vp_host_env::set(vp_env);
+        assert!(!validate_tx(vec![], token, keys_changed, verifiers));
+    }
+}
@@ -4,6 +4,22 @@
 //! sending tokens (receiving tokens is permissive).
 //!
 //! It allows to bond, unbond and withdraw tokens to and from PoS system with a
+//! valid signature. The consensus key of a validator account can only be
+//! changed with a valid signature.
+//!
+//! An alias of this account can only be registered in the name service with a
+//! valid signature.
+//!
+//! It allows to rotate this account's public key with a valid signature of
+//! its current key. The public key cannot be removed.
+//!
+//! It allows spenders approved by this account to transfer its tokens up to
+//! their allowances. The allowances given by this account can only be changed
+//! with a valid signature, except for a spender using up its allowance by a
+//! matching debit of this account's balance. The allowances given to this
+//! account can only be used up with a valid signature.
+//!
+//! The tokens of which this account is the minter can only be minted with a
 //! valid signature.
 //!
 //! It allows to fulfil intents that were signed by this account's key if they
@@ -22,9 +38,17 @@ use rust_decimal::prelude::*;
 
 enum KeyType<'a> {
     Token(&'a Address),
+    TokenSupply(&'a Address),
+    TokenAllowance {
+        token: &'a Address,
+        owner: &'a Address,
+        spender: &'a Address,
+    },
+    PublicKey(&'a Address),
     PoS,
     InvalidIntentSet(&'a Address),
     Nft(&'a Address),
+    Alias,
     Vp(&'a Address),
     GovernanceVote(&'a Address),
     Unknown,
@@ -34,12 +58,26 @@ impl<'a> From<&'a storage::Key> for KeyType<'a> {
     fn from(key: &'a storage::Key) -> KeyType<'a> {
         if let Some(address) = token::is_any_token_balance_key(key) {
             Self::Token(address)
+        } else if let Some(token) = token::is_any_total_supply_key(key) {
+            Self::TokenSupply(token)
+        } else if let Some((token, owner, spender)) =
+            token::is_any_token_allowance_key(key)
+        {
+            Self::TokenAllowance {
+                token,
+                owner,
+                spender,
+            }
+        } else if let Some(address) = key::is_pk_key(key) {
+            Self::PublicKey(address)
         } else if proof_of_stake::is_pos_key(key) {
             Self::PoS
         } else if let Some(address) = intent::is_invalid_intent_key(key) {
             Self::InvalidIntentSet(address)
         } else if let Some(address) = nft::is_nft_key(key) {
             Self::Nft(address)
+        } else if name_service_storage::is_alias_key(key).is_some() {
+            Self::Alias
         } else if gov_storage::is_vote_key(key) {
             let voter_address = gov_storage::get_voter_address(key);
             if let Some(address) = voter_address {
@@ -97,13 +135,23 @@ fn validate_tx(
         let is_valid = match key_type {
             KeyType::Token(owner) => {
                 if owner == &addr {
+                    let balance_key = key;
                     let key = key.to_string();
                     let pre: token::Amount = read_pre(&key).unwrap_or_default();
                     let post: token::Amount =
                         read_post(&key).unwrap_or_default();
                     let change = post.change() - pre.change();
-                    // debit has to signed, credit doesn't
-                    let valid = change >= 0 || *valid_sig || *valid_intent;
+                    // debit has to signed or covered by the allowances used up
+                    // by approved spenders, credit doesn't
+                    let valid = change >= 0
+                        || *valid_sig
+                        || *valid_intent
+                        || is_debit_within_allowances(
+                            balance_key,
+                            &addr,
+                            change,
+                            &keys_changed,
+                        );
                     debug_log!(
                         "token key: {}, change: {}, valid_sig: {}, \
                          valid_intent: {}, valid modification: {}",
@@ -126,17 +174,97 @@ fn validate_tx(
                     true
                 }
             }
+            KeyType::TokenSupply(token) => {
+                let metadata: Option<token::Metadata> =
+                    read_pre(token::metadata_key(token).to_string());
+                let is_minter = metadata.and_then(|metadata| metadata.minter)
+                    == Some(addr.clone());
+                let key = key.to_string();
+                let pre: token::Amount = read_pre(&key).unwrap_or_default();
+                let post: token::Amount = read_post(&key).unwrap_or_default();
+                // Minting a token of which this account is the minter has to
+                // be signed
+                let valid = !is_minter || post <= pre || *valid_sig;
+                debug_log!(
+                    "token supply key: {}, pre: {}, post: {}, is_minter: {}, \
+                     valid_sig: {}, valid modification: {}",
+                    key,
+                    pre,
+                    post,
+                    is_minter,
+                    *valid_sig,
+                    valid
+                );
+                valid
+            }
+            KeyType::TokenAllowance {
+                token,
+                owner,
+                spender,
+            } => {
+                let key = key.to_string();
+                let pre: token::Amount = read_pre(&key).unwrap_or_default();
+                let post: token::Amount = read_post(&key).unwrap_or_default();
+                let valid = if owner == &addr {
+                    // Any change of an allowance given by this account has to
+                    // be signed, unless a spender uses it up to transfer this
+                    // account's tokens
+                    *valid_sig
+                        || (post < pre
+                            && token::are_allowances_spent_debited(
+                                token,
+                                &addr,
+                                &keys_changed,
+                            ))
+                } else if spender == &addr {
+                    // Using up an allowance given to this account has to be
+                    // signed
+                    post >= pre || *valid_sig
+                } else {
+                    true
+                };
+                debug_log!(
+                    "token allowance key: {}, pre: {}, post: {}, valid_sig: \
+                     {}, valid modification: {}",
+                    key,
+                    pre,
+                    post,
+                    *valid_sig,
+                    valid
+                );
+                valid
+            }
             KeyType::PoS => {
                 // Allow the account to be used in PoS
                 let bond_id = proof_of_stake::is_bond_key(key)
-                    .or_else(|| proof_of_stake::is_unbond_key(key));
-                let valid = match bond_id {
-                    Some(bond_id) => {
-                        // Bonds and unbonds changes for this address
-                        // must be signed
+                    .or_else(|| proof_of_stake::is_unbond_key(key))
+                    .or_else(|| {
+                        proof_of_stake::is_rewards_claimed_epoch_key(key)
+                    });
+                let consensus_key_validator =
+                    proof_of_stake::is_validator_consensus_key_key(key)
+                        .or_else(|| {
+                            proof_of_stake::is_validator_commission_rate_key(
+                                key,
+                            )
+                        })
+                        .or_else(|| proof_of_stake::is_validator_state_key(key))
+                        .or_else(|| {
+                            proof_of_stake::is_validator_metadata_key(key)
+                        });
+                let valid = match (bond_id, consensus_key_validator) {
+                    (Some(bond_id), _) => {
+                        // Bonds, unbonds and rewards claims changes for this
+                        // address must be signed
                         bond_id.source != addr || *valid_sig
                     }
-                    None => {
+                    (None, Some(validator)) => {
+                        // Consensus key, commission rate, state (e.g. an
+                        // unjailing) and metadata changes of this address
+                        // must be signed
+                        validator != &addr || *valid_sig
+                    }
+                    (None, None) => {
                         // Any other PoS changes are allowed without signature
                         true
                     }
@@ -169,8 +297,21 @@ fn validate_tx(
                     true
                 }
             }
-            KeyType::Nft(owner) => {
-                if owner == &addr {
+            KeyType::Nft(nft_address) => {
+                // changes of the NFT itself and of tokens owned by this
+                // account must be signed
+                if nft_address == &addr
+                    || nft::token_owner(key, nft_address).as_ref()
+                        == Some(&addr)
+                {
+                    *valid_sig
+                } else {
+                    true
+                }
+            }
+            KeyType::Alias => {
+                let registered: Option<Address> = read_post(key.to_string());
+                if registered.as_ref() == Some(&addr) {
                     *valid_sig
                 } else {
                     true
@@ -183,6 +324,15 @@ fn validate_tx(
                     true
                 }
             }
+            KeyType::PublicKey(owner) => {
+                if owner == &addr {
+                    // the new key must be signed for with the current key
+                    let has_post: bool = has_key_post(key.to_string());
+                    *valid_sig && has_post
+                } else {
+                    true
+                }
+            }
             KeyType::Vp(owner) => {
                 let key = key.to_string();
                 let has_post: bool = has_key_post(&key);
@@ -218,6 +368,24 @@ fn validate_tx(
     true
 }
 
+/// Check that the debit of the owner's token balance with the given key is
+/// covered by the owner's allowances that have been used up in the
+/// transaction.
+fn is_debit_within_allowances(
+    balance_key: &storage::Key,
+    owner: &Address,
+    change: token::Change,
+    keys_changed: &BTreeSet<storage::Key>,
+) -> bool {
+    match balance_key.segments.get(0) {
+        Some(storage::DbKeySeg::AddressSeg(token)) => {
+            let spent = token::allowances_spent(token, owner, keys_changed);
+            spent.change() + change >= 0
+        }
+        _ => false,
+    }
+}
+
 fn check_intent_transfers(
     addr: &Address,
     signed_tx_data: &SignedTxData,
@@ -369,6 +537,7 @@ mod tests {
     use anoma_tests::vp::vp_host_env::storage::Key;
     use anoma_tests::vp::*;
     use anoma_vp_prelude::key::RefTo;
+    use borsh::BorshSerialize;
     use proptest::prelude::*;
     use storage::testing::arb_account_storage_key_no_vp;
 
@@ -497,6 +666,71 @@ mod tests {
         assert!(validate_tx(tx_data, vp_owner, keys_changed, verifiers));
     }
 
+    /// Test that a registration of an alias of this account without a valid
+    /// signature is rejected, even though this account is a verifier.
+    #[test]
+    fn test_unsigned_alias_registration_rejected() {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let alias_key = name_service_storage::alias_key("alice");
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner]);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Register the alias of the VP owner in a transaction
+            tx_host_env::write(alias_key.to_string(), address);
+            tx_host_env::insert_verifier(address);
+        });
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = vp_env.get_verifiers();
+        vp_host_env::set(vp_env);
+        assert!(!validate_tx(tx_data, vp_owner, keys_changed, verifiers));
+    }
+
+    /// Test that a registration of an alias of this account with a valid
+    /// signature is accepted.
+    #[test]
+    fn test_signed_alias_registration_accepted() {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let keypair = key::testing::keypair_1();
+        let public_key = keypair.ref_to();
+        let alias_key = name_service_storage::alias_key("alice");
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner]);
+
+        tx_env.write_public_key(&vp_owner, &public_key);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Register the alias of the VP owner in a transaction
+            tx_host_env::write(alias_key.to_string(), address);
+            tx_host_env::insert_verifier(address);
+        });
+
+        let mut vp_env = vp_host_env::take();
+        let tx = vp_env.tx.clone();
+        let signed_tx = tx.sign(&keypair);
+        let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
+        vp_env.tx = signed_tx;
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = vp_env.get_verifiers();
+        vp_host_env::set(vp_env);
+        assert!(validate_tx(tx_data, vp_owner, keys_changed, verifiers));
+    }
+
     /// Test that a transfer on with accounts other than self is accepted.
     #[test]
     fn test_transfer_between_other_parties_accepted() {
@@ -532,6 +766,240 @@ mod tests {
         assert!(validate_tx(tx_data, vp_owner, keys_changed, verifiers));
     }
 
+    /// Test that a debit transfer made by an approved spender within its
+    /// allowance is accepted without the owner's signature.
+    #[test]
+    fn test_debit_transfer_from_within_allowance_accepted() {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let spender = address::testing::established_address_2();
+        let target = address::testing::established_address_3();
+        let token = address::xan();
+        let amount = token::Amount::from(10_098_123);
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &spender, &target, &token]);
+
+        // Credit the tokens to the VP owner and approve the spender before
+        // running the transaction
+        tx_env.credit_tokens(&vp_owner, &token, amount);
+        tx_env.approve_tokens(&vp_owner, &spender, &token, amount);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Apply transfer from the allowance in a transaction
+            tx_host_env::token::transfer_from(
+                &spender, address, &target, &token, amount,
+            );
+        });
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        assert!(validate_tx(tx_data, vp_owner, keys_changed, verifiers));
+    }
+
+    /// Test that using up an allowance without the spender's signature is
+    /// rejected by the spender's VP.
+    #[test]
+    fn test_unsigned_allowance_use_rejected() {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let owner = address::testing::established_address_2();
+        let target = address::testing::established_address_3();
+        let token = address::xan();
+        let amount = token::Amount::from(10_098_123);
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &owner, &target, &token]);
+
+        // Credit the tokens to the owner and approve the VP owner as a spender
+        // before running the transaction
+        tx_env.credit_tokens(&owner, &token, amount);
+        tx_env.approve_tokens(&owner, &vp_owner, &token, amount);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Apply transfer from the allowance in a transaction
+            tx_host_env::token::transfer_from(
+                address, &owner, &target, &token, amount,
+            );
+        });
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        assert!(!validate_tx(tx_data, vp_owner, keys_changed, verifiers));
+    }
+
+    /// Test that the owner can revoke an allowance with a valid signature.
+    #[test]
+    fn test_signed_allowance_revoke_accepted() {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let keypair = key::testing::keypair_1();
+        let public_key = keypair.ref_to();
+        let spender = address::testing::established_address_2();
+        let token = address::xan();
+        let amount = token::Amount::from(10_098_123);
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &spender, &token]);
+        tx_env.write_public_key(&vp_owner, &public_key);
+
+        // Approve the spender before running the transaction
+        tx_env.approve_tokens(&vp_owner, &spender, &token, amount);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Revoke the allowance in a transaction
+            tx_host_env::token::approve(
+                address,
+                &spender,
+                &token,
+                token::Amount::default(),
+            );
+        });
+
+        let mut vp_env = vp_host_env::take();
+        let tx = vp_env.tx.clone();
+        let signed_tx = tx.sign(&keypair);
+        let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
+        vp_env.tx = signed_tx;
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = vp_env.get_verifiers();
+        vp_host_env::set(vp_env);
+        assert!(validate_tx(tx_data, vp_owner, keys_changed, verifiers));
+    }
+
+    /// Test that a spender cannot decrease an allowance of this account
+    /// without a matching debit of its balance.
+    #[test]
+    fn test_allowance_decrease_without_transfer_rejected() {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let spender = address::testing::established_address_2();
+        let token = address::xan();
+        let amount = token::Amount::from(10_098_123);
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &spender, &token]);
+
+        // Credit the tokens to the VP owner and approve the spender before
+        // running the transaction
+        tx_env.credit_tokens(&vp_owner, &token, amount);
+        tx_env.approve_tokens(&vp_owner, &spender, &token, amount);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Decrease the allowance without any transfer in a transaction
+            tx_host_env::token::approve(
+                address,
+                &spender,
+                &token,
+                token::Amount::from(1),
+            );
+        });
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = [spender].into_iter().collect();
+        vp_host_env::set(vp_env);
+        assert!(!validate_tx(tx_data, vp_owner, keys_changed, verifiers));
+    }
+
+    /// Initialize a tx environment with a token whose minter is the given VP
+    /// owner.
+    fn init_minter_env(token: &Address, vp_owner: &Address) -> TestTxEnv {
+        let mut tx_env = TestTxEnv::default();
+        let target = address::testing::established_address_3();
+        tx_env.spawn_accounts([token, vp_owner, &target]);
+        let metadata = token::Metadata {
+            symbol: "TEST".to_string(),
+            decimals: 6,
+            max_supply: None,
+            minter: Some(vp_owner.clone()),
+        };
+        tx_env
+            .storage
+            .write(&token::metadata_key(token), metadata.try_to_vec().unwrap())
+            .unwrap();
+        tx_env
+    }
+
+    /// Test that minting a token of which this account is the minter without
+    /// a valid signature is rejected, even though this account is a verifier.
+    #[test]
+    fn test_unsigned_mint_rejected() {
+        let vp_owner = address::testing::established_address_1();
+        let token = address::testing::established_address_2();
+        let target = address::testing::established_address_3();
+        let amount = token::Amount::from(10_098_123);
+        let tx_env = init_minter_env(&token, &vp_owner);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Mint the tokens in a transaction
+            tx_host_env::token::mint(address, &target, &token, amount);
+        });
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = vp_env.get_verifiers();
+        vp_host_env::set(vp_env);
+        assert!(!validate_tx(tx_data, vp_owner, keys_changed, verifiers));
+    }
+
+    /// Test that minting a token of which this account is the minter with a
+    /// valid signature is accepted.
+    #[test]
+    fn test_signed_mint_accepted() {
+        let vp_owner = address::testing::established_address_1();
+        let keypair = key::testing::keypair_1();
+        let public_key = keypair.ref_to();
+        let token = address::testing::established_address_2();
+        let target = address::testing::established_address_3();
+        let amount = token::Amount::from(10_098_123);
+        let mut tx_env = init_minter_env(&token, &vp_owner);
+        tx_env.write_public_key(&vp_owner, &public_key);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Mint the tokens in a transaction
+            tx_host_env::token::mint(address, &target, &token, amount);
+        });
+
+        let mut vp_env = vp_host_env::take();
+        let tx = vp_env.tx.clone();
+        let signed_tx = tx.sign(&keypair);
+        let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
+        vp_env.tx = signed_tx;
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = vp_env.get_verifiers();
+        vp_host_env::set(vp_env);
+        assert!(validate_tx(tx_data, vp_owner, keys_changed, verifiers));
+    }
+
     prop_compose! {
         /// Generates an account address and a storage key inside its storage.
         fn arb_account_storage_subspace_key()
@@ -696,6 +1164,81 @@ mod tests {
         assert!(validate_tx(tx_data, vp_owner, keys_changed, verifiers));
     }
 
+    /// Test that a public key rotation signed with the current key is
+    /// accepted, but one signed with the new key is rejected.
+    #[test]
+    fn test_public_key_rotation() {
+        let vp_owner = address::testing::established_address_1();
+        let current_keypair = key::testing::keypair_1();
+        let new_keypair = key::testing::keypair_2();
+
+        for (signer, expected) in
+            [(&current_keypair, true), (&new_keypair, false)]
+        {
+            // Initialize a tx environment
+            let mut tx_env = TestTxEnv::default();
+
+            // Spawn the accounts to be able to modify their storage
+            tx_env.spawn_accounts([&vp_owner]);
+
+            tx_env.write_public_key(&vp_owner, &current_keypair.ref_to());
+
+            // Initialize VP environment from a transaction
+            vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+                // Rotate the public key in a transaction
+                let pk_key = key::pk_key(address);
+                tx_host_env::write(pk_key.to_string(), new_keypair.ref_to());
+            });
+
+            let mut vp_env = vp_host_env::take();
+            let tx = vp_env.tx.clone();
+            let signed_tx = tx.sign(signer);
+            let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
+            vp_env.tx = signed_tx;
+            let keys_changed: BTreeSet<storage::Key> =
+                vp_env.all_touched_storage_keys();
+            let verifiers: BTreeSet<Address> = BTreeSet::default();
+            vp_host_env::set(vp_env);
+            assert_eq!(
+                validate_tx(tx_data, vp_owner.clone(), keys_changed, verifiers),
+                expected
+            );
+        }
+    }
+
+    /// Test that a signed removal of the public key is rejected.
+    #[test]
+    fn test_signed_public_key_removal_rejected() {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let keypair = key::testing::keypair_1();
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner]);
+
+        tx_env.write_public_key(&vp_owner, &keypair.ref_to());
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Remove the public key in a transaction
+            let pk_key = key::pk_key(address);
+            tx_host_env::delete(pk_key.to_string());
+        });
+
+        let mut vp_env = vp_host_env::take();
+        let tx = vp_env.tx.clone();
+        let signed_tx = tx.sign(&keypair);
+        let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
+        vp_env.tx = signed_tx;
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        assert!(!validate_tx(tx_data, vp_owner, keys_changed, verifiers));
+    }
+
     /// Test that a validity predicate update is rejected if not whitelisted
     #[test]
     fn test_signed_vp_update_not_whitelisted_rejected() {